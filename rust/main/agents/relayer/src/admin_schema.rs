@@ -0,0 +1,101 @@
+//! Export of the JSON schemas of the versioned admin API responses, so that
+//! external tooling can validate against, or generate clients from, the exact
+//! types a relayer build serves.
+
+use std::{fs, path::PathBuf};
+
+use eyre::{bail, eyre, Result};
+
+use crate::server::v1;
+
+/// `admin-schema [--out <dir>]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdminSchemaCommand {
+    /// Directory to write a `v1/<Type>.json` file per type to. The schemas are
+    /// printed to stdout if unset.
+    pub out: Option<PathBuf>,
+}
+
+impl AdminSchemaCommand {
+    /// Parse an admin-schema subcommand from the process arguments, excluding
+    /// the binary name. Returns `None` if the arguments aren't an admin-schema
+    /// command, in which case the relayer should start as usual.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Option<Result<Self>> {
+        let mut args = args.into_iter();
+        match args.next()?.as_str() {
+            "admin-schema" => Some(Self::parse(args)),
+            _ => None,
+        }
+    }
+
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self> {
+        const USAGE: &str = "Usage: admin-schema [--out <dir>]";
+        let mut out = None;
+        while let Some(flag) = args.next() {
+            match flag.as_str() {
+                "--out" => {
+                    out = Some(
+                        args.next()
+                            .ok_or_else(|| eyre!("{flag} needs a value"))?
+                            .into(),
+                    )
+                }
+                _ => bail!("Unknown argument `{flag}`. {USAGE}"),
+            }
+        }
+        Ok(Self { out })
+    }
+
+    pub fn run(self) -> Result<()> {
+        let schemas = v1::schemas();
+        let Some(out) = self.out else {
+            let versions = serde_json::json!({ "v1": schemas });
+            println!("{}", serde_json::to_string_pretty(&versions)?);
+            return Ok(());
+        };
+        let dir = out.join("v1");
+        fs::create_dir_all(&dir)?;
+        for (name, schema) in schemas {
+            let path = dir.join(format!("{name}.json"));
+            fs::write(&path, serde_json::to_string_pretty(&schema)?)?;
+            println!("Wrote {}", path.display());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn args(args: &str) -> impl Iterator<Item = String> + '_ {
+        args.split_whitespace().map(str::to_owned)
+    }
+
+    #[test]
+    fn test_parse_admin_schema_command() {
+        let command = AdminSchemaCommand::from_args(args("admin-schema --out /tmp/schemas"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            command,
+            AdminSchemaCommand {
+                out: Some("/tmp/schemas".into())
+            }
+        );
+        assert_eq!(
+            AdminSchemaCommand::from_args(args("admin-schema"))
+                .unwrap()
+                .unwrap(),
+            AdminSchemaCommand { out: None }
+        );
+
+        assert!(AdminSchemaCommand::from_args(args("explain --db /tmp/db")).is_none());
+        assert!(AdminSchemaCommand::from_args(args("admin-schema --out"))
+            .unwrap()
+            .is_err());
+        assert!(AdminSchemaCommand::from_args(args("admin-schema --v2"))
+            .unwrap()
+            .is_err());
+    }
+}
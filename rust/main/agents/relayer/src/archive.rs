@@ -0,0 +1,710 @@
+//! Export of an origin chain's merkle tree and message data as an archive that
+//! auditors can verify offline, without trusting the relayer that produced it.
+//!
+//! An archive is a directory containing:
+//! - `leaves.csv`: every merkle tree leaf as `leaf_index,message_id`
+//! - `checkpoints.json`: the quorum of signed checkpoints each message's
+//!   metadata was built from
+//! - `proofs.json`: inclusion proofs for every delivered message
+//! - `manifest.json`: the archive version, origin and a keccak256 hash of
+//!   every other file
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use hyperlane_base::db::{DbError, HyperlaneDb, HyperlaneRocksDB, DB};
+use hyperlane_core::{
+    accumulator::{incremental::IncrementalMerkle, merkle::Proof},
+    HyperlaneDomain, HyperlaneDomainProtocol, HyperlaneDomainType, KnownHyperlaneDomain,
+    SignedCheckpointWithMessageId, H160, H256,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::prover::{Prover, ProverError};
+
+/// Version of the archive layout, bumped on incompatible changes
+pub const ARCHIVE_VERSION: u32 = 1;
+
+const LEAVES_FILE: &str = "leaves.csv";
+const CHECKPOINTS_FILE: &str = "checkpoints.json";
+const PROOFS_FILE: &str = "proofs.json";
+const MANIFEST_FILE: &str = "manifest.json";
+const LEAVES_HEADER: &str = "leaf_index,message_id";
+
+/// Errors raised while exporting or verifying an archive
+#[derive(Debug, thiserror::Error)]
+pub enum ArchiveError {
+    /// Reading or writing an archive file failed
+    #[error("Failed to access {path}: {source}")]
+    Io {
+        /// The file being accessed
+        path: PathBuf,
+        /// The underlying error
+        source: std::io::Error,
+    },
+    /// An archive file could not be (de)serialized
+    #[error("Malformed {file}: {source}")]
+    Json {
+        /// The offending file
+        file: &'static str,
+        /// The underlying error
+        source: serde_json::Error,
+    },
+    /// A line of the leaves file could not be parsed
+    #[error("Malformed {LEAVES_FILE} at line {line}: {reason}")]
+    MalformedLeaf {
+        /// 1-based line number
+        line: usize,
+        /// What was wrong with it
+        reason: String,
+    },
+    /// The archive was produced by an incompatible version
+    #[error("Unsupported archive version {0}, expected {ARCHIVE_VERSION}")]
+    UnsupportedVersion(u32),
+    /// A file's contents don't match the hash recorded in the manifest
+    #[error("Hash of {file} is {actual:?} but the manifest records {expected:?}")]
+    HashMismatch {
+        /// The tampered file
+        file: String,
+        /// Hash recorded in the manifest
+        expected: H256,
+        /// Hash of the file on disk
+        actual: H256,
+    },
+    /// A file listed in the manifest doesn't exist in the archive
+    #[error("{0} is listed in the manifest but missing from the archive")]
+    MissingFile(String),
+    /// The leaves are not a contiguous sequence starting at zero
+    #[error("Expected leaf index {expected} but found {found}")]
+    LeafGap {
+        /// The next expected leaf index
+        expected: u32,
+        /// The leaf index found instead
+        found: u32,
+    },
+    /// A checkpoint or proof refers to a leaf that isn't in the archive
+    #[error("Leaf index {index} referenced by message {message_id:?} is not in the archive")]
+    UnknownLeaf {
+        /// The referenced leaf index
+        index: u32,
+        /// The message referencing it
+        message_id: H256,
+    },
+    /// A message id doesn't match the leaf at the claimed index
+    #[error("Leaf {index} is {actual:?}, but message {message_id:?} claims to be at that index")]
+    LeafMismatch {
+        /// The leaf index
+        index: u32,
+        /// The message claiming the leaf
+        message_id: H256,
+        /// The leaf actually at that index
+        actual: H256,
+    },
+    /// A signed checkpoint's root differs from the root rebuilt from the leaves
+    #[error("Checkpoint at index {index} for message {message_id:?} has root {claimed:?}, but the leaves produce {actual:?}")]
+    RootMismatch {
+        /// The checkpoint index
+        index: u32,
+        /// The message the checkpoint was used for
+        message_id: H256,
+        /// The root in the checkpoint
+        claimed: H256,
+        /// The root rebuilt from the leaves
+        actual: H256,
+    },
+    /// A signature doesn't recover to the signer recorded for it
+    #[error("Signature {position} on the checkpoint for message {message_id:?} does not recover to {expected:?}")]
+    InvalidSignature {
+        /// The message the checkpoint was used for
+        message_id: H256,
+        /// Position of the signature in the quorum
+        position: usize,
+        /// The signer recorded in the archive
+        expected: H160,
+    },
+    /// An inclusion proof doesn't evaluate to the root at its checkpoint
+    #[error("Proof for message {message_id:?} does not evaluate to the root at checkpoint index {checkpoint_index}")]
+    InvalidProof {
+        /// The message being proven
+        message_id: H256,
+        /// The checkpoint index the proof was generated against
+        checkpoint_index: u32,
+    },
+    /// The relayer database couldn't be read
+    #[error(transparent)]
+    Db(#[from] DbError),
+    /// A proof couldn't be generated
+    #[error(transparent)]
+    Prover(#[from] ProverError),
+}
+
+type Result<T, E = ArchiveError> = std::result::Result<T, E>;
+
+/// Contents of `manifest.json`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveManifest {
+    /// Layout version of the archive
+    pub version: u32,
+    /// Version of the relayer that produced the archive
+    pub relayer_version: String,
+    /// Name of the origin chain
+    pub origin: String,
+    /// Number of leaves in the tree
+    pub leaf_count: u32,
+    /// keccak256 of every other file in the archive
+    pub files: BTreeMap<String, H256>,
+}
+
+/// The quorum of signed checkpoints a message's metadata was built from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchivedCheckpoint {
+    /// The message the metadata was built for
+    pub message_id: H256,
+    /// Leaf index of the message
+    pub leaf_index: u32,
+    /// Address each signature recovers to, in the same order as `signed`
+    pub signers: Vec<H160>,
+    /// The signed checkpoints making up the quorum
+    pub signed: Vec<SignedCheckpointWithMessageId>,
+}
+
+/// Inclusion proof of a delivered message
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchivedProof {
+    /// The delivered message
+    pub message_id: H256,
+    /// Nonce of the message
+    pub nonce: u32,
+    /// Leaf index of the message
+    pub leaf_index: u32,
+    /// Index of the tree root the proof was generated against
+    pub checkpoint_index: u32,
+    /// The proof itself
+    pub proof: Proof,
+}
+
+/// Summary of a successfully verified archive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveSummary {
+    /// Number of leaves in the tree
+    pub leaves: u32,
+    /// Number of quorum checkpoints checked
+    pub checkpoints: usize,
+    /// Number of inclusion proofs checked
+    pub proofs: usize,
+}
+
+/// Standalone archive subcommands of the relayer binary
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArchiveCommand {
+    /// `export-archive --db <path> --origin <chain> --out <dir>`
+    Export {
+        /// Path to the relayer database
+        db: PathBuf,
+        /// Name of the origin chain to export
+        origin: String,
+        /// Directory to write the archive to
+        out: PathBuf,
+    },
+    /// `verify-archive <dir>`
+    Verify {
+        /// Directory containing the archive
+        dir: PathBuf,
+    },
+}
+
+impl ArchiveCommand {
+    /// Parse an archive subcommand from the process arguments, excluding the
+    /// binary name. Returns `None` if the arguments aren't an archive command,
+    /// in which case the relayer should start as usual.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Option<eyre::Result<Self>> {
+        let mut args = args.into_iter();
+        match args.next()?.as_str() {
+            "export-archive" => Some(Self::parse_export(args)),
+            "verify-archive" => Some(match (args.next(), args.next()) {
+                (Some(dir), None) => Ok(Self::Verify { dir: dir.into() }),
+                _ => Err(eyre::eyre!("Usage: verify-archive <dir>")),
+            }),
+            _ => None,
+        }
+    }
+
+    fn parse_export(mut args: impl Iterator<Item = String>) -> eyre::Result<Self> {
+        const USAGE: &str = "Usage: export-archive --db <path> --origin <chain> --out <dir>";
+        let (mut db, mut origin, mut out) = (None, None, None);
+        while let Some(flag) = args.next() {
+            let slot = match flag.as_str() {
+                "--db" => &mut db,
+                "--origin" => &mut origin,
+                "--out" => &mut out,
+                _ => eyre::bail!("Unknown argument `{flag}`. {USAGE}"),
+            };
+            *slot = Some(
+                args.next()
+                    .ok_or_else(|| eyre::eyre!("{flag} needs a value"))?,
+            );
+        }
+        match (db, origin, out) {
+            (Some(db), Some(origin), Some(out)) => Ok(Self::Export {
+                db: db.into(),
+                origin,
+                out: out.into(),
+            }),
+            _ => Err(eyre::eyre!(USAGE)),
+        }
+    }
+
+    /// Run the command, printing a short report to stdout
+    pub fn run(self) -> eyre::Result<()> {
+        match self {
+            Self::Export { db, origin, out } => {
+                let db = open_origin_db(&db, origin)?;
+                let manifest = export_archive(&db, &out)?;
+                println!(
+                    "Exported {} leaves of {} to {}",
+                    manifest.leaf_count,
+                    manifest.origin,
+                    out.display()
+                );
+            }
+            Self::Verify { dir } => {
+                let summary = verify_archive(&dir)?;
+                println!(
+                    "Archive OK: {} leaves, {} checkpoints, {} proofs",
+                    summary.leaves, summary.checkpoints, summary.proofs
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Open the relayer database at `path` for the origin chain named `origin`.
+/// The db is keyed by domain name, so chains unknown to this build work too.
+pub(crate) fn open_origin_db(path: &Path, origin: String) -> eyre::Result<HyperlaneRocksDB> {
+    let domain = KnownHyperlaneDomain::from_str(&origin)
+        .map(HyperlaneDomain::Known)
+        .unwrap_or(HyperlaneDomain::Unknown {
+            domain_id: 0,
+            domain_name: origin,
+            domain_type: HyperlaneDomainType::Unknown,
+            domain_protocol: HyperlaneDomainProtocol::Ethereum,
+            domain_technical_stack: Default::default(),
+        });
+    Ok(HyperlaneRocksDB::new(&domain, DB::from_path(path)?))
+}
+
+/// Write the tree, quorum checkpoints and delivered-message proofs of the
+/// database's origin to `out`.
+pub fn export_archive(db: &HyperlaneRocksDB, out: &Path) -> Result<ArchiveManifest> {
+    fs::create_dir_all(out).map_err(|source| ArchiveError::Io {
+        path: out.into(),
+        source,
+    })?;
+
+    let mut leaves = vec![];
+    while let Some(insertion) =
+        db.retrieve_merkle_tree_insertion_by_leaf_index(&(leaves.len() as u32))?
+    {
+        leaves.push(insertion.message_id());
+    }
+    let prover = Prover::from(&leaves);
+    let leaf_count = leaves.len() as u32;
+
+    let mut checkpoints = vec![];
+    let mut proofs = vec![];
+    let highest_nonce = db.retrieve_highest_seen_message_nonce()?;
+    for nonce in highest_nonce.map(|n| 0..=n).into_iter().flatten() {
+        let Some(message_id) = db.retrieve_message_id_by_nonce(&nonce)? else {
+            continue;
+        };
+        let Some(leaf_index) = db
+            .retrieve_merkle_leaf_index_by_message_id(&message_id)?
+            .filter(|index| *index < leaf_count)
+        else {
+            continue;
+        };
+        let quorum = db.retrieve_quorum_checkpoint_by_message_id(&message_id)?;
+        if let Some(quorum) = &quorum {
+            let signed = quorum.signed_checkpoints();
+            checkpoints.push(ArchivedCheckpoint {
+                message_id,
+                leaf_index,
+                signers: signed
+                    .iter()
+                    .filter_map(|signed| signed.recover().ok())
+                    .collect(),
+                signed,
+            });
+        }
+        if db.retrieve_processed_by_nonce(&nonce)? == Some(true) {
+            let checkpoint_index = quorum
+                .map(|quorum| quorum.checkpoint.index)
+                .filter(|index| *index >= leaf_index && *index < leaf_count)
+                .unwrap_or(leaf_count - 1);
+            let proof =
+                prover.prove_against_previous(leaf_index as usize, checkpoint_index as usize)?;
+            proofs.push(ArchivedProof {
+                message_id,
+                nonce,
+                leaf_index,
+                checkpoint_index,
+                proof,
+            });
+        }
+    }
+
+    let mut leaves_csv = format!("{LEAVES_HEADER}\n");
+    for (index, leaf) in leaves.iter().enumerate() {
+        writeln!(leaves_csv, "{index},{leaf:?}").expect("writing to a String never fails");
+    }
+
+    let mut files = BTreeMap::new();
+    for (file, contents) in [
+        (LEAVES_FILE, leaves_csv.into_bytes()),
+        (CHECKPOINTS_FILE, to_json(CHECKPOINTS_FILE, &checkpoints)?),
+        (PROOFS_FILE, to_json(PROOFS_FILE, &proofs)?),
+    ] {
+        files.insert(file.to_owned(), hash(&contents));
+        write_file(&out.join(file), &contents)?;
+    }
+
+    let manifest = ArchiveManifest {
+        version: ARCHIVE_VERSION,
+        relayer_version: env!("CARGO_PKG_VERSION").to_owned(),
+        origin: db.domain().name().to_owned(),
+        leaf_count,
+        files,
+    };
+    write_file(
+        &out.join(MANIFEST_FILE),
+        &to_json(MANIFEST_FILE, &manifest)?,
+    )?;
+    Ok(manifest)
+}
+
+/// Check an archive produced by [`export_archive`]: file hashes, the
+/// contiguity of the leaves, every checkpoint's root and signatures, and every
+/// inclusion proof.
+pub fn verify_archive(dir: &Path) -> Result<ArchiveSummary> {
+    let manifest: ArchiveManifest =
+        from_json(MANIFEST_FILE, &read_file(&dir.join(MANIFEST_FILE))?)?;
+    if manifest.version != ARCHIVE_VERSION {
+        return Err(ArchiveError::UnsupportedVersion(manifest.version));
+    }
+    for (file, expected) in &manifest.files {
+        let path = dir.join(file);
+        if !path.exists() {
+            return Err(ArchiveError::MissingFile(file.clone()));
+        }
+        let actual = hash(&read_file(&path)?);
+        if actual != *expected {
+            return Err(ArchiveError::HashMismatch {
+                file: file.clone(),
+                expected: *expected,
+                actual,
+            });
+        }
+    }
+
+    let leaves = parse_leaves(&read_file(&dir.join(LEAVES_FILE))?)?;
+    // roots[i] is the root of the tree once leaf i was inserted
+    let mut tree = IncrementalMerkle::default();
+    let roots: Vec<H256> = leaves
+        .iter()
+        .map(|leaf| {
+            tree.ingest(*leaf);
+            tree.root()
+        })
+        .collect();
+    let leaf_at = |index: u32, message_id: H256| {
+        leaves
+            .get(index as usize)
+            .copied()
+            .ok_or(ArchiveError::UnknownLeaf { index, message_id })
+    };
+
+    let checkpoints: Vec<ArchivedCheckpoint> =
+        from_json(CHECKPOINTS_FILE, &read_file(&dir.join(CHECKPOINTS_FILE))?)?;
+    for archived in &checkpoints {
+        let message_id = archived.message_id;
+        let actual = leaf_at(archived.leaf_index, message_id)?;
+        if actual != message_id {
+            return Err(ArchiveError::LeafMismatch {
+                index: archived.leaf_index,
+                message_id,
+                actual,
+            });
+        }
+        for (position, signed) in archived.signed.iter().enumerate() {
+            let checkpoint = &signed.value;
+            let leaf = leaf_at(checkpoint.index, message_id)?;
+            if leaf != checkpoint.message_id {
+                return Err(ArchiveError::LeafMismatch {
+                    index: checkpoint.index,
+                    message_id: checkpoint.message_id,
+                    actual: leaf,
+                });
+            }
+            let root = roots[checkpoint.index as usize];
+            if root != checkpoint.root {
+                return Err(ArchiveError::RootMismatch {
+                    index: checkpoint.index,
+                    message_id,
+                    claimed: checkpoint.root,
+                    actual: root,
+                });
+            }
+            let expected = archived.signers.get(position).copied().unwrap_or_default();
+            if signed.recover().ok() != Some(expected) {
+                return Err(ArchiveError::InvalidSignature {
+                    message_id,
+                    position,
+                    expected,
+                });
+            }
+        }
+    }
+
+    let proofs: Vec<ArchivedProof> = from_json(PROOFS_FILE, &read_file(&dir.join(PROOFS_FILE))?)?;
+    for archived in &proofs {
+        let message_id = archived.message_id;
+        let leaf = leaf_at(archived.leaf_index, message_id)?;
+        if leaf != message_id {
+            return Err(ArchiveError::LeafMismatch {
+                index: archived.leaf_index,
+                message_id,
+                actual: leaf,
+            });
+        }
+        let invalid = || ArchiveError::InvalidProof {
+            message_id,
+            checkpoint_index: archived.checkpoint_index,
+        };
+        let root = *roots
+            .get(archived.checkpoint_index as usize)
+            .ok_or_else(invalid)?;
+        let proof = &archived.proof;
+        if proof.leaf != message_id
+            || proof.index != archived.leaf_index as usize
+            || proof.root() != root
+        {
+            return Err(invalid());
+        }
+    }
+
+    Ok(ArchiveSummary {
+        leaves: leaves.len() as u32,
+        checkpoints: checkpoints.len(),
+        proofs: proofs.len(),
+    })
+}
+
+fn parse_leaves(contents: &[u8]) -> Result<Vec<H256>> {
+    let malformed = |line: usize, reason: &str| ArchiveError::MalformedLeaf {
+        line,
+        reason: reason.to_owned(),
+    };
+    let contents = std::str::from_utf8(contents).map_err(|_| malformed(1, "not valid UTF-8"))?;
+    let mut lines = contents.lines().enumerate();
+    if lines.next().map(|(_, header)| header) != Some(LEAVES_HEADER) {
+        return Err(malformed(1, "missing header"));
+    }
+    let mut leaves = vec![];
+    for (line_index, line) in lines {
+        let line_number = line_index + 1;
+        let (index, leaf) = line
+            .split_once(',')
+            .ok_or_else(|| malformed(line_number, "expected two columns"))?;
+        let index: u32 = index
+            .parse()
+            .map_err(|_| malformed(line_number, "invalid leaf index"))?;
+        let leaf = H256::from_str(leaf.trim_start_matches("0x"))
+            .map_err(|_| malformed(line_number, "invalid message id"))?;
+        if index as usize != leaves.len() {
+            return Err(ArchiveError::LeafGap {
+                expected: leaves.len() as u32,
+                found: index,
+            });
+        }
+        leaves.push(leaf);
+    }
+    Ok(leaves)
+}
+
+fn hash(contents: &[u8]) -> H256 {
+    ethers::utils::keccak256(contents).into()
+}
+
+fn to_json<T: Serialize>(file: &'static str, value: &T) -> Result<Vec<u8>> {
+    serde_json::to_vec_pretty(value).map_err(|source| ArchiveError::Json { file, source })
+}
+
+fn from_json<T: DeserializeOwned>(file: &'static str, contents: &[u8]) -> Result<T> {
+    serde_json::from_slice(contents).map_err(|source| ArchiveError::Json { file, source })
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>> {
+    fs::read(path).map_err(|source| ArchiveError::Io {
+        path: path.into(),
+        source,
+    })
+}
+
+fn write_file(path: &Path, contents: &[u8]) -> Result<()> {
+    fs::write(path, contents).map_err(|source| ArchiveError::Io {
+        path: path.into(),
+        source,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use hyperlane_base::db::test_utils;
+    use hyperlane_core::{
+        Checkpoint, CheckpointWithMessageId, HyperlaneMessage, HyperlaneSignerExt,
+        MerkleTreeInsertion, MultisigSignedCheckpoint,
+    };
+    use hyperlane_ethereum::test_keys::test_signers;
+
+    use super::*;
+
+    const MESSAGES: u32 = 5;
+
+    async fn seed_db(db: &HyperlaneRocksDB) {
+        let signer = test_signers(0);
+        let mut tree = IncrementalMerkle::default();
+        for nonce in 0..MESSAGES {
+            let message = HyperlaneMessage {
+                nonce,
+                origin: db.domain().id(),
+                ..Default::default()
+            };
+            let id = message.id();
+            db.store_message(&message, nonce as u64).unwrap();
+            db.process_tree_insertion(&MerkleTreeInsertion::new(nonce, id), nonce as u64)
+                .unwrap();
+            tree.ingest(id);
+
+            let checkpoint = CheckpointWithMessageId {
+                checkpoint: Checkpoint {
+                    merkle_tree_hook_address: H256::repeat_byte(1),
+                    mailbox_domain: db.domain().id(),
+                    root: tree.root(),
+                    index: nonce,
+                },
+                message_id: id,
+            };
+            let signed = signer.sign(checkpoint).await.unwrap();
+            db.store_quorum_checkpoint_by_message_id(
+                &id,
+                &MultisigSignedCheckpoint {
+                    checkpoint,
+                    signatures: vec![signed.signature],
+                },
+            )
+            .unwrap();
+            // leave the last message undelivered
+            if nonce + 1 < MESSAGES {
+                db.store_processed_by_nonce(&nonce, &true).unwrap();
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn export_round_trips_through_verify() {
+        test_utils::run_test_db(|db| async move {
+            let domain = HyperlaneDomain::Known(KnownHyperlaneDomain::Test1);
+            let db = HyperlaneRocksDB::new(&domain, db);
+            seed_db(&db).await;
+
+            let out = tempfile::tempdir().unwrap();
+            let manifest = export_archive(&db, out.path()).unwrap();
+            assert_eq!(manifest.leaf_count, MESSAGES);
+            assert_eq!(manifest.origin, "test1");
+
+            let summary = verify_archive(out.path()).unwrap();
+            assert_eq!(
+                summary,
+                ArchiveSummary {
+                    leaves: MESSAGES,
+                    checkpoints: MESSAGES as usize,
+                    proofs: MESSAGES as usize - 1,
+                }
+            );
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn tampering_is_pinpointed() {
+        test_utils::run_test_db(|db| async move {
+            let domain = HyperlaneDomain::Known(KnownHyperlaneDomain::Test1);
+            let db = HyperlaneRocksDB::new(&domain, db);
+            seed_db(&db).await;
+
+            let out = tempfile::tempdir().unwrap();
+            export_archive(&db, out.path()).unwrap();
+
+            // editing a file without updating the manifest
+            let leaves_path = out.path().join(LEAVES_FILE);
+            let original = fs::read_to_string(&leaves_path).unwrap();
+            fs::write(&leaves_path, format!("{original}\n")).unwrap();
+            assert!(matches!(
+                verify_archive(out.path()),
+                Err(ArchiveError::HashMismatch { file, .. }) if file == LEAVES_FILE
+            ));
+
+            // swapping a leaf and re-hashing, so only the roots give it away
+            let mut lines: Vec<String> = original.lines().map(str::to_owned).collect();
+            lines[3] = format!("2,{:?}", H256::repeat_byte(0xaa));
+            let tampered = lines.join("\n") + "\n";
+            fs::write(&leaves_path, &tampered).unwrap();
+            let manifest_path = out.path().join(MANIFEST_FILE);
+            let mut manifest: ArchiveManifest =
+                serde_json::from_slice(&fs::read(&manifest_path).unwrap()).unwrap();
+            manifest
+                .files
+                .insert(LEAVES_FILE.to_owned(), hash(tampered.as_bytes()));
+            fs::write(&manifest_path, serde_json::to_vec(&manifest).unwrap()).unwrap();
+            assert!(matches!(
+                verify_archive(out.path()),
+                Err(ArchiveError::LeafMismatch { index: 2, .. })
+            ));
+        })
+        .await;
+    }
+
+    #[test]
+    fn parses_archive_commands() {
+        let args = |s: &str| s.split_whitespace().map(str::to_owned).collect::<Vec<_>>();
+        assert_eq!(
+            ArchiveCommand::from_args(args("--configPath foo")).map(|_| ()),
+            None
+        );
+        assert_eq!(
+            ArchiveCommand::from_args(args("export-archive --db /db --origin test1 --out /out"))
+                .unwrap()
+                .unwrap(),
+            ArchiveCommand::Export {
+                db: "/db".into(),
+                origin: "test1".into(),
+                out: "/out".into(),
+            }
+        );
+        assert!(ArchiveCommand::from_args(args("export-archive --db /db"))
+            .unwrap()
+            .is_err());
+        assert_eq!(
+            ArchiveCommand::from_args(args("verify-archive /out"))
+                .unwrap()
+                .unwrap(),
+            ArchiveCommand::Verify { dir: "/out".into() }
+        );
+    }
+}
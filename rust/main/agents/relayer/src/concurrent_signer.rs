@@ -0,0 +1,306 @@
+use std::{
+    fmt::{Display, Formatter},
+    time::Duration,
+};
+
+use futures::future::join_all;
+use hyperlane_base::{settings::ChainConf, Alert, AlertDispatcher, AlertKind, CoreMetrics};
+use hyperlane_core::{ChainResult, HyperlaneDomain, HyperlaneProvider};
+use serde_json::json;
+use tokio::time::{sleep, timeout};
+use tracing::{error, info, warn};
+
+/// How long observing a signer may take beyond the observation window, e.g.
+/// to connect to the RPC
+const OBSERVATION_GRACE: Duration = Duration::from_secs(30);
+
+/// Nonces of a signer at one point in time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SignerNonces {
+    /// Transactions of the signer included in the latest block
+    pub latest: u64,
+    /// Transactions of the signer, including the pending ones
+    pub pending: u64,
+}
+
+/// Nonces of a signer that moved while nothing was submitted with it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConcurrentSignerEvidence {
+    /// Nonces when the observation started
+    pub before: SignerNonces,
+    /// Nonces when it ended
+    pub after: SignerNonces,
+}
+
+impl Display for ConcurrentSignerEvidence {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "nonces moved from {} latest, {} pending to {} latest, {} pending while the relayer submitted nothing",
+            self.before.latest, self.before.pending, self.after.latest, self.after.pending
+        )
+    }
+}
+
+async fn signer_nonces(
+    provider: &dyn HyperlaneProvider,
+    address: &str,
+) -> ChainResult<Option<SignerNonces>> {
+    let Some(latest) = provider
+        .get_transaction_count(address.to_owned(), false)
+        .await?
+    else {
+        return Ok(None);
+    };
+    let Some(pending) = provider
+        .get_transaction_count(address.to_owned(), true)
+        .await?
+    else {
+        return Ok(None);
+    };
+    Ok(Some(SignerNonces { latest, pending }))
+}
+
+/// Observe the nonces of the signer at `address` for `window`, while the
+/// relayer submits nothing with it. Transactions sent in the meantime can
+/// only have been sent by another process using the same signer.
+/// Transactions already pending when the observation starts, e.g. left over
+/// by a previous run, don't count. `None` if there's no evidence, or the
+/// protocol has no account nonces.
+pub async fn detect_concurrent_signer(
+    provider: &dyn HyperlaneProvider,
+    address: &str,
+    window: Duration,
+) -> ChainResult<Option<ConcurrentSignerEvidence>> {
+    let Some(before) = signer_nonces(provider, address).await? else {
+        return Ok(None);
+    };
+    sleep(window).await;
+    let Some(after) = signer_nonces(provider, address).await? else {
+        return Ok(None);
+    };
+    let sent = after.pending > before.pending || after.latest > before.pending;
+    Ok(sent.then_some(ConcurrentSignerEvidence { before, after }))
+}
+
+/// Observe the signer of every destination for `window`, raising a critical
+/// alert for each one another process appears to be using. Failing to
+/// observe a signer doesn't keep the relayer from starting.
+pub async fn check_concurrent_signers<'a>(
+    destinations: impl Iterator<Item = (&'a HyperlaneDomain, &'a ChainConf)>,
+    metrics: &CoreMetrics,
+    window: Duration,
+    alerts: &AlertDispatcher,
+) {
+    if window.is_zero() {
+        return;
+    }
+    let checks = destinations.map(|(domain, conf)| async move {
+        let observation = async {
+            let Some(signer) = conf.chain_signer().await? else {
+                return Ok(None);
+            };
+            let address = signer.address_string();
+            let provider = conf.build_provider(metrics).await?;
+            let evidence = detect_concurrent_signer(&*provider, &address, window).await?;
+            Ok::<_, eyre::Report>(evidence.map(|evidence| (address, evidence)))
+        };
+        match timeout(window + OBSERVATION_GRACE, observation).await {
+            Ok(Ok(Some((address, evidence)))) => report(domain, &address, evidence, alerts),
+            Ok(Ok(None)) => {}
+            Ok(Err(err)) => warn!(%domain, ?err, "Failed to observe the nonces of the signer"),
+            Err(_) => warn!(%domain, "Timed out observing the nonces of the signer"),
+        }
+    });
+    info!(
+        window_secs = window.as_secs(),
+        "Observing the nonces of the destination signers before submitting"
+    );
+    join_all(checks).await;
+}
+
+fn report(
+    domain: &HyperlaneDomain,
+    address: &str,
+    evidence: ConcurrentSignerEvidence,
+    alerts: &AlertDispatcher,
+) {
+    error!(
+        %domain,
+        %address,
+        %evidence,
+        "Another process appears to be sending transactions with the signer of this relayer"
+    );
+    alerts.dispatch(
+        Alert::new(
+            AlertKind::ConcurrentSigner,
+            format!("concurrent_signer:{domain}:{address}"),
+            format!("relayer/{domain}"),
+            format!("Signer {address} is used by another process: {evidence}"),
+        )
+        .with_details(json!({
+            "signer": address,
+            "before": { "latest": evidence.before.latest, "pending": evidence.before.pending },
+            "after": { "latest": evidence.after.latest, "pending": evidence.after.pending },
+        })),
+    );
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::VecDeque, sync::Mutex};
+
+    use async_trait::async_trait;
+    use hyperlane_core::{
+        BlockInfo, ChainInfo, HyperlaneChain, KnownHyperlaneDomain, TxnInfo, H256, H512, U256,
+    };
+
+    use super::*;
+
+    /// A provider answering nonce queries from a script of
+    /// `(latest, pending)` nonces, one pair per observation
+    #[derive(Debug)]
+    struct ScriptedNonces {
+        domain: HyperlaneDomain,
+        nonces: Mutex<VecDeque<(u64, u64)>>,
+    }
+
+    impl ScriptedNonces {
+        fn new(nonces: &[(u64, u64)]) -> Self {
+            Self {
+                domain: HyperlaneDomain::Known(KnownHyperlaneDomain::Test1),
+                nonces: Mutex::new(nonces.iter().copied().collect()),
+            }
+        }
+    }
+
+    impl HyperlaneChain for ScriptedNonces {
+        fn domain(&self) -> &HyperlaneDomain {
+            &self.domain
+        }
+
+        fn provider(&self) -> Box<dyn HyperlaneProvider> {
+            unimplemented!()
+        }
+    }
+
+    #[async_trait]
+    impl HyperlaneProvider for ScriptedNonces {
+        async fn get_block_by_height(&self, _height: u64) -> ChainResult<BlockInfo> {
+            unimplemented!()
+        }
+
+        async fn get_txn_by_hash(&self, _hash: &H512) -> ChainResult<TxnInfo> {
+            unimplemented!()
+        }
+
+        async fn is_contract(&self, _address: &H256) -> ChainResult<bool> {
+            unimplemented!()
+        }
+
+        async fn get_balance(&self, _address: String) -> ChainResult<U256> {
+            unimplemented!()
+        }
+
+        async fn get_chain_metrics(&self) -> ChainResult<Option<ChainInfo>> {
+            unimplemented!()
+        }
+
+        async fn get_transaction_count(
+            &self,
+            _address: String,
+            pending: bool,
+        ) -> ChainResult<Option<u64>> {
+            let mut nonces = self.nonces.lock().unwrap();
+            let (latest, pending_count) = *nonces.front().unwrap();
+            if pending {
+                nonces.pop_front();
+                Ok(Some(pending_count))
+            } else {
+                Ok(Some(latest))
+            }
+        }
+    }
+
+    async fn detect(nonces: &[(u64, u64)]) -> Option<ConcurrentSignerEvidence> {
+        detect_concurrent_signer(&ScriptedNonces::new(nonces), "0x01", Duration::ZERO)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_transactions_sent_during_the_observation_are_evidence() {
+        // Nothing moved
+        assert_eq!(detect(&[(5, 5), (5, 5)]).await, None);
+        // Transactions left pending by a previous run were included
+        assert_eq!(detect(&[(5, 7), (7, 7)]).await, None);
+
+        // A new transaction is pending
+        assert_eq!(
+            detect(&[(5, 5), (5, 6)]).await,
+            Some(ConcurrentSignerEvidence {
+                before: SignerNonces {
+                    latest: 5,
+                    pending: 5
+                },
+                after: SignerNonces {
+                    latest: 5,
+                    pending: 6
+                },
+            })
+        );
+        // A transaction that wasn't even pending was included
+        let evidence = detect(&[(5, 6), (7, 7)]).await.unwrap();
+        assert_eq!(
+            evidence.to_string(),
+            "nonces moved from 5 latest, 6 pending to 7 latest, 7 pending while the relayer submitted nothing"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_protocols_without_nonces_are_not_observed() {
+        #[derive(Debug)]
+        struct NoNonces(ScriptedNonces);
+
+        impl HyperlaneChain for NoNonces {
+            fn domain(&self) -> &HyperlaneDomain {
+                self.0.domain()
+            }
+
+            fn provider(&self) -> Box<dyn HyperlaneProvider> {
+                unimplemented!()
+            }
+        }
+
+        #[async_trait]
+        impl HyperlaneProvider for NoNonces {
+            async fn get_block_by_height(&self, height: u64) -> ChainResult<BlockInfo> {
+                self.0.get_block_by_height(height).await
+            }
+
+            async fn get_txn_by_hash(&self, hash: &H512) -> ChainResult<TxnInfo> {
+                self.0.get_txn_by_hash(hash).await
+            }
+
+            async fn is_contract(&self, address: &H256) -> ChainResult<bool> {
+                self.0.is_contract(address).await
+            }
+
+            async fn get_balance(&self, address: String) -> ChainResult<U256> {
+                self.0.get_balance(address).await
+            }
+
+            async fn get_chain_metrics(&self) -> ChainResult<Option<ChainInfo>> {
+                self.0.get_chain_metrics().await
+            }
+        }
+
+        let provider = NoNonces(ScriptedNonces::new(&[]));
+        assert_eq!(
+            detect_concurrent_signer(&provider, "0x01", Duration::ZERO)
+                .await
+                .unwrap(),
+            None
+        );
+    }
+}
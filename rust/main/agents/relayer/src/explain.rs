@@ -0,0 +1,135 @@
+//! Explanation of past relaying decisions from the decision snapshots
+//! persisted with every attempt, to answer why a message was or wasn't
+//! delivered at some point in time.
+
+use std::{path::PathBuf, str::FromStr};
+
+use eyre::{bail, eyre, Result};
+use hyperlane_base::db::HyperlaneRocksDB;
+use hyperlane_core::H256;
+
+use crate::{archive::open_origin_db, msg::decision::DecisionSnapshot};
+
+/// `explain <message-id> --db <path> --origin <chain> [--attempt <n>]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExplainCommand {
+    /// The message to explain a decision about
+    pub message_id: H256,
+    /// Path to the relayer database
+    pub db: PathBuf,
+    /// Name of the origin chain of the message
+    pub origin: String,
+    /// The attempt to explain, the latest one if unset
+    pub attempt: Option<u32>,
+}
+
+impl ExplainCommand {
+    /// Parse an explain subcommand from the process arguments, excluding the
+    /// binary name. Returns `None` if the arguments aren't an explain command,
+    /// in which case the relayer should start as usual.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Option<Result<Self>> {
+        let mut args = args.into_iter();
+        match args.next()?.as_str() {
+            "explain" => Some(Self::parse(args)),
+            _ => None,
+        }
+    }
+
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self> {
+        const USAGE: &str =
+            "Usage: explain <message-id> --db <path> --origin <chain> [--attempt <n>]";
+        let message_id = args.next().ok_or_else(|| eyre!(USAGE))?;
+        let message_id = H256::from_str(&message_id)
+            .map_err(|err| eyre!("Invalid message id `{message_id}`: {err}"))?;
+        let (mut db, mut origin, mut attempt) = (None, None, None);
+        while let Some(flag) = args.next() {
+            let slot = match flag.as_str() {
+                "--db" => &mut db,
+                "--origin" => &mut origin,
+                "--attempt" => &mut attempt,
+                _ => bail!("Unknown argument `{flag}`. {USAGE}"),
+            };
+            *slot = Some(args.next().ok_or_else(|| eyre!("{flag} needs a value"))?);
+        }
+        let attempt = attempt
+            .map(|attempt| {
+                attempt
+                    .parse()
+                    .map_err(|err| eyre!("Invalid attempt `{attempt}`: {err}"))
+            })
+            .transpose()?;
+        match (db, origin) {
+            (Some(db), Some(origin)) => Ok(Self {
+                message_id,
+                db: db.into(),
+                origin,
+                attempt,
+            }),
+            _ => Err(eyre!(USAGE)),
+        }
+    }
+
+    /// Run the command, printing the explanation to stdout
+    pub fn run(self) -> Result<()> {
+        let db = open_origin_db(&self.db, self.origin)?;
+        println!("{}", explain(&db, self.message_id, self.attempt)?);
+        Ok(())
+    }
+}
+
+/// Explain the decision made in an attempt to relay a message, the latest
+/// attempt if none is given
+pub(crate) fn explain(
+    db: &HyperlaneRocksDB,
+    message_id: H256,
+    attempt: Option<u32>,
+) -> Result<String> {
+    let attempts = db.retrieve_decision_snapshot_count(&message_id)?;
+    let Some(latest) = attempts.checked_sub(1) else {
+        bail!("No decisions were recorded for message {message_id:?}");
+    };
+    let attempt = attempt.unwrap_or(latest);
+    let Some(snapshot) = db.retrieve_decision_snapshot::<DecisionSnapshot>(&message_id, attempt)?
+    else {
+        bail!("Message {message_id:?} has attempts 0 to {latest}, not {attempt}");
+    };
+    Ok(snapshot.explain(attempt))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn args(args: &str) -> impl Iterator<Item = String> + '_ {
+        args.split_whitespace().map(str::to_owned)
+    }
+
+    #[test]
+    fn test_parse_explain_command() {
+        let message_id = H256::repeat_byte(0xab);
+        let command = ExplainCommand::from_args(args(&format!(
+            "explain {message_id:?} --origin test1 --db /tmp/db --attempt 2"
+        )))
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            command,
+            ExplainCommand {
+                message_id,
+                db: "/tmp/db".into(),
+                origin: "test1".to_owned(),
+                attempt: Some(2),
+            }
+        );
+
+        assert!(ExplainCommand::from_args(args("export-archive --db /tmp/db")).is_none());
+        assert!(ExplainCommand::from_args(args("explain 0x12 --db /tmp/db"))
+            .unwrap()
+            .is_err());
+        assert!(ExplainCommand::from_args(args(&format!(
+            "explain {message_id:?} --db /tmp/db --origin test1 --attempt latest"
+        )))
+        .unwrap()
+        .is_err());
+    }
+}
@@ -1,10 +1,29 @@
+mod admin_schema;
+mod archive;
+mod concurrent_signer;
+mod explain;
 mod merkle_tree;
 mod msg;
+mod origin_startup;
 mod processor;
 mod prover;
+mod reindex;
 mod relayer;
+mod self_test;
 mod server;
 mod settings;
+mod tasks;
 
-pub use msg::GAS_EXPENDITURE_LOG_MESSAGE;
+pub use admin_schema::AdminSchemaCommand;
+pub use archive::ArchiveCommand;
+pub use explain::ExplainCommand;
+pub use merkle_tree::builder::{MerkleTreeBuilder, MerkleTreeBuilderError};
+pub use msg::{
+    MessageEvent, MessageEventBus, MessageEventFilter, MessageEventKind, MessageEventStream,
+    GAS_EXPENDITURE_LOG_MESSAGE, MESSAGE_EVENTS_CAPACITY,
+};
 pub use relayer::*;
+pub use self_test::SelfTestCommand;
+pub use settings::snapshot::{ConfigHandle, ConfigSnapshot, ConfigSnapshotError};
+/// Cancels a running [`Relayer`], see [`Relayer::run`]
+pub use tokio_util::sync::CancellationToken;
@@ -11,13 +11,27 @@ use eyre::Result;
 
 use hyperlane_base::agent_main;
 
-use relayer::Relayer;
+use relayer::{AdminSchemaCommand, ArchiveCommand, ExplainCommand, Relayer, SelfTestCommand};
 
 #[cfg(feature = "memory-profiling")]
 mod memory_profiler;
 
 #[tokio::main(flavor = "multi_thread", worker_threads = 20)]
 async fn main() -> Result<()> {
+    // Archive, explain and admin-schema commands run standalone, without loading the agent settings
+    if let Some(command) = ArchiveCommand::from_args(std::env::args().skip(1)) {
+        return command?.run();
+    }
+    if let Some(command) = ExplainCommand::from_args(std::env::args().skip(1)) {
+        return command?.run();
+    }
+    if let Some(command) = AdminSchemaCommand::from_args(std::env::args().skip(1)) {
+        return command?.run();
+    }
+    if let Some(command) = SelfTestCommand::from_args(std::env::args().skip(1)) {
+        std::process::exit(command?.run().await?);
+    }
+
     let agent_main_fut = agent_main::<Relayer>();
 
     #[cfg(feature = "memory-profiling")]
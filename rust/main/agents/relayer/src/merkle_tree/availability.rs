@@ -0,0 +1,54 @@
+use serde::Serialize;
+
+/// Whether an origin's merkle tree can currently be used to generate proofs.
+/// Routes whose ISM needs proofs park while the tree isn't `Ready`; other
+/// routes keep delivering.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "state", rename_all = "camelCase")]
+pub enum TreeAvailability {
+    /// The tree has caught up with the indexed insertions
+    Ready,
+    /// The tree is being built from indexed insertions, e.g. after a restart
+    Rebuilding {
+        /// Number of leaves ingested so far
+        progress: u32,
+    },
+    /// The tree can't be trusted until the issue is resolved
+    Diverged {
+        /// Why the tree diverged
+        reason: String,
+    },
+    /// The tree holds as many leaves as its depth allows. Its leaves can still
+    /// be proven, but no more will be ingested
+    Full {
+        /// Number of leaves the tree holds
+        capacity: u64,
+    },
+}
+
+impl Default for TreeAvailability {
+    fn default() -> Self {
+        Self::Rebuilding { progress: 0 }
+    }
+}
+
+impl TreeAvailability {
+    pub fn is_ready(&self) -> bool {
+        matches!(self, Self::Ready | Self::Full { .. })
+    }
+
+    /// Value reported by the `merkle_tree_availability` metric
+    pub fn metric_value(&self) -> i64 {
+        match self {
+            Self::Ready => 0,
+            Self::Rebuilding { .. } => 1,
+            Self::Diverged { .. } => 2,
+            Self::Full { .. } => 3,
+        }
+    }
+
+    /// Whether `other` is a different state, ignoring rebuild progress
+    pub fn is_transition_to(&self, other: &Self) -> bool {
+        std::mem::discriminant(self) != std::mem::discriminant(other)
+    }
+}
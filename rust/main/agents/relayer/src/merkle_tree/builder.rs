@@ -1,21 +1,36 @@
-use std::fmt::Display;
+use std::{collections::BTreeMap, fmt::Display, sync::Arc, time::Instant};
 
 use eyre::{Context, Result};
-use tracing::{debug, error, instrument};
+use tracing::{debug, error, info, instrument};
 
 use hyperlane_base::db::DbError;
 use hyperlane_core::{
-    accumulator::{incremental::IncrementalMerkle, merkle::Proof},
-    ChainCommunicationError, H256,
+    accumulator::{
+        incremental::IncrementalMerkle,
+        instrumentation::{self, count_hashes, HashCount, HashCounters, HashOperation},
+        merkle::{merkle_root_from_branch, Proof},
+        TREE_DEPTH,
+    },
+    ChainCommunicationError, HyperlaneDomain, MerkleTreeInsertion, H256,
 };
 
 use crate::prover::{Prover, ProverError};
 
+use super::{
+    availability::TreeAvailability,
+    observer::{MerkleTreeObserver, ObserverHandle, TreeDivergence},
+};
+
 /// Struct to sync prover.
 #[derive(Debug)]
 pub struct MerkleTreeBuilder {
     prover: Prover,
     incremental: IncrementalMerkle,
+    availability: TreeAvailability,
+    observer: Option<ObserverHandle>,
+    /// Hashes computed by the operations on both trees, with the
+    /// `hash-metrics` feature enabled
+    hashes: HashCounters,
 }
 
 impl Display for MerkleTreeBuilder {
@@ -62,41 +77,246 @@ pub enum MerkleTreeBuilderError {
 
 impl MerkleTreeBuilder {
     pub fn new() -> Self {
-        let prover = Prover::default();
+        Self::with_depth(TREE_DEPTH)
+    }
+
+    /// A tree holding at most 2^`depth` leaves, for origins whose contracts
+    /// use shallower trees than the standard depth of 32
+    pub fn with_depth(depth: usize) -> Self {
+        let prover = Prover::new(depth);
         let incremental = IncrementalMerkle::default();
         Self {
             prover,
             incremental,
+            availability: TreeAvailability::default(),
+            observer: None,
+            hashes: HashCounters::default(),
         }
     }
 
+    /// Pass the events of the tree, which belongs to `domain`, to `observer`
+    pub fn with_observer(
+        mut self,
+        domain: HyperlaneDomain,
+        observer: Arc<dyn MerkleTreeObserver>,
+    ) -> Self {
+        self.observer = Some(ObserverHandle::new(domain, observer));
+        self
+    }
+
+    /// Number of times the observer panicked, if there is one
+    pub fn observer_panics(&self) -> u64 {
+        self.observer.as_ref().map_or(0, ObserverHandle::panics)
+    }
+
+    pub fn availability(&self) -> &TreeAvailability {
+        &self.availability
+    }
+
+    /// Update the availability of the tree, logging state transitions
+    pub fn set_availability(&mut self, availability: TreeAvailability, reason: &str) {
+        if self.availability.is_transition_to(&availability) {
+            info!(
+                from = ?self.availability,
+                to = ?availability,
+                reason,
+                "Merkle tree availability changed"
+            );
+            if let (TreeAvailability::Diverged { reason }, Some(observer)) =
+                (&availability, &self.observer)
+            {
+                let details = TreeDivergence {
+                    domain: observer.domain().clone(),
+                    leaf_count: self.count(),
+                    reason: reason.clone(),
+                };
+                observer.notify("divergence", |observer, _| observer.on_divergence(&details));
+            }
+        }
+        self.availability = availability;
+    }
+
     #[instrument(err, skip(self), level="debug", fields(prover_latest_index=self.count()-1))]
     pub fn get_proof(
         &self,
         leaf_index: u32,
         root_index: u32,
     ) -> Result<Proof, MerkleTreeBuilderError> {
-        self.prover
-            .prove_against_previous(leaf_index as usize, root_index as usize)
-            .map_err(MerkleTreeBuilderError::from)
+        let start = Instant::now();
+        let (proof, hashes) = count_hashes(|| {
+            self.prover
+                .prove_against_previous(leaf_index as usize, root_index as usize)
+        });
+        self.record_hashes(HashOperation::Prove, hashes);
+        let proof = proof.map_err(MerkleTreeBuilderError::from)?;
+        if let Some(observer) = &self.observer {
+            let duration = start.elapsed();
+            observer.notify("proof", |observer, domain| {
+                observer.on_proof(domain, leaf_index, root_index, duration)
+            });
+        }
+        Ok(proof)
+    }
+
+    /// Proofs of several leaves against the same root
+    pub fn get_proofs(
+        &self,
+        leaf_indices: &[u32],
+        root_index: u32,
+    ) -> Result<Vec<Proof>, MerkleTreeBuilderError> {
+        leaf_indices
+            .iter()
+            .map(|leaf_index| self.get_proof(*leaf_index, root_index))
+            .collect()
     }
 
     pub fn count(&self) -> u32 {
         self.prover.count() as u32
     }
 
+    pub fn depth(&self) -> usize {
+        self.prover.depth()
+    }
+
+    /// Number of leaves the tree can hold
+    pub fn capacity(&self) -> u64 {
+        self.prover.capacity() as u64
+    }
+
+    /// The root of the tree the proof was generated against, for trees of
+    /// this depth
+    pub fn proof_root(&self, proof: &Proof) -> H256 {
+        let depth = self.depth();
+        merkle_root_from_branch(proof.leaf, &proof.branch()[..depth], depth, proof.index)
+    }
+
+    /// Hashes computed by each operation so far, if they're counted, i.e.
+    /// with the `hash-metrics` feature enabled
+    pub fn hash_counts(&self) -> Option<BTreeMap<&'static str, HashCount>> {
+        instrumentation::ENABLED.then(|| {
+            HashOperation::ALL
+                .iter()
+                .map(|operation| (operation.as_str(), self.hashes.get(*operation)))
+                .collect()
+        })
+    }
+
+    fn record_hashes(&self, operation: HashOperation, count: HashCount) {
+        if !instrumentation::ENABLED {
+            return;
+        }
+        self.hashes.record(operation, count);
+        if let Some(observer) = &self.observer {
+            observer.notify("hashes", |observer, domain| {
+                observer.on_hashes(domain, operation, count)
+            });
+        }
+    }
+
+    /// Snapshot of the tree, compact enough to persist after every leaf
+    pub fn snapshot(&self) -> IncrementalMerkle {
+        self.incremental.clone()
+    }
+
+    /// Ingest a leaf along with its index. With the `strict-merkle-ordering`
+    /// feature enabled, asserts that leaves arrive gap-free and in order.
+    pub async fn ingest_insertion(&mut self, insertion: &MerkleTreeInsertion) -> Result<()> {
+        #[cfg(feature = "strict-merkle-ordering")]
+        debug_assert_eq!(
+            insertion.index(),
+            self.count(),
+            "Merkle tree leaves must be ingested in order and without gaps"
+        );
+        self.ingest_message_id(insertion.message_id()).await
+    }
+
     pub async fn ingest_message_id(&mut self, message_id: H256) -> Result<()> {
+        self.ingest_message_ids(&[message_id]).await
+    }
+
+    /// Ingest consecutive leaves, comparing the prover's root to the
+    /// incremental tree's only once all of them were ingested
+    pub async fn ingest_message_ids(&mut self, message_ids: &[H256]) -> Result<()> {
         const CTX: &str = "When ingesting message id";
-        debug!(?message_id, "Ingesting leaf");
-        self.prover.ingest(message_id).expect("tree full");
-        self.incremental.ingest(message_id);
-        match self.prover.root().eq(&self.incremental.root()) {
+        for message_id in message_ids {
+            debug!(?message_id, "Ingesting leaf");
+            let (ingested, hashes) = count_hashes(|| {
+                self.prover.ingest(*message_id)?;
+                self.incremental.ingest(*message_id);
+                Ok::<_, ProverError>(())
+            });
+            self.record_hashes(HashOperation::Ingest, hashes);
+            ingested
+                .map_err(MerkleTreeBuilderError::from)
+                .context(CTX)?;
+            if let Some(observer) = &self.observer {
+                let index = self.count() - 1;
+                let root = self.incremental_root();
+                observer.notify("ingest", |observer, domain| {
+                    observer.on_ingest(domain, index, *message_id, root)
+                });
+            }
+        }
+        let incremental_root = self.incremental_root();
+        match self.prover.root().eq(&incremental_root) {
             true => Ok(()),
             false => Err(MerkleTreeBuilderError::MismatchedRoots {
                 prover_root: self.prover.root(),
-                incremental_root: self.incremental.root(),
+                incremental_root,
             }),
         }
         .context(CTX)
     }
+
+    /// Root of the incremental tree, for trees of this depth
+    fn incremental_root(&self) -> H256 {
+        let (root, hashes) = count_hashes(|| self.incremental.root_at_depth(self.depth()));
+        self.record_hashes(HashOperation::Root, hashes);
+        root
+    }
+}
+
+#[cfg(all(test, feature = "hash-metrics"))]
+mod test {
+    use super::*;
+
+    fn hashes(hashes: u64) -> HashCount {
+        HashCount {
+            hashes,
+            bytes: hashes * 64,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hashes_are_counted_by_operation() {
+        let mut builder = MerkleTreeBuilder::new();
+        let ids: Vec<_> = (0..4).map(H256::from_low_u64_be).collect();
+        builder.ingest_message_ids(&ids).await.unwrap();
+
+        // The prover hashes once per level on each ingest, the incremental
+        // tree once per subtree each leaf fills
+        let counts = builder.hash_counts().unwrap();
+        assert_eq!(counts["ingest"], hashes(4 * 32 + 3));
+        // The roots are compared once for all the leaves
+        assert_eq!(counts["root"], hashes(32));
+        assert_eq!(counts["prove"], hashes(0));
+
+        // Proving rebuilds the paths of the leaf and of the root, 32 hashes
+        // each, and merges the levels they share
+        builder.get_proof(3, 3).unwrap();
+        assert_eq!(builder.hash_counts().unwrap()["prove"], hashes(3 * 32));
+        builder.get_proof(1, 3).unwrap();
+        assert_eq!(
+            builder.hash_counts().unwrap()["prove"],
+            hashes(3 * 32 + 2 * 32 + 31)
+        );
+
+        builder
+            .ingest_message_id(H256::from_low_u64_be(4))
+            .await
+            .unwrap();
+        let counts = builder.hash_counts().unwrap();
+        assert_eq!(counts["ingest"], hashes(5 * 32 + 3));
+        assert_eq!(counts["root"], hashes(2 * 32));
+    }
 }
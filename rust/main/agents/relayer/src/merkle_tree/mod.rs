@@ -1,2 +1,6 @@
+pub(crate) mod availability;
 pub(crate) mod builder;
+pub(crate) mod observer;
+pub(crate) mod ordering;
 pub(crate) mod processor;
+pub(crate) mod shutdown;
@@ -0,0 +1,298 @@
+//! Typed events of the merkle tree, for instrumenting it from outside.
+//!
+//! Observers are called synchronously, while the tree is locked, so they must
+//! not block: anything slow should be handed off to another task. A panicking
+//! observer doesn't take the tree down with it, its panics are caught and
+//! counted instead.
+
+use std::{
+    fmt::Debug,
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use eyre::Result;
+use hyperlane_base::CoreMetrics;
+use hyperlane_core::{
+    accumulator::instrumentation::{HashCount, HashOperation},
+    HyperlaneDomain, H256,
+};
+use prometheus::{HistogramVec, IntCounterVec};
+use tracing::warn;
+
+/// Why an origin's merkle tree can no longer be trusted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeDivergence {
+    /// Origin of the tree
+    pub domain: HyperlaneDomain,
+    /// Number of leaves the tree held when it diverged
+    pub leaf_count: u32,
+    /// Why the tree diverged
+    pub reason: String,
+}
+
+/// Receives the events of a merkle tree. Every event is a no-op by default,
+/// and trees without an observer skip computing them altogether.
+pub trait MerkleTreeObserver: Debug + Send + Sync {
+    /// A leaf was ingested, after which the tree has `root`
+    fn on_ingest(&self, _domain: &HyperlaneDomain, _index: u32, _id: H256, _root: H256) {}
+
+    /// A proof of `leaf` against the root at `root_index` took `duration`
+    fn on_proof(
+        &self,
+        _domain: &HyperlaneDomain,
+        _leaf: u32,
+        _root_index: u32,
+        _duration: Duration,
+    ) {
+    }
+
+    /// The tree diverged
+    fn on_divergence(&self, _details: &TreeDivergence) {}
+
+    /// An `operation` on the tree computed `count` hashes. Only called with
+    /// the `hash-metrics` feature enabled.
+    fn on_hashes(&self, _domain: &HyperlaneDomain, _operation: HashOperation, _count: HashCount) {}
+}
+
+/// Reports the events of every origin's tree as Prometheus metrics
+#[derive(Debug, Clone)]
+pub struct PrometheusMerkleTreeObserver {
+    leaves_ingested: IntCounterVec,
+    proof_duration: HistogramVec,
+    divergences: IntCounterVec,
+    hashes: IntCounterVec,
+    hashed_bytes: IntCounterVec,
+}
+
+impl PrometheusMerkleTreeObserver {
+    pub fn new(metrics: &CoreMetrics) -> Result<Self> {
+        Ok(Self {
+            leaves_ingested: metrics.new_int_counter(
+                "merkle_tree_leaves_ingested",
+                "Number of leaves ingested into the origin merkle tree",
+                &["origin"],
+            )?,
+            proof_duration: metrics.new_histogram(
+                "merkle_tree_proof_duration_seconds",
+                "Time taken to prove a leaf of the origin merkle tree",
+                &["origin"],
+                vec![0.00001, 0.0001, 0.001, 0.01, 0.1, 1.0],
+            )?,
+            divergences: metrics.new_int_counter(
+                "merkle_tree_divergences",
+                "Number of times the origin merkle tree diverged",
+                &["origin"],
+            )?,
+            hashes: metrics.new_int_counter(
+                "merkle_tree_hashes",
+                "Number of hashes computed by operations on the origin merkle tree",
+                &["origin", "operation"],
+            )?,
+            hashed_bytes: metrics.new_int_counter(
+                "merkle_tree_hashed_bytes",
+                "Number of bytes hashed by operations on the origin merkle tree",
+                &["origin", "operation"],
+            )?,
+        })
+    }
+}
+
+impl MerkleTreeObserver for PrometheusMerkleTreeObserver {
+    fn on_ingest(&self, domain: &HyperlaneDomain, _index: u32, _id: H256, _root: H256) {
+        self.leaves_ingested
+            .with_label_values(&[domain.name()])
+            .inc();
+    }
+
+    fn on_proof(&self, domain: &HyperlaneDomain, _leaf: u32, _root_index: u32, duration: Duration) {
+        self.proof_duration
+            .with_label_values(&[domain.name()])
+            .observe(duration.as_secs_f64());
+    }
+
+    fn on_divergence(&self, details: &TreeDivergence) {
+        self.divergences
+            .with_label_values(&[details.domain.name()])
+            .inc();
+    }
+
+    fn on_hashes(&self, domain: &HyperlaneDomain, operation: HashOperation, count: HashCount) {
+        let labels = [domain.name(), operation.as_str()];
+        self.hashes.with_label_values(&labels).inc_by(count.hashes);
+        self.hashed_bytes
+            .with_label_values(&labels)
+            .inc_by(count.bytes);
+    }
+}
+
+/// The observer of one origin's tree, isolating the tree from its panics
+#[derive(Debug)]
+pub(crate) struct ObserverHandle {
+    domain: HyperlaneDomain,
+    observer: Arc<dyn MerkleTreeObserver>,
+    panics: AtomicU64,
+}
+
+impl ObserverHandle {
+    pub fn new(domain: HyperlaneDomain, observer: Arc<dyn MerkleTreeObserver>) -> Self {
+        Self {
+            domain,
+            observer,
+            panics: AtomicU64::new(0),
+        }
+    }
+
+    pub fn domain(&self) -> &HyperlaneDomain {
+        &self.domain
+    }
+
+    /// Number of times the observer panicked
+    pub fn panics(&self) -> u64 {
+        self.panics.load(Ordering::Relaxed)
+    }
+
+    /// Pass an event to the observer, catching and counting its panics
+    pub fn notify(&self, event: &str, f: impl FnOnce(&dyn MerkleTreeObserver, &HyperlaneDomain)) {
+        let observer = self.observer.as_ref();
+        if catch_unwind(AssertUnwindSafe(|| f(observer, &self.domain))).is_err() {
+            let panics = self.panics.fetch_add(1, Ordering::Relaxed) + 1;
+            warn!(
+                domain = self.domain.name(),
+                event, panics, "Merkle tree observer panicked"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Mutex;
+
+    use hyperlane_core::{accumulator::incremental::IncrementalMerkle, KnownHyperlaneDomain};
+
+    use super::*;
+    use crate::merkle_tree::{availability::TreeAvailability, builder::MerkleTreeBuilder};
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    enum Event {
+        Ingest(u32, H256, H256),
+        Proof(u32, u32),
+        Divergence(TreeDivergence),
+    }
+
+    #[derive(Debug, Default)]
+    struct RecordingObserver {
+        events: Mutex<Vec<Event>>,
+    }
+
+    impl MerkleTreeObserver for RecordingObserver {
+        fn on_ingest(&self, _domain: &HyperlaneDomain, index: u32, id: H256, root: H256) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(Event::Ingest(index, id, root));
+        }
+
+        fn on_proof(
+            &self,
+            _domain: &HyperlaneDomain,
+            leaf: u32,
+            root_index: u32,
+            _duration: Duration,
+        ) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(Event::Proof(leaf, root_index));
+        }
+
+        fn on_divergence(&self, details: &TreeDivergence) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(Event::Divergence(details.clone()));
+        }
+    }
+
+    #[derive(Debug)]
+    struct PanickingObserver;
+
+    impl MerkleTreeObserver for PanickingObserver {
+        fn on_ingest(&self, _domain: &HyperlaneDomain, _index: u32, _id: H256, _root: H256) {
+            panic!("observer failed on ingest");
+        }
+
+        fn on_proof(
+            &self,
+            _domain: &HyperlaneDomain,
+            _leaf: u32,
+            _root_index: u32,
+            _duration: Duration,
+        ) {
+            panic!("observer failed on proof");
+        }
+    }
+
+    fn leaves() -> Vec<H256> {
+        (1..=3).map(H256::from_low_u64_be).collect()
+    }
+
+    #[tokio::test]
+    async fn test_observer_receives_events_in_order() {
+        let domain: HyperlaneDomain = KnownHyperlaneDomain::Ethereum.into();
+        let observer = Arc::new(RecordingObserver::default());
+        let mut tree = MerkleTreeBuilder::new().with_observer(domain.clone(), observer.clone());
+
+        tree.ingest_message_ids(&leaves()).await.unwrap();
+        tree.get_proof(1, 2).unwrap();
+        tree.set_availability(TreeAvailability::Ready, "caught up");
+        let reason = "Leaf index 5 is missing".to_owned();
+        tree.set_availability(
+            TreeAvailability::Diverged {
+                reason: reason.clone(),
+            },
+            &reason,
+        );
+
+        let mut expected_tree = IncrementalMerkle::default();
+        let mut expected = vec![];
+        for (index, id) in leaves().into_iter().enumerate() {
+            expected_tree.ingest(id);
+            expected.push(Event::Ingest(index as u32, id, expected_tree.root()));
+        }
+        expected.push(Event::Proof(1, 2));
+        expected.push(Event::Divergence(TreeDivergence {
+            domain,
+            leaf_count: 3,
+            reason,
+        }));
+        assert_eq!(*observer.events.lock().unwrap(), expected);
+        assert_eq!(tree.observer_panics(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_observer_panics_are_caught_and_counted() {
+        let domain: HyperlaneDomain = KnownHyperlaneDomain::Ethereum.into();
+        let mut tree = MerkleTreeBuilder::new().with_observer(domain, Arc::new(PanickingObserver));
+
+        tree.ingest_message_ids(&leaves()).await.unwrap();
+        let proof = tree.get_proof(0, 2).unwrap();
+        assert_eq!(tree.count(), 3);
+        assert_eq!(tree.proof_root(&proof), tree.snapshot().root());
+        assert_eq!(tree.observer_panics(), 4);
+
+        // Events the observer doesn't override don't panic
+        tree.set_availability(
+            TreeAvailability::Diverged {
+                reason: "test".to_owned(),
+            },
+            "test",
+        );
+        assert_eq!(tree.observer_panics(), 4);
+    }
+}
@@ -0,0 +1,212 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use hyperlane_core::MerkleTreeInsertion;
+use tokio::time::Instant;
+
+/// Maximum number of out-of-order leaves held before new ones are rejected
+pub const DEFAULT_ORDERING_BUFFER_CAPACITY: usize = 10_000;
+/// How long a gap may stay open before the buffer is considered stalled
+pub const DEFAULT_ORDERING_STALL_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Errors returned when buffering a leaf
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum OrderingBufferError {
+    /// The buffer is at capacity and the leaf can't be released yet
+    #[error("Ordering buffer is full ({capacity} leaves) while waiting for leaf index {missing}")]
+    Full {
+        /// Configured capacity of the buffer
+        capacity: usize,
+        /// The leaf index the buffer is waiting for
+        missing: u32,
+    },
+    /// A different leaf was already buffered at this index
+    #[error("Conflicting insertions for leaf index {0}")]
+    Conflict(u32),
+}
+
+/// Sits between the indexer output and the `MerkleTreeBuilder`, holding
+/// insertions that arrive out of order and releasing them strictly by leaf
+/// index, so the builder never observes a gap.
+#[derive(Debug)]
+pub struct OrderingBuffer {
+    next_index: u32,
+    pending: BTreeMap<u32, MerkleTreeInsertion>,
+    capacity: usize,
+    stall_timeout: Duration,
+    /// When the buffer started waiting on the current gap
+    gap_opened_at: Option<Instant>,
+}
+
+impl Default for OrderingBuffer {
+    fn default() -> Self {
+        Self::new(
+            0,
+            DEFAULT_ORDERING_BUFFER_CAPACITY,
+            DEFAULT_ORDERING_STALL_TIMEOUT,
+        )
+    }
+}
+
+impl OrderingBuffer {
+    pub fn new(next_index: u32, capacity: usize, stall_timeout: Duration) -> Self {
+        Self {
+            next_index,
+            pending: BTreeMap::new(),
+            capacity,
+            stall_timeout,
+            gap_opened_at: None,
+        }
+    }
+
+    /// The next leaf index to be released
+    pub fn next_index(&self) -> u32 {
+        self.next_index
+    }
+
+    /// Number of leaves held back waiting for a missing index
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Buffer an insertion. Leaves that were already released are ignored.
+    /// Returns whether the insertion was newly buffered.
+    pub fn push(&mut self, insertion: MerkleTreeInsertion) -> Result<bool, OrderingBufferError> {
+        let index = insertion.index();
+        if index < self.next_index {
+            return Ok(false);
+        }
+        if let Some(existing) = self.pending.get(&index) {
+            return match existing == &insertion {
+                true => Ok(false),
+                false => Err(OrderingBufferError::Conflict(index)),
+            };
+        }
+        // Always leave room for the missing leaf itself, otherwise the buffer could never drain
+        if index != self.next_index && self.pending.len() + 1 >= self.capacity {
+            return Err(OrderingBufferError::Full {
+                capacity: self.capacity,
+                missing: self.next_index,
+            });
+        }
+        self.pending.insert(index, insertion);
+        if index != self.next_index && self.gap_opened_at.is_none() {
+            self.gap_opened_at = Some(Instant::now());
+        }
+        Ok(true)
+    }
+
+    /// Release all contiguous leaves starting at `next_index`, in order.
+    pub fn pop_ready(&mut self) -> Vec<MerkleTreeInsertion> {
+        let mut ready = vec![];
+        while let Some(insertion) = self.pending.remove(&self.next_index) {
+            ready.push(insertion);
+            self.next_index += 1;
+        }
+        if !ready.is_empty() {
+            self.gap_opened_at = (!self.pending.is_empty()).then(Instant::now);
+        }
+        ready
+    }
+
+    /// Returns the missing leaf index if the buffer has been waiting for it
+    /// for longer than the stall timeout.
+    pub fn stalled(&self) -> Option<u32> {
+        self.stalled_at(Instant::now())
+    }
+
+    fn stalled_at(&self, now: Instant) -> Option<u32> {
+        let opened_at = self.gap_opened_at?;
+        (now.saturating_duration_since(opened_at) >= self.stall_timeout).then_some(self.next_index)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use hyperlane_core::H256;
+    use rand::seq::SliceRandom;
+
+    use super::*;
+
+    fn insertion(index: u32) -> MerkleTreeInsertion {
+        MerkleTreeInsertion::new(index, H256::from_low_u64_be(index as u64))
+    }
+
+    #[test]
+    fn releases_shuffled_leaves_in_order() {
+        let mut indices: Vec<u32> = (0..200).collect();
+        indices.shuffle(&mut rand::thread_rng());
+
+        let mut buffer = OrderingBuffer::default();
+        let mut released = vec![];
+        for index in indices {
+            buffer.push(insertion(index)).unwrap();
+            released.extend(buffer.pop_ready().into_iter().map(|i| i.index()));
+        }
+
+        assert_eq!(released, (0..200).collect::<Vec<_>>());
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.next_index(), 200);
+        assert_eq!(buffer.stalled(), None);
+    }
+
+    #[test]
+    fn ignores_already_released_and_duplicate_leaves() {
+        let mut buffer = OrderingBuffer::default();
+        assert!(buffer.push(insertion(0)).unwrap());
+        assert_eq!(buffer.pop_ready().len(), 1);
+        assert!(!buffer.push(insertion(0)).unwrap());
+
+        assert!(buffer.push(insertion(2)).unwrap());
+        assert!(!buffer.push(insertion(2)).unwrap());
+        assert_eq!(
+            buffer.push(MerkleTreeInsertion::new(2, H256::zero())),
+            Err(OrderingBufferError::Conflict(2))
+        );
+    }
+
+    #[test]
+    fn raises_stall_alarm_on_permanent_gap() {
+        let timeout = Duration::from_secs(60);
+        let mut buffer = OrderingBuffer::new(0, 100, timeout);
+        // leaf 1 never arrives
+        for index in [0, 2, 3, 4] {
+            buffer.push(insertion(index)).unwrap();
+        }
+        assert_eq!(buffer.pop_ready().len(), 1);
+        assert_eq!(buffer.stalled(), None);
+
+        let later = Instant::now() + timeout;
+        assert_eq!(buffer.stalled_at(later), Some(1));
+
+        // closing the gap clears the alarm
+        buffer.push(insertion(1)).unwrap();
+        assert_eq!(buffer.pop_ready().len(), 4);
+        assert_eq!(buffer.stalled_at(later), None);
+    }
+
+    #[test]
+    fn memory_is_bounded() {
+        let capacity = 10;
+        let mut buffer = OrderingBuffer::new(0, capacity, DEFAULT_ORDERING_STALL_TIMEOUT);
+        for index in 1..capacity as u32 {
+            buffer.push(insertion(index)).unwrap();
+        }
+        assert_eq!(
+            buffer.push(insertion(capacity as u32)),
+            Err(OrderingBufferError::Full {
+                capacity,
+                missing: 0
+            })
+        );
+        assert!(buffer.len() < capacity);
+
+        // the missing leaf is still accepted, unblocking everything
+        buffer.push(insertion(0)).unwrap();
+        assert_eq!(buffer.pop_ready().len(), capacity);
+        assert!(buffer.is_empty());
+    }
+}
@@ -6,16 +6,43 @@ use std::{
 
 use async_trait::async_trait;
 use derive_new::new;
-use eyre::Result;
-use hyperlane_base::db::{HyperlaneDb, HyperlaneRocksDB};
-use hyperlane_core::{HyperlaneDomain, MerkleTreeInsertion};
-use prometheus::IntGauge;
+use eyre::{eyre, Result};
+use hyperlane_base::{
+    db::{CorruptionTolerance, DiskGuard, HyperlaneDb, HyperlaneRocksDB},
+    Alert, AlertDispatcher, AlertKind, CoreMetrics,
+};
+use hyperlane_core::{
+    accumulator::incremental::IncrementalMerkle, HyperlaneDomain, MerkleTreeInsertion,
+};
+use prometheus::{Gauge, IntGauge};
 use tokio::sync::RwLock;
-use tracing::trace;
+use tracing::{info, trace, warn};
 
 use crate::processor::ProcessorExt;
 
-use super::builder::MerkleTreeBuilder;
+use super::{
+    availability::TreeAvailability, builder::MerkleTreeBuilder, ordering::OrderingBuffer,
+    shutdown::startup_snapshot,
+};
+
+/// How many leaves past a missing one are looked up, to tell a gap in the
+/// indexed leaves from an origin with nothing new to index
+const GAP_PROBE_WINDOW: u32 = 16;
+
+/// Steps of ingesting a leaf into the tree, in the order they happen. Indexing
+/// a leaf durably records an intent to ingest it, in the same write as the
+/// leaf itself; the intent is only marked as applied once a snapshot of the
+/// tree covering the leaf is persisted. A crash between any two steps is
+/// recovered from by replaying the unapplied intents on startup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TreeIngestionStep {
+    /// The leaf was ingested into the in-memory tree
+    Ingested,
+    /// A snapshot of the tree covering the leaf was persisted
+    SnapshotPersisted,
+    /// The intent to ingest the leaf was marked as applied
+    IntentApplied,
+}
 
 /// Finds unprocessed merkle tree insertions and adds them to the prover sync
 #[derive(new)]
@@ -23,8 +50,37 @@ pub struct MerkleTreeProcessor {
     db: HyperlaneRocksDB,
     metrics: MerkleTreeProcessorMetrics,
     prover_sync: Arc<RwLock<MerkleTreeBuilder>>,
+    alerts: AlertDispatcher,
+    /// Fraction of the tree's capacity past which to warn that it's filling up
+    capacity_warning: f64,
+    /// Corrupted insertions are waited on to be re-indexed up to it
+    corruption: CorruptionTolerance,
     #[new(default)]
     leaf_index: u32,
+    #[new(default)]
+    ordering_buffer: OrderingBuffer,
+    /// Leaf index of the last corrupted insertion counted against the
+    /// tolerance
+    #[new(default)]
+    corrupted_leaf: Option<u32>,
+    /// Set once the tree hit an error it can't recover from by itself
+    #[new(default)]
+    diverged: bool,
+    /// Set once the tree can't take any more leaves
+    #[new(default)]
+    full: bool,
+    /// Set once the tree filled up past the capacity warning
+    #[new(default)]
+    capacity_warned: bool,
+    /// The latest persisted snapshot of the tree, loaded on the first tick
+    #[new(default)]
+    snapshot: Option<IncrementalMerkle>,
+    /// Ingestion pauses while the disk of the DB is almost full
+    #[new(default)]
+    disk_guard: Option<DiskGuard>,
+    /// Simulates a crash after a step of ingesting a leaf, for tests
+    #[new(default)]
+    fault: Option<(u32, TreeIngestionStep)>,
 }
 
 impl Debug for MerkleTreeProcessor {
@@ -47,17 +103,89 @@ impl ProcessorExt for MerkleTreeProcessor {
     /// One round of processing, extracted from infinite work loop for
     /// testing purposes.
     async fn tick(&mut self) -> Result<()> {
-        if let Some(insertion) = self.next_unprocessed_leaf()? {
-            // Feed the message to the prover sync
-            self.prover_sync
-                .write()
-                .await
-                .ingest_message_id(insertion.message_id())
-                .await?;
-
-            // Increase the leaf index to move on to the next leaf
-            self.leaf_index += 1;
+        if self.full {
+            // Terminal, as the contracts can't insert more leaves either
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            return Ok(());
+        }
+        if self.disk_guard.as_ref().is_some_and(DiskGuard::is_frozen) {
+            // Resumed from the same leaf once space is freed up
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            return Ok(());
+        }
+        if self.snapshot.is_none() {
+            self.recover()?;
+        }
+        if let Some(insertion) = self.next_unprocessed_leaf().await? {
+            self.buffer(insertion).await?;
+
+            // Feed any leaves that are now in order to the prover sync, and
+            // move on past them, including those indexed ahead of a gap that
+            // just closed
+            let ready = self.ordering_buffer.pop_ready();
+            self.leaf_index = self.ordering_buffer.next_index();
+            if !ready.is_empty() {
+                let prover_sync = self.prover_sync.clone();
+                let mut prover_sync = prover_sync.write().await;
+                for insertion in ready {
+                    // Ingestion is idempotent, leaves the tree already has are skipped
+                    if insertion.index() < prover_sync.count() {
+                        continue;
+                    }
+                    if u64::from(prover_sync.count()) >= prover_sync.capacity() {
+                        self.fill(&mut prover_sync, insertion.index());
+                        return Ok(());
+                    }
+                    if let Err(err) = prover_sync.ingest_insertion(&insertion).await {
+                        self.diverged = true;
+                        let reason = format!("{err:#}");
+                        self.set_availability(
+                            &mut prover_sync,
+                            TreeAvailability::Diverged {
+                                reason: reason.clone(),
+                            },
+                            &reason,
+                        );
+                        return Err(err);
+                    }
+                    self.inject_fault(insertion.index(), TreeIngestionStep::Ingested)?;
+                    self.persist_progress(&mut prover_sync, insertion.index())?;
+                    self.watch_capacity(&prover_sync);
+                }
+                if let TreeAvailability::Rebuilding { .. } = prover_sync.availability() {
+                    let progress = prover_sync.count();
+                    self.set_availability(
+                        &mut prover_sync,
+                        TreeAvailability::Rebuilding { progress },
+                        "Ingesting indexed leaves",
+                    );
+                }
+            }
         } else {
+            self.buffer_indexed_ahead().await?;
+            if let Some(missing) = self.ordering_buffer.stalled() {
+                warn!(
+                    missing_leaf_index = missing,
+                    buffered = self.ordering_buffer.len(),
+                    "Merkle tree ingestion stalled waiting for a missing leaf"
+                );
+                let reason = format!("Waiting for missing leaf index {missing}");
+                let mut prover_sync = self.prover_sync.write().await;
+                self.set_availability(
+                    &mut prover_sync,
+                    TreeAvailability::Diverged {
+                        reason: reason.clone(),
+                    },
+                    &reason,
+                );
+            } else if self.ordering_buffer.is_empty() && !self.diverged {
+                let mut prover_sync = self.prover_sync.write().await;
+                self.set_availability(
+                    &mut prover_sync,
+                    TreeAvailability::Ready,
+                    "Caught up with indexed leaves",
+                );
+            }
             tokio::time::sleep(Duration::from_secs(1)).await;
         }
         Ok(())
@@ -65,11 +193,225 @@ impl ProcessorExt for MerkleTreeProcessor {
 }
 
 impl MerkleTreeProcessor {
-    fn next_unprocessed_leaf(&mut self) -> Result<Option<MerkleTreeInsertion>> {
-        let leaf = if let Some(insertion) = self
+    /// Pause ingestion while the disk of the DB is almost full
+    pub fn with_disk_guard(mut self, disk_guard: Option<DiskGuard>) -> Self {
+        self.disk_guard = disk_guard;
+        self
+    }
+
+    fn set_availability(
+        &self,
+        prover_sync: &mut MerkleTreeBuilder,
+        availability: TreeAvailability,
+        reason: &str,
+    ) {
+        self.metrics
+            .availability_gauge
+            .set(availability.metric_value());
+        if let TreeAvailability::Diverged { .. } = availability {
+            let origin = self.db.domain().name();
+            self.alerts.dispatch(
+                Alert::new(
+                    AlertKind::CheckpointDivergence,
+                    format!("merkle_tree_diverged:{origin}"),
+                    format!("relayer/{origin}"),
+                    format!("Origin merkle tree diverged: {reason}"),
+                )
+                .with_details(serde_json::json!({ "leaf_count": prover_sync.count() })),
+            );
+        }
+        prover_sync.set_availability(availability, reason);
+    }
+
+    /// Mark the tree as full for good, as the origin can't insert more leaves
+    /// either. Its leaves can still be proven.
+    fn fill(&mut self, prover_sync: &mut MerkleTreeBuilder, leaf_index: u32) {
+        self.full = true;
+        let capacity = prover_sync.capacity();
+        let reason = format!(
+            "Leaf index {leaf_index} doesn't fit in a tree of depth {}",
+            prover_sync.depth()
+        );
+        let origin = self.db.domain().name();
+        self.alerts.dispatch(
+            Alert::new(
+                AlertKind::MerkleTreeFull,
+                format!("merkle_tree_full:{origin}"),
+                format!("relayer/{origin}"),
+                format!("Origin merkle tree is full: {reason}"),
+            )
+            .with_details(serde_json::json!({ "capacity": capacity })),
+        );
+        self.set_availability(prover_sync, TreeAvailability::Full { capacity }, &reason);
+    }
+
+    /// Report how much of its capacity the tree uses, warning once it's past
+    /// the configured fraction
+    fn watch_capacity(&mut self, prover_sync: &MerkleTreeBuilder) {
+        let used = prover_sync.count() as f64 / prover_sync.capacity() as f64;
+        self.metrics.capacity_used_gauge.set(used);
+        if used > self.capacity_warning && !self.capacity_warned {
+            self.capacity_warned = true;
+            warn!(
+                leaf_count = prover_sync.count(),
+                capacity = prover_sync.capacity(),
+                depth = prover_sync.depth(),
+                "Merkle tree is nearing its capacity"
+            );
+        }
+    }
+
+    /// Mark the tree as diverged for good, as it can't recover by itself
+    async fn diverge(&mut self, reason: String) {
+        self.diverged = true;
+        let mut prover_sync = self.prover_sync.write().await;
+        self.set_availability(
+            &mut prover_sync,
+            TreeAvailability::Diverged {
+                reason: reason.clone(),
+            },
+            &reason,
+        );
+    }
+
+    /// Load the latest persisted snapshot of the tree, preferring the one of
+    /// a shutdown snapshot set, and finish marking the intents it covers as
+    /// applied, in case we crashed before doing so. Leaves are then replayed
+    /// from the start to rebuild the in-memory tree, which must match the
+    /// snapshot once it covers the same leaves; the unapplied intents after
+    /// it are replayed in order.
+    fn recover(&mut self) -> Result<()> {
+        let (snapshot, source) = startup_snapshot(&self.db)?;
+        let applied = self.db.retrieve_merkle_tree_intents_applied()?;
+        if applied < snapshot.count() as u32 {
+            self.db
+                .mark_merkle_tree_intents_applied(snapshot.count() as u32)?;
+        }
+        if self
             .db
-            .retrieve_merkle_tree_insertion_by_leaf_index(&self.leaf_index)?
+            .retrieve_merkle_tree_intent(snapshot.count() as u32)?
+            .is_some()
         {
+            info!(
+                snapshot_leaf_count = snapshot.count(),
+                ?source,
+                "Replaying unapplied merkle tree intents"
+            );
+        }
+        self.snapshot = Some(snapshot);
+        Ok(())
+    }
+
+    /// Persist a snapshot of the tree after ingesting a leaf and mark the
+    /// intents it covers as applied. While replaying leaves that are already
+    /// covered by the persisted snapshot, check the tree against it instead.
+    fn persist_progress(
+        &mut self,
+        prover_sync: &mut MerkleTreeBuilder,
+        leaf_index: u32,
+    ) -> Result<()> {
+        let tree = prover_sync.snapshot();
+        let persisted = self.snapshot.clone().unwrap_or_default();
+        if tree.count() <= persisted.count() {
+            if tree.count() == persisted.count() && tree.root() != persisted.root() {
+                self.diverged = true;
+                let reason = format!(
+                    "Rebuilt merkle tree root {:?} does not match persisted snapshot root {:?} at {} leaves",
+                    tree.root(),
+                    persisted.root(),
+                    tree.count()
+                );
+                self.set_availability(
+                    prover_sync,
+                    TreeAvailability::Diverged {
+                        reason: reason.clone(),
+                    },
+                    &reason,
+                );
+                return Err(eyre!(reason));
+            }
+            return Ok(());
+        }
+        self.db.store_merkle_tree_snapshot(&tree)?;
+        self.snapshot = Some(tree.clone());
+        self.inject_fault(leaf_index, TreeIngestionStep::SnapshotPersisted)?;
+        self.db
+            .mark_merkle_tree_intents_applied(tree.count() as u32)?;
+        self.inject_fault(leaf_index, TreeIngestionStep::IntentApplied)
+    }
+
+    fn inject_fault(&self, leaf_index: u32, step: TreeIngestionStep) -> Result<()> {
+        match self.fault {
+            Some(fault) if fault == (leaf_index, step) => Err(eyre!(
+                "Injected fault after {step:?} of leaf index {leaf_index}"
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Simulate a crash after a step of ingesting a leaf
+    #[cfg(test)]
+    fn with_fault(mut self, leaf_index: u32, step: TreeIngestionStep) -> Self {
+        self.fault = Some((leaf_index, step));
+        self
+    }
+
+    /// Hold an insertion in the ordering buffer until every leaf before it
+    /// was released
+    async fn buffer(&mut self, insertion: MerkleTreeInsertion) -> Result<()> {
+        if let Err(err) = self.ordering_buffer.push(insertion) {
+            self.diverge(err.to_string()).await;
+            return Err(err.into());
+        }
+        Ok(())
+    }
+
+    /// Buffer the leaves indexed past the missing one at the leaf index, so
+    /// that a gap in the DB opens the stall timer of the ordering buffer while
+    /// an origin with nothing new to index doesn't
+    async fn buffer_indexed_ahead(&mut self) -> Result<()> {
+        let window = self.leaf_index + 1..=self.leaf_index.saturating_add(GAP_PROBE_WINDOW);
+        for leaf_index in window {
+            // Corrupted leaves are waited on once they're next
+            if let Ok(Some(insertion)) = self
+                .db
+                .retrieve_merkle_tree_insertion_by_leaf_index(&leaf_index)
+            {
+                self.buffer(insertion).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// A corrupted insertion can't be skipped, as the tree needs every leaf,
+    /// so it's waited on like an unindexed one until re-indexing overwrites
+    /// it. Each corrupted leaf counts against the tolerance once, past which
+    /// the tree diverges.
+    async fn next_unprocessed_leaf(&mut self) -> Result<Option<MerkleTreeInsertion>> {
+        let read = self
+            .db
+            .retrieve_merkle_tree_insertion_by_leaf_index(&self.leaf_index);
+        let indexed = match read {
+            Err(err) if err.is_corruption() && self.corrupted_leaf == Some(self.leaf_index) => {
+                trace!(leaf_index=?self.leaf_index, error=%err, "Merkle tree insertion is still corrupted, waiting for it to be re-indexed");
+                None
+            }
+            read => match self.corruption.skip_corrupted(read) {
+                Ok(Some(indexed)) => indexed,
+                Ok(None) => {
+                    self.corrupted_leaf = Some(self.leaf_index);
+                    None
+                }
+                Err(err) => {
+                    if err.is_corruption() {
+                        self.diverge(format!("Too many corrupted merkle tree insertions: {err}"))
+                            .await;
+                    }
+                    return Err(err.into());
+                }
+            },
+        };
+        let leaf = if let Some(insertion) = indexed {
             // Update the metrics
             self.metrics
                 .max_leaf_index_gauge
@@ -86,16 +428,369 @@ impl MerkleTreeProcessor {
 #[derive(Debug)]
 pub struct MerkleTreeProcessorMetrics {
     max_leaf_index_gauge: IntGauge,
+    availability_gauge: IntGauge,
+    capacity_used_gauge: Gauge,
 }
 
 impl MerkleTreeProcessorMetrics {
-    pub fn new() -> Self {
+    pub fn new(metrics: &CoreMetrics, origin: &HyperlaneDomain) -> Self {
         Self {
             max_leaf_index_gauge: IntGauge::new(
                 "max_leaf_index_gauge",
                 "The max merkle tree leaf index",
             )
             .unwrap(),
+            availability_gauge: metrics
+                .merkle_tree_availability()
+                .with_label_values(&[origin.name()]),
+            capacity_used_gauge: metrics
+                .merkle_tree_capacity_used()
+                .with_label_values(&[origin.name()]),
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use hyperlane_base::db::test_utils::{self, disk_watcher, MockFreeSpaceProbe};
+    use hyperlane_core::{
+        accumulator::TREE_DEPTH, HyperlaneLogStore, Indexed, KnownHyperlaneDomain, LogMeta, H256,
+    };
+    use prometheus::{IntCounter, Registry};
+
+    use super::*;
+    use crate::merkle_tree::ordering::DEFAULT_ORDERING_STALL_TIMEOUT;
+
+    const LEAVES: u32 = 6;
+    const STEPS: [TreeIngestionStep; 3] = [
+        TreeIngestionStep::Ingested,
+        TreeIngestionStep::SnapshotPersisted,
+        TreeIngestionStep::IntentApplied,
+    ];
+
+    fn insertion(index: u32) -> MerkleTreeInsertion {
+        MerkleTreeInsertion::new(index, H256::from_low_u64_be(index as u64 + 1))
+    }
+
+    async fn index(db: &HyperlaneRocksDB, leaves: std::ops::Range<u32>) {
+        let logs: Vec<_> = leaves
+            .map(|i| (Indexed::new(insertion(i)), LogMeta::random()))
+            .collect();
+        db.store_logs(&logs).await.unwrap();
+    }
+
+    fn corruption_tolerance(tolerance: u32) -> CorruptionTolerance {
+        CorruptionTolerance::new(
+            tolerance,
+            IntCounter::new("dummy_db_corrupted_records", "help string").unwrap(),
+        )
+    }
+
+    /// A freshly started processor, as after a crash
+    fn restart(db: &HyperlaneRocksDB) -> (MerkleTreeProcessor, Arc<RwLock<MerkleTreeBuilder>>) {
+        let core_metrics = CoreMetrics::new("test", 0, Registry::new()).unwrap();
+        let (processor, prover_sync, _) = restart_with_depth(db, TREE_DEPTH, &core_metrics);
+        (processor, prover_sync)
+    }
+
+    fn restart_with_depth(
+        db: &HyperlaneRocksDB,
+        depth: usize,
+        core_metrics: &CoreMetrics,
+    ) -> (
+        MerkleTreeProcessor,
+        Arc<RwLock<MerkleTreeBuilder>>,
+        MerkleTreeProcessorMetrics,
+    ) {
+        let prover_sync = Arc::new(RwLock::new(MerkleTreeBuilder::with_depth(depth)));
+        let processor = MerkleTreeProcessor::new(
+            db.clone(),
+            MerkleTreeProcessorMetrics::new(core_metrics, db.domain()),
+            prover_sync.clone(),
+            AlertDispatcher::default(),
+            0.75,
+            corruption_tolerance(1),
+        );
+        let metrics = MerkleTreeProcessorMetrics::new(core_metrics, db.domain());
+        (processor, prover_sync, metrics)
+    }
+
+    /// Tick until every indexed leaf was processed or the processor crashed
+    async fn run(processor: &mut MerkleTreeProcessor, indexed: u32) -> Result<()> {
+        while processor.leaf_index < indexed {
+            processor.tick().await?;
+        }
+        Ok(())
+    }
+
+    fn expected_tree(leaves: u32) -> IncrementalMerkle {
+        let mut tree = IncrementalMerkle::default();
+        for i in 0..leaves {
+            tree.ingest(insertion(i).message_id());
+        }
+        tree
+    }
+
+    async fn assert_consistent(db: &HyperlaneRocksDB, prover_sync: &RwLock<MerkleTreeBuilder>) {
+        let expected = expected_tree(LEAVES);
+        let prover_sync = prover_sync.read().await;
+        assert_eq!(prover_sync.snapshot(), expected);
+        assert!(!matches!(
+            prover_sync.availability(),
+            TreeAvailability::Diverged { .. }
+        ));
+        assert_eq!(db.retrieve_merkle_tree_snapshot().unwrap(), Some(expected));
+        assert_eq!(db.retrieve_merkle_tree_intents_applied().unwrap(), LEAVES);
+        for i in 0..LEAVES {
+            assert_eq!(db.retrieve_merkle_tree_intent(i).unwrap(), None);
+            assert_eq!(
+                db.retrieve_merkle_tree_insertion_by_leaf_index(&i).unwrap(),
+                Some(insertion(i))
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_recovers_from_crash_between_every_step() {
+        // Crash while catching up, on the last indexed leaf, and on a leaf
+        // indexed after the first run
+        for crash_leaf in [1, 3, 4] {
+            for step in STEPS {
+                test_utils::run_test_db(|db| async move {
+                    let db = HyperlaneRocksDB::new(&KnownHyperlaneDomain::Test1.into(), db);
+                    index(&db, 0..4).await;
+
+                    let (processor, _) = restart(&db);
+                    let mut processor = processor.with_fault(crash_leaf, step);
+                    if crash_leaf >= 4 {
+                        run(&mut processor, 4).await.unwrap();
+                        index(&db, 4..LEAVES).await;
+                    }
+                    assert!(run(&mut processor, LEAVES).await.is_err());
+
+                    // The indexer keeps going while the tree processor is down
+                    index(&db, 4..LEAVES).await;
+                    let (mut processor, prover_sync) = restart(&db);
+                    run(&mut processor, LEAVES).await.unwrap();
+                    assert_consistent(&db, &prover_sync).await;
+                })
+                .await;
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_recovers_from_crash_during_replay() {
+        for step in STEPS {
+            test_utils::run_test_db(|db| async move {
+                let db = HyperlaneRocksDB::new(&KnownHyperlaneDomain::Test1.into(), db);
+                index(&db, 0..LEAVES).await;
+
+                let (processor, _) = restart(&db);
+                let mut processor = processor.with_fault(4, step);
+                assert!(run(&mut processor, LEAVES).await.is_err());
+                // Crash again while replaying leaves covered by the snapshot
+                let (processor, _) = restart(&db);
+                let mut processor = processor.with_fault(2, step);
+                if step == TreeIngestionStep::Ingested {
+                    assert!(run(&mut processor, LEAVES).await.is_err());
+                } else {
+                    // Nothing is persisted for replayed leaves, so there's no
+                    // later step to crash after
+                    run(&mut processor, LEAVES).await.unwrap();
+                }
+
+                let (mut processor, prover_sync) = restart(&db);
+                run(&mut processor, LEAVES).await.unwrap();
+                assert_consistent(&db, &prover_sync).await;
+            })
+            .await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_diverges_when_replay_does_not_match_snapshot() {
+        test_utils::run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(&KnownHyperlaneDomain::Test1.into(), db);
+            index(&db, 0..LEAVES).await;
+            let mut tampered = expected_tree(2);
+            tampered.ingest(H256::repeat_byte(0xff));
+            db.store_merkle_tree_snapshot(&tampered).unwrap();
+
+            let (mut processor, prover_sync) = restart(&db);
+            assert!(run(&mut processor, LEAVES).await.is_err());
+            assert!(matches!(
+                prover_sync.read().await.availability(),
+                TreeAvailability::Diverged { .. }
+            ));
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_full_tree_is_terminal() {
+        test_utils::run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(&KnownHyperlaneDomain::Test1.into(), db);
+            let core_metrics = CoreMetrics::new("test", 0, Registry::new()).unwrap();
+            let (mut processor, prover_sync, metrics) = restart_with_depth(&db, 4, &core_metrics);
+
+            index(&db, 0..12).await;
+            run(&mut processor, 12).await.unwrap();
+            assert!(!processor.capacity_warned);
+            index(&db, 12..16).await;
+            run(&mut processor, 16).await.unwrap();
+            // 13 of 16 leaves is past the 75% warning
+            assert!(processor.capacity_warned);
+            assert_eq!(metrics.capacity_used_gauge.get(), 1.0);
+
+            // A leaf past the capacity fills the tree instead of retrying it
+            index(&db, 16..17).await;
+            run(&mut processor, 17).await.unwrap();
+            assert!(processor.full);
+            processor.tick().await.unwrap();
+            let prover_sync = prover_sync.read().await;
+            assert_eq!(
+                prover_sync.availability(),
+                &TreeAvailability::Full { capacity: 16 }
+            );
+            assert!(prover_sync.availability().is_ready());
+            assert_eq!(prover_sync.count(), 16);
+            assert_eq!(metrics.availability_gauge.get(), 3);
+            assert_eq!(
+                prover_sync.snapshot().root_at_depth(4),
+                expected_tree(16).root_at_depth(4)
+            );
+        })
+        .await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_gap_in_indexed_leaves_raises_stall_alarm() {
+        test_utils::run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(&KnownHyperlaneDomain::Test1.into(), db);
+            let core_metrics = CoreMetrics::new("test", 0, Registry::new()).unwrap();
+            let (mut processor, prover_sync, metrics) =
+                restart_with_depth(&db, TREE_DEPTH, &core_metrics);
+            // Leaf 2 is missing while the ones after it are indexed
+            index(&db, 0..2).await;
+            index(&db, 3..LEAVES).await;
+
+            run(&mut processor, 2).await.unwrap();
+            processor.tick().await.unwrap();
+            assert_eq!(processor.leaf_index, 2);
+            assert_eq!(processor.ordering_buffer.len(), (LEAVES - 3) as usize);
+            assert_eq!(processor.ordering_buffer.stalled(), None);
+
+            // Every tick waiting on the missing leaf takes a second
+            for _ in 0..DEFAULT_ORDERING_STALL_TIMEOUT.as_secs() {
+                processor.tick().await.unwrap();
+            }
+            assert_eq!(processor.ordering_buffer.stalled(), Some(2));
+            assert!(matches!(
+                prover_sync.read().await.availability(),
+                TreeAvailability::Diverged { reason } if reason.contains("missing leaf index 2")
+            ));
+            assert_eq!(metrics.availability_gauge.get(), 2);
+
+            // Indexing the missing leaf releases the ones buffered behind it
+            index(&db, 2..3).await;
+            processor.tick().await.unwrap();
+            assert_eq!(processor.leaf_index, LEAVES);
+            assert!(processor.ordering_buffer.is_empty());
+
+            // Waiting for leaves that weren't dispatched yet isn't a stall
+            for _ in 0..=DEFAULT_ORDERING_STALL_TIMEOUT.as_secs() {
+                processor.tick().await.unwrap();
+            }
+            assert_eq!(processor.ordering_buffer.stalled(), None);
+            assert_eq!(metrics.availability_gauge.get(), 0);
+            assert_consistent(&db, &prover_sync).await;
+        })
+        .await;
+    }
+
+    /// Overwrite the stored insertion of a leaf with undecodable bytes
+    fn corrupt(db: &HyperlaneRocksDB, leaf_index: u32) {
+        db.store_bytes(
+            "merkle_tree_insertion_",
+            leaf_index.to_be_bytes(),
+            b"garbage",
+        )
+        .unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_corrupted_leaf_is_waited_on_until_reindexed() {
+        test_utils::run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(&KnownHyperlaneDomain::Test1.into(), db);
+            index(&db, 0..LEAVES).await;
+            corrupt(&db, 2);
+
+            let (mut processor, prover_sync) = restart(&db);
+            for _ in 0..5 {
+                processor.tick().await.unwrap();
+            }
+            assert_eq!(processor.leaf_index, 2);
+            // Counted once however often it's read
+            assert_eq!(processor.corruption.skipped(), 1);
+            assert!(!processor.diverged);
+
+            // Re-indexing the leaf repairs it
+            index(&db, 2..3).await;
+            run(&mut processor, LEAVES).await.unwrap();
+            assert_consistent(&db, &prover_sync).await;
+        })
+        .await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_diverges_past_corruption_tolerance() {
+        test_utils::run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(&KnownHyperlaneDomain::Test1.into(), db);
+            index(&db, 0..LEAVES).await;
+            corrupt(&db, 2);
+
+            let (mut processor, prover_sync) = restart(&db);
+            processor.corruption = corruption_tolerance(0);
+            let err = run(&mut processor, LEAVES).await.unwrap_err();
+            assert!(err.to_string().contains("merkle_tree_insertion"));
+            assert_eq!(processor.leaf_index, 2);
+            assert!(matches!(
+                prover_sync.read().await.availability(),
+                TreeAvailability::Diverged { .. }
+            ));
+        })
+        .await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_ingestion_pauses_while_the_disk_is_almost_full() {
+        test_utils::run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(&KnownHyperlaneDomain::Test1.into(), db);
+            index(&db, 0..LEAVES).await;
+            let probe = MockFreeSpaceProbe::default();
+            let watcher = disk_watcher(&probe);
+
+            let (processor, prover_sync) = restart(&db);
+            let mut processor = processor.with_disk_guard(Some(watcher.guard()));
+            probe.set(5000);
+            watcher.check();
+            run(&mut processor, 2).await.unwrap();
+
+            probe.set(50);
+            watcher.check();
+            for _ in 0..5 {
+                processor.tick().await.unwrap();
+            }
+            assert_eq!(processor.leaf_index, 2);
+            assert_eq!(prover_sync.read().await.count(), 2);
+            assert_eq!(db.retrieve_merkle_tree_intents_applied().unwrap(), 2);
+
+            probe.set(5000);
+            watcher.check();
+            run(&mut processor, LEAVES).await.unwrap();
+            assert_consistent(&db, &prover_sync).await;
+        })
+        .await;
+    }
+}
@@ -0,0 +1,276 @@
+//! Coordinated snapshot of every origin's merkle tree on shutdown.
+//!
+//! Trees are persisted after every leaf, but an origin in the middle of a
+//! slow ingestion round when the process exits leaves its latest leaves to be
+//! replayed. On shutdown, ingestion is frozen across all origins by taking
+//! the write lock of their trees, each within a timeout, and the snapshots of
+//! the frozen trees are written with their cursors in a single batch, along
+//! with a marker recording whether every origin made it into the set. The
+//! locks are only released once the process exits.
+
+use std::{
+    io::{Read, Write},
+    sync::Arc,
+    time::Duration,
+};
+
+use eyre::Result;
+use futures::future::join_all;
+use hyperlane_base::db::HyperlaneRocksDB;
+use hyperlane_core::{
+    accumulator::incremental::IncrementalMerkle, Decode, Encode, HyperlaneProtocolError,
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{OwnedRwLockWriteGuard, RwLock};
+use tracing::{info, warn};
+
+use super::builder::MerkleTreeBuilder;
+use crate::msg::decision::now;
+
+/// Marker of a shutdown snapshot set, written with the snapshots of all
+/// origins that made it into the set
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShutdownSnapshotMarker {
+    /// Unix timestamp, in seconds, of the shutdown
+    pub taken_at: u64,
+    /// Whether every origin's tree was snapshotted
+    pub complete: bool,
+    /// Origins whose tree couldn't be frozen in time
+    pub missing: Vec<String>,
+}
+
+impl Encode for ShutdownSnapshotMarker {
+    fn write_to<W>(&self, writer: &mut W) -> std::io::Result<usize>
+    where
+        W: Write,
+    {
+        let serialized = serde_json::to_vec(self)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?;
+        writer.write_all(&serialized)?;
+        Ok(serialized.len())
+    }
+}
+
+impl Decode for ShutdownSnapshotMarker {
+    fn read_from<R>(reader: &mut R) -> Result<Self, HyperlaneProtocolError>
+    where
+        R: Read,
+        Self: Sized,
+    {
+        serde_json::from_reader(reader).map_err(|err| {
+            HyperlaneProtocolError::IoError(std::io::Error::new(std::io::ErrorKind::Other, err))
+        })
+    }
+}
+
+/// Snapshots the trees of all origins together on shutdown
+#[derive(Debug)]
+pub struct ShutdownSnapshotter {
+    trees: Vec<(HyperlaneRocksDB, Arc<RwLock<MerkleTreeBuilder>>)>,
+    /// How long to wait for each origin to finish its ingestion round
+    timeout: Duration,
+}
+
+/// A committed shutdown snapshot set. Ingestion stays frozen on the origins
+/// that made it into the set until this is dropped, i.e. until exit.
+#[derive(Debug)]
+pub struct ShutdownSnapshot {
+    pub marker: ShutdownSnapshotMarker,
+    _frozen: Vec<OwnedRwLockWriteGuard<MerkleTreeBuilder>>,
+}
+
+impl ShutdownSnapshotter {
+    pub fn new(
+        trees: Vec<(HyperlaneRocksDB, Arc<RwLock<MerkleTreeBuilder>>)>,
+        timeout: Duration,
+    ) -> Self {
+        Self { trees, timeout }
+    }
+
+    /// Freeze ingestion on every origin, in parallel, and commit the
+    /// snapshots of the frozen trees in one batch. Origins that can't be
+    /// frozen within the timeout are left out of the set, which is then
+    /// marked as incomplete.
+    pub async fn snapshot(&self) -> Result<ShutdownSnapshot> {
+        let frozen = join_all(self.trees.iter().map(|(db, tree)| async move {
+            let guard = tokio::time::timeout(self.timeout, tree.clone().write_owned())
+                .await
+                .ok();
+            if guard.is_none() {
+                warn!(
+                    origin = db.domain().name(),
+                    timeout = ?self.timeout,
+                    "Timed out freezing merkle tree ingestion for the shutdown snapshot"
+                );
+            }
+            (db, guard)
+        }))
+        .await;
+
+        let missing: Vec<String> = frozen
+            .iter()
+            .filter(|(_, guard)| guard.is_none())
+            .map(|(db, _)| db.domain().name().to_owned())
+            .collect();
+        let marker = ShutdownSnapshotMarker {
+            taken_at: now(),
+            complete: missing.is_empty(),
+            missing,
+        };
+        if let Some((db, _)) = self.trees.first() {
+            let mut batch = db.batch();
+            for (db, guard) in &frozen {
+                let tree = guard.as_ref().map(|tree| tree.snapshot());
+                db.batch_merkle_tree_shutdown_snapshot(&mut batch, tree.as_ref(), &marker)?;
+            }
+            db.write(batch)?;
+        }
+        info!(
+            complete = marker.complete,
+            missing = ?marker.missing,
+            "Committed shutdown snapshot of the merkle trees"
+        );
+        Ok(ShutdownSnapshot {
+            marker,
+            _frozen: frozen.into_iter().filter_map(|(_, guard)| guard).collect(),
+        })
+    }
+}
+
+/// Where the tree an origin starts from comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotSource {
+    /// A shutdown snapshot set every origin made it into
+    CompleteSet,
+    /// An incomplete shutdown snapshot set this origin made it into
+    PartialSet,
+    /// The snapshot persisted by the origin itself after its latest leaf
+    Origin,
+}
+
+/// The snapshot of its tree an origin should start from: the one of the
+/// latest shutdown snapshot set, preferably complete, unless the origin
+/// isn't part of it or persisted a more recent snapshot since
+pub fn startup_snapshot(db: &HyperlaneRocksDB) -> Result<(IncrementalMerkle, SnapshotSource)> {
+    let snapshot = db.retrieve_merkle_tree_snapshot()?.unwrap_or_default();
+    let marker: Option<ShutdownSnapshotMarker> = db.retrieve_merkle_tree_shutdown_marker()?;
+    let Some(marker) = marker else {
+        return Ok((snapshot, SnapshotSource::Origin));
+    };
+    match db.retrieve_merkle_tree_shutdown_snapshot()? {
+        Some(tree) if tree.count() >= snapshot.count() => {
+            let source = if marker.complete {
+                SnapshotSource::CompleteSet
+            } else {
+                SnapshotSource::PartialSet
+            };
+            Ok((tree, source))
+        }
+        _ => Ok((snapshot, SnapshotSource::Origin)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use hyperlane_base::db::test_utils;
+    use hyperlane_core::{HyperlaneDomain, KnownHyperlaneDomain, H256};
+
+    use super::*;
+
+    async fn tree(leaves: u64) -> Arc<RwLock<MerkleTreeBuilder>> {
+        let mut tree = MerkleTreeBuilder::new();
+        let ids: Vec<_> = (1..=leaves).map(H256::from_low_u64_be).collect();
+        tree.ingest_message_ids(&ids).await.unwrap();
+        Arc::new(RwLock::new(tree))
+    }
+
+    fn db(domain: KnownHyperlaneDomain, db: &hyperlane_base::db::DB) -> HyperlaneRocksDB {
+        HyperlaneRocksDB::new(&HyperlaneDomain::from(domain), db.clone())
+    }
+
+    #[tokio::test]
+    async fn test_complete_set_is_preferred_on_startup() {
+        test_utils::run_test_db(|rocks| async move {
+            let fast = db(KnownHyperlaneDomain::Test1, &rocks);
+            let other = db(KnownHyperlaneDomain::Test2, &rocks);
+            let trees = vec![
+                (fast.clone(), tree(3).await),
+                (other.clone(), tree(5).await),
+            ];
+            let expected = trees[1].1.read().await.snapshot();
+            let snapshotter = ShutdownSnapshotter::new(trees.clone(), Duration::from_secs(1));
+
+            let snapshot = snapshotter.snapshot().await.unwrap();
+            assert!(snapshot.marker.complete);
+            // Ingestion stays frozen until the snapshot is dropped
+            assert!(trees[0].1.try_write().is_err());
+            drop(snapshot);
+            assert!(trees[0].1.try_write().is_ok());
+
+            assert_eq!(
+                startup_snapshot(&other).unwrap(),
+                (expected, SnapshotSource::CompleteSet)
+            );
+            assert_eq!(other.retrieve_merkle_tree_intents_applied().unwrap(), 5);
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_slow_origin_does_not_hold_back_the_others() {
+        test_utils::run_test_db(|rocks| async move {
+            let fast = db(KnownHyperlaneDomain::Test1, &rocks);
+            let slow = db(KnownHyperlaneDomain::Test2, &rocks);
+            let other = db(KnownHyperlaneDomain::Test3, &rocks);
+            // The slow origin's tree predates a tree snapshot of its own
+            let slow_snapshot = tree(2).await.read().await.snapshot();
+            slow.store_merkle_tree_snapshot(&slow_snapshot).unwrap();
+            let trees = vec![
+                (fast.clone(), tree(3).await),
+                (slow.clone(), tree(4).await),
+                (other.clone(), tree(1).await),
+            ];
+            // Stuck in an ingestion round past the timeout
+            let _ingesting = trees[1].1.clone().write_owned().await;
+            let snapshotter = ShutdownSnapshotter::new(trees.clone(), Duration::from_millis(50));
+
+            let snapshot = snapshotter.snapshot().await.unwrap();
+            let marker = ShutdownSnapshotMarker {
+                taken_at: snapshot.marker.taken_at,
+                complete: false,
+                missing: vec![slow.domain().name().to_owned()],
+            };
+            assert_eq!(snapshot.marker, marker);
+
+            // The others' snapshots are committed, marked as partial
+            for (db, tree) in [&trees[0], &trees[2]] {
+                assert!(tree.try_read().is_err());
+                assert_eq!(
+                    db.retrieve_merkle_tree_shutdown_marker::<ShutdownSnapshotMarker>()
+                        .unwrap(),
+                    Some(marker.clone())
+                );
+                let (tree, source) = startup_snapshot(db).unwrap();
+                assert_eq!(source, SnapshotSource::PartialSet);
+                assert_eq!(
+                    db.retrieve_merkle_tree_intents_applied().unwrap(),
+                    tree.count() as u32
+                );
+            }
+            assert_eq!(startup_snapshot(&fast).unwrap().0.count(), 3);
+
+            // The slow origin falls back to its own snapshot
+            assert_eq!(slow.retrieve_merkle_tree_shutdown_snapshot().unwrap(), None);
+            assert_eq!(
+                slow.retrieve_merkle_tree_shutdown_marker::<ShutdownSnapshotMarker>()
+                    .unwrap(),
+                Some(marker)
+            );
+            assert_eq!(
+                startup_snapshot(&slow).unwrap(),
+                (slow_snapshot, SnapshotSource::Origin)
+            );
+        })
+        .await;
+    }
+}
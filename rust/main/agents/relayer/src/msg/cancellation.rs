@@ -0,0 +1,294 @@
+//! Cancellation of pending messages that must not be delivered, e.g. because
+//! of a bad payload.
+//!
+//! Cancelled messages are terminal: they're marked as processed so they
+//! aren't picked up again, and pending operations of them are dropped before
+//! being prepared or submitted again. Messages can only be cancelled until a
+//! transaction delivering them was broadcast. Messages superseded by a newer
+//! one are cancelled the same way, as are messages of apps bouncing the same
+//! message back and forth too many times.
+
+use std::io::{Read, Write};
+
+use hyperlane_base::db::{DbError, HyperlaneDb, HyperlaneRocksDB};
+use hyperlane_core::{
+    Decode, Encode, HyperlaneMessage, HyperlaneProtocolError, Signable, SignedType, H160, H256,
+};
+use serde::{Deserialize, Serialize};
+
+use super::decision::{now, Decision, DecisionInputs, DecisionSnapshot, SkipReason};
+
+/// Who cancelled a message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "by", rename_all = "snake_case")]
+pub enum CancelledBy {
+    /// An operator of the relayer, through the admin server
+    Operator,
+    /// The sender of the message, with a signed request
+    Sender {
+        /// Address the request was signed by
+        address: H160,
+    },
+    /// The relayer, as a newer message of the same sender and recipient
+    /// supersedes it
+    Superseded {
+        /// Id of the newer message
+        newer_message_id: H256,
+    },
+    /// The relayer, as the same sender, recipient and body crossed its route
+    /// pair more times than allowed
+    RoundTripLimit {
+        /// Id of the tuple of the message, see `RoundTripTuple`
+        tuple_id: H256,
+    },
+}
+
+impl CancelledBy {
+    /// The terminal decision about messages cancelled this way
+    pub fn skip_reason(&self) -> SkipReason {
+        match self {
+            Self::Operator => SkipReason::CancelledByOperator,
+            Self::Sender { .. } => SkipReason::CancelledBySender,
+            Self::Superseded { .. } => SkipReason::Superseded,
+            Self::RoundTripLimit { .. } => SkipReason::RoundTripLimit,
+        }
+    }
+}
+
+/// Who cancelled a message, when and why
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MessageCancellation {
+    #[serde(flatten)]
+    pub cancelled_by: CancelledBy,
+    /// Unix timestamp, in seconds, of the cancellation
+    pub cancelled_at: u64,
+    pub reason: String,
+}
+
+impl Encode for MessageCancellation {
+    fn write_to<W>(&self, writer: &mut W) -> std::io::Result<usize>
+    where
+        W: Write,
+    {
+        let serialized = serde_json::to_vec(self)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?;
+        writer.write_all(&serialized)?;
+        Ok(serialized.len())
+    }
+}
+
+impl Decode for MessageCancellation {
+    fn read_from<R>(reader: &mut R) -> Result<Self, HyperlaneProtocolError>
+    where
+        R: Read,
+        Self: Sized,
+    {
+        serde_json::from_reader(reader).map_err(|err| {
+            HyperlaneProtocolError::IoError(std::io::Error::new(std::io::ErrorKind::Other, err))
+        })
+    }
+}
+
+/// A sender's request to cancel one of its messages. The sender signs the
+/// EIP-191 hash of the message id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CancellationRequest {
+    pub message_id: H256,
+}
+
+impl Signable for CancellationRequest {
+    fn signing_hash(&self) -> H256 {
+        self.message_id
+    }
+}
+
+/// Why a message couldn't be cancelled
+#[derive(Debug, thiserror::Error)]
+pub enum CancellationError {
+    #[error("Message {0:?} not found")]
+    NotFound(H256),
+    #[error("Message {0:?} was dispatched on several origins ({1:?}); qualify it with an origin")]
+    AmbiguousId(H256, Vec<u32>),
+    #[error("Message {0:?} was already cancelled")]
+    AlreadyCancelled(H256),
+    #[error("Message {0:?} was already delivered or dropped")]
+    AlreadyProcessed(H256),
+    #[error("A transaction delivering message {0:?} was already broadcast")]
+    AlreadyBroadcast(H256),
+    #[error("Sender {0:?} isn't allowed to cancel messages")]
+    SenderNotAllowed(H160),
+    #[error("Signature doesn't recover to the sender of message {0:?}")]
+    InvalidSignature(H256),
+    #[error(transparent)]
+    Db(#[from] DbError),
+}
+
+/// The address that signed a sender's cancellation request of `message`,
+/// checked against the message's sender and the senders allowed to cancel
+pub fn verify_sender_request(
+    message: &HyperlaneMessage,
+    signed: &SignedType<CancellationRequest>,
+    allowed_senders: &[H160],
+) -> Result<H160, CancellationError> {
+    let message_id = message.id();
+    let signer = signed
+        .recover()
+        .map_err(|_| CancellationError::InvalidSignature(message_id))?;
+    if signed.value.message_id != message_id || H256::from(signer) != message.sender {
+        return Err(CancellationError::InvalidSignature(message_id));
+    }
+    if !allowed_senders.contains(&signer) {
+        return Err(CancellationError::SenderNotAllowed(signer));
+    }
+    Ok(signer)
+}
+
+/// Cancel `message`, unless it was already delivered or a transaction
+/// delivering it was broadcast. The cancellation is recorded as an attempt
+/// of the message, along with who cancelled it, when and why.
+pub fn cancel_message(
+    db: &HyperlaneRocksDB,
+    message: &HyperlaneMessage,
+    cancelled_by: CancelledBy,
+    reason: String,
+    config_hash: String,
+) -> Result<MessageCancellation, CancellationError> {
+    let message_id = message.id();
+    if db
+        .retrieve_message_cancellation::<MessageCancellation>(&message_id)?
+        .is_some()
+    {
+        return Err(CancellationError::AlreadyCancelled(message_id));
+    }
+    if db.retrieve_message_broadcast(&message_id)? {
+        return Err(CancellationError::AlreadyBroadcast(message_id));
+    }
+    if db.retrieve_processed_by_nonce(&message.nonce)? == Some(true) {
+        return Err(CancellationError::AlreadyProcessed(message_id));
+    }
+
+    let cancellation = MessageCancellation {
+        cancelled_by,
+        cancelled_at: now(),
+        reason,
+    };
+    db.store_message_cancellation(&message_id, &cancellation)?;
+    // So the message processor doesn't pick the message up again
+    db.store_processed_by_nonce(&message.nonce, &true)?;
+    let snapshot = DecisionSnapshot::new(
+        message,
+        cancellation.cancelled_at,
+        config_hash,
+        DecisionInputs {
+            cancelled: Some(cancellation.clone()),
+            ..Default::default()
+        },
+        Decision::Skip(cancelled_by.skip_reason()),
+    );
+    db.store_decision_snapshot(&message_id, &snapshot)?;
+    Ok(cancellation)
+}
+
+#[cfg(test)]
+mod test {
+    use hyperlane_base::db::test_utils;
+    use hyperlane_core::{
+        HyperlaneDomain, HyperlaneSigner, HyperlaneSignerExt, KnownHyperlaneDomain,
+    };
+    use hyperlane_ethereum::test_keys::test_signers;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_sender_requests_are_verified() {
+        let signer = test_signers(0);
+        let sender = signer.eth_address();
+        let message = HyperlaneMessage {
+            sender: sender.into(),
+            ..Default::default()
+        };
+        let request = CancellationRequest {
+            message_id: message.id(),
+        };
+        let signed = signer.sign(request).await.unwrap();
+
+        assert_eq!(
+            verify_sender_request(&message, &signed, &[sender]).unwrap(),
+            sender
+        );
+        assert!(matches!(
+            verify_sender_request(&message, &signed, &[]),
+            Err(CancellationError::SenderNotAllowed(address)) if address == sender
+        ));
+
+        // Signed by someone else than the sender
+        let other = HyperlaneMessage {
+            sender: H256::from(H160::repeat_byte(1)),
+            ..Default::default()
+        };
+        let signed_other = signer
+            .sign(CancellationRequest {
+                message_id: other.id(),
+            })
+            .await
+            .unwrap();
+        assert!(matches!(
+            verify_sender_request(&other, &signed_other, &[sender]),
+            Err(CancellationError::InvalidSignature(_))
+        ));
+        // Signed for another message
+        assert!(matches!(
+            verify_sender_request(&message, &signed_other, &[sender]),
+            Err(CancellationError::InvalidSignature(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_cancellation_is_recorded_and_terminal() {
+        test_utils::run_test_db(|db| async move {
+            let domain = HyperlaneDomain::Known(KnownHyperlaneDomain::Test1);
+            let db = HyperlaneRocksDB::new(&domain, db);
+            let message = HyperlaneMessage::default();
+            db.store_message(&message, 0).unwrap();
+
+            let cancellation = cancel_message(
+                &db,
+                &message,
+                CancelledBy::Operator,
+                "bad payload".to_owned(),
+                "config_hash".to_owned(),
+            )
+            .unwrap();
+            assert_eq!(
+                db.retrieve_message_cancellation(&message.id()).unwrap(),
+                Some(cancellation.clone())
+            );
+            assert_eq!(db.retrieve_processed_by_nonce(&0).unwrap(), Some(true));
+
+            let snapshot: DecisionSnapshot = db
+                .retrieve_decision_snapshot(&message.id(), 0)
+                .unwrap()
+                .unwrap();
+            assert_eq!(
+                snapshot.decision,
+                Decision::Skip(SkipReason::CancelledByOperator)
+            );
+            assert_eq!(snapshot.inputs.cancelled, Some(cancellation));
+            assert!(snapshot
+                .explain(0)
+                .contains("Cancelled: by an operator at unix time"));
+
+            assert!(matches!(
+                cancel_message(
+                    &db,
+                    &message,
+                    CancelledBy::Operator,
+                    "again".to_owned(),
+                    "config_hash".to_owned(),
+                ),
+                Err(CancellationError::AlreadyCancelled(_))
+            ));
+        })
+        .await;
+    }
+}
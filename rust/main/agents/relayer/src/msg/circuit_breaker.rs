@@ -0,0 +1,568 @@
+use std::{
+    collections::VecDeque,
+    io::{Read, Write},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use hyperlane_base::{db::HyperlaneRocksDB, Alert, AlertDispatcher, AlertKind};
+use hyperlane_core::{
+    Decode, Encode, HyperlaneDomain, HyperlaneProtocolError, MessageKey, TxOutcome,
+};
+use prometheus::IntGauge;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use super::decision::now as unix_now;
+use crate::settings::CircuitBreakerConf;
+
+/// State of the circuit breaker of a destination
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// Messages are submitted as usual
+    Closed,
+    /// Submissions are paused, except for a single canary at a time
+    HalfOpen,
+    /// Submissions are paused until the next canary is due
+    Open,
+}
+
+impl CircuitState {
+    fn metric_value(&self) -> i64 {
+        match self {
+            Self::Closed => 0,
+            Self::HalfOpen => 1,
+            Self::Open => 2,
+        }
+    }
+}
+
+/// State an operator pinned a circuit breaker to, regardless of revert rates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitOverride {
+    Open,
+    Closed,
+}
+
+/// Whether the circuit breaker let a message that is otherwise ready through
+/// to submission
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitAdmission {
+    /// The breaker is closed
+    Closed,
+    /// The message is the canary of an open breaker
+    Canary,
+    /// The breaker is open, the message has to wait
+    Open,
+}
+
+/// The state of a circuit breaker as of its latest transition, as persisted
+/// and reported by the admin endpoint
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CircuitBreakerRecord {
+    pub state: CircuitState,
+    pub manual_override: Option<CircuitOverride>,
+    /// Unix timestamp, in seconds, of the latest transition
+    pub since: u64,
+    /// Why the latest transition happened
+    pub reason: String,
+}
+
+impl Encode for CircuitBreakerRecord {
+    fn write_to<W>(&self, writer: &mut W) -> std::io::Result<usize>
+    where
+        W: Write,
+    {
+        let serialized = serde_json::to_vec(self)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?;
+        writer.write_all(&serialized)?;
+        Ok(serialized.len())
+    }
+}
+
+impl Decode for CircuitBreakerRecord {
+    fn read_from<R>(reader: &mut R) -> Result<Self, HyperlaneProtocolError>
+    where
+        R: Read,
+        Self: Sized,
+    {
+        serde_json::from_reader(reader).map_err(|err| {
+            HyperlaneProtocolError::IoError(std::io::Error::new(std::io::ErrorKind::Other, err))
+        })
+    }
+}
+
+/// A circuit breaker's record along with the outcomes currently in its window
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CircuitBreakerStatus {
+    #[serde(flatten)]
+    pub record: CircuitBreakerRecord,
+    /// Process transactions within the window
+    pub samples: usize,
+    /// Process transactions within the window which reverted
+    pub reverts: usize,
+}
+
+#[derive(Debug)]
+struct Breaker {
+    record: CircuitBreakerRecord,
+    /// Outcomes of process transactions within the window, true if reverted
+    outcomes: VecDeque<(Instant, bool)>,
+    /// When the next canary may be let through while open
+    next_canary_at: Instant,
+    /// The canary in flight while half-open, and when it was let through
+    canary: Option<(MessageKey, Instant)>,
+    /// Consecutive canaries which succeeded since the breaker opened
+    canary_successes: u32,
+}
+
+/// Stops paying for process transactions to a destination where most of
+/// them revert, e.g. after a bad ISM upgrade.
+///
+/// The revert rate of process transactions is measured over a sliding
+/// window. Once it reaches the threshold over enough transactions, the
+/// breaker opens and messages to the destination wait instead of being
+/// submitted. Every canary interval a single message is let through as a
+/// canary, and the breaker closes after enough consecutive canaries
+/// succeeded. An operator can pin the breaker open or closed.
+///
+/// The breaker is shared by every origin delivering to the destination.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    conf: CircuitBreakerConf,
+    destination: HyperlaneDomain,
+    /// Database of the destination, the state is persisted to
+    db: HyperlaneRocksDB,
+    alerts: AlertDispatcher,
+    /// Labelled by destination
+    state_gauge: IntGauge,
+    breaker: Mutex<Breaker>,
+}
+
+impl CircuitBreaker {
+    /// Create the breaker of a destination, resuming its persisted state. A
+    /// breaker which was open or half-open resumes as open, its next canary
+    /// being due after a full canary interval.
+    pub fn new(
+        conf: CircuitBreakerConf,
+        destination: HyperlaneDomain,
+        db: HyperlaneRocksDB,
+        alerts: AlertDispatcher,
+        state_gauge: IntGauge,
+        now: Instant,
+    ) -> Self {
+        let persisted = db
+            .retrieve_circuit_breaker_state::<CircuitBreakerRecord>()
+            .unwrap_or_else(|err| {
+                warn!(?err, %destination, "Failed to read the circuit breaker state");
+                None
+            });
+        let mut record = persisted.unwrap_or_else(|| CircuitBreakerRecord {
+            state: CircuitState::Closed,
+            manual_override: None,
+            since: unix_now(),
+            reason: "Started".to_owned(),
+        });
+        if record.state == CircuitState::HalfOpen {
+            record.state = CircuitState::Open;
+        }
+        state_gauge.set(record.state.metric_value());
+        if record.state == CircuitState::Open {
+            warn!(%destination, reason = record.reason, "Resuming with the circuit breaker open");
+        }
+        Self {
+            breaker: Mutex::new(Breaker {
+                record,
+                outcomes: VecDeque::new(),
+                next_canary_at: now + conf.canary_interval,
+                canary: None,
+                canary_successes: 0,
+            }),
+            conf,
+            destination,
+            db,
+            alerts,
+            state_gauge,
+        }
+    }
+
+    /// Whether a message that is otherwise ready may be submitted. While the
+    /// breaker is open, the first message asking once a canary is due
+    /// becomes the canary, and stays it until its outcome is recorded or a
+    /// canary interval passed without one.
+    pub fn admit(&self, message: MessageKey, now: Instant) -> CircuitAdmission {
+        let mut breaker = self.breaker.lock().unwrap();
+        match breaker.record.manual_override {
+            Some(CircuitOverride::Open) => return CircuitAdmission::Open,
+            Some(CircuitOverride::Closed) => return CircuitAdmission::Closed,
+            None => {}
+        }
+        match (breaker.record.state, breaker.canary) {
+            (CircuitState::Closed, _) => CircuitAdmission::Closed,
+            (CircuitState::Open, _) if now < breaker.next_canary_at => CircuitAdmission::Open,
+            (CircuitState::HalfOpen, Some((canary, _))) if canary == message => {
+                CircuitAdmission::Canary
+            }
+            (CircuitState::HalfOpen, Some((_, let_through_at)))
+                if now.duration_since(let_through_at) < self.conf.canary_interval =>
+            {
+                CircuitAdmission::Open
+            }
+            (CircuitState::Open | CircuitState::HalfOpen, _) => {
+                breaker.canary = Some((message, now));
+                if breaker.record.state == CircuitState::Open {
+                    self.transition(
+                        &mut breaker,
+                        CircuitState::HalfOpen,
+                        "Canary due".to_owned(),
+                    );
+                }
+                let destination = &self.destination;
+                info!(%destination, %message, "Letting a canary through the circuit breaker");
+                CircuitAdmission::Canary
+            }
+        }
+    }
+
+    /// Record the outcome of a process transaction submitted for a message
+    pub fn record(&self, message: MessageKey, outcome: &TxOutcome, now: Instant) {
+        let reverted = !outcome.executed;
+        let mut breaker = self.breaker.lock().unwrap();
+        breaker.outcomes.push_back((now, reverted));
+        prune(&mut breaker.outcomes, now, self.conf.window);
+        if breaker.record.manual_override.is_some() {
+            return;
+        }
+        match breaker.record.state {
+            CircuitState::Closed => {
+                let samples = breaker.outcomes.len();
+                let reverts = breaker.outcomes.iter().filter(|(_, r)| *r).count();
+                let tripped = self.conf.revert_threshold.map_or(false, |threshold| {
+                    samples >= self.conf.min_samples && reverts as f64 >= threshold * samples as f64
+                });
+                if tripped {
+                    let reason =
+                        format!("{reverts} of the last {samples} process transactions reverted");
+                    self.open(&mut breaker, now, reason);
+                }
+            }
+            // The outcome of a transaction submitted before the breaker opened
+            CircuitState::Open => {}
+            CircuitState::HalfOpen => {
+                if !matches!(breaker.canary, Some((canary, _)) if canary == message) {
+                    return;
+                }
+                breaker.canary = None;
+                if reverted {
+                    self.open(&mut breaker, now, "Canary reverted".to_owned());
+                    return;
+                }
+                breaker.canary_successes += 1;
+                if breaker.canary_successes >= self.conf.canary_successes {
+                    let reason = format!("{} canaries succeeded", breaker.canary_successes);
+                    self.close(&mut breaker, reason);
+                }
+            }
+        }
+    }
+
+    /// Pin the breaker open or closed, or let it act on revert rates again
+    pub fn set_override(&self, manual_override: Option<CircuitOverride>, now: Instant) {
+        let mut breaker = self.breaker.lock().unwrap();
+        breaker.record.manual_override = manual_override;
+        match manual_override {
+            Some(CircuitOverride::Open) => {
+                self.open(&mut breaker, now, "Opened manually".to_owned())
+            }
+            Some(CircuitOverride::Closed) => self.close(&mut breaker, "Closed manually".to_owned()),
+            None => {
+                let state = breaker.record.state;
+                self.transition(&mut breaker, state, "Override lifted".to_owned());
+            }
+        }
+    }
+
+    /// How long a message the breaker held back should wait before asking
+    /// again, i.e. until the next canary may be due
+    pub fn retry_after(&self, now: Instant) -> Duration {
+        let breaker = self.breaker.lock().unwrap();
+        let due_at = match (breaker.record.state, breaker.canary) {
+            (CircuitState::HalfOpen, Some((_, let_through_at))) => {
+                let_through_at + self.conf.canary_interval
+            }
+            _ => breaker.next_canary_at,
+        };
+        let retry_after = due_at.saturating_duration_since(now);
+        if retry_after.is_zero() {
+            self.conf.canary_interval
+        } else {
+            retry_after
+        }
+    }
+
+    pub fn status(&self, now: Instant) -> CircuitBreakerStatus {
+        let mut breaker = self.breaker.lock().unwrap();
+        prune(&mut breaker.outcomes, now, self.conf.window);
+        CircuitBreakerStatus {
+            record: breaker.record.clone(),
+            samples: breaker.outcomes.len(),
+            reverts: breaker.outcomes.iter().filter(|(_, r)| *r).count(),
+        }
+    }
+
+    fn open(&self, breaker: &mut Breaker, now: Instant, reason: String) {
+        breaker.next_canary_at = now + self.conf.canary_interval;
+        breaker.canary = None;
+        breaker.canary_successes = 0;
+        let reopened = breaker.record.state != CircuitState::Closed;
+        self.transition(breaker, CircuitState::Open, reason.clone());
+        if reopened {
+            return;
+        }
+        self.alerts.dispatch(
+            Alert::new(
+                AlertKind::CircuitBreakerOpened,
+                format!("circuit_breaker_opened:{}", self.destination),
+                format!("relayer/{}", self.destination),
+                format!("Paused submissions to {}: {reason}", self.destination),
+            )
+            .with_details(serde_json::json!({
+                "canary_interval_seconds": self.conf.canary_interval.as_secs(),
+            })),
+        );
+    }
+
+    fn close(&self, breaker: &mut Breaker, reason: String) {
+        // Reverts from before the breaker opened would trip it right away
+        breaker.outcomes.clear();
+        breaker.canary = None;
+        breaker.canary_successes = 0;
+        self.transition(breaker, CircuitState::Closed, reason);
+    }
+
+    fn transition(&self, breaker: &mut Breaker, state: CircuitState, reason: String) {
+        let previous = breaker.record.state;
+        breaker.record.state = state;
+        breaker.record.since = unix_now();
+        breaker.record.reason = reason;
+        self.state_gauge.set(state.metric_value());
+        let destination = &self.destination;
+        let reason = &breaker.record.reason;
+        if state == CircuitState::Open && previous != CircuitState::Open {
+            warn!(%destination, ?previous, reason, "Circuit breaker opened");
+        } else {
+            info!(%destination, ?previous, ?state, reason, "Circuit breaker transitioned");
+        }
+        if let Err(err) = self.db.store_circuit_breaker_state(&breaker.record) {
+            warn!(?err, %destination, "Failed to persist the circuit breaker state");
+        }
+    }
+}
+
+/// Drop the outcomes which left the window
+fn prune(outcomes: &mut VecDeque<(Instant, bool)>, now: Instant, window: Duration) {
+    while let Some((time, _)) = outcomes.front() {
+        if now.duration_since(*time) < window {
+            break;
+        }
+        outcomes.pop_front();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ethers::types::{TransactionReceipt, U64};
+    use hyperlane_base::db::test_utils;
+    use hyperlane_core::H256;
+    use prometheus::{opts, IntGaugeVec};
+
+    use super::*;
+
+    const CANARY_INTERVAL: Duration = Duration::from_secs(60);
+    const ORIGIN: u32 = 1;
+
+    fn new_breaker(db: &HyperlaneRocksDB, start: Instant) -> CircuitBreaker {
+        let gauge = IntGaugeVec::new(opts!("circuit_breaker_state", "help"), &["destination"])
+            .unwrap()
+            .with_label_values(&["test2"]);
+        CircuitBreaker::new(
+            CircuitBreakerConf {
+                window: Duration::from_secs(600),
+                revert_threshold: Some(0.5),
+                min_samples: 4,
+                canary_interval: CANARY_INTERVAL,
+                canary_successes: 3,
+            },
+            HyperlaneDomain::new_test_domain("test2"),
+            db.clone(),
+            AlertDispatcher::default(),
+            gauge,
+            start,
+        )
+    }
+
+    /// The outcome of a process transaction, from its receipt
+    fn receipt(executed: bool) -> TxOutcome {
+        TransactionReceipt {
+            status: Some(U64::from(executed as u64)),
+            ..Default::default()
+        }
+        .into()
+    }
+
+    fn id(id: u64) -> MessageKey {
+        MessageKey::new(ORIGIN, H256::from_low_u64_be(id))
+    }
+
+    fn state(breaker: &CircuitBreaker) -> CircuitState {
+        breaker.status(Instant::now()).record.state
+    }
+
+    #[tokio::test]
+    async fn test_breaker_opens_on_revert_rate_and_closes_after_canaries() {
+        test_utils::run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(&HyperlaneDomain::new_test_domain("test2"), db);
+            let start = Instant::now();
+            let breaker = new_breaker(&db, start);
+
+            // Reverts below the minimum sample size don't open the breaker
+            for nonce in 0..3 {
+                breaker.record(id(nonce), &receipt(false), start);
+            }
+            assert_eq!(state(&breaker), CircuitState::Closed);
+            assert_eq!(breaker.admit(id(10), start), CircuitAdmission::Closed);
+
+            breaker.record(id(3), &receipt(true), start);
+            assert_eq!(state(&breaker), CircuitState::Open);
+            assert_eq!(breaker.state_gauge.get(), 2);
+            assert_eq!(
+                db.retrieve_circuit_breaker_state::<CircuitBreakerRecord>()
+                    .unwrap()
+                    .unwrap()
+                    .state,
+                CircuitState::Open
+            );
+            assert_eq!(breaker.admit(id(10), start), CircuitAdmission::Open);
+            assert_eq!(breaker.retry_after(start), CANARY_INTERVAL);
+
+            // A single canary is let through once due
+            let due = start + CANARY_INTERVAL;
+            assert_eq!(breaker.admit(id(10), due), CircuitAdmission::Canary);
+            assert_eq!(state(&breaker), CircuitState::HalfOpen);
+            assert_eq!(breaker.state_gauge.get(), 1);
+            assert_eq!(breaker.admit(id(11), due), CircuitAdmission::Open);
+            assert_eq!(breaker.admit(id(10), due), CircuitAdmission::Canary);
+
+            // Outcomes of other messages don't count as canaries
+            breaker.record(id(11), &receipt(true), due);
+            assert_eq!(state(&breaker), CircuitState::HalfOpen);
+
+            breaker.record(id(10), &receipt(true), due);
+            assert_eq!(breaker.admit(id(11), due), CircuitAdmission::Canary);
+            breaker.record(id(11), &receipt(true), due);
+            assert_eq!(state(&breaker), CircuitState::HalfOpen);
+            assert_eq!(breaker.admit(id(12), due), CircuitAdmission::Canary);
+            breaker.record(id(12), &receipt(true), due);
+            assert_eq!(state(&breaker), CircuitState::Closed);
+            assert_eq!(breaker.state_gauge.get(), 0);
+            assert_eq!(breaker.admit(id(13), due), CircuitAdmission::Closed);
+            // The reverts from before the breaker opened are forgotten
+            assert_eq!(breaker.status(due).samples, 0);
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_reverting_canary_reopens_the_breaker() {
+        test_utils::run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(&HyperlaneDomain::new_test_domain("test2"), db);
+            let start = Instant::now();
+            let breaker = new_breaker(&db, start);
+            for nonce in 0..4 {
+                breaker.record(id(nonce), &receipt(false), start);
+            }
+
+            let due = start + CANARY_INTERVAL;
+            assert_eq!(breaker.admit(id(10), due), CircuitAdmission::Canary);
+            breaker.record(id(10), &receipt(true), due);
+            assert_eq!(breaker.admit(id(11), due), CircuitAdmission::Canary);
+            breaker.record(id(11), &receipt(false), due);
+            assert_eq!(state(&breaker), CircuitState::Open);
+            assert_eq!(breaker.admit(id(12), due), CircuitAdmission::Open);
+
+            // Successes before the reverting canary don't count anymore
+            let due = due + CANARY_INTERVAL;
+            assert_eq!(breaker.admit(id(12), due), CircuitAdmission::Canary);
+            breaker.record(id(12), &receipt(true), due);
+            assert_eq!(state(&breaker), CircuitState::HalfOpen);
+
+            // A canary without an outcome is replaced after a canary interval
+            assert_eq!(breaker.admit(id(13), due), CircuitAdmission::Canary);
+            assert_eq!(breaker.admit(id(14), due), CircuitAdmission::Open);
+            let due = due + CANARY_INTERVAL;
+            assert_eq!(breaker.admit(id(14), due), CircuitAdmission::Canary);
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_canaries_are_told_apart_from_colliding_ids_of_other_origins() {
+        test_utils::run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(&HyperlaneDomain::new_test_domain("test2"), db);
+            let start = Instant::now();
+            let breaker = new_breaker(&db, start);
+            for nonce in 0..4 {
+                breaker.record(id(nonce), &receipt(false), start);
+            }
+
+            let due = start + CANARY_INTERVAL;
+            let canary = id(10);
+            let colliding = MessageKey::new(ORIGIN + 1, canary.id);
+            assert_eq!(breaker.admit(canary, due), CircuitAdmission::Canary);
+            assert_eq!(breaker.admit(colliding, due), CircuitAdmission::Open);
+            // The outcome of the other origin's message isn't the canary's
+            breaker.record(colliding, &receipt(true), due);
+            assert_eq!(breaker.admit(canary, due), CircuitAdmission::Canary);
+            assert_eq!(breaker.admit(colliding, due), CircuitAdmission::Open);
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_manual_override_and_persisted_state() {
+        test_utils::run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(&HyperlaneDomain::new_test_domain("test2"), db);
+            let start = Instant::now();
+            let breaker = new_breaker(&db, start);
+
+            breaker.set_override(Some(CircuitOverride::Open), start);
+            assert_eq!(
+                breaker.admit(id(1), start + CANARY_INTERVAL),
+                CircuitAdmission::Open
+            );
+
+            // A restarted breaker resumes as it was left
+            let restarted = new_breaker(&db, start);
+            assert_eq!(
+                restarted.status(start).record.manual_override,
+                Some(CircuitOverride::Open)
+            );
+            assert_eq!(state(&restarted), CircuitState::Open);
+
+            breaker.set_override(Some(CircuitOverride::Closed), start);
+            for nonce in 0..10 {
+                breaker.record(id(nonce), &receipt(false), start);
+            }
+            assert_eq!(breaker.admit(id(1), start), CircuitAdmission::Closed);
+
+            breaker.set_override(None, start);
+            assert_eq!(state(&breaker), CircuitState::Closed);
+            breaker.record(id(10), &receipt(false), start);
+            assert_eq!(state(&breaker), CircuitState::Open);
+        })
+        .await;
+    }
+}
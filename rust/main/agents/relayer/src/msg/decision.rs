@@ -0,0 +1,770 @@
+//! The inputs each relaying decision about a message is made from, and the
+//! pure state machine making it.
+//!
+//! Every attempt to prepare a message persists a [`DecisionSnapshot`] of the
+//! inputs it gathered and the decision reached, so that the decision can be
+//! explained and replayed later on. Snapshots only hold small, fixed-size
+//! values and truncated error messages, never message bodies or metadata.
+
+use std::{
+    fmt::{Display, Formatter},
+    io::{Read, Write},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use ethers::utils::hex;
+use hyperlane_core::{
+    Decode, Encode, HyperlaneMessage, HyperlaneProtocolError, QueueLane, ReprepareReason,
+    TxCostEstimate, H256, U256,
+};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    cancellation::{CancelledBy, MessageCancellation},
+    circuit_breaker::CircuitAdmission,
+    gas_payment::GasPolicyStatus,
+};
+
+/// Error messages are truncated to this many characters
+const MAX_ERROR_LEN: usize = 256;
+
+/// The outcome of a call made to gather a decision input
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Observed<T> {
+    /// The call returned a value
+    Value(T),
+    /// The call failed with this, truncated, error
+    Error(String),
+}
+
+impl<T: Clone> Observed<T> {
+    pub fn new<E: Display>(result: &Result<T, E>) -> Self {
+        match result {
+            Ok(value) => Self::Value(value.clone()),
+            Err(err) => Self::error(err),
+        }
+    }
+}
+
+impl<T> Observed<T> {
+    pub fn error(err: &impl Display) -> Self {
+        Self::Error(error_message(err))
+    }
+}
+
+/// Whether the message passed the relayer's message and address lists
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ListMembership {
+    pub whitelisted: bool,
+    pub blacklisted: bool,
+    /// Hex of the blacklisted address the message involves, if any
+    pub blacklisted_address: Option<String>,
+    /// Whether the destination is one this relayer delivers to
+    pub destination_serviced: bool,
+}
+
+impl ListMembership {
+    pub fn new(
+        whitelisted: bool,
+        blacklisted: bool,
+        blacklisted_address: Option<&[u8]>,
+        destination_serviced: bool,
+    ) -> Self {
+        Self {
+            whitelisted,
+            blacklisted,
+            blacklisted_address: blacklisted_address.map(|address| truncate(hex::encode(address))),
+            destination_serviced,
+        }
+    }
+}
+
+/// How building the ISM metadata went
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MetadataOutcome {
+    /// Metadata was built
+    Built,
+    /// The ISM's requirements couldn't be met yet, e.g. no quorum of
+    /// checkpoints covering the message
+    NotFetched,
+    /// The origin merkle tree was unavailable
+    TreeUnavailable(String),
+    /// Building metadata failed
+    Error(String),
+}
+
+/// What metadata was built from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MetadataInputs {
+    /// Number of leaves in the relayer's origin merkle tree
+    pub tree_count: u32,
+    /// Leaf index of the message, if it was indexed
+    pub leaf_index: Option<u32>,
+    /// Index of the quorum checkpoint metadata was built against, if any
+    pub checkpoint_index: Option<u32>,
+    pub outcome: MetadataOutcome,
+}
+
+/// The gas estimate of the process call
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GasQuote {
+    pub gas_limit: U256,
+    /// Gas price in the destination's smallest unit, if it is a whole number
+    pub gas_price: Option<U256>,
+    pub l2_gas_limit: Option<U256>,
+}
+
+impl From<&TxCostEstimate> for GasQuote {
+    fn from(estimate: &TxCostEstimate) -> Self {
+        Self {
+            gas_limit: estimate.gas_limit,
+            gas_price: estimate.gas_price.clone().try_into().ok(),
+            l2_gas_limit: estimate.l2_gas_limit,
+        }
+    }
+}
+
+/// What the gas payment policy made of the message's payments
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GasPolicyOutcome {
+    NoPaymentFound,
+    PolicyNotMet,
+    /// The policy is met, allowing the given gas limit
+    PolicyMet(U256),
+}
+
+impl From<&GasPolicyStatus> for GasPolicyOutcome {
+    fn from(status: &GasPolicyStatus) -> Self {
+        match status {
+            GasPolicyStatus::NoPaymentFound => Self::NoPaymentFound,
+            GasPolicyStatus::PolicyNotMet => Self::PolicyNotMet,
+            GasPolicyStatus::PolicyMet(gas_limit) => Self::PolicyMet(*gas_limit),
+        }
+    }
+}
+
+/// Everything a relaying decision is made from, in the order it's gathered.
+/// Inputs that weren't gathered because an earlier one decided the attempt
+/// are `None`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DecisionInputs {
+    /// Lane of the submitter's queue the attempt was popped from, which is
+    /// the priority lane if an operator asked to retry the message
+    #[serde(default)]
+    pub lane: QueueLane,
+    /// Who cancelled the message, when and why, if it was cancelled
+    #[serde(default)]
+    pub cancelled: Option<MessageCancellation>,
+    pub lists: ListMembership,
+    /// Whether the message was dispatched before the block the origin's
+    /// messages are delivered from
+    #[serde(default)]
+    pub before_delivery_start: bool,
+    /// Hard limit on the gas of process transactions to the destination
+    pub transaction_gas_limit: Option<U256>,
+    /// Highest gas price the message's route is submitted at
+    #[serde(default)]
+    pub gas_price_ceiling: Option<U256>,
+    pub delivered: Option<Observed<bool>>,
+    pub recipient_is_contract: Option<Observed<bool>>,
+    pub recipient_ism: Option<Observed<H256>>,
+    pub metadata_builder: Option<Observed<()>>,
+    pub metadata: Option<MetadataInputs>,
+    pub gas_quote: Option<Observed<GasQuote>>,
+    pub gas_policy: Option<Observed<GasPolicyOutcome>>,
+    /// Whether the destination's circuit breaker let the message through.
+    /// Only asked for messages which would be submitted otherwise.
+    pub circuit: Option<CircuitAdmission>,
+}
+
+impl DecisionInputs {
+    /// The error that decided the attempt, if any
+    pub fn error(&self) -> Option<&str> {
+        fn error<T>(observed: &Option<Observed<T>>) -> Option<&str> {
+            match observed {
+                Some(Observed::Error(err)) => Some(err.as_str()),
+                _ => None,
+            }
+        }
+        let metadata = self
+            .metadata
+            .as_ref()
+            .and_then(|metadata| match &metadata.outcome {
+                MetadataOutcome::TreeUnavailable(err) | MetadataOutcome::Error(err) => {
+                    Some(err.as_str())
+                }
+                _ => None,
+            });
+        error(&self.delivered)
+            .or_else(|| error(&self.recipient_is_contract))
+            .or_else(|| error(&self.recipient_ism))
+            .or_else(|| error(&self.metadata_builder))
+            .or(metadata)
+            .or_else(|| error(&self.gas_quote))
+            .or_else(|| error(&self.gas_policy))
+    }
+}
+
+/// Why the message processor didn't hand a message to the submitter
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    NotWhitelisted,
+    Blacklisted,
+    BlacklistedAddress,
+    UnservicedDestination,
+    BeforeDeliveryStart,
+    CancelledByOperator,
+    CancelledBySender,
+    Superseded,
+    RoundTripLimit,
+}
+
+/// What was decided in an attempt to relay a message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Decision {
+    /// The message isn't relayed at all
+    Skip(SkipReason),
+    /// The message was already delivered, only confirm it
+    Confirm,
+    /// The recipient isn't a contract, give up on the message
+    Drop,
+    /// Try preparing the message again later
+    Reprepare(ReprepareReason),
+    /// Submit the message with the given gas limit
+    Submit(U256),
+    /// The inputs end before a decision was reached
+    Incomplete,
+}
+
+impl Display for Decision {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Skip(SkipReason::NotWhitelisted) => write!(f, "skip, not whitelisted"),
+            Self::Skip(SkipReason::Blacklisted) => write!(f, "skip, blacklisted"),
+            Self::Skip(SkipReason::BlacklistedAddress) => {
+                write!(f, "skip, involves a blacklisted address")
+            }
+            Self::Skip(SkipReason::UnservicedDestination) => {
+                write!(f, "skip, destination isn't relayed to")
+            }
+            Self::Skip(SkipReason::BeforeDeliveryStart) => {
+                write!(f, "skip, dispatched before the delivery start block")
+            }
+            Self::Skip(SkipReason::CancelledByOperator) => {
+                write!(f, "skip, cancelled by an operator")
+            }
+            Self::Skip(SkipReason::CancelledBySender) => write!(f, "skip, cancelled by its sender"),
+            Self::Skip(SkipReason::Superseded) => write!(f, "skip, superseded by a newer message"),
+            Self::Skip(SkipReason::RoundTripLimit) => {
+                write!(f, "skip, crossed its route pair too many times")
+            }
+            Self::Confirm => write!(f, "confirm, already delivered"),
+            Self::Drop => write!(f, "drop, recipient is not a contract"),
+            Self::Reprepare(reason) => write!(f, "retry later: {reason}"),
+            Self::Submit(gas_limit) => write!(f, "submit with gas limit {gas_limit}"),
+            Self::Incomplete => write!(f, "none, the inputs end before a decision"),
+        }
+    }
+}
+
+/// Decide what to do with a message given the inputs gathered so far.
+/// Returns `None` if more inputs are needed.
+pub fn decide(inputs: &DecisionInputs) -> Option<Decision> {
+    use Decision::*;
+
+    if let Some(cancellation) = &inputs.cancelled {
+        return Some(Skip(cancellation.cancelled_by.skip_reason()));
+    }
+    let lists = &inputs.lists;
+    if !lists.whitelisted {
+        return Some(Skip(SkipReason::NotWhitelisted));
+    }
+    if lists.blacklisted {
+        return Some(Skip(SkipReason::Blacklisted));
+    }
+    if lists.blacklisted_address.is_some() {
+        return Some(Skip(SkipReason::BlacklistedAddress));
+    }
+    if !lists.destination_serviced {
+        return Some(Skip(SkipReason::UnservicedDestination));
+    }
+    if inputs.before_delivery_start {
+        return Some(Skip(SkipReason::BeforeDeliveryStart));
+    }
+
+    let reprepare = |reason| Some(Reprepare(reason));
+    match inputs.delivered.as_ref()? {
+        Observed::Error(_) => return reprepare(ReprepareReason::ErrorCheckingDeliveryStatus),
+        Observed::Value(true) => return Some(Confirm),
+        Observed::Value(false) => {}
+    }
+    match inputs.recipient_is_contract.as_ref()? {
+        Observed::Error(_) => {
+            return reprepare(ReprepareReason::ErrorCheckingIfRecipientIsContract)
+        }
+        Observed::Value(false) => return Some(Drop),
+        Observed::Value(true) => {}
+    }
+    if let Observed::Error(_) = inputs.recipient_ism.as_ref()? {
+        return reprepare(ReprepareReason::ErrorFetchingIsmAddress);
+    }
+    if let Observed::Error(_) = inputs.metadata_builder.as_ref()? {
+        return reprepare(ReprepareReason::ErrorGettingMetadataBuilder);
+    }
+    match &inputs.metadata.as_ref()?.outcome {
+        MetadataOutcome::TreeUnavailable(_) => return reprepare(ReprepareReason::TreeUnavailable),
+        MetadataOutcome::Error(_) => return reprepare(ReprepareReason::ErrorBuildingMetadata),
+        MetadataOutcome::NotFetched => return reprepare(ReprepareReason::CouldNotFetchMetadata),
+        MetadataOutcome::Built => {}
+    }
+    if let Observed::Error(_) = inputs.gas_quote.as_ref()? {
+        return reprepare(ReprepareReason::ErrorEstimatingGas);
+    }
+    let gas_limit = match inputs.gas_policy.as_ref()? {
+        Observed::Error(_) => return reprepare(ReprepareReason::ErrorCheckingGasRequirement),
+        Observed::Value(GasPolicyOutcome::NoPaymentFound) => {
+            return reprepare(ReprepareReason::GasPaymentNotFound)
+        }
+        Observed::Value(GasPolicyOutcome::PolicyNotMet) => {
+            return reprepare(ReprepareReason::GasPaymentRequirementNotMet)
+        }
+        Observed::Value(GasPolicyOutcome::PolicyMet(gas_limit)) => *gas_limit,
+    };
+    // TODO: consider dropping instead of repreparing in this case
+    if matches!(inputs.transaction_gas_limit, Some(max_limit) if gas_limit > max_limit) {
+        return reprepare(ReprepareReason::ExceedsMaxGasLimit);
+    }
+    let gas_price = match inputs.gas_quote.as_ref()? {
+        Observed::Value(quote) => quote.gas_price,
+        Observed::Error(_) => None,
+    };
+    if let (Some(gas_price), Some(ceiling)) = (gas_price, inputs.gas_price_ceiling) {
+        if gas_price > ceiling {
+            return reprepare(ReprepareReason::GasPriceCeiling);
+        }
+    }
+    if inputs.circuit == Some(CircuitAdmission::Open) {
+        return reprepare(ReprepareReason::CircuitOpen);
+    }
+    Some(Submit(gas_limit))
+}
+
+/// The inputs of an attempt to relay a message and the decision made from
+/// them, as persisted per attempt
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DecisionSnapshot {
+    pub message_id: H256,
+    pub nonce: u32,
+    pub origin: u32,
+    pub destination: u32,
+    /// Unix timestamp, in seconds, of the decision
+    pub recorded_at: u64,
+    /// Hash of the relayer config in force, identifying the policies applied
+    pub config_hash: String,
+    pub inputs: DecisionInputs,
+    pub decision: Decision,
+}
+
+impl DecisionSnapshot {
+    pub fn new(
+        message: &HyperlaneMessage,
+        recorded_at: u64,
+        config_hash: String,
+        inputs: DecisionInputs,
+        decision: Decision,
+    ) -> Self {
+        Self {
+            message_id: message.id(),
+            nonce: message.nonce,
+            origin: message.origin,
+            destination: message.destination,
+            recorded_at,
+            config_hash,
+            inputs,
+            decision,
+        }
+    }
+
+    /// Human-readable explanation of the decision made in this attempt,
+    /// replaying it to flag whether the current code decides differently
+    pub fn explain(&self, attempt: u32) -> String {
+        let replayed = decide(&self.inputs).unwrap_or(Decision::Incomplete);
+        let inputs = &self.inputs;
+        let lists = &inputs.lists;
+        let mut lines = vec![
+            format!(
+                "Message {:?} (nonce {}, {} -> {}), attempt {attempt} at unix time {}",
+                self.message_id, self.nonce, self.origin, self.destination, self.recorded_at
+            ),
+            format!("Config hash: {}", self.config_hash),
+            format!("Queue lane: {}", inputs.lane),
+            format!("Cancelled: {}", cancelled(inputs.cancelled.as_ref())),
+            format!("Whitelisted: {}", yes_no(lists.whitelisted)),
+            format!("Blacklisted: {}", yes_no(lists.blacklisted)),
+            format!(
+                "Involves a blacklisted address: {}",
+                lists.blacklisted_address.as_deref().unwrap_or("no")
+            ),
+            format!(
+                "Destination relayed to: {}",
+                yes_no(lists.destination_serviced)
+            ),
+            format!(
+                "Dispatched before the delivery start block: {}",
+                yes_no(inputs.before_delivery_start)
+            ),
+            format!(
+                "Delivered on destination: {}",
+                observed(&inputs.delivered, |delivered| yes_no(*delivered).to_owned())
+            ),
+            format!(
+                "Recipient is a contract: {}",
+                observed(&inputs.recipient_is_contract, |is_contract| yes_no(
+                    *is_contract
+                )
+                .to_owned())
+            ),
+            format!(
+                "Recipient ISM: {}",
+                observed(&inputs.recipient_ism, |ism| format!("{ism:?}"))
+            ),
+            format!(
+                "Metadata builder: {}",
+                observed(&inputs.metadata_builder, |_| "ok".to_owned())
+            ),
+            format!("Metadata: {}", metadata(inputs.metadata.as_ref())),
+            format!(
+                "Gas estimate: {}",
+                observed(&inputs.gas_quote, |quote| {
+                    let gas_price = quote
+                        .gas_price
+                        .map_or_else(|| "unknown".to_owned(), |price| price.to_string());
+                    format!("gas limit {}, gas price {gas_price}", quote.gas_limit)
+                })
+            ),
+            format!(
+                "Gas policy: {}",
+                observed(&inputs.gas_policy, |outcome| match outcome {
+                    GasPolicyOutcome::NoPaymentFound => "no payment found".to_owned(),
+                    GasPolicyOutcome::PolicyNotMet => "not met".to_owned(),
+                    GasPolicyOutcome::PolicyMet(gas_limit) => {
+                        format!("met, allowing gas limit {gas_limit}")
+                    }
+                })
+            ),
+            format!(
+                "Max transaction gas limit: {}",
+                inputs
+                    .transaction_gas_limit
+                    .map_or_else(|| "none".to_owned(), |limit| limit.to_string())
+            ),
+            format!(
+                "Gas price ceiling: {}",
+                inputs
+                    .gas_price_ceiling
+                    .map_or_else(|| "none".to_owned(), |ceiling| ceiling.to_string())
+            ),
+            format!(
+                "Circuit breaker: {}",
+                match inputs.circuit {
+                    None => "not checked",
+                    Some(CircuitAdmission::Closed) => "closed",
+                    Some(CircuitAdmission::Canary) => "open, let through as a canary",
+                    Some(CircuitAdmission::Open) => "open",
+                }
+            ),
+            format!("Decision: {}", self.decision),
+        ];
+        if replayed == self.decision {
+            lines.push("Replayed with the current code: same decision".to_owned());
+        } else {
+            lines.push(format!(
+                "Replayed with the current code: DIFFERENT decision, {replayed}"
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+impl Encode for DecisionSnapshot {
+    fn write_to<W>(&self, writer: &mut W) -> std::io::Result<usize>
+    where
+        W: Write,
+    {
+        let serialized = serde_json::to_vec(self)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?;
+        writer.write_all(&serialized)?;
+        Ok(serialized.len())
+    }
+}
+
+impl Decode for DecisionSnapshot {
+    fn read_from<R>(reader: &mut R) -> Result<Self, HyperlaneProtocolError>
+    where
+        R: Read,
+        Self: Sized,
+    {
+        serde_json::from_reader(reader).map_err(|err| {
+            HyperlaneProtocolError::IoError(std::io::Error::new(std::io::ErrorKind::Other, err))
+        })
+    }
+}
+
+/// An error's message, truncated to keep snapshots small
+pub fn error_message(err: &impl Display) -> String {
+    truncate(format!("{err:#}"))
+}
+
+/// Current unix timestamp, in seconds
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+fn truncate(mut s: String) -> String {
+    if let Some((index, _)) = s.char_indices().nth(MAX_ERROR_LEN) {
+        s.truncate(index);
+        s.push('…');
+    }
+    s
+}
+
+fn yes_no(value: bool) -> &'static str {
+    if value {
+        "yes"
+    } else {
+        "no"
+    }
+}
+
+fn cancelled(cancellation: Option<&MessageCancellation>) -> String {
+    let Some(cancellation) = cancellation else {
+        return "no".to_owned();
+    };
+    let by = match cancellation.cancelled_by {
+        CancelledBy::Operator => "an operator".to_owned(),
+        CancelledBy::Sender { address } => format!("its sender {address:?}"),
+        CancelledBy::Superseded { newer_message_id } => {
+            format!("the relayer, superseded by message {newer_message_id:?}")
+        }
+        CancelledBy::RoundTripLimit { tuple_id } => {
+            format!("the relayer, round trip limit of tuple {tuple_id:?} exceeded")
+        }
+    };
+    format!(
+        "by {by} at unix time {}: {}",
+        cancellation.cancelled_at, cancellation.reason
+    )
+}
+
+fn observed<T>(observed: &Option<Observed<T>>, describe: impl FnOnce(&T) -> String) -> String {
+    match observed {
+        None => "not checked".to_owned(),
+        Some(Observed::Value(value)) => describe(value),
+        Some(Observed::Error(err)) => format!("failed: {err}"),
+    }
+}
+
+fn metadata(metadata: Option<&MetadataInputs>) -> String {
+    let Some(metadata) = metadata else {
+        return "not checked".to_owned();
+    };
+    let outcome = match &metadata.outcome {
+        MetadataOutcome::Built => "built".to_owned(),
+        MetadataOutcome::NotFetched => "ISM requirements not met yet".to_owned(),
+        MetadataOutcome::TreeUnavailable(err) => format!("merkle tree unavailable: {err}"),
+        MetadataOutcome::Error(err) => format!("failed: {err}"),
+    };
+    let known = |index: Option<u32>| index.map_or_else(|| "unknown".to_owned(), |i| i.to_string());
+    format!(
+        "{outcome} (leaf index {}, checkpoint index {}, tree count {})",
+        known(metadata.leaf_index),
+        known(metadata.checkpoint_index),
+        metadata.tree_count
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use hyperlane_core::H160;
+
+    use super::*;
+
+    fn serviced() -> ListMembership {
+        ListMembership::new(true, false, None, true)
+    }
+
+    fn submittable() -> DecisionInputs {
+        DecisionInputs {
+            cancelled: None,
+            lists: serviced(),
+            before_delivery_start: false,
+            transaction_gas_limit: None,
+            gas_price_ceiling: None,
+            delivered: Some(Observed::Value(false)),
+            recipient_is_contract: Some(Observed::Value(true)),
+            recipient_ism: Some(Observed::Value(H256::repeat_byte(1))),
+            metadata_builder: Some(Observed::Value(())),
+            metadata: Some(MetadataInputs {
+                tree_count: 12,
+                leaf_index: Some(7),
+                checkpoint_index: Some(9),
+                outcome: MetadataOutcome::Built,
+            }),
+            gas_quote: Some(Observed::Value(GasQuote {
+                gas_limit: 100_000.into(),
+                gas_price: Some(10.into()),
+                l2_gas_limit: None,
+            })),
+            gas_policy: Some(Observed::Value(GasPolicyOutcome::PolicyMet(120_000.into()))),
+            circuit: None,
+        }
+    }
+
+    #[test]
+    fn test_decisions_follow_the_first_deciding_input() {
+        assert_eq!(
+            decide(&submittable()),
+            Some(Decision::Submit(120_000.into()))
+        );
+
+        let inputs = DecisionInputs {
+            lists: ListMembership::new(true, false, Some(&[0xab]), true),
+            ..submittable()
+        };
+        assert_eq!(
+            decide(&inputs),
+            Some(Decision::Skip(SkipReason::BlacklistedAddress))
+        );
+
+        let inputs = DecisionInputs {
+            before_delivery_start: true,
+            ..submittable()
+        };
+        assert_eq!(
+            decide(&inputs),
+            Some(Decision::Skip(SkipReason::BeforeDeliveryStart))
+        );
+
+        let inputs = DecisionInputs {
+            cancelled: Some(MessageCancellation {
+                cancelled_by: CancelledBy::Sender {
+                    address: H160::repeat_byte(1),
+                },
+                cancelled_at: 0,
+                reason: "bad payload".to_owned(),
+            }),
+            ..submittable()
+        };
+        assert_eq!(
+            decide(&inputs),
+            Some(Decision::Skip(SkipReason::CancelledBySender))
+        );
+
+        let inputs = DecisionInputs {
+            gas_policy: Some(Observed::Value(GasPolicyOutcome::PolicyNotMet)),
+            ..submittable()
+        };
+        assert_eq!(
+            decide(&inputs),
+            Some(Decision::Reprepare(
+                ReprepareReason::GasPaymentRequirementNotMet
+            ))
+        );
+
+        let inputs = DecisionInputs {
+            transaction_gas_limit: Some(110_000.into()),
+            ..submittable()
+        };
+        assert_eq!(
+            decide(&inputs),
+            Some(Decision::Reprepare(ReprepareReason::ExceedsMaxGasLimit))
+        );
+
+        let inputs = DecisionInputs {
+            gas_price_ceiling: Some(9.into()),
+            ..submittable()
+        };
+        assert_eq!(
+            decide(&inputs),
+            Some(Decision::Reprepare(ReprepareReason::GasPriceCeiling))
+        );
+        let inputs = DecisionInputs {
+            gas_price_ceiling: Some(10.into()),
+            ..submittable()
+        };
+        assert_eq!(decide(&inputs), Some(Decision::Submit(120_000.into())));
+
+        let inputs = DecisionInputs {
+            circuit: Some(CircuitAdmission::Open),
+            ..submittable()
+        };
+        assert_eq!(
+            decide(&inputs),
+            Some(Decision::Reprepare(ReprepareReason::CircuitOpen))
+        );
+
+        let inputs = DecisionInputs {
+            metadata: None,
+            gas_quote: None,
+            gas_policy: None,
+            ..submittable()
+        };
+        assert_eq!(decide(&inputs), None);
+    }
+
+    #[test]
+    fn test_snapshots_stay_small() {
+        let long_error: Result<bool, String> = Err("x".repeat(10_000));
+        let inputs = DecisionInputs {
+            delivered: Some(Observed::new(&long_error)),
+            ..submittable()
+        };
+        assert_eq!(inputs.error().map(|err| err.chars().count()), Some(257));
+
+        let message = HyperlaneMessage {
+            body: vec![0xff; 100_000],
+            ..Default::default()
+        };
+        let snapshot =
+            DecisionSnapshot::new(&message, 0, "hash".to_owned(), inputs, Decision::Incomplete);
+        assert!(snapshot.to_vec().len() < 2_048);
+        assert_eq!(
+            DecisionSnapshot::read_from(&mut snapshot.to_vec().as_slice()).unwrap(),
+            snapshot
+        );
+    }
+
+    #[test]
+    fn test_explain_flags_decisions_the_current_code_makes_differently() {
+        let message = HyperlaneMessage::default();
+        let snapshot = DecisionSnapshot::new(
+            &message,
+            1_700_000_000,
+            "abcd".to_owned(),
+            submittable(),
+            Decision::Submit(120_000.into()),
+        );
+        let explanation = snapshot.explain(3);
+        assert!(explanation.contains("attempt 3 at unix time 1700000000"));
+        assert!(explanation
+            .contains("Metadata: built (leaf index 7, checkpoint index 9, tree count 12)"));
+        assert!(explanation.contains("Decision: submit with gas limit 120000"));
+        assert!(explanation.ends_with("Replayed with the current code: same decision"));
+
+        let outdated = DecisionSnapshot {
+            decision: Decision::Reprepare(ReprepareReason::GasPaymentNotFound),
+            ..snapshot
+        };
+        assert!(outdated.explain(3).ends_with(
+            "Replayed with the current code: DIFFERENT decision, submit with gas limit 120000"
+        ));
+    }
+}
@@ -0,0 +1,135 @@
+//! The block an origin's messages are delivered from.
+//!
+//! Operators may set an origin's `index.from` after its mailbox was deployed.
+//! Messages dispatched before that block aren't delivered, but their leaves
+//! still shift the indices of every later leaf of the merkle tree, so the tree
+//! is always built from its first leaf, whatever the start block.
+
+use hyperlane_base::{db::HyperlaneDb, settings::IndexSettings};
+use hyperlane_core::HyperlaneMessage;
+
+/// Delivers the messages of an origin dispatched from `from_block` on, while
+/// its merkle tree covers every leaf
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DeliveryStart {
+    from_block: u32,
+}
+
+impl DeliveryStart {
+    pub fn new(from_block: u32) -> Self {
+        Self { from_block }
+    }
+
+    /// Whether `message` was dispatched before the start block. Messages
+    /// whose dispatch block isn't known aren't.
+    pub fn excludes(&self, db: &dyn HyperlaneDb, message: &HyperlaneMessage) -> bool {
+        if self.from_block == 0 {
+            return false;
+        }
+        matches!(
+            db.retrieve_dispatched_block_number_by_nonce(&message.nonce),
+            Ok(Some(block)) if block < self.from_block as u64
+        )
+    }
+
+    /// Whether leaves inserted before the start block may be missing, i.e.
+    /// the start block isn't the first one and the first leaf wasn't indexed
+    pub fn has_leaf_gap(&self, db: &dyn HyperlaneDb) -> bool {
+        self.from_block > 0
+            && !matches!(
+                db.retrieve_merkle_tree_insertion_by_leaf_index(&0),
+                Ok(Some(_))
+            )
+    }
+
+    /// Index settings covering the leaves missing from the tree, if any
+    pub fn tree_index_settings(&self, db: &dyn HyperlaneDb, index: IndexSettings) -> IndexSettings {
+        if !self.has_leaf_gap(db) {
+            return index;
+        }
+        IndexSettings { from: 0, ..index }
+    }
+
+    /// The policy, as reported alongside the tree's status, if messages are
+    /// excluded at all
+    pub fn policy(&self) -> Option<String> {
+        (self.from_block > 0)
+            .then(|| format!("tree-complete, delivery-from-block-{}", self.from_block))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use hyperlane_base::db::{test_utils, HyperlaneRocksDB};
+    use hyperlane_core::{
+        HyperlaneDomain, HyperlaneLogStore, Indexed, KnownHyperlaneDomain, LogMeta,
+        MerkleTreeInsertion, H256,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_late_start_block_extends_tree_backfill() {
+        test_utils::run_test_db(|db| async move {
+            let domain = HyperlaneDomain::Known(KnownHyperlaneDomain::Ethereum);
+            let db = HyperlaneRocksDB::new(&domain, db);
+            let index = IndexSettings {
+                from: 100,
+                chunk_size: 10,
+                mode: Default::default(),
+                pacer: None,
+            };
+
+            // Starting from the first block, nothing is missing
+            let from_genesis = DeliveryStart::new(0);
+            assert!(!from_genesis.has_leaf_gap(&db));
+            assert_eq!(from_genesis.policy(), None);
+
+            let late = DeliveryStart::new(100);
+            assert!(late.has_leaf_gap(&db));
+            assert_eq!(late.tree_index_settings(&db, index.clone()).from, 0);
+            assert_eq!(
+                late.policy().unwrap(),
+                "tree-complete, delivery-from-block-100"
+            );
+
+            // Once the first leaf is indexed, the configured block is used
+            let insertion = MerkleTreeInsertion::new(0, H256::random());
+            db.store_logs(&[(Indexed::new(insertion), LogMeta::random())])
+                .await
+                .unwrap();
+            assert!(!late.has_leaf_gap(&db));
+            assert_eq!(late.tree_index_settings(&db, index).from, 100);
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_messages_before_start_block_are_excluded() {
+        test_utils::run_test_db(|db| async move {
+            let domain = HyperlaneDomain::Known(KnownHyperlaneDomain::Ethereum);
+            let db = HyperlaneRocksDB::new(&domain, db);
+            let messages: Vec<_> = (0..3)
+                .map(|nonce| HyperlaneMessage {
+                    nonce,
+                    ..Default::default()
+                })
+                .collect();
+            for (message, block) in messages.iter().zip([50, 99, 100]) {
+                db.store_message(message, block).unwrap();
+            }
+            let unknown = HyperlaneMessage {
+                nonce: 3,
+                ..Default::default()
+            };
+
+            let start = DeliveryStart::new(100);
+            assert!(start.excludes(&db, &messages[0]));
+            assert!(start.excludes(&db, &messages[1]));
+            assert!(!start.excludes(&db, &messages[2]));
+            assert!(!start.excludes(&db, &unknown));
+            assert!(!DeliveryStart::new(0).excludes(&db, &messages[0]));
+        })
+        .await;
+    }
+}
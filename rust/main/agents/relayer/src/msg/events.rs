@@ -0,0 +1,326 @@
+//! Lifecycle events of the messages the relayer processes, for services
+//! embedding the relayer to subscribe to instead of polling its admin server.
+
+use std::{
+    fmt::{Debug, Formatter},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
+
+use futures::{stream::BoxStream, Stream, StreamExt};
+use hyperlane_core::{HyperlaneMessage, H256, H512};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::debug;
+
+/// Events a subscriber may fall behind by before the oldest ones are dropped
+/// for it
+pub const MESSAGE_EVENTS_CAPACITY: usize = 1024;
+
+/// A transition in the lifecycle of a message
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum MessageEventKind {
+    /// The message was indexed and handed over to be relayed
+    Indexed,
+    /// Everything needed to deliver the message is in place, and it is
+    /// about to be submitted
+    Deliverable,
+    /// Delivery is held back until the message is prepared again
+    Parked {
+        /// Why the message was parked
+        reason: String,
+    },
+    /// The message was delivered to its recipient
+    Delivered {
+        /// The transaction the relayer delivered the message in, if it
+        /// wasn't delivered by someone else
+        tx: Option<H512>,
+    },
+    /// The message won't be relayed
+    DeadLettered,
+    /// A newer message of the same sender and recipient supersedes the
+    /// message, which won't be relayed
+    Superseded {
+        /// Id of the newer message
+        by: H256,
+    },
+}
+
+/// A message's lifecycle event
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MessageEvent {
+    /// Id of the message
+    pub message_id: H256,
+    /// Nonce of the message on its origin
+    pub nonce: u32,
+    /// Domain the message was sent from
+    pub origin: u32,
+    /// Address the message was sent by
+    pub sender: H256,
+    /// Domain the message is sent to
+    pub destination: u32,
+    /// Address the message is sent to
+    pub recipient: H256,
+    /// The transition
+    #[serde(flatten)]
+    pub kind: MessageEventKind,
+}
+
+impl MessageEvent {
+    /// The event of a transition of `message`
+    pub fn new(message: &HyperlaneMessage, kind: MessageEventKind) -> Self {
+        Self {
+            message_id: message.id(),
+            nonce: message.nonce,
+            origin: message.origin,
+            sender: message.sender,
+            destination: message.destination,
+            recipient: message.recipient,
+            kind,
+        }
+    }
+}
+
+/// Which messages to receive the events of. Unset fields match any message.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct MessageEventFilter {
+    /// Only messages sent by this address
+    pub sender: Option<H256>,
+    /// Only messages sent from this domain
+    pub origin: Option<u32>,
+    /// Only messages sent to this domain
+    pub destination: Option<u32>,
+}
+
+impl MessageEventFilter {
+    /// Only receive events of messages sent by `sender`
+    pub fn with_sender(mut self, sender: H256) -> Self {
+        self.sender = Some(sender);
+        self
+    }
+
+    /// Only receive events of messages sent from `origin` to `destination`
+    pub fn with_route(mut self, origin: u32, destination: u32) -> Self {
+        self.origin = Some(origin);
+        self.destination = Some(destination);
+        self
+    }
+
+    /// Whether the event is of a message the filter matches
+    pub fn matches(&self, event: &MessageEvent) -> bool {
+        self.sender.map_or(true, |sender| sender == event.sender)
+            && self.origin.map_or(true, |origin| origin == event.origin)
+            && self
+                .destination
+                .map_or(true, |destination| destination == event.destination)
+    }
+}
+
+/// Fans the lifecycle events of messages out to subscribers. Publishing
+/// never waits on subscribers: one that falls more than
+/// [`MESSAGE_EVENTS_CAPACITY`] events behind misses the oldest ones.
+#[derive(Clone)]
+pub struct MessageEventBus {
+    sender: broadcast::Sender<MessageEvent>,
+}
+
+impl Debug for MessageEventBus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MessageEventBus")
+            .field("subscribers", &self.sender.receiver_count())
+            .finish()
+    }
+}
+
+impl Default for MessageEventBus {
+    fn default() -> Self {
+        Self::new(MESSAGE_EVENTS_CAPACITY)
+    }
+}
+
+impl MessageEventBus {
+    /// A bus subscribers may fall `capacity` events behind on
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self { sender }
+    }
+
+    /// Publish a transition of `message` to the current subscribers
+    pub fn publish(&self, message: &HyperlaneMessage, kind: MessageEventKind) {
+        if self.sender.receiver_count() == 0 {
+            return;
+        }
+        // Only fails if every subscriber has gone away since
+        let _ = self.sender.send(MessageEvent::new(message, kind));
+    }
+
+    /// Subscribe to the events published from now on of the messages
+    /// `filter` matches
+    pub fn subscribe(&self, filter: MessageEventFilter) -> MessageEventStream {
+        MessageEventStream::new(self.sender.subscribe(), filter)
+    }
+}
+
+/// The events of the messages a subscriber's filter matches, in the order
+/// they were published. Ends once the relayer shuts down.
+pub struct MessageEventStream {
+    events: BoxStream<'static, MessageEvent>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl Debug for MessageEventStream {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MessageEventStream")
+            .field("dropped", &self.dropped_events())
+            .finish()
+    }
+}
+
+impl MessageEventStream {
+    fn new(receiver: broadcast::Receiver<MessageEvent>, filter: MessageEventFilter) -> Self {
+        let dropped = Arc::new(AtomicU64::new(0));
+        let events = futures::stream::unfold(
+            (receiver, filter, dropped.clone()),
+            |(mut receiver, filter, dropped)| async move {
+                loop {
+                    match receiver.recv().await {
+                        Ok(event) if filter.matches(&event) => {
+                            return Some((event, (receiver, filter, dropped)));
+                        }
+                        Ok(_) => {}
+                        Err(RecvError::Lagged(missed)) => {
+                            debug!(missed, "Message event subscriber lagged behind");
+                            dropped.fetch_add(missed, Ordering::Relaxed);
+                        }
+                        Err(RecvError::Closed) => return None,
+                    }
+                }
+            },
+        )
+        .boxed();
+        Self { events, dropped }
+    }
+
+    /// Number of events this subscriber missed by falling behind. Missed
+    /// events are counted whether or not the filter would have matched them.
+    pub fn dropped_events(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+impl Stream for MessageEventStream {
+    type Item = MessageEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.events.poll_next_unpin(cx)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use hyperlane_core::ReprepareReason;
+
+    use super::*;
+
+    fn message(nonce: u32, sender: H256, destination: u32) -> HyperlaneMessage {
+        HyperlaneMessage {
+            nonce,
+            origin: 1,
+            sender,
+            destination,
+            ..Default::default()
+        }
+    }
+
+    fn kinds(events: Vec<MessageEvent>) -> Vec<(u32, MessageEventKind)> {
+        events.into_iter().map(|e| (e.nonce, e.kind)).collect()
+    }
+
+    #[tokio::test]
+    async fn test_subscribers_receive_filtered_transitions() {
+        let bus = MessageEventBus::default();
+        let (partner, other) = (H256::repeat_byte(1), H256::repeat_byte(2));
+        let mut stream = bus.subscribe(MessageEventFilter::default().with_sender(partner));
+        let mut route = bus.subscribe(MessageEventFilter::default().with_route(1, 3));
+
+        let (first, second, third) = (
+            message(0, partner, 2),
+            message(1, other, 3),
+            message(2, partner, 3),
+        );
+        let parked = MessageEventKind::Parked {
+            reason: ReprepareReason::GasPaymentRequirementNotMet.to_string(),
+        };
+        let delivered = MessageEventKind::Delivered {
+            tx: Some(H512::repeat_byte(7)),
+        };
+        bus.publish(&first, MessageEventKind::Indexed);
+        bus.publish(&second, MessageEventKind::Indexed);
+        bus.publish(&first, parked.clone());
+        bus.publish(&third, MessageEventKind::Indexed);
+        bus.publish(&second, MessageEventKind::DeadLettered);
+        bus.publish(&first, MessageEventKind::Deliverable);
+        bus.publish(&first, delivered.clone());
+        drop(bus);
+
+        assert_eq!(
+            kinds(stream.by_ref().collect().await),
+            vec![
+                (0, MessageEventKind::Indexed),
+                (0, parked),
+                (2, MessageEventKind::Indexed),
+                (0, MessageEventKind::Deliverable),
+                (0, delivered),
+            ]
+        );
+        assert_eq!(
+            kinds(route.collect().await),
+            vec![
+                (1, MessageEventKind::Indexed),
+                (2, MessageEventKind::Indexed),
+                (1, MessageEventKind::DeadLettered),
+            ]
+        );
+        assert_eq!(stream.dropped_events(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_slow_subscribers_drop_the_oldest_events() {
+        let bus = MessageEventBus::new(4);
+        let sender = H256::repeat_byte(1);
+        let mut slow = bus.subscribe(MessageEventFilter::default());
+
+        // Publishing never waits on the subscriber
+        for nonce in 0..10 {
+            bus.publish(&message(nonce, sender, 2), MessageEventKind::Indexed);
+        }
+        assert_eq!(slow.next().await.map(|e| e.nonce), Some(6));
+        assert_eq!(slow.dropped_events(), 6);
+
+        // Once caught up, nothing else is dropped
+        bus.publish(&message(10, sender, 2), MessageEventKind::Indexed);
+        drop(bus);
+        let rest: Vec<_> = slow.by_ref().map(|e| e.nonce).collect().await;
+        assert_eq!(rest, vec![7, 8, 9, 10]);
+        assert_eq!(slow.dropped_events(), 6);
+    }
+
+    #[test]
+    fn test_events_serialize_flat() {
+        let event = MessageEvent::new(
+            &message(5, H256::zero(), 2),
+            MessageEventKind::Parked {
+                reason: "Not ready".to_owned(),
+            },
+        );
+        let json = serde_json::to_value(event).unwrap();
+        assert_eq!(json["kind"], "parked");
+        assert_eq!(json["reason"], "Not ready");
+        assert_eq!(json["nonce"], 5);
+    }
+}
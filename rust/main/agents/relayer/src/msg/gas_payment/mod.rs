@@ -178,10 +178,10 @@ impl GasPaymentEnforcer {
 mod test {
     use std::str::FromStr;
 
-    use hyperlane_base::db::{test_utils, HyperlaneRocksDB};
+    use hyperlane_base::db::{test_utils, HyperlaneRocksDB, IgpContractStore};
     use hyperlane_core::{
-        HyperlaneDomain, HyperlaneMessage, InterchainGasPayment, LogMeta, TxCostEstimate, H160,
-        H256, U256,
+        GasPaymentKey, HyperlaneDomain, HyperlaneLogStore, HyperlaneMessage, Indexed,
+        InterchainGasPayment, LogMeta, TxCostEstimate, H160, H256, U256,
     };
 
     use super::GasPaymentEnforcer;
@@ -375,6 +375,134 @@ mod test {
         .await;
     }
 
+    #[tokio::test]
+    async fn test_payments_across_igp_contracts() {
+        test_utils::run_test_db(|db| async move {
+            let msg = HyperlaneMessage {
+                destination: 123,
+                ..HyperlaneMessage::default()
+            };
+            let hyperlane_db = HyperlaneRocksDB::new(
+                &HyperlaneDomain::new_test_domain("test_payments_across_igp_contracts"),
+                db,
+            );
+            let (legacy_igp, current_igp) = (H256::from_low_u64_be(1), H256::from_low_u64_be(2));
+            let legacy = IgpContractStore::additional(hyperlane_db.clone(), legacy_igp);
+            let current = IgpContractStore::configured(hyperlane_db.clone(), current_igp);
+
+            let enforcer = GasPaymentEnforcer::new(
+                vec![GasPaymentEnforcementConf {
+                    policy: GasPaymentEnforcementPolicy::Minimum {
+                        payment: U256::from(3),
+                    },
+                    matching_list: MatchingList::default(),
+                }],
+                hyperlane_db.clone(),
+            );
+
+            let payment = |amount: u64| InterchainGasPayment {
+                message_id: msg.id(),
+                destination: msg.destination,
+                payment: U256::from(amount),
+                gas_amount: U256::from(amount),
+            };
+            legacy
+                .store_logs(&[(Indexed::new(payment(1)), LogMeta::random())])
+                .await
+                .unwrap();
+            // Neither contract's payment meets the policy on its own
+            assert_eq!(
+                enforcer
+                    .message_meets_gas_payment_requirement(&msg, &TxCostEstimate::default())
+                    .await
+                    .unwrap(),
+                GasPolicyStatus::PolicyNotMet
+            );
+
+            current
+                .store_logs(&[(Indexed::new(payment(2)), LogMeta::random())])
+                .await
+                .unwrap();
+            assert_eq!(
+                enforcer
+                    .message_meets_gas_payment_requirement(&msg, &TxCostEstimate::default())
+                    .await
+                    .unwrap(),
+                GasPolicyStatus::PolicyMet(U256::zero())
+            );
+
+            // Each contract's share is attributed to it
+            let key = GasPaymentKey {
+                message_id: msg.id(),
+                destination: msg.destination,
+            };
+            for (igp, amount) in [(legacy_igp, 1), (current_igp, 2)] {
+                assert_eq!(
+                    hyperlane_db
+                        .retrieve_gas_payment_by_contract(key, &igp)
+                        .unwrap(),
+                    Some(payment(amount))
+                );
+            }
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_rolled_back_payment_is_parked_again() {
+        test_utils::run_test_db(|db| async move {
+            let msg = HyperlaneMessage {
+                destination: 123,
+                ..HyperlaneMessage::default()
+            };
+            let hyperlane_db = HyperlaneRocksDB::new(
+                &HyperlaneDomain::new_test_domain("test_rolled_back_payment_is_parked_again"),
+                db,
+            );
+            let enforcer = GasPaymentEnforcer::new(
+                vec![GasPaymentEnforcementConf {
+                    policy: GasPaymentEnforcementPolicy::Minimum {
+                        payment: U256::from(2),
+                    },
+                    matching_list: MatchingList::default(),
+                }],
+                hyperlane_db.clone(),
+            );
+            let payment = InterchainGasPayment {
+                message_id: msg.id(),
+                destination: msg.destination,
+                payment: U256::from(2),
+                gas_amount: U256::from(2),
+            };
+            let meta = LogMeta {
+                block_number: 10,
+                ..LogMeta::random()
+            };
+            // Indexing the payment twice still counts it once
+            for _ in 0..2 {
+                hyperlane_db.process_gas_payment(payment, &meta).unwrap();
+            }
+            assert_eq!(
+                enforcer
+                    .message_meets_gas_payment_requirement(&msg, &TxCostEstimate::default())
+                    .await
+                    .unwrap(),
+                GasPolicyStatus::PolicyMet(U256::zero())
+            );
+
+            // Once the payment's block is rolled back, the message is parked
+            assert_eq!(hyperlane_db.roll_back_gas_payments_in_block(10).unwrap(), 1);
+            assert_eq!(
+                enforcer
+                    .message_meets_gas_payment_requirement(&msg, &TxCostEstimate::default())
+                    .await
+                    .unwrap(),
+                GasPolicyStatus::PolicyNotMet
+            );
+        })
+        .await;
+    }
+
     #[tokio::test]
     async fn test_non_empty_matching_list() {
         test_utils::run_test_db(|db| async move {
@@ -0,0 +1,102 @@
+//! Gas price ceilings of message routes.
+//!
+//! A high-value bridge message is worth delivering at a gas price a spam ping
+//! isn't. Messages are deferred while the gas price of their destination is
+//! above the ceiling of their route, and checked against it again every
+//! recheck interval, until the gas price declines. The ceiling also caps the
+//! fees of their process transactions.
+
+use std::time::Duration;
+
+use hyperlane_core::{HyperlaneMessage, U256};
+
+use crate::settings::GasPriceCeilingConf;
+
+/// Resolves the highest gas price each message is submitted at
+#[derive(Debug, Clone)]
+pub struct GasPriceCeiling {
+    conf: GasPriceCeilingConf,
+}
+
+impl GasPriceCeiling {
+    pub fn new(conf: GasPriceCeilingConf) -> Self {
+        Self { conf }
+    }
+
+    /// Ceiling of the first override `message` matches, otherwise the default
+    /// one. `None` if the message is submitted at any gas price.
+    pub fn ceiling_for(&self, message: &HyperlaneMessage) -> Option<U256> {
+        self.conf
+            .overrides
+            .iter()
+            .find(|(matching_list, _)| matching_list.msg_matches(message, false))
+            .map(|(_, ceiling)| *ceiling)
+            .or(self.conf.default)
+    }
+
+    /// How long messages deferred for their gas price wait before being
+    /// checked against it again
+    pub fn recheck_interval(&self) -> Duration {
+        self.conf.recheck_interval
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use hyperlane_core::H256;
+
+    use super::*;
+
+    fn message(sender: H256) -> HyperlaneMessage {
+        HyperlaneMessage {
+            sender,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_first_matching_override_decides_the_ceiling() {
+        let bridge = H256::from_low_u64_be(1);
+        let ping = H256::from_low_u64_be(2);
+        let conf = GasPriceCeilingConf {
+            default: Some(50.into()),
+            overrides: vec![
+                (
+                    serde_json::from_str(&format!(r#"[{{"senderaddress": "{bridge:?}"}}]"#))
+                        .unwrap(),
+                    500.into(),
+                ),
+                (serde_json::from_str("[{}]").unwrap(), 100.into()),
+            ],
+            ..Default::default()
+        };
+        let ceiling = GasPriceCeiling::new(conf);
+
+        assert_eq!(ceiling.ceiling_for(&message(bridge)), Some(500.into()));
+        assert_eq!(ceiling.ceiling_for(&message(ping)), Some(100.into()));
+    }
+
+    #[test]
+    fn test_default_ceiling_applies_without_matching_override() {
+        let conf = GasPriceCeilingConf {
+            default: Some(50.into()),
+            overrides: vec![(
+                serde_json::from_str(&format!(
+                    r#"[{{"senderaddress": "{:?}"}}]"#,
+                    H256::from_low_u64_be(1)
+                ))
+                .unwrap(),
+                500.into(),
+            )],
+            ..Default::default()
+        };
+        let ceiling = GasPriceCeiling::new(conf);
+        assert_eq!(
+            ceiling.ceiling_for(&message(H256::from_low_u64_be(2))),
+            Some(50.into())
+        );
+
+        let unbounded = GasPriceCeiling::new(GasPriceCeilingConf::default());
+        assert_eq!(unbounded.ceiling_for(&message(H256::zero())), None);
+    }
+}
@@ -0,0 +1,77 @@
+use async_trait::async_trait;
+use derive_new::new;
+use hyperlane_base::db::HyperlaneRocksDB;
+use hyperlane_core::{
+    HyperlaneCoreResult, HyperlaneLogStore, HyperlaneMessage,
+    HyperlaneSequenceAwareIndexerStoreReader, HyperlaneWatermarkedLogStore, Indexed, LogMeta,
+};
+use tokio::sync::mpsc::UnboundedSender;
+use tracing::trace;
+
+/// Log store for dispatched messages which, once they're written to the db,
+/// also hands them straight to the origin's message processor. Without this
+/// the processor only discovers new messages on its next db poll.
+///
+/// The db stays the source of truth: the processor drops any message it
+/// already got to by polling, and anything lost here is still found by the
+/// poll loop.
+#[derive(Debug, Clone, new)]
+pub struct MessageIntakeStore {
+    db: HyperlaneRocksDB,
+    intake: UnboundedSender<HyperlaneMessage>,
+}
+
+#[async_trait]
+impl HyperlaneLogStore<HyperlaneMessage> for MessageIntakeStore {
+    async fn store_logs(
+        &self,
+        messages: &[(Indexed<HyperlaneMessage>, LogMeta)],
+    ) -> HyperlaneCoreResult<u32> {
+        let stored = HyperlaneLogStore::<HyperlaneMessage>::store_logs(&self.db, messages).await?;
+        for (message, _) in messages {
+            if self.intake.send(message.inner().clone()).is_err() {
+                trace!("Message processor isn't running, leaving messages to the db poll");
+                break;
+            }
+        }
+        Ok(stored)
+    }
+}
+
+#[async_trait]
+impl HyperlaneSequenceAwareIndexerStoreReader<HyperlaneMessage> for MessageIntakeStore {
+    async fn retrieve_by_sequence(
+        &self,
+        sequence: u32,
+    ) -> HyperlaneCoreResult<Option<HyperlaneMessage>> {
+        HyperlaneSequenceAwareIndexerStoreReader::<HyperlaneMessage>::retrieve_by_sequence(
+            &self.db, sequence,
+        )
+        .await
+    }
+
+    async fn retrieve_log_block_number_by_sequence(
+        &self,
+        sequence: u32,
+    ) -> HyperlaneCoreResult<Option<u64>> {
+        HyperlaneSequenceAwareIndexerStoreReader::<HyperlaneMessage>::retrieve_log_block_number_by_sequence(
+            &self.db, sequence,
+        )
+        .await
+    }
+}
+
+#[async_trait]
+impl HyperlaneWatermarkedLogStore<HyperlaneMessage> for MessageIntakeStore {
+    async fn retrieve_high_watermark(&self) -> HyperlaneCoreResult<Option<u32>> {
+        HyperlaneWatermarkedLogStore::<HyperlaneMessage>::retrieve_high_watermark(&self.db).await
+    }
+
+    async fn store_high_watermark(&self, block_number: u32) -> HyperlaneCoreResult<()> {
+        HyperlaneWatermarkedLogStore::<HyperlaneMessage>::store_high_watermark(
+            &self.db,
+            block_number,
+        )
+        .await
+    }
+}
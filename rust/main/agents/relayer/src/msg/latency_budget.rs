@@ -0,0 +1,476 @@
+//! Latency budgets of the stages of the relaying pipeline.
+//!
+//! Every message is timed through three stages: indexing, from the inclusion
+//! of its dispatch block to it being handed to the submitter; metadata, the
+//! building of its metadata in the attempt that finds the quorum covering it;
+//! and broadcast, from it being ready to be submitted to its process
+//! transaction being broadcast. Each stage of a message is timed once, the
+//! first time it completes. A stage taking longer than the budget of the
+//! message's route is counted as a breach, and recorded on the message so
+//! that its status tells which stage blew its budget.
+//!
+//! The stage latencies of each route are kept over a rolling window, so that
+//! their percentiles can be reported, and are observed by a histogram.
+
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use eyre::Result;
+use hyperlane_base::{CoreMetrics, ExemplarHistogramVec};
+use hyperlane_core::HyperlaneMessage;
+use prometheus::IntCounterVec;
+use serde::Serialize;
+use tracing::debug;
+
+use crate::settings::{LatencyBudgetConf, StageBudgets};
+
+/// Name the latencies of the messages matching none of the routes are
+/// reported under
+const DEFAULT_ROUTE: &str = "default";
+
+/// Buckets of the stage duration histogram, from a metadata build served
+/// from cache to indexing held up by reorg-safe confirmations
+const STAGE_DURATION_BUCKETS: &[f64] = &[
+    0.1, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1800.0,
+];
+
+/// A stage of the relaying pipeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PipelineStage {
+    Indexing,
+    Metadata,
+    Broadcast,
+}
+
+impl PipelineStage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Indexing => "indexing",
+            Self::Metadata => "metadata",
+            Self::Broadcast => "broadcast",
+        }
+    }
+
+    fn budget(&self, budgets: &StageBudgets) -> Option<Duration> {
+        match self {
+            Self::Indexing => budgets.indexing,
+            Self::Metadata => budgets.metadata,
+            Self::Broadcast => budgets.broadcast,
+        }
+    }
+}
+
+/// How far a message got through the pipeline, as far as timing it goes
+#[derive(Debug, Clone, Default)]
+pub struct StageTimeline {
+    /// Stages already timed, or given up on
+    timed: Vec<PipelineStage>,
+    /// When the message was first ready to be submitted
+    ready_at: Option<Instant>,
+    /// Stages which took longer than their budget
+    breaches: Vec<PipelineStage>,
+}
+
+impl StageTimeline {
+    /// Whether `stage` was timed already, or given up on
+    pub fn is_timed(&self, stage: PipelineStage) -> bool {
+        self.timed.contains(&stage)
+    }
+
+    /// Give up on timing `stage`, e.g. if when it started can't be told
+    pub fn skip(&mut self, stage: PipelineStage) {
+        if !self.is_timed(stage) {
+            self.timed.push(stage);
+        }
+    }
+
+    /// Note that the message is ready to be submitted. Only the first time
+    /// it is counts, as it may have to be prepared again.
+    pub fn mark_ready(&mut self, at: Instant) {
+        self.ready_at.get_or_insert(at);
+    }
+
+    /// When the message was first ready to be submitted
+    pub fn ready_at(&self) -> Option<Instant> {
+        self.ready_at
+    }
+
+    /// Stages which took longer than their budget, in the order they were
+    /// timed
+    pub fn breaches(&self) -> &[PipelineStage] {
+        &self.breaches
+    }
+}
+
+/// Latency of a stage of a route over the window
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StageLatency {
+    /// Messages timed through the stage within the window
+    pub samples: usize,
+    /// 95th percentile of the latencies, in milliseconds
+    pub p95_ms: u64,
+    /// Budget of the stage, in milliseconds, if it has one
+    pub budget_ms: Option<u64>,
+    /// Messages which took longer than the budget within the window
+    pub breaches: usize,
+}
+
+/// Stage latencies of every route over the window
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LatencyReport {
+    pub window_secs: u64,
+    pub routes: BTreeMap<String, BTreeMap<PipelineStage, StageLatency>>,
+}
+
+/// A stage latency, and whether it took longer than the budget
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    at: Instant,
+    elapsed: Duration,
+    breached: bool,
+}
+
+/// Times messages through the stages of the pipeline against the budgets of
+/// their routes, shared by all origins
+#[derive(Debug)]
+pub struct LatencyBudgetTracker {
+    conf: LatencyBudgetConf,
+    samples: Mutex<BTreeMap<(String, PipelineStage), VecDeque<Sample>>>,
+    breaches: IntCounterVec,
+    stage_duration: ExemplarHistogramVec,
+}
+
+impl LatencyBudgetTracker {
+    pub fn new(conf: LatencyBudgetConf, metrics: &CoreMetrics) -> Result<Self> {
+        Ok(Self::with_metrics(
+            conf,
+            metrics.new_int_counter(
+                "latency_budget_breaches",
+                "Number of messages which took longer than the latency budget of a pipeline stage",
+                &["route", "stage"],
+            )?,
+            metrics.new_exemplar_histogram(
+                "pipeline_stage_duration_seconds",
+                "Time taken by messages to go through a stage of the pipeline",
+                &["route", "stage"],
+                STAGE_DURATION_BUCKETS,
+            )?,
+        ))
+    }
+
+    fn with_metrics(
+        conf: LatencyBudgetConf,
+        breaches: IntCounterVec,
+        stage_duration: ExemplarHistogramVec,
+    ) -> Self {
+        Self {
+            conf,
+            samples: Default::default(),
+            breaches,
+            stage_duration,
+        }
+    }
+
+    /// Name and budgets of the route of `message`
+    fn route(&self, message: &HyperlaneMessage) -> (&str, StageBudgets) {
+        self.conf
+            .routes
+            .iter()
+            .find(|route| route.matching_list.msg_matches(message, false))
+            .map(|route| (route.name.as_str(), route.budgets.or(self.conf.default)))
+            .unwrap_or((DEFAULT_ROUTE, self.conf.default))
+    }
+
+    /// Time `stage` of `message`, which took `elapsed` and completed at `at`,
+    /// unless it was timed already. Returns whether it took longer than the
+    /// budget of the message's route, in which case the breach is recorded
+    /// on the message's timeline.
+    pub fn time(
+        &self,
+        timeline: &mut StageTimeline,
+        message: &HyperlaneMessage,
+        stage: PipelineStage,
+        elapsed: Duration,
+        at: Instant,
+    ) -> bool {
+        if timeline.is_timed(stage) {
+            return false;
+        }
+        timeline.timed.push(stage);
+        let (route, budgets) = self.route(message);
+        let budget = stage.budget(&budgets);
+        let breached = budget.map_or(false, |budget| elapsed > budget);
+        self.stage_duration
+            .with_label_values(&[route, stage.as_str()])
+            .observe(elapsed.as_secs_f64());
+        if breached {
+            debug!(
+                id = ?message.id(),
+                route,
+                stage = stage.as_str(),
+                ?elapsed,
+                ?budget,
+                "Message took longer than the latency budget of the stage"
+            );
+            self.breaches
+                .with_label_values(&[route, stage.as_str()])
+                .inc();
+            timeline.breaches.push(stage);
+        }
+        let mut samples = self.samples.lock().unwrap();
+        let window = samples.entry((route.to_owned(), stage)).or_default();
+        window.push_back(Sample {
+            at,
+            elapsed,
+            breached,
+        });
+        self.expire(window, at);
+        breached
+    }
+
+    /// Drop the samples which fell out of the window
+    fn expire(&self, window: &mut VecDeque<Sample>, now: Instant) {
+        while window.front().map_or(false, |sample| {
+            now.duration_since(sample.at) > self.conf.window
+        }) {
+            window.pop_front();
+        }
+    }
+
+    /// Stage latencies of each route over the window ending at `now`
+    pub fn report(&self, now: Instant) -> LatencyReport {
+        let mut routes: BTreeMap<String, BTreeMap<PipelineStage, StageLatency>> = BTreeMap::new();
+        let mut samples = self.samples.lock().unwrap();
+        for ((route, stage), window) in samples.iter_mut() {
+            self.expire(window, now);
+            if window.is_empty() {
+                continue;
+            }
+            let budget = self
+                .conf
+                .routes
+                .iter()
+                .find(|conf| conf.name == *route)
+                .map_or(self.conf.default, |conf| conf.budgets.or(self.conf.default));
+            let mut latencies = window.iter().map(|s| s.elapsed).collect::<Vec<_>>();
+            latencies.sort();
+            // Nearest rank
+            let rank = (latencies.len() * 95 + 99) / 100;
+            routes.entry(route.clone()).or_default().insert(
+                *stage,
+                StageLatency {
+                    samples: latencies.len(),
+                    p95_ms: latencies[rank - 1].as_millis() as u64,
+                    budget_ms: stage.budget(&budget).map(|b| b.as_millis() as u64),
+                    breaches: window.iter().filter(|s| s.breached).count(),
+                },
+            );
+        }
+        LatencyReport {
+            window_secs: self.conf.window.as_secs(),
+            routes,
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use hyperlane_base::ExemplarRegistry;
+    use prometheus::{histogram_opts, opts, HistogramVec, IntCounterVec};
+    use tokio::time::sleep;
+
+    use super::*;
+    use crate::settings::{matching_list::MatchingList, LatencyBudgetRouteConf};
+
+    pub(crate) fn latency_budget_tracker(conf: LatencyBudgetConf) -> LatencyBudgetTracker {
+        let breaches = IntCounterVec::new(opts!("breaches", "help"), &["route", "stage"]).unwrap();
+        let stage_duration = ExemplarRegistry::default().register(
+            HistogramVec::new(
+                histogram_opts!("stage_duration", "help", STAGE_DURATION_BUCKETS.to_vec()),
+                &["route", "stage"],
+            )
+            .unwrap(),
+            STAGE_DURATION_BUCKETS,
+        );
+        LatencyBudgetTracker::with_metrics(conf, breaches, stage_duration)
+    }
+
+    fn secs(secs: u64) -> Option<Duration> {
+        Some(Duration::from_secs(secs))
+    }
+
+    fn conf() -> LatencyBudgetConf {
+        LatencyBudgetConf {
+            default: StageBudgets {
+                indexing: secs(30),
+                metadata: secs(10),
+                broadcast: secs(5),
+            },
+            routes: vec![LatencyBudgetRouteConf {
+                name: "bridge".to_owned(),
+                matching_list: MatchingList::with_destination_domain(1),
+                budgets: StageBudgets {
+                    broadcast: secs(2),
+                    ..Default::default()
+                },
+            }],
+            window: Duration::from_secs(60),
+        }
+    }
+
+    fn message(destination: u32, nonce: u32) -> HyperlaneMessage {
+        HyperlaneMessage {
+            destination,
+            nonce,
+            ..Default::default()
+        }
+    }
+
+    fn now() -> Instant {
+        tokio::time::Instant::now().into_std()
+    }
+
+    /// Drive `message` through the metadata and broadcast stages, which take
+    /// `metadata` and `broadcast` of virtual time
+    async fn relay(
+        tracker: &LatencyBudgetTracker,
+        message: &HyperlaneMessage,
+        metadata: Duration,
+        broadcast: Duration,
+    ) -> StageTimeline {
+        let mut timeline = StageTimeline::default();
+        let quorum_at = now();
+        sleep(metadata).await;
+        tracker.time(
+            &mut timeline,
+            message,
+            PipelineStage::Metadata,
+            now() - quorum_at,
+            now(),
+        );
+        timeline.mark_ready(now());
+        sleep(broadcast).await;
+        let ready_at = timeline.ready_at().unwrap();
+        tracker.time(
+            &mut timeline,
+            message,
+            PipelineStage::Broadcast,
+            now() - ready_at,
+            now(),
+        );
+        timeline
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_breaches_are_attributed_to_the_stage_over_budget() {
+        let tracker = latency_budget_tracker(conf());
+
+        let slow_metadata = relay(
+            &tracker,
+            &message(2, 0),
+            Duration::from_secs(11),
+            Duration::from_secs(3),
+        )
+        .await;
+        // The bridge route has a tighter broadcast budget, but inherits the
+        // default metadata one
+        let slow_broadcast = relay(
+            &tracker,
+            &message(1, 1),
+            Duration::from_secs(9),
+            Duration::from_secs(3),
+        )
+        .await;
+
+        assert_eq!(slow_metadata.breaches(), &[PipelineStage::Metadata]);
+        assert_eq!(slow_broadcast.breaches(), &[PipelineStage::Broadcast]);
+        let breaches = |route: &str, stage: PipelineStage| {
+            tracker
+                .breaches
+                .with_label_values(&[route, stage.as_str()])
+                .get()
+        };
+        assert_eq!(breaches("default", PipelineStage::Metadata), 1);
+        assert_eq!(breaches("default", PipelineStage::Broadcast), 0);
+        assert_eq!(breaches("bridge", PipelineStage::Metadata), 0);
+        assert_eq!(breaches("bridge", PipelineStage::Broadcast), 1);
+        let broadcast = tracker
+            .stage_duration
+            .with_label_values(&["default", PipelineStage::Broadcast.as_str()]);
+        assert_eq!(broadcast.get_sample_count(), 1);
+        assert_eq!(broadcast.get_sample_sum(), 3.0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_stages_are_timed_once() {
+        let tracker = latency_budget_tracker(conf());
+        let message = message(2, 0);
+        let mut timeline = StageTimeline::default();
+
+        timeline.mark_ready(now());
+        sleep(Duration::from_secs(10)).await;
+        // Prepared again after a failed submission
+        timeline.mark_ready(now());
+        let elapsed = now() - timeline.ready_at().unwrap();
+        let stage = PipelineStage::Broadcast;
+
+        assert_eq!(elapsed, Duration::from_secs(10));
+        assert!(tracker.time(&mut timeline, &message, stage, elapsed, now()));
+        assert!(!tracker.time(&mut timeline, &message, stage, elapsed, now()));
+        assert_eq!(timeline.breaches(), &[stage]);
+        assert_eq!(tracker.report(now()).routes["default"][&stage].samples, 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_report_covers_the_window() {
+        let tracker = latency_budget_tracker(conf());
+        for nonce in 0..19 {
+            relay(
+                &tracker,
+                &message(2, nonce),
+                Duration::from_secs(1),
+                Duration::from_secs(1),
+            )
+            .await;
+        }
+        relay(
+            &tracker,
+            &message(2, 19),
+            Duration::from_secs(1),
+            Duration::from_secs(8),
+        )
+        .await;
+
+        let report = tracker.report(now());
+        let broadcast = &report.routes["default"][&PipelineStage::Broadcast];
+        assert_eq!(
+            broadcast,
+            &StageLatency {
+                samples: 20,
+                p95_ms: 1000,
+                budget_ms: Some(5000),
+                breaches: 1,
+            }
+        );
+        relay(
+            &tracker,
+            &message(2, 20),
+            Duration::from_secs(1),
+            Duration::from_secs(8),
+        )
+        .await;
+        let report = tracker.report(now());
+        assert_eq!(
+            report.routes["default"][&PipelineStage::Broadcast].p95_ms,
+            8000
+        );
+
+        // Every sample falls out of the window
+        sleep(Duration::from_secs(61)).await;
+        assert!(tracker.report(now()).routes.is_empty());
+    }
+}
@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use async_trait::async_trait;
 use derive_more::Deref;
 use futures_util::future::join_all;
@@ -30,12 +32,12 @@ struct SubModuleMetadata {
 
 #[derive(Debug)]
 struct IsmAndMetadata {
-    ism: Box<dyn InterchainSecurityModule>,
+    ism: Arc<dyn InterchainSecurityModule>,
     meta: SubModuleMetadata,
 }
 
 impl IsmAndMetadata {
-    fn new(ism: Box<dyn InterchainSecurityModule>, index: usize, metadata: Vec<u8>) -> Self {
+    fn new(ism: Arc<dyn InterchainSecurityModule>, index: usize, metadata: Vec<u8>) -> Self {
         Self {
             ism,
             meta: SubModuleMetadata::new(index, metadata),
@@ -117,7 +119,8 @@ impl AggregationIsmMetadataBuilder {
 
 #[async_trait]
 impl MetadataBuilder for AggregationIsmMetadataBuilder {
-    #[instrument(err, skip(self), ret)]
+    // The built metadata is logged, truncated, by `build_ism_and_metadata`
+    #[instrument(err, skip(self))]
     #[allow(clippy::blocks_in_conditions)] // TODO: `rustc` 1.80.1 clippy issue
     async fn build(
         &self,
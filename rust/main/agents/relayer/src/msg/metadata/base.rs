@@ -11,26 +11,28 @@ use std::{
 };
 
 use crate::{
-    merkle_tree::builder::MerkleTreeBuilder,
+    merkle_tree::{availability::TreeAvailability, builder::MerkleTreeBuilder},
     msg::metadata::{
         multisig::{MerkleRootMultisigMetadataBuilder, MessageIdMultisigMetadataBuilder},
-        AggregationIsmMetadataBuilder, CcipReadIsmMetadataBuilder, NullMetadataBuilder,
-        RoutingIsmMetadataBuilder,
+        AggregationIsmMetadataBuilder, CcipReadIsmMetadataBuilder, CheckpointCache,
+        NullMetadataBuilder, QuorumPrefetcher, RoutingIsmMetadataBuilder,
     },
-    settings::matching_list::MatchingList,
+    settings::{matching_list::MatchingList, CcipReadConf},
 };
 use async_trait::async_trait;
 use derive_new::new;
 use eyre::{Context, Result};
 use hyperlane_base::db::{HyperlaneDb, HyperlaneRocksDB};
 use hyperlane_base::{
-    settings::{ChainConf, CheckpointSyncerConf},
-    CheckpointSyncer, CoreMetrics, MultisigCheckpointSyncer,
+    settings::{AnnouncedStorageLocations, ChainConf, CheckpointSyncerConf, ContractClientCache},
+    CheckpointPreference, CheckpointSyncer, CoreMetrics, MultisigCheckpointSyncer,
+    ReplicatedCheckpointSyncer, ValidatorReputations, WithMessageContext,
 };
 use hyperlane_core::{
-    accumulator::merkle::Proof, AggregationIsm, CcipReadIsm, Checkpoint, HyperlaneDomain,
-    HyperlaneMessage, InterchainSecurityModule, Mailbox, ModuleType, MultisigIsm, RoutingIsm,
-    ValidatorAnnounce, H160, H256,
+    accumulator::merkle::Proof, log_fields::LogOptBytes, AggregationIsm, CcipReadIsm, Checkpoint,
+    HyperlaneChain, HyperlaneDomain, HyperlaneMessage, HyperlaneProvider, InterchainSecurityModule,
+    Mailbox, ModuleType, MultisigIsm, MultisigSignedCheckpoint, RoutingIsm, ValidatorAnnounce,
+    H160, H256,
 };
 
 use tokio::sync::RwLock;
@@ -42,15 +44,39 @@ pub enum MetadataBuilderError {
     UnsupportedModuleType(ModuleType),
     #[error("Exceeded max depth when building metadata ({0})")]
     MaxDepthExceeded(u32),
+    #[error("Origin merkle tree is unavailable ({0:?})")]
+    TreeUnavailable(TreeAvailability),
+}
+
+impl MetadataBuilderError {
+    /// Whether metadata couldn't be built because the origin's merkle tree
+    /// is unavailable
+    pub fn is_tree_unavailable(err: &eyre::Report) -> bool {
+        err.chain().any(|cause| {
+            matches!(
+                cause.downcast_ref::<MetadataBuilderError>(),
+                Some(MetadataBuilderError::TreeUnavailable(_))
+            )
+        })
+    }
 }
 
-#[derive(Debug)]
 pub struct IsmWithMetadataAndType {
-    pub ism: Box<dyn InterchainSecurityModule>,
+    pub ism: Arc<dyn InterchainSecurityModule>,
     pub metadata: Option<Vec<u8>>,
     pub module_type: ModuleType,
 }
 
+impl Debug for IsmWithMetadataAndType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IsmWithMetadataAndType")
+            .field("ism", &self.ism)
+            .field("metadata", &LogOptBytes(self.metadata.as_deref()))
+            .field("module_type", &self.module_type)
+            .finish()
+    }
+}
+
 #[async_trait]
 pub trait MetadataBuilder: Send + Sync {
     async fn build(&self, ism_address: H256, message: &HyperlaneMessage)
@@ -176,7 +202,8 @@ pub struct MessageMetadataBuilder {
     /// ISMs can be structured recursively. We keep track of the depth
     /// of the recursion to avoid infinite loops.
     pub depth: u32,
-    pub app_context: Option<String>,
+    /// Shared by the builders of nested ISMs, which are cloned per level
+    pub app_context: Option<Arc<str>>,
 }
 
 impl Deref for MessageMetadataBuilder {
@@ -195,9 +222,17 @@ impl MetadataBuilder for MessageMetadataBuilder {
         ism_address: H256,
         message: &HyperlaneMessage,
     ) -> Result<Option<Vec<u8>>> {
-        self.build_ism_and_metadata(ism_address, message)
+        let metadata = self
+            .build_ism_and_metadata(ism_address, message)
             .await
-            .map(|ism_with_metadata| ism_with_metadata.metadata)
+            .map(|ism_with_metadata| ism_with_metadata.metadata);
+        // Metadata of sub-ISMs is built recursively, the outermost build
+        // attaches the message
+        if self.depth == 0 {
+            metadata.with_message_context(message)
+        } else {
+            metadata
+        }
     }
 }
 
@@ -210,7 +245,8 @@ impl MessageMetadataBuilder {
         let app_context = base
             .app_context_classifier
             .get_app_context(message, ism_address)
-            .await?;
+            .await?
+            .map(Arc::from);
         Ok(Self {
             base,
             depth: 0,
@@ -234,7 +270,7 @@ impl MessageMetadataBuilder {
         ism_address: H256,
         message: &HyperlaneMessage,
     ) -> Result<IsmWithMetadataAndType> {
-        let ism: Box<dyn InterchainSecurityModule> = self
+        let ism: Arc<dyn InterchainSecurityModule> = self
             .build_ism(ism_address)
             .await
             .context("When building ISM")?;
@@ -282,8 +318,16 @@ pub struct BaseMetadataBuilder {
     metrics: Arc<CoreMetrics>,
     db: HyperlaneRocksDB,
     app_context_classifier: IsmAwareAppContextClassifier,
+    client_cache: Arc<ContractClientCache>,
+    validator_reputations: ValidatorReputations,
+    checkpoint_preferences: Vec<(MatchingList, CheckpointPreference)>,
+    ccip_read: CcipReadConf,
     #[new(value = "7")]
     max_depth: u32,
+    #[new(default)]
+    quorum_prefetcher: Option<QuorumPrefetcher>,
+    #[new(default)]
+    checkpoint_cache: Option<CheckpointCache>,
 }
 
 impl Debug for BaseMetadataBuilder {
@@ -297,6 +341,18 @@ impl Debug for BaseMetadataBuilder {
 }
 
 impl BaseMetadataBuilder {
+    /// Prepare the proofs of messages whose quorum is within reach
+    pub fn with_quorum_prefetcher(mut self, prefetcher: QuorumPrefetcher) -> Self {
+        self.quorum_prefetcher = Some(prefetcher);
+        self
+    }
+
+    /// Reuse quorum checkpoints across the messages they cover
+    pub fn with_checkpoint_cache(mut self, cache: CheckpointCache) -> Self {
+        self.checkpoint_cache = Some(cache);
+        self
+    }
+
     pub fn origin_domain(&self) -> &HyperlaneDomain {
         &self.origin_domain
     }
@@ -307,23 +363,76 @@ impl BaseMetadataBuilder {
 
     pub async fn get_proof(&self, leaf_index: u32, checkpoint: Checkpoint) -> Result<Proof> {
         const CTX: &str = "When fetching message proof";
-        let proof = self
-            .origin_prover_sync
-            .read()
-            .await
+        let prover_sync = self.origin_prover_sync.read().await;
+        let proof = prover_sync
             .get_proof(leaf_index, checkpoint.index)
             .context(CTX)?;
 
-        if proof.root() != checkpoint.root {
+        let canonical_root = prover_sync.proof_root(&proof);
+        if canonical_root != checkpoint.root {
             info!(
                 ?checkpoint,
-                canonical_root = ?proof.root(),
+                ?canonical_root,
                 "Could not fetch metadata: checkpoint root does not match canonical root from merkle proof"
             );
         }
         Ok(proof)
     }
 
+    /// Which quorum checkpoint to deliver the message against, as set for
+    /// the first route it matches
+    pub fn checkpoint_preference(&self, message: &HyperlaneMessage) -> CheckpointPreference {
+        self.checkpoint_preferences
+            .iter()
+            .find(|(matching_list, _)| matching_list.msg_matches(message, false))
+            .map(|(_, preference)| *preference)
+            .unwrap_or_default()
+    }
+
+    /// How offchain lookups of CCIP read ISMs are performed
+    pub fn ccip_read(&self) -> &CcipReadConf {
+        &self.ccip_read
+    }
+
+    pub fn quorum_prefetcher(&self) -> Option<&QuorumPrefetcher> {
+        self.quorum_prefetcher.as_ref()
+    }
+
+    pub fn checkpoint_cache(&self) -> Option<&CheckpointCache> {
+        self.checkpoint_cache.as_ref()
+    }
+
+    /// How long to wait before retrying a message whose metadata couldn't be
+    /// fetched, if its quorum is anticipated
+    pub fn anticipated_quorum_retry_delay(&self, message_id: H256) -> Option<Duration> {
+        self.quorum_prefetcher
+            .as_ref()?
+            .retry_delay(message_id, Instant::now())
+    }
+
+    /// The proof of a leaf against the tree as of a checkpoint index, without
+    /// the checkpoint itself being known yet
+    pub async fn get_proof_at(&self, leaf_index: u32, checkpoint_index: u32) -> Result<Proof> {
+        self.origin_prover_sync
+            .read()
+            .await
+            .get_proof(leaf_index, checkpoint_index)
+            .context("When fetching message proof")
+    }
+
+    pub async fn tree_availability(&self) -> TreeAvailability {
+        self.origin_prover_sync.read().await.availability().clone()
+    }
+
+    pub async fn tree_count(&self) -> u32 {
+        self.origin_prover_sync.read().await.count()
+    }
+
+    /// Provider of the origin chain
+    pub fn origin_provider(&self) -> Box<dyn HyperlaneProvider> {
+        self.origin_validator_announce.provider()
+    }
+
     pub async fn highest_known_leaf_index(&self) -> Option<u32> {
         self.origin_prover_sync.read().await.count().checked_sub(1)
     }
@@ -335,33 +444,45 @@ impl BaseMetadataBuilder {
         Ok(merkle_leaf)
     }
 
-    pub async fn build_ism(&self, address: H256) -> Result<Box<dyn InterchainSecurityModule>> {
-        self.destination_chain_setup
-            .build_ism(address, &self.metrics)
+    /// Record the quorum checkpoint that metadata was built from, so it can be
+    /// exported for auditing later on
+    pub fn store_quorum_checkpoint(
+        &self,
+        message_id: H256,
+        checkpoint: &MultisigSignedCheckpoint,
+    ) -> Result<()> {
+        self.db
+            .store_quorum_checkpoint_by_message_id(&message_id, checkpoint)?;
+        Ok(())
+    }
+
+    pub async fn build_ism(&self, address: H256) -> Result<Arc<dyn InterchainSecurityModule>> {
+        self.client_cache
+            .ism(&self.destination_chain_setup, address)
             .await
     }
 
-    pub async fn build_routing_ism(&self, address: H256) -> Result<Box<dyn RoutingIsm>> {
-        self.destination_chain_setup
-            .build_routing_ism(address, &self.metrics)
+    pub async fn build_routing_ism(&self, address: H256) -> Result<Arc<dyn RoutingIsm>> {
+        self.client_cache
+            .routing_ism(&self.destination_chain_setup, address)
             .await
     }
 
-    pub async fn build_multisig_ism(&self, address: H256) -> Result<Box<dyn MultisigIsm>> {
-        self.destination_chain_setup
-            .build_multisig_ism(address, &self.metrics)
+    pub async fn build_multisig_ism(&self, address: H256) -> Result<Arc<dyn MultisigIsm>> {
+        self.client_cache
+            .multisig_ism(&self.destination_chain_setup, address)
             .await
     }
 
-    pub async fn build_aggregation_ism(&self, address: H256) -> Result<Box<dyn AggregationIsm>> {
-        self.destination_chain_setup
-            .build_aggregation_ism(address, &self.metrics)
+    pub async fn build_aggregation_ism(&self, address: H256) -> Result<Arc<dyn AggregationIsm>> {
+        self.client_cache
+            .aggregation_ism(&self.destination_chain_setup, address)
             .await
     }
 
-    pub async fn build_ccip_read_ism(&self, address: H256) -> Result<Box<dyn CcipReadIsm>> {
-        self.destination_chain_setup
-            .build_ccip_read_ism(address, &self.metrics)
+    pub async fn build_ccip_read_ism(&self, address: H256) -> Result<Arc<dyn CcipReadIsm>> {
+        self.client_cache
+            .ccip_read_ism(&self.destination_chain_setup, address)
             .await
     }
 
@@ -379,42 +500,21 @@ impl BaseMetadataBuilder {
         let mut checkpoint_syncers: HashMap<H160, Arc<dyn CheckpointSyncer>> = HashMap::new();
         for (&validator, validator_storage_locations) in validators.iter().zip(storage_locations) {
             for storage_location in validator_storage_locations.iter().rev() {
-                let Ok(config) = CheckpointSyncerConf::from_str(storage_location) else {
-                    debug!(
-                        ?validator,
-                        ?storage_location,
-                        "Could not parse checkpoint syncer config for validator"
-                    );
-                    continue;
-                };
-
-                // If this is a LocalStorage based checkpoint syncer and it's not
-                // allowed, ignore it
-                if !self.allow_local_checkpoint_syncers
-                    && matches!(config, CheckpointSyncerConf::LocalStorage { .. })
+                if let Some(checkpoint_syncer) = build_announced_checkpoint_syncer(
+                    storage_location,
+                    self.allow_local_checkpoint_syncers,
+                )
+                .await
                 {
-                    debug!(
-                        ?config,
-                        "Ignoring disallowed LocalStorage based checkpoint syncer"
-                    );
-                    continue;
-                }
-
-                match config.build_and_validate(None).await {
-                    Ok(checkpoint_syncer) => {
-                        // found the syncer for this validator
-                        checkpoint_syncers.insert(validator.into(), checkpoint_syncer.into());
-                        break;
-                    }
-                    Err(err) => {
-                        debug!(
-                            error=%err,
-                            ?config,
-                            ?validator,
-                            "Error when loading checkpoint syncer; will attempt to use the next config"
-                        );
-                    }
+                    // found the syncer for this validator
+                    checkpoint_syncers.insert(validator.into(), checkpoint_syncer);
+                    break;
                 }
+                debug!(
+                    ?validator,
+                    ?storage_location,
+                    "No usable checkpoint syncer at storage location; will attempt to use the next one"
+                );
             }
             if checkpoint_syncers.get(&validator.into()).is_none() {
                 if validator_storage_locations.is_empty() {
@@ -428,10 +528,199 @@ impl BaseMetadataBuilder {
                 }
             }
         }
-        Ok(MultisigCheckpointSyncer::new(
+        Ok(
+            MultisigCheckpointSyncer::new(checkpoint_syncers, self.metrics.clone(), app_context)
+                .with_reputations(self.validator_reputations.clone()),
+        )
+    }
+}
+
+/// Build the checkpoint syncer of an announced storage location. Validators
+/// publishing to several backends may list alternates after their primary
+/// location, which are read from whenever the primary fails or misses a
+/// checkpoint, and used on their own if the primary can't be built.
+async fn build_announced_checkpoint_syncer(
+    storage_location: &str,
+    allow_local_checkpoint_syncers: bool,
+) -> Option<Arc<dyn CheckpointSyncer>> {
+    let announced = AnnouncedStorageLocations::from_str(storage_location).ok()?;
+    let mut checkpoint_syncers: Vec<Arc<dyn CheckpointSyncer>> = vec![];
+    for location in announced.iter() {
+        let Ok(config) = CheckpointSyncerConf::from_str(location) else {
+            debug!(
+                ?location,
+                "Could not parse checkpoint syncer config for validator"
+            );
+            continue;
+        };
+
+        // If this is a LocalStorage based checkpoint syncer and it's not
+        // allowed, ignore it
+        if !allow_local_checkpoint_syncers
+            && matches!(config, CheckpointSyncerConf::LocalStorage { .. })
+        {
+            debug!(
+                ?config,
+                "Ignoring disallowed LocalStorage based checkpoint syncer"
+            );
+            continue;
+        }
+
+        match config.build_and_validate(None).await {
+            Ok(checkpoint_syncer) => checkpoint_syncers.push(checkpoint_syncer.into()),
+            Err(err) => {
+                debug!(
+                    error=%err,
+                    ?config,
+                    "Error when loading checkpoint syncer; will attempt to use the next config"
+                );
+            }
+        }
+    }
+    match checkpoint_syncers.len() {
+        0 => None,
+        1 => checkpoint_syncers.pop(),
+        // Relayers never write, so the write quorum doesn't matter
+        _ => Some(Arc::new(ReplicatedCheckpointSyncer::new(
             checkpoint_syncers,
-            self.metrics.clone(),
-            app_context,
-        ))
+            1,
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use eyre::WrapErr;
+    use hyperlane_base::LocalStorage;
+    use hyperlane_core::{
+        log_fields::DEFAULT_LOG_FIELD_BUDGET, ChainResult, CheckpointWithMessageId, HyperlaneChain,
+        HyperlaneContract, HyperlaneProvider, Signature, SignedCheckpointWithMessageId, U256,
+    };
+
+    use super::*;
+
+    mockall::mock! {
+        pub Ism {}
+
+        impl Debug for Ism {
+            fn fmt<'a>(&self, f: &mut std::fmt::Formatter<'a>) -> std::fmt::Result;
+        }
+
+        impl HyperlaneChain for Ism {
+            fn domain(&self) -> &HyperlaneDomain;
+            fn provider(&self) -> Box<dyn HyperlaneProvider>;
+        }
+
+        impl HyperlaneContract for Ism {
+            fn address(&self) -> H256;
+        }
+
+        #[async_trait]
+        impl InterchainSecurityModule for Ism {
+            async fn module_type(&self) -> ChainResult<ModuleType>;
+            async fn dry_run_verify(
+                &self,
+                message: &HyperlaneMessage,
+                metadata: &[u8],
+            ) -> ChainResult<Option<U256>>;
+        }
+    }
+
+    #[test]
+    fn test_built_metadata_is_logged_truncated() {
+        let mut ism = MockIsm::new();
+        ism.expect_fmt().returning(|f| write!(f, "MockIsm"));
+        let metadata = vec![0xab; 4 * DEFAULT_LOG_FIELD_BUDGET];
+        let built = IsmWithMetadataAndType {
+            ism: Arc::new(ism),
+            metadata: Some(metadata.clone()),
+            module_type: ModuleType::Aggregation,
+        };
+        let logged = format!("{built:?}");
+        assert!(logged.len() < 2 * DEFAULT_LOG_FIELD_BUDGET);
+        assert!(logged.contains(&LogOptBytes(Some(&metadata)).to_string()));
+        assert!(logged.contains(&format!("truncated, {} bytes", metadata.len())));
+
+        let unbuilt = IsmWithMetadataAndType {
+            metadata: None,
+            ..built
+        };
+        assert!(format!("{unbuilt:?}").contains("metadata: None"));
+    }
+
+    #[test]
+    fn test_tree_unavailable_is_detected_through_context() {
+        let err: eyre::Result<()> = Err(MetadataBuilderError::TreeUnavailable(
+            TreeAvailability::Rebuilding { progress: 3 },
+        )
+        .into());
+        let err = err.wrap_err("Building routing ISM metadata").unwrap_err();
+        assert!(MetadataBuilderError::is_tree_unavailable(&err));
+
+        let other: eyre::Report = MetadataBuilderError::MaxDepthExceeded(3).into();
+        assert!(!MetadataBuilderError::is_tree_unavailable(&other));
+    }
+
+    fn signed_checkpoint(index: u32) -> SignedCheckpointWithMessageId {
+        SignedCheckpointWithMessageId {
+            value: CheckpointWithMessageId {
+                checkpoint: Checkpoint {
+                    merkle_tree_hook_address: H256::zero(),
+                    mailbox_domain: 1,
+                    root: H256::from_low_u64_be(index as u64),
+                    index,
+                },
+                message_id: H256::from_low_u64_be(index as u64),
+            },
+            signature: Signature {
+                r: U256::one(),
+                s: U256::one(),
+                v: 27,
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_checkpoints_are_read_from_the_announced_alternate() {
+        let (primary_dir, alternate_dir) =
+            (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        let alternate = LocalStorage::new(alternate_dir.path().to_owned(), None).unwrap();
+        alternate
+            .write_checkpoint(&signed_checkpoint(3))
+            .await
+            .unwrap();
+        alternate.write_latest_index(3).await.unwrap();
+        let alternate_location = alternate.announcement_location();
+
+        // The primary is missing the checkpoint
+        let primary_location = format!("file://{}", primary_dir.path().display());
+        let syncer = build_announced_checkpoint_syncer(
+            &format!("{primary_location}|{alternate_location}"),
+            true,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            syncer.fetch_checkpoint(3).await.unwrap(),
+            Some(signed_checkpoint(3))
+        );
+
+        // The primary can't be built at all
+        let syncer = build_announced_checkpoint_syncer(
+            &format!("file:///dev/null/primary|{alternate_location}"),
+            true,
+        )
+        .await
+        .unwrap();
+        assert_eq!(syncer.latest_index().await.unwrap(), Some(3));
+        assert_eq!(syncer.announcement_location(), alternate_location);
+
+        // Local alternates are ignored like any local location
+        assert!(build_announced_checkpoint_syncer(
+            &format!("file:///dev/null/primary|{alternate_location}"),
+            false,
+        )
+        .await
+        .is_none());
     }
 }
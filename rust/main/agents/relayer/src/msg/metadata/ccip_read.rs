@@ -1,25 +1,54 @@
 #![allow(clippy::blocks_in_conditions)] // TODO: `rustc` 1.80.1 clippy issue
 
+//! Metadata of CCIP read ISMs, fetched offchain following ERC-3668: the ISM
+//! reverts with an `OffchainLookup` telling which gateways to ask, and the
+//! data a gateway answers with is the metadata the ISM verifies the message
+//! with. Gateways may answer with another lookup, which is followed up to a
+//! configured depth.
+
 use async_trait::async_trait;
 use derive_more::Deref;
 use derive_new::new;
-use ethers::{abi::AbiDecode, core::utils::hex::decode as hex_decode};
+use ethers::{
+    abi::AbiDecode, contract::EthError, core::utils::hex::decode as hex_decode, types::H160,
+};
 use eyre::Context;
-use hyperlane_core::{utils::bytes_to_hex, HyperlaneMessage, RawHyperlaneMessage, H256};
+use hyperlane_core::{
+    utils::bytes_to_hex, Address, CcipReadIsm, ChainResult, HyperlaneMessage, RawHyperlaneMessage,
+    H256,
+};
 use hyperlane_ethereum::OffchainLookup;
 use regex::Regex;
-use reqwest::Client;
+use reqwest::{Client, Url};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use tracing::{info, instrument};
+use tracing::{debug, instrument, warn};
 
 use super::{base::MessageMetadataBuilder, MetadataBuilder};
+use crate::settings::CcipReadConf;
 
 #[derive(Serialize, Deserialize)]
 struct OffchainResponse {
     data: String,
 }
 
+/// Why the metadata of a CCIP read ISM couldn't be fetched
+#[derive(Debug, thiserror::Error)]
+pub enum CcipReadError {
+    #[error("CCIP read ISM didn't revert with an OffchainLookup: {0}")]
+    NoOffchainLookup(String),
+    #[error("OffchainLookup sender {sender:?} isn't the ISM {ism:?}")]
+    SenderMismatch { sender: H160, ism: H160 },
+    #[error("None of the gateway URLs {0:?} are allowed for this route")]
+    NoAllowedUrls(Vec<String>),
+    #[error("Gateway {url} refused the lookup: {reason}")]
+    Refused { url: String, reason: String },
+    #[error("All gateways failed: {}", .0.join("; "))]
+    AllGatewaysFailed(Vec<String>),
+    #[error("Exceeded the max of {0} offchain lookups")]
+    MaxLookupsExceeded(u32),
+}
+
 #[derive(Clone, Debug, new, Deref)]
 pub struct CcipReadIsmMetadataBuilder {
     base: MessageMetadataBuilder,
@@ -35,65 +64,417 @@ impl MetadataBuilder for CcipReadIsmMetadataBuilder {
     ) -> eyre::Result<Option<Vec<u8>>> {
         const CTX: &str = "When fetching CcipRead metadata";
         let ism = self.build_ccip_read_ism(ism_address).await.context(CTX)?;
+        let conf = self.ccip_read();
+        let client = Client::builder().timeout(conf.timeout).build()?;
+        // the ISM must be an EVM contract, so its address can't have bytes
+        // beyond the 20 of an EVM address
+        let ism_h160 = Address::from(ism_address).into_evm_h160().context(CTX)?;
+        let metadata = fetch_metadata(ism.as_ref(), ism_h160.into(), message, conf, &client)
+            .await
+            .context(CTX)?;
+        Ok(Some(metadata))
+    }
+}
 
-        let response = ism
-            .get_offchain_verify_info(RawHyperlaneMessage::from(message).to_vec())
-            .await;
-        let info: OffchainLookup = match response {
-            Ok(_) => {
-                info!("incorrectly configured getOffchainVerifyInfo, expected revert");
-                return Ok(None);
+/// Fetch the metadata of `message` for the CCIP read ISM at `ism_address`,
+/// following the lookups it and its gateways ask for
+pub async fn fetch_metadata(
+    ism: &dyn CcipReadIsm,
+    ism_address: H160,
+    message: &HyperlaneMessage,
+    conf: &CcipReadConf,
+    client: &Client,
+) -> Result<Vec<u8>, CcipReadError> {
+    let response = ism
+        .get_offchain_verify_info(RawHyperlaneMessage::from(message))
+        .await;
+    let mut lookup = offchain_lookup(response)?;
+    let allowed = allowed_urls(conf, message);
+    for _ in 0..conf.max_lookups {
+        if lookup.sender != ism_address {
+            return Err(CcipReadError::SenderMismatch {
+                sender: lookup.sender,
+                ism: ism_address,
+            });
+        }
+        let response = query_gateways(&lookup, allowed, conf, client).await?;
+        if !response.starts_with(&OffchainLookup::selector()) {
+            return Ok(response);
+        }
+        debug!("Gateway answered with another offchain lookup");
+        lookup = OffchainLookup::decode(&response).map_err(|err| {
+            CcipReadError::AllGatewaysFailed(vec![format!("invalid nested lookup: {err}")])
+        })?;
+    }
+    Err(CcipReadError::MaxLookupsExceeded(conf.max_lookups))
+}
+
+/// The `OffchainLookup` a call to the ISM reverted with
+fn offchain_lookup(response: ChainResult<()>) -> Result<OffchainLookup, CcipReadError> {
+    let raw_error = match response {
+        Ok(_) => {
+            return Err(CcipReadError::NoOffchainLookup(
+                "getOffchainVerifyInfo didn't revert".to_owned(),
+            ))
+        }
+        Err(raw_error) => raw_error.to_string(),
+    };
+    let matching_regex = Regex::new(r"0x[[:xdigit:]]+").expect("valid regex");
+    let revert_data = matching_regex
+        .find(&raw_error)
+        .and_then(|matching| hex_decode(&matching.as_str()[2..]).ok())
+        .ok_or_else(|| CcipReadError::NoOffchainLookup(raw_error.clone()))?;
+    OffchainLookup::decode(revert_data)
+        .map_err(|err| CcipReadError::NoOffchainLookup(format!("{raw_error}: {err}")))
+}
+
+/// Gateway URLs allowed for `message`, `None` allowing any
+fn allowed_urls<'a>(conf: &'a CcipReadConf, message: &HyperlaneMessage) -> Option<&'a [String]> {
+    conf.allowlist.as_ref().map(|routes| {
+        routes
+            .iter()
+            .find(|(matching_list, _)| matching_list.msg_matches(message, true))
+            .map(|(_, urls)| urls.as_slice())
+            .unwrap_or_default()
+    })
+}
+
+/// Whether `url` has the same origin as `allowed` and extends its path
+fn url_allowed(url: &str, allowed: &str) -> bool {
+    let (Ok(url), Ok(allowed)) = (Url::parse(url), Url::parse(allowed)) else {
+        return false;
+    };
+    let prefix = allowed.path().trim_end_matches('/');
+    url.origin() == allowed.origin()
+        && (url.path() == prefix || url.path().starts_with(&format!("{prefix}/")))
+}
+
+/// A gateway that couldn't answer a lookup
+struct GatewayFailure {
+    reason: String,
+    /// Whether to try the next gateway. Per ERC-3668, gateways answering
+    /// with a 4xx status reject the lookup for good.
+    retryable: bool,
+}
+
+impl GatewayFailure {
+    fn retryable(reason: impl ToString) -> Self {
+        Self {
+            reason: reason.to_string(),
+            retryable: true,
+        }
+    }
+}
+
+/// Ask the allowed gateways of `lookup` in order, until one answers
+async fn query_gateways(
+    lookup: &OffchainLookup,
+    allowed: Option<&[String]>,
+    conf: &CcipReadConf,
+    client: &Client,
+) -> Result<Vec<u8>, CcipReadError> {
+    // Need to explicitly convert the sender H160 the hex because the `ToString` implementation
+    // for `H160` truncates the output. (e.g. `0xc66a…7b6f` instead of returning
+    // the full address)
+    let sender = bytes_to_hex(lookup.sender.as_bytes());
+    let data = lookup.call_data.to_string();
+    let mut failures = vec![];
+    let mut queried = false;
+    for url in lookup.urls.iter() {
+        let interpolated_url = url.replace("{sender}", &sender).replace("{data}", &data);
+        if let Some(allowed) = allowed {
+            if !allowed
+                .iter()
+                .any(|prefix| url_allowed(&interpolated_url, prefix))
+            {
+                warn!(%url, "Skipping gateway URL that isn't allowed for this route");
+                continue;
+            }
+        }
+        queried = true;
+        let request = if url.contains("{data}") {
+            client.get(interpolated_url)
+        } else {
+            client
+                .post(interpolated_url)
+                .json(&json!({ "sender": sender, "data": data }))
+        };
+        match query_gateway(request, conf.max_response_bytes).await {
+            Ok(response) => return Ok(response),
+            Err(failure) if failure.retryable => {
+                failures.push(format!("{url}: {}", failure.reason))
             }
-            Err(raw_error) => {
-                let matching_regex = Regex::new(r"0x[[:xdigit:]]+")?;
-                if let Some(matching) = &matching_regex.captures(&raw_error.to_string()) {
-                    OffchainLookup::decode(hex_decode(&matching[0][2..])?)?
-                } else {
-                    info!("unable to parse custom error out of revert");
-                    return Ok(None);
-                }
+            Err(failure) => {
+                return Err(CcipReadError::Refused {
+                    url: url.clone(),
+                    reason: failure.reason,
+                })
             }
+        }
+    }
+    if !queried {
+        return Err(CcipReadError::NoAllowedUrls(lookup.urls.clone()));
+    }
+    Err(CcipReadError::AllGatewaysFailed(failures))
+}
+
+async fn query_gateway(
+    request: reqwest::RequestBuilder,
+    max_response_bytes: usize,
+) -> Result<Vec<u8>, GatewayFailure> {
+    let mut response = request.send().await.map_err(GatewayFailure::retryable)?;
+    let status = response.status();
+    if status.is_client_error() {
+        return Err(GatewayFailure {
+            reason: format!("status {status}"),
+            retryable: false,
+        });
+    }
+    if !status.is_success() {
+        return Err(GatewayFailure::retryable(format!("status {status}")));
+    }
+    let too_large =
+        || GatewayFailure::retryable(format!("response larger than {max_response_bytes} bytes"));
+    if response.content_length().unwrap_or_default() > max_response_bytes as u64 {
+        return Err(too_large());
+    }
+    let mut body = vec![];
+    while let Some(chunk) = response.chunk().await.map_err(GatewayFailure::retryable)? {
+        if body.len() + chunk.len() > max_response_bytes {
+            return Err(too_large());
+        }
+        body.extend_from_slice(&chunk);
+    }
+    let response: OffchainResponse =
+        serde_json::from_slice(&body).map_err(GatewayFailure::retryable)?;
+    // remove leading 0x which hex_decode doesn't like
+    hex_decode(response.data.trim_start_matches("0x")).map_err(GatewayFailure::retryable)
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fmt::Debug, net::SocketAddr, time::Duration};
+
+    use axum::{
+        extract::{Path, State},
+        routing::{get, post},
+        Json, Router,
+    };
+    use ethers::{abi::AbiEncode, core::utils::hex};
+    use hyperlane_core::{
+        ChainCommunicationError, HyperlaneChain, HyperlaneContract, HyperlaneDomain,
+        HyperlaneProvider,
+    };
+    use serde_json::Value;
+
+    use super::*;
+    use crate::settings::matching_list::MatchingList;
+
+    mockall::mock! {
+        pub CcipReadIsm {}
+
+        impl Debug for CcipReadIsm {
+            fn fmt<'a>(&self, f: &mut std::fmt::Formatter<'a>) -> std::fmt::Result;
+        }
+
+        impl HyperlaneChain for CcipReadIsm {
+            fn domain(&self) -> &HyperlaneDomain;
+            fn provider(&self) -> Box<dyn HyperlaneProvider>;
+        }
+
+        impl HyperlaneContract for CcipReadIsm {
+            fn address(&self) -> H256;
+        }
+
+        #[async_trait]
+        impl CcipReadIsm for CcipReadIsm {
+            async fn get_offchain_verify_info(&self, message: Vec<u8>) -> ChainResult<()>;
+        }
+    }
+
+    const METADATA: &[u8] = b"offchain metadata";
+
+    fn ism_address() -> H160 {
+        H160::repeat_byte(0x11)
+    }
+
+    fn lookup(urls: Vec<String>) -> OffchainLookup {
+        OffchainLookup {
+            sender: ism_address(),
+            urls,
+            call_data: vec![0xca, 0x11].into(),
+            callback_function: [0; 4],
+            extra_data: vec![].into(),
+        }
+    }
+
+    /// An ISM reverting with a lookup asking `urls`
+    fn reverting_ism(urls: Vec<String>) -> MockCcipReadIsm {
+        let revert_data = hex::encode(lookup(urls).encode());
+        let mut ism = MockCcipReadIsm::new();
+        ism.expect_get_offchain_verify_info().returning(move |_| {
+            Err(ChainCommunicationError::from_other_str(&format!(
+                "Contract call reverted with data: 0x{revert_data}"
+            )))
+        });
+        ism
+    }
+
+    /// A gateway answering lookups with `METADATA` over GET and POST, with a
+    /// nested lookup, with an oversized response, and refusing them
+    fn gateway() -> SocketAddr {
+        async fn by_path(Path((sender, data)): Path<(String, String)>) -> Json<Value> {
+            assert_eq!(sender, bytes_to_hex(ism_address().as_bytes()));
+            assert_eq!(data, "0xca11");
+            Json(json!({ "data": bytes_to_hex(METADATA) }))
+        }
+        async fn by_body(Json(body): Json<Value>) -> Json<Value> {
+            assert_eq!(body["data"], "0xca11");
+            Json(json!({ "data": bytes_to_hex(METADATA) }))
+        }
+        async fn nested(State(addr): State<SocketAddr>) -> Json<Value> {
+            let nested = lookup(vec![format!("http://{addr}/get/{{sender}}/{{data}}")]);
+            Json(json!({ "data": bytes_to_hex(&nested.encode()) }))
+        }
+        async fn oversized() -> Json<Value> {
+            Json(json!({ "data": bytes_to_hex(&[0u8; 1024]) }))
+        }
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = Router::new()
+            .route("/get/:sender/:data", get(by_path))
+            .route("/post", post(by_body))
+            .route("/nested", post(nested))
+            .route("/oversized", post(oversized))
+            .with_state(addr);
+        let server = axum::Server::from_tcp(listener)
+            .unwrap()
+            .serve(app.into_make_service());
+        tokio::spawn(server);
+        addr
+    }
+
+    fn conf(allowlist: Option<Vec<(MatchingList, Vec<String>)>>) -> CcipReadConf {
+        CcipReadConf {
+            allowlist,
+            timeout: Duration::from_secs(5),
+            max_response_bytes: 512,
+            max_lookups: 2,
+        }
+    }
+
+    async fn fetch(ism: MockCcipReadIsm, conf: &CcipReadConf) -> Result<Vec<u8>, CcipReadError> {
+        let message = HyperlaneMessage::default();
+        fetch_metadata(&ism, ism_address(), &message, conf, &Client::new()).await
+    }
+
+    #[tokio::test]
+    async fn test_metadata_is_fetched_from_gateways() {
+        let addr = gateway();
+        let get_url = format!("http://{addr}/get/{{sender}}/{{data}}");
+        let post_url = format!("http://{addr}/post");
+        for url in [&get_url, &post_url] {
+            let metadata = fetch(reverting_ism(vec![url.clone()]), &conf(None)).await;
+            assert_eq!(metadata.unwrap(), METADATA);
+        }
+
+        // Gateways failing are skipped, within the size limit
+        let urls = vec![
+            format!("http://{addr}/oversized"),
+            format!("http://{addr}/missing"),
+            post_url.clone(),
+        ];
+        let metadata = fetch(reverting_ism(urls[..1].to_vec()), &conf(None)).await;
+        assert!(matches!(metadata, Err(CcipReadError::AllGatewaysFailed(_))));
+        // 4xx responses refuse the lookup without trying the next gateway
+        let metadata = fetch(reverting_ism(urls), &conf(None)).await;
+        assert!(matches!(
+            metadata,
+            Err(CcipReadError::Refused { ref url, .. }) if url.ends_with("/missing")
+        ));
+
+        // Nested lookups are followed, up to the max
+        let nested = vec![format!("http://{addr}/nested")];
+        let metadata = fetch(reverting_ism(nested.clone()), &conf(None)).await;
+        assert_eq!(metadata.unwrap(), METADATA);
+        let mut shallow = conf(None);
+        shallow.max_lookups = 1;
+        let metadata = fetch(reverting_ism(nested), &shallow).await;
+        assert!(matches!(
+            metadata,
+            Err(CcipReadError::MaxLookupsExceeded(1))
+        ));
+
+        let mut ism = MockCcipReadIsm::new();
+        ism.expect_get_offchain_verify_info().returning(|_| Ok(()));
+        let metadata = fetch(ism, &conf(None)).await;
+        assert!(matches!(metadata, Err(CcipReadError::NoOffchainLookup(_))));
+    }
+
+    #[tokio::test]
+    async fn test_allowlist_is_enforced() {
+        let addr = gateway();
+        let post_url = format!("http://{addr}/post");
+        let allow = |urls: &[&str]| {
+            Some(vec![
+                (
+                    MatchingList::with_destination_domain(1),
+                    vec![format!("http://{addr}")],
+                ),
+                (
+                    MatchingList::default(),
+                    urls.iter().map(|url| url.to_string()).collect(),
+                ),
+            ])
         };
 
-        for url in info.urls.iter() {
-            // Need to explicitly convert the sender H160 the hex because the `ToString` implementation
-            // for `H160` truncates the output. (e.g. `0xc66a…7b6f` instead of returning
-            // the full address)
-            let sender_as_bytes = &bytes_to_hex(info.sender.as_bytes());
-            let data_as_bytes = &info.call_data.to_string();
-            let interpolated_url = url
-                .replace("{sender}", sender_as_bytes)
-                .replace("{data}", data_as_bytes);
-            let res = if !url.contains("{data}") {
-                let body = json!({
-                    "sender": sender_as_bytes,
-                    "data": data_as_bytes
-                });
-                Client::new()
-                    .post(interpolated_url)
-                    .header("Content-Type", "application/json")
-                    .json(&body)
-                    .send()
-                    .await?
-            } else {
-                reqwest::get(interpolated_url).await?
-            };
-
-            let json: Result<OffchainResponse, reqwest::Error> = res.json().await;
-
-            match json {
-                Ok(result) => {
-                    // remove leading 0x which hex_decode doesn't like
-                    let metadata = hex_decode(&result.data[2..])?;
-                    return Ok(Some(metadata));
-                }
-                Err(_err) => {
-                    // try the next URL
-                }
-            }
+        let allowed = conf(allow(&[&format!("http://{addr}/")]));
+        let metadata = fetch(reverting_ism(vec![post_url.clone()]), &allowed).await;
+        assert_eq!(metadata.unwrap(), METADATA);
+
+        // Disallowed URLs are skipped, the allowed ones still tried
+        let urls = vec!["https://evil.example/post".to_owned(), post_url.clone()];
+        let metadata = fetch(reverting_ism(urls), &allowed).await;
+        assert_eq!(metadata.unwrap(), METADATA);
+
+        for prefix in [
+            format!("http://{addr}/get"),
+            format!("http://{addr}/pos"),
+            format!("https://{addr}/"),
+            "http://127.0.0.2/".to_owned(),
+        ] {
+            let metadata = fetch(
+                reverting_ism(vec![post_url.clone()]),
+                &conf(allow(&[&prefix])),
+            )
+            .await;
+            assert!(
+                matches!(metadata, Err(CcipReadError::NoAllowedUrls(_))),
+                "{prefix} shouldn't allow {post_url}"
+            );
         }
+        // Routes without URLs can't fetch at all
+        let metadata = fetch(reverting_ism(vec![post_url]), &conf(allow(&[]))).await;
+        assert!(matches!(metadata, Err(CcipReadError::NoAllowedUrls(_))));
+    }
 
-        // No metadata endpoints or endpoints down
-        Ok(None)
+    #[tokio::test]
+    async fn test_lookup_sender_must_be_the_ism() {
+        let addr = gateway();
+        let mut other = lookup(vec![format!("http://{addr}/post")]);
+        other.sender = H160::repeat_byte(0x22);
+        let revert_data = hex::encode(other.encode());
+        let mut ism = MockCcipReadIsm::new();
+        ism.expect_get_offchain_verify_info().returning(move |_| {
+            Err(ChainCommunicationError::from_other_str(&format!(
+                "Contract call reverted with data: 0x{revert_data}"
+            )))
+        });
+        let metadata = fetch(ism, &conf(None)).await;
+        assert!(matches!(
+            metadata,
+            Err(CcipReadError::SenderMismatch { .. })
+        ));
     }
 }
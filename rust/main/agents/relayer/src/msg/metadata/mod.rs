@@ -3,13 +3,19 @@ mod base;
 mod ccip_read;
 mod multisig;
 mod null_metadata;
+mod pool;
+mod prefetch;
 mod routing;
 
 use aggregation::AggregationIsmMetadataBuilder;
 pub(crate) use base::MetadataBuilder;
 pub(crate) use base::{
-    AppContextClassifier, BaseMetadataBuilder, IsmAwareAppContextClassifier, MessageMetadataBuilder,
+    AppContextClassifier, BaseMetadataBuilder, IsmAwareAppContextClassifier,
+    MessageMetadataBuilder, MetadataBuilderError,
 };
 use ccip_read::CcipReadIsmMetadataBuilder;
+pub(crate) use multisig::CheckpointCache;
 use null_metadata::NullMetadataBuilder;
+pub(crate) use pool::{MetadataBuildPool, MetadataBuildRoute};
+pub(crate) use prefetch::QuorumPrefetcher;
 use routing::RoutingIsmMetadataBuilder;
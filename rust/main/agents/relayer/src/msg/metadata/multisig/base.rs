@@ -1,4 +1,4 @@
-use std::fmt::Debug;
+use std::{fmt::Debug, sync::Arc};
 
 use async_trait::async_trait;
 use derive_more::{AsRef, Deref};
@@ -7,19 +7,23 @@ use ethers::abi::Token;
 
 use eyre::{Context, Result};
 use hyperlane_base::MultisigCheckpointSyncer;
+use hyperlane_core::abi::ToAbiToken;
 use hyperlane_core::accumulator::merkle::Proof;
-use hyperlane_core::{HyperlaneMessage, MultisigSignedCheckpoint, H256};
+use hyperlane_core::{HyperlaneMessage, H256};
 use strum::Display;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
 use crate::msg::metadata::base::MessageMetadataBuilder;
 
 use crate::msg::metadata::MetadataBuilder;
 
+use super::CheckpointSignatures;
+
 #[derive(new, AsRef, Deref)]
 pub struct MultisigMetadata {
+    /// Shared by the messages the checkpoint covers
     #[deref]
-    quorum_checkpoint: MultisigSignedCheckpoint,
+    signed_checkpoint: Arc<CheckpointSignatures>,
     merkle_leaf_index: u32,
     // optional because it's only used for MerkleRootMultisig
     proof: Option<Proof>,
@@ -40,6 +44,7 @@ pub enum MetadataToken {
 pub trait MultisigIsmMetadataBuilder: AsRef<MessageMetadataBuilder> + Send + Sync {
     async fn fetch_metadata(
         &self,
+        ism_address: H256,
         validators: &[H256],
         threshold: u8,
         message: &HyperlaneMessage,
@@ -49,46 +54,49 @@ pub trait MultisigIsmMetadataBuilder: AsRef<MessageMetadataBuilder> + Send + Syn
     fn token_layout(&self) -> Vec<MetadataToken>;
 
     fn format_metadata(&self, metadata: MultisigMetadata) -> Result<Vec<u8>> {
-        let build_token = |token: &MetadataToken| -> Result<Vec<u8>> {
-            match token {
-                MetadataToken::CheckpointMerkleRoot => {
-                    Ok(metadata.checkpoint.root.to_fixed_bytes().into())
-                }
-                MetadataToken::MessageMerkleLeafIndex => {
-                    Ok(metadata.merkle_leaf_index.to_be_bytes().into())
-                }
-                MetadataToken::CheckpointIndex => {
-                    Ok(metadata.checkpoint.index.to_be_bytes().into())
-                }
-                MetadataToken::CheckpointMerkleTreeHook => Ok(metadata
-                    .checkpoint
-                    .merkle_tree_hook_address
-                    .to_fixed_bytes()
-                    .into()),
-                MetadataToken::MessageId => {
-                    Ok(metadata.checkpoint.message_id.to_fixed_bytes().into())
-                }
-                MetadataToken::MerkleProof => {
-                    let proof_tokens: Vec<Token> = metadata
-                        .proof
-                        .unwrap()
-                        .path
-                        .iter()
-                        .map(|x| Token::FixedBytes(x.to_fixed_bytes().into()))
-                        .collect();
-                    Ok(ethers::abi::encode(&proof_tokens))
-                }
-                MetadataToken::Signatures => Ok(metadata
-                    .signatures
+        format_multisig_metadata(&self.token_layout(), &metadata)
+    }
+}
+
+/// Lay out the metadata of a message, only its own tokens being encoded
+/// here, the signatures being encoded once per checkpoint
+pub fn format_multisig_metadata(
+    layout: &[MetadataToken],
+    metadata: &MultisigMetadata,
+) -> Result<Vec<u8>> {
+    let build_token = |token: &MetadataToken| -> Result<Vec<u8>> {
+        match token {
+            MetadataToken::CheckpointMerkleRoot => {
+                Ok(metadata.checkpoint.root.to_fixed_bytes().into())
+            }
+            MetadataToken::MessageMerkleLeafIndex => {
+                Ok(metadata.merkle_leaf_index.to_be_bytes().into())
+            }
+            MetadataToken::CheckpointIndex => Ok(metadata.checkpoint.index.to_be_bytes().into()),
+            MetadataToken::CheckpointMerkleTreeHook => Ok(metadata
+                .checkpoint
+                .merkle_tree_hook_address
+                .to_fixed_bytes()
+                .into()),
+            MetadataToken::MessageId => Ok(metadata.checkpoint.message_id.to_fixed_bytes().into()),
+            MetadataToken::MerkleProof => {
+                let proof_tokens: Vec<Token> = metadata
+                    .proof
+                    .as_ref()
+                    .unwrap()
+                    .path
                     .iter()
-                    .map(|x| x.to_vec())
-                    .collect::<Vec<_>>()
-                    .concat()),
+                    .map(ToAbiToken::to_abi_token)
+                    .collect();
+                Ok(ethers::abi::encode(&proof_tokens))
             }
-        };
-        let metas: Result<Vec<Vec<u8>>> = self.token_layout().iter().map(build_token).collect();
-        Ok(metas?.into_iter().flatten().collect())
-    }
+            MetadataToken::Signatures => {
+                Ok(metadata.signed_checkpoint.encoded_signatures().to_vec())
+            }
+        }
+    };
+    let metas: Result<Vec<Vec<u8>>> = layout.iter().map(build_token).collect();
+    Ok(metas?.into_iter().flatten().collect())
 }
 
 #[async_trait]
@@ -117,16 +125,31 @@ impl<T: MultisigIsmMetadataBuilder> MetadataBuilder for T {
 
         let checkpoint_syncer = self
             .as_ref()
-            .build_checkpoint_syncer(&validators, self.as_ref().app_context.clone())
+            .build_checkpoint_syncer(
+                &validators,
+                self.as_ref().app_context.as_deref().map(str::to_owned),
+            )
             .await
             .context(CTX)?;
 
         if let Some(metadata) = self
-            .fetch_metadata(&validators, threshold, message, &checkpoint_syncer)
+            .fetch_metadata(
+                ism_address,
+                &validators,
+                threshold,
+                message,
+                &checkpoint_syncer,
+            )
             .await
             .context(CTX)?
         {
             debug!(hyp_message=?message, ?metadata.checkpoint, "Found checkpoint with quorum");
+            if let Err(err) = self.as_ref().store_quorum_checkpoint(
+                message.id(),
+                metadata.signed_checkpoint.quorum_checkpoint(),
+            ) {
+                warn!(hyp_message=?message, ?err, "Failed to store quorum checkpoint");
+            }
             Ok(Some(self.format_metadata(metadata)?))
         } else {
             info!(
@@ -0,0 +1,516 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    future::Future,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use derive_more::Deref;
+use eyre::Result;
+use hyperlane_base::{CheckpointPreference, CoreMetrics};
+use hyperlane_core::{HyperlaneDomain, MultisigSignedCheckpoint, H256};
+use prometheus::{IntCounterVec, IntGaugeVec};
+use tracing::debug;
+
+use crate::settings::CheckpointCacheConf;
+
+/// The checkpoint-signatures part of multisig metadata: a quorum checkpoint
+/// with its signatures encoded, shared by every message it covers
+#[derive(Debug, Deref)]
+pub struct CheckpointSignatures {
+    #[deref]
+    quorum_checkpoint: MultisigSignedCheckpoint,
+    encoded_signatures: Vec<u8>,
+}
+
+impl CheckpointSignatures {
+    pub fn new(quorum_checkpoint: MultisigSignedCheckpoint) -> Self {
+        let encoded_signatures = quorum_checkpoint
+            .signatures
+            .iter()
+            .flat_map(|signature| signature.to_vec())
+            .collect();
+        Self {
+            quorum_checkpoint,
+            encoded_signatures,
+        }
+    }
+
+    pub fn quorum_checkpoint(&self) -> &MultisigSignedCheckpoint {
+        &self.quorum_checkpoint
+    }
+
+    /// The signatures, concatenated as they're laid out in the metadata
+    pub fn encoded_signatures(&self) -> &[u8] {
+        &self.encoded_signatures
+    }
+}
+
+/// Messages whose multisig metadata can share checkpoints
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CheckpointRoute {
+    pub origin: HyperlaneDomain,
+    pub destination: HyperlaneDomain,
+    pub ism: H256,
+}
+
+#[derive(Debug)]
+struct CachedCheckpoint {
+    signatures: Arc<CheckpointSignatures>,
+    cached_at: Instant,
+}
+
+/// The checkpoints cached for a route, valid for the validator set they were
+/// fetched for
+#[derive(Debug)]
+struct RouteCheckpoints {
+    validators: Vec<H256>,
+    threshold: u8,
+    checkpoints: BTreeMap<u32, CachedCheckpoint>,
+    last_used: Instant,
+    /// Held while fetching a checkpoint for the route, so that a burst of
+    /// messages fetches it once
+    fetching: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl RouteCheckpoints {
+    fn new(validators: &[H256], threshold: u8, now: Instant) -> Self {
+        Self {
+            validators: validators.to_vec(),
+            threshold,
+            checkpoints: BTreeMap::new(),
+            last_used: now,
+            fetching: Default::default(),
+        }
+    }
+
+    fn is_for(&self, validators: &[H256], threshold: u8) -> bool {
+        self.validators == validators && self.threshold == threshold
+    }
+}
+
+/// The cached checkpoint covering a leaf, or else the lock to hold while
+/// fetching one for the route
+enum Lookup {
+    Hit(Arc<CheckpointSignatures>),
+    Miss(Arc<tokio::sync::Mutex<()>>),
+}
+
+#[derive(Debug, Clone)]
+struct CheckpointCacheMetrics {
+    lookups: IntCounterVec,
+    cached_checkpoints: IntGaugeVec,
+}
+
+/// Caches the quorum checkpoints of multisig metadata by route and
+/// checkpoint index, so that a burst of messages covered by the same
+/// checkpoint fetches and encodes its signatures once, only their proofs
+/// being built per message.
+///
+/// A route's checkpoints are invalidated once its validator set or
+/// threshold changes, and expire after `ttl` so that newer checkpoints are
+/// fetched. Routes are bounded by `max_routes`, the least recently used
+/// being evicted first, and their checkpoints by `max_checkpoints_per_route`.
+#[derive(Debug, Clone)]
+pub struct CheckpointCache {
+    conf: CheckpointCacheConf,
+    routes: Arc<Mutex<HashMap<CheckpointRoute, RouteCheckpoints>>>,
+    metrics: CheckpointCacheMetrics,
+}
+
+impl CheckpointCache {
+    pub fn new(conf: CheckpointCacheConf, metrics: &CoreMetrics) -> Result<Self> {
+        Ok(Self {
+            conf,
+            routes: Default::default(),
+            metrics: CheckpointCacheMetrics {
+                lookups: metrics.new_int_counter(
+                    "metadata_checkpoint_cache_lookups",
+                    "Number of quorum checkpoints looked up in the metadata checkpoint cache",
+                    &["origin", "destination", "result"],
+                )?,
+                cached_checkpoints: metrics.new_int_gauge(
+                    "metadata_checkpoint_cache_size",
+                    "Number of quorum checkpoints in the metadata checkpoint cache",
+                    &[],
+                )?,
+            },
+        })
+    }
+
+    /// The cached checkpoint of the route covering the leaf, as picked by
+    /// `preference`, or else the one `fetch` returns, which is cached
+    #[allow(clippy::too_many_arguments)]
+    pub async fn get_or_fetch<Fut>(
+        &self,
+        route: &CheckpointRoute,
+        validators: &[H256],
+        threshold: u8,
+        leaf_index: u32,
+        highest_leaf_index: u32,
+        preference: CheckpointPreference,
+        fetch: impl FnOnce() -> Fut,
+    ) -> Result<Option<Arc<CheckpointSignatures>>>
+    where
+        Fut: Future<Output = Result<Option<MultisigSignedCheckpoint>>>,
+    {
+        let lookup = |now| {
+            self.lookup(
+                route,
+                validators,
+                threshold,
+                leaf_index,
+                highest_leaf_index,
+                preference,
+                now,
+            )
+        };
+        let fetching = match lookup(Instant::now()) {
+            Lookup::Hit(cached) => return Ok(Some(self.record_hit(route, cached))),
+            Lookup::Miss(fetching) => fetching,
+        };
+        let _fetching = fetching.lock().await;
+        // Another message of the route may have fetched it in the meantime
+        if let Lookup::Hit(cached) = lookup(Instant::now()) {
+            return Ok(Some(self.record_hit(route, cached)));
+        }
+        self.record(route, "miss");
+        let Some(quorum_checkpoint) = fetch().await? else {
+            return Ok(None);
+        };
+        let signatures = Arc::new(CheckpointSignatures::new(quorum_checkpoint));
+        self.insert(
+            route,
+            validators,
+            threshold,
+            signatures.clone(),
+            Instant::now(),
+        );
+        Ok(Some(signatures))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn lookup(
+        &self,
+        route: &CheckpointRoute,
+        validators: &[H256],
+        threshold: u8,
+        leaf_index: u32,
+        highest_leaf_index: u32,
+        preference: CheckpointPreference,
+        now: Instant,
+    ) -> Lookup {
+        let mut routes = self.routes.lock().unwrap();
+        if routes
+            .get(route)
+            .is_some_and(|checkpoints| !checkpoints.is_for(validators, threshold))
+        {
+            debug!(
+                ?route,
+                "Validator set changed, invalidating cached checkpoints"
+            );
+            routes.remove(route);
+        }
+        if !routes.contains_key(route) {
+            self.evict_least_recently_used(&mut routes);
+        }
+        let checkpoints = routes
+            .entry(route.clone())
+            .or_insert_with(|| RouteCheckpoints::new(validators, threshold, now));
+        checkpoints.last_used = now;
+        checkpoints
+            .checkpoints
+            .retain(|_, cached| now.saturating_duration_since(cached.cached_at) < self.conf.ttl);
+        let cached = match preference {
+            CheckpointPreference::Latest if leaf_index > highest_leaf_index => None,
+            CheckpointPreference::Latest => checkpoints
+                .checkpoints
+                .range(leaf_index..=highest_leaf_index)
+                .next_back()
+                .map(|(_, cached)| cached),
+            CheckpointPreference::EarliestSatisfiable => checkpoints.checkpoints.get(&leaf_index),
+        };
+        let result = match cached {
+            Some(cached) => Lookup::Hit(cached.signatures.clone()),
+            None => Lookup::Miss(checkpoints.fetching.clone()),
+        };
+        self.update_size(&routes);
+        result
+    }
+
+    fn insert(
+        &self,
+        route: &CheckpointRoute,
+        validators: &[H256],
+        threshold: u8,
+        signatures: Arc<CheckpointSignatures>,
+        now: Instant,
+    ) {
+        let mut routes = self.routes.lock().unwrap();
+        let Some(checkpoints) = routes.get_mut(route) else {
+            // Evicted while fetching
+            return;
+        };
+        if !checkpoints.is_for(validators, threshold) {
+            return;
+        }
+        checkpoints.checkpoints.insert(
+            signatures.checkpoint.index,
+            CachedCheckpoint {
+                signatures,
+                cached_at: now,
+            },
+        );
+        while checkpoints.checkpoints.len() > self.conf.max_checkpoints_per_route {
+            checkpoints.checkpoints.pop_first();
+        }
+        self.update_size(&routes);
+    }
+
+    fn evict_least_recently_used(&self, routes: &mut HashMap<CheckpointRoute, RouteCheckpoints>) {
+        while routes.len() >= self.conf.max_routes.max(1) {
+            let Some(oldest) = routes
+                .iter()
+                .min_by_key(|(_, checkpoints)| checkpoints.last_used)
+                .map(|(route, _)| route.clone())
+            else {
+                return;
+            };
+            debug!(route = ?oldest, "Evicting least recently used checkpoint route");
+            routes.remove(&oldest);
+        }
+    }
+
+    fn record_hit(
+        &self,
+        route: &CheckpointRoute,
+        cached: Arc<CheckpointSignatures>,
+    ) -> Arc<CheckpointSignatures> {
+        self.record(route, "hit");
+        cached
+    }
+
+    fn record(&self, route: &CheckpointRoute, result: &str) {
+        self.metrics
+            .lookups
+            .with_label_values(&[route.origin.name(), route.destination.name(), result])
+            .inc();
+    }
+
+    fn update_size(&self, routes: &HashMap<CheckpointRoute, RouteCheckpoints>) {
+        let size: usize = routes
+            .values()
+            .map(|checkpoints| checkpoints.checkpoints.len())
+            .sum();
+        self.metrics
+            .cached_checkpoints
+            .with_label_values(&[])
+            .set(size as i64);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use hyperlane_core::{test_utils::dummy_domain, Checkpoint, CheckpointWithMessageId};
+    use prometheus::Registry;
+
+    use super::*;
+
+    fn conf() -> CheckpointCacheConf {
+        CheckpointCacheConf {
+            max_routes: 2,
+            max_checkpoints_per_route: 3,
+            ttl: Duration::from_secs(60),
+        }
+    }
+
+    fn cache() -> CheckpointCache {
+        let metrics = CoreMetrics::new("test", 0, Registry::new()).unwrap();
+        CheckpointCache::new(conf(), &metrics).unwrap()
+    }
+
+    fn route(ism: u64) -> CheckpointRoute {
+        CheckpointRoute {
+            origin: dummy_domain(0, "origin"),
+            destination: dummy_domain(1, "destination"),
+            ism: H256::from_low_u64_be(ism),
+        }
+    }
+
+    fn quorum_checkpoint(index: u32) -> MultisigSignedCheckpoint {
+        MultisigSignedCheckpoint {
+            checkpoint: CheckpointWithMessageId {
+                checkpoint: Checkpoint {
+                    merkle_tree_hook_address: H256::zero(),
+                    mailbox_domain: 0,
+                    root: H256::from_low_u64_be(index as u64),
+                    index,
+                },
+                message_id: H256::zero(),
+            },
+            signatures: vec![],
+        }
+    }
+
+    /// Look up the checkpoint covering the leaf, fetching the one at
+    /// `fetched` on a miss. The index of the checkpoint, and whether it was
+    /// fetched.
+    async fn get(
+        cache: &CheckpointCache,
+        route: &CheckpointRoute,
+        validators: &[H256],
+        leaf_index: u32,
+        preference: CheckpointPreference,
+        fetched: u32,
+    ) -> (u32, bool) {
+        let mut was_fetched = false;
+        let signatures = cache
+            .get_or_fetch(route, validators, 1, leaf_index, 100, preference, || {
+                was_fetched = true;
+                async move { Ok(Some(quorum_checkpoint(fetched))) }
+            })
+            .await
+            .unwrap()
+            .unwrap();
+        (signatures.checkpoint.index, was_fetched)
+    }
+
+    #[tokio::test]
+    async fn test_checkpoints_are_reused_for_the_leaves_they_cover() {
+        use CheckpointPreference::*;
+
+        let cache = cache();
+        let validators = [H256::repeat_byte(1)];
+        let route = route(1);
+
+        assert_eq!(
+            get(&cache, &route, &validators, 3, Latest, 10).await,
+            (10, true)
+        );
+        assert_eq!(
+            get(&cache, &route, &validators, 7, Latest, 11).await,
+            (10, false)
+        );
+        // Not covered by the cached checkpoint
+        assert_eq!(
+            get(&cache, &route, &validators, 11, Latest, 12).await,
+            (12, true)
+        );
+        assert_eq!(
+            get(&cache, &route, &validators, 5, Latest, 13).await,
+            (12, false)
+        );
+        // Only the leaf's own checkpoint satisfies the earliest preference
+        assert_eq!(
+            get(&cache, &route, &validators, 5, EarliestSatisfiable, 5).await,
+            (5, true)
+        );
+        assert_eq!(
+            get(&cache, &route, &validators, 5, EarliestSatisfiable, 6).await,
+            (5, false)
+        );
+
+        let lookups = |result| {
+            cache
+                .metrics
+                .lookups
+                .with_label_values(&["origin", "destination", result])
+                .get()
+        };
+        assert_eq!((lookups("hit"), lookups("miss")), (3, 3));
+
+        // The lowest index is evicted to keep the route within bounds
+        assert_eq!(
+            get(&cache, &route, &validators, 13, Latest, 14).await,
+            (14, true)
+        );
+        assert_eq!(
+            cache
+                .metrics
+                .cached_checkpoints
+                .with_label_values(&[])
+                .get(),
+            3
+        );
+        assert_eq!(
+            get(&cache, &route, &validators, 5, EarliestSatisfiable, 5).await,
+            (5, true)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_checkpoints_are_invalidated_with_their_validator_set() {
+        let cache = cache();
+        let route = route(1);
+        let validators = [H256::repeat_byte(1)];
+        let rotated = [H256::repeat_byte(2)];
+
+        get(
+            &cache,
+            &route,
+            &validators,
+            3,
+            CheckpointPreference::Latest,
+            10,
+        )
+        .await;
+        assert_eq!(
+            get(
+                &cache,
+                &route,
+                &rotated,
+                3,
+                CheckpointPreference::Latest,
+                11
+            )
+            .await,
+            (11, true)
+        );
+        assert_eq!(
+            get(
+                &cache,
+                &route,
+                &validators,
+                3,
+                CheckpointPreference::Latest,
+                12
+            )
+            .await,
+            (12, true)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_checkpoints_expire_and_routes_are_bounded() {
+        let cache = cache();
+        let validators = [H256::repeat_byte(1)];
+        let start = Instant::now();
+        let lookup = |route: &CheckpointRoute, now| {
+            let lookup = cache.lookup(
+                route,
+                &validators,
+                1,
+                3,
+                100,
+                CheckpointPreference::Latest,
+                now,
+            );
+            matches!(lookup, Lookup::Hit(_))
+        };
+
+        for ism in 1..=2 {
+            assert!(!lookup(&route(ism), start));
+            let signatures = Arc::new(CheckpointSignatures::new(quorum_checkpoint(10)));
+            cache.insert(&route(ism), &validators, 1, signatures, start);
+        }
+        assert!(lookup(&route(1), start + Duration::from_secs(1)));
+        // A third route evicts the least recently used one
+        assert!(!lookup(&route(3), start + Duration::from_secs(2)));
+        assert!(lookup(&route(1), start + Duration::from_secs(3)));
+        assert!(!lookup(&route(2), start + Duration::from_secs(4)));
+        // Expired
+        assert!(!lookup(&route(1), start + Duration::from_secs(60)));
+    }
+}
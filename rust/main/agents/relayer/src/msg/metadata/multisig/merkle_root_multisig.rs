@@ -1,41 +1,54 @@
-use std::fmt::Debug;
+use std::{fmt::Debug, sync::Arc, time::Instant};
 
 use async_trait::async_trait;
 use derive_more::{AsRef, Deref};
 use derive_new::new;
 
 use eyre::{Context, Result};
-use hyperlane_base::MultisigCheckpointSyncer;
+use hyperlane_base::{CheckpointPreference, MultisigCheckpointSyncer};
 use hyperlane_core::{unwrap_or_none_result, HyperlaneMessage, H256};
 use tracing::debug;
 
-use crate::msg::metadata::MessageMetadataBuilder;
+use crate::msg::metadata::{
+    prefetch::anticipated_checkpoint, MessageMetadataBuilder, MetadataBuilderError,
+};
 
-use super::base::{MetadataToken, MultisigIsmMetadataBuilder, MultisigMetadata};
+use super::{
+    base::{MetadataToken, MultisigIsmMetadataBuilder, MultisigMetadata},
+    CheckpointCache, CheckpointRoute, CheckpointSignatures,
+};
+
+const TOKEN_LAYOUT: &[MetadataToken] = &[
+    MetadataToken::CheckpointMerkleTreeHook,
+    MetadataToken::MessageMerkleLeafIndex,
+    MetadataToken::MessageId,
+    MetadataToken::MerkleProof,
+    MetadataToken::CheckpointIndex,
+    MetadataToken::Signatures,
+];
 
 #[derive(Debug, Clone, Deref, new, AsRef)]
 pub struct MerkleRootMultisigMetadataBuilder(MessageMetadataBuilder);
 #[async_trait]
 impl MultisigIsmMetadataBuilder for MerkleRootMultisigMetadataBuilder {
     fn token_layout(&self) -> Vec<MetadataToken> {
-        vec![
-            MetadataToken::CheckpointMerkleTreeHook,
-            MetadataToken::MessageMerkleLeafIndex,
-            MetadataToken::MessageId,
-            MetadataToken::MerkleProof,
-            MetadataToken::CheckpointIndex,
-            MetadataToken::Signatures,
-        ]
+        TOKEN_LAYOUT.to_vec()
     }
 
     async fn fetch_metadata(
         &self,
+        ism_address: H256,
         validators: &[H256],
         threshold: u8,
         message: &HyperlaneMessage,
         checkpoint_syncer: &MultisigCheckpointSyncer,
     ) -> Result<Option<MultisigMetadata>> {
         const CTX: &str = "When fetching MerkleRootMultisig metadata";
+        // Proofs can only be generated from a tree that's caught up
+        let availability = self.tree_availability().await;
+        if !availability.is_ready() {
+            return Err(MetadataBuilderError::TreeUnavailable(availability).into());
+        }
         let highest_leaf_index = unwrap_or_none_result!(
             self.highest_known_leaf_index().await,
             debug!("Couldn't get highest known leaf index")
@@ -49,27 +62,58 @@ impl MultisigIsmMetadataBuilder for MerkleRootMultisigMetadataBuilder {
                 "No merkle leaf found for message id, must have not been enqueued in the tree"
             )
         );
-        let quorum_checkpoint = unwrap_or_none_result!(
-            checkpoint_syncer
-                .fetch_checkpoint_in_range(
-                    validators,
-                    threshold as usize,
-                    leaf_index,
-                    highest_leaf_index,
-                    self.origin_domain(),
-                    self.destination_domain(),
-                )
-                .await
-                .context(CTX)?,
+        let route = CheckpointRoute {
+            origin: self.origin_domain().clone(),
+            destination: self.destination_domain().clone(),
+            ism: ism_address,
+        };
+        let Some(quorum_checkpoint) = fetch_quorum_checkpoint(
+            self.checkpoint_cache(),
+            &route,
+            checkpoint_syncer,
+            validators,
+            threshold,
+            leaf_index,
+            highest_leaf_index,
+            self.checkpoint_preference(message),
+        )
+        .await
+        .context(CTX)?
+        else {
             debug!(
                 leaf_index,
                 highest_leaf_index, "Couldn't get checkpoint in range"
+            );
+            self.prepare_ahead_of_quorum(
+                validators,
+                threshold,
+                message,
+                leaf_index,
+                highest_leaf_index,
+                checkpoint_syncer,
             )
-        );
-        let proof = self
-            .get_proof(leaf_index, quorum_checkpoint.checkpoint.checkpoint)
             .await
             .context(CTX)?;
+            return Ok(None);
+        };
+        let checkpoint = quorum_checkpoint.checkpoint.checkpoint;
+        let prepared = self
+            .quorum_prefetcher()
+            .and_then(|prefetcher| {
+                prefetcher.take(message.id(), leaf_index, checkpoint.index, Instant::now())
+            })
+            .filter(|proof| proof.root() == checkpoint.root);
+        let proof = match prepared {
+            Some(proof) => {
+                debug!(
+                    leaf_index,
+                    checkpoint_index = checkpoint.index,
+                    "Using proof prepared ahead of quorum"
+                );
+                proof
+            }
+            None => self.get_proof(leaf_index, checkpoint).await.context(CTX)?,
+        };
         Ok(Some(MultisigMetadata::new(
             quorum_checkpoint,
             leaf_index,
@@ -77,3 +121,457 @@ impl MultisigIsmMetadataBuilder for MerkleRootMultisigMetadataBuilder {
         )))
     }
 }
+
+/// The quorum checkpoint covering the leaf, reused from the cache if another
+/// message of the route already fetched one
+#[allow(clippy::too_many_arguments)]
+async fn fetch_quorum_checkpoint(
+    cache: Option<&CheckpointCache>,
+    route: &CheckpointRoute,
+    checkpoint_syncer: &MultisigCheckpointSyncer,
+    validators: &[H256],
+    threshold: u8,
+    leaf_index: u32,
+    highest_leaf_index: u32,
+    preference: CheckpointPreference,
+) -> Result<Option<Arc<CheckpointSignatures>>> {
+    let fetch = || {
+        checkpoint_syncer.fetch_checkpoint_in_range(
+            validators,
+            threshold as usize,
+            leaf_index,
+            highest_leaf_index,
+            preference,
+            &route.origin,
+            &route.destination,
+        )
+    };
+    match cache {
+        Some(cache) => {
+            cache
+                .get_or_fetch(
+                    route,
+                    validators,
+                    threshold,
+                    leaf_index,
+                    highest_leaf_index,
+                    preference,
+                    fetch,
+                )
+                .await
+        }
+        None => Ok(fetch()
+            .await?
+            .map(|quorum_checkpoint| Arc::new(CheckpointSignatures::new(quorum_checkpoint)))),
+    }
+}
+
+impl MerkleRootMultisigMetadataBuilder {
+    /// If a majority but not all of the threshold signed a checkpoint
+    /// covering the message, prepare its proof against the checkpoint the
+    /// quorum is anticipated at
+    async fn prepare_ahead_of_quorum(
+        &self,
+        validators: &[H256],
+        threshold: u8,
+        message: &HyperlaneMessage,
+        leaf_index: u32,
+        highest_leaf_index: u32,
+        checkpoint_syncer: &MultisigCheckpointSyncer,
+    ) -> Result<()> {
+        let Some(prefetcher) = self.quorum_prefetcher() else {
+            return Ok(());
+        };
+        // Only reached when there's no quorum yet, so this second lookup of
+        // the latest indices doesn't slow down delivery
+        let latest_indices = checkpoint_syncer
+            .get_validator_latest_checkpoints_and_update_metrics(
+                validators,
+                self.origin_domain(),
+                self.destination_domain(),
+            )
+            .await;
+        let Some(checkpoint_index) = anticipated_checkpoint(
+            &latest_indices,
+            threshold as usize,
+            leaf_index,
+            highest_leaf_index,
+            self.checkpoint_preference(message),
+        ) else {
+            return Ok(());
+        };
+        if prefetcher.is_prepared(message.id(), checkpoint_index, Instant::now()) {
+            return Ok(());
+        }
+        let proof = self.get_proof_at(leaf_index, checkpoint_index).await?;
+        prefetcher.prepare(
+            message.id(),
+            leaf_index,
+            checkpoint_index,
+            proof,
+            Instant::now(),
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        collections::{HashMap, HashSet},
+        path::Path,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Instant,
+    };
+
+    use async_trait::async_trait;
+    use eyre::Result;
+    use hyperlane_base::{
+        AgentMetadata, CheckpointPreference, CheckpointSyncer, CoreMetrics, LocalStorage,
+        MultisigCheckpointSyncer,
+    };
+    use hyperlane_core::{
+        test_utils::dummy_domain, Checkpoint, CheckpointWithMessageId, HyperlaneSigner,
+        HyperlaneSignerExt, ReorgEvent, SignedAnnouncement, SignedCheckpointWithMessageId, H160,
+        H256,
+    };
+    use hyperlane_ethereum::{test_keys::test_signers, Signers};
+    use prometheus::Registry;
+
+    use super::{fetch_quorum_checkpoint, TOKEN_LAYOUT};
+    use crate::{
+        merkle_tree::builder::MerkleTreeBuilder,
+        msg::metadata::{
+            multisig::{
+                base::format_multisig_metadata, CheckpointCache, CheckpointRoute, MultisigMetadata,
+            },
+            prefetch::anticipated_checkpoint,
+            QuorumPrefetcher,
+        },
+        settings::{CheckpointCacheConf, QuorumPrefetchConf},
+    };
+
+    const LEAVES: u32 = 12;
+    const THRESHOLD: usize = 2;
+
+    fn leaf(index: u32) -> H256 {
+        H256::from_low_u64_be(index as u64 + 1)
+    }
+
+    /// An origin tree of `leaves`, and its checkpoint after each insertion
+    async fn checkpoints(leaves: u32) -> (MerkleTreeBuilder, Vec<CheckpointWithMessageId>) {
+        let origin = dummy_domain(0, "origin");
+        let mut tree = MerkleTreeBuilder::new();
+        let mut checkpoints = vec![];
+        for index in 0..leaves {
+            tree.ingest_message_id(leaf(index)).await.unwrap();
+            checkpoints.push(CheckpointWithMessageId {
+                checkpoint: Checkpoint {
+                    merkle_tree_hook_address: H256::repeat_byte(1),
+                    mailbox_domain: origin.id(),
+                    root: tree.snapshot().root(),
+                    index,
+                },
+                message_id: leaf(index),
+            });
+        }
+        (tree, checkpoints)
+    }
+
+    /// Have a validator sign the checkpoints up to `latest_index`
+    async fn sign_up_to(
+        storage: &LocalStorage,
+        signer: &Signers,
+        checkpoints: &[CheckpointWithMessageId],
+        latest_index: u32,
+    ) {
+        for checkpoint in &checkpoints[..=latest_index as usize] {
+            let signed = signer.sign(*checkpoint).await.unwrap();
+            storage.write_checkpoint(&signed).await.unwrap();
+        }
+        storage.write_latest_index(latest_index).await.unwrap();
+    }
+
+    /// An origin tree, and validators which signed its checkpoints up to
+    /// staggered latest indices
+    async fn setup(dir: &Path) -> (MerkleTreeBuilder, Vec<H256>, MultisigCheckpointSyncer) {
+        let (tree, checkpoints) = checkpoints(LEAVES).await;
+        let mut validators = vec![];
+        let mut checkpoint_syncers: HashMap<H160, Arc<dyn CheckpointSyncer>> = HashMap::new();
+        for (key, latest_index) in [(1u32, 5u32), (2, 8), (3, 10)] {
+            let signer = test_signers(key);
+            let storage = LocalStorage::new(dir.join(key.to_string()), None).unwrap();
+            sign_up_to(&storage, &signer, &checkpoints, latest_index).await;
+            validators.push(H256::from(signer.eth_address()));
+            checkpoint_syncers.insert(signer.eth_address(), Arc::new(storage));
+        }
+        let metrics = Arc::new(CoreMetrics::new("test", 0, Registry::new()).unwrap());
+        let syncer = MultisigCheckpointSyncer::new(checkpoint_syncers, metrics, None);
+        (tree, validators, syncer)
+    }
+
+    #[tokio::test]
+    async fn test_proof_prepared_ahead_of_staggered_quorum() {
+        let dir = tempfile::tempdir().unwrap();
+        let (tree, validators, syncer) = setup(dir.path()).await;
+        let (_, checkpoints) = checkpoints(LEAVES).await;
+        let origin = dummy_domain(0, "origin");
+        let destination = dummy_domain(1, "destination");
+        let conf = QuorumPrefetchConf::default();
+        let prefetcher = QuorumPrefetcher::new(conf.clone());
+        let (threshold, leaf_index) = (3, 6);
+        let message_id = leaf(leaf_index);
+        let highest_leaf_index = tree.count() - 1;
+
+        // 2 of the 3 signatures covering the leaf have arrived
+        let quorum = syncer
+            .fetch_checkpoint_in_range(
+                &validators,
+                threshold,
+                leaf_index,
+                highest_leaf_index,
+                CheckpointPreference::Latest,
+                &origin,
+                &destination,
+            )
+            .await
+            .unwrap();
+        assert!(quorum.is_none());
+        let latest_indices = syncer
+            .get_validator_latest_checkpoints_and_update_metrics(&validators, &origin, &destination)
+            .await;
+        let anticipated = anticipated_checkpoint(
+            &latest_indices,
+            threshold,
+            leaf_index,
+            highest_leaf_index,
+            CheckpointPreference::Latest,
+        )
+        .unwrap();
+        assert_eq!(anticipated, 8);
+        let proof = tree.get_proof(leaf_index, anticipated).unwrap();
+        prefetcher.prepare(message_id, leaf_index, anticipated, proof, Instant::now());
+
+        // The message is retried at the next tick while the last signature
+        // is awaited
+        assert_eq!(
+            prefetcher.retry_delay(message_id, Instant::now()),
+            Some(conf.poll_interval)
+        );
+
+        // The last signature arrives
+        let storage = LocalStorage::new(dir.path().join("1"), None).unwrap();
+        sign_up_to(&storage, &test_signers(1), &checkpoints, 8).await;
+        let quorum = syncer
+            .fetch_checkpoint_in_range(
+                &validators,
+                threshold,
+                leaf_index,
+                highest_leaf_index,
+                CheckpointPreference::Latest,
+                &origin,
+                &destination,
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(quorum.checkpoint.index, anticipated);
+
+        // The prepared proof is used as is
+        let prepared = prefetcher
+            .take(
+                message_id,
+                leaf_index,
+                quorum.checkpoint.index,
+                Instant::now(),
+            )
+            .unwrap();
+        assert_eq!(prepared.leaf, message_id);
+        assert_eq!(prepared.root(), quorum.checkpoint.root);
+        assert_eq!(prefetcher.retry_delay(message_id, Instant::now()), None);
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_preference_picks_quorum_checkpoint_and_proof_verifies() {
+        let dir = tempfile::tempdir().unwrap();
+        let (tree, validators, syncer) = setup(dir.path()).await;
+        let origin = dummy_domain(0, "origin");
+        let destination = dummy_domain(1, "destination");
+        let leaf_index = 3;
+
+        // The second highest latest index is the highest one with a quorum
+        for (preference, expected_index) in [
+            (CheckpointPreference::Latest, 8),
+            (CheckpointPreference::EarliestSatisfiable, leaf_index),
+        ] {
+            let quorum_checkpoint = syncer
+                .fetch_checkpoint_in_range(
+                    &validators,
+                    THRESHOLD,
+                    leaf_index,
+                    tree.count() - 1,
+                    preference,
+                    &origin,
+                    &destination,
+                )
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(quorum_checkpoint.checkpoint.index, expected_index);
+            assert_eq!(quorum_checkpoint.signatures.len(), THRESHOLD);
+
+            let proof = tree
+                .get_proof(leaf_index, quorum_checkpoint.checkpoint.index)
+                .unwrap();
+            assert_eq!(proof.leaf, leaf(leaf_index));
+            assert_eq!(proof.root(), quorum_checkpoint.checkpoint.root);
+        }
+
+        // Leaves above the highest quorum index can't be delivered either way
+        for preference in [
+            CheckpointPreference::Latest,
+            CheckpointPreference::EarliestSatisfiable,
+        ] {
+            let quorum_checkpoint = syncer
+                .fetch_checkpoint_in_range(
+                    &validators,
+                    THRESHOLD,
+                    9,
+                    tree.count() - 1,
+                    preference,
+                    &origin,
+                    &destination,
+                )
+                .await
+                .unwrap();
+            assert!(quorum_checkpoint.is_none());
+        }
+    }
+
+    /// Serves the checkpoints of a validator, counting the signed ones
+    /// fetched
+    #[derive(Debug)]
+    struct CountingStorage {
+        storage: LocalStorage,
+        fetches: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl CheckpointSyncer for CountingStorage {
+        async fn latest_index(&self) -> Result<Option<u32>> {
+            self.storage.latest_index().await
+        }
+
+        async fn write_latest_index(&self, index: u32) -> Result<()> {
+            self.storage.write_latest_index(index).await
+        }
+
+        async fn fetch_checkpoint(
+            &self,
+            index: u32,
+        ) -> Result<Option<SignedCheckpointWithMessageId>> {
+            self.fetches.fetch_add(1, Ordering::SeqCst);
+            self.storage.fetch_checkpoint(index).await
+        }
+
+        async fn write_checkpoint(
+            &self,
+            signed_checkpoint: &SignedCheckpointWithMessageId,
+        ) -> Result<()> {
+            self.storage.write_checkpoint(signed_checkpoint).await
+        }
+
+        async fn write_metadata(&self, metadata: &AgentMetadata) -> Result<()> {
+            self.storage.write_metadata(metadata).await
+        }
+
+        async fn write_announcement(&self, signed_announcement: &SignedAnnouncement) -> Result<()> {
+            self.storage.write_announcement(signed_announcement).await
+        }
+
+        fn announcement_location(&self) -> String {
+            self.storage.announcement_location()
+        }
+
+        async fn write_reorg_status(&self, reorg_event: &ReorgEvent) -> Result<()> {
+            self.storage.write_reorg_status(reorg_event).await
+        }
+
+        async fn reorg_status(&self) -> Result<Option<ReorgEvent>> {
+            self.storage.reorg_status().await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_burst_under_one_checkpoint_fetches_its_signatures_once() {
+        const MESSAGES: u32 = 50;
+        let dir = tempfile::tempdir().unwrap();
+        let (tree, checkpoints) = checkpoints(MESSAGES).await;
+        let fetches = Arc::new(AtomicUsize::new(0));
+        let mut validators = vec![];
+        let mut checkpoint_syncers: HashMap<H160, Arc<dyn CheckpointSyncer>> = HashMap::new();
+        for key in 1..=3 {
+            let signer = test_signers(key);
+            let storage = LocalStorage::new(dir.path().join(key.to_string()), None).unwrap();
+            sign_up_to(&storage, &signer, &checkpoints, MESSAGES - 1).await;
+            validators.push(H256::from(signer.eth_address()));
+            let storage = CountingStorage {
+                storage,
+                fetches: fetches.clone(),
+            };
+            checkpoint_syncers.insert(signer.eth_address(), Arc::new(storage));
+        }
+        let metrics = Arc::new(CoreMetrics::new("test", 0, Registry::new()).unwrap());
+        let syncer = MultisigCheckpointSyncer::new(checkpoint_syncers, metrics.clone(), None);
+        let cache = CheckpointCache::new(CheckpointCacheConf::default(), &metrics).unwrap();
+        let route = CheckpointRoute {
+            origin: dummy_domain(0, "origin"),
+            destination: dummy_domain(1, "destination"),
+            ism: H256::repeat_byte(2),
+        };
+
+        let mut shared = None;
+        let mut metadata = HashSet::new();
+        for leaf_index in 0..MESSAGES {
+            let signatures = fetch_quorum_checkpoint(
+                Some(&cache),
+                &route,
+                &syncer,
+                &validators,
+                THRESHOLD as u8,
+                leaf_index,
+                MESSAGES - 1,
+                CheckpointPreference::Latest,
+            )
+            .await
+            .unwrap()
+            .unwrap();
+            assert_eq!(signatures.checkpoint.index, MESSAGES - 1);
+            assert_eq!(signatures.signatures.len(), THRESHOLD);
+            // The signatures are fetched and encoded for the first message only
+            let (first, fetched) =
+                shared.get_or_insert_with(|| (signatures.clone(), fetches.load(Ordering::SeqCst)));
+            assert!(Arc::ptr_eq(&signatures, first));
+            assert_eq!(fetches.load(Ordering::SeqCst), *fetched);
+
+            // Each message still gets its own proof
+            let proof = tree
+                .get_proof(leaf_index, signatures.checkpoint.index)
+                .unwrap();
+            assert_eq!(proof.leaf, leaf(leaf_index));
+            assert_eq!(proof.root(), signatures.checkpoint.root);
+            let encoded_signatures = signatures.encoded_signatures().to_vec();
+            let message_metadata = format_multisig_metadata(
+                TOKEN_LAYOUT,
+                &MultisigMetadata::new(signatures, leaf_index, Some(proof)),
+            )
+            .unwrap();
+            assert!(message_metadata.ends_with(&encoded_signatures));
+            assert!(metadata.insert(message_metadata));
+        }
+        assert!(shared.unwrap().1 > 0);
+    }
+}
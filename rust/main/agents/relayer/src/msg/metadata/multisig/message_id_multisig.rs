@@ -1,4 +1,4 @@
-use std::fmt::Debug;
+use std::{fmt::Debug, sync::Arc};
 
 use async_trait::async_trait;
 use derive_more::{AsRef, Deref};
@@ -11,7 +11,10 @@ use tracing::{debug, warn};
 
 use crate::msg::metadata::MessageMetadataBuilder;
 
-use super::base::{MetadataToken, MultisigIsmMetadataBuilder, MultisigMetadata};
+use super::{
+    base::{MetadataToken, MultisigIsmMetadataBuilder, MultisigMetadata},
+    CheckpointSignatures,
+};
 
 #[derive(Debug, Clone, Deref, new, AsRef)]
 pub struct MessageIdMultisigMetadataBuilder(MessageMetadataBuilder);
@@ -29,6 +32,7 @@ impl MultisigIsmMetadataBuilder for MessageIdMultisigMetadataBuilder {
 
     async fn fetch_metadata(
         &self,
+        _ism_address: H256,
         validators: &[H256],
         threshold: u8,
         message: &HyperlaneMessage,
@@ -78,8 +82,10 @@ impl MultisigIsmMetadataBuilder for MessageIdMultisigMetadataBuilder {
             return Ok(None);
         }
 
+        // Message id checkpoints only cover their own message, so they aren't
+        // worth caching
         Ok(Some(MultisigMetadata::new(
-            quorum_checkpoint,
+            Arc::new(CheckpointSignatures::new(quorum_checkpoint)),
             leaf_index,
             None,
         )))
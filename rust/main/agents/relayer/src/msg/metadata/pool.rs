@@ -0,0 +1,438 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    future::Future,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use eyre::Result;
+use hyperlane_base::CoreMetrics;
+use hyperlane_core::HyperlaneDomain;
+use prometheus::{Histogram, HistogramVec, IntGauge, IntGaugeVec};
+use tokio::sync::{oneshot, watch};
+use tracing::{debug, info};
+
+use crate::settings::MetadataPoolConf;
+
+/// Route metadata is built for, as the domain ids of its origin and
+/// destination
+type RouteKey = (u32, u32);
+
+#[derive(Debug, thiserror::Error)]
+pub enum MetadataPoolError {
+    #[error("Metadata building was cancelled as the relayer is shutting down")]
+    ShutDown,
+}
+
+/// Bounds the metadata builds in flight, i.e. checkpoint fetches and ISM
+/// calls, across all routes.
+///
+/// A route may only have `max_in_flight_per_route` builds in flight, and
+/// routes waiting for a slot are served in turn as slots free up. A route
+/// whose validators are slow to serve checkpoints thus holds a bounded share
+/// of the pool, rather than starving the routes sharing its origin.
+#[derive(Debug, Clone)]
+pub struct MetadataBuildPool {
+    inner: Arc<PoolInner>,
+}
+
+#[derive(Debug)]
+struct PoolInner {
+    conf: MetadataPoolConf,
+    state: Mutex<PoolState>,
+    /// Set once the pool is shut down
+    shut_down: watch::Sender<bool>,
+    in_flight: IntGaugeVec,
+    queue_wait: HistogramVec,
+}
+
+#[derive(Debug, Default)]
+struct PoolState {
+    in_flight: usize,
+    routes: HashMap<RouteKey, RouteState>,
+    /// Routes waiting for a slot, in the order they're served
+    rotation: VecDeque<RouteKey>,
+}
+
+#[derive(Debug)]
+struct RouteState {
+    in_flight: usize,
+    waiters: VecDeque<oneshot::Sender<Slot>>,
+    in_flight_gauge: IntGauge,
+}
+
+/// A slot of the pool, freed once dropped
+#[derive(Debug)]
+struct Slot {
+    pool: Arc<PoolInner>,
+    route: RouteKey,
+    /// Whether the slot is counted as in flight, i.e. it wasn't refused by a
+    /// cancelled build
+    taken: bool,
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        if self.taken {
+            self.pool.free(self.route);
+        }
+    }
+}
+
+impl MetadataBuildPool {
+    pub fn new(conf: MetadataPoolConf, metrics: &CoreMetrics) -> Result<Self> {
+        let in_flight = metrics.new_int_gauge(
+            "metadata_builds_in_flight",
+            "Number of metadata builds in flight for a route",
+            &["origin", "destination"],
+        )?;
+        let queue_wait = metrics.new_histogram(
+            "metadata_build_queue_wait_seconds",
+            "Time metadata builds of a route waited for a slot of the pool",
+            &["origin", "destination"],
+            vec![0.001, 0.01, 0.1, 1.0, 10.0, 60.0],
+        )?;
+        Ok(Self {
+            inner: Arc::new(PoolInner {
+                conf,
+                state: Default::default(),
+                shut_down: watch::channel(false).0,
+                in_flight,
+                queue_wait,
+            }),
+        })
+    }
+
+    /// Handle to build the metadata of messages from `origin` to
+    /// `destination` through the pool
+    pub fn route(
+        &self,
+        origin: &HyperlaneDomain,
+        destination: &HyperlaneDomain,
+    ) -> MetadataBuildRoute {
+        let labels = [origin.name(), destination.name()];
+        let key = (origin.id(), destination.id());
+        self.inner
+            .state
+            .lock()
+            .unwrap()
+            .routes
+            .entry(key)
+            .or_insert_with(|| RouteState {
+                in_flight: 0,
+                waiters: VecDeque::new(),
+                in_flight_gauge: self.inner.in_flight.with_label_values(&labels),
+            });
+        MetadataBuildRoute {
+            pool: self.inner.clone(),
+            route: key,
+            queue_wait: self.inner.queue_wait.with_label_values(&labels),
+        }
+    }
+
+    /// Cancel the builds in flight and those waiting for a slot, and refuse
+    /// any further ones
+    pub fn shut_down(&self) {
+        info!("Shutting down metadata build pool");
+        self.inner.shut_down.send_replace(true);
+        let mut state = self.inner.state.lock().unwrap();
+        state.rotation.clear();
+        for route in state.routes.values_mut() {
+            route.waiters.clear();
+        }
+    }
+}
+
+impl PoolInner {
+    fn has_capacity(&self, state: &PoolState, route: &RouteState) -> bool {
+        state.in_flight < self.conf.max_concurrency
+            && route.in_flight < self.conf.max_in_flight_per_route
+    }
+
+    /// Take a slot for `route` right away if it has capacity and none of its
+    /// builds are already waiting, otherwise wait in line for one
+    fn take(self: &Arc<Self>, route: RouteKey) -> Result<Slot, oneshot::Receiver<Slot>> {
+        let mut state = self.state.lock().unwrap();
+        let route_state = &state.routes[&route];
+        if route_state.waiters.is_empty() && self.has_capacity(&state, route_state) {
+            take_slot(&mut state, route);
+            return Ok(Slot {
+                pool: self.clone(),
+                route,
+                taken: true,
+            });
+        }
+        let (sender, receiver) = oneshot::channel();
+        state
+            .routes
+            .get_mut(&route)
+            .unwrap()
+            .waiters
+            .push_back(sender);
+        if !state.rotation.contains(&route) {
+            state.rotation.push_back(route);
+        }
+        // Builds waiting ahead may have been cancelled since
+        self.serve_waiters(&mut state);
+        Err(receiver)
+    }
+
+    /// Free a slot of `route` and hand the freed capacity to waiting routes
+    fn free(self: &Arc<Self>, route: RouteKey) {
+        let mut state = self.state.lock().unwrap();
+        state.in_flight -= 1;
+        let route_state = state.routes.get_mut(&route).unwrap();
+        route_state.in_flight -= 1;
+        route_state.in_flight_gauge.dec();
+        self.serve_waiters(&mut state);
+    }
+
+    /// Hand free slots to the routes waiting for one, in turn, skipping
+    /// routes at their own limit
+    fn serve_waiters(self: &Arc<Self>, state: &mut PoolState) {
+        let mut skipped = 0;
+        while state.in_flight < self.conf.max_concurrency && skipped < state.rotation.len() {
+            let Some(route) = state.rotation.pop_front() else {
+                break;
+            };
+            let route_state = &state.routes[&route];
+            if !self.has_capacity(state, route_state) {
+                state.rotation.push_back(route);
+                skipped += 1;
+                continue;
+            }
+            let route_state = state.routes.get_mut(&route).unwrap();
+            let mut served = false;
+            while let Some(waiter) = route_state.waiters.pop_front() {
+                let slot = Slot {
+                    pool: self.clone(),
+                    route,
+                    taken: true,
+                };
+                // Builds cancelled while waiting don't take a slot. The slot
+                // is only freed by the build, once this lock is released.
+                if let Err(mut slot) = waiter.send(slot) {
+                    slot.taken = false;
+                    continue;
+                }
+                served = true;
+                break;
+            }
+            let waiting = !route_state.waiters.is_empty();
+            if served {
+                take_slot(state, route);
+                skipped = 0;
+            }
+            if waiting {
+                state.rotation.push_back(route);
+            }
+        }
+    }
+}
+
+/// Count a slot of `route` as taken
+fn take_slot(state: &mut PoolState, route: RouteKey) {
+    state.in_flight += 1;
+    let route_state = state.routes.get_mut(&route).unwrap();
+    route_state.in_flight += 1;
+    route_state.in_flight_gauge.inc();
+}
+
+/// Builds the metadata of a route's messages through the pool
+#[derive(Debug, Clone)]
+pub struct MetadataBuildRoute {
+    pool: Arc<PoolInner>,
+    route: RouteKey,
+    queue_wait: Histogram,
+}
+
+impl MetadataBuildRoute {
+    /// Run `build` once the route gets a slot of the pool, holding it until
+    /// the build is done. Fails if the pool is shut down before the build is.
+    pub async fn run<F: Future>(&self, build: F) -> Result<F::Output, MetadataPoolError> {
+        let mut shut_down = self.pool.shut_down.subscribe();
+        if *shut_down.borrow() {
+            return Err(MetadataPoolError::ShutDown);
+        }
+        let started = Instant::now();
+        let build = async {
+            let _slot = match self.pool.take(self.route) {
+                Ok(slot) => slot,
+                // The pool drops the builds waiting when it shuts down
+                Err(waiting) => waiting.await.map_err(|_| MetadataPoolError::ShutDown)?,
+            };
+            self.queue_wait.observe(started.elapsed().as_secs_f64());
+            Ok(build.await)
+        };
+        tokio::select! {
+            output = build => output,
+            _ = shut_down.changed() => {
+                debug!(route = ?self.route, "Metadata build cancelled by shutdown");
+                Err(MetadataPoolError::ShutDown)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    use futures::future::join_all;
+    use hyperlane_core::KnownHyperlaneDomain;
+    use prometheus::Registry;
+    use tokio::time::sleep;
+
+    use super::*;
+
+    fn domain(known: KnownHyperlaneDomain) -> HyperlaneDomain {
+        HyperlaneDomain::Known(known)
+    }
+
+    fn pool(max_concurrency: usize, max_in_flight_per_route: usize) -> MetadataBuildPool {
+        let metrics = CoreMetrics::new("test", 0, Registry::new()).unwrap();
+        MetadataBuildPool::new(
+            MetadataPoolConf {
+                max_concurrency,
+                max_in_flight_per_route,
+            },
+            &metrics,
+        )
+        .unwrap()
+    }
+
+    fn in_flight(pool: &MetadataBuildPool) -> usize {
+        pool.inner.state.lock().unwrap().in_flight
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn never_exceeds_max_concurrency() {
+        let pool = pool(3, 2);
+        let routes = [
+            pool.route(
+                &domain(KnownHyperlaneDomain::Test1),
+                &domain(KnownHyperlaneDomain::Test2),
+            ),
+            pool.route(
+                &domain(KnownHyperlaneDomain::Test2),
+                &domain(KnownHyperlaneDomain::Test3),
+            ),
+        ];
+        let (running, max_running) = (&AtomicUsize::new(0), &AtomicUsize::new(0));
+        let builds = (0..10).map(|i| {
+            routes[i % 2].run(async move {
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                max_running.fetch_max(now, Ordering::SeqCst);
+                sleep(Duration::from_secs(1)).await;
+                running.fetch_sub(1, Ordering::SeqCst);
+            })
+        });
+
+        let results = join_all(builds).await;
+
+        assert!(results.iter().all(Result::is_ok));
+        assert_eq!(max_running.load(Ordering::SeqCst), 3);
+        assert_eq!(in_flight(&pool), 0);
+        assert_eq!(
+            pool.inner
+                .in_flight
+                .with_label_values(&["test1", "test2"])
+                .get(),
+            0
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn slow_route_does_not_starve_others() {
+        let pool = pool(4, 2);
+        let slow = pool.route(
+            &domain(KnownHyperlaneDomain::Test1),
+            &domain(KnownHyperlaneDomain::Test2),
+        );
+        let fast = pool.route(
+            &domain(KnownHyperlaneDomain::Test1),
+            &domain(KnownHyperlaneDomain::Test3),
+        );
+        let completed = Mutex::new(Vec::new());
+        let build = |route: &'static str, secs| {
+            let completed = &completed;
+            async move {
+                sleep(Duration::from_secs(secs)).await;
+                completed.lock().unwrap().push(route);
+            }
+        };
+        // The slow route's builds are all queued ahead of the fast route's
+        let slow_builds = (0..8).map(|_| slow.run(build("slow", 60)));
+        let fast_builds = (0..4).map(|_| fast.run(build("fast", 1)));
+
+        join_all(slow_builds.chain(fast_builds)).await;
+
+        let completed = completed.into_inner().unwrap();
+        assert_eq!(completed[..4], ["fast"; 4]);
+        assert_eq!(completed[4..], ["slow"; 8]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn serves_waiting_routes_in_turn() {
+        let pool = pool(1, 1);
+        let [a, b, c] = [
+            (KnownHyperlaneDomain::Test1, KnownHyperlaneDomain::Test2),
+            (KnownHyperlaneDomain::Test1, KnownHyperlaneDomain::Test3),
+            (KnownHyperlaneDomain::Test2, KnownHyperlaneDomain::Test3),
+        ]
+        .map(|(origin, destination)| pool.route(&domain(origin), &domain(destination)));
+        let completed = Mutex::new(Vec::new());
+        let build = |route: &'static str| {
+            let completed = &completed;
+            async move {
+                sleep(Duration::from_secs(1)).await;
+                completed.lock().unwrap().push(route);
+            }
+        };
+        // Holds the only slot while `a` and `b` queue up
+        let builds = std::iter::once(c.run(build("c")))
+            .chain((0..3).map(|_| a.run(build("a"))))
+            .chain((0..3).map(|_| b.run(build("b"))));
+
+        join_all(builds).await;
+
+        assert_eq!(
+            completed.into_inner().unwrap(),
+            ["c", "a", "b", "a", "b", "a", "b"]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn shutting_down_cancels_builds() {
+        let pool = pool(1, 1);
+        let route = pool.route(
+            &domain(KnownHyperlaneDomain::Test1),
+            &domain(KnownHyperlaneDomain::Test2),
+        );
+        let build = || sleep(Duration::from_secs(60));
+
+        let (in_flight_build, queued_build, _) =
+            tokio::join!(route.run(build()), route.run(build()), async {
+                sleep(Duration::from_secs(1)).await;
+                pool.shut_down();
+            });
+
+        assert!(matches!(in_flight_build, Err(MetadataPoolError::ShutDown)));
+        assert!(matches!(queued_build, Err(MetadataPoolError::ShutDown)));
+        assert_eq!(in_flight(&pool), 0);
+        assert_eq!(
+            pool.inner
+                .in_flight
+                .with_label_values(&["test1", "test2"])
+                .get(),
+            0
+        );
+        assert!(matches!(
+            route.run(build()).await,
+            Err(MetadataPoolError::ShutDown)
+        ));
+    }
+}
@@ -0,0 +1,264 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use hyperlane_base::CheckpointPreference;
+use hyperlane_core::{accumulator::merkle::Proof, H256};
+use tracing::debug;
+
+use crate::settings::QuorumPrefetchConf;
+
+/// A proof prepared against the checkpoint a message's quorum is expected
+/// to be reached at
+#[derive(Debug, Clone)]
+struct Preparation {
+    leaf_index: u32,
+    checkpoint_index: u32,
+    proof: Proof,
+    prepared_at: Instant,
+}
+
+/// Prepares the merkle proofs of messages whose quorum is within reach, i.e.
+/// a majority but not all of the threshold signed a checkpoint covering
+/// them, so that their metadata is finalized as soon as the last signature
+/// arrives. Until then, or until the preparation expires, the message is
+/// retried every `poll_interval` rather than backing off.
+///
+/// Preparations are bounded by `max_preparations`, the oldest being evicted
+/// first, and expire after `ttl` if the quorum never completes.
+#[derive(Debug, Clone)]
+pub struct QuorumPrefetcher {
+    conf: QuorumPrefetchConf,
+    preparations: Arc<Mutex<HashMap<H256, Preparation>>>,
+}
+
+/// The checkpoint index a message's quorum is anticipated at, if a majority
+/// but not all of the threshold signed a checkpoint covering the leaf.
+///
+/// With the `Latest` preference, that's the lowest latest index among the
+/// validators which already signed, as the quorum can't be reached above
+/// it. With `EarliestSatisfiable`, it's the leaf's own index.
+pub fn anticipated_checkpoint(
+    latest_indices: &[u32],
+    threshold: usize,
+    leaf_index: u32,
+    highest_leaf_index: u32,
+    preference: CheckpointPreference,
+) -> Option<u32> {
+    let covering = latest_indices.iter().filter(|index| **index >= leaf_index);
+    let signed = covering.clone().count();
+    if signed >= threshold || signed < threshold / 2 + 1 {
+        return None;
+    }
+    let lowest_signed = *covering.min()?;
+    match preference {
+        CheckpointPreference::Latest => Some(lowest_signed.min(highest_leaf_index)),
+        CheckpointPreference::EarliestSatisfiable => Some(leaf_index),
+    }
+}
+
+impl QuorumPrefetcher {
+    pub fn new(conf: QuorumPrefetchConf) -> Self {
+        Self {
+            conf,
+            preparations: Default::default(),
+        }
+    }
+
+    /// Whether a proof is already prepared for the message at this
+    /// checkpoint
+    pub fn is_prepared(&self, message_id: H256, checkpoint_index: u32, now: Instant) -> bool {
+        let mut preparations = self.preparations.lock().unwrap();
+        self.reclaim_expired(&mut preparations, now);
+        preparations
+            .get(&message_id)
+            .is_some_and(|preparation| preparation.checkpoint_index == checkpoint_index)
+    }
+
+    /// Keep the proof of a message against its anticipated quorum checkpoint
+    pub fn prepare(
+        &self,
+        message_id: H256,
+        leaf_index: u32,
+        checkpoint_index: u32,
+        proof: Proof,
+        now: Instant,
+    ) {
+        let mut preparations = self.preparations.lock().unwrap();
+        self.reclaim_expired(&mut preparations, now);
+        if !preparations.contains_key(&message_id)
+            && preparations.len() >= self.conf.max_preparations
+        {
+            let oldest = preparations
+                .iter()
+                .min_by_key(|(_, preparation)| preparation.prepared_at)
+                .map(|(id, _)| *id);
+            if let Some(oldest) = oldest {
+                debug!(message_id = ?oldest, "Evicting oldest quorum preparation");
+                preparations.remove(&oldest);
+            }
+        }
+        debug!(
+            ?message_id,
+            leaf_index, checkpoint_index, "Prepared proof ahead of quorum"
+        );
+        preparations.insert(
+            message_id,
+            Preparation {
+                leaf_index,
+                checkpoint_index,
+                proof,
+                prepared_at: now,
+            },
+        );
+    }
+
+    /// The prepared proof of a message whose quorum was reached, if it was
+    /// prepared against the quorum checkpoint. The preparation is consumed
+    /// either way.
+    pub fn take(
+        &self,
+        message_id: H256,
+        leaf_index: u32,
+        checkpoint_index: u32,
+        now: Instant,
+    ) -> Option<Proof> {
+        let mut preparations = self.preparations.lock().unwrap();
+        self.reclaim_expired(&mut preparations, now);
+        preparations
+            .remove(&message_id)
+            .filter(|preparation| {
+                preparation.leaf_index == leaf_index
+                    && preparation.checkpoint_index == checkpoint_index
+            })
+            .map(|preparation| preparation.proof)
+    }
+
+    /// How long to wait before retrying a message whose metadata couldn't be
+    /// fetched, if its quorum is anticipated
+    pub fn retry_delay(&self, message_id: H256, now: Instant) -> Option<Duration> {
+        let mut preparations = self.preparations.lock().unwrap();
+        self.reclaim_expired(&mut preparations, now);
+        preparations
+            .contains_key(&message_id)
+            .then_some(self.conf.poll_interval)
+    }
+
+    fn reclaim_expired(&self, preparations: &mut HashMap<H256, Preparation>, now: Instant) {
+        let before = preparations.len();
+        preparations.retain(|_, preparation| {
+            now.saturating_duration_since(preparation.prepared_at) < self.conf.ttl
+        });
+        let reclaimed = before - preparations.len();
+        if reclaimed > 0 {
+            debug!(reclaimed, "Reclaimed expired quorum preparations");
+        }
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.preparations.lock().unwrap().len()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use hyperlane_core::accumulator::TREE_DEPTH;
+
+    use super::*;
+
+    fn conf() -> QuorumPrefetchConf {
+        QuorumPrefetchConf {
+            max_preparations: 2,
+            ttl: Duration::from_secs(60),
+            poll_interval: Duration::from_millis(500),
+        }
+    }
+
+    fn proof(leaf_index: u32) -> Proof {
+        Proof {
+            leaf: H256::from_low_u64_be(leaf_index as u64),
+            index: leaf_index as usize,
+            path: [H256::zero(); TREE_DEPTH],
+        }
+    }
+
+    #[test]
+    fn test_anticipated_checkpoint() {
+        use CheckpointPreference::*;
+
+        // 2 of a threshold of 3 signed past the leaf
+        assert_eq!(
+            anticipated_checkpoint(&[9, 7, 2], 3, 5, 20, Latest),
+            Some(7)
+        );
+        assert_eq!(
+            anticipated_checkpoint(&[9, 7, 2], 3, 5, 20, EarliestSatisfiable),
+            Some(5)
+        );
+        // Capped to the highest leaf a proof can be generated for
+        assert_eq!(anticipated_checkpoint(&[9, 7, 2], 3, 5, 6, Latest), Some(6));
+        // Not a majority of the threshold
+        assert_eq!(
+            anticipated_checkpoint(&[9, 2, 2, 2], 4, 5, 20, Latest),
+            None
+        );
+        // Already a quorum
+        assert_eq!(anticipated_checkpoint(&[9, 7, 6], 3, 5, 20, Latest), None);
+        assert_eq!(anticipated_checkpoint(&[], 3, 5, 20, Latest), None);
+    }
+
+    #[test]
+    fn test_prepared_proof_is_used_once_for_the_anticipated_checkpoint() {
+        let prefetcher = QuorumPrefetcher::new(conf());
+        let now = Instant::now();
+        let id = H256::random();
+
+        assert_eq!(prefetcher.retry_delay(id, now), None);
+        prefetcher.prepare(id, 3, 7, proof(3), now);
+        assert!(prefetcher.is_prepared(id, 7, now));
+        assert!(!prefetcher.is_prepared(id, 8, now));
+        assert_eq!(
+            prefetcher.retry_delay(id, now),
+            Some(Duration::from_millis(500))
+        );
+
+        assert_eq!(prefetcher.take(id, 3, 7, now), Some(proof(3)));
+        assert_eq!(prefetcher.take(id, 3, 7, now), None);
+        assert_eq!(prefetcher.retry_delay(id, now), None);
+
+        // The quorum was reached at another checkpoint than anticipated
+        prefetcher.prepare(id, 3, 7, proof(3), now);
+        assert_eq!(prefetcher.take(id, 3, 8, now), None);
+        assert_eq!(prefetcher.len(), 0);
+    }
+
+    #[test]
+    fn test_abandoned_preparations_are_bounded_and_expire() {
+        let prefetcher = QuorumPrefetcher::new(conf());
+        let start = Instant::now();
+        let ids: Vec<_> = (0..3).map(|_| H256::random()).collect();
+
+        for (offset, id) in ids.iter().enumerate() {
+            let now = start + Duration::from_secs(offset as u64);
+            prefetcher.prepare(*id, offset as u32, 10, proof(offset as u32), now);
+        }
+        // The oldest was evicted to make room
+        assert_eq!(prefetcher.len(), 2);
+        assert_eq!(prefetcher.retry_delay(ids[0], start), None);
+
+        // Expired preparations are reclaimed, so their messages back off again
+        let later = start + Duration::from_secs(61);
+        assert_eq!(
+            prefetcher.retry_delay(ids[2], later),
+            Some(conf().poll_interval)
+        );
+        assert_eq!(prefetcher.len(), 1);
+        let later = start + Duration::from_secs(62);
+        assert_eq!(prefetcher.retry_delay(ids[2], later), None);
+        assert_eq!(prefetcher.take(ids[2], 2, 10, later), None);
+        assert_eq!(prefetcher.len(), 0);
+    }
+}
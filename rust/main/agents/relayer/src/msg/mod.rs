@@ -28,11 +28,27 @@
 //!   switch everyone to new one)
 
 pub(crate) mod blacklist;
+pub(crate) mod cancellation;
+pub(crate) mod circuit_breaker;
+pub(crate) mod decision;
+pub(crate) mod delivery_start;
+pub(crate) mod events;
 pub(crate) mod gas_payment;
+pub(crate) mod gas_price_ceiling;
+pub(crate) mod intake;
+pub(crate) mod latency_budget;
 pub(crate) mod metadata;
 pub(crate) mod op_queue;
 pub(crate) mod op_submitter;
 pub(crate) mod pending_message;
 pub(crate) mod processor;
+pub(crate) mod round_trip;
+pub(crate) mod shadow;
+pub(crate) mod supersession;
+pub(crate) mod throttle;
 
+pub use events::{
+    MessageEvent, MessageEventBus, MessageEventFilter, MessageEventKind, MessageEventStream,
+    MESSAGE_EVENTS_CAPACITY,
+};
 pub use gas_payment::GAS_EXPENDITURE_LOG_MESSAGE;
@@ -1,15 +1,79 @@
-use std::{cmp::Reverse, collections::BinaryHeap, sync::Arc};
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, VecDeque},
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use derive_new::new;
-use hyperlane_core::{PendingOperation, PendingOperationStatus, QueueOperation};
+use hyperlane_core::{PendingOperation, PendingOperationStatus, QueueLane, QueueOperation, H256};
+use itertools::Itertools;
 use prometheus::{IntGauge, IntGaugeVec};
+use serde::{Deserialize, Serialize};
 use tokio::sync::{broadcast::Receiver, Mutex};
 use tracing::{debug, info, instrument};
 
 use crate::settings::matching_list::MatchingList;
 
+/// Most operations the priority lane of a queue holds, so that operators
+/// retrying messages can't starve the rest of the queue
+pub const PRIORITY_LANE_CAPACITY: usize = 16;
+
 pub type OperationPriorityQueue = Arc<Mutex<BinaryHeap<Reverse<QueueOperation>>>>;
 
+/// Operations drained before the rest of a queue, in the order they were
+/// added. An operation only jumps the queue once: if it has to be attempted
+/// again, it goes back into the queue.
+pub type PriorityLane = Arc<Mutex<VecDeque<QueueOperation>>>;
+
+/// Where an operation asked to be retried ended up
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PrioritizedOperation {
+    pub id: H256,
+    pub destination: u32,
+    /// The priority lane, unless it was full
+    pub lane: QueueLane,
+    /// How many operations will be popped before it
+    pub position: usize,
+    /// Rough wait before it's popped, from how long apart the latest pops of
+    /// the queue were. Unknown until the queue has been busy for a while.
+    pub estimated_wait_seconds: Option<u64>,
+}
+
+/// How long apart pops of a busy queue are, to estimate waits from
+#[derive(Debug, Default)]
+struct PopCadence {
+    last_pop: Option<Instant>,
+    /// Moving average of the time between two consecutive non-empty pops
+    interval: Option<Duration>,
+    batch_size: usize,
+}
+
+impl PopCadence {
+    fn record(&mut self, popped: usize, batch_size: usize) {
+        if popped == 0 {
+            // the queue was idle, which says nothing about how long pops take
+            self.last_pop = None;
+            return;
+        }
+        let now = Instant::now();
+        if let Some(last_pop) = self.last_pop {
+            let interval = now.duration_since(last_pop);
+            self.interval = Some(match self.interval {
+                Some(average) => average.mul_f64(0.8) + interval.mul_f64(0.2),
+                None => interval,
+            });
+        }
+        self.last_pop = Some(now);
+        self.batch_size = batch_size.max(1);
+    }
+
+    fn estimated_wait(&self, position: usize) -> Option<Duration> {
+        self.interval
+            .map(|interval| interval * (position / self.batch_size.max(1)) as u32)
+    }
+}
+
 /// Queue of generic operations that can be submitted to a destination chain.
 /// Includes logic for maintaining queue metrics by the destination and `app_context` of an operation
 #[derive(Debug, Clone, new)]
@@ -19,6 +83,10 @@ pub struct OpQueue {
     retry_rx: Arc<Mutex<Receiver<MatchingList>>>,
     #[new(default)]
     pub queue: OperationPriorityQueue,
+    #[new(default)]
+    pub priority_lane: PriorityLane,
+    #[new(default)]
+    cadence: Arc<std::sync::Mutex<PopCadence>>,
 }
 
 impl OpQueue {
@@ -37,6 +105,78 @@ impl OpQueue {
         self.queue.lock().await.push(Reverse(op));
     }
 
+    /// Push an element onto the priority lane, e.g. a probe which should be
+    /// attempted ahead of the queue, and update metrics. Returns where it
+    /// ended up, which is the queue if the lane is full.
+    #[instrument(skip(self), fields(queue_label=%self.queue_metrics_label), level = "debug")]
+    pub async fn push_priority(
+        &self,
+        mut op: QueueOperation,
+        new_status: Option<PendingOperationStatus>,
+    ) -> PrioritizedOperation {
+        op.set_status_and_update_metrics(
+            new_status,
+            Arc::new(self.get_operation_metric(op.as_ref())),
+        );
+
+        let mut queue = self.queue.lock().await;
+        let mut lane = self.priority_lane.lock().await;
+        self.prioritize_locked(op, &mut queue, &mut lane)
+    }
+
+    /// Move the operations matching `matching_list` to the priority lane, in
+    /// their queue order, and reset their attempts so they're retried right
+    /// away. Those the lane has no room for stay in the queue, reset.
+    pub async fn prioritize(&self, matching_list: &MatchingList) -> Vec<PrioritizedOperation> {
+        let mut queue = self.queue.lock().await;
+        let mut lane = self.priority_lane.lock().await;
+        let (matched, rest): (Vec<_>, Vec<_>) = queue
+            .drain()
+            .map(|Reverse(op)| op)
+            .partition(|op| matching_list.op_matches(op));
+        queue.extend(rest.into_iter().map(Reverse));
+        // sorted before their attempts are reset, which would tie them
+        matched
+            .into_iter()
+            .sorted()
+            .map(|mut op| {
+                info!(
+                    operation = %op,
+                    queue_label = %self.queue_metrics_label,
+                    "Retrying OpQueue operation"
+                );
+                op.reset_attempts();
+                self.prioritize_locked(op, &mut queue, &mut lane)
+            })
+            .collect()
+    }
+
+    fn prioritize_locked(
+        &self,
+        op: QueueOperation,
+        queue: &mut BinaryHeap<Reverse<QueueOperation>>,
+        lane: &mut VecDeque<QueueOperation>,
+    ) -> PrioritizedOperation {
+        let id = op.id();
+        let destination = op.destination_domain().id();
+        let (lane_kind, position) = if lane.len() < PRIORITY_LANE_CAPACITY {
+            lane.push_back(op);
+            (QueueLane::Priority, lane.len() - 1)
+        } else {
+            let ahead = queue.iter().filter(|Reverse(other)| *other < op).count();
+            queue.push(Reverse(op));
+            (QueueLane::Normal, lane.len() + ahead)
+        };
+        let estimated_wait = self.cadence.lock().unwrap().estimated_wait(position);
+        PrioritizedOperation {
+            id,
+            destination,
+            lane: lane_kind,
+            position,
+            estimated_wait_seconds: estimated_wait.map(|wait| wait.as_secs_f64().ceil() as u64),
+        }
+    }
+
     /// Pop an element from the queue and update metrics
     #[instrument(skip(self), ret, fields(queue_label=%self.queue_metrics_label), level = "trace")]
     pub async fn pop(&mut self) -> Option<QueueOperation> {
@@ -44,18 +184,29 @@ impl OpQueue {
         pop_attempt.into_iter().next()
     }
 
-    /// Pop multiple elements at once from the queue and update metrics
+    /// Pop multiple elements at once from the queue and update metrics.
+    /// The priority lane is drained first.
     #[instrument(skip(self), fields(queue_label=%self.queue_metrics_label), level = "debug")]
     pub async fn pop_many(&mut self, limit: usize) -> Vec<QueueOperation> {
         self.process_retry_requests().await;
         let mut queue = self.queue.lock().await;
+        let mut lane = self.priority_lane.lock().await;
         let mut popped = vec![];
-        while let Some(Reverse(op)) = queue.pop() {
+        while popped.len() < limit {
+            let Some(mut op) = lane.pop_front() else {
+                break;
+            };
+            op.set_lane(QueueLane::Priority);
             popped.push(op);
-            if popped.len() >= limit {
+        }
+        while popped.len() < limit {
+            let Some(Reverse(mut op)) = queue.pop() else {
                 break;
-            }
+            };
+            op.set_lane(QueueLane::Normal);
+            popped.push(op);
         }
+        self.cadence.lock().unwrap().record(popped.len(), limit);
         // This function is called very often by the op_submitter tasks, so only log when there are operations to pop
         // to avoid spamming the logs
         if !popped.is_empty() {
@@ -77,25 +228,9 @@ impl OpQueue {
         while let Ok(message_id) = self.retry_rx.lock().await.try_recv() {
             message_retry_requests.push(message_id);
         }
-        if message_retry_requests.is_empty() {
-            return;
+        for retry_request in message_retry_requests {
+            self.prioritize(&retry_request).await;
         }
-        let mut queue = self.queue.lock().await;
-        let mut reprioritized_queue: BinaryHeap<_> = queue
-            .drain()
-            .map(|Reverse(mut op)| {
-                if message_retry_requests.iter().any(|r| r.op_matches(&op)) {
-                    info!(
-                        operation = %op,
-                        queue_label = %self.queue_metrics_label,
-                        "Retrying OpQueue operation"
-                    );
-                    op.reset_attempts()
-                }
-                Reverse(op)
-            })
-            .collect();
-        queue.append(&mut reprioritized_queue);
     }
 
     /// Get the metric associated with this operation
@@ -129,30 +264,40 @@ pub mod test {
     #[derive(Debug, Clone, Serialize)]
     pub struct MockPendingOperation {
         id: H256,
+        #[serde(skip_serializing)]
+        nonce: u32,
         sender_address: H256,
         origin_domain_id: u32,
         destination_domain_id: u32,
         recipient_address: H256,
         seconds_to_next_attempt: u64,
         destination_domain: HyperlaneDomain,
+        #[serde(skip_serializing)]
+        status: PendingOperationStatus,
+        #[serde(skip_serializing)]
+        age: Option<Duration>,
     }
 
     impl MockPendingOperation {
         pub fn new(seconds_to_next_attempt: u64, destination_domain: HyperlaneDomain) -> Self {
             Self {
                 id: H256::random(),
+                nonce: 0,
                 seconds_to_next_attempt,
                 destination_domain_id: destination_domain.id(),
                 destination_domain,
                 sender_address: H256::random(),
                 recipient_address: H256::random(),
                 origin_domain_id: 0,
+                status: PendingOperationStatus::FirstPrepareAttempt,
+                age: None,
             }
         }
 
         pub fn with_message_data(message: HyperlaneMessage) -> Self {
             Self {
                 id: message.id(),
+                nonce: message.nonce,
                 sender_address: message.sender,
                 recipient_address: message.recipient,
                 origin_domain_id: message.origin,
@@ -165,6 +310,8 @@ pub mod test {
                     domain_protocol: HyperlaneDomainProtocol::Ethereum,
                     domain_technical_stack: HyperlaneDomainTechnicalStack::Other,
                 },
+                status: PendingOperationStatus::FirstPrepareAttempt,
+                age: None,
             }
         }
 
@@ -188,6 +335,24 @@ pub mod test {
                 ..self
             }
         }
+
+        pub fn with_origin_domain_id(self, origin_domain_id: u32) -> Self {
+            Self {
+                origin_domain_id,
+                ..self
+            }
+        }
+
+        pub fn with_status(self, status: PendingOperationStatus) -> Self {
+            Self { status, ..self }
+        }
+
+        pub fn with_age(self, age: Duration) -> Self {
+            Self {
+                age: Some(age),
+                ..self
+            }
+        }
     }
 
     impl TryBatchAs<HyperlaneMessage> for MockPendingOperation {}
@@ -200,10 +365,12 @@ pub mod test {
         }
 
         fn status(&self) -> PendingOperationStatus {
-            PendingOperationStatus::FirstPrepareAttempt
+            self.status.clone()
         }
 
-        fn set_status(&mut self, _status: PendingOperationStatus) {}
+        fn set_status(&mut self, status: PendingOperationStatus) {
+            self.status = status;
+        }
 
         fn reset_attempts(&mut self) {
             self.seconds_to_next_attempt = 0;
@@ -224,7 +391,7 @@ pub mod test {
         fn set_metric(&mut self, _metric: Arc<IntGauge>) {}
 
         fn priority(&self) -> u32 {
-            todo!()
+            self.nonce
         }
 
         fn retrieve_status_from_db(&self) -> Option<PendingOperationStatus> {
@@ -295,6 +462,10 @@ pub mod test {
         fn set_retries(&mut self, _retries: u32) {
             todo!()
         }
+
+        fn age(&self) -> Option<Duration> {
+            self.age
+        }
     }
 
     pub fn dummy_metrics_and_label() -> (IntGaugeVec, String) {
@@ -376,9 +547,10 @@ pub mod test {
         }
 
         // The elements sent over the channel should be the first ones popped,
-        // regardless of their initial `next_attempt_after`
-        assert_eq!(queue_1_popped[0].id(), op_ids[2]);
-        assert_eq!(queue_1_popped[1].id(), op_ids[1]);
+        // in the order they were retried, regardless of their initial
+        // `next_attempt_after`
+        assert_eq!(queue_1_popped[0].id(), op_ids[1]);
+        assert_eq!(queue_1_popped[1].id(), op_ids[2]);
         assert_eq!(queue_1_popped[2].id(), op_ids[0]);
 
         // Pop elements from queue 2
@@ -435,13 +607,115 @@ pub mod test {
             popped.push(op.id());
         }
 
-        // First messages should be those to `destination_domain_2` - their exact order depends on
-        // how they were stored in the heap
+        // First messages should be those to `destination_domain_2`, in their
+        // queue order
         assert_eq!(popped[0], op_ids[2]);
-        assert_eq!(popped[1], op_ids[4]);
-        assert_eq!(popped[2], op_ids[3]);
+        assert_eq!(popped[1], op_ids[3]);
+        assert_eq!(popped[2], op_ids[4]);
         // Non-retried messages should be at the end
         assert_eq!(popped[3], op_ids[0]);
         assert_eq!(popped[4], op_ids[1]);
     }
+
+    fn op_queue() -> (OpQueue, sync::broadcast::Sender<MatchingList>) {
+        let (metrics, queue_metrics_label) = dummy_metrics_and_label();
+        let broadcaster = sync::broadcast::Sender::new(100);
+        let op_queue = OpQueue::new(
+            metrics,
+            queue_metrics_label,
+            Arc::new(Mutex::new(broadcaster.subscribe())),
+        );
+        (op_queue, broadcaster)
+    }
+
+    #[tokio::test]
+    async fn test_manual_retry_jumps_a_long_queue() {
+        let (mut op_queue, _broadcaster) = op_queue();
+        let destination: HyperlaneDomain = KnownHyperlaneDomain::Ethereum.into();
+        for _ in 0..200 {
+            op_queue
+                .push(
+                    Box::new(MockPendingOperation::new(0, destination.clone())),
+                    None,
+                )
+                .await;
+        }
+        let stuck = MockPendingOperation::new(3600, destination.clone());
+        let stuck_id = stuck.id();
+        op_queue.push(Box::new(stuck), None).await;
+
+        let prioritized = op_queue
+            .prioritize(&MatchingList::with_message_id(stuck_id))
+            .await;
+        assert_eq!(prioritized.len(), 1);
+        assert_eq!(prioritized[0].id, stuck_id);
+        assert_eq!(prioritized[0].lane, QueueLane::Priority);
+        assert_eq!(prioritized[0].position, 0);
+
+        assert_eq!(op_queue.pop().await.unwrap().id(), stuck_id);
+        // it only jumped the queue once
+        assert!(op_queue.priority_lane.lock().await.is_empty());
+        assert_eq!(op_queue.queue.lock().await.len(), 200);
+    }
+
+    #[tokio::test]
+    async fn test_priority_lane_is_bounded_and_normal_traffic_progresses() {
+        let (mut op_queue, _broadcaster) = op_queue();
+        let retried: HyperlaneDomain = KnownHyperlaneDomain::Ethereum.into();
+        let normal: HyperlaneDomain = KnownHyperlaneDomain::Arbitrum.into();
+        for _ in 0..PRIORITY_LANE_CAPACITY + 2 {
+            op_queue
+                .push(
+                    Box::new(MockPendingOperation::new(10, retried.clone())),
+                    None,
+                )
+                .await;
+        }
+        let normal_op = MockPendingOperation::new(0, normal);
+        let normal_id = normal_op.id();
+        op_queue.push(Box::new(normal_op), None).await;
+
+        let prioritized = op_queue
+            .prioritize(&MatchingList::with_destination_domain(retried.id()))
+            .await;
+        let (in_lane, overflow): (Vec<_>, Vec<_>) = prioritized
+            .iter()
+            .partition(|op| op.lane == QueueLane::Priority);
+        assert_eq!(in_lane.len(), PRIORITY_LANE_CAPACITY);
+        assert_eq!(
+            in_lane.iter().map(|op| op.position).collect::<Vec<_>>(),
+            (0..PRIORITY_LANE_CAPACITY).collect::<Vec<_>>()
+        );
+        // those which didn't fit wait behind the lane, reset
+        assert_eq!(overflow.len(), 2);
+        assert!(overflow
+            .iter()
+            .all(|op| op.position >= PRIORITY_LANE_CAPACITY));
+
+        // probes can't jump the queue while the lane is full either
+        let probe = op_queue
+            .push_priority(
+                Box::new(MockPendingOperation::new(0, retried.clone())),
+                None,
+            )
+            .await;
+        assert_eq!(probe.lane, QueueLane::Normal);
+
+        let lane_ids: Vec<_> = in_lane.iter().map(|op| op.id).collect();
+        let popped: Vec<_> = op_queue
+            .pop_many(PRIORITY_LANE_CAPACITY)
+            .await
+            .iter()
+            .map(|op| op.id())
+            .collect();
+        assert_eq!(popped, lane_ids);
+
+        // once the lane is drained, the rest of the queue is popped again
+        let mut rest = vec![];
+        while let Some(op) = op_queue.pop().await {
+            rest.push(op.id());
+        }
+        assert_eq!(rest.len(), 4);
+        assert!(rest.contains(&normal_id));
+    }
 }
@@ -25,7 +25,7 @@ use tokio_metrics::TaskMonitor;
 use tracing::{debug, info_span, instrument, instrument::Instrumented, trace, Instrument};
 use tracing::{info, warn};
 
-use hyperlane_base::CoreMetrics;
+use hyperlane_base::{CoreMetrics, WithChainContext};
 use hyperlane_core::{
     ChainCommunicationError, ChainResult, HyperlaneDomain, HyperlaneDomainProtocol,
     PendingOperationResult, QueueOperation, TxOutcome,
@@ -33,6 +33,7 @@ use hyperlane_core::{
 
 use crate::msg::pending_message::CONFIRM_DELAY;
 use crate::settings::matching_list::MatchingList;
+use crate::tasks::AbortOnDrop;
 
 use super::op_queue::OpQueue;
 use super::op_queue::OperationPriorityQueue;
@@ -142,6 +143,21 @@ impl SerialSubmitter {
         self.prepare_queue.queue.clone()
     }
 
+    /// The prepare, submit and confirm queues, in the order operations go
+    /// through them
+    pub fn queues(&self) -> Vec<OperationPriorityQueue> {
+        [&self.prepare_queue, &self.submit_queue, &self.confirm_queue]
+            .into_iter()
+            .map(|queue| queue.queue.clone())
+            .collect()
+    }
+
+    /// The queue of operations waiting to be prepared, sharing its state with
+    /// the submitter
+    pub fn prepare_op_queue(&self) -> OpQueue {
+        self.prepare_queue.clone()
+    }
+
     pub fn spawn(self) -> Instrumented<JoinHandle<()>> {
         let span = info_span!("SerialSubmitter", destination=%self.domain);
         let task_monitor = self.task_monitor.clone();
@@ -202,6 +218,7 @@ impl SerialSubmitter {
             )),
         ];
 
+        let _abort = AbortOnDrop::new(&tasks);
         if let Err(err) = try_join_all(tasks).await {
             tracing::error!(
                 error=?err,
@@ -521,7 +538,6 @@ impl SerialSubmitterMetrics {
 #[derive(new, Debug)]
 struct OperationBatch {
     operations: Vec<QueueOperation>,
-    #[allow(dead_code)]
     domain: HyperlaneDomain,
 }
 
@@ -532,7 +548,11 @@ impl OperationBatch {
         confirm_queue: &mut OpQueue,
         metrics: &SerialSubmitterMetrics,
     ) {
-        let excluded_ops = match self.try_submit_as_batch(metrics).await {
+        let batch_result = self
+            .try_submit_as_batch(metrics)
+            .await
+            .with_chain_context(&self.domain);
+        let excluded_ops = match batch_result {
             Ok(batch_result) => {
                 Self::handle_batch_result(self.operations, batch_result, confirm_queue).await
             }
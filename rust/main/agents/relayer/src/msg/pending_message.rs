@@ -11,22 +11,36 @@ use derive_new::new;
 use eyre::Result;
 use hyperlane_base::{
     db::{HyperlaneDb, HyperlaneRocksDB},
-    CoreMetrics,
+    CoreMetrics, ExemplarHistogram,
 };
 use hyperlane_core::{
     gas_used_by_operation, BatchItem, ChainCommunicationError, ChainResult, ConfirmReason,
-    HyperlaneChain, HyperlaneDomain, HyperlaneMessage, Mailbox, MessageSubmissionData,
-    PendingOperation, PendingOperationResult, PendingOperationStatus, ReprepareReason, TryBatchAs,
-    TxOutcome, H256, U256,
+    HyperlaneChain, HyperlaneDomain, HyperlaneMessage, HyperlaneProvider, Mailbox, MessageKey,
+    MessageSubmissionData, PendingOperation, PendingOperationResult, PendingOperationStatus,
+    QueueLane, ReprepareReason, TryBatchAs, TxOutcome, TxSubmissionPath, H256, U256,
 };
 use prometheus::{IntCounter, IntGauge};
 use serde::Serialize;
 use tracing::{debug, error, info, info_span, instrument, trace, warn, Instrument};
 
 use super::{
-    gas_payment::{GasPaymentEnforcer, GasPolicyStatus},
-    metadata::{BaseMetadataBuilder, MessageMetadataBuilder, MetadataBuilder},
+    cancellation::MessageCancellation,
+    circuit_breaker::{CircuitAdmission, CircuitBreaker},
+    decision::{
+        decide, error_message, now, Decision, DecisionInputs, DecisionSnapshot, GasPolicyOutcome,
+        GasQuote, ListMembership, MetadataInputs, MetadataOutcome, Observed,
+    },
+    events::{MessageEventBus, MessageEventKind},
+    gas_payment::GasPaymentEnforcer,
+    gas_price_ceiling::GasPriceCeiling,
+    latency_budget::{LatencyBudgetTracker, PipelineStage, StageTimeline},
+    metadata::{
+        BaseMetadataBuilder, MessageMetadataBuilder, MetadataBuildRoute, MetadataBuilder,
+        MetadataBuilderError,
+    },
+    shadow::ShadowEvaluator,
 };
+use crate::settings::snapshot::ConfigHandle;
 
 pub const CONFIRM_DELAY: Duration = if cfg!(any(test, feature = "test-utils")) {
     // Wait 5 seconds after submitting the message before confirming in test mode
@@ -53,6 +67,22 @@ pub struct MessageContext {
     /// destination.
     pub transaction_gas_limit: Option<U256>,
     pub metrics: MessageSubmissionMetrics,
+    /// Hot-reloadable state derived from the settings, whose config hash is
+    /// recorded with every decision
+    pub config: ConfigHandle,
+    /// Pauses submissions to the destination while too many of them revert
+    pub circuit_breaker: Option<Arc<CircuitBreaker>>,
+    /// Highest gas prices messages are submitted at, by route
+    pub gas_price_ceiling: Option<Arc<GasPriceCeiling>>,
+    /// Candidate configs the messages of some routes are also decided with
+    pub shadow: Option<Arc<ShadowEvaluator>>,
+    /// Bounds the metadata builds in flight, if they're pooled
+    pub metadata_builds: Option<MetadataBuildRoute>,
+    /// Times messages through the pipeline against the latency budgets of
+    /// their routes, if any are configured
+    pub latency_budgets: Option<Arc<LatencyBudgetTracker>>,
+    /// Where lifecycle events of the messages are published
+    pub events: MessageEventBus,
 }
 
 /// A message that the submitter can and should try to submit.
@@ -72,6 +102,9 @@ pub struct PendingMessage {
     num_retries: u32,
     #[new(value = "Instant::now()")]
     #[serde(skip_serializing)]
+    created_at: Instant,
+    #[new(value = "Instant::now()")]
+    #[serde(skip_serializing)]
     last_attempted_at: Instant,
     #[new(default)]
     #[serde(skip_serializing)]
@@ -79,12 +112,37 @@ pub struct PendingMessage {
     #[new(default)]
     #[serde(skip_serializing)]
     submission_outcome: Option<TxOutcome>,
+    /// How the transaction of each submission attempt was broadcast
+    #[new(default)]
+    submission_paths: Vec<TxSubmissionPath>,
     #[new(default)]
     #[serde(skip_serializing)]
     metadata: Option<Vec<u8>>,
     #[new(default)]
     #[serde(skip_serializing)]
     metric: Option<Arc<IntGauge>>,
+    /// How the message fared against the relayer's lists
+    #[new(default)]
+    #[serde(skip_serializing)]
+    lists: ListMembership,
+    /// Lane of the submitter's queue the message was last popped from
+    #[new(default)]
+    #[serde(skip_serializing)]
+    lane: QueueLane,
+    /// Inputs gathered by the current preparation attempt
+    #[new(default)]
+    #[serde(skip_serializing)]
+    decision_inputs: DecisionInputs,
+    /// What the candidate gas policies shadowing the message's route made of
+    /// its payments in the current preparation attempt
+    #[new(default)]
+    #[serde(skip_serializing)]
+    shadow_gas_policy: Option<Observed<GasPolicyOutcome>>,
+    /// How far the message got through the stages timed against its latency
+    /// budgets
+    #[new(default)]
+    #[serde(skip_serializing)]
+    stages: StageTimeline,
 }
 
 impl Debug for PendingMessage {
@@ -163,6 +221,10 @@ impl PendingOperation for PendingMessage {
         self.message.origin
     }
 
+    fn key(&self) -> MessageKey {
+        MessageKey::new(self.ctx.origin_db.domain().id(), self.id())
+    }
+
     fn destination_domain(&self) -> &HyperlaneDomain {
         self.ctx.destination_mailbox.domain()
     }
@@ -189,153 +251,132 @@ impl PendingOperation for PendingMessage {
         self.app_context.clone()
     }
 
-    #[instrument(skip(self), fields(id=?self.id()), level = "debug")]
+    #[instrument(skip(self), fields(message_id=?self.id()), level = "debug")]
     async fn prepare(&mut self) -> PendingOperationResult {
         if !self.is_ready() {
             trace!("Message is not ready to be submitted yet");
             return PendingOperationResult::NotReady;
         }
+        self.time_indexing().await;
+
+        // Every input gathered below is recorded, and the attempt concludes as
+        // soon as the inputs gathered so far decide it.
+        self.decision_inputs = DecisionInputs {
+            lane: self.lane,
+            cancelled: self.cancellation(),
+            lists: self.lists.clone(),
+            transaction_gas_limit: self.ctx.transaction_gas_limit,
+            gas_price_ceiling: self.gas_price_ceiling(),
+            ..Default::default()
+        };
+        self.shadow_gas_policy = None;
+        if self.decision_inputs.cancelled.is_some() {
+            return self.conclude();
+        }
 
         // If the message has already been processed, e.g. due to another relayer having
         // already processed, then mark it as already-processed, and move on to
         // the next tick.
-        let is_already_delivered = match self
+        let is_already_delivered = self
             .ctx
             .destination_mailbox
             .delivered(self.message.id())
-            .await
-        {
-            Ok(is_delivered) => is_delivered,
-            Err(err) => {
-                return self.on_reprepare(Some(err), ReprepareReason::ErrorCheckingDeliveryStatus);
-            }
+            .await;
+        self.decision_inputs.delivered = Some(Observed::new(&is_already_delivered));
+        let Ok(false) = is_already_delivered else {
+            return self.conclude();
         };
-        if is_already_delivered {
-            debug!("Message has already been delivered, marking as submitted.");
-            self.submitted = true;
-            self.set_next_attempt_after(CONFIRM_DELAY);
-            return PendingOperationResult::Confirm(ConfirmReason::AlreadySubmitted);
-        }
 
         let provider = self.ctx.destination_mailbox.provider();
 
         // We cannot deliver to an address that is not a contract so check and drop if it isn't.
-        let is_contract = match provider.is_contract(&self.message.recipient).await {
-            Ok(is_contract) => is_contract,
-            Err(err) => {
-                return self.on_reprepare(
-                    Some(err),
-                    ReprepareReason::ErrorCheckingIfRecipientIsContract,
-                );
-            }
+        let is_contract = provider.is_contract(&self.message.recipient).await;
+        self.decision_inputs.recipient_is_contract = Some(Observed::new(&is_contract));
+        let Ok(true) = is_contract else {
+            return self.conclude();
         };
-        if !is_contract {
-            info!(
-                recipient=?self.message.recipient,
-                "Dropping message because recipient is not a contract"
-            );
-            return PendingOperationResult::Drop;
-        }
 
-        let ism_address = match self
+        let ism_address = self
             .ctx
             .destination_mailbox
             .recipient_ism(self.message.recipient)
-            .await
-        {
-            Ok(ism_address) => ism_address,
-            Err(err) => {
-                return self.on_reprepare(Some(err), ReprepareReason::ErrorFetchingIsmAddress);
-            }
+            .await;
+        self.decision_inputs.recipient_ism = Some(Observed::new(&ism_address));
+        let Ok(ism_address) = ism_address else {
+            return self.conclude();
         };
 
-        let message_metadata_builder = match MessageMetadataBuilder::new(
+        let message_metadata_builder = MessageMetadataBuilder::new(
             ism_address,
             &self.message,
             self.ctx.metadata_builder.clone(),
         )
-        .await
-        {
-            Ok(message_metadata_builder) => message_metadata_builder,
-            Err(err) => {
-                return self.on_reprepare(Some(err), ReprepareReason::ErrorGettingMetadataBuilder);
-            }
+        .await;
+        self.decision_inputs.metadata_builder = Some(Observed::new(
+            &message_metadata_builder.as_ref().map(|_| ()),
+        ));
+        let Ok(message_metadata_builder) = message_metadata_builder else {
+            return self.conclude();
         };
 
-        let metadata = match message_metadata_builder
-            .build(ism_address, &self.message)
-            .await
-        {
-            Ok(metadata) => metadata,
-            Err(err) => {
-                return self.on_reprepare(Some(err), ReprepareReason::ErrorBuildingMetadata);
-            }
+        let build_started_at = Instant::now();
+        let build = message_metadata_builder.build(ism_address, &self.message);
+        let metadata = match &self.ctx.metadata_builds {
+            Some(builds) => builds
+                .run(build)
+                .await
+                .unwrap_or_else(|err| Err(err.into())),
+            None => build.await,
         };
-        self.metadata = metadata.clone();
-
-        let Some(metadata) = metadata else {
-            return self.on_reprepare::<String>(None, ReprepareReason::CouldNotFetchMetadata);
+        self.decision_inputs.metadata = Some(self.metadata_inputs(&metadata).await);
+        if let Ok(Some(_)) = &metadata {
+            self.time_stage(PipelineStage::Metadata, build_started_at.elapsed());
+        }
+        if let Ok(metadata) = &metadata {
+            self.metadata = metadata.clone();
+        }
+        let Ok(Some(metadata)) = metadata else {
+            return self.conclude();
         };
 
         // Estimate transaction costs for the process call. If there are issues, it's
         // likely that gas estimation has failed because the message is
         // reverting. This is defined behavior, so we just log the error and
         // move onto the next tick.
-        let tx_cost_estimate = match self
+        let tx_cost_estimate = self
             .ctx
             .destination_mailbox
             .process_estimate_costs(&self.message, &metadata)
-            .await
-        {
-            Ok(tx_cost_estimate) => tx_cost_estimate,
-            Err(err) => {
-                return self.on_reprepare(Some(err), ReprepareReason::ErrorEstimatingGas);
-            }
+            .await;
+        self.decision_inputs.gas_quote = Some(Observed::new(
+            &tx_cost_estimate.as_ref().map(GasQuote::from),
+        ));
+        let Ok(tx_cost_estimate) = tx_cost_estimate else {
+            return self.conclude();
         };
 
         // If the gas payment requirement hasn't been met, move to the next tick.
-        let gas_limit = match self
+        let gas_policy = self
             .ctx
             .origin_gas_payment_enforcer
             .message_meets_gas_payment_requirement(&self.message, &tx_cost_estimate)
-            .await
-        {
-            Ok(gas_limit) => gas_limit,
-            Err(err) => {
-                return self.on_reprepare(Some(err), ReprepareReason::ErrorCheckingGasRequirement);
-            }
-        };
-
-        let gas_limit = match gas_limit {
-            GasPolicyStatus::NoPaymentFound => {
-                return self.on_reprepare::<String>(None, ReprepareReason::GasPaymentNotFound)
-            }
-            GasPolicyStatus::PolicyNotMet => {
-                return self
-                    .on_reprepare::<String>(None, ReprepareReason::GasPaymentRequirementNotMet)
-            }
-            GasPolicyStatus::PolicyMet(gas_limit) => gas_limit,
-        };
-
-        // Go ahead and attempt processing of message to destination chain.
-        debug!(
-            ?gas_limit,
-            ?tx_cost_estimate,
-            "Gas payment requirement met, ready to process message"
-        );
-
-        if let Some(max_limit) = self.ctx.transaction_gas_limit {
-            if gas_limit > max_limit {
-                // TODO: consider dropping instead of repreparing in this case
-                return self.on_reprepare::<String>(None, ReprepareReason::ExceedsMaxGasLimit);
-            }
+            .await;
+        self.decision_inputs.gas_policy = Some(Observed::new(
+            &gas_policy.as_ref().map(GasPolicyOutcome::from),
+        ));
+        if let Some(shadow) = &self.ctx.shadow {
+            self.shadow_gas_policy = shadow
+                .candidate_gas_policy(&self.message, &tx_cost_estimate)
+                .await;
         }
 
-        self.submission_data = Some(Box::new(MessageSubmissionData {
-            metadata,
-            gas_limit,
-        }));
-        PendingOperationResult::Success
+        // Only messages which would be submitted otherwise may become canaries
+        if let (Some(Decision::Submit(_)), Some(breaker)) =
+            (decide(&self.decision_inputs), &self.ctx.circuit_breaker)
+        {
+            self.decision_inputs.circuit = Some(breaker.admit(self.key(), Instant::now()));
+        }
+        self.conclude()
     }
 
     #[instrument]
@@ -350,6 +391,20 @@ impl PendingOperation for PendingMessage {
             .clone()
             .expect("Pending message must be prepared before it can be submitted");
 
+        // The message may have been cancelled since it was prepared
+        if let Some(cancellation) = self.cancellation() {
+            info!(?cancellation, "Message was cancelled, dropping it");
+            self.publish(MessageEventKind::DeadLettered);
+            return PendingOperationResult::Drop;
+        }
+
+        // The breaker may have opened since the message was prepared
+        if let Some(breaker) = &self.ctx.circuit_breaker {
+            if breaker.admit(self.key(), Instant::now()) == CircuitAdmission::Open {
+                return self.hold_back_for_circuit();
+            }
+        }
+
         // To avoid spending gas on a tx that will revert, dry-run just before submitting.
         if let Some(metadata) = self.metadata.as_ref() {
             if self
@@ -364,17 +419,35 @@ impl PendingOperation for PendingMessage {
         }
 
         // We use the estimated gas limit from the prior call to
-        // `process_estimate_costs` to avoid a second gas estimation.
+        // `process_estimate_costs` to avoid a second gas estimation. The gas
+        // price may have risen since the message was prepared, so its fees are
+        // capped at the ceiling of its route again.
         let tx_outcome = self
             .ctx
             .destination_mailbox
-            .process(&self.message, &state.metadata, Some(state.gas_limit))
+            .process_with_gas_price_ceiling(
+                &self.message,
+                &state.metadata,
+                Some(state.gas_limit),
+                self.gas_price_ceiling(),
+            )
             .await;
         match tx_outcome {
             Ok(outcome) => {
+                if let Some(ready_at) = self.stages.ready_at() {
+                    self.time_stage(PipelineStage::Broadcast, ready_at.elapsed());
+                }
                 self.set_operation_outcome(outcome, state.gas_limit);
                 PendingOperationResult::Confirm(ConfirmReason::SubmittedBySelf)
             }
+            Err(ChainCommunicationError::GasPriceCeilingExceeded { gas_price, ceiling }) => {
+                debug!(
+                    ?gas_price,
+                    ?ceiling,
+                    "Gas price rose above the ceiling since preparing"
+                );
+                self.await_gas_price_decline()
+            }
             Err(e) => {
                 error!(error=?e, "Error when processing message");
                 return PendingOperationResult::Reprepare(ReprepareReason::ErrorSubmitting);
@@ -416,6 +489,8 @@ impl PendingOperation for PendingMessage {
                 submission=?self.submission_outcome,
                 "Message successfully processed"
             );
+            let tx = self.submission_outcome.as_ref().map(|o| o.transaction_id);
+            self.publish(MessageEventKind::Delivered { tx });
             PendingOperationResult::Success
         } else {
             let span = info_span!(
@@ -434,6 +509,14 @@ impl PendingOperation for PendingMessage {
         submission_outcome: TxOutcome,
         submission_estimated_cost: U256,
     ) {
+        // Messages can't be cancelled anymore once broadcast
+        if let Err(err) = self.ctx.origin_db.store_message_broadcast(&self.id()) {
+            warn!(?err, "Failed to record that the message was broadcast");
+        }
+        self.record_submission_path(submission_outcome.submission_path);
+        if let Some(breaker) = &self.ctx.circuit_breaker {
+            breaker.record(self.key(), &submission_outcome, Instant::now());
+        }
         let Some(operation_estimate) = self.get_tx_cost_estimate() else {
             warn!("Cannot set operation outcome without a cost estimate set previously");
             return;
@@ -485,6 +568,10 @@ impl PendingOperation for PendingMessage {
         self.reset_attempts();
     }
 
+    fn set_lane(&mut self, lane: QueueLane) {
+        self.lane = lane;
+    }
+
     fn set_retries(&mut self, retries: u32) {
         self.set_retries(retries);
     }
@@ -493,6 +580,15 @@ impl PendingOperation for PendingMessage {
         Some(self.ctx.destination_mailbox.clone())
     }
 
+    fn age(&self) -> Option<Duration> {
+        Some(self.created_at.elapsed())
+    }
+
+    fn latency_budget_breaches(&self) -> Vec<String> {
+        let breaches = self.stages.breaches().iter();
+        breaches.map(|stage| stage.as_str().to_owned()).collect()
+    }
+
     fn get_metric(&self) -> Option<Arc<IntGauge>> {
         self.metric.clone()
     }
@@ -509,6 +605,7 @@ impl PendingMessage {
         message: HyperlaneMessage,
         ctx: Arc<MessageContext>,
         app_context: Option<String>,
+        lists: ListMembership,
     ) -> Self {
         let mut pm = Self::new(
             message,
@@ -517,6 +614,7 @@ impl PendingMessage {
             PendingOperationStatus::FirstPrepareAttempt,
             app_context,
         );
+        pm.lists = lists;
         match pm
             .ctx
             .origin_db
@@ -547,6 +645,9 @@ impl PendingMessage {
         } else {
             warn!("Repreparing message: {}", reason.clone());
         }
+        self.publish(MessageEventKind::Parked {
+            reason: reason.to_string(),
+        });
         PendingOperationResult::Reprepare(reason)
     }
 
@@ -560,6 +661,255 @@ impl PendingMessage {
         PendingOperationResult::NotReady
     }
 
+    /// Decide the attempt from the inputs gathered so far, persisting them
+    /// together with the decision so it can be explained later on
+    fn conclude(&mut self) -> PendingOperationResult {
+        let decision = decide(&self.decision_inputs).unwrap_or(Decision::Incomplete);
+        self.record_decision(&decision);
+        self.shadow(&decision);
+        match decision {
+            Decision::Confirm => {
+                debug!("Message has already been delivered, marking as submitted.");
+                self.submitted = true;
+                self.set_next_attempt_after(CONFIRM_DELAY);
+                PendingOperationResult::Confirm(ConfirmReason::AlreadySubmitted)
+            }
+            Decision::Drop | Decision::Skip(_) => {
+                info!(
+                    recipient=?self.message.recipient,
+                    %decision,
+                    "Dropping message"
+                );
+                self.publish(MessageEventKind::DeadLettered);
+                PendingOperationResult::Drop
+            }
+            Decision::Reprepare(ReprepareReason::CircuitOpen) => self.hold_back_for_circuit(),
+            Decision::Reprepare(ReprepareReason::GasPriceCeiling) => self.await_gas_price_decline(),
+            Decision::Reprepare(reason) => {
+                if matches!(reason, ReprepareReason::CouldNotFetchMetadata) {
+                    let builder = &self.ctx.metadata_builder;
+                    if let Some(delay) = builder.anticipated_quorum_retry_delay(self.id()) {
+                        return self.await_anticipated_quorum(delay);
+                    }
+                }
+                let err = self.decision_inputs.error().map(str::to_owned);
+                self.on_reprepare(err, reason)
+            }
+            Decision::Submit(gas_limit) => {
+                debug!(
+                    ?gas_limit,
+                    gas_quote = ?self.decision_inputs.gas_quote,
+                    "Gas payment requirement met, ready to process message"
+                );
+                self.submission_data = self.metadata.clone().map(|metadata| {
+                    Box::new(MessageSubmissionData {
+                        metadata,
+                        gas_limit,
+                    })
+                });
+                self.stages.mark_ready(Instant::now());
+                self.publish(MessageEventKind::Deliverable);
+                PendingOperationResult::Success
+            }
+            Decision::Incomplete => {
+                error!(
+                    inputs = ?self.decision_inputs,
+                    "Preparing message ended without a decision"
+                );
+                PendingOperationResult::NotReady
+            }
+        }
+    }
+
+    /// Hold the message back while the destination's circuit breaker is
+    /// open. It's not the message's fault, so rather than backing off it
+    /// waits until the breaker may let a canary through.
+    fn hold_back_for_circuit(&mut self) -> PendingOperationResult {
+        if let Some(breaker) = &self.ctx.circuit_breaker {
+            self.set_next_attempt_after(breaker.retry_after(Instant::now()));
+        }
+        debug!("Circuit breaker of the destination is open, holding message back");
+        let reason = ReprepareReason::CircuitOpen;
+        self.publish(MessageEventKind::Parked {
+            reason: reason.to_string(),
+        });
+        PendingOperationResult::Reprepare(reason)
+    }
+
+    /// Retry the message at the next tick while the last signature of its
+    /// quorum is awaited, its proof being prepared already. It's not failing,
+    /// so its retries don't increase until the preparation expires.
+    fn await_anticipated_quorum(&mut self, delay: Duration) -> PendingOperationResult {
+        self.last_attempted_at = Instant::now();
+        self.set_next_attempt_after(delay);
+        debug!(
+            ?delay,
+            "Quorum of the message is anticipated, retrying soon"
+        );
+        let reason = ReprepareReason::CouldNotFetchMetadata;
+        self.publish(MessageEventKind::Parked {
+            reason: reason.to_string(),
+        });
+        PendingOperationResult::Reprepare(reason)
+    }
+
+    /// Defer the message while the gas price of its destination is above the
+    /// ceiling of its route, checking it again every recheck interval. The
+    /// message isn't failing, so its retries don't increase.
+    fn await_gas_price_decline(&mut self) -> PendingOperationResult {
+        if let Some(ceiling) = &self.ctx.gas_price_ceiling {
+            self.last_attempted_at = Instant::now();
+            self.set_next_attempt_after(ceiling.recheck_interval());
+        }
+        debug!(
+            ceiling = ?self.decision_inputs.gas_price_ceiling,
+            gas_quote = ?self.decision_inputs.gas_quote,
+            "Gas price is above the ceiling of the message's route, deferring it"
+        );
+        self.submitted = false;
+        let reason = ReprepareReason::GasPriceCeiling;
+        self.publish(MessageEventKind::Parked {
+            reason: reason.to_string(),
+        });
+        PendingOperationResult::Reprepare(reason)
+    }
+
+    /// Decide the attempt with the candidate config shadowing the message's
+    /// route, if any, recording where it diverges from the active decision
+    fn shadow(&mut self, decision: &Decision) {
+        if let Some(shadow) = &self.ctx.shadow {
+            let preference = self
+                .ctx
+                .metadata_builder
+                .checkpoint_preference(&self.message);
+            shadow.compare(
+                &self.message,
+                &self.decision_inputs,
+                decision,
+                self.shadow_gas_policy.take(),
+                preference,
+            );
+        }
+    }
+
+    /// Highest gas price the message is submitted at, if any
+    fn gas_price_ceiling(&self) -> Option<U256> {
+        self.ctx
+            .gas_price_ceiling
+            .as_ref()
+            .and_then(|ceiling| ceiling.ceiling_for(&self.message))
+    }
+
+    /// Who cancelled the message, if it was cancelled
+    fn cancellation(&self) -> Option<MessageCancellation> {
+        match self.ctx.origin_db.retrieve_message_cancellation(&self.id()) {
+            Ok(cancellation) => cancellation,
+            Err(err) => {
+                warn!(?err, "Failed to retrieve the cancellation of the message");
+                None
+            }
+        }
+    }
+
+    fn publish(&self, kind: MessageEventKind) {
+        self.ctx.events.publish(&self.message, kind);
+    }
+
+    fn record_decision(&self, decision: &Decision) {
+        let snapshot = DecisionSnapshot::new(
+            &self.message,
+            now(),
+            self.ctx.config.load().config_hash.clone(),
+            self.decision_inputs.clone(),
+            decision.clone(),
+        );
+        match self
+            .ctx
+            .origin_db
+            .store_decision_snapshot(&self.message.id(), &snapshot)
+        {
+            Ok(attempt) => trace!(attempt, %decision, "Recorded decision snapshot"),
+            Err(err) => warn!(?err, "Failed to record decision snapshot"),
+        }
+    }
+
+    /// Time the indexing of the message, from the inclusion of its dispatch
+    /// block to it being handed to the submitter, which happened as it was
+    /// created
+    async fn time_indexing(&mut self) {
+        if self.ctx.latency_budgets.is_none() || self.stages.is_timed(PipelineStage::Indexing) {
+            return;
+        }
+        let block_number = self
+            .ctx
+            .origin_db
+            .retrieve_dispatched_block_number_by_nonce(&self.message.nonce);
+        let Ok(Some(block_number)) = block_number else {
+            self.stages.skip(PipelineStage::Indexing);
+            return;
+        };
+        let provider = self.ctx.metadata_builder.origin_provider();
+        match provider.get_block_by_height(block_number).await {
+            Ok(block) => {
+                let handed_over_at = now().saturating_sub(self.created_at.elapsed().as_secs());
+                let elapsed = handed_over_at.saturating_sub(block.timestamp);
+                self.time_stage(PipelineStage::Indexing, Duration::from_secs(elapsed));
+            }
+            Err(err) => {
+                debug!(?err, block_number, "Failed to fetch the dispatch block");
+                self.stages.skip(PipelineStage::Indexing);
+            }
+        }
+    }
+
+    /// Time `stage` of the message against the latency budget of its route
+    fn time_stage(&mut self, stage: PipelineStage, elapsed: Duration) {
+        if let Some(budgets) = &self.ctx.latency_budgets {
+            budgets.time(
+                &mut self.stages,
+                &self.message,
+                stage,
+                elapsed,
+                Instant::now(),
+            );
+        }
+    }
+
+    /// What the metadata of this attempt was built from
+    async fn metadata_inputs(&self, metadata: &Result<Option<Vec<u8>>>) -> MetadataInputs {
+        let builder = &self.ctx.metadata_builder;
+        let leaf_index = builder
+            .get_merkle_leaf_id_by_message_id(self.message.id())
+            .await
+            .ok()
+            .flatten();
+        let outcome = match metadata {
+            Ok(Some(_)) => MetadataOutcome::Built,
+            Ok(None) => MetadataOutcome::NotFetched,
+            Err(err) if MetadataBuilderError::is_tree_unavailable(err) => {
+                MetadataOutcome::TreeUnavailable(error_message(err))
+            }
+            Err(err) => MetadataOutcome::Error(error_message(err)),
+        };
+        // Only the quorum checkpoint of built metadata was used in this attempt
+        let checkpoint_index = match outcome {
+            MetadataOutcome::Built => self
+                .ctx
+                .origin_db
+                .retrieve_quorum_checkpoint_by_message_id(&self.message.id())
+                .ok()
+                .flatten()
+                .map(|quorum| quorum.checkpoint.index),
+            _ => None,
+        };
+        MetadataInputs {
+            tree_count: builder.tree_count().await,
+            leaf_index,
+            checkpoint_index,
+            outcome,
+        }
+    }
+
     fn is_ready(&self) -> bool {
         self.next_attempt_after
             .map(|a| Instant::now() >= a)
@@ -579,9 +929,18 @@ impl PendingMessage {
             .store_processed_by_nonce(&self.message.nonce, &true)?;
         self.ctx.metrics.update_nonce(&self.message);
         self.ctx.metrics.messages_processed.inc();
+        self.ctx
+            .metrics
+            .pickup_to_delivery
+            .observe(self.created_at.elapsed().as_secs_f64());
         Ok(())
     }
 
+    fn record_submission_path(&mut self, path: TxSubmissionPath) {
+        self.submission_paths.push(path);
+        self.ctx.metrics.submission_path(path).inc();
+    }
+
     fn reset_attempts(&mut self) {
         self.next_attempt_after = None;
         self.last_attempted_at = Instant::now();
@@ -643,6 +1002,10 @@ pub struct MessageSubmissionMetrics {
     // Fields are public for testing purposes
     pub last_known_nonce: IntGauge,
     pub messages_processed: IntCounter,
+    pub public_submissions: IntCounter,
+    pub private_submissions: IntCounter,
+    pub public_fallback_submissions: IntCounter,
+    pub pickup_to_delivery: ExemplarHistogram,
 }
 
 impl MessageSubmissionMetrics {
@@ -662,6 +1025,34 @@ impl MessageSubmissionMetrics {
             messages_processed: metrics
                 .messages_processed_count()
                 .with_label_values(&[origin, destination]),
+            public_submissions: metrics.message_submissions_by_path().with_label_values(&[
+                origin,
+                destination,
+                TxSubmissionPath::Public.as_str(),
+            ]),
+            private_submissions: metrics.message_submissions_by_path().with_label_values(&[
+                origin,
+                destination,
+                TxSubmissionPath::Private.as_str(),
+            ]),
+            public_fallback_submissions: metrics.message_submissions_by_path().with_label_values(
+                &[
+                    origin,
+                    destination,
+                    TxSubmissionPath::PublicFallback.as_str(),
+                ],
+            ),
+            pickup_to_delivery: metrics
+                .message_pickup_to_delivery()
+                .with_label_values(&[origin, destination]),
+        }
+    }
+
+    fn submission_path(&self, path: TxSubmissionPath) -> &IntCounter {
+        match path {
+            TxSubmissionPath::Public => &self.public_submissions,
+            TxSubmissionPath::Private => &self.private_submissions,
+            TxSubmissionPath::PublicFallback => &self.public_fallback_submissions,
         }
     }
 
@@ -673,3 +1064,247 @@ impl MessageSubmissionMetrics {
             .set(std::cmp::max(self.last_known_nonce.get(), msg.nonce as i64));
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::sync::Mutex;
+
+    use hyperlane_base::db::test_utils;
+    use hyperlane_core::{test_utils::dummy_domain, FixedPointNumber, TxCostEstimate, H512};
+    use hyperlane_test::mocks::MockMailboxContract;
+
+    use super::*;
+    use crate::{
+        msg::{
+            processor::test::{dummy_config, dummy_metadata_builder, dummy_submission_metrics},
+            shadow::test::{matching_sender, shadow_divergences},
+        },
+        settings::{GasPriceCeilingConf, ShadowRouteConf},
+    };
+
+    const RECHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+    fn gwei(amount: u64) -> U256 {
+        U256::from(amount) * U256::exp10(9)
+    }
+
+    /// A pending message, prepared to be submitted, whose destination's gas
+    /// price follows `gas_price`
+    fn prepared_message(
+        db: &HyperlaneRocksDB,
+        gas_price: Arc<Mutex<U256>>,
+        offered_gas_prices: Arc<Mutex<Vec<Option<U256>>>>,
+        shadow: Option<Arc<ShadowEvaluator>>,
+    ) -> PendingMessage {
+        let origin_domain = dummy_domain(0, "dummy_origin_domain");
+        let destination_domain = dummy_domain(1, "dummy_destination_domain");
+        let message = HyperlaneMessage {
+            origin: origin_domain.id(),
+            destination: destination_domain.id(),
+            ..Default::default()
+        };
+
+        let mut mailbox = MockMailboxContract::default();
+        let quoted_gas_price = gas_price.clone();
+        mailbox
+            .expect_process_estimate_costs()
+            .returning(move |_, _| {
+                Ok(TxCostEstimate {
+                    gas_limit: 100_000.into(),
+                    gas_price: FixedPointNumber::try_from(*quoted_gas_price.lock().unwrap())
+                        .unwrap(),
+                    l2_gas_limit: None,
+                })
+            });
+        mailbox
+            .expect_process_with_gas_price_ceiling()
+            .returning(move |_, _, _, max_gas_price| {
+                offered_gas_prices.lock().unwrap().push(max_gas_price);
+                let gas_price = *gas_price.lock().unwrap();
+                match max_gas_price {
+                    Some(ceiling) if gas_price > ceiling => {
+                        Err(ChainCommunicationError::GasPriceCeilingExceeded { gas_price, ceiling })
+                    }
+                    _ => Ok(TxOutcome {
+                        transaction_id: H512::zero(),
+                        executed: true,
+                        gas_used: 80_000.into(),
+                        gas_price: FixedPointNumber::try_from(gas_price).unwrap(),
+                        submission_path: TxSubmissionPath::Public,
+                    }),
+                }
+            });
+
+        let ctx = Arc::new(MessageContext {
+            destination_mailbox: Arc::new(mailbox),
+            origin_db: db.clone(),
+            metadata_builder: Arc::new(dummy_metadata_builder(
+                &origin_domain,
+                &destination_domain,
+                db,
+            )),
+            origin_gas_payment_enforcer: Arc::new(GasPaymentEnforcer::new([], db.clone())),
+            transaction_gas_limit: None,
+            metrics: dummy_submission_metrics(),
+            config: dummy_config(),
+            circuit_breaker: None,
+            gas_price_ceiling: Some(Arc::new(GasPriceCeiling::new(GasPriceCeilingConf {
+                default: Some(gwei(500)),
+                overrides: vec![],
+                recheck_interval: RECHECK_INTERVAL,
+            }))),
+            shadow,
+            metadata_builds: None,
+            latency_budgets: None,
+            events: Default::default(),
+        });
+        let mut pending_message = PendingMessage::new(
+            message,
+            ctx,
+            PendingOperationStatus::FirstPrepareAttempt,
+            None,
+        );
+        pending_message.metadata = Some(vec![]);
+        pending_message
+    }
+
+    /// Conclude a preparation attempt which quoted the current gas price
+    async fn conclude_with_quote(pending_message: &mut PendingMessage) -> PendingOperationResult {
+        let quote = pending_message
+            .ctx
+            .destination_mailbox
+            .process_estimate_costs(&pending_message.message, &[])
+            .await;
+        pending_message.decision_inputs = DecisionInputs {
+            lists: ListMembership::new(true, false, None, true),
+            gas_price_ceiling: pending_message.gas_price_ceiling(),
+            delivered: Some(Observed::Value(false)),
+            recipient_is_contract: Some(Observed::Value(true)),
+            recipient_ism: Some(Observed::Value(H256::zero())),
+            metadata_builder: Some(Observed::Value(())),
+            metadata: Some(MetadataInputs {
+                tree_count: 1,
+                leaf_index: Some(0),
+                checkpoint_index: Some(0),
+                outcome: MetadataOutcome::Built,
+            }),
+            gas_quote: Some(Observed::new(&quote.as_ref().map(GasQuote::from))),
+            gas_policy: Some(Observed::Value(GasPolicyOutcome::PolicyMet(100_000.into()))),
+            ..Default::default()
+        };
+        pending_message.conclude()
+    }
+
+    fn assert_deferred_for_gas_price(
+        pending_message: &PendingMessage,
+        result: PendingOperationResult,
+    ) {
+        assert!(matches!(
+            result,
+            PendingOperationResult::Reprepare(ReprepareReason::GasPriceCeiling)
+        ));
+        // Deferring isn't failing, the message is rechecked at the interval
+        assert_eq!(pending_message.num_retries, 0);
+        let next_attempt = pending_message.next_attempt_after.unwrap();
+        assert!(next_attempt > Instant::now() + RECHECK_INTERVAL - Duration::from_secs(1));
+        assert!(next_attempt <= Instant::now() + RECHECK_INTERVAL);
+    }
+
+    #[tokio::test]
+    async fn test_messages_are_deferred_during_gas_spikes_and_released_after() {
+        test_utils::run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(&dummy_domain(0, "dummy_origin_domain"), db);
+            let gas_price = Arc::new(Mutex::new(gwei(600)));
+            let offered_gas_prices = Arc::new(Mutex::new(vec![]));
+            let mut pending_message =
+                prepared_message(&db, gas_price.clone(), offered_gas_prices.clone(), None);
+
+            // The gas price spiked above the ceiling
+            let result = conclude_with_quote(&mut pending_message).await;
+            assert_deferred_for_gas_price(&pending_message, result);
+            assert!(pending_message.submission_data.is_none());
+
+            // Once it declines, the message is released
+            *gas_price.lock().unwrap() = gwei(20);
+            let result = conclude_with_quote(&mut pending_message).await;
+            assert!(matches!(result, PendingOperationResult::Success));
+            let result = pending_message.submit().await;
+            assert!(matches!(
+                result,
+                PendingOperationResult::Confirm(ConfirmReason::SubmittedBySelf)
+            ));
+            assert_eq!(*offered_gas_prices.lock().unwrap(), vec![Some(gwei(500))]);
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_submissions_never_offer_more_than_the_ceiling() {
+        test_utils::run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(&dummy_domain(0, "dummy_origin_domain"), db);
+            let gas_price = Arc::new(Mutex::new(gwei(20)));
+            let offered_gas_prices = Arc::new(Mutex::new(vec![]));
+            let mut pending_message =
+                prepared_message(&db, gas_price.clone(), offered_gas_prices.clone(), None);
+            let result = conclude_with_quote(&mut pending_message).await;
+            assert!(matches!(result, PendingOperationResult::Success));
+
+            // The gas price spikes between preparing and submitting. Rather than
+            // bumping the fees past the ceiling, the submission is deferred.
+            *gas_price.lock().unwrap() = gwei(600);
+            let result = pending_message.submit().await;
+            assert_deferred_for_gas_price(&pending_message, result);
+            assert!(pending_message.submission_outcome.is_none());
+
+            *gas_price.lock().unwrap() = gwei(450);
+            let result = pending_message.submit().await;
+            assert!(matches!(
+                result,
+                PendingOperationResult::Confirm(ConfirmReason::SubmittedBySelf)
+            ));
+            assert_eq!(
+                *offered_gas_prices.lock().unwrap(),
+                vec![Some(gwei(500)), Some(gwei(500))]
+            );
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_shadowed_messages_are_delivered_as_the_active_config_decides() {
+        test_utils::run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(&dummy_domain(0, "dummy_origin_domain"), db);
+            // The candidate would park the message at a fraction of the ceiling
+            let routes = [ShadowRouteConf {
+                name: "everything".to_owned(),
+                matching_list: matching_sender(H256::zero()),
+                gas_price_ceiling: Some(gwei(10)),
+                ..Default::default()
+            }];
+            let divergences = shadow_divergences();
+            let shadow = Arc::new(ShadowEvaluator::new(&routes, &db, divergences.clone()));
+            let offered_gas_prices = Arc::new(Mutex::new(vec![]));
+            let mut pending_message = prepared_message(
+                &db,
+                Arc::new(Mutex::new(gwei(20))),
+                offered_gas_prices.clone(),
+                Some(shadow),
+            );
+
+            let result = conclude_with_quote(&mut pending_message).await;
+            assert!(matches!(result, PendingOperationResult::Success));
+            let result = pending_message.submit().await;
+            assert!(matches!(
+                result,
+                PendingOperationResult::Confirm(ConfirmReason::SubmittedBySelf)
+            ));
+            // Submitted at the active ceiling, not the candidate's
+            assert_eq!(*offered_gas_prices.lock().unwrap(), vec![Some(gwei(500))]);
+
+            let report = divergences.report();
+            assert_eq!(report.routes["everything"].evaluated, 1);
+            assert_eq!(report.routes["everything"].would_park, 1);
+        })
+        .await;
+    }
+}
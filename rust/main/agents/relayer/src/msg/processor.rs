@@ -1,6 +1,6 @@
 use std::{
     cmp::max,
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     fmt::{Debug, Formatter},
     sync::Arc,
     time::Duration,
@@ -8,44 +8,72 @@ use std::{
 
 use async_trait::async_trait;
 use derive_new::new;
-use ethers::utils::hex;
 use eyre::Result;
 use hyperlane_base::{
-    db::{HyperlaneDb, HyperlaneRocksDB},
-    CoreMetrics,
+    db::{CorruptionTolerance, DbResult, HyperlaneDb, HyperlaneRocksDB},
+    CoreMetrics, WithMessageContext,
 };
-use hyperlane_core::{HyperlaneDomain, HyperlaneMessage, QueueOperation};
+use hyperlane_core::{HyperlaneDomain, HyperlaneMessage, QueueOperation, H256};
 use prometheus::IntGauge;
-use tokio::sync::mpsc::UnboundedSender;
-use tracing::{debug, instrument, trace};
+use tokio::{
+    sync::mpsc::{error::TryRecvError, UnboundedReceiver, UnboundedSender},
+    time::{timeout, Instant},
+};
+use tracing::{debug, instrument, trace, warn};
+
+use super::{
+    cancellation::{cancel_message, CancellationError, CancelledBy},
+    decision::{decide, now, Decision, DecisionInputs, DecisionSnapshot, ListMembership},
+    delivery_start::DeliveryStart,
+    events::MessageEventKind,
+    metadata::AppContextClassifier,
+    pending_message::*,
+    round_trip::{RoundTrip, RoundTripGuard},
+    supersession::{supersede, LatestMessages, Supersession, SupersessionKey},
+    throttle::BurstThrottle,
+};
+use crate::{processor::ProcessorExt, settings::snapshot::ConfigHandle};
 
-use super::{blacklist::AddressBlacklist, metadata::AppContextClassifier, pending_message::*};
-use crate::{processor::ProcessorExt, settings::matching_list::MatchingList};
+/// How long to wait for new messages before polling the db again
+const MESSAGE_POLL_INTERVAL: Duration = Duration::from_secs(1);
 
 /// Finds unprocessed messages from an origin and submits then through a channel
 /// for to the appropriate destination.
 #[allow(clippy::too_many_arguments)]
 pub struct MessageProcessor {
-    /// A matching list of messages that should be whitelisted.
-    message_whitelist: Arc<MatchingList>,
-    /// A matching list of messages that should be blacklisted.
-    message_blacklist: Arc<MatchingList>,
-    /// Addresses that messages may not interact with.
-    address_blacklist: Arc<AddressBlacklist>,
+    /// Lists of the messages to relay, app contexts and config hash, as of
+    /// the latest settings reload
+    config: ConfigHandle,
     metrics: MessageProcessorMetrics,
     /// channel for each destination chain to send operations (i.e. message
     /// submissions) to
     send_channels: HashMap<u32, UnboundedSender<QueueOperation>>,
     /// Needed context to send a message for each destination chain
     destination_ctxs: HashMap<u32, Arc<MessageContext>>,
-    metric_app_contexts: Vec<(MatchingList, String)>,
     nonce_iterator: ForwardBackwardIterator,
+    /// Messages handed over by the indexer as soon as they're stored
+    message_intake: UnboundedReceiver<HyperlaneMessage>,
+    /// Holds back messages of bursting senders and routes
+    throttle: BurstThrottle,
+    /// Where skipped messages are recorded
+    db: HyperlaneRocksDB,
+    /// Messages dispatched before the origin's start block are skipped
+    delivery_start: DeliveryStart,
+    /// Newest message relayed in each group of the supersession routes
+    latest_messages: LatestMessages,
+    /// Replay protection of the route pairs the origin is part of
+    round_trips: Vec<Arc<RoundTripGuard>>,
+    /// Corrupted messages found while scanning the db are skipped up to it
+    corruption: CorruptionTolerance,
 }
 
 #[derive(Debug)]
 struct ForwardBackwardIterator {
     low_nonce_iter: DirectionalNonceIterator,
     high_nonce_iter: DirectionalNonceIterator,
+    /// Freshly indexed messages by nonce, taken instead of reading them back
+    /// from the db once the high nonce iterator gets to them
+    intake: BTreeMap<u32, HyperlaneMessage>,
     // here for debugging purposes
     _domain: String,
 }
@@ -75,17 +103,56 @@ impl ForwardBackwardIterator {
         Self {
             low_nonce_iter,
             high_nonce_iter,
+            intake: BTreeMap::new(),
             _domain: domain,
         }
     }
 
+    /// Keep a freshly indexed message until the high nonce iterator gets to
+    /// it. Messages the iterator already passed were found by polling the db,
+    /// so they're dropped rather than processed twice.
+    fn stash(&mut self, message: HyperlaneMessage) {
+        match self.high_nonce_iter.nonce {
+            Some(nonce) if message.nonce >= nonce => {
+                self.intake.entry(message.nonce).or_insert(message);
+            }
+            _ => trace!(?message, "Dropping message the db poll already got to"),
+        }
+    }
+
+    /// Take the message at the high nonce iterator's position, if it was
+    /// handed over by the indexer
+    fn take_stashed(&mut self) -> Option<HyperlaneMessage> {
+        let nonce = self.high_nonce_iter.nonce?;
+        self.intake = self.intake.split_off(&nonce);
+        self.intake.remove(&nonce)
+    }
+
     async fn try_get_next_message(
         &mut self,
         metrics: &MessageProcessorMetrics,
+        corruption: &mut CorruptionTolerance,
     ) -> Result<Option<HyperlaneMessage>> {
         loop {
-            let high_nonce_message_status = self.high_nonce_iter.try_get_next_nonce(metrics)?;
-            let low_nonce_message_status = self.low_nonce_iter.try_get_next_nonce(metrics)?;
+            let high_nonce_message_status = match self.take_stashed() {
+                // The indexer already handed this message over, so only its processed
+                // status has to be read from the db
+                Some(message) => {
+                    DirectionalNonceIterator::update_max_nonce_gauge(&message, metrics);
+                    if self.high_nonce_iter.is_message_processed()? {
+                        MessageStatus::Processed
+                    } else {
+                        debug!(hyp_message=?message, "Found processable message from the indexer");
+                        MessageStatus::Processable(message)
+                    }
+                }
+                None => self
+                    .high_nonce_iter
+                    .try_get_next_nonce(metrics, corruption)?,
+            };
+            let low_nonce_message_status = self
+                .low_nonce_iter
+                .try_get_next_nonce(metrics, corruption)?;
             // Always prioritize the high nonce message
             match (high_nonce_message_status, low_nonce_message_status) {
                 // Keep iterating if only processed messages are found
@@ -156,11 +223,20 @@ impl DirectionalNonceIterator {
         }
     }
 
+    /// Corrupted messages are skipped over, as they can't be processed
+    /// anyway, until there are more of them than tolerated
     fn try_get_next_nonce(
         &mut self,
         metrics: &MessageProcessorMetrics,
+        corruption: &mut CorruptionTolerance,
     ) -> Result<MessageStatus<HyperlaneMessage>> {
-        if let Some(message) = self.indexed_message_with_nonce()? {
+        let indexed = loop {
+            match corruption.skip_corrupted(self.indexed_message_with_nonce())? {
+                Some(indexed) => break indexed,
+                None => self.iterate(),
+            }
+        };
+        if let Some(message) = indexed {
             Self::update_max_nonce_gauge(&message, metrics);
             if !self.is_message_processed()? {
                 debug!(hyp_message=?message, iterator=?self, "Found processable message");
@@ -182,12 +258,9 @@ impl DirectionalNonceIterator {
         }
     }
 
-    fn indexed_message_with_nonce(&self) -> Result<Option<HyperlaneMessage>> {
+    fn indexed_message_with_nonce(&self) -> DbResult<Option<HyperlaneMessage>> {
         match self.nonce {
-            Some(nonce) => {
-                let msg = self.db.retrieve_message_by_nonce(nonce)?;
-                Ok(msg)
-            }
+            Some(nonce) => self.db.retrieve_message_by_nonce(nonce),
             None => Ok(None),
         }
     }
@@ -225,8 +298,9 @@ impl Debug for MessageProcessor {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "MessageProcessor {{ message_whitelist: {:?}, message_blacklist: {:?}, address_blacklist: {:?}, nonce_iterator: {:?}}}",
-            self.message_whitelist, self.message_blacklist, self.address_blacklist, self.nonce_iterator
+            "MessageProcessor {{ config: {:?}, nonce_iterator: {:?}}}",
+            self.config.load(),
+            self.nonce_iterator
         )
     }
 }
@@ -246,64 +320,106 @@ impl ProcessorExt for MessageProcessor {
         // self.tx_msg and then continue the scan at the next highest
         // nonce.
         // Scan until we find next nonce without delivery confirmation.
-        if let Some(msg) = self.try_get_unprocessed_message().await? {
+        let mut next_message = self.try_get_unprocessed_message().await?;
+        // Messages held back by the throttle only get processed when there's
+        // nothing else to process
+        let mut released = false;
+        if next_message.is_none() {
+            next_message = self.throttle.release(Instant::now());
+            released = next_message.is_some();
+        }
+        if next_message.is_none() {
+            self.wait_for_intake().await;
+            next_message = self.try_get_unprocessed_message().await?;
+        }
+        if let Some(msg) = next_message {
             debug!(
                 ?msg,
                 cursor = ?self.nonce_iterator,
                 "Processor working on message"
             );
             let destination = msg.destination;
-
-            // Skip if not whitelisted.
-            if !self.message_whitelist.msg_matches(&msg, true) {
-                debug!(?msg, whitelist=?self.message_whitelist, "Message not whitelisted, skipping");
-                return Ok(());
-            }
-
-            // Skip if the message is blacklisted
-            if self.message_blacklist.msg_matches(&msg, false) {
-                debug!(?msg, blacklist=?self.message_blacklist, "Message blacklisted, skipping");
+            // Every part of the config is read from the same snapshot, even if
+            // it is reloaded meanwhile
+            let config = self.config.load();
+
+            // Skip if the message isn't whitelisted, is blacklisted or involves a
+            // blacklisted address, if it's intended for this origin or a
+            // destination we do not service, or if it was dispatched before the
+            // block messages are delivered from
+            let lists = ListMembership::new(
+                config.message_whitelist.msg_matches(&msg, true),
+                config.message_blacklist.msg_matches(&msg, false),
+                config
+                    .address_blacklist
+                    .find_blacklisted_address(&msg)
+                    .as_deref(),
+                destination != self.domain().id() && self.send_channels.contains_key(&destination),
+            );
+            let inputs = DecisionInputs {
+                lists: lists.clone(),
+                before_delivery_start: self.delivery_start.excludes(&self.db, &msg),
+                ..Default::default()
+            };
+            if let Some(decision) = decide(&inputs) {
+                debug!(
+                    ?msg,
+                    %decision,
+                    whitelist=?config.message_whitelist,
+                    blacklist=?config.message_blacklist,
+                    "Skipping message"
+                );
+                self.record_skip(&msg, &config.config_hash, inputs, decision);
                 return Ok(());
             }
 
-            // Skip if the message involves a blacklisted address
-            if let Some(blacklisted_address) = self.address_blacklist.find_blacklisted_address(&msg)
-            {
+            // Defer if the sender or route is flooding us
+            if !released && !self.throttle.admit(&msg, Instant::now()) {
                 debug!(
                     ?msg,
-                    blacklisted_address = hex::encode(blacklisted_address),
-                    "Message involves blacklisted address, skipping"
+                    deferred = self.throttle.deferred_count(),
+                    "Sender or route is bursting, deferring message"
                 );
                 return Ok(());
             }
 
-            // Skip if the message is intended for this origin
-            if destination == self.domain().id() {
-                debug!(?msg, "Message destined for self, skipping");
-                return Ok(());
+            // Park the message if the same one crossed its route pair back and
+            // forth too many times
+            if let Some(guard) = self.round_trips.iter().find(|guard| guard.covers(&msg)) {
+                if let RoundTrip::Tripped { tuple_id } = guard.observe(&msg, now()) {
+                    if self.park_round_trip(&msg, tuple_id, &config.config_hash) {
+                        return Ok(());
+                    }
+                }
             }
 
-            // Skip if the message is intended for a destination we do not service
-            if !self.send_channels.contains_key(&destination) {
-                debug!(?msg, "Message destined for unknown domain, skipping");
-                return Ok(());
+            // Supersede the older message of the same group, which may be
+            // this one if a newer message was scanned first
+            if let Some(key) = SupersessionKey::of(&config.supersession_routes, &msg) {
+                if let Some(supersession) = self.latest_messages.observe(key, &msg) {
+                    if self.cancel_superseded(&supersession, &config.config_hash)
+                        && supersession.superseded.id() == msg.id()
+                    {
+                        return Ok(());
+                    }
+                }
             }
 
             debug!(%msg, "Sending message to submitter");
 
             let app_context_classifier =
-                AppContextClassifier::new(self.metric_app_contexts.clone());
+                AppContextClassifier::new(config.metric_app_contexts.clone());
 
-            let app_context = app_context_classifier.get_app_context(&msg).await?;
+            let app_context = app_context_classifier
+                .get_app_context(&msg)
+                .await
+                .with_message_context(&msg)?;
             // Finally, build the submit arg and dispatch it to the submitter.
-            let pending_msg = PendingMessage::from_persisted_retries(
-                msg,
-                self.destination_ctxs[&destination].clone(),
-                app_context,
-            );
+            let ctx = &self.destination_ctxs[&destination];
+            ctx.events.publish(&msg, MessageEventKind::Indexed);
+            let pending_msg =
+                PendingMessage::from_persisted_retries(msg, ctx.clone(), app_context, lists);
             self.send_channels[&destination].send(Box::new(pending_msg) as QueueOperation)?;
-        } else {
-            tokio::time::sleep(Duration::from_secs(1)).await;
         }
         Ok(())
     }
@@ -313,31 +429,158 @@ impl MessageProcessor {
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         db: HyperlaneRocksDB,
-        message_whitelist: Arc<MatchingList>,
-        message_blacklist: Arc<MatchingList>,
-        address_blacklist: Arc<AddressBlacklist>,
+        config: ConfigHandle,
         metrics: MessageProcessorMetrics,
         send_channels: HashMap<u32, UnboundedSender<QueueOperation>>,
         destination_ctxs: HashMap<u32, Arc<MessageContext>>,
-        metric_app_contexts: Vec<(MatchingList, String)>,
+        message_intake: UnboundedReceiver<HyperlaneMessage>,
+        throttle: BurstThrottle,
+        delivery_start: DeliveryStart,
+        round_trips: Vec<Arc<RoundTripGuard>>,
+        corruption: CorruptionTolerance,
     ) -> Self {
         Self {
-            message_whitelist,
-            message_blacklist,
-            address_blacklist,
+            db: db.clone(),
+            config,
             metrics,
             send_channels,
             destination_ctxs,
-            metric_app_contexts,
             nonce_iterator: ForwardBackwardIterator::new(Arc::new(db) as Arc<dyn HyperlaneDb>),
+            message_intake,
+            throttle,
+            delivery_start,
+            latest_messages: Default::default(),
+            round_trips,
+            corruption,
+        }
+    }
+
+    /// Cancel a superseded message, unless a transaction delivering it was
+    /// already broadcast. Returns whether it was cancelled.
+    fn cancel_superseded(&self, supersession: &Supersession, config_hash: &str) -> bool {
+        let superseded = &supersession.superseded;
+        match supersede(&self.db, supersession, config_hash.to_owned()) {
+            Ok(_) => {
+                debug!(
+                    message_id = ?superseded.id(),
+                    newer_message_id = ?supersession.newer_message_id,
+                    "Message superseded by a newer one"
+                );
+                if let Some(ctx) = self.destination_ctxs.get(&superseded.destination) {
+                    ctx.events.publish(
+                        superseded,
+                        MessageEventKind::Superseded {
+                            by: supersession.newer_message_id,
+                        },
+                    );
+                }
+                true
+            }
+            Err(
+                CancellationError::AlreadyBroadcast(_)
+                | CancellationError::AlreadyProcessed(_)
+                | CancellationError::AlreadyCancelled(_),
+            ) => {
+                debug!(
+                    message_id = ?superseded.id(),
+                    "Superseded message is already broadcast or terminal, leaving it alone"
+                );
+                false
+            }
+            Err(err) => {
+                warn!(?err, message_id = ?superseded.id(), "Failed to supersede message");
+                false
+            }
+        }
+    }
+
+    /// Cancel a message whose tuple tripped the round trip limit of its route
+    /// pair, unless a transaction delivering it was already broadcast.
+    /// Returns whether it was cancelled.
+    fn park_round_trip(
+        &self,
+        message: &HyperlaneMessage,
+        tuple_id: H256,
+        config_hash: &str,
+    ) -> bool {
+        match cancel_message(
+            &self.db,
+            message,
+            CancelledBy::RoundTripLimit { tuple_id },
+            "The same message crossed its route pair too many times".to_owned(),
+            config_hash.to_owned(),
+        ) {
+            Ok(_) => {
+                warn!(
+                    message_id = ?message.id(),
+                    ?tuple_id,
+                    "Parked message exceeding the round trip limit of its route pair"
+                );
+                if let Some(ctx) = self.destination_ctxs.get(&message.destination) {
+                    ctx.events.publish(message, MessageEventKind::DeadLettered);
+                }
+                true
+            }
+            Err(
+                CancellationError::AlreadyBroadcast(_)
+                | CancellationError::AlreadyProcessed(_)
+                | CancellationError::AlreadyCancelled(_),
+            ) => {
+                debug!(
+                    message_id = ?message.id(),
+                    "Message exceeding the round trip limit is already broadcast or terminal"
+                );
+                false
+            }
+            Err(err) => {
+                warn!(?err, message_id = ?message.id(), "Failed to park message");
+                false
+            }
+        }
+    }
+
+    /// Persist why a message is skipped, so the decision can be explained
+    /// later on
+    fn record_skip(
+        &self,
+        message: &HyperlaneMessage,
+        config_hash: &str,
+        inputs: DecisionInputs,
+        decision: Decision,
+    ) {
+        let snapshot =
+            DecisionSnapshot::new(message, now(), config_hash.to_owned(), inputs, decision);
+        if let Err(err) = self.db.store_decision_snapshot(&message.id(), &snapshot) {
+            warn!(?err, message_id = ?message.id(), "Failed to record decision snapshot");
+        }
+    }
+
+    /// Stash every message the indexer handed over since the last tick
+    fn drain_intake(&mut self) {
+        loop {
+            match self.message_intake.try_recv() {
+                Ok(message) => self.nonce_iterator.stash(message),
+                Err(TryRecvError::Empty | TryRecvError::Disconnected) => break,
+            }
+        }
+    }
+
+    /// Wait up to the poll interval for the indexer to hand over new messages
+    async fn wait_for_intake(&mut self) {
+        match timeout(MESSAGE_POLL_INTERVAL, self.message_intake.recv()).await {
+            Ok(Some(message)) => self.nonce_iterator.stash(message),
+            // The indexer is gone, only the db is left to poll
+            Ok(None) => tokio::time::sleep(MESSAGE_POLL_INTERVAL).await,
+            Err(_) => {}
         }
     }
 
     async fn try_get_unprocessed_message(&mut self) -> Result<Option<HyperlaneMessage>> {
+        self.drain_intake();
         trace!(nonce_iterator=?self.nonce_iterator, "Trying to get the next processor message");
         let next_message = self
             .nonce_iterator
-            .try_get_next_message(&self.metrics)
+            .try_get_next_message(&self.metrics, &mut self.corruption)
             .await?;
         if next_message.is_none() {
             trace!(nonce_iterator=?self.nonce_iterator, "No message found in DB for nonce");
@@ -383,32 +626,56 @@ impl MessageProcessorMetrics {
 }
 
 #[cfg(test)]
-mod test {
-    use std::time::Instant;
+pub mod test {
+    use std::{
+        collections::HashSet,
+        sync::atomic::{AtomicU32, Ordering},
+        time::Instant,
+    };
+
+    use futures::{FutureExt, StreamExt};
 
     use crate::{
-        merkle_tree::builder::MerkleTreeBuilder,
+        explain::explain,
+        merkle_tree::{
+            builder::MerkleTreeBuilder,
+            processor::{MerkleTreeProcessor, MerkleTreeProcessorMetrics},
+        },
         msg::{
+            cancellation::{CancelledBy, MessageCancellation},
+            events::MessageEventFilter,
             gas_payment::GasPaymentEnforcer,
             metadata::{BaseMetadataBuilder, IsmAwareAppContextClassifier},
+            round_trip::{
+                test::{bounce, round_trip_conf},
+                RoundTripTuple,
+            },
         },
         processor::Processor,
+        settings::{
+            matching_list::MatchingList, snapshot::ConfigSnapshot, MessageThrottleConf,
+            SupersessionMode, SupersessionRouteConf,
+        },
     };
 
     use super::*;
     use hyperlane_base::{
         db::{
-            test_utils, DbResult, HyperlaneRocksDB, InterchainGasExpenditureData,
+            test_utils, DbError, DbResult, HyperlaneRocksDB, InterchainGasExpenditureData,
             InterchainGasPaymentData,
         },
-        settings::{ChainConf, ChainConnectionConf, Settings},
+        settings::{ChainConf, ChainConnectionConf, ContractClientCache, Settings},
+        ExemplarRegistry, ValidatorReputations,
     };
     use hyperlane_core::{
-        test_utils::dummy_domain, GasPaymentKey, InterchainGasPayment, InterchainGasPaymentMeta,
-        MerkleTreeInsertion, PendingOperationStatus, H256,
+        accumulator::{incremental::IncrementalMerkle, TREE_DEPTH},
+        test_utils::dummy_domain,
+        ChainCommunicationError, GasPaymentKey, HyperlaneLogStore, Indexed, InterchainGasPayment,
+        InterchainGasPaymentMeta, LogMeta, MerkleTreeInsertion, PendingOperation,
+        PendingOperationResult, PendingOperationStatus, ReprepareReason, H256,
     };
     use hyperlane_test::mocks::{MockMailboxContract, MockValidatorAnnounceContract};
-    use prometheus::{IntCounter, Registry};
+    use prometheus::{histogram_opts, HistogramVec, IntCounter, IntGaugeVec, Registry};
     use tokio::{
         sync::{
             mpsc::{self, UnboundedReceiver},
@@ -432,10 +699,24 @@ mod test {
         }
     }
 
-    fn dummy_submission_metrics() -> MessageSubmissionMetrics {
+    pub fn dummy_submission_metrics() -> MessageSubmissionMetrics {
         MessageSubmissionMetrics {
             last_known_nonce: IntGauge::new("last_known_nonce_gauge", "help string").unwrap(),
             messages_processed: IntCounter::new("message_processed_gauge", "help string").unwrap(),
+            public_submissions: IntCounter::new("public_submissions", "help string").unwrap(),
+            private_submissions: IntCounter::new("private_submissions", "help string").unwrap(),
+            public_fallback_submissions: IntCounter::new(
+                "public_fallback_submissions",
+                "help string",
+            )
+            .unwrap(),
+            pickup_to_delivery: ExemplarRegistry::default()
+                .register(
+                    HistogramVec::new(histogram_opts!("pickup_to_delivery", "help string"), &[])
+                        .unwrap(),
+                    prometheus::DEFAULT_BUCKETS,
+                )
+                .with_label_values(&[]),
         }
     }
 
@@ -444,6 +725,8 @@ mod test {
             domain: domain.clone(),
             signer: Default::default(),
             reorg_period: Default::default(),
+            reorg_period_fallback: None,
+            finality_blocks: 1,
             addresses: Default::default(),
             connection: ChainConnectionConf::Ethereum(hyperlane_ethereum::ConnectionConf {
                 rpc_connection: hyperlane_ethereum::RpcConnectionConf::Http {
@@ -451,13 +734,25 @@ mod test {
                 },
                 transaction_overrides: Default::default(),
                 operation_batch: Default::default(),
+                private_submission: None,
+                get_logs_caps: Default::default(),
             }),
             metrics_conf: Default::default(),
             index: Default::default(),
+            additional_interchain_gas_paymasters: vec![],
+            merkle_tree_depth: TREE_DEPTH,
         }
     }
 
-    fn dummy_metadata_builder(
+    pub fn dummy_config() -> ConfigHandle {
+        ConfigHandle::new(ConfigSnapshot {
+            config_hash: "dummy_config_hash".to_owned(),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    pub fn dummy_metadata_builder(
         origin_domain: &HyperlaneDomain,
         destination_domain: &HyperlaneDomain,
         db: &HyperlaneRocksDB,
@@ -472,16 +767,21 @@ mod test {
             dummy_chain_conf(destination_domain),
         );
         let destination_chain_conf = settings.chain_setup(destination_domain).unwrap();
-        let core_metrics = CoreMetrics::new("dummy_relayer", 37582, Registry::new()).unwrap();
+        let core_metrics =
+            Arc::new(CoreMetrics::new("dummy_relayer", 37582, Registry::new()).unwrap());
         BaseMetadataBuilder::new(
             origin_domain.clone(),
             destination_chain_conf.clone(),
             Arc::new(RwLock::new(MerkleTreeBuilder::new())),
             Arc::new(MockValidatorAnnounceContract::default()),
             false,
-            Arc::new(core_metrics),
+            core_metrics.clone(),
             db.clone(),
             IsmAwareAppContextClassifier::new(Arc::new(MockMailboxContract::default()), vec![]),
+            Arc::new(ContractClientCache::new(core_metrics.clone())),
+            ValidatorReputations::new(db.clone(), core_metrics),
+            vec![],
+            Default::default(),
         )
     }
 
@@ -490,6 +790,25 @@ mod test {
         destination_domain: &HyperlaneDomain,
         db: &HyperlaneRocksDB,
     ) -> (MessageProcessor, UnboundedReceiver<QueueOperation>) {
+        let (message_processor, receive_channel, _) = dummy_message_processor_with_intake(
+            origin_domain,
+            destination_domain,
+            db,
+            Default::default(),
+        );
+        (message_processor, receive_channel)
+    }
+
+    fn dummy_message_processor_with_intake(
+        origin_domain: &HyperlaneDomain,
+        destination_domain: &HyperlaneDomain,
+        db: &HyperlaneRocksDB,
+        throttle_conf: MessageThrottleConf,
+    ) -> (
+        MessageProcessor,
+        UnboundedReceiver<QueueOperation>,
+        UnboundedSender<HyperlaneMessage>,
+    ) {
         let base_metadata_builder = dummy_metadata_builder(origin_domain, destination_domain, db);
         let message_context = Arc::new(MessageContext {
             destination_mailbox: Arc::new(MockMailboxContract::default()),
@@ -498,21 +817,47 @@ mod test {
             origin_gas_payment_enforcer: Arc::new(GasPaymentEnforcer::new([], db.clone())),
             transaction_gas_limit: Default::default(),
             metrics: dummy_submission_metrics(),
+            config: dummy_config(),
+            circuit_breaker: None,
+            gas_price_ceiling: None,
+            shadow: None,
+            metadata_builds: None,
+            latency_budgets: None,
+            events: Default::default(),
         });
 
         let (send_channel, receive_channel) = mpsc::unbounded_channel::<QueueOperation>();
+        let (intake_sender, intake_receiver) = mpsc::unbounded_channel::<HyperlaneMessage>();
         (
             MessageProcessor::new(
                 db.clone(),
-                Default::default(),
-                Default::default(),
-                Default::default(),
+                message_context.config.clone(),
                 dummy_processor_metrics(origin_domain.id()),
                 HashMap::from([(destination_domain.id(), send_channel)]),
                 HashMap::from([(destination_domain.id(), message_context)]),
+                intake_receiver,
+                BurstThrottle::new(
+                    throttle_conf,
+                    origin_domain.name().to_owned(),
+                    IntGaugeVec::new(
+                        prometheus::opts!("dummy_throttled_message_sources", "help string"),
+                        &["origin", "kind", "id"],
+                    )
+                    .unwrap(),
+                ),
+                DeliveryStart::default(),
                 vec![],
+                dummy_corruption_tolerance(0),
             ),
             receive_channel,
+            intake_sender,
+        )
+    }
+
+    fn dummy_corruption_tolerance(tolerance: u32) -> CorruptionTolerance {
+        CorruptionTolerance::new(
+            tolerance,
+            IntCounter::new("dummy_db_corrupted_records", "help string").unwrap(),
         )
     }
 
@@ -795,6 +1140,540 @@ mod test {
         .await;
     }
 
+    #[tokio::test(start_paused = true)]
+    async fn test_indexed_message_is_processed_without_waiting_for_poll() {
+        test_utils::run_test_db(|db| async move {
+            let origin_domain = dummy_domain(0, "dummy_origin_domain");
+            let destination_domain = dummy_domain(1, "dummy_destination_domain");
+            let db = HyperlaneRocksDB::new(&origin_domain, db);
+            let (mut message_processor, mut receive_channel, intake) =
+                dummy_message_processor_with_intake(
+                    &origin_domain,
+                    &destination_domain,
+                    &db,
+                    Default::default(),
+                );
+
+            let message = dummy_hyperlane_message(&destination_domain, 0);
+            let start = tokio::time::Instant::now();
+            let index_message = async {
+                sleep(Duration::from_millis(100)).await;
+                add_db_entry(&db, &message, 0);
+                intake.send(message.clone()).unwrap();
+            };
+            let (tick_result, _) = tokio::join!(message_processor.tick(), index_message);
+            tick_result.unwrap();
+
+            let operation = receive_channel.try_recv().unwrap();
+            assert_eq!(operation.id(), message.id());
+            assert!(start.elapsed() < MESSAGE_POLL_INTERVAL);
+        })
+        .await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_message_handed_over_twice_is_processed_once() {
+        test_utils::run_test_db(|db| async move {
+            let origin_domain = dummy_domain(0, "dummy_origin_domain");
+            let destination_domain = dummy_domain(1, "dummy_destination_domain");
+            let db = HyperlaneRocksDB::new(&origin_domain, db);
+            let (mut message_processor, mut receive_channel, intake) =
+                dummy_message_processor_with_intake(
+                    &origin_domain,
+                    &destination_domain,
+                    &db,
+                    Default::default(),
+                );
+
+            // Found by polling the db first, then handed over by the indexer
+            let polled_message = dummy_hyperlane_message(&destination_domain, 0);
+            add_db_entry(&db, &polled_message, 0);
+            message_processor.tick().await.unwrap();
+            intake.send(polled_message.clone()).unwrap();
+
+            // Handed over by the indexer twice
+            let indexed_message = dummy_hyperlane_message(&destination_domain, 1);
+            add_db_entry(&db, &indexed_message, 0);
+            intake.send(indexed_message.clone()).unwrap();
+            intake.send(indexed_message.clone()).unwrap();
+
+            for _ in 0..3 {
+                message_processor.tick().await.unwrap();
+            }
+
+            let mut operation_ids = vec![];
+            while let Ok(operation) = receive_channel.try_recv() {
+                operation_ids.push(operation.id());
+            }
+            assert_eq!(
+                operation_ids,
+                vec![polled_message.id(), indexed_message.id()]
+            );
+        })
+        .await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_flood_is_deprioritized_and_still_drains() {
+        test_utils::run_test_db(|db| async move {
+            let origin_domain = dummy_domain(0, "dummy_origin_domain");
+            let destination_domain = dummy_domain(1, "dummy_destination_domain");
+            let db = HyperlaneRocksDB::new(&origin_domain, db);
+            let throttle_conf = MessageThrottleConf {
+                window: Duration::from_secs(10),
+                sender_burst: Some(3),
+                route_burst: None,
+                throttled_budget: 2,
+            };
+            let (mut message_processor, mut receive_channel, _intake) =
+                dummy_message_processor_with_intake(
+                    &origin_domain,
+                    &destination_domain,
+                    &db,
+                    throttle_conf,
+                );
+
+            // A spammer dispatches 4 out of every 5 messages
+            let flood_sender = H256::from_low_u64_be(1);
+            let messages: Vec<HyperlaneMessage> = (0..25)
+                .map(|nonce| HyperlaneMessage {
+                    sender: if nonce % 5 == 4 {
+                        H256::from_low_u64_be(2)
+                    } else {
+                        flood_sender
+                    },
+                    ..dummy_hyperlane_message(&destination_domain, nonce)
+                })
+                .collect();
+            messages.iter().for_each(|msg| add_db_entry(&db, msg, 0));
+
+            // Normal messages are handed to the submitter as soon as they're read
+            for msg in &messages {
+                message_processor.tick().await.unwrap();
+                let operation = receive_channel.try_recv().ok().map(|op| op.id());
+                if msg.sender != flood_sender {
+                    assert_eq!(operation, Some(msg.id()));
+                }
+            }
+
+            // Deferred flood messages only go through within the budget until
+            // the burst leaves the window, then all of them drain
+            let start = tokio::time::Instant::now();
+            let mut drained = vec![];
+            while drained.len() < 17 {
+                message_processor.tick().await.unwrap();
+                if let Ok(operation) = receive_channel.try_recv() {
+                    drained.push((operation.id(), start.elapsed()));
+                }
+                assert!(start.elapsed() < Duration::from_secs(60));
+            }
+            let released_in_burst = drained
+                .iter()
+                .filter(|(_, elapsed)| *elapsed < Duration::from_secs(10))
+                .count();
+            assert_eq!(released_in_burst, 2);
+            let flood_ids: HashSet<H256> = messages
+                .iter()
+                .filter(|msg| msg.sender == flood_sender)
+                .skip(3)
+                .map(|msg| msg.id())
+                .collect();
+            assert_eq!(
+                drained.iter().map(|(id, _)| *id).collect::<HashSet<_>>(),
+                flood_ids
+            );
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_skipped_message_decision_is_explained() {
+        test_utils::run_test_db(|db| async move {
+            let origin_domain = dummy_domain(0, "dummy_origin_domain");
+            let destination_domain = dummy_domain(1, "dummy_destination_domain");
+            let unserviced_domain = dummy_domain(2, "dummy_unserviced_domain");
+            let db = HyperlaneRocksDB::new(&origin_domain, db);
+            let message = dummy_hyperlane_message(&unserviced_domain, 0);
+            add_db_entry(&db, &message, 0);
+
+            let (mut message_processor, mut receive_channel) =
+                dummy_message_processor(&origin_domain, &destination_domain, &db);
+            message_processor.tick().await.unwrap();
+            assert!(receive_channel.try_recv().is_err());
+
+            let explanation = explain(&db, message.id(), None).unwrap();
+            assert!(explanation.contains("Config hash: dummy_config_hash"));
+            assert!(explanation.contains("Destination relayed to: no"));
+            assert!(explanation.contains("Decision: skip, destination isn't relayed to"));
+            assert!(explanation.ends_with("Replayed with the current code: same decision"));
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_messages_before_late_start_block_are_excluded_but_in_the_tree() {
+        test_utils::run_test_db(|db| async move {
+            let origin_domain = dummy_domain(0, "dummy_origin_domain");
+            let destination_domain = dummy_domain(1, "dummy_destination_domain");
+            let db = HyperlaneRocksDB::new(&origin_domain, db);
+            // Messages dispatched at blocks 10 to 50, with the start block at 35
+            let messages: Vec<_> = (0..5)
+                .map(|nonce| dummy_hyperlane_message(&destination_domain, nonce))
+                .collect();
+            let mut onchain_tree = IncrementalMerkle::default();
+            for (message, block) in messages.iter().zip((10..).step_by(10)) {
+                db.store_message(message, block).unwrap();
+                onchain_tree.ingest(message.id());
+                let insertion = MerkleTreeInsertion::new(message.nonce, message.id());
+                db.store_logs(&[(Indexed::new(insertion), LogMeta::random())])
+                    .await
+                    .unwrap();
+            }
+            let delivery_start = DeliveryStart::new(35);
+            assert!(!delivery_start.has_leaf_gap(&db));
+
+            // The tree includes the leaves of the excluded messages
+            let core_metrics = CoreMetrics::new("test", 0, Registry::new()).unwrap();
+            let prover_sync = Arc::new(RwLock::new(MerkleTreeBuilder::new()));
+            let mut tree_processor = MerkleTreeProcessor::new(
+                db.clone(),
+                MerkleTreeProcessorMetrics::new(&core_metrics, &origin_domain),
+                prover_sync.clone(),
+                Default::default(),
+                0.75,
+                dummy_corruption_tolerance(0),
+            );
+            while prover_sync.read().await.count() < messages.len() as u32 {
+                tree_processor.tick().await.unwrap();
+            }
+            assert_eq!(
+                prover_sync.read().await.snapshot().root(),
+                onchain_tree.root()
+            );
+
+            let (mut message_processor, mut receive_channel) =
+                dummy_message_processor(&origin_domain, &destination_domain, &db);
+            message_processor.delivery_start = delivery_start;
+            for _ in &messages {
+                message_processor.tick().await.unwrap();
+            }
+            let pending: Vec<_> = std::iter::from_fn(|| receive_channel.try_recv().ok())
+                .map(|operation| operation.id())
+                .collect();
+            assert_eq!(pending, vec![messages[3].id(), messages[4].id()]);
+            for message in &messages[..3] {
+                let explanation = explain(&db, message.id(), None).unwrap();
+                assert!(explanation
+                    .contains("Decision: skip, dispatched before the delivery start block"));
+            }
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_only_the_newest_message_of_a_superseding_route_is_delivered() {
+        test_utils::run_test_db(|db| async move {
+            let origin_domain = dummy_domain(0, "dummy_origin_domain");
+            let destination_domain = dummy_domain(1, "dummy_destination_domain");
+            let db = HyperlaneRocksDB::new(&origin_domain, db);
+            // A message whose delivery was already broadcast, followed by
+            // three generations of the same state
+            let messages: Vec<_> = (0..4)
+                .map(|nonce| dummy_hyperlane_message(&destination_domain, nonce))
+                .collect();
+            for message in &messages {
+                add_db_entry(&db, message, 0);
+            }
+            db.store_message_broadcast(&messages[0].id()).unwrap();
+
+            let (mut message_processor, mut receive_channel) =
+                dummy_message_processor(&origin_domain, &destination_domain, &db);
+            message_processor.config = ConfigHandle::new(ConfigSnapshot {
+                supersession_routes: vec![SupersessionRouteConf {
+                    matching_list: MatchingList::with_destination_domain(destination_domain.id()),
+                    supersession: SupersessionMode::LatestPerSenderRecipient,
+                    body_prefix_len: 0,
+                }],
+                ..Default::default()
+            })
+            .unwrap();
+            for _ in &messages {
+                message_processor.tick().await.unwrap();
+            }
+            let mut operations: HashMap<_, _> =
+                std::iter::from_fn(|| receive_channel.try_recv().ok())
+                    .map(|operation| (operation.id(), operation))
+                    .collect();
+            assert_eq!(operations.len(), messages.len());
+
+            // The superseded generations are terminal, and dropped before
+            // being delivered
+            for (message, newer) in messages[1..3].iter().zip(&messages[2..]) {
+                let mut operation = operations.remove(&message.id()).unwrap();
+                assert!(matches!(
+                    operation.prepare().await,
+                    PendingOperationResult::Drop
+                ));
+                let cancellation = db
+                    .retrieve_message_cancellation::<MessageCancellation>(&message.id())
+                    .unwrap()
+                    .unwrap();
+                assert_eq!(
+                    cancellation.cancelled_by,
+                    CancelledBy::Superseded {
+                        newer_message_id: newer.id()
+                    }
+                );
+                assert_eq!(
+                    db.retrieve_processed_by_nonce(&message.nonce).unwrap(),
+                    Some(true)
+                );
+                let explanation = explain(&db, message.id(), None).unwrap();
+                assert!(explanation.contains("Decision: skip, superseded by a newer message"));
+            }
+            // The broadcast message is left alone, and the newest one is
+            // delivered
+            for message in [&messages[0], &messages[3]] {
+                assert!(operations.contains_key(&message.id()));
+                assert_eq!(
+                    db.retrieve_message_cancellation::<MessageCancellation>(&message.id())
+                        .unwrap(),
+                    None
+                );
+            }
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_a_slow_ping_pong_is_parked_once_it_trips_until_cleared() {
+        test_utils::run_test_db(|db| async move {
+            let domain_a = dummy_domain(1, "dummy_domain_a");
+            let domain_b = dummy_domain(2, "dummy_domain_b");
+            let db_a = HyperlaneRocksDB::new(&domain_a, db.clone());
+            let db_b = HyperlaneRocksDB::new(&domain_b, db);
+            let guard = Arc::new(RoundTripGuard::new(round_trip_conf(3), 100, db_a.clone()));
+            let (mut processor_a, mut operations_a) =
+                dummy_message_processor(&domain_a, &domain_b, &db_a);
+            let (mut processor_b, mut operations_b) =
+                dummy_message_processor(&domain_b, &domain_a, &db_b);
+            processor_a.round_trips = vec![guard.clone()];
+            processor_b.round_trips = vec![guard.clone()];
+
+            // Each app answers every delivery with the very same message
+            let mut parked = vec![];
+            for nonce in 0..3 {
+                let there = bounce(nonce, false, b"ping");
+                add_db_entry(&db_a, &there, 0);
+                processor_a.tick().await.unwrap();
+                let back = bounce(nonce, true, b"ping");
+                add_db_entry(&db_b, &back, 0);
+                processor_b.tick().await.unwrap();
+                parked.extend([(there, &db_a), (back, &db_b)]);
+            }
+            // The first three crossings are relayed, the fourth trips the
+            // tuple and every later one is parked too
+            let relayed = |operations: &mut UnboundedReceiver<QueueOperation>| {
+                std::iter::from_fn(|| operations.try_recv().ok())
+                    .map(|operation| operation.id())
+                    .collect::<Vec<_>>()
+            };
+            let relayed_a = relayed(&mut operations_a);
+            let relayed_b = relayed(&mut operations_b);
+            let (relayed_messages, parked): (Vec<_>, Vec<_>) = parked
+                .into_iter()
+                .partition(|(m, _)| relayed_a.contains(&m.id()) || relayed_b.contains(&m.id()));
+            assert_eq!(relayed_messages.len(), 3);
+            assert_eq!(parked.len(), 3);
+            assert_eq!(parked[0].0, bounce(1, true, b"ping"));
+
+            let tuple_id = RoundTripTuple::of(&parked[0].0).id();
+            for (message, db) in &parked {
+                let cancellation = db
+                    .retrieve_message_cancellation::<MessageCancellation>(&message.id())
+                    .unwrap()
+                    .unwrap();
+                assert_eq!(
+                    cancellation.cancelled_by,
+                    CancelledBy::RoundTripLimit { tuple_id }
+                );
+                let explanation = explain(db, message.id(), None).unwrap();
+                assert!(
+                    explanation.contains("Decision: skip, crossed its route pair too many times")
+                );
+            }
+            let tripped = guard.tripped();
+            assert_eq!(tripped.len(), 1);
+            assert_eq!(tripped[0].tuple.id(), tuple_id);
+
+            // Once an operator clears the tuple, its messages are relayed
+            // again
+            assert!(guard.clear(&tuple_id).is_some());
+            assert!(guard.tripped().is_empty());
+            let next = bounce(3, false, b"ping");
+            add_db_entry(&db_a, &next, 0);
+            processor_a.tick().await.unwrap();
+            assert_eq!(relayed(&mut operations_a), vec![next.id()]);
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_prepare_attempts_record_explainable_decisions() {
+        test_utils::run_test_db(|db| async move {
+            let origin_domain = dummy_domain(0, "dummy_origin_domain");
+            let destination_domain = dummy_domain(1, "dummy_destination_domain");
+            let db = HyperlaneRocksDB::new(&origin_domain, db);
+            let message = dummy_hyperlane_message(&destination_domain, 0);
+
+            // The delivery check fails on the first attempt and finds the
+            // message delivered on the second one
+            let calls = Arc::new(AtomicU32::new(0));
+            let mut mailbox = MockMailboxContract::default();
+            mailbox.expect__delivered().returning(move |_| {
+                match calls.fetch_add(1, Ordering::SeqCst) {
+                    0 => Err(ChainCommunicationError::from_other_str("rpc down")),
+                    _ => Ok(true),
+                }
+            });
+            let message_context = Arc::new(MessageContext {
+                destination_mailbox: Arc::new(mailbox),
+                origin_db: db.clone(),
+                metadata_builder: Arc::new(dummy_metadata_builder(
+                    &origin_domain,
+                    &destination_domain,
+                    &db,
+                )),
+                origin_gas_payment_enforcer: Arc::new(GasPaymentEnforcer::new([], db.clone())),
+                transaction_gas_limit: Default::default(),
+                metrics: dummy_submission_metrics(),
+                config: dummy_config(),
+                circuit_breaker: None,
+                gas_price_ceiling: None,
+                shadow: None,
+                metadata_builds: None,
+                latency_budgets: None,
+                events: Default::default(),
+            });
+            let mut pending_message = PendingMessage::from_persisted_retries(
+                message.clone(),
+                message_context,
+                None,
+                ListMembership::new(true, false, None, true),
+            );
+
+            assert!(matches!(
+                pending_message.prepare().await,
+                PendingOperationResult::Reprepare(ReprepareReason::ErrorCheckingDeliveryStatus)
+            ));
+            pending_message.reset_attempts();
+            assert!(matches!(
+                pending_message.prepare().await,
+                PendingOperationResult::Confirm(_)
+            ));
+
+            let first = explain(&db, message.id(), Some(0)).unwrap();
+            assert!(first.contains("Delivered on destination: failed: rpc down"));
+            assert!(first.contains("Recipient is a contract: not checked"));
+            assert!(first
+                .contains("Decision: retry later: Error checking message delivery status"));
+            assert!(first.ends_with("Replayed with the current code: same decision"));
+
+            let latest = explain(&db, message.id(), None).unwrap();
+            assert!(latest.contains("attempt 1 at unix time"));
+            assert!(latest.contains("Delivered on destination: yes"));
+            assert!(latest.contains("Decision: confirm, already delivered"));
+            assert!(latest.ends_with("Replayed with the current code: same decision"));
+
+            assert!(explain(&db, message.id(), Some(2)).is_err());
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_message_lifecycle_events_are_published() {
+        test_utils::run_test_db(|db| async move {
+            let origin_domain = dummy_domain(0, "dummy_origin_domain");
+            let destination_domain = dummy_domain(1, "dummy_destination_domain");
+            let db = HyperlaneRocksDB::new(&origin_domain, db);
+            let message = dummy_hyperlane_message(&destination_domain, 0);
+            let (mut message_processor, _receive_channel) =
+                dummy_message_processor(&origin_domain, &destination_domain, &db);
+            let events = message_processor.destination_ctxs[&destination_domain.id()]
+                .events
+                .clone();
+            let mut stream = events.subscribe(
+                MessageEventFilter::default()
+                    .with_route(origin_domain.id(), destination_domain.id()),
+            );
+            let mut unrelated = events.subscribe(MessageEventFilter::default().with_route(0, 2));
+
+            add_db_entry(&db, &message, 0);
+            message_processor.tick().await.unwrap();
+
+            // The delivery check fails on the first attempt and finds the
+            // message delivered afterwards
+            let calls = Arc::new(AtomicU32::new(0));
+            let mut mailbox = MockMailboxContract::default();
+            mailbox.expect__delivered().returning(move |_| {
+                match calls.fetch_add(1, Ordering::SeqCst) {
+                    0 => Err(ChainCommunicationError::from_other_str("rpc down")),
+                    _ => Ok(true),
+                }
+            });
+            let message_context = Arc::new(MessageContext {
+                destination_mailbox: Arc::new(mailbox),
+                origin_db: db.clone(),
+                metadata_builder: Arc::new(dummy_metadata_builder(
+                    &origin_domain,
+                    &destination_domain,
+                    &db,
+                )),
+                origin_gas_payment_enforcer: Arc::new(GasPaymentEnforcer::new([], db.clone())),
+                transaction_gas_limit: Default::default(),
+                metrics: dummy_submission_metrics(),
+                config: dummy_config(),
+                circuit_breaker: None,
+                gas_price_ceiling: None,
+                shadow: None,
+                metadata_builds: None,
+                latency_budgets: None,
+                events: events.clone(),
+            });
+            let mut pending_message = PendingMessage::from_persisted_retries(
+                message.clone(),
+                message_context,
+                None,
+                ListMembership::new(true, false, None, true),
+            );
+            pending_message.prepare().await;
+            pending_message.reset_attempts();
+            pending_message.prepare().await;
+            pending_message.reset_attempts();
+            assert!(matches!(
+                pending_message.confirm().await,
+                PendingOperationResult::Success
+            ));
+
+            let published: Vec<_> = stream.by_ref().take(3).collect().await;
+            assert!(published.iter().all(|e| e.message_id == message.id()));
+            assert_eq!(
+                published.into_iter().map(|e| e.kind).collect::<Vec<_>>(),
+                vec![
+                    MessageEventKind::Indexed,
+                    MessageEventKind::Parked {
+                        reason: ReprepareReason::ErrorCheckingDeliveryStatus.to_string()
+                    },
+                    // Delivered by someone else
+                    MessageEventKind::Delivered { tx: None },
+                ]
+            );
+            assert_eq!(stream.dropped_events(), 0);
+            assert!(unrelated.next().now_or_never().is_none());
+        })
+        .await;
+    }
+
     #[tokio::test]
     async fn test_forward_backward_iterator() {
         let mut mock_db = MockDb::new();
@@ -844,7 +1723,7 @@ mod test {
 
         let mut messages = vec![];
         while let Some(msg) = forward_backward_iterator
-            .try_get_next_message(&dummy_metrics)
+            .try_get_next_message(&dummy_metrics, &mut dummy_corruption_tolerance(0))
             .await
             .unwrap()
         {
@@ -867,4 +1746,65 @@ mod test {
             Some(MAX_ONCHAIN_NONCE + 1)
         );
     }
+
+    /// Scan messages 0 to 4, starting from 2, of which 1 and 3 are corrupted
+    async fn scan_with_corrupted_messages(tolerance: &mut CorruptionTolerance) -> Result<Vec<u32>> {
+        const CORRUPTED: [u32; 2] = [1, 3];
+        let mut mock_db = MockDb::new();
+        mock_db
+            .expect_domain()
+            .return_const(dummy_domain(0, "dummy_domain"));
+        mock_db
+            .expect_retrieve_highest_seen_message_nonce()
+            .returning(|| Ok(Some(2)));
+        mock_db
+            .expect_retrieve_message_by_nonce()
+            .returning(|nonce| match nonce {
+                nonce if CORRUPTED.contains(&nonce) => Err(DbError::corruption(
+                    b"message_",
+                    &nonce.to_be_bytes(),
+                    "failed to fill whole buffer",
+                )),
+                0..=4 => Ok(Some(dummy_hyperlane_message(
+                    &dummy_domain(1, "dummy_domain"),
+                    nonce,
+                ))),
+                _ => Ok(None),
+            });
+        mock_db
+            .expect_retrieve_processed_by_nonce()
+            .returning(|_| Ok(Some(false)));
+        let dummy_metrics = dummy_processor_metrics(0);
+        let mut iterator = ForwardBackwardIterator::new(Arc::new(mock_db));
+
+        let mut messages = vec![];
+        while let Some(msg) = iterator
+            .try_get_next_message(&dummy_metrics, tolerance)
+            .await?
+        {
+            messages.push(msg.nonce);
+        }
+        Ok(messages)
+    }
+
+    #[tokio::test]
+    async fn test_forward_backward_iterator_skips_corrupted_messages() {
+        // The corrupted messages are skipped in both directions
+        let mut tolerance = dummy_corruption_tolerance(2);
+        assert_eq!(
+            scan_with_corrupted_messages(&mut tolerance).await.unwrap(),
+            vec![2, 4, 0]
+        );
+        assert_eq!(tolerance.skipped(), 2);
+
+        // Past the tolerance, the scan is aborted
+        let mut tolerance = dummy_corruption_tolerance(1);
+        let err = scan_with_corrupted_messages(&mut tolerance)
+            .await
+            .unwrap_err();
+        assert!(err
+            .downcast_ref::<DbError>()
+            .is_some_and(|err| err.is_corruption()));
+        assert_eq!(tolerance.skipped(), 1);
+    }
 }
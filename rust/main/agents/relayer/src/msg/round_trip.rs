@@ -0,0 +1,373 @@
+//! Replay protection for apps that dispatch a message while handling one.
+//!
+//! Such an app relaying its own responses can, through a bug, answer a
+//! message with an identical one, which is then delivered back to it, and so
+//! on until someone runs out of funds. Loops that are too slow to stand out
+//! by their rate go on for as long.
+//!
+//! On the route pairs configured for it, messages are grouped into tuples of
+//! the two apps and the hash of the body, whichever direction they cross the
+//! pair in. Once a tuple crossed more than the configured number of times
+//! within the window, it trips: its message is cancelled, as are all the
+//! later ones, until an operator clears it.
+//!
+//! The tuples of a pair are persisted to the database of its first domain,
+//! and bounded in number.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    io::{Read, Write},
+    sync::Mutex,
+};
+
+use ethers::utils::keccak256;
+use hyperlane_base::db::HyperlaneRocksDB;
+use hyperlane_core::{Decode, Encode, HyperlaneMessage, HyperlaneProtocolError, H256};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::settings::RoundTripRouteConf;
+
+/// An app on a domain, one end of a tuple
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct RoundTripApp {
+    pub domain: u32,
+    pub address: H256,
+}
+
+/// The messages between the same two apps with the same body, in either
+/// direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct RoundTripTuple {
+    /// The sender and recipient, ordered so that both directions have the
+    /// same tuple
+    pub apps: [RoundTripApp; 2],
+    pub body_hash: H256,
+}
+
+impl RoundTripTuple {
+    /// The tuple of `message`
+    pub fn of(message: &HyperlaneMessage) -> Self {
+        let sender = RoundTripApp {
+            domain: message.origin,
+            address: message.sender,
+        };
+        let recipient = RoundTripApp {
+            domain: message.destination,
+            address: message.recipient,
+        };
+        let mut apps = [sender, recipient];
+        apps.sort();
+        Self {
+            apps,
+            body_hash: keccak256(&message.body).into(),
+        }
+    }
+
+    /// Identifies the tuple, e.g. to clear it
+    pub fn id(&self) -> H256 {
+        let mut bytes = vec![];
+        for app in &self.apps {
+            bytes.extend_from_slice(&app.domain.to_be_bytes());
+            bytes.extend_from_slice(app.address.as_bytes());
+        }
+        bytes.extend_from_slice(self.body_hash.as_bytes());
+        keccak256(bytes).into()
+    }
+}
+
+/// A message of a tuple crossing its route pair
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoundTripCrossing {
+    pub message_id: H256,
+    /// Unix timestamp, in seconds, the message was processed at
+    pub at: u64,
+}
+
+/// The crossings of a tuple, as persisted and reported by the admin endpoint
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoundTripRecord {
+    pub tuple: RoundTripTuple,
+    /// Crossings within the window as of the latest one, oldest first
+    pub crossings: VecDeque<RoundTripCrossing>,
+    /// Unix timestamp, in seconds, the tuple tripped at, if it did
+    pub tripped_at: Option<u64>,
+    /// Unix timestamp, in seconds, a message of the tuple was last processed
+    /// at
+    pub last_seen_at: u64,
+}
+
+/// The tuples tracked on a route pair
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+struct RoundTripState(Vec<RoundTripRecord>);
+
+impl Encode for RoundTripState {
+    fn write_to<W>(&self, writer: &mut W) -> std::io::Result<usize>
+    where
+        W: Write,
+    {
+        let serialized = serde_json::to_vec(self)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?;
+        writer.write_all(&serialized)?;
+        Ok(serialized.len())
+    }
+}
+
+impl Decode for RoundTripState {
+    fn read_from<R>(reader: &mut R) -> Result<Self, HyperlaneProtocolError>
+    where
+        R: Read,
+        Self: Sized,
+    {
+        serde_json::from_reader(reader).map_err(|err| {
+            HyperlaneProtocolError::IoError(std::io::Error::new(std::io::ErrorKind::Other, err))
+        })
+    }
+}
+
+/// Whether a message may be relayed, as far as round trips go
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoundTrip {
+    Allowed,
+    /// The tuple of the message tripped, the message must be parked
+    Tripped {
+        tuple_id: H256,
+    },
+}
+
+/// Counts the crossings of the tuples of a route pair, tripping the tuples
+/// which cross too many times.
+///
+/// The guard is shared by the message processors of both domains of the
+/// pair.
+#[derive(Debug)]
+pub struct RoundTripGuard {
+    conf: RoundTripRouteConf,
+    max_tuples: usize,
+    /// Database of the first domain of the pair, the tuples are persisted to
+    db: HyperlaneRocksDB,
+    /// Records by tuple id
+    tuples: Mutex<HashMap<H256, RoundTripRecord>>,
+}
+
+impl RoundTripGuard {
+    /// Create the guard of a route pair, resuming its persisted tuples
+    pub fn new(conf: RoundTripRouteConf, max_tuples: usize, db: HyperlaneRocksDB) -> Self {
+        let persisted = db
+            .retrieve_round_trip_state::<RoundTripState>(&conf.domains[1])
+            .unwrap_or_else(|err| {
+                warn!(?err, domains = ?conf.domains, "Failed to read the round trip state");
+                None
+            })
+            .unwrap_or_default();
+        let tuples = persisted
+            .0
+            .into_iter()
+            .map(|record| (record.tuple.id(), record))
+            .collect();
+        Self {
+            conf,
+            max_tuples,
+            db,
+            tuples: Mutex::new(tuples),
+        }
+    }
+
+    /// Ids of the two domains of the pair
+    pub fn domains(&self) -> [u32; 2] {
+        self.conf.domains
+    }
+
+    /// Whether `message` crosses the pair, in either direction
+    pub fn covers(&self, message: &HyperlaneMessage) -> bool {
+        let [a, b] = self.conf.domains;
+        let route = (message.origin, message.destination);
+        route == (a, b) || route == (b, a)
+    }
+
+    /// Count `message` as a crossing of its tuple, unless it was already
+    /// counted, and tell whether the tuple tripped. Crossings older than the
+    /// window are forgotten.
+    pub fn observe(&self, message: &HyperlaneMessage, now: u64) -> RoundTrip {
+        let tuple = RoundTripTuple::of(message);
+        let tuple_id = tuple.id();
+        let message_id = message.id();
+        let mut tuples = self.tuples.lock().unwrap();
+        let record = tuples.entry(tuple_id).or_insert_with(|| RoundTripRecord {
+            tuple,
+            crossings: VecDeque::new(),
+            tripped_at: None,
+            last_seen_at: now,
+        });
+        record.last_seen_at = now;
+        let counted = record
+            .crossings
+            .iter()
+            .any(|crossing| crossing.message_id == message_id);
+        if record.tripped_at.is_none() && !counted {
+            let window_start = now.saturating_sub(self.conf.window.as_secs());
+            while matches!(record.crossings.front(), Some(crossing) if crossing.at < window_start) {
+                record.crossings.pop_front();
+            }
+            record.crossings.push_back(RoundTripCrossing {
+                message_id,
+                at: now,
+            });
+            if record.crossings.len() > self.conf.max_round_trips as usize {
+                record.tripped_at = Some(now);
+                warn!(
+                    ?tuple_id,
+                    ?message_id,
+                    crossings = record.crossings.len(),
+                    window_secs = self.conf.window.as_secs(),
+                    "The same message crossed its route pair too many times, parking its tuple"
+                );
+            }
+        }
+        let round_trip = match record.tripped_at {
+            Some(_) => RoundTrip::Tripped { tuple_id },
+            None => RoundTrip::Allowed,
+        };
+        self.evict(&mut tuples, tuple_id);
+        self.persist(&tuples);
+        round_trip
+    }
+
+    /// The tuples which tripped and weren't cleared yet, earliest first
+    pub fn tripped(&self) -> Vec<RoundTripRecord> {
+        let tuples = self.tuples.lock().unwrap();
+        let mut tripped: Vec<_> = tuples
+            .values()
+            .filter(|record| record.tripped_at.is_some())
+            .cloned()
+            .collect();
+        tripped.sort_by_key(|record| record.tripped_at);
+        tripped
+    }
+
+    /// Forget a tuple, so that its messages are relayed again. Returns its
+    /// record, if it was tracked.
+    pub fn clear(&self, tuple_id: &H256) -> Option<RoundTripRecord> {
+        let mut tuples = self.tuples.lock().unwrap();
+        let cleared = tuples.remove(tuple_id)?;
+        self.persist(&tuples);
+        Some(cleared)
+    }
+
+    /// Forget the tuples which crossed least recently beyond the bound, the
+    /// tripped ones last, other than the tuple just observed
+    fn evict(&self, tuples: &mut HashMap<H256, RoundTripRecord>, observed: H256) {
+        while tuples.len() > self.max_tuples {
+            let evicted = tuples
+                .iter()
+                .filter(|(id, _)| **id != observed)
+                .min_by_key(|(_, record)| (record.tripped_at.is_some(), record.last_seen_at))
+                .map(|(id, _)| *id);
+            match evicted {
+                Some(id) => tuples.remove(&id),
+                None => break,
+            };
+        }
+    }
+
+    fn persist(&self, tuples: &HashMap<H256, RoundTripRecord>) {
+        let state = RoundTripState(tuples.values().cloned().collect());
+        if let Err(err) = self
+            .db
+            .store_round_trip_state(&self.conf.domains[1], &state)
+        {
+            warn!(?err, domains = ?self.conf.domains, "Failed to persist the round trip state");
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use std::time::Duration;
+
+    use hyperlane_base::db::test_utils;
+    use hyperlane_core::{HyperlaneDomain, KnownHyperlaneDomain};
+
+    use super::*;
+
+    pub(crate) fn round_trip_conf(max_round_trips: u32) -> RoundTripRouteConf {
+        RoundTripRouteConf {
+            domains: [1, 2],
+            max_round_trips,
+            window: Duration::from_secs(3600),
+        }
+    }
+
+    /// A message of app `a` on domain 1 to app `b` on domain 2, or back
+    pub(crate) fn bounce(nonce: u32, back: bool, body: &[u8]) -> HyperlaneMessage {
+        let (a, b) = (H256::repeat_byte(0xa), H256::repeat_byte(0xb));
+        let ((origin, sender), (destination, recipient)) = if back {
+            ((2, b), (1, a))
+        } else {
+            ((1, a), (2, b))
+        };
+        HyperlaneMessage {
+            nonce,
+            origin,
+            sender,
+            destination,
+            recipient,
+            body: body.to_vec(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_both_directions_share_a_tuple() {
+        let there = RoundTripTuple::of(&bounce(0, false, b"ping"));
+        assert_eq!(there, RoundTripTuple::of(&bounce(1, true, b"ping")));
+        assert_ne!(there, RoundTripTuple::of(&bounce(1, true, b"pong")));
+        assert_ne!(
+            there.id(),
+            RoundTripTuple::of(&bounce(1, true, b"pong")).id()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_crossings_out_of_the_window_are_forgotten_and_tuples_are_bounded() {
+        test_utils::run_test_db(|db| async move {
+            let domain = HyperlaneDomain::Known(KnownHyperlaneDomain::Test1);
+            let db = HyperlaneRocksDB::new(&domain, db);
+            let guard = RoundTripGuard::new(round_trip_conf(2), 2, db.clone());
+            let hour = 3600;
+
+            // Two crossings an hour apart never trip a limit of two
+            for nonce in 0..10 {
+                let message = bounce(nonce, nonce % 2 == 1, b"ping");
+                assert_eq!(
+                    guard.observe(&message, nonce as u64 * hour),
+                    RoundTrip::Allowed
+                );
+                // Processing the same message again doesn't count
+                assert_eq!(
+                    guard.observe(&message, nonce as u64 * hour),
+                    RoundTrip::Allowed
+                );
+            }
+            assert!(guard.tripped().is_empty());
+
+            // Beyond the bound, the tuple crossed least recently goes
+            guard.observe(&bounce(10, false, b"a"), 10 * hour);
+            guard.observe(&bounce(11, false, b"b"), 11 * hour);
+            let tracked = |guard: &RoundTripGuard, body: &[u8]| {
+                let id = RoundTripTuple::of(&bounce(0, false, body)).id();
+                guard.tuples.lock().unwrap().contains_key(&id)
+            };
+            assert!(!tracked(&guard, b"ping"));
+            assert!(tracked(&guard, b"a"));
+            assert!(tracked(&guard, b"b"));
+
+            // And the tuples are resumed from the db
+            let resumed = RoundTripGuard::new(round_trip_conf(2), 2, db);
+            assert!(tracked(&resumed, b"a"));
+            assert!(tracked(&resumed, b"b"));
+        })
+        .await;
+    }
+}
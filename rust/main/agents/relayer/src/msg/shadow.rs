@@ -0,0 +1,547 @@
+//! Shadow evaluation of candidate configs.
+//!
+//! Before a route's gas policy or quorum preference is changed, the candidate
+//! config can shadow the active one: every attempt to relay a message of the
+//! route is decided with both, the active decision being the only one acted
+//! on. Where the candidate would have decided otherwise is counted by route,
+//! so it can be vetted against live traffic before being promoted.
+//!
+//! The candidate decision is made from the inputs the active one gathered,
+//! so shadowing makes no RPC calls of its own. Candidate gas policies only
+//! read the gas payments already indexed in the origin db.
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+use eyre::Result;
+use hyperlane_base::{db::HyperlaneRocksDB, CheckpointPreference, CoreMetrics};
+use hyperlane_core::{HyperlaneMessage, TxCostEstimate, U256};
+use prometheus::IntCounterVec;
+use serde::Serialize;
+use tracing::debug;
+
+use super::{
+    decision::{decide, now, Decision, DecisionInputs, GasPolicyOutcome, MetadataInputs, Observed},
+    gas_payment::GasPaymentEnforcer,
+};
+use crate::settings::{matching_list::MatchingList, ShadowRouteConf};
+
+/// How the candidate decision of an attempt differs from the active one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Divergence {
+    /// The active config delivered the message, the candidate would have
+    /// parked it
+    WouldPark,
+    /// The active config parked the message, the candidate would have
+    /// delivered it
+    WouldDeliver,
+    /// Both configs deliver, or both park the message, but differently, e.g.
+    /// with another gas limit or for another reason
+    DifferentOutcome,
+    /// The candidate would have delivered the message against another quorum
+    /// checkpoint
+    DifferentQuorum,
+    /// The candidate may have delivered the message against another quorum
+    /// checkpoint, which can't be told without fetching checkpoints
+    UndeterminedQuorum,
+}
+
+impl Divergence {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::WouldPark => "would_park",
+            Self::WouldDeliver => "would_deliver",
+            Self::DifferentOutcome => "different_outcome",
+            Self::DifferentQuorum => "different_quorum",
+            Self::UndeterminedQuorum => "undetermined_quorum",
+        }
+    }
+}
+
+/// Divergences of a shadowed route, counted over its decided attempts
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ShadowRouteReport {
+    /// Attempts decided with both configs
+    pub evaluated: u64,
+    pub would_park: u64,
+    pub would_deliver: u64,
+    pub different_outcome: u64,
+    pub different_quorum: u64,
+    pub undetermined_quorum: u64,
+}
+
+impl ShadowRouteReport {
+    fn record(&mut self, divergences: &[Divergence]) {
+        self.evaluated += 1;
+        for divergence in divergences {
+            *match divergence {
+                Divergence::WouldPark => &mut self.would_park,
+                Divergence::WouldDeliver => &mut self.would_deliver,
+                Divergence::DifferentOutcome => &mut self.different_outcome,
+                Divergence::DifferentQuorum => &mut self.different_quorum,
+                Divergence::UndeterminedQuorum => &mut self.undetermined_quorum,
+            } += 1;
+        }
+    }
+}
+
+/// Divergences of every shadowed route since shadowing began
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ShadowReport {
+    /// Unix timestamp, in seconds, shadowing began at
+    pub since: u64,
+    pub routes: BTreeMap<String, ShadowRouteReport>,
+}
+
+/// Divergences counted by route, shared by the shadow evaluators of all
+/// origins
+#[derive(Debug)]
+pub struct ShadowDivergences {
+    since: u64,
+    routes: Mutex<BTreeMap<String, ShadowRouteReport>>,
+    evaluations: IntCounterVec,
+    divergences: IntCounterVec,
+}
+
+impl ShadowDivergences {
+    pub fn new(metrics: &CoreMetrics) -> Result<Self> {
+        Ok(Self {
+            since: now(),
+            routes: Default::default(),
+            evaluations: metrics.new_int_counter(
+                "shadow_config_evaluations",
+                "Number of relaying attempts decided with both the active and a candidate config",
+                &["route"],
+            )?,
+            divergences: metrics.new_int_counter(
+                "shadow_config_divergences",
+                "Number of relaying attempts a candidate config would have decided differently",
+                &["route", "divergence"],
+            )?,
+        })
+    }
+
+    fn record(&self, route: &str, divergences: &[Divergence]) {
+        self.evaluations.with_label_values(&[route]).inc();
+        for divergence in divergences {
+            self.divergences
+                .with_label_values(&[route, divergence.as_str()])
+                .inc();
+        }
+        self.routes
+            .lock()
+            .unwrap()
+            .entry(route.to_owned())
+            .or_default()
+            .record(divergences);
+    }
+
+    /// Divergences of each route since shadowing began
+    pub fn report(&self) -> ShadowReport {
+        ShadowReport {
+            since: self.since,
+            routes: self.routes.lock().unwrap().clone(),
+        }
+    }
+}
+
+/// The candidate config of a route, with the gas policies reading the
+/// payments of an origin
+#[derive(Debug)]
+struct ShadowRoute {
+    name: String,
+    matching_list: MatchingList,
+    gas_payment_enforcer: Option<GasPaymentEnforcer>,
+    gas_price_ceiling: Option<U256>,
+    checkpoint_preference: Option<CheckpointPreference>,
+}
+
+/// Decides the messages of an origin with the candidate configs shadowing
+/// their routes
+#[derive(Debug)]
+pub struct ShadowEvaluator {
+    routes: Vec<ShadowRoute>,
+    divergences: Arc<ShadowDivergences>,
+}
+
+impl ShadowEvaluator {
+    pub fn new(
+        routes: &[ShadowRouteConf],
+        origin_db: &HyperlaneRocksDB,
+        divergences: Arc<ShadowDivergences>,
+    ) -> Self {
+        let routes = routes
+            .iter()
+            .map(|route| ShadowRoute {
+                name: route.name.clone(),
+                matching_list: route.matching_list.clone(),
+                gas_payment_enforcer: route
+                    .gas_payment_enforcement
+                    .clone()
+                    .map(|policies| GasPaymentEnforcer::new(policies, origin_db.clone())),
+                gas_price_ceiling: route.gas_price_ceiling,
+                checkpoint_preference: route.checkpoint_preference,
+            })
+            .collect();
+        Self {
+            routes,
+            divergences,
+        }
+    }
+
+    /// The candidate config shadowing the message's route, if any
+    fn route(&self, message: &HyperlaneMessage) -> Option<&ShadowRoute> {
+        self.routes
+            .iter()
+            .find(|route| route.matching_list.msg_matches(message, false))
+    }
+
+    /// What the candidate gas policies make of the message's payments, given
+    /// the estimate the active ones were evaluated against. `None` if the
+    /// message's route has no candidate gas policies.
+    pub async fn candidate_gas_policy(
+        &self,
+        message: &HyperlaneMessage,
+        tx_cost_estimate: &TxCostEstimate,
+    ) -> Option<Observed<GasPolicyOutcome>> {
+        let enforcer = self.route(message)?.gas_payment_enforcer.as_ref()?;
+        let status = enforcer
+            .message_meets_gas_payment_requirement(message, tx_cost_estimate)
+            .await;
+        Some(Observed::new(&status.as_ref().map(GasPolicyOutcome::from)))
+    }
+
+    /// Decide an attempt with the candidate config of the message's route,
+    /// and record where it diverges from the `active` decision. The
+    /// candidate gas policy outcome, if any, is the one evaluated against the
+    /// same estimate as the active one.
+    pub fn compare(
+        &self,
+        message: &HyperlaneMessage,
+        inputs: &DecisionInputs,
+        active: &Decision,
+        candidate_gas_policy: Option<Observed<GasPolicyOutcome>>,
+        active_preference: CheckpointPreference,
+    ) -> Option<Vec<Divergence>> {
+        let route = self.route(message)?;
+        let mut candidate_inputs = inputs.clone();
+        if let Some(gas_policy) = candidate_gas_policy {
+            candidate_inputs.gas_policy = Some(gas_policy);
+        }
+        if route.gas_price_ceiling.is_some() {
+            candidate_inputs.gas_price_ceiling = route.gas_price_ceiling;
+        }
+        // The circuit breaker is only asked for messages the active config
+        // submits, which the candidate sees as closed otherwise
+        let candidate = decide(&candidate_inputs).unwrap_or(Decision::Incomplete);
+        let candidate_preference = route.checkpoint_preference.unwrap_or(active_preference);
+        let divergences = divergences(
+            active,
+            &candidate,
+            inputs.metadata.as_ref(),
+            active_preference,
+            candidate_preference,
+        );
+        if !divergences.is_empty() {
+            debug!(
+                route = route.name,
+                %active,
+                %candidate,
+                ?divergences,
+                "Candidate config would have decided differently"
+            );
+        }
+        self.divergences.record(&route.name, &divergences);
+        Some(divergences)
+    }
+}
+
+/// Where the candidate decision of an attempt diverges from the active one
+fn divergences(
+    active: &Decision,
+    candidate: &Decision,
+    metadata: Option<&MetadataInputs>,
+    active_preference: CheckpointPreference,
+    candidate_preference: CheckpointPreference,
+) -> Vec<Divergence> {
+    let mut divergences = vec![];
+    match (active, candidate) {
+        (active, candidate) if active == candidate => {}
+        (Decision::Submit(_), Decision::Reprepare(_)) => {
+            divergences.push(Divergence::WouldPark);
+        }
+        (Decision::Reprepare(_), Decision::Submit(_)) => {
+            divergences.push(Divergence::WouldDeliver);
+        }
+        _ => divergences.push(Divergence::DifferentOutcome),
+    }
+    if let (Decision::Submit(_), Some(metadata)) = (candidate, metadata) {
+        divergences.extend(quorum_divergence(
+            metadata,
+            active_preference,
+            candidate_preference,
+        ));
+    }
+    divergences
+}
+
+/// Whether the candidate preference would have chosen another quorum
+/// checkpoint than the one the metadata was built against.
+///
+/// Validators sign every checkpoint, so any index between the leaf and the
+/// active quorum checkpoint has a quorum too: `EarliestSatisfiable` chooses
+/// the leaf's own index. `Latest` chooses the highest checkpoint with a
+/// quorum, which only the active checkpoint being the tree's last leaf
+/// tells without fetching the latest checkpoints of the validators.
+fn quorum_divergence(
+    metadata: &MetadataInputs,
+    active: CheckpointPreference,
+    candidate: CheckpointPreference,
+) -> Option<Divergence> {
+    if active == candidate {
+        return None;
+    }
+    let (Some(leaf_index), Some(checkpoint_index)) =
+        (metadata.leaf_index, metadata.checkpoint_index)
+    else {
+        return None;
+    };
+    match candidate {
+        CheckpointPreference::EarliestSatisfiable => {
+            (checkpoint_index != leaf_index).then_some(Divergence::DifferentQuorum)
+        }
+        CheckpointPreference::Latest => {
+            (checkpoint_index + 1 < metadata.tree_count).then_some(Divergence::UndeterminedQuorum)
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use hyperlane_base::db::test_utils;
+    use hyperlane_core::{
+        test_utils::dummy_domain, FixedPointNumber, InterchainGasPayment, LogMeta, ReprepareReason,
+        H256,
+    };
+    use prometheus::Registry;
+
+    use super::*;
+    use crate::{
+        msg::decision::{GasQuote, ListMembership, MetadataOutcome},
+        settings::{GasPaymentEnforcementConf, GasPaymentEnforcementPolicy},
+    };
+
+    pub fn shadow_divergences() -> Arc<ShadowDivergences> {
+        let metrics = CoreMetrics::new("test", 0, Registry::new()).unwrap();
+        Arc::new(ShadowDivergences::new(&metrics).unwrap())
+    }
+
+    pub fn matching_sender(sender: H256) -> MatchingList {
+        serde_json::from_str(&format!(r#"[{{"senderaddress": "{sender:?}"}}]"#)).unwrap()
+    }
+
+    fn message(sender: H256, nonce: u32) -> HyperlaneMessage {
+        HyperlaneMessage {
+            nonce,
+            sender,
+            destination: 1,
+            ..Default::default()
+        }
+    }
+
+    fn estimate(gas_price: u64) -> TxCostEstimate {
+        TxCostEstimate {
+            gas_limit: 100_000.into(),
+            gas_price: FixedPointNumber::try_from(U256::from(gas_price)).unwrap(),
+            l2_gas_limit: None,
+        }
+    }
+
+    /// The inputs of an attempt the active config decided after evaluating
+    /// its gas policy
+    fn inputs(
+        estimate: &TxCostEstimate,
+        gas_policy: GasPolicyOutcome,
+        checkpoint_index: u32,
+    ) -> DecisionInputs {
+        DecisionInputs {
+            lists: ListMembership::new(true, false, None, true),
+            delivered: Some(Observed::Value(false)),
+            recipient_is_contract: Some(Observed::Value(true)),
+            recipient_ism: Some(Observed::Value(H256::zero())),
+            metadata_builder: Some(Observed::Value(())),
+            metadata: Some(MetadataInputs {
+                tree_count: 10,
+                leaf_index: Some(4),
+                checkpoint_index: Some(checkpoint_index),
+                outcome: MetadataOutcome::Built,
+            }),
+            gas_quote: Some(Observed::Value(GasQuote::from(estimate))),
+            gas_policy: Some(Observed::Value(gas_policy)),
+            ..Default::default()
+        }
+    }
+
+    fn pay(db: &HyperlaneRocksDB, message: &HyperlaneMessage, payment: u64) {
+        let payment = InterchainGasPayment {
+            message_id: message.id(),
+            destination: message.destination,
+            payment: payment.into(),
+            gas_amount: 0.into(),
+        };
+        db.process_gas_payment(payment, &LogMeta::random());
+    }
+
+    #[tokio::test]
+    async fn test_divergences_are_counted_by_route() {
+        test_utils::run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(&dummy_domain(0, "dummy_origin_domain"), db);
+            let bridge = H256::from_low_u64_be(1);
+            let ping = H256::from_low_u64_be(2);
+            let unshadowed = H256::from_low_u64_be(3);
+            // Bridge messages would need a payment, pings a lower gas price
+            let routes = [
+                ShadowRouteConf {
+                    name: "bridge".to_owned(),
+                    matching_list: matching_sender(bridge),
+                    gas_payment_enforcement: Some(vec![GasPaymentEnforcementConf {
+                        policy: GasPaymentEnforcementPolicy::Minimum {
+                            payment: 100.into(),
+                        },
+                        matching_list: Default::default(),
+                    }]),
+                    ..Default::default()
+                },
+                ShadowRouteConf {
+                    name: "ping".to_owned(),
+                    matching_list: matching_sender(ping),
+                    gas_price_ceiling: Some(50.into()),
+                    checkpoint_preference: Some(CheckpointPreference::EarliestSatisfiable),
+                    ..Default::default()
+                },
+            ];
+            let divergences = shadow_divergences();
+            let shadow = ShadowEvaluator::new(&routes, &db, divergences.clone());
+            let active_enforcer =
+                GasPaymentEnforcer::new([GasPaymentEnforcementConf::default()], db.clone());
+
+            // Mixed traffic, as (message, gas price, active quorum checkpoint)
+            let paid_bridge = message(bridge, 0);
+            pay(&db, &paid_bridge, 150);
+            let traffic = [
+                (paid_bridge, 20, 4),
+                (message(bridge, 1), 20, 4),
+                (message(ping, 2), 20, 9),
+                (message(ping, 3), 80, 4),
+                (message(unshadowed, 4), 80, 9),
+            ];
+            let mut candidate_divergences = vec![];
+            for (message, gas_price, checkpoint_index) in traffic {
+                let estimate = estimate(gas_price);
+                let gas_policy = active_enforcer
+                    .message_meets_gas_payment_requirement(&message, &estimate)
+                    .await
+                    .unwrap();
+                let inputs = inputs(&estimate, (&gas_policy).into(), checkpoint_index);
+                let active = decide(&inputs).unwrap();
+                let candidate_gas_policy = shadow.candidate_gas_policy(&message, &estimate).await;
+                candidate_divergences.push(shadow.compare(
+                    &message,
+                    &inputs,
+                    &active,
+                    candidate_gas_policy,
+                    CheckpointPreference::Latest,
+                ));
+                // The active decision stands either way
+                assert_eq!(active, Decision::Submit(100_000.into()));
+            }
+
+            assert_eq!(
+                candidate_divergences,
+                vec![
+                    Some(vec![]),
+                    Some(vec![Divergence::WouldPark]),
+                    Some(vec![Divergence::DifferentQuorum]),
+                    Some(vec![Divergence::WouldPark]),
+                    None,
+                ]
+            );
+            let report = divergences.report();
+            assert_eq!(
+                report.routes["bridge"],
+                ShadowRouteReport {
+                    evaluated: 2,
+                    would_park: 1,
+                    ..Default::default()
+                }
+            );
+            assert_eq!(
+                report.routes["ping"],
+                ShadowRouteReport {
+                    evaluated: 2,
+                    would_park: 1,
+                    different_quorum: 1,
+                    ..Default::default()
+                }
+            );
+            assert_eq!(report.routes.len(), 2);
+            assert_eq!(
+                divergences
+                    .divergences
+                    .with_label_values(&["ping", "would_park"])
+                    .get(),
+                1
+            );
+            assert_eq!(
+                divergences.evaluations.with_label_values(&["bridge"]).get(),
+                2
+            );
+        })
+        .await;
+    }
+
+    #[test]
+    fn test_candidate_quorums() {
+        use CheckpointPreference::*;
+
+        let metadata = |checkpoint_index| MetadataInputs {
+            tree_count: 10,
+            leaf_index: Some(4),
+            checkpoint_index: Some(checkpoint_index),
+            outcome: MetadataOutcome::Built,
+        };
+        assert_eq!(quorum_divergence(&metadata(7), Latest, Latest), None);
+        assert_eq!(
+            quorum_divergence(&metadata(7), Latest, EarliestSatisfiable),
+            Some(Divergence::DifferentQuorum)
+        );
+        assert_eq!(
+            quorum_divergence(&metadata(4), Latest, EarliestSatisfiable),
+            None
+        );
+        assert_eq!(
+            quorum_divergence(&metadata(4), EarliestSatisfiable, Latest),
+            Some(Divergence::UndeterminedQuorum)
+        );
+        // Already at the last leaf, there's no higher checkpoint to choose
+        assert_eq!(
+            quorum_divergence(&metadata(9), EarliestSatisfiable, Latest),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parked_messages_the_candidate_would_deliver() {
+        let divergences = divergences(
+            &Decision::Reprepare(ReprepareReason::GasPaymentNotFound),
+            &Decision::Submit(100_000.into()),
+            None,
+            CheckpointPreference::Latest,
+            CheckpointPreference::Latest,
+        );
+        assert_eq!(divergences, vec![Divergence::WouldDeliver]);
+    }
+}
@@ -0,0 +1,181 @@
+//! Supersession of messages carrying the latest state of their app.
+//!
+//! Some apps dispatch idempotent "latest state" messages, of which only the
+//! newest matters. On the routes configured for it, messages are grouped by
+//! sender and recipient, and optionally by a prefix of their body. When a
+//! message of a group is relayed, the older one relayed before it is
+//! cancelled as superseded, unless a transaction delivering it was already
+//! broadcast. Its pending operation is then dropped before it's prepared or
+//! submitted again, like the one of any cancelled message.
+
+use std::collections::HashMap;
+
+use hyperlane_base::db::HyperlaneRocksDB;
+use hyperlane_core::{HyperlaneMessage, H256};
+
+use super::cancellation::{cancel_message, CancellationError, CancelledBy, MessageCancellation};
+use crate::settings::{SupersessionMode, SupersessionRouteConf};
+
+/// The group of messages superseding each other a message belongs to
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SupersessionKey {
+    destination: u32,
+    sender: H256,
+    recipient: H256,
+    /// Leading bytes of the body, if the route tells the channels between a
+    /// sender and recipient apart by them
+    body_prefix: Vec<u8>,
+}
+
+impl SupersessionKey {
+    /// The group of `message`, if it's on one of the `routes`. A message is
+    /// grouped by the first route it matches.
+    pub fn of(routes: &[SupersessionRouteConf], message: &HyperlaneMessage) -> Option<Self> {
+        let route = routes
+            .iter()
+            .find(|route| route.matching_list.msg_matches(message, false))?;
+        match route.supersession {
+            SupersessionMode::LatestPerSenderRecipient => Some(Self {
+                destination: message.destination,
+                sender: message.sender,
+                recipient: message.recipient,
+                body_prefix: message
+                    .body
+                    .iter()
+                    .take(route.body_prefix_len)
+                    .copied()
+                    .collect(),
+            }),
+        }
+    }
+}
+
+/// A message superseded by a newer one of its group
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Supersession {
+    pub superseded: HyperlaneMessage,
+    pub newer_message_id: H256,
+}
+
+/// The newest message relayed in each group of an origin
+#[derive(Debug, Default)]
+pub struct LatestMessages {
+    latest: HashMap<SupersessionKey, HyperlaneMessage>,
+}
+
+impl LatestMessages {
+    /// Record that `message` of the `key` group is relayed. Returns the older
+    /// message of the group it supersedes, or `message` itself if a newer one
+    /// was already relayed, as older messages may be scanned last.
+    pub fn observe(
+        &mut self,
+        key: SupersessionKey,
+        message: &HyperlaneMessage,
+    ) -> Option<Supersession> {
+        match self.latest.get_mut(&key) {
+            None => {
+                self.latest.insert(key, message.clone());
+                None
+            }
+            Some(latest) if latest.nonce < message.nonce => {
+                let superseded = std::mem::replace(latest, message.clone());
+                Some(Supersession {
+                    superseded,
+                    newer_message_id: message.id(),
+                })
+            }
+            Some(latest) if latest.nonce > message.nonce => Some(Supersession {
+                superseded: message.clone(),
+                newer_message_id: latest.id(),
+            }),
+            // The same message, e.g. released by the throttle
+            Some(_) => None,
+        }
+    }
+}
+
+/// Cancel the superseded message, unless it was already delivered or a
+/// transaction delivering it was broadcast
+pub fn supersede(
+    db: &HyperlaneRocksDB,
+    supersession: &Supersession,
+    config_hash: String,
+) -> Result<MessageCancellation, CancellationError> {
+    cancel_message(
+        db,
+        &supersession.superseded,
+        CancelledBy::Superseded {
+            newer_message_id: supersession.newer_message_id,
+        },
+        "Superseded by a newer message of the same sender and recipient".to_owned(),
+        config_hash,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::settings::matching_list::MatchingList;
+
+    fn route(destination: u32, body_prefix_len: usize) -> SupersessionRouteConf {
+        SupersessionRouteConf {
+            matching_list: MatchingList::with_destination_domain(destination),
+            supersession: SupersessionMode::LatestPerSenderRecipient,
+            body_prefix_len,
+        }
+    }
+
+    fn message(nonce: u32, sender: u64, body: &[u8]) -> HyperlaneMessage {
+        HyperlaneMessage {
+            nonce,
+            destination: 1,
+            sender: H256::from_low_u64_be(sender),
+            body: body.to_vec(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_messages_are_grouped_by_sender_recipient_and_body_prefix() {
+        let routes = [route(1, 2)];
+        let key = |message: &HyperlaneMessage| SupersessionKey::of(&routes, message).unwrap();
+
+        assert_eq!(key(&message(0, 1, b"ab-1")), key(&message(1, 1, b"ab-2")));
+        assert_ne!(key(&message(0, 1, b"ab")), key(&message(1, 1, b"cd")));
+        assert_ne!(key(&message(0, 1, b"ab")), key(&message(1, 2, b"ab")));
+        // Off the routes, messages are never superseded
+        let other_destination = HyperlaneMessage {
+            destination: 2,
+            ..message(0, 1, b"ab")
+        };
+        assert_eq!(SupersessionKey::of(&routes, &other_destination), None);
+    }
+
+    #[test]
+    fn test_the_older_message_is_superseded_in_either_order() {
+        let routes = [route(1, 0)];
+        let mut latest = LatestMessages::default();
+        let observe = |latest: &mut LatestMessages, message: &HyperlaneMessage| {
+            latest.observe(SupersessionKey::of(&routes, message).unwrap(), message)
+        };
+        let (first, second, third) = (message(0, 1, b""), message(1, 1, b""), message(2, 1, b""));
+
+        assert_eq!(observe(&mut latest, &second), None);
+        assert_eq!(observe(&mut latest, &second), None);
+        assert_eq!(
+            observe(&mut latest, &third),
+            Some(Supersession {
+                superseded: second,
+                newer_message_id: third.id(),
+            })
+        );
+        // Scanned after a newer message
+        assert_eq!(
+            observe(&mut latest, &first),
+            Some(Supersession {
+                superseded: first,
+                newer_message_id: third.id(),
+            })
+        );
+    }
+}
@@ -0,0 +1,288 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    time::Duration,
+};
+
+use ethers::utils::hex;
+use hyperlane_core::{HyperlaneMessage, H256};
+use prometheus::IntGaugeVec;
+use tokio::time::Instant;
+use tracing::{debug, info};
+
+use crate::settings::MessageThrottleConf;
+
+/// What a burst is measured over
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum ThrottleKey {
+    /// Every message dispatched by a sender
+    Sender(H256),
+    /// Every message to a recipient on a destination
+    Route { destination: u32, recipient: H256 },
+}
+
+impl ThrottleKey {
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::Sender(_) => "sender",
+            Self::Route { .. } => "route",
+        }
+    }
+
+    fn label(&self) -> String {
+        match self {
+            Self::Sender(sender) => hex::encode(sender),
+            Self::Route {
+                destination,
+                recipient,
+            } => format!("{destination}:{}", hex::encode(recipient)),
+        }
+    }
+}
+
+/// Protects the relayer's RPC quota from message floods.
+///
+/// Message arrivals are counted per sender and per route over a sliding
+/// window. Once a sender or route goes over its burst threshold, its
+/// messages are deferred rather than handed to the submitter, and only get
+/// released when there is no other message to process, at a capped rate.
+/// A sender or route stops being throttled once its arrivals fall back
+/// under the threshold.
+#[derive(Debug)]
+pub struct BurstThrottle {
+    conf: MessageThrottleConf,
+    origin: String,
+    /// Arrival times within the window, by sender and route
+    arrivals: HashMap<ThrottleKey, VecDeque<Instant>>,
+    throttled: HashSet<ThrottleKey>,
+    /// Messages held back, in the order they arrived
+    deferred: VecDeque<HyperlaneMessage>,
+    /// When deferred messages of throttled senders or routes were released
+    /// within the window
+    releases: VecDeque<Instant>,
+    /// Labels: `origin`, `kind`, `id`
+    throttled_sources: IntGaugeVec,
+}
+
+impl BurstThrottle {
+    pub fn new(conf: MessageThrottleConf, origin: String, throttled_sources: IntGaugeVec) -> Self {
+        Self {
+            conf,
+            origin,
+            arrivals: HashMap::new(),
+            throttled: HashSet::new(),
+            deferred: VecDeque::new(),
+            releases: VecDeque::new(),
+            throttled_sources,
+        }
+    }
+
+    /// Record the arrival of a message. Returns false if the message was
+    /// deferred because its sender or route is bursting.
+    pub fn admit(&mut self, message: &HyperlaneMessage, now: Instant) -> bool {
+        let mut admitted = true;
+        for (key, burst) in self.keys(message) {
+            let arrivals = self.arrivals.entry(key).or_default();
+            arrivals.push_back(now);
+            prune(arrivals, now, self.conf.window);
+            let bursting = arrivals.len() > burst;
+            self.set_throttled(key, bursting);
+            admitted &= !bursting;
+        }
+        if !admitted {
+            self.deferred.push_back(message.clone());
+        }
+        admitted
+    }
+
+    /// Take the next deferred message to process, if any may be processed
+    /// now. Messages of senders and routes which are no longer throttled
+    /// come first; the others only within the budget.
+    pub fn release(&mut self, now: Instant) -> Option<HyperlaneMessage> {
+        if self.deferred.is_empty() {
+            return None;
+        }
+        self.refresh(now);
+
+        let unthrottled = self.deferred.iter().position(|message| {
+            self.keys(message)
+                .iter()
+                .all(|(key, _)| !self.throttled.contains(key))
+        });
+        if let Some(position) = unthrottled {
+            return self.deferred.remove(position);
+        }
+
+        prune(&mut self.releases, now, self.conf.window);
+        if self.releases.len() >= self.conf.throttled_budget {
+            return None;
+        }
+        self.releases.push_back(now);
+        self.deferred.pop_front()
+    }
+
+    /// Number of messages held back
+    pub fn deferred_count(&self) -> usize {
+        self.deferred.len()
+    }
+
+    /// Drop arrivals that left the window and stop throttling senders and
+    /// routes which are back under their burst threshold
+    fn refresh(&mut self, now: Instant) {
+        let conf = &self.conf;
+        self.arrivals.retain(|_, arrivals| {
+            prune(arrivals, now, conf.window);
+            !arrivals.is_empty()
+        });
+        let subsided: Vec<ThrottleKey> = self
+            .throttled
+            .iter()
+            .filter(|key| {
+                let burst = match key {
+                    ThrottleKey::Sender(_) => conf.sender_burst,
+                    ThrottleKey::Route { .. } => conf.route_burst,
+                };
+                let arrivals = self.arrivals.get(key).map_or(0, VecDeque::len);
+                burst.map_or(true, |burst| arrivals <= burst)
+            })
+            .copied()
+            .collect();
+        for key in subsided {
+            self.set_throttled(key, false);
+        }
+    }
+
+    fn set_throttled(&mut self, key: ThrottleKey, throttled: bool) {
+        let id = key.label();
+        let labels = [self.origin.as_str(), key.kind(), &id];
+        if throttled && self.throttled.insert(key) {
+            info!(
+                origin = self.origin,
+                kind = key.kind(),
+                id,
+                "Throttling burst of messages"
+            );
+            self.throttled_sources.with_label_values(&labels).set(1);
+        } else if !throttled && self.throttled.remove(&key) {
+            debug!(
+                origin = self.origin,
+                kind = key.kind(),
+                id,
+                "Burst of messages subsided"
+            );
+            let _ = self.throttled_sources.remove_label_values(&labels);
+        }
+    }
+
+    /// The keys a message counts towards, with their burst thresholds
+    fn keys(&self, message: &HyperlaneMessage) -> Vec<(ThrottleKey, usize)> {
+        let sender = self
+            .conf
+            .sender_burst
+            .map(|burst| (ThrottleKey::Sender(message.sender), burst));
+        let route = self.conf.route_burst.map(|burst| {
+            (
+                ThrottleKey::Route {
+                    destination: message.destination,
+                    recipient: message.recipient,
+                },
+                burst,
+            )
+        });
+        sender.into_iter().chain(route).collect()
+    }
+}
+
+/// Drop the times which left the window
+fn prune(times: &mut VecDeque<Instant>, now: Instant, window: Duration) {
+    while let Some(time) = times.front() {
+        if now.duration_since(*time) < window {
+            break;
+        }
+        times.pop_front();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use prometheus::opts;
+
+    use super::*;
+
+    fn throttle(sender_burst: Option<usize>, route_burst: Option<usize>) -> BurstThrottle {
+        BurstThrottle::new(
+            MessageThrottleConf {
+                window: Duration::from_secs(10),
+                sender_burst,
+                route_burst,
+                throttled_budget: 2,
+            },
+            "origin".to_owned(),
+            IntGaugeVec::new(opts!("throttled", "help string"), &["origin", "kind", "id"]).unwrap(),
+        )
+    }
+
+    fn message(nonce: u32, sender: u64, recipient: u64) -> HyperlaneMessage {
+        HyperlaneMessage {
+            nonce,
+            sender: H256::from_low_u64_be(sender),
+            recipient: H256::from_low_u64_be(recipient),
+            destination: 1,
+            ..Default::default()
+        }
+    }
+
+    fn throttled_gauge(throttle: &BurstThrottle, sender: u64) -> i64 {
+        let id = hex::encode(H256::from_low_u64_be(sender));
+        throttle
+            .throttled_sources
+            .with_label_values(&["origin", "sender", &id])
+            .get()
+    }
+
+    #[test]
+    fn test_nothing_is_throttled_without_thresholds() {
+        let mut throttle = throttle(None, None);
+        let now = Instant::now();
+        assert!((0..100).all(|nonce| throttle.admit(&message(nonce, 1, 1), now)));
+        assert_eq!(throttle.deferred_count(), 0);
+    }
+
+    #[test]
+    fn test_bursting_sender_is_deferred_until_it_subsides() {
+        let mut throttle = throttle(Some(3), None);
+        let start = Instant::now();
+
+        let admitted: Vec<bool> = (0..6)
+            .map(|nonce| throttle.admit(&message(nonce, 1, 1), start))
+            .collect();
+        assert_eq!(admitted, vec![true, true, true, false, false, false]);
+        assert_eq!(throttled_gauge(&throttle, 1), 1);
+        // Other senders aren't affected
+        assert!(throttle.admit(&message(6, 2, 1), start));
+
+        // Throttled messages are released within the budget only
+        assert_eq!(throttle.release(start).map(|m| m.nonce), Some(3));
+        assert_eq!(throttle.release(start).map(|m| m.nonce), Some(4));
+        assert_eq!(throttle.release(start), None);
+
+        // Once the burst left the window, the sender is released entirely
+        let later = start + Duration::from_secs(10);
+        assert_eq!(throttle.release(later).map(|m| m.nonce), Some(5));
+        assert_eq!(throttle.release(later), None);
+        assert!(throttle.admit(&message(7, 1, 1), later));
+        assert_eq!(throttled_gauge(&throttle, 1), 0);
+    }
+
+    #[test]
+    fn test_bursting_route_is_deferred_across_senders() {
+        let mut throttle = throttle(None, Some(2));
+        let now = Instant::now();
+
+        let admitted: Vec<bool> = (0..4)
+            .map(|nonce| throttle.admit(&message(nonce, nonce as u64, 7), now))
+            .collect();
+        assert_eq!(admitted, vec![true, true, false, false]);
+        assert!(throttle.admit(&message(4, 1, 8), now));
+        assert_eq!(throttle.deferred_count(), 2);
+    }
+}
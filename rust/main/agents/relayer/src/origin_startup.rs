@@ -0,0 +1,348 @@
+//! Bringing up the pipelines of the relayer's origins, i.e. their contract
+//! syncs and processors. Origins with work to do are started first, and in
+//! lazy mode the relayer is ready as soon as those are up, the rest being
+//! started in the background.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    future::Future,
+    sync::{Arc, RwLock},
+};
+
+use futures_util::future::try_join_all;
+use hyperlane_base::db::HyperlaneDb;
+use hyperlane_core::HyperlaneDomain;
+use serde::Serialize;
+use tokio::task::JoinHandle;
+use tracing::{info, info_span, instrument::Instrumented, Instrument};
+
+use crate::tasks::AbortOnDrop;
+
+/// How many of the most recently seen nonces of an origin are checked for
+/// messages that weren't delivered yet
+const UNDELIVERED_SCAN_DEPTH: u32 = 1000;
+
+/// Whether an origin's pipeline is up
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "state", rename_all = "camelCase")]
+pub enum OriginState {
+    /// The pipeline is being or is yet to be started
+    Initializing,
+    /// The pipeline is running
+    Ready,
+    /// Part of the pipeline couldn't be started
+    Failed {
+        /// Why it couldn't be started
+        error: String,
+    },
+}
+
+/// The state of each origin's pipeline, shared with the admin server
+#[derive(Debug, Clone, Default)]
+pub struct OriginHealth {
+    inner: Arc<RwLock<OriginHealthInner>>,
+}
+
+#[derive(Debug, Default)]
+struct OriginHealthInner {
+    origins: BTreeMap<String, OriginState>,
+    priority: HashSet<String>,
+}
+
+impl OriginHealth {
+    fn initializing(&self, origin: &HyperlaneDomain, priority: bool) {
+        let mut inner = self.inner.write().unwrap();
+        inner
+            .origins
+            .insert(origin.name().to_owned(), OriginState::Initializing);
+        if priority {
+            inner.priority.insert(origin.name().to_owned());
+        }
+    }
+
+    /// Mark the origin's pipeline as up, unless part of it failed to start
+    fn ready(&self, origin: &HyperlaneDomain) {
+        let mut inner = self.inner.write().unwrap();
+        match inner.origins.get_mut(origin.name()) {
+            Some(state) if *state == OriginState::Initializing => *state = OriginState::Ready,
+            _ => {}
+        }
+    }
+
+    /// Record that part of the origin's pipeline couldn't be started
+    pub fn fail(&self, origin: &HyperlaneDomain, error: impl ToString) {
+        self.inner.write().unwrap().origins.insert(
+            origin.name().to_owned(),
+            OriginState::Failed {
+                error: error.to_string(),
+            },
+        );
+    }
+
+    /// The state of the origin's pipeline
+    pub fn state(&self, origin: &HyperlaneDomain) -> Option<OriginState> {
+        self.inner
+            .read()
+            .unwrap()
+            .origins
+            .get(origin.name())
+            .cloned()
+    }
+
+    /// The state of every origin's pipeline, by origin name
+    pub fn origins(&self) -> BTreeMap<String, OriginState> {
+        self.inner.read().unwrap().origins.clone()
+    }
+
+    /// Whether every priority origin is done initializing. Origins that
+    /// failed don't hold readiness back, and are reported as such instead.
+    pub fn is_ready(&self) -> bool {
+        let inner = self.inner.read().unwrap();
+        inner
+            .priority
+            .iter()
+            .all(|origin| inner.origins.get(origin) != Some(&OriginState::Initializing))
+    }
+}
+
+/// Whether any of the origin's most recent messages isn't known to be
+/// delivered
+pub(crate) fn has_undelivered_messages(db: &dyn HyperlaneDb) -> bool {
+    let Ok(Some(highest)) = db.retrieve_highest_seen_message_nonce() else {
+        return false;
+    };
+    (highest.saturating_sub(UNDELIVERED_SCAN_DEPTH - 1)..=highest)
+        .any(|nonce| !matches!(db.retrieve_processed_by_nonce(&nonce), Ok(Some(true))))
+}
+
+/// The order to start the origins in, along with whether each of them is a
+/// priority origin. Origins with undelivered messages come first, then the
+/// configured `priority_origins` in the order given, then the rest by name.
+/// Both of the former are priority origins.
+pub(crate) fn startup_order<'a>(
+    origins: impl IntoIterator<Item = &'a HyperlaneDomain>,
+    priority_origins: &[HyperlaneDomain],
+    has_pending_work: impl Fn(&HyperlaneDomain) -> bool,
+) -> Vec<(HyperlaneDomain, bool)> {
+    let mut order: Vec<_> = origins
+        .into_iter()
+        .map(|origin| {
+            let pending = has_pending_work(origin);
+            let configured = priority_origins.iter().position(|o| o == origin);
+            (origin.clone(), pending, configured)
+        })
+        .collect();
+    order.sort_by(
+        |(a, a_pending, a_configured), (b, b_pending, b_configured)| {
+            b_pending
+                .cmp(a_pending)
+                .then_with(|| {
+                    a_configured
+                        .unwrap_or(usize::MAX)
+                        .cmp(&b_configured.unwrap_or(usize::MAX))
+                })
+                .then_with(|| a.name().cmp(b.name()))
+        },
+    );
+    order
+        .into_iter()
+        .map(|(origin, pending, configured)| (origin, pending || configured.is_some()))
+        .collect()
+}
+
+/// Start the pipelines of the origins in `order` one after the other with
+/// `start`, which records origins it fails to fully start in `health`.
+///
+/// Returns once the priority origins are started, with the tasks of their
+/// pipelines and a task starting the remaining origins in the background,
+/// which runs for as long as their pipelines do.
+pub(crate) async fn start_origins<S, Fut>(
+    order: Vec<(HyperlaneDomain, bool)>,
+    health: OriginHealth,
+    start: S,
+) -> Vec<Instrumented<JoinHandle<()>>>
+where
+    S: Fn(HyperlaneDomain) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Vec<Instrumented<JoinHandle<()>>>> + Send + 'static,
+{
+    for (origin, priority) in &order {
+        health.initializing(origin, *priority);
+    }
+    let (priority, rest): (Vec<_>, Vec<_>) = order.into_iter().partition(|(_, p)| *p);
+
+    // Pipelines already started are stopped if startup is abandoned
+    let mut tasks = vec![];
+    let mut abort = AbortOnDrop::default();
+    for (origin, _) in priority {
+        let started = start(origin.clone()).await;
+        abort.extend(started.iter().map(Instrumented::inner));
+        tasks.extend(started);
+        health.ready(&origin);
+    }
+    abort.disarm();
+    if rest.is_empty() {
+        return tasks;
+    }
+    info!(
+        remaining = rest.len(),
+        "Priority origins started, starting the remaining ones in the background"
+    );
+    let background = tokio::spawn(async move {
+        let mut tasks = vec![];
+        // Stops the pipelines with the background task
+        let mut abort = AbortOnDrop::default();
+        for (origin, _) in rest {
+            let started = start(origin.clone()).await;
+            abort.extend(started.iter().map(Instrumented::inner));
+            tasks.extend(started);
+            health.ready(&origin);
+        }
+        info!("All origins started");
+        // Propagate task panics
+        if let Err(err) = try_join_all(tasks).await {
+            panic!("Origin task panicked: {err:?}");
+        }
+    })
+    .instrument(info_span!("OriginStartup"));
+    tasks.push(background);
+    tasks
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use hyperlane_base::db::{test_utils, HyperlaneRocksDB};
+    use hyperlane_core::{HyperlaneMessage, KnownHyperlaneDomain};
+    use tokio::time::{sleep, Instant};
+
+    use super::*;
+
+    fn domain(domain: KnownHyperlaneDomain) -> HyperlaneDomain {
+        HyperlaneDomain::Known(domain)
+    }
+
+    #[test]
+    fn test_origins_with_work_start_first() {
+        use KnownHyperlaneDomain::*;
+        let origins: Vec<_> = [Arbitrum, Ethereum, Optimism, Polygon, Celo]
+            .into_iter()
+            .map(domain)
+            .collect();
+        let order = startup_order(&origins, &[domain(Polygon), domain(Ethereum)], |o| {
+            *o == domain(Optimism)
+        });
+        assert_eq!(
+            order,
+            vec![
+                (domain(Optimism), true),
+                (domain(Polygon), true),
+                (domain(Ethereum), true),
+                (domain(Arbitrum), false),
+                (domain(Celo), false),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_undelivered_messages_are_detected() {
+        test_utils::run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(&domain(KnownHyperlaneDomain::Ethereum), db);
+            assert!(!has_undelivered_messages(&db));
+
+            for nonce in 0..3 {
+                let message = HyperlaneMessage {
+                    nonce,
+                    ..Default::default()
+                };
+                db.store_message(&message, 1).unwrap();
+            }
+            assert!(has_undelivered_messages(&db));
+
+            for nonce in 0..3 {
+                db.store_processed_by_nonce(&nonce, &true).unwrap();
+            }
+            assert!(!has_undelivered_messages(&db));
+        })
+        .await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_ready_once_priority_origins_are_up() {
+        use KnownHyperlaneDomain::*;
+        // Origins with how long restoring them takes, and whether it fails
+        let origins = [
+            (Ethereum, 5, false),
+            (Optimism, 1, false),
+            (Arbitrum, 30, false),
+            (Polygon, 10, true),
+            (Celo, 20, false),
+        ];
+        let order = startup_order(
+            &origins.iter().map(|(o, ..)| domain(*o)).collect::<Vec<_>>(),
+            &[domain(Optimism)],
+            |o| *o == domain(Ethereum),
+        );
+        let health = OriginHealth::default();
+        let start_health = health.clone();
+        let started = Instant::now();
+
+        let tasks = start_origins(order, health.clone(), move |origin| {
+            let health = start_health.clone();
+            async move {
+                let (_, cost, fails) = origins
+                    .into_iter()
+                    .find(|(o, ..)| domain(*o) == origin)
+                    .unwrap();
+                sleep(Duration::from_secs(cost)).await;
+                if fails {
+                    health.fail(&origin, "Error building cursor for origin");
+                }
+                vec![tokio::spawn(async {}).instrument(info_span!("Pipeline"))]
+            }
+        })
+        .await;
+
+        // Ready as soon as the priority origins are up
+        assert_eq!(started.elapsed(), Duration::from_secs(6));
+        assert!(health.is_ready());
+        let state = |o| health.state(&domain(o)).unwrap();
+        assert_eq!(state(Ethereum), OriginState::Ready);
+        assert_eq!(state(Optimism), OriginState::Ready);
+        assert_eq!(state(Arbitrum), OriginState::Initializing);
+        assert_eq!(state(Celo), OriginState::Initializing);
+        assert_eq!(state(Polygon), OriginState::Initializing);
+
+        // The rest are started in the background, by name
+        sleep(Duration::from_secs(31)).await;
+        assert_eq!(state(Arbitrum), OriginState::Ready);
+        assert_eq!(state(Celo), OriginState::Initializing);
+        sleep(Duration::from_secs(30)).await;
+        assert_eq!(state(Celo), OriginState::Ready);
+        assert!(matches!(state(Polygon), OriginState::Failed { .. }));
+        try_join_all(tasks).await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_failed_priority_origins_dont_hold_back_readiness() {
+        let origin = domain(KnownHyperlaneDomain::Ethereum);
+        let health = OriginHealth::default();
+        let start_health = health.clone();
+
+        let tasks = start_origins(vec![(origin.clone(), true)], health.clone(), move |o| {
+            let health = start_health.clone();
+            async move {
+                health.fail(&o, "Error building cursor for origin");
+                vec![]
+            }
+        })
+        .await;
+
+        assert!(tasks.is_empty());
+        assert!(health.is_ready());
+        assert!(matches!(
+            health.state(&origin),
+            Some(OriginState::Failed { .. })
+        ));
+    }
+}
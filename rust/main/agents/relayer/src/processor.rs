@@ -3,6 +3,7 @@ use std::fmt::Debug;
 use async_trait::async_trait;
 use derive_new::new;
 use eyre::Result;
+use hyperlane_base::WithChainContext;
 use hyperlane_core::HyperlaneDomain;
 use tokio::task::JoinHandle;
 use tokio_metrics::TaskMonitor;
@@ -35,8 +36,9 @@ impl Processor {
     #[instrument(ret, skip(self), level = "info", fields(domain=%self.ticker.domain()))]
     async fn main_loop(mut self) {
         loop {
-            if let Err(err) = self.ticker.tick().await {
-                warn!(error=%err, "Error in processor tick");
+            let tick = self.ticker.tick().await;
+            if let Err(err) = tick.with_chain_context(self.ticker.domain()) {
+                warn!(error=?err, "Error in processor tick");
                 tokio::time::sleep(std::time::Duration::from_secs(5)).await;
             }
         }
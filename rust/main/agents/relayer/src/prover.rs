@@ -9,11 +9,17 @@ use hyperlane_core::accumulator::{
 use hyperlane_core::H256;
 use tracing::{error, instrument};
 
-/// A depth-32 sparse Merkle tree capable of producing proofs for arbitrary
-/// elements.
+/// A sparse Merkle tree capable of producing proofs for arbitrary elements.
+///
+/// The tree holds at most 2^`depth` leaves, `depth` being 32 unless the
+/// origin uses shallower trees. Such a tree is the leftmost subtree of a
+/// depth-32 one, so proofs always have 32 levels, the ones above `depth`
+/// being zero hashes, and only the first `depth` are needed to prove against
+/// the root of the shallower tree.
 #[derive(Debug)]
 pub struct Prover {
     count: usize,
+    depth: usize,
     tree: MerkleTree,
 }
 
@@ -23,6 +29,12 @@ pub enum ProverError {
     /// Index is above tree max size
     #[error("Requested proof for index above u32::MAX: {0}")]
     IndexTooHigh(usize),
+    /// The tree holds as many leaves as its depth allows
+    #[error("Merkle tree of depth {depth} is full")]
+    TreeFull {
+        /// The depth of the tree
+        depth: usize,
+    },
     /// Requested proof for a zero element
     #[error("Requested proof for a zero element. Requested: {index}. Tree has: {count}")]
     ZeroProof {
@@ -47,27 +59,44 @@ pub enum ProverError {
 
 impl Default for Prover {
     fn default() -> Self {
-        let full = MerkleTree::create(&[], TREE_DEPTH);
+        Self::new(TREE_DEPTH)
+    }
+}
+
+impl Prover {
+    /// An empty tree holding at most 2^`depth` leaves
+    pub fn new(depth: usize) -> Self {
+        assert!(
+            (1..=TREE_DEPTH).contains(&depth),
+            "merkle tree depth must be between 1 and {TREE_DEPTH}, got {depth}"
+        );
         Self {
             count: 0,
-            tree: full,
+            depth,
+            tree: MerkleTree::create(&[], TREE_DEPTH),
         }
     }
-}
 
-impl Prover {
     /// Push a leaf to the tree. Appends it to the first unoccupied slot
     ///
-    /// This will fail if the underlying tree is full.
+    /// This will fail if the tree is full.
     pub fn ingest(&mut self, element: H256) -> Result<H256, ProverError> {
-        self.count += 1;
+        if self.count >= self.capacity() {
+            return Err(ProverError::TreeFull { depth: self.depth });
+        }
         self.tree.push_leaf(element, TREE_DEPTH)?;
-        Ok(self.tree.hash())
+        self.count += 1;
+        Ok(self.root())
     }
 
     /// Return the current root hash of the tree
     pub fn root(&self) -> H256 {
-        self.tree.hash()
+        let mut subtree = &self.tree;
+        for _ in self.depth..TREE_DEPTH {
+            // Only leaves are missing branches, and they're at depth 0
+            subtree = subtree.left_and_right_branches().unwrap().0;
+        }
+        subtree.hash()
     }
 
     /// Return the number of leaves that have been ingested
@@ -75,6 +104,16 @@ impl Prover {
         self.count
     }
 
+    /// Return the depth of the tree
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Return the number of leaves the tree can hold
+    pub fn capacity(&self) -> usize {
+        1 << self.depth
+    }
+
     /// Create a proof of a leaf in this tree.
     #[instrument(err, skip(self), fields(prover_msg_count=self.count()))]
     pub fn prove_against_previous(
@@ -98,7 +137,12 @@ impl Prover {
     /// Verify a proof against this tree's root.
     #[allow(dead_code)]
     pub fn verify(&self, proof: &Proof) -> Result<(), ProverError> {
-        let actual = merkle_root_from_branch(proof.leaf, &proof.path, TREE_DEPTH, proof.index);
+        let actual = merkle_root_from_branch(
+            proof.leaf,
+            &proof.branch()[..self.depth],
+            self.depth,
+            proof.index,
+        );
         let expected = self.root();
         if expected == actual {
             Ok(())
@@ -116,6 +160,7 @@ where
         let slice = t.as_ref();
         Self {
             count: slice.len(),
+            depth: TREE_DEPTH,
             tree: MerkleTree::create(slice, TREE_DEPTH),
         }
     }
@@ -177,4 +222,23 @@ mod test {
             }
         }
     }
+
+    #[test]
+    fn it_fills_shallower_trees() {
+        let leaves: Vec<_> = (0..16).map(H256::from_low_u64_be).collect();
+        let mut tree = Prover::new(4);
+        for leaf in leaves.iter() {
+            tree.ingest(*leaf).unwrap();
+        }
+        assert_eq!(tree.root(), MerkleTree::create(&leaves, 4).hash());
+        for n in 0..leaves.len() {
+            let proof = tree.prove_against_previous(n, tree.count() - 1).unwrap();
+            tree.verify(&proof).unwrap();
+        }
+        assert!(matches!(
+            tree.ingest(H256::from_low_u64_be(16)),
+            Err(ProverError::TreeFull { depth: 4 })
+        ));
+        assert_eq!(tree.count(), 16);
+    }
 }
@@ -0,0 +1,403 @@
+//! Operator-triggered re-fetching of the dispatches and gas payments an origin
+//! emitted in a block range, e.g. after learning that an RPC served bad data
+//! for it, without rebuilding the whole database.
+//!
+//! Fetched records are reconciled against the stored ones: missing records
+//! are inserted, while records differing from the stored ones are reported as
+//! conflicts and never overwritten. A dispatch conflicting with a stored
+//! message puts the order of the origin's merkle leaves in doubt, so it marks
+//! the origin's tree as diverged, notifying its divergence observers.
+
+use std::{ops::RangeInclusive, sync::Arc};
+
+use derive_new::new;
+use hyperlane_base::db::{DbError, HyperlaneDb, HyperlaneRocksDB};
+use hyperlane_core::{
+    ChainCommunicationError, HyperlaneMessage, Indexed, Indexer, InterchainGasPayment,
+    InterchainGasPaymentMeta, LogMeta, SequenceAwareIndexer, H256, H512,
+};
+use serde::Serialize;
+use tokio::sync::{Mutex, RwLock};
+use tracing::{info, warn};
+
+use crate::merkle_tree::{availability::TreeAvailability, builder::MerkleTreeBuilder};
+
+/// Errors raised while reindexing a block range
+#[derive(Debug, thiserror::Error)]
+pub enum ReindexError {
+    /// The block range is empty
+    #[error("Invalid block range {from}..={to}")]
+    InvalidRange {
+        /// First block of the range
+        from: u32,
+        /// Last block of the range
+        to: u32,
+    },
+    /// The origin is already being reindexed
+    #[error("A reindex of this origin is already running")]
+    AlreadyRunning,
+    /// Fetching the logs failed
+    #[error(transparent)]
+    Chain(#[from] ChainCommunicationError),
+    /// Reading or writing the database failed
+    #[error(transparent)]
+    Db(#[from] DbError),
+}
+
+/// A stored message differing from the one fetched for its nonce
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MessageConflict {
+    /// Nonce of the message
+    pub nonce: u32,
+    /// Id of the stored message
+    pub stored_id: H256,
+    /// Id of the message fetched from the chain
+    pub fetched_id: H256,
+}
+
+/// A stored gas payment differing from the one fetched for its log
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GasPaymentConflict {
+    /// Transaction the payment log was emitted in
+    pub transaction_id: H512,
+    /// Index of the payment log in the transaction
+    pub log_index: u64,
+    /// Message the stored payment is for
+    pub stored_message_id: H256,
+    /// Message the payment fetched from the chain is for
+    pub fetched_message_id: H256,
+}
+
+/// What reindexing a block range changed, and what it found conflicting
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ReindexSummary {
+    /// First block of the reindexed range
+    pub from_block: u32,
+    /// Last block of the reindexed range
+    pub to_block: u32,
+    /// Number of messages that were missing and got inserted
+    pub messages_added: u32,
+    /// Number of messages matching the stored ones
+    pub messages_unchanged: u32,
+    /// Messages differing from the stored ones, which were kept
+    pub message_conflicts: Vec<MessageConflict>,
+    /// Number of gas payments that were missing and got inserted
+    pub gas_payments_added: u32,
+    /// Number of gas payments matching the stored ones
+    pub gas_payments_unchanged: u32,
+    /// Gas payments differing from the stored ones, which were kept
+    pub gas_payment_conflicts: Vec<GasPaymentConflict>,
+    /// Whether conflicting dispatches marked the merkle tree as diverged
+    pub tree_diverged: bool,
+}
+
+/// Reindexes block ranges of one origin, one range at a time
+#[derive(new, Clone)]
+pub struct OriginReindexer {
+    db: HyperlaneRocksDB,
+    message_indexer: Arc<dyn SequenceAwareIndexer<HyperlaneMessage>>,
+    gas_payment_indexer: Arc<dyn SequenceAwareIndexer<InterchainGasPayment>>,
+    prover_sync: Arc<RwLock<MerkleTreeBuilder>>,
+    /// Number of blocks to fetch logs for at once
+    chunk_size: u32,
+    #[new(default)]
+    running: Arc<Mutex<()>>,
+}
+
+impl OriginReindexer {
+    /// Re-fetch the dispatches and gas payments emitted in `range` and
+    /// reconcile them with the stored ones
+    pub async fn reindex(
+        &self,
+        range: RangeInclusive<u32>,
+    ) -> Result<ReindexSummary, ReindexError> {
+        let (from, to) = (*range.start(), *range.end());
+        if range.is_empty() {
+            return Err(ReindexError::InvalidRange { from, to });
+        }
+        let _running = self
+            .running
+            .try_lock()
+            .map_err(|_| ReindexError::AlreadyRunning)?;
+
+        let mut summary = ReindexSummary {
+            from_block: from,
+            to_block: to,
+            ..Default::default()
+        };
+        let chunk_size = self.chunk_size.max(1);
+        for start in (from..=to).step_by(chunk_size as usize) {
+            let chunk = start..=start.saturating_add(chunk_size - 1).min(to);
+            let messages = self
+                .message_indexer
+                .fetch_logs_in_range(chunk.clone())
+                .await?;
+            for (message, meta) in messages {
+                self.reconcile_message(message.inner(), meta.block_number, &mut summary)?;
+            }
+            let payments = self.gas_payment_indexer.fetch_logs_in_range(chunk).await?;
+            for (payment, meta) in payments {
+                self.reconcile_gas_payment(payment, &meta, &mut summary)?;
+            }
+        }
+
+        if !summary.message_conflicts.is_empty() {
+            let reason = format!(
+                "Reindexing blocks {from}..={to} found {} dispatches conflicting with stored messages",
+                summary.message_conflicts.len()
+            );
+            self.prover_sync.write().await.set_availability(
+                TreeAvailability::Diverged {
+                    reason: reason.clone(),
+                },
+                &reason,
+            );
+            summary.tree_diverged = true;
+        }
+        info!(domain = %self.db.domain(), ?summary, "Reindexed block range");
+        Ok(summary)
+    }
+
+    fn reconcile_message(
+        &self,
+        message: &HyperlaneMessage,
+        block_number: u64,
+        summary: &mut ReindexSummary,
+    ) -> Result<(), ReindexError> {
+        let fetched_id = message.id();
+        match self.db.retrieve_message_id_by_nonce(&message.nonce)? {
+            None => {
+                self.db.store_message(message, block_number)?;
+                summary.messages_added += 1;
+            }
+            Some(stored_id) if stored_id == fetched_id => summary.messages_unchanged += 1,
+            Some(stored_id) => {
+                warn!(
+                    nonce = message.nonce,
+                    ?stored_id,
+                    ?fetched_id,
+                    "Reindexed dispatch conflicts with the stored message"
+                );
+                summary.message_conflicts.push(MessageConflict {
+                    nonce: message.nonce,
+                    stored_id,
+                    fetched_id,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn reconcile_gas_payment(
+        &self,
+        payment: Indexed<InterchainGasPayment>,
+        meta: &LogMeta,
+        summary: &mut ReindexSummary,
+    ) -> Result<(), ReindexError> {
+        let payment_meta = InterchainGasPaymentMeta::from(meta);
+        match self.db.retrieve_gas_payment_contribution(&payment_meta)? {
+            None => {
+                if self.db.process_indexed_gas_payment(payment, meta)? {
+                    summary.gas_payments_added += 1;
+                }
+            }
+            Some(stored) if stored.payment == *payment.inner() => {
+                summary.gas_payments_unchanged += 1
+            }
+            Some(stored) => {
+                warn!(
+                    ?payment_meta,
+                    stored = ?stored.payment,
+                    fetched = ?payment.inner(),
+                    "Reindexed gas payment conflicts with the stored one"
+                );
+                summary.gas_payment_conflicts.push(GasPaymentConflict {
+                    transaction_id: payment_meta.transaction_id,
+                    log_index: payment_meta.log_index,
+                    stored_message_id: stored.payment.message_id,
+                    fetched_message_id: payment.inner().message_id,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use std::fmt::Debug;
+
+    use async_trait::async_trait;
+    use hyperlane_base::db::test_utils;
+    use hyperlane_core::{ChainResult, HyperlaneDomain, MerkleTreeInsertion, U256};
+
+    use super::*;
+
+    /// Serves a fixed set of logs, by the block they were emitted in
+    #[derive(Debug)]
+    pub(crate) struct FixedLogs<T>(pub Vec<(Indexed<T>, LogMeta)>);
+
+    #[async_trait]
+    impl<T: Clone + Debug + Send + Sync + 'static> Indexer<T> for FixedLogs<T> {
+        async fn fetch_logs_in_range(
+            &self,
+            range: RangeInclusive<u32>,
+        ) -> ChainResult<Vec<(Indexed<T>, LogMeta)>> {
+            Ok(self
+                .0
+                .iter()
+                .filter(|(_, meta)| range.contains(&(meta.block_number as u32)))
+                .cloned()
+                .collect())
+        }
+
+        async fn get_finalized_block_number(&self) -> ChainResult<u32> {
+            Ok(u32::MAX)
+        }
+    }
+
+    #[async_trait]
+    impl<T: Clone + Debug + Send + Sync + 'static> SequenceAwareIndexer<T> for FixedLogs<T> {
+        async fn latest_sequence_count_and_tip(&self) -> ChainResult<(Option<u32>, u32)> {
+            Ok((None, u32::MAX))
+        }
+    }
+
+    pub(crate) fn meta(block: u64) -> LogMeta {
+        LogMeta {
+            block_number: block,
+            transaction_id: H512::from_low_u64_be(block),
+            log_index: U256::zero(),
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn dispatch(nonce: u32) -> (Indexed<HyperlaneMessage>, LogMeta) {
+        let message = HyperlaneMessage {
+            nonce,
+            ..Default::default()
+        };
+        (message.into(), meta(10 + nonce as u64))
+    }
+
+    pub(crate) fn payment(nonce: u32) -> (Indexed<InterchainGasPayment>, LogMeta) {
+        let payment = InterchainGasPayment {
+            message_id: dispatch(nonce).0.inner().id(),
+            destination: 2,
+            payment: U256::from(100),
+            gas_amount: U256::from(1_000),
+        };
+        (Indexed::new(payment), meta(10 + nonce as u64))
+    }
+
+    /// A reindexer of an origin on chain with three dispatches and their gas
+    /// payments, with the chain serving `messages` instead if given
+    pub(crate) fn reindexer(
+        db: &HyperlaneRocksDB,
+        messages: Option<Vec<(Indexed<HyperlaneMessage>, LogMeta)>>,
+    ) -> OriginReindexer {
+        let messages = messages.unwrap_or_else(|| (0..3).map(dispatch).collect());
+        OriginReindexer::new(
+            db.clone(),
+            Arc::new(FixedLogs(messages)),
+            Arc::new(FixedLogs((0..3).map(payment).collect())),
+            Arc::new(RwLock::new(MerkleTreeBuilder::new())),
+            5,
+        )
+    }
+
+    /// Seeds `db` with the chain's records, except the dispatch of nonce 1
+    pub(crate) fn seed_missing_one(db: &HyperlaneRocksDB) {
+        for nonce in [0, 2] {
+            let (message, meta) = dispatch(nonce);
+            db.store_message(message.inner(), meta.block_number)
+                .unwrap();
+            db.process_tree_insertion(
+                &MerkleTreeInsertion::new(nonce, message.inner().id()),
+                meta.block_number,
+            )
+            .unwrap();
+        }
+        for nonce in 0..3 {
+            let (payment, meta) = payment(nonce);
+            db.process_indexed_gas_payment(payment, &meta).unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reindex_inserts_missing_records() {
+        test_utils::run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(&HyperlaneDomain::new_test_domain("test"), db);
+            seed_missing_one(&db);
+            let reindexer = reindexer(&db, None);
+
+            let summary = reindexer.reindex(0..=20).await.unwrap();
+            assert_eq!(summary.messages_added, 1);
+            assert_eq!(summary.messages_unchanged, 2);
+            assert_eq!(summary.gas_payments_unchanged, 3);
+            assert!(summary.message_conflicts.is_empty());
+            assert!(!summary.tree_diverged);
+            assert_eq!(
+                db.retrieve_message_by_nonce(1).unwrap(),
+                Some(dispatch(1).0.inner().clone())
+            );
+
+            // Reindexing again is a no-op
+            let summary = reindexer.reindex(0..=20).await.unwrap();
+            assert_eq!(summary.messages_added, 0);
+            assert_eq!(summary.messages_unchanged, 3);
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_conflicting_dispatches_diverge_the_tree() {
+        test_utils::run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(&HyperlaneDomain::new_test_domain("test"), db);
+            seed_missing_one(&db);
+            // the chain now serves a different message at nonce 2
+            let mut messages: Vec<_> = (0..3).map(dispatch).collect();
+            let conflicting = HyperlaneMessage {
+                nonce: 2,
+                body: vec![1],
+                ..Default::default()
+            };
+            messages[2].0 = conflicting.clone().into();
+            let reindexer = reindexer(&db, Some(messages));
+
+            let summary = reindexer.reindex(0..=20).await.unwrap();
+            assert_eq!(
+                summary.message_conflicts,
+                vec![MessageConflict {
+                    nonce: 2,
+                    stored_id: dispatch(2).0.inner().id(),
+                    fetched_id: conflicting.id(),
+                }]
+            );
+            assert!(summary.tree_diverged);
+            // the stored message is kept
+            assert_eq!(
+                db.retrieve_message_id_by_nonce(&2).unwrap(),
+                Some(dispatch(2).0.inner().id())
+            );
+            assert!(matches!(
+                reindexer.prover_sync.read().await.availability(),
+                TreeAvailability::Diverged { .. }
+            ));
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_empty_ranges_are_rejected() {
+        test_utils::run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(&HyperlaneDomain::new_test_domain("test"), db);
+            let result = reindexer(&db, None).reindex(5..=4).await;
+            assert!(matches!(
+                result,
+                Err(ReindexError::InvalidRange { from: 5, to: 4 })
+            ));
+        })
+        .await;
+    }
+}
@@ -1,53 +1,84 @@
 use std::{
     collections::{HashMap, HashSet},
     fmt::{Debug, Formatter},
-    sync::Arc,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
 };
 
 use async_trait::async_trait;
 use derive_more::AsRef;
-use eyre::Result;
+use eyre::{Context, Result};
 use futures_util::future::try_join_all;
 use hyperlane_base::{
     broadcast::BroadcastMpscSender,
-    db::{HyperlaneRocksDB, DB},
+    db::{
+        CorruptionTolerance, DiskGuard, DiskWatcher, HyperlaneRocksDB, InstanceLock, StatvfsProbe,
+        DB,
+    },
     metrics::{AgentMetrics, MetricsUpdater},
-    settings::{ChainConf, IndexSettings},
-    AgentMetadata, BaseAgent, ChainMetrics, ContractSyncMetrics, ContractSyncer, CoreMetrics,
-    HyperlaneAgentCore, SyncOptions,
+    pacing::RebuildPacer,
+    settings::{
+        check_db_environment, ChainConf, ContractClientCache, IgpContractSync, IndexSettings,
+    },
+    AgentMetadata, AlertDispatcher, BaseAgent, ChainMetrics, ContractSyncMetrics, ContractSyncer,
+    CoreMetrics, HyperlaneAgentCore, SyncOptions, ValidatorReputations,
 };
 use hyperlane_core::{
     rpc_clients::call_and_retry_n_times, ChainCommunicationError, ContractSyncCursor,
-    HyperlaneDomain, HyperlaneMessage, InterchainGasPayment, MerkleTreeInsertion, QueueOperation,
-    H512, U256,
+    HyperlaneDomain, HyperlaneMessage, IndexMode, Mailbox, MerkleTreeInsertion, QueueOperation,
+    ValidatorAnnounce, H160, H512, U256,
 };
+use prometheus::Registry;
 use tokio::{
     sync::{
         broadcast::Sender as BroadcastSender,
-        mpsc::{self, Receiver as MpscReceiver, UnboundedSender},
+        mpsc::{self, Receiver as MpscReceiver, UnboundedReceiver, UnboundedSender},
         RwLock,
     },
     task::JoinHandle,
 };
 use tokio_metrics::TaskMonitor;
+use tokio_util::sync::CancellationToken;
 use tracing::{error, info, info_span, instrument::Instrumented, warn, Instrument};
 
 use crate::{
-    merkle_tree::builder::MerkleTreeBuilder,
+    concurrent_signer::check_concurrent_signers,
+    merkle_tree::processor::{MerkleTreeProcessor, MerkleTreeProcessorMetrics},
+    processor::ProcessorExt,
+};
+use crate::{
+    merkle_tree::{
+        builder::MerkleTreeBuilder, observer::PrometheusMerkleTreeObserver,
+        shutdown::ShutdownSnapshotter,
+    },
     msg::{
-        blacklist::AddressBlacklist,
+        circuit_breaker::CircuitBreaker,
+        delivery_start::DeliveryStart,
+        events::MessageEventBus,
         gas_payment::GasPaymentEnforcer,
-        metadata::{BaseMetadataBuilder, IsmAwareAppContextClassifier},
+        gas_price_ceiling::GasPriceCeiling,
+        intake::MessageIntakeStore,
+        latency_budget::LatencyBudgetTracker,
+        metadata::{
+            BaseMetadataBuilder, CheckpointCache, IsmAwareAppContextClassifier, MetadataBuildPool,
+            QuorumPrefetcher,
+        },
         op_submitter::{SerialSubmitter, SerialSubmitterMetrics},
         pending_message::{MessageContext, MessageSubmissionMetrics},
         processor::{MessageProcessor, MessageProcessorMetrics},
+        round_trip::RoundTripGuard,
+        shadow::{ShadowDivergences, ShadowEvaluator},
+        throttle::BurstThrottle,
     },
+    origin_startup::{has_undelivered_messages, start_origins, startup_order, OriginHealth},
+    reindex::OriginReindexer,
     server::{self as relayer_server},
-    settings::{matching_list::MatchingList, RelayerSettings},
-};
-use crate::{
-    merkle_tree::processor::{MerkleTreeProcessor, MerkleTreeProcessorMetrics},
-    processor::ProcessorExt,
+    settings::{
+        matching_list::MatchingList,
+        snapshot::{ConfigHandle, ConfigSnapshot},
+        MessageThrottleConf, RelayerSettings,
+    },
+    tasks::AbortOnDrop,
 };
 use crate::{processor::Processor, server::ENDPOINT_MESSAGES_QUEUE_SIZE};
 
@@ -68,21 +99,73 @@ pub struct Relayer {
     #[as_ref]
     core: HyperlaneAgentCore,
     message_syncs: HashMap<HyperlaneDomain, Arc<dyn ContractSyncer<HyperlaneMessage>>>,
-    interchain_gas_payment_syncs:
-        HashMap<HyperlaneDomain, Arc<dyn ContractSyncer<InterchainGasPayment>>>,
+    /// Receiving end of the messages each origin's indexer hands straight to
+    /// its message processor
+    message_intakes: HashMap<HyperlaneDomain, UnboundedReceiver<HyperlaneMessage>>,
+    interchain_gas_payment_syncs: HashMap<HyperlaneDomain, Vec<IgpContractSync>>,
     /// Context data for each (origin, destination) chain pair a message can be
     /// sent between
     msg_ctxs: HashMap<ContextKey, Arc<MessageContext>>,
+    circuit_breakers: HashMap<HyperlaneDomain, Arc<CircuitBreaker>>,
+    /// Replay protection of the route pairs whose round trips are limited
+    round_trips: Vec<Arc<RoundTripGuard>>,
+    /// Where candidate configs diverge from the active one, if any shadows it
+    shadow_divergences: Option<Arc<ShadowDivergences>>,
+    /// Bounds the metadata builds in flight across all routes, if enabled
+    metadata_build_pool: Option<MetadataBuildPool>,
+    /// Times messages through the pipeline against their latency budgets, if
+    /// any are configured
+    latency_budgets: Option<Arc<LatencyBudgetTracker>>,
+    /// Lifecycle events of the messages relayed, shared by all routes
+    message_events: MessageEventBus,
+    /// Whether the admin server streams the lifecycle events
+    expose_message_events: bool,
     prover_syncs: HashMap<HyperlaneDomain, Arc<RwLock<MerkleTreeBuilder>>>,
+    /// Reindexes block ranges of the origins indexed by block on operator
+    /// request, if an admin token is configured
+    reindexers: HashMap<HyperlaneDomain, OriginReindexer>,
+    /// The block each origin's messages are delivered from
+    delivery_starts: HashMap<HyperlaneDomain, DeliveryStart>,
+    validator_reputations: HashMap<HyperlaneDomain, ValidatorReputations>,
     merkle_tree_hook_syncs: HashMap<HyperlaneDomain, Arc<dyn ContractSyncer<MerkleTreeInsertion>>>,
+    /// Paces backfilling the merkle tree insertions of each origin
+    rebuild_pacers: HashMap<HyperlaneDomain, RebuildPacer>,
     dbs: HashMap<HyperlaneDomain, HyperlaneRocksDB>,
-    message_whitelist: Arc<MatchingList>,
-    message_blacklist: Arc<MatchingList>,
-    address_blacklist: Arc<AddressBlacklist>,
+    /// Hot-reloadable state derived from the settings
+    config: ConfigHandle,
     transaction_gas_limit: Option<U256>,
     skip_transaction_gas_limit_for: HashSet<u32>,
     allow_local_checkpoint_syncers: bool,
-    metric_app_contexts: Vec<(MatchingList, String)>,
+    message_throttle: MessageThrottleConf,
+    merkle_tree_capacity_warning: f64,
+    /// How many corrupted db records each scan of an origin skips
+    corruption_tolerance: u32,
+    /// How long to wait for each origin to freeze its merkle tree on shutdown
+    merkle_tree_shutdown_timeout: Duration,
+    /// State of each origin's pipeline while they're started
+    origin_health: OriginHealth,
+    /// Watches the free disk space of the db, if thresholds are configured.
+    /// Taken when the relayer is run.
+    disk_watcher: Option<DiskWatcher>,
+    /// Pauses indexing while the disk of the db is almost full
+    disk_guard: Option<DiskGuard>,
+    /// Keeps other relayers from running on the same db, if enabled. Taken
+    /// when the relayer is run.
+    instance_lock: Option<InstanceLock>,
+    /// How long to watch the destination signers for transactions sent by
+    /// another process before submitting
+    signer_observation: Duration,
+    /// Whether to report ready once the priority origins are up, starting the
+    /// others in the background
+    lazy_origin_startup: bool,
+    priority_origins: Vec<HyperlaneDomain>,
+    /// Token operators authenticate admin requests with
+    admin_token: Option<String>,
+    /// Senders allowed to cancel their pending messages
+    cancellation_senders: Vec<H160>,
+    /// Hash of the config, recorded with every relaying decision
+    config_hash: String,
+    alerts: AlertDispatcher,
     core_metrics: Arc<CoreMetrics>,
     // TODO: decide whether to consolidate `agent_metrics` and `chain_metrics` into a single struct
     // or move them in `core_metrics`, like the validator metrics
@@ -96,12 +179,10 @@ impl Debug for Relayer {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "Relayer {{ origin_chains: {:?}, destination_chains: {:?}, message_whitelist: {:?}, message_blacklist: {:?}, address_blacklist: {:?}, transaction_gas_limit: {:?}, skip_transaction_gas_limit_for: {:?}, allow_local_checkpoint_syncers: {:?} }}",
+            "Relayer {{ origin_chains: {:?}, destination_chains: {:?}, config: {:?}, transaction_gas_limit: {:?}, skip_transaction_gas_limit_for: {:?}, allow_local_checkpoint_syncers: {:?} }}",
             self.origin_chains,
             self.destination_chains,
-            self.message_whitelist,
-            self.message_blacklist,
-            self.address_blacklist,
+            self.config.load(),
             self.transaction_gas_limit,
             self.skip_transaction_gas_limit_for,
             self.allow_local_checkpoint_syncers
@@ -109,6 +190,69 @@ impl Debug for Relayer {
     }
 }
 
+/// What a service embedding the relayer supplies instead of the relayer
+/// building it from its settings
+#[derive(Default)]
+pub struct RelayerDeps {
+    registry: Option<Registry>,
+    metrics: Option<(Arc<CoreMetrics>, AgentMetrics, ChainMetrics)>,
+    db: Option<DB>,
+    mailboxes: HashMap<HyperlaneDomain, Arc<dyn Mailbox>>,
+    validator_announces: HashMap<HyperlaneDomain, Arc<dyn ValidatorAnnounce>>,
+    tokio_console_server: Option<console_subscriber::Server>,
+}
+
+impl RelayerDeps {
+    /// Register the relayer's metrics with `registry` rather than a new one
+    pub fn with_registry(mut self, registry: Registry) -> Self {
+        self.registry = Some(registry);
+        self
+    }
+
+    /// Report to metrics already registered, e.g. those tracing reports to,
+    /// rather than registering them
+    pub fn with_metrics(
+        mut self,
+        core_metrics: Arc<CoreMetrics>,
+        agent_metrics: AgentMetrics,
+        chain_metrics: ChainMetrics,
+    ) -> Self {
+        self.metrics = Some((core_metrics, agent_metrics, chain_metrics));
+        self
+    }
+
+    /// Store the relayer's state in `db` rather than the one at the db path
+    /// of the settings
+    pub fn with_db(mut self, db: DB) -> Self {
+        self.db = Some(db);
+        self
+    }
+
+    /// Deliver messages to `domain` through `mailbox` rather than a client
+    /// built from the settings
+    pub fn with_mailbox(mut self, domain: HyperlaneDomain, mailbox: Arc<dyn Mailbox>) -> Self {
+        self.mailboxes.insert(domain, mailbox);
+        self
+    }
+
+    /// Look up the validators of `domain` through `validator_announce`
+    /// rather than a client built from the settings
+    pub fn with_validator_announce(
+        mut self,
+        domain: HyperlaneDomain,
+        validator_announce: Arc<dyn ValidatorAnnounce>,
+    ) -> Self {
+        self.validator_announces.insert(domain, validator_announce);
+        self
+    }
+
+    /// Serve the tokio console while the relayer runs
+    pub fn with_tokio_console_server(mut self, server: console_subscriber::Server) -> Self {
+        self.tokio_console_server = Some(server);
+        self
+    }
+}
+
 #[async_trait]
 #[allow(clippy::unit_arg)]
 impl BaseAgent for Relayer {
@@ -127,31 +271,142 @@ impl BaseAgent for Relayer {
     where
         Self: Sized,
     {
+        let deps = RelayerDeps::default()
+            .with_metrics(core_metrics, agent_metrics, chain_metrics)
+            .with_tokio_console_server(tokio_console_server);
+        Relayer::from_settings(settings, deps).await
+    }
+
+    #[allow(clippy::async_yields_async)]
+    async fn run(self) {
+        let token = CancellationToken::new();
+        let shutdown = token.clone();
+        tokio::spawn(async move {
+            shutdown_signal().await;
+            info!("Received shutdown signal");
+            shutdown.cancel();
+        });
+        if let Err(err) = Relayer::run(self, token).await {
+            error!(?err, "Relayer failed");
+        }
+    }
+}
+
+/// Resolves once the process is asked to terminate
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = terminate.recv() => {}
+                }
+                return;
+            }
+            Err(err) => warn!(?err, "Failed to listen for the terminate signal"),
+        }
+    }
+    if let Err(err) = tokio::signal::ctrl_c().await {
+        warn!(?err, "Failed to listen for the interrupt signal");
+        std::future::pending::<()>().await;
+    }
+}
+
+impl Relayer {
+    /// Build a relayer from its settings and what the service embedding it
+    /// supplies, see [`RelayerDeps`]. Nothing is registered globally, so that
+    /// several relayers can be embedded in the same process.
+    pub async fn from_settings(settings: RelayerSettings, deps: RelayerDeps) -> Result<Self> {
+        let RelayerDeps {
+            registry,
+            metrics,
+            db,
+            mailboxes: supplied_mailboxes,
+            validator_announces: supplied_validator_announces,
+            tokio_console_server,
+        } = deps;
+        let (core_metrics, agent_metrics, chain_metrics) = match metrics {
+            Some(metrics) => metrics,
+            None => {
+                let core_metrics = Arc::new(CoreMetrics::new(
+                    Self::AGENT_NAME,
+                    settings.metrics_port,
+                    registry.unwrap_or_default(),
+                )?);
+                let agent_metrics = AgentMetrics::new(&core_metrics)?;
+                let chain_metrics = ChainMetrics::new(&core_metrics)?;
+                (core_metrics, agent_metrics, chain_metrics)
+            }
+        };
+        core_metrics.set_config_hash(&settings.config_fingerprint.hash);
+
         let core = settings.build_hyperlane_core(core_metrics.clone());
-        let db = DB::from_path(&settings.db)?;
+        let db = match db {
+            Some(db) => db,
+            None => DB::from_path(&settings.db)?,
+        }
+        .with_message_compression(settings.message_compression.clone());
+        check_db_environment(&db, settings.environment.as_deref())?;
+        let instance_lock = if settings.instance_lock.enabled {
+            let conf = settings.instance_lock.clone();
+            Some(InstanceLock::acquire(db.clone(), &settings.db, conf, settings.takeover).await?)
+        } else {
+            None
+        };
+        settings.config_fingerprint.record(&db);
+        let config_hash = settings.config_fingerprint.hash.clone();
         let dbs = settings
             .origin_chains
             .iter()
             .map(|origin| (origin.clone(), HyperlaneRocksDB::new(origin, db.clone())))
             .collect::<HashMap<_, _>>();
 
-        let mailboxes = settings
-            .build_mailboxes(settings.destination_chains.iter(), &core_metrics)
-            .await?;
-        let validator_announces = settings
-            .build_validator_announces(settings.origin_chains.iter(), &core_metrics)
-            .await?;
+        // Contract clients are shared by every task using the same contract
+        let client_cache = Arc::new(ContractClientCache::new(core_metrics.clone()));
+        for (domain, mailbox) in supplied_mailboxes {
+            let conf = settings.chain_setup(&domain)?;
+            client_cache.insert(conf, conf.addresses.mailbox, mailbox);
+        }
+        for (domain, validator_announce) in supplied_validator_announces {
+            let conf = settings.chain_setup(&domain)?;
+            client_cache.insert(conf, conf.addresses.validator_announce, validator_announce);
+        }
+        let mut mailboxes = HashMap::new();
+        for destination in &settings.destination_chains {
+            let mailbox = client_cache
+                .mailbox(settings.chain_setup(destination)?)
+                .await?;
+            mailboxes.insert(destination.clone(), mailbox);
+        }
+        let mut validator_announces = HashMap::new();
+        for origin in &settings.origin_chains {
+            let validator_announce = client_cache
+                .validator_announce(settings.chain_setup(origin)?)
+                .await?;
+            validator_announces.insert(origin.clone(), validator_announce);
+        }
 
         let contract_sync_metrics = Arc::new(ContractSyncMetrics::new(&core_metrics));
 
+        let mut message_intakes = HashMap::new();
+        let mut message_stores = HashMap::new();
+        for origin in &settings.origin_chains {
+            let (intake_sender, intake_receiver) = mpsc::unbounded_channel();
+            message_intakes.insert(origin.clone(), intake_receiver);
+            message_stores.insert(
+                origin.clone(),
+                Arc::new(MessageIntakeStore::new(dbs[origin].clone(), intake_sender)),
+            );
+        }
+
         let message_syncs: HashMap<_, Arc<dyn ContractSyncer<HyperlaneMessage>>> = settings
             .contract_syncs::<HyperlaneMessage, _>(
                 settings.origin_chains.iter(),
                 &core_metrics,
                 &contract_sync_metrics,
-                dbs.iter()
-                    .map(|(d, db)| (d.clone(), Arc::new(db.clone())))
-                    .collect(),
+                message_stores,
             )
             .await?
             .into_iter()
@@ -159,18 +414,13 @@ impl BaseAgent for Relayer {
             .collect();
 
         let interchain_gas_payment_syncs = settings
-            .contract_syncs::<InterchainGasPayment, _>(
+            .igp_contract_syncs(
                 settings.origin_chains.iter(),
                 &core_metrics,
                 &contract_sync_metrics,
-                dbs.iter()
-                    .map(|(d, db)| (d.clone(), Arc::new(db.clone())))
-                    .collect(),
+                &dbs,
             )
-            .await?
-            .into_iter()
-            .map(|(k, v)| (k, v as _))
-            .collect();
+            .await?;
 
         let merkle_tree_hook_syncs = settings
             .contract_syncs::<MerkleTreeInsertion, _>(
@@ -185,30 +435,106 @@ impl BaseAgent for Relayer {
             .into_iter()
             .map(|(k, v)| (k, v as _))
             .collect();
+        let rebuild_pacers = settings
+            .origin_chains
+            .iter()
+            .map(|origin| {
+                let eta = contract_sync_metrics
+                    .backfill_eta
+                    .with_label_values(&["merkle_tree_hook", origin.name()]);
+                let pacer = RebuildPacer::new(settings.rebuild_pacing.clone(), "merkle_tree_hook")
+                    .with_db(dbs[origin].clone())
+                    .with_eta_metric(eta);
+                (origin.clone(), pacer)
+            })
+            .collect();
+
+        let disk_watcher = settings
+            .disk_space
+            .is_enabled()
+            .then(|| {
+                DiskWatcher::new(
+                    Box::new(StatvfsProbe::new(&settings.db)),
+                    settings.disk_space.clone(),
+                    &core_metrics,
+                )
+            })
+            .transpose()?;
+        let disk_guard = disk_watcher.as_ref().map(DiskWatcher::guard);
 
-        let message_whitelist = Arc::new(settings.whitelist);
-        let message_blacklist = Arc::new(settings.blacklist);
-        let address_blacklist = Arc::new(AddressBlacklist::new(settings.address_blacklist));
+        let config = ConfigHandle::new(ConfigSnapshot::from_settings(&settings))?;
+        let snapshot = config.load();
         let skip_transaction_gas_limit_for = settings.skip_transaction_gas_limit_for;
         let transaction_gas_limit = settings.transaction_gas_limit;
 
         info!(
-            %message_whitelist,
-            %message_blacklist,
-            ?address_blacklist,
+            message_whitelist = %snapshot.message_whitelist,
+            message_blacklist = %snapshot.message_blacklist,
+            address_blacklist = ?snapshot.address_blacklist,
             ?transaction_gas_limit,
             ?skip_transaction_gas_limit_for,
             "Whitelist configuration"
         );
 
-        // provers by origin chain
+        let chains = settings.chains_by_domain();
+
+        // provers by origin chain, sharing the observer reporting their events
+        let tree_observer = Arc::new(PrometheusMerkleTreeObserver::new(&core_metrics)?);
         let prover_syncs = settings
             .origin_chains
             .iter()
             .map(|origin| {
                 (
                     origin.clone(),
-                    Arc::new(RwLock::new(MerkleTreeBuilder::new())),
+                    Arc::new(RwLock::new(
+                        MerkleTreeBuilder::with_depth(chains[origin].merkle_tree_depth)
+                            .with_observer(origin.clone(), tree_observer.clone()),
+                    )),
+                )
+            })
+            .collect::<HashMap<_, _>>();
+
+        // only block ranges can be reindexed, and only on an operator's request
+        let mut reindexers = HashMap::new();
+        if settings.admin_token.is_some() {
+            for origin in &settings.origin_chains {
+                let chain = chains[origin];
+                if !matches!(chain.index.mode, IndexMode::Block) {
+                    continue;
+                }
+                let message_indexer = chain.build_message_indexer(&core_metrics).await?;
+                let gas_payment_indexer = chain
+                    .build_interchain_gas_payment_indexer(&core_metrics)
+                    .await?;
+                let reindexer = OriginReindexer::new(
+                    dbs[origin].clone(),
+                    Arc::from(message_indexer),
+                    Arc::from(gas_payment_indexer),
+                    prover_syncs[origin].clone(),
+                    chain.index.chunk_size,
+                );
+                reindexers.insert(origin.clone(), reindexer);
+            }
+        }
+
+        // messages dispatched before an origin's start block aren't delivered
+        let delivery_starts = settings
+            .origin_chains
+            .iter()
+            .map(|origin| {
+                let from = chains[origin].index.from;
+                (origin.clone(), DeliveryStart::new(from))
+            })
+            .collect::<HashMap<_, _>>();
+
+        // validator reputations by origin chain, shared by all its destinations
+        let validator_reputations = settings
+            .origin_chains
+            .iter()
+            .map(|origin| {
+                (
+                    origin.clone(),
+                    ValidatorReputations::new(dbs[origin].clone(), core_metrics.clone()),
                 )
             })
             .collect::<HashMap<_, _>>();
@@ -231,11 +557,98 @@ impl BaseAgent for Relayer {
             })
             .collect();
 
+        // candidate configs by origin chain, again due to the database scoping
+        let shadow_divergences = if settings.shadow_config.is_empty() {
+            None
+        } else {
+            info!(shadow_config=?settings.shadow_config, "Shadowing routes with candidate configs");
+            Some(Arc::new(ShadowDivergences::new(&core_metrics)?))
+        };
+        let shadow_evaluators: HashMap<_, _> = match &shadow_divergences {
+            Some(divergences) => settings
+                .origin_chains
+                .iter()
+                .map(|domain| {
+                    (
+                        domain.clone(),
+                        Arc::new(ShadowEvaluator::new(
+                            &settings.shadow_config,
+                            &dbs[domain],
+                            divergences.clone(),
+                        )),
+                    )
+                })
+                .collect(),
+            None => HashMap::new(),
+        };
+
+        let metadata_build_pool = if settings.metadata_pool.max_concurrency > 0 {
+            Some(MetadataBuildPool::new(
+                settings.metadata_pool.clone(),
+                &core_metrics,
+            )?)
+        } else {
+            None
+        };
+
+        let latency_budgets = if settings.latency_budgets.is_enabled() {
+            Some(Arc::new(LatencyBudgetTracker::new(
+                settings.latency_budgets.clone(),
+                &core_metrics,
+            )?))
+        } else {
+            None
+        };
+
+        let alerts = settings.alerts.build(&core_metrics);
+        let message_events = MessageEventBus::default();
         let mut msg_ctxs = HashMap::new();
         let mut destination_chains = HashMap::new();
+        let mut circuit_breakers = HashMap::new();
+        let gas_price_ceiling = Arc::new(GasPriceCeiling::new(settings.gas_price_ceiling.clone()));
+        // Shared by every route, so that its bounds are global
+        let checkpoint_cache = (settings.checkpoint_cache.max_routes > 0)
+            .then(|| CheckpointCache::new(settings.checkpoint_cache.clone(), &core_metrics))
+            .transpose()?;
+        // One per route pair, shared by the message processors of both of its
+        // domains
+        let round_trips = settings
+            .round_trips
+            .routes
+            .iter()
+            .filter_map(|conf| {
+                let Some((_, db)) = dbs
+                    .iter()
+                    .find(|(origin, _)| origin.id() == conf.domains[0])
+                else {
+                    warn!(
+                        domains = ?conf.domains,
+                        "Not limiting round trips on a pair whose first domain isn't an origin"
+                    );
+                    return None;
+                };
+                Some(Arc::new(RoundTripGuard::new(
+                    conf.clone(),
+                    settings.round_trips.max_tuples,
+                    db.clone(),
+                )))
+            })
+            .collect();
         for destination in &settings.destination_chains {
             let destination_chain_setup = core.settings.chain_setup(destination).unwrap().clone();
             destination_chains.insert(destination.clone(), destination_chain_setup.clone());
+            // One per destination, shared by all of its origins
+            let circuit_breaker = Arc::new(CircuitBreaker::new(
+                settings.circuit_breaker.clone(),
+                destination.clone(),
+                HyperlaneRocksDB::new(destination, db.clone()),
+                alerts.clone(),
+                core_metrics
+                    .circuit_breaker_state()
+                    .with_label_values(&[destination.name()]),
+                Instant::now(),
+            ));
+            circuit_breakers.insert(destination.clone(), circuit_breaker.clone());
             let transaction_gas_limit: Option<U256> =
                 if skip_transaction_gas_limit_for.contains(&destination.id()) {
                     None
@@ -245,7 +658,7 @@ impl BaseAgent for Relayer {
 
             for origin in &settings.origin_chains {
                 let db = dbs.get(origin).unwrap().clone();
-                let metadata_builder = BaseMetadataBuilder::new(
+                let mut metadata_builder = BaseMetadataBuilder::new(
                     origin.clone(),
                     destination_chain_setup.clone(),
                     prover_syncs[origin].clone(),
@@ -257,7 +670,19 @@ impl BaseAgent for Relayer {
                         mailboxes[destination].clone(),
                         settings.metric_app_contexts.clone(),
                     ),
+                    client_cache.clone(),
+                    validator_reputations[origin].clone(),
+                    settings.checkpoint_preferences.clone(),
+                    settings.ccip_read.clone(),
                 );
+                if settings.quorum_prefetch.max_preparations > 0 {
+                    metadata_builder = metadata_builder.with_quorum_prefetcher(
+                        QuorumPrefetcher::new(settings.quorum_prefetch.clone()),
+                    );
+                }
+                if let Some(cache) = &checkpoint_cache {
+                    metadata_builder = metadata_builder.with_checkpoint_cache(cache.clone());
+                }
 
                 msg_ctxs.insert(
                     ContextKey {
@@ -271,6 +696,15 @@ impl BaseAgent for Relayer {
                         origin_gas_payment_enforcer: gas_payment_enforcers[origin].clone(),
                         transaction_gas_limit,
                         metrics: MessageSubmissionMetrics::new(&core_metrics, origin, destination),
+                        config: config.clone(),
+                        circuit_breaker: Some(circuit_breaker.clone()),
+                        gas_price_ceiling: Some(gas_price_ceiling.clone()),
+                        shadow: shadow_evaluators.get(origin).cloned(),
+                        metadata_builds: metadata_build_pool
+                            .as_ref()
+                            .map(|pool| pool.route(origin, destination)),
+                        latency_budgets: latency_budgets.clone(),
+                        events: message_events.clone(),
                     }),
                 );
             }
@@ -281,27 +715,86 @@ impl BaseAgent for Relayer {
             origin_chains: settings.origin_chains,
             destination_chains,
             msg_ctxs,
+            circuit_breakers,
+            round_trips,
+            shadow_divergences,
+            metadata_build_pool,
+            latency_budgets,
+            message_events,
+            expose_message_events: settings.expose_message_events,
             core,
             message_syncs,
+            message_intakes,
             interchain_gas_payment_syncs,
             prover_syncs,
+            reindexers,
+            delivery_starts,
+            validator_reputations,
             merkle_tree_hook_syncs,
-            message_whitelist,
-            message_blacklist,
-            address_blacklist,
+            rebuild_pacers,
+            config,
             transaction_gas_limit,
             skip_transaction_gas_limit_for,
             allow_local_checkpoint_syncers: settings.allow_local_checkpoint_syncers,
-            metric_app_contexts: settings.metric_app_contexts,
+            message_throttle: settings.message_throttle,
+            merkle_tree_capacity_warning: settings.merkle_tree_capacity_warning,
+            corruption_tolerance: settings.corruption_tolerance,
+            merkle_tree_shutdown_timeout: settings.merkle_tree_shutdown_timeout,
+            origin_health: OriginHealth::default(),
+            disk_watcher,
+            disk_guard,
+            instance_lock,
+            signer_observation: settings.signer_observation,
+            lazy_origin_startup: settings.lazy_origin_startup,
+            priority_origins: settings.priority_origins,
+            admin_token: settings.admin_token,
+            cancellation_senders: settings.cancellation_senders,
+            config_hash,
+            alerts,
             core_metrics,
             agent_metrics,
             chain_metrics,
-            tokio_console_server: Some(tokio_console_server),
+            tokio_console_server,
         })
     }
 
-    #[allow(clippy::async_yields_async)]
-    async fn run(mut self) {
+    /// Run the relayer until `token` is cancelled, then snapshot its merkle
+    /// trees and stop its tasks. Fails if the relayer can't be started, or
+    /// one of its tasks panics.
+    pub async fn run(mut self, token: CancellationToken) -> Result<()> {
+        // Also cancelled if another relayer takes over the db
+        let token = token.child_token();
+        // Whatever may fail is set up before any task is spawned
+        let server = self.core.settings.server(self.core_metrics.clone())?;
+        let mut metrics_updaters = Vec::with_capacity(self.destination_chains.len());
+        for (dest_domain, dest_conf) in &self.destination_chains {
+            let metrics_updater = MetricsUpdater::new(
+                dest_conf,
+                self.core_metrics.clone(),
+                self.agent_metrics.clone(),
+                self.chain_metrics.clone(),
+                Self::AGENT_NAME.to_string(),
+            )
+            .await
+            .with_context(|| {
+                format!("Error creating metrics updater for destination {dest_domain}")
+            })?;
+            metrics_updaters.push(metrics_updater);
+        }
+        // Nothing is submitted while the signers are observed
+        tokio::select! {
+            _ = check_concurrent_signers(
+                self.destination_chains.iter(),
+                &self.core_metrics,
+                self.signer_observation,
+                &self.alerts,
+            ) => {}
+            _ = token.cancelled() => {
+                info!("Shut down while observing the destination signers");
+                return Ok(());
+            }
+        }
+
         let mut tasks = vec![];
 
         let task_monitor = tokio_metrics::TaskMonitor::new();
@@ -315,11 +808,25 @@ impl BaseAgent for Relayer {
                 }));
             tasks.push(console_server.instrument(info_span!("Tokio console server")));
         }
+        if let Some(disk_watcher) = self.disk_watcher.take() {
+            tasks.push(disk_watcher.spawn().instrument(info_span!("DiskWatcher")));
+        }
+        if let Some(instance_lock) = self.instance_lock.take() {
+            let token = token.clone();
+            let keep_alive = tokio::spawn(async move {
+                let err = instance_lock.keep_alive().await;
+                error!(%err, "Lost the instance lock of the db, shutting down");
+                token.cancel();
+            });
+            tasks.push(keep_alive.instrument(info_span!("InstanceLock")));
+        }
         let sender = BroadcastSender::<MatchingList>::new(ENDPOINT_MESSAGES_QUEUE_SIZE);
         // send channels by destination chain
         let mut send_channels = HashMap::with_capacity(self.destination_chains.len());
         let mut prep_queues = HashMap::with_capacity(self.destination_chains.len());
-        for (dest_domain, dest_conf) in &self.destination_chains {
+        let mut retry_queues = HashMap::with_capacity(self.destination_chains.len());
+        let mut submitter_queues = HashMap::with_capacity(self.destination_chains.len());
+        for dest_domain in self.destination_chains.keys() {
             let (send_channel, receive_channel) = mpsc::unbounded_channel::<QueueOperation>();
             send_channels.insert(dest_domain.id(), send_channel);
             let serial_submitter = SerialSubmitter::new(
@@ -336,95 +843,243 @@ impl BaseAgent for Relayer {
                 task_monitor.clone(),
             );
             prep_queues.insert(dest_domain.id(), serial_submitter.prepare_queue().await);
+            retry_queues.insert(dest_domain.id(), serial_submitter.prepare_op_queue());
+            submitter_queues.insert(dest_domain.id(), serial_submitter.queues());
 
             tasks.push(self.run_destination_submitter(
                 dest_domain,
                 serial_submitter,
                 task_monitor.clone(),
             ));
+        }
+        tasks.extend(metrics_updaters.into_iter().map(MetricsUpdater::spawn));
 
-            let metrics_updater = MetricsUpdater::new(
-                dest_conf,
-                self.core_metrics.clone(),
-                self.agent_metrics.clone(),
-                self.chain_metrics.clone(),
-                Self::AGENT_NAME.to_string(),
+        // run server
+        let mut custom_routes = relayer_server::Server::new()
+            .with_op_retry(sender.clone(), retry_queues)
+            .with_message_queue(prep_queues)
+            .with_submitter_queues(submitter_queues)
+            .with_dbs(
+                self.dbs
+                    .iter()
+                    .map(|(d, db)| (d.id(), db.clone()))
+                    .collect(),
             )
-            .await
-            .unwrap_or_else(|_| {
-                panic!("Error creating metrics updater for destination {dest_domain}")
-            });
-            tasks.push(metrics_updater.spawn());
+            .with_igp_contracts(
+                self.interchain_gas_payment_syncs
+                    .iter()
+                    .map(|(d, igps)| (d.id(), igps.iter().map(|igp| igp.address).collect()))
+                    .collect(),
+            )
+            .with_prover_syncs(
+                self.prover_syncs
+                    .iter()
+                    .map(|(d, prover_sync)| (d.name().to_owned(), prover_sync.clone()))
+                    .collect(),
+            )
+            .with_delivery_policies(
+                self.delivery_starts
+                    .iter()
+                    .filter_map(|(d, start)| Some((d.name().to_owned(), start.policy()?)))
+                    .collect(),
+            )
+            .with_validator_reputations(
+                self.validator_reputations
+                    .iter()
+                    .map(|(d, reputations)| (d.name().to_owned(), reputations.clone()))
+                    .collect(),
+            )
+            .with_circuit_breakers(
+                self.circuit_breakers
+                    .iter()
+                    .map(|(d, breaker)| (d.name().to_owned(), breaker.clone()))
+                    .collect(),
+            )
+            .with_rebuild_pacers(
+                self.rebuild_pacers
+                    .iter()
+                    .map(|(d, pacer)| (d.name().to_owned(), pacer.clone()))
+                    .collect(),
+            );
+        if let Some(shadow_divergences) = &self.shadow_divergences {
+            custom_routes = custom_routes.with_shadow_divergences(shadow_divergences.clone());
         }
-
-        for origin in &self.origin_chains {
-            self.chain_metrics.set_critical_error(origin.name(), false);
-            let maybe_broadcaster = self
-                .message_syncs
-                .get(origin)
-                .and_then(|sync| sync.get_broadcaster());
-            tasks.push(self.run_message_sync(origin, task_monitor.clone()).await);
-            tasks.push(
-                self.run_interchain_gas_payment_sync(
-                    origin,
-                    BroadcastMpscSender::map_get_receiver(maybe_broadcaster.as_ref()).await,
-                    task_monitor.clone(),
-                )
-                .await,
+        if let Some(latency_budgets) = &self.latency_budgets {
+            custom_routes = custom_routes.with_latency_budgets(latency_budgets.clone());
+        }
+        if !self.round_trips.is_empty() {
+            custom_routes = custom_routes.with_round_trips(self.round_trips.clone());
+        }
+        if self.expose_message_events {
+            custom_routes = custom_routes.with_message_events(self.message_events.clone());
+        }
+        custom_routes = custom_routes
+            .with_origin_health(self.origin_health.clone())
+            .with_readiness(self.origin_health.clone(), self.disk_guard.clone())
+            .with_message_cancellation(
+                self.admin_token.clone(),
+                self.cancellation_senders.clone(),
+                self.config_hash.clone(),
             );
-            tasks.push(
-                self.run_merkle_tree_hook_syncs(
-                    origin,
-                    BroadcastMpscSender::map_get_receiver(maybe_broadcaster.as_ref()).await,
-                    task_monitor.clone(),
-                )
-                .await,
+        if let Some(admin_token) = &self.admin_token {
+            custom_routes = custom_routes.with_reindexers(
+                self.reindexers
+                    .iter()
+                    .map(|(d, reindexer)| (d.name().to_owned(), reindexer.clone()))
+                    .collect(),
+                admin_token.clone(),
             );
         }
-        // run server
-        let custom_routes = relayer_server::Server::new()
-            .with_op_retry(sender.clone())
-            .with_message_queue(prep_queues)
-            .routes();
+        let custom_routes = custom_routes.routes();
 
-        let server = self
-            .core
-            .settings
-            .server(self.core_metrics.clone())
-            .expect("Failed to create server");
         let server_task = server
             .run_with_custom_routes(custom_routes)
             .instrument(info_span!("Relayer server"));
         tasks.push(server_task);
+        // Every task is stopped once the relayer returns
+        let _abort = AbortOnDrop::new(tasks.iter().map(Instrumented::inner));
 
-        // each message process attempts to send messages from a chain
-        for origin in &self.origin_chains {
-            tasks.push(self.run_message_processor(
-                origin,
-                send_channels.clone(),
-                task_monitor.clone(),
-            ));
-            tasks.push(self.run_merkle_tree_processor(origin, task_monitor.clone()));
-        }
+        let shutdown_snapshotter = ShutdownSnapshotter::new(
+            self.origin_chains
+                .iter()
+                .map(|origin| (self.dbs[origin].clone(), self.prover_syncs[origin].clone()))
+                .collect(),
+            self.merkle_tree_shutdown_timeout,
+        );
+        // Origins are started once the server is up, so that it reports their
+        // health while they are
+        let order = self.origin_startup_order();
+        let origin_health = self.origin_health.clone();
+        let message_intakes = Mutex::new(std::mem::take(&mut self.message_intakes));
+        let metadata_build_pool = self.metadata_build_pool.clone();
+        let relayer = Arc::new(self);
+        let startup = start_origins(order, origin_health, move |origin| {
+            let relayer = relayer.clone();
+            let send_channels = send_channels.clone();
+            let message_intake = message_intakes.lock().unwrap().remove(&origin).unwrap();
+            let task_monitor = task_monitor.clone();
+            async move {
+                relayer
+                    .run_origin(&origin, send_channels, message_intake, task_monitor)
+                    .await
+            }
+        });
+        let origin_tasks = tokio::select! {
+            origin_tasks = startup => origin_tasks,
+            _ = token.cancelled() => {
+                info!("Shut down while starting the origins");
+                return Ok(());
+            }
+        };
+        let _abort_origins = AbortOnDrop::new(origin_tasks.iter().map(Instrumented::inner));
+        tasks.extend(origin_tasks);
 
-        if let Err(err) = try_join_all(tasks).await {
-            tracing::error!(
-                error=?err,
-                "Relayer task panicked"
-            );
+        tokio::select! {
+            result = try_join_all(tasks) => {
+                result.context("Relayer task panicked")?;
+            }
+            _ = token.cancelled() => {
+                info!("Shutting down, snapshotting merkle trees");
+                if let Some(pool) = &metadata_build_pool {
+                    pool.shut_down();
+                }
+                // Ingestion stays frozen until the tasks are stopped
+                let _snapshot = shutdown_snapshotter
+                    .snapshot()
+                    .await
+                    .map_err(|err| error!(?err, "Failed to snapshot merkle trees on shutdown"));
+            }
         }
+        Ok(())
+    }
+
+    /// Lifecycle events of the messages this relayer processes, to subscribe
+    /// to when embedding it in another service
+    pub fn message_events(&self) -> MessageEventBus {
+        self.message_events.clone()
+    }
+
+    /// Handle on the hot-reloadable state derived from the settings, for a
+    /// service embedding the relayer to reload them through
+    pub fn config(&self) -> ConfigHandle {
+        self.config.clone()
     }
-}
 
-impl Relayer {
     fn record_critical_error(
         &self,
         origin: &HyperlaneDomain,
         err: ChainCommunicationError,
         message: &str,
     ) {
+        self.origin_health.fail(origin, format!("{message}: {err}"));
         error!(?err, origin=?origin, "{message}");
-        self.chain_metrics.set_critical_error(origin.name(), true);
+        self.chain_metrics.set_critical_error(origin, true);
+    }
+
+    /// The order to start the origins in, and whether the relayer waits for
+    /// each of them before it's ready. Unless startup is lazy, it waits for
+    /// all of them.
+    fn origin_startup_order(&self) -> Vec<(HyperlaneDomain, bool)> {
+        let order = startup_order(&self.origin_chains, &self.priority_origins, |origin| {
+            has_undelivered_messages(&self.dbs[origin])
+        });
+        if self.lazy_origin_startup {
+            order
+        } else {
+            order
+                .into_iter()
+                .map(|(origin, _)| (origin, true))
+                .collect()
+        }
+    }
+
+    /// Start the syncs and processors of an origin
+    async fn run_origin(
+        &self,
+        origin: &HyperlaneDomain,
+        send_channels: HashMap<u32, UnboundedSender<QueueOperation>>,
+        message_intake: UnboundedReceiver<HyperlaneMessage>,
+        task_monitor: TaskMonitor,
+    ) -> Vec<Instrumented<JoinHandle<()>>> {
+        self.chain_metrics.set_critical_error(origin, false);
+        let mut tasks = vec![];
+        // The syncs already started are stopped if startup is abandoned
+        let mut abort = AbortOnDrop::default();
+        let maybe_broadcaster = self
+            .message_syncs
+            .get(origin)
+            .and_then(|sync| sync.get_broadcaster());
+        let message_sync = self.run_message_sync(origin, task_monitor.clone()).await;
+        abort.extend([message_sync.inner()]);
+        tasks.push(message_sync);
+        let igp_syncs = self
+            .run_interchain_gas_payment_syncs(
+                origin,
+                maybe_broadcaster.as_ref(),
+                task_monitor.clone(),
+            )
+            .await;
+        abort.extend(igp_syncs.iter().map(Instrumented::inner));
+        tasks.extend(igp_syncs);
+        tasks.push(
+            self.run_merkle_tree_hook_syncs(
+                origin,
+                BroadcastMpscSender::map_get_receiver(maybe_broadcaster.as_ref()).await,
+                task_monitor.clone(),
+            )
+            .await,
+        );
+        abort.disarm();
+        tasks.push(self.run_message_compression_migration(origin));
+        // the message processor attempts to send messages from the chain
+        tasks.push(self.run_message_processor(
+            origin,
+            send_channels,
+            message_intake,
+            task_monitor.clone(),
+        ));
+        tasks.push(self.run_merkle_tree_processor(origin, task_monitor));
+        tasks
     }
 
     async fn instantiate_cursor_with_retries<T: 'static>(
@@ -445,11 +1100,59 @@ impl Relayer {
         .await
     }
 
+    /// Compress messages stored before compression was enabled or its
+    /// threshold lowered
+    fn run_message_compression_migration(
+        &self,
+        origin: &HyperlaneDomain,
+    ) -> Instrumented<JoinHandle<()>> {
+        let db = self.dbs.get(origin).unwrap().clone();
+        tokio::task::spawn_blocking(move || match db.compress_stored_messages() {
+            Ok(compressed) => info!(compressed, "Finished compressing stored messages"),
+            Err(err) => warn!(?err, "Failed to compress stored messages"),
+        })
+        .instrument(info_span!(
+            "MessageCompressionMigration",
+            origin = origin.name()
+        ))
+    }
+
+    /// Index the messages stored before the nonce indices were maintained,
+    /// which the message cursor looks its progress up in
+    async fn backfill_message_nonce_indices(&self, origin: &HyperlaneDomain) {
+        let db = self.dbs.get(origin).unwrap().clone();
+        let backfill = tokio::task::spawn_blocking(move || db.backfill_message_nonce_indices())
+            .instrument(info_span!(
+                "MessageNonceIndexBackfill",
+                origin = origin.name()
+            ))
+            .await;
+        match backfill {
+            Ok(Ok(0)) => {}
+            Ok(Ok(indexed)) => info!(
+                indexed,
+                origin = origin.name(),
+                "Backfilled the nonce indices of stored messages"
+            ),
+            Ok(Err(err)) => warn!(
+                ?err,
+                origin = origin.name(),
+                "Failed to backfill the nonce indices of stored messages"
+            ),
+            Err(err) => warn!(
+                ?err,
+                origin = origin.name(),
+                "Nonce index backfill task panicked"
+            ),
+        }
+    }
+
     async fn run_message_sync(
         &self,
         origin: &HyperlaneDomain,
         task_monitor: TaskMonitor,
     ) -> Instrumented<JoinHandle<()>> {
+        self.backfill_message_nonce_indices(origin).await;
         let index_settings = self.as_ref().settings.chains[origin.name()].index_settings();
         let contract_sync = self.message_syncs.get(origin).unwrap().clone();
         let cursor_instantiation_result =
@@ -462,47 +1165,61 @@ impl Relayer {
                 return tokio::spawn(async {}).instrument(info_span!("MessageSync"));
             }
         };
+        let disk_guard = self.disk_guard.clone();
         tokio::spawn(TaskMonitor::instrument(&task_monitor, async move {
             contract_sync
                 .clone()
-                .sync("dispatched_messages", cursor.into())
+                .sync(
+                    "dispatched_messages",
+                    SyncOptions::from(cursor).with_disk_guard(disk_guard),
+                )
                 .await
         }))
         .instrument(info_span!("MessageSync"))
     }
 
-    async fn run_interchain_gas_payment_sync(
+    /// Spawn a gas payment sync for each of the origin's IGP contracts
+    async fn run_interchain_gas_payment_syncs(
         &self,
         origin: &HyperlaneDomain,
-        tx_id_receiver: Option<MpscReceiver<H512>>,
+        maybe_broadcaster: Option<&BroadcastMpscSender<H512>>,
         task_monitor: TaskMonitor,
-    ) -> Instrumented<JoinHandle<()>> {
-        let index_settings = self.as_ref().settings.chains[origin.name()].index_settings();
-        let contract_sync = self
-            .interchain_gas_payment_syncs
-            .get(origin)
-            .unwrap()
-            .clone();
-        let cursor_instantiation_result =
-            Self::instantiate_cursor_with_retries(contract_sync.clone(), index_settings.clone())
-                .await;
-        let cursor = match cursor_instantiation_result {
-            Ok(cursor) => cursor,
-            Err(err) => {
-                self.record_critical_error(origin, err, CURSOR_BUILDING_ERROR);
-                return tokio::spawn(async {}).instrument(info_span!("IgpSync"));
-            }
-        };
-        tokio::spawn(TaskMonitor::instrument(&task_monitor, async move {
-            contract_sync
-                .clone()
-                .sync(
-                    "gas_payments",
-                    SyncOptions::new(Some(cursor), tx_id_receiver),
-                )
-                .await
-        }))
-        .instrument(info_span!("IgpSync"))
+    ) -> Vec<Instrumented<JoinHandle<()>>> {
+        let mut tasks = vec![];
+        // The syncs already started are stopped if startup is abandoned
+        let mut abort = AbortOnDrop::default();
+        for igp in &self.interchain_gas_payment_syncs[origin] {
+            let span = info_span!("IgpSync", igp = ?igp.address);
+            let contract_sync = igp.sync.clone();
+            let cursor_instantiation_result = Self::instantiate_cursor_with_retries(
+                contract_sync.clone(),
+                igp.index_settings.clone(),
+            )
+            .await;
+            let cursor = match cursor_instantiation_result {
+                Ok(cursor) => cursor,
+                Err(err) => {
+                    self.record_critical_error(origin, err, CURSOR_BUILDING_ERROR);
+                    tasks.push(tokio::spawn(async {}).instrument(span));
+                    continue;
+                }
+            };
+            let label = if igp.configured {
+                "gas_payments"
+            } else {
+                "additional_gas_payments"
+            };
+            let tx_id_receiver = BroadcastMpscSender::map_get_receiver(maybe_broadcaster).await;
+            let opts = SyncOptions::new(Some(cursor), tx_id_receiver)
+                .with_disk_guard(self.disk_guard.clone());
+            let task = tokio::spawn(TaskMonitor::instrument(&task_monitor, async move {
+                contract_sync.clone().sync(label, opts).await
+            }));
+            abort.extend([&task]);
+            tasks.push(task.instrument(span));
+        }
+        abort.disarm();
+        tasks
     }
 
     async fn run_merkle_tree_hook_syncs(
@@ -511,7 +1228,19 @@ impl Relayer {
         tx_id_receiver: Option<MpscReceiver<H512>>,
         task_monitor: TaskMonitor,
     ) -> Instrumented<JoinHandle<()>> {
-        let index_settings = self.as_ref().settings.chains[origin.name()].index.clone();
+        let configured = self.as_ref().settings.chains[origin.name()].index.clone();
+        // The tree needs every leaf, including those of messages dispatched
+        // before the start block
+        let mut index_settings =
+            self.delivery_starts[origin].tree_index_settings(&self.dbs[origin], configured.clone());
+        if index_settings.from != configured.from {
+            info!(
+                from = configured.from,
+                "Leaves before the index start block may be missing, backfilling the merkle tree \
+                 from the first block"
+            );
+        }
+        index_settings.pacer = Some(self.rebuild_pacers[origin].clone());
         let contract_sync = self.merkle_tree_hook_syncs.get(origin).unwrap().clone();
         let cursor_instantiation_result =
             Self::instantiate_cursor_with_retries(contract_sync.clone(), index_settings.clone())
@@ -523,14 +1252,10 @@ impl Relayer {
                 return tokio::spawn(async {}).instrument(info_span!("MerkleTreeHookSync"));
             }
         };
+        let opts =
+            SyncOptions::new(Some(cursor), tx_id_receiver).with_disk_guard(self.disk_guard.clone());
         tokio::spawn(TaskMonitor::instrument(&task_monitor, async move {
-            contract_sync
-                .clone()
-                .sync(
-                    "merkle_tree_hook",
-                    SyncOptions::new(Some(cursor), tx_id_receiver),
-                )
-                .await
+            contract_sync.clone().sync("merkle_tree_hook", opts).await
         }))
         .instrument(info_span!("MerkleTreeHookSync"))
     }
@@ -539,6 +1264,7 @@ impl Relayer {
         &self,
         origin: &HyperlaneDomain,
         send_channels: HashMap<u32, UnboundedSender<QueueOperation>>,
+        message_intake: UnboundedReceiver<HyperlaneMessage>,
         task_monitor: TaskMonitor,
     ) -> Instrumented<JoinHandle<()>> {
         let metrics = MessageProcessorMetrics::new(
@@ -564,13 +1290,23 @@ impl Relayer {
 
         let message_processor = MessageProcessor::new(
             self.dbs.get(origin).unwrap().clone(),
-            self.message_whitelist.clone(),
-            self.message_blacklist.clone(),
-            self.address_blacklist.clone(),
+            self.config.clone(),
             metrics,
             send_channels,
             destination_ctxs,
-            self.metric_app_contexts.clone(),
+            message_intake,
+            BurstThrottle::new(
+                self.message_throttle.clone(),
+                origin.name().to_owned(),
+                self.core_metrics.throttled_message_sources(),
+            ),
+            self.delivery_starts[origin],
+            self.round_trips
+                .iter()
+                .filter(|guard| guard.domains().contains(&origin.id()))
+                .cloned()
+                .collect(),
+            self.corruption_tolerance(origin, "message_processor"),
         );
 
         let span = info_span!("MessageProcessor", origin=%message_processor.domain());
@@ -579,17 +1315,32 @@ impl Relayer {
         processor.spawn().instrument(span)
     }
 
+    /// Skip up to the configured number of corrupted records in a scan of
+    /// the origin's db
+    fn corruption_tolerance(&self, origin: &HyperlaneDomain, scan: &str) -> CorruptionTolerance {
+        CorruptionTolerance::new(
+            self.corruption_tolerance,
+            self.core_metrics
+                .db_corrupted_records()
+                .with_label_values(&[origin.name(), scan]),
+        )
+    }
+
     fn run_merkle_tree_processor(
         &self,
         origin: &HyperlaneDomain,
         task_monitor: TaskMonitor,
     ) -> Instrumented<JoinHandle<()>> {
-        let metrics = MerkleTreeProcessorMetrics::new();
+        let metrics = MerkleTreeProcessorMetrics::new(&self.core_metrics, origin);
         let merkle_tree_processor = MerkleTreeProcessor::new(
             self.dbs.get(origin).unwrap().clone(),
             metrics,
             self.prover_syncs[origin].clone(),
-        );
+            self.alerts.clone(),
+            self.merkle_tree_capacity_warning,
+            self.corruption_tolerance(origin, "merkle_tree_processor"),
+        )
+        .with_disk_guard(self.disk_guard.clone());
 
         let span = info_span!("MerkleTreeProcessor", origin=%merkle_tree_processor.domain());
         let processor = Processor::new(Box::new(merkle_tree_processor), task_monitor.clone());
@@ -607,8 +1358,10 @@ impl Relayer {
         let span = info_span!("SerialSubmitter", destination=%destination);
         let destination = destination.clone();
         tokio::spawn(TaskMonitor::instrument(&task_monitor, async move {
+            let submitter = serial_submitter.spawn();
+            let _abort = AbortOnDrop::new([submitter.inner()]);
             // Propagate task panics
-            serial_submitter.spawn().await.unwrap_or_else(|err| {
+            submitter.await.unwrap_or_else(|err| {
                 panic!(
                     "destination submitter panicked for destination {}: {:?}",
                     destination, err
@@ -620,4 +1373,120 @@ impl Relayer {
 }
 
 #[cfg(test)]
-mod test {}
+mod test {
+    use std::{path::Path, time::Duration};
+
+    use config::{Config, FileFormat};
+    use hyperlane_core::{
+        config::{ConfigPath, FromRawConf},
+        KnownHyperlaneDomain,
+    };
+    use hyperlane_test::mocks::{MockMailboxContract, MockValidatorAnnounceContract};
+    use serde_json::json;
+    use tokio::time::timeout;
+
+    use super::*;
+    use crate::settings::RawRelayerSettings;
+
+    const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Settings relaying ethereum, whose RPC nothing listens at
+    fn settings(db: &Path) -> RelayerSettings {
+        let config = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../config/mainnet_config.json");
+        let config: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(config).unwrap()).unwrap();
+        let mut ethereum = config["chains"]["ethereum"].clone();
+        ethereum["rpcUrls"] = json!([{ "http": "http://127.0.0.1:1" }]);
+        let raw = json!({
+            "chains": { "ethereum": ethereum },
+            "relayChains": "ethereum",
+            "db": db,
+            "metricsPort": 0,
+        });
+        let raw: RawRelayerSettings = Config::builder()
+            .add_source(config::File::from_str(&raw.to_string(), FileFormat::Json))
+            .build()
+            .unwrap()
+            .try_deserialize()
+            .unwrap();
+        RelayerSettings::from_config(raw, &ConfigPath::default()).unwrap()
+    }
+
+    /// Contract clients the relayer built from its settings, by client
+    fn constructions(registry: &Registry) -> HashMap<String, f64> {
+        registry
+            .gather()
+            .iter()
+            .filter(|family| family.get_name() == "hyperlane_contract_client_constructions")
+            .flat_map(|family| family.get_metric())
+            .map(|metric| {
+                let client = metric
+                    .get_label()
+                    .iter()
+                    .find(|label| label.get_name() == "client")
+                    .map(|label| label.get_value().to_owned())
+                    .unwrap_or_default();
+                (client, metric.get_counter().get_value())
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_embedded_relayers_are_isolated_and_shut_down_independently() {
+        let ethereum = HyperlaneDomain::Known(KnownHyperlaneDomain::Ethereum);
+        let (db_a, db_b) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        let (registry_a, registry_b) = (Registry::new(), Registry::new());
+
+        // The first relayer is handed its db and all its clients, the second
+        // opens its db and builds its validator announce from its settings
+        let relayer_a = Relayer::from_settings(
+            settings(db_a.path()),
+            RelayerDeps::default()
+                .with_registry(registry_a.clone())
+                .with_db(DB::from_path(db_a.path()).unwrap())
+                .with_mailbox(ethereum.clone(), Arc::new(MockMailboxContract::default()))
+                .with_validator_announce(
+                    ethereum.clone(),
+                    Arc::new(MockValidatorAnnounceContract::default()),
+                ),
+        )
+        .await
+        .unwrap();
+        let relayer_b = Relayer::from_settings(
+            settings(db_b.path()),
+            RelayerDeps::default()
+                .with_registry(registry_b.clone())
+                .with_mailbox(ethereum.clone(), Arc::new(MockMailboxContract::default())),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(constructions(&registry_a), HashMap::new());
+        assert_eq!(
+            constructions(&registry_b),
+            HashMap::from([("validator_announce".to_owned(), 1.0)])
+        );
+
+        let (token_a, token_b) = (CancellationToken::new(), CancellationToken::new());
+        let run_a = tokio::spawn(relayer_a.run(token_a.clone()));
+        let run_b = tokio::spawn(relayer_b.run(token_b.clone()));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        token_a.cancel();
+        timeout(SHUTDOWN_TIMEOUT, run_a)
+            .await
+            .expect("The relayer didn't shut down")
+            .unwrap()
+            .unwrap();
+        // The other relayer keeps running until it's shut down too
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(!run_b.is_finished());
+
+        token_b.cancel();
+        timeout(SHUTDOWN_TIMEOUT, run_b)
+            .await
+            .expect("The relayer didn't shut down")
+            .unwrap()
+            .unwrap();
+    }
+}
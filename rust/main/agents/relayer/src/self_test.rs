@@ -0,0 +1,840 @@
+//! One-shot `self-test` command that checks, against the configured chains but
+//! without delivering anything, that every stage of the relayer's pipeline
+//! would work: RPCs, contracts, indexing, the merkle tree, metadata building
+//! and signer balances.
+//!
+//! Each check runs once per configured chain and can be skipped with
+//! `--skip-<check>`. The command prints a pass/fail table and exits non-zero if
+//! any check failed.
+//!
+//! The self-test opens the relayer database, so it must run while the relayer
+//! is stopped, or against a copy of the database passed with `--db`.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::{self, Display, Formatter},
+    ops::RangeInclusive,
+    str::FromStr,
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+use eyre::{bail, ensure, eyre, Result};
+use hyperlane_base::{
+    db::{HyperlaneDb, HyperlaneRocksDB, DB},
+    settings::{ChainConf, ContractClientCache, IndexSettings},
+    CheckpointPreference, CoreMetrics, LoadableFromSettings, ValidatorReputations,
+};
+use hyperlane_core::{
+    Balance, HyperlaneMessage, IndexMode, Mailbox, ModuleType, SequenceAwareIndexer, H256, U256,
+};
+use strum::IntoEnumIterator;
+use tokio::sync::RwLock;
+
+use crate::{
+    merkle_tree::{availability::TreeAvailability, builder::MerkleTreeBuilder},
+    msg::metadata::{
+        BaseMetadataBuilder, IsmAwareAppContextClassifier, MessageMetadataBuilder, MetadataBuilder,
+    },
+    settings::RelayerSettings,
+};
+
+/// How many of the most recent messages are scanned for one to test with
+const MAX_SCANNED_MESSAGES: u32 = 100;
+/// How many ISMs are inspected when looking for the validators of a message
+const MAX_INSPECTED_ISMS: usize = 16;
+
+/// The checks run by the self-test, in the order they're run and reported
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    strum::Display,
+    strum::EnumIter,
+    strum::EnumString,
+)]
+#[strum(serialize_all = "kebab-case")]
+pub enum SelfTestCheck {
+    /// The RPC is reachable and reports the configured chain id
+    Rpc,
+    /// Contract code exists at every configured core contract address
+    Contracts,
+    /// The indexer can fetch logs from a recent range
+    Indexer,
+    /// The indexed merkle tree matches a quorum of validator checkpoints
+    TreeRoot,
+    /// Metadata can be built for the most recent undelivered message
+    Metadata,
+    /// The signer balance exceeds its threshold
+    Balance,
+}
+
+/// Successful outcome of a single check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckOutcome {
+    /// The check passed, with a short description of what was found
+    Passed(String),
+    /// The check doesn't apply to this chain, e.g. metadata on a
+    /// destination-only chain
+    NotApplicable(String),
+}
+
+/// A chain the self-test runs its checks against
+#[async_trait]
+pub trait SelfTestTarget: Send + Sync {
+    /// Name of the chain, as shown in the report
+    fn chain(&self) -> &str;
+
+    /// Run a single check. Errors are reported as failures.
+    async fn check(&self, check: SelfTestCheck) -> Result<CheckOutcome>;
+}
+
+/// Status of a check in the report
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::Display)]
+#[strum(serialize_all = "UPPERCASE")]
+pub enum CheckStatus {
+    /// The check passed
+    Pass,
+    /// The check failed
+    Fail,
+    /// The check was skipped by flag or doesn't apply to the chain
+    Skip,
+}
+
+/// Result of a single check against a single chain
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckResult {
+    /// Name of the chain
+    pub chain: String,
+    /// The check that was run
+    pub check: SelfTestCheck,
+    /// Whether it passed
+    pub status: CheckStatus,
+    /// What was found, or why it failed
+    pub detail: String,
+}
+
+/// Results of every check against every chain
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SelfTestReport {
+    /// One result per chain and check
+    pub results: Vec<CheckResult>,
+}
+
+impl SelfTestReport {
+    fn count(&self, status: CheckStatus) -> usize {
+        self.results.iter().filter(|r| r.status == status).count()
+    }
+
+    /// Whether no check failed
+    pub fn passed(&self) -> bool {
+        self.count(CheckStatus::Fail) == 0
+    }
+
+    /// Process exit code for the report
+    pub fn exit_code(&self) -> i32 {
+        if self.passed() {
+            0
+        } else {
+            1
+        }
+    }
+}
+
+impl Display for SelfTestReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        const HEADER: [&str; 4] = ["CHAIN", "CHECK", "STATUS", "DETAIL"];
+        let rows: Vec<[String; 4]> = self
+            .results
+            .iter()
+            .map(|r| {
+                [
+                    r.chain.clone(),
+                    r.check.to_string(),
+                    r.status.to_string(),
+                    r.detail.clone(),
+                ]
+            })
+            .collect();
+        let mut widths = HEADER.map(str::len);
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.len());
+            }
+        }
+        let mut write_row = |cells: [&str; 4]| {
+            writeln!(
+                f,
+                "{:<w0$}  {:<w1$}  {:<w2$}  {}",
+                cells[0],
+                cells[1],
+                cells[2],
+                cells[3],
+                w0 = widths[0],
+                w1 = widths[1],
+                w2 = widths[2],
+            )
+        };
+        write_row(HEADER)?;
+        for row in &rows {
+            write_row(row.each_ref().map(String::as_str))?;
+        }
+        writeln!(
+            f,
+            "{} passed, {} failed, {} skipped",
+            self.count(CheckStatus::Pass),
+            self.count(CheckStatus::Fail),
+            self.count(CheckStatus::Skip),
+        )
+    }
+}
+
+/// Run every check that isn't in `skip` against every target
+pub async fn run_self_test(
+    targets: &[Box<dyn SelfTestTarget>],
+    skip: &BTreeSet<SelfTestCheck>,
+) -> SelfTestReport {
+    let mut results = vec![];
+    for target in targets {
+        for check in SelfTestCheck::iter() {
+            let (status, detail) = if skip.contains(&check) {
+                (CheckStatus::Skip, "skipped by flag".to_owned())
+            } else {
+                match target.check(check).await {
+                    Ok(CheckOutcome::Passed(detail)) => (CheckStatus::Pass, detail),
+                    Ok(CheckOutcome::NotApplicable(detail)) => (CheckStatus::Skip, detail),
+                    Err(err) => (CheckStatus::Fail, format!("{err:#}")),
+                }
+            };
+            results.push(CheckResult {
+                chain: target.chain().to_owned(),
+                check,
+                status,
+                detail,
+            });
+        }
+    }
+    SelfTestReport { results }
+}
+
+/// The `self-test` subcommand of the relayer binary
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SelfTestCommand {
+    /// Checks skipped with `--skip-<check>`
+    pub skip: BTreeSet<SelfTestCheck>,
+    /// Minimum signer balance by chain name, set with
+    /// `--min-balance <chain>=<amount>`. Destinations without a threshold only
+    /// need a non-zero balance.
+    pub min_balances: HashMap<String, U256>,
+    /// Remaining arguments, passed on to the settings loader
+    pub settings_args: Vec<String>,
+}
+
+impl SelfTestCommand {
+    /// Parse the self-test subcommand from the process arguments, excluding
+    /// the binary name. Returns `None` if the arguments aren't a self-test, in
+    /// which case the relayer should start as usual.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Option<Result<Self>> {
+        let mut args = args.into_iter();
+        if args.next()? != "self-test" {
+            return None;
+        }
+        Some(Self::parse(args))
+    }
+
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self> {
+        let mut command = Self::default();
+        while let Some(arg) = args.next() {
+            if let Some(check) = arg.strip_prefix("--skip-") {
+                let check = SelfTestCheck::from_str(check).map_err(|_| {
+                    let checks = SelfTestCheck::iter().map(|c| c.to_string());
+                    eyre!(
+                        "Unknown check `{check}`, expected one of {}",
+                        checks.collect::<Vec<_>>().join(", ")
+                    )
+                })?;
+                command.skip.insert(check);
+            } else if arg == "--min-balance" {
+                let value = args
+                    .next()
+                    .ok_or_else(|| eyre!("--min-balance needs a value"))?;
+                let (chain, amount) = value
+                    .split_once('=')
+                    .ok_or_else(|| eyre!("Expected --min-balance <chain>=<amount>"))?;
+                let amount = U256::from_dec_str(amount)
+                    .map_err(|err| eyre!("Invalid --min-balance amount `{amount}`: {err}"))?;
+                command.min_balances.insert(chain.to_owned(), amount);
+            } else {
+                command.settings_args.push(arg);
+            }
+        }
+        Ok(command)
+    }
+
+    /// Load the relayer settings, run the self-test against the configured
+    /// chains and print the report. Returns the process exit code.
+    pub async fn run(self) -> Result<i32> {
+        let settings = RelayerSettings::load_from_args(self.settings_args)?;
+        let metrics = settings.metrics("relayer")?;
+        let targets = configured_targets(&settings, metrics, &self.min_balances).await?;
+        let report = run_self_test(&targets, &self.skip).await;
+        print!("{report}");
+        Ok(report.exit_code())
+    }
+}
+
+/// Build a target for every origin and destination chain in the settings
+async fn configured_targets(
+    settings: &RelayerSettings,
+    metrics: Arc<CoreMetrics>,
+    min_balances: &HashMap<String, U256>,
+) -> Result<Vec<Box<dyn SelfTestTarget>>> {
+    let db = DB::from_path(&settings.db)?;
+    let client_cache = Arc::new(ContractClientCache::new(metrics.clone()));
+
+    // Sorted by name so the report is stable
+    let chains: BTreeMap<_, _> = settings
+        .origin_chains
+        .iter()
+        .chain(&settings.destination_chains)
+        .map(|domain| (domain.name(), domain))
+        .collect();
+
+    let mut targets: Vec<Box<dyn SelfTestTarget>> = vec![];
+    for domain in chains.into_values() {
+        let origin = if settings.origin_chains.contains(domain) {
+            let db = HyperlaneRocksDB::new(domain, db.clone());
+            let prover_sync = Arc::new(RwLock::new(load_tree(&db).await?));
+            let mut destinations = HashMap::new();
+            let validator_reputations = ValidatorReputations::new(db.clone(), metrics.clone());
+            let validator_announce = client_cache
+                .validator_announce(settings.chain_setup(domain)?)
+                .await?;
+            for destination in &settings.destination_chains {
+                let destination_conf = settings.chain_setup(destination)?;
+                let mailbox = client_cache.mailbox(destination_conf).await?;
+                let metadata_builder = BaseMetadataBuilder::new(
+                    domain.clone(),
+                    destination_conf.clone(),
+                    prover_sync.clone(),
+                    validator_announce.clone(),
+                    settings.allow_local_checkpoint_syncers,
+                    metrics.clone(),
+                    db.clone(),
+                    IsmAwareAppContextClassifier::new(
+                        mailbox.clone(),
+                        settings.metric_app_contexts.clone(),
+                    ),
+                    client_cache.clone(),
+                    validator_reputations.clone(),
+                    settings.checkpoint_preferences.clone(),
+                    settings.ccip_read.clone(),
+                );
+                destinations.insert(
+                    destination.id(),
+                    DestinationSetup {
+                        mailbox,
+                        metadata_builder: Arc::new(metadata_builder),
+                    },
+                );
+            }
+            Some(OriginSetup {
+                db,
+                prover_sync,
+                destinations,
+            })
+        } else {
+            None
+        };
+        let min_balance = settings
+            .destination_chains
+            .contains(domain)
+            .then(|| min_balances.get(domain.name()).copied().unwrap_or_default());
+        targets.push(Box::new(ConfiguredChain {
+            conf: settings.chain_setup(domain)?.clone(),
+            metrics: metrics.clone(),
+            min_balance,
+            origin,
+        }));
+    }
+    Ok(targets)
+}
+
+/// Build the merkle tree from the leaves already in the database
+async fn load_tree(db: &HyperlaneRocksDB) -> Result<MerkleTreeBuilder> {
+    let mut tree = MerkleTreeBuilder::new();
+    while let Some(insertion) = db.retrieve_merkle_tree_insertion_by_leaf_index(&tree.count())? {
+        tree.ingest_insertion(&insertion).await?;
+    }
+    tree.set_availability(TreeAvailability::Ready, "Loaded indexed leaves");
+    Ok(tree)
+}
+
+struct DestinationSetup {
+    mailbox: Arc<dyn Mailbox>,
+    metadata_builder: Arc<BaseMetadataBuilder>,
+}
+
+struct OriginSetup {
+    db: HyperlaneRocksDB,
+    prover_sync: Arc<RwLock<MerkleTreeBuilder>>,
+    /// By destination domain id
+    destinations: HashMap<u32, DestinationSetup>,
+}
+
+impl OriginSetup {
+    /// The most recent indexed message to a configured destination, if
+    /// `undelivered` only considering messages that haven't been delivered
+    async fn latest_message(
+        &self,
+        undelivered: bool,
+    ) -> Result<Option<(HyperlaneMessage, &DestinationSetup)>> {
+        let Some(highest) = self.db.retrieve_highest_seen_message_nonce()? else {
+            return Ok(None);
+        };
+        for nonce in (highest.saturating_sub(MAX_SCANNED_MESSAGES)..=highest).rev() {
+            let Some(message) = self.db.retrieve_message_by_nonce(nonce)? else {
+                continue;
+            };
+            let Some(destination) = self.destinations.get(&message.destination) else {
+                continue;
+            };
+            if undelivered
+                && (self.db.retrieve_processed_by_nonce(&nonce)? == Some(true)
+                    || destination.mailbox.delivered(message.id()).await?)
+            {
+                continue;
+            }
+            return Ok(Some((message, destination)));
+        }
+        Ok(None)
+    }
+}
+
+/// A chain from the relayer settings
+struct ConfiguredChain {
+    conf: ChainConf,
+    metrics: Arc<CoreMetrics>,
+    /// Set if the chain is a destination
+    min_balance: Option<U256>,
+    /// Set if the chain is an origin
+    origin: Option<OriginSetup>,
+}
+
+#[async_trait]
+impl SelfTestTarget for ConfiguredChain {
+    fn chain(&self) -> &str {
+        self.conf.domain.name()
+    }
+
+    async fn check(&self, check: SelfTestCheck) -> Result<CheckOutcome> {
+        match check {
+            SelfTestCheck::Rpc => self.check_rpc().await,
+            SelfTestCheck::Contracts => self.check_contracts().await,
+            SelfTestCheck::Indexer => self.check_indexer().await,
+            SelfTestCheck::TreeRoot => self.check_tree_root().await,
+            SelfTestCheck::Metadata => self.check_metadata().await,
+            SelfTestCheck::Balance => self.check_balance().await,
+        }
+    }
+}
+
+impl ConfiguredChain {
+    async fn check_rpc(&self) -> Result<CheckOutcome> {
+        let provider = self.conf.build_provider(&self.metrics).await?;
+        let latest_block = provider
+            .get_chain_metrics()
+            .await?
+            .ok_or_else(|| eyre!("RPC did not return the latest block"))?
+            .latest_block
+            .number;
+        // Chains we don't know the canonical chain id of conventionally use
+        // their domain id
+        let expected = self
+            .conf
+            .domain
+            .evm_chain_id()
+            .unwrap_or(self.conf.domain.id() as u64);
+        match provider.get_chain_id().await? {
+            Some(chain_id) if chain_id != expected => {
+                bail!(
+                    "RPC reports chain id {chain_id}, but the chain id of {} is {expected}",
+                    self.conf.domain
+                )
+            }
+            Some(chain_id) => Ok(CheckOutcome::Passed(format!(
+                "chain id {chain_id}, latest block {latest_block}"
+            ))),
+            None => Ok(CheckOutcome::Passed(format!("latest block {latest_block}"))),
+        }
+    }
+
+    async fn check_contracts(&self) -> Result<CheckOutcome> {
+        let provider = self.conf.build_provider(&self.metrics).await?;
+        let addresses = &self.conf.addresses;
+        let mut found = 0;
+        let mut missing = vec![];
+        for (name, address) in [
+            ("mailbox", addresses.mailbox),
+            (
+                "interchain_gas_paymaster",
+                addresses.interchain_gas_paymaster,
+            ),
+            ("validator_announce", addresses.validator_announce),
+            ("merkle_tree_hook", addresses.merkle_tree_hook),
+        ] {
+            if address.is_zero() {
+                continue;
+            }
+            if provider.is_contract(&address).await? {
+                found += 1;
+            } else {
+                missing.push(name);
+            }
+        }
+        ensure!(
+            missing.is_empty(),
+            "No contract code for {}",
+            missing.join(", ")
+        );
+        Ok(CheckOutcome::Passed(format!("{found} contracts found")))
+    }
+
+    async fn check_indexer(&self) -> Result<CheckOutcome> {
+        let index = &self.conf.index;
+        if self.origin.is_some() {
+            probe_indexer(self.conf.build_message_indexer(&self.metrics).await?, index).await
+        } else {
+            probe_indexer(
+                self.conf.build_delivery_indexer(&self.metrics).await?,
+                index,
+            )
+            .await
+        }
+    }
+
+    async fn check_tree_root(&self) -> Result<CheckOutcome> {
+        let Some(origin) = &self.origin else {
+            return Ok(CheckOutcome::NotApplicable("not an origin".to_owned()));
+        };
+        // Validators are looked up through the ISM of the latest message
+        let Some((message, destination)) = origin.latest_message(false).await? else {
+            return Ok(CheckOutcome::NotApplicable(
+                "no messages indexed yet".to_owned(),
+            ));
+        };
+        let base = &destination.metadata_builder;
+        let ism = destination.mailbox.recipient_ism(message.recipient).await?;
+        let Some((validators, threshold)) = multisig_validators(base, ism, &message).await? else {
+            return Ok(CheckOutcome::NotApplicable(
+                "the latest message isn't secured by a multisig ISM".to_owned(),
+            ));
+        };
+
+        let onchain = self
+            .conf
+            .build_merkle_tree_hook(&self.metrics)
+            .await?
+            .latest_checkpoint(&self.conf.reorg_period)
+            .await?;
+        let quorum = base
+            .build_checkpoint_syncer(&validators, None)
+            .await?
+            .fetch_checkpoint_in_range(
+                &validators,
+                threshold as usize,
+                0,
+                onchain.index,
+                CheckpointPreference::Latest,
+                &self.conf.domain,
+                base.destination_domain(),
+            )
+            .await?
+            .ok_or_else(|| {
+                eyre!(
+                    "No quorum of validator checkpoints up to index {}",
+                    onchain.index
+                )
+            })?;
+        let index = quorum.checkpoint.index;
+        let tree = origin.prover_sync.read().await;
+        ensure!(
+            index < tree.count(),
+            "Validators signed index {index}, but only {} leaves are indexed",
+            tree.count()
+        );
+        let indexed_root = tree.get_proof(index, index)?.root();
+        ensure!(
+            indexed_root == quorum.checkpoint.root,
+            "Validators signed root {:?} at index {index}, but the indexed tree has {indexed_root:?}",
+            quorum.checkpoint.root
+        );
+        ensure!(
+            index != onchain.index || onchain.root == quorum.checkpoint.root,
+            "Validators signed root {:?} at index {index}, but the chain has {:?}",
+            quorum.checkpoint.root,
+            onchain.root
+        );
+        Ok(CheckOutcome::Passed(format!(
+            "root at index {index} matches {} validator signatures",
+            quorum.signatures.len()
+        )))
+    }
+
+    async fn check_metadata(&self) -> Result<CheckOutcome> {
+        let Some(origin) = &self.origin else {
+            return Ok(CheckOutcome::NotApplicable("not an origin".to_owned()));
+        };
+        let Some((message, destination)) = origin.latest_message(true).await? else {
+            return Ok(CheckOutcome::NotApplicable(
+                "no undelivered messages".to_owned(),
+            ));
+        };
+        let ism = destination.mailbox.recipient_ism(message.recipient).await?;
+        let metadata =
+            MessageMetadataBuilder::new(ism, &message, destination.metadata_builder.clone())
+                .await?
+                .build(ism, &message)
+                .await?
+                .ok_or_else(|| eyre!("Could not build metadata for nonce {} yet", message.nonce))?;
+        Ok(CheckOutcome::Passed(format!(
+            "{} bytes of metadata for nonce {}",
+            metadata.len(),
+            message.nonce
+        )))
+    }
+
+    async fn check_balance(&self) -> Result<CheckOutcome> {
+        let Some(min_balance) = self.min_balance else {
+            return Ok(CheckOutcome::NotApplicable("not a destination".to_owned()));
+        };
+        let signer = self
+            .conf
+            .chain_signer()
+            .await?
+            .ok_or_else(|| eyre!("No signer configured"))?;
+        let address = signer.address_string();
+        let balance = Balance::from(
+            self.conf
+                .build_provider(&self.metrics)
+                .await?
+                .get_balance(address.clone())
+                .await?,
+        );
+        let min_balance = Balance::from(min_balance);
+        ensure!(
+            balance > min_balance,
+            "Balance of {address} is {}, which doesn't exceed {}",
+            balance.0,
+            min_balance.0
+        );
+        Ok(CheckOutcome::Passed(format!(
+            "{address} holds {}",
+            balance.0
+        )))
+    }
+}
+
+/// Fetch a recent range of logs, sized by the chain's index settings
+async fn probe_indexer<T>(
+    indexer: Box<dyn SequenceAwareIndexer<T>>,
+    index: &IndexSettings,
+) -> Result<CheckOutcome> {
+    let (count, tip) = indexer.latest_sequence_count_and_tip().await?;
+    let range: RangeInclusive<u32> = match index.mode {
+        IndexMode::Block => tip.saturating_sub(index.chunk_size.max(1) - 1)..=tip,
+        IndexMode::Sequence => match count {
+            Some(count) if count > 0 => count - 1..=count - 1,
+            _ => {
+                return Ok(CheckOutcome::Passed(format!(
+                    "tip {tip}, nothing to index yet"
+                )))
+            }
+        },
+    };
+    let logs = indexer.fetch_logs_in_range(range.clone()).await?;
+    Ok(CheckOutcome::Passed(format!(
+        "{} logs in {range:?} ({:?} mode)",
+        logs.len(),
+        index.mode
+    )))
+}
+
+/// Find the validators and threshold of the first multisig ISM securing
+/// `message`, following routing and aggregation ISMs
+async fn multisig_validators(
+    base: &BaseMetadataBuilder,
+    ism: H256,
+    message: &HyperlaneMessage,
+) -> Result<Option<(Vec<H256>, u8)>> {
+    let mut pending = vec![ism];
+    let mut inspected = 0;
+    while let Some(address) = pending.pop() {
+        inspected += 1;
+        if inspected > MAX_INSPECTED_ISMS {
+            break;
+        }
+        match base.build_ism(address).await?.module_type().await? {
+            ModuleType::MerkleRootMultisig | ModuleType::MessageIdMultisig => {
+                let multisig = base.build_multisig_ism(address).await?;
+                return Ok(Some(multisig.validators_and_threshold(message).await?));
+            }
+            ModuleType::Routing => {
+                let routing = base.build_routing_ism(address).await?;
+                pending.push(routing.route(message).await?);
+            }
+            ModuleType::Aggregation => {
+                let aggregation = base.build_aggregation_ism(address).await?;
+                let (modules, _) = aggregation.modules_and_threshold(message).await?;
+                // Inspect the modules in order
+                pending.extend(modules.into_iter().rev());
+            }
+            _ => {}
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// A chain on which the checks in `failing` fail
+    struct MockTarget {
+        chain: String,
+        failing: BTreeSet<SelfTestCheck>,
+    }
+
+    impl MockTarget {
+        fn boxed(chain: &str, failing: &[SelfTestCheck]) -> Box<dyn SelfTestTarget> {
+            Box::new(Self {
+                chain: chain.to_owned(),
+                failing: failing.iter().copied().collect(),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl SelfTestTarget for MockTarget {
+        fn chain(&self) -> &str {
+            &self.chain
+        }
+
+        async fn check(&self, check: SelfTestCheck) -> Result<CheckOutcome> {
+            if self.failing.contains(&check) {
+                bail!("injected {check} failure")
+            }
+            Ok(CheckOutcome::Passed("ok".to_owned()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_all_checks_pass() {
+        let targets = vec![
+            MockTarget::boxed("test1", &[]),
+            MockTarget::boxed("test2", &[]),
+        ];
+        let report = run_self_test(&targets, &BTreeSet::new()).await;
+        assert_eq!(report.results.len(), 2 * SelfTestCheck::iter().count());
+        assert!(report
+            .results
+            .iter()
+            .all(|result| result.status == CheckStatus::Pass));
+        assert_eq!(report.exit_code(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_each_injected_failure_fails_the_run() {
+        for failing in SelfTestCheck::iter() {
+            let targets = vec![
+                MockTarget::boxed("test1", &[]),
+                MockTarget::boxed("test2", &[failing]),
+            ];
+            let report = run_self_test(&targets, &BTreeSet::new()).await;
+            let failures: Vec<_> = report
+                .results
+                .iter()
+                .filter(|result| result.status == CheckStatus::Fail)
+                .collect();
+            assert_eq!(failures.len(), 1, "{failing}");
+            assert_eq!(failures[0].chain, "test2");
+            assert_eq!(failures[0].check, failing);
+            assert_eq!(failures[0].detail, format!("injected {failing} failure"));
+            assert_eq!(report.exit_code(), 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_skipped_checks_are_not_run() {
+        for failing in SelfTestCheck::iter() {
+            let targets = vec![MockTarget::boxed("test1", &[failing])];
+            let report = run_self_test(&targets, &BTreeSet::from([failing])).await;
+            let skipped = report
+                .results
+                .iter()
+                .find(|result| result.check == failing)
+                .unwrap();
+            assert_eq!(skipped.status, CheckStatus::Skip);
+            assert_eq!(report.exit_code(), 0);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_report_table() {
+        let targets = vec![MockTarget::boxed("test1", &[SelfTestCheck::Contracts])];
+        let report = run_self_test(&targets, &BTreeSet::from([SelfTestCheck::Balance])).await;
+        let expected = "\
+CHAIN  CHECK      STATUS  DETAIL
+test1  rpc        PASS    ok
+test1  contracts  FAIL    injected contracts failure
+test1  indexer    PASS    ok
+test1  tree-root  PASS    ok
+test1  metadata   PASS    ok
+test1  balance    SKIP    skipped by flag
+4 passed, 1 failed, 1 skipped
+";
+        assert_eq!(report.to_string(), expected);
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+
+        assert!(SelfTestCommand::from_args(args(&["export-archive"])).is_none());
+        assert!(SelfTestCommand::from_args(args(&[])).is_none());
+
+        let command = SelfTestCommand::from_args(args(&[
+            "self-test",
+            "--skip-tree-root",
+            "--originChainName",
+            "test1",
+            "--skip-balance",
+            "--min-balance",
+            "test2=1000",
+        ]))
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            command.skip,
+            BTreeSet::from([SelfTestCheck::TreeRoot, SelfTestCheck::Balance])
+        );
+        assert_eq!(command.min_balances["test2"], U256::from(1000));
+        assert_eq!(command.settings_args, args(&["--originChainName", "test1"]));
+
+        assert!(
+            SelfTestCommand::from_args(args(&["self-test", "--skip-everything"]))
+                .unwrap()
+                .is_err()
+        );
+        assert!(
+            SelfTestCommand::from_args(args(&["self-test", "--min-balance", "test2"]))
+                .unwrap()
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,159 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+    time::Instant,
+};
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    routing, Json, Router,
+};
+use derive_new::new;
+use serde::Deserialize;
+
+use crate::msg::circuit_breaker::{CircuitBreaker, CircuitBreakerStatus, CircuitOverride};
+
+const CIRCUIT_BREAKERS_API_BASE: &str = "/circuit_breakers";
+
+type Breakers = HashMap<String, Arc<CircuitBreaker>>;
+
+/// Body of a request to override the circuit breaker of a destination
+#[derive(Clone, Debug, Deserialize)]
+pub struct CircuitOverrideRequest {
+    /// The state to pin the breaker to, or none to let it act on revert
+    /// rates again
+    #[serde(rename = "override")]
+    manual_override: Option<CircuitOverride>,
+}
+
+/// Reports and overrides the circuit breaker of each destination
+#[derive(new, Clone)]
+pub struct CircuitBreakersApi {
+    breakers: Breakers,
+}
+
+async fn circuit_breakers(
+    State(breakers): State<Breakers>,
+) -> Json<BTreeMap<String, CircuitBreakerStatus>> {
+    let now = Instant::now();
+    Json(
+        breakers
+            .iter()
+            .map(|(destination, breaker)| (destination.clone(), breaker.status(now)))
+            .collect(),
+    )
+}
+
+async fn override_circuit_breaker(
+    State(breakers): State<Breakers>,
+    Path(destination): Path<String>,
+    Json(request): Json<CircuitOverrideRequest>,
+) -> Result<Json<CircuitBreakerStatus>, (StatusCode, String)> {
+    let breaker = breakers.get(&destination).ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            format!("No circuit breaker for destination {destination}"),
+        )
+    })?;
+    let now = Instant::now();
+    breaker.set_override(request.manual_override, now);
+    Ok(Json(breaker.status(now)))
+}
+
+impl CircuitBreakersApi {
+    pub fn router(&self) -> Router {
+        Router::new()
+            .route("/", routing::get(circuit_breakers))
+            .route("/:destination", routing::post(override_circuit_breaker))
+            .with_state(self.breakers.clone())
+    }
+
+    pub fn get_route(&self) -> (&'static str, Router) {
+        (CIRCUIT_BREAKERS_API_BASE, self.router())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use hyperlane_base::{
+        db::{test_utils, HyperlaneRocksDB},
+        AlertDispatcher,
+    };
+    use hyperlane_core::HyperlaneDomain;
+    use prometheus::{opts, IntGaugeVec};
+    use serde_json::json;
+
+    use super::*;
+    use crate::{msg::circuit_breaker::CircuitAdmission, settings::CircuitBreakerConf};
+
+    #[tokio::test]
+    async fn test_circuit_breaker_override() {
+        test_utils::run_test_db(|db| async move {
+            let destination = HyperlaneDomain::new_test_domain("test2");
+            let gauge = IntGaugeVec::new(opts!("circuit_breaker_state", "help"), &["destination"])
+                .unwrap()
+                .with_label_values(&["test2"]);
+            let breaker = Arc::new(CircuitBreaker::new(
+                CircuitBreakerConf::default(),
+                destination.clone(),
+                HyperlaneRocksDB::new(&destination, db),
+                AlertDispatcher::default(),
+                gauge.clone(),
+                Instant::now(),
+            ));
+            let app =
+                CircuitBreakersApi::new(HashMap::from([("test2".to_owned(), breaker.clone())]))
+                    .router();
+            let server =
+                axum::Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(app.into_make_service());
+            let addr: SocketAddr = server.local_addr();
+            tokio::spawn(server);
+            let client = reqwest::Client::new();
+
+            let response = client
+                .post(format!("http://{addr}/test2"))
+                .json(&json!({ "override": "open" }))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = response.json::<serde_json::Value>().await.unwrap();
+            assert_eq!(body["state"], "open");
+            assert_eq!(body["manual_override"], "open");
+            assert_eq!(gauge.get(), 2);
+            assert_eq!(
+                breaker.admit(Default::default(), Instant::now()),
+                CircuitAdmission::Open
+            );
+
+            let response = client
+                .post(format!("http://{addr}/test2"))
+                .json(&json!({ "override": null }))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+
+            let body = reqwest::get(format!("http://{addr}/"))
+                .await
+                .unwrap()
+                .json::<serde_json::Value>()
+                .await
+                .unwrap();
+            assert_eq!(body["test2"]["state"], "open");
+            assert_eq!(body["test2"]["manual_override"], json!(null));
+
+            let response = client
+                .post(format!("http://{addr}/test3"))
+                .json(&json!({ "override": "closed" }))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        })
+        .await;
+    }
+}
@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    routing, Json, Router,
+};
+use derive_new::new;
+use hyperlane_base::db::HyperlaneRocksDB;
+use hyperlane_core::{
+    hex::{Hex20Or32, Hex32},
+    GasPaymentKey, H256, U256,
+};
+use serde::{Deserialize, Serialize};
+
+const GAS_PAYMENTS_API_BASE: &str = "/gas_payments";
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct GasPaymentsRequest {
+    origin_domain: u32,
+    #[serde(deserialize_with = "Hex32::deserialize")]
+    message_id: H256,
+    destination_domain: u32,
+}
+
+/// Gas paid for a message, in total and through each of the origin's IGPs
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct GasPaymentsResponse {
+    payment: U256,
+    gas_amount: U256,
+    by_contract: Vec<ContractGasPayment>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ContractGasPayment {
+    #[serde(serialize_with = "Hex20Or32::serialize")]
+    contract: H256,
+    payment: U256,
+    gas_amount: U256,
+}
+
+/// Reports the gas paid for a message across every IGP contract indexed on
+/// its origin
+#[derive(new, Clone)]
+pub struct GasPaymentsApi {
+    dbs: HashMap<u32, HyperlaneRocksDB>,
+    igp_contracts: HashMap<u32, Vec<H256>>,
+}
+
+async fn gas_payments(
+    State(api): State<GasPaymentsApi>,
+    Query(request): Query<GasPaymentsRequest>,
+) -> Result<Json<GasPaymentsResponse>, (StatusCode, String)> {
+    let db = api.dbs.get(&request.origin_domain).ok_or((
+        StatusCode::NOT_FOUND,
+        format!("Unknown origin domain {}", request.origin_domain),
+    ))?;
+    let key = GasPaymentKey {
+        message_id: request.message_id,
+        destination: request.destination_domain,
+    };
+    let internal_error =
+        |err: hyperlane_base::db::DbError| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string());
+
+    let mut response = GasPaymentsResponse::default();
+    if let Some(total) = db
+        .retrieve_gas_payment_by_gas_payment_key(key)
+        .map_err(internal_error)?
+    {
+        response.payment = total.payment;
+        response.gas_amount = total.gas_amount;
+    }
+    let contracts = api
+        .igp_contracts
+        .get(&request.origin_domain)
+        .cloned()
+        .unwrap_or_default();
+    for contract in contracts {
+        if let Some(payment) = db
+            .retrieve_gas_payment_by_contract(key, &contract)
+            .map_err(internal_error)?
+        {
+            response.by_contract.push(ContractGasPayment {
+                contract,
+                payment: payment.payment,
+                gas_amount: payment.gas_amount,
+            });
+        }
+    }
+    Ok(Json(response))
+}
+
+impl GasPaymentsApi {
+    pub fn router(&self) -> Router {
+        Router::new()
+            .route("/", routing::get(gas_payments))
+            .with_state(self.clone())
+    }
+
+    pub fn get_route(&self) -> (&'static str, Router) {
+        (GAS_PAYMENTS_API_BASE, self.router())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use hyperlane_base::db::{test_utils, IgpContractStore};
+    use hyperlane_core::{
+        HyperlaneDomain, HyperlaneLogStore, Indexed, InterchainGasPayment, KnownHyperlaneDomain,
+        LogMeta,
+    };
+
+    use super::*;
+
+    const ORIGIN: KnownHyperlaneDomain = KnownHyperlaneDomain::Test1;
+
+    #[tokio::test]
+    async fn test_gas_payments_by_contract() {
+        test_utils::run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(&HyperlaneDomain::Known(ORIGIN), db);
+            let (legacy_igp, current_igp) = (H256::from_low_u64_be(1), H256::from_low_u64_be(2));
+            let message_id = H256::from_low_u64_be(42);
+            let payment = |amount: u64| InterchainGasPayment {
+                message_id,
+                destination: 2,
+                payment: U256::from(amount),
+                gas_amount: U256::from(amount * 10),
+            };
+            IgpContractStore::configured(db.clone(), current_igp)
+                .store_logs(&[(Indexed::new(payment(2)), LogMeta::random())])
+                .await
+                .unwrap();
+            IgpContractStore::additional(db.clone(), legacy_igp)
+                .store_logs(&[(Indexed::new(payment(1)), LogMeta::random())])
+                .await
+                .unwrap();
+
+            let app = GasPaymentsApi::new(
+                HashMap::from([(ORIGIN as u32, db)]),
+                HashMap::from([(ORIGIN as u32, vec![current_igp, legacy_igp])]),
+            )
+            .router();
+            let server =
+                axum::Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(app.into_make_service());
+            let addr: SocketAddr = server.local_addr();
+            tokio::spawn(server);
+
+            let response = reqwest::get(format!(
+                "http://{addr}/?origin_domain={}&message_id={message_id:?}&destination_domain=2",
+                ORIGIN as u32
+            ))
+            .await
+            .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(
+                response.json::<serde_json::Value>().await.unwrap(),
+                serde_json::to_value(GasPaymentsResponse {
+                    payment: U256::from(3),
+                    gas_amount: U256::from(30),
+                    by_contract: vec![
+                        ContractGasPayment {
+                            contract: current_igp,
+                            payment: U256::from(2),
+                            gas_amount: U256::from(20),
+                        },
+                        ContractGasPayment {
+                            contract: legacy_igp,
+                            payment: U256::from(1),
+                            gas_amount: U256::from(10),
+                        },
+                    ],
+                })
+                .unwrap()
+            );
+
+            let response = reqwest::get(format!(
+                "http://{addr}/?origin_domain=999&message_id={message_id:?}&destination_domain=2"
+            ))
+            .await
+            .unwrap();
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        })
+        .await;
+    }
+}
@@ -0,0 +1,92 @@
+use std::{sync::Arc, time::Instant};
+
+use axum::{extract::State, routing, Json, Router};
+use derive_new::new;
+
+use crate::msg::latency_budget::{LatencyBudgetTracker, LatencyReport};
+
+const LATENCY_BUDGETS_API_BASE: &str = "/latency_budgets";
+
+/// Reports the 95th percentile latency of each pipeline stage of each route
+/// over the rolling window, against its budget
+#[derive(new, Clone)]
+pub struct LatencyBudgetsApi {
+    tracker: Arc<LatencyBudgetTracker>,
+}
+
+async fn latency_report(State(tracker): State<Arc<LatencyBudgetTracker>>) -> Json<LatencyReport> {
+    Json(tracker.report(Instant::now()))
+}
+
+impl LatencyBudgetsApi {
+    pub fn router(&self) -> Router {
+        Router::new()
+            .route("/", routing::get(latency_report))
+            .with_state(self.tracker.clone())
+    }
+
+    pub fn get_route(&self) -> (&'static str, Router) {
+        (LATENCY_BUDGETS_API_BASE, self.router())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::SocketAddr, time::Duration};
+
+    use hyperlane_core::HyperlaneMessage;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        msg::latency_budget::{test::latency_budget_tracker, PipelineStage, StageTimeline},
+        settings::{LatencyBudgetConf, StageBudgets},
+    };
+
+    #[tokio::test]
+    async fn test_latency_report() {
+        let tracker = Arc::new(latency_budget_tracker(LatencyBudgetConf {
+            default: StageBudgets {
+                metadata: Some(Duration::from_secs(10)),
+                ..Default::default()
+            },
+            ..Default::default()
+        }));
+        tracker.time(
+            &mut StageTimeline::default(),
+            &HyperlaneMessage::default(),
+            PipelineStage::Metadata,
+            Duration::from_secs(12),
+            Instant::now(),
+        );
+
+        let app = LatencyBudgetsApi::new(tracker).router();
+        let server =
+            axum::Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(app.into_make_service());
+        let addr: SocketAddr = server.local_addr();
+        tokio::spawn(server);
+
+        let body = reqwest::get(format!("http://{addr}/"))
+            .await
+            .unwrap()
+            .json::<serde_json::Value>()
+            .await
+            .unwrap();
+        assert_eq!(
+            body,
+            json!({
+                "window_secs": 3600,
+                "routes": {
+                    "default": {
+                        "metadata": {
+                            "samples": 1,
+                            "p95_ms": 12000,
+                            "budget_ms": 10000,
+                            "breaches": 1,
+                        }
+                    }
+                }
+            })
+        );
+    }
+}
@@ -1,13 +1,14 @@
 use axum::{
     extract::{Query, State},
-    routing, Router,
+    http::StatusCode,
+    routing, Json, Router,
 };
 use derive_new::new;
-use hyperlane_core::{QueueOperation, H256};
-use serde::{Deserialize, Serialize};
-use serde_json::Value;
-use std::collections::HashMap;
+use hyperlane_core::QueueOperation;
+use serde::Deserialize;
+use std::{cmp::Reverse, collections::HashMap};
 
+use super::v1::{MessageList, MessageStatus, Versioned};
 use crate::msg::op_queue::OperationPriorityQueue;
 
 const LIST_OPERATIONS_API_BASE: &str = "/list_operations";
@@ -31,40 +32,21 @@ pub struct ListOperationsApi {
 async fn list_operations(
     State(queues): State<HashMap<u32, OperationPriorityQueue>>,
     Query(request): Query<ListOperationsRequest>,
-) -> String {
+) -> Result<Json<Versioned<MessageList>>, (StatusCode, String)> {
     let domain = request.destination_domain;
     let Some(op_queue) = queues.get(&domain) else {
-        return format!("No queue found for domain {}", domain);
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("No queue found for domain {}", domain),
+        ));
     };
-    format_queue(op_queue.clone()).await
-}
-
-#[derive(Debug, Serialize)]
-struct OperationWithId<'a> {
-    id: H256,
-    operation: &'a QueueOperation,
-}
-
-impl<'a> OperationWithId<'a> {
-    fn new(operation: &'a QueueOperation) -> Self {
-        Self {
-            id: operation.id(),
-            operation,
-        }
-    }
-}
-
-pub async fn format_queue(queue: OperationPriorityQueue) -> String {
-    let res: Result<Vec<Value>, _> = queue
+    let operations = op_queue
         .lock()
         .await
         .iter()
-        .map(|reverse| serde_json::to_value(OperationWithId::new(&reverse.0)))
+        .map(|Reverse(op)| MessageStatus::from(op))
         .collect();
-    match res.and_then(|v| serde_json::to_string_pretty(&v)) {
-        Ok(s) => s,
-        Err(e) => format!("Error formatting queue: {}", e),
-    }
+    Ok(Json(Versioned::new(MessageList { operations })))
 }
 
 impl ListOperationsApi {
@@ -140,40 +122,29 @@ mod tests {
                 .with_recipient_address(recipient_address),
         ) as QueueOperation;
 
-        // The reason there already is an id inside `operation` here is because it's a field on `MockPendingOperation` - that field is
-        // missing on `PendingMessage` because it's derived, hence the need to hence the need to have it explicitly serialized alongside the operation.
-        let expected_response = r#"[
-  {
-    "id": "0x1acbee9798118b11ebef0d94b0a2936eafd58e3bfab91b05da875825c4a1c39b",
-    "operation": {
-      "destination_domain": {
-        "Known": "Arbitrum"
-      },
-      "destination_domain_id": 42161,
+        let expected_response = r#"{
+  "schema_version": 1,
+  "operations": [
+    {
       "id": "0x1acbee9798118b11ebef0d94b0a2936eafd58e3bfab91b05da875825c4a1c39b",
-      "origin_domain_id": 0,
-      "recipient_address": "0x586d41b02fb35df0f84ecb2b73e076b40c929ee3e1ceeada9a078aa7b46d3b08",
-      "seconds_to_next_attempt": 1,
+      "origin_domain": 0,
+      "destination_domain": 42161,
+      "nonce": 0,
       "sender_address": "0x586d41b02fb35df0f84ecb2b73e076b40c929ee3e1ceeada9a078aa7b46d3b08",
-      "type": "MockPendingOperation"
-    }
-  },
-  {
-    "id": "0x51e7be221ce90a49dee46ca0d0270c48d338a7b9d85c2a89d83fac0816571914",
-    "operation": {
-      "destination_domain": {
-        "Known": "Arbitrum"
-      },
-      "destination_domain_id": 42161,
-      "id": "0x51e7be221ce90a49dee46ca0d0270c48d338a7b9d85c2a89d83fac0816571914",
-      "origin_domain_id": 0,
       "recipient_address": "0x586d41b02fb35df0f84ecb2b73e076b40c929ee3e1ceeada9a078aa7b46d3b08",
-      "seconds_to_next_attempt": 2,
+      "status": "FirstPrepareAttempt"
+    },
+    {
+      "id": "0x51e7be221ce90a49dee46ca0d0270c48d338a7b9d85c2a89d83fac0816571914",
+      "origin_domain": 0,
+      "destination_domain": 42161,
+      "nonce": 0,
       "sender_address": "0x586d41b02fb35df0f84ecb2b73e076b40c929ee3e1ceeada9a078aa7b46d3b08",
-      "type": "MockPendingOperation"
+      "recipient_address": "0x586d41b02fb35df0f84ecb2b73e076b40c929ee3e1ceeada9a078aa7b46d3b08",
+      "status": "FirstPrepareAttempt"
     }
-  }
-]"#;
+  ]
+}"#;
         op_queue.lock().await.push(Reverse(dummy_operation_1));
         op_queue.lock().await.push(Reverse(dummy_operation_2));
 
@@ -188,7 +159,10 @@ mod tests {
         // Check that the response status code is OK
         assert_eq!(response.status(), StatusCode::OK);
 
-        let response_text = response.text().await.unwrap();
-        assert_eq!(response_text, expected_response);
+        let response_json: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(
+            response_json,
+            serde_json::from_str::<serde_json::Value>(expected_response).unwrap()
+        );
     }
 }
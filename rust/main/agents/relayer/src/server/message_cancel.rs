@@ -0,0 +1,445 @@
+use std::{cmp::Reverse, collections::HashMap};
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{header::AUTHORIZATION, HeaderMap, StatusCode},
+    routing, Json, Router,
+};
+use derive_new::new;
+use hyperlane_base::db::{HyperlaneDb, HyperlaneRocksDB};
+use hyperlane_core::{HyperlaneMessage, MessageKey, SignedType, H160, H256};
+use serde::Deserialize;
+use tracing::info;
+
+use crate::msg::{
+    cancellation::{
+        cancel_message, verify_sender_request, CancellationError, CancellationRequest, CancelledBy,
+        MessageCancellation,
+    },
+    op_queue::OperationPriorityQueue,
+};
+
+const MESSAGE_API_BASE: &str = "/message";
+
+/// Query of a request to cancel a pending message
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct CancelMessageQuery {
+    /// Origin domain id of the message. Only needed if messages with the id
+    /// were dispatched on several origins.
+    origin: Option<u32>,
+}
+
+/// Body of a request to cancel a pending message
+#[derive(Clone, Debug, Deserialize)]
+pub struct CancelMessageRequest {
+    /// Why the message is cancelled
+    reason: String,
+    /// Hex EIP-191 signature of the message id by the message's sender.
+    /// Operators authenticate with the admin token instead.
+    signature: Option<String>,
+}
+
+/// Cancels pending messages, on behalf of an operator authenticated with the
+/// admin token or of a sender allowed to cancel its messages
+#[derive(new, Clone)]
+pub struct MessageCancelApi {
+    /// Prepare queues by destination domain id
+    op_queues: HashMap<u32, OperationPriorityQueue>,
+    /// Origin databases by origin domain id
+    dbs: HashMap<u32, HyperlaneRocksDB>,
+    admin_token: Option<String>,
+    cancellation_senders: Vec<H160>,
+    config_hash: String,
+}
+
+fn status_code(err: &CancellationError) -> StatusCode {
+    match err {
+        CancellationError::NotFound(_) => StatusCode::NOT_FOUND,
+        CancellationError::AmbiguousId(..)
+        | CancellationError::AlreadyCancelled(_)
+        | CancellationError::AlreadyProcessed(_)
+        | CancellationError::AlreadyBroadcast(_) => StatusCode::CONFLICT,
+        CancellationError::SenderNotAllowed(_) => StatusCode::FORBIDDEN,
+        CancellationError::InvalidSignature(_) => StatusCode::UNAUTHORIZED,
+        CancellationError::Db(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+async fn cancel(
+    State(api): State<MessageCancelApi>,
+    Path(message_id): Path<H256>,
+    Query(query): Query<CancelMessageQuery>,
+    headers: HeaderMap,
+    Json(request): Json<CancelMessageRequest>,
+) -> Result<Json<MessageCancellation>, (StatusCode, String)> {
+    let to_response = |err: CancellationError| (status_code(&err), err.to_string());
+    let (origin, db, message) = api.find(message_id, query.origin).map_err(to_response)?;
+    let cancelled_by = match &request.signature {
+        Some(signature) => {
+            let signature = signature
+                .parse::<ethers::types::Signature>()
+                .map_err(|err| (StatusCode::BAD_REQUEST, format!("Invalid signature: {err}")))?;
+            let signed = SignedType {
+                value: CancellationRequest { message_id },
+                signature: signature.into(),
+            };
+            let address = verify_sender_request(&message, &signed, &api.cancellation_senders)
+                .map_err(to_response)?;
+            CancelledBy::Sender { address }
+        }
+        None => {
+            api.authenticate_operator(&headers)?;
+            CancelledBy::Operator
+        }
+    };
+
+    let cancellation = cancel_message(
+        db,
+        &message,
+        cancelled_by,
+        request.reason,
+        api.config_hash.clone(),
+    )
+    .map_err(to_response)?;
+    info!(
+        ?message_id,
+        ?cancellation,
+        "Cancelled message via admin endpoint"
+    );
+    api.dequeue(&message, MessageKey::new(origin, message_id))
+        .await;
+    Ok(Json(cancellation))
+}
+
+impl MessageCancelApi {
+    /// The message along with its origin and the origin's database. The
+    /// origin has to be given if several origins dispatched a message with
+    /// the id.
+    fn find(
+        &self,
+        message_id: H256,
+        origin: Option<u32>,
+    ) -> Result<(u32, &HyperlaneRocksDB, HyperlaneMessage), CancellationError> {
+        let mut found = vec![];
+        for (domain, db) in &self.dbs {
+            if origin.is_some_and(|origin| origin != *domain) {
+                continue;
+            }
+            if let Some(message) = db.retrieve_message_by_id(&message_id)? {
+                found.push((*domain, db, message));
+            }
+        }
+        match found.len() {
+            0 => Err(CancellationError::NotFound(message_id)),
+            1 => Ok(found.remove(0)),
+            _ => {
+                let mut origins: Vec<_> = found.into_iter().map(|(domain, ..)| domain).collect();
+                origins.sort();
+                Err(CancellationError::AmbiguousId(message_id, origins))
+            }
+        }
+    }
+
+    fn authenticate_operator(&self, headers: &HeaderMap) -> Result<(), (StatusCode, String)> {
+        let Some(admin_token) = &self.admin_token else {
+            return Err((
+                StatusCode::FORBIDDEN,
+                "Operators can't cancel messages without an admin token configured".to_owned(),
+            ));
+        };
+        let token = headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+        if token != Some(admin_token.as_str()) {
+            return Err((
+                StatusCode::UNAUTHORIZED,
+                "Missing or invalid admin token".to_owned(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Drop the pending operation of a cancelled message from the prepare
+    /// queue. Operations already past it are dropped before being submitted.
+    async fn dequeue(&self, message: &HyperlaneMessage, key: MessageKey) {
+        let Some(queue) = self.op_queues.get(&message.destination) else {
+            return;
+        };
+        let mut queue = queue.lock().await;
+        let (cancelled, retained) = std::mem::take(&mut *queue)
+            .into_iter()
+            .partition::<Vec<_>, _>(|Reverse(op)| op.key() == key);
+        queue.extend(retained);
+        for Reverse(op) in cancelled {
+            op.decrement_metric_if_exists();
+        }
+    }
+
+    pub fn router(&self) -> Router {
+        Router::new()
+            .route("/:message_id/cancel", routing::post(cancel))
+            .with_state(self.clone())
+    }
+
+    pub fn get_route(&self) -> (&'static str, Router) {
+        (MESSAGE_API_BASE, self.router())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use hyperlane_base::db::test_utils;
+    use hyperlane_core::{
+        HyperlaneSigner, HyperlaneSignerExt, KnownHyperlaneDomain, QueueOperation,
+    };
+    use hyperlane_ethereum::test_keys::test_signers;
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::msg::op_queue::test::MockPendingOperation;
+
+    const ORIGIN: KnownHyperlaneDomain = KnownHyperlaneDomain::Test1;
+    const DESTINATION: KnownHyperlaneDomain = KnownHyperlaneDomain::Test2;
+    const ADMIN_TOKEN: &str = "admin-token";
+
+    fn setup_test_server(db: HyperlaneRocksDB) -> (SocketAddr, OperationPriorityQueue) {
+        setup_test_server_with_origins(HashMap::from([(ORIGIN as u32, db)]))
+    }
+
+    fn setup_test_server_with_origins(
+        dbs: HashMap<u32, HyperlaneRocksDB>,
+    ) -> (SocketAddr, OperationPriorityQueue) {
+        let queue = OperationPriorityQueue::default();
+        let api = MessageCancelApi::new(
+            HashMap::from([(DESTINATION as u32, queue.clone())]),
+            dbs,
+            Some(ADMIN_TOKEN.to_owned()),
+            vec![test_signers(0).eth_address()],
+            "config_hash".to_owned(),
+        );
+        let (path, router) = api.get_route();
+        let app = Router::new().nest(path, router);
+        let server =
+            axum::Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(app.into_make_service());
+        let addr = server.local_addr();
+        tokio::spawn(server);
+        (addr, queue)
+    }
+
+    async fn seed(db: &HyperlaneRocksDB, queue: &OperationPriorityQueue) -> Vec<HyperlaneMessage> {
+        let mut messages = vec![];
+        for nonce in 0..3 {
+            let message = HyperlaneMessage {
+                nonce,
+                origin: ORIGIN as u32,
+                destination: DESTINATION as u32,
+                sender: test_signers(0).eth_address().into(),
+                ..Default::default()
+            };
+            db.store_message(&message, 0).unwrap();
+            let op = MockPendingOperation::with_message_data(message.clone());
+            queue
+                .lock()
+                .await
+                .push(Reverse(Box::new(op) as QueueOperation));
+            messages.push(message);
+        }
+        messages
+    }
+
+    async fn post(
+        addr: SocketAddr,
+        message_id: H256,
+        token: Option<&str>,
+        body: Value,
+    ) -> reqwest::Response {
+        let mut request = reqwest::Client::new()
+            .post(format!(
+                "http://{addr}{MESSAGE_API_BASE}/{message_id:?}/cancel"
+            ))
+            .json(&body);
+        if let Some(token) = token {
+            request = request.bearer_auth(token);
+        }
+        request.send().await.unwrap()
+    }
+
+    async fn queued(queue: &OperationPriorityQueue) -> Vec<H256> {
+        let mut ids: Vec<_> = queue
+            .lock()
+            .await
+            .iter()
+            .map(|Reverse(op)| op.id())
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    #[tokio::test]
+    async fn test_operator_cancels_a_pending_message() {
+        test_utils::run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(&ORIGIN.into(), db);
+            let (addr, queue) = setup_test_server(db.clone());
+            let messages = seed(&db, &queue).await;
+            let id = messages[1].id();
+            let body = json!({ "reason": "bad payload" });
+
+            let response = post(addr, id, None, body.clone()).await;
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+            let response = post(addr, id, Some("wrong"), body.clone()).await;
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+            assert_eq!(queued(&queue).await.len(), 3);
+
+            let response = post(addr, id, Some(ADMIN_TOKEN), body.clone()).await;
+            assert_eq!(response.status(), StatusCode::OK);
+            let cancellation: Value = response.json().await.unwrap();
+            assert_eq!(cancellation["by"], "operator");
+            assert_eq!(cancellation["reason"], "bad payload");
+            assert!(!queued(&queue).await.contains(&id));
+            assert_eq!(queued(&queue).await.len(), 2);
+            assert!(db
+                .retrieve_message_cancellation::<MessageCancellation>(&id)
+                .unwrap()
+                .is_some());
+
+            let response = post(addr, id, Some(ADMIN_TOKEN), body.clone()).await;
+            assert_eq!(response.status(), StatusCode::CONFLICT);
+            let response = post(addr, H256::random(), Some(ADMIN_TOKEN), body).await;
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_sender_cancels_with_a_signed_request() {
+        test_utils::run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(&ORIGIN.into(), db);
+            let (addr, queue) = setup_test_server(db.clone());
+            let messages = seed(&db, &queue).await;
+            let sign = |message_id| async move {
+                let signed = test_signers(0)
+                    .sign(CancellationRequest { message_id })
+                    .await
+                    .unwrap();
+                format!("0x{}", signed.signature)
+            };
+
+            // Signed for another message
+            let body =
+                json!({ "reason": "bad payload", "signature": sign(messages[2].id()).await });
+            let response = post(addr, messages[0].id(), None, body).await;
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+            let id = messages[0].id();
+            let body = json!({ "reason": "bad payload", "signature": sign(id).await });
+            let response = post(addr, id, None, body).await;
+            assert_eq!(response.status(), StatusCode::OK);
+            let cancellation: Value = response.json().await.unwrap();
+            assert_eq!(cancellation["by"], "sender");
+            assert_eq!(
+                cancellation["address"],
+                json!(test_signers(0).eth_address())
+            );
+            assert!(!queued(&queue).await.contains(&id));
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_cancelling_is_rejected_once_broadcast_or_delivered() {
+        test_utils::run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(&ORIGIN.into(), db);
+            let (addr, queue) = setup_test_server(db.clone());
+            let messages = seed(&db, &queue).await;
+            let body = json!({ "reason": "too late" });
+
+            db.store_message_broadcast(&messages[0].id()).unwrap();
+            let response = post(addr, messages[0].id(), Some(ADMIN_TOKEN), body.clone()).await;
+            assert_eq!(response.status(), StatusCode::CONFLICT);
+            assert!(response.text().await.unwrap().contains("already broadcast"));
+
+            db.store_processed_by_nonce(&messages[1].nonce, &true)
+                .unwrap();
+            let response = post(addr, messages[1].id(), Some(ADMIN_TOKEN), body).await;
+            assert_eq!(response.status(), StatusCode::CONFLICT);
+
+            assert_eq!(queued(&queue).await.len(), 3);
+            assert!(db
+                .retrieve_message_cancellation::<MessageCancellation>(&messages[0].id())
+                .unwrap()
+                .is_none());
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_ids_dispatched_on_several_origins_need_an_origin() {
+        test_utils::run_test_db(|db| async move {
+            // A fork of the origin replaying its messages
+            const FORK: KnownHyperlaneDomain = KnownHyperlaneDomain::Test3;
+            let origin_db = HyperlaneRocksDB::new(&ORIGIN.into(), db.clone());
+            let fork_db = HyperlaneRocksDB::new(&FORK.into(), db);
+            let (addr, queue) = setup_test_server_with_origins(HashMap::from([
+                (ORIGIN as u32, origin_db.clone()),
+                (FORK as u32, fork_db.clone()),
+            ]));
+            let message = seed(&origin_db, &queue).await.remove(0);
+            let id = message.id();
+            fork_db.store_message(&message, 0).unwrap();
+            let op = MockPendingOperation::with_message_data(message.clone())
+                .with_origin_domain_id(FORK as u32);
+            queue
+                .lock()
+                .await
+                .push(Reverse(Box::new(op) as QueueOperation));
+            let cancel = |origin: Option<KnownHyperlaneDomain>| {
+                let query =
+                    origin.map_or(String::new(), |origin| format!("?origin={}", origin as u32));
+                reqwest::Client::new()
+                    .post(format!(
+                        "http://{addr}{MESSAGE_API_BASE}/{id:?}/cancel{query}"
+                    ))
+                    .bearer_auth(ADMIN_TOKEN)
+                    .json(&json!({ "reason": "forked" }))
+                    .send()
+            };
+
+            let response = cancel(None).await.unwrap();
+            assert_eq!(response.status(), StatusCode::CONFLICT);
+            assert!(response
+                .text()
+                .await
+                .unwrap()
+                .contains("qualify it with an origin"));
+            assert_eq!(queued(&queue).await.len(), 4);
+
+            let response = cancel(Some(FORK)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            // Only the fork's message is cancelled and dequeued
+            let remaining: Vec<_> = queue
+                .lock()
+                .await
+                .iter()
+                .filter(|Reverse(op)| op.id() == id)
+                .map(|Reverse(op)| op.origin_domain_id())
+                .collect();
+            assert_eq!(remaining, vec![ORIGIN as u32]);
+            assert!(fork_db
+                .retrieve_message_cancellation::<MessageCancellation>(&id)
+                .unwrap()
+                .is_some());
+            assert!(origin_db
+                .retrieve_message_cancellation::<MessageCancellation>(&id)
+                .unwrap()
+                .is_none());
+
+            // The origin's message is tracked independently of the fork's
+            let response = cancel(Some(ORIGIN)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        })
+        .await;
+    }
+}
@@ -0,0 +1,96 @@
+use std::convert::Infallible;
+
+use axum::{
+    extract::{Query, State},
+    response::sse::{Event, KeepAlive, Sse},
+    routing, Router,
+};
+use derive_new::new;
+use futures::{Stream, StreamExt};
+use tracing::warn;
+
+use crate::msg::events::{MessageEventBus, MessageEventFilter};
+
+const MESSAGE_EVENTS_API_BASE: &str = "/message_events";
+
+/// Streams the lifecycle events of messages as server-sent events, filtered
+/// by the `sender`, `origin` and `destination` query parameters
+#[derive(new, Clone)]
+pub struct MessageEventsApi {
+    events: MessageEventBus,
+}
+
+async fn message_events(
+    State(events): State<MessageEventBus>,
+    Query(filter): Query<MessageEventFilter>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let stream = events.subscribe(filter).filter_map(|event| async move {
+        match Event::default().json_data(&event) {
+            Ok(event) => Some(Ok(event)),
+            Err(err) => {
+                warn!(?err, ?event, "Failed to serialize message event");
+                None
+            }
+        }
+    });
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+impl MessageEventsApi {
+    pub fn router(&self) -> Router {
+        Router::new()
+            .route("/", routing::get(message_events))
+            .with_state(self.events.clone())
+    }
+
+    pub fn get_route(&self) -> (&'static str, Router) {
+        (MESSAGE_EVENTS_API_BASE, self.router())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use axum::http::StatusCode;
+    use hyperlane_core::{HyperlaneMessage, H256};
+
+    use super::*;
+    use crate::msg::events::MessageEventKind;
+
+    #[tokio::test]
+    async fn test_message_events_are_streamed() {
+        let events = MessageEventBus::default();
+        let app = MessageEventsApi::new(events.clone()).router();
+        let server =
+            axum::Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(app.into_make_service());
+        let addr: SocketAddr = server.local_addr();
+        tokio::spawn(server);
+
+        let sender = H256::repeat_byte(1);
+        let mut response = reqwest::get(format!("http://{addr}/?sender={sender:#x}&origin=1"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let message = |nonce, sender| HyperlaneMessage {
+            nonce,
+            origin: 1,
+            sender,
+            ..Default::default()
+        };
+        // Subscribed once the response started
+        events.publish(&message(0, H256::zero()), MessageEventKind::Indexed);
+        events.publish(&message(1, sender), MessageEventKind::DeadLettered);
+
+        let mut body = String::new();
+        while !body.ends_with("\n\n") {
+            let chunk = response.chunk().await.unwrap().unwrap();
+            body.push_str(std::str::from_utf8(&chunk).unwrap());
+        }
+        let data = body.strip_prefix("data: ").unwrap().trim_end();
+        let event: serde_json::Value = serde_json::from_str(data).unwrap();
+        assert_eq!(event["nonce"], 1);
+        assert_eq!(event["kind"], "dead_lettered");
+    }
+}
@@ -1,6 +1,12 @@
-use crate::settings::matching_list::MatchingList;
+use std::collections::HashMap;
+
+use crate::{
+    msg::op_queue::{OpQueue, PrioritizedOperation},
+    settings::matching_list::MatchingList,
+};
 use axum::{extract::State, routing, Json, Router};
 use derive_new::new;
+use serde::{Deserialize, Serialize};
 use tokio::sync::broadcast::Sender;
 
 const MESSAGE_RETRY_API_BASE: &str = "/message_retry";
@@ -8,25 +14,45 @@ const MESSAGE_RETRY_API_BASE: &str = "/message_retry";
 #[derive(new, Clone)]
 pub struct MessageRetryApi {
     tx: Sender<MatchingList>,
+    /// Queues of operations waiting to be prepared, by destination domain
+    prepare_queues: HashMap<u32, OpQueue>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageRetryResponse {
+    pub message: String,
+    /// The matching operations waiting to be prepared, and where they are now
+    pub prioritized: Vec<PrioritizedOperation>,
 }
 
 async fn retry_message(
-    State(tx): State<Sender<MatchingList>>,
+    State(api): State<MessageRetryApi>,
     Json(retry_req_payload): Json<MatchingList>,
-) -> String {
-    match tx.send(retry_req_payload) {
+) -> Json<MessageRetryResponse> {
+    let mut prioritized = vec![];
+    for queue in api.prepare_queues.values() {
+        prioritized.extend(queue.prioritize(&retry_req_payload).await);
+    }
+    prioritized.sort_by_key(|op| (op.destination, op.position));
+    // operations already prepared are retried by the submit and confirm
+    // queues as they pick the request up
+    let message = match api.tx.send(retry_req_payload) {
         Ok(_) => "Moved message(s) to the front of the queue".to_string(),
         // Technically it's bad practice to print the error message to the user, but
         // this endpoint is for debugging purposes only.
         Err(err) => format!("Failed to send retry request to the queue: {}", err),
-    }
+    };
+    Json(MessageRetryResponse {
+        message,
+        prioritized,
+    })
 }
 
 impl MessageRetryApi {
     pub fn router(&self) -> Router {
         Router::new()
             .route("/", routing::post(retry_message))
-            .with_state(self.tx.clone())
+            .with_state(self.clone())
     }
 
     pub fn get_route(&self) -> (&'static str, Router) {
@@ -36,18 +62,33 @@ impl MessageRetryApi {
 
 #[cfg(test)]
 mod tests {
-    use crate::{msg::op_queue::test::MockPendingOperation, server::ENDPOINT_MESSAGES_QUEUE_SIZE};
+    use crate::{
+        msg::op_queue::test::{dummy_metrics_and_label, MockPendingOperation},
+        server::ENDPOINT_MESSAGES_QUEUE_SIZE,
+    };
 
     use super::*;
     use axum::http::StatusCode;
-    use hyperlane_core::{HyperlaneMessage, QueueOperation};
+    use hyperlane_core::{
+        HyperlaneDomain, HyperlaneMessage, KnownHyperlaneDomain, PendingOperation, QueueLane,
+        QueueOperation,
+    };
     use serde_json::json;
-    use std::net::SocketAddr;
-    use tokio::sync::broadcast::{Receiver, Sender};
+    use std::{net::SocketAddr, sync::Arc};
+    use tokio::sync::{
+        broadcast::{Receiver, Sender},
+        Mutex,
+    };
 
     fn setup_test_server() -> (SocketAddr, Receiver<MatchingList>) {
+        setup_test_server_with_queues(HashMap::new())
+    }
+
+    fn setup_test_server_with_queues(
+        prepare_queues: HashMap<u32, OpQueue>,
+    ) -> (SocketAddr, Receiver<MatchingList>) {
         let broadcast_tx = Sender::<MatchingList>::new(ENDPOINT_MESSAGES_QUEUE_SIZE);
-        let message_retry_api = MessageRetryApi::new(broadcast_tx.clone());
+        let message_retry_api = MessageRetryApi::new(broadcast_tx.clone(), prepare_queues);
         let (path, retry_router) = message_retry_api.get_route();
 
         let app = Router::new().nest(path, retry_router);
@@ -247,4 +288,46 @@ mod tests {
         // Check that the list received by the server matches the pending operation
         assert!(list.op_matches(&(Box::new(pending_operation) as QueueOperation)));
     }
+
+    #[tokio::test]
+    async fn test_retry_reports_position_in_the_priority_lane() {
+        let (metrics, queue_metrics_label) = dummy_metrics_and_label();
+        let (_, retry_rx) = tokio::sync::broadcast::channel(1);
+        let mut prepare_queue =
+            OpQueue::new(metrics, queue_metrics_label, Arc::new(Mutex::new(retry_rx)));
+        let destination: HyperlaneDomain = KnownHyperlaneDomain::Ethereum.into();
+        for _ in 0..10 {
+            prepare_queue
+                .push(
+                    Box::new(MockPendingOperation::new(0, destination.clone())),
+                    None,
+                )
+                .await;
+        }
+        let stuck = MockPendingOperation::new(3600, destination.clone());
+        let stuck_id = stuck.id();
+        prepare_queue.push(Box::new(stuck), None).await;
+
+        let (addr, mut rx) = setup_test_server_with_queues(HashMap::from([(
+            destination.id(),
+            prepare_queue.clone(),
+        )]));
+
+        let response = reqwest::Client::new()
+            .post(format!("http://{}{}", addr, MESSAGE_RETRY_API_BASE))
+            .json(&json!([{ "messageid": stuck_id }]))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response: MessageRetryResponse = response.json().await.unwrap();
+
+        assert_eq!(response.prioritized.len(), 1);
+        assert_eq!(response.prioritized[0].id, stuck_id);
+        assert_eq!(response.prioritized[0].lane, QueueLane::Priority);
+        assert_eq!(response.prioritized[0].position, 0);
+        // the request is still broadcast for the submit and confirm queues
+        assert!(rx.try_recv().is_ok());
+        assert_eq!(prepare_queue.pop().await.unwrap().id(), stuck_id);
+    }
 }
@@ -1,29 +1,119 @@
 use axum::Router;
 use derive_new::new;
-use std::collections::HashMap;
-use tokio::sync::broadcast::Sender;
+use hyperlane_base::{
+    db::{DiskGuard, HyperlaneRocksDB},
+    pacing::RebuildPacer,
+    ValidatorReputations,
+};
+use hyperlane_core::{H160, H256};
+use std::{collections::HashMap, sync::Arc};
+use tokio::sync::{broadcast::Sender, RwLock};
 
-use crate::{msg::op_queue::OperationPriorityQueue, settings::matching_list::MatchingList};
+use crate::{
+    merkle_tree::builder::MerkleTreeBuilder,
+    msg::{
+        circuit_breaker::CircuitBreaker, events::MessageEventBus,
+        latency_budget::LatencyBudgetTracker,
+        op_queue::{OpQueue, OperationPriorityQueue},
+        round_trip::RoundTripGuard, shadow::ShadowDivergences,
+    },
+    origin_startup::OriginHealth,
+    reindex::OriginReindexer,
+    settings::matching_list::MatchingList,
+};
 
 pub const ENDPOINT_MESSAGES_QUEUE_SIZE: usize = 100;
 
+pub use circuit_breakers::*;
+pub use gas_payments::*;
+pub use latency_budgets::*;
 pub use list_messages::*;
+pub use message_cancel::*;
+pub use message_events::*;
 pub use message_retry::*;
+pub use origin_health::*;
+pub use queues::*;
+pub use readiness::*;
+pub use rebuild_pacing::*;
+pub use reindex::*;
+pub use round_trips::*;
+pub use shadow_config::*;
+pub use tree_status::*;
+pub use validator_reputations::*;
 
+mod circuit_breakers;
+mod gas_payments;
+mod latency_budgets;
 mod list_messages;
+mod message_cancel;
+mod message_events;
 mod message_retry;
+mod origin_health;
+mod queues;
+mod readiness;
+mod rebuild_pacing;
+mod reindex;
+mod round_trips;
+mod shadow_config;
+mod tree_status;
+pub mod v1;
+mod validator_reputations;
 
 #[derive(new)]
 pub struct Server {
+    /// Retry broadcaster and the prepare queues retried operations jump the
+    /// queue of, by destination domain
     #[new(default)]
-    retry_transmitter: Option<Sender<MatchingList>>,
+    retry_transmitter: Option<(Sender<MatchingList>, HashMap<u32, OpQueue>)>,
     #[new(default)]
     op_queues: Option<HashMap<u32, OperationPriorityQueue>>,
+    /// Prepare, submit and confirm queues, by destination domain
+    #[new(default)]
+    submitter_queues: Option<HashMap<u32, Vec<OperationPriorityQueue>>>,
+    #[new(default)]
+    dbs: Option<HashMap<u32, HyperlaneRocksDB>>,
+    #[new(default)]
+    prover_syncs: Option<HashMap<String, Arc<RwLock<MerkleTreeBuilder>>>>,
+    #[new(default)]
+    delivery_policies: Option<HashMap<String, String>>,
+    #[new(default)]
+    igp_contracts: Option<HashMap<u32, Vec<H256>>>,
+    #[new(default)]
+    validator_reputations: Option<HashMap<String, ValidatorReputations>>,
+    #[new(default)]
+    circuit_breakers: Option<HashMap<String, Arc<CircuitBreaker>>>,
+    #[new(default)]
+    rebuild_pacers: Option<HashMap<String, RebuildPacer>>,
+    #[new(default)]
+    shadow_divergences: Option<Arc<ShadowDivergences>>,
+    #[new(default)]
+    latency_budgets: Option<Arc<LatencyBudgetTracker>>,
+    #[new(default)]
+    round_trips: Option<Vec<Arc<RoundTripGuard>>>,
+    #[new(default)]
+    message_events: Option<MessageEventBus>,
+    #[new(default)]
+    origin_health: Option<OriginHealth>,
+    /// Origin health and disk space the readiness probe reports on
+    #[new(default)]
+    readiness: Option<(OriginHealth, Option<DiskGuard>)>,
+    /// Admin token, senders allowed to cancel and config hash, if messages
+    /// can be cancelled
+    #[new(default)]
+    message_cancellation: Option<(Option<String>, Vec<H160>, String)>,
+    /// Reindexers by origin chain name and the admin token, if block ranges
+    /// can be reindexed
+    #[new(default)]
+    reindexers: Option<(HashMap<String, OriginReindexer>, String)>,
 }
 
 impl Server {
-    pub fn with_op_retry(mut self, transmitter: Sender<MatchingList>) -> Self {
-        self.retry_transmitter = Some(transmitter);
+    pub fn with_op_retry(
+        mut self,
+        transmitter: Sender<MatchingList>,
+        prepare_queues: HashMap<u32, OpQueue>,
+    ) -> Self {
+        self.retry_transmitter = Some((transmitter, prepare_queues));
         self
     }
 
@@ -32,16 +122,183 @@ impl Server {
         self
     }
 
+    pub fn with_submitter_queues(
+        mut self,
+        submitter_queues: HashMap<u32, Vec<OperationPriorityQueue>>,
+    ) -> Self {
+        self.submitter_queues = Some(submitter_queues);
+        self
+    }
+
+    pub fn with_dbs(mut self, dbs: HashMap<u32, HyperlaneRocksDB>) -> Self {
+        self.dbs = Some(dbs);
+        self
+    }
+
+    pub fn with_prover_syncs(
+        mut self,
+        prover_syncs: HashMap<String, Arc<RwLock<MerkleTreeBuilder>>>,
+    ) -> Self {
+        self.prover_syncs = Some(prover_syncs);
+        self
+    }
+
+    pub fn with_delivery_policies(mut self, delivery_policies: HashMap<String, String>) -> Self {
+        self.delivery_policies = Some(delivery_policies);
+        self
+    }
+
+    pub fn with_igp_contracts(mut self, igp_contracts: HashMap<u32, Vec<H256>>) -> Self {
+        self.igp_contracts = Some(igp_contracts);
+        self
+    }
+
+    pub fn with_validator_reputations(
+        mut self,
+        validator_reputations: HashMap<String, ValidatorReputations>,
+    ) -> Self {
+        self.validator_reputations = Some(validator_reputations);
+        self
+    }
+
+    pub fn with_circuit_breakers(
+        mut self,
+        circuit_breakers: HashMap<String, Arc<CircuitBreaker>>,
+    ) -> Self {
+        self.circuit_breakers = Some(circuit_breakers);
+        self
+    }
+
+    pub fn with_rebuild_pacers(mut self, rebuild_pacers: HashMap<String, RebuildPacer>) -> Self {
+        self.rebuild_pacers = Some(rebuild_pacers);
+        self
+    }
+
+    pub fn with_shadow_divergences(mut self, shadow_divergences: Arc<ShadowDivergences>) -> Self {
+        self.shadow_divergences = Some(shadow_divergences);
+        self
+    }
+
+    pub fn with_latency_budgets(mut self, latency_budgets: Arc<LatencyBudgetTracker>) -> Self {
+        self.latency_budgets = Some(latency_budgets);
+        self
+    }
+
+    pub fn with_round_trips(mut self, round_trips: Vec<Arc<RoundTripGuard>>) -> Self {
+        self.round_trips = Some(round_trips);
+        self
+    }
+
+    pub fn with_message_events(mut self, message_events: MessageEventBus) -> Self {
+        self.message_events = Some(message_events);
+        self
+    }
+
+    pub fn with_origin_health(mut self, origin_health: OriginHealth) -> Self {
+        self.origin_health = Some(origin_health);
+        self
+    }
+
+    pub fn with_readiness(
+        mut self,
+        origin_health: OriginHealth,
+        disk_guard: Option<DiskGuard>,
+    ) -> Self {
+        self.readiness = Some((origin_health, disk_guard));
+        self
+    }
+
+    pub fn with_message_cancellation(
+        mut self,
+        admin_token: Option<String>,
+        cancellation_senders: Vec<H160>,
+        config_hash: String,
+    ) -> Self {
+        self.message_cancellation = Some((admin_token, cancellation_senders, config_hash));
+        self
+    }
+
+    pub fn with_reindexers(
+        mut self,
+        reindexers: HashMap<String, OriginReindexer>,
+        admin_token: String,
+    ) -> Self {
+        self.reindexers = Some((reindexers, admin_token));
+        self
+    }
+
     /// Returns a vector of agent-specific endpoint routes to be served.
     /// Can be extended with additional routes and feature flags to enable/disable individually.
+    /// Routes responding with versioned types are also served under the
+    /// prefix of their version.
     pub fn routes(self) -> Vec<(&'static str, Router)> {
         let mut routes = vec![];
-        if let Some(retry_transmitter) = self.retry_transmitter {
-            routes.push(MessageRetryApi::new(retry_transmitter).get_route());
+        let mut v1_router = Router::new();
+        if let Some((retry_transmitter, prepare_queues)) = self.retry_transmitter {
+            routes.push(MessageRetryApi::new(retry_transmitter, prepare_queues).get_route());
+        }
+        if let (Some(dbs), Some(igp_contracts)) = (&self.dbs, self.igp_contracts) {
+            routes.push(GasPaymentsApi::new(dbs.clone(), igp_contracts).get_route());
         }
         if let Some(op_queues) = self.op_queues {
-            routes.push(ListOperationsApi::new(op_queues).get_route());
+            if let (Some(dbs), Some((admin_token, senders, config_hash))) =
+                (&self.dbs, self.message_cancellation)
+            {
+                routes.push(
+                    MessageCancelApi::new(
+                        op_queues.clone(),
+                        dbs.clone(),
+                        admin_token,
+                        senders,
+                        config_hash,
+                    )
+                    .get_route(),
+                );
+            }
+            let route = ListOperationsApi::new(op_queues).get_route();
+            routes.push(v1::with_v1_route(route, &mut v1_router));
+        }
+        if let (Some(dbs), Some(submitter_queues)) = (self.dbs, self.submitter_queues) {
+            let route = QueuesApi::new(submitter_queues, dbs).get_route();
+            routes.push(v1::with_v1_route(route, &mut v1_router));
+        }
+        if let Some(prover_syncs) = self.prover_syncs {
+            let policies = self.delivery_policies.unwrap_or_default();
+            let route = TreeStatusApi::new(prover_syncs, policies).get_route();
+            routes.push(v1::with_v1_route(route, &mut v1_router));
+        }
+        if let Some(validator_reputations) = self.validator_reputations {
+            routes.push(ValidatorReputationsApi::new(validator_reputations).get_route());
+        }
+        if let Some(circuit_breakers) = self.circuit_breakers {
+            routes.push(CircuitBreakersApi::new(circuit_breakers).get_route());
+        }
+        if let Some(rebuild_pacers) = self.rebuild_pacers {
+            routes.push(RebuildPacingApi::new(rebuild_pacers).get_route());
+        }
+        if let Some(shadow_divergences) = self.shadow_divergences {
+            routes.push(ShadowConfigApi::new(shadow_divergences).get_route());
+        }
+        if let Some(latency_budgets) = self.latency_budgets {
+            routes.push(LatencyBudgetsApi::new(latency_budgets).get_route());
+        }
+        if let Some(round_trips) = self.round_trips {
+            routes.push(RoundTripsApi::new(round_trips).get_route());
+        }
+        if let Some(message_events) = self.message_events {
+            routes.push(MessageEventsApi::new(message_events).get_route());
+        }
+        if let Some(origin_health) = self.origin_health {
+            let route = OriginHealthApi::new(origin_health).get_route();
+            routes.push(v1::with_v1_route(route, &mut v1_router));
+        }
+        if let Some((origin_health, disk_guard)) = self.readiness {
+            routes.push(ReadinessApi::new(origin_health, disk_guard).get_route());
+        }
+        if let Some((reindexers, admin_token)) = self.reindexers {
+            routes.push(ReindexApi::new(reindexers, admin_token).get_route());
         }
+        routes.push((v1::PREFIX, v1_router));
 
         routes
     }
@@ -0,0 +1,144 @@
+use axum::{extract::State, http::StatusCode, routing, Json, Router};
+use derive_new::new;
+
+use super::v1::{OriginHealthReport, Versioned};
+use crate::origin_startup::OriginHealth;
+
+const ORIGIN_HEALTH_API_BASE: &str = "/origin_health";
+
+/// Reports whether the priority origins are up, responding with 503 until
+/// they are, so that it can be used as a readiness probe
+#[derive(new, Clone)]
+pub struct OriginHealthApi {
+    health: OriginHealth,
+}
+
+async fn origin_health(
+    State(health): State<OriginHealth>,
+) -> (StatusCode, Json<Versioned<OriginHealthReport>>) {
+    let ready = health.is_ready();
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    let origins = health
+        .origins()
+        .into_iter()
+        .map(|(origin, state)| (origin, state.into()))
+        .collect();
+    (
+        status,
+        Json(Versioned::new(OriginHealthReport { ready, origins })),
+    )
+}
+
+impl OriginHealthApi {
+    pub fn router(&self) -> Router {
+        Router::new()
+            .route("/", routing::get(origin_health))
+            .with_state(self.health.clone())
+    }
+
+    pub fn get_route(&self) -> (&'static str, Router) {
+        (ORIGIN_HEALTH_API_BASE, self.router())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, net::SocketAddr, sync::Arc};
+
+    use hyperlane_core::{HyperlaneDomain, KnownHyperlaneDomain};
+    use serde_json::{json, Value};
+    use tokio::sync::Notify;
+
+    use super::*;
+    use crate::origin_startup::start_origins;
+
+    #[tokio::test]
+    async fn test_origin_health_during_startup() {
+        let (ethereum, polygon) = (
+            HyperlaneDomain::Known(KnownHyperlaneDomain::Ethereum),
+            HyperlaneDomain::Known(KnownHyperlaneDomain::Polygon),
+        );
+        let health = OriginHealth::default();
+        let app = OriginHealthApi::new(health.clone()).router();
+        let server =
+            axum::Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(app.into_make_service());
+        let addr: SocketAddr = server.local_addr();
+        tokio::spawn(server);
+        let get = move || async move {
+            let response = reqwest::get(format!("http://{addr}/")).await.unwrap();
+            (response.status(), response.json::<Value>().await.unwrap())
+        };
+
+        // Each origin is done initializing once released, polygon failing to
+        let releases: Arc<HashMap<_, _>> = Arc::new(
+            [
+                (ethereum.clone(), Notify::new()),
+                (polygon.clone(), Notify::new()),
+            ]
+            .into_iter()
+            .collect(),
+        );
+        let startup = tokio::spawn(start_origins(
+            vec![(ethereum.clone(), true), (polygon.clone(), false)],
+            health.clone(),
+            {
+                let (health, releases, failing) =
+                    (health.clone(), releases.clone(), polygon.clone());
+                move |origin| {
+                    let (health, releases, failing) =
+                        (health.clone(), releases.clone(), failing.clone());
+                    async move {
+                        releases[&origin].notified().await;
+                        if origin == failing {
+                            health.fail(&origin, "Error building cursor for origin");
+                        }
+                        vec![]
+                    }
+                }
+            },
+        ));
+        while health.origins().is_empty() {
+            tokio::task::yield_now().await;
+        }
+
+        let (status, body) = get().await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(
+            body,
+            json!({
+                "schema_version": 1,
+                "ready": false,
+                "origins": {
+                    "ethereum": { "state": "initializing" },
+                    "polygon": { "state": "initializing" },
+                }
+            })
+        );
+
+        // Ready once the priority origin is up
+        releases[&ethereum].notify_one();
+        let tasks = startup.await.unwrap();
+        let (status, body) = get().await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["ready"], true);
+        assert_eq!(body["origins"]["ethereum"], json!({ "state": "ready" }));
+        assert_eq!(
+            body["origins"]["polygon"],
+            json!({ "state": "initializing" })
+        );
+
+        // Origins failing in the background are reported, not initializing
+        releases[&polygon].notify_one();
+        futures::future::join_all(tasks).await;
+        let (status, body) = get().await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body["origins"]["polygon"],
+            json!({ "state": "failed", "error": "Error building cursor for origin" })
+        );
+    }
+}
@@ -0,0 +1,717 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+    sync::Arc,
+    time::Duration,
+};
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    routing, Json, Router,
+};
+use derive_new::new;
+use hyperlane_base::db::{HyperlaneDb, HyperlaneRocksDB};
+use hyperlane_core::{HyperlaneDomain, QueueOperation, H256};
+use serde::{Deserialize, Serialize};
+use tokio::{sync::Mutex, time::Instant};
+use tracing::{info, warn};
+
+use super::v1::{BulkOperationResult, MessageStatus, QueueListing, Versioned};
+use crate::msg::op_queue::OperationPriorityQueue;
+
+const QUEUES_API_BASE: &str = "/queues";
+const DEFAULT_LIST_LIMIT: usize = 100;
+/// How long the confirmation token of a dry-run can be used for
+const CONFIRMATION_TOKEN_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Filter selecting the queued operations a bulk operation applies to.
+/// All fields are optional; an empty filter matches every operation.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct QueueFilter {
+    /// Domain id or name of the origin
+    origin_domain: Option<HyperlaneDomain>,
+    /// Domain id or name of the destination
+    destination_domain: Option<HyperlaneDomain>,
+    /// Case-insensitive substring of the operation status, e.g. `gas payment`
+    reason: Option<String>,
+    sender_address: Option<H256>,
+    /// Only match operations that have been known for at least this long
+    min_age_secs: Option<u64>,
+}
+
+impl QueueFilter {
+    fn matches(&self, op: &QueueOperation) -> bool {
+        if self
+            .origin_domain
+            .as_ref()
+            .map_or(false, |origin| origin.id() != op.origin_domain_id())
+        {
+            return false;
+        }
+        if self
+            .destination_domain
+            .as_ref()
+            .map_or(false, |destination| {
+                destination.id() != op.destination_domain().id()
+            })
+        {
+            return false;
+        }
+        if self
+            .sender_address
+            .map_or(false, |sender| &sender != op.sender_address())
+        {
+            return false;
+        }
+        if let Some(reason) = &self.reason {
+            let status = op.status().to_string().to_lowercase();
+            if !status.contains(&reason.to_lowercase()) {
+                return false;
+            }
+        }
+        if let Some(min_age) = self.min_age_secs.map(Duration::from_secs) {
+            if op.age().map_or(true, |age| age < min_age) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ListQueuesRequest {
+    /// Domain id or name of the destination
+    destination: Option<HyperlaneDomain>,
+    reason: Option<String>,
+    limit: Option<usize>,
+    #[serde(default)]
+    offset: usize,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct BulkOperationRequest {
+    #[serde(default)]
+    filter: QueueFilter,
+    /// Token returned by a previous dry-run of the same request. Required to
+    /// execute destructive operations.
+    confirmation_token: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum BulkAction {
+    Requeue,
+    Drop,
+}
+
+impl BulkAction {
+    fn is_destructive(&self) -> bool {
+        matches!(self, BulkAction::Drop)
+    }
+}
+
+/// A dry-run whose confirmation token awaits execution
+#[derive(Clone, Debug)]
+struct PendingConfirmation {
+    action: BulkAction,
+    filter: QueueFilter,
+    issued_at: Instant,
+}
+
+impl PendingConfirmation {
+    fn is_expired(&self) -> bool {
+        self.issued_at.elapsed() >= CONFIRMATION_TOKEN_TTL
+    }
+}
+
+#[derive(new, Clone)]
+pub struct QueuesApi {
+    /// Prepare, submit and confirm queues by destination domain id
+    op_queues: HashMap<u32, Vec<OperationPriorityQueue>>,
+    /// Origin databases by origin domain id
+    dbs: HashMap<u32, HyperlaneRocksDB>,
+    /// Confirmation tokens handed out by dry-runs, awaiting execution
+    #[new(default)]
+    pending_confirmations: Arc<Mutex<HashMap<String, PendingConfirmation>>>,
+}
+
+async fn list_queues(
+    State(api): State<QueuesApi>,
+    Query(request): Query<ListQueuesRequest>,
+) -> Result<Json<Versioned<QueueListing>>, (StatusCode, String)> {
+    let filter = QueueFilter {
+        destination_domain: request.destination,
+        reason: request.reason,
+        ..Default::default()
+    };
+    let mut matching = vec![];
+    for queue in api.queues_for(&filter)? {
+        matching.extend(
+            queue
+                .lock()
+                .await
+                .iter()
+                .filter(|Reverse(op)| filter.matches(op))
+                .map(|Reverse(op)| MessageStatus::from(op)),
+        );
+    }
+    // Present operations in a stable order across requests so pagination is meaningful
+    matching.sort_by_key(|op| (op.origin_domain, op.destination_domain, op.nonce, op.id));
+    let total = matching.len();
+    let operations = matching
+        .into_iter()
+        .skip(request.offset)
+        .take(request.limit.unwrap_or(DEFAULT_LIST_LIMIT))
+        .collect();
+    Ok(Json(Versioned::new(QueueListing {
+        total,
+        offset: request.offset,
+        operations,
+    })))
+}
+
+async fn requeue_operations(
+    State(api): State<QueuesApi>,
+    Json(request): Json<BulkOperationRequest>,
+) -> Result<Json<Versioned<BulkOperationResult>>, (StatusCode, String)> {
+    api.execute(BulkAction::Requeue, request).await.map(Json)
+}
+
+async fn drop_operations(
+    State(api): State<QueuesApi>,
+    Json(request): Json<BulkOperationRequest>,
+) -> Result<Json<Versioned<BulkOperationResult>>, (StatusCode, String)> {
+    api.execute(BulkAction::Drop, request).await.map(Json)
+}
+
+impl QueuesApi {
+    fn queues_for(
+        &self,
+        filter: &QueueFilter,
+    ) -> Result<Vec<OperationPriorityQueue>, (StatusCode, String)> {
+        match &filter.destination_domain {
+            Some(domain) => match self.op_queues.get(&domain.id()) {
+                Some(queues) => Ok(queues.clone()),
+                None => Err((
+                    StatusCode::NOT_FOUND,
+                    format!("No queue found for domain {}", domain),
+                )),
+            },
+            None => Ok(self.op_queues.values().flatten().cloned().collect()),
+        }
+    }
+
+    async fn execute(
+        &self,
+        action: BulkAction,
+        request: BulkOperationRequest,
+    ) -> Result<Versioned<BulkOperationResult>, (StatusCode, String)> {
+        let BulkOperationRequest {
+            filter,
+            confirmation_token,
+        } = request;
+        let queues = self.queues_for(&filter)?;
+
+        let dry_run = action.is_destructive() && confirmation_token.is_none();
+        if let Some(token) = confirmation_token {
+            let confirmed = self.pending_confirmations.lock().await.remove(&token);
+            let matches = confirmed.map_or(false, |confirmed| {
+                confirmed.action == action && confirmed.filter == filter && !confirmed.is_expired()
+            });
+            if !matches {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    "Confirmation token does not match an unexpired dry-run of this request"
+                        .to_owned(),
+                ));
+            }
+        }
+
+        let mut affected = vec![];
+        for queue in queues {
+            // Hold the queue lock while updating the db, so the submitter can't observe
+            // an operation whose persisted state disagrees with its in-memory state.
+            let mut queue = queue.lock().await;
+            if dry_run {
+                affected.extend(
+                    queue
+                        .iter()
+                        .filter(|Reverse(op)| filter.matches(op))
+                        .map(|Reverse(op)| op.id()),
+                );
+                continue;
+            }
+            let mut retained = BinaryHeap::with_capacity(queue.len());
+            for Reverse(mut op) in queue.drain() {
+                if !filter.matches(&op) {
+                    retained.push(Reverse(op));
+                    continue;
+                }
+                if let Err(err) = self.persist(action, &op) {
+                    warn!(operation = %op, error = %err, ?action, "Failed to persist bulk queue operation");
+                    retained.push(Reverse(op));
+                    continue;
+                }
+                affected.push(op.id());
+                match action {
+                    BulkAction::Requeue => {
+                        info!(operation = %op, "Requeueing operation via admin endpoint");
+                        op.reset_attempts();
+                        retained.push(Reverse(op));
+                    }
+                    BulkAction::Drop => {
+                        info!(operation = %op, "Dropping operation via admin endpoint");
+                        op.decrement_metric_if_exists();
+                    }
+                }
+            }
+            *queue = retained;
+        }
+
+        let confirmation_token = if dry_run {
+            let token = format!("{:016x}", rand::random::<u64>());
+            let mut pending_confirmations = self.pending_confirmations.lock().await;
+            // Tokens of dry-runs that were never executed would pile up otherwise
+            pending_confirmations.retain(|_, pending| !pending.is_expired());
+            pending_confirmations.insert(
+                token.clone(),
+                PendingConfirmation {
+                    action,
+                    filter,
+                    issued_at: Instant::now(),
+                },
+            );
+            Some(token)
+        } else {
+            None
+        };
+        Ok(Versioned::new(BulkOperationResult {
+            dry_run,
+            affected: affected.len(),
+            operations: affected,
+            confirmation_token,
+        }))
+    }
+
+    /// Reflect a bulk action in the origin database of the operation.
+    fn persist(&self, action: BulkAction, op: &QueueOperation) -> eyre::Result<()> {
+        let Some(db) = self.dbs.get(&op.origin_domain_id()) else {
+            // Operations from unknown origins have no persisted state to update
+            return Ok(());
+        };
+        match action {
+            BulkAction::Requeue => {
+                db.store_pending_message_retry_count_by_message_id(&op.id(), &0)?;
+            }
+            BulkAction::Drop => {
+                // Dropped messages are marked as processed so they aren't picked up
+                // again by the message processor after a restart.
+                let nonce = match db.retrieve_nonce_by_message_id(&op.id())? {
+                    Some(nonce) => nonce,
+                    // Stored before the nonce index and not backfilled yet
+                    None => {
+                        db.retrieve_message_by_id(&op.id())?
+                            .ok_or_else(|| eyre::eyre!("Message {:?} not found in db", op.id()))?
+                            .nonce
+                    }
+                };
+                db.store_processed_by_nonce(&nonce, &true)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn router(&self) -> Router {
+        Router::new()
+            .route("/", routing::get(list_queues))
+            .route("/requeue", routing::post(requeue_operations))
+            .route("/drop", routing::post(drop_operations))
+            .with_state(self.clone())
+    }
+
+    pub fn get_route(&self) -> (&'static str, Router) {
+        (QUEUES_API_BASE, self.router())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::msg::op_queue::test::MockPendingOperation;
+    use hyperlane_base::db::test_utils;
+    use hyperlane_core::{
+        HyperlaneDomain, HyperlaneMessage, KnownHyperlaneDomain, PendingOperationStatus,
+        ReprepareReason,
+    };
+    use serde_json::json;
+    use std::net::SocketAddr;
+
+    const ORIGIN: KnownHyperlaneDomain = KnownHyperlaneDomain::Test1;
+    const DESTINATION: KnownHyperlaneDomain = KnownHyperlaneDomain::Test2;
+
+    /// The prepare, submit and confirm queues of the destination, and the
+    /// api managing them
+    fn setup_test_api(db: HyperlaneRocksDB) -> (QueuesApi, Vec<OperationPriorityQueue>) {
+        let queues: Vec<_> = (0..3).map(|_| OperationPriorityQueue::default()).collect();
+        let op_queues = HashMap::from([(DESTINATION as u32, queues.clone())]);
+        let dbs = HashMap::from([(ORIGIN as u32, db)]);
+        (QueuesApi::new(op_queues, dbs), queues)
+    }
+
+    fn serve(api: QueuesApi) -> SocketAddr {
+        let (path, router) = api.get_route();
+        let app = Router::new().nest(path, router);
+
+        // Running the app in the background using a test server
+        let server =
+            axum::Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(app.into_make_service());
+        let addr = server.local_addr();
+        tokio::spawn(server);
+        addr
+    }
+
+    /// Serves the api, returning the prepare queue of the destination
+    fn setup_test_server(db: HyperlaneRocksDB) -> (SocketAddr, OperationPriorityQueue) {
+        let (api, queues) = setup_test_api(db);
+        (serve(api), queues[0].clone())
+    }
+
+    /// Seeds the db and the queue with messages in mixed states. Returns the
+    /// messages in nonce order; even nonces are waiting on gas payments.
+    async fn seed(db: &HyperlaneRocksDB, queue: &OperationPriorityQueue) -> Vec<HyperlaneMessage> {
+        let spammer = H256::from_low_u64_be(0xbad);
+        let mut messages = vec![];
+        for nonce in 0..6 {
+            let message = HyperlaneMessage {
+                nonce,
+                origin: ORIGIN as u32,
+                destination: DESTINATION as u32,
+                sender: if nonce % 3 == 0 {
+                    spammer
+                } else {
+                    H256::from_low_u64_be(nonce as u64)
+                },
+                ..Default::default()
+            };
+            db.store_message(&message, 0).unwrap();
+            db.store_pending_message_retry_count_by_message_id(&message.id(), &5)
+                .unwrap();
+            let status = if nonce % 2 == 0 {
+                PendingOperationStatus::Retry(ReprepareReason::GasPaymentRequirementNotMet)
+            } else {
+                PendingOperationStatus::Retry(ReprepareReason::ErrorEstimatingGas)
+            };
+            let op = MockPendingOperation::with_message_data(message.clone())
+                .with_status(status)
+                .with_age(Duration::from_secs(nonce as u64 * 60));
+            queue
+                .lock()
+                .await
+                .push(Reverse(Box::new(op) as QueueOperation));
+            messages.push(message);
+        }
+        messages
+    }
+
+    async fn post(addr: SocketAddr, path: &str, body: serde_json::Value) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!("http://{}{}{}", addr, QUEUES_API_BASE, path))
+            .json(&body)
+            .send()
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn test_filter_matching() {
+        let message = HyperlaneMessage {
+            origin: 1,
+            destination: 2,
+            ..Default::default()
+        };
+        let op: QueueOperation = Box::new(
+            MockPendingOperation::with_message_data(message.clone())
+                .with_status(PendingOperationStatus::Retry(
+                    ReprepareReason::GasPaymentRequirementNotMet,
+                ))
+                .with_age(Duration::from_secs(120)),
+        );
+
+        assert!(QueueFilter::default().matches(&op));
+        let filter = QueueFilter {
+            origin_domain: Some(1.into()),
+            destination_domain: Some(2.into()),
+            reason: Some("GAS PAYMENT".to_owned()),
+            sender_address: Some(message.sender),
+            min_age_secs: Some(60),
+        };
+        assert!(filter.matches(&op));
+        assert!(!QueueFilter {
+            origin_domain: Some(2.into()),
+            ..filter.clone()
+        }
+        .matches(&op));
+        assert!(!QueueFilter {
+            reason: Some("estimating".to_owned()),
+            ..filter.clone()
+        }
+        .matches(&op));
+        assert!(!QueueFilter {
+            sender_address: Some(H256::from_low_u64_be(1)),
+            ..filter.clone()
+        }
+        .matches(&op));
+        assert!(!QueueFilter {
+            min_age_secs: Some(600),
+            ..filter
+        }
+        .matches(&op));
+    }
+
+    #[tokio::test]
+    async fn test_list_queues_filters_and_paginates() {
+        test_utils::run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(&HyperlaneDomain::Known(ORIGIN), db);
+            let (addr, queue) = setup_test_server(db.clone());
+            let messages = seed(&db, &queue).await;
+
+            let response: Versioned<QueueListing> = reqwest::get(format!(
+                "http://{}{}?destination={}&reason=gas%20payment&limit=2&offset=1",
+                addr, QUEUES_API_BASE, DESTINATION as u32
+            ))
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+
+            assert_eq!(response.total, 3);
+            assert_eq!(response.offset, 1);
+            let ids: Vec<_> = response.operations.iter().map(|op| op.id).collect();
+            assert_eq!(ids, vec![messages[2].id(), messages[4].id()]);
+
+            // The destination can also be given by name
+            let response: Versioned<QueueListing> = reqwest::get(format!(
+                "http://{}{}?destination={}&reason=gas%20payment",
+                addr, QUEUES_API_BASE, DESTINATION
+            ))
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+            assert_eq!(response.total, 3);
+
+            let response =
+                reqwest::get(format!("http://{}{}?destination=1", addr, QUEUES_API_BASE))
+                    .await
+                    .unwrap();
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_requeue_resets_retries() {
+        test_utils::run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(&HyperlaneDomain::Known(ORIGIN), db);
+            let (addr, queue) = setup_test_server(db.clone());
+            let messages = seed(&db, &queue).await;
+
+            let response: Versioned<BulkOperationResult> = post(
+                addr,
+                "/requeue",
+                json!({ "filter": { "reason": "estimating", "min_age_secs": 120 } }),
+            )
+            .await
+            .json()
+            .await
+            .unwrap();
+
+            // nonces 3 and 5 are old enough and failing gas estimation
+            assert!(!response.dry_run);
+            assert_eq!(response.affected, 2);
+            for message in &messages {
+                let retries = db
+                    .retrieve_pending_message_retry_count_by_message_id(&message.id())
+                    .unwrap();
+                let expected = if response.operations.contains(&message.id()) {
+                    0
+                } else {
+                    5
+                };
+                assert_eq!(retries, Some(expected));
+            }
+            // requeued operations stay in the queue
+            assert_eq!(queue.lock().await.len(), messages.len());
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_drop_requires_confirmation() {
+        test_utils::run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(&HyperlaneDomain::Known(ORIGIN), db);
+            let (addr, queue) = setup_test_server(db.clone());
+            let messages = seed(&db, &queue).await;
+            let spammer = H256::from_low_u64_be(0xbad);
+            let filter = json!({ "sender_address": spammer });
+
+            // dry-run doesn't touch anything
+            let dry_run: Versioned<BulkOperationResult> =
+                post(addr, "/drop", json!({ "filter": filter }))
+                    .await
+                    .json()
+                    .await
+                    .unwrap();
+            assert!(dry_run.dry_run);
+            assert_eq!(dry_run.affected, 2);
+            assert_eq!(queue.lock().await.len(), messages.len());
+            let token = dry_run.confirmation_token.clone().unwrap();
+
+            // a token can't be used for a different filter
+            let response = post(
+                addr,
+                "/drop",
+                json!({ "filter": {}, "confirmation_token": token }),
+            )
+            .await;
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+            // the rejected attempt consumed the token, so get a fresh one
+            let dry_run: Versioned<BulkOperationResult> =
+                post(addr, "/drop", json!({ "filter": filter }))
+                    .await
+                    .json()
+                    .await
+                    .unwrap();
+            let confirmed: Versioned<BulkOperationResult> = post(
+                addr,
+                "/drop",
+                json!({ "filter": filter, "confirmation_token": dry_run.confirmation_token }),
+            )
+            .await
+            .json()
+            .await
+            .unwrap();
+            assert!(!confirmed.dry_run);
+            assert_eq!(confirmed.operations, dry_run.operations);
+
+            // queue and db agree on what was dropped
+            let remaining: Vec<_> = queue
+                .lock()
+                .await
+                .iter()
+                .map(|Reverse(op)| op.id())
+                .collect();
+            for message in &messages {
+                let dropped = message.sender == spammer;
+                assert_eq!(remaining.contains(&message.id()), !dropped);
+                assert_eq!(
+                    db.retrieve_processed_by_nonce(&message.nonce).unwrap(),
+                    dropped.then_some(true)
+                );
+            }
+
+            // tokens are single use
+            let response = post(
+                addr,
+                "/drop",
+                json!({ "filter": filter, "confirmation_token": dry_run.confirmation_token }),
+            )
+            .await;
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_operations_are_reached_in_every_submitter_queue() {
+        test_utils::run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(&HyperlaneDomain::Known(ORIGIN), db);
+            let (api, queues) = setup_test_api(db.clone());
+            let addr = serve(api);
+            // Submitted and awaiting confirmation rather than being prepared
+            let confirm_queue = &queues[2];
+            let messages = seed(&db, confirm_queue).await;
+            let spammer = H256::from_low_u64_be(0xbad);
+
+            let listing: Versioned<QueueListing> =
+                reqwest::get(format!("http://{}{}", addr, QUEUES_API_BASE))
+                    .await
+                    .unwrap()
+                    .json()
+                    .await
+                    .unwrap();
+            assert_eq!(listing.total, messages.len());
+
+            let filter = json!({ "sender_address": spammer });
+            let dry_run: Versioned<BulkOperationResult> =
+                post(addr, "/drop", json!({ "filter": filter }))
+                    .await
+                    .json()
+                    .await
+                    .unwrap();
+            let confirmed: Versioned<BulkOperationResult> = post(
+                addr,
+                "/drop",
+                json!({ "filter": filter, "confirmation_token": dry_run.confirmation_token }),
+            )
+            .await
+            .json()
+            .await
+            .unwrap();
+            assert_eq!(confirmed.affected, 2);
+            assert_eq!(confirm_queue.lock().await.len(), messages.len() - 2);
+        })
+        .await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_confirmation_tokens_expire() {
+        test_utils::run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(&HyperlaneDomain::Known(ORIGIN), db);
+            let (api, queues) = setup_test_api(db.clone());
+            let messages = seed(&db, &queues[0]).await;
+            let dry_run = || BulkOperationRequest {
+                filter: QueueFilter::default(),
+                confirmation_token: None,
+            };
+
+            let expired = api.execute(BulkAction::Drop, dry_run()).await.unwrap();
+            tokio::time::advance(CONFIRMATION_TOKEN_TTL).await;
+            // Expired tokens are evicted as new ones are handed out
+            let fresh = api.execute(BulkAction::Drop, dry_run()).await.unwrap();
+            assert_eq!(api.pending_confirmations.lock().await.len(), 1);
+
+            let (status, _) = api
+                .execute(
+                    BulkAction::Drop,
+                    BulkOperationRequest {
+                        confirmation_token: expired.confirmation_token.clone(),
+                        ..dry_run()
+                    },
+                )
+                .await
+                .unwrap_err();
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+            assert_eq!(queues[0].lock().await.len(), messages.len());
+
+            // Unused tokens expire too
+            tokio::time::advance(CONFIRMATION_TOKEN_TTL).await;
+            let (status, _) = api
+                .execute(
+                    BulkAction::Drop,
+                    BulkOperationRequest {
+                        confirmation_token: fresh.confirmation_token.clone(),
+                        ..dry_run()
+                    },
+                )
+                .await
+                .unwrap_err();
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+            assert!(api.pending_confirmations.lock().await.is_empty());
+        })
+        .await;
+    }
+}
@@ -0,0 +1,98 @@
+use axum::{extract::State, http::StatusCode, routing, Json, Router};
+use derive_new::new;
+use hyperlane_base::db::DiskGuard;
+use serde_json::{json, Value};
+
+use crate::origin_startup::OriginHealth;
+
+const READINESS_API_BASE: &str = "/readyz";
+
+/// Readiness probe of the relayer, responding with 503 and the reasons it
+/// isn't ready while its priority origins are starting up or indexing is
+/// paused for the disk of the DB being almost full
+#[derive(new, Clone)]
+pub struct ReadinessApi {
+    origin_health: OriginHealth,
+    disk_guard: Option<DiskGuard>,
+}
+
+async fn readiness(State(api): State<ReadinessApi>) -> (StatusCode, Json<Value>) {
+    let mut reasons = vec![];
+    if !api.origin_health.is_ready() {
+        reasons.push("Priority origins are still starting up".to_owned());
+    }
+    if let Some(reason) = api.disk_guard.and_then(|guard| guard.not_ready_reason()) {
+        reasons.push(reason);
+    }
+    let ready = reasons.is_empty();
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(json!({ "ready": ready, "reasons": reasons })))
+}
+
+impl ReadinessApi {
+    pub fn router(&self) -> Router {
+        Router::new()
+            .route("/", routing::get(readiness))
+            .with_state(self.clone())
+    }
+
+    pub fn get_route(&self) -> (&'static str, Router) {
+        (READINESS_API_BASE, self.router())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use hyperlane_base::db::test_utils::{disk_watcher, MockFreeSpaceProbe};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_not_ready_while_the_disk_is_almost_full() {
+        let health = OriginHealth::default();
+        let probe = MockFreeSpaceProbe::default();
+        let watcher = disk_watcher(&probe);
+        let app = ReadinessApi::new(health.clone(), Some(watcher.guard())).router();
+        let server =
+            axum::Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(app.into_make_service());
+        let addr: SocketAddr = server.local_addr();
+        tokio::spawn(server);
+        let get = move || async move {
+            let response = reqwest::get(format!("http://{addr}/")).await.unwrap();
+            (response.status(), response.json::<Value>().await.unwrap())
+        };
+
+        // Without priority origins, only the disk holds readiness back
+        probe.set(5000);
+        watcher.check();
+        let (status, body) = get().await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, json!({ "ready": true, "reasons": [] }));
+
+        probe.set(50);
+        watcher.check();
+        let (status, body) = get().await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(
+            body,
+            json!({
+                "ready": false,
+                "reasons": [
+                    "Free disk space of the DB (50 bytes) is below the critical threshold (100 bytes), indexing is paused"
+                ]
+            })
+        );
+
+        // Ready again once space is freed up
+        probe.set(5000);
+        watcher.check();
+        let (status, _) = get().await;
+        assert_eq!(status, StatusCode::OK);
+    }
+}
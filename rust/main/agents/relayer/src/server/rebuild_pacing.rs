@@ -0,0 +1,178 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::Duration,
+};
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    routing, Json, Router,
+};
+use derive_new::new;
+use hyperlane_base::pacing::{PacingStatus, RebuildPacer};
+use serde::Deserialize;
+use tracing::info;
+
+const REBUILD_PACING_API_BASE: &str = "/rebuild_pacing";
+
+type Pacers = HashMap<String, RebuildPacer>;
+
+/// Body of a request to change the rebuild budget of an origin
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RaiseBudgetRequest {
+    /// The budget to use for a while, or none to go back to the configured
+    /// one
+    calls_per_minute: Option<u32>,
+    /// How long the raised budget applies for
+    duration_seconds: Option<u64>,
+}
+
+/// Reports the pacing of each origin's merkle tree rebuild, and temporarily
+/// raises its budget for supervised fast rebuilds
+#[derive(new, Clone)]
+pub struct RebuildPacingApi {
+    pacers: Pacers,
+}
+
+async fn rebuild_pacing(State(pacers): State<Pacers>) -> Json<BTreeMap<String, PacingStatus>> {
+    Json(
+        pacers
+            .iter()
+            .map(|(origin, pacer)| (origin.clone(), pacer.status()))
+            .collect(),
+    )
+}
+
+async fn raise_budget(
+    State(pacers): State<Pacers>,
+    Path(origin): Path<String>,
+    Json(request): Json<RaiseBudgetRequest>,
+) -> Result<Json<PacingStatus>, (StatusCode, String)> {
+    let pacer = pacers.get(&origin).ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            format!("No rebuild pacing for origin {origin}"),
+        )
+    })?;
+    match (request.calls_per_minute, request.duration_seconds) {
+        (Some(calls_per_minute), Some(duration)) => {
+            info!(
+                %origin,
+                calls_per_minute,
+                duration,
+                "Raising merkle tree rebuild budget"
+            );
+            pacer.raise_budget(calls_per_minute, Duration::from_secs(duration));
+        }
+        (Some(_), None) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "A raised budget requires durationSeconds".to_owned(),
+            ))
+        }
+        (None, _) => {
+            info!(%origin, "Resetting merkle tree rebuild budget");
+            pacer.reset_budget();
+        }
+    }
+    Ok(Json(pacer.status()))
+}
+
+impl RebuildPacingApi {
+    pub fn router(&self) -> Router {
+        Router::new()
+            .route("/", routing::get(rebuild_pacing))
+            .route("/:origin", routing::post(raise_budget))
+            .with_state(self.pacers.clone())
+    }
+
+    pub fn get_route(&self) -> (&'static str, Router) {
+        (REBUILD_PACING_API_BASE, self.router())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use hyperlane_base::pacing::RebuildPacingConf;
+    use serde_json::json;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_raise_rebuild_budget() {
+        let pacer = RebuildPacer::new(
+            RebuildPacingConf {
+                calls_per_minute: Some(6),
+                chunk_sleep: Duration::ZERO,
+            },
+            "merkle_tree_hook",
+        );
+        pacer.set_remaining_chunks(Some(60));
+        let app =
+            RebuildPacingApi::new(HashMap::from([("test1".to_owned(), pacer.clone())])).router();
+        let server =
+            axum::Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(app.into_make_service());
+        let addr: SocketAddr = server.local_addr();
+        tokio::spawn(server);
+        let client = reqwest::Client::new();
+
+        let body = reqwest::get(format!("http://{addr}/"))
+            .await
+            .unwrap()
+            .json::<serde_json::Value>()
+            .await
+            .unwrap();
+        assert_eq!(
+            body,
+            json!({
+                "test1": {
+                    "callsPerMinute": 6,
+                    "configuredCallsPerMinute": 6,
+                    "raisedForSeconds": null,
+                    "remainingChunks": 60,
+                    "etaSeconds": 600,
+                }
+            })
+        );
+
+        let response = client
+            .post(format!("http://{addr}/test1"))
+            .json(&json!({ "callsPerMinute": 60, "durationSeconds": 3600 }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.json::<serde_json::Value>().await.unwrap();
+        assert_eq!(body["callsPerMinute"], 60);
+        assert_eq!(body["etaSeconds"], 60);
+        assert_eq!(pacer.calls_per_minute(), Some(60));
+
+        let response = client
+            .post(format!("http://{addr}/test1"))
+            .json(&json!({ "callsPerMinute": 60 }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = client
+            .post(format!("http://{addr}/test1"))
+            .json(&json!({ "callsPerMinute": null }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(pacer.calls_per_minute(), Some(6));
+
+        let response = client
+            .post(format!("http://{addr}/test2"))
+            .json(&json!({ "callsPerMinute": null }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Path, State},
+    http::{header::AUTHORIZATION, HeaderMap, StatusCode},
+    routing, Json, Router,
+};
+use derive_new::new;
+use serde::Deserialize;
+
+use crate::reindex::{OriginReindexer, ReindexError, ReindexSummary};
+
+const REINDEX_API_BASE: &str = "/chains";
+
+/// Body of a request to reindex a block range of an origin
+#[derive(Clone, Debug, Deserialize)]
+pub struct ReindexRequest {
+    /// First block of the range
+    from_block: u32,
+    /// Last block of the range, inclusive
+    to_block: u32,
+}
+
+/// Re-fetches the dispatches and gas payments of a block range of an origin,
+/// on behalf of an operator authenticated with the admin token
+#[derive(new, Clone)]
+pub struct ReindexApi {
+    /// Reindexers by origin chain name
+    reindexers: HashMap<String, OriginReindexer>,
+    admin_token: String,
+}
+
+fn status_code(err: &ReindexError) -> StatusCode {
+    match err {
+        ReindexError::InvalidRange { .. } => StatusCode::BAD_REQUEST,
+        ReindexError::AlreadyRunning => StatusCode::CONFLICT,
+        ReindexError::Chain(_) => StatusCode::BAD_GATEWAY,
+        ReindexError::Db(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+async fn reindex(
+    State(api): State<ReindexApi>,
+    Path(chain): Path<String>,
+    headers: HeaderMap,
+    Json(request): Json<ReindexRequest>,
+) -> Result<Json<ReindexSummary>, (StatusCode, String)> {
+    let token = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    if token != Some(api.admin_token.as_str()) {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            "Missing or invalid admin token".to_owned(),
+        ));
+    }
+    let Some(reindexer) = api.reindexers.get(&chain) else {
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("No origin chain {chain} indexed by block range"),
+        ));
+    };
+    let summary = reindexer
+        .reindex(request.from_block..=request.to_block)
+        .await
+        .map_err(|err| (status_code(&err), err.to_string()))?;
+    Ok(Json(summary))
+}
+
+impl ReindexApi {
+    pub fn router(&self) -> Router {
+        Router::new()
+            .route("/:chain/reindex", routing::post(reindex))
+            .with_state(self.clone())
+    }
+
+    pub fn get_route(&self) -> (&'static str, Router) {
+        (REINDEX_API_BASE, self.router())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use hyperlane_base::db::{test_utils, HyperlaneDb, HyperlaneRocksDB};
+    use hyperlane_core::HyperlaneDomain;
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::reindex::test::{dispatch, reindexer, seed_missing_one};
+
+    const ADMIN_TOKEN: &str = "admin-token";
+
+    fn setup_test_server(db: &HyperlaneRocksDB) -> SocketAddr {
+        let reindexers = HashMap::from([("test".to_owned(), reindexer(db, None))]);
+        let app = ReindexApi::new(reindexers, ADMIN_TOKEN.to_owned()).router();
+        let server =
+            axum::Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(app.into_make_service());
+        let addr = server.local_addr();
+        tokio::spawn(server);
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_reindex_reports_added_records() {
+        test_utils::run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(&HyperlaneDomain::new_test_domain("test"), db);
+            seed_missing_one(&db);
+            let addr = setup_test_server(&db);
+            let client = reqwest::Client::new();
+            let body = json!({ "from_block": 0, "to_block": 20 });
+
+            let response = client
+                .post(format!("http://{addr}/test/reindex"))
+                .json(&body)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+            let response = client
+                .post(format!("http://{addr}/unknown/reindex"))
+                .bearer_auth(ADMIN_TOKEN)
+                .json(&body)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+            let response = client
+                .post(format!("http://{addr}/test/reindex"))
+                .bearer_auth(ADMIN_TOKEN)
+                .json(&body)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let summary: Value = response.json().await.unwrap();
+            assert_eq!(summary["messages_added"], json!(1));
+            assert_eq!(summary["messages_unchanged"], json!(2));
+            assert_eq!(summary["message_conflicts"], json!([]));
+            assert_eq!(summary["tree_diverged"], json!(false));
+            assert_eq!(
+                db.retrieve_message_id_by_nonce(&1).unwrap(),
+                Some(dispatch(1).0.inner().id())
+            );
+        })
+        .await;
+    }
+}
@@ -0,0 +1,155 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    routing, Json, Router,
+};
+use derive_new::new;
+use hyperlane_core::H256;
+use serde::Serialize;
+
+use crate::msg::round_trip::{RoundTripGuard, RoundTripRecord};
+
+const ROUND_TRIPS_API_BASE: &str = "/round_trips";
+
+type Guards = Vec<Arc<RoundTripGuard>>;
+
+/// A tuple which crossed its route pair too many times, whose messages are
+/// parked until it's cleared
+#[derive(Clone, Debug, Serialize)]
+pub struct TrippedTuple {
+    pub tuple_id: H256,
+    /// Ids of the two domains of the route pair
+    pub domains: [u32; 2],
+    #[serde(flatten)]
+    pub record: RoundTripRecord,
+}
+
+/// Lists and clears the tuples tripped on the route pairs whose round trips
+/// are limited
+#[derive(new, Clone)]
+pub struct RoundTripsApi {
+    guards: Guards,
+}
+
+async fn tripped_tuples(State(guards): State<Guards>) -> Json<Vec<TrippedTuple>> {
+    Json(
+        guards
+            .iter()
+            .flat_map(|guard| {
+                guard.tripped().into_iter().map(|record| TrippedTuple {
+                    tuple_id: record.tuple.id(),
+                    domains: guard.domains(),
+                    record,
+                })
+            })
+            .collect(),
+    )
+}
+
+async fn clear_tuple(
+    State(guards): State<Guards>,
+    Path(tuple_id): Path<H256>,
+) -> Result<Json<TrippedTuple>, (StatusCode, String)> {
+    guards
+        .iter()
+        .find_map(|guard| {
+            guard.clear(&tuple_id).map(|record| TrippedTuple {
+                tuple_id,
+                domains: guard.domains(),
+                record,
+            })
+        })
+        .map(Json)
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                format!("No tuple {tuple_id:?} tracked"),
+            )
+        })
+}
+
+impl RoundTripsApi {
+    pub fn router(&self) -> Router {
+        Router::new()
+            .route("/", routing::get(tripped_tuples))
+            .route("/:tuple_id", routing::delete(clear_tuple))
+            .with_state(self.guards.clone())
+    }
+
+    pub fn get_route(&self) -> (&'static str, Router) {
+        (ROUND_TRIPS_API_BASE, self.router())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use hyperlane_base::db::{test_utils, HyperlaneRocksDB};
+    use hyperlane_core::HyperlaneDomain;
+    use serde_json::Value;
+
+    use super::*;
+    use crate::msg::round_trip::{
+        test::{bounce, round_trip_conf},
+        RoundTrip, RoundTripTuple,
+    };
+
+    #[tokio::test]
+    async fn test_tripped_tuples_are_listed_and_cleared() {
+        test_utils::run_test_db(|db| async move {
+            let domain = HyperlaneDomain::new_test_domain("test1");
+            let guard = Arc::new(RoundTripGuard::new(
+                round_trip_conf(1),
+                100,
+                HyperlaneRocksDB::new(&domain, db),
+            ));
+            guard.observe(&bounce(0, false, b"ping"), 0);
+            guard.observe(&bounce(0, true, b"ping"), 1);
+            let tuple_id = RoundTripTuple::of(&bounce(0, false, b"ping")).id();
+
+            let app = RoundTripsApi::new(vec![guard.clone()]).router();
+            let server =
+                axum::Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(app.into_make_service());
+            let addr: SocketAddr = server.local_addr();
+            tokio::spawn(server);
+            let client = reqwest::Client::new();
+
+            let body = reqwest::get(format!("http://{addr}/"))
+                .await
+                .unwrap()
+                .json::<Value>()
+                .await
+                .unwrap();
+            let tripped = body.as_array().unwrap();
+            assert_eq!(tripped.len(), 1);
+            assert_eq!(tripped[0]["tuple_id"], format!("{tuple_id:?}"));
+            assert_eq!(tripped[0]["domains"], serde_json::json!([1, 2]));
+            assert_eq!(tripped[0]["tripped_at"], 1);
+            assert_eq!(tripped[0]["crossings"].as_array().unwrap().len(), 2);
+
+            let response = client
+                .delete(format!("http://{addr}/{tuple_id:?}"))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            assert!(guard.tripped().is_empty());
+            // The tuple's messages are relayed again
+            assert_eq!(
+                guard.observe(&bounce(1, false, b"ping"), 2),
+                RoundTrip::Allowed
+            );
+
+            let response = client
+                .delete(format!("http://{addr}/{:?}", H256::zero()))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        })
+        .await;
+    }
+}
@@ -0,0 +1,103 @@
+use std::sync::Arc;
+
+use axum::{extract::State, routing, Json, Router};
+use derive_new::new;
+
+use crate::msg::shadow::{ShadowDivergences, ShadowReport};
+
+const SHADOW_CONFIG_API_BASE: &str = "/shadow_config";
+
+/// Reports where the candidate configs shadowing routes diverged from the
+/// active one since shadowing began
+#[derive(new, Clone)]
+pub struct ShadowConfigApi {
+    divergences: Arc<ShadowDivergences>,
+}
+
+async fn shadow_report(State(divergences): State<Arc<ShadowDivergences>>) -> Json<ShadowReport> {
+    Json(divergences.report())
+}
+
+impl ShadowConfigApi {
+    pub fn router(&self) -> Router {
+        Router::new()
+            .route("/", routing::get(shadow_report))
+            .with_state(self.divergences.clone())
+    }
+
+    pub fn get_route(&self) -> (&'static str, Router) {
+        (SHADOW_CONFIG_API_BASE, self.router())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use hyperlane_base::{
+        db::{test_utils, HyperlaneRocksDB},
+        CheckpointPreference,
+    };
+    use hyperlane_core::{test_utils::dummy_domain, HyperlaneMessage};
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        msg::{
+            decision::{decide, DecisionInputs},
+            shadow::{test::shadow_divergences, ShadowEvaluator},
+        },
+        settings::ShadowRouteConf,
+    };
+
+    #[tokio::test]
+    async fn test_shadow_report() {
+        test_utils::run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(&dummy_domain(0, "dummy_origin_domain"), db);
+            let divergences = shadow_divergences();
+            let routes = [ShadowRouteConf {
+                name: "everything".to_owned(),
+                ..Default::default()
+            }];
+            let shadow = ShadowEvaluator::new(&routes, &db, divergences.clone());
+            let inputs = DecisionInputs::default();
+            shadow.compare(
+                &HyperlaneMessage::default(),
+                &inputs,
+                &decide(&inputs).unwrap(),
+                None,
+                CheckpointPreference::Latest,
+            );
+
+            let app = ShadowConfigApi::new(divergences.clone()).router();
+            let server =
+                axum::Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(app.into_make_service());
+            let addr: SocketAddr = server.local_addr();
+            tokio::spawn(server);
+
+            let body = reqwest::get(format!("http://{addr}/"))
+                .await
+                .unwrap()
+                .json::<serde_json::Value>()
+                .await
+                .unwrap();
+            assert_eq!(
+                body,
+                json!({
+                    "since": divergences.report().since,
+                    "routes": {
+                        "everything": {
+                            "evaluated": 1,
+                            "would_park": 0,
+                            "would_deliver": 0,
+                            "different_outcome": 0,
+                            "different_quorum": 0,
+                            "undetermined_quorum": 0,
+                        }
+                    }
+                })
+            );
+        })
+        .await;
+    }
+}
@@ -0,0 +1,109 @@
+use axum::{extract::State, routing, Json, Router};
+use derive_new::new;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+use tokio::sync::RwLock;
+
+use super::v1::{TreeStatus, TreeStatusReport, Versioned};
+use crate::merkle_tree::builder::MerkleTreeBuilder;
+
+const TREE_STATUS_API_BASE: &str = "/merkle_tree_status";
+
+type ProverSyncs = HashMap<String, Arc<RwLock<MerkleTreeBuilder>>>;
+
+/// Reports the availability of each origin's merkle tree
+#[derive(new, Clone)]
+pub struct TreeStatusApi {
+    prover_syncs: ProverSyncs,
+    /// Delivery start policy by origin, for origins that have one
+    policies: HashMap<String, String>,
+}
+
+async fn tree_status(
+    State((prover_syncs, policies)): State<(ProverSyncs, HashMap<String, String>)>,
+) -> Json<Versioned<TreeStatusReport>> {
+    let mut trees = BTreeMap::new();
+    for (origin, prover_sync) in prover_syncs {
+        let builder = prover_sync.read().await;
+        let availability = builder.availability().clone();
+        let hashes = builder.hash_counts();
+        drop(builder);
+        let policy = policies.get(&origin).cloned();
+        trees.insert(
+            origin,
+            TreeStatus {
+                availability: availability.into(),
+                policy,
+                hashes: hashes.map(|hashes| {
+                    hashes
+                        .into_iter()
+                        .map(|(operation, count)| (operation.to_owned(), count.into()))
+                        .collect()
+                }),
+            },
+        );
+    }
+    Json(Versioned::new(TreeStatusReport { trees }))
+}
+
+impl TreeStatusApi {
+    pub fn router(&self) -> Router {
+        Router::new()
+            .route("/", routing::get(tree_status))
+            .with_state((self.prover_syncs.clone(), self.policies.clone()))
+    }
+
+    pub fn get_route(&self) -> (&'static str, Router) {
+        (TREE_STATUS_API_BASE, self.router())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle_tree::availability::TreeAvailability;
+    use axum::http::StatusCode;
+    use std::net::SocketAddr;
+
+    #[tokio::test]
+    async fn test_tree_status() {
+        let mut builder = MerkleTreeBuilder::new();
+        builder.set_availability(TreeAvailability::Rebuilding { progress: 7 }, "test");
+        let prover_syncs = HashMap::from([
+            ("test1".to_owned(), Arc::new(RwLock::new(builder))),
+            (
+                "test2".to_owned(),
+                Arc::new(RwLock::new(MerkleTreeBuilder::new())),
+            ),
+        ]);
+        let policies = HashMap::from([(
+            "test2".to_owned(),
+            "tree-complete, delivery-from-block-100".to_owned(),
+        )]);
+
+        let app = TreeStatusApi::new(prover_syncs, policies).router();
+        let server =
+            axum::Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(app.into_make_service());
+        let addr: SocketAddr = server.local_addr();
+        tokio::spawn(server);
+
+        let response = reqwest::get(format!("http://{addr}/")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.json::<serde_json::Value>().await.unwrap(),
+            serde_json::json!({
+                "schema_version": 1,
+                "trees": {
+                    "test1": { "state": "rebuilding", "progress": 7 },
+                    "test2": {
+                        "state": "rebuilding",
+                        "progress": 0,
+                        "policy": "tree-complete, delivery-from-block-100"
+                    },
+                },
+            })
+        );
+    }
+}
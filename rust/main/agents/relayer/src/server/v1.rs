@@ -0,0 +1,448 @@
+//! Version 1 of the responses of the admin endpoints, which external tooling
+//! consumes. Fields may be added to these types, but renaming or removing
+//! one, or changing what it means, takes a new version. The routes serving
+//! these types are also served under [`PREFIX`], where they keep serving
+//! them once the unprefixed routes move on to a later version.
+
+use std::{collections::BTreeMap, ops::Deref};
+
+use axum::Router;
+use hyperlane_core::{
+    accumulator::instrumentation::HashCount,
+    utils::{fmt_address_for_domain, fmt_address_for_domain_id},
+    QueueOperation, H256,
+};
+use schemars::{schema::RootSchema, schema_for, JsonSchema};
+use serde::{Deserialize, Serialize};
+
+use crate::{merkle_tree::availability::TreeAvailability, origin_startup::OriginState};
+
+/// Version of the schema of the types in this module
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Prefix of the routes always serving version 1
+pub const PREFIX: &str = "/v1";
+
+/// A response tagged with the version of its schema
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Versioned<T> {
+    /// Always [`SCHEMA_VERSION`]
+    pub schema_version: u32,
+    #[serde(flatten)]
+    pub body: T,
+}
+
+impl<T> Versioned<T> {
+    pub fn new(body: T) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            body,
+        }
+    }
+}
+
+impl<T> Deref for Versioned<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.body
+    }
+}
+
+/// Status of a message waiting in a queue
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct MessageStatus {
+    #[schemars(with = "String")]
+    pub id: H256,
+    pub origin_domain: u32,
+    pub destination_domain: u32,
+    pub nonce: u32,
+    /// Sender, collapsed to 20 bytes if it's a padded EVM address
+    pub sender_address: String,
+    /// Recipient, collapsed to 20 bytes if it's a padded EVM address
+    pub recipient_address: String,
+    /// Why the message is waiting, e.g. `Retry(GasPaymentRequirementNotMet)`
+    pub status: String,
+    /// Stages of the pipeline the message took longer than its latency budget
+    /// in, e.g. `metadata`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub latency_budget_breaches: Vec<String>,
+}
+
+impl From<&QueueOperation> for MessageStatus {
+    fn from(op: &QueueOperation) -> Self {
+        Self {
+            id: op.id(),
+            origin_domain: op.origin_domain_id(),
+            destination_domain: op.destination_domain().id(),
+            nonce: op.priority(),
+            sender_address: fmt_address_for_domain_id(op.sender_address(), op.origin_domain_id()),
+            recipient_address: fmt_address_for_domain(
+                op.recipient_address(),
+                Some(op.destination_domain()),
+            ),
+            status: op.status().to_string(),
+            latency_budget_breaches: op.latency_budget_breaches(),
+        }
+    }
+}
+
+/// The messages in the queue of a destination, in the queue's order
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct MessageList {
+    pub operations: Vec<MessageStatus>,
+}
+
+/// A page of the queued messages matching a filter
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct QueueListing {
+    /// Number of messages matching the filter, across all pages
+    pub total: usize,
+    pub offset: usize,
+    pub operations: Vec<MessageStatus>,
+}
+
+/// The messages a bulk queue operation applied, or would apply, to
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct BulkOperationResult {
+    pub dry_run: bool,
+    pub affected: usize,
+    #[schemars(with = "Vec<String>")]
+    pub operations: Vec<H256>,
+    /// Set on dry-runs of destructive operations; must be echoed back to
+    /// execute them
+    pub confirmation_token: Option<String>,
+}
+
+/// Whether the relayer is ready, along with the state of each origin's
+/// pipeline
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct OriginHealthReport {
+    pub ready: bool,
+    pub origins: BTreeMap<String, OriginStatus>,
+}
+
+/// State of an origin's pipeline
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "state", rename_all = "camelCase")]
+pub enum OriginStatus {
+    Initializing,
+    Ready,
+    Failed { error: String },
+}
+
+impl From<OriginState> for OriginStatus {
+    fn from(state: OriginState) -> Self {
+        match state {
+            OriginState::Initializing => Self::Initializing,
+            OriginState::Ready => Self::Ready,
+            OriginState::Failed { error } => Self::Failed { error },
+        }
+    }
+}
+
+/// Status of each origin's merkle tree
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct TreeStatusReport {
+    pub trees: BTreeMap<String, TreeStatus>,
+}
+
+/// The availability of an origin's merkle tree, along with the policy of
+/// origins whose messages are only delivered from some block on, and the
+/// hashes its operations computed if they're counted
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct TreeStatus {
+    #[serde(flatten)]
+    pub availability: TreeState,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hashes: Option<BTreeMap<String, TreeHashes>>,
+}
+
+/// Whether an origin's merkle tree can be used to generate proofs
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "state", rename_all = "camelCase")]
+pub enum TreeState {
+    Ready,
+    Rebuilding { progress: u32 },
+    Diverged { reason: String },
+    Full { capacity: u64 },
+}
+
+impl From<TreeAvailability> for TreeState {
+    fn from(availability: TreeAvailability) -> Self {
+        match availability {
+            TreeAvailability::Ready => Self::Ready,
+            TreeAvailability::Rebuilding { progress } => Self::Rebuilding { progress },
+            TreeAvailability::Diverged { reason } => Self::Diverged { reason },
+            TreeAvailability::Full { capacity } => Self::Full { capacity },
+        }
+    }
+}
+
+/// Hashes computed by an operation of a merkle tree, and bytes hashed
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct TreeHashes {
+    pub hashes: u64,
+    pub bytes: u64,
+}
+
+impl From<HashCount> for TreeHashes {
+    fn from(count: HashCount) -> Self {
+        Self {
+            hashes: count.hashes,
+            bytes: count.bytes,
+        }
+    }
+}
+
+/// The JSON schemas of the version 1 responses, by type name
+pub fn schemas() -> BTreeMap<&'static str, RootSchema> {
+    BTreeMap::from([
+        ("MessageList", schema_for!(Versioned<MessageList>)),
+        ("QueueListing", schema_for!(Versioned<QueueListing>)),
+        (
+            "BulkOperationResult",
+            schema_for!(Versioned<BulkOperationResult>),
+        ),
+        (
+            "OriginHealthReport",
+            schema_for!(Versioned<OriginHealthReport>),
+        ),
+        ("TreeStatusReport", schema_for!(Versioned<TreeStatusReport>)),
+    ])
+}
+
+/// Serve `route` under [`PREFIX`] too, by nesting it in `v1_router`
+pub fn with_v1_route(
+    (path, router): (&'static str, Router),
+    v1_router: &mut Router,
+) -> (&'static str, Router) {
+    *v1_router = std::mem::take(v1_router).nest(path, router.clone());
+    (path, router)
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::Value;
+
+    use super::*;
+
+    fn message_status(nonce: u32) -> MessageStatus {
+        MessageStatus {
+            id: H256::from_low_u64_be(nonce as u64 + 1),
+            origin_domain: 1,
+            destination_domain: 2,
+            nonce,
+            sender_address: "0x0000000000000000000000000000000000000bad".to_owned(),
+            recipient_address: "0x000000000000000000000000000000000000000f".to_owned(),
+            status: "Retry(GasPaymentRequirementNotMet)".to_owned(),
+            latency_budget_breaches: vec![],
+        }
+    }
+
+    fn snapshot<T: Serialize>(body: T) -> String {
+        serde_json::to_string_pretty(&Versioned::new(body)).unwrap()
+    }
+
+    #[test]
+    fn test_message_list_snapshot() {
+        let list = MessageList {
+            operations: vec![message_status(3)],
+        };
+        assert_eq!(
+            snapshot(list),
+            r#"{
+  "schema_version": 1,
+  "operations": [
+    {
+      "id": "0x0000000000000000000000000000000000000000000000000000000000000004",
+      "origin_domain": 1,
+      "destination_domain": 2,
+      "nonce": 3,
+      "sender_address": "0x0000000000000000000000000000000000000bad",
+      "recipient_address": "0x000000000000000000000000000000000000000f",
+      "status": "Retry(GasPaymentRequirementNotMet)"
+    }
+  ]
+}"#
+        );
+    }
+
+    #[test]
+    fn test_queue_listing_snapshot() {
+        let listing = QueueListing {
+            total: 2,
+            offset: 1,
+            operations: vec![message_status(0)],
+        };
+        assert_eq!(
+            snapshot(listing),
+            r#"{
+  "schema_version": 1,
+  "total": 2,
+  "offset": 1,
+  "operations": [
+    {
+      "id": "0x0000000000000000000000000000000000000000000000000000000000000001",
+      "origin_domain": 1,
+      "destination_domain": 2,
+      "nonce": 0,
+      "sender_address": "0x0000000000000000000000000000000000000bad",
+      "recipient_address": "0x000000000000000000000000000000000000000f",
+      "status": "Retry(GasPaymentRequirementNotMet)"
+    }
+  ]
+}"#
+        );
+    }
+
+    #[test]
+    fn test_bulk_operation_result_snapshot() {
+        let result = BulkOperationResult {
+            dry_run: true,
+            affected: 1,
+            operations: vec![H256::from_low_u64_be(1)],
+            confirmation_token: Some("00000000000000ff".to_owned()),
+        };
+        assert_eq!(
+            snapshot(result),
+            r#"{
+  "schema_version": 1,
+  "dry_run": true,
+  "affected": 1,
+  "operations": [
+    "0x0000000000000000000000000000000000000000000000000000000000000001"
+  ],
+  "confirmation_token": "00000000000000ff"
+}"#
+        );
+    }
+
+    #[test]
+    fn test_origin_health_report_snapshot() {
+        let report = OriginHealthReport {
+            ready: true,
+            origins: BTreeMap::from([
+                ("ethereum".to_owned(), OriginStatus::Ready),
+                (
+                    "polygon".to_owned(),
+                    OriginStatus::Failed {
+                        error: "Error building cursor for origin".to_owned(),
+                    },
+                ),
+                ("solana".to_owned(), OriginStatus::Initializing),
+            ]),
+        };
+        assert_eq!(
+            snapshot(report),
+            r#"{
+  "schema_version": 1,
+  "ready": true,
+  "origins": {
+    "ethereum": {
+      "state": "ready"
+    },
+    "polygon": {
+      "state": "failed",
+      "error": "Error building cursor for origin"
+    },
+    "solana": {
+      "state": "initializing"
+    }
+  }
+}"#
+        );
+    }
+
+    #[test]
+    fn test_tree_status_report_snapshot() {
+        let report = TreeStatusReport {
+            trees: BTreeMap::from([
+                (
+                    "ethereum".to_owned(),
+                    TreeStatus {
+                        availability: TreeState::Ready,
+                        policy: None,
+                        hashes: Some(BTreeMap::from([(
+                            "ingest".to_owned(),
+                            TreeHashes {
+                                hashes: 32,
+                                bytes: 2048,
+                            },
+                        )])),
+                    },
+                ),
+                (
+                    "polygon".to_owned(),
+                    TreeStatus {
+                        availability: TreeState::Rebuilding { progress: 7 },
+                        policy: Some("tree-complete, delivery-from-block-100".to_owned()),
+                        hashes: None,
+                    },
+                ),
+                (
+                    "solana".to_owned(),
+                    TreeStatus {
+                        availability: TreeState::Diverged {
+                            reason: "root mismatch".to_owned(),
+                        },
+                        policy: None,
+                        hashes: None,
+                    },
+                ),
+                (
+                    "test1".to_owned(),
+                    TreeStatus {
+                        availability: TreeState::Full { capacity: 1 << 32 },
+                        policy: None,
+                        hashes: None,
+                    },
+                ),
+            ]),
+        };
+        assert_eq!(
+            snapshot(report),
+            r#"{
+  "schema_version": 1,
+  "trees": {
+    "ethereum": {
+      "state": "ready",
+      "hashes": {
+        "ingest": {
+          "hashes": 32,
+          "bytes": 2048
+        }
+      }
+    },
+    "polygon": {
+      "state": "rebuilding",
+      "progress": 7,
+      "policy": "tree-complete, delivery-from-block-100"
+    },
+    "solana": {
+      "state": "diverged",
+      "reason": "root mismatch"
+    },
+    "test1": {
+      "state": "full",
+      "capacity": 4294967296
+    }
+  }
+}"#
+        );
+    }
+
+    #[test]
+    fn test_schemas_require_schema_version() {
+        for (name, schema) in schemas() {
+            let schema = serde_json::to_value(schema).unwrap();
+            let required = schema["required"].as_array().unwrap();
+            assert!(
+                required.contains(&Value::from("schema_version")),
+                "{name} doesn't require a schema version"
+            );
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use std::collections::{BTreeMap, HashMap};
+
+use axum::{extract::State, routing, Json, Router};
+use derive_new::new;
+use hyperlane_base::ValidatorReputations;
+use hyperlane_core::H160;
+use serde::Serialize;
+
+const VALIDATOR_REPUTATIONS_API_BASE: &str = "/validator_reputations";
+
+type Reputations = HashMap<String, ValidatorReputations>;
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ValidatorReputationSummary {
+    validator: H160,
+    score: f64,
+    successes: f64,
+    failures: f64,
+    verification_failures: f64,
+    latency_ewma_ms: f64,
+}
+
+/// Reports the reputation of each origin's validators, best first
+#[derive(new, Clone)]
+pub struct ValidatorReputationsApi {
+    reputations: Reputations,
+}
+
+async fn validator_reputations(
+    State(reputations): State<Reputations>,
+) -> Json<BTreeMap<String, Vec<ValidatorReputationSummary>>> {
+    let summaries = reputations
+        .into_iter()
+        .map(|(origin, reputations)| {
+            let standings = reputations
+                .standings()
+                .into_iter()
+                .map(|standing| ValidatorReputationSummary {
+                    validator: standing.validator,
+                    score: standing.score,
+                    successes: standing.reputation.successes,
+                    failures: standing.reputation.failures,
+                    verification_failures: standing.reputation.verification_failures,
+                    latency_ewma_ms: standing.reputation.latency_ewma_ms,
+                })
+                .collect();
+            (origin, standings)
+        })
+        .collect();
+    Json(summaries)
+}
+
+impl ValidatorReputationsApi {
+    pub fn router(&self) -> Router {
+        Router::new()
+            .route("/", routing::get(validator_reputations))
+            .with_state(self.reputations.clone())
+    }
+
+    pub fn get_route(&self) -> (&'static str, Router) {
+        (VALIDATOR_REPUTATIONS_API_BASE, self.router())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::SocketAddr, sync::Arc, time::Duration};
+
+    use axum::http::StatusCode;
+    use hyperlane_base::{
+        db::{test_utils, HyperlaneRocksDB},
+        CheckpointFetchOutcome, CoreMetrics,
+    };
+    use hyperlane_core::HyperlaneDomain;
+    use prometheus::Registry;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_validator_reputations() {
+        test_utils::run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(&HyperlaneDomain::new_test_domain("test1"), db);
+            let metrics = Arc::new(CoreMetrics::new("test", 0, Registry::new()).unwrap());
+            let reputations = ValidatorReputations::new(db, metrics);
+            let (healthy, faulty) = (H160::from_low_u64_be(1), H160::from_low_u64_be(2));
+            reputations.record(&healthy, CheckpointFetchOutcome::Verified, Duration::ZERO);
+            reputations.record(
+                &faulty,
+                CheckpointFetchOutcome::VerificationFailed,
+                Duration::ZERO,
+            );
+
+            let app =
+                ValidatorReputationsApi::new(HashMap::from([("test1".to_owned(), reputations)]))
+                    .router();
+            let server =
+                axum::Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(app.into_make_service());
+            let addr: SocketAddr = server.local_addr();
+            tokio::spawn(server);
+
+            let response = reqwest::get(format!("http://{addr}/")).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = response.json::<serde_json::Value>().await.unwrap();
+            let standings = body["test1"].as_array().unwrap();
+            assert_eq!(standings.len(), 2);
+            assert_eq!(standings[0]["validator"], serde_json::json!(healthy));
+            assert_eq!(standings[0]["successes"], 1.0);
+            assert_eq!(standings[1]["validator"], serde_json::json!(faulty));
+            assert_eq!(standings[1]["verification_failures"], 1.0);
+        })
+        .await;
+    }
+}
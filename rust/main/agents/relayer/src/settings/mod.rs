@@ -4,20 +4,23 @@
 //! and validations it defines are not applied here, we should mirror them.
 //! ANY CHANGES HERE NEED TO BE REFLECTED IN THE TYPESCRIPT SDK.
 
-use std::{collections::HashSet, path::PathBuf};
+use std::{collections::HashSet, path::PathBuf, time::Duration};
 
 use convert_case::Case;
 use derive_more::{AsMut, AsRef, Deref, DerefMut};
 use ethers::utils::hex;
 use eyre::{eyre, Context};
 use hyperlane_base::{
+    db::{DiskSpaceConf, InstanceLockConf, MessageCompression},
     impl_loadable_from_settings,
+    pacing::RebuildPacingConf,
     settings::{
         parser::{recase_json_value, RawAgentConf, ValueParser},
-        Settings,
+        DurationUnit, Settings,
     },
+    CheckpointPreference,
 };
-use hyperlane_core::{cfg_unwrap_all, config::*, HyperlaneDomain, U256};
+use hyperlane_core::{cfg_unwrap_all, config::*, Address, HyperlaneDomain, H160, U256};
 use itertools::Itertools;
 use serde::Deserialize;
 use serde_json::Value;
@@ -25,6 +28,7 @@ use serde_json::Value;
 use crate::settings::matching_list::MatchingList;
 
 pub mod matching_list;
+pub mod snapshot;
 
 /// Settings for `Relayer`
 #[derive(Debug, AsRef, AsMut, Deref, DerefMut)]
@@ -37,6 +41,8 @@ pub struct RelayerSettings {
 
     /// Database path
     pub db: PathBuf,
+    /// How message values are compressed in the database
+    pub message_compression: MessageCompression,
     /// The chain to relay messages from
     pub origin_chains: HashSet<HyperlaneDomain>,
     /// Chains to relay messages to
@@ -61,6 +67,401 @@ pub struct RelayerSettings {
     pub allow_local_checkpoint_syncers: bool,
     /// App contexts used for metrics.
     pub metric_app_contexts: Vec<(MatchingList, String)>,
+    /// Which quorum checkpoint to deliver messages against, by route. The
+    /// first matching list a message matches decides, otherwise the latest
+    /// checkpoint is preferred.
+    pub checkpoint_preferences: Vec<(MatchingList, CheckpointPreference)>,
+    /// How messages of bursting senders and routes are deprioritized
+    pub message_throttle: MessageThrottleConf,
+    /// When submissions to a destination are paused for reverting
+    pub circuit_breaker: CircuitBreakerConf,
+    /// Highest gas prices messages are submitted at, by route
+    pub gas_price_ceiling: GasPriceCeilingConf,
+    /// Candidate policies evaluated alongside the active ones, by route,
+    /// without acting on them
+    pub shadow_config: Vec<ShadowRouteConf>,
+    /// How offchain lookups of CCIP read ISMs are performed
+    pub ccip_read: CcipReadConf,
+    /// Fraction of an origin merkle tree's capacity past which to warn that
+    /// it's filling up
+    pub merkle_tree_capacity_warning: f64,
+    /// How long to wait for each origin to freeze its merkle tree for the
+    /// shutdown snapshot
+    pub merkle_tree_shutdown_timeout: Duration,
+    /// How backfilling the merkle tree insertions of an origin, e.g. for a
+    /// full-history rebuild, is paced to stay within its RPC budget
+    pub rebuild_pacing: RebuildPacingConf,
+    /// Free disk space of the database below which to warn, and below which
+    /// to pause indexing until space is freed up
+    pub disk_space: DiskSpaceConf,
+    /// How the relayer keeps other instances from using its database and
+    /// signers at the same time
+    pub instance_lock: InstanceLockConf,
+    /// How long the nonces of the destination signers are observed at
+    /// startup, before anything is submitted, for evidence of another process
+    /// using them. Zero disables the observation.
+    pub signer_observation: Duration,
+    /// If true, the relayer waits for the heartbeat of another instance using
+    /// its database to expire rather than refusing to start
+    pub takeover: bool,
+    /// How the proofs of messages whose quorum is within reach are prepared
+    /// ahead of the last signature
+    pub quorum_prefetch: QuorumPrefetchConf,
+    /// How the quorum checkpoints of multisig metadata are reused across
+    /// the messages they cover
+    pub checkpoint_cache: CheckpointCacheConf,
+    /// How many metadata builds run at once, overall and by route
+    pub metadata_pool: MetadataPoolConf,
+    /// How long messages may spend in each stage of the pipeline, by route
+    pub latency_budgets: LatencyBudgetConf,
+    /// Routes whose older undelivered messages are superseded by newer ones
+    pub supersession_routes: Vec<SupersessionRouteConf>,
+    /// Route pairs on which messages bouncing between the same apps are
+    /// parked once they crossed too many times
+    pub round_trips: RoundTripConf,
+    /// How many corrupted db records the scans of an origin skip before
+    /// aborting, as the corruption is then unlikely to be isolated
+    pub corruption_tolerance: u32,
+    /// If true, the admin server streams the lifecycle events of messages
+    pub expose_message_events: bool,
+    /// If true, the relayer is ready once its priority origins are up, the
+    /// others being started in the background
+    pub lazy_origin_startup: bool,
+    /// Origins to start before the others, in order, after the ones with
+    /// undelivered messages
+    pub priority_origins: Vec<HyperlaneDomain>,
+    /// Bearer token operators authenticate admin requests with, e.g. to
+    /// cancel messages. Such requests are refused if unset.
+    pub admin_token: Option<String>,
+    /// Senders allowed to cancel their pending messages with a signed request
+    pub cancellation_senders: Vec<H160>,
+}
+
+/// Config for deprioritizing message floods
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageThrottleConf {
+    /// Window message arrivals are counted over
+    pub window: Duration,
+    /// Messages a sender may dispatch within the window before being
+    /// throttled. Senders aren't throttled if unset.
+    pub sender_burst: Option<usize>,
+    /// Messages a recipient on a destination may receive within the window
+    /// before being throttled. Routes aren't throttled if unset.
+    pub route_burst: Option<usize>,
+    /// Messages of throttled senders and routes handed to the submitter per
+    /// window, which bounds the gas estimations and metadata fetches spent
+    /// on them
+    pub throttled_budget: usize,
+}
+
+impl Default for MessageThrottleConf {
+    fn default() -> Self {
+        Self {
+            window: Duration::from_secs(60),
+            sender_burst: None,
+            route_burst: None,
+            throttled_budget: 60,
+        }
+    }
+}
+
+/// Config for pausing submissions to destinations where too many process
+/// transactions revert
+#[derive(Debug, Clone, PartialEq)]
+pub struct CircuitBreakerConf {
+    /// Window the revert rate of process transactions is measured over
+    pub window: Duration,
+    /// Share of the process transactions within the window which, once
+    /// reverted, opens the breaker. Breakers never open on their own if unset.
+    pub revert_threshold: Option<f64>,
+    /// Process transactions needed within the window before the revert rate
+    /// is acted on
+    pub min_samples: usize,
+    /// How long an open breaker waits before letting a canary transaction
+    /// through, and between canaries after one reverts
+    pub canary_interval: Duration,
+    /// Consecutive canaries which need to succeed to close the breaker
+    pub canary_successes: u32,
+}
+
+impl Default for CircuitBreakerConf {
+    fn default() -> Self {
+        Self {
+            window: Duration::from_secs(60 * 10),
+            revert_threshold: None,
+            min_samples: 10,
+            canary_interval: Duration::from_secs(60 * 5),
+            canary_successes: 3,
+        }
+    }
+}
+
+/// Config for deferring the submission of messages while the gas price of
+/// their destination is higher than they're worth relaying at
+#[derive(Debug, Clone)]
+pub struct GasPriceCeilingConf {
+    /// Highest gas price, in the destination's smallest unit, messages are
+    /// submitted at. Messages are submitted at any gas price if unset.
+    pub default: Option<U256>,
+    /// Ceilings overriding the default, by route. The first matching list a
+    /// message matches decides.
+    pub overrides: Vec<(MatchingList, U256)>,
+    /// How often deferred messages are checked against the gas price again
+    pub recheck_interval: Duration,
+}
+
+impl Default for GasPriceCeilingConf {
+    fn default() -> Self {
+        Self {
+            default: None,
+            overrides: vec![],
+            recheck_interval: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Config of the candidate policies of a route, shadowing the active ones.
+/// Messages of the route are decided with both, but only the active
+/// decision is acted on; where the candidate would have decided otherwise
+/// is recorded, so it can be vetted against live traffic before promotion.
+#[derive(Debug, Clone, Default)]
+pub struct ShadowRouteConf {
+    /// Name the divergences of the route are reported under
+    pub name: String,
+    /// Messages of the route. A message is shadowed by the first route it
+    /// matches.
+    pub matching_list: MatchingList,
+    /// Candidate gas payment enforcement policies, in the shape of the
+    /// active ones. The active policies also apply to the candidate if unset.
+    pub gas_payment_enforcement: Option<Vec<GasPaymentEnforcementConf>>,
+    /// Candidate gas price ceiling. The active ceiling also applies to the
+    /// candidate if unset.
+    pub gas_price_ceiling: Option<U256>,
+    /// Candidate quorum checkpoint preference. The active preference also
+    /// applies to the candidate if unset.
+    pub checkpoint_preference: Option<CheckpointPreference>,
+}
+
+/// Config for the offchain lookups (ERC-3668) of CCIP read ISMs
+#[derive(Debug, Clone)]
+pub struct CcipReadConf {
+    /// Gateway URLs offchain lookups may be sent to, by route. A message uses
+    /// the URLs of the first matching list it matches, an empty list matching
+    /// any message, and may not send lookups if it matches none. A URL is
+    /// allowed if it has the same origin as one of them and extends its
+    /// path. Any URL is allowed if unset.
+    pub allowlist: Option<Vec<(MatchingList, Vec<String>)>>,
+    /// Timeout of each request to a gateway
+    pub timeout: Duration,
+    /// Largest gateway response to accept, in bytes
+    pub max_response_bytes: usize,
+    /// Most lookups to perform for a message, as gateways may answer with
+    /// another lookup
+    pub max_lookups: u32,
+}
+
+impl Default for CcipReadConf {
+    fn default() -> Self {
+        Self {
+            allowlist: None,
+            timeout: Duration::from_secs(10),
+            max_response_bytes: 256 * 1024,
+            max_lookups: 4,
+        }
+    }
+}
+
+/// Config for preparing the proofs of messages ahead of their quorum
+#[derive(Debug, Clone)]
+pub struct QuorumPrefetchConf {
+    /// Most messages to keep prepared proofs for. Zero disables preparing
+    /// ahead of the quorum.
+    pub max_preparations: usize,
+    /// How long a preparation is kept if the quorum never completes
+    pub ttl: Duration,
+    /// How often messages whose quorum is anticipated are retried
+    pub poll_interval: Duration,
+}
+
+impl Default for QuorumPrefetchConf {
+    fn default() -> Self {
+        Self {
+            max_preparations: 1000,
+            ttl: Duration::from_secs(10 * 60),
+            poll_interval: Duration::from_millis(500),
+        }
+    }
+}
+
+/// Config for caching the quorum checkpoints of multisig metadata, and
+/// their encoded signatures, by route and checkpoint index
+#[derive(Debug, Clone)]
+pub struct CheckpointCacheConf {
+    /// Most routes, i.e. origin, destination and ISM, to cache checkpoints
+    /// for, the least recently used being evicted first. Zero disables the
+    /// cache.
+    pub max_routes: usize,
+    /// Most checkpoints cached for a single route, the lowest indices being
+    /// evicted first
+    pub max_checkpoints_per_route: usize,
+    /// How long a checkpoint is reused for before fetching a newer one
+    pub ttl: Duration,
+}
+
+impl Default for CheckpointCacheConf {
+    fn default() -> Self {
+        Self {
+            max_routes: 256,
+            max_checkpoints_per_route: 8,
+            ttl: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Config for bounding the metadata builds in flight, so that routes with
+/// slow validators or ISMs can't starve the others
+#[derive(Debug, Clone)]
+pub struct MetadataPoolConf {
+    /// Most metadata builds in flight across all routes. Zero lets metadata
+    /// be built without limit.
+    pub max_concurrency: usize,
+    /// Most metadata builds in flight for a single route, i.e. origin and
+    /// destination
+    pub max_in_flight_per_route: usize,
+}
+
+impl Default for MetadataPoolConf {
+    fn default() -> Self {
+        Self {
+            max_concurrency: 64,
+            max_in_flight_per_route: 16,
+        }
+    }
+}
+
+/// Config for the latency budgets of the stages messages go through, from
+/// the inclusion of their dispatch to the broadcast of their delivery
+#[derive(Debug, Clone)]
+pub struct LatencyBudgetConf {
+    /// Budgets of the messages matching none of the routes
+    pub default: StageBudgets,
+    /// Budgets by route. A message is held to the budgets of the first route
+    /// it matches, falling back to the default ones for the stages the route
+    /// leaves unset.
+    pub routes: Vec<LatencyBudgetRouteConf>,
+    /// Window the latency percentiles of each route are computed over
+    pub window: Duration,
+}
+
+impl LatencyBudgetConf {
+    /// Whether messages are timed through the pipeline at all
+    pub fn is_enabled(&self) -> bool {
+        self.default != StageBudgets::default() || !self.routes.is_empty()
+    }
+}
+
+impl Default for LatencyBudgetConf {
+    fn default() -> Self {
+        Self {
+            default: StageBudgets::default(),
+            routes: vec![],
+            window: Duration::from_secs(60 * 60),
+        }
+    }
+}
+
+/// How long a message may spend in each stage of the pipeline. Stages
+/// without a budget are timed but never breached.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StageBudgets {
+    /// From the inclusion of the dispatch block to the message being handed
+    /// to the submitter
+    pub indexing: Option<Duration>,
+    /// Building the metadata of the message, in the attempt that finds the
+    /// quorum covering it
+    pub metadata: Option<Duration>,
+    /// From the message being ready to be submitted to its process
+    /// transaction being broadcast
+    pub broadcast: Option<Duration>,
+}
+
+impl StageBudgets {
+    /// These budgets, with the stages they leave unset taken from `fallback`
+    pub fn or(self, fallback: StageBudgets) -> Self {
+        Self {
+            indexing: self.indexing.or(fallback.indexing),
+            metadata: self.metadata.or(fallback.metadata),
+            broadcast: self.broadcast.or(fallback.broadcast),
+        }
+    }
+}
+
+/// Config of the latency budgets of a route
+#[derive(Debug, Clone, Default)]
+pub struct LatencyBudgetRouteConf {
+    /// Name the latencies and breaches of the route are reported under
+    pub name: String,
+    /// Messages of the route
+    pub matching_list: MatchingList,
+    pub budgets: StageBudgets,
+}
+
+/// Which older messages of a route a newer one supersedes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SupersessionMode {
+    /// The undelivered messages of the same sender to the same recipient
+    LatestPerSenderRecipient,
+}
+
+/// Config of a route whose apps dispatch idempotent "latest state" messages,
+/// of which only the newest matters. Delivering the older ones once a newer
+/// one is indexed would only waste gas.
+#[derive(Debug, Clone)]
+pub struct SupersessionRouteConf {
+    /// Messages of the route. A message is grouped by the first route it
+    /// matches.
+    pub matching_list: MatchingList,
+    pub supersession: SupersessionMode,
+    /// Leading bytes of the body that are also part of the grouping key, for
+    /// apps multiplexing several channels between the same sender and
+    /// recipient
+    pub body_prefix_len: usize,
+}
+
+/// Config for the replay protection of apps that dispatch a message while
+/// handling one, which can bounce the same message between two chains until
+/// someone runs out of funds
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoundTripConf {
+    /// Route pairs the protection is enabled on
+    pub routes: Vec<RoundTripRouteConf>,
+    /// Tuples tracked per route pair. Beyond it, the tuples which crossed
+    /// least recently are forgotten, the tripped ones last.
+    pub max_tuples: usize,
+}
+
+impl Default for RoundTripConf {
+    fn default() -> Self {
+        Self {
+            routes: vec![],
+            max_tuples: 10_000,
+        }
+    }
+}
+
+/// Config of a route pair whose apps relay their own responses
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoundTripRouteConf {
+    /// Ids of the two domains of the pair. Messages count in either
+    /// direction.
+    pub domains: [u32; 2],
+    /// Times the same sender, recipient and body may cross the pair, in
+    /// either direction, within the window. The next message of the tuple is
+    /// parked, as are all the later ones until an operator clears it.
+    pub max_round_trips: u32,
+    /// Window crossings are counted over
+    pub window: Duration,
 }
 
 /// Config for gas payment enforcement
@@ -91,7 +492,7 @@ pub enum GasPaymentEnforcementPolicy {
 
 #[derive(Debug, Deserialize)]
 #[serde(transparent)]
-struct RawRelayerSettings(Value);
+pub(crate) struct RawRelayerSettings(Value);
 
 impl_loadable_from_settings!(Relayer, RawRelayerSettings -> RelayerSettings);
 
@@ -125,61 +526,75 @@ impl FromRawConf<RawRelayerSettings> for RelayerSettings {
             .parse_from_str("Expected database path")
             .unwrap_or_else(|| std::env::current_dir().unwrap().join("hyperlane_db"));
 
+        let message_compression = parse_message_compression(&p, &mut err);
+        let message_throttle = parse_message_throttle(&p, &mut err);
+        let circuit_breaker = parse_circuit_breaker(&p, &mut err);
+        let gas_price_ceiling = parse_gas_price_ceiling(&p, &mut err);
+        let shadow_config = parse_shadow_config(&p, &mut err);
+        let ccip_read = parse_ccip_read(&p, &mut err);
+
+        let merkle_tree_capacity_warning = p
+            .chain(&mut err)
+            .get_opt_key("merkleTreeCapacityWarning")
+            .parse_f64()
+            .and_then(|fraction| {
+                if fraction > 0. && fraction <= 1. {
+                    Ok(fraction)
+                } else {
+                    Err(eyre!("Merkle tree capacity warning must be in (0, 1]"))
+                        .into_config_result(|| &p.cwp + "merkle_tree_capacity_warning")
+                }
+            })
+            .unwrap_or(0.9);
+
+        let merkle_tree_shutdown_timeout = p
+            .chain(&mut err)
+            .get_opt_key("merkleTreeShutdownTimeoutSeconds")
+            .parse_duration(DurationUnit::Seconds)
+            .unwrap_or(Duration::from_secs(10));
+
+        let rebuild_pacing = parse_rebuild_pacing(&p, &mut err);
+
+        let disk_space = parse_disk_space(&p, &mut err);
+
+        let (instance_lock, signer_observation) = parse_instance_lock(&p, &mut err);
+
+        let takeover = p
+            .chain(&mut err)
+            .get_opt_key("takeover")
+            .parse_flag()
+            .unwrap_or(false);
+
+        let quorum_prefetch = parse_quorum_prefetch(&p, &mut err);
+
+        let checkpoint_cache = parse_checkpoint_cache(&p, &mut err);
+
+        let metadata_pool = parse_metadata_pool(&p, &mut err);
+
+        let latency_budgets = parse_latency_budgets(&p, &mut err);
+
+        let supersession_routes = parse_supersession_routes(&p, &mut err);
+
+        let round_trips = parse_round_trips(&p, &mut err);
+
+        let corruption_tolerance = p
+            .chain(&mut err)
+            .get_opt_key("corruptionTolerance")
+            .parse_u32()
+            .unwrap_or(10);
+
         let (raw_gas_payment_enforcement_path, raw_gas_payment_enforcement) = p
             .get_opt_key("gasPaymentEnforcement")
             .take_config_err_flat(&mut err)
             .and_then(parse_json_array)
             .unwrap_or_else(|| (&p.cwp + "gas_payment_enforcement", Value::Array(vec![])));
 
-        let gas_payment_enforcement_parser = ValueParser::new(
+        let gas_payment_enforcement_parser = p.with_value(
             raw_gas_payment_enforcement_path,
             &raw_gas_payment_enforcement,
         );
-        let mut gas_payment_enforcement = gas_payment_enforcement_parser.into_array_iter().map(|itr| {
-            itr.filter_map(|policy| {
-                let policy_type = policy.chain(&mut err).get_opt_key("type").parse_string().end();
-                let minimum_is_defined = matches!(policy.get_opt_key("minimum"), Ok(Some(_)));
-
-                let matching_list = policy.chain(&mut err).get_opt_key("matchingList").and_then(parse_matching_list).unwrap_or_default();
-
-                let parse_minimum = |p| GasPaymentEnforcementPolicy::Minimum { payment: p };
-                match policy_type {
-                    Some("minimum") => policy.chain(&mut err).get_opt_key("payment").parse_u256().end().map(parse_minimum),
-                    None if minimum_is_defined => policy.chain(&mut err).get_opt_key("payment").parse_u256().end().map(parse_minimum),
-                    Some("none") | None => Some(GasPaymentEnforcementPolicy::None),
-                    Some("onChainFeeQuoting") => {
-                        let gas_fraction = policy.chain(&mut err)
-                            .get_opt_key("gasFraction")
-                            .parse_string()
-                            .map(|v| v.replace(' ', ""))
-                            .unwrap_or_else(|| "1/2".to_owned());
-                        let (numerator, denominator) = gas_fraction
-                            .split_once('/')
-                            .ok_or_else(|| eyre!("Invalid `gas_fraction` for OnChainFeeQuoting gas payment enforcement policy; expected `numerator / denominator`"))
-                            .take_err(&mut err, || &policy.cwp + "gas_fraction")
-                            .unwrap_or(("1", "1"));
-
-                        Some(GasPaymentEnforcementPolicy::OnChainFeeQuoting {
-                            gas_fraction_numerator: numerator
-                                .parse()
-                                .context("Error parsing gas fraction numerator")
-                                .take_err(&mut err, || &policy.cwp + "gas_fraction")
-                                .unwrap_or(1),
-                            gas_fraction_denominator: denominator
-                                .parse()
-                                .context("Error parsing gas fraction denominator")
-                                .take_err(&mut err, || &policy.cwp + "gas_fraction")
-                                .unwrap_or(1),
-                        })
-                    }
-                    Some(pt) => Err(eyre!("Unknown gas payment enforcement policy type `{pt}`"))
-                        .take_err(&mut err, || cwp + "type"),
-                }.map(|policy| GasPaymentEnforcementConf {
-                    policy,
-                    matching_list,
-                })
-            }).collect_vec()
-        }).unwrap_or_default();
+        let mut gas_payment_enforcement =
+            parse_gas_payment_enforcement(gas_payment_enforcement_parser, &mut err);
 
         if gas_payment_enforcement.is_empty() {
             gas_payment_enforcement.push(GasPaymentEnforcementConf::default());
@@ -223,6 +638,49 @@ impl FromRawConf<RawRelayerSettings> for RelayerSettings {
             .parse_bool()
             .unwrap_or(false);
 
+        let expose_message_events = p
+            .chain(&mut err)
+            .get_opt_key("exposeMessageEvents")
+            .parse_bool()
+            .unwrap_or(false);
+
+        let lazy_origin_startup = p
+            .chain(&mut err)
+            .get_opt_key("lazyOriginStartup")
+            .parse_bool()
+            .unwrap_or(false);
+
+        let priority_origin_names: Vec<&str> = p
+            .chain(&mut err)
+            .get_opt_key("priorityOrigins")
+            .parse_string()
+            .map(|v| v.split(',').collect())
+            .unwrap_or_default();
+
+        let admin_token = p
+            .chain(&mut err)
+            .get_opt_key("adminToken")
+            .parse_string()
+            .end()
+            .map(str::to_owned);
+
+        let cancellation_senders = p
+            .chain(&mut err)
+            .get_opt_key("cancellationSenders")
+            .parse_string()
+            .end()
+            .map(|str| {
+                parse_address_list(str, &mut err, || &p.cwp + "cancellation_senders")
+                    .into_iter()
+                    .filter_map(|address| {
+                        Address::try_from_slice(&address)
+                            .and_then(|address| H160::try_from(&address))
+                            .take_err(&mut err, || &p.cwp + "cancellation_senders")
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
         cfg_unwrap_all!(cwp, err: [base]);
 
         let skip_transaction_gas_limit_for = skip_transaction_gas_limit_for_names
@@ -236,6 +694,16 @@ impl FromRawConf<RawRelayerSettings> for RelayerSettings {
             .map(|d| d.id())
             .collect();
 
+        let priority_origins = priority_origin_names
+            .into_iter()
+            .filter_map(|chain| {
+                base.lookup_domain(chain)
+                    .context("Missing configuration for a chain in `priorityOrigins`")
+                    .into_config_result(|| cwp + "priority_origins")
+                    .take_config_err(&mut err)
+            })
+            .collect();
+
         let relay_chains: HashSet<HyperlaneDomain> = relay_chain_names
             .unwrap_or_default()
             .into_iter()
@@ -254,7 +722,7 @@ impl FromRawConf<RawRelayerSettings> for RelayerSettings {
             .unwrap_or_else(|| (&p.cwp + "metric_app_contexts", Value::Array(vec![])));
 
         let metric_app_contexts_parser =
-            ValueParser::new(raw_metric_app_contexts_path, &raw_metric_app_contexts);
+            p.with_value(raw_metric_app_contexts_path, &raw_metric_app_contexts);
         let metric_app_contexts = metric_app_contexts_parser
             .into_array_iter()
             .map(|itr| {
@@ -273,9 +741,45 @@ impl FromRawConf<RawRelayerSettings> for RelayerSettings {
             })
             .unwrap_or_default();
 
+        let (raw_checkpoint_preferences_path, raw_checkpoint_preferences) = p
+            .get_opt_key("checkpointPreferences")
+            .take_config_err_flat(&mut err)
+            .and_then(parse_json_array)
+            .unwrap_or_else(|| (&p.cwp + "checkpoint_preferences", Value::Array(vec![])));
+
+        let checkpoint_preferences_parser =
+            p.with_value(raw_checkpoint_preferences_path, &raw_checkpoint_preferences);
+        let checkpoint_preferences = checkpoint_preferences_parser
+            .into_array_iter()
+            .map(|itr| {
+                itr.filter_map(|route| {
+                    let preference = route
+                        .chain(&mut err)
+                        .get_key("preference")
+                        .parse_value("Expected `latest` or `earliestSatisfiable`")
+                        .end();
+
+                    let matching_list = route
+                        .chain(&mut err)
+                        .get_key("matchingList")
+                        .and_then(parse_matching_list)
+                        .unwrap_or_default();
+
+                    preference.map(|preference| (matching_list, preference))
+                })
+                .collect_vec()
+            })
+            .unwrap_or_default();
+
+        // the relayer's own fields set to bare numbers are warned about with
+        // those of the base settings
+        let mut base = base;
+        base.bare_numbers.extend(p.bare_numbers.fields());
+
         err.into_result(RelayerSettings {
             base,
             db,
+            message_compression,
             origin_chains: relay_chains.clone(),
             destination_chains: relay_chains,
             gas_payment_enforcement,
@@ -286,6 +790,31 @@ impl FromRawConf<RawRelayerSettings> for RelayerSettings {
             skip_transaction_gas_limit_for,
             allow_local_checkpoint_syncers,
             metric_app_contexts,
+            checkpoint_preferences,
+            message_throttle,
+            circuit_breaker,
+            gas_price_ceiling,
+            shadow_config,
+            ccip_read,
+            merkle_tree_capacity_warning,
+            merkle_tree_shutdown_timeout,
+            rebuild_pacing,
+            disk_space,
+            instance_lock,
+            signer_observation,
+            takeover,
+            quorum_prefetch,
+            checkpoint_cache,
+            metadata_pool,
+            latency_budgets,
+            supersession_routes,
+            round_trips,
+            corruption_tolerance,
+            expose_message_events,
+            lazy_origin_startup,
+            priority_origins,
+            admin_token,
+            cancellation_senders,
         })
     }
 }
@@ -297,6 +826,7 @@ fn parse_json_array(p: ValueParser) -> Option<(ConfigPath, Value)> {
         ValueParser {
             val: Value::String(array_str),
             cwp,
+            ..
         } => serde_json::from_str::<Value>(array_str)
             .context("Expected JSON string")
             .take_err(&mut err, || cwp.clone())
@@ -304,12 +834,753 @@ fn parse_json_array(p: ValueParser) -> Option<(ConfigPath, Value)> {
         ValueParser {
             val: value @ Value::Array(_),
             cwp,
+            ..
         } => Some((cwp, value.clone())),
         _ => Err(eyre!("Expected JSON array or stringified JSON"))
             .take_err(&mut err, || p.cwp.clone()),
     }
 }
 
+/// Gas payment enforcement policies, as configured in `gasPaymentEnforcement`
+fn parse_gas_payment_enforcement(
+    policies: ValueParser,
+    err: &mut ConfigParsingError,
+) -> Vec<GasPaymentEnforcementConf> {
+    policies.into_array_iter().map(|itr| {
+        itr.filter_map(|policy| {
+            let policy_type = policy.chain(err).get_opt_key("type").parse_string().end();
+            let minimum_is_defined = matches!(policy.get_opt_key("minimum"), Ok(Some(_)));
+
+            let matching_list = policy.chain(err).get_opt_key("matchingList").and_then(parse_matching_list).unwrap_or_default();
+
+            let parse_minimum = |p| GasPaymentEnforcementPolicy::Minimum { payment: p };
+            match policy_type {
+                Some("minimum") => policy.chain(err).get_opt_key("payment").parse_u256().end().map(parse_minimum),
+                None if minimum_is_defined => policy.chain(err).get_opt_key("payment").parse_u256().end().map(parse_minimum),
+                Some("none") | None => Some(GasPaymentEnforcementPolicy::None),
+                Some("onChainFeeQuoting") => {
+                    let gas_fraction = policy.chain(err)
+                        .get_opt_key("gasFraction")
+                        .parse_string()
+                        .map(|v| v.replace(' ', ""))
+                        .unwrap_or_else(|| "1/2".to_owned());
+                    let (numerator, denominator) = gas_fraction
+                        .split_once('/')
+                        .ok_or_else(|| eyre!("Invalid `gas_fraction` for OnChainFeeQuoting gas payment enforcement policy; expected `numerator / denominator`"))
+                        .take_err(err, || &policy.cwp + "gas_fraction")
+                        .unwrap_or(("1", "1"));
+
+                    Some(GasPaymentEnforcementPolicy::OnChainFeeQuoting {
+                        gas_fraction_numerator: numerator
+                            .parse()
+                            .context("Error parsing gas fraction numerator")
+                            .take_err(err, || &policy.cwp + "gas_fraction")
+                            .unwrap_or(1),
+                        gas_fraction_denominator: denominator
+                            .parse()
+                            .context("Error parsing gas fraction denominator")
+                            .take_err(err, || &policy.cwp + "gas_fraction")
+                            .unwrap_or(1),
+                    })
+                }
+                Some(pt) => Err(eyre!("Unknown gas payment enforcement policy type `{pt}`"))
+                    .take_err(err, || &policy.cwp + "type"),
+            }.map(|policy| GasPaymentEnforcementConf {
+                policy,
+                matching_list,
+            })
+        }).collect_vec()
+    }).unwrap_or_default()
+}
+
+fn parse_message_compression(p: &ValueParser, err: &mut ConfigParsingError) -> MessageCompression {
+    let default = MessageCompression::default();
+    let enabled = p
+        .chain(err)
+        .get_opt_key("messageCompression")
+        .get_opt_key("enabled")
+        .parse_bool()
+        .unwrap_or(default.enabled);
+    let threshold = p
+        .chain(err)
+        .get_opt_key("messageCompression")
+        .get_opt_key("threshold")
+        .parse_u64()
+        .map(|t| t as usize)
+        .unwrap_or(default.threshold);
+    let level = p
+        .chain(err)
+        .get_opt_key("messageCompression")
+        .get_opt_key("level")
+        .parse_i32()
+        .unwrap_or(default.level);
+    let dictionary = p
+        .chain(err)
+        .get_opt_key("messageCompression")
+        .get_opt_key("dictionary")
+        .parse_from_str::<PathBuf>("Expected dictionary path")
+        .and_then(|path| {
+            std::fs::read(&path)
+                .with_context(|| format!("Failed to read compression dictionary {path:?}"))
+                .into_config_result(|| &p.cwp + "message_compression.dictionary")
+        })
+        .end();
+
+    MessageCompression {
+        enabled,
+        threshold,
+        level,
+        dictionary,
+    }
+}
+
+fn parse_message_throttle(p: &ValueParser, err: &mut ConfigParsingError) -> MessageThrottleConf {
+    let default = MessageThrottleConf::default();
+    let window = p
+        .chain(err)
+        .get_opt_key("messageThrottle")
+        .get_opt_key("windowSeconds")
+        .parse_duration(DurationUnit::Seconds)
+        .unwrap_or(default.window);
+    let sender_burst = p
+        .chain(err)
+        .get_opt_key("messageThrottle")
+        .get_opt_key("senderBurst")
+        .parse_u64()
+        .map(|burst| burst as usize)
+        .end();
+    let route_burst = p
+        .chain(err)
+        .get_opt_key("messageThrottle")
+        .get_opt_key("routeBurst")
+        .parse_u64()
+        .map(|burst| burst as usize)
+        .end();
+    let throttled_budget = p
+        .chain(err)
+        .get_opt_key("messageThrottle")
+        .get_opt_key("throttledBudget")
+        .parse_u64()
+        .map(|budget| budget as usize)
+        .unwrap_or(default.throttled_budget);
+
+    MessageThrottleConf {
+        window,
+        sender_burst,
+        route_burst,
+        throttled_budget,
+    }
+}
+
+fn parse_rebuild_pacing(p: &ValueParser, err: &mut ConfigParsingError) -> RebuildPacingConf {
+    let calls_per_minute = p
+        .chain(err)
+        .get_opt_key("rebuildPacing")
+        .get_opt_key("callsPerMinute")
+        .parse_u32()
+        .end();
+    let chunk_sleep = p
+        .chain(err)
+        .get_opt_key("rebuildPacing")
+        .get_opt_key("chunkSleepMillis")
+        .parse_duration(DurationUnit::Millis)
+        .unwrap_or_default();
+    RebuildPacingConf {
+        calls_per_minute,
+        chunk_sleep,
+    }
+}
+
+fn parse_disk_space(p: &ValueParser, err: &mut ConfigParsingError) -> DiskSpaceConf {
+    let default = DiskSpaceConf::default();
+    let warning_free_bytes = p
+        .chain(err)
+        .get_opt_key("diskSpace")
+        .get_opt_key("warningFreeBytes")
+        .parse_size()
+        .end();
+    let critical_free_bytes = p
+        .chain(err)
+        .get_opt_key("diskSpace")
+        .get_opt_key("criticalFreeBytes")
+        .parse_size()
+        .end();
+    let check_interval = p
+        .chain(err)
+        .get_opt_key("diskSpace")
+        .get_opt_key("checkIntervalSeconds")
+        .parse_duration(DurationUnit::Seconds)
+        .unwrap_or(default.check_interval);
+    if let (Some(warning), Some(critical)) = (warning_free_bytes, critical_free_bytes) {
+        if critical > warning {
+            err.push(
+                &p.cwp + "disk_space.critical_free_bytes",
+                eyre!("Expected the critical threshold ({critical}) to be at most the warning threshold ({warning})"),
+            );
+        }
+    }
+    DiskSpaceConf {
+        warning_free_bytes,
+        critical_free_bytes,
+        check_interval,
+    }
+}
+
+fn parse_instance_lock(
+    p: &ValueParser,
+    err: &mut ConfigParsingError,
+) -> (InstanceLockConf, Duration) {
+    let default = InstanceLockConf::default();
+    let enabled = p
+        .chain(err)
+        .get_opt_key("instanceLock")
+        .get_opt_key("enabled")
+        .parse_bool()
+        .unwrap_or(default.enabled);
+    let heartbeat_interval = p
+        .chain(err)
+        .get_opt_key("instanceLock")
+        .get_opt_key("heartbeatIntervalSeconds")
+        .parse_duration(DurationUnit::Seconds)
+        .unwrap_or(default.heartbeat_interval);
+    let heartbeat_ttl = p
+        .chain(err)
+        .get_opt_key("instanceLock")
+        .get_opt_key("heartbeatTtlSeconds")
+        .parse_duration(DurationUnit::Seconds)
+        .unwrap_or(default.heartbeat_ttl);
+    let signer_observation = p
+        .chain(err)
+        .get_opt_key("instanceLock")
+        .get_opt_key("signerObservationSeconds")
+        .parse_duration(DurationUnit::Seconds)
+        .unwrap_or(Duration::from_secs(15));
+    if heartbeat_ttl <= heartbeat_interval {
+        err.push(
+            &p.cwp + "instance_lock.heartbeat_ttl_seconds",
+            eyre!(
+                "Expected the heartbeat TTL ({}s) to exceed the heartbeat interval ({}s)",
+                heartbeat_ttl.as_secs(),
+                heartbeat_interval.as_secs()
+            ),
+        );
+    }
+    let conf = InstanceLockConf {
+        enabled,
+        heartbeat_interval,
+        heartbeat_ttl,
+    };
+    (conf, signer_observation)
+}
+
+fn parse_quorum_prefetch(p: &ValueParser, err: &mut ConfigParsingError) -> QuorumPrefetchConf {
+    let default = QuorumPrefetchConf::default();
+    let max_preparations = p
+        .chain(err)
+        .get_opt_key("quorumPrefetch")
+        .get_opt_key("maxPreparations")
+        .parse_u64()
+        .map(|max| max as usize)
+        .unwrap_or(default.max_preparations);
+    let ttl = p
+        .chain(err)
+        .get_opt_key("quorumPrefetch")
+        .get_opt_key("ttlSeconds")
+        .parse_duration(DurationUnit::Seconds)
+        .unwrap_or(default.ttl);
+    let poll_interval = p
+        .chain(err)
+        .get_opt_key("quorumPrefetch")
+        .get_opt_key("pollIntervalMillis")
+        .parse_duration(DurationUnit::Millis)
+        .unwrap_or(default.poll_interval);
+    QuorumPrefetchConf {
+        max_preparations,
+        ttl,
+        poll_interval,
+    }
+}
+
+fn parse_checkpoint_cache(p: &ValueParser, err: &mut ConfigParsingError) -> CheckpointCacheConf {
+    let default = CheckpointCacheConf::default();
+    let max_routes = p
+        .chain(err)
+        .get_opt_key("checkpointCache")
+        .get_opt_key("maxRoutes")
+        .parse_u64()
+        .map(|max| max as usize)
+        .unwrap_or(default.max_routes);
+    let max_checkpoints_per_route = p
+        .chain(err)
+        .get_opt_key("checkpointCache")
+        .get_opt_key("maxCheckpointsPerRoute")
+        .parse_u64()
+        .map(|max| max as usize)
+        .unwrap_or(default.max_checkpoints_per_route);
+    let ttl = p
+        .chain(err)
+        .get_opt_key("checkpointCache")
+        .get_opt_key("ttlSeconds")
+        .parse_duration(DurationUnit::Seconds)
+        .unwrap_or(default.ttl);
+    if max_routes > 0 && max_checkpoints_per_route == 0 {
+        err.push(
+            &p.cwp + "checkpoint_cache.max_checkpoints_per_route",
+            eyre!("Must be at least 1 while the checkpoint cache is enabled"),
+        );
+    }
+    CheckpointCacheConf {
+        max_routes,
+        max_checkpoints_per_route,
+        ttl,
+    }
+}
+
+fn parse_metadata_pool(p: &ValueParser, err: &mut ConfigParsingError) -> MetadataPoolConf {
+    let default = MetadataPoolConf::default();
+    let max_concurrency = p
+        .chain(err)
+        .get_opt_key("metadataPool")
+        .get_opt_key("maxConcurrency")
+        .parse_u64()
+        .map(|max| max as usize)
+        .unwrap_or(default.max_concurrency);
+    let max_in_flight_per_route = p
+        .chain(err)
+        .get_opt_key("metadataPool")
+        .get_opt_key("maxInFlightPerRoute")
+        .parse_u64()
+        .and_then(|max| {
+            if max > 0 {
+                Ok(max as usize)
+            } else {
+                Err(eyre!(
+                    "Expected at least one metadata build in flight per route"
+                ))
+            }
+            .into_config_result(|| &p.cwp + "metadata_pool.max_in_flight_per_route")
+        })
+        .unwrap_or(default.max_in_flight_per_route);
+    MetadataPoolConf {
+        max_concurrency,
+        max_in_flight_per_route,
+    }
+}
+
+fn parse_latency_budgets(p: &ValueParser, err: &mut ConfigParsingError) -> LatencyBudgetConf {
+    let default = LatencyBudgetConf::default();
+    let Some(p) = p.chain(err).get_opt_key("latencyBudgets").end() else {
+        return default;
+    };
+    let window = p
+        .chain(err)
+        .get_opt_key("windowSeconds")
+        .parse_duration(DurationUnit::Seconds)
+        .unwrap_or(default.window);
+
+    let raw_routes = p
+        .chain(err)
+        .get_opt_key("routes")
+        .end()
+        .and_then(parse_json_array);
+    let routes = raw_routes
+        .map(|(path, raw)| {
+            p.with_value(path, &raw)
+                .chain(err)
+                .into_array_iter()
+                .map(|itr| {
+                    itr.filter_map(|route| {
+                        let name = route.chain(err).get_key("name").parse_string().end();
+
+                        let matching_list = route
+                            .chain(err)
+                            .get_key("matchingList")
+                            .and_then(parse_matching_list)
+                            .unwrap_or_default();
+
+                        let budgets = parse_stage_budgets(&route, err);
+
+                        name.map(|name| LatencyBudgetRouteConf {
+                            name: name.to_owned(),
+                            matching_list,
+                            budgets,
+                        })
+                    })
+                    .collect_vec()
+                })
+                .unwrap_or_default()
+        })
+        .unwrap_or_default();
+
+    LatencyBudgetConf {
+        default: parse_stage_budgets(&p, err),
+        routes,
+        window,
+    }
+}
+
+fn parse_supersession_routes(
+    p: &ValueParser,
+    err: &mut ConfigParsingError,
+) -> Vec<SupersessionRouteConf> {
+    let raw_routes = p
+        .chain(err)
+        .get_opt_key("supersessionRoutes")
+        .end()
+        .and_then(parse_json_array);
+    raw_routes
+        .map(|(path, raw)| {
+            p.with_value(path, &raw)
+                .chain(err)
+                .into_array_iter()
+                .map(|itr| {
+                    itr.filter_map(|route| {
+                        let supersession = route
+                            .chain(err)
+                            .get_key("supersession")
+                            .parse_value("Expected `latest_per_sender_recipient`")
+                            .end();
+
+                        let matching_list = route
+                            .chain(err)
+                            .get_key("matchingList")
+                            .and_then(parse_matching_list)
+                            .unwrap_or_default();
+
+                        let body_prefix_len = route
+                            .chain(err)
+                            .get_opt_key("bodyPrefixBytes")
+                            .parse_u64()
+                            .map(|bytes| bytes as usize)
+                            .unwrap_or_default();
+
+                        supersession.map(|supersession| SupersessionRouteConf {
+                            matching_list,
+                            supersession,
+                            body_prefix_len,
+                        })
+                    })
+                    .collect_vec()
+                })
+                .unwrap_or_default()
+        })
+        .unwrap_or_default()
+}
+
+fn parse_round_trips(p: &ValueParser, err: &mut ConfigParsingError) -> RoundTripConf {
+    let default = RoundTripConf::default();
+    let Some(p) = p.chain(err).get_opt_key("roundTrips").end() else {
+        return default;
+    };
+    let max_tuples = p
+        .chain(err)
+        .get_opt_key("maxTuples")
+        .parse_u64()
+        .map(|max| max as usize)
+        .unwrap_or(default.max_tuples);
+
+    let raw_routes = p
+        .chain(err)
+        .get_opt_key("routes")
+        .end()
+        .and_then(parse_json_array);
+    let routes = raw_routes
+        .map(|(path, raw)| {
+            p.with_value(path, &raw)
+                .chain(err)
+                .into_array_iter()
+                .map(|itr| {
+                    itr.filter_map(|route| {
+                        let domains = route
+                            .chain(err)
+                            .get_key("domains")
+                            .into_array_iter()
+                            .map(|itr| {
+                                itr.filter_map(|domain| domain.chain(err).parse_u32().end())
+                                    .collect_vec()
+                            })
+                            .and_then(|domains| match domains[..] {
+                                [a, b] if a != b => Some([a, b]),
+                                _ => {
+                                    err.push(
+                                        &route.cwp + "domains",
+                                        eyre!("Expected the ids of two different domains"),
+                                    );
+                                    None
+                                }
+                            });
+
+                        let max_round_trips =
+                            route.chain(err).get_key("maxRoundTrips").parse_u32().end();
+
+                        let window = route
+                            .chain(err)
+                            .get_key("windowSeconds")
+                            .parse_duration(DurationUnit::Seconds)
+                            .end();
+
+                        Some(RoundTripRouteConf {
+                            domains: domains?,
+                            max_round_trips: max_round_trips?,
+                            window: window?,
+                        })
+                    })
+                    .collect_vec()
+                })
+                .unwrap_or_default()
+        })
+        .unwrap_or_default();
+
+    if !routes.is_empty() && max_tuples == 0 {
+        err.push(
+            &p.cwp + "max_tuples",
+            eyre!("Must be at least 1 while round trips are limited on some routes"),
+        );
+    }
+    RoundTripConf { routes, max_tuples }
+}
+
+fn parse_stage_budgets(p: &ValueParser, err: &mut ConfigParsingError) -> StageBudgets {
+    let mut budget = |key: &str| {
+        p.chain(err)
+            .get_opt_key(key)
+            .parse_duration(DurationUnit::Seconds)
+            .end()
+    };
+    StageBudgets {
+        indexing: budget("indexingSeconds"),
+        metadata: budget("metadataSeconds"),
+        broadcast: budget("broadcastSeconds"),
+    }
+}
+
+fn parse_circuit_breaker(p: &ValueParser, err: &mut ConfigParsingError) -> CircuitBreakerConf {
+    let default = CircuitBreakerConf::default();
+    let window = p
+        .chain(err)
+        .get_opt_key("circuitBreaker")
+        .get_opt_key("windowSeconds")
+        .parse_duration(DurationUnit::Seconds)
+        .unwrap_or(default.window);
+    let revert_threshold = p
+        .chain(err)
+        .get_opt_key("circuitBreaker")
+        .get_opt_key("revertThreshold")
+        .parse_f64()
+        .and_then(|threshold| {
+            if threshold > 0. && threshold <= 1. {
+                Ok(threshold)
+            } else {
+                Err(eyre!("Expected a share between 0 and 1, got {threshold}"))
+            }
+            .into_config_result(|| &p.cwp + "circuit_breaker.revert_threshold")
+        })
+        .end();
+    let min_samples = p
+        .chain(err)
+        .get_opt_key("circuitBreaker")
+        .get_opt_key("minSamples")
+        .parse_u64()
+        .map(|samples| samples as usize)
+        .unwrap_or(default.min_samples);
+    let canary_interval = p
+        .chain(err)
+        .get_opt_key("circuitBreaker")
+        .get_opt_key("canaryIntervalSeconds")
+        .parse_duration(DurationUnit::Seconds)
+        .unwrap_or(default.canary_interval);
+    let canary_successes = p
+        .chain(err)
+        .get_opt_key("circuitBreaker")
+        .get_opt_key("canarySuccesses")
+        .parse_u32()
+        .unwrap_or(default.canary_successes);
+
+    CircuitBreakerConf {
+        window,
+        revert_threshold,
+        min_samples,
+        canary_interval,
+        canary_successes,
+    }
+}
+
+fn parse_gas_price_ceiling(p: &ValueParser, err: &mut ConfigParsingError) -> GasPriceCeilingConf {
+    let default = GasPriceCeilingConf::default();
+    let default_ceiling = p
+        .chain(err)
+        .get_opt_key("gasPriceCeiling")
+        .get_opt_key("default")
+        .parse_u256()
+        .end();
+    let recheck_interval = p
+        .chain(err)
+        .get_opt_key("gasPriceCeiling")
+        .get_opt_key("recheckIntervalSeconds")
+        .parse_duration(DurationUnit::Seconds)
+        .unwrap_or(default.recheck_interval);
+
+    let raw_overrides = p
+        .chain(err)
+        .get_opt_key("gasPriceCeiling")
+        .get_opt_key("overrides")
+        .end()
+        .and_then(parse_json_array);
+    let overrides = raw_overrides
+        .map(|(path, raw)| {
+            p.with_value(path, &raw)
+                .chain(err)
+                .into_array_iter()
+                .map(|itr| {
+                    itr.filter_map(|route| {
+                        let max_gas_price =
+                            route.chain(err).get_key("maxGasPrice").parse_u256().end();
+
+                        let matching_list = route
+                            .chain(err)
+                            .get_key("matchingList")
+                            .and_then(parse_matching_list)
+                            .unwrap_or_default();
+
+                        max_gas_price.map(|max_gas_price| (matching_list, max_gas_price))
+                    })
+                    .collect_vec()
+                })
+                .unwrap_or_default()
+        })
+        .unwrap_or_default();
+
+    GasPriceCeilingConf {
+        default: default_ceiling,
+        overrides,
+        recheck_interval,
+    }
+}
+
+fn parse_shadow_config(p: &ValueParser, err: &mut ConfigParsingError) -> Vec<ShadowRouteConf> {
+    let raw_routes = p
+        .chain(err)
+        .get_opt_key("shadowConfig")
+        .end()
+        .and_then(parse_json_array);
+    raw_routes
+        .map(|(path, raw)| {
+            p.with_value(path, &raw)
+                .chain(err)
+                .into_array_iter()
+                .map(|itr| {
+                    itr.filter_map(|route| {
+                        let name = route.chain(err).get_key("name").parse_string().end();
+
+                        let matching_list = route
+                            .chain(err)
+                            .get_key("matchingList")
+                            .and_then(parse_matching_list)
+                            .unwrap_or_default();
+
+                        let gas_payment_enforcement = route
+                            .chain(err)
+                            .get_opt_key("gasPaymentEnforcement")
+                            .end()
+                            .and_then(parse_json_array)
+                            .map(|(path, raw)| {
+                                parse_gas_payment_enforcement(route.with_value(path, &raw), err)
+                            });
+
+                        let gas_price_ceiling = route
+                            .chain(err)
+                            .get_opt_key("gasPriceCeiling")
+                            .parse_u256()
+                            .end();
+
+                        let checkpoint_preference = route
+                            .chain(err)
+                            .get_opt_key("checkpointPreference")
+                            .parse_value("Expected `latest` or `earliestSatisfiable`")
+                            .end();
+
+                        name.map(|name| ShadowRouteConf {
+                            name: name.to_owned(),
+                            matching_list,
+                            gas_payment_enforcement,
+                            gas_price_ceiling,
+                            checkpoint_preference,
+                        })
+                    })
+                    .collect_vec()
+                })
+                .unwrap_or_default()
+        })
+        .unwrap_or_default()
+}
+
+fn parse_ccip_read(p: &ValueParser, err: &mut ConfigParsingError) -> CcipReadConf {
+    let default = CcipReadConf::default();
+    let timeout = p
+        .chain(err)
+        .get_opt_key("ccipRead")
+        .get_opt_key("timeoutSeconds")
+        .parse_duration(DurationUnit::Seconds)
+        .unwrap_or(default.timeout);
+    let max_response_bytes = p
+        .chain(err)
+        .get_opt_key("ccipRead")
+        .get_opt_key("maxResponseBytes")
+        .parse_size()
+        .map(|bytes| bytes as usize)
+        .unwrap_or(default.max_response_bytes);
+    let max_lookups = p
+        .chain(err)
+        .get_opt_key("ccipRead")
+        .get_opt_key("maxLookups")
+        .parse_u32()
+        .unwrap_or(default.max_lookups);
+
+    let raw_allowlist = p
+        .chain(err)
+        .get_opt_key("ccipRead")
+        .get_opt_key("allowlist")
+        .end()
+        .and_then(parse_json_array);
+    let allowlist = raw_allowlist.map(|(path, raw)| {
+        p.with_value(path, &raw)
+            .chain(err)
+            .into_array_iter()
+            .map(|itr| {
+                itr.filter_map(|route| {
+                    let urls = route
+                        .chain(err)
+                        .get_key("urls")
+                        .parse_string()
+                        .map(|urls| {
+                            urls.split(',')
+                                .map(|url| url.trim().to_owned())
+                                .filter(|url| !url.is_empty())
+                                .collect_vec()
+                        })
+                        .end();
+
+                    let matching_list = route
+                        .chain(err)
+                        .get_key("matchingList")
+                        .and_then(parse_matching_list)
+                        .unwrap_or_default();
+
+                    urls.map(|urls| (matching_list, urls))
+                })
+                .collect_vec()
+            })
+            .unwrap_or_default()
+    });
+
+    CcipReadConf {
+        allowlist,
+        timeout,
+        max_response_bytes,
+        max_lookups,
+    }
+}
+
 fn parse_matching_list(p: ValueParser) -> ConfigResult<MatchingList> {
     let mut err = ConfigParsingError::default();
 
@@ -317,7 +1588,7 @@ fn parse_matching_list(p: ValueParser) -> ConfigResult<MatchingList> {
     let Some(raw_list) = raw_list else {
         return err.into_result(MatchingList::default());
     };
-    let p = ValueParser::new(p.cwp.clone(), &raw_list);
+    let p = p.with_value(p.cwp.clone(), &raw_list);
     let ml = p
         .parse_value::<MatchingList>("Expected matching list")
         .take_config_err(&mut err)
@@ -332,13 +1603,7 @@ fn parse_address_list(
     err_path: impl Fn() -> ConfigPath,
 ) -> Vec<Vec<u8>> {
     str.split(',')
-        .filter_map(|s| {
-            let mut s = s.trim().to_owned();
-            if let Some(stripped) = s.strip_prefix("0x") {
-                s = stripped.to_owned();
-            }
-            hex::decode(s).take_err(err, &err_path)
-        })
+        .filter_map(|s| hyperlane_core::hex::decode(s.trim()).take_err(err, &err_path))
         .collect_vec()
 }
 
@@ -0,0 +1,235 @@
+//! Hot-reloadable state derived from the relayer settings.
+//!
+//! Everything which may change on a settings reload is assembled into a
+//! single immutable [`ConfigSnapshot`], swapped atomically behind a
+//! [`ConfigHandle`]. Tasks load the current snapshot once per iteration and
+//! read all of it from there, so an iteration sees either the old or the new
+//! config, never a mix of both, and no task holds a lock on any part of it.
+
+use std::{collections::HashSet, sync::Arc};
+
+use arc_swap::ArcSwap;
+
+use super::{matching_list::MatchingList, RelayerSettings, SupersessionRouteConf};
+use crate::msg::blacklist::AddressBlacklist;
+
+/// Errors raised while validating a config snapshot, in which case it is
+/// not swapped in
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum ConfigSnapshotError {
+    /// An empty address would blacklist every message
+    #[error("The address blacklist contains an empty address, which would block every message")]
+    EmptyBlacklistedAddress,
+    /// Metrics of an app context can't be labelled without a name
+    #[error("An app context of the metrics has an empty name")]
+    UnnamedAppContext,
+    /// Two app contexts of the metrics have the same name
+    #[error("App context `{0}` of the metrics is defined more than once")]
+    DuplicateAppContext(String),
+}
+
+/// The hot-reloadable state derived from the relayer settings, as of one
+/// reload
+#[derive(Debug, Clone, Default)]
+pub struct ConfigSnapshot {
+    /// Hash of the config the snapshot was built from, recorded with the
+    /// decisions made with it
+    pub config_hash: String,
+    pub message_whitelist: MatchingList,
+    pub message_blacklist: MatchingList,
+    pub address_blacklist: AddressBlacklist,
+    /// App contexts of the metrics, by the messages they apply to
+    pub metric_app_contexts: Vec<(MatchingList, String)>,
+    /// Routes whose older undelivered messages are superseded by newer ones
+    pub supersession_routes: Vec<SupersessionRouteConf>,
+}
+
+impl ConfigSnapshot {
+    /// Snapshot of the hot-reloadable state of `settings`
+    pub fn from_settings(settings: &RelayerSettings) -> Self {
+        Self {
+            config_hash: settings.config_fingerprint.hash.clone(),
+            message_whitelist: settings.whitelist.clone(),
+            message_blacklist: settings.blacklist.clone(),
+            address_blacklist: AddressBlacklist::new(settings.address_blacklist.clone()),
+            metric_app_contexts: settings.metric_app_contexts.clone(),
+            supersession_routes: settings.supersession_routes.clone(),
+        }
+    }
+
+    /// Check the snapshot is consistent before it's swapped in
+    pub fn validate(&self) -> Result<(), ConfigSnapshotError> {
+        if self
+            .address_blacklist
+            .blacklist
+            .iter()
+            .any(|address| address.is_empty())
+        {
+            return Err(ConfigSnapshotError::EmptyBlacklistedAddress);
+        }
+        let mut names = HashSet::new();
+        for (_, name) in &self.metric_app_contexts {
+            if name.is_empty() {
+                return Err(ConfigSnapshotError::UnnamedAppContext);
+            }
+            if !names.insert(name) {
+                return Err(ConfigSnapshotError::DuplicateAppContext(name.clone()));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Shared handle on the current config snapshot. Clones share the snapshot.
+#[derive(Debug, Clone)]
+pub struct ConfigHandle {
+    current: Arc<ArcSwap<ConfigSnapshot>>,
+}
+
+impl ConfigHandle {
+    /// A handle on `snapshot`, validating it first
+    pub fn new(snapshot: ConfigSnapshot) -> Result<Self, ConfigSnapshotError> {
+        snapshot.validate()?;
+        Ok(Self {
+            current: Arc::new(ArcSwap::from_pointee(snapshot)),
+        })
+    }
+
+    /// The current snapshot. Tasks should load it once per iteration and
+    /// read everything from it, rather than loading it again midway.
+    pub fn load(&self) -> Arc<ConfigSnapshot> {
+        self.current.load_full()
+    }
+
+    /// Swap in `snapshot` if it is valid, returning the snapshot it replaced.
+    /// The current snapshot is kept otherwise.
+    pub fn replace(
+        &self,
+        snapshot: ConfigSnapshot,
+    ) -> Result<Arc<ConfigSnapshot>, ConfigSnapshotError> {
+        snapshot.validate()?;
+        Ok(self.current.swap(Arc::new(snapshot)))
+    }
+
+    /// Build a snapshot of the reloaded `settings` and swap it in, if valid
+    pub fn reload(
+        &self,
+        settings: &RelayerSettings,
+    ) -> Result<Arc<ConfigSnapshot>, ConfigSnapshotError> {
+        self.replace(ConfigSnapshot::from_settings(settings))
+    }
+}
+
+impl Default for ConfigHandle {
+    fn default() -> Self {
+        Self::new(ConfigSnapshot::default()).expect("the default snapshot is valid")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        sync::atomic::{AtomicBool, Ordering},
+        thread,
+    };
+
+    use hyperlane_core::HyperlaneMessage;
+
+    use super::*;
+
+    const GENERATIONS: u32 = 2_000;
+
+    /// A snapshot whose every part tells the generation it was built in
+    fn snapshot(generation: u32) -> ConfigSnapshot {
+        ConfigSnapshot {
+            config_hash: generation.to_string(),
+            message_whitelist: MatchingList::with_destination_domain(generation),
+            message_blacklist: MatchingList::with_destination_domain(generation + 1),
+            address_blacklist: AddressBlacklist::new(vec![generation.to_be_bytes().to_vec()]),
+            metric_app_contexts: vec![(MatchingList::default(), format!("app-{generation}"))],
+            supersession_routes: vec![],
+        }
+    }
+
+    /// Check every part of `snapshot` was built in the same generation
+    fn assert_consistent(snapshot: &ConfigSnapshot) -> u32 {
+        let generation: u32 = snapshot.config_hash.parse().unwrap();
+        let message = |destination| HyperlaneMessage {
+            destination,
+            ..Default::default()
+        };
+        assert!(snapshot
+            .message_whitelist
+            .msg_matches(&message(generation), false));
+        assert!(snapshot
+            .message_blacklist
+            .msg_matches(&message(generation + 1), false));
+        assert_eq!(
+            snapshot.address_blacklist.blacklist,
+            vec![generation.to_be_bytes().to_vec()]
+        );
+        assert_eq!(
+            snapshot.metric_app_contexts[0].1,
+            format!("app-{generation}")
+        );
+        generation
+    }
+
+    #[test]
+    fn test_reload_never_exposes_a_mixed_view() {
+        let handle = ConfigHandle::new(snapshot(0)).unwrap();
+        let done = Arc::new(AtomicBool::new(false));
+        let readers = (0..4)
+            .map(|_| {
+                let handle = handle.clone();
+                let done = done.clone();
+                thread::spawn(move || {
+                    let mut last = 0;
+                    while !done.load(Ordering::Relaxed) {
+                        // An iteration keeps reading the snapshot it loaded,
+                        // however many reloads happen meanwhile
+                        let iteration = handle.load();
+                        let generation = assert_consistent(&iteration);
+                        thread::yield_now();
+                        assert_eq!(assert_consistent(&iteration), generation);
+                        // Reloads are seen in order
+                        assert!(generation >= last);
+                        last = generation;
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for generation in 1..=GENERATIONS {
+            let previous = handle.replace(snapshot(generation)).unwrap();
+            assert_eq!(assert_consistent(&previous), generation - 1);
+        }
+        done.store(true, Ordering::Relaxed);
+        for reader in readers {
+            reader.join().unwrap();
+        }
+        assert_eq!(assert_consistent(&handle.load()), GENERATIONS);
+    }
+
+    #[test]
+    fn test_invalid_snapshot_is_not_swapped_in() {
+        let handle = ConfigHandle::new(snapshot(1)).unwrap();
+
+        let mut blocking_everything = snapshot(2);
+        blocking_everything.address_blacklist = AddressBlacklist::new(vec![vec![]]);
+        let mut duplicate = snapshot(2);
+        duplicate
+            .metric_app_contexts
+            .push((MatchingList::default(), "app-2".to_owned()));
+
+        assert_eq!(
+            handle.replace(blocking_everything).unwrap_err(),
+            ConfigSnapshotError::EmptyBlacklistedAddress
+        );
+        assert_eq!(
+            handle.replace(duplicate).unwrap_err(),
+            ConfigSnapshotError::DuplicateAppContext("app-2".to_owned())
+        );
+        assert_eq!(assert_consistent(&handle.load()), 1);
+    }
+}
@@ -0,0 +1,74 @@
+//! Tasks spawned by the relayer's tasks, which must stop with them so that a
+//! relayer shut down within a longer running process doesn't leave any
+//! behind.
+
+use tokio::task::{AbortHandle, JoinHandle};
+
+/// Aborts the tasks it guards when dropped, e.g. when the task holding it is
+/// aborted, unless they're handed on with [`AbortOnDrop::disarm`]
+#[derive(Debug, Default)]
+pub(crate) struct AbortOnDrop(Vec<AbortHandle>);
+
+impl AbortOnDrop {
+    /// Guard `tasks`
+    pub(crate) fn new<'a, T: 'a>(tasks: impl IntoIterator<Item = &'a JoinHandle<T>>) -> Self {
+        let mut guard = Self::default();
+        guard.extend(tasks);
+        guard
+    }
+
+    /// Also guard `tasks`
+    pub(crate) fn extend<'a, T: 'a>(&mut self, tasks: impl IntoIterator<Item = &'a JoinHandle<T>>) {
+        self.0
+            .extend(tasks.into_iter().map(JoinHandle::abort_handle));
+    }
+
+    /// Stop guarding the tasks, leaving them to whoever they're handed to
+    pub(crate) fn disarm(mut self) {
+        self.0.clear();
+    }
+}
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        for task in &self.0 {
+            task.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_aborting_a_task_aborts_the_tasks_it_spawned() {
+        let (sender, mut receiver) = tokio::sync::mpsc::channel::<()>(1);
+        let parent = tokio::spawn(async move {
+            let child = tokio::spawn(async move {
+                let _sender = sender;
+                std::future::pending::<()>().await
+            });
+            let _abort = AbortOnDrop::new([&child]);
+            child.await.unwrap();
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        parent.abort();
+        // The child's sender is dropped once it's aborted
+        tokio::time::timeout(Duration::from_secs(1), receiver.recv())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_disarmed_tasks_keep_running() {
+        let task = tokio::spawn(std::future::pending::<()>());
+        AbortOnDrop::new([&task]).disarm();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(!task.is_finished());
+        task.abort();
+    }
+}
@@ -12,9 +12,10 @@ use tracing::{trace, warn};
 
 use hyperlane_base::settings::IndexSettings;
 use hyperlane_core::{
-    unwrap_or_none_result, BlockId, BlockInfo, Delivery, HyperlaneDomain, HyperlaneLogStore,
-    HyperlaneMessage, HyperlaneProvider, HyperlaneSequenceAwareIndexerStoreReader,
-    HyperlaneWatermarkedLogStore, Indexed, InterchainGasPayment, LogMeta, H256, H512,
+    unwrap_or_none_result, BlockId, BlockInfo, Delivery, HyperlaneCoreError, HyperlaneCoreResult,
+    HyperlaneDomain, HyperlaneLogStore, HyperlaneMessage, HyperlaneProvider,
+    HyperlaneSequenceAwareIndexerStoreReader, HyperlaneWatermarkedLogStore, Indexed,
+    InterchainGasPayment, LogMeta, H256, H512,
 };
 
 use crate::db::{
@@ -297,7 +298,10 @@ impl HyperlaneLogStore<HyperlaneMessage> for HyperlaneSqlDb {
     /// Store dispatched messages from the origin mailbox into the database.
     /// We store only messages from blocks and transaction which we could successfully insert
     /// into database.
-    async fn store_logs(&self, messages: &[(Indexed<HyperlaneMessage>, LogMeta)]) -> Result<u32> {
+    async fn store_logs(
+        &self,
+        messages: &[(Indexed<HyperlaneMessage>, LogMeta)],
+    ) -> HyperlaneCoreResult<u32> {
         if messages.is_empty() {
             return Ok(0);
         }
@@ -326,7 +330,10 @@ impl HyperlaneLogStore<Delivery> for HyperlaneSqlDb {
     /// Store delivered message ids from the destination mailbox into the database.
     /// We store only delivered messages ids from blocks and transaction which we could successfully
     /// insert into database.
-    async fn store_logs(&self, deliveries: &[(Indexed<Delivery>, LogMeta)]) -> Result<u32> {
+    async fn store_logs(
+        &self,
+        deliveries: &[(Indexed<Delivery>, LogMeta)],
+    ) -> HyperlaneCoreResult<u32> {
         if deliveries.is_empty() {
             return Ok(0);
         }
@@ -363,7 +370,7 @@ impl HyperlaneLogStore<InterchainGasPayment> for HyperlaneSqlDb {
     async fn store_logs(
         &self,
         payments: &[(Indexed<InterchainGasPayment>, LogMeta)],
-    ) -> Result<u32> {
+    ) -> HyperlaneCoreResult<u32> {
         if payments.is_empty() {
             return Ok(0);
         }
@@ -392,7 +399,10 @@ impl HyperlaneLogStore<InterchainGasPayment> for HyperlaneSqlDb {
 #[async_trait]
 impl HyperlaneSequenceAwareIndexerStoreReader<HyperlaneMessage> for HyperlaneSqlDb {
     /// Gets a message by its nonce.
-    async fn retrieve_by_sequence(&self, sequence: u32) -> Result<Option<HyperlaneMessage>> {
+    async fn retrieve_by_sequence(
+        &self,
+        sequence: u32,
+    ) -> HyperlaneCoreResult<Option<HyperlaneMessage>> {
         let message = self
             .db
             .retrieve_message_by_nonce(self.domain().id(), &self.mailbox_address, sequence)
@@ -401,7 +411,10 @@ impl HyperlaneSequenceAwareIndexerStoreReader<HyperlaneMessage> for HyperlaneSql
     }
 
     /// Gets the block number at which the log occurred.
-    async fn retrieve_log_block_number_by_sequence(&self, sequence: u32) -> Result<Option<u64>> {
+    async fn retrieve_log_block_number_by_sequence(
+        &self,
+        sequence: u32,
+    ) -> HyperlaneCoreResult<Option<u64>> {
         let tx_id = unwrap_or_none_result!(
             self.db
                 .retrieve_dispatched_tx_id(self.domain().id(), &self.mailbox_address, sequence)
@@ -418,11 +431,17 @@ where
     HyperlaneSqlDb: HyperlaneLogStore<T>,
 {
     /// Gets the block number high watermark
-    async fn retrieve_high_watermark(&self) -> Result<Option<u32>> {
-        Ok(Some(self.cursor.height().await.try_into()?))
+    async fn retrieve_high_watermark(&self) -> HyperlaneCoreResult<Option<u32>> {
+        let height = self
+            .cursor
+            .height()
+            .await
+            .try_into()
+            .map_err(HyperlaneCoreError::from_other)?;
+        Ok(Some(height))
     }
     /// Stores the block number high watermark
-    async fn store_high_watermark(&self, block_number: u32) -> Result<()> {
+    async fn store_high_watermark(&self, block_number: u32) -> HyperlaneCoreResult<()> {
         self.cursor.update(block_number.into()).await;
         Ok(())
     }
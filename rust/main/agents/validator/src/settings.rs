@@ -12,7 +12,7 @@ use hyperlane_base::{
     impl_loadable_from_settings,
     settings::{
         parser::{RawAgentConf, RawAgentSignerConf, ValueParser},
-        CheckpointSyncerConf, Settings, SignerConf,
+        CheckpointSyncerConf, DurationUnit, Settings, SignerConf,
     },
 };
 use hyperlane_core::{
@@ -34,16 +34,36 @@ pub struct ValidatorSettings {
     pub db: PathBuf,
     /// Chain to validate messages on
     pub origin_chain: HyperlaneDomain,
-    /// The validator attestation signer
-    pub validator: SignerConf,
-    /// The checkpoint syncer configuration
-    pub checkpoint_syncer: CheckpointSyncerConf,
+    /// Whether to sign checkpoints or only verify them
+    pub mode: ValidatorMode,
     /// The reorg configuration
     pub reorg_period: ReorgPeriod,
     /// How frequently to check for new checkpoints
     pub interval: Duration,
 }
 
+/// What the validator does with the checkpoints it builds
+#[derive(Debug, Clone)]
+pub enum ValidatorMode {
+    /// Sign checkpoints and publish them to checkpoint storage
+    Sign {
+        /// The validator attestation signer
+        validator: SignerConf,
+        /// The checkpoint syncers to publish to, the first being the
+        /// primary one
+        checkpoint_syncers: Vec<CheckpointSyncerConf>,
+        /// How many checkpoint syncers have to accept a write for it to
+        /// succeed
+        write_quorum: usize,
+        /// Whether to list the other checkpoint syncers as alternates in the
+        /// announced storage location
+        announce_alternates: bool,
+    },
+    /// Only check that the locally built tree reproduces the onchain roots,
+    /// without loading a signing key or writing to checkpoint storage
+    Verify,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(transparent)]
 struct RawValidatorSettings(Value);
@@ -84,14 +104,31 @@ impl FromRawConf<RawValidatorSettings> for ValidatorSettings {
             None
         };
 
-        let validator = p
+        let verify = p
             .chain(&mut err)
-            .get_key("validator")
-            .parse_from_raw_config::<SignerConf, RawAgentSignerConf, NoFilter>(
-                (),
-                "Expected valid validator configuration",
-            )
-            .end();
+            .get_opt_key("mode")
+            .parse_string()
+            .and_then(|mode| match mode {
+                "sign" => Ok(false),
+                "verify" => Ok(true),
+                _ => Err(eyre!("Unknown validator mode `{mode}`"))
+                    .into_config_result(|| &p.cwp + "mode"),
+            })
+            .unwrap_or(false);
+
+        // A verifying validator neither signs nor publishes, so it doesn't
+        // need a signer or checkpoint syncer
+        let validator = (!verify)
+            .then(|| {
+                p.chain(&mut err)
+                    .get_key("validator")
+                    .parse_from_raw_config::<SignerConf, RawAgentSignerConf, NoFilter>(
+                        (),
+                        "Expected valid validator configuration",
+                    )
+                    .end()
+            })
+            .flatten();
 
         let db = p
             .chain(&mut err)
@@ -103,17 +140,63 @@ impl FromRawConf<RawValidatorSettings> for ValidatorSettings {
                     .join(format!("validator_db_{}", origin_chain_name.unwrap_or("")))
             });
 
-        let checkpoint_syncer = p
+        // Either a single checkpoint syncer, or several replicating the
+        // checkpoints, the first of which is the primary
+        let checkpoint_syncers = (!verify)
+            .then(|| {
+                let syncers = p
+                    .chain(&mut err)
+                    .get_opt_key("checkpointSyncers")
+                    .into_array_iter()
+                    .map(|itr| {
+                        itr.filter_map(|syncer| {
+                            syncer
+                                .chain(&mut err)
+                                .and_then(parse_checkpoint_syncer)
+                                .end()
+                        })
+                        .collect::<Vec<_>>()
+                    });
+                match syncers {
+                    Some(syncers) if !syncers.is_empty() => Some(syncers),
+                    _ => p
+                        .chain(&mut err)
+                        .get_key("checkpointSyncer")
+                        .and_then(parse_checkpoint_syncer)
+                        .end()
+                        .map(|syncer| vec![syncer]),
+                }
+            })
+            .flatten();
+
+        let write_quorum = p
             .chain(&mut err)
-            .get_key("checkpointSyncer")
-            .and_then(parse_checkpoint_syncer)
-            .end();
+            .get_opt_key("checkpointSyncerQuorum")
+            .parse_u64()
+            .map(|quorum| quorum as usize)
+            .unwrap_or(1);
+        if let Some(syncers) = &checkpoint_syncers {
+            if write_quorum == 0 || write_quorum > syncers.len() {
+                err.push(
+                    &p.cwp + "checkpoint_syncer_quorum",
+                    eyre!(
+                        "Checkpoint syncer quorum must be between 1 and the number of checkpoint syncers ({})",
+                        syncers.len()
+                    ),
+                );
+            }
+        }
+
+        let announce_alternates = p
+            .chain(&mut err)
+            .get_opt_key("announceAlternateStorageLocations")
+            .parse_bool()
+            .unwrap_or(false);
 
         let interval = p
             .chain(&mut err)
             .get_opt_key("interval")
-            .parse_u64()
-            .map(Duration::from_secs)
+            .parse_duration(DurationUnit::Seconds)
             .unwrap_or(Duration::from_secs(5));
 
         cfg_unwrap_all!(cwp, err: [origin_chain_name]);
@@ -127,13 +210,32 @@ impl FromRawConf<RawValidatorSettings> for ValidatorSettings {
             .parse_value("Invalid reorgPeriod")
             .unwrap_or(ReorgPeriod::from_blocks(1));
 
-        cfg_unwrap_all!(cwp, err: [base, origin_chain, validator, checkpoint_syncer]);
+        let mode = if verify {
+            Some(ValidatorMode::Verify)
+        } else if let (Some(validator), Some(checkpoint_syncers)) = (validator, checkpoint_syncers)
+        {
+            Some(ValidatorMode::Sign {
+                validator,
+                checkpoint_syncers,
+                write_quorum,
+                announce_alternates,
+            })
+        } else {
+            None
+        };
+
+        cfg_unwrap_all!(cwp, err: [base, origin_chain, mode]);
 
         let mut base: Settings = base;
+        // the validator's own fields set to bare numbers are warned about with
+        // those of the base settings
+        base.bare_numbers.extend(p.bare_numbers.fields());
         // If the origin chain is an EVM chain, then we can use the validator as the signer if needed.
-        if origin_chain.domain_protocol() == HyperlaneDomainProtocol::Ethereum {
-            if let Some(origin) = base.chains.get_mut(origin_chain.name()) {
-                origin.signer.get_or_insert_with(|| validator.clone());
+        if let ValidatorMode::Sign { validator, .. } = &mode {
+            if origin_chain.domain_protocol() == HyperlaneDomainProtocol::Ethereum {
+                if let Some(origin) = base.chains.get_mut(origin_chain.name()) {
+                    origin.signer.get_or_insert_with(|| validator.clone());
+                }
             }
         }
 
@@ -141,15 +243,15 @@ impl FromRawConf<RawValidatorSettings> for ValidatorSettings {
             base,
             db,
             origin_chain,
-            validator,
-            checkpoint_syncer,
+            mode,
             reorg_period,
             interval,
         })
     }
 }
 
-/// Expects ValidatorAgentConfig.checkpointSyncer
+/// Expects ValidatorAgentConfig.checkpointSyncer, or an item of
+/// ValidatorAgentConfig.checkpointSyncers
 fn parse_checkpoint_syncer(syncer: ValueParser) -> ConfigResult<CheckpointSyncerConf> {
     let mut err = ConfigParsingError::default();
     let syncer_type = syncer.chain(&mut err).get_key("type").parse_string().end();
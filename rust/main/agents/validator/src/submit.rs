@@ -1,30 +1,44 @@
-use std::sync::Arc;
+use std::fmt::{self, Display, Formatter};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use std::vec;
 
-use prometheus::IntGauge;
+use prometheus::{IntCounter, IntGauge};
 use tokio::time::sleep;
 use tracing::{debug, error, info};
 
 use hyperlane_base::db::HyperlaneDb;
-use hyperlane_base::{CheckpointSyncer, CoreMetrics};
+use hyperlane_base::{Alert, AlertDispatcher, AlertKind, CheckpointSyncer, CoreMetrics};
 use hyperlane_core::rpc_clients::call_and_retry_indefinitely;
 use hyperlane_core::{
     accumulator::incremental::IncrementalMerkle, Checkpoint, CheckpointWithMessageId,
-    HyperlaneChain, HyperlaneContract, HyperlaneDomain, HyperlaneSignerExt,
+    HyperlaneChain, HyperlaneContract, HyperlaneDomain, HyperlaneSignerExt, H256,
 };
 use hyperlane_core::{ChainResult, MerkleTreeHook, ReorgEvent, ReorgPeriod};
 use hyperlane_ethereum::SingletonSignerHandle;
 
+/// What happens to the checkpoints once the tree is found to be consistent
+/// with the onchain one
+#[derive(Clone)]
+pub(crate) enum CheckpointPublisher {
+    /// Sign the checkpoints and write them to checkpoint storage
+    Sign {
+        signer: SingletonSignerHandle,
+        checkpoint_syncer: Arc<dyn CheckpointSyncer>,
+    },
+    /// Only record whether the checkpoints agreed with the onchain roots
+    Record(VerificationRecorder),
+}
+
 #[derive(Clone)]
 pub(crate) struct ValidatorSubmitter {
     interval: Duration,
     reorg_period: ReorgPeriod,
-    signer: SingletonSignerHandle,
+    publisher: CheckpointPublisher,
     merkle_tree_hook: Arc<dyn MerkleTreeHook>,
-    checkpoint_syncer: Arc<dyn CheckpointSyncer>,
     db: Arc<dyn HyperlaneDb>,
     metrics: ValidatorSubmitterMetrics,
+    alerts: AlertDispatcher,
 }
 
 impl ValidatorSubmitter {
@@ -32,19 +46,19 @@ impl ValidatorSubmitter {
         interval: Duration,
         reorg_period: ReorgPeriod,
         merkle_tree_hook: Arc<dyn MerkleTreeHook>,
-        signer: SingletonSignerHandle,
-        checkpoint_syncer: Arc<dyn CheckpointSyncer>,
+        publisher: CheckpointPublisher,
         db: Arc<dyn HyperlaneDb>,
         metrics: ValidatorSubmitterMetrics,
+        alerts: AlertDispatcher,
     ) -> Self {
         Self {
             reorg_period,
             interval,
             merkle_tree_hook,
-            signer,
-            checkpoint_syncer,
+            publisher,
             db,
             metrics,
+            alerts,
         }
     }
 
@@ -68,6 +82,10 @@ impl ValidatorSubmitter {
             ?target_checkpoint,
             "Backfill checkpoint submitter successfully reached target checkpoint"
         );
+        if let CheckpointPublisher::Record(recorder) = &self.publisher {
+            let report = recorder.report();
+            info!(%report, "Verified all checkpoints up to the backfill target");
+        }
     }
 
     /// Submits signed checkpoints indefinitely, starting from the `tree`.
@@ -203,6 +221,17 @@ impl ValidatorSubmitter {
 
         let checkpoint = self.checkpoint(tree);
 
+        let (signer, checkpoint_syncer) = match &self.publisher {
+            CheckpointPublisher::Sign {
+                signer,
+                checkpoint_syncer,
+            } => (signer, checkpoint_syncer),
+            CheckpointPublisher::Record(recorder) => {
+                recorder.record(&checkpoint, correctness_checkpoint, checkpoint_queue.len());
+                return;
+            }
+        };
+
         // If the tree's checkpoint doesn't match the correctness checkpoint, something went wrong
         // and we bail loudly.
         if checkpoint != *correctness_checkpoint {
@@ -220,12 +249,25 @@ impl ValidatorSubmitter {
                 "Incorrect tree root, something went wrong"
             );
 
+            let origin = self.merkle_tree_hook.domain().name();
+            // Wait for delivery, since the panic below stops the validator
+            self.alerts
+                .dispatch_and_wait(
+                    Alert::new(
+                        AlertKind::ReorgDetected,
+                        format!("reorg_detected:{origin}"),
+                        format!("validator/{origin}"),
+                        format!(
+                            "Local merkle tree root doesn't match the onchain root at checkpoint index {}",
+                            checkpoint.index
+                        ),
+                    )
+                    .with_details(serde_json::json!(reorg_event)),
+                )
+                .await;
+
             let mut panic_message = "Incorrect tree root, something went wrong.".to_owned();
-            if let Err(e) = self
-                .checkpoint_syncer
-                .write_reorg_status(&reorg_event)
-                .await
-            {
+            if let Err(e) = checkpoint_syncer.write_reorg_status(&reorg_event).await {
                 panic_message.push_str(&format!(
                     " Reorg troubleshooting details couldn't be written to checkpoint storage: {}",
                     e
@@ -240,7 +282,8 @@ impl ValidatorSubmitter {
                 queue_len = checkpoint_queue.len(),
                 "Reached tree consistency"
             );
-            self.sign_and_submit_checkpoints(checkpoint_queue).await;
+            self.sign_and_submit_checkpoints(signer, checkpoint_syncer, checkpoint_queue)
+                .await;
 
             info!(
                 index = checkpoint.index,
@@ -250,19 +293,17 @@ impl ValidatorSubmitter {
     }
 
     async fn sign_and_submit_checkpoint(
-        &self,
+        signer: &SingletonSignerHandle,
+        checkpoint_syncer: &dyn CheckpointSyncer,
         checkpoint: CheckpointWithMessageId,
     ) -> ChainResult<()> {
-        let existing = self
-            .checkpoint_syncer
-            .fetch_checkpoint(checkpoint.index)
-            .await?;
+        let existing = checkpoint_syncer.fetch_checkpoint(checkpoint.index).await?;
         if existing.is_some() {
             debug!(index = checkpoint.index, "Checkpoint already submitted");
             return Ok(());
         }
-        let signed_checkpoint = self.signer.sign(checkpoint).await?;
-        self.checkpoint_syncer
+        let signed_checkpoint = signer.sign(checkpoint).await?;
+        checkpoint_syncer
             .write_checkpoint(&signed_checkpoint)
             .await?;
         debug!(index = checkpoint.index, "Signed and submitted checkpoint");
@@ -274,7 +315,12 @@ impl ValidatorSubmitter {
     }
 
     /// Signs and submits any previously unsubmitted checkpoints.
-    async fn sign_and_submit_checkpoints(&self, checkpoints: Vec<CheckpointWithMessageId>) {
+    async fn sign_and_submit_checkpoints(
+        &self,
+        signer: &SingletonSignerHandle,
+        checkpoint_syncer: &Arc<dyn CheckpointSyncer>,
+        checkpoints: Vec<CheckpointWithMessageId>,
+    ) {
         let last_checkpoint = checkpoints.as_slice()[checkpoints.len() - 1];
         // Submits checkpoints to the store in reverse order. This speeds up processing historic checkpoints (those before the validator is spun up),
         // since those are the most likely to make messages become processable.
@@ -282,11 +328,15 @@ impl ValidatorSubmitter {
         for queued_checkpoint in checkpoints.into_iter().rev() {
             // certain checkpoint stores rate limit very aggressively, so we retry indefinitely
             call_and_retry_indefinitely(|| {
-                let self_clone = self.clone();
+                let signer = signer.clone();
+                let checkpoint_syncer = checkpoint_syncer.clone();
                 Box::pin(async move {
-                    self_clone
-                        .sign_and_submit_checkpoint(queued_checkpoint)
-                        .await?;
+                    Self::sign_and_submit_checkpoint(
+                        &signer,
+                        checkpoint_syncer.as_ref(),
+                        queued_checkpoint,
+                    )
+                    .await?;
                     Ok(())
                 })
             })
@@ -294,10 +344,9 @@ impl ValidatorSubmitter {
         }
 
         call_and_retry_indefinitely(|| {
-            let self_clone = self.clone();
+            let checkpoint_syncer = checkpoint_syncer.clone();
             Box::pin(async move {
-                self_clone
-                    .checkpoint_syncer
+                checkpoint_syncer
                     .update_latest_index(last_checkpoint.index)
                     .await?;
                 Ok(())
@@ -334,6 +383,110 @@ impl ValidatorSubmitterMetrics {
     }
 }
 
+/// A checkpoint at which the locally built tree disagreed with the onchain one
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct RootMismatch {
+    /// Index of the checkpoint
+    pub index: u32,
+    /// Root of the tree built from indexed insertions
+    pub local_root: H256,
+    /// Root reported by the merkle tree hook
+    pub onchain_root: H256,
+}
+
+/// How the locally built tree compared to the onchain roots while verifying
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct VerificationReport {
+    /// Number of indices covered by a checkpoint that matched the onchain root
+    pub matched: u32,
+    /// Checkpoints that didn't match the onchain root, in the order found
+    pub mismatches: Vec<RootMismatch>,
+    /// Highest checkpoint index compared, if any
+    pub latest_index: Option<u32>,
+}
+
+impl Display for VerificationReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Some(latest_index) = self.latest_index else {
+            return write!(f, "no checkpoints verified yet");
+        };
+        write!(
+            f,
+            "{} indices matched and {} checkpoints mismatched up to index {latest_index}",
+            self.matched,
+            self.mismatches.len()
+        )?;
+        if !self.mismatches.is_empty() {
+            let indices = self
+                .mismatches
+                .iter()
+                .map(|mismatch| mismatch.index.to_string())
+                .collect::<Vec<_>>();
+            write!(f, " (mismatched at {})", indices.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Stands in for signing and publishing when the validator only verifies,
+/// recording how each correctness checkpoint compared instead
+#[derive(Clone)]
+pub(crate) struct VerificationRecorder {
+    report: Arc<Mutex<VerificationReport>>,
+    matched: IntCounter,
+    mismatched: IntCounter,
+}
+
+impl VerificationRecorder {
+    pub fn new(metrics: &CoreMetrics, mailbox_chain: &HyperlaneDomain) -> Self {
+        let chain_name = mailbox_chain.name();
+        Self {
+            report: Default::default(),
+            matched: metrics
+                .verified_checkpoint_indices()
+                .with_label_values(&[chain_name, "matched"]),
+            mismatched: metrics
+                .verified_checkpoint_indices()
+                .with_label_values(&[chain_name, "mismatched"]),
+        }
+    }
+
+    /// The report of everything verified so far
+    pub fn report(&self) -> VerificationReport {
+        self.report.lock().unwrap().clone()
+    }
+
+    /// Records the comparison of the local `checkpoint` with the onchain
+    /// `correctness_checkpoint`, which covered `queued` newly ingested leaves
+    fn record(&self, checkpoint: &Checkpoint, correctness_checkpoint: &Checkpoint, queued: usize) {
+        let mut report = self.report.lock().unwrap();
+        report.latest_index = report.latest_index.max(Some(checkpoint.index));
+        if checkpoint == correctness_checkpoint {
+            report.matched += queued as u32;
+            self.matched.inc_by(queued as u64);
+            if queued > 0 {
+                info!(
+                    index = checkpoint.index,
+                    queued, "Verified checkpoints until index"
+                );
+            }
+        } else {
+            let mismatch = RootMismatch {
+                index: checkpoint.index,
+                local_root: checkpoint.root,
+                onchain_root: correctness_checkpoint.root,
+            };
+            error!(
+                ?mismatch,
+                ?correctness_checkpoint,
+                "Local tree root doesn't match the onchain root"
+            );
+            report.mismatches.push(mismatch);
+            self.mismatched.inc();
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -347,8 +500,9 @@ mod test {
         test_utils::dummy_domain, GasPaymentKey, HyperlaneChain, HyperlaneContract,
         HyperlaneDomain, HyperlaneMessage, HyperlaneProvider, InterchainGasPayment,
         InterchainGasPaymentMeta, MerkleTreeHook, MerkleTreeInsertion, PendingOperationStatus,
-        ReorgEvent, SignedAnnouncement, SignedCheckpointWithMessageId, H160, H256,
+        ReorgEvent, SignedAnnouncement, SignedCheckpointWithMessageId, H256,
     };
+    use hyperlane_ethereum::test_keys::test_address;
     use prometheus::Registry;
     use std::{fmt::Debug, sync::Arc, time::Duration};
     use tokio::sync::mpsc;
@@ -524,7 +678,7 @@ mod test {
     }
 
     fn dummy_singleton_handle() -> SingletonSignerHandle {
-        SingletonSignerHandle::new(H160::from_low_u64_be(0), mpsc::unbounded_channel().0)
+        SingletonSignerHandle::new(test_address(0), mpsc::unbounded_channel().0)
     }
 
     fn reorg_event_is_correct(
@@ -627,10 +781,13 @@ mod test {
             Duration::from_secs(1),
             ReorgPeriod::from_blocks(expected_reorg_period),
             Arc::new(mock_merkle_tree_hook),
-            dummy_singleton_handle(),
-            Arc::new(mock_checkpoint_syncer),
+            CheckpointPublisher::Sign {
+                signer: dummy_singleton_handle(),
+                checkpoint_syncer: Arc::new(mock_checkpoint_syncer),
+            },
             Arc::new(db),
             dummy_metrics(),
+            AlertDispatcher::default(),
         );
 
         // mock the correctness checkpoint response
@@ -650,4 +807,93 @@ mod test {
             )
             .await;
     }
+
+    #[tokio::test]
+    async fn verify_mode_reports_mismatched_roots() {
+        let insertions = (0..6)
+            .map(|index| MerkleTreeInsertion::new(index, H256::random()))
+            .collect::<Vec<_>>();
+        let mut onchain_tree = IncrementalMerkle::default();
+        let onchain_roots = insertions
+            .iter()
+            .map(|insertion| {
+                onchain_tree.ingest(insertion.message_id());
+                onchain_tree.root()
+            })
+            .collect::<Vec<_>>();
+
+        // the db only expects reads, so writing anything would fail the test
+        let mut db = MockDb::new();
+        let db_insertions = insertions.clone();
+        db.expect_retrieve_merkle_tree_insertion_by_leaf_index()
+            .returning(move |index| Ok(Some(db_insertions[*index as usize].clone())));
+
+        let mut mock_merkle_tree_hook = MockMerkleTreeHook::new();
+        mock_merkle_tree_hook
+            .expect_address()
+            .returning(|| H256::from_low_u64_be(0));
+        let dummy_domain = dummy_domain(0, "dummy_domain");
+        mock_merkle_tree_hook
+            .expect_domain()
+            .return_const(dummy_domain.clone());
+
+        // there's no signer or checkpoint syncer to write to when verifying
+        let core_metrics = CoreMetrics::new("dummy_validator", 37582, Registry::new()).unwrap();
+        let recorder = VerificationRecorder::new(&core_metrics, &dummy_domain);
+        let validator_submitter = ValidatorSubmitter::new(
+            Duration::from_secs(1),
+            ReorgPeriod::from_blocks(1),
+            Arc::new(mock_merkle_tree_hook),
+            CheckpointPublisher::Record(recorder.clone()),
+            Arc::new(db),
+            dummy_metrics(),
+            AlertDispatcher::default(),
+        );
+
+        // the chain reports a diverging root at index 3 only
+        let diverging_root = H256::random();
+        let mut tree = IncrementalMerkle::default();
+        for index in [1, 3, 5] {
+            let root = if index == 3 {
+                diverging_root
+            } else {
+                onchain_roots[index as usize]
+            };
+            let onchain_checkpoint = Checkpoint {
+                root,
+                index,
+                merkle_tree_hook_address: H256::from_low_u64_be(0),
+                mailbox_domain: dummy_domain.id(),
+            };
+            validator_submitter
+                .submit_checkpoints_until_correctness_checkpoint(&mut tree, &onchain_checkpoint)
+                .await;
+        }
+
+        assert_eq!(
+            recorder.report(),
+            VerificationReport {
+                matched: 4,
+                mismatches: vec![RootMismatch {
+                    index: 3,
+                    local_root: onchain_roots[3],
+                    onchain_root: diverging_root,
+                }],
+                latest_index: Some(5),
+            }
+        );
+        let verified = core_metrics.verified_checkpoint_indices();
+        assert_eq!(
+            verified
+                .with_label_values(&["dummy_domain", "matched"])
+                .get(),
+            4
+        );
+        assert_eq!(
+            verified
+                .with_label_values(&["dummy_domain", "mismatched"])
+                .get(),
+            1
+        );
+    }
 }
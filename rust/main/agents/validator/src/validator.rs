@@ -12,9 +12,10 @@ use tracing::{error, info, info_span, instrument::Instrumented, warn, Instrument
 use hyperlane_base::{
     db::{HyperlaneDb, HyperlaneRocksDB, DB},
     metrics::AgentMetrics,
-    settings::ChainConf,
+    settings::{check_db_environment, ChainConf},
     AgentMetadata, BaseAgent, ChainMetrics, CheckpointSyncer, ContractSyncMetrics, ContractSyncer,
-    CoreMetrics, HyperlaneAgentCore, MetricsUpdater, SequencedDataContractSync,
+    CoreMetrics, HyperlaneAgentCore, MetricsUpdater, ReplicatedCheckpointSyncer,
+    SequencedDataContractSync,
 };
 
 use hyperlane_core::{
@@ -25,8 +26,10 @@ use hyperlane_core::{
 use hyperlane_ethereum::{SingletonSigner, SingletonSignerHandle};
 
 use crate::{
-    settings::ValidatorSettings,
-    submit::{ValidatorSubmitter, ValidatorSubmitterMetrics},
+    settings::{ValidatorMode, ValidatorSettings},
+    submit::{
+        CheckpointPublisher, ValidatorSubmitter, ValidatorSubmitterMetrics, VerificationRecorder,
+    },
 };
 
 /// A validator agent
@@ -41,12 +44,15 @@ pub struct Validator {
     mailbox: Arc<dyn Mailbox>,
     merkle_tree_hook: Arc<dyn MerkleTreeHook>,
     validator_announce: Arc<dyn ValidatorAnnounce>,
-    signer: SingletonSignerHandle,
+    // signs and publishes checkpoints, or only records them in verify mode
+    publisher: CheckpointPublisher,
+    // retries the writes checkpoint storage backends missed, when publishing
+    // to several of them
+    replicated_checkpoint_syncer: Option<Arc<ReplicatedCheckpointSyncer>>,
     // temporary holder until `run` is called
     signer_instance: Option<Box<SingletonSigner>>,
     reorg_period: ReorgPeriod,
     interval: Duration,
-    checkpoint_syncer: Arc<dyn CheckpointSyncer>,
     core_metrics: Arc<CoreMetrics>,
     agent_metrics: AgentMetrics,
     chain_metrics: ChainMetrics,
@@ -71,17 +77,62 @@ impl BaseAgent for Validator {
         Self: Sized,
     {
         let db = DB::from_path(&settings.db)?;
+        check_db_environment(&db, settings.environment.as_deref())?;
+        settings.config_fingerprint.record(&db);
         let msg_db = HyperlaneRocksDB::new(&settings.origin_chain, db);
 
-        // Intentionally using hyperlane_ethereum for the validator's signer
-        let (signer_instance, signer) = SingletonSigner::new(settings.validator.build().await?);
-
         let core = settings.build_hyperlane_core(metrics.clone());
-        let checkpoint_syncer = settings
-            .checkpoint_syncer
-            .build_and_validate(None)
-            .await?
-            .into();
+
+        let mut replicated_checkpoint_syncer = None;
+        let (signer_instance, publisher) = match &settings.mode {
+            ValidatorMode::Sign {
+                validator,
+                checkpoint_syncers,
+                write_quorum,
+                announce_alternates,
+            } => {
+                // Intentionally using hyperlane_ethereum for the validator's signer
+                let (signer_instance, signer) = SingletonSigner::new(validator.build().await?);
+                let mut syncers: Vec<Arc<dyn CheckpointSyncer>> = vec![];
+                for conf in checkpoint_syncers {
+                    syncers.push(conf.build_and_validate(None).await?.into());
+                }
+                let checkpoint_syncer: Arc<dyn CheckpointSyncer> = if syncers.len() == 1 {
+                    syncers.remove(0)
+                } else {
+                    let lag = metrics.new_int_gauge(
+                        "checkpoint_storage_backend_lag",
+                        "Number of signed checkpoints a checkpoint storage backend missed and is yet to catch up on",
+                        &["location"],
+                    )?;
+                    let mut replicated = ReplicatedCheckpointSyncer::new(syncers, *write_quorum)
+                        .with_lag_gauge(&lag);
+                    if *announce_alternates {
+                        replicated = replicated.with_announced_alternates();
+                    }
+                    let replicated = Arc::new(replicated);
+                    replicated_checkpoint_syncer = Some(replicated.clone());
+                    replicated
+                };
+                (
+                    Some(Box::new(signer_instance)),
+                    CheckpointPublisher::Sign {
+                        signer,
+                        checkpoint_syncer,
+                    },
+                )
+            }
+            ValidatorMode::Verify => {
+                info!("Running in verify mode, checkpoints will not be signed or published");
+                (
+                    None,
+                    CheckpointPublisher::Record(VerificationRecorder::new(
+                        &metrics,
+                        &settings.origin_chain,
+                    )),
+                )
+            }
+        };
 
         let mailbox = settings
             .build_mailbox(&settings.origin_chain, &metrics)
@@ -121,11 +172,11 @@ impl BaseAgent for Validator {
             merkle_tree_hook: merkle_tree_hook.into(),
             merkle_tree_hook_sync,
             validator_announce: validator_announce.into(),
-            signer,
-            signer_instance: Some(Box::new(signer_instance)),
+            publisher,
+            replicated_checkpoint_syncer,
+            signer_instance,
             reorg_period: settings.reorg_period,
             interval: settings.interval,
-            checkpoint_syncer,
             agent_metrics,
             chain_metrics,
             core_metrics: metrics,
@@ -176,13 +227,29 @@ impl BaseAgent for Validator {
             .instrument(info_span!("MetricsUpdater")),
         );
 
-        // report agent metadata
-        self.metadata()
-            .await
-            .expect("Failed to report agent metadata");
+        if let Some(replicated_checkpoint_syncer) = self.replicated_checkpoint_syncer.clone() {
+            tasks.push(
+                replicated_checkpoint_syncer
+                    .spawn_catch_up(self.interval)
+                    .instrument(info_span!("CheckpointStorageCatchUp")),
+            );
+        }
 
-        // announce the validator after spawning the signer task
-        self.announce().await.expect("Failed to announce validator");
+        if let CheckpointPublisher::Sign {
+            signer,
+            checkpoint_syncer,
+        } = &self.publisher
+        {
+            // report agent metadata
+            self.metadata(checkpoint_syncer.as_ref())
+                .await
+                .expect("Failed to report agent metadata");
+
+            // announce the validator after spawning the signer task
+            self.announce(signer, checkpoint_syncer.as_ref())
+                .await
+                .expect("Failed to announce validator");
+        }
 
         // Ensure that the merkle tree hook has count > 0 before we begin indexing
         // messages or submitting checkpoints.
@@ -241,10 +308,10 @@ impl Validator {
             self.interval,
             self.reorg_period.clone(),
             self.merkle_tree_hook.clone(),
-            self.signer.clone(),
-            self.checkpoint_syncer.clone(),
+            self.publisher.clone(),
             Arc::new(self.db.clone()) as Arc<dyn HyperlaneDb>,
             ValidatorSubmitterMetrics::new(&self.core.metrics, &self.origin_chain),
+            self.core.settings.alerts.build(&self.core.metrics),
         );
 
         let tip_tree = self
@@ -307,17 +374,21 @@ impl Validator {
         }
     }
 
-    async fn metadata(&self) -> Result<()> {
-        self.checkpoint_syncer
+    async fn metadata(&self, checkpoint_syncer: &dyn CheckpointSyncer) -> Result<()> {
+        checkpoint_syncer
             .write_metadata(&self.agent_metadata)
             .await?;
 
         Ok(())
     }
 
-    async fn announce(&self) -> Result<()> {
-        let address = self.signer.eth_address();
-        let announcement_location = self.checkpoint_syncer.announcement_location();
+    async fn announce(
+        &self,
+        signer: &SingletonSignerHandle,
+        checkpoint_syncer: &dyn CheckpointSyncer,
+    ) -> Result<()> {
+        let address = signer.eth_address();
+        let announcement_location = checkpoint_syncer.announcement_location();
 
         // Sign and post the validator announcement
         let announcement = Announcement {
@@ -326,8 +397,8 @@ impl Validator {
             mailbox_domain: self.mailbox.domain().id(),
             storage_location: announcement_location.clone(),
         };
-        let signed_announcement = self.signer.sign(announcement.clone()).await?;
-        self.checkpoint_syncer
+        let signed_announcement = signer.sign(announcement.clone()).await?;
+        checkpoint_syncer
             .write_announcement(&signed_announcement)
             .await?;
 
@@ -6,7 +6,7 @@ use cosmrs::proto::cosmos::base::abci::v1beta1::TxResponse;
 use tracing::instrument;
 
 use hyperlane_core::{
-    utils::bytes_to_hex, ChainResult, ContractLocator, HyperlaneChain, HyperlaneContract,
+    log_fields::LogBytes, ChainResult, ContractLocator, HyperlaneChain, HyperlaneContract,
     HyperlaneDomain, HyperlaneMessage, HyperlaneProvider, Mailbox, RawHyperlaneMessage,
     ReorgPeriod, TxCostEstimate, TxOutcome, H256, U256,
 };
@@ -153,7 +153,7 @@ impl Mailbox for CosmosMailbox {
         Ok(ism.digest())
     }
 
-    #[instrument(err, ret, skip(self))]
+    #[instrument(err, ret, skip(self, metadata), fields(metadata=%LogBytes(metadata)))]
     #[allow(clippy::blocks_in_conditions)] // TODO: `rustc` 1.80.1 clippy issue
     async fn process(
         &self,
@@ -177,7 +177,7 @@ impl Mailbox for CosmosMailbox {
         Ok(tx_response_to_outcome(response)?)
     }
 
-    #[instrument(err, ret, skip(self), fields(hyp_message=%message, metadata=%bytes_to_hex(metadata)))]
+    #[instrument(err, ret, skip(self), fields(hyp_message=%message, metadata=%LogBytes(metadata)))]
     #[allow(clippy::blocks_in_conditions)] // TODO: `rustc` 1.80.1 clippy issue
     async fn process_estimate_costs(
         &self,
@@ -1,5 +1,5 @@
 use cosmrs::proto::{cosmos::base::abci::v1beta1::TxResponse, tendermint::Error};
-use hyperlane_core::{ChainResult, ModuleType, TxOutcome, H256, U256};
+use hyperlane_core::{ChainResult, ModuleType, TxOutcome, TxSubmissionPath, H256, U256};
 use url::Url;
 
 pub struct IsmType(pub hyperlane_cosmwasm_interface::ism::IsmType);
@@ -37,5 +37,6 @@ pub fn tx_response_to_outcome(response: TxResponse) -> ChainResult<TxOutcome> {
         executed: response.code == 0,
         gas_used: U256::from(response.gas_used),
         gas_price: U256::one().try_into()?,
+        submission_path: TxSubmissionPath::Public,
     })
 }
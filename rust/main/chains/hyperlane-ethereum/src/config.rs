@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use ethers::providers::Middleware;
 use ethers_core::types::{BlockId, BlockNumber};
 use hyperlane_core::{
@@ -39,6 +41,81 @@ pub struct ConnectionConf {
     pub transaction_overrides: TransactionOverrides,
     /// Operation batching configuration
     pub operation_batch: OperationBatchConfig,
+    /// Endpoint to submit process transactions to instead of the public
+    /// mempool, if any
+    pub private_submission: Option<PrivateSubmissionConf>,
+    /// Number of logs the providers cap `eth_getLogs` responses at, by the
+    /// host of their url, on top of [`DEFAULT_GET_LOGS_CAPS`]. A cap of 0
+    /// means the provider doesn't cap its responses.
+    pub get_logs_caps: HashMap<String, u32>,
+}
+
+/// Number of logs providers are known to silently cap `eth_getLogs`
+/// responses at, by the domain of their urls
+pub const DEFAULT_GET_LOGS_CAPS: &[(&str, u32)] = &[
+    ("alchemy.com", 10_000),
+    ("infura.io", 10_000),
+    ("quiknode.pro", 10_000),
+    ("ankr.com", 10_000),
+];
+
+fn default_get_logs_cap(host: &str) -> Option<u32> {
+    DEFAULT_GET_LOGS_CAPS
+        .iter()
+        .find(|(domain, _)| host == *domain || host.ends_with(&format!(".{domain}")))
+        .map(|(_, cap)| *cap)
+}
+
+impl ConnectionConf {
+    /// The lowest number of logs any of the providers caps `eth_getLogs`
+    /// responses at, if any does. The lowest is used as responses of a
+    /// fallback or quorum provider may come from any of them.
+    pub fn get_logs_cap(&self) -> Option<u32> {
+        let urls = match &self.rpc_connection {
+            RpcConnectionConf::HttpQuorum { urls } | RpcConnectionConf::HttpFallback { urls } => {
+                urls.iter().collect()
+            }
+            RpcConnectionConf::Http { url } | RpcConnectionConf::Ws { url } => vec![url],
+        };
+        urls.into_iter()
+            .filter_map(|url| {
+                let host = url.host_str()?;
+                let cap = match self.get_logs_caps.get(host) {
+                    Some(cap) => *cap,
+                    None => default_get_logs_cap(host)?,
+                };
+                (cap > 0).then_some(cap)
+            })
+            .min()
+    }
+}
+
+/// Default number of blocks to wait for a privately submitted transaction to
+/// be included before falling back to the public mempool
+pub const DEFAULT_PRIVATE_SUBMISSION_FALLBACK_BLOCKS: u32 = 25;
+
+/// Private transaction submission configuration, to keep process transactions
+/// out of the public mempool where they can be front-run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrivateSubmissionConf {
+    /// Url of the private endpoint
+    pub url: Url,
+    /// What kind of endpoint it is
+    pub mode: PrivateSubmissionMode,
+    /// Number of blocks to wait for the transaction to be included before
+    /// broadcasting it to the public mempool
+    pub fallback_after_blocks: u32,
+}
+
+/// Kind of private submission endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrivateSubmissionMode {
+    /// A private RPC accepting `eth_sendRawTransaction`, like Flashbots Protect
+    Rpc,
+    /// A bundle relay accepting `eth_sendBundle` without request signing. The
+    /// transaction is resubmitted as a single transaction bundle for every
+    /// block until it's included.
+    Bundle,
 }
 
 /// Ethereum transaction overrides.
@@ -104,3 +181,45 @@ impl EthereumReorgPeriod {
         Ok(block_id)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn conf(urls: &[&str], get_logs_caps: &[(&str, u32)]) -> ConnectionConf {
+        ConnectionConf {
+            rpc_connection: RpcConnectionConf::HttpFallback {
+                urls: urls.iter().map(|url| url.parse().unwrap()).collect(),
+            },
+            transaction_overrides: Default::default(),
+            operation_batch: Default::default(),
+            private_submission: None,
+            get_logs_caps: get_logs_caps
+                .iter()
+                .map(|(host, cap)| (host.to_string(), *cap))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_get_logs_cap_is_the_lowest_of_the_providers() {
+        assert_eq!(conf(&["https://rpc.example.com"], &[]).get_logs_cap(), None);
+        assert_eq!(
+            conf(&["https://eth-mainnet.g.alchemy.com/v2/key"], &[]).get_logs_cap(),
+            Some(10_000)
+        );
+        let urls = [
+            "https://eth-mainnet.g.alchemy.com/v2/key",
+            "https://rpc.example.com",
+        ];
+        assert_eq!(
+            conf(&urls, &[("rpc.example.com", 5_000)]).get_logs_cap(),
+            Some(5_000)
+        );
+        // Configured caps override the defaults, 0 meaning uncapped
+        assert_eq!(
+            conf(&urls, &[("eth-mainnet.g.alchemy.com", 0)]).get_logs_cap(),
+            None
+        );
+    }
+}
@@ -15,12 +15,14 @@ use hyperlane_core::{
 };
 use tracing::instrument;
 
-use super::utils::{fetch_raw_logs_and_meta, get_finalized_block_number};
+use super::utils::{fetch_logs_splitting_truncated, fetch_raw_logs_and_meta};
 use crate::interfaces::i_interchain_gas_paymaster::{
     GasPaymentFilter, IInterchainGasPaymaster as EthereumInterchainGasPaymasterInternal,
     IINTERCHAINGASPAYMASTER_ABI,
 };
-use crate::{BuildableWithProvider, ConnectionConf, EthereumProvider, EthereumReorgPeriod};
+use crate::{
+    BuildableWithProvider, ConnectionConf, EthereumFinality, EthereumProvider, EthereumReorgPeriod,
+};
 
 impl<M> Display for EthereumInterchainGasPaymasterInternal<M>
 where
@@ -34,6 +36,7 @@ where
 pub struct InterchainGasPaymasterIndexerBuilder {
     pub mailbox_address: H160,
     pub reorg_period: EthereumReorgPeriod,
+    pub reorg_period_fallback: Option<u32>,
 }
 
 #[async_trait]
@@ -44,14 +47,17 @@ impl BuildableWithProvider for InterchainGasPaymasterIndexerBuilder {
     async fn build_with_provider<M: Middleware + 'static>(
         &self,
         provider: M,
-        _conn: &ConnectionConf,
+        conn: &ConnectionConf,
         locator: &ContractLocator,
     ) -> Self::Output {
-        Box::new(EthereumInterchainGasPaymasterIndexer::new(
-            Arc::new(provider),
-            locator,
-            self.reorg_period,
-        ))
+        Box::new(
+            EthereumInterchainGasPaymasterIndexer::new(
+                Arc::new(provider),
+                locator,
+                EthereumFinality::new(self.reorg_period, self.reorg_period_fallback),
+            )
+            .with_get_logs_cap(conn.get_logs_cap()),
+        )
     }
 }
 
@@ -63,7 +69,8 @@ where
 {
     contract: Arc<EthereumInterchainGasPaymasterInternal<M>>,
     provider: Arc<M>,
-    reorg_period: EthereumReorgPeriod,
+    finality: EthereumFinality,
+    get_logs_cap: Option<u32>,
 }
 
 impl<M> EthereumInterchainGasPaymasterIndexer<M>
@@ -71,20 +78,24 @@ where
     M: Middleware + 'static,
 {
     /// Create new EthereumInterchainGasPaymasterIndexer
-    pub fn new(
-        provider: Arc<M>,
-        locator: &ContractLocator,
-        reorg_period: EthereumReorgPeriod,
-    ) -> Self {
+    pub fn new(provider: Arc<M>, locator: &ContractLocator, finality: EthereumFinality) -> Self {
         Self {
             contract: Arc::new(EthereumInterchainGasPaymasterInternal::new(
                 locator.address,
                 provider.clone(),
             )),
             provider,
-            reorg_period,
+            finality,
+            get_logs_cap: None,
         }
     }
+
+    /// Split the block ranges whose `eth_getLogs` responses have as many
+    /// logs as `get_logs_cap`, as they may have been truncated
+    pub fn with_get_logs_cap(mut self, get_logs_cap: Option<u32>) -> Self {
+        self.get_logs_cap = get_logs_cap;
+        self
+    }
 }
 
 #[async_trait]
@@ -99,34 +110,37 @@ where
         &self,
         range: RangeInclusive<u32>,
     ) -> ChainResult<Vec<(Indexed<InterchainGasPayment>, LogMeta)>> {
-        let events = self
-            .contract
-            .gas_payment_filter()
-            .from_block(*range.start())
-            .to_block(*range.end())
-            .query_with_meta()
-            .await?;
+        fetch_logs_splitting_truncated(range, self.get_logs_cap, |range| async move {
+            let events = self
+                .contract
+                .gas_payment_filter()
+                .from_block(*range.start())
+                .to_block(*range.end())
+                .query_with_meta()
+                .await?;
 
-        Ok(events
-            .into_iter()
-            .map(|(log, log_meta)| {
-                (
-                    Indexed::new(InterchainGasPayment {
-                        message_id: H256::from(log.message_id),
-                        destination: log.destination_domain,
-                        payment: log.payment.into(),
-                        gas_amount: log.gas_amount.into(),
-                    }),
-                    log_meta.into(),
-                )
-            })
-            .collect())
+            Ok(events
+                .into_iter()
+                .map(|(log, log_meta)| {
+                    (
+                        Indexed::new(InterchainGasPayment {
+                            message_id: H256::from(log.message_id),
+                            destination: log.destination_domain,
+                            payment: log.payment.into(),
+                            gas_amount: log.gas_amount.into(),
+                        }),
+                        log_meta.into(),
+                    )
+                })
+                .collect())
+        })
+        .await
     }
 
     #[instrument(level = "debug", err, ret, skip(self))]
     #[allow(clippy::blocks_in_conditions)] // TODO: `rustc` 1.80.1 clippy issue
     async fn get_finalized_block_number(&self) -> ChainResult<u32> {
-        get_finalized_block_number(&self.provider, &self.reorg_period).await
+        self.finality.finalized_block_number(&self.provider).await
     }
 
     async fn fetch_logs_by_tx_hash(
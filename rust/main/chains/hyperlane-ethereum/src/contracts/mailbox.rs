@@ -7,37 +7,39 @@ use std::sync::Arc;
 
 use async_trait::async_trait;
 use derive_new::new;
-use ethers::abi::{AbiEncode, Detokenize};
+use ethers::abi::Detokenize;
 use ethers::prelude::Middleware;
 use ethers_contract::builders::ContractCall;
 use ethers_contract::{Multicall, MulticallResult};
 use futures_util::future::join_all;
+use hyperlane_core::abi::encode_process_call;
 use hyperlane_core::rpc_clients::call_and_retry_indefinitely;
 use hyperlane_core::{BatchResult, QueueOperation, ReorgPeriod, H512};
 use itertools::Itertools;
 use tracing::instrument;
 
 use hyperlane_core::{
-    utils::bytes_to_hex, BatchItem, ChainCommunicationError, ChainResult, ContractLocator,
+    log_fields::LogBytes, BatchItem, ChainCommunicationError, ChainResult, ContractLocator,
     HyperlaneAbi, HyperlaneChain, HyperlaneContract, HyperlaneDomain, HyperlaneMessage,
-    HyperlaneProtocolError, HyperlaneProvider, Indexed, Indexer, LogMeta, Mailbox,
+    HyperlaneProtocolError, HyperlaneProvider, IndexMode, Indexed, Indexer, LogMeta, Mailbox,
     RawHyperlaneMessage, SequenceAwareIndexer, TxCostEstimate, TxOutcome, H160, H256, U256,
 };
 
 use crate::error::HyperlaneEthereumError;
 use crate::interfaces::arbitrum_node_interface::ArbitrumNodeInterface;
-use crate::interfaces::i_mailbox::{
-    IMailbox as EthereumMailboxInternal, ProcessCall, IMAILBOX_ABI,
-};
+use crate::interfaces::i_mailbox::{IMailbox as EthereumMailboxInternal, IMAILBOX_ABI};
 use crate::interfaces::mailbox::DispatchFilter;
-use crate::tx::{call_with_reorg_period, fill_tx_gas_params, report_tx};
+use crate::tx::{call_with_reorg_period, fill_tx_gas_params, report_tx_with_private_submission};
 use crate::{
-    BuildableWithProvider, ConnectionConf, EthereumProvider, EthereumReorgPeriod,
-    TransactionOverrides,
+    BuildableWithProvider, ConnectionConf, EthereumFinality, EthereumProvider, EthereumReorgPeriod,
+    PrivateSubmissionConf, TransactionOverrides,
 };
 
 use super::multicall::{self, build_multicall};
-use super::utils::{fetch_raw_logs_and_meta, get_finalized_block_number};
+use super::utils::{
+    check_sequences_complete, fetch_logs_by_sequence, fetch_logs_splitting_truncated,
+    fetch_raw_logs_and_meta,
+};
 
 impl<M> std::fmt::Display for EthereumMailboxInternal<M>
 where
@@ -50,6 +52,8 @@ where
 
 pub struct SequenceIndexerBuilder {
     pub reorg_period: EthereumReorgPeriod,
+    pub reorg_period_fallback: Option<u32>,
+    pub index_mode: IndexMode,
 }
 
 #[async_trait]
@@ -60,19 +64,24 @@ impl BuildableWithProvider for SequenceIndexerBuilder {
     async fn build_with_provider<M: Middleware + 'static>(
         &self,
         provider: M,
-        _conn: &ConnectionConf,
+        conn: &ConnectionConf,
         locator: &ContractLocator,
     ) -> Self::Output {
-        Box::new(EthereumMailboxIndexer::new(
-            Arc::new(provider),
-            locator,
-            self.reorg_period,
-        ))
+        Box::new(
+            EthereumMailboxIndexer::new(
+                Arc::new(provider),
+                locator,
+                EthereumFinality::new(self.reorg_period, self.reorg_period_fallback),
+                self.index_mode,
+            )
+            .with_get_logs_cap(conn.get_logs_cap()),
+        )
     }
 }
 
 pub struct DeliveryIndexerBuilder {
     pub reorg_period: EthereumReorgPeriod,
+    pub reorg_period_fallback: Option<u32>,
 }
 
 #[async_trait]
@@ -83,14 +92,18 @@ impl BuildableWithProvider for DeliveryIndexerBuilder {
     async fn build_with_provider<M: Middleware + 'static>(
         &self,
         provider: M,
-        _conn: &ConnectionConf,
+        conn: &ConnectionConf,
         locator: &ContractLocator,
     ) -> Self::Output {
-        Box::new(EthereumMailboxIndexer::new(
-            Arc::new(provider),
-            locator,
-            self.reorg_period,
-        ))
+        Box::new(
+            EthereumMailboxIndexer::new(
+                Arc::new(provider),
+                locator,
+                EthereumFinality::new(self.reorg_period, self.reorg_period_fallback),
+                IndexMode::Block,
+            )
+            .with_get_logs_cap(conn.get_logs_cap()),
+        )
     }
 }
 
@@ -102,7 +115,9 @@ where
 {
     contract: Arc<EthereumMailboxInternal<M>>,
     provider: Arc<M>,
-    reorg_period: EthereumReorgPeriod,
+    finality: EthereumFinality,
+    index_mode: IndexMode,
+    get_logs_cap: Option<u32>,
 }
 
 impl<M> EthereumMailboxIndexer<M>
@@ -113,7 +128,8 @@ where
     pub fn new(
         provider: Arc<M>,
         locator: &ContractLocator,
-        reorg_period: EthereumReorgPeriod,
+        finality: EthereumFinality,
+        index_mode: IndexMode,
     ) -> Self {
         let contract = Arc::new(EthereumMailboxInternal::new(
             locator.address,
@@ -122,29 +138,25 @@ where
         Self {
             contract,
             provider,
-            reorg_period,
+            finality,
+            index_mode,
+            get_logs_cap: None,
         }
     }
 
-    #[instrument(level = "debug", err, ret, skip(self))]
-    async fn get_finalized_block_number(&self) -> ChainResult<u32> {
-        get_finalized_block_number(&self.provider, &self.reorg_period).await
+    /// Split the block ranges whose `eth_getLogs` responses have as many
+    /// logs as `get_logs_cap`, as they may have been truncated
+    pub fn with_get_logs_cap(mut self, get_logs_cap: Option<u32>) -> Self {
+        self.get_logs_cap = get_logs_cap;
+        self
     }
-}
 
-#[async_trait]
-impl<M> Indexer<HyperlaneMessage> for EthereumMailboxIndexer<M>
-where
-    M: Middleware + 'static,
-{
+    #[instrument(level = "debug", err, ret, skip(self))]
     async fn get_finalized_block_number(&self) -> ChainResult<u32> {
-        self.get_finalized_block_number().await
+        self.finality.finalized_block_number(&self.provider).await
     }
 
-    /// Note: This call may return duplicates depending on the provider used
-    #[instrument(err, skip(self))]
-    #[allow(clippy::blocks_in_conditions)] // TODO: `rustc` 1.80.1 clippy issue
-    async fn fetch_logs_in_range(
+    async fn fetch_dispatches_in_blocks(
         &self,
         range: RangeInclusive<u32>,
     ) -> ChainResult<Vec<(Indexed<HyperlaneMessage>, LogMeta)>> {
@@ -167,6 +179,52 @@ where
         events.sort_by(|a, b| a.0.inner().nonce.cmp(&b.0.inner().nonce));
         Ok(events)
     }
+}
+
+#[async_trait]
+impl<M> Indexer<HyperlaneMessage> for EthereumMailboxIndexer<M>
+where
+    M: Middleware + 'static,
+{
+    async fn get_finalized_block_number(&self) -> ChainResult<u32> {
+        self.get_finalized_block_number().await
+    }
+
+    /// Note: This call may return duplicates depending on the provider used
+    ///
+    /// In `IndexMode::Sequence` the range is a range of nonces, and only the
+    /// blocks those messages were dispatched in are fetched.
+    #[instrument(err, skip(self))]
+    #[allow(clippy::blocks_in_conditions)] // TODO: `rustc` 1.80.1 clippy issue
+    async fn fetch_logs_in_range(
+        &self,
+        range: RangeInclusive<u32>,
+    ) -> ChainResult<Vec<(Indexed<HyperlaneMessage>, LogMeta)>> {
+        match self.index_mode {
+            IndexMode::Block => {
+                fetch_logs_splitting_truncated(range, self.get_logs_cap, |range| async move {
+                    let logs = self.fetch_dispatches_in_blocks(range.clone()).await?;
+                    let count = self.contract.nonce().block(u64::from(*range.end())).call();
+                    check_sequences_complete(&range, logs, count.await.map_err(Into::into))
+                })
+                .await
+            }
+            IndexMode::Sequence => {
+                let tip = self.get_finalized_block_number().await?;
+                fetch_logs_by_sequence(
+                    range,
+                    tip,
+                    |block| async move {
+                        Ok::<_, ChainCommunicationError>(
+                            self.contract.nonce().block(u64::from(block)).call().await?,
+                        )
+                    },
+                    |block| self.fetch_dispatches_in_blocks(block..=block),
+                )
+                .await
+            }
+        }
+    }
 
     async fn fetch_logs_by_tx_hash(
         &self,
@@ -223,16 +281,19 @@ where
         &self,
         range: RangeInclusive<u32>,
     ) -> ChainResult<Vec<(Indexed<H256>, LogMeta)>> {
-        Ok(self
-            .contract
-            .process_id_filter()
-            .from_block(*range.start())
-            .to_block(*range.end())
-            .query_with_meta()
-            .await?
-            .into_iter()
-            .map(|(event, meta)| (Indexed::new(H256::from(event.message_id)), meta.into()))
-            .collect())
+        fetch_logs_splitting_truncated(range, self.get_logs_cap, |range| async move {
+            Ok(self
+                .contract
+                .process_id_filter()
+                .from_block(*range.start())
+                .to_block(*range.end())
+                .query_with_meta()
+                .await?
+                .into_iter()
+                .map(|(event, meta)| (Indexed::new(H256::from(event.message_id)), meta.into()))
+                .collect())
+        })
+        .await
     }
 }
 
@@ -315,25 +376,28 @@ where
         message: &HyperlaneMessage,
         metadata: &[u8],
         tx_gas_estimate: Option<U256>,
+        gas_price_ceiling: Option<U256>,
     ) -> ChainResult<ContractCall<M, ()>> {
         let mut tx = self.contract.process(
             metadata.to_vec().into(),
-            RawHyperlaneMessage::from(message).to_vec().into(),
+            RawHyperlaneMessage::from(message).into(),
         );
         if let Some(gas_estimate) = tx_gas_estimate {
             tx = tx.gas(gas_estimate);
         }
-        self.add_gas_overrides(tx).await
+        self.add_gas_overrides(tx, gas_price_ceiling).await
     }
 
     async fn add_gas_overrides<D: Detokenize>(
         &self,
         tx: ContractCall<M, D>,
+        gas_price_ceiling: Option<U256>,
     ) -> ChainResult<ContractCall<M, D>> {
         fill_tx_gas_params(
             tx,
             self.provider.clone(),
             &self.conn.transaction_overrides.clone(),
+            gas_price_ceiling,
         )
         .await
     }
@@ -382,6 +446,7 @@ where
             call,
             provider: self.provider.clone(),
             transaction_overrides: self.conn.transaction_overrides.clone(),
+            private_submission: self.conn.private_submission.clone(),
         }
     }
 }
@@ -418,14 +483,24 @@ pub struct SubmittableBatch<M> {
     pub call: ContractCall<M, Vec<MulticallResult>>,
     provider: Arc<M>,
     transaction_overrides: TransactionOverrides,
+    private_submission: Option<PrivateSubmissionConf>,
 }
 
 impl<M: Middleware + 'static> SubmittableBatch<M> {
     pub async fn submit(self) -> ChainResult<TxOutcome> {
-        let call_with_gas_overrides =
-            fill_tx_gas_params(self.call, self.provider, &self.transaction_overrides).await?;
-        let outcome = report_tx(call_with_gas_overrides).await?;
-        Ok(outcome.into())
+        let call_with_gas_overrides = fill_tx_gas_params(
+            self.call,
+            self.provider.clone(),
+            &self.transaction_overrides,
+            None,
+        )
+        .await?;
+        report_tx_with_private_submission(
+            call_with_gas_overrides,
+            self.provider,
+            self.private_submission.as_ref(),
+        )
+        .await
     }
 }
 
@@ -487,18 +562,33 @@ where
             .into())
     }
 
-    #[instrument(skip(self), fields(metadata=%bytes_to_hex(metadata)))]
     async fn process(
         &self,
         message: &HyperlaneMessage,
         metadata: &[u8],
         tx_gas_limit: Option<U256>,
+    ) -> ChainResult<TxOutcome> {
+        self.process_with_gas_price_ceiling(message, metadata, tx_gas_limit, None)
+            .await
+    }
+
+    #[instrument(skip(self), fields(metadata=%LogBytes(metadata)))]
+    async fn process_with_gas_price_ceiling(
+        &self,
+        message: &HyperlaneMessage,
+        metadata: &[u8],
+        tx_gas_limit: Option<U256>,
+        max_gas_price: Option<U256>,
     ) -> ChainResult<TxOutcome> {
         let contract_call = self
-            .process_contract_call(message, metadata, tx_gas_limit)
+            .process_contract_call(message, metadata, tx_gas_limit, max_gas_price)
             .await?;
-        let receipt = report_tx(contract_call).await?;
-        Ok(receipt.into())
+        report_tx_with_private_submission(
+            contract_call,
+            self.provider.clone(),
+            self.conn.private_submission.as_ref(),
+        )
+        .await
     }
 
     #[instrument(skip(self, ops), fields(size=%ops.len()))]
@@ -520,6 +610,7 @@ where
                     &batch_item.data,
                     &batch_item.submission_data.metadata,
                     Some(batch_item.submission_data.gas_limit),
+                    None,
                 )
                 .await
             })
@@ -533,13 +624,15 @@ where
         batch_simulation.try_submit().await
     }
 
-    #[instrument(skip(self), fields(msg=%message, metadata=%bytes_to_hex(metadata)))]
+    #[instrument(skip(self), fields(msg=%message, metadata=%LogBytes(metadata)))]
     async fn process_estimate_costs(
         &self,
         message: &HyperlaneMessage,
         metadata: &[u8],
     ) -> ChainResult<TxCostEstimate> {
-        let contract_call = self.process_contract_call(message, metadata, None).await?;
+        let contract_call = self
+            .process_contract_call(message, metadata, None, None)
+            .await?;
         let gas_limit = contract_call
             .tx
             .gas()
@@ -582,12 +675,7 @@ where
     }
 
     fn process_calldata(&self, message: &HyperlaneMessage, metadata: &[u8]) -> Vec<u8> {
-        let process_call = ProcessCall {
-            message: RawHyperlaneMessage::from(message).to_vec().into(),
-            metadata: metadata.to_vec().into(),
-        };
-
-        AbiEncode::encode(process_call)
+        encode_process_call(metadata, message)
     }
 }
 
@@ -603,7 +691,7 @@ impl HyperlaneAbi for EthereumMailboxAbi {
 
 #[cfg(test)]
 mod test {
-    use std::{str::FromStr, sync::Arc};
+    use std::{ops::RangeInclusive, str::FromStr, sync::Arc};
 
     use ethers::{
         providers::{MockProvider, Provider},
@@ -611,10 +699,14 @@ mod test {
     };
 
     use hyperlane_core::{
-        ContractLocator, HyperlaneDomain, HyperlaneMessage, KnownHyperlaneDomain, Mailbox,
-        TxCostEstimate, H160, H256, U256,
+        accumulator::incremental::IncrementalMerkle, ChainCommunicationError, ChainResult,
+        ContractLocator, HyperlaneDomain, HyperlaneMessage, Indexed, KnownHyperlaneDomain, LogMeta,
+        Mailbox, TxCostEstimate, H160, H256, U256,
     };
 
+    use crate::contracts::utils::{
+        check_sequences_complete, fetch_logs_by_sequence, fetch_logs_splitting_truncated,
+    };
     use crate::{contracts::EthereumMailbox, ConnectionConf, RpcConnectionConf};
 
     /// An amount of gas to add to the estimated gas
@@ -634,6 +726,8 @@ mod test {
             },
             transaction_overrides: Default::default(),
             operation_batch: Default::default(),
+            private_submission: None,
+            get_logs_caps: Default::default(),
         };
 
         let mailbox = EthereumMailbox::new(
@@ -756,4 +850,212 @@ mod test {
             },
         );
     }
+
+    #[tokio::test]
+    async fn test_process_is_refused_above_the_gas_price_ceiling() {
+        let (mailbox, mock_provider) =
+            get_test_mailbox(HyperlaneDomain::Known(KnownHyperlaneDomain::Ethereum));
+
+        let message = HyperlaneMessage::default();
+        let metadata: Vec<u8> = vec![];
+        let gwei = |amount: &str| -> U256 {
+            EthersU256::from(ethers::utils::parse_units(amount, "gwei").unwrap()).into()
+        };
+
+        // The MockProvider responses we push are processed in LIFO
+        // order, so we start with the final RPCs and work toward the first
+        // RPCs
+
+        // RPC 3: eth_gasPrice from the fill_tx_gas_params call in process_contract_call,
+        // as the latest block has no base fee. Return a 600 gwei spike
+        mock_provider.push(gwei("600")).unwrap();
+
+        let latest_block: Block<Transaction> = Block {
+            gas_limit: ethers::types::U256::MAX,
+            ..Block::<Transaction>::default()
+        };
+        // RPC 2: eth_getBlockByNumber from the fill_tx_gas_params call in process_contract_call
+        // to get the latest block gas limit and for eip 1559 fee estimation
+        mock_provider.push(latest_block).unwrap();
+
+        // RPC 1: eth_estimateGas from the estimate_gas call in process_contract_call
+        mock_provider.push(U256::from(1000000u32)).unwrap();
+
+        let err = mailbox
+            .process_with_gas_price_ceiling(&message, &metadata, None, Some(gwei("500")))
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            err,
+            ChainCommunicationError::GasPriceCeilingExceeded { gas_price, ceiling }
+                if gas_price == gwei("600") && ceiling == gwei("500")
+        ));
+    }
+
+    /// Mocked mailbox state: the messages dispatched in each block
+    struct MockDispatches(Vec<Vec<HyperlaneMessage>>);
+
+    impl MockDispatches {
+        fn new() -> Self {
+            let mut nonce = 0;
+            let blocks = (0..40u32)
+                .map(|block| {
+                    // empty blocks, and blocks with several dispatches
+                    let count = [0, 1, 0, 3, 0, 0, 2][block as usize % 7];
+                    (0..count)
+                        .map(|_| {
+                            nonce += 1;
+                            HyperlaneMessage {
+                                nonce: nonce - 1,
+                                body: vec![block as u8; block as usize],
+                                ..Default::default()
+                            }
+                        })
+                        .collect()
+                })
+                .collect();
+            Self(blocks)
+        }
+
+        fn tip(&self) -> u32 {
+            self.0.len() as u32 - 1
+        }
+
+        fn count(&self) -> u32 {
+            self.0.iter().map(Vec::len).sum::<usize>() as u32
+        }
+
+        fn nonce_at(&self, block: u32) -> ChainResult<u32> {
+            Ok(self.0[..=block as usize]
+                .iter()
+                .map(Vec::len)
+                .sum::<usize>() as u32)
+        }
+
+        fn logs_in_range(
+            &self,
+            range: RangeInclusive<u32>,
+        ) -> ChainResult<Vec<(Indexed<HyperlaneMessage>, LogMeta)>> {
+            Ok(range
+                .flat_map(|block| {
+                    self.0[block as usize]
+                        .iter()
+                        .enumerate()
+                        .map(move |(log_index, message)| {
+                            let meta = LogMeta {
+                                block_number: block as u64,
+                                block_hash: H256::from_low_u64_be(block as u64),
+                                log_index: U256::from(log_index),
+                                ..Default::default()
+                            };
+                            (message.clone().into(), meta)
+                        })
+                })
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn sequence_mode_matches_block_mode() {
+        let chain = &MockDispatches::new();
+        let block_mode = chain.logs_in_range(0..=chain.tip()).unwrap();
+
+        let mut sequence_mode = vec![];
+        let chunk_size = 4;
+        for start in (0..chain.count()).step_by(chunk_size) {
+            let end = (start + chunk_size as u32 - 1).min(chain.count() - 1);
+            sequence_mode.extend(
+                fetch_logs_by_sequence(
+                    start..=end,
+                    chain.tip(),
+                    |block| async move { chain.nonce_at(block) },
+                    |block| async move { chain.logs_in_range(block..=block) },
+                )
+                .await
+                .unwrap(),
+            );
+        }
+
+        let records = |logs: &[(Indexed<HyperlaneMessage>, LogMeta)]| {
+            logs.iter()
+                .map(|(message, meta)| (message.inner().to_vec(), meta.clone()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(records(&sequence_mode), records(&block_mode));
+
+        let root = |logs: &[(Indexed<HyperlaneMessage>, LogMeta)]| {
+            let mut tree = IncrementalMerkle::default();
+            logs.iter()
+                .for_each(|(message, _)| tree.ingest(message.inner().id()));
+            tree.root()
+        };
+        assert_eq!(root(&sequence_mode), root(&block_mode));
+    }
+
+    #[tokio::test]
+    async fn sequence_mode_rejects_undispatched_nonces() {
+        let chain = &MockDispatches::new();
+        let result = fetch_logs_by_sequence(
+            chain.count()..=chain.count(),
+            chain.tip(),
+            |block| async move { chain.nonce_at(block) },
+            |block| async move { chain.logs_in_range(block..=block) },
+        )
+        .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn block_mode_splits_capped_responses() {
+        let chain = &MockDispatches::new();
+        let all_logs = chain.logs_in_range(0..=chain.tip()).unwrap();
+        let cap = 5;
+        // a provider silently dropping the logs past its cap
+        let capped = |range: RangeInclusive<u32>| async move {
+            let mut logs = chain.logs_in_range(range)?;
+            logs.truncate(cap);
+            Ok::<_, ChainCommunicationError>(logs)
+        };
+
+        let logs = fetch_logs_splitting_truncated(0..=chain.tip(), Some(cap as u32), capped)
+            .await
+            .unwrap();
+        assert_eq!(logs, all_logs);
+    }
+
+    #[tokio::test]
+    async fn block_mode_completes_ranges_once_nonces_are_contiguous() {
+        let chain = &MockDispatches::new();
+        let all_logs = chain.logs_in_range(0..=chain.tip()).unwrap();
+        // a provider whose cap isn't known, only the nonce check catches it
+        let capped = |range: RangeInclusive<u32>| async move {
+            let mut logs = chain.logs_in_range(range.clone())?;
+            logs.truncate(5);
+            check_sequences_complete(&range, logs, chain.nonce_at(*range.end()))
+        };
+        let logs = fetch_logs_splitting_truncated(0..=chain.tip(), None, capped)
+            .await
+            .unwrap();
+        assert_eq!(logs, all_logs);
+
+        // a block never returned in full is never marked complete
+        let dense_block = 3;
+        let dropping = |range: RangeInclusive<u32>| async move {
+            let logs = chain
+                .logs_in_range(range.clone())?
+                .into_iter()
+                .filter(|(_, meta)| {
+                    meta.block_number != dense_block as u64 || meta.log_index < U256::from(2)
+                })
+                .collect();
+            check_sequences_complete(&range, logs, chain.nonce_at(*range.end()))
+        };
+        let result = fetch_logs_splitting_truncated(0..=chain.tip(), None, dropping).await;
+        assert!(matches!(
+            result,
+            Err(ChainCommunicationError::PossiblyTruncatedResponse { from, to, .. })
+                if from == dense_block && to == dense_block
+        ));
+    }
 }
@@ -9,18 +9,23 @@ use hyperlane_core::rpc_clients::call_and_retry_indefinitely;
 use tracing::instrument;
 
 use hyperlane_core::{
-    ChainResult, Checkpoint, ContractLocator, HyperlaneChain, HyperlaneContract, HyperlaneDomain,
-    HyperlaneProvider, Indexed, Indexer, LogMeta, MerkleTreeHook, MerkleTreeInsertion, ReorgPeriod,
-    SequenceAwareIndexer, H256, H512,
+    ChainCommunicationError, ChainResult, Checkpoint, ContractLocator, HyperlaneChain,
+    HyperlaneContract, HyperlaneDomain, HyperlaneProvider, IndexMode, Indexed, Indexer, LogMeta,
+    MerkleTreeHook, MerkleTreeInsertion, ReorgPeriod, SequenceAwareIndexer, H256, H512,
 };
 
 use crate::interfaces::merkle_tree_hook::{
     InsertedIntoTreeFilter, MerkleTreeHook as MerkleTreeHookContract, Tree,
 };
 use crate::tx::call_with_reorg_period;
-use crate::{BuildableWithProvider, ConnectionConf, EthereumProvider, EthereumReorgPeriod};
+use crate::{
+    BuildableWithProvider, ConnectionConf, EthereumFinality, EthereumProvider, EthereumReorgPeriod,
+};
 
-use super::utils::{fetch_raw_logs_and_meta, get_finalized_block_number};
+use super::utils::{
+    check_sequences_complete, fetch_logs_by_sequence, fetch_logs_splitting_truncated,
+    fetch_raw_logs_and_meta,
+};
 
 // We don't need the reverse of this impl, so it's ok to disable the clippy lint
 #[allow(clippy::from_over_into)]
@@ -58,6 +63,8 @@ impl BuildableWithProvider for MerkleTreeHookBuilder {
 
 pub struct MerkleTreeHookIndexerBuilder {
     pub reorg_period: EthereumReorgPeriod,
+    pub reorg_period_fallback: Option<u32>,
+    pub index_mode: IndexMode,
 }
 
 #[async_trait]
@@ -68,14 +75,18 @@ impl BuildableWithProvider for MerkleTreeHookIndexerBuilder {
     async fn build_with_provider<M: Middleware + 'static>(
         &self,
         provider: M,
-        _conn: &ConnectionConf,
+        conn: &ConnectionConf,
         locator: &ContractLocator,
     ) -> Self::Output {
-        Box::new(EthereumMerkleTreeHookIndexer::new(
-            Arc::new(provider),
-            locator,
-            self.reorg_period,
-        ))
+        Box::new(
+            EthereumMerkleTreeHookIndexer::new(
+                Arc::new(provider),
+                locator,
+                EthereumFinality::new(self.reorg_period, self.reorg_period_fallback),
+                self.index_mode,
+            )
+            .with_get_logs_cap(conn.get_logs_cap()),
+        )
     }
 }
 
@@ -87,7 +98,9 @@ where
 {
     contract: Arc<MerkleTreeHookContract<M>>,
     provider: Arc<M>,
-    reorg_period: EthereumReorgPeriod,
+    finality: EthereumFinality,
+    index_mode: IndexMode,
+    get_logs_cap: Option<u32>,
 }
 
 impl<M> EthereumMerkleTreeHookIndexer<M>
@@ -98,7 +111,8 @@ where
     pub fn new(
         provider: Arc<M>,
         locator: &ContractLocator,
-        reorg_period: EthereumReorgPeriod,
+        finality: EthereumFinality,
+        index_mode: IndexMode,
     ) -> Self {
         Self {
             contract: Arc::new(MerkleTreeHookContract::new(
@@ -106,20 +120,20 @@ where
                 provider.clone(),
             )),
             provider,
-            reorg_period,
+            finality,
+            index_mode,
+            get_logs_cap: None,
         }
     }
-}
 
-#[async_trait]
-impl<M> Indexer<MerkleTreeInsertion> for EthereumMerkleTreeHookIndexer<M>
-where
-    M: Middleware + 'static,
-{
-    /// Note: This call may return duplicates depending on the provider used
-    #[instrument(err, skip(self))]
-    #[allow(clippy::blocks_in_conditions)] // TODO: `rustc` 1.80.1 clippy issue
-    async fn fetch_logs_in_range(
+    /// Split the block ranges whose `eth_getLogs` responses have as many
+    /// logs as `get_logs_cap`, as they may have been truncated
+    pub fn with_get_logs_cap(mut self, get_logs_cap: Option<u32>) -> Self {
+        self.get_logs_cap = get_logs_cap;
+        self
+    }
+
+    async fn fetch_insertions_in_blocks(
         &self,
         range: RangeInclusive<u32>,
     ) -> ChainResult<Vec<(Indexed<MerkleTreeInsertion>, LogMeta)>> {
@@ -142,11 +156,53 @@ where
             .collect();
         Ok(logs)
     }
+}
+
+#[async_trait]
+impl<M> Indexer<MerkleTreeInsertion> for EthereumMerkleTreeHookIndexer<M>
+where
+    M: Middleware + 'static,
+{
+    /// Note: This call may return duplicates depending on the provider used
+    ///
+    /// In `IndexMode::Sequence` the range is a range of leaf indices, and only
+    /// the blocks those leaves were inserted in are fetched.
+    #[instrument(err, skip(self))]
+    #[allow(clippy::blocks_in_conditions)] // TODO: `rustc` 1.80.1 clippy issue
+    async fn fetch_logs_in_range(
+        &self,
+        range: RangeInclusive<u32>,
+    ) -> ChainResult<Vec<(Indexed<MerkleTreeInsertion>, LogMeta)>> {
+        match self.index_mode {
+            IndexMode::Block => {
+                fetch_logs_splitting_truncated(range, self.get_logs_cap, |range| async move {
+                    let logs = self.fetch_insertions_in_blocks(range.clone()).await?;
+                    let count = self.contract.count().block(u64::from(*range.end())).call();
+                    check_sequences_complete(&range, logs, count.await.map_err(Into::into))
+                })
+                .await
+            }
+            IndexMode::Sequence => {
+                let tip = self.get_finalized_block_number().await?;
+                fetch_logs_by_sequence(
+                    range,
+                    tip,
+                    |block| async move {
+                        Ok::<_, ChainCommunicationError>(
+                            self.contract.count().block(u64::from(block)).call().await?,
+                        )
+                    },
+                    |block| self.fetch_insertions_in_blocks(block..=block),
+                )
+                .await
+            }
+        }
+    }
 
     #[instrument(level = "debug", err, skip(self))]
     #[allow(clippy::blocks_in_conditions)] // TODO: `rustc` 1.80.1 clippy issue
     async fn get_finalized_block_number(&self) -> ChainResult<u32> {
-        get_finalized_block_number(&self.provider, &self.reorg_period).await
+        self.finality.finalized_block_number(&self.provider).await
     }
 
     async fn fetch_logs_by_tx_hash(
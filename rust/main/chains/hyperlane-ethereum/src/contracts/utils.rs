@@ -1,4 +1,9 @@
-use std::sync::Arc;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    future::Future,
+    ops::RangeInclusive,
+    sync::Arc,
+};
 
 use ethers::{
     abi::RawLog,
@@ -6,8 +11,8 @@ use ethers::{
     types::{H160 as EthersH160, H256 as EthersH256},
 };
 use ethers_contract::{ContractError, EthEvent, LogMeta as EthersLogMeta};
-use hyperlane_core::{ChainCommunicationError, ChainResult, LogMeta, H512};
-use tracing::instrument;
+use hyperlane_core::{ChainCommunicationError, ChainResult, Indexed, LogMeta, Sequenced, H512};
+use tracing::{debug, instrument, warn};
 
 use crate::EthereumReorgPeriod;
 
@@ -77,3 +82,191 @@ where
 
     Ok(number)
 }
+
+/// Fetch the logs with sequences in `sequences`, visiting only the blocks
+/// they were emitted in, for `IndexMode::Sequence` on chains whose log
+/// support can't be relied on for wide block ranges.
+///
+/// The block of each sequence is found by binary searching the contract's
+/// sequence count at historical blocks, via `count_at`, and only that block's
+/// logs are then fetched, via `logs_in_block`. This costs more RPC calls than
+/// scanning block ranges, but is fully deterministic, and since the logs come
+/// from the same decoding path the records are identical to block mode.
+pub async fn fetch_logs_by_sequence<T, C, CFut, L, LFut>(
+    sequences: RangeInclusive<u32>,
+    tip: u32,
+    count_at: C,
+    logs_in_block: L,
+) -> ChainResult<Vec<(Indexed<T>, LogMeta)>>
+where
+    T: Send + Sync + 'static,
+    C: Fn(u32) -> CFut,
+    CFut: Future<Output = ChainResult<u32>>,
+    L: Fn(u32) -> LFut,
+    LFut: Future<Output = ChainResult<Vec<(Indexed<T>, LogMeta)>>>,
+{
+    let mut logs = BTreeMap::new();
+    let mut next_sequence = *sequences.start();
+    let mut lowest_block = 0;
+    while next_sequence <= *sequences.end() {
+        let block = find_sequence_block(next_sequence, lowest_block, tip, &count_at).await?;
+        for (log, meta) in logs_in_block(block).await? {
+            let Some(sequence) = log.sequence() else {
+                continue;
+            };
+            if sequence >= next_sequence && sequences.contains(&sequence) {
+                logs.entry(sequence).or_insert((log, meta));
+            }
+        }
+        if !logs.contains_key(&next_sequence) {
+            return Err(ChainCommunicationError::CustomError(format!(
+                "Sequence {next_sequence} missing from the logs of block {block}"
+            )));
+        }
+        // sequences within a block are contiguous, so everything up to the
+        // highest one found is now covered
+        next_sequence = logs.keys().next_back().map_or(next_sequence, |s| s + 1);
+        lowest_block = block + 1;
+    }
+    Ok(logs.into_values().collect())
+}
+
+/// Binary search for the first block in `[low, tip]` at the end of which the
+/// sequence count exceeds `sequence`, i.e. the block `sequence` was emitted in.
+async fn find_sequence_block<C, CFut>(
+    sequence: u32,
+    low: u32,
+    tip: u32,
+    count_at: &C,
+) -> ChainResult<u32>
+where
+    C: Fn(u32) -> CFut,
+    CFut: Future<Output = ChainResult<u32>>,
+{
+    if count_at(tip).await? <= sequence {
+        return Err(ChainCommunicationError::CustomError(format!(
+            "Sequence {sequence} has not been emitted as of block {tip}"
+        )));
+    }
+    let (mut low, mut high) = (low.min(tip), tip);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if count_at(mid).await? > sequence {
+            high = mid;
+        } else {
+            low = mid + 1;
+        }
+    }
+    Ok(high)
+}
+
+/// Number of logs from which a response whose logs all sit in the first half
+/// of its block range is suspected to have been truncated
+const SUSPICIOUS_DENSITY_MIN_LOGS: usize = 1_000;
+
+/// Fetch the logs emitted in `range` via `fetch`, splitting any range whose
+/// response may have been silently truncated by the provider and fetching
+/// both halves again.
+///
+/// A response is suspected to be truncated if it has as many logs as the
+/// provider caps responses at, or if it's dense and its logs end well before
+/// the end of the range. `fetch` may also report a truncated response itself,
+/// e.g. after checking the sequences it got back are complete. A single block
+/// range that is still truncated fails with the error.
+pub async fn fetch_logs_splitting_truncated<T, F, Fut>(
+    range: RangeInclusive<u32>,
+    get_logs_cap: Option<u32>,
+    fetch: F,
+) -> ChainResult<Vec<(T, LogMeta)>>
+where
+    F: Fn(RangeInclusive<u32>) -> Fut,
+    Fut: Future<Output = ChainResult<Vec<(T, LogMeta)>>>,
+{
+    let mut logs = vec![];
+    let mut pending = vec![range];
+    while let Some(range) = pending.pop() {
+        let result = fetch(range.clone())
+            .await
+            .and_then(|fetched| check_not_truncated(&range, fetched, get_logs_cap));
+        match result {
+            Ok(fetched) => logs.extend(fetched),
+            Err(err @ ChainCommunicationError::PossiblyTruncatedResponse { .. })
+                if range.start() < range.end() =>
+            {
+                let (start, end) = (*range.start(), *range.end());
+                let mid = start + (end - start) / 2;
+                warn!(?range, error = %err, "Splitting block range of possibly truncated logs");
+                // the lower half is popped first, keeping the logs in order
+                pending.push(mid + 1..=end);
+                pending.push(start..=mid);
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(logs)
+}
+
+fn check_not_truncated<T>(
+    range: &RangeInclusive<u32>,
+    logs: Vec<(T, LogMeta)>,
+    get_logs_cap: Option<u32>,
+) -> ChainResult<Vec<(T, LogMeta)>> {
+    let (start, end) = (*range.start(), *range.end());
+    let truncated = |reason: String| ChainCommunicationError::PossiblyTruncatedResponse {
+        from: start,
+        to: end,
+        logs: logs.len(),
+        reason,
+    };
+    if let Some(cap) = get_logs_cap.filter(|cap| logs.len() >= *cap as usize) {
+        return Err(truncated(format!(
+            "the provider caps responses at {cap} logs"
+        )));
+    }
+    let last_block = logs.iter().map(|(_, meta)| meta.block_number).max();
+    if let Some(last_block) = last_block {
+        let middle = u64::from(start) + u64::from(end - start) / 2;
+        if logs.len() >= SUSPICIOUS_DENSITY_MIN_LOGS && last_block < middle {
+            return Err(truncated(format!(
+                "the logs end at block {last_block}, in the first half of the range"
+            )));
+        }
+    }
+    Ok(logs)
+}
+
+/// Check the sequenced logs fetched for `range` are complete, given the
+/// contract's sequence count at the end of the range, if it could be read.
+///
+/// The sequences must be contiguous from the lowest one fetched up to the
+/// count. A missing count, e.g. from a non-archive node, skips the check.
+pub fn check_sequences_complete<T>(
+    range: &RangeInclusive<u32>,
+    logs: Vec<(Indexed<T>, LogMeta)>,
+    count_at_end: ChainResult<u32>,
+) -> ChainResult<Vec<(Indexed<T>, LogMeta)>>
+where
+    T: Send + Sync + 'static,
+{
+    let count = match count_at_end {
+        Ok(count) => count,
+        Err(err) => {
+            debug!(?range, ?err, "Skipping the sequence check of fetched logs");
+            return Ok(logs);
+        }
+    };
+    let sequences: BTreeSet<u32> = logs.iter().filter_map(|(log, _)| log.sequence()).collect();
+    let Some(lowest) = sequences.first().copied() else {
+        return Ok(logs);
+    };
+    let expected = count.saturating_sub(lowest);
+    if (sequences.len() as u32) < expected {
+        return Err(ChainCommunicationError::PossiblyTruncatedResponse {
+            from: *range.start(),
+            to: *range.end(),
+            logs: logs.len(),
+            reason: format!("got {} of the sequences {lowest}..{count}", sequences.len()),
+        });
+    }
+    Ok(logs)
+}
@@ -0,0 +1,230 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use ethers::{prelude::Middleware, types::BlockId};
+use hyperlane_core::{ChainCommunicationError, ChainResult};
+use tracing::{instrument, warn};
+
+use crate::{get_finalized_block_number, EthereumReorgPeriod};
+
+/// How long a block tag resolved to a block number is reused for. Indexers
+/// ask for the finalized block several times per tick, which this saves RPC
+/// calls on.
+const TAG_RESOLUTION_TTL: Duration = Duration::from_secs(1);
+
+/// Resolves the latest finalized block of a chain for its indexers.
+///
+/// Numeric reorg periods count confirmations back from the latest block. Tags
+/// are resolved through the provider instead, unless the provider turns out
+/// not to support the tag, in which case the numeric fallback is used from
+/// then on.
+#[derive(Debug, Clone)]
+pub struct EthereumFinality {
+    reorg_period: EthereumReorgPeriod,
+    fallback: Option<u32>,
+    state: Arc<Mutex<TagState>>,
+}
+
+#[derive(Debug, Default)]
+struct TagState {
+    support: TagSupport,
+    resolved: Option<(Instant, u32)>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum TagSupport {
+    /// The tag was never resolved
+    #[default]
+    Unknown,
+    Supported,
+    Unsupported,
+}
+
+impl EthereumFinality {
+    /// Finality by `reorg_period`, falling back to `fallback` confirmations if
+    /// the reorg period is a tag the provider doesn't support
+    pub fn new(reorg_period: EthereumReorgPeriod, fallback: Option<u32>) -> Self {
+        Self {
+            reorg_period,
+            fallback,
+            state: Default::default(),
+        }
+    }
+
+    /// The latest finalized block number
+    #[instrument(level = "trace", err, ret, skip(self, provider))]
+    pub async fn finalized_block_number<M: Middleware + 'static>(
+        &self,
+        provider: &M,
+    ) -> ChainResult<u32> {
+        let EthereumReorgPeriod::Tag(tag) = self.reorg_period else {
+            return get_finalized_block_number(provider, &self.reorg_period).await;
+        };
+        let support = {
+            let state = self.state.lock().unwrap();
+            match state.resolved {
+                Some((at, number)) if at.elapsed() < TAG_RESOLUTION_TTL => return Ok(number),
+                _ => state.support,
+            }
+        };
+        if support == TagSupport::Unsupported {
+            return self.fallback_block_number(provider, tag).await;
+        }
+
+        let block = provider.get_block(tag).await;
+        if let Ok(Some(number)) = block.as_ref().map(|b| b.as_ref().and_then(|b| b.number)) {
+            let number = number.as_u32();
+            let mut state = self.state.lock().unwrap();
+            state.support = TagSupport::Supported;
+            state.resolved = Some((Instant::now(), number));
+            return Ok(number);
+        }
+        // Once the tag was resolved, failures are assumed to be transient.
+        // Before then, the tag is unsupported if the provider is otherwise
+        // reachable.
+        let unsupported = support == TagSupport::Unknown
+            && match &block {
+                Ok(_) => true,
+                Err(_) => provider.get_block_number().await.is_ok(),
+            };
+        if !unsupported {
+            return Err(match block {
+                Err(err) => ChainCommunicationError::from_other(err),
+                Ok(_) => ChainCommunicationError::CustomError(format!(
+                    "Unable to resolve block tag {tag:?}"
+                )),
+            });
+        }
+        if self.fallback.is_some() {
+            warn!(
+                ?tag,
+                fallback = self.fallback,
+                "Provider doesn't support the block tag, falling back to counting confirmations"
+            );
+            self.state.lock().unwrap().support = TagSupport::Unsupported;
+        }
+        self.fallback_block_number(provider, tag).await
+    }
+
+    async fn fallback_block_number<M: Middleware + 'static>(
+        &self,
+        provider: &M,
+        tag: BlockId,
+    ) -> ChainResult<u32> {
+        let Some(fallback) = self.fallback else {
+            return Err(ChainCommunicationError::CustomError(format!(
+                "Provider doesn't support block tag {tag:?}, and no fallback is configured"
+            )));
+        };
+        get_finalized_block_number(provider, &EthereumReorgPeriod::Blocks(fallback)).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use ethers::{
+        providers::{MockProvider, Provider},
+        types::{Block, BlockNumber, H256, U64},
+    };
+
+    use super::*;
+
+    fn finalized() -> EthereumReorgPeriod {
+        EthereumReorgPeriod::Tag(BlockNumber::Finalized.into())
+    }
+
+    fn block(number: u64) -> Option<Block<H256>> {
+        Some(Block {
+            number: Some(number.into()),
+            ..Default::default()
+        })
+    }
+
+    fn provider() -> (Provider<Arc<MockProvider>>, Arc<MockProvider>) {
+        let mock = Arc::new(MockProvider::new());
+        (Provider::new(mock.clone()), mock)
+    }
+
+    #[tokio::test]
+    async fn test_supported_tag_is_resolved_and_cached() {
+        let (provider, mock) = provider();
+        let finality = EthereumFinality::new(finalized(), Some(10));
+        mock.push(block(100)).unwrap();
+
+        assert_eq!(
+            finality.finalized_block_number(&provider).await.unwrap(),
+            100
+        );
+        // Resolved again within the same tick without another request
+        assert_eq!(
+            finality.finalized_block_number(&provider).await.unwrap(),
+            100
+        );
+        mock.assert_request("eth_getBlockByNumber", ("finalized", false))
+            .unwrap();
+        assert!(mock
+            .assert_request("eth_getBlockByNumber", ("finalized", false))
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_tag_falls_back_for_good() {
+        let (provider, mock) = provider();
+        let finality = EthereumFinality::new(finalized(), Some(10));
+        // The MockProvider responses we push are processed in LIFO order
+        mock.push(U64::from(205)).unwrap();
+        mock.push(U64::from(200)).unwrap();
+        mock.push(None::<Block<H256>>).unwrap();
+
+        assert_eq!(
+            finality.finalized_block_number(&provider).await.unwrap(),
+            190
+        );
+        // The tag isn't tried again
+        assert_eq!(
+            finality.finalized_block_number(&provider).await.unwrap(),
+            195
+        );
+        mock.assert_request("eth_getBlockByNumber", ("finalized", false))
+            .unwrap();
+        mock.assert_request("eth_blockNumber", ()).unwrap();
+        mock.assert_request("eth_blockNumber", ()).unwrap();
+        assert!(mock
+            .assert_request("eth_getBlockByNumber", ("finalized", false))
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_failures_after_resolving_the_tag_are_transient() {
+        let (provider, mock) = provider();
+        let finality = EthereumFinality::new(finalized(), Some(10));
+        mock.push(block(100)).unwrap();
+        assert_eq!(
+            finality.finalized_block_number(&provider).await.unwrap(),
+            100
+        );
+
+        // Past the cache, the provider fails to resolve the tag once
+        finality.state.lock().unwrap().resolved = None;
+        mock.push(None::<Block<H256>>).unwrap();
+        assert!(finality.finalized_block_number(&provider).await.is_err());
+        mock.push(block(101)).unwrap();
+        assert_eq!(
+            finality.finalized_block_number(&provider).await.unwrap(),
+            101
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_tag_without_fallback_errors() {
+        let (provider, mock) = provider();
+        let finality = EthereumFinality::new(finalized(), None);
+        mock.push(None::<Block<H256>>).unwrap();
+
+        assert!(finality.finalized_block_number(&provider).await.is_err());
+    }
+}
@@ -100,7 +100,7 @@ where
     ) -> ChainResult<(Vec<H256>, u8)> {
         let (isms, threshold) = self
             .contract
-            .modules_and_threshold(RawHyperlaneMessage::from(message).to_vec().into())
+            .modules_and_threshold(RawHyperlaneMessage::from(message).into())
             .call()
             .await?;
         let isms_h256 = isms.iter().map(|address| (*address).into()).collect();
@@ -118,7 +118,7 @@ where
     ) -> ChainResult<Option<U256>> {
         let tx = self.contract.verify(
             metadata.to_owned().into(),
-            RawHyperlaneMessage::from(message).to_vec().into(),
+            RawHyperlaneMessage::from(message).into(),
         );
         let (verifies, gas_estimate) = try_join(tx.call(), tx.estimate_gas()).await?;
         if verifies {
@@ -106,7 +106,7 @@ where
     ) -> ChainResult<(Vec<H256>, u8)> {
         let (validator_addresses, threshold) = self
             .contract
-            .validators_and_threshold(RawHyperlaneMessage::from(message).to_vec().into())
+            .validators_and_threshold(RawHyperlaneMessage::from(message).into())
             .call()
             .await?;
         let validators: Vec<H256> = validator_addresses.iter().map(|&x| H256::from(x)).collect();
@@ -94,7 +94,7 @@ where
     async fn route(&self, message: &HyperlaneMessage) -> ChainResult<H256> {
         let ism = self
             .contract
-            .route(RawHyperlaneMessage::from(message).to_vec().into())
+            .route(RawHyperlaneMessage::from(message).into())
             .call()
             .await?;
         Ok(ism.into())
@@ -8,7 +8,7 @@ use std::collections::HashMap;
 use ethers::abi::FunctionExt;
 use ethers::prelude::{abi, Lazy, Middleware};
 
-pub use self::{config::*, contracts::*, ism::*, rpc_clients::*, signer::*};
+pub use self::{config::*, contracts::*, finality::*, ism::*, rpc_clients::*, signer::*};
 
 mod tx;
 
@@ -26,6 +26,11 @@ mod signer;
 
 mod config;
 mod error;
+mod finality;
+
+/// Deterministic keys for tests
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_keys;
 
 fn extract_fn_map(abi: &'static Lazy<abi::Abi>) -> HashMap<Vec<u8>, &'static str> {
     abi.functions()
@@ -126,69 +126,36 @@ where
 
 #[cfg(test)]
 mod tests {
+    use ethers_core::types::U64;
     use ethers_prometheus::json_rpc_client::{JsonRpcBlockGetter, BLOCK_NUMBER_RPC};
-    use hyperlane_core::rpc_clients::test::ProviderMock;
     use hyperlane_core::rpc_clients::FallbackProviderBuilder;
 
-    use super::*;
-
-    #[derive(Debug, Clone, Default)]
-    struct EthereumProviderMock(ProviderMock);
-
-    impl Deref for EthereumProviderMock {
-        type Target = ProviderMock;
-
-        fn deref(&self) -> &Self::Target {
-            &self.0
-        }
-    }
-
-    impl EthereumProviderMock {
-        fn new(request_sleep: Option<Duration>) -> Self {
-            Self(ProviderMock::new(request_sleep))
-        }
-    }
+    use crate::rpc_clients::{SimulatedError, SimulatedTransport};
 
-    impl From<EthereumProviderMock> for JsonRpcBlockGetter<EthereumProviderMock> {
-        fn from(val: EthereumProviderMock) -> Self {
-            JsonRpcBlockGetter::new(val)
-        }
-    }
-
-    fn dummy_return_value<R: DeserializeOwned>() -> Result<R, HttpClientError> {
-        serde_json::from_str("0").map_err(|e| HttpClientError::SerdeJson {
-            err: e,
-            text: "".to_owned(),
-        })
-    }
+    use super::*;
 
-    #[async_trait]
-    impl JsonRpcClient for EthereumProviderMock {
-        type Error = HttpClientError;
-
-        /// Pushes the `(method, params)` to the back of the `requests` queue,
-        /// pops the responses from the back of the `responses` queue
-        async fn request<T: Debug + Serialize + Send + Sync, R: DeserializeOwned>(
-            &self,
-            method: &str,
-            params: T,
-        ) -> Result<R, Self::Error> {
-            self.push(method, params);
-            if let Some(sleep_duration) = self.request_sleep() {
-                sleep(sleep_duration).await;
-            }
-            dummy_return_value()
-        }
+    type SimulatedFallbackProvider =
+        EthereumFallbackProvider<SimulatedTransport, JsonRpcBlockGetter<SimulatedTransport>>;
+
+    /// Transports answering block number requests with the genesis block
+    fn transports(count: usize) -> Vec<SimulatedTransport> {
+        (0..count)
+            .map(|i| {
+                let transport = SimulatedTransport::new(format!("node{i}"));
+                transport.set_default(BLOCK_NUMBER_RPC, Ok("0x0".into()));
+                transport
+            })
+            .collect()
     }
 
-    impl PrometheusJsonRpcClientConfigExt for EthereumProviderMock {
-        fn node_host(&self) -> &str {
-            todo!()
-        }
-
-        fn chain_name(&self) -> &str {
-            todo!()
-        }
+    /// How many times each provider was called, in order of priority
+    async fn get_call_counts(provider: &SimulatedFallbackProvider) -> Vec<usize> {
+        provider
+            .take_priorities_snapshot()
+            .await
+            .iter()
+            .map(|p| provider.inner.providers[p.index].calls().len())
+            .collect()
     }
 
     impl<C> EthereumFallbackProvider<C, JsonRpcBlockGetter<C>>
@@ -200,45 +167,65 @@ mod tests {
         JsonRpcBlockGetter<C>: BlockNumberGetter,
     {
         async fn low_level_test_call(&self) {
-            self.request::<_, u64>(BLOCK_NUMBER_RPC, ()).await.unwrap();
+            self.request::<_, U64>(BLOCK_NUMBER_RPC, ()).await.unwrap();
         }
     }
 
     #[tokio::test]
     async fn test_first_provider_is_attempted() {
         let fallback_provider_builder = FallbackProviderBuilder::default();
-        let providers = vec![
-            EthereumProviderMock::default(),
-            EthereumProviderMock::default(),
-            EthereumProviderMock::default(),
-        ];
-        let fallback_provider = fallback_provider_builder.add_providers(providers).build();
+        let fallback_provider = fallback_provider_builder
+            .add_providers(transports(3))
+            .build();
         let ethereum_fallback_provider = EthereumFallbackProvider::new(fallback_provider);
         ethereum_fallback_provider.low_level_test_call().await;
-        let provider_call_count: Vec<_> =
-            ProviderMock::get_call_counts(&ethereum_fallback_provider).await;
+        let provider_call_count: Vec<_> = get_call_counts(&ethereum_fallback_provider).await;
         assert_eq!(provider_call_count, vec![1, 0, 0]);
     }
 
     #[tokio::test]
     async fn test_one_stalled_provider() {
         let fallback_provider_builder = FallbackProviderBuilder::default();
-        let providers = vec![
-            EthereumProviderMock::new(Some(Duration::from_millis(10))),
-            EthereumProviderMock::default(),
-            EthereumProviderMock::default(),
-        ];
+        let providers = transports(3);
+        providers[0].set_latency(BLOCK_NUMBER_RPC, Duration::from_millis(10));
         let fallback_provider = fallback_provider_builder
             .add_providers(providers)
             .with_max_block_time(Duration::from_secs(0))
             .build();
         let ethereum_fallback_provider = EthereumFallbackProvider::new(fallback_provider);
         ethereum_fallback_provider.low_level_test_call().await;
-        let provider_call_count: Vec<_> =
-            ProviderMock::get_call_counts(&ethereum_fallback_provider).await;
+        let provider_call_count: Vec<_> = get_call_counts(&ethereum_fallback_provider).await;
         assert_eq!(provider_call_count, vec![0, 0, 2]);
     }
 
-    // TODO: make `categorize_client_response` generic over `ProviderError` to allow testing
-    // two stalled providers (so that the for loop in `request` doesn't stop after the first provider)
+    #[tokio::test]
+    async fn test_two_failing_providers() {
+        let fallback_provider_builder = FallbackProviderBuilder::default();
+        let providers = transports(3);
+        providers[0].push_err(BLOCK_NUMBER_RPC, SimulatedError::Unavailable);
+        providers[1].push_err(BLOCK_NUMBER_RPC, SimulatedError::RateLimited);
+        let fallback_provider = fallback_provider_builder.add_providers(providers).build();
+        let ethereum_fallback_provider = EthereumFallbackProvider::new(fallback_provider);
+        ethereum_fallback_provider.low_level_test_call().await;
+        let provider_call_count: Vec<_> = get_call_counts(&ethereum_fallback_provider).await;
+        assert_eq!(provider_call_count, vec![1, 1, 1]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_non_retryable_error_is_not_retried_on_other_providers() {
+        let fallback_provider_builder = FallbackProviderBuilder::default();
+        let providers = transports(3);
+        providers[0].push_err(
+            "eth_sendRawTransaction",
+            SimulatedError::json_rpc(-32000, "nonce too low"),
+        );
+        let fallback_provider = fallback_provider_builder.add_providers(providers).build();
+        let ethereum_fallback_provider = EthereumFallbackProvider::new(fallback_provider);
+        let result = ethereum_fallback_provider
+            .request::<_, Value>("eth_sendRawTransaction", ["0x00"])
+            .await;
+        assert!(result.is_err());
+        let provider_call_count: Vec<_> = get_call_counts(&ethereum_fallback_provider).await;
+        assert_eq!(provider_call_count, vec![1, 0, 0]);
+    }
 }
@@ -3,9 +3,14 @@ use tracing::{info, trace, warn};
 
 pub use self::{fallback::*, provider::*, retrying::*, trait_builder::*};
 
+#[cfg(any(test, feature = "test-utils"))]
+pub use self::simulated::*;
+
 mod fallback;
 mod provider;
 mod retrying;
+#[cfg(any(test, feature = "test-utils"))]
+mod simulated;
 mod trait_builder;
 
 enum CategorizedResponse<R> {
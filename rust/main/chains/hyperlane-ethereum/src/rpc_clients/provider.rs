@@ -174,6 +174,38 @@ where
         );
         Ok(Some(chain_metrics))
     }
+
+    #[instrument(err, skip(self))]
+    #[allow(clippy::blocks_in_conditions)] // TODO: `rustc` 1.80.1 clippy issue
+    async fn get_chain_id(&self) -> ChainResult<Option<u64>> {
+        let chain_id = self
+            .provider
+            .get_chainid()
+            .await
+            .map_err(ChainCommunicationError::from_other)?;
+        Ok(Some(chain_id.as_u64()))
+    }
+
+    #[instrument(err, skip(self))]
+    #[allow(clippy::blocks_in_conditions)] // TODO: `rustc` 1.80.1 clippy issue
+    async fn get_transaction_count(
+        &self,
+        address: String,
+        pending: bool,
+    ) -> ChainResult<Option<u64>> {
+        let addr: Address = address.parse()?;
+        let block = if pending {
+            BlockNumber::Pending
+        } else {
+            BlockNumber::Latest
+        };
+        let count = self
+            .provider
+            .get_transaction_count(addr, Some(block.into()))
+            .await
+            .map_err(ChainCommunicationError::from_other)?;
+        Ok(Some(count.as_u64()))
+    }
 }
 
 impl<M> EthereumProvider<M>
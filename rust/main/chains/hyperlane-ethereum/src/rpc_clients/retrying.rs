@@ -2,7 +2,7 @@ use std::{fmt::Debug, str::FromStr, time::Duration};
 
 use crate::rpc_clients::{categorize_client_response, CategorizedResponse};
 use async_trait::async_trait;
-use ethers::providers::{Http, JsonRpcClient, ProviderError};
+use ethers::providers::{HttpClientError, JsonRpcClient, ProviderError};
 use ethers_prometheus::json_rpc_client::{
     PrometheusJsonRpcClient, PrometheusJsonRpcClientConfigExt,
 };
@@ -165,8 +165,11 @@ where
 
 #[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
 #[cfg_attr(not(target_arch = "wasm32"), async_trait)]
-impl JsonRpcClient for RetryingProvider<PrometheusJsonRpcClient<Http>> {
-    type Error = RetryingProviderError<PrometheusJsonRpcClient<Http>>;
+impl<C> JsonRpcClient for RetryingProvider<PrometheusJsonRpcClient<C>>
+where
+    C: JsonRpcClient<Error = HttpClientError> + 'static,
+{
+    type Error = RetryingProviderError<PrometheusJsonRpcClient<C>>;
 
     #[instrument(skip(self), fields(provider_host = %self.inner.node_host(), chain_name = %self.inner.chain_name()))]
     async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
@@ -206,3 +209,103 @@ where
         Ok(Self::new(src.parse()?, None, None))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use ethers_core::types::U64;
+    use ethers_prometheus::json_rpc_client::{
+        JsonRpcClientMetricsBuilder, PrometheusJsonRpcClientConfig,
+    };
+    use tokio::time::Instant;
+
+    use crate::rpc_clients::{SimulatedError, SimulatedTransport};
+
+    use super::*;
+
+    fn retrying_provider(
+        transport: &SimulatedTransport,
+    ) -> RetryingProvider<PrometheusJsonRpcClient<SimulatedTransport>> {
+        let client = PrometheusJsonRpcClient::new(
+            transport.clone(),
+            JsonRpcClientMetricsBuilder::default().build().unwrap(),
+            PrometheusJsonRpcClientConfig::default(),
+        );
+        RetryingProvider::new(client, Some(4), Some(100))
+    }
+
+    /// Time elapsed between consecutive calls
+    fn backoffs(transport: &SimulatedTransport) -> Vec<Duration> {
+        transport
+            .calls()
+            .windows(2)
+            .map(|calls| calls[1].at - calls[0].at)
+            .collect()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retries_with_exponential_backoff() {
+        let transport = SimulatedTransport::new("node");
+        transport
+            .push_err("eth_blockNumber", SimulatedError::Unavailable)
+            .push_err(
+                "eth_blockNumber",
+                SimulatedError::json_rpc(-32000, "header not found"),
+            )
+            .push_ok("eth_blockNumber", "0x10");
+        let provider = retrying_provider(&transport);
+
+        let block: U64 = provider.request("eth_blockNumber", ()).await.unwrap();
+
+        assert_eq!(block, 16.into());
+        assert_eq!(
+            backoffs(&transport),
+            vec![Duration::from_millis(100), Duration::from_millis(200)]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_backs_off_longer_when_rate_limited() {
+        let transport = SimulatedTransport::new("node");
+        transport
+            .push_err("eth_blockNumber", SimulatedError::RateLimited)
+            .push_ok("eth_blockNumber", "0x10");
+        let provider = retrying_provider(&transport);
+
+        let block: U64 = provider.request("eth_blockNumber", ()).await.unwrap();
+
+        assert_eq!(block, 16.into());
+        assert_eq!(backoffs(&transport), vec![Duration::from_secs(20)]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_halts_on_non_retryable_errors() {
+        let transport = SimulatedTransport::new("node");
+        transport.push_err(
+            "eth_call",
+            SimulatedError::json_rpc(3, "execution reverted"),
+        );
+        let provider = retrying_provider(&transport);
+
+        let result = provider.request::<_, Value>("eth_call", ()).await;
+
+        assert!(matches!(
+            result,
+            Err(RetryingProviderError::JsonRpcClientError(_))
+        ));
+        assert_eq!(transport.calls().len(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_gives_up_after_max_requests() {
+        let transport = SimulatedTransport::new("node");
+        transport.set_default("eth_blockNumber", Err(SimulatedError::Unavailable));
+        let provider = retrying_provider(&transport);
+        let start = Instant::now();
+
+        let result = provider.request::<_, U64>("eth_blockNumber", ()).await;
+
+        assert!(matches!(result, Err(RetryingProviderError::MaxRequests(_))));
+        assert_eq!(transport.calls().len(), 4);
+        assert_eq!(start.elapsed(), Duration::from_millis(100 + 200 + 400));
+    }
+}
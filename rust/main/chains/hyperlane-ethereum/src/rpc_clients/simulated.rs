@@ -0,0 +1,285 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Debug,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use ethers::providers::{HttpClientError, JsonRpcClient, JsonRpcError};
+use ethers_prometheus::json_rpc_client::{JsonRpcBlockGetter, PrometheusJsonRpcClientConfigExt};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use tokio::time::{sleep, Instant};
+
+/// Error a `SimulatedTransport` can be scripted to fail a call with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SimulatedError {
+    /// The node rejected the call as over its rate limit
+    RateLimited,
+    /// The node couldn't be reached or answered garbage, e.g. a 502 page
+    Unavailable,
+    /// The node answered with a JSON-RPC error
+    JsonRpc {
+        /// JSON-RPC error code
+        code: i64,
+        /// JSON-RPC error message
+        message: String,
+    },
+}
+
+impl SimulatedError {
+    /// A JSON-RPC error with the given code and message
+    pub fn json_rpc(code: i64, message: impl Into<String>) -> Self {
+        Self::JsonRpc {
+            code,
+            message: message.into(),
+        }
+    }
+
+    /// The error the HTTP transport surfaces for this failure
+    fn into_client_error(self) -> HttpClientError {
+        match self {
+            Self::RateLimited => HttpClientError::JsonRpcError(JsonRpcError {
+                code: 429,
+                message: "Too many requests".to_owned(),
+                data: None,
+            }),
+            Self::Unavailable => {
+                let text = "<html>502 Bad Gateway</html>".to_owned();
+                HttpClientError::SerdeJson {
+                    err: serde_json::from_str::<Value>(&text).unwrap_err(),
+                    text,
+                }
+            }
+            Self::JsonRpc { code, message } => HttpClientError::JsonRpcError(JsonRpcError {
+                code,
+                message,
+                data: None,
+            }),
+        }
+    }
+}
+
+/// Response to a call to a `SimulatedTransport`
+pub type SimulatedResponse = Result<Value, SimulatedError>;
+
+/// A call made to a `SimulatedTransport`
+#[derive(Debug, Clone)]
+pub struct SimulatedCall {
+    /// Index of the call among all calls made to the transport
+    pub index: usize,
+    /// JSON-RPC method called
+    pub method: String,
+    /// Params of the call
+    pub params: Value,
+    /// When the call was made, on tokio's clock
+    pub at: Instant,
+}
+
+#[derive(Debug, Default)]
+struct SimulatedState {
+    /// Responses to the next calls of each method, in order
+    responses: HashMap<String, VecDeque<SimulatedResponse>>,
+    /// Responses to calls of each method once its queue is empty
+    defaults: HashMap<String, SimulatedResponse>,
+    /// Errors injected by call index, taking precedence over the responses
+    failures: HashMap<usize, SimulatedError>,
+    latencies: HashMap<String, Duration>,
+    calls: Vec<SimulatedCall>,
+}
+
+/// A JSON-RPC transport answering calls from a script rather than a node, so
+/// that the provider middleware stack can be tested deterministically.
+///
+/// Calls are answered from the method's response queue, then its default
+/// response, unless an error was injected at the call's index. Latencies
+/// elapse on tokio's clock, so tests pausing it (e.g. with
+/// `#[tokio::test(start_paused = true)]`) share a single virtual clock with
+/// the backoffs and timeouts of the middleware under test.
+///
+/// Clones share their script and recorded calls, so a test can keep a handle
+/// on a transport moved into a stack.
+#[derive(Debug, Clone)]
+pub struct SimulatedTransport {
+    host: String,
+    state: Arc<Mutex<SimulatedState>>,
+}
+
+impl SimulatedTransport {
+    /// Create a transport reporting `host` as the node it's connected to
+    pub fn new(host: impl Into<String>) -> Self {
+        Self {
+            host: host.into(),
+            state: Default::default(),
+        }
+    }
+
+    /// Answer the next unanswered call of `method` with `value`
+    pub fn push_ok(&self, method: &str, value: impl Serialize) -> &Self {
+        self.push(method, Ok(serde_json::to_value(value).expect("valid")))
+    }
+
+    /// Fail the next unanswered call of `method` with `error`
+    pub fn push_err(&self, method: &str, error: SimulatedError) -> &Self {
+        self.push(method, Err(error))
+    }
+
+    /// Queue `response` for the next unanswered call of `method`
+    pub fn push(&self, method: &str, response: SimulatedResponse) -> &Self {
+        self.state
+            .lock()
+            .unwrap()
+            .responses
+            .entry(method.to_owned())
+            .or_default()
+            .push_back(response);
+        self
+    }
+
+    /// Answer the calls of `method` with `response` once its queue is empty
+    pub fn set_default(&self, method: &str, response: SimulatedResponse) -> &Self {
+        self.state
+            .lock()
+            .unwrap()
+            .defaults
+            .insert(method.to_owned(), response);
+        self
+    }
+
+    /// Fail the call with index `index`, whatever its method, with `error`
+    pub fn fail_call(&self, index: usize, error: SimulatedError) -> &Self {
+        self.state.lock().unwrap().failures.insert(index, error);
+        self
+    }
+
+    /// Delay the responses to `method` by `latency`
+    pub fn set_latency(&self, method: &str, latency: Duration) -> &Self {
+        self.state
+            .lock()
+            .unwrap()
+            .latencies
+            .insert(method.to_owned(), latency);
+        self
+    }
+
+    /// The calls made so far, in order
+    pub fn calls(&self) -> Vec<SimulatedCall> {
+        self.state.lock().unwrap().calls.clone()
+    }
+
+    /// The calls of `method` made so far, in order
+    pub fn calls_of(&self, method: &str) -> Vec<SimulatedCall> {
+        self.calls()
+            .into_iter()
+            .filter(|call| call.method == method)
+            .collect()
+    }
+
+    /// Record a call and pick its response and latency from the script
+    fn answer(&self, method: &str, params: Value) -> (SimulatedResponse, Duration) {
+        let mut state = self.state.lock().unwrap();
+        let index = state.calls.len();
+        state.calls.push(SimulatedCall {
+            index,
+            method: method.to_owned(),
+            params,
+            at: Instant::now(),
+        });
+        let latency = state.latencies.get(method).copied().unwrap_or_default();
+        if let Some(error) = state.failures.remove(&index) {
+            return (Err(error), latency);
+        }
+        let response = state
+            .responses
+            .get_mut(method)
+            .and_then(VecDeque::pop_front)
+            .or_else(|| state.defaults.get(method).cloned())
+            .unwrap_or_else(|| {
+                Err(SimulatedError::json_rpc(
+                    -32601,
+                    format!("No response scripted for {method}"),
+                ))
+            });
+        (response, latency)
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for SimulatedTransport {
+    type Error = HttpClientError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned,
+    {
+        let params = serde_json::to_value(params).expect("valid");
+        let (response, latency) = self.answer(method, params);
+        sleep(latency).await;
+        let value = response.map_err(SimulatedError::into_client_error)?;
+        serde_json::from_value(value.clone()).map_err(|err| HttpClientError::SerdeJson {
+            err,
+            text: value.to_string(),
+        })
+    }
+}
+
+impl PrometheusJsonRpcClientConfigExt for SimulatedTransport {
+    fn node_host(&self) -> &str {
+        &self.host
+    }
+
+    fn chain_name(&self) -> &str {
+        "simulated"
+    }
+}
+
+impl From<SimulatedTransport> for JsonRpcBlockGetter<SimulatedTransport> {
+    fn from(val: SimulatedTransport) -> Self {
+        JsonRpcBlockGetter::new(val)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ethers_core::types::U64;
+
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn answers_calls_from_the_script() {
+        let transport = SimulatedTransport::new("node");
+        transport
+            .push_ok("eth_blockNumber", "0x1")
+            .set_default("eth_blockNumber", Ok("0x2".into()))
+            .set_latency("eth_blockNumber", Duration::from_secs(1))
+            .fail_call(2, SimulatedError::RateLimited);
+        let start = Instant::now();
+
+        let first: U64 = transport.request("eth_blockNumber", ()).await.unwrap();
+        let second: U64 = transport.request("eth_blockNumber", ()).await.unwrap();
+        let third = transport.request::<_, U64>("eth_blockNumber", ()).await;
+        let unscripted = transport.request::<_, U64>("eth_chainId", ()).await;
+
+        assert_eq!((first, second), (1.into(), 2.into()));
+        assert!(matches!(
+            third,
+            Err(HttpClientError::JsonRpcError(JsonRpcError {
+                code: 429,
+                ..
+            }))
+        ));
+        assert!(matches!(
+            unscripted,
+            Err(HttpClientError::JsonRpcError(JsonRpcError {
+                code: -32601,
+                ..
+            }))
+        ));
+        let calls = transport.calls();
+        assert_eq!(calls.len(), 4);
+        assert_eq!(calls[1].at - start, Duration::from_secs(1));
+        assert_eq!(transport.calls_of("eth_chainId")[0].index, 3);
+    }
+}
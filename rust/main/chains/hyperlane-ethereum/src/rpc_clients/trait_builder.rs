@@ -213,9 +213,7 @@ pub trait BuildableWithProvider {
         M: Middleware + 'static,
     {
         Ok(if let Some(signer) = signer {
-            let signing_provider = wrap_with_signer(provider, signer)
-                .await
-                .map_err(ChainCommunicationError::from_other)?;
+            let signing_provider = wrap_with_signer(provider, signer, locator.domain).await?;
             self.build_with_provider(signing_provider, conn, locator)
         } else {
             self.build_with_provider(provider, conn, locator)
@@ -234,12 +232,29 @@ pub trait BuildableWithProvider {
         M: Middleware + 'static;
 }
 
+/// Wrap the provider with a signer for the chain it serves, failing if that
+/// isn't the chain of `domain`, e.g. if the RPC url of another chain is
+/// configured, so that transactions are never signed for the wrong chain
 async fn wrap_with_signer<M: Middleware>(
     provider: M,
     signer: Signers,
-) -> Result<SignerMiddleware<NonceManagerMiddleware<M>, Signers>, M::Error> {
-    let provider_chain_id = provider.get_chainid().await?;
-    let signer = ethers::signers::Signer::with_chain_id(signer, provider_chain_id.as_u64());
+    domain: &HyperlaneDomain,
+) -> ChainResult<SignerMiddleware<NonceManagerMiddleware<M>, Signers>> {
+    let provider_chain_id = provider
+        .get_chainid()
+        .await
+        .map_err(ChainCommunicationError::from_other)?
+        .as_u64();
+    if let Some(expected) = domain.evm_chain_id() {
+        if provider_chain_id != expected {
+            return Err(ChainCommunicationError::ChainIdMismatch {
+                domain: domain.name().to_owned(),
+                expected,
+                actual: provider_chain_id,
+            });
+        }
+    }
+    let signer = ethers::signers::Signer::with_chain_id(signer, provider_chain_id);
 
     let address = ethers::prelude::Signer::address(&signer);
     let provider = NonceManagerMiddleware::new(provider, address);
@@ -277,3 +292,213 @@ where
     };
     Ok(GasOracleMiddleware::new(provider, gas_oracle))
 }
+
+#[cfg(test)]
+mod tests {
+    use ethers::signers::LocalWallet;
+    use ethers_core::types::U256;
+    use ethers_prometheus::json_rpc_client::{
+        PrometheusJsonRpcClientConfigExt, REQUEST_COUNT_HELP, REQUEST_COUNT_LABELS,
+    };
+    use prometheus::{IntCounterVec, Opts};
+
+    use crate::rpc_clients::{SimulatedError, SimulatedTransport};
+
+    use super::*;
+
+    const NONCE_RPC: &str = "eth_getTransactionCount";
+
+    /// The middleware stack built for a signer over `C`
+    type SignerStack<C> = SignerMiddleware<
+        NonceManagerMiddleware<GasOracleMiddleware<Arc<Provider<C>>, Box<dyn GasOracle>>>,
+        Signers,
+    >;
+
+    async fn signer_stack_for<C: JsonRpcClient + 'static>(
+        client: C,
+        domain: &HyperlaneDomain,
+    ) -> ChainResult<SignerStack<C>> {
+        let signer: LocalWallet =
+            "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+                .parse()
+                .unwrap();
+        let provider = wrap_with_gas_oracle(Provider::new(client), domain).unwrap();
+        wrap_with_signer(provider, signer.into(), domain).await
+    }
+
+    async fn signer_stack<C: JsonRpcClient + 'static>(client: C) -> SignerStack<C> {
+        signer_stack_for(client, &HyperlaneDomain::Known(KnownHyperlaneDomain::Test1))
+            .await
+            .unwrap()
+    }
+
+    fn transport(host: &str) -> SimulatedTransport {
+        let transport = SimulatedTransport::new(host);
+        transport
+            .set_default("eth_chainId", Ok("0x97441b".into()))
+            .set_default("eth_blockNumber", Ok("0x0".into()));
+        transport
+    }
+
+    fn request_count() -> IntCounterVec {
+        IntCounterVec::new(
+            Opts::new("request_count", REQUEST_COUNT_HELP),
+            REQUEST_COUNT_LABELS,
+        )
+        .unwrap()
+    }
+
+    fn with_metrics<C>(
+        client: C,
+        host: &str,
+        request_count: &IntCounterVec,
+    ) -> PrometheusJsonRpcClient<C> {
+        PrometheusJsonRpcClient::new(
+            client,
+            JsonRpcClientMetricsBuilder::default()
+                .request_count(request_count.clone())
+                .build()
+                .unwrap(),
+            PrometheusJsonRpcClientConfig {
+                node: Some(NodeInfo {
+                    host: Some(host.to_owned()),
+                }),
+                chain: None,
+            },
+        )
+    }
+
+    /// The client `RpcConnectionConf::HttpFallback` builds
+    fn fallback_client(
+        transports: &[SimulatedTransport],
+        request_count: &IntCounterVec,
+    ) -> EthereumFallbackProvider<
+        PrometheusJsonRpcClient<SimulatedTransport>,
+        JsonRpcBlockGetter<PrometheusJsonRpcClient<SimulatedTransport>>,
+    > {
+        let providers = transports
+            .iter()
+            .map(|transport| with_metrics(transport.clone(), transport.node_host(), request_count));
+        EthereumFallbackProvider::new(FallbackProvider::new(providers))
+    }
+
+    /// The client `RpcConnectionConf::HttpFallback` would build if the
+    /// metrics wrapped the fallback rather than each of its providers
+    fn misordered_fallback_client(
+        transports: &[SimulatedTransport],
+        request_count: &IntCounterVec,
+    ) -> PrometheusJsonRpcClient<
+        EthereumFallbackProvider<SimulatedTransport, JsonRpcBlockGetter<SimulatedTransport>>,
+    > {
+        let fallback = EthereumFallbackProvider::new(FallbackProvider::new(transports.to_vec()));
+        with_metrics(fallback, "fallback", request_count)
+    }
+
+    /// The client `RpcConnectionConf::Http` builds
+    fn http_client(
+        transport: &SimulatedTransport,
+        request_count: &IntCounterVec,
+    ) -> RetryingProvider<PrometheusJsonRpcClient<SimulatedTransport>> {
+        let client = with_metrics(transport.clone(), transport.node_host(), request_count);
+        RetryingProvider::new(client, None, None)
+    }
+
+    fn requests(request_count: &IntCounterVec, host: &str, method: &str, status: &str) -> u64 {
+        request_count
+            .with_label_values(&[host, "unknown", method, status])
+            .get()
+    }
+
+    /// Initialize the nonce of a signer while the primary provider is rate
+    /// limited, and check the rate limit is reported against it
+    async fn initialize_nonce_past_rate_limit<C: JsonRpcClient + 'static>(
+        client: C,
+        transports: &[SimulatedTransport],
+        request_count: &IntCounterVec,
+    ) {
+        let stack = signer_stack(client).await;
+
+        let nonce = stack.inner().initialize_nonce(None).await.unwrap();
+
+        assert_eq!(nonce, U256::from(7));
+        assert_eq!(stack.inner().next(), U256::from(7));
+        assert_eq!(stack.inner().next(), U256::from(8));
+        assert_eq!(transports[0].calls_of(NONCE_RPC).len(), 1);
+        assert_eq!(transports[1].calls_of(NONCE_RPC).len(), 1);
+        assert_eq!(
+            requests(request_count, "primary", NONCE_RPC, "failure"),
+            1,
+            "rate limits of primary aren't reported"
+        );
+        assert_eq!(
+            requests(request_count, "secondary", NONCE_RPC, "success"),
+            1
+        );
+    }
+
+    fn rate_limited_primary() -> [SimulatedTransport; 2] {
+        let transports = [transport("primary"), transport("secondary")];
+        transports[0].push_err(NONCE_RPC, SimulatedError::RateLimited);
+        transports[1].push_ok(NONCE_RPC, "0x7");
+        transports
+    }
+
+    #[tokio::test]
+    async fn test_signers_are_only_built_for_the_chain_the_rpc_serves() {
+        // The RPC serves the chain of test1
+        let err = signer_stack_for(
+            transport("node"),
+            &HyperlaneDomain::Known(KnownHyperlaneDomain::Test2),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "The RPC of test2 serves chain id 9913371, but the chain id of test2 is 9913372"
+        );
+
+        // Domains without a known chain id are signed for the chain the RPC serves
+        let stack = signer_stack_for(transport("node"), &HyperlaneDomain::from_domain_id(888_888))
+            .await
+            .unwrap();
+        assert_eq!(ethers::signers::Signer::chain_id(stack.signer()), 9913371);
+    }
+
+    #[tokio::test]
+    async fn test_fallback_stack_initializes_nonce_past_rate_limited_provider() {
+        let transports = rate_limited_primary();
+        let request_count = request_count();
+        let client = fallback_client(&transports, &request_count);
+
+        initialize_nonce_past_rate_limit(client, &transports, &request_count).await;
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "rate limits of primary aren't reported")]
+    async fn test_misordered_fallback_stack_hides_rate_limits() {
+        let transports = rate_limited_primary();
+        let request_count = request_count();
+        let client = misordered_fallback_client(&transports, &request_count);
+
+        initialize_nonce_past_rate_limit(client, &transports, &request_count).await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_http_stack_retries_rate_limited_nonce_query() {
+        let transport = transport("node");
+        transport
+            .push_err(NONCE_RPC, SimulatedError::RateLimited)
+            .push_ok(NONCE_RPC, "0x7");
+        let request_count = request_count();
+        let stack = signer_stack(http_client(&transport, &request_count)).await;
+
+        let nonce = stack.inner().initialize_nonce(None).await.unwrap();
+
+        assert_eq!(nonce, U256::from(7));
+        let calls = transport.calls_of(NONCE_RPC);
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[1].at - calls[0].at, Duration::from_secs(20));
+        assert_eq!(requests(&request_count, "node", NONCE_RPC, "failure"), 1);
+        assert_eq!(requests(&request_count, "node", NONCE_RPC, "success"), 1);
+    }
+}
@@ -123,16 +123,12 @@ mod test {
         Checkpoint, CheckpointWithMessageId, HyperlaneSigner, HyperlaneSignerExt, H256,
     };
 
-    use super::Signers;
+    use crate::test_keys::test_signers;
 
     #[test]
     fn it_sign() {
         let t = async {
-            let signer: Signers =
-                "1111111111111111111111111111111111111111111111111111111111111111"
-                    .parse::<ethers::signers::LocalWallet>()
-                    .unwrap()
-                    .into();
+            let signer = test_signers(0);
             let message = CheckpointWithMessageId {
                 checkpoint: Checkpoint {
                     merkle_tree_hook_address: H256::repeat_byte(2),
@@ -0,0 +1,120 @@
+//! Deterministic keys, addresses and hashes for tests, and payloads signed
+//! with those keys.
+//!
+//! Everything is derived from fixed, public seeds: these keys are for tests
+//! only and their addresses must never hold funds or be configured anywhere.
+
+use ethers::utils::keccak256;
+use ethers_signers::{LocalWallet, Signer};
+use hyperlane_core::{
+    Announcement, CheckpointWithMessageId, HyperlaneSignerExt, SignedAnnouncement,
+    SignedCheckpointWithMessageId, H160, H256,
+};
+
+use crate::Signers;
+
+/// Seed of the test private keys
+const KEY_SEED: &[u8] = b"hyperlane-test-key";
+/// Seed of the test hashes, distinct so no hash is also a test private key
+const H256_SEED: &[u8] = b"hyperlane-test-h256";
+
+fn derive(seed: &[u8], n: u32) -> [u8; 32] {
+    keccak256([seed, &n.to_be_bytes()].concat())
+}
+
+/// The `n`th test wallet
+pub fn test_signer(n: u32) -> LocalWallet {
+    LocalWallet::from_bytes(&derive(KEY_SEED, n)).expect("Invalid derived test key")
+}
+
+/// The `n`th test wallet, as an agent signer
+pub fn test_signers(n: u32) -> Signers {
+    test_signer(n).into()
+}
+
+/// The address of the `n`th test wallet
+pub fn test_address(n: u32) -> H160 {
+    test_signer(n).address().into()
+}
+
+/// The `n`th test hash, e.g. for message ids, roots or contract addresses
+pub fn test_h256(n: u32) -> H256 {
+    derive(H256_SEED, n).into()
+}
+
+/// `checkpoint` signed by the `n`th test wallet
+pub async fn signed_checkpoint(
+    n: u32,
+    checkpoint: CheckpointWithMessageId,
+) -> SignedCheckpointWithMessageId {
+    test_signers(n)
+        .sign(checkpoint)
+        .await
+        .expect("Failed to sign with a test wallet")
+}
+
+/// The `n`th test wallet's announcement of `storage_location` for the mailbox
+/// at `mailbox_address` on `mailbox_domain`, signed by it
+pub async fn signed_announcement(
+    n: u32,
+    mailbox_address: H256,
+    mailbox_domain: u32,
+    storage_location: &str,
+) -> SignedAnnouncement {
+    let announcement = Announcement {
+        validator: test_address(n),
+        mailbox_address,
+        mailbox_domain,
+        storage_location: storage_location.to_owned(),
+    };
+    test_signers(n)
+        .sign(announcement)
+        .await
+        .expect("Failed to sign with a test wallet")
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use hyperlane_core::{Checkpoint, H256};
+
+    use super::*;
+
+    const INDICES: u32 = 1_000;
+
+    #[test]
+    fn test_keys_are_deterministic_and_unique() {
+        assert_eq!(test_address(7), test_address(7));
+        assert_eq!(test_h256(7), test_h256(7));
+
+        let addresses: HashSet<H160> = (0..INDICES).map(test_address).collect();
+        assert_eq!(addresses.len(), INDICES as usize);
+        let hashes: HashSet<H256> = (0..INDICES).map(test_h256).collect();
+        assert_eq!(hashes.len(), INDICES as usize);
+        // no test hash doubles as a test private key
+        let keys: HashSet<H256> = (0..INDICES)
+            .map(|n| H256::from_slice(&test_signer(n).signer().to_bytes()))
+            .collect();
+        assert!(keys.is_disjoint(&hashes));
+    }
+
+    #[tokio::test]
+    async fn test_signed_payloads_recover_the_test_address() {
+        let checkpoint = CheckpointWithMessageId {
+            checkpoint: Checkpoint {
+                merkle_tree_hook_address: test_h256(0),
+                mailbox_domain: 1,
+                root: test_h256(1),
+                index: 2,
+            },
+            message_id: test_h256(2),
+        };
+        let signed = signed_checkpoint(3, checkpoint).await;
+        assert_eq!(signed.recover().unwrap(), test_address(3));
+
+        let announcement = signed_announcement(4, test_h256(0), 1, "file:///tmp").await;
+        assert_eq!(announcement.value.validator, test_address(4));
+        assert_eq!(announcement.recover().unwrap(), test_address(4));
+    }
+}
@@ -4,8 +4,12 @@ use std::time::Duration;
 use ethers::{
     abi::Detokenize,
     prelude::{NameOrAddress, TransactionReceipt},
-    providers::{JsonRpcClient, PendingTransaction, ProviderError},
-    types::{Block, Eip1559TransactionRequest, TxHash},
+    providers::{Http, JsonRpcClient, PendingTransaction, Provider, ProviderError},
+    types::{
+        transaction::eip2718::TypedTransaction, Block, Bytes, Eip1559TransactionRequest, TxHash,
+        U64,
+    },
+    utils::keccak256,
 };
 use ethers_contract::builders::ContractCall;
 use ethers_core::{
@@ -16,11 +20,17 @@ use ethers_core::{
     },
 };
 use hyperlane_core::{
-    utils::bytes_to_hex, ChainCommunicationError, ChainResult, ReorgPeriod, H256, U256,
+    log_fields::LogOptBytes, ChainCommunicationError, ChainResult, ReorgPeriod, TxOutcome,
+    TxSubmissionPath, H256, U256,
 };
+use serde::Serialize;
+use tokio::time::Instant;
 use tracing::{debug, error, info, warn};
 
-use crate::{EthereumReorgPeriod, Middleware, TransactionOverrides};
+use crate::{
+    EthereumReorgPeriod, Middleware, PrivateSubmissionConf, PrivateSubmissionMode,
+    TransactionOverrides,
+};
 
 /// An amount of gas to add to the estimated gas
 pub const GAS_ESTIMATE_BUFFER: u32 = 75_000;
@@ -30,6 +40,7 @@ pub fn apply_gas_estimate_buffer(gas: U256) -> U256 {
 }
 
 const PENDING_TRANSACTION_POLLING_INTERVAL: Duration = Duration::from_secs(2);
+const PENDING_TRANSACTION_TIMEOUT: Duration = Duration::from_secs(150);
 
 /// Dispatches a transaction, logs the tx id, and returns the result
 pub(crate) async fn report_tx<M, D>(tx: ContractCall<M, D>) -> ChainResult<TransactionReceipt>
@@ -37,11 +48,7 @@ where
     M: Middleware + 'static,
     D: Detokenize,
 {
-    let data = tx
-        .tx
-        .data()
-        .map(|b| bytes_to_hex(b))
-        .unwrap_or_else(|| "None".into());
+    let data = LogOptBytes(tx.tx.data().map(|b| b.as_ref()));
 
     let to = tx
         .tx
@@ -65,7 +72,7 @@ pub(crate) async fn track_pending_tx<P: JsonRpcClient>(
 
     info!(?tx_hash, "Dispatched tx");
 
-    match tokio::time::timeout(Duration::from_secs(150), pending_tx).await {
+    match tokio::time::timeout(PENDING_TRANSACTION_TIMEOUT, pending_tx).await {
         // all good
         Ok(Ok(Some(receipt))) => {
             info!(?tx_hash, "confirmed transaction");
@@ -87,11 +94,197 @@ pub(crate) async fn track_pending_tx<P: JsonRpcClient>(
     }
 }
 
-/// Populates the gas limit and price for a transaction
+/// Dispatches a transaction through the private submission endpoint if one is
+/// configured, and publicly like [`report_tx`] otherwise
+pub(crate) async fn report_tx_with_private_submission<M, D>(
+    tx: ContractCall<M, D>,
+    provider: Arc<M>,
+    private_submission: Option<&PrivateSubmissionConf>,
+) -> ChainResult<TxOutcome>
+where
+    M: Middleware + 'static,
+    D: Detokenize,
+{
+    let Some(conf) = private_submission else {
+        return Ok(report_tx(tx).await?.into());
+    };
+    let signed_tx = sign_tx(&tx.tx, provider.as_ref()).await?;
+    let private_endpoint = Provider::new(Http::new(conf.url.clone()));
+    submit_privately(
+        signed_tx,
+        provider.as_ref(),
+        &private_endpoint,
+        conf,
+        PENDING_TRANSACTION_POLLING_INTERVAL,
+    )
+    .await
+}
+
+/// A signed transaction, ready to be broadcast through any endpoint
+#[derive(Debug, Clone)]
+struct SignedTx {
+    raw: Bytes,
+    hash: H256,
+}
+
+/// Fill in and sign a transaction with the provider's signer, without
+/// broadcasting it
+async fn sign_tx<M: Middleware + 'static>(
+    tx: &TypedTransaction,
+    provider: &M,
+) -> ChainResult<SignedTx> {
+    let mut tx = tx.clone();
+    provider
+        .fill_transaction(&mut tx, None)
+        .await
+        .map_err(ChainCommunicationError::from_other)?;
+    let from = *tx.from().ok_or_else(|| {
+        ChainCommunicationError::from_other_str("Transaction has no sender to sign it with")
+    })?;
+    let signature = provider
+        .sign_transaction(&tx, from)
+        .await
+        .map_err(ChainCommunicationError::from_other)?;
+    let raw = tx.rlp_signed(&signature);
+    let hash = keccak256(&raw).into();
+    Ok(SignedTx { raw, hash })
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SendBundleParams {
+    txs: Vec<Bytes>,
+    block_number: U64,
+}
+
+/// Send a signed transaction to a private endpoint, targeting `block` if it
+/// takes bundles
+async fn send_privately<P: JsonRpcClient>(
+    private_endpoint: &Provider<P>,
+    mode: PrivateSubmissionMode,
+    tx: &SignedTx,
+    block: u64,
+) -> ChainResult<()> {
+    match mode {
+        PrivateSubmissionMode::Rpc => {
+            private_endpoint
+                .request::<_, TxHash>("eth_sendRawTransaction", [tx.raw.clone()])
+                .await?;
+        }
+        PrivateSubmissionMode::Bundle => {
+            let params = SendBundleParams {
+                txs: vec![tx.raw.clone()],
+                block_number: block.into(),
+            };
+            private_endpoint
+                .request::<_, serde_json::Value>("eth_sendBundle", [params])
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Submit a signed transaction to the private endpoint and wait for it to be
+/// included. If it isn't included within the configured number of blocks, or
+/// the private endpoint fails, the same transaction is broadcast to the
+/// public mempool. As it's the same transaction, only one of the two can ever
+/// be included.
+async fn submit_privately<M, P>(
+    tx: SignedTx,
+    provider: &M,
+    private_endpoint: &Provider<P>,
+    conf: &PrivateSubmissionConf,
+    polling_interval: Duration,
+) -> ChainResult<TxOutcome>
+where
+    M: Middleware + 'static,
+    P: JsonRpcClient,
+{
+    let tx_hash = tx.hash;
+    let block_number = move || async move {
+        provider
+            .get_block_number()
+            .await
+            .map(|n| n.as_u64())
+            .map_err(ChainCommunicationError::from_other)
+    };
+    let receipt = move || async move {
+        provider
+            .get_transaction_receipt(tx_hash)
+            .await
+            .map_err(ChainCommunicationError::from_other)
+    };
+    let outcome = |receipt: TransactionReceipt, submission_path: TxSubmissionPath| {
+        info!(?tx_hash, ?submission_path, "confirmed transaction");
+        TxOutcome {
+            submission_path,
+            ..TxOutcome::from(receipt)
+        }
+    };
+
+    let submitted_at = block_number().await?;
+    let mut target_block = submitted_at + 1;
+    match send_privately(private_endpoint, conf.mode, &tx, target_block).await {
+        Ok(()) => {
+            info!(?tx_hash, mode = ?conf.mode, "Dispatched tx privately");
+            loop {
+                if let Some(receipt) = receipt().await? {
+                    return Ok(outcome(receipt, TxSubmissionPath::Private));
+                }
+                let current_block = block_number().await?;
+                if current_block >= submitted_at + conf.fallback_after_blocks as u64 {
+                    warn!(
+                        ?tx_hash,
+                        blocks = conf.fallback_after_blocks,
+                        "Privately submitted tx wasn't included in time, broadcasting it publicly"
+                    );
+                    break;
+                }
+                if conf.mode == PrivateSubmissionMode::Bundle && current_block >= target_block {
+                    // Bundles are only valid for the block they target
+                    target_block = current_block + 1;
+                    if let Err(err) =
+                        send_privately(private_endpoint, conf.mode, &tx, target_block).await
+                    {
+                        warn!(?tx_hash, error = ?err, "Failed to resubmit bundle, broadcasting tx publicly");
+                        break;
+                    }
+                }
+                tokio::time::sleep(polling_interval).await;
+            }
+        }
+        Err(err) => {
+            warn!(?tx_hash, error = ?err, "Failed to submit tx privately, broadcasting it publicly");
+        }
+    }
+
+    provider
+        .send_raw_transaction(tx.raw.clone())
+        .await
+        .map_err(ChainCommunicationError::from_other)?;
+    info!(?tx_hash, "Dispatched tx");
+    let deadline = Instant::now() + PENDING_TRANSACTION_TIMEOUT;
+    loop {
+        if let Some(receipt) = receipt().await? {
+            return Ok(outcome(receipt, TxSubmissionPath::PublicFallback));
+        }
+        if Instant::now() >= deadline {
+            error!(?tx_hash, "waiting for receipt timed out");
+            return Err(ChainCommunicationError::TransactionTimeout());
+        }
+        tokio::time::sleep(polling_interval).await;
+    }
+}
+
+/// Populates the gas limit and price for a transaction. If a `gas_price_ceiling`
+/// is given, the transaction never pays more than it per unit of gas, and is
+/// refused with `ChainCommunicationError::GasPriceCeilingExceeded` if the
+/// current gas price is above it.
 pub(crate) async fn fill_tx_gas_params<M, D>(
     tx: ContractCall<M, D>,
     provider: Arc<M>,
     transaction_overrides: &TransactionOverrides,
+    gas_price_ceiling: Option<U256>,
 ) -> ChainResult<ContractCall<M, D>>
 where
     M: Middleware + 'static,
@@ -130,14 +323,15 @@ where
 
     if let Some(gas_price) = transaction_overrides.gas_price {
         // If the gas price is set, we treat as a non-EIP-1559 chain.
+        check_gas_price_ceiling(gas_price, gas_price_ceiling)?;
         return Ok(tx.gas_price(gas_price).gas(gas_limit));
     }
 
     let Ok((base_fee, max_fee, max_priority_fee)) =
-        estimate_eip1559_fees(provider, None, &latest_block).await
+        estimate_eip1559_fees(provider.clone(), None, &latest_block).await
     else {
         // Is not EIP 1559 chain
-        return Ok(tx.gas(gas_limit));
+        return fill_legacy_gas_price(tx.gas(gas_limit), provider, gas_price_ceiling).await;
     };
 
     // If the base fee is zero, just treat the chain as a non-EIP-1559 chain.
@@ -146,7 +340,7 @@ where
     // fee lower than 3 gwei because of privileged transactions being included by block
     // producers that have a lower priority fee.
     if base_fee.is_zero() {
-        return Ok(tx.gas(gas_limit));
+        return fill_legacy_gas_price(tx.gas(gas_limit), provider, gas_price_ceiling).await;
     }
 
     // Apply overrides for EIP 1559 tx params if they exist.
@@ -158,6 +352,8 @@ where
         .max_priority_fee_per_gas
         .map(Into::into)
         .unwrap_or(max_priority_fee);
+    let (max_fee, max_priority_fee) =
+        cap_eip1559_fees(base_fee, max_fee, max_priority_fee, gas_price_ceiling)?;
 
     // Is EIP 1559 chain
     let mut request = Eip1559TransactionRequest::new();
@@ -180,6 +376,57 @@ where
     Ok(eip_1559_tx.gas(gas_limit))
 }
 
+/// Without a ceiling, leaves the gas price of a legacy transaction to be filled
+/// by the provider when sending it. With one, fetches the current gas price and
+/// sets it, so that the price checked against the ceiling is the one paid.
+async fn fill_legacy_gas_price<M, D>(
+    tx: ContractCall<M, D>,
+    provider: Arc<M>,
+    gas_price_ceiling: Option<U256>,
+) -> ChainResult<ContractCall<M, D>>
+where
+    M: Middleware + 'static,
+    D: Detokenize,
+{
+    if gas_price_ceiling.is_none() {
+        return Ok(tx);
+    }
+    let gas_price: U256 = provider
+        .get_gas_price()
+        .await
+        .map_err(ChainCommunicationError::from_other)?
+        .into();
+    check_gas_price_ceiling(gas_price, gas_price_ceiling)?;
+    Ok(tx.gas_price(gas_price))
+}
+
+fn check_gas_price_ceiling(gas_price: U256, ceiling: Option<U256>) -> ChainResult<()> {
+    match ceiling {
+        Some(ceiling) if gas_price > ceiling => {
+            Err(ChainCommunicationError::GasPriceCeilingExceeded { gas_price, ceiling })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Caps the max fee of an EIP-1559 transaction at the ceiling. The transaction
+/// is refused if the base fee and priority fee alone are above it, since it
+/// would then not be included until the base fee declines anyway.
+fn cap_eip1559_fees(
+    base_fee: EthersU256,
+    max_fee: EthersU256,
+    max_priority_fee: EthersU256,
+    ceiling: Option<U256>,
+) -> ChainResult<(EthersU256, EthersU256)> {
+    let Some(ceiling) = ceiling else {
+        return Ok((max_fee, max_priority_fee));
+    };
+    let effective_gas_price = base_fee.saturating_add(max_priority_fee);
+    check_gas_price_ceiling(effective_gas_price.into(), Some(ceiling))?;
+    let max_fee = max_fee.min(ceiling.into());
+    Ok((max_fee, max_priority_fee.min(max_fee)))
+}
+
 type FeeEstimator = fn(EthersU256, Vec<Vec<EthersU256>>) -> (EthersU256, EthersU256);
 
 /// Pretty much a copy of the logic in ethers-rs (https://github.com/hyperlane-xyz/ethers-rs/blob/c9ced035628da59376c369be035facda1648577a/ethers-providers/src/provider.rs#L478)
@@ -235,3 +482,233 @@ where
         Ok(call)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::{sync::Arc, time::Duration};
+
+    use ethers::{
+        providers::{MockProvider, Provider},
+        types::{Bytes, TransactionReceipt, U64},
+    };
+    use ethers_core::types::U256 as EthersU256;
+    use hyperlane_core::{ChainCommunicationError, TxSubmissionPath, H256, U256};
+
+    use super::{cap_eip1559_fees, submit_privately, SendBundleParams, SignedTx};
+    use crate::{PrivateSubmissionConf, PrivateSubmissionMode};
+
+    fn signed_tx() -> SignedTx {
+        SignedTx {
+            raw: Bytes::from(vec![0xde, 0xad, 0xbe, 0xef]),
+            hash: H256::from_low_u64_be(42),
+        }
+    }
+
+    fn conf(mode: PrivateSubmissionMode, fallback_after_blocks: u32) -> PrivateSubmissionConf {
+        PrivateSubmissionConf {
+            url: "http://127.0.0.1:8545".parse().unwrap(),
+            mode,
+            fallback_after_blocks,
+        }
+    }
+
+    fn receipt(tx: &SignedTx) -> Option<TransactionReceipt> {
+        Some(TransactionReceipt {
+            transaction_hash: tx.hash.into(),
+            status: Some(1.into()),
+            ..Default::default()
+        })
+    }
+
+    fn providers() -> (
+        Provider<Arc<MockProvider>>,
+        Arc<MockProvider>,
+        Provider<Arc<MockProvider>>,
+        Arc<MockProvider>,
+    ) {
+        let public = Arc::new(MockProvider::new());
+        let private = Arc::new(MockProvider::new());
+        (
+            Provider::new(public.clone()),
+            public,
+            Provider::new(private.clone()),
+            private,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_private_submission_included_privately() {
+        let tx = signed_tx();
+        let (provider, public, private_endpoint, private) = providers();
+        // The MockProvider responses we push are processed in LIFO order
+        public.push(receipt(&tx)).unwrap();
+        public.push(U64::from(101)).unwrap();
+        public.push(None::<TransactionReceipt>).unwrap();
+        public.push(U64::from(100)).unwrap();
+        private.push(tx.hash).unwrap();
+
+        let outcome = submit_privately(
+            tx.clone(),
+            &provider,
+            &private_endpoint,
+            &conf(PrivateSubmissionMode::Rpc, 2),
+            Duration::ZERO,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(outcome.transaction_id, tx.hash.into());
+        assert_eq!(outcome.submission_path, TxSubmissionPath::Private);
+        private
+            .assert_request("eth_sendRawTransaction", [tx.raw.clone()])
+            .unwrap();
+        // The transaction never went through the public mempool
+        public.assert_request("eth_blockNumber", ()).unwrap();
+        public
+            .assert_request("eth_getTransactionReceipt", [tx.hash])
+            .unwrap();
+        public.assert_request("eth_blockNumber", ()).unwrap();
+        public
+            .assert_request("eth_getTransactionReceipt", [tx.hash])
+            .unwrap();
+        assert!(public.assert_request("eth_blockNumber", ()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_private_submission_falls_back_after_blocks() {
+        let tx = signed_tx();
+        let (provider, public, private_endpoint, private) = providers();
+        public.push(receipt(&tx)).unwrap();
+        public.push(tx.hash).unwrap();
+        public.push(U64::from(102)).unwrap();
+        public.push(None::<TransactionReceipt>).unwrap();
+        public.push(U64::from(101)).unwrap();
+        public.push(None::<TransactionReceipt>).unwrap();
+        public.push(U64::from(100)).unwrap();
+        private.push(tx.hash).unwrap();
+
+        let outcome = submit_privately(
+            tx.clone(),
+            &provider,
+            &private_endpoint,
+            &conf(PrivateSubmissionMode::Rpc, 2),
+            Duration::ZERO,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(outcome.transaction_id, tx.hash.into());
+        assert_eq!(outcome.submission_path, TxSubmissionPath::PublicFallback);
+        for _ in 0..2 {
+            public.assert_request("eth_blockNumber", ()).unwrap();
+            public
+                .assert_request("eth_getTransactionReceipt", [tx.hash])
+                .unwrap();
+        }
+        // The same signed transaction is broadcast once the fallback block is reached
+        public.assert_request("eth_blockNumber", ()).unwrap();
+        public
+            .assert_request("eth_sendRawTransaction", [tx.raw.clone()])
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_private_submission_resubmits_bundles_for_each_block() {
+        let tx = signed_tx();
+        let (provider, public, private_endpoint, private) = providers();
+        public.push(receipt(&tx)).unwrap();
+        public.push(U64::from(101)).unwrap();
+        public.push(None::<TransactionReceipt>).unwrap();
+        public.push(U64::from(100)).unwrap();
+        private.push(serde_json::Value::Null).unwrap();
+        private.push(serde_json::Value::Null).unwrap();
+
+        let outcome = submit_privately(
+            tx.clone(),
+            &provider,
+            &private_endpoint,
+            &conf(PrivateSubmissionMode::Bundle, 3),
+            Duration::ZERO,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(outcome.submission_path, TxSubmissionPath::Private);
+        for block_number in [101u64, 102] {
+            private
+                .assert_request(
+                    "eth_sendBundle",
+                    [SendBundleParams {
+                        txs: vec![tx.raw.clone()],
+                        block_number: block_number.into(),
+                    }],
+                )
+                .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_private_submission_falls_back_when_endpoint_fails() {
+        let tx = signed_tx();
+        // No responses are pushed to the private endpoint, so it errors
+        let (provider, public, private_endpoint, _private) = providers();
+        public.push(receipt(&tx)).unwrap();
+        public.push(tx.hash).unwrap();
+        public.push(U64::from(100)).unwrap();
+
+        let outcome = submit_privately(
+            tx.clone(),
+            &provider,
+            &private_endpoint,
+            &conf(PrivateSubmissionMode::Rpc, 25),
+            Duration::ZERO,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(outcome.submission_path, TxSubmissionPath::PublicFallback);
+        public.assert_request("eth_blockNumber", ()).unwrap();
+        public
+            .assert_request("eth_sendRawTransaction", [tx.raw.clone()])
+            .unwrap();
+    }
+
+    fn gwei(amount: u64) -> EthersU256 {
+        EthersU256::from(amount) * EthersU256::exp10(9)
+    }
+
+    #[test]
+    fn test_eip1559_fees_are_capped_at_the_gas_price_ceiling() {
+        let ceiling = Some(U256::from(gwei(500)));
+
+        // No ceiling, the estimated fees are kept
+        assert_eq!(
+            cap_eip1559_fees(gwei(400), gwei(802), gwei(2), None).unwrap(),
+            (gwei(802), gwei(2))
+        );
+        // The base fee is below the ceiling, but the estimated max fee would
+        // let a later spike push the paid gas price past it
+        assert_eq!(
+            cap_eip1559_fees(gwei(400), gwei(802), gwei(2), ceiling).unwrap(),
+            (gwei(500), gwei(2))
+        );
+        // Fees are kept once they fit under the ceiling
+        assert_eq!(
+            cap_eip1559_fees(gwei(20), gwei(42), gwei(2), ceiling).unwrap(),
+            (gwei(42), gwei(2))
+        );
+    }
+
+    #[test]
+    fn test_eip1559_fees_above_the_gas_price_ceiling_are_refused() {
+        let ceiling = U256::from(gwei(500));
+
+        let err = cap_eip1559_fees(gwei(499), gwei(1000), gwei(2), Some(ceiling)).unwrap_err();
+
+        assert!(matches!(
+            err,
+            ChainCommunicationError::GasPriceCeilingExceeded { gas_price, ceiling: c }
+                if gas_price == U256::from(gwei(501)) && c == ceiling
+        ));
+    }
+}
@@ -8,10 +8,10 @@ use fuels::{
     types::{transaction::TxPolicies, Bytes},
 };
 use hyperlane_core::{
-    utils::bytes_to_hex, ChainCommunicationError, ChainResult, ContractLocator, HyperlaneAbi,
+    log_fields::LogBytes, ChainCommunicationError, ChainResult, ContractLocator, HyperlaneAbi,
     HyperlaneChain, HyperlaneContract, HyperlaneDomain, HyperlaneMessage, HyperlaneProvider,
     Indexed, Indexer, LogMeta, Mailbox, RawHyperlaneMessage, ReorgPeriod, SequenceAwareIndexer,
-    TxCostEstimate, TxOutcome, H256, H512, U256,
+    TxCostEstimate, TxOutcome, TxSubmissionPath, H256, H512, U256,
 };
 use std::{
     collections::HashMap,
@@ -123,7 +123,7 @@ impl Mailbox for FuelMailbox {
             .map_err(ChainCommunicationError::from_other)
     }
 
-    #[instrument(err, ret, skip(self))]
+    #[instrument(err, ret, skip(self, metadata), fields(metadata=%LogBytes(metadata)))]
     #[allow(clippy::blocks_in_conditions)] // TODO: `rustc` 1.80.1 clippy issue
     async fn process(
         &self,
@@ -180,11 +180,12 @@ impl Mailbox for FuelMailbox {
             executed: success,
             gas_used: call_res.gas_used.into(),
             gas_price: gas_price.into(),
+            submission_path: TxSubmissionPath::Public,
         })
     }
 
     // Process cost of the `process` method
-    #[instrument(err, ret, skip(self), fields(hyp_message=%message, metadata=%bytes_to_hex(metadata)))]
+    #[instrument(err, ret, skip(self), fields(hyp_message=%message, metadata=%LogBytes(metadata)))]
     #[allow(clippy::blocks_in_conditions)] // TODO: `rustc` 1.80.1 clippy issue
     async fn process_estimate_costs(
         &self,
@@ -51,12 +51,12 @@ use solana_transaction_status::{
 use tracing::{debug, info, instrument, warn};
 
 use hyperlane_core::{
-    accumulator::incremental::IncrementalMerkle, BatchItem, ChainCommunicationError,
-    ChainCommunicationError::ContractError, ChainResult, Checkpoint, ContractLocator, Decode as _,
-    Encode as _, FixedPointNumber, HyperlaneAbi, HyperlaneChain, HyperlaneContract,
-    HyperlaneDomain, HyperlaneMessage, HyperlaneProvider, Indexed, Indexer, KnownHyperlaneDomain,
-    LogMeta, Mailbox, MerkleTreeHook, ReorgPeriod, SequenceAwareIndexer, TxCostEstimate, TxOutcome,
-    H256, H512, U256,
+    accumulator::incremental::IncrementalMerkle, log_fields::LogBytes, BatchItem,
+    ChainCommunicationError, ChainCommunicationError::ContractError, ChainResult, Checkpoint,
+    ContractLocator, Decode as _, Encode as _, FixedPointNumber, HyperlaneAbi, HyperlaneChain,
+    HyperlaneContract, HyperlaneDomain, HyperlaneMessage, HyperlaneProvider, Indexed, Indexer,
+    KnownHyperlaneDomain, LogMeta, Mailbox, MerkleTreeHook, ReorgPeriod, SequenceAwareIndexer,
+    TxCostEstimate, TxOutcome, TxSubmissionPath, H256, H512, U256,
 };
 
 use crate::account::{search_accounts_by_discriminator, search_and_validate_account};
@@ -471,7 +471,7 @@ impl Mailbox for SealevelMailbox {
         Ok(ism_pubkey.to_bytes().into())
     }
 
-    #[instrument(err, ret, skip(self))]
+    #[instrument(err, ret, skip(self, metadata), fields(metadata=%LogBytes(metadata)))]
     async fn process(
         &self,
         message: &HyperlaneMessage,
@@ -614,10 +614,11 @@ impl Mailbox for SealevelMailbox {
             // TODO use correct data upon integrating IGP support
             gas_price: U256::zero().try_into()?,
             gas_used: U256::zero(),
+            submission_path: TxSubmissionPath::Public,
         })
     }
 
-    #[instrument(err, ret, skip(self))]
+    #[instrument(err, ret, skip(self, _metadata), fields(metadata=%LogBytes(_metadata)))]
     async fn process_estimate_costs(
         &self,
         _message: &HyperlaneMessage,
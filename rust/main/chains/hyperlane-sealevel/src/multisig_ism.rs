@@ -68,7 +68,7 @@ impl MultisigIsm for SealevelMultisigIsm {
         &self,
         message: &HyperlaneMessage,
     ) -> ChainResult<(Vec<H256>, u8)> {
-        let message_bytes = RawHyperlaneMessage::from(message).to_vec();
+        let message_bytes = RawHyperlaneMessage::from(message);
 
         let account_metas = self
             .get_validators_and_threshold_account_metas(message_bytes.clone())
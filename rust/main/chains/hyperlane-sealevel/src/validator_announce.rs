@@ -1,7 +1,7 @@
 use async_trait::async_trait;
 use hyperlane_core::{
     Announcement, ChainResult, ContractLocator, HyperlaneChain, HyperlaneContract, HyperlaneDomain,
-    SignedType, TxOutcome, ValidatorAnnounce, H160, H256, H512, U256,
+    SignedType, TxOutcome, TxSubmissionPath, ValidatorAnnounce, H160, H256, H512, U256,
 };
 use hyperlane_sealevel_validator_announce::{
     accounts::ValidatorStorageLocationsAccount, validator_storage_locations_pda_seeds,
@@ -122,6 +122,7 @@ impl ValidatorAnnounce for SealevelValidatorAnnounce {
             executed: false,
             gas_used: U256::zero(),
             gas_price: U256::zero().try_into()?,
+            submission_path: TxSubmissionPath::Public,
         })
     }
 }
@@ -0,0 +1,240 @@
+//! Histograms which attach the trace id of the active span to a sample of
+//! their observations as OpenMetrics exemplars, linking a latency to the trace
+//! of the request or message it was measured for.
+//!
+//! Observations are recorded twice: to a plain prometheus histogram, served on
+//! classic text format scrapes exactly as before, and to an OpenMetrics one
+//! carrying the exemplars, served to scrapers accepting OpenMetrics instead.
+//! Exemplars are only attached while spans are traced with OpenTelemetry.
+
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use opentelemetry::trace::{TraceContextExt, TraceId};
+use parking_lot::Mutex;
+use prometheus::core::Collector;
+use prometheus::{Histogram, HistogramVec};
+use prometheus_client::encoding::{text, EncodeLabelSet};
+use prometheus_client::metrics::exemplar::HistogramWithExemplars;
+use prometheus_client::metrics::family::{Family, MetricConstructor};
+use prometheus_client::registry::Registry;
+use rand::Rng;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+/// Share of the observations an exemplar is attached to by default. Each
+/// bucket keeps only its latest exemplar, so sampling mostly bounds the cost
+/// of looking up the trace and how often exemplars churn between scrapes.
+pub const DEFAULT_EXEMPLAR_SAMPLE_RATE: f64 = 0.1;
+
+/// Labels of an exemplar: the trace the observation was made in.
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+pub struct TraceExemplar {
+    /// Hex encoded OpenTelemetry trace id.
+    pub trace_id: String,
+}
+
+/// The histograms which carry exemplars, and the share of observations they
+/// attach one to. Clones share both.
+#[derive(Clone)]
+pub struct ExemplarRegistry {
+    registry: Arc<Mutex<Registry>>,
+    /// Names of the registered histograms
+    names: Arc<Mutex<Vec<String>>>,
+    /// Bits of the `f64` sample rate
+    sample_rate: Arc<AtomicU64>,
+}
+
+impl Default for ExemplarRegistry {
+    fn default() -> Self {
+        Self {
+            registry: Default::default(),
+            names: Default::default(),
+            sample_rate: Arc::new(AtomicU64::new(DEFAULT_EXEMPLAR_SAMPLE_RATE.to_bits())),
+        }
+    }
+}
+
+impl Debug for ExemplarRegistry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "ExemplarRegistry {{ names: {:?}, sample_rate: {} }}",
+            self.names.lock(),
+            self.sample_rate()
+        )
+    }
+}
+
+impl ExemplarRegistry {
+    /// Share of the observations an exemplar is attached to, between 0 and 1.
+    pub fn sample_rate(&self) -> f64 {
+        f64::from_bits(self.sample_rate.load(Ordering::Relaxed))
+    }
+
+    /// Set the share of the observations an exemplar is attached to, clamped
+    /// to between 0 and 1. Applies to the histograms already registered too.
+    pub fn set_sample_rate(&self, sample_rate: f64) {
+        let sample_rate = sample_rate.clamp(0., 1.);
+        self.sample_rate
+            .store(sample_rate.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Attach exemplars to the observations of `histogram`, which must have
+    /// been created with `buckets`. Its name, help and labels are reused for
+    /// the OpenMetrics histogram.
+    pub fn register(&self, histogram: HistogramVec, buckets: &[f64]) -> ExemplarHistogramVec {
+        let desc = histogram.desc()[0].clone();
+        let const_labels = desc
+            .const_label_pairs
+            .iter()
+            .map(|pair| (pair.get_name().to_owned(), pair.get_value().to_owned()))
+            .collect();
+        let exemplars = Family::new_with_constructor(Buckets(buckets.into()));
+        self.registry
+            .lock()
+            .register(&desc.fq_name, desc.help, exemplars.clone());
+        self.names.lock().push(desc.fq_name);
+        ExemplarHistogramVec {
+            label_names: desc.variable_labels.into(),
+            const_labels: Arc::new(const_labels),
+            histogram,
+            exemplars,
+            sample_rate: self.sample_rate.clone(),
+        }
+    }
+
+    /// Whether `name` is the name of a registered histogram.
+    pub fn contains(&self, name: &str) -> bool {
+        self.names.lock().iter().any(|registered| registered == name)
+    }
+
+    /// Encode the registered histograms in the OpenMetrics text format,
+    /// including the closing `# EOF`.
+    pub fn encode(&self, out: &mut String) -> std::fmt::Result {
+        text::encode(out, &self.registry.lock())
+    }
+}
+
+/// Label sets of the OpenMetrics histograms, const labels first
+type LabelSet = Vec<(String, String)>;
+
+/// Creates the OpenMetrics histograms of a family
+#[derive(Clone, Debug)]
+struct Buckets(Arc<[f64]>);
+
+impl MetricConstructor<HistogramWithExemplars<TraceExemplar>> for Buckets {
+    fn new_metric(&self) -> HistogramWithExemplars<TraceExemplar> {
+        HistogramWithExemplars::new(self.0.iter().copied())
+    }
+}
+
+/// A [`HistogramVec`] which attaches the trace id of the active span to a
+/// sample of its observations as OpenMetrics exemplars. Created by
+/// [`ExemplarRegistry::register`].
+#[derive(Clone)]
+pub struct ExemplarHistogramVec {
+    label_names: Arc<[String]>,
+    const_labels: Arc<LabelSet>,
+    histogram: HistogramVec,
+    exemplars: Family<LabelSet, HistogramWithExemplars<TraceExemplar>, Buckets>,
+    sample_rate: Arc<AtomicU64>,
+}
+
+impl Debug for ExemplarHistogramVec {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ExemplarHistogramVec({:?})", self.histogram.desc()[0].fq_name)
+    }
+}
+
+impl ExemplarHistogramVec {
+    /// The histogram with the given label values, in the order of the label
+    /// names. Panics like [`HistogramVec::with_label_values`] if their number
+    /// doesn't match.
+    pub fn with_label_values(&self, values: &[&str]) -> ExemplarHistogram {
+        let histogram = self.histogram.with_label_values(values);
+        let labels = self
+            .label_names
+            .iter()
+            .zip(values)
+            .map(|(name, value)| (name.clone(), (*value).to_owned()));
+        self.histogram_with(histogram, labels)
+    }
+
+    /// The histogram with the given labels. Panics like [`HistogramVec::with`]
+    /// if they don't match the label names.
+    pub fn with(&self, labels: &HashMap<&str, &str>) -> ExemplarHistogram {
+        let histogram = self.histogram.with(labels);
+        let labels = self
+            .label_names
+            .iter()
+            .map(|name| (name.clone(), labels[name.as_str()].to_owned()));
+        self.histogram_with(histogram, labels)
+    }
+
+    fn histogram_with(
+        &self,
+        histogram: Histogram,
+        labels: impl Iterator<Item = (String, String)>,
+    ) -> ExemplarHistogram {
+        let labels: LabelSet = self.const_labels.iter().cloned().chain(labels).collect();
+        ExemplarHistogram {
+            histogram,
+            exemplars: self.exemplars.get_or_create(&labels).clone(),
+            sample_rate: self.sample_rate.clone(),
+        }
+    }
+}
+
+/// A [`Histogram`] which attaches the trace id of the active span to a sample
+/// of its observations as OpenMetrics exemplars.
+#[derive(Clone)]
+pub struct ExemplarHistogram {
+    histogram: Histogram,
+    exemplars: HistogramWithExemplars<TraceExemplar>,
+    sample_rate: Arc<AtomicU64>,
+}
+
+impl Debug for ExemplarHistogram {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ExemplarHistogram({:?})", self.histogram.desc()[0].fq_name)
+    }
+}
+
+impl ExemplarHistogram {
+    /// Observe `value`, attaching the trace id of the active span as an
+    /// exemplar if the observation is sampled.
+    pub fn observe(&self, value: f64) {
+        self.histogram.observe(value);
+        let sample_rate = f64::from_bits(self.sample_rate.load(Ordering::Relaxed));
+        let exemplar = (sample_rate > 0. && rand::thread_rng().gen_bool(sample_rate))
+            .then(current_trace_id)
+            .flatten()
+            .map(|trace_id| TraceExemplar {
+                trace_id: trace_id.to_string(),
+            });
+        self.exemplars.observe(value, exemplar);
+    }
+
+    /// Number of observations.
+    pub fn get_sample_count(&self) -> u64 {
+        self.histogram.get_sample_count()
+    }
+
+    /// Sum of the observed values.
+    pub fn get_sample_sum(&self) -> f64 {
+        self.histogram.get_sample_sum()
+    }
+}
+
+/// The id of the trace the active span is part of, if spans are traced with
+/// OpenTelemetry.
+pub fn current_trace_id() -> Option<TraceId> {
+    let context = tracing::Span::current().context();
+    let span = context.span();
+    let span_context = span.span_context();
+    span_context
+        .is_valid()
+        .then(|| span_context.trace_id())
+}
@@ -15,6 +15,7 @@ use maplit::hashmap;
 use prometheus::{CounterVec, IntCounterVec};
 use serde::{de::DeserializeOwned, Serialize};
 
+use crate::exemplar::ExemplarHistogramVec;
 pub use crate::ChainInfo;
 
 /// Some basic information about a node.
@@ -51,6 +52,18 @@ pub struct JsonRpcClientMetrics {
     ///   might still be an "error" but not one with the transport layer.
     #[builder(setter(into, strip_option), default)]
     request_duration_seconds: Option<CounterVec>,
+
+    /// Distribution of the latency of requests, in seconds, with the trace
+    /// of a sample of them as exemplars.
+    /// - `provider_node`: node this is connecting to, e.g. `alchemy.com`,
+    ///   `quicknode.pro`, or `localhost:8545`.
+    /// - `chain`: chain name (or chain id if the name is unknown) of the chain
+    ///   the request was made on.
+    /// - `method`: request method string.
+    /// - `status`: `success` or `failure` depending on the response. A `success`
+    ///   might still be an "error" but not one with the transport layer.
+    #[builder(setter(into, strip_option), default)]
+    request_latency_seconds: Option<ExemplarHistogramVec>,
 }
 
 /// Expected label names for the metric.
@@ -64,6 +77,16 @@ pub const REQUEST_DURATION_SECONDS_LABELS: &[&str] =
 /// Help string for the metric.
 pub const REQUEST_DURATION_SECONDS_HELP: &str = "Total number of seconds spent making requests";
 
+/// Expected label names for the metric.
+pub const REQUEST_LATENCY_SECONDS_LABELS: &[&str] = &["provider_node", "chain", "method", "status"];
+/// Help string for the metric.
+pub const REQUEST_LATENCY_SECONDS_HELP: &str =
+    "Latency of requests made to this client, in seconds";
+/// Buckets of the metric, from a local node to a congested remote one.
+pub const REQUEST_LATENCY_SECONDS_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+];
+
 /// Configuration for the prometheus JsonRpcClioent. This can be loaded via
 /// serde.
 #[derive(Default, Clone, Debug)]
@@ -177,10 +200,12 @@ where
         if let Some(counter) = &self.metrics.request_count {
             counter.with(&labels).inc()
         }
+        let elapsed = (Instant::now() - start).as_secs_f64();
         if let Some(counter) = &self.metrics.request_duration_seconds {
-            counter
-                .with(&labels)
-                .inc_by((Instant::now() - start).as_secs_f64())
+            counter.with(&labels).inc_by(elapsed)
+        };
+        if let Some(histogram) = &self.metrics.request_latency_seconds {
+            histogram.with(&labels).observe(elapsed)
         };
         res
     }
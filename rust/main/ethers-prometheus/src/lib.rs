@@ -5,6 +5,7 @@
 
 mod contracts;
 
+pub mod exemplar;
 pub mod json_rpc_client;
 pub mod middleware;
 
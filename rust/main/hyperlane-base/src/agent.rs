@@ -27,6 +27,10 @@ pub trait LoadableFromSettings: AsRef<Settings> + Sized {
     /// Create a new instance of these settings by reading the configs and env
     /// vars.
     fn load() -> ConfigResult<Self>;
+
+    /// Like [`LoadableFromSettings::load`], but reading command line overrides
+    /// from `args` rather than the process arguments.
+    fn load_from_args(args: Vec<String>) -> ConfigResult<Self>;
 }
 
 /// A fundamental agent which does not make any assumptions about the tools
@@ -87,6 +91,12 @@ pub async fn agent_main<A: BaseAgent>() -> Result<()> {
 
     let metrics = settings.as_ref().metrics(A::AGENT_NAME)?;
     let tokio_server = core_settings.tracing.start_tracing(&metrics)?;
+    let config_hash = &core_settings.config_fingerprint.hash;
+    metrics.set_config_hash(config_hash);
+    info!(agent = A::AGENT_NAME, %config_hash, "Loaded config");
+    core_settings.warn_bare_numbers();
+    core_settings.warn_deprecated_domain_names();
+    core_settings.warn_unknown_domains();
     let agent_metrics = AgentMetrics::new(&metrics)?;
     let chain_metrics = ChainMetrics::new(&metrics)?;
     let agent = A::from_settings(
@@ -9,7 +9,8 @@ use async_trait::async_trait;
 use derive_new::new;
 use eyre::Result;
 use hyperlane_core::{
-    ContractSyncCursor, CursorAction, HyperlaneWatermarkedLogStore, Indexed, Indexer, LogMeta,
+    ContractSyncCursor, CursorAction, HyperlaneCoreResult, HyperlaneWatermarkedLogStore, Indexed,
+    Indexer, LogMeta,
 };
 
 use crate::contract_sync::eta_calculator::SyncerEtaCalculator;
@@ -162,7 +163,7 @@ impl<T> ContractSyncCursor<T> for RateLimitedContractSyncCursor<T>
 where
     T: Send + Sync + Debug + 'static,
 {
-    async fn next_action(&mut self) -> Result<(CursorAction, Duration)> {
+    async fn next_action(&mut self) -> HyperlaneCoreResult<(CursorAction, Duration)> {
         let eta = self.sync_eta();
 
         let rate_limit = self.get_rate_limit().await?;
@@ -186,7 +187,7 @@ where
         &mut self,
         _: Vec<(Indexed<T>, LogMeta)>,
         range: RangeInclusive<u32>,
-    ) -> Result<()> {
+    ) -> HyperlaneCoreResult<()> {
         // Store a relatively conservative view of the high watermark, which should allow a single watermark to be
         // safely shared across multiple cursors, so long as they are running sufficiently in sync
         self.db
@@ -210,7 +211,8 @@ where
                 return Err(eyre::eyre!(
                     "Failed to update the cursor because we could not get the current tip: {}",
                     e
-                ))
+                )
+                .into())
             }
         }
     }
@@ -258,13 +260,13 @@ pub(crate) mod test {
 
         #[async_trait]
         impl HyperlaneLogStore<()> for Db {
-            async fn store_logs(&self, logs: &[(hyperlane_core::Indexed<()> , LogMeta)]) -> Result<u32>;
+            async fn store_logs(&self, logs: &[(hyperlane_core::Indexed<()> , LogMeta)]) -> HyperlaneCoreResult<u32>;
         }
 
         #[async_trait]
         impl HyperlaneWatermarkedLogStore<()> for Db {
-            async fn retrieve_high_watermark(&self) -> Result<Option<u32>>;
-            async fn store_high_watermark(&self, block_number: u32) -> Result<()>;
+            async fn retrieve_high_watermark(&self) -> HyperlaneCoreResult<Option<u32>>;
+            async fn store_high_watermark(&self, block_number: u32) -> HyperlaneCoreResult<()>;
         }
     }
 
@@ -5,7 +5,7 @@ use std::{collections::HashSet, fmt::Debug, ops::RangeInclusive, sync::Arc, time
 use async_trait::async_trait;
 use eyre::Result;
 use hyperlane_core::{
-    indexed_to_sequence_indexed_array, ContractSyncCursor, CursorAction,
+    indexed_to_sequence_indexed_array, ContractSyncCursor, CursorAction, HyperlaneCoreResult,
     HyperlaneSequenceAwareIndexerStoreReader, IndexMode, Indexed, LogMeta, SequenceIndexed,
 };
 use itertools::Itertools;
@@ -13,6 +13,7 @@ use tokio::time::sleep;
 use tracing::{debug, instrument, warn};
 
 use super::{LastIndexedSnapshot, TargetSnapshot};
+use crate::pacing::{BackfillProgress, RebuildPacer};
 
 const MAX_BACKWARD_SYNC_BLOCKING_TIME: Duration = Duration::from_secs(5);
 
@@ -33,6 +34,17 @@ pub(crate) struct BackwardSequenceAwareSyncCursor<T> {
     current_indexing_snapshot: Option<TargetSnapshot>,
     /// The mode of indexing to use.
     index_mode: IndexMode,
+    /// Paces querying, if backfilling is limited to an RPC budget.
+    pacer: Option<RebuildPacer>,
+    /// The highest sequence this cursor backfills, i.e. the one preceding the
+    /// sequence count it started at, if any.
+    backfill_high: Option<u32>,
+    /// Progress persisted by a previous run, which is skipped to once reached.
+    resume: Option<BackfillProgress>,
+    /// Number of ranges queried, and sequences they yielded, to estimate the
+    /// number of ranges left in block mode.
+    queries: u64,
+    queried_sequences: u64,
 }
 
 impl<T> Debug for BackwardSequenceAwareSyncCursor<T> {
@@ -73,9 +85,27 @@ impl<T: Debug> BackwardSequenceAwareSyncCursor<T> {
             current_indexing_snapshot: last_indexed_snapshot.previous_target(),
             last_indexed_snapshot,
             index_mode,
+            pacer: None,
+            backfill_high: current_sequence_count.checked_sub(1),
+            resume: None,
+            queries: 0,
+            queried_sequences: 0,
         }
     }
 
+    /// Paces querying with `pacer`, resuming from the progress it persisted
+    pub fn with_pacer(mut self, pacer: RebuildPacer) -> Self {
+        self.resume = pacer.persisted_progress();
+        self.pacer = Some(pacer);
+        self
+    }
+
+    /// How long to wait before querying the next range, if backfilling is
+    /// paced and the budget doesn't allow it yet
+    pub fn pace(&self) -> Option<Duration> {
+        self.pacer.as_ref()?.pace()
+    }
+
     /// Gets the next range of logs to query.
     /// If the cursor is fully synced, this returns None.
     /// Otherwise, it returns the next range to query, either by block or sequence depending on the mode.
@@ -87,6 +117,7 @@ impl<T: Debug> BackwardSequenceAwareSyncCursor<T> {
             // return early to allow the forward cursor to also make progress
             _ = sleep(MAX_BACKWARD_SYNC_BLOCKING_TIME) => { return Ok(None); }
         };
+        self.report_progress();
 
         // If `self.current_indexing_snapshot` is None, we are synced and there are no more ranges to query.
         // Otherwise, we query the next range, searching for logs prior to and including the current indexing snapshot.
@@ -140,6 +171,18 @@ impl<T: Debug> BackwardSequenceAwareSyncCursor<T> {
                     sequence: Some(current_indexing_sequence),
                     at_block: block_number,
                 };
+                // Everything down to where a previous run stopped is indexed
+                if let Some(resume) = self.resume.filter(|resume| {
+                    (resume.low_sequence..=resume.high_sequence)
+                        .contains(&current_indexing_sequence)
+                }) {
+                    self.resume = None;
+                    self.last_indexed_snapshot = LastIndexedSnapshot {
+                        sequence: Some(resume.low_sequence),
+                        at_block: resume.low_block,
+                    };
+                    debug!(?resume, "Resumed backfill from persisted progress");
+                }
 
                 self.current_indexing_snapshot = self.last_indexed_snapshot.previous_target();
 
@@ -166,7 +209,7 @@ impl<T: Debug> BackwardSequenceAwareSyncCursor<T> {
     /// log for the sequence number hasn't been indexed.
     async fn get_sequence_log_block_number(&self, sequence: u32) -> Result<Option<u32>> {
         // Ensure there's a full entry for the sequence.
-        if self.db.retrieve_by_sequence(sequence).await?.is_some() {
+        if self.db.is_sequence_stored(sequence).await? {
             // And get the block number.
             if let Some(block_number) = self
                 .db
@@ -325,16 +368,57 @@ impl<T: Debug> BackwardSequenceAwareSyncCursor<T> {
     fn rewind(&mut self) {
         self.current_indexing_snapshot = self.last_indexed_snapshot.previous_target();
     }
+
+    /// Estimated number of ranges left to query, if it can be estimated yet
+    fn remaining_chunks(&self) -> Option<u64> {
+        let Some(current_indexing_snapshot) = &self.current_indexing_snapshot else {
+            return Some(0);
+        };
+        let remaining_sequences = u64::from(current_indexing_snapshot.sequence) + 1;
+        let (chunks, sequences) = match &self.index_mode {
+            IndexMode::Sequence => (1, u64::from(self.chunk_size.max(1))),
+            // The number of sequences in a block range varies, so the ones
+            // queried so far are extrapolated
+            IndexMode::Block if self.queried_sequences > 0 => {
+                (self.queries, self.queried_sequences)
+            }
+            IndexMode::Block => return None,
+        };
+        Some((remaining_sequences * chunks + sequences - 1) / sequences)
+    }
+
+    /// Persists the range indexed without gaps and updates the estimated
+    /// time left, if backfilling is paced
+    fn report_progress(&self) {
+        let Some(pacer) = &self.pacer else {
+            return;
+        };
+        if let (Some(high_sequence), Some(low_sequence)) =
+            (self.backfill_high, self.last_indexed_snapshot.sequence)
+        {
+            if low_sequence <= high_sequence {
+                pacer.persist_progress(BackfillProgress {
+                    low_sequence,
+                    low_block: self.last_indexed_snapshot.at_block,
+                    high_sequence,
+                });
+            }
+        }
+        pacer.set_remaining_chunks(self.remaining_chunks());
+    }
 }
 
 #[async_trait]
 impl<T: Send + Sync + Clone + Debug + 'static> ContractSyncCursor<T>
     for BackwardSequenceAwareSyncCursor<T>
 {
-    async fn next_action(&mut self) -> Result<(CursorAction, Duration)> {
+    async fn next_action(&mut self) -> HyperlaneCoreResult<(CursorAction, Duration)> {
         // TODO: Fix ETA calculation
         let eta = Duration::from_secs(0);
         if let Some(range) = self.get_next_range().await? {
+            if let Some(delay) = self.pace() {
+                return Ok((CursorAction::Sleep(delay), eta));
+            }
             Ok((CursorAction::Query(range), eta))
         } else {
             // TODO: Define the sleep time from interval flag
@@ -363,7 +447,7 @@ impl<T: Send + Sync + Clone + Debug + 'static> ContractSyncCursor<T>
         &mut self,
         logs: Vec<(Indexed<T>, LogMeta)>,
         range: RangeInclusive<u32>,
-    ) -> Result<()> {
+    ) -> HyperlaneCoreResult<()> {
         let Some(current_indexing_snapshot) = self.current_indexing_snapshot.clone() else {
             // We're synced, no need to update at all.
             return Ok(());
@@ -382,6 +466,8 @@ impl<T: Send + Sync + Clone + Debug + 'static> ContractSyncCursor<T>
             .map(|(log, _)| log.sequence)
             .collect::<HashSet<_>>();
 
+        self.queries += 1;
+        self.queried_sequences += all_log_sequences.len() as u64;
         match &self.index_mode {
             IndexMode::Sequence => self.update_sequence_range(
                 logs,
@@ -393,6 +479,7 @@ impl<T: Send + Sync + Clone + Debug + 'static> ContractSyncCursor<T>
                 self.update_block_range(logs, &all_log_sequences, range, current_indexing_snapshot)?
             }
         }
+        self.report_progress();
 
         Ok(())
     }
@@ -1024,4 +1111,61 @@ mod test {
             assert_eq!(range, None);
         }
     }
+
+    #[tokio::test]
+    async fn test_resumes_from_persisted_progress() {
+        use crate::db::{test_utils, HyperlaneRocksDB};
+        use crate::pacing::RebuildPacingConf;
+        use hyperlane_core::{HyperlaneDomain, KnownHyperlaneDomain};
+
+        test_utils::run_test_db(|rocks| async move {
+            let domain = HyperlaneDomain::Known(KnownHyperlaneDomain::Test1);
+            let pacer = || {
+                RebuildPacer::new(RebuildPacingConf::default(), "test")
+                    .with_db(HyperlaneRocksDB::new(&domain, rocks.clone()))
+            };
+            // A previous run, started at sequence count 51, got down to sequence 3
+            pacer().persist_progress(BackfillProgress {
+                low_sequence: 3,
+                low_block: 30,
+                high_sequence: 50,
+            });
+            // Since then, sequences up to 60 were indexed going forward
+            let db = Arc::new(MockHyperlaneSequenceAwareIndexerStore {
+                logs: (50..=60)
+                    .map(|i| {
+                        (
+                            MockSequencedData::new(i),
+                            log_meta_with_block(i as u64 * 10),
+                        )
+                    })
+                    .collect(),
+            });
+            let pacer = pacer();
+            let mut cursor =
+                BackwardSequenceAwareSyncCursor::new(5, db, 61, 610, IndexMode::Sequence)
+                    .with_pacer(pacer.clone());
+
+            // Sequences the previous run indexed are skipped without a lookup
+            let range = cursor.get_next_range().await.unwrap();
+            assert_eq!(range, Some(0..=2));
+            assert_eq!(
+                cursor.last_indexed_snapshot,
+                LastIndexedSnapshot {
+                    sequence: Some(3),
+                    at_block: 30,
+                }
+            );
+            assert_eq!(
+                pacer.persisted_progress(),
+                Some(BackfillProgress {
+                    low_sequence: 3,
+                    low_block: 30,
+                    high_sequence: 60,
+                })
+            );
+            assert_eq!(pacer.status().remaining_chunks, Some(1));
+        })
+        .await;
+    }
 }
@@ -8,7 +8,7 @@ use std::{
 use async_trait::async_trait;
 use eyre::Result;
 use hyperlane_core::{
-    indexed_to_sequence_indexed_array, ContractSyncCursor, CursorAction,
+    indexed_to_sequence_indexed_array, ContractSyncCursor, CursorAction, HyperlaneCoreResult,
     HyperlaneSequenceAwareIndexerStoreReader, IndexMode, Indexed, LogMeta, SequenceAwareIndexer,
     SequenceIndexed,
 };
@@ -221,7 +221,7 @@ impl<T: Debug> ForwardSequenceAwareSyncCursor<T> {
     /// log for the sequence number hasn't been indexed.
     async fn get_sequence_log_block_number(&self, sequence: u32) -> Result<Option<u32>> {
         // Ensure there's a full entry for the sequence.
-        if self.db.retrieve_by_sequence(sequence).await?.is_some() {
+        if self.db.is_sequence_stored(sequence).await? {
             // And get the block number.
             if let Some(block_number) = self
                 .db
@@ -407,7 +407,7 @@ impl<T: Debug> ForwardSequenceAwareSyncCursor<T> {
 impl<T: Send + Sync + Clone + Debug + 'static> ContractSyncCursor<T>
     for ForwardSequenceAwareSyncCursor<T>
 {
-    async fn next_action(&mut self) -> Result<(CursorAction, Duration)> {
+    async fn next_action(&mut self) -> HyperlaneCoreResult<(CursorAction, Duration)> {
         // TODO: Fix ETA calculation
         let eta = Duration::from_secs(0);
         if let Some(range) = self.get_next_range().await? {
@@ -443,7 +443,7 @@ impl<T: Send + Sync + Clone + Debug + 'static> ContractSyncCursor<T>
         &mut self,
         logs: Vec<(Indexed<T>, LogMeta)>,
         range: RangeInclusive<u32>,
-    ) -> Result<()> {
+    ) -> HyperlaneCoreResult<()> {
         // Remove any sequence duplicates, filter out any logs preceding our current snapshot,
         // and sort in ascending order.
         let logs = indexed_to_sequence_indexed_array(logs)?
@@ -513,7 +513,7 @@ pub(crate) mod test {
 
     #[async_trait]
     impl<T: Sequenced + Debug> HyperlaneLogStore<T> for MockHyperlaneSequenceAwareIndexerStore<T> {
-        async fn store_logs(&self, logs: &[(Indexed<T>, LogMeta)]) -> eyre::Result<u32> {
+        async fn store_logs(&self, logs: &[(Indexed<T>, LogMeta)]) -> HyperlaneCoreResult<u32> {
             Ok(logs.len() as u32)
         }
     }
@@ -522,7 +522,7 @@ pub(crate) mod test {
     impl<T: Sequenced + Debug + Clone> HyperlaneSequenceAwareIndexerStoreReader<T>
         for MockHyperlaneSequenceAwareIndexerStore<T>
     {
-        async fn retrieve_by_sequence(&self, sequence: u32) -> eyre::Result<Option<T>> {
+        async fn retrieve_by_sequence(&self, sequence: u32) -> HyperlaneCoreResult<Option<T>> {
             Ok(self
                 .logs
                 .iter()
@@ -533,7 +533,7 @@ pub(crate) mod test {
         async fn retrieve_log_block_number_by_sequence(
             &self,
             sequence: u32,
-        ) -> eyre::Result<Option<u64>> {
+        ) -> HyperlaneCoreResult<Option<u64>> {
             Ok(self
                 .logs
                 .iter()
@@ -3,11 +3,13 @@ use std::{fmt::Debug, sync::Arc, time::Duration};
 use async_trait::async_trait;
 use eyre::Result;
 use hyperlane_core::{
-    ChainCommunicationError, ContractSyncCursor, CursorAction,
+    ChainCommunicationError, ContractSyncCursor, CursorAction, HyperlaneCoreResult,
     HyperlaneSequenceAwareIndexerStoreReader, IndexMode, Indexed, LogMeta, SequenceAwareIndexer,
 };
 use std::ops::RangeInclusive;
 
+use crate::pacing::RebuildPacer;
+
 mod backward;
 mod forward;
 
@@ -76,6 +78,7 @@ impl<T: Debug> ForwardBackwardSequenceAwareSyncCursor<T> {
         db: Arc<dyn HyperlaneSequenceAwareIndexerStoreReader<T>>,
         chunk_size: u32,
         mode: IndexMode,
+        pacer: Option<RebuildPacer>,
     ) -> Result<Self> {
         let (sequence_count, tip) = latest_sequence_querier
             .latest_sequence_count_and_tip()
@@ -91,8 +94,11 @@ impl<T: Debug> ForwardBackwardSequenceAwareSyncCursor<T> {
             tip,
             mode,
         );
-        let backward_cursor =
+        let mut backward_cursor =
             BackwardSequenceAwareSyncCursor::new(chunk_size, db, sequence_count, tip, mode);
+        if let Some(pacer) = pacer {
+            backward_cursor = backward_cursor.with_pacer(pacer);
+        }
         Ok(Self {
             forward: forward_cursor,
             backward: backward_cursor,
@@ -105,7 +111,7 @@ impl<T: Debug> ForwardBackwardSequenceAwareSyncCursor<T> {
 impl<T: Send + Sync + Clone + Debug + 'static> ContractSyncCursor<T>
     for ForwardBackwardSequenceAwareSyncCursor<T>
 {
-    async fn next_action(&mut self) -> Result<(CursorAction, Duration)> {
+    async fn next_action(&mut self) -> HyperlaneCoreResult<(CursorAction, Duration)> {
         // TODO: Proper ETA for backwards sync
         let eta = Duration::from_secs(0);
         // Prioritize forward syncing over backward syncing.
@@ -115,6 +121,10 @@ impl<T: Send + Sync + Clone + Debug + 'static> ContractSyncCursor<T>
         }
 
         if let Some(backward_range) = self.backward.get_next_range().await? {
+            // Backfilling may be limited to an RPC budget, unlike following the tip
+            if let Some(delay) = self.backward.pace() {
+                return Ok((CursorAction::Sleep(delay), eta));
+            }
             self.last_direction = SyncDirection::Backward;
             return Ok((CursorAction::Query(backward_range), eta));
         }
@@ -130,10 +140,103 @@ impl<T: Send + Sync + Clone + Debug + 'static> ContractSyncCursor<T>
         &mut self,
         logs: Vec<(Indexed<T>, LogMeta)>,
         range: RangeInclusive<u32>,
-    ) -> Result<()> {
+    ) -> HyperlaneCoreResult<()> {
         match self.last_direction {
             SyncDirection::Forward => self.forward.update(logs, range).await,
             SyncDirection::Backward => self.backward.update(logs, range).await,
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::sync::Mutex;
+
+    use hyperlane_core::{ChainResult, Indexer, Sequenced};
+
+    use super::forward::test::*;
+    use super::*;
+    use crate::pacing::{RebuildPacingConf, MAX_PACING_SLEEP};
+
+    /// Sequence count and tip that can move while the cursor runs
+    #[derive(Debug, Default)]
+    struct MovingTip(Mutex<(u32, u32)>);
+
+    #[async_trait]
+    impl<T: Sequenced + Debug> SequenceAwareIndexer<T> for MovingTip {
+        async fn latest_sequence_count_and_tip(&self) -> ChainResult<(Option<u32>, u32)> {
+            let (count, tip) = *self.0.lock().unwrap();
+            Ok((Some(count), tip))
+        }
+    }
+
+    #[async_trait]
+    impl<T: Sequenced + Debug> Indexer<T> for MovingTip {
+        async fn fetch_logs_in_range(
+            &self,
+            _range: RangeInclusive<u32>,
+        ) -> ChainResult<Vec<(Indexed<T>, LogMeta)>> {
+            Ok(vec![])
+        }
+
+        async fn get_finalized_block_number(&self) -> ChainResult<u32> {
+            Ok(self.0.lock().unwrap().1)
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_pacing_only_applies_to_backfilling() {
+        let tip = Arc::new(MovingTip(Mutex::new((10, 100))));
+        let db =
+            Arc::new(MockHyperlaneSequenceAwareIndexerStore::<MockSequencedData> { logs: vec![] });
+        // One chunk per minute
+        let pacer = RebuildPacer::new(
+            RebuildPacingConf {
+                calls_per_minute: Some(1),
+                chunk_sleep: Duration::ZERO,
+            },
+            "test",
+        );
+        let mut cursor = ForwardBackwardSequenceAwareSyncCursor::new(
+            tip.clone(),
+            db,
+            5,
+            IndexMode::Sequence,
+            Some(pacer.clone()),
+        )
+        .await
+        .unwrap();
+
+        // The first backfill chunk is within the budget
+        let (action, _) = cursor.next_action().await.unwrap();
+        assert!(matches!(action, CursorAction::Query(range) if range == (4..=9)));
+        assert!(matches!(cursor.last_direction, SyncDirection::Backward));
+        let logs = (4..=9)
+            .map(|i| (MockSequencedData::new(i).into(), log_meta_with_block(90)))
+            .collect();
+        cursor.update(logs, 4..=9).await.unwrap();
+        assert_eq!(pacer.eta(), Some(Duration::from_secs(60)));
+
+        // The next one has to wait
+        let (action, _) = cursor.next_action().await.unwrap();
+        assert!(matches!(action, CursorAction::Sleep(delay) if delay <= MAX_PACING_SLEEP));
+
+        // While the tip is followed right away
+        *tip.0.lock().unwrap() = (12, 110);
+        let (action, _) = cursor.next_action().await.unwrap();
+        assert!(matches!(action, CursorAction::Query(range) if range == (10..=11)));
+        assert!(matches!(cursor.last_direction, SyncDirection::Forward));
+        let logs = (10..=11)
+            .map(|i| (MockSequencedData::new(i).into(), log_meta_with_block(105)))
+            .collect();
+        cursor.update(logs, 10..=11).await.unwrap();
+
+        // A raised budget lets backfilling go faster
+        pacer.raise_budget(60, Duration::from_secs(600));
+        assert_eq!(pacer.eta(), Some(Duration::from_secs(1)));
+        tokio::time::advance(Duration::from_secs(1)).await;
+        let (action, _) = cursor.next_action().await.unwrap();
+        assert!(matches!(action, CursorAction::Query(range) if range == (0..=3)));
+        assert!(matches!(cursor.last_direction, SyncDirection::Backward));
+    }
+}
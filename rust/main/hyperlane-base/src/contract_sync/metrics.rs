@@ -20,6 +20,13 @@ pub struct ContractSyncMetrics {
 
     /// See `last_known_message_nonce` in CoreMetrics.
     pub message_nonce: IntGaugeVec,
+
+    /// Estimated seconds until a paced backfill completes, or -1 if unknown.
+    ///
+    /// Labels:
+    /// - `data_type`: the data the indexer is recording. E.g. `messages` or `gas_payments`.
+    /// - `chain`: Chain the indexer is collecting data from.
+    pub backfill_eta: IntGaugeVec,
 }
 
 impl ContractSyncMetrics {
@@ -43,10 +50,19 @@ impl ContractSyncMetrics {
 
         let message_nonce = metrics.last_known_message_nonce();
 
+        let backfill_eta = metrics
+            .new_int_gauge(
+                "contract_sync_backfill_eta_seconds",
+                "Estimated seconds until a paced backfill completes, -1 if unknown",
+                &["data_type", "chain"],
+            )
+            .expect("failed to register backfill_eta metric");
+
         ContractSyncMetrics {
             indexed_height,
             stored_events,
             message_nonce,
+            backfill_eta,
         }
     }
 }
@@ -19,13 +19,14 @@ use tokio::sync::mpsc::{error::TryRecvError, Receiver as MpscReceiver};
 use tokio::time::sleep;
 use tracing::{debug, info, instrument, trace, warn};
 
-use crate::settings::IndexSettings;
+use crate::{db::DiskGuard, settings::IndexSettings, WithChainContext};
 
 /// Broadcast channel utility, with async interface for `send`
 pub mod broadcast;
 pub(crate) mod cursors;
 mod eta_calculator;
 mod metrics;
+pub mod pacing;
 
 use cursors::ForwardBackwardSequenceAwareSyncCursor;
 
@@ -95,32 +96,61 @@ where
             .with_label_values(&[label, chain_name]);
 
         loop {
+            // No new work is taken while the disk of the db is almost full
+            if is_frozen(opts.disk_guard.as_ref()) {
+                sleep(SLEEP_DURATION).await;
+                continue;
+            }
             if let Some(rx) = opts.tx_id_receiver.as_mut() {
-                self.fetch_logs_from_receiver(rx, &stored_logs_metric).await;
+                self.fetch_logs_from_receiver(rx, &stored_logs_metric, opts.disk_guard.as_ref())
+                    .await;
             }
             if let Some(cursor) = opts.cursor.as_mut() {
-                self.fetch_logs_with_cursor(cursor, &stored_logs_metric, &indexed_height_metric)
-                    .await;
+                self.fetch_logs_with_cursor(
+                    cursor,
+                    &stored_logs_metric,
+                    &indexed_height_metric,
+                    opts.disk_guard.as_ref(),
+                )
+                .await;
             }
         }
     }
 
-    #[instrument(fields(domain=self.domain().name()), skip(self, recv, stored_logs_metric))]
+    #[instrument(
+        fields(domain=self.domain().name()),
+        skip(self, recv, stored_logs_metric, disk_guard)
+    )]
     async fn fetch_logs_from_receiver(
         &self,
         recv: &mut MpscReceiver<H512>,
         stored_logs_metric: &GenericCounter<AtomicU64>,
+        disk_guard: Option<&DiskGuard>,
     ) {
-        loop {
+        // Tx ids left in the channel are picked up once the freeze is lifted
+        while !is_frozen(disk_guard) {
             match recv.try_recv() {
                 Ok(tx_id) => {
-                    let logs = match self.indexer.fetch_logs_by_tx_hash(tx_id).await {
+                    let logs = match self
+                        .indexer
+                        .fetch_logs_by_tx_hash(tx_id)
+                        .await
+                        .with_chain_context(&self.domain)
+                    {
                         Ok(logs) => logs,
                         Err(err) => {
                             warn!(?err, ?tx_id, "Error fetching logs for tx id");
                             continue;
                         }
                     };
+                    // Logs of a tx id dropped here are indexed by the cursor
+                    if is_frozen(disk_guard) {
+                        debug!(
+                            ?tx_id,
+                            "Disk space is critically low, dropping fetched logs"
+                        );
+                        break;
+                    }
                     let logs = self.dedupe_and_store_logs(logs, stored_logs_metric).await;
                     let num_logs = logs.len() as u64;
                     info!(
@@ -143,12 +173,16 @@ where
         }
     }
 
-    #[instrument(fields(domain=self.domain().name()), skip(self, stored_logs_metric, indexed_height_metric))]
+    #[instrument(
+        fields(domain=self.domain().name()),
+        skip(self, stored_logs_metric, indexed_height_metric, disk_guard)
+    )]
     async fn fetch_logs_with_cursor(
         &self,
         cursor: &mut Box<dyn ContractSyncCursor<T>>,
         stored_logs_metric: &GenericCounter<AtomicU64>,
         indexed_height_metric: &GenericGauge<AtomicI64>,
+        disk_guard: Option<&DiskGuard>,
     ) {
         indexed_height_metric.set(cursor.latest_queried_block() as i64);
         let (action, eta) = match cursor.next_action().await {
@@ -166,13 +200,28 @@ where
             CursorAction::Query(range) => loop {
                 debug!(?range, "Looking for events in index range");
 
-                let logs = match self.indexer.fetch_logs_in_range(range.clone()).await {
+                let logs = match self
+                    .indexer
+                    .fetch_logs_in_range(range.clone())
+                    .await
+                    .with_chain_context(&self.domain)
+                {
                     Ok(logs) => logs,
                     Err(err) => {
                         warn!(?err, ?range, "Error fetching logs in range");
                         break Some(SLEEP_DURATION);
                     }
                 };
+                // The disk may have filled up while fetching. The batch is
+                // dropped whole, leaving the cursor to query the range again
+                // once the freeze is lifted.
+                if is_frozen(disk_guard) {
+                    debug!(
+                        ?range,
+                        "Disk space is critically low, dropping fetched logs"
+                    );
+                    break Some(SLEEP_DURATION);
+                }
 
                 let logs = self.dedupe_and_store_logs(logs, stored_logs_metric).await;
                 let logs_found = logs.len() as u64;
@@ -277,6 +326,17 @@ pub struct SyncOptions<T> {
     // txids from a channel to other indexing tasks
     cursor: Option<Box<dyn ContractSyncCursor<T>>>,
     tx_id_receiver: Option<MpscReceiver<H512>>,
+    /// Syncing pauses while it's frozen
+    #[new(default)]
+    disk_guard: Option<DiskGuard>,
+}
+
+impl<T> SyncOptions<T> {
+    /// Pause syncing while the disk of the db is almost full
+    pub fn with_disk_guard(mut self, disk_guard: Option<DiskGuard>) -> Self {
+        self.disk_guard = disk_guard;
+        self
+    }
 }
 
 impl<T> From<Box<dyn ContractSyncCursor<T>>> for SyncOptions<T> {
@@ -284,10 +344,15 @@ impl<T> From<Box<dyn ContractSyncCursor<T>>> for SyncOptions<T> {
         Self {
             cursor: Some(cursor),
             tx_id_receiver: None,
+            disk_guard: None,
         }
     }
 }
 
+fn is_frozen(disk_guard: Option<&DiskGuard>) -> bool {
+    disk_guard.is_some_and(DiskGuard::is_frozen)
+}
+
 #[async_trait]
 impl<T> ContractSyncer<T> for WatermarkContractSync<T>
 where
@@ -301,8 +366,7 @@ where
         let watermark = self.db.retrieve_high_watermark().await.unwrap();
         let index_settings = IndexSettings {
             from: watermark.unwrap_or(index_settings.from),
-            chunk_size: index_settings.chunk_size,
-            mode: index_settings.mode,
+            ..index_settings
         };
         Ok(Box::new(
             RateLimitedContractSyncCursor::new(
@@ -351,6 +415,7 @@ where
                 Arc::new(self.db.clone()),
                 index_settings.chunk_size,
                 index_settings.mode,
+                index_settings.pacer,
             )
             .await?,
         ))
@@ -368,3 +433,170 @@ where
         ContractSync::get_broadcaster(self)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::{
+        ops::RangeInclusive,
+        sync::{Arc, Mutex},
+    };
+
+    use hyperlane_core::{
+        ChainResult, HyperlaneCoreResult, HyperlaneMessage, KnownHyperlaneDomain,
+    };
+    use prometheus::Registry;
+
+    use super::*;
+    use crate::{
+        db::{
+            test_utils::{disk_watcher, MockFreeSpaceProbe},
+            DiskWatcher,
+        },
+        CoreMetrics,
+    };
+
+    /// Fills the disk up while fetching when told to
+    #[derive(Debug)]
+    struct MockIndexer {
+        probe: MockFreeSpaceProbe,
+        watcher: Arc<DiskWatcher>,
+        fill_disk_while_fetching: Mutex<bool>,
+    }
+
+    #[async_trait]
+    impl Indexer<HyperlaneMessage> for MockIndexer {
+        async fn fetch_logs_in_range(
+            &self,
+            range: RangeInclusive<u32>,
+        ) -> ChainResult<Vec<(Indexed<HyperlaneMessage>, LogMeta)>> {
+            if std::mem::take(&mut *self.fill_disk_while_fetching.lock().unwrap()) {
+                self.probe.set(0);
+                self.watcher.check();
+            }
+            Ok(range
+                .map(|nonce| {
+                    let message = HyperlaneMessage {
+                        nonce,
+                        ..Default::default()
+                    };
+                    (message.into(), LogMeta::default())
+                })
+                .collect())
+        }
+
+        async fn get_finalized_block_number(&self) -> ChainResult<u32> {
+            Ok(0)
+        }
+    }
+
+    #[derive(Debug, Default, Clone)]
+    struct MockStore {
+        stored: Arc<Mutex<Vec<u32>>>,
+    }
+
+    impl MockStore {
+        fn stored(&self) -> Vec<u32> {
+            let mut stored = self.stored.lock().unwrap().clone();
+            stored.sort();
+            stored
+        }
+    }
+
+    #[async_trait]
+    impl HyperlaneLogStore<HyperlaneMessage> for MockStore {
+        async fn store_logs(
+            &self,
+            logs: &[(Indexed<HyperlaneMessage>, LogMeta)],
+        ) -> HyperlaneCoreResult<u32> {
+            let mut stored = self.stored.lock().unwrap();
+            stored.extend(logs.iter().map(|(log, _)| log.inner().nonce));
+            Ok(logs.len() as u32)
+        }
+    }
+
+    /// Queries the same range until it's updated with it
+    #[derive(Debug, Default)]
+    struct MockCursor {
+        updates: Arc<Mutex<Vec<RangeInclusive<u32>>>>,
+    }
+
+    #[async_trait]
+    impl ContractSyncCursor<HyperlaneMessage> for MockCursor {
+        async fn next_action(&mut self) -> HyperlaneCoreResult<(CursorAction, Duration)> {
+            Ok((CursorAction::Query(0..=2), Duration::ZERO))
+        }
+
+        fn latest_queried_block(&self) -> u32 {
+            0
+        }
+
+        async fn update(
+            &mut self,
+            _logs: Vec<(Indexed<HyperlaneMessage>, LogMeta)>,
+            range: RangeInclusive<u32>,
+        ) -> HyperlaneCoreResult<()> {
+            self.updates.lock().unwrap().push(range);
+            Ok(())
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_a_batch_fetched_as_the_disk_fills_up_is_dropped_whole() {
+        let probe = MockFreeSpaceProbe::default();
+        probe.set(u64::MAX);
+        let watcher = Arc::new(disk_watcher(&probe));
+        watcher.check();
+        let guard = watcher.guard();
+        let store = MockStore::default();
+        let indexer = MockIndexer {
+            probe: probe.clone(),
+            watcher: watcher.clone(),
+            fill_disk_while_fetching: Mutex::new(true),
+        };
+        let metrics =
+            ContractSyncMetrics::new(&CoreMetrics::new("test", 0, Registry::new()).unwrap());
+        let sync = ContractSync::new(
+            HyperlaneDomain::Known(KnownHyperlaneDomain::Ethereum),
+            store.clone(),
+            indexer,
+            metrics,
+        );
+        let updates = Arc::new(Mutex::new(vec![]));
+        let mut cursor: Box<dyn ContractSyncCursor<HyperlaneMessage>> = Box::new(MockCursor {
+            updates: updates.clone(),
+        });
+        let stored_logs_metric = sync
+            .metrics
+            .stored_events
+            .with_label_values(&["test", "test"]);
+        let indexed_height_metric = sync
+            .metrics
+            .indexed_height
+            .with_label_values(&["test", "test"]);
+
+        // Nothing of the batch is stored, nor is the cursor moved past it
+        sync.fetch_logs_with_cursor(
+            &mut cursor,
+            &stored_logs_metric,
+            &indexed_height_metric,
+            Some(&guard),
+        )
+        .await;
+        assert!(guard.is_frozen());
+        assert!(store.stored().is_empty());
+        assert!(updates.lock().unwrap().is_empty());
+
+        // The same batch is indexed in full once space is freed up
+        probe.set(u64::MAX);
+        watcher.check();
+        sync.fetch_logs_with_cursor(
+            &mut cursor,
+            &stored_logs_metric,
+            &indexed_height_metric,
+            Some(&guard),
+        )
+        .await;
+        assert_eq!(store.stored(), vec![0, 1, 2]);
+        assert_eq!(*updates.lock().unwrap(), vec![0..=2]);
+    }
+}
@@ -0,0 +1,400 @@
+//! Pacing of backfill cursors, which rebuild history backward from the tip
+//! the agent started at, so that full-history rebuilds stay within an RPC
+//! budget that public RPCs tolerate. Cursors following the tip are never
+//! paced.
+//!
+//! Every chunk a backfill cursor queries is counted as one RPC call. Chunks
+//! are spaced by the interval the calls-per-minute budget allows, and at
+//! least by the configured per-chunk sleep. The budget can be raised for a
+//! while, e.g. for a supervised fast rebuild, after which it falls back to
+//! the configured one.
+
+use std::{
+    io::{Read, Write},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use hyperlane_core::{Decode, Encode, HyperlaneProtocolError};
+use prometheus::IntGauge;
+use serde::Serialize;
+use tokio::time::Instant;
+use tracing::warn;
+
+use crate::db::HyperlaneRocksDB;
+
+/// Longest a cursor sleeps for at once while waiting on the budget, so that
+/// the tip keeps being followed
+pub const MAX_PACING_SLEEP: Duration = Duration::from_secs(5);
+
+/// Pacing of backfill cursors
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RebuildPacingConf {
+    /// Maximum number of chunks queried per minute. None for no limit, zero
+    /// to pause backfilling.
+    pub calls_per_minute: Option<u32>,
+    /// Minimum time between two chunks
+    pub chunk_sleep: Duration,
+}
+
+/// Range of sequences a backfill cursor indexed without gaps, persisted so
+/// that an interrupted rebuild resumes from where it stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BackfillProgress {
+    /// Lowest sequence of the range
+    pub low_sequence: u32,
+    /// Block of the lowest sequence
+    pub low_block: u32,
+    /// Highest sequence of the range
+    pub high_sequence: u32,
+}
+
+impl Encode for BackfillProgress {
+    fn write_to<W>(&self, writer: &mut W) -> std::io::Result<usize>
+    where
+        W: Write,
+    {
+        Ok(self.low_sequence.write_to(writer)?
+            + self.low_block.write_to(writer)?
+            + self.high_sequence.write_to(writer)?)
+    }
+}
+
+impl Decode for BackfillProgress {
+    fn read_from<R>(reader: &mut R) -> Result<Self, HyperlaneProtocolError>
+    where
+        R: Read,
+        Self: Sized,
+    {
+        Ok(Self {
+            low_sequence: u32::read_from(reader)?,
+            low_block: u32::read_from(reader)?,
+            high_sequence: u32::read_from(reader)?,
+        })
+    }
+}
+
+/// Pacing state of a backfill, as reported by the relayer's admin server
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PacingStatus {
+    /// Budget in effect, in calls per minute
+    pub calls_per_minute: Option<u32>,
+    /// Budget from the config, in effect once a raise expires
+    pub configured_calls_per_minute: Option<u32>,
+    /// Seconds until a raised budget expires
+    pub raised_for_seconds: Option<u64>,
+    /// Estimated number of chunks left to backfill
+    pub remaining_chunks: Option<u64>,
+    /// Estimated seconds until the backfill completes at the current pace
+    pub eta_seconds: Option<u64>,
+}
+
+#[derive(Debug)]
+struct PacerState {
+    conf: RebuildPacingConf,
+    /// Raised budget and when it expires
+    raised: Option<(u32, Instant)>,
+    last_call: Option<Instant>,
+    /// Time between the two latest calls
+    observed_interval: Option<Duration>,
+    remaining_chunks: Option<u64>,
+    persisted: Option<BackfillProgress>,
+}
+
+impl PacerState {
+    fn calls_per_minute(&mut self, now: Instant) -> Option<u32> {
+        if matches!(self.raised, Some((_, until)) if until <= now) {
+            self.raised = None;
+        }
+        match self.raised {
+            Some((raised, _)) => Some(raised),
+            None => self.conf.calls_per_minute,
+        }
+    }
+
+    /// Time between two calls allowed by the budget, or None if paused
+    fn interval(&mut self, now: Instant) -> Option<Duration> {
+        let budget = match self.calls_per_minute(now) {
+            Some(0) => return None,
+            Some(calls) => Duration::from_secs(60) / calls,
+            None => Duration::ZERO,
+        };
+        Some(budget.max(self.conf.chunk_sleep))
+    }
+
+    fn eta(&mut self, now: Instant) -> Option<Duration> {
+        let remaining = self.remaining_chunks?;
+        if remaining == 0 {
+            return Some(Duration::ZERO);
+        }
+        let interval = self.interval(now)?;
+        // Without a limit, the pace is whatever the RPC allows
+        let pace = if interval.is_zero() {
+            self.observed_interval?
+        } else {
+            interval
+        };
+        Some(pace.saturating_mul(remaining.try_into().unwrap_or(u32::MAX)))
+    }
+}
+
+/// Paces the backfill cursor of some data on an origin. Clones share their
+/// state, so the budget can be changed while the cursor runs.
+#[derive(Debug, Clone)]
+pub struct RebuildPacer {
+    state: Arc<Mutex<PacerState>>,
+    /// The data being backfilled, e.g. `merkle_tree_hook`
+    data_type: &'static str,
+    db: Option<HyperlaneRocksDB>,
+    eta_metric: Option<IntGauge>,
+}
+
+impl RebuildPacer {
+    /// Paces backfilling `data_type` according to `conf`
+    pub fn new(conf: RebuildPacingConf, data_type: &'static str) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(PacerState {
+                conf,
+                raised: None,
+                last_call: None,
+                observed_interval: None,
+                remaining_chunks: None,
+                persisted: None,
+            })),
+            data_type,
+            db: None,
+            eta_metric: None,
+        }
+    }
+
+    /// Persist the backfill's progress in `db`
+    pub fn with_db(mut self, db: HyperlaneRocksDB) -> Self {
+        self.db = Some(db);
+        self
+    }
+
+    /// Report the estimated seconds until the backfill completes to `metric`
+    pub fn with_eta_metric(mut self, metric: IntGauge) -> Self {
+        self.eta_metric = Some(metric);
+        self
+    }
+
+    /// Budget in effect, in calls per minute
+    pub fn calls_per_minute(&self) -> Option<u32> {
+        self.state.lock().unwrap().calls_per_minute(Instant::now())
+    }
+
+    /// Use `calls_per_minute` as the budget for `duration`, after which the
+    /// configured budget applies again
+    pub fn raise_budget(&self, calls_per_minute: u32, duration: Duration) {
+        let mut state = self.state.lock().unwrap();
+        state.raised = Some((calls_per_minute, Instant::now() + duration));
+        self.report_eta(&mut state);
+    }
+
+    /// Go back to the configured budget
+    pub fn reset_budget(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.raised = None;
+        self.report_eta(&mut state);
+    }
+
+    /// How long to wait before the next chunk can be queried, if at all.
+    /// Otherwise, the chunk about to be queried is counted against the
+    /// budget.
+    pub fn pace(&self) -> Option<Duration> {
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap();
+        let Some(interval) = state.interval(now) else {
+            return Some(MAX_PACING_SLEEP);
+        };
+        if let Some(last_call) = state.last_call {
+            let next_call = last_call + interval;
+            if next_call > now {
+                return Some((next_call - now).min(MAX_PACING_SLEEP));
+            }
+            state.observed_interval = Some(now - last_call);
+        }
+        state.last_call = Some(now);
+        None
+    }
+
+    /// Update the estimated number of chunks left to backfill
+    pub fn set_remaining_chunks(&self, remaining_chunks: Option<u64>) {
+        let mut state = self.state.lock().unwrap();
+        state.remaining_chunks = remaining_chunks;
+        self.report_eta(&mut state);
+    }
+
+    /// Estimated time until the backfill completes at the current pace
+    pub fn eta(&self) -> Option<Duration> {
+        self.state.lock().unwrap().eta(Instant::now())
+    }
+
+    /// Pacing state, as reported by the relayer's admin server
+    pub fn status(&self) -> PacingStatus {
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap();
+        let calls_per_minute = state.calls_per_minute(now);
+        PacingStatus {
+            calls_per_minute,
+            configured_calls_per_minute: state.conf.calls_per_minute,
+            raised_for_seconds: state
+                .raised
+                .map(|(_, until)| until.saturating_duration_since(now).as_secs()),
+            remaining_chunks: state.remaining_chunks,
+            eta_seconds: state.eta(now).map(|eta| eta.as_secs()),
+        }
+    }
+
+    /// The progress persisted by a previous run, if any
+    pub fn persisted_progress(&self) -> Option<BackfillProgress> {
+        let db = self.db.as_ref()?;
+        match db.retrieve_backfill_progress(self.data_type) {
+            Ok(progress) => {
+                self.state.lock().unwrap().persisted = progress;
+                progress
+            }
+            Err(err) => {
+                warn!(
+                    ?err,
+                    data_type = self.data_type,
+                    "Error retrieving backfill progress"
+                );
+                None
+            }
+        }
+    }
+
+    /// Persist the backfill's progress, unless it's unchanged
+    pub fn persist_progress(&self, progress: BackfillProgress) {
+        let Some(db) = &self.db else {
+            return;
+        };
+        let mut state = self.state.lock().unwrap();
+        if state.persisted == Some(progress) {
+            return;
+        }
+        match db.store_backfill_progress(self.data_type, &progress) {
+            Ok(()) => state.persisted = Some(progress),
+            Err(err) => warn!(
+                ?err,
+                data_type = self.data_type,
+                "Error storing backfill progress"
+            ),
+        }
+    }
+
+    fn report_eta(&self, state: &mut PacerState) {
+        if let Some(metric) = &self.eta_metric {
+            let eta = state.eta(Instant::now());
+            // -1 while the ETA is unknown, e.g. when paused
+            metric.set(eta.map(|eta| eta.as_secs() as i64).unwrap_or(-1));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::test_utils;
+    use hyperlane_core::{HyperlaneDomain, KnownHyperlaneDomain};
+
+    fn pacer(calls_per_minute: Option<u32>, chunk_sleep: Duration) -> RebuildPacer {
+        RebuildPacer::new(
+            RebuildPacingConf {
+                calls_per_minute,
+                chunk_sleep,
+            },
+            "test",
+        )
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_pacing_respects_the_budget() {
+        let pacer = pacer(Some(6), Duration::from_secs(2));
+        let start = Instant::now();
+        let mut calls = vec![];
+        while calls.len() < 4 {
+            match pacer.pace() {
+                Some(delay) => tokio::time::sleep(delay).await,
+                None => calls.push(Instant::now() - start),
+            }
+        }
+        // 6 calls per minute leaves 10s between calls, above the chunk sleep
+        let expected: Vec<_> = (0..4).map(|i| Duration::from_secs(10 * i)).collect();
+        assert_eq!(calls, expected);
+
+        // The chunk sleep applies when the budget allows more
+        let pacer = self::pacer(None, Duration::from_secs(2));
+        assert_eq!(pacer.pace(), None);
+        assert_eq!(pacer.pace(), Some(Duration::from_secs(2)));
+
+        // Waits are capped so that the tip keeps being followed, and a zero
+        // budget pauses backfilling
+        let pacer = self::pacer(Some(1), Duration::ZERO);
+        assert_eq!(pacer.pace(), None);
+        assert_eq!(pacer.pace(), Some(MAX_PACING_SLEEP));
+        let paused = self::pacer(Some(0), Duration::ZERO);
+        for _ in 0..3 {
+            assert_eq!(paused.pace(), Some(MAX_PACING_SLEEP));
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_eta_follows_the_budget() {
+        let metric = IntGauge::new("eta", "help").unwrap();
+        let pacer = pacer(Some(6), Duration::ZERO).with_eta_metric(metric.clone());
+        assert_eq!(pacer.eta(), None);
+
+        pacer.set_remaining_chunks(Some(30));
+        assert_eq!(pacer.eta(), Some(Duration::from_secs(300)));
+        assert_eq!(metric.get(), 300);
+
+        // A raised budget speeds things up until it expires
+        pacer.raise_budget(60, Duration::from_secs(120));
+        assert_eq!(pacer.calls_per_minute(), Some(60));
+        assert_eq!(pacer.eta(), Some(Duration::from_secs(30)));
+        assert_eq!(metric.get(), 30);
+        assert_eq!(pacer.status().raised_for_seconds, Some(120));
+        tokio::time::advance(Duration::from_secs(120)).await;
+        assert_eq!(pacer.calls_per_minute(), Some(6));
+        assert_eq!(pacer.eta(), Some(Duration::from_secs(300)));
+
+        pacer.raise_budget(0, Duration::from_secs(60));
+        assert_eq!(pacer.eta(), None);
+        assert_eq!(metric.get(), -1);
+        pacer.reset_budget();
+        assert_eq!(metric.get(), 300);
+
+        // Without a limit, the observed pace is used
+        let unlimited = self::pacer(None, Duration::ZERO);
+        unlimited.set_remaining_chunks(Some(10));
+        assert_eq!(unlimited.eta(), None);
+        assert_eq!(unlimited.pace(), None);
+        tokio::time::advance(Duration::from_secs(3)).await;
+        assert_eq!(unlimited.pace(), None);
+        assert_eq!(unlimited.eta(), Some(Duration::from_secs(30)));
+    }
+
+    #[tokio::test]
+    async fn test_progress_is_persisted() {
+        test_utils::run_test_db(|db| async move {
+            let domain = HyperlaneDomain::Known(KnownHyperlaneDomain::Test1);
+            let db = HyperlaneRocksDB::new(&domain, db);
+            let pacer = pacer(None, Duration::ZERO).with_db(db.clone());
+            assert_eq!(pacer.persisted_progress(), None);
+
+            let progress = BackfillProgress {
+                low_sequence: 10,
+                low_block: 1000,
+                high_sequence: 99,
+            };
+            pacer.persist_progress(progress);
+            let restarted = self::pacer(None, Duration::ZERO).with_db(db);
+            assert_eq!(restarted.persisted_progress(), Some(progress));
+        })
+        .await;
+    }
+}
@@ -0,0 +1,55 @@
+use prometheus::IntCounter;
+use tracing::{error, warn};
+
+use super::DbResult;
+
+/// Lets a scan over the db skip corrupted records instead of stopping at the
+/// first one, up to `tolerance` of them. Past that, the corruption is likely
+/// not isolated and the scan is aborted with the error of the next one.
+#[derive(Debug, Clone)]
+pub struct CorruptionTolerance {
+    tolerance: u32,
+    skipped: u32,
+    skipped_metric: IntCounter,
+}
+
+impl CorruptionTolerance {
+    /// Skip up to `tolerance` corrupted records, counting them in
+    /// `skipped_metric`
+    pub fn new(tolerance: u32, skipped_metric: IntCounter) -> Self {
+        Self {
+            tolerance,
+            skipped: 0,
+            skipped_metric,
+        }
+    }
+
+    /// Pass a read through, unless it found a corrupted record, which is
+    /// skipped and reported as `None` while within the tolerance. Errors other
+    /// than corruption are passed through as well.
+    pub fn skip_corrupted<T>(&mut self, read: DbResult<T>) -> DbResult<Option<T>> {
+        match read {
+            Err(err) if err.is_corruption() => {
+                if self.skipped >= self.tolerance {
+                    error!(
+                        error = %err,
+                        skipped = self.skipped,
+                        tolerance = self.tolerance,
+                        "Too many corrupted db records, aborting scan"
+                    );
+                    return Err(err);
+                }
+                self.skipped += 1;
+                self.skipped_metric.inc();
+                warn!(error = %err, skipped = self.skipped, "Skipping corrupted db record");
+                Ok(None)
+            }
+            read => read.map(Some),
+        }
+    }
+
+    /// Number of corrupted records skipped so far
+    pub fn skipped(&self) -> u32 {
+        self.skipped
+    }
+}
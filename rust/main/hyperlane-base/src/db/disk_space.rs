@@ -0,0 +1,288 @@
+//! Monitoring of the disk space left to the agent DB.
+//!
+//! Rocksdb writes start failing once the disk fills up, part way through
+//! the bookkeeping of cursors and trees. Below a warning threshold of free
+//! space the agent warns. Below a critical threshold it stops taking new
+//! indexing work: syncs and tree ingestion pause at the boundary of a batch,
+//! leaving their state as it was before it, and resume by themselves once
+//! space is freed up.
+
+use std::{
+    fmt::Debug,
+    path::PathBuf,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use eyre::Result;
+use prometheus::IntGaugeVec;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+use crate::CoreMetrics;
+
+/// Thresholds of free disk space the agent DB is watched against
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiskSpaceConf {
+    /// Free bytes below which the agent warns
+    pub warning_free_bytes: Option<u64>,
+    /// Free bytes below which the agent stops taking new indexing work
+    pub critical_free_bytes: Option<u64>,
+    /// How often the free space is checked
+    pub check_interval: Duration,
+}
+
+impl Default for DiskSpaceConf {
+    fn default() -> Self {
+        Self {
+            warning_free_bytes: None,
+            critical_free_bytes: None,
+            check_interval: Duration::from_secs(30),
+        }
+    }
+}
+
+impl DiskSpaceConf {
+    /// Whether the disk space is watched at all
+    pub fn is_enabled(&self) -> bool {
+        self.warning_free_bytes.is_some() || self.critical_free_bytes.is_some()
+    }
+
+    fn level(&self, free_bytes: u64) -> DiskSpaceLevel {
+        let below = |threshold: Option<u64>| threshold.is_some_and(|t| free_bytes < t);
+        if below(self.critical_free_bytes) {
+            DiskSpaceLevel::Critical
+        } else if below(self.warning_free_bytes) {
+            DiskSpaceLevel::Warning
+        } else {
+            DiskSpaceLevel::Ok
+        }
+    }
+}
+
+/// How short of disk space the agent DB is
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DiskSpaceLevel {
+    /// Above every threshold
+    #[default]
+    Ok,
+    /// Below the warning threshold
+    Warning,
+    /// Below the critical threshold, new indexing work is paused
+    Critical,
+}
+
+/// Source of the free disk space of the agent DB
+pub trait FreeSpaceProbe: Debug + Send + Sync {
+    /// Bytes available to the agent on the disk of the DB
+    fn free_bytes(&self) -> std::io::Result<u64>;
+}
+
+/// Probes the filesystem the DB is stored on
+#[derive(Debug, Clone)]
+pub struct StatvfsProbe {
+    path: PathBuf,
+}
+
+impl StatvfsProbe {
+    /// Probe the filesystem of the DB at `path`
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl FreeSpaceProbe for StatvfsProbe {
+    fn free_bytes(&self) -> std::io::Result<u64> {
+        let stat = nix::sys::statvfs::statvfs(&self.path)?;
+        #[allow(clippy::useless_conversion)] // the widths differ by platform
+        Ok(u64::from(stat.blocks_available()) * u64::from(stat.fragment_size()))
+    }
+}
+
+/// Latest free disk space of the DB and the level it's at
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct DiskSpaceStatus {
+    level: DiskSpaceLevel,
+    free_bytes: u64,
+}
+
+/// Shared view of the disk space of the DB, checked by the tasks writing to
+/// it before taking on new work
+#[derive(Debug, Clone, Default)]
+pub struct DiskGuard {
+    status: Arc<RwLock<DiskSpaceStatus>>,
+    critical_free_bytes: Option<u64>,
+}
+
+impl DiskGuard {
+    /// The level the free disk space was at when last checked
+    pub fn level(&self) -> DiskSpaceLevel {
+        self.status.read().unwrap().level
+    }
+
+    /// Whether new indexing work is paused for the disk being almost full
+    pub fn is_frozen(&self) -> bool {
+        self.level() == DiskSpaceLevel::Critical
+    }
+
+    /// Why the agent isn't ready, if the disk is almost full
+    pub fn not_ready_reason(&self) -> Option<String> {
+        let status = *self.status.read().unwrap();
+        (status.level == DiskSpaceLevel::Critical).then(|| {
+            format!(
+                "Free disk space of the DB ({} bytes) is below the critical threshold ({} bytes), indexing is paused",
+                status.free_bytes,
+                self.critical_free_bytes.unwrap_or_default()
+            )
+        })
+    }
+}
+
+/// Periodically checks the free disk space of the DB, updating its gauges
+/// and guard
+#[derive(Debug)]
+pub struct DiskWatcher {
+    probe: Box<dyn FreeSpaceProbe>,
+    conf: DiskSpaceConf,
+    guard: DiskGuard,
+    free_bytes_gauge: IntGaugeVec,
+    level_gauge: IntGaugeVec,
+}
+
+impl DiskWatcher {
+    /// Watch the free space reported by `probe` against the thresholds of
+    /// `conf`
+    pub fn new(
+        probe: Box<dyn FreeSpaceProbe>,
+        conf: DiskSpaceConf,
+        metrics: &CoreMetrics,
+    ) -> Result<Self> {
+        let free_bytes_gauge = metrics.new_int_gauge(
+            "db_free_disk_bytes",
+            "Bytes of disk space available to the agent DB",
+            &[],
+        )?;
+        let level_gauge = metrics.new_int_gauge(
+            "db_disk_space_level",
+            "How short of disk space the agent DB is: 0 ok, 1 warning, 2 critical with indexing paused",
+            &[],
+        )?;
+        let guard = DiskGuard {
+            critical_free_bytes: conf.critical_free_bytes,
+            ..Default::default()
+        };
+        Ok(Self {
+            probe,
+            conf,
+            guard,
+            free_bytes_gauge,
+            level_gauge,
+        })
+    }
+
+    /// The guard of the watched DB
+    pub fn guard(&self) -> DiskGuard {
+        self.guard.clone()
+    }
+
+    /// Check the free disk space once. The previous level is kept if it
+    /// can't be probed.
+    pub fn check(&self) -> DiskSpaceLevel {
+        let free_bytes = match self.probe.free_bytes() {
+            Ok(free_bytes) => free_bytes,
+            Err(err) => {
+                warn!(?err, "Failed to probe the free disk space of the DB");
+                return self.guard.level();
+            }
+        };
+        let level = self.conf.level(free_bytes);
+        let previous = std::mem::replace(
+            &mut *self.guard.status.write().unwrap(),
+            DiskSpaceStatus { level, free_bytes },
+        );
+        self.free_bytes_gauge
+            .with_label_values(&[])
+            .set(free_bytes as i64);
+        self.level_gauge.with_label_values(&[]).set(level as i64);
+
+        if level != previous.level {
+            match level {
+                DiskSpaceLevel::Critical => error!(
+                    free_bytes,
+                    threshold = ?self.conf.critical_free_bytes,
+                    "Free disk space of the DB is critically low, pausing indexing"
+                ),
+                DiskSpaceLevel::Warning => warn!(
+                    free_bytes,
+                    threshold = ?self.conf.warning_free_bytes,
+                    "Free disk space of the DB is low"
+                ),
+                DiskSpaceLevel::Ok => {
+                    info!(free_bytes, "Free disk space of the DB recovered")
+                }
+            }
+            if previous.level == DiskSpaceLevel::Critical {
+                info!(free_bytes, "Resuming indexing");
+            }
+        }
+        level
+    }
+
+    /// Check the free disk space every check interval
+    pub fn spawn(self) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                self.check();
+                tokio::time::sleep(self.conf.check_interval).await;
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::db::test_utils::{disk_watcher, MockFreeSpaceProbe};
+
+    use super::*;
+
+    #[test]
+    fn test_indexing_is_frozen_below_the_critical_threshold_until_space_recovers() {
+        let probe = MockFreeSpaceProbe::default();
+        let watcher = disk_watcher(&probe);
+        let guard = watcher.guard();
+
+        probe.set(5000);
+        assert_eq!(watcher.check(), DiskSpaceLevel::Ok);
+        probe.set(500);
+        assert_eq!(watcher.check(), DiskSpaceLevel::Warning);
+        assert!(!guard.is_frozen());
+        assert_eq!(guard.not_ready_reason(), None);
+
+        probe.set(50);
+        assert_eq!(watcher.check(), DiskSpaceLevel::Critical);
+        assert!(guard.is_frozen());
+        assert_eq!(
+            guard.not_ready_reason().unwrap(),
+            "Free disk space of the DB (50 bytes) is below the critical threshold (100 bytes), indexing is paused"
+        );
+        assert_eq!(watcher.level_gauge.with_label_values(&[]).get(), 2);
+        assert_eq!(watcher.free_bytes_gauge.with_label_values(&[]).get(), 50);
+
+        probe.set(2000);
+        assert_eq!(watcher.check(), DiskSpaceLevel::Ok);
+        assert!(!guard.is_frozen());
+        assert_eq!(guard.not_ready_reason(), None);
+    }
+
+    #[test]
+    fn test_unset_thresholds_are_never_crossed() {
+        let conf = DiskSpaceConf {
+            warning_free_bytes: Some(1000),
+            ..Default::default()
+        };
+        assert!(conf.is_enabled());
+        assert_eq!(conf.level(0), DiskSpaceLevel::Warning);
+        assert!(!DiskSpaceConf::default().is_enabled());
+        assert_eq!(DiskSpaceConf::default().level(0), DiskSpaceLevel::Ok);
+    }
+}
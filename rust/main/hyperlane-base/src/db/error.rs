@@ -1,13 +1,13 @@
-use std::{io, path::PathBuf};
+use std::{fmt::Display, io, path::PathBuf};
 
-use hyperlane_core::{ChainCommunicationError, HyperlaneProtocolError};
+use hyperlane_core::{ChainCommunicationError, HyperlaneCoreError};
 
 /// DB Error type
 #[derive(thiserror::Error, Debug)]
 pub enum DbError {
-    /// Rocks DB Error
+    /// The underlying store failed to read or write
     #[error("{0}")]
-    RockError(#[from] rocksdb::Error),
+    Io(#[from] rocksdb::Error),
     #[error("Failed to open {path}, canonicalized as {canonicalized}: {source}")]
     /// Error opening the database
     OpeningError {
@@ -22,9 +22,76 @@ pub enum DbError {
     /// Could not parse the provided database path string
     #[error("Invalid database path supplied {1:?}; {0}")]
     InvalidDbPath(#[source] io::Error, String),
-    /// Hyperlane Error
-    #[error("{0}")]
-    HyperlaneError(#[from] HyperlaneProtocolError),
+    /// A stored value couldn't be decoded
+    #[error("Corrupted value in {column} at key {key_hex}: {detail}")]
+    Corruption {
+        /// Prefix of the keys the value is stored under
+        column: String,
+        /// Hex encoded key of the value, without its prefixes
+        key_hex: String,
+        /// Why the value couldn't be decoded
+        detail: String,
+    },
+    /// A value couldn't be encoded to be stored
+    #[error("Failed to serialize {type_name}: {detail}")]
+    Serialization {
+        /// Type of the value
+        type_name: &'static str,
+        /// Why the value couldn't be encoded
+        detail: String,
+    },
+    /// The database was opened read-only
+    #[error("Cannot write to a read-only database")]
+    ReadOnly,
+    /// A value referred to by another stored value is missing
+    #[error("No value in {column} at key {key_hex}")]
+    NotFound {
+        /// Prefix of the keys the value should be stored under
+        column: String,
+        /// Hex encoded key of the value, without its prefixes
+        key_hex: String,
+    },
+}
+
+impl DbError {
+    /// The value stored under `prefix` and `key` couldn't be decoded
+    pub fn corruption(prefix: &[u8], key: &[u8], detail: impl Display) -> Self {
+        DbError::Corruption {
+            column: column_name(prefix),
+            key_hex: hyperlane_core::hex::encode(key),
+            detail: detail.to_string(),
+        }
+    }
+
+    /// No value is stored under `prefix` and `key`, though one should be
+    pub fn not_found(prefix: &[u8], key: &[u8]) -> Self {
+        DbError::NotFound {
+            column: column_name(prefix),
+            key_hex: hyperlane_core::hex::encode(key),
+        }
+    }
+
+    /// A value of type `T` couldn't be encoded
+    pub fn serialization<T: ?Sized>(detail: impl Display) -> Self {
+        DbError::Serialization {
+            type_name: std::any::type_name::<T>(),
+            detail: detail.to_string(),
+        }
+    }
+
+    /// Whether a single record is damaged, i.e. it can't be decoded or
+    /// refers to a missing value, as opposed to the store failing as a whole.
+    /// Scans can skip such records and move on.
+    pub fn is_corruption(&self) -> bool {
+        matches!(self, DbError::Corruption { .. } | DbError::NotFound { .. })
+    }
+}
+
+/// Keys prefixes end with an underscore separating them from the key
+fn column_name(prefix: &[u8]) -> String {
+    String::from_utf8_lossy(prefix)
+        .trim_end_matches('_')
+        .to_owned()
 }
 
 impl From<DbError> for ChainCommunicationError {
@@ -32,3 +99,9 @@ impl From<DbError> for ChainCommunicationError {
         ChainCommunicationError::from_other(value)
     }
 }
+
+impl From<DbError> for HyperlaneCoreError {
+    fn from(value: DbError) -> Self {
+        HyperlaneCoreError::from_other(value)
+    }
+}
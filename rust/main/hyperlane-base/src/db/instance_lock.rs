@@ -0,0 +1,404 @@
+use std::{
+    fs::{File, OpenOptions},
+    io,
+    os::fd::AsRawFd,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use hyperlane_core::H256;
+use nix::{
+    errno::Errno,
+    fcntl::{flock, FlockArg},
+};
+use rocksdb::WriteBatch;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use super::{DbError, DB};
+
+/// DB key the heartbeat of the instance using the DB is stored under
+const INSTANCE_HEARTBEAT_KEY: &[u8] = b"instance_heartbeat";
+
+/// Name of the lock file, inside the DB directory
+const INSTANCE_LOCK_FILE: &str = "INSTANCE_LOCK";
+
+/// How an agent keeps other instances from using its DB at the same time
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstanceLockConf {
+    /// Whether the lock is taken at all
+    pub enabled: bool,
+    /// How often the instance holding the lock records a heartbeat
+    pub heartbeat_interval: Duration,
+    /// How long after its last heartbeat an instance is considered gone
+    pub heartbeat_ttl: Duration,
+}
+
+impl Default for InstanceLockConf {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            heartbeat_interval: Duration::from_secs(10),
+            heartbeat_ttl: Duration::from_secs(60),
+        }
+    }
+}
+
+/// The last heartbeat of the instance using a DB
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstanceHeartbeat {
+    /// Random id the instance drew when it started
+    pub instance_id: H256,
+    /// When the heartbeat was recorded, in milliseconds since the unix epoch
+    pub timestamp_millis: u64,
+}
+
+impl InstanceHeartbeat {
+    fn now(instance_id: H256) -> Self {
+        Self {
+            instance_id,
+            timestamp_millis: now_millis(),
+        }
+    }
+
+    /// Time since the heartbeat was recorded
+    pub fn age(&self) -> Duration {
+        Duration::from_millis(now_millis().saturating_sub(self.timestamp_millis))
+    }
+}
+
+/// Why the instance lock of a DB can't be taken or kept
+#[derive(Debug, thiserror::Error)]
+pub enum InstanceLockError {
+    /// Another process on this host holds the lock file
+    #[error("The instance lock file {} is held by another process", .path.display())]
+    LockFileHeld {
+        /// Path of the lock file
+        path: PathBuf,
+    },
+    /// Another instance recorded a heartbeat recently enough to be alive
+    #[error(
+        "Instance {instance_id:?} recorded a heartbeat {}s ago, within the {}s it's considered alive for; stop it, or pass --takeover to wait until its heartbeat expires",
+        .age.as_secs(),
+        .ttl.as_secs()
+    )]
+    InstanceAlive {
+        /// Id of the other instance
+        instance_id: H256,
+        /// Time since its last heartbeat
+        age: Duration,
+        /// How long after its last heartbeat it's considered gone
+        ttl: Duration,
+    },
+    /// Another instance took the lock over, e.g. after this one stalled for
+    /// longer than the heartbeat TTL
+    #[error("Instance {instance_id:?} took over the DB")]
+    TakenOver {
+        /// Id of the other instance
+        instance_id: H256,
+    },
+    /// The lock file couldn't be opened or locked
+    #[error("Failed to lock the instance lock file {}: {source}", .path.display())]
+    LockFile {
+        /// Path of the lock file
+        path: PathBuf,
+        /// Why it couldn't be locked
+        #[source]
+        source: io::Error,
+    },
+    /// The heartbeat couldn't be read or written
+    #[error(transparent)]
+    Db(#[from] DbError),
+}
+
+/// Held by the instance of an agent using a DB. An advisory lock on a file
+/// in the DB directory keeps out other processes on the same host, and a
+/// heartbeat recorded in the DB keeps out those that can't see the lock,
+/// e.g. on another host mounting the same volume. Dropping the lock clears
+/// the heartbeat, so that the next instance doesn't have to wait for it to
+/// expire.
+#[derive(Debug)]
+pub struct InstanceLock {
+    db: DB,
+    conf: InstanceLockConf,
+    instance_id: H256,
+    /// Holds the advisory lock until it's closed
+    _file: File,
+}
+
+impl InstanceLock {
+    /// Take the lock of the DB at `db_path`. Fails if another instance
+    /// recorded a heartbeat within the TTL, unless `takeover` is set, in which
+    /// case this waits for its heartbeat to expire.
+    pub async fn acquire(
+        db: DB,
+        db_path: &Path,
+        conf: InstanceLockConf,
+        takeover: bool,
+    ) -> Result<Self, InstanceLockError> {
+        let file = lock_file(&db_path.join(INSTANCE_LOCK_FILE))?;
+        while let Some(heartbeat) = retrieve_heartbeat(&db)? {
+            let age = heartbeat.age();
+            if age >= conf.heartbeat_ttl {
+                warn!(
+                    instance_id = ?heartbeat.instance_id,
+                    age_secs = age.as_secs(),
+                    "Taking over the DB from an instance whose heartbeat expired"
+                );
+                break;
+            }
+            if !takeover {
+                return Err(InstanceLockError::InstanceAlive {
+                    instance_id: heartbeat.instance_id,
+                    age,
+                    ttl: conf.heartbeat_ttl,
+                });
+            }
+            let wait = conf.heartbeat_ttl - age;
+            info!(
+                instance_id = ?heartbeat.instance_id,
+                wait_secs = wait.as_secs(),
+                "Waiting for the heartbeat of the instance using the DB to expire"
+            );
+            tokio::time::sleep(wait).await;
+        }
+
+        let lock = Self {
+            db,
+            conf,
+            instance_id: H256::random(),
+            _file: file,
+        };
+        store_heartbeat(&lock.db, &InstanceHeartbeat::now(lock.instance_id))?;
+        info!(instance_id = ?lock.instance_id, "Took the instance lock of the DB");
+        Ok(lock)
+    }
+
+    /// Random id of this instance
+    pub fn instance_id(&self) -> H256 {
+        self.instance_id
+    }
+
+    /// Record a heartbeat, unless another instance took over
+    pub fn beat(&self) -> Result<(), InstanceLockError> {
+        match retrieve_heartbeat(&self.db)? {
+            Some(heartbeat) if heartbeat.instance_id != self.instance_id => {
+                Err(InstanceLockError::TakenOver {
+                    instance_id: heartbeat.instance_id,
+                })
+            }
+            _ => Ok(store_heartbeat(
+                &self.db,
+                &InstanceHeartbeat::now(self.instance_id),
+            )?),
+        }
+    }
+
+    /// Record a heartbeat every interval until another instance takes over,
+    /// returning the error saying which. The lock is released once the
+    /// returned future is dropped.
+    pub async fn keep_alive(self) -> InstanceLockError {
+        loop {
+            tokio::time::sleep(self.conf.heartbeat_interval).await;
+            match self.beat() {
+                Ok(()) => {}
+                Err(err @ InstanceLockError::TakenOver { .. }) => return err,
+                Err(err) => warn!(%err, "Failed to record the instance heartbeat"),
+            }
+        }
+    }
+}
+
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        match retrieve_heartbeat(&self.db) {
+            Ok(Some(heartbeat)) if heartbeat.instance_id == self.instance_id => {
+                let mut batch = WriteBatch::default();
+                batch.delete(INSTANCE_HEARTBEAT_KEY);
+                if let Err(err) = self.db.write(batch) {
+                    warn!(%err, "Failed to clear the instance heartbeat");
+                }
+            }
+            Ok(_) => {}
+            Err(err) => warn!(%err, "Failed to read the instance heartbeat"),
+        }
+    }
+}
+
+/// The heartbeat of the instance last using the DB, if it didn't clear it
+pub fn retrieve_heartbeat(db: &DB) -> Result<Option<InstanceHeartbeat>, DbError> {
+    db.retrieve(INSTANCE_HEARTBEAT_KEY)?
+        .map(|bytes| {
+            serde_json::from_slice(&bytes)
+                .map_err(|err| DbError::corruption(INSTANCE_HEARTBEAT_KEY, &[], err))
+        })
+        .transpose()
+}
+
+fn store_heartbeat(db: &DB, heartbeat: &InstanceHeartbeat) -> Result<(), DbError> {
+    let bytes =
+        serde_json::to_vec(heartbeat).map_err(DbError::serialization::<InstanceHeartbeat>)?;
+    db.store(INSTANCE_HEARTBEAT_KEY, &bytes)
+}
+
+/// Open and lock the file at `path`, which stays locked until it's closed
+fn lock_file(path: &Path) -> Result<File, InstanceLockError> {
+    let lock_err = |source| InstanceLockError::LockFile {
+        path: path.to_owned(),
+        source,
+    };
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path)
+        .map_err(lock_err)?;
+    match flock(file.as_raw_fd(), FlockArg::LockExclusiveNonblock) {
+        Ok(()) => Ok(file),
+        Err(Errno::EWOULDBLOCK) => Err(InstanceLockError::LockFileHeld {
+            path: path.to_owned(),
+        }),
+        Err(errno) => Err(lock_err(errno.into())),
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Instant;
+
+    use tempfile::TempDir;
+    use tokio::time::timeout;
+
+    use super::*;
+
+    fn conf(heartbeat_ttl: Duration) -> InstanceLockConf {
+        InstanceLockConf {
+            enabled: true,
+            heartbeat_interval: Duration::from_millis(10),
+            heartbeat_ttl,
+        }
+    }
+
+    fn open_db() -> (TempDir, DB) {
+        let dir = tempfile::tempdir().unwrap();
+        let db = DB::from_path(dir.path()).unwrap();
+        (dir, db)
+    }
+
+    /// Record the heartbeat of another instance, `age` ago
+    fn other_instance(db: &DB, age: Duration) -> H256 {
+        let instance_id = H256::random();
+        let heartbeat = InstanceHeartbeat {
+            instance_id,
+            timestamp_millis: now_millis() - age.as_millis() as u64,
+        };
+        store_heartbeat(db, &heartbeat).unwrap();
+        instance_id
+    }
+
+    #[tokio::test]
+    async fn test_fresh_heartbeat_of_another_instance_is_refused() {
+        let (dir, db) = open_db();
+        let other = other_instance(&db, Duration::from_secs(5));
+
+        let err =
+            InstanceLock::acquire(db.clone(), dir.path(), conf(Duration::from_secs(60)), false)
+                .await
+                .unwrap_err();
+        assert!(
+            matches!(err, InstanceLockError::InstanceAlive { instance_id, .. } if instance_id == other),
+            "{err}"
+        );
+        assert!(err.to_string().contains("pass --takeover"), "{err}");
+        assert_eq!(retrieve_heartbeat(&db).unwrap().unwrap().instance_id, other);
+    }
+
+    #[tokio::test]
+    async fn test_stale_heartbeat_is_taken_over() {
+        let (dir, db) = open_db();
+        other_instance(&db, Duration::from_secs(120));
+
+        let lock =
+            InstanceLock::acquire(db.clone(), dir.path(), conf(Duration::from_secs(60)), false)
+                .await
+                .unwrap();
+        assert_eq!(
+            retrieve_heartbeat(&db).unwrap().unwrap().instance_id,
+            lock.instance_id()
+        );
+
+        // Releasing the lock clears the heartbeat for the next instance
+        drop(lock);
+        assert_eq!(retrieve_heartbeat(&db).unwrap(), None);
+        InstanceLock::acquire(db, dir.path(), conf(Duration::from_secs(60)), false)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_takeover_waits_for_the_heartbeat_to_expire() {
+        let (dir, db) = open_db();
+        other_instance(&db, Duration::ZERO);
+
+        let start = Instant::now();
+        let lock = InstanceLock::acquire(
+            db.clone(),
+            dir.path(),
+            conf(Duration::from_millis(300)),
+            true,
+        )
+        .await
+        .unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(250));
+        assert_eq!(
+            retrieve_heartbeat(&db).unwrap().unwrap().instance_id,
+            lock.instance_id()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_held_lock_file_is_refused() {
+        let (dir, db) = open_db();
+        let lock =
+            InstanceLock::acquire(db.clone(), dir.path(), conf(Duration::from_secs(60)), true)
+                .await
+                .unwrap();
+
+        // Even when taking over, as the process holding it is alive
+        let err = InstanceLock::acquire(db, dir.path(), conf(Duration::from_secs(60)), true)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, InstanceLockError::LockFileHeld { .. }),
+            "{err}"
+        );
+        drop(lock);
+    }
+
+    #[tokio::test]
+    async fn test_heartbeats_stop_once_another_instance_takes_over() {
+        let (dir, db) = open_db();
+        let lock =
+            InstanceLock::acquire(db.clone(), dir.path(), conf(Duration::from_secs(60)), false)
+                .await
+                .unwrap();
+        let other = other_instance(&db, Duration::ZERO);
+
+        let err = timeout(Duration::from_secs(5), lock.keep_alive())
+            .await
+            .expect("The takeover wasn't noticed");
+        assert!(
+            matches!(err, InstanceLockError::TakenOver { instance_id } if instance_id == other),
+            "{err}"
+        );
+        // The heartbeat of the other instance is left alone
+        assert_eq!(retrieve_heartbeat(&db).unwrap().unwrap().instance_id, other);
+    }
+}
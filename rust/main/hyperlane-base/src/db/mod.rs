@@ -1,13 +1,22 @@
+pub use corruption::*;
+pub use disk_space::*;
 pub use error::*;
+pub use instance_lock::*;
 use hyperlane_core::{
     GasPaymentKey, HyperlaneDomain, HyperlaneMessage, InterchainGasPayment,
     InterchainGasPaymentMeta, MerkleTreeInsertion, PendingOperationStatus, H256,
 };
 pub use rocks::*;
 
-pub use self::storage_types::{InterchainGasExpenditureData, InterchainGasPaymentData};
+pub use self::storage_types::{
+    GasPaymentAggregate, GasPaymentContribution, InterchainGasExpenditureData,
+    InterchainGasPaymentData, ValidatorReputation,
+};
 
+mod corruption;
+pub(crate) mod disk_space;
 mod error;
+mod instance_lock;
 mod rocks;
 pub(crate) mod storage_types;
 
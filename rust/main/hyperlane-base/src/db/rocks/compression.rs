@@ -0,0 +1,105 @@
+use std::{
+    fmt::Debug,
+    io::{self, Read, Write},
+};
+
+use hyperlane_core::HyperlaneMessage;
+
+use crate::db::error::DbError;
+
+/// Format byte prefixed to zstd-compressed message values. Uncompressed values
+/// are stored as the plain encoded message, which starts with the message
+/// version, so the marker must never be a valid version.
+pub const COMPRESSED_MESSAGE_FORMAT: u8 = 0xff;
+
+/// Compression applied to message values in the store. Messages whose body
+/// exceeds `threshold` bytes are stored zstd-compressed; smaller ones (and
+/// everything when disabled) are stored as-is, so reads work either way.
+#[derive(Clone, PartialEq, Eq)]
+pub struct MessageCompression {
+    /// Whether new oversized messages are compressed
+    pub enabled: bool,
+    /// Body size in bytes above which messages are compressed
+    pub threshold: usize,
+    /// zstd compression level
+    pub level: i32,
+    /// Optional zstd dictionary shared by compression and decompression.
+    /// Changing it makes values compressed with the previous one unreadable.
+    pub dictionary: Option<Vec<u8>>,
+}
+
+impl Default for MessageCompression {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            threshold: 1024,
+            level: 3,
+            dictionary: None,
+        }
+    }
+}
+
+impl Debug for MessageCompression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MessageCompression")
+            .field("enabled", &self.enabled)
+            .field("threshold", &self.threshold)
+            .field("level", &self.level)
+            .field("dictionary_len", &self.dictionary.as_ref().map(Vec::len))
+            .finish()
+    }
+}
+
+impl MessageCompression {
+    /// Leave every value uncompressed
+    pub fn disabled() -> Self {
+        Self {
+            enabled: false,
+            ..Default::default()
+        }
+    }
+
+    /// Whether a message with a body of `body_len` bytes should be compressed
+    pub fn should_compress(&self, body_len: usize) -> bool {
+        self.enabled && body_len > self.threshold
+    }
+
+    /// Compress an encoded message into a format-prefixed value
+    pub fn compress(&self, encoded: &[u8]) -> Result<Vec<u8>, DbError> {
+        self.try_compress(encoded)
+            .map_err(DbError::serialization::<HyperlaneMessage>)
+    }
+
+    /// Recover the encoded message from a stored value, whether or not it
+    /// was compressed
+    pub fn decompress(&self, value: Vec<u8>) -> io::Result<Vec<u8>> {
+        if !is_compressed(&value) {
+            return Ok(value);
+        }
+        let mut encoded = vec![];
+        zstd::stream::read::Decoder::with_dictionary(&value[1..], self.dictionary())?
+            .read_to_end(&mut encoded)?;
+        Ok(encoded)
+    }
+
+    fn try_compress(&self, encoded: &[u8]) -> io::Result<Vec<u8>> {
+        let mut value = vec![COMPRESSED_MESSAGE_FORMAT];
+        let mut encoder = zstd::stream::write::Encoder::with_dictionary(
+            &mut value,
+            self.level,
+            self.dictionary(),
+        )?;
+        encoder.write_all(encoded)?;
+        encoder.finish()?;
+        Ok(value)
+    }
+
+    fn dictionary(&self) -> &[u8] {
+        self.dictionary.as_deref().unwrap_or_default()
+    }
+}
+
+/// Whether a stored message value is compressed
+pub fn is_compressed(value: &[u8]) -> bool {
+    value.first() == Some(&COMPRESSED_MESSAGE_FORMAT)
+}
@@ -1,18 +1,26 @@
+use std::ops::RangeInclusive;
+
 use async_trait::async_trait;
-use eyre::{bail, Result};
 use tracing::{debug, instrument, trace};
 
 use hyperlane_core::{
-    Decode, Encode, GasPaymentKey, HyperlaneDomain, HyperlaneLogStore, HyperlaneMessage,
+    accumulator::incremental::IncrementalMerkle, Decode, Encode, GasPaymentKey, HyperlaneCoreError,
+    HyperlaneCoreResult, HyperlaneDomain, HyperlaneLogStore, HyperlaneMessage,
     HyperlaneSequenceAwareIndexerStoreReader, HyperlaneWatermarkedLogStore, Indexed,
     InterchainGasExpenditure, InterchainGasPayment, InterchainGasPaymentMeta, LogMeta,
-    MerkleTreeInsertion, PendingOperationStatus, H256,
+    MerkleTreeInsertion, MultisigSignedCheckpoint, PendingOperationStatus, H160, H256,
 };
 
-use super::{DbError, TypedDB, DB};
-use crate::db::{
-    storage_types::{InterchainGasExpenditureData, InterchainGasPaymentData},
-    HyperlaneDb,
+use super::{is_compressed, DbError, MessageCompression, TypedBatch, TypedDB, DB};
+use crate::{
+    contract_sync::pacing::BackfillProgress,
+    db::{
+        storage_types::{
+            GasPaymentAggregate, GasPaymentContribution, InterchainGasExpenditureData,
+            InterchainGasPaymentData, ValidatorReputation,
+        },
+        HyperlaneDb,
+    },
 };
 
 // these keys MUST not be given multiple uses in case multiple agents are
@@ -36,6 +44,34 @@ const MERKLE_LEAF_INDEX_BY_MESSAGE_ID: &str = "merkle_leaf_index_by_message_id_"
 const MERKLE_TREE_INSERTION_BLOCK_NUMBER_BY_LEAF_INDEX: &str =
     "merkle_tree_insertion_block_number_by_leaf_index_";
 const LATEST_INDEXED_GAS_PAYMENT_BLOCK: &str = "latest_indexed_gas_payment_block";
+const QUORUM_CHECKPOINT_BY_MESSAGE_ID: &str = "quorum_checkpoint_by_message_id_";
+const MESSAGE_COMPRESSION_MIGRATED_NONCE: &str = "message_compression_migrated_nonce_";
+const GAS_PAYMENT_BY_CONTRACT_FOR_MESSAGE_ID: &str = "gas_payment_by_contract_for_message_id_";
+const VALIDATOR_REPUTATION: &str = "validator_reputation_";
+const MERKLE_TREE_INTENT: &str = "merkle_tree_intent_";
+const MERKLE_TREE_INTENTS_APPLIED: &str = "merkle_tree_intents_applied_";
+const MERKLE_TREE_SNAPSHOT: &str = "merkle_tree_snapshot_";
+const MERKLE_TREE_SHUTDOWN_SNAPSHOT: &str = "merkle_tree_shutdown_snapshot_";
+const MERKLE_TREE_SHUTDOWN_MARKER: &str = "merkle_tree_shutdown_marker_";
+const BACKFILL_PROGRESS: &str = "backfill_progress_";
+const DECISION_SNAPSHOT: &str = "decision_snapshot_";
+const DECISION_SNAPSHOT_COUNT_BY_MESSAGE_ID: &str = "decision_snapshot_count_by_message_id_";
+const CIRCUIT_BREAKER_STATE: &str = "circuit_breaker_state_";
+const MESSAGE_CANCELLATION: &str = "message_cancellation_";
+const ROUND_TRIP_STATE: &str = "round_trip_state_";
+const MESSAGE_BROADCAST: &str = "message_broadcast_";
+const GAS_PAYMENT_CONTRIBUTION: &str = "gas_payment_contribution_";
+const GAS_PAYMENT_CONTRIBUTION_BY_BLOCK: &str = "gas_payment_contribution_by_block_";
+const GAS_PAYMENT_CONTRIBUTION_COUNT_BY_BLOCK: &str = "gas_payment_contribution_count_by_block_";
+const GAS_PAYMENT_CONTRIBUTION_COUNT_FOR_GAS_PAYMENT_KEY: &str =
+    "gas_payment_contribution_count_for_gas_payment_key_";
+const MESSAGE_ID_BY_ORIGIN_AND_NONCE: &str = "message_id_by_origin_and_nonce_";
+const NONCE_BY_MESSAGE_ID: &str = "nonce_by_message_id_";
+const MESSAGE_NONCE_INDEX_BACKFILL_PROGRESS: &str = "message_nonce_index_backfill_progress_";
+const MESSAGE_NONCE_INDEX_BACKFILLED: &str = "message_nonce_index_backfilled_";
+
+/// Messages whose nonce indices are backfilled in a single write
+const NONCE_INDEX_BACKFILL_CHUNK: u32 = 1000;
 
 /// Rocks DB result type
 pub type DbResult<T> = std::result::Result<T, DbError>;
@@ -75,12 +111,15 @@ impl HyperlaneRocksDB {
         &self.0
     }
 
-    /// Store a raw committed message
+    /// Store a raw committed message. All keys are written atomically, so a
+    /// crash never leaves a partially stored message behind.
     ///
     /// Keys --> Values:
     /// - `nonce` --> `id`
     /// - `id` --> `message`
     /// - `nonce` --> `dispatched block number`
+    /// - `big-endian nonce` --> `id`, iterable in nonce order
+    /// - `id` --> `nonce`
     pub fn store_message(
         &self,
         message: &HyperlaneMessage,
@@ -94,14 +133,30 @@ impl HyperlaneRocksDB {
         let id = message.id();
         debug!(hyp_message=?message,  "Storing new message in db",);
 
+        let mut batch = self.batch();
         // - `id` --> `message`
-        self.store_message_by_id(&id, message)?;
+        batch.store_bytes(MESSAGE, id.to_vec(), &self.encode_message(message)?);
         // - `nonce` --> `id`
-        self.store_message_id_by_nonce(&message.nonce, &id)?;
+        batch.store_keyed_encodable(MESSAGE_ID, &message.nonce, &id);
+        batch_nonce_indices(&mut batch, message.nonce, &id);
         // Update the max seen nonce to allow forward-backward iteration in the processor
-        self.try_update_max_seen_message_nonce(message.nonce)?;
+        let current_max = self
+            .retrieve_highest_seen_message_nonce()?
+            .unwrap_or_default();
+        if message.nonce >= current_max {
+            batch.store_keyed_encodable(
+                HIGHEST_SEEN_MESSAGE_NONCE,
+                &bool::default(),
+                &message.nonce,
+            );
+        }
         // - `nonce` --> `dispatched block number`
-        self.store_dispatched_block_number_by_nonce(&message.nonce, &dispatched_block_number)?;
+        batch.store_keyed_encodable(
+            MESSAGE_DISPATCHED_BLOCK_NUMBER,
+            &message.nonce,
+            &dispatched_block_number,
+        );
+        self.write(batch)?;
         Ok(true)
     }
 
@@ -110,10 +165,97 @@ impl HyperlaneRocksDB {
         let id = self.retrieve_message_id_by_nonce(&nonce)?;
         match id {
             None => Ok(None),
-            Some(id) => self.retrieve_message_by_id(&id),
+            // The id and message are stored together, so the message missing
+            // means the db is damaged rather than it not being indexed yet
+            Some(id) => self
+                .retrieve_message_by_id(&id)?
+                .ok_or_else(|| DbError::not_found(MESSAGE.as_bytes(), id.as_bytes()))
+                .map(Some),
         }
     }
 
+    /// The id of the message with the given nonce, as kept by the index
+    /// iterable in nonce order
+    pub fn retrieve_message_id_by_origin_and_nonce(&self, nonce: u32) -> DbResult<Option<H256>> {
+        self.retrieve_decodable(MESSAGE_ID_BY_ORIGIN_AND_NONCE, nonce.to_be_bytes())
+    }
+
+    /// The nonce of the message with the given id, without retrieving the
+    /// message
+    pub fn retrieve_nonce_by_message_id(&self, id: &H256) -> DbResult<Option<u32>> {
+        self.retrieve_value_by_key(NONCE_BY_MESSAGE_ID, id)
+    }
+
+    /// The nonces and ids of the stored messages with a nonce in `nonces`,
+    /// in nonce order. Nonces that aren't indexed yet are skipped.
+    pub fn iterate_message_ids_by_nonce(
+        &self,
+        nonces: RangeInclusive<u32>,
+    ) -> impl Iterator<Item = DbResult<(u32, H256)>> + '_ {
+        let end = *nonces.end();
+        self.iterate_decodable_from::<H256>(
+            MESSAGE_ID_BY_ORIGIN_AND_NONCE,
+            nonces.start().to_be_bytes(),
+        )
+        .keyed()
+        .map(|entry| -> DbResult<(u32, H256)> {
+            let (key, id) = entry?;
+            let nonce = <[u8; 4]>::try_from(key.as_slice())
+                .map(u32::from_be_bytes)
+                .map_err(|_| {
+                    DbError::corruption(
+                        MESSAGE_ID_BY_ORIGIN_AND_NONCE.as_bytes(),
+                        &key,
+                        "Expected a 4 byte nonce",
+                    )
+                })?;
+            Ok((nonce, id))
+        })
+        .take_while(move |entry| !matches!(entry, Ok((nonce, _)) if *nonce > end))
+    }
+
+    /// Build the nonce indices of the messages stored before they were
+    /// maintained. Messages stored since are indexed as they're written, so
+    /// this only runs to completion once; progress is persisted, so an
+    /// interrupted run resumes where it left off. Returns the number of
+    /// messages indexed.
+    pub fn backfill_message_nonce_indices(&self) -> DbResult<u32> {
+        if self.retrieve_value_by_key(MESSAGE_NONCE_INDEX_BACKFILLED, &bool::default())?
+            == Some(true)
+        {
+            return Ok(0);
+        }
+        let start: u32 = self
+            .retrieve_value_by_key(MESSAGE_NONCE_INDEX_BACKFILL_PROGRESS, &bool::default())?
+            .unwrap_or_default();
+        let highest = self.retrieve_highest_seen_message_nonce()?;
+
+        let mut indexed = 0;
+        let mut chunk_start = Some(start);
+        while let Some((start, highest)) = chunk_start.zip(highest).filter(|(s, h)| s <= h) {
+            let end = start
+                .saturating_add(NONCE_INDEX_BACKFILL_CHUNK - 1)
+                .min(highest);
+            let mut batch = self.batch();
+            for nonce in start..=end {
+                // Messages missing now are indexed as they're stored
+                if let Some(id) = self.retrieve_message_id_by_nonce(&nonce)? {
+                    batch_nonce_indices(&mut batch, nonce, &id);
+                    indexed += 1;
+                }
+            }
+            chunk_start = end.checked_add(1);
+            batch.store_keyed_encodable(
+                MESSAGE_NONCE_INDEX_BACKFILL_PROGRESS,
+                &bool::default(),
+                &chunk_start.unwrap_or(end),
+            );
+            self.write(batch)?;
+        }
+        self.store_value_by_key(MESSAGE_NONCE_INDEX_BACKFILLED, &bool::default(), &true)?;
+        Ok(indexed)
+    }
+
     /// Update the nonce of the highest processed message we're aware of
     pub fn try_update_max_seen_message_nonce(&self, nonce: u32) -> DbResult<()> {
         let current_max = self
@@ -125,6 +267,68 @@ impl HyperlaneRocksDB {
         Ok(())
     }
 
+    /// Compress stored messages whose body exceeds the compression threshold,
+    /// e.g. ones stored before compression was enabled. Progress is persisted,
+    /// so this resumes where it left off and only visits new messages on later
+    /// runs. Meant to be run in the background; returns the number of
+    /// messages compressed.
+    pub fn compress_stored_messages(&self) -> DbResult<u32> {
+        let compression = self.message_compression();
+        if !compression.enabled {
+            return Ok(0);
+        }
+        let Some(highest) = self.retrieve_highest_seen_message_nonce()? else {
+            return Ok(0);
+        };
+        let start = self
+            .retrieve_value_by_key(MESSAGE_COMPRESSION_MIGRATED_NONCE, &bool::default())?
+            .map_or(0, |migrated: u32| migrated + 1);
+
+        let mut compressed = 0;
+        for nonce in start..=highest {
+            let Some(id) = self.retrieve_message_id_by_nonce(&nonce)? else {
+                // Nonces are indexed out of order; stop at the first gap so
+                // the rest gets visited once it's filled
+                break;
+            };
+            if let Some(value) = self.retrieve_bytes(MESSAGE, id.to_vec())? {
+                if !is_compressed(&value) {
+                    let message =
+                        HyperlaneMessage::read_from(&mut value.as_slice()).map_err(|err| {
+                            DbError::corruption(MESSAGE.as_bytes(), id.as_bytes(), err)
+                        })?;
+                    if compression.should_compress(message.body.len()) {
+                        self.store_bytes(MESSAGE, id.to_vec(), &compression.compress(&value)?)?;
+                        compressed += 1;
+                    }
+                }
+            }
+            self.store_value_by_key(MESSAGE_COMPRESSION_MIGRATED_NONCE, &bool::default(), &nonce)?;
+        }
+        if compressed > 0 {
+            debug!(
+                compressed,
+                domain = self.domain().name(),
+                "Compressed stored messages"
+            );
+        }
+        Ok(compressed)
+    }
+
+    /// Encode a message the way it's stored, compressing it if it's large
+    fn encode_message(&self, message: &HyperlaneMessage) -> DbResult<Vec<u8>> {
+        let compression = self.message_compression();
+        let value = message.to_vec();
+        if compression.should_compress(message.body.len()) {
+            return compression.compress(&value);
+        }
+        Ok(value)
+    }
+
+    fn message_compression(&self) -> &MessageCompression {
+        AsRef::<DB>::as_ref(self).message_compression()
+    }
+
     /// If the provided gas payment, identified by its metadata, has not been
     /// processed, processes the gas payment and records it as processed.
     /// Returns whether the gas payment was processed for the first time.
@@ -134,7 +338,10 @@ impl HyperlaneRocksDB {
         log_meta: &LogMeta,
     ) -> DbResult<bool> {
         let payment = *(indexed_payment.inner());
-        let gas_processing_successful = self.process_gas_payment(payment, log_meta)?;
+        let contribution =
+            GasPaymentContribution::new(payment, log_meta.block_number, indexed_payment.sequence);
+        let gas_processing_successful =
+            self.process_gas_payment_contribution(contribution, log_meta)?;
 
         // only store the payment and return early if there's no sequence
         let Some(gas_payment_sequence) = indexed_payment.sequence else {
@@ -165,7 +372,22 @@ impl HyperlaneRocksDB {
         payment: InterchainGasPayment,
         log_meta: &LogMeta,
     ) -> DbResult<bool> {
-        let payment_meta = log_meta.into();
+        let contribution = GasPaymentContribution::new(payment, log_meta.block_number, None);
+        self.process_gas_payment_contribution(contribution, log_meta)
+    }
+
+    /// Counts the contribution towards its message's total payment, unless
+    /// the payment log, identified by its transaction and log index, was
+    /// already counted. The contribution is recorded along with the total,
+    /// so re-indexing the same log is a no-op and rolling back its block
+    /// can subtract it again.
+    fn process_gas_payment_contribution(
+        &self,
+        contribution: GasPaymentContribution,
+        log_meta: &LogMeta,
+    ) -> DbResult<bool> {
+        let payment = contribution.payment;
+        let payment_meta: InterchainGasPaymentMeta = log_meta.into();
         // If the gas payment has already been processed, do nothing
         if self
             .retrieve_processed_by_gas_payment_meta(&payment_meta)?
@@ -179,17 +401,201 @@ impl HyperlaneRocksDB {
             // Return false to indicate the gas payment was already processed
             return Ok(false);
         }
-        // Set the gas payment as processed
-        self.store_processed_by_gas_payment_meta(&payment_meta, &true)?;
 
-        // Update the total gas payment for the message to include the payment
-        self.update_gas_payment_by_gas_payment_key(payment)?;
+        let gas_payment_key: GasPaymentKey = payment.into();
+        let total = self
+            .retrieve_gas_payment_by_gas_payment_key(gas_payment_key)?
+            .unwrap_or_else(|| InterchainGasPayment::from_gas_payment_key(gas_payment_key))
+            + payment;
+        let contributions = self.retrieve_gas_payment_contribution_count(gas_payment_key)? + 1;
+        let block = contribution.block_number;
+        let in_block = self.retrieve_gas_payment_contribution_count_by_block(block)?;
+        debug!(?payment, new_total_gas_payment=?total, contributions, "Storing gas payment");
+
+        // Set the gas payment as processed and update the total gas payment
+        // for the message to include it, atomically
+        let mut batch = self.batch();
+        batch.store_keyed_encodable(GAS_PAYMENT_META_PROCESSED, &payment_meta, &true);
+        batch.store_keyed_encodable(GAS_PAYMENT_CONTRIBUTION, &payment_meta, &contribution);
+        batch.store_encodable(
+            GAS_PAYMENT_CONTRIBUTION_BY_BLOCK,
+            gas_payment_contribution_by_block_key(block, in_block),
+            &payment_meta,
+        );
+        batch.store_keyed_encodable(
+            GAS_PAYMENT_CONTRIBUTION_COUNT_BY_BLOCK,
+            &block,
+            &(in_block + 1),
+        );
+        batch.store_keyed_encodable(
+            GAS_PAYMENT_FOR_MESSAGE_ID,
+            &gas_payment_key,
+            &InterchainGasPaymentData::from(total),
+        );
+        batch.store_keyed_encodable(
+            GAS_PAYMENT_CONTRIBUTION_COUNT_FOR_GAS_PAYMENT_KEY,
+            &gas_payment_key,
+            &contributions,
+        );
+        self.write(batch)?;
 
         // Return true to indicate the gas payment was processed for the first time
         Ok(true)
     }
 
-    /// Store the merkle tree insertion event, and also store a mapping from message_id to leaf_index
+    /// Subtract the gas payments emitted in a block from their messages'
+    /// totals, e.g. because the block was reorged out. The payment logs are
+    /// forgotten, so they are counted again if re-indexed, and the gas
+    /// payment watermark is moved back below the block. Returns the number
+    /// of payments rolled back.
+    pub fn roll_back_gas_payments_in_block(&self, block_number: u64) -> DbResult<u32> {
+        let in_block = self.retrieve_gas_payment_contribution_count_by_block(block_number)?;
+        let mut rolled_back = 0;
+        for i in 0..in_block {
+            let Some(payment_meta) = self.retrieve_decodable::<InterchainGasPaymentMeta>(
+                GAS_PAYMENT_CONTRIBUTION_BY_BLOCK,
+                gas_payment_contribution_by_block_key(block_number, i),
+            )?
+            else {
+                continue;
+            };
+            if self.roll_back_gas_payment_contribution(&payment_meta)? {
+                rolled_back += 1;
+            }
+        }
+
+        let mut batch = self.batch();
+        for i in 0..in_block {
+            batch.delete(
+                GAS_PAYMENT_CONTRIBUTION_BY_BLOCK,
+                gas_payment_contribution_by_block_key(block_number, i),
+            );
+        }
+        batch.delete_keyed(GAS_PAYMENT_CONTRIBUTION_COUNT_BY_BLOCK, &block_number);
+        let watermark: Option<u32> =
+            self.retrieve_decodable("", LATEST_INDEXED_GAS_PAYMENT_BLOCK)?;
+        if watermark.is_some_and(|w| u64::from(w) >= block_number) {
+            let rewound = block_number.saturating_sub(1) as u32;
+            batch.store_encodable("", LATEST_INDEXED_GAS_PAYMENT_BLOCK, &rewound);
+        }
+        self.write(batch)?;
+
+        if rolled_back > 0 {
+            debug!(block_number, rolled_back, "Rolled back gas payments");
+        }
+        Ok(rolled_back)
+    }
+
+    /// Subtract a single payment log from its message's totals and forget
+    /// it, atomically. Returns false if it wasn't counted.
+    fn roll_back_gas_payment_contribution(
+        &self,
+        payment_meta: &InterchainGasPaymentMeta,
+    ) -> DbResult<bool> {
+        let Some(contribution) = self.retrieve_gas_payment_contribution(payment_meta)? else {
+            return Ok(false);
+        };
+        let payment = contribution.payment;
+        let gas_payment_key: GasPaymentKey = payment.into();
+
+        let mut batch = self.batch();
+        let total = self
+            .retrieve_interchain_gas_payment_data_by_gas_payment_key(&gas_payment_key)?
+            .unwrap_or_default();
+        batch.store_keyed_encodable(
+            GAS_PAYMENT_FOR_MESSAGE_ID,
+            &gas_payment_key,
+            &subtract_gas_payment(total, payment),
+        );
+        let contributions = self.retrieve_gas_payment_contribution_count(gas_payment_key)?;
+        batch.store_keyed_encodable(
+            GAS_PAYMENT_CONTRIBUTION_COUNT_FOR_GAS_PAYMENT_KEY,
+            &gas_payment_key,
+            &contributions.saturating_sub(1),
+        );
+        if let Some(contract) = contribution.contract {
+            let key = gas_payment_by_contract_key(gas_payment_key, &contract);
+            let total: InterchainGasPaymentData = self
+                .retrieve_decodable(GAS_PAYMENT_BY_CONTRACT_FOR_MESSAGE_ID, &key)?
+                .unwrap_or_default();
+            batch.store_encodable(
+                GAS_PAYMENT_BY_CONTRACT_FOR_MESSAGE_ID,
+                key,
+                &subtract_gas_payment(total, payment),
+            );
+        }
+        if let Some(sequence) = contribution.sequence {
+            let (by_sequence, block_by_sequence) = match contribution.contract {
+                Some(contract) if contribution.scoped => (
+                    contract_scoped_prefix(GAS_PAYMENT_BY_SEQUENCE, &contract),
+                    contract_scoped_prefix(GAS_PAYMENT_BLOCK_BY_SEQUENCE, &contract),
+                ),
+                _ => (
+                    GAS_PAYMENT_BY_SEQUENCE.as_bytes().to_vec(),
+                    GAS_PAYMENT_BLOCK_BY_SEQUENCE.as_bytes().to_vec(),
+                ),
+            };
+            batch.delete_keyed(by_sequence, &sequence);
+            batch.delete_keyed(block_by_sequence, &sequence);
+        }
+        batch.delete_keyed(GAS_PAYMENT_CONTRIBUTION, payment_meta);
+        batch.delete_keyed(GAS_PAYMENT_META_PROCESSED, payment_meta);
+        self.write(batch)?;
+
+        trace!(?payment, ?payment_meta, "Rolled back gas payment");
+        Ok(true)
+    }
+
+    /// Number of payment logs counted towards the total payment of a message
+    fn retrieve_gas_payment_contribution_count(
+        &self,
+        gas_payment_key: GasPaymentKey,
+    ) -> DbResult<u32> {
+        Ok(self
+            .retrieve_value_by_key(
+                GAS_PAYMENT_CONTRIBUTION_COUNT_FOR_GAS_PAYMENT_KEY,
+                &gas_payment_key,
+            )?
+            .unwrap_or_default())
+    }
+
+    /// Number of payment logs counted from a block
+    fn retrieve_gas_payment_contribution_count_by_block(&self, block_number: u64) -> DbResult<u32> {
+        Ok(self
+            .retrieve_value_by_key(GAS_PAYMENT_CONTRIBUTION_COUNT_BY_BLOCK, &block_number)?
+            .unwrap_or_default())
+    }
+
+    /// Retrieve the payment log counted towards a message's total, if it
+    /// was counted and not rolled back since
+    pub fn retrieve_gas_payment_contribution(
+        &self,
+        payment_meta: &InterchainGasPaymentMeta,
+    ) -> DbResult<Option<GasPaymentContribution>> {
+        self.retrieve_value_by_key(GAS_PAYMENT_CONTRIBUTION, payment_meta)
+    }
+
+    /// Retrieve the total gas payment for a message, along with the number
+    /// of payment logs it was aggregated from
+    pub fn retrieve_gas_payment_aggregate(
+        &self,
+        gas_payment_key: GasPaymentKey,
+    ) -> DbResult<Option<GasPaymentAggregate>> {
+        let Some(total) = self.retrieve_gas_payment_by_gas_payment_key(gas_payment_key)? else {
+            return Ok(None);
+        };
+        Ok(Some(GasPaymentAggregate {
+            total,
+            contributions: self.retrieve_gas_payment_contribution_count(gas_payment_key)?,
+        }))
+    }
+
+    /// Store the merkle tree insertion event, and also store a mapping from message_id to leaf_index.
+    ///
+    /// Along with it, an intent to ingest the leaf into the merkle tree is
+    /// recorded, in the same atomic write. It stays pending until a tree
+    /// snapshot covering the leaf is persisted, see
+    /// [`HyperlaneRocksDB::store_merkle_tree_snapshot`].
     pub fn process_tree_insertion(
         &self,
         insertion: &MerkleTreeInsertion,
@@ -200,44 +606,142 @@ impl HyperlaneRocksDB {
             return Ok(false);
         }
 
+        let mut batch = self.batch();
         // even if double insertions are ok, store the leaf by `leaf_index` (guaranteed to be unique)
         // rather than by `message_id` (not guaranteed to be recurring), so that leaves can be retrieved
         // based on insertion order.
-        self.store_merkle_tree_insertion_by_leaf_index(&insertion.index(), insertion)?;
+        batch.store_keyed_encodable(MERKLE_TREE_INSERTION, &insertion.index(), insertion);
 
-        self.store_merkle_leaf_index_by_message_id(&insertion.message_id(), &insertion.index())?;
+        batch.store_keyed_encodable(
+            MERKLE_LEAF_INDEX_BY_MESSAGE_ID,
+            &insertion.message_id(),
+            &insertion.index(),
+        );
 
-        self.store_merkle_tree_insertion_block_number_by_leaf_index(
+        batch.store_keyed_encodable(
+            MERKLE_TREE_INSERTION_BLOCK_NUMBER_BY_LEAF_INDEX,
             &insertion.index(),
             &insertion_block_number,
-        )?;
+        );
+
+        batch.store_keyed_encodable(MERKLE_TREE_INTENT, &insertion.index(), insertion);
+        self.write(batch)?;
         // Return true to indicate the tree insertion was processed
         Ok(true)
     }
 
-    /// Processes the gas expenditure and store the total expenditure for the
-    /// message.
-    pub fn process_gas_expenditure(&self, expenditure: InterchainGasExpenditure) -> DbResult<()> {
-        // Update the total gas expenditure for the message to include the payment
-        self.update_gas_expenditure_by_message_id(expenditure)
+    /// Retrieve the pending intent to ingest a leaf into the merkle tree, if
+    /// it wasn't applied yet
+    pub fn retrieve_merkle_tree_intent(
+        &self,
+        leaf_index: u32,
+    ) -> DbResult<Option<MerkleTreeInsertion>> {
+        self.retrieve_value_by_key(MERKLE_TREE_INTENT, &leaf_index)
     }
 
-    /// Update the total gas payment for a message to include gas_payment
-    fn update_gas_payment_by_gas_payment_key(&self, event: InterchainGasPayment) -> DbResult<()> {
-        let gas_payment_key = event.into();
-        let existing_payment =
-            match self.retrieve_gas_payment_by_gas_payment_key(gas_payment_key)? {
-                Some(payment) => payment,
-                None => InterchainGasPayment::from_gas_payment_key(gas_payment_key),
-            };
-        let total = existing_payment + event;
+    /// Number of leaves whose intents were marked as applied, i.e. the first
+    /// leaf index whose intent may still be pending
+    pub fn retrieve_merkle_tree_intents_applied(&self) -> DbResult<u32> {
+        Ok(self
+            .retrieve_value_by_key(MERKLE_TREE_INTENTS_APPLIED, &bool::default())?
+            .unwrap_or_default())
+    }
 
-        debug!(?event, new_total_gas_payment=?total, "Storing gas payment");
-        self.store_interchain_gas_payment_data_by_gas_payment_key(&gas_payment_key, &total.into())?;
+    /// Persist a snapshot of the merkle tree. Intents of the leaves it covers
+    /// can be marked as applied afterwards.
+    pub fn store_merkle_tree_snapshot(&self, tree: &IncrementalMerkle) -> DbResult<()> {
+        self.store_value_by_key(MERKLE_TREE_SNAPSHOT, &bool::default(), tree)
+    }
+
+    /// Retrieve the latest persisted snapshot of the merkle tree
+    pub fn retrieve_merkle_tree_snapshot(&self) -> DbResult<Option<IncrementalMerkle>> {
+        self.retrieve_value_by_key(MERKLE_TREE_SNAPSHOT, &bool::default())
+    }
 
+    /// Add this origin's part of a coordinated shutdown snapshot to `batch`,
+    /// which may span several origins: the snapshot of its tree, if it could
+    /// be taken, marking the intents it covers as applied, and the marker of
+    /// the whole set. Without a tree, any older shutdown snapshot is removed
+    /// so it isn't mistaken for part of the set.
+    pub fn batch_merkle_tree_shutdown_snapshot<V: Encode>(
+        &self,
+        batch: &mut TypedBatch,
+        tree: Option<&IncrementalMerkle>,
+        marker: &V,
+    ) -> DbResult<()> {
+        batch.scope_to(self);
+        if let Some(tree) = tree {
+            batch.store_keyed_encodable(MERKLE_TREE_SHUTDOWN_SNAPSHOT, &bool::default(), tree);
+            let leaf_count = tree.count() as u32;
+            let applied = self.retrieve_merkle_tree_intents_applied()?;
+            if leaf_count > applied {
+                for leaf_index in applied..leaf_count {
+                    batch.delete_keyed(MERKLE_TREE_INTENT, &leaf_index);
+                }
+                batch.store_keyed_encodable(
+                    MERKLE_TREE_INTENTS_APPLIED,
+                    &bool::default(),
+                    &leaf_count,
+                );
+            }
+        } else {
+            batch.delete_keyed(MERKLE_TREE_SHUTDOWN_SNAPSHOT, &bool::default());
+        }
+        batch.store_keyed_encodable(MERKLE_TREE_SHUTDOWN_MARKER, &bool::default(), marker);
         Ok(())
     }
 
+    /// Retrieve the snapshot of the merkle tree taken on the latest shutdown,
+    /// if it could be taken
+    pub fn retrieve_merkle_tree_shutdown_snapshot(&self) -> DbResult<Option<IncrementalMerkle>> {
+        self.retrieve_value_by_key(MERKLE_TREE_SHUTDOWN_SNAPSHOT, &bool::default())
+    }
+
+    /// Retrieve the marker of the latest shutdown snapshot set
+    pub fn retrieve_merkle_tree_shutdown_marker<V: Decode>(&self) -> DbResult<Option<V>> {
+        self.retrieve_value_by_key(MERKLE_TREE_SHUTDOWN_MARKER, &bool::default())
+    }
+
+    /// Store the progress of the backfill of `data_type`
+    pub fn store_backfill_progress(
+        &self,
+        data_type: &str,
+        progress: &BackfillProgress,
+    ) -> DbResult<()> {
+        self.store_encodable(BACKFILL_PROGRESS, data_type, progress)
+    }
+
+    /// Retrieve the progress of the backfill of `data_type`
+    pub fn retrieve_backfill_progress(
+        &self,
+        data_type: &str,
+    ) -> DbResult<Option<BackfillProgress>> {
+        self.retrieve_decodable(BACKFILL_PROGRESS, data_type)
+    }
+
+    /// Mark the intents of all leaves below `leaf_count` as applied, removing
+    /// them. Only to be called once a snapshot covering them is persisted.
+    /// Idempotent, so it can safely be repeated after a crash.
+    pub fn mark_merkle_tree_intents_applied(&self, leaf_count: u32) -> DbResult<()> {
+        let applied = self.retrieve_merkle_tree_intents_applied()?;
+        if leaf_count <= applied {
+            return Ok(());
+        }
+        let mut batch = self.batch();
+        for leaf_index in applied..leaf_count {
+            batch.delete_keyed(MERKLE_TREE_INTENT, &leaf_index);
+        }
+        batch.store_keyed_encodable(MERKLE_TREE_INTENTS_APPLIED, &bool::default(), &leaf_count);
+        self.write(batch)
+    }
+
+    /// Processes the gas expenditure and store the total expenditure for the
+    /// message.
+    pub fn process_gas_expenditure(&self, expenditure: InterchainGasExpenditure) -> DbResult<()> {
+        // Update the total gas expenditure for the message to include the payment
+        self.update_gas_expenditure_by_message_id(expenditure)
+    }
+
     /// Update the total gas spent for a message
     fn update_gas_expenditure_by_message_id(
         &self,
@@ -269,6 +773,52 @@ impl HyperlaneRocksDB {
             }))
     }
 
+    /// Add a payment to the total paid for the message through the IGP
+    /// `contract`, and attribute its contribution to the contract
+    fn update_gas_payment_by_contract(
+        &self,
+        contract: &H256,
+        payment: InterchainGasPayment,
+        log_meta: &LogMeta,
+    ) -> DbResult<()> {
+        let key = gas_payment_by_contract_key(payment.into(), contract);
+        let existing: InterchainGasPaymentData = self
+            .retrieve_decodable(GAS_PAYMENT_BY_CONTRACT_FOR_MESSAGE_ID, &key)?
+            .unwrap_or_default();
+        let total = InterchainGasPaymentData {
+            payment: existing.payment + payment.payment,
+            gas_amount: existing.gas_amount + payment.gas_amount,
+        };
+        let mut batch = self.batch();
+        batch.store_encodable(GAS_PAYMENT_BY_CONTRACT_FOR_MESSAGE_ID, key, &total);
+        let payment_meta: InterchainGasPaymentMeta = log_meta.into();
+        if let Some(contribution) = self.retrieve_gas_payment_contribution(&payment_meta)? {
+            let attributed = GasPaymentContribution {
+                contract: Some(*contract),
+                ..contribution
+            };
+            batch.store_keyed_encodable(GAS_PAYMENT_CONTRIBUTION, &payment_meta, &attributed);
+        }
+        self.write(batch)
+    }
+
+    /// Retrieve the part of a message's total gas payment that was paid
+    /// through the IGP `contract`
+    pub fn retrieve_gas_payment_by_contract(
+        &self,
+        gas_payment_key: GasPaymentKey,
+        contract: &H256,
+    ) -> DbResult<Option<InterchainGasPayment>> {
+        Ok(self
+            .retrieve_decodable::<InterchainGasPaymentData>(
+                GAS_PAYMENT_BY_CONTRACT_FOR_MESSAGE_ID,
+                gas_payment_by_contract_key(gas_payment_key, contract),
+            )?
+            .map(|payment| {
+                payment.complete(gas_payment_key.message_id, gas_payment_key.destination)
+            }))
+    }
+
     /// Retrieve the total gas payment for a message
     pub fn retrieve_gas_expenditure_by_message_id(
         &self,
@@ -279,13 +829,146 @@ impl HyperlaneRocksDB {
             .unwrap_or_default()
             .complete(message_id))
     }
+
+    /// Store the quorum of signed checkpoints used to build metadata for a message
+    pub fn store_quorum_checkpoint_by_message_id(
+        &self,
+        message_id: &H256,
+        checkpoint: &MultisigSignedCheckpoint,
+    ) -> DbResult<()> {
+        self.store_value_by_key(QUORUM_CHECKPOINT_BY_MESSAGE_ID, message_id, checkpoint)
+    }
+
+    /// Retrieve the quorum of signed checkpoints used to build metadata for a message
+    pub fn retrieve_quorum_checkpoint_by_message_id(
+        &self,
+        message_id: &H256,
+    ) -> DbResult<Option<MultisigSignedCheckpoint>> {
+        self.retrieve_value_by_key(QUORUM_CHECKPOINT_BY_MESSAGE_ID, message_id)
+    }
+
+    /// Store the track record of a validator's checkpoint storage
+    pub fn store_validator_reputation(
+        &self,
+        validator: &H160,
+        reputation: &ValidatorReputation,
+    ) -> DbResult<()> {
+        self.store_value_by_key(VALIDATOR_REPUTATION, validator, reputation)
+    }
+
+    /// Retrieve the track record of a validator's checkpoint storage
+    pub fn retrieve_validator_reputation(
+        &self,
+        validator: &H160,
+    ) -> DbResult<Option<ValidatorReputation>> {
+        self.retrieve_value_by_key(VALIDATOR_REPUTATION, validator)
+    }
+
+    /// Store a snapshot of the inputs a relaying decision about a message was
+    /// made from, as the message's next attempt. Returns the index of the
+    /// attempt, starting at zero.
+    pub fn store_decision_snapshot<V: Encode>(
+        &self,
+        message_id: &H256,
+        snapshot: &V,
+    ) -> DbResult<u32> {
+        let attempt = self.retrieve_decision_snapshot_count(message_id)?;
+        let mut batch = self.batch();
+        batch.store_encodable(
+            DECISION_SNAPSHOT,
+            decision_snapshot_key(message_id, attempt),
+            snapshot,
+        );
+        batch.store_keyed_encodable(
+            DECISION_SNAPSHOT_COUNT_BY_MESSAGE_ID,
+            message_id,
+            &(attempt + 1),
+        );
+        self.write(batch)?;
+        Ok(attempt)
+    }
+
+    /// Retrieve the decision snapshot of an attempt to relay a message
+    pub fn retrieve_decision_snapshot<V: Decode>(
+        &self,
+        message_id: &H256,
+        attempt: u32,
+    ) -> DbResult<Option<V>> {
+        self.retrieve_decodable(
+            DECISION_SNAPSHOT,
+            decision_snapshot_key(message_id, attempt),
+        )
+    }
+
+    /// Number of attempts to relay a message a decision snapshot was stored for
+    pub fn retrieve_decision_snapshot_count(&self, message_id: &H256) -> DbResult<u32> {
+        Ok(self
+            .retrieve_value_by_key(DECISION_SNAPSHOT_COUNT_BY_MESSAGE_ID, message_id)?
+            .unwrap_or_default())
+    }
+
+    /// Persist the state of the circuit breaker guarding submissions to this
+    /// domain, as of its latest transition
+    pub fn store_circuit_breaker_state<V: Encode>(&self, state: &V) -> DbResult<()> {
+        self.store_value_by_key(CIRCUIT_BREAKER_STATE, &bool::default(), state)
+    }
+
+    /// Retrieve the latest persisted state of the circuit breaker guarding
+    /// submissions to this domain
+    pub fn retrieve_circuit_breaker_state<V: Decode>(&self) -> DbResult<Option<V>> {
+        self.retrieve_value_by_key(CIRCUIT_BREAKER_STATE, &bool::default())
+    }
+
+    /// Persist the messages tracked crossing between this domain and `peer`
+    /// and back, for replay protection of the route pair
+    pub fn store_round_trip_state<V: Encode>(&self, peer: &u32, state: &V) -> DbResult<()> {
+        self.store_value_by_key(ROUND_TRIP_STATE, peer, state)
+    }
+
+    /// Retrieve the messages tracked crossing between this domain and `peer`
+    /// and back
+    pub fn retrieve_round_trip_state<V: Decode>(&self, peer: &u32) -> DbResult<Option<V>> {
+        self.retrieve_value_by_key(ROUND_TRIP_STATE, peer)
+    }
+
+    /// Store who cancelled a message, when and why
+    pub fn store_message_cancellation<V: Encode>(
+        &self,
+        message_id: &H256,
+        cancellation: &V,
+    ) -> DbResult<()> {
+        self.store_value_by_key(MESSAGE_CANCELLATION, message_id, cancellation)
+    }
+
+    /// Retrieve who cancelled a message, when and why, if it was cancelled
+    pub fn retrieve_message_cancellation<V: Decode>(
+        &self,
+        message_id: &H256,
+    ) -> DbResult<Option<V>> {
+        self.retrieve_value_by_key(MESSAGE_CANCELLATION, message_id)
+    }
+
+    /// Record that a transaction delivering a message was broadcast
+    pub fn store_message_broadcast(&self, message_id: &H256) -> DbResult<()> {
+        self.store_value_by_key(MESSAGE_BROADCAST, message_id, &true)
+    }
+
+    /// Whether a transaction delivering a message was ever broadcast
+    pub fn retrieve_message_broadcast(&self, message_id: &H256) -> DbResult<bool> {
+        Ok(self
+            .retrieve_value_by_key(MESSAGE_BROADCAST, message_id)?
+            .unwrap_or_default())
+    }
 }
 
 #[async_trait]
 impl HyperlaneLogStore<HyperlaneMessage> for HyperlaneRocksDB {
     /// Store a list of dispatched messages and their associated metadata.
     #[instrument(skip_all)]
-    async fn store_logs(&self, messages: &[(Indexed<HyperlaneMessage>, LogMeta)]) -> Result<u32> {
+    async fn store_logs(
+        &self,
+        messages: &[(Indexed<HyperlaneMessage>, LogMeta)],
+    ) -> HyperlaneCoreResult<u32> {
         let mut stored = 0;
         for (message, meta) in messages {
             let stored_message = self.store_message(message.inner(), meta.block_number)?;
@@ -305,7 +988,7 @@ async fn store_and_count_new<T: Copy>(
     logs: &[(T, LogMeta)],
     log_type: &str,
     process: impl Fn(&HyperlaneRocksDB, T, &LogMeta) -> DbResult<bool>,
-) -> Result<u32> {
+) -> HyperlaneCoreResult<u32> {
     let mut new_logs = 0;
     for (log, meta) in logs {
         if process(store, *log, meta)? {
@@ -325,7 +1008,7 @@ impl HyperlaneLogStore<InterchainGasPayment> for HyperlaneRocksDB {
     async fn store_logs(
         &self,
         payments: &[(Indexed<InterchainGasPayment>, LogMeta)],
-    ) -> Result<u32> {
+    ) -> HyperlaneCoreResult<u32> {
         store_and_count_new(
             self,
             payments,
@@ -340,7 +1023,10 @@ impl HyperlaneLogStore<InterchainGasPayment> for HyperlaneRocksDB {
 impl HyperlaneLogStore<MerkleTreeInsertion> for HyperlaneRocksDB {
     /// Store every tree insertion event
     #[instrument(skip_all)]
-    async fn store_logs(&self, leaves: &[(Indexed<MerkleTreeInsertion>, LogMeta)]) -> Result<u32> {
+    async fn store_logs(
+        &self,
+        leaves: &[(Indexed<MerkleTreeInsertion>, LogMeta)],
+    ) -> HyperlaneCoreResult<u32> {
         let mut insertions = 0;
         for (insertion, meta) in leaves {
             if self.process_tree_insertion(insertion.inner(), meta.block_number)? {
@@ -354,13 +1040,26 @@ impl HyperlaneLogStore<MerkleTreeInsertion> for HyperlaneRocksDB {
 #[async_trait]
 impl HyperlaneSequenceAwareIndexerStoreReader<HyperlaneMessage> for HyperlaneRocksDB {
     /// Gets data by its sequence.
-    async fn retrieve_by_sequence(&self, sequence: u32) -> Result<Option<HyperlaneMessage>> {
+    async fn retrieve_by_sequence(
+        &self,
+        sequence: u32,
+    ) -> HyperlaneCoreResult<Option<HyperlaneMessage>> {
         let message = self.retrieve_message_by_nonce(sequence)?;
         Ok(message)
     }
 
+    /// Whether the message is stored, without retrieving it
+    async fn is_sequence_stored(&self, sequence: u32) -> HyperlaneCoreResult<bool> {
+        Ok(self
+            .retrieve_message_id_by_origin_and_nonce(sequence)?
+            .is_some())
+    }
+
     /// Gets the block number at which the log occurred.
-    async fn retrieve_log_block_number_by_sequence(&self, sequence: u32) -> Result<Option<u64>> {
+    async fn retrieve_log_block_number_by_sequence(
+        &self,
+        sequence: u32,
+    ) -> HyperlaneCoreResult<Option<u64>> {
         let number = self.retrieve_dispatched_block_number_by_nonce(&sequence)?;
         Ok(number)
     }
@@ -369,13 +1068,19 @@ impl HyperlaneSequenceAwareIndexerStoreReader<HyperlaneMessage> for HyperlaneRoc
 #[async_trait]
 impl HyperlaneSequenceAwareIndexerStoreReader<MerkleTreeInsertion> for HyperlaneRocksDB {
     /// Gets data by its sequence.
-    async fn retrieve_by_sequence(&self, sequence: u32) -> Result<Option<MerkleTreeInsertion>> {
+    async fn retrieve_by_sequence(
+        &self,
+        sequence: u32,
+    ) -> HyperlaneCoreResult<Option<MerkleTreeInsertion>> {
         let insertion = self.retrieve_merkle_tree_insertion_by_leaf_index(&sequence)?;
         Ok(insertion)
     }
 
     /// Gets the block number at which the log occurred.
-    async fn retrieve_log_block_number_by_sequence(&self, sequence: u32) -> Result<Option<u64>> {
+    async fn retrieve_log_block_number_by_sequence(
+        &self,
+        sequence: u32,
+    ) -> HyperlaneCoreResult<Option<u64>> {
         let number = self.retrieve_merkle_tree_insertion_block_number_by_leaf_index(&sequence)?;
         Ok(number)
     }
@@ -385,12 +1090,18 @@ impl HyperlaneSequenceAwareIndexerStoreReader<MerkleTreeInsertion> for Hyperlane
 #[async_trait]
 impl HyperlaneSequenceAwareIndexerStoreReader<InterchainGasPayment> for HyperlaneRocksDB {
     /// Gets data by its sequence.
-    async fn retrieve_by_sequence(&self, sequence: u32) -> Result<Option<InterchainGasPayment>> {
+    async fn retrieve_by_sequence(
+        &self,
+        sequence: u32,
+    ) -> HyperlaneCoreResult<Option<InterchainGasPayment>> {
         Ok(self.retrieve_gas_payment_by_sequence(&sequence)?)
     }
 
     /// Gets the block number at which the log occurred.
-    async fn retrieve_log_block_number_by_sequence(&self, sequence: u32) -> Result<Option<u64>> {
+    async fn retrieve_log_block_number_by_sequence(
+        &self,
+        sequence: u32,
+    ) -> HyperlaneCoreResult<Option<u64>> {
         Ok(self.retrieve_gas_payment_block_by_sequence(&sequence)?)
     }
 }
@@ -398,13 +1109,13 @@ impl HyperlaneSequenceAwareIndexerStoreReader<InterchainGasPayment> for Hyperlan
 #[async_trait]
 impl HyperlaneWatermarkedLogStore<InterchainGasPayment> for HyperlaneRocksDB {
     /// Gets the block number high watermark
-    async fn retrieve_high_watermark(&self) -> Result<Option<u32>> {
+    async fn retrieve_high_watermark(&self) -> HyperlaneCoreResult<Option<u32>> {
         let watermark = self.retrieve_decodable("", LATEST_INDEXED_GAS_PAYMENT_BLOCK)?;
         Ok(watermark)
     }
 
     /// Stores the block number high watermark
-    async fn store_high_watermark(&self, block_number: u32) -> Result<()> {
+    async fn store_high_watermark(&self, block_number: u32) -> HyperlaneCoreResult<()> {
         let result = self.store_encodable("", LATEST_INDEXED_GAS_PAYMENT_BLOCK, &block_number)?;
         Ok(result)
     }
@@ -414,13 +1125,13 @@ impl HyperlaneWatermarkedLogStore<InterchainGasPayment> for HyperlaneRocksDB {
 #[async_trait]
 impl HyperlaneWatermarkedLogStore<HyperlaneMessage> for HyperlaneRocksDB {
     /// Gets the block number high watermark
-    async fn retrieve_high_watermark(&self) -> Result<Option<u32>> {
-        bail!("Not implemented")
+    async fn retrieve_high_watermark(&self) -> HyperlaneCoreResult<Option<u32>> {
+        Err(HyperlaneCoreError::from_other("Not implemented"))
     }
 
     /// Stores the block number high watermark
-    async fn store_high_watermark(&self, _block_number: u32) -> Result<()> {
-        bail!("Not implemented")
+    async fn store_high_watermark(&self, _block_number: u32) -> HyperlaneCoreResult<()> {
+        Err(HyperlaneCoreError::from_other("Not implemented"))
     }
 }
 
@@ -428,13 +1139,210 @@ impl HyperlaneWatermarkedLogStore<HyperlaneMessage> for HyperlaneRocksDB {
 #[async_trait]
 impl HyperlaneWatermarkedLogStore<MerkleTreeInsertion> for HyperlaneRocksDB {
     /// Gets the block number high watermark
-    async fn retrieve_high_watermark(&self) -> Result<Option<u32>> {
-        bail!("Not implemented")
+    async fn retrieve_high_watermark(&self) -> HyperlaneCoreResult<Option<u32>> {
+        Err(HyperlaneCoreError::from_other("Not implemented"))
     }
 
     /// Stores the block number high watermark
-    async fn store_high_watermark(&self, _block_number: u32) -> Result<()> {
-        bail!("Not implemented")
+    async fn store_high_watermark(&self, _block_number: u32) -> HyperlaneCoreResult<()> {
+        Err(HyperlaneCoreError::from_other("Not implemented"))
+    }
+}
+
+fn gas_payment_by_contract_key(gas_payment_key: GasPaymentKey, contract: &H256) -> Vec<u8> {
+    let mut key = gas_payment_key.to_vec();
+    key.extend_from_slice(contract.as_bytes());
+    key
+}
+
+/// Key prefix of the cursor state of an IGP indexed in addition to the
+/// configured one
+fn contract_scoped_prefix(prefix: &str, contract: &H256) -> Vec<u8> {
+    format!("{prefix}{contract:x}_").into_bytes()
+}
+
+fn gas_payment_contribution_by_block_key(block_number: u64, index: u32) -> Vec<u8> {
+    [&block_number.to_be_bytes()[..], &index.to_be_bytes()].concat()
+}
+
+fn subtract_gas_payment(
+    total: InterchainGasPaymentData,
+    payment: InterchainGasPayment,
+) -> InterchainGasPaymentData {
+    InterchainGasPaymentData {
+        payment: total.payment.saturating_sub(payment.payment),
+        gas_amount: total.gas_amount.saturating_sub(payment.gas_amount),
+    }
+}
+
+/// Log store for the gas payments of one of a chain's IGP contracts, for
+/// chains with several, e.g. a legacy and a current deployment.
+///
+/// Payments from every contract count towards the same per-message totals,
+/// and are also attributed to the contract they were made through. Each
+/// contract is indexed with its own locator, so only its own payments reach
+/// its store. The chain's configured IGP keeps the chain's existing cursor
+/// state; additional contracts keep theirs under keys of their own.
+#[derive(Debug, Clone)]
+pub struct IgpContractStore {
+    db: HyperlaneRocksDB,
+    contract: H256,
+    additional: bool,
+}
+
+impl IgpContractStore {
+    /// Store for the chain's configured IGP
+    pub fn configured(db: HyperlaneRocksDB, contract: H256) -> Self {
+        Self {
+            db,
+            contract,
+            additional: false,
+        }
+    }
+
+    /// Store for an IGP indexed in addition to the configured one
+    pub fn additional(db: HyperlaneRocksDB, contract: H256) -> Self {
+        Self {
+            db,
+            contract,
+            additional: true,
+        }
+    }
+
+    /// The IGP contract
+    pub fn contract(&self) -> H256 {
+        self.contract
+    }
+
+    /// Cursor state key prefix of an additional contract
+    fn scoped(&self, prefix: &str) -> Vec<u8> {
+        contract_scoped_prefix(prefix, &self.contract)
+    }
+
+    fn process_indexed_gas_payment(
+        &self,
+        indexed_payment: Indexed<InterchainGasPayment>,
+        log_meta: &LogMeta,
+    ) -> DbResult<bool> {
+        let payment = *indexed_payment.inner();
+        let processed = if !self.additional {
+            self.db
+                .process_indexed_gas_payment(indexed_payment, log_meta)?
+        } else {
+            let contribution = GasPaymentContribution {
+                contract: Some(self.contract),
+                scoped: true,
+                ..GasPaymentContribution::new(
+                    payment,
+                    log_meta.block_number,
+                    indexed_payment.sequence,
+                )
+            };
+            let processed = self
+                .db
+                .process_gas_payment_contribution(contribution, log_meta)?;
+            if let Some(sequence) = indexed_payment.sequence {
+                self.db.store_encodable(
+                    self.scoped(GAS_PAYMENT_BY_SEQUENCE),
+                    sequence.to_vec(),
+                    &payment,
+                )?;
+                self.db.store_encodable(
+                    self.scoped(GAS_PAYMENT_BLOCK_BY_SEQUENCE),
+                    sequence.to_vec(),
+                    &log_meta.block_number,
+                )?;
+            }
+            processed
+        };
+        if processed {
+            self.db
+                .update_gas_payment_by_contract(&self.contract, payment, log_meta)?;
+        }
+        Ok(processed)
+    }
+}
+
+#[async_trait]
+impl HyperlaneLogStore<InterchainGasPayment> for IgpContractStore {
+    /// Store a list of interchain gas payments and their associated metadata.
+    #[instrument(skip_all, fields(contract = ?self.contract))]
+    async fn store_logs(
+        &self,
+        payments: &[(Indexed<InterchainGasPayment>, LogMeta)],
+    ) -> HyperlaneCoreResult<u32> {
+        let mut new_logs = 0;
+        for (payment, meta) in payments {
+            if self.process_indexed_gas_payment(*payment, meta)? {
+                new_logs += 1;
+            }
+        }
+        if new_logs > 0 {
+            debug!(new_logs, "Wrote new gas payments to database");
+        }
+        Ok(new_logs)
+    }
+}
+
+#[async_trait]
+impl HyperlaneSequenceAwareIndexerStoreReader<InterchainGasPayment> for IgpContractStore {
+    /// Gets data by its sequence.
+    async fn retrieve_by_sequence(
+        &self,
+        sequence: u32,
+    ) -> HyperlaneCoreResult<Option<InterchainGasPayment>> {
+        if !self.additional {
+            return Ok(self.db.retrieve_gas_payment_by_sequence(&sequence)?);
+        }
+        Ok(self
+            .db
+            .retrieve_decodable(self.scoped(GAS_PAYMENT_BY_SEQUENCE), sequence.to_vec())?)
+    }
+
+    /// Gets the block number at which the log occurred.
+    async fn retrieve_log_block_number_by_sequence(
+        &self,
+        sequence: u32,
+    ) -> HyperlaneCoreResult<Option<u64>> {
+        if !self.additional {
+            return Ok(self.db.retrieve_gas_payment_block_by_sequence(&sequence)?);
+        }
+        Ok(self.db.retrieve_decodable(
+            self.scoped(GAS_PAYMENT_BLOCK_BY_SEQUENCE),
+            sequence.to_vec(),
+        )?)
+    }
+}
+
+#[async_trait]
+impl HyperlaneWatermarkedLogStore<InterchainGasPayment> for IgpContractStore {
+    /// Gets the block number high watermark
+    async fn retrieve_high_watermark(&self) -> HyperlaneCoreResult<Option<u32>> {
+        if !self.additional {
+            return HyperlaneWatermarkedLogStore::<InterchainGasPayment>::retrieve_high_watermark(
+                &self.db,
+            )
+            .await;
+        }
+        Ok(self
+            .db
+            .retrieve_decodable("", self.scoped(LATEST_INDEXED_GAS_PAYMENT_BLOCK))?)
+    }
+
+    /// Stores the block number high watermark
+    async fn store_high_watermark(&self, block_number: u32) -> HyperlaneCoreResult<()> {
+        if !self.additional {
+            return HyperlaneWatermarkedLogStore::<InterchainGasPayment>::store_high_watermark(
+                &self.db,
+                block_number,
+            )
+            .await;
+        }
+        Ok(self.db.store_encodable(
+            "",
+            self.scoped(LATEST_INDEXED_GAS_PAYMENT_BLOCK),
+            &block_number,
+        )?)
     }
 }
 
@@ -460,11 +1368,23 @@ impl HyperlaneDb for HyperlaneRocksDB {
     }
 
     fn store_message_by_id(&self, id: &H256, message: &HyperlaneMessage) -> DbResult<()> {
-        self.store_value_by_key(MESSAGE, id, message)
+        self.store_bytes(MESSAGE, id.to_vec(), &self.encode_message(message)?)
     }
 
     fn retrieve_message_by_id(&self, id: &H256) -> DbResult<Option<HyperlaneMessage>> {
-        self.retrieve_value_by_key(MESSAGE, id)
+        let Some(value) = self.retrieve_bytes(MESSAGE, id.to_vec())? else {
+            return Ok(None);
+        };
+        let corruption = |err: &dyn std::fmt::Display| {
+            DbError::corruption(MESSAGE.as_bytes(), id.as_bytes(), err)
+        };
+        let encoded = self
+            .message_compression()
+            .decompress(value)
+            .map_err(|err| corruption(&err))?;
+        HyperlaneMessage::read_from(&mut encoded.as_slice())
+            .map(Some)
+            .map_err(|err| corruption(&err))
     }
 
     fn store_dispatched_block_number_by_nonce(
@@ -676,3 +1596,646 @@ impl HyperlaneRocksDB {
         self.retrieve_decodable(prefix, key.to_vec())
     }
 }
+
+/// Index a stored message by its nonce, in nonce order, and its nonce by its
+/// id. Nonces are keyed big-endian, so that their keys sort like them.
+fn batch_nonce_indices(batch: &mut TypedBatch, nonce: u32, id: &H256) {
+    batch.store_encodable(MESSAGE_ID_BY_ORIGIN_AND_NONCE, nonce.to_be_bytes(), id);
+    batch.store_keyed_encodable(NONCE_BY_MESSAGE_ID, id, &nonce);
+}
+
+fn decision_snapshot_key(message_id: &H256, attempt: u32) -> Vec<u8> {
+    [message_id.as_bytes(), &attempt.to_be_bytes()].concat()
+}
+
+#[cfg(test)]
+mod test {
+    use hyperlane_core::U256;
+
+    use super::*;
+    use crate::db::{test_utils::run_test_db, CorruptionTolerance};
+
+    const THRESHOLD: usize = 64;
+
+    fn compression() -> MessageCompression {
+        MessageCompression {
+            threshold: THRESHOLD,
+            ..Default::default()
+        }
+    }
+
+    fn message(nonce: u32, body: Vec<u8>) -> HyperlaneMessage {
+        HyperlaneMessage {
+            nonce,
+            body,
+            ..Default::default()
+        }
+    }
+
+    /// Compressible body of `len` bytes
+    fn body(len: usize) -> Vec<u8> {
+        b"hyperlane ".iter().copied().cycle().take(len).collect()
+    }
+
+    fn stored_value(db: &HyperlaneRocksDB, message: &HyperlaneMessage) -> Vec<u8> {
+        db.retrieve_bytes(MESSAGE, message.id().to_vec())
+            .unwrap()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_compression_round_trips_across_threshold() {
+        run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(
+                &HyperlaneDomain::new_test_domain("compression_round_trip"),
+                db.with_message_compression(compression()),
+            );
+            for (nonce, len) in [0, THRESHOLD - 1, THRESHOLD, THRESHOLD + 1, 10_000]
+                .into_iter()
+                .enumerate()
+            {
+                let message = message(nonce as u32, body(len));
+                db.store_message(&message, 1).unwrap();
+
+                assert_eq!(is_compressed(&stored_value(&db, &message)), len > THRESHOLD);
+                assert_eq!(
+                    db.retrieve_message_by_nonce(message.nonce).unwrap(),
+                    Some(message)
+                );
+            }
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_compression_is_disabled_for_test_dbs() {
+        run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(
+                &HyperlaneDomain::new_test_domain("compression_disabled"),
+                db,
+            );
+            let message = message(0, body(10_000));
+            db.store_message(&message, 1).unwrap();
+
+            assert_eq!(stored_value(&db, &message), message.to_vec());
+            assert_eq!(db.compress_stored_messages().unwrap(), 0);
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_background_migration_compresses_oversized_messages() {
+        run_test_db(|db| async move {
+            let domain = HyperlaneDomain::new_test_domain("compression_migration");
+            let uncompressed = HyperlaneRocksDB::new(&domain, db.clone());
+            let messages = [
+                message(0, body(10_000)),
+                message(1, body(THRESHOLD)),
+                message(2, body(THRESHOLD + 1)),
+            ];
+            for message in &messages {
+                uncompressed.store_message(message, 1).unwrap();
+            }
+
+            let db = HyperlaneRocksDB::new(&domain, db.with_message_compression(compression()));
+            let migration = {
+                let db = db.clone();
+                tokio::task::spawn_blocking(move || db.compress_stored_messages())
+            };
+            assert_eq!(migration.await.unwrap().unwrap(), 2);
+
+            for message in &messages {
+                assert_eq!(
+                    is_compressed(&stored_value(&db, message)),
+                    message.body.len() > THRESHOLD
+                );
+                assert_eq!(
+                    db.retrieve_message_by_nonce(message.nonce)
+                        .unwrap()
+                        .as_ref(),
+                    Some(message)
+                );
+                // Compressed values stay readable without compression enabled
+                assert_eq!(
+                    uncompressed
+                        .retrieve_message_by_nonce(message.nonce)
+                        .unwrap()
+                        .as_ref(),
+                    Some(message)
+                );
+            }
+
+            // Progress is persisted, so only new messages are visited next time
+            assert_eq!(db.compress_stored_messages().unwrap(), 0);
+            let late = message(3, body(10_000));
+            uncompressed.store_message(&late, 1).unwrap();
+            assert_eq!(db.compress_stored_messages().unwrap(), 1);
+            assert!(is_compressed(&stored_value(&db, &late)));
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_compression_shrinks_stored_bytes() {
+        run_test_db(|db| async move {
+            let domain = HyperlaneDomain::new_test_domain("compression_size");
+            let plain = HyperlaneRocksDB::new(&domain, db.clone());
+            let compressed =
+                HyperlaneRocksDB::new(&domain, db.with_message_compression(compression()));
+            let (first, second) = (message(0, body(10_000)), message(1, body(10_001)));
+            plain.store_message(&first, 1).unwrap();
+            compressed.store_message(&second, 1).unwrap();
+
+            let plain_len = stored_value(&plain, &first).len();
+            let compressed_len = stored_value(&compressed, &second).len();
+            assert!(
+                compressed_len * 10 < plain_len,
+                "{compressed_len} bytes compressed vs {plain_len} plain"
+            );
+        })
+        .await;
+    }
+
+    #[test]
+    fn test_compression_with_dictionary() {
+        let with_dictionary = MessageCompression {
+            dictionary: Some(body(1024)),
+            ..compression()
+        };
+        let encoded = message(0, body(4096)).to_vec();
+
+        let value = with_dictionary.compress(&encoded).unwrap();
+        assert_eq!(with_dictionary.decompress(value.clone()).unwrap(), encoded);
+        assert_ne!(compression().decompress(value).ok(), Some(encoded));
+    }
+
+    #[tokio::test]
+    async fn test_tree_intents_are_applied_idempotently() {
+        run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(&HyperlaneDomain::new_test_domain("test"), db);
+            let insertions: Vec<_> = (0..3)
+                .map(|i| MerkleTreeInsertion::new(i, H256::from_low_u64_be(i as u64)))
+                .collect();
+            for insertion in &insertions {
+                assert!(db.process_tree_insertion(insertion, 1).unwrap());
+            }
+            // Written together with the insertion
+            assert_eq!(
+                db.retrieve_merkle_leaf_index_by_message_id(&insertions[2].message_id())
+                    .unwrap(),
+                Some(2)
+            );
+            assert_eq!(
+                db.retrieve_merkle_tree_intent(2).unwrap(),
+                Some(insertions[2])
+            );
+
+            db.mark_merkle_tree_intents_applied(2).unwrap();
+            db.mark_merkle_tree_intents_applied(2).unwrap();
+            db.mark_merkle_tree_intents_applied(1).unwrap();
+            assert_eq!(db.retrieve_merkle_tree_intents_applied().unwrap(), 2);
+            assert_eq!(db.retrieve_merkle_tree_intent(0).unwrap(), None);
+            assert_eq!(db.retrieve_merkle_tree_intent(1).unwrap(), None);
+            assert_eq!(
+                db.retrieve_merkle_tree_intent(2).unwrap(),
+                Some(insertions[2])
+            );
+            // The insertions themselves are kept
+            assert_eq!(
+                db.retrieve_merkle_tree_insertion_by_leaf_index(&0).unwrap(),
+                Some(insertions[0])
+            );
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_decision_snapshots_are_stored_per_attempt() {
+        run_test_db(|db| async move {
+            let db =
+                HyperlaneRocksDB::new(&HyperlaneDomain::new_test_domain("decision_snapshots"), db);
+            let (first, second) = (H256::repeat_byte(1), H256::repeat_byte(2));
+
+            assert_eq!(db.retrieve_decision_snapshot_count(&first).unwrap(), 0);
+            assert_eq!(db.store_decision_snapshot(&first, &10u32).unwrap(), 0);
+            assert_eq!(db.store_decision_snapshot(&first, &11u32).unwrap(), 1);
+            assert_eq!(db.store_decision_snapshot(&second, &20u32).unwrap(), 0);
+
+            assert_eq!(db.retrieve_decision_snapshot_count(&first).unwrap(), 2);
+            assert_eq!(
+                db.retrieve_decision_snapshot::<u32>(&first, 1).unwrap(),
+                Some(11)
+            );
+            assert_eq!(
+                db.retrieve_decision_snapshot::<u32>(&second, 0).unwrap(),
+                Some(20)
+            );
+            assert_eq!(
+                db.retrieve_decision_snapshot::<u32>(&first, 2).unwrap(),
+                None
+            );
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_gas_payments_are_counted_once_and_rolled_back() {
+        run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(&HyperlaneDomain::new_test_domain("gas_payments"), db);
+            let key = GasPaymentKey {
+                message_id: H256::repeat_byte(1),
+                destination: 2,
+            };
+            let payment = |amount: u64| InterchainGasPayment {
+                message_id: key.message_id,
+                destination: key.destination,
+                payment: U256::from(amount),
+                gas_amount: U256::from(amount),
+            };
+            let logs = [
+                (
+                    Indexed::new(payment(1)).with_sequence(0),
+                    LogMeta {
+                        block_number: 5,
+                        ..LogMeta::random()
+                    },
+                ),
+                (
+                    Indexed::new(payment(2)).with_sequence(1),
+                    LogMeta {
+                        block_number: 6,
+                        ..LogMeta::random()
+                    },
+                ),
+            ];
+            let aggregate = |db: &HyperlaneRocksDB| {
+                let aggregate = db.retrieve_gas_payment_aggregate(key).unwrap().unwrap();
+                (aggregate.total.payment.as_u64(), aggregate.contributions)
+            };
+
+            // Re-indexing the same logs, e.g. after a cursor rewind, doesn't
+            // count them twice
+            assert_eq!(db.store_logs(&logs).await.unwrap(), 2);
+            assert_eq!(db.store_logs(&logs).await.unwrap(), 0);
+            assert_eq!(aggregate(&db), (3, 2));
+
+            HyperlaneWatermarkedLogStore::<InterchainGasPayment>::store_high_watermark(&db, 6)
+                .await
+                .unwrap();
+            assert_eq!(db.roll_back_gas_payments_in_block(6).unwrap(), 1);
+            assert_eq!(aggregate(&db), (1, 1));
+            assert_eq!(db.retrieve_gas_payment_by_sequence(&1).unwrap(), None);
+            assert_eq!(
+                db.retrieve_gas_payment_contribution(&(&logs[1].1).into())
+                    .unwrap(),
+                None
+            );
+            assert_eq!(
+                HyperlaneWatermarkedLogStore::<InterchainGasPayment>::retrieve_high_watermark(&db)
+                    .await
+                    .unwrap(),
+                Some(5)
+            );
+            // Rolling back is idempotent
+            assert_eq!(db.roll_back_gas_payments_in_block(6).unwrap(), 0);
+            assert_eq!(aggregate(&db), (1, 1));
+
+            // Once re-indexed, the rolled back log counts again
+            assert_eq!(db.store_logs(&logs).await.unwrap(), 1);
+            assert_eq!(aggregate(&db), (3, 2));
+        })
+        .await;
+    }
+
+    fn corrupted_column_and_key(err: DbError) -> (String, String) {
+        match err {
+            DbError::Corruption {
+                column, key_hex, ..
+            } => (column, key_hex),
+            err => panic!("Expected a corruption error, got {err:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_corrupted_values_are_reported_with_their_context() {
+        run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(&HyperlaneDomain::new_test_domain("corruption"), db);
+            let stored = message(0, body(10));
+            db.store_message(&stored, 1).unwrap();
+            db.store_bytes(MESSAGE, stored.id().to_vec(), b"garbage")
+                .unwrap();
+            db.store_bytes(MESSAGE_ID, 1u32.to_vec(), &[1, 2, 3])
+                .unwrap();
+            let dangling = H256::repeat_byte(7);
+            db.store_message_id_by_nonce(&2, &dangling).unwrap();
+
+            let err = db.retrieve_message_by_nonce(0).unwrap_err();
+            assert!(err.is_corruption());
+            assert_eq!(
+                corrupted_column_and_key(err),
+                ("message".to_owned(), format!("{:?}", stored.id()))
+            );
+            assert_eq!(
+                corrupted_column_and_key(db.retrieve_message_by_nonce(1).unwrap_err()),
+                ("message_id".to_owned(), "0x00000001".to_owned())
+            );
+            match db.retrieve_message_by_nonce(2).unwrap_err() {
+                DbError::NotFound { column, key_hex } => {
+                    assert_eq!(column, "message");
+                    assert_eq!(key_hex, format!("{dangling:?}"));
+                }
+                err => panic!("Expected a missing value error, got {err:?}"),
+            }
+            assert_eq!(db.retrieve_message_by_nonce(3).unwrap(), None);
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_scans_skip_corrupted_records_within_tolerance() {
+        run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(&HyperlaneDomain::new_test_domain("corrupt_scan"), db);
+            for nonce in 0..5u32 {
+                db.store_message_id_by_nonce(&nonce, &H256::from_low_u64_be(nonce as u64))
+                    .unwrap();
+            }
+            for nonce in [1u32, 3] {
+                db.store_bytes(MESSAGE_ID, nonce.to_vec(), b"garbage")
+                    .unwrap();
+            }
+            let scan = |tolerance: &mut CorruptionTolerance| {
+                db.iterate_decodable::<H256>(MESSAGE_ID)
+                    .filter_map(|read| tolerance.skip_corrupted(read).transpose())
+                    .collect::<DbResult<Vec<_>>>()
+            };
+
+            let metric = prometheus::IntCounter::new("corrupted", "help").unwrap();
+            let mut tolerance = CorruptionTolerance::new(2, metric.clone());
+            let ids = scan(&mut tolerance).unwrap();
+            assert_eq!(
+                ids,
+                [0, 2, 4].map(H256::from_low_u64_be).to_vec(),
+                "Only the corrupted records are skipped"
+            );
+            assert_eq!(tolerance.skipped(), 2);
+            assert_eq!(metric.get(), 2);
+
+            // The second corrupted record aborts the scan
+            let mut tolerance = CorruptionTolerance::new(1, metric.clone());
+            let err = scan(&mut tolerance).unwrap_err();
+            assert_eq!(
+                corrupted_column_and_key(err),
+                ("message_id".to_owned(), "0x00000003".to_owned())
+            );
+            assert_eq!(tolerance.skipped(), 1);
+            assert_eq!(metric.get(), 3);
+        })
+        .await;
+    }
+
+    #[test]
+    fn test_read_only_db_rejects_writes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("db");
+        DB::from_path(&path)
+            .unwrap()
+            .store(b"key", b"value")
+            .unwrap();
+
+        let db = DB::from_path_read_only(&path).unwrap();
+        assert_eq!(db.retrieve(b"key").unwrap(), Some(b"value".to_vec()));
+        assert!(matches!(db.store(b"key", b"other"), Err(DbError::ReadOnly)));
+        assert!(matches!(
+            db.write(rocksdb::WriteBatch::default()),
+            Err(DbError::ReadOnly)
+        ));
+    }
+
+    fn nonces_in(db: &HyperlaneRocksDB, nonces: RangeInclusive<u32>) -> Vec<u32> {
+        db.iterate_message_ids_by_nonce(nonces)
+            .map(|entry| entry.unwrap().0)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_nonce_indices_are_maintained_with_batched_writes() {
+        run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(&HyperlaneDomain::new_test_domain("nonce_index"), db);
+            // Indexed out of order, with nonce 2 still missing
+            let messages: Vec<_> = [3, 0, 1, 4]
+                .into_iter()
+                .map(|nonce| message(nonce, body(nonce as usize)))
+                .collect();
+            let logs: Vec<_> = messages
+                .iter()
+                .map(|message| (Indexed::new(message.clone()), LogMeta::random()))
+                .collect();
+            assert_eq!(db.store_logs(&logs).await.unwrap(), 4);
+            // Storing them again leaves the indices as they were
+            assert_eq!(db.store_logs(&logs).await.unwrap(), 0);
+
+            for message in &messages {
+                assert_eq!(
+                    db.retrieve_message_id_by_origin_and_nonce(message.nonce)
+                        .unwrap(),
+                    Some(message.id())
+                );
+                assert_eq!(
+                    db.retrieve_nonce_by_message_id(&message.id()).unwrap(),
+                    Some(message.nonce)
+                );
+            }
+            assert_eq!(db.retrieve_message_id_by_origin_and_nonce(2).unwrap(), None);
+            let db_ref = &db;
+            let is_stored = move |nonce| {
+                HyperlaneSequenceAwareIndexerStoreReader::<HyperlaneMessage>::is_sequence_stored(
+                    db_ref, nonce,
+                )
+            };
+            assert!(!is_stored(2).await.unwrap());
+            assert!(is_stored(3).await.unwrap());
+            assert_eq!(
+                db.iterate_message_ids_by_nonce(0..=4)
+                    .map(Result::unwrap)
+                    .collect::<Vec<_>>(),
+                [0, 1, 3, 4]
+                    .into_iter()
+                    .map(|nonce| (nonce, message(nonce, body(nonce as usize)).id()))
+                    .collect::<Vec<_>>()
+            );
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_nonce_range_boundaries() {
+        run_test_db(|db| async move {
+            let origin =
+                HyperlaneRocksDB::new(&HyperlaneDomain::new_test_domain("origin"), db.clone());
+            let other = HyperlaneRocksDB::new(&HyperlaneDomain::new_test_domain("other"), db);
+            // Nonces past 255 tell big-endian keys apart from little-endian ones
+            for nonce in [0, 1, 2, 255, 256, 257, 70_000] {
+                origin.store_message(&message(nonce, vec![]), 1).unwrap();
+            }
+            other.store_message(&message(3, vec![]), 1).unwrap();
+
+            assert_eq!(nonces_in(&origin, 0..=2), [0, 1, 2]);
+            assert_eq!(nonces_in(&origin, 1..=1), [1]);
+            assert_eq!(nonces_in(&origin, 2..=255), [2, 255]);
+            assert_eq!(nonces_in(&origin, 3..=254), Vec::<u32>::new());
+            assert_eq!(nonces_in(&origin, 256..=u32::MAX), [256, 257, 70_000]);
+            assert_eq!(nonces_in(&origin, 70_001..=u32::MAX), Vec::<u32>::new());
+            #[allow(clippy::reversed_empty_ranges)]
+            let reversed = nonces_in(&origin, 2..=1);
+            assert_eq!(reversed, Vec::<u32>::new());
+            // Scoped to the origin of the db
+            assert_eq!(nonces_in(&other, 0..=u32::MAX), [3]);
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_nonce_indices_are_backfilled_for_messages_stored_before_them() {
+        run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(&HyperlaneDomain::new_test_domain("backfill"), db);
+            // Messages stored the way they were before the nonce indices,
+            // across several backfill chunks and with a gap
+            let stored: Vec<_> = (0..2500)
+                .filter(|nonce| *nonce != 1200)
+                .map(|nonce| message(nonce, vec![]))
+                .collect();
+            for message in &stored {
+                db.store_keyed_encodable(MESSAGE_ID, &message.nonce, &message.id())
+                    .unwrap();
+                db.store_bytes(MESSAGE, message.id().to_vec(), &message.to_vec())
+                    .unwrap();
+            }
+            db.store_highest_seen_message_nonce_number(&2499).unwrap();
+            assert_eq!(nonces_in(&db, 0..=u32::MAX), Vec::<u32>::new());
+
+            assert_eq!(db.backfill_message_nonce_indices().unwrap(), 2499);
+            assert_eq!(
+                db.iterate_message_ids_by_nonce(0..=u32::MAX)
+                    .map(Result::unwrap)
+                    .collect::<Vec<_>>(),
+                stored
+                    .iter()
+                    .map(|message| (message.nonce, message.id()))
+                    .collect::<Vec<_>>()
+            );
+            for message in &stored {
+                assert_eq!(
+                    db.retrieve_nonce_by_message_id(&message.id()).unwrap(),
+                    Some(message.nonce)
+                );
+            }
+
+            // The gap is indexed as it's filled, the backfill isn't run again
+            db.store_message(&message(1200, vec![]), 1).unwrap();
+            assert_eq!(db.backfill_message_nonce_indices().unwrap(), 0);
+            assert_eq!(nonces_in(&db, 1199..=1201), [1199, 1200, 1201]);
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_nonce_index_backfill_resumes() {
+        run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(&HyperlaneDomain::new_test_domain("resume"), db);
+            for nonce in 0..10 {
+                let message = message(nonce, vec![]);
+                db.store_keyed_encodable(MESSAGE_ID, &nonce, &message.id())
+                    .unwrap();
+            }
+            db.store_highest_seen_message_nonce_number(&9).unwrap();
+            // Interrupted after indexing the first 6 messages
+            db.store_value_by_key(
+                MESSAGE_NONCE_INDEX_BACKFILL_PROGRESS,
+                &bool::default(),
+                &6u32,
+            )
+            .unwrap();
+
+            assert_eq!(db.backfill_message_nonce_indices().unwrap(), 4);
+            assert_eq!(nonces_in(&db, 0..=9), [6, 7, 8, 9]);
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_colliding_message_ids_are_tracked_per_origin() {
+        run_test_db(|db| async move {
+            // A fork replaying the origin's messages, sharing the database
+            let origin =
+                HyperlaneRocksDB::new(&HyperlaneDomain::new_test_domain("origin"), db.clone());
+            let fork = HyperlaneRocksDB::new(&HyperlaneDomain::new_test_domain("fork"), db);
+            let message = message(0, body(8));
+            let id = message.id();
+            origin.store_message(&message, 1).unwrap();
+            fork.store_message(&message, 2).unwrap();
+
+            origin
+                .store_status_by_message_id(&id, &PendingOperationStatus::FirstPrepareAttempt)
+                .unwrap();
+            origin
+                .store_pending_message_retry_count_by_message_id(&id, &3)
+                .unwrap();
+            let payment = InterchainGasPayment {
+                message_id: id,
+                destination: message.destination,
+                payment: U256::from(100),
+                gas_amount: U256::from(10),
+            };
+            assert!(origin
+                .process_gas_payment(payment, &LogMeta::default())
+                .unwrap());
+            origin
+                .process_gas_expenditure(InterchainGasExpenditure {
+                    message_id: id,
+                    tokens_used: U256::from(5),
+                    gas_used: U256::from(1),
+                })
+                .unwrap();
+
+            let key = GasPaymentKey {
+                message_id: id,
+                destination: message.destination,
+            };
+            assert_eq!(
+                origin
+                    .retrieve_dispatched_block_number_by_nonce(&0)
+                    .unwrap(),
+                Some(1)
+            );
+            assert_eq!(
+                fork.retrieve_dispatched_block_number_by_nonce(&0).unwrap(),
+                Some(2)
+            );
+            assert!(origin.retrieve_status_by_message_id(&id).unwrap().is_some());
+            assert!(fork.retrieve_status_by_message_id(&id).unwrap().is_none());
+            assert_eq!(
+                fork.retrieve_pending_message_retry_count_by_message_id(&id)
+                    .unwrap(),
+                None
+            );
+            assert_eq!(
+                origin
+                    .retrieve_gas_payment_by_gas_payment_key(key)
+                    .unwrap()
+                    .map(|payment| payment.payment),
+                Some(U256::from(100))
+            );
+            assert_eq!(
+                fork.retrieve_gas_payment_by_gas_payment_key(key).unwrap(),
+                None
+            );
+            assert_eq!(
+                fork.retrieve_gas_expenditure_by_message_id(id)
+                    .unwrap()
+                    .tokens_used,
+                U256::zero()
+            );
+        })
+        .await;
+    }
+}
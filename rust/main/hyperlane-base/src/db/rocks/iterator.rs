@@ -2,30 +2,90 @@ use std::marker::PhantomData;
 
 use rocksdb::DBIterator;
 
-use hyperlane_core::{Decode, Encode};
+use hyperlane_core::Decode;
 
-/// An iterator over a prefix that deserializes values
+use crate::db::DbError;
+
+/// An iterator over a prefix that deserializes values. Values that can't be
+/// read are yielded as errors, so that callers can decide whether to skip
+/// them or stop.
 pub struct PrefixIterator<'a, V> {
     iter: DBIterator<'a>,
-    prefix: &'a [u8],
+    prefix: Vec<u8>,
+    /// Length of the domain prefix of the keys, left out of error context
+    domain_prefix_len: usize,
+    done: bool,
     _phantom: PhantomData<*const V>,
 }
 
+impl<'a, V> PrefixIterator<'a, V> {
+    /// Iterate over the values of `iter` stored under `prefix`, the first
+    /// `domain_prefix_len` bytes of which scope it to a domain
+    pub fn new(iter: DBIterator<'a>, prefix: Vec<u8>, domain_prefix_len: usize) -> Self {
+        Self {
+            iter,
+            prefix,
+            domain_prefix_len,
+            done: false,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<'a, V> PrefixIterator<'a, V>
+where
+    V: Decode,
+{
+    /// Yield the key of each value too, without its prefix
+    pub fn keyed(self) -> KeyedPrefixIterator<'a, V> {
+        KeyedPrefixIterator(self)
+    }
+
+    fn next_entry(&mut self) -> Option<Result<(Vec<u8>, V), DbError>> {
+        if self.done {
+            return None;
+        }
+        let (k, v) = match self.iter.next()? {
+            Ok(entry) => entry,
+            Err(err) => return Some(Err(err.into())),
+        };
+        // Keys are sorted, so none after the first one outside the prefix match
+        let Some(key) = k.strip_prefix(self.prefix.as_slice()) else {
+            self.done = true;
+            return None;
+        };
+        Some(
+            V::read_from(&mut &v[..])
+                .map(|value| (key.to_vec(), value))
+                .map_err(|err| {
+                    DbError::corruption(&self.prefix[self.domain_prefix_len..], key, err)
+                }),
+        )
+    }
+}
+
 impl<'a, V> Iterator for PrefixIterator<'a, V>
 where
-    V: Encode + Decode,
+    V: Decode,
+{
+    type Item = Result<V, DbError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_entry().map(|entry| entry.map(|(_, value)| value))
+    }
+}
+
+/// A [`PrefixIterator`] that yields the keys of the values along with them,
+/// without their prefix
+pub struct KeyedPrefixIterator<'a, V>(PrefixIterator<'a, V>);
+
+impl<'a, V> Iterator for KeyedPrefixIterator<'a, V>
+where
+    V: Decode,
 {
-    type Item = V;
+    type Item = Result<(Vec<u8>, V), DbError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let prefix = self.prefix;
-        self.iter.find_map(|r| {
-            let (k, v) = r.expect("Database error when iterating prefixed keys");
-            if k.strip_prefix(prefix).is_some() {
-                Some(V::read_from(&mut &v[..]).expect("!corrupt"))
-            } else {
-                None
-            }
-        })
+        self.0.next_entry()
     }
 }
@@ -1,15 +1,21 @@
-use std::{path::Path, sync::Arc};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use super::error::DbError;
-use rocksdb::{Options, DB as Rocks};
+use rocksdb::{DBIterator, Direction, IteratorMode, Options, WriteBatch, DB as Rocks};
 use tracing::info;
 
+pub use compression::*;
 pub use hyperlane_db::*;
 pub use typed_db::*;
 
 /// Shared functionality surrounding use of rocksdb
 pub mod iterator;
 
+/// Compression of stored message values
+mod compression;
 /// DB operations tied to specific Mailbox
 mod hyperlane_db;
 /// Type-specific db operations
@@ -21,31 +27,33 @@ pub mod test_utils;
 
 #[derive(Debug, Clone)]
 /// A KV Store
-pub struct DB(Arc<Rocks>);
+pub struct DB {
+    rocks: Arc<Rocks>,
+    message_compression: Arc<MessageCompression>,
+    read_only: bool,
+}
 
+/// Message compression is left disabled for databases wrapped directly, e.g.
+/// the temporary ones used in tests, unless enabled with
+/// [`DB::with_message_compression`].
 impl From<Rocks> for DB {
     fn from(rocks: Rocks) -> Self {
-        Self(Arc::new(rocks))
+        Self {
+            rocks: Arc::new(rocks),
+            message_compression: Arc::new(MessageCompression::disabled()),
+            read_only: false,
+        }
     }
 }
 
 type Result<T> = std::result::Result<T, DbError>;
 
 impl DB {
-    /// Opens db at `db_path` and creates if missing
+    /// Opens db at `db_path` and creates if missing, with the default message
+    /// compression
     #[tracing::instrument(err)]
     pub fn from_path(db_path: &Path) -> Result<DB> {
-        let path = {
-            let mut path = db_path
-                .parent()
-                .unwrap_or(Path::new("."))
-                .canonicalize()
-                .map_err(|e| DbError::InvalidDbPath(e, db_path.to_string_lossy().into()))?;
-            if let Some(file_name) = db_path.file_name() {
-                path.push(file_name);
-            }
-            path
-        };
+        let path = canonicalize(db_path)?;
 
         if path.is_dir() {
             info!(path=%path.to_string_lossy(), "Opening existing db")
@@ -62,16 +70,84 @@ impl DB {
                 path: db_path.into(),
                 canonicalized: path,
             })
-            .map(Into::into)
+            .map(|rocks| DB::from(rocks).with_message_compression(Default::default()))
+    }
+
+    /// Opens the existing db at `db_path` for reading only, e.g. to inspect
+    /// the db of a running agent. Writes fail with [`DbError::ReadOnly`].
+    #[tracing::instrument(err)]
+    pub fn from_path_read_only(db_path: &Path) -> Result<DB> {
+        let path = canonicalize(db_path)?;
+        Rocks::open_for_read_only(&Options::default(), &path, false)
+            .map_err(|e| DbError::OpeningError {
+                source: e,
+                path: db_path.into(),
+                canonicalized: path,
+            })
+            .map(|rocks| DB {
+                read_only: true,
+                ..DB::from(rocks)
+            })
+    }
+
+    /// Set how message values are compressed. Values already stored are
+    /// read regardless of the setting.
+    pub fn with_message_compression(mut self, compression: MessageCompression) -> Self {
+        self.message_compression = Arc::new(compression);
+        self
+    }
+
+    /// How message values are compressed
+    pub fn message_compression(&self) -> &MessageCompression {
+        &self.message_compression
     }
 
     /// Store a value in the DB
     pub fn store(&self, key: &[u8], value: &[u8]) -> Result<()> {
-        Ok(self.0.put(key, value)?)
+        self.ensure_writable()?;
+        Ok(self.rocks.put(key, value)?)
     }
 
     /// Retrieve a value from the DB
     pub fn retrieve(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
-        Ok(self.0.get(key)?)
+        Ok(self.rocks.get(key)?)
+    }
+
+    /// Apply a batch of writes atomically
+    pub fn write(&self, batch: WriteBatch) -> Result<()> {
+        self.ensure_writable()?;
+        Ok(self.rocks.write(batch)?)
+    }
+
+    /// Iterate over the entries whose key starts with `prefix`, and then the
+    /// ones after them
+    pub fn prefix_iterator(&self, prefix: &[u8]) -> DBIterator<'_> {
+        self.rocks.prefix_iterator(prefix)
+    }
+
+    /// Iterate over the entries from `key` on, in key order
+    pub fn iterator_from(&self, key: &[u8]) -> DBIterator<'_> {
+        self.rocks
+            .iterator(IteratorMode::From(key, Direction::Forward))
+    }
+
+    fn ensure_writable(&self) -> Result<()> {
+        if self.read_only {
+            return Err(DbError::ReadOnly);
+        }
+        Ok(())
+    }
+}
+
+/// Resolve the parent directory of `db_path`, which must exist
+fn canonicalize(db_path: &Path) -> Result<PathBuf> {
+    let mut path = db_path
+        .parent()
+        .unwrap_or(Path::new("."))
+        .canonicalize()
+        .map_err(|e| DbError::InvalidDbPath(e, db_path.to_string_lossy().into()))?;
+    if let Some(file_name) = db_path.file_name() {
+        path.push(file_name);
     }
+    Ok(path)
 }
@@ -1,8 +1,17 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
 use futures_util::Future;
+use prometheus::Registry;
 use rocksdb::Options;
 use tempfile::TempDir;
 
-use crate::db::DB;
+use crate::{
+    db::{DiskSpaceConf, DiskWatcher, FreeSpaceProbe, DB},
+    CoreMetrics,
+};
 
 /// Create a database from a path.
 pub fn setup_db(db_path: String) -> DB {
@@ -28,6 +37,35 @@ where
     let _ = rocksdb::DB::destroy(&Options::default(), db_tmp_dir);
 }
 
+/// Reports the free disk space it's set to
+#[derive(Debug, Clone, Default)]
+pub struct MockFreeSpaceProbe(pub Arc<AtomicU64>);
+
+impl MockFreeSpaceProbe {
+    /// Set the free disk space reported from now on
+    pub fn set(&self, free_bytes: u64) {
+        self.0.store(free_bytes, Ordering::Relaxed);
+    }
+}
+
+impl FreeSpaceProbe for MockFreeSpaceProbe {
+    fn free_bytes(&self) -> std::io::Result<u64> {
+        Ok(self.0.load(Ordering::Relaxed))
+    }
+}
+
+/// Watch the free disk space reported by `probe`, warning below 1000 bytes
+/// and pausing indexing below 100
+pub fn disk_watcher(probe: &MockFreeSpaceProbe) -> DiskWatcher {
+    let conf = DiskSpaceConf {
+        warning_free_bytes: Some(1000),
+        critical_free_bytes: Some(100),
+        ..Default::default()
+    };
+    let metrics = CoreMetrics::new("test", 0, Registry::new()).unwrap();
+    DiskWatcher::new(Box::new(probe.clone()), conf, &metrics).unwrap()
+}
+
 #[cfg(test)]
 mod test {
     use hyperlane_core::{
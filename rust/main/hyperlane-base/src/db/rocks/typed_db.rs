@@ -1,6 +1,7 @@
 use hyperlane_core::{Decode, Encode, HyperlaneDomain};
+use rocksdb::WriteBatch;
 
-use crate::db::{error::DbError, DB};
+use crate::db::{error::DbError, iterator::PrefixIterator, DB};
 
 type Result<T> = std::result::Result<T, DbError>;
 
@@ -33,12 +34,22 @@ impl TypedDB {
     }
 
     fn prefixed_key(&self, prefix: &[u8], key: &[u8]) -> Vec<u8> {
-        self.domain_prefix
-            .iter()
-            .chain(prefix)
-            .chain(key)
-            .copied()
-            .collect()
+        prefixed_key(&self.domain_prefix, prefix, key)
+    }
+
+    /// Start a batch of writes, to be applied atomically with
+    /// [`TypedDB::write`]
+    pub fn batch(&self) -> TypedBatch {
+        TypedBatch {
+            domain_prefix: self.domain_prefix.clone(),
+            batch: WriteBatch::default(),
+        }
+    }
+
+    /// Apply a batch of writes atomically: either all of them are persisted
+    /// or none are
+    pub fn write(&self, batch: TypedBatch) -> Result<()> {
+        self.db.write(batch.batch)
     }
 
     /// Store encodable value
@@ -54,17 +65,68 @@ impl TypedDB {
         )
     }
 
-    /// Retrieve decodable value
+    /// Retrieve decodable value. Values that can't be decoded are reported as
+    /// [`DbError::Corruption`] along with their prefix and key.
     pub fn retrieve_decodable<V: Decode>(
         &self,
         prefix: impl AsRef<[u8]>,
         key: impl AsRef<[u8]>,
     ) -> Result<Option<V>> {
+        let (prefix, key) = (prefix.as_ref(), key.as_ref());
         self.db
-            .retrieve(&self.prefixed_key(prefix.as_ref(), key.as_ref()))?
-            .map(|v| V::read_from(&mut v.as_slice()))
+            .retrieve(&self.prefixed_key(prefix, key))?
+            .map(|v| {
+                V::read_from(&mut v.as_slice()).map_err(|err| DbError::corruption(prefix, key, err))
+            })
             .transpose()
-            .map_err(Into::into)
+    }
+
+    /// Iterate over the decodable values stored under a prefix, in key order.
+    /// Each value that can't be decoded is yielded as an error, so that the
+    /// ones after it can still be read.
+    pub fn iterate_decodable<V: Decode>(&self, prefix: impl AsRef<[u8]>) -> PrefixIterator<'_, V> {
+        let prefix = self.prefixed_key(prefix.as_ref(), &[]);
+        PrefixIterator::new(
+            self.db.prefix_iterator(&prefix),
+            prefix,
+            self.domain_prefix.len(),
+        )
+    }
+
+    /// Iterate over the decodable values stored under a prefix, in key order,
+    /// from the first one whose key is at least `start_key`
+    pub fn iterate_decodable_from<V: Decode>(
+        &self,
+        prefix: impl AsRef<[u8]>,
+        start_key: impl AsRef<[u8]>,
+    ) -> PrefixIterator<'_, V> {
+        let (prefix, start_key) = (prefix.as_ref(), start_key.as_ref());
+        PrefixIterator::new(
+            self.db.iterator_from(&self.prefixed_key(prefix, start_key)),
+            self.prefixed_key(prefix, &[]),
+            self.domain_prefix.len(),
+        )
+    }
+
+    /// Store a value that is already serialized
+    pub fn store_bytes(
+        &self,
+        prefix: impl AsRef<[u8]>,
+        key: impl AsRef<[u8]>,
+        value: &[u8],
+    ) -> Result<()> {
+        self.db
+            .store(&self.prefixed_key(prefix.as_ref(), key.as_ref()), value)
+    }
+
+    /// Retrieve a value without decoding it
+    pub fn retrieve_bytes(
+        &self,
+        prefix: impl AsRef<[u8]>,
+        key: impl AsRef<[u8]>,
+    ) -> Result<Option<Vec<u8>>> {
+        self.db
+            .retrieve(&self.prefixed_key(prefix.as_ref(), key.as_ref()))
     }
 
     /// Store encodable kv pair
@@ -86,3 +148,79 @@ impl TypedDB {
         self.retrieve_decodable(prefix, key.to_vec())
     }
 }
+
+/// Writes to a [`TypedDB`] that are applied atomically, using the same key
+/// structure.
+pub struct TypedBatch {
+    domain_prefix: Vec<u8>,
+    batch: WriteBatch,
+}
+
+impl TypedBatch {
+    /// Scope the writes that follow to the domain of `db`, so that a single
+    /// batch can span the data of several domains
+    pub fn scope_to(&mut self, db: &TypedDB) {
+        self.domain_prefix = db.domain_prefix.clone();
+    }
+
+    /// Store encodable value
+    pub fn store_encodable<V: Encode>(
+        &mut self,
+        prefix: impl AsRef<[u8]>,
+        key: impl AsRef<[u8]>,
+        value: &V,
+    ) {
+        self.store_bytes(prefix, key, &value.to_vec())
+    }
+
+    /// Store a value that is already serialized
+    pub fn store_bytes(&mut self, prefix: impl AsRef<[u8]>, key: impl AsRef<[u8]>, value: &[u8]) {
+        self.batch.put(
+            prefixed_key(&self.domain_prefix, prefix.as_ref(), key.as_ref()),
+            value,
+        )
+    }
+
+    /// Store encodable kv pair
+    pub fn store_keyed_encodable<K: Encode, V: Encode>(
+        &mut self,
+        prefix: impl AsRef<[u8]>,
+        key: &K,
+        value: &V,
+    ) {
+        self.store_encodable(prefix, key.to_vec(), value)
+    }
+
+    /// Delete a value
+    pub fn delete(&mut self, prefix: impl AsRef<[u8]>, key: impl AsRef<[u8]>) {
+        self.batch.delete(prefixed_key(
+            &self.domain_prefix,
+            prefix.as_ref(),
+            key.as_ref(),
+        ))
+    }
+
+    /// Delete the value of an encodable key
+    pub fn delete_keyed<K: Encode>(&mut self, prefix: impl AsRef<[u8]>, key: &K) {
+        self.delete(prefix, key.to_vec())
+    }
+
+    /// Number of writes in the batch
+    pub fn len(&self) -> usize {
+        self.batch.len()
+    }
+
+    /// Whether the batch has no writes
+    pub fn is_empty(&self) -> bool {
+        self.batch.is_empty()
+    }
+}
+
+fn prefixed_key(domain_prefix: &[u8], prefix: &[u8], key: &[u8]) -> Vec<u8> {
+    domain_prefix
+        .iter()
+        .chain(prefix)
+        .chain(key)
+        .copied()
+        .collect()
+}
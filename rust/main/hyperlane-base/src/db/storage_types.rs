@@ -126,3 +126,127 @@ impl Decode for InterchainGasExpenditureData {
         })
     }
 }
+
+/// Track record of a validator's checkpoint storage. Counts are
+/// exponentially decayed, so they are fractional.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct ValidatorReputation {
+    /// Checkpoints fetched and verified
+    pub successes: f64,
+    /// Fetches that errored or found no checkpoint
+    pub failures: f64,
+    /// Fetched checkpoints that failed verification
+    pub verification_failures: f64,
+    /// Exponentially weighted moving average of fetch latency, in milliseconds
+    pub latency_ewma_ms: f64,
+    /// Unix timestamp, in seconds, the counts were last decayed at
+    pub updated_at: u64,
+}
+
+impl Encode for ValidatorReputation {
+    fn write_to<W>(&self, writer: &mut W) -> std::io::Result<usize>
+    where
+        W: Write,
+    {
+        Ok(self.successes.to_bits().write_to(writer)?
+            + self.failures.to_bits().write_to(writer)?
+            + self.verification_failures.to_bits().write_to(writer)?
+            + self.latency_ewma_ms.to_bits().write_to(writer)?
+            + self.updated_at.write_to(writer)?)
+    }
+}
+
+impl Decode for ValidatorReputation {
+    fn read_from<R>(reader: &mut R) -> Result<Self, HyperlaneProtocolError>
+    where
+        R: Read,
+        Self: Sized,
+    {
+        Ok(Self {
+            successes: f64::from_bits(u64::read_from(reader)?),
+            failures: f64::from_bits(u64::read_from(reader)?),
+            verification_failures: f64::from_bits(u64::read_from(reader)?),
+            latency_ewma_ms: f64::from_bits(u64::read_from(reader)?),
+            updated_at: u64::read_from(reader)?,
+        })
+    }
+}
+
+/// A gas payment log counted towards a message's total payment. Kept so the
+/// payment can be subtracted again if the block it was emitted in is rolled
+/// back.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct GasPaymentContribution {
+    /// The payment
+    pub payment: InterchainGasPayment,
+    /// The block the payment log was emitted in
+    pub block_number: u64,
+    /// The sequence the payment was indexed at, if it was
+    pub sequence: Option<u32>,
+    /// The IGP contract the payment is attributed to, if any
+    pub contract: Option<H256>,
+    /// Whether the payment's sequence is stored under keys scoped to
+    /// `contract`, as for IGPs indexed in addition to the configured one
+    pub scoped: bool,
+}
+
+impl GasPaymentContribution {
+    /// A contribution not attributed to any contract
+    pub fn new(payment: InterchainGasPayment, block_number: u64, sequence: Option<u32>) -> Self {
+        Self {
+            payment,
+            block_number,
+            sequence,
+            contract: None,
+            scoped: false,
+        }
+    }
+}
+
+impl Encode for GasPaymentContribution {
+    fn write_to<W>(&self, writer: &mut W) -> std::io::Result<usize>
+    where
+        W: Write,
+    {
+        Ok(self.payment.write_to(writer)?
+            + self.block_number.write_to(writer)?
+            + self.sequence.is_some().write_to(writer)?
+            + self.sequence.unwrap_or_default().write_to(writer)?
+            + self.contract.is_some().write_to(writer)?
+            + self.contract.unwrap_or_default().write_to(writer)?
+            + self.scoped.write_to(writer)?)
+    }
+}
+
+impl Decode for GasPaymentContribution {
+    fn read_from<R>(reader: &mut R) -> Result<Self, HyperlaneProtocolError>
+    where
+        R: Read,
+        Self: Sized,
+    {
+        let payment = InterchainGasPayment::read_from(reader)?;
+        let block_number = u64::read_from(reader)?;
+        let has_sequence = bool::read_from(reader)?;
+        let sequence = u32::read_from(reader)?;
+        let has_contract = bool::read_from(reader)?;
+        let contract = H256::read_from(reader)?;
+        Ok(Self {
+            payment,
+            block_number,
+            sequence: has_sequence.then_some(sequence),
+            contract: has_contract.then_some(contract),
+            scoped: bool::read_from(reader)?,
+        })
+    }
+}
+
+/// A message's total gas payment, along with the number of payment logs it
+/// was aggregated from
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct GasPaymentAggregate {
+    /// The total payment
+    pub total: InterchainGasPayment,
+    /// Number of payment logs counted towards the total. Payments indexed
+    /// before contributions were tracked aren't included.
+    pub contributions: u32,
+}
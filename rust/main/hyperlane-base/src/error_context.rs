@@ -0,0 +1,226 @@
+//! Chain and message context of errors.
+//!
+//! Errors bubbling up through the agents easily lose track of the chain and
+//! message they occurred for by the time they're logged. [`SpanContext`]
+//! captures the context fields of the tracing spans an error is created in,
+//! so the error report keeps them when it crosses task boundaries, and the
+//! [`WithChainContext`] and [`WithMessageContext`] extension traits attach
+//! them explicitly at the seams of the pipeline.
+
+use std::fmt::{self, Display, Formatter};
+
+use eyre::WrapErr;
+use hyperlane_core::{HyperlaneDomain, HyperlaneMessage};
+use tracing_error::SpanTrace;
+
+/// The span fields carried into error reports, by the names they're reported
+/// under and the names spans record them as
+const CONTEXT_FIELDS: &[(&str, &[&str])] = &[
+    ("chain", &["chain", "domain"]),
+    ("origin", &["origin"]),
+    ("destination", &["destination", "destination_domain"]),
+    ("message_id", &["message_id"]),
+    ("route", &["route"]),
+];
+
+/// The context fields of the tracing spans an error was created in. The
+/// innermost span recording a field wins.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SpanContext {
+    fields: Vec<(&'static str, String)>,
+}
+
+impl SpanContext {
+    /// Capture the context fields of the current span and its parents. Empty
+    /// unless the `tracing_error::ErrorLayer` is installed.
+    pub fn capture() -> Self {
+        let mut context = Self::default();
+        SpanTrace::capture().with_spans(|_metadata, fields| {
+            for (name, value) in parse_fields(fields) {
+                context.record(name, value);
+            }
+            true
+        });
+        context
+    }
+
+    fn record(&mut self, name: &str, value: &str) {
+        let Some((field, _)) = CONTEXT_FIELDS
+            .iter()
+            .find(|(_, aliases)| aliases.contains(&name))
+        else {
+            return;
+        };
+        if self.get(field).is_none() {
+            self.fields.push((field, value.to_owned()));
+        }
+    }
+
+    /// The value of a context field, e.g. `chain` or `message_id`
+    pub fn get(&self, field: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(name, _)| *name == field)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Whether no context field was captured
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+}
+
+impl Display for SpanContext {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (i, (name, value)) in self.fields.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{name}={value}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Split span fields formatted as `name=value name="quoted value"` into
+/// their names and unquoted values. Bare values, e.g. messages, are skipped.
+fn parse_fields(fields: &str) -> Vec<(&str, &str)> {
+    let mut parsed = vec![];
+    let mut rest = fields.trim_start();
+    while !rest.is_empty() {
+        let token_end = rest.find(' ').unwrap_or(rest.len());
+        let Some(eq) = rest[..token_end].find('=') else {
+            rest = rest[token_end..].trim_start();
+            continue;
+        };
+        let name = &rest[..eq];
+        let value_and_rest = &rest[eq + 1..];
+        let (value, value_end) = match value_and_rest.strip_prefix('"') {
+            Some(quoted) => {
+                let end = closing_quote(quoted).unwrap_or(quoted.len());
+                (&quoted[..end], (end + 2).min(value_and_rest.len()))
+            }
+            None => {
+                let end = value_and_rest.find(' ').unwrap_or(value_and_rest.len());
+                (&value_and_rest[..end], end)
+            }
+        };
+        parsed.push((name, value));
+        rest = value_and_rest[value_end..].trim_start();
+    }
+    parsed
+}
+
+/// The index of the first unescaped `"`
+fn closing_quote(s: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (i, c) in s.char_indices() {
+        match c {
+            '\\' if !escaped => escaped = true,
+            '"' if !escaped => return Some(i),
+            _ => escaped = false,
+        }
+    }
+    None
+}
+
+/// Attach the chain an operation ran against to its error
+pub trait WithChainContext<T, E> {
+    /// Wrap the error with the `domain` it occurred on
+    fn with_chain_context(self, domain: &HyperlaneDomain) -> eyre::Result<T>;
+}
+
+impl<T, E> WithChainContext<T, E> for Result<T, E>
+where
+    Result<T, E>: WrapErr<T, E>,
+{
+    fn with_chain_context(self, domain: &HyperlaneDomain) -> eyre::Result<T> {
+        self.wrap_err_with(|| format!("chain={}", domain.name()))
+    }
+}
+
+/// Attach the message an operation ran for to its error
+pub trait WithMessageContext<T, E> {
+    /// Wrap the error with the id and route of the `message` it occurred for
+    fn with_message_context(self, message: &HyperlaneMessage) -> eyre::Result<T>;
+}
+
+impl<T, E> WithMessageContext<T, E> for Result<T, E>
+where
+    Result<T, E>: WrapErr<T, E>,
+{
+    fn with_message_context(self, message: &HyperlaneMessage) -> eyre::Result<T> {
+        self.wrap_err_with(|| {
+            format!(
+                "message_id={:?} route={}->{}",
+                message.id(),
+                HyperlaneDomain::from_domain_id(message.origin).name(),
+                HyperlaneDomain::from_domain_id(message.destination).name(),
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use hyperlane_core::{ChainCommunicationError, ChainResult, KnownHyperlaneDomain};
+    use tracing::info_span;
+    use tracing_error::ErrorLayer;
+    use tracing_subscriber::{layer::SubscriberExt, Registry};
+
+    use super::*;
+
+    #[test]
+    fn test_fields_are_parsed_with_quoted_values() {
+        assert_eq!(
+            parse_fields(r#"chain=ethereum reason="deadline \"has\" elapsed" bare nonce=3"#),
+            vec![
+                ("chain", "ethereum"),
+                ("reason", r#"deadline \"has\" elapsed"#),
+                ("nonce", "3"),
+            ]
+        );
+        assert_eq!(parse_fields(""), vec![]);
+    }
+
+    #[test]
+    fn test_innermost_span_fields_are_captured() {
+        let subscriber = Registry::default().with(ErrorLayer::default());
+        let context = tracing::subscriber::with_default(subscriber, || {
+            let outer = info_span!("MessageProcessor", origin = "ethereum", domain = "ethereum");
+            let inner = info_span!("prepare", domain = "arbitrum", message_id = "0x01");
+            outer.in_scope(|| inner.in_scope(SpanContext::capture))
+        });
+
+        assert_eq!(context.get("chain"), Some("arbitrum"));
+        assert_eq!(context.get("origin"), Some("ethereum"));
+        assert_eq!(context.get("message_id"), Some("0x01"));
+        assert_eq!(context.get("route"), None);
+    }
+
+    #[test]
+    fn test_seam_context_wraps_the_chain_error() {
+        let message = HyperlaneMessage {
+            origin: KnownHyperlaneDomain::Ethereum as u32,
+            destination: KnownHyperlaneDomain::Arbitrum as u32,
+            ..Default::default()
+        };
+        let domain = HyperlaneDomain::Known(KnownHyperlaneDomain::Arbitrum);
+        let failed: ChainResult<()> = Err(ChainCommunicationError::from_other_str(
+            "deadline has elapsed",
+        ));
+
+        let err = failed
+            .with_chain_context(&domain)
+            .with_message_context(&message)
+            .unwrap_err();
+
+        assert_eq!(
+            format!("{err:#}"),
+            format!(
+                "message_id={:?} route=ethereum->arbitrum: chain=arbitrum: deadline has elapsed",
+                message.id()
+            )
+        );
+    }
+}
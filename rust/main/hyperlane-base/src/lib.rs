@@ -27,6 +27,9 @@ pub use server::*;
 mod contract_sync;
 pub use contract_sync::*;
 
+mod error_context;
+pub use error_context::*;
+
 mod traits;
 pub use traits::*;
 
@@ -1,6 +1,7 @@
 //! Metrics either related to the agents, or observed by them
 #![allow(unexpected_cfgs)] // TODO: `rustc` 1.80.1 clippy issue
 
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -8,8 +9,10 @@ use eyre::Result;
 use hyperlane_core::metrics::agent::decimals_by_protocol;
 use hyperlane_core::metrics::agent::u256_as_scaled_f64;
 use hyperlane_core::metrics::agent::METRICS_SCRAPE_INTERVAL;
+use hyperlane_core::Balance;
 use hyperlane_core::HyperlaneDomain;
 use hyperlane_core::HyperlaneProvider;
+use hyperlane_core::NativeToken;
 use maplit::hashmap;
 use prometheus::GaugeVec;
 use prometheus::IntGaugeVec;
@@ -34,18 +37,18 @@ pub const WALLET_BALANCE_HELP: &str =
     "Current native token balance for the wallet addresses in the `wallets` set";
 
 /// Expected label names for the `block_height` metric.
-pub const BLOCK_HEIGHT_LABELS: &[&str] = &["chain"];
+pub const BLOCK_HEIGHT_LABELS: &[&str] = &["chain", "domain_type"];
 /// Help string for the metric.
 pub const BLOCK_HEIGHT_HELP: &str = "Tracks the current block height of the chain";
 
 /// Expected label names for the `gas_price` metric.
-pub const GAS_PRICE_LABELS: &[&str] = &["chain"];
+pub const GAS_PRICE_LABELS: &[&str] = &["chain", "domain_type"];
 /// Help string for the metric.
 pub const GAS_PRICE_HELP: &str =
     "Tracks the current gas price of the chain, in the lowest denomination (e.g. wei)";
 
 /// Expected label names for the `critical_error` metric.
-pub const CRITICAL_ERROR_LABELS: &[&str] = &["chain"];
+pub const CRITICAL_ERROR_LABELS: &[&str] = &["chain", "domain_type"];
 /// Help string for the metric.
 pub const CRITICAL_ERROR_HELP: &str =
     "Boolean marker for critical errors on a chain, signalling loss of liveness";
@@ -66,7 +69,8 @@ pub struct AgentMetrics {
 }
 
 impl AgentMetrics {
-    pub(crate) fn new(metrics: &CoreMetrics) -> Result<AgentMetrics> {
+    /// Register the agent metrics with the registry of `metrics`
+    pub fn new(metrics: &CoreMetrics) -> Result<AgentMetrics> {
         let agent_metrics = AgentMetrics {
             wallet_balance: Some(metrics.new_gauge(
                 "wallet_balance",
@@ -84,6 +88,7 @@ pub struct ChainMetrics {
     /// Tracks the current block height of the chain.
     /// - `chain`: the chain name (or ID if the name is unknown) of the chain
     ///   the block number refers to.
+    /// - `domain_type`: the type of the domain, e.g. `mainnet` or `testnet`.
     pub block_height: IntGaugeVec,
 
     /// Tracks the current gas price of the chain. Uses the base_fee_per_gas if
@@ -91,14 +96,19 @@ pub struct ChainMetrics {
     /// TODO: use the median of the transactions.
     /// - `chain`: the chain name (or chain ID if the name is unknown) of the
     ///   chain the gas price refers to.
+    /// - `domain_type`: the type of the domain, e.g. `mainnet` or `testnet`.
     pub gas_price: Option<GaugeVec>,
 
     /// Boolean marker for critical errors on a chain, signalling loss of liveness.
+    /// - `chain`: the chain name (or chain ID if the name is unknown) of the
+    ///   chain the error occurred on.
+    /// - `domain_type`: the type of the domain, e.g. `mainnet` or `testnet`.
     critical_error: IntGaugeVec,
 }
 
 impl ChainMetrics {
-    pub(crate) fn new(metrics: &CoreMetrics) -> Result<ChainMetrics> {
+    /// Register the chain metrics with the registry of `metrics`
+    pub fn new(metrics: &CoreMetrics) -> Result<ChainMetrics> {
         let block_height_metrics =
             metrics.new_int_gauge("block_height", BLOCK_HEIGHT_HELP, BLOCK_HEIGHT_LABELS)?;
         let gas_price_metrics = metrics.new_gauge("gas_price", GAS_PRICE_HELP, GAS_PRICE_LABELS)?;
@@ -112,26 +122,33 @@ impl ChainMetrics {
         Ok(chain_metrics)
     }
 
-    pub(crate) fn set_gas_price(&self, chain: &str, price: f64) {
+    pub(crate) fn set_gas_price(&self, domain: &HyperlaneDomain, price: f64) {
         if let Some(gas_price) = &self.gas_price {
-            gas_price.with(&hashmap! { "chain" => chain }).set(price);
+            gas_price.with(&chain_labels(domain)).set(price);
         }
     }
 
-    pub(crate) fn set_block_height(&self, chain: &str, height: i64) {
-        self.block_height
-            .with(&hashmap! { "chain" => chain })
-            .set(height);
+    pub(crate) fn set_block_height(&self, domain: &HyperlaneDomain, height: i64) {
+        self.block_height.with(&chain_labels(domain)).set(height);
     }
 
     /// Flag that a critical error has occurred on the chain
-    pub fn set_critical_error(&self, chain: &str, is_critical: bool) {
+    pub fn set_critical_error(&self, domain: &HyperlaneDomain, is_critical: bool) {
         self.critical_error
-            .with(&hashmap! { "chain" => chain })
+            .with(&chain_labels(domain))
             .set(is_critical as i64);
     }
 }
 
+/// Labels of the chain metrics for `domain`, so that dashboards can aggregate
+/// them by the type of the domain as well
+fn chain_labels(domain: &HyperlaneDomain) -> HashMap<&str, &str> {
+    hashmap! {
+        "chain" => domain.name(),
+        "domain_type" => domain.domain_type().into(),
+    }
+}
+
 /// Configuration for the prometheus middleware. This can be loaded via serde.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "serde", derive(serde::Deserialize))]
@@ -188,8 +205,10 @@ impl MetricsUpdater {
 
         match self.provider.get_balance(wallet_addr.clone()).await {
             Ok(balance) => {
+                let NativeToken { symbol, decimals } = self.conf.domain.native_token();
+                let units = Balance::from(balance).format_units(decimals);
+                trace!("Wallet {wallet_name} ({wallet_addr}) on chain {chain} balance is {units} {symbol}");
                 let balance = u256_as_scaled_f64(balance, self.conf.domain.domain_protocol());
-                trace!("Wallet {wallet_name} ({wallet_addr}) on chain {chain} balance is {balance} of the native currency");
                 wallet_balance_metric
                 .with(&hashmap! {
                     "chain" => chain,
@@ -225,7 +244,8 @@ impl MetricsUpdater {
 
         let height = chain_metrics.latest_block.number as i64;
         trace!(chain, height, "Fetched block height for metrics");
-        self.chain_metrics.set_block_height(chain, height);
+        self.chain_metrics
+            .set_block_height(&self.conf.domain, height);
         if self.chain_metrics.gas_price.is_some() {
             let protocol = self.conf.domain.domain_protocol();
             let decimals_scale = 10f64.powf(decimals_by_protocol(protocol).into());
@@ -236,7 +256,7 @@ impl MetricsUpdater {
                 gas = format!("{gas:.2}"),
                 "Gas price updated for chain (using lowest denomination)"
             );
-            self.chain_metrics.set_gas_price(chain, gas);
+            self.chain_metrics.set_gas_price(&self.conf.domain, gas);
         }
     }
 
@@ -13,7 +13,11 @@ use prometheus::{
 };
 use tokio::sync::RwLock;
 
-use ethers_prometheus::{json_rpc_client::JsonRpcClientMetrics, middleware::MiddlewareMetrics};
+use ethers_prometheus::{
+    exemplar::{ExemplarHistogramVec, ExemplarRegistry},
+    json_rpc_client::JsonRpcClientMetrics,
+    middleware::MiddlewareMetrics,
+};
 
 use crate::metrics::{
     json_rpc_client::create_json_rpc_client_metrics, provider::create_provider_metrics,
@@ -26,10 +30,18 @@ macro_rules! namespaced {
     };
 }
 
+/// Buckets of the time from picking a message up to its delivery
+const MESSAGE_PICKUP_TO_DELIVERY_BUCKETS: &[f64] = &[
+    1.0, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1800.0, 3600.0, 21600.0,
+];
+
 /// Metrics for a particular domain
 pub struct CoreMetrics {
     /// Metrics registry for adding new metrics and gathering reports
     registry: Registry,
+    /// Histograms attaching the trace of some observations as exemplars,
+    /// which are served to scrapers accepting OpenMetrics
+    exemplars: ExemplarRegistry,
     const_labels: HashMap<String, String>,
     listen_port: u16,
     agent_name: String,
@@ -42,8 +54,27 @@ pub struct CoreMetrics {
 
     operations_processed_count: IntCounterVec,
     messages_processed_count: IntCounterVec,
+    message_submissions_by_path: IntCounterVec,
+    message_pickup_to_delivery: ExemplarHistogramVec,
+    throttled_message_sources: IntGaugeVec,
+    circuit_breaker_state: IntGaugeVec,
 
     latest_checkpoint: IntGaugeVec,
+    verified_checkpoint_indices: IntCounterVec,
+
+    merkle_tree_availability: IntGaugeVec,
+    merkle_tree_capacity_used: GaugeVec,
+
+    contract_client_cache_size: IntGaugeVec,
+    contract_client_constructions: IntCounterVec,
+
+    validator_reputation_score: GaugeVec,
+
+    alert_sink_failures: IntCounterVec,
+
+    db_corrupted_records: IntCounterVec,
+
+    agent_info: IntGaugeVec,
 
     /// Set of metrics that tightly wrap the JsonRpcClient for use with the
     /// quorum provider.
@@ -147,6 +178,116 @@ impl CoreMetrics {
             registry
         )?;
 
+        let verified_checkpoint_indices = register_int_counter_vec_with_registry!(
+            opts!(
+                namespaced!("verified_checkpoint_indices"),
+                "Number of checkpoint indices a verifying validator compared against the onchain root",
+                const_labels_ref
+            ),
+            &["chain", "outcome"],
+            registry
+        )?;
+
+        let merkle_tree_availability = register_int_gauge_vec_with_registry!(
+            opts!(
+                namespaced!("merkle_tree_availability"),
+                "Availability of the origin merkle tree: 0 = ready, 1 = rebuilding, 2 = diverged, 3 = full",
+                const_labels_ref
+            ),
+            &["origin"],
+            registry
+        )?;
+
+        let merkle_tree_capacity_used = register_gauge_vec_with_registry!(
+            opts!(
+                namespaced!("merkle_tree_capacity_used"),
+                "Fraction of the leaves its depth allows that the origin merkle tree holds",
+                const_labels_ref
+            ),
+            &["origin"],
+            registry
+        )?;
+
+        let contract_client_cache_size = register_int_gauge_vec_with_registry!(
+            opts!(
+                namespaced!("contract_client_cache_size"),
+                "Number of contract clients held by the contract client cache",
+                const_labels_ref
+            ),
+            &["chain"],
+            registry
+        )?;
+
+        let contract_client_constructions = register_int_counter_vec_with_registry!(
+            opts!(
+                namespaced!("contract_client_constructions"),
+                "Number of contract clients constructed by the contract client cache",
+                const_labels_ref
+            ),
+            &["chain", "client"],
+            registry
+        )?;
+
+        let validator_reputation_score = register_gauge_vec_with_registry!(
+            opts!(
+                namespaced!("validator_reputation_score"),
+                "Reputation of a validator's checkpoint storage, between 0 and 1",
+                const_labels_ref
+            ),
+            &["origin", "validator"],
+            registry
+        )?;
+
+        let alert_sink_failures = register_int_counter_vec_with_registry!(
+            opts!(
+                namespaced!("alert_sink_failures"),
+                "Number of alerts an alert sink failed to accept",
+                const_labels_ref
+            ),
+            &["sink"],
+            registry
+        )?;
+
+        let db_corrupted_records = register_int_counter_vec_with_registry!(
+            opts!(
+                namespaced!("db_corrupted_records"),
+                "Number of corrupted db records skipped by scans",
+                const_labels_ref
+            ),
+            &["origin", "scan"],
+            registry
+        )?;
+
+        let throttled_message_sources = register_int_gauge_vec_with_registry!(
+            opts!(
+                namespaced!("throttled_message_sources"),
+                "Senders and routes whose messages are deprioritized for bursting, set to 1 while throttled",
+                const_labels_ref
+            ),
+            &["origin", "kind", "id"],
+            registry
+        )?;
+
+        let circuit_breaker_state = register_int_gauge_vec_with_registry!(
+            opts!(
+                namespaced!("circuit_breaker_state"),
+                "State of the circuit breaker guarding submissions to a destination: 0 closed, 1 half-open, 2 open",
+                const_labels_ref
+            ),
+            &["destination"],
+            registry
+        )?;
+
+        let agent_info = register_int_gauge_vec_with_registry!(
+            opts!(
+                namespaced!("agent_info"),
+                "Always 1, labelled with information about the running agent",
+                const_labels_ref
+            ),
+            &["config_hash"],
+            registry
+        )?;
+
         let operations_processed_count = register_int_counter_vec_with_registry!(
             opts!(
                 namespaced!("operations_processed_count"),
@@ -167,9 +308,35 @@ impl CoreMetrics {
             registry
         )?;
 
+        let message_submissions_by_path = register_int_counter_vec_with_registry!(
+            opts!(
+                namespaced!("message_submissions_by_path"),
+                "Number of message process transactions included, by how they were broadcast",
+                const_labels_ref
+            ),
+            &["origin", "remote", "path"],
+            registry
+        )?;
+
+        let exemplars = ExemplarRegistry::default();
+        let message_pickup_to_delivery = exemplars.register(
+            register_histogram_vec_with_registry!(
+                histogram_opts!(
+                    namespaced!("message_pickup_to_delivery_seconds"),
+                    "Time from this relayer process picking a message up to its delivery being confirmed",
+                    MESSAGE_PICKUP_TO_DELIVERY_BUCKETS.to_vec(),
+                    const_labels.clone()
+                ),
+                &["origin", "remote"],
+                registry
+            )?,
+            MESSAGE_PICKUP_TO_DELIVERY_BUCKETS,
+        );
+
         Ok(Self {
             agent_name: for_agent.into(),
             registry,
+            exemplars,
             listen_port,
             const_labels,
 
@@ -182,8 +349,27 @@ impl CoreMetrics {
 
             operations_processed_count,
             messages_processed_count,
+            message_submissions_by_path,
+            message_pickup_to_delivery,
+            throttled_message_sources,
+            circuit_breaker_state,
 
             latest_checkpoint,
+            verified_checkpoint_indices,
+
+            merkle_tree_availability,
+            merkle_tree_capacity_used,
+
+            contract_client_cache_size,
+            contract_client_constructions,
+
+            validator_reputation_score,
+
+            alert_sink_failures,
+
+            db_corrupted_records,
+
+            agent_info,
 
             json_rpc_client_metrics: OnceLock::new(),
             provider_metrics: OnceLock::new(),
@@ -284,6 +470,25 @@ impl CoreMetrics {
         )?)
     }
 
+    /// Create and register a new histogram attaching the trace of a sample of
+    /// its observations as exemplars.
+    pub fn new_exemplar_histogram(
+        &self,
+        metric_name: &str,
+        help: &str,
+        labels: &[&str],
+        buckets: &[f64],
+    ) -> Result<ExemplarHistogramVec> {
+        let histogram = self.new_histogram(metric_name, help, labels, buckets.to_vec())?;
+        Ok(self.exemplars.register(histogram, buckets))
+    }
+
+    /// Set the share of observations the exemplar histograms attach the
+    /// trace of, between 0 and 1.
+    pub fn set_exemplar_sample_rate(&self, sample_rate: f64) {
+        self.exemplars.set_sample_rate(sample_rate);
+    }
+
     /// Reports the current highest message nonce at multiple phases of the
     /// relaying process. There may be messages that have not reached a certain
     /// stage, such as being fully processed, even if the reported nonce is
@@ -319,6 +524,118 @@ impl CoreMetrics {
         self.latest_checkpoint.clone()
     }
 
+    /// Checkpoint indices a validator running in verify mode compared
+    /// against the onchain merkle root.
+    ///
+    /// Labels:
+    /// - `chain`: Chain the checkpoints are for.
+    /// - `outcome`: `matched` if the local root agreed with the onchain one,
+    ///   `mismatched` otherwise.
+    pub fn verified_checkpoint_indices(&self) -> IntCounterVec {
+        self.verified_checkpoint_indices.clone()
+    }
+
+    /// Availability of the merkle tree the relayer builds for each origin.
+    ///
+    /// Values:
+    /// - `0`: ready, proofs can be generated.
+    /// - `1`: rebuilding from indexed insertions.
+    /// - `2`: diverged, e.g. a leaf is missing or roots mismatched.
+    /// - `3`: full, no more leaves fit but existing ones can be proven.
+    pub fn merkle_tree_availability(&self) -> IntGaugeVec {
+        self.merkle_tree_availability.clone()
+    }
+
+    /// Fraction of the leaves its depth allows that the merkle tree the
+    /// relayer builds for each origin holds, from 0 to 1.
+    ///
+    /// Labels:
+    /// - `origin`: Origin chain of the tree.
+    pub fn merkle_tree_capacity_used(&self) -> GaugeVec {
+        self.merkle_tree_capacity_used.clone()
+    }
+
+    /// Number of contract clients held by the contract client cache.
+    ///
+    /// Labels:
+    /// - `chain`: Chain the clients are for.
+    pub fn contract_client_cache_size(&self) -> IntGaugeVec {
+        self.contract_client_cache_size.clone()
+    }
+
+    /// Number of contract clients constructed by the contract client cache.
+    /// Grows only on cache misses, e.g. first use or after a settings reload.
+    ///
+    /// Labels:
+    /// - `chain`: Chain the client is for.
+    /// - `client`: Kind of client, e.g. `mailbox` or `multisig_ism`.
+    pub fn contract_client_constructions(&self) -> IntCounterVec {
+        self.contract_client_constructions.clone()
+    }
+
+    /// Reputation of each validator's checkpoint storage, used to order
+    /// validators when assembling a quorum. Between 0 and 1, higher is better.
+    ///
+    /// Labels:
+    /// - `origin`: Origin chain the validator signs checkpoints for.
+    /// - `validator`: Address of the validator.
+    pub fn validator_reputation_score(&self) -> GaugeVec {
+        self.validator_reputation_score.clone()
+    }
+
+    /// Number of alerts an alert sink failed to accept, because it errored,
+    /// timed out or couldn't be reached.
+    ///
+    /// Labels:
+    /// - `sink`: Kind of sink, e.g. `webhook` or `pager_duty`.
+    pub fn alert_sink_failures(&self) -> IntCounterVec {
+        self.alert_sink_failures.clone()
+    }
+
+    /// Number of corrupted db records skipped by scans.
+    ///
+    /// Labels:
+    /// - `origin`: Origin chain the records belong to.
+    /// - `scan`: The scan that skipped them, e.g. `message_processor`.
+    pub fn db_corrupted_records(&self) -> IntCounterVec {
+        self.db_corrupted_records.clone()
+    }
+
+    /// Senders and routes whose messages are deprioritized for bursting, set
+    /// to 1 while throttled.
+    ///
+    /// Labels:
+    /// - `origin`: Origin chain the messages are dispatched on.
+    /// - `kind`: `sender`, or `route` for a recipient on a destination.
+    /// - `id`: The sender, or the destination domain and recipient.
+    pub fn throttled_message_sources(&self) -> IntGaugeVec {
+        self.throttled_message_sources.clone()
+    }
+
+    /// State of the circuit breaker guarding submissions to a destination:
+    /// 0 while closed, 1 while letting a canary through, 2 while open.
+    ///
+    /// Labels:
+    /// - `destination`: Destination chain the breaker guards.
+    pub fn circuit_breaker_state(&self) -> IntGaugeVec {
+        self.circuit_breaker_state.clone()
+    }
+
+    /// Information about the running agent, always set to 1
+    ///
+    /// Labels:
+    /// - `config_hash`: Hash of the redacted config the agent is running with.
+    pub fn agent_info(&self) -> IntGaugeVec {
+        self.agent_info.clone()
+    }
+
+    /// Expose the hash of the config the agent is running with, replacing
+    /// the one exposed before, e.g. after a settings reload
+    pub fn set_config_hash(&self, config_hash: &str) {
+        self.agent_info.reset();
+        self.agent_info.with_label_values(&[config_hash]).set(1);
+    }
+
     /// Measure of the queue lengths in Submitter instances
     ///
     /// Labels:
@@ -372,6 +689,30 @@ impl CoreMetrics {
         self.messages_processed_count.clone()
     }
 
+    /// Number of message process transactions included, by how they were
+    /// broadcast.
+    ///
+    /// Labels:
+    /// - `origin`: Chain the message came from.
+    /// - `remote`: Chain we delivered the message to.
+    /// - `path`: How the transaction was broadcast, one of `public`, `private`
+    ///   or `public_fallback`.
+    pub fn message_submissions_by_path(&self) -> IntCounterVec {
+        self.message_submissions_by_path.clone()
+    }
+
+    /// Time from this relayer process picking a message up to its delivery
+    /// being confirmed, including every retry. A message picked up again after
+    /// a restart is timed from the restart. The time taken to index the
+    /// message is left out, being timed as a stage of the pipeline.
+    ///
+    /// Labels:
+    /// - `origin`: Chain the message came from.
+    /// - `remote`: Chain we delivered the message to.
+    pub fn message_pickup_to_delivery(&self) -> ExemplarHistogramVec {
+        self.message_pickup_to_delivery.clone()
+    }
+
     /// Measure of span durations provided by tracing.
     ///
     /// Labels:
@@ -414,6 +755,27 @@ impl CoreMetrics {
         Ok(out_buf)
     }
 
+    /// Gather available metrics into an encoded report in the OpenMetrics
+    /// text format, with the exemplars of the histograms attaching them.
+    pub fn gather_openmetrics(&self) -> prometheus::Result<Vec<u8>> {
+        // the exemplar histograms are encoded with their exemplars instead
+        let collected_metrics = self
+            .registry
+            .gather()
+            .into_iter()
+            .filter(|family| !self.exemplars.contains(family.get_name()))
+            .collect::<Vec<_>>();
+        let mut out_buf = Vec::with_capacity(1024 * 64);
+        let encoder = prometheus::TextEncoder::new();
+        encoder.encode(&collected_metrics, &mut out_buf)?;
+        let mut exemplars = String::new();
+        self.exemplars
+            .encode(&mut exemplars)
+            .map_err(|err| prometheus::Error::Msg(err.to_string()))?;
+        out_buf.extend(exemplars.into_bytes());
+        Ok(out_buf)
+    }
+
     /// Get the name of this agent, e.g. "relayer"
     pub fn agent_name(&self) -> &str {
         &self.agent_name
@@ -17,5 +17,11 @@ pub(crate) fn create_json_rpc_client_metrics(
             REQUEST_DURATION_SECONDS_HELP,
             REQUEST_DURATION_SECONDS_LABELS,
         )?)
+        .request_latency_seconds(metrics.new_exemplar_histogram(
+            "request_latency_seconds",
+            REQUEST_LATENCY_SECONDS_HELP,
+            REQUEST_LATENCY_SECONDS_LABELS,
+            REQUEST_LATENCY_SECONDS_BUCKETS,
+        )?)
         .build()?)
 }
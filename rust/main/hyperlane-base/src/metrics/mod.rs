@@ -1,6 +1,7 @@
 //! Useful metrics that all agents should track.
 
 pub use self::core::*;
+pub use ethers_prometheus::exemplar::{ExemplarHistogram, ExemplarHistogramVec, ExemplarRegistry};
 
 /// The metrics namespace prefix. All metric names will start with `{NAMESPACE}_`.
 pub const NAMESPACE: &str = "hyperlane";
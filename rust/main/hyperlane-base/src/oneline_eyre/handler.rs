@@ -5,6 +5,8 @@ use std::{env, fmt, iter};
 use backtrace::Backtrace;
 use eyre::EyreHandler;
 
+use crate::SpanContext;
+
 /// The default separator used to delimitate lines in error messages.
 const DEFAULT_LINE_SEPARATOR: &str = " ## ";
 /// The default separator used to delimitate error sections.
@@ -15,6 +17,7 @@ pub struct Handler {
     line_separator: &'static str,
     section_separator: &'static str,
     backtrace: Option<Backtrace>,
+    span_context: SpanContext,
     show_full_paths: bool,
 }
 
@@ -40,6 +43,15 @@ impl Handler {
         Ok(())
     }
 
+    /// Format the context fields of the spans the error was created in.
+    fn fmt_span_context(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}Span context: {}",
+            self.section_separator, self.span_context
+        )
+    }
+
     /// Format a backtrace onto a single line.
     fn fmt_backtrace(&self, backtrace: &Backtrace, f: &mut Formatter<'_>) -> fmt::Result {
         write!(f, "{}Stack backtrace:", self.section_separator)?;
@@ -59,6 +71,9 @@ impl EyreHandler for Handler {
         if let Some(cause) = error.source() {
             self.fmt_cause(cause, f)?;
         }
+        if !self.span_context.is_empty() {
+            self.fmt_span_context(f)?;
+        }
         if let Some(backtrace) = &self.backtrace {
             self.fmt_backtrace(backtrace, f)?;
         }
@@ -100,6 +115,7 @@ impl HookBuilder {
 
         Handler {
             backtrace,
+            span_context: SpanContext::capture(),
             line_separator: self.line_separator.unwrap_or(DEFAULT_LINE_SEPARATOR),
             section_separator: self.section_separator.unwrap_or(DEFAULT_SECTION_SEPARATOR),
             show_full_paths: self.show_full_paths,
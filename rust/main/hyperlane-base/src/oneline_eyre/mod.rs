@@ -1,6 +1,7 @@
 //! This provides a custom [`eyre::EyreHandler`] type for usage with [`eyre`] that provides
-//! a minimal error report with no additional context. Essentially the minimal implementation of an
-//! error reporter.
+//! a minimal error report with no additional context beyond the chain and message fields of the
+//! tracing spans the error was created in. Essentially the minimal implementation of an error
+//! reporter.
 //!
 //! ## Setup
 //!
@@ -1,9 +1,22 @@
 use crate::CoreMetrics;
-use axum::{http::StatusCode, response::IntoResponse, routing::get, Router};
+use axum::{
+    http::{
+        header::{ACCEPT, CONTENT_TYPE},
+        HeaderMap, StatusCode,
+    },
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
 use derive_new::new;
 use std::{net::SocketAddr, sync::Arc};
 use tokio::task::JoinHandle;
 
+/// Media type scrapers accepting the OpenMetrics text format ask for
+const OPENMETRICS_MEDIA_TYPE: &str = "application/openmetrics-text";
+/// Content type of reports in the OpenMetrics text format
+const OPENMETRICS_CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
 /// A server that serves agent-specific routes
 #[derive(new, Debug)]
 pub struct Server {
@@ -21,7 +34,8 @@ impl Server {
     ///
     /// routes:
     ///  - metrics - serving OpenMetrics format reports on `/metrics`
-    ///     (this is compatible with Prometheus, which ought to be configured to scrape this endpoint)
+    ///     (this is compatible with Prometheus, which ought to be configured to scrape this endpoint).
+    ///     Exemplars are only served to scrapers accepting `application/openmetrics-text`
     ///  - custom_routes - additional routes to be served by the server as per the specific agent
     pub fn run_with_custom_routes(
         self: Arc<Self>,
@@ -34,7 +48,7 @@ impl Server {
 
         let mut app = Router::new().route(
             "/metrics",
-            get(move || Self::gather_metrics(core_metrics_clone)),
+            get(move |headers| Self::gather_metrics(core_metrics_clone, headers)),
         );
 
         for (route, router) in custom_routes {
@@ -51,34 +65,54 @@ impl Server {
     }
 
     /// Gather available metrics into an encoded (plaintext, OpenMetrics format)
-    /// report.
-    async fn gather_metrics(core_metrics: Arc<CoreMetrics>) -> impl IntoResponse {
+    /// report, with exemplars if the scraper accepts the OpenMetrics format.
+    async fn gather_metrics(core_metrics: Arc<CoreMetrics>, headers: HeaderMap) -> Response {
         tracing::debug!("Traversing route for /metrics endpoint for serving Prometheus metrics");
-        match core_metrics.gather() {
+        let openmetrics = headers
+            .get(ACCEPT)
+            .and_then(|accept| accept.to_str().ok())
+            .is_some_and(|accept| accept.contains(OPENMETRICS_MEDIA_TYPE));
+        let gathered = if openmetrics {
+            core_metrics.gather_openmetrics()
+        } else {
+            core_metrics.gather()
+        };
+        match gathered {
             Ok(metrics) => {
                 let metrics = match String::from_utf8(metrics) {
                     Ok(metrics_string) => metrics_string,
                     Err(_) => {
-                        return (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            "Internal Server Error".into(),
-                        )
+                        return (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error")
+                            .into_response()
                     }
                 };
-                (StatusCode::OK, metrics)
+                if openmetrics {
+                    (
+                        StatusCode::OK,
+                        [(CONTENT_TYPE, OPENMETRICS_CONTENT_TYPE)],
+                        metrics,
+                    )
+                        .into_response()
+                } else {
+                    (StatusCode::OK, metrics).into_response()
+                }
             }
             Err(_) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                "Failed to gather metrics".into(),
-            ),
+                "Failed to gather metrics",
+            )
+                .into_response(),
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use ethers_prometheus::exemplar::current_trace_id;
+    use opentelemetry::trace::TracerProvider as _;
     use prometheus::{Counter, Registry};
     use reqwest;
+    use tracing_subscriber::layer::SubscriberExt;
 
     use super::*;
 
@@ -112,4 +146,88 @@ mod tests {
         let body = response.text().await.expect("Failed to read response body");
         assert!(body.contains("expected_metric_content"));
     }
+
+    #[tokio::test]
+    async fn test_metrics_endpoint_serves_exemplars_with_openmetrics() {
+        let core_metrics = Arc::new(CoreMetrics::new("test", 8081, Registry::new()).unwrap());
+        core_metrics.set_exemplar_sample_rate(1.0);
+        let delivery = |remote, seconds| {
+            core_metrics
+                .message_pickup_to_delivery()
+                .with_label_values(&["test1", remote])
+                .observe(seconds)
+        };
+
+        // each delivery is traced in its own root span
+        let provider = opentelemetry_sdk::trace::TracerProvider::builder().build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+        let trace_ids = tracing::subscriber::with_default(subscriber, || {
+            [2.0, 20.0].map(|seconds| {
+                let span = tracing::info_span!("deliver");
+                let _guard = span.enter();
+                delivery("test2", seconds);
+                current_trace_id().unwrap().to_string()
+            })
+        });
+        // not traced
+        delivery("test3", 2.0);
+
+        let server = Arc::new(Server::new(8081, core_metrics.clone()));
+        let _server_task = tokio::spawn(async move {
+            server.run().await.unwrap();
+        });
+        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
+
+        let client = reqwest::Client::new();
+        let response = client
+            .get("http://127.0.0.1:8081/metrics")
+            .header(
+                ACCEPT,
+                "application/openmetrics-text;version=1.0.0,text/plain;q=0.5",
+            )
+            .send()
+            .await
+            .expect("Failed to send request");
+        assert!(response.status().is_success());
+        assert_eq!(
+            response.headers()[CONTENT_TYPE.as_str()],
+            OPENMETRICS_CONTENT_TYPE
+        );
+        let body = response.text().await.expect("Failed to read response body");
+        assert!(body.ends_with("# EOF\n"), "{body}");
+        let name = "hyperlane_message_pickup_to_delivery_seconds";
+        assert_eq!(body.matches(&format!("# TYPE {name} ")).count(), 1);
+        let bucket = |le: &str, remote: &str| {
+            body.lines()
+                .find(|line| {
+                    line.starts_with(&format!("{name}_bucket{{le=\"{le}\""))
+                        && line.contains(&format!("remote=\"{remote}\""))
+                })
+                .unwrap()
+        };
+        for (trace_id, (le, seconds)) in trace_ids.iter().zip([("5.0", 2.0), ("30.0", 20.0)]) {
+            assert_eq!(trace_id.len(), 32);
+            assert!(trace_id.chars().all(|c| c.is_ascii_hexdigit()));
+            assert!(
+                bucket(le, "test2")
+                    .ends_with(&format!(" # {{trace_id=\"{trace_id}\"}} {seconds:?}")),
+                "{body}"
+            );
+        }
+        assert!(!bucket("5.0", "test3").contains('#'), "{body}");
+
+        // scrapers of the classic text format get the same report as before
+        let body = client
+            .get("http://127.0.0.1:8081/metrics")
+            .send()
+            .await
+            .expect("Failed to send request")
+            .text()
+            .await
+            .expect("Failed to read response body");
+        assert!(body.contains(&format!("{name}_bucket{{")));
+        assert!(!body.contains("trace_id"));
+        assert!(!body.contains("# EOF"));
+    }
 }
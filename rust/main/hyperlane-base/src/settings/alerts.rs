@@ -0,0 +1,82 @@
+use std::time::Duration;
+
+use url::Url;
+
+use crate::{AlertDispatcher, CoreMetrics};
+
+/// PagerDuty Events API v2 endpoint, used unless a sink overrides it
+pub const PAGER_DUTY_EVENTS_URL: &str = "https://events.pagerduty.com/v2/enqueue";
+
+/// How long to wait before sending an alert with the same dedup key again,
+/// unless configured otherwise
+const DEFAULT_RATE_LIMIT: Duration = Duration::from_secs(10 * 60);
+
+/// Where critical events are pushed to as soon as they happen, on top of
+/// being logged and exported as metrics
+#[derive(Debug, Clone)]
+pub struct AlertConf {
+    /// Sinks every alert is sent to
+    pub sinks: Vec<AlertSinkConf>,
+    /// How long to wait before sending an alert with the same dedup key again
+    pub rate_limit: Duration,
+}
+
+impl Default for AlertConf {
+    fn default() -> Self {
+        Self {
+            sinks: vec![],
+            rate_limit: DEFAULT_RATE_LIMIT,
+        }
+    }
+}
+
+impl AlertConf {
+    /// Build a dispatcher sending alerts to the configured sinks
+    pub fn build(&self, metrics: &CoreMetrics) -> AlertDispatcher {
+        AlertDispatcher::new(self, metrics.alert_sink_failures())
+    }
+}
+
+/// A destination for alerts
+#[derive(Debug, Clone)]
+pub enum AlertSinkConf {
+    /// Any endpoint accepting a JSON body
+    Webhook {
+        /// Endpoint alerts are posted to
+        url: Url,
+        /// JSON body with `{{kind}}`, `{{dedup_key}}`, `{{source}}`,
+        /// `{{summary}}` and `{{details}}` placeholders. The alert itself is
+        /// posted if unset.
+        body_template: Option<String>,
+    },
+    /// A Slack-compatible incoming webhook
+    Slack {
+        /// Incoming webhook url
+        url: Url,
+    },
+    /// PagerDuty Events API v2
+    PagerDuty {
+        /// Events API endpoint
+        url: Url,
+        /// Integration key of the service to trigger incidents on
+        routing_key: String,
+    },
+}
+
+impl AlertSinkConf {
+    /// Name of the kind of sink, used in logs and metrics
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Webhook { .. } => "webhook",
+            Self::Slack { .. } => "slack",
+            Self::PagerDuty { .. } => "pager_duty",
+        }
+    }
+
+    /// Endpoint alerts are posted to
+    pub fn url(&self) -> &Url {
+        match self {
+            Self::Webhook { url, .. } | Self::Slack { url } | Self::PagerDuty { url, .. } => url,
+        }
+    }
+}
@@ -1,16 +1,30 @@
-use std::{collections::HashMap, fmt::Debug, hash::Hash, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Debug,
+    hash::Hash,
+    sync::Arc,
+};
 
 use eyre::{eyre, Context, Result};
 use futures_util::future::try_join_all;
+use hyperlane_core::utils::{closest_names, fmt_did_you_mean};
 use hyperlane_core::{
-    HyperlaneChain, HyperlaneDomain, HyperlaneLogStore, HyperlaneProvider,
+    DomainRegistry, HyperlaneChain, HyperlaneDomain, HyperlaneLogStore, HyperlaneProvider,
     HyperlaneSequenceAwareIndexerStoreReader, HyperlaneWatermarkedLogStore, InterchainGasPaymaster,
-    Mailbox, MerkleTreeHook, MultisigIsm, SequenceAwareIndexer, ValidatorAnnounce, H256,
+    InterchainGasPayment, KnownHyperlaneDomain, Mailbox, MerkleTreeHook, MultisigIsm,
+    SequenceAwareIndexer, ValidatorAnnounce, H256,
 };
+use itertools::Itertools;
+use tracing::warn;
 
 use crate::{
     cursors::{CursorType, Indexable},
-    settings::{chains::ChainConf, trace::TracingConfig},
+    db::{HyperlaneRocksDB, IgpContractStore},
+    settings::{
+        chains::{ChainConf, IndexSettings},
+        trace::TracingConfig,
+        AlertConf, ConfigFingerprint,
+    },
     ContractSync, ContractSyncMetrics, ContractSyncer, CoreMetrics, HyperlaneAgentCore,
     SequenceAwareLogStore, SequencedDataContractSync, Server, WatermarkContractSync,
     WatermarkLogStore,
@@ -44,12 +58,29 @@ use super::TryFromWithMetrics;
 /// ```
 #[derive(Debug, Default)]
 pub struct Settings {
+    /// The environment the config is for, if it declares one
+    pub environment: Option<String>,
     /// Configuration for contracts on each chain
     pub chains: HashMap<String, ChainConf>,
+    /// The known domains and those of the configured chains
+    pub domains: DomainRegistry,
     /// Port to listen for prometheus scrape requests
     pub metrics_port: u16,
+    /// Share of the observations of the delivery pipeline histograms the
+    /// trace is attached to as an exemplar
+    pub exemplar_sample_rate: f64,
     /// The tracing configuration
     pub tracing: TracingConfig,
+    /// Where critical events are pushed to
+    pub alerts: AlertConf,
+    /// Fingerprint of the redacted raw config these settings were parsed from
+    pub config_fingerprint: ConfigFingerprint,
+    /// Deprecated domain names chain entries were configured with, and the
+    /// domains they resolved to
+    pub deprecated_domain_names: BTreeMap<String, KnownHyperlaneDomain>,
+    /// Fields set to a deprecated bare number, by their json name, and the
+    /// units they were read in
+    pub bare_numbers: BTreeMap<String, &'static str>,
 }
 
 impl Settings {
@@ -81,21 +112,88 @@ impl Settings {
             .ok_or_else(|| eyre!("No chain setup found for {domain}"))
     }
 
+    /// The chain configurations keyed by their domain rather than their name
+    pub fn chains_by_domain(&self) -> HashMap<HyperlaneDomain, &ChainConf> {
+        self.chains
+            .values()
+            .map(|chain| (chain.domain.clone(), chain))
+            .collect()
+    }
+
+    /// Warn about each chain which isn't a known domain, whose name and
+    /// domain id couldn't be checked against each other when loading. Call
+    /// once tracing is up.
+    pub fn warn_unknown_domains(&self) {
+        let unknown = self
+            .chains
+            .iter()
+            .filter(|(_, chain)| matches!(chain.domain, HyperlaneDomain::Unknown { .. }))
+            .sorted_by_key(|(name, _)| *name);
+        for (name, chain) in unknown {
+            warn!(
+                chain = name,
+                domain = chain.domain.id(),
+                "Chain isn't a known domain, so its name and domain id weren't checked against each other"
+            );
+        }
+    }
+
+    /// Warn about each chain entry configured with a deprecated domain name,
+    /// returning the deprecated names. Call once tracing is up.
+    pub fn warn_deprecated_domain_names(&self) -> Vec<String> {
+        self.deprecated_domain_names
+            .iter()
+            .map(|(name, domain)| {
+                warn!(
+                    chain = %name,
+                    %domain,
+                    "Chain is configured with a deprecated domain name; it's loaded as `{domain}`, rename it before support is dropped"
+                );
+                name.clone()
+            })
+            .collect()
+    }
+
+    /// Warn about each field set to a bare number, returning their names. Call
+    /// once tracing is up.
+    pub fn warn_bare_numbers(&self) -> Vec<String> {
+        self.bare_numbers
+            .iter()
+            .map(|(field, unit)| {
+                warn!(
+                    field = %field,
+                    unit,
+                    "Deprecated bare number in the settings, read in {unit}; give it a unit instead, e.g. `30s` or `10mb`"
+                );
+                field.clone()
+            })
+            .collect()
+    }
+
     /// Try to get the domain for a given chain by name.
+    /// Chains configured under a deprecated domain name are found by it too.
     pub fn lookup_domain(&self, chain_name: &str) -> Result<HyperlaneDomain> {
         self.chains
             .get(chain_name)
-            .ok_or_else(|| eyre!("No chain setup found for {chain_name}"))
+            .or_else(|| {
+                KnownHyperlaneDomain::from_deprecated_name(chain_name)
+                    .and_then(|domain| self.chains.get(domain.as_str()))
+            })
+            .ok_or_else(|| {
+                let closest = closest_names(chain_name, self.chains.keys().map(String::as_str));
+                eyre!(
+                    "No chain setup found for {chain_name}{}",
+                    fmt_did_you_mean(&closest)
+                )
+            })
             .map(|c| c.domain.clone())
     }
 
     /// Create the core metrics from the settings given the name of the agent.
     pub fn metrics(&self, name: &str) -> Result<Arc<CoreMetrics>> {
-        Ok(Arc::new(CoreMetrics::new(
-            name,
-            self.metrics_port,
-            prometheus::Registry::new(),
-        )?))
+        let metrics = CoreMetrics::new(name, self.metrics_port, prometheus::Registry::new())?;
+        metrics.set_exemplar_sample_rate(self.exemplar_sample_rate);
+        Ok(Arc::new(metrics))
     }
 
     /// Create the server from the settings given the name of the agent.
@@ -107,9 +205,16 @@ impl Settings {
     /// agent consumes the settings.
     fn clone(&self) -> Self {
         Self {
+            environment: self.environment.clone(),
             chains: self.chains.clone(),
+            domains: self.domains.clone(),
             metrics_port: self.metrics_port,
+            exemplar_sample_rate: self.exemplar_sample_rate,
             tracing: self.tracing.clone(),
+            alerts: self.alerts.clone(),
+            config_fingerprint: self.config_fingerprint.clone(),
+            deprecated_domain_names: self.deprecated_domain_names.clone(),
+            bare_numbers: self.bare_numbers.clone(),
         }
     }
 }
@@ -144,6 +249,19 @@ macro_rules! build_contract_fns {
 
 type SequenceIndexer<T> = Arc<dyn SequenceAwareIndexer<T>>;
 
+/// Gas payment sync for one of a chain's IGP contracts
+#[derive(Clone)]
+pub struct IgpContractSync {
+    /// Address of the IGP contract
+    pub address: H256,
+    /// Whether this is the chain's configured IGP rather than an additional one
+    pub configured: bool,
+    /// Settings to index the contract with
+    pub index_settings: IndexSettings,
+    /// The contract's sync
+    pub sync: Arc<dyn ContractSyncer<InterchainGasPayment>>,
+}
+
 impl Settings {
     build_contract_fns!(build_interchain_gas_paymaster, build_interchain_gas_paymasters -> dyn InterchainGasPaymaster);
     build_contract_fns!(build_mailbox, build_mailboxes -> dyn Mailbox);
@@ -220,23 +338,17 @@ impl Settings {
         // TODO: parallelize these calls again
         let mut syncs = vec![];
         for domain in domains {
+            let db = dbs
+                .get(domain)
+                .ok_or_else(|| eyre!("No database for domain {domain}"))?
+                .clone();
             let sync = match T::indexing_cursor(domain.domain_protocol()) {
                 CursorType::SequenceAware => self
-                    .sequenced_contract_sync(
-                        domain,
-                        metrics,
-                        sync_metrics,
-                        dbs.get(domain).unwrap().clone(),
-                    )
+                    .sequenced_contract_sync(domain, metrics, sync_metrics, db)
                     .await
                     .map(|r| r as Arc<dyn ContractSyncer<T>>)?,
                 CursorType::RateLimited => self
-                    .watermark_contract_sync(
-                        domain,
-                        metrics,
-                        sync_metrics,
-                        dbs.get(domain).unwrap().clone(),
-                    )
+                    .watermark_contract_sync(domain, metrics, sync_metrics, db)
                     .await
                     .map(|r| r as Arc<dyn ContractSyncer<T>>)?,
             };
@@ -248,4 +360,60 @@ impl Settings {
             .map(|i| Ok((i.domain().clone(), i)))
             .collect()
     }
+
+    /// Build a gas payment sync for every IGP contract of each domain: the
+    /// configured one first, followed by any additional ones
+    pub async fn igp_contract_syncs(
+        &self,
+        domains: impl Iterator<Item = &HyperlaneDomain>,
+        metrics: &CoreMetrics,
+        sync_metrics: &ContractSyncMetrics,
+        dbs: &HashMap<HyperlaneDomain, HyperlaneRocksDB>,
+    ) -> Result<HashMap<HyperlaneDomain, Vec<IgpContractSync>>> {
+        let mut syncs = HashMap::new();
+        for domain in domains {
+            let db = dbs
+                .get(domain)
+                .ok_or_else(|| eyre!("No db found for {domain}"))?;
+            let mut domain_syncs = vec![];
+            for (i, setup) in self
+                .chain_setup(domain)?
+                .igp_index_confs()
+                .iter()
+                .enumerate()
+            {
+                let address = setup.addresses.interchain_gas_paymaster;
+                let store = Arc::new(match i {
+                    0 => IgpContractStore::configured(db.clone(), address),
+                    _ => IgpContractStore::additional(db.clone(), address),
+                });
+                let indexer =
+                    SequenceIndexer::<InterchainGasPayment>::try_from_with_metrics(setup, metrics)
+                        .await?;
+                let sync: Arc<dyn ContractSyncer<InterchainGasPayment>> =
+                    match InterchainGasPayment::indexing_cursor(domain.domain_protocol()) {
+                        CursorType::SequenceAware => Arc::new(ContractSync::new(
+                            domain.clone(),
+                            store as SequenceAwareLogStore<_>,
+                            indexer,
+                            sync_metrics.clone(),
+                        )),
+                        CursorType::RateLimited => Arc::new(ContractSync::new(
+                            domain.clone(),
+                            store as WatermarkLogStore<_>,
+                            indexer,
+                            sync_metrics.clone(),
+                        )),
+                    };
+                domain_syncs.push(IgpContractSync {
+                    address,
+                    configured: i == 0,
+                    index_settings: setup.index_settings(),
+                    sync,
+                });
+            }
+            syncs.insert(domain.clone(), domain_syncs);
+        }
+        Ok(syncs)
+    }
 }
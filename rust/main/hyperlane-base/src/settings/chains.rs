@@ -23,6 +23,7 @@ use hyperlane_sealevel as h_sealevel;
 
 use crate::{
     metrics::AgentMetricsConf,
+    pacing::RebuildPacer,
     settings::signers::{BuildableWithSignerConf, SignerConf},
     CoreMetrics,
 };
@@ -46,6 +47,12 @@ pub struct ChainConf {
     pub signer: Option<SignerConf>,
     /// The reorg period of the chain, i.e. the number of blocks until finality
     pub reorg_period: ReorgPeriod,
+    /// Number of blocks until finality to fall back to if `reorg_period` is a
+    /// block tag the chain's provider doesn't support
+    pub reorg_period_fallback: Option<u32>,
+    /// Blocks to wait before treating an event as final, the domain's default
+    /// unless configured
+    pub finality_blocks: u32,
     /// Addresses of contracts on the chain
     pub addresses: CoreContractAddresses,
     /// The chain connection details
@@ -56,6 +63,12 @@ pub struct ChainConf {
     pub metrics_conf: PrometheusMiddlewareConf,
     /// Settings for event indexing
     pub index: IndexSettings,
+    /// IGP contracts whose payments are indexed in addition to the one in
+    /// `addresses`, e.g. legacy deployments still receiving payments
+    pub additional_interchain_gas_paymasters: Vec<AdditionalIgpConf>,
+    /// Depth of the chain's merkle tree, the standard 32 unless its contracts
+    /// were deployed with shallower trees
+    pub merkle_tree_depth: usize,
 }
 
 /// A sequence-aware indexer for messages
@@ -150,6 +163,15 @@ pub struct CoreContractAddresses {
     pub merkle_tree_hook: H256,
 }
 
+/// An IGP contract indexed in addition to the chain's configured one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdditionalIgpConf {
+    /// Address of the IGP contract
+    pub address: H256,
+    /// The height at which to start indexing its payments
+    pub from: u32,
+}
+
 /// Indexing settings
 #[derive(Debug, Default, Clone)]
 pub struct IndexSettings {
@@ -159,6 +181,9 @@ pub struct IndexSettings {
     pub chunk_size: u32,
     /// The indexing mode.
     pub mode: IndexMode,
+    /// Paces backfilling, if it's limited to an RPC budget. Never set from
+    /// the chain config: agents set it on the data they rebuild history of.
+    pub pacer: Option<RebuildPacer>,
 }
 
 impl ChainConf {
@@ -167,6 +192,19 @@ impl ChainConf {
         self.index.clone()
     }
 
+    /// Chain settings to index each IGP contract with: the configured one
+    /// first, then each additional one with its own start block
+    pub fn igp_index_confs(&self) -> Vec<ChainConf> {
+        let additional = self.additional_interchain_gas_paymasters.iter().map(|igp| {
+            let mut conf = self.clone();
+            conf.addresses.interchain_gas_paymaster = igp.address;
+            conf.index.from = igp.from;
+            conf.additional_interchain_gas_paymasters.clear();
+            conf
+        });
+        std::iter::once(self.clone()).chain(additional).collect()
+    }
+
     /// Try to convert the chain settings into an HyperlaneProvider.
     pub async fn build_provider(
         &self,
@@ -278,7 +316,11 @@ impl ChainConf {
                     conf,
                     &locator,
                     metrics,
-                    h_eth::SequenceIndexerBuilder { reorg_period },
+                    h_eth::SequenceIndexerBuilder {
+                        reorg_period,
+                        reorg_period_fallback: self.reorg_period_fallback,
+                        index_mode: self.index.mode,
+                    },
                 )
                 .await
             }
@@ -318,7 +360,10 @@ impl ChainConf {
                     conf,
                     &locator,
                     metrics,
-                    h_eth::DeliveryIndexerBuilder { reorg_period },
+                    h_eth::DeliveryIndexerBuilder {
+                        reorg_period,
+                        reorg_period_fallback: self.reorg_period_fallback,
+                    },
                 )
                 .await
             }
@@ -400,6 +445,7 @@ impl ChainConf {
                     h_eth::InterchainGasPaymasterIndexerBuilder {
                         mailbox_address: self.addresses.mailbox.into(),
                         reorg_period,
+                        reorg_period_fallback: self.reorg_period_fallback,
                     },
                 )
                 .await
@@ -440,7 +486,11 @@ impl ChainConf {
                     conf,
                     &locator,
                     metrics,
-                    h_eth::MerkleTreeHookIndexerBuilder { reorg_period },
+                    h_eth::MerkleTreeHookIndexerBuilder {
+                        reorg_period,
+                        reorg_period_fallback: self.reorg_period_fallback,
+                        index_mode: self.index.mode,
+                    },
                 )
                 .await
             }
@@ -741,9 +791,12 @@ impl ChainConf {
                 .entry(address.into())
                 .or_insert_with(|| ContractInfo {
                     name: Some(name.into()),
+                    // abi function selectors are always 4 bytes
                     functions: fns
                         .into_iter()
-                        .map(|s| (Selector::try_from(s.0).unwrap(), s.1))
+                        .filter_map(|(selector, name)| {
+                            Some((Selector::try_from(selector).ok()?, name))
+                        })
                         .collect(),
                 });
         };
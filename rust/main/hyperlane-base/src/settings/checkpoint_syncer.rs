@@ -100,6 +100,56 @@ impl FromStr for CheckpointSyncerConf {
     }
 }
 
+/// Separates the alternates from the primary in an announced storage location
+const ALTERNATE_LOCATION_SEPARATOR: char = '|';
+
+/// The storage locations a validator announces in one announcement: the
+/// primary one, optionally followed by alternates publishing the same
+/// checkpoints, as `<primary>|<alternate>|...`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnnouncedStorageLocations {
+    /// The location checkpoints are read from first
+    pub primary: String,
+    /// The locations to fall back to, in order
+    pub alternates: Vec<String>,
+}
+
+impl AnnouncedStorageLocations {
+    /// All the locations, the primary first
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.primary.as_str()).chain(self.alternates.iter().map(String::as_str))
+    }
+}
+
+impl FromStr for AnnouncedStorageLocations {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut locations = s.split(ALTERNATE_LOCATION_SEPARATOR).map(str::trim);
+        let primary = locations.next().unwrap_or_default();
+        if primary.is_empty() {
+            return Err(eyre!("Empty storage location ({s})"));
+        }
+        Ok(Self {
+            primary: primary.to_owned(),
+            alternates: locations
+                .filter(|location| !location.is_empty())
+                .map(str::to_owned)
+                .collect(),
+        })
+    }
+}
+
+impl std::fmt::Display for AnnouncedStorageLocations {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.primary)?;
+        for alternate in &self.alternates {
+            write!(f, "{ALTERNATE_LOCATION_SEPARATOR}{alternate}")?;
+        }
+        Ok(())
+    }
+}
+
 impl CheckpointSyncerConf {
     /// Turn conf info a Checkpoint Syncer
     ///
@@ -182,6 +232,30 @@ mod test {
     use futures_util::FutureExt;
     use hyperlane_core::{ReorgEvent, ReorgPeriod, H256};
 
+    use super::AnnouncedStorageLocations;
+
+    #[test]
+    fn test_announced_alternates_round_trip() {
+        let announced: AnnouncedStorageLocations =
+            "s3://bucket/us-east-1|gs://backup/folder".parse().unwrap();
+        assert_eq!(
+            announced,
+            AnnouncedStorageLocations {
+                primary: "s3://bucket/us-east-1".to_owned(),
+                alternates: vec!["gs://backup/folder".to_owned()],
+            }
+        );
+        assert_eq!(
+            announced.to_string(),
+            "s3://bucket/us-east-1|gs://backup/folder"
+        );
+
+        // Plain locations announce no alternates
+        let plain: AnnouncedStorageLocations = "s3://bucket/us-east-1".parse().unwrap();
+        assert!(plain.alternates.is_empty());
+        assert!("".parse::<AnnouncedStorageLocations>().is_err());
+    }
+
     #[tokio::test]
     async fn test_build_and_validate() {
         use super::*;
@@ -0,0 +1,350 @@
+use std::{
+    any::{Any, TypeId},
+    collections::{hash_map::DefaultHasher, HashMap},
+    fmt::{Debug, Formatter},
+    future::Future,
+    hash::{Hash, Hasher},
+    sync::{Arc, Mutex},
+};
+
+use eyre::Result;
+use hyperlane_core::{
+    AggregationIsm, CcipReadIsm, HyperlaneDomain, InterchainGasPaymaster, InterchainSecurityModule,
+    Mailbox, MerkleTreeHook, MultisigIsm, RoutingIsm, ValidatorAnnounce, H256,
+};
+use tokio::sync::OnceCell;
+use tracing::info;
+
+use crate::{settings::ChainConf, CoreMetrics};
+
+type CachedClient = Arc<OnceCell<Box<dyn Any + Send + Sync>>>;
+
+/// Clients are keyed by contract locator and client type, since the same
+/// contract may be used through several interfaces (e.g. an ISM as both
+/// `InterchainSecurityModule` and `MultisigIsm`).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct ClientKey {
+    domain: HyperlaneDomain,
+    address: H256,
+    client: TypeId,
+}
+
+/// Generate a cached getter for a core contract at its configured address
+macro_rules! cached_core_contract_fn {
+    ($name:ident, $address:ident, $build:ident -> $ret:ty) => {
+        /// Shared client, built with the `ChainConf` builder on first use
+        pub async fn $name(&self, conf: &ChainConf) -> Result<Arc<$ret>> {
+            self.get_or_build(stringify!($name), conf, conf.addresses.$address, || {
+                conf.$build(&self.metrics)
+            })
+            .await
+        }
+    };
+}
+
+/// Generate a cached getter for a contract at an arbitrary address
+macro_rules! cached_contract_fn {
+    ($name:ident, $build:ident -> $ret:ty) => {
+        /// Shared client, built with the `ChainConf` builder on first use
+        pub async fn $name(&self, conf: &ChainConf, address: H256) -> Result<Arc<$ret>> {
+            self.get_or_build(stringify!($name), conf, address, || {
+                conf.$build(address, &self.metrics)
+            })
+            .await
+        }
+    };
+}
+
+/// Hands out contract clients shared across agent tasks, so the same
+/// (chain, address) doesn't get a separate middleware stack and connection in
+/// every indexer, processor and submitter.
+///
+/// Clients are built lazily, exactly once per contract locator and client
+/// type even when requested concurrently. When a chain's connection settings
+/// change, its clients are dropped and rebuilt on next use.
+pub struct ContractClientCache {
+    metrics: Arc<CoreMetrics>,
+    clients: Mutex<HashMap<ClientKey, CachedClient>>,
+    /// Fingerprint of the connection settings each chain's clients were built
+    /// with
+    connections: Mutex<HashMap<HyperlaneDomain, u64>>,
+}
+
+impl Debug for ContractClientCache {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "ContractClientCache {{ clients: {} }}",
+            self.clients.lock().unwrap().len()
+        )
+    }
+}
+
+impl ContractClientCache {
+    /// Create an empty cache
+    pub fn new(metrics: Arc<CoreMetrics>) -> Self {
+        Self {
+            metrics,
+            clients: Default::default(),
+            connections: Default::default(),
+        }
+    }
+
+    cached_core_contract_fn!(mailbox, mailbox, build_mailbox -> dyn Mailbox);
+    cached_core_contract_fn!(interchain_gas_paymaster, interchain_gas_paymaster, build_interchain_gas_paymaster -> dyn InterchainGasPaymaster);
+    cached_core_contract_fn!(validator_announce, validator_announce, build_validator_announce -> dyn ValidatorAnnounce);
+    cached_core_contract_fn!(merkle_tree_hook, merkle_tree_hook, build_merkle_tree_hook -> dyn MerkleTreeHook);
+    cached_contract_fn!(ism, build_ism -> dyn InterchainSecurityModule);
+    cached_contract_fn!(multisig_ism, build_multisig_ism -> dyn MultisigIsm);
+    cached_contract_fn!(routing_ism, build_routing_ism -> dyn RoutingIsm);
+    cached_contract_fn!(aggregation_ism, build_aggregation_ism -> dyn AggregationIsm);
+    cached_contract_fn!(ccip_read_ism, build_ccip_read_ism -> dyn CcipReadIsm);
+
+    /// Get the client of type `C` for the contract at `address`, building it
+    /// with `build` if it isn't cached yet. `client_name` labels the
+    /// construction metric.
+    pub async fn get_or_build<C, F, Fut>(
+        &self,
+        client_name: &'static str,
+        conf: &ChainConf,
+        address: H256,
+        build: F,
+    ) -> Result<Arc<C>>
+    where
+        C: ?Sized + Send + Sync + 'static,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Box<C>>>,
+    {
+        self.reload(conf);
+        let key = ClientKey {
+            domain: conf.domain.clone(),
+            address,
+            client: TypeId::of::<C>(),
+        };
+        let cell = self.clients.lock().unwrap().entry(key).or_default().clone();
+        let client = cell
+            .get_or_try_init(|| async {
+                let client: Arc<C> = Arc::from(build().await?);
+                self.metrics
+                    .contract_client_constructions()
+                    .with_label_values(&[conf.domain.name(), client_name])
+                    .inc();
+                Ok::<_, eyre::Report>(Box::new(client) as Box<dyn Any + Send + Sync>)
+            })
+            .await?;
+        self.update_size(&conf.domain);
+        Ok(client
+            .downcast_ref::<Arc<C>>()
+            .expect("clients are keyed by their type")
+            .clone())
+    }
+
+    /// Seed the cache with a client of type `C` for the contract at
+    /// `address`, built elsewhere, e.g. by a service embedding the agent. It's
+    /// handed out instead of building one, until the chain's connection
+    /// settings change.
+    pub fn insert<C>(&self, conf: &ChainConf, address: H256, client: Arc<C>)
+    where
+        C: ?Sized + Send + Sync + 'static,
+    {
+        self.reload(conf);
+        let key = ClientKey {
+            domain: conf.domain.clone(),
+            address,
+            client: TypeId::of::<C>(),
+        };
+        let client = Box::new(client) as Box<dyn Any + Send + Sync>;
+        self.clients
+            .lock()
+            .unwrap()
+            .insert(key, Arc::new(OnceCell::new_with(Some(client))));
+        self.update_size(&conf.domain);
+    }
+
+    /// Settings reload hook: drops the chain's clients if its connection
+    /// settings changed since they were built. Returns the number of clients
+    /// dropped.
+    pub fn reload(&self, conf: &ChainConf) -> usize {
+        let fingerprint = connection_fingerprint(conf);
+        let previous = self
+            .connections
+            .lock()
+            .unwrap()
+            .insert(conf.domain.clone(), fingerprint);
+        match previous {
+            Some(previous) if previous != fingerprint => self.invalidate(&conf.domain),
+            _ => 0,
+        }
+    }
+
+    /// Drop every client of the chain, so they get rebuilt on next use.
+    /// Returns the number of clients dropped.
+    pub fn invalidate(&self, domain: &HyperlaneDomain) -> usize {
+        let dropped = {
+            let mut clients = self.clients.lock().unwrap();
+            let before = clients.len();
+            clients.retain(|key, _| key.domain != *domain);
+            before - clients.len()
+        };
+        if dropped > 0 {
+            info!(
+                domain = domain.name(),
+                dropped, "Dropped cached contract clients"
+            );
+        }
+        self.update_size(domain);
+        dropped
+    }
+
+    fn update_size(&self, domain: &HyperlaneDomain) {
+        let size = self
+            .clients
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(key, client)| key.domain == *domain && client.initialized())
+            .count();
+        self.metrics
+            .contract_client_cache_size()
+            .with_label_values(&[domain.name()])
+            .set(size as i64);
+    }
+}
+
+/// Hash of everything clients are built from besides the contract address
+fn connection_fingerprint(conf: &ChainConf) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    format!("{:?}{:?}", conf.connection, conf.signer).hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod test {
+    use hyperlane_core::{accumulator::TREE_DEPTH, KnownHyperlaneDomain};
+    use prometheus::Registry;
+
+    use crate::settings::ChainConnectionConf;
+
+    use super::*;
+
+    fn chain_conf(domain: KnownHyperlaneDomain, url: &str) -> ChainConf {
+        ChainConf {
+            domain: HyperlaneDomain::Known(domain),
+            signer: Default::default(),
+            reorg_period: Default::default(),
+            reorg_period_fallback: None,
+            finality_blocks: 1,
+            addresses: Default::default(),
+            connection: ChainConnectionConf::Ethereum(hyperlane_ethereum::ConnectionConf {
+                rpc_connection: hyperlane_ethereum::RpcConnectionConf::Http {
+                    url: url.parse().unwrap(),
+                },
+                transaction_overrides: Default::default(),
+                operation_batch: Default::default(),
+                private_submission: None,
+                get_logs_caps: Default::default(),
+            }),
+            metrics_conf: Default::default(),
+            index: Default::default(),
+            additional_interchain_gas_paymasters: vec![],
+            merkle_tree_depth: TREE_DEPTH,
+        }
+    }
+
+    fn cache() -> Arc<ContractClientCache> {
+        let metrics = CoreMetrics::new("test", 0, Registry::new()).unwrap();
+        Arc::new(ContractClientCache::new(Arc::new(metrics)))
+    }
+
+    fn constructions(cache: &ContractClientCache, conf: &ChainConf, client: &str) -> u64 {
+        cache
+            .metrics
+            .contract_client_constructions()
+            .with_label_values(&[conf.domain.name(), client])
+            .get()
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_requests_share_one_client() {
+        let cache = cache();
+        let conf = chain_conf(KnownHyperlaneDomain::Test1, "http://example.com");
+
+        let tasks = (0..8).map(|_| {
+            let (cache, conf) = (cache.clone(), conf.clone());
+            tokio::spawn(async move { cache.mailbox(&conf).await.unwrap() })
+        });
+        let mailboxes: Vec<_> = futures_util::future::join_all(tasks)
+            .await
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+
+        assert!(mailboxes.iter().all(|m| Arc::ptr_eq(m, &mailboxes[0])));
+        assert_eq!(constructions(&cache, &conf, "mailbox"), 1);
+        assert_eq!(
+            cache
+                .metrics
+                .contract_client_cache_size()
+                .with_label_values(&[conf.domain.name()])
+                .get(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_clients_are_keyed_by_address_and_type() {
+        let cache = cache();
+        let conf = chain_conf(KnownHyperlaneDomain::Test1, "http://example.com");
+        let (first, second) = (H256::from_low_u64_be(1), H256::from_low_u64_be(2));
+
+        let ism = cache.ism(&conf, first).await.unwrap();
+        assert!(Arc::ptr_eq(&ism, &cache.ism(&conf, first).await.unwrap()));
+        assert!(!Arc::ptr_eq(&ism, &cache.ism(&conf, second).await.unwrap()));
+        cache.multisig_ism(&conf, first).await.unwrap();
+
+        assert_eq!(constructions(&cache, &conf, "ism"), 2);
+        assert_eq!(constructions(&cache, &conf, "multisig_ism"), 1);
+    }
+
+    #[tokio::test]
+    async fn test_inserted_clients_are_handed_out() {
+        let cache = cache();
+        let conf = chain_conf(KnownHyperlaneDomain::Test1, "http://example.com");
+        // Built elsewhere, outside of the cache
+        let mailbox: Arc<dyn Mailbox> =
+            Arc::from(conf.build_mailbox(&cache.metrics).await.unwrap());
+        cache.insert(&conf, conf.addresses.mailbox, mailbox.clone());
+
+        assert!(Arc::ptr_eq(&mailbox, &cache.mailbox(&conf).await.unwrap()));
+        assert_eq!(constructions(&cache, &conf, "mailbox"), 0);
+    }
+
+    #[tokio::test]
+    async fn test_reload_rebuilds_only_affected_chains() {
+        let cache = cache();
+        let test1 = chain_conf(KnownHyperlaneDomain::Test1, "http://example.com");
+        let test2 = chain_conf(KnownHyperlaneDomain::Test2, "http://example.com");
+        let test1_mailbox = cache.mailbox(&test1).await.unwrap();
+        let test2_mailbox = cache.mailbox(&test2).await.unwrap();
+
+        // Unchanged settings keep the clients
+        assert_eq!(cache.reload(&test1), 0);
+        assert!(Arc::ptr_eq(
+            &test1_mailbox,
+            &cache.mailbox(&test1).await.unwrap()
+        ));
+
+        let test1 = chain_conf(KnownHyperlaneDomain::Test1, "http://example.org");
+        assert_eq!(cache.reload(&test1), 1);
+        assert!(!Arc::ptr_eq(
+            &test1_mailbox,
+            &cache.mailbox(&test1).await.unwrap()
+        ));
+        assert!(Arc::ptr_eq(
+            &test2_mailbox,
+            &cache.mailbox(&test2).await.unwrap()
+        ));
+        assert_eq!(constructions(&cache, &test1, "mailbox"), 2);
+        assert_eq!(constructions(&cache, &test2, "mailbox"), 1);
+    }
+}
@@ -0,0 +1,567 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use config::{Config, FileFormat};
+use eyre::{Context, Result};
+use hyperlane_core::{
+    config::{ConfigPath, FromRawConf},
+    HyperlaneDomain, HyperlaneDomainProtocol, HyperlaneDomainType, KnownHyperlaneDomain,
+    ReorgPeriod, H256,
+};
+use serde_json::Value;
+
+use crate::settings::{parser::RawAgentConf, ChainConf, Settings};
+
+/// Fewest blocks a mainnet may wait for finality, unless it uses a finality tag
+pub const MIN_MAINNET_REORG_PERIOD: u32 = 1;
+/// Most blocks any chain may wait for finality; larger values are almost
+/// certainly a mistake, e.g. a block number
+pub const MAX_REORG_PERIOD: u32 = 1_000;
+
+/// A problem found by [`validate_config_consistency`]
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ConsistencyIssue {
+    /// The chain's name and domain id don't agree with each other or with the
+    /// known domains
+    #[error("chain `{chain}` with domain id {domain}: {reason}")]
+    DomainNameMismatch {
+        /// Name of the chain entry
+        chain: String,
+        /// Configured domain id
+        domain: u32,
+        /// What disagrees
+        reason: String,
+    },
+    /// Several chains of one environment use the same domain id
+    #[error("domain id {domain} is used by several chains: {}", chains.join(", "))]
+    DuplicateDomainId {
+        /// The shared domain id
+        domain: u32,
+        /// Names of the chains using it
+        chains: Vec<String>,
+    },
+    /// A chain is configured with different domain ids across files
+    #[error("chain `{chain}` is configured with several domain ids: {domains:?}")]
+    ConflictingDomainIds {
+        /// Name of the chain
+        chain: String,
+        /// The domain ids it is configured with
+        domains: Vec<u32>,
+    },
+    /// A core contract address isn't a valid address for the chain's protocol
+    #[error("chain `{chain}` has an invalid {contract} address {address:?}: {reason}")]
+    InvalidAddress {
+        /// Name of the chain
+        chain: String,
+        /// Which contract the address is for
+        contract: &'static str,
+        /// The configured address
+        address: H256,
+        /// Why it is invalid
+        reason: &'static str,
+    },
+    /// The reorg period is outside the bounds for the chain's domain type
+    #[error("chain `{chain}` has a reorg period of {blocks} blocks, outside the {min}..={max} allowed for {domain_type:?} chains")]
+    ReorgPeriodOutOfBounds {
+        /// Name of the chain
+        chain: String,
+        /// Domain type the bounds were picked for
+        domain_type: HyperlaneDomainType,
+        /// Configured reorg period
+        blocks: u32,
+        /// Lower bound
+        min: u32,
+        /// Upper bound
+        max: u32,
+    },
+    /// A relayer destination has no chain entry in the environment
+    #[error("relay chain `{chain}` in {} has no chain entry", file.display())]
+    UnknownRelayChain {
+        /// Name of the relay chain
+        chain: String,
+        /// File listing it
+        file: PathBuf,
+    },
+    /// A config file couldn't be parsed into settings
+    #[error("{} could not be parsed: {error}", file.display())]
+    Unparseable {
+        /// The config file
+        file: PathBuf,
+        /// The parsing error
+        error: String,
+    },
+}
+
+/// Check that the settings of an environment, e.g. all the config files
+/// deployed together, agree with each other and with the known domains.
+/// Settings declaring different environments are only checked against those
+/// of their own environment, e.g. a testnet may reuse a mainnet's domain id.
+/// Returns every issue found rather than stopping at the first one.
+pub fn validate_config_consistency(settings: &[Settings]) -> Vec<ConsistencyIssue> {
+    let mut issues = vec![];
+    let mut chains_by_domain: BTreeMap<(Option<&str>, u32), BTreeSet<&str>> = BTreeMap::new();
+    let mut domains_by_chain: BTreeMap<(Option<&str>, &str), BTreeSet<u32>> = BTreeMap::new();
+
+    let chains = settings.iter().flat_map(|s| {
+        let environment = s.environment.as_deref();
+        s.chains
+            .iter()
+            .map(move |(key, chain)| (environment, key, chain))
+    });
+    for (environment, key, chain) in chains {
+        let (name, domain) = (chain.domain.name(), chain.domain.id());
+        chains_by_domain
+            .entry((environment, domain))
+            .or_default()
+            .insert(name);
+        domains_by_chain
+            .entry((environment, name))
+            .or_default()
+            .insert(domain);
+
+        issues.extend(domain_name_issue(key, name, domain));
+        if let HyperlaneDomain::Unknown { .. } = chain.domain {
+            // Known domains were checked against their id when constructed
+            issues.extend(known_domain_issue(key, name, domain));
+        }
+        issues.extend(address_issues(key, chain));
+        issues.extend(reorg_period_issue(key, chain));
+    }
+
+    issues.extend(
+        chains_by_domain
+            .into_iter()
+            .filter(|(_, chains)| chains.len() > 1)
+            .map(
+                |((_, domain), chains)| ConsistencyIssue::DuplicateDomainId {
+                    domain,
+                    chains: chains.into_iter().map(str::to_owned).collect(),
+                },
+            ),
+    );
+    issues.extend(
+        domains_by_chain
+            .into_iter()
+            .filter(|(_, domains)| domains.len() > 1)
+            .map(
+                |((_, chain), domains)| ConsistencyIssue::ConflictingDomainIds {
+                    chain: chain.to_owned(),
+                    domains: domains.into_iter().collect(),
+                },
+            ),
+    );
+    issues
+}
+
+/// Validate every JSON config file directly inside `dir` as one environment,
+/// for pre-deploy checks. On top of [`validate_config_consistency`], this
+/// reports files that don't parse and relay chains without a chain entry.
+pub fn validate_config_dir(dir: &Path) -> Result<Vec<ConsistencyIssue>> {
+    let mut paths = fs::read_dir(dir)
+        .with_context(|| format!("Reading config directory {}", dir.display()))?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    paths.retain(|p| p.extension().is_some_and(|ext| ext == "json"));
+    paths.sort();
+
+    let mut issues = vec![];
+    let mut settings = vec![];
+    let mut relay_chains = vec![];
+    for path in paths {
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("Reading config file {}", path.display()))?;
+        let raw: Value = match serde_json::from_str(&contents) {
+            Ok(raw) => raw,
+            Err(err) => {
+                issues.push(unparseable(&path, err));
+                continue;
+            }
+        };
+        issues.extend(raw_domain_name_issues(&raw));
+        if let Some(chains) = raw.get("relayChains").and_then(Value::as_str) {
+            relay_chains.extend(
+                chains
+                    .split(',')
+                    .map(|chain| (chain.trim().to_owned(), path.clone())),
+            );
+        }
+        match parse_settings(&contents) {
+            Ok(parsed) => settings.push(parsed),
+            Err(err) => issues.push(unparseable(&path, err)),
+        }
+    }
+
+    issues.extend(validate_config_consistency(&settings));
+    issues.extend(
+        relay_chains
+            .into_iter()
+            .filter(|(chain, _)| !settings.iter().any(|s| s.chains.contains_key(chain)))
+            .map(|(chain, file)| ConsistencyIssue::UnknownRelayChain { chain, file }),
+    );
+    Ok(issues)
+}
+
+fn parse_settings(contents: &str) -> Result<Settings> {
+    let raw = Config::builder()
+        .add_source(config::File::from_str(contents, FileFormat::Json))
+        .build()?
+        .try_deserialize::<RawAgentConf>()?;
+    Ok(Settings::from_config(raw, &ConfigPath::default())?)
+}
+
+fn unparseable(path: &Path, err: impl std::fmt::Display) -> ConsistencyIssue {
+    ConsistencyIssue::Unparseable {
+        file: path.to_owned(),
+        error: err.to_string(),
+    }
+}
+
+/// Domain/name agreement of the raw chain entries, which would otherwise
+/// only surface as a parsing error
+fn raw_domain_name_issues(raw: &Value) -> Vec<ConsistencyIssue> {
+    let Some(chains) = raw.get("chains").and_then(Value::as_object) else {
+        return vec![];
+    };
+    chains
+        .iter()
+        .filter_map(|(key, chain)| {
+            let name = chain.get("name")?.as_str()?;
+            let domain = chain
+                .get("domainId")
+                .or_else(|| chain.get("chainId"))?
+                .as_u64()?;
+            Some((key, name, u32::try_from(domain).ok()?))
+        })
+        .flat_map(|(key, name, domain)| chain_domain_issues(key, name, domain))
+        .collect()
+}
+
+/// Disagreements of the name and domain id of the chain entry `key`, with
+/// each other and with the known domains. Domains unknown to this release
+/// can't be checked against each other and pass.
+pub(crate) fn chain_domain_issues(key: &str, name: &str, domain: u32) -> Vec<ConsistencyIssue> {
+    domain_name_issue(key, name, domain)
+        .into_iter()
+        .chain(known_domain_issue(key, name, domain))
+        .collect()
+}
+
+/// The chain entry must be named after its domain
+fn domain_name_issue(key: &str, name: &str, domain: u32) -> Option<ConsistencyIssue> {
+    (key != name).then(|| ConsistencyIssue::DomainNameMismatch {
+        chain: key.to_owned(),
+        domain,
+        reason: format!("the chain entry is for a domain named `{name}`"),
+    })
+}
+
+/// A known name or domain id must be used with its known counterpart
+fn known_domain_issue(key: &str, name: &str, domain: u32) -> Option<ConsistencyIssue> {
+    let reason = if let Ok(known) = KnownHyperlaneDomain::try_from(domain) {
+        // deprecated names resolve to their replacement, with a warning
+        (known.as_str() != name && KnownHyperlaneDomain::from_deprecated_name(name) != Some(known))
+            .then(|| format!("domain id {domain} belongs to `{known}`"))
+    } else if let Ok(known) = name.parse::<KnownHyperlaneDomain>() {
+        Some(format!("`{name}` has domain id {}", known as u32))
+    } else {
+        None
+    }?;
+    Some(ConsistencyIssue::DomainNameMismatch {
+        chain: key.to_owned(),
+        domain,
+        reason,
+    })
+}
+
+fn address_issues(key: &str, chain: &ChainConf) -> Vec<ConsistencyIssue> {
+    [
+        ("mailbox", chain.addresses.mailbox),
+        (
+            "interchain gas paymaster",
+            chain.addresses.interchain_gas_paymaster,
+        ),
+    ]
+    .into_iter()
+    .filter_map(|(contract, address)| {
+        let reason = if address.is_zero() {
+            "the zero address"
+        } else if chain.domain.domain_protocol() == HyperlaneDomainProtocol::Ethereum
+            && address.as_bytes()[..12] != [0; 12]
+        {
+            "longer than 20 bytes for an EVM chain"
+        } else {
+            return None;
+        };
+        Some(ConsistencyIssue::InvalidAddress {
+            chain: key.to_owned(),
+            contract,
+            address,
+            reason,
+        })
+    })
+    .collect()
+}
+
+fn reorg_period_issue(key: &str, chain: &ChainConf) -> Option<ConsistencyIssue> {
+    let blocks = match &chain.reorg_period {
+        ReorgPeriod::None => 0,
+        ReorgPeriod::Blocks(blocks) => blocks.get(),
+        // Finality is up to the chain
+        ReorgPeriod::Tag(_) => return None,
+    };
+    let domain_type = chain.domain.domain_type();
+    let min = match domain_type {
+        HyperlaneDomainType::Mainnet => MIN_MAINNET_REORG_PERIOD,
+        _ => 0,
+    };
+    (!(min..=MAX_REORG_PERIOD).contains(&blocks)).then(|| {
+        ConsistencyIssue::ReorgPeriodOutOfBounds {
+            chain: key.to_owned(),
+            domain_type,
+            blocks,
+            min,
+            max: MAX_REORG_PERIOD,
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use hyperlane_core::{accumulator::TREE_DEPTH, HyperlaneDomainTechnicalStack};
+
+    use super::*;
+    use crate::settings::ChainConnectionConf;
+
+    fn chain(domain: HyperlaneDomain, reorg_period: ReorgPeriod) -> ChainConf {
+        ChainConf {
+            domain,
+            signer: Default::default(),
+            reorg_period,
+            reorg_period_fallback: None,
+            finality_blocks: 1,
+            addresses: crate::settings::CoreContractAddresses {
+                mailbox: H256::from_low_u64_be(1),
+                interchain_gas_paymaster: H256::from_low_u64_be(2),
+                ..Default::default()
+            },
+            connection: ChainConnectionConf::Ethereum(hyperlane_ethereum::ConnectionConf {
+                rpc_connection: hyperlane_ethereum::RpcConnectionConf::Http {
+                    url: "http://example.com".parse().unwrap(),
+                },
+                transaction_overrides: Default::default(),
+                operation_batch: Default::default(),
+                private_submission: None,
+                get_logs_caps: Default::default(),
+            }),
+            metrics_conf: Default::default(),
+            index: Default::default(),
+            additional_interchain_gas_paymasters: vec![],
+            merkle_tree_depth: TREE_DEPTH,
+        }
+    }
+
+    fn unknown_domain(name: &str, domain_id: u32) -> HyperlaneDomain {
+        HyperlaneDomain::Unknown {
+            domain_id,
+            domain_name: name.to_owned(),
+            domain_type: HyperlaneDomainType::Unknown,
+            domain_protocol: HyperlaneDomainProtocol::Ethereum,
+            domain_technical_stack: HyperlaneDomainTechnicalStack::Other,
+        }
+    }
+
+    fn settings(chains: impl IntoIterator<Item = ChainConf>) -> Settings {
+        Settings {
+            environment: None,
+            chains: chains
+                .into_iter()
+                .map(|c| (c.domain.name().to_owned(), c))
+                .collect::<HashMap<_, _>>(),
+            domains: Default::default(),
+            metrics_port: 9090,
+            exemplar_sample_rate: 0.,
+            tracing: Default::default(),
+            alerts: Default::default(),
+            config_fingerprint: Default::default(),
+            deprecated_domain_names: Default::default(),
+            bare_numbers: Default::default(),
+        }
+    }
+
+    fn blocks(blocks: u32) -> ReorgPeriod {
+        ReorgPeriod::from_blocks(blocks)
+    }
+
+    #[test]
+    fn test_consistent_settings_have_no_issues() {
+        let settings = [
+            settings([chain(KnownHyperlaneDomain::Ethereum.into(), blocks(15))]),
+            settings([
+                chain(KnownHyperlaneDomain::Ethereum.into(), blocks(15)),
+                chain(unknown_domain("newchain", 123456), blocks(0)),
+                chain(
+                    KnownHyperlaneDomain::Polygon.into(),
+                    ReorgPeriod::Tag("finalized".into()),
+                ),
+            ]),
+        ];
+        assert_eq!(validate_config_consistency(&settings), vec![]);
+    }
+
+    #[test]
+    fn test_domain_name_disagreement_is_reported() {
+        let mut renamed = settings([chain(unknown_domain("newchain", 123456), blocks(1))]);
+        let conf = renamed.chains.remove("newchain").unwrap();
+        renamed.chains.insert("otherchain".into(), conf);
+        let settings = [
+            renamed,
+            settings([
+                chain(unknown_domain("notethereum", 1), blocks(1)),
+                chain(unknown_domain("ethereum", 5), blocks(1)),
+            ]),
+        ];
+
+        let issues = validate_config_consistency(&settings);
+        let mismatched: BTreeSet<_> = issues
+            .iter()
+            .filter_map(|issue| match issue {
+                ConsistencyIssue::DomainNameMismatch { chain, .. } => Some(chain.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            mismatched,
+            BTreeSet::from(["otherchain", "notethereum", "ethereum"])
+        );
+    }
+
+    #[test]
+    fn test_duplicate_domain_ids_are_reported() {
+        let settings = [
+            settings([chain(unknown_domain("chaina", 123456), blocks(1))]),
+            settings([
+                chain(unknown_domain("chainb", 123456), blocks(1)),
+                chain(unknown_domain("chaina", 654321), blocks(1)),
+            ]),
+        ];
+
+        let issues = validate_config_consistency(&settings);
+        assert!(issues.contains(&ConsistencyIssue::DuplicateDomainId {
+            domain: 123456,
+            chains: vec!["chaina".into(), "chainb".into()],
+        }));
+        assert!(issues.contains(&ConsistencyIssue::ConflictingDomainIds {
+            chain: "chaina".into(),
+            domains: vec![123456, 654321],
+        }));
+    }
+
+    #[test]
+    fn test_domain_ids_are_unique_per_environment() {
+        let in_environment = |environment: &str, chains| Settings {
+            environment: Some(environment.to_owned()),
+            ..settings(chains)
+        };
+        let settings = [
+            in_environment(
+                "mainnet3",
+                [chain(unknown_domain("chaina", 123456), blocks(1))],
+            ),
+            in_environment(
+                "testnet4",
+                [chain(unknown_domain("chainb", 123456), blocks(1))],
+            ),
+            in_environment(
+                "testnet4",
+                [chain(unknown_domain("chaina", 654321), blocks(1))],
+            ),
+        ];
+        assert!(validate_config_consistency(&settings).is_empty());
+
+        let settings = [
+            in_environment(
+                "testnet4",
+                [chain(unknown_domain("chaina", 123456), blocks(1))],
+            ),
+            in_environment(
+                "testnet4",
+                [chain(unknown_domain("chainb", 123456), blocks(1))],
+            ),
+        ];
+        assert_eq!(
+            validate_config_consistency(&settings),
+            vec![ConsistencyIssue::DuplicateDomainId {
+                domain: 123456,
+                chains: vec!["chaina".into(), "chainb".into()],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_invalid_addresses_are_reported() {
+        let mut conf = chain(unknown_domain("newchain", 123456), blocks(1));
+        conf.addresses.mailbox = H256::zero();
+        conf.addresses.interchain_gas_paymaster = H256::repeat_byte(1);
+
+        let issues = validate_config_consistency(&[settings([conf])]);
+        let contracts: Vec<_> = issues
+            .iter()
+            .filter_map(|issue| match issue {
+                ConsistencyIssue::InvalidAddress { contract, .. } => Some(*contract),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(contracts, ["mailbox", "interchain gas paymaster"]);
+    }
+
+    #[test]
+    fn test_reorg_periods_out_of_bounds_are_reported() {
+        let settings = [settings([
+            chain(KnownHyperlaneDomain::Ethereum.into(), blocks(0)),
+            chain(KnownHyperlaneDomain::Sepolia.into(), blocks(0)),
+            chain(
+                unknown_domain("newchain", 123456),
+                blocks(MAX_REORG_PERIOD + 1),
+            ),
+        ])];
+
+        let mut out_of_bounds: Vec<_> = validate_config_consistency(&settings)
+            .into_iter()
+            .filter_map(|issue| match issue {
+                ConsistencyIssue::ReorgPeriodOutOfBounds { chain, .. } => Some(chain),
+                _ => None,
+            })
+            .collect();
+        out_of_bounds.sort();
+        assert_eq!(out_of_bounds, ["ethereum", "newchain"]);
+    }
+
+    #[test]
+    fn test_inconsistent_chains_fail_loading_in_one_error() {
+        let raw: RawAgentConf = serde_json::from_value(serde_json::json!({
+            "chains": {
+                "polygon": { "name": "polygon", "domainid": 5, "protocol": "ethereum" },
+                "ethereum": { "name": "ethereum", "domainid": 137, "protocol": "ethereum" },
+                "newchain": { "name": "otherchain", "domainid": 123456, "protocol": "ethereum" },
+            }
+        }))
+        .unwrap();
+
+        let err = Settings::from_config(raw, &ConfigPath::default())
+            .unwrap_err()
+            .to_string();
+        assert_eq!(err.matches("inconsistent").count(), 1, "{err}");
+        for entry in [
+            "chain `polygon` with domain id 5",
+            "chain `ethereum` with domain id 137",
+            "chain `newchain` with domain id 123456",
+        ] {
+            assert!(err.contains(entry), "{entry} missing from {err}");
+        }
+    }
+}
@@ -0,0 +1,223 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use eyre::{Context, Result};
+use serde_json::Value;
+
+use crate::db::DB;
+
+/// Key config files declare the environment they're for under, e.g.
+/// `mainnet3` or `testnet4`
+pub const ENVIRONMENT_KEY: &str = "environment";
+
+/// DB key the environment the database was created for is stored under
+const DB_ENVIRONMENT_KEY: &[u8] = b"db_environment";
+
+/// Config files or a database that belong to different environments
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum EnvironmentError {
+    /// Config files of several environments, or declaring one and not, were
+    /// going to be merged into one config
+    #[error("config files of different environments can't be merged: {}", describe(.files))]
+    Mixed {
+        /// The merged files, along with the environment they declare
+        files: Vec<(PathBuf, Option<String>)>,
+    },
+    /// The database was created for another environment
+    #[error("the database was created for environment `{db}`, not `{config}`")]
+    DbMismatch {
+        /// Environment the database was created for
+        db: String,
+        /// Environment of the config
+        config: String,
+    },
+}
+
+fn describe(files: &[(PathBuf, Option<String>)]) -> String {
+    files
+        .iter()
+        .map(|(file, environment)| {
+            let environment = environment.as_deref().unwrap_or("no environment");
+            format!("{} ({environment})", file.display())
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The environment the JSON config file at `path` declares, if any
+pub fn file_environment(path: &Path) -> Result<Option<String>> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("Reading config file {}", path.display()))?;
+    let raw: Value = serde_json::from_str(&contents)
+        .with_context(|| format!("Parsing config file {}", path.display()))?;
+    Ok(raw
+        .get(ENVIRONMENT_KEY)
+        .and_then(Value::as_str)
+        .map(str::to_owned))
+}
+
+/// The JSON config files directly inside `dir` which declare `environment`,
+/// sorted by path
+pub fn select_environment_files(dir: &Path, environment: &str) -> Result<Vec<PathBuf>> {
+    let mut paths = fs::read_dir(dir)
+        .with_context(|| format!("Reading config directory {}", dir.display()))?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    paths.retain(|p| p.is_file() && p.extension().is_some_and(|ext| ext == "json"));
+    paths.sort();
+
+    let mut selected = vec![];
+    for path in paths {
+        if file_environment(&path)?.as_deref() == Some(environment) {
+            selected.push(path);
+        }
+    }
+    Ok(selected)
+}
+
+/// The environment of config files merged together. Files that don't declare
+/// one can only be merged with other such files, e.g. before environments
+/// were declared at all.
+pub fn merged_environment(
+    files: &[(PathBuf, Option<String>)],
+) -> Result<Option<String>, EnvironmentError> {
+    let Some((_, environment)) = files.first() else {
+        return Ok(None);
+    };
+    if files.iter().any(|(_, other)| other != environment) {
+        return Err(EnvironmentError::Mixed {
+            files: files.to_vec(),
+        });
+    }
+    Ok(environment.clone())
+}
+
+/// Check that `db` was created for `environment`, recording it if the
+/// database doesn't belong to one yet. Configs without an environment aren't
+/// checked.
+pub fn check_db_environment(db: &DB, environment: Option<&str>) -> Result<()> {
+    let Some(environment) = environment else {
+        return Ok(());
+    };
+    match db.retrieve(DB_ENVIRONMENT_KEY)? {
+        Some(stored) if stored != environment.as_bytes() => Err(EnvironmentError::DbMismatch {
+            db: String::from_utf8_lossy(&stored).into_owned(),
+            config: environment.to_owned(),
+        }
+        .into()),
+        Some(_) => Ok(()),
+        None => Ok(db.store(DB_ENVIRONMENT_KEY, environment.as_bytes())?),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    fn write(dir: &Path, name: &str, config: Value) -> PathBuf {
+        let path = dir.join(name);
+        fs::write(&path, config.to_string()).unwrap();
+        path
+    }
+
+    fn declared(path: &Path, environment: Option<&str>) -> (PathBuf, Option<String>) {
+        (path.to_owned(), environment.map(str::to_owned))
+    }
+
+    #[test]
+    fn test_files_of_different_environments_are_not_merged() {
+        let mainnet = Path::new("mainnet_config.json");
+        let testnet = Path::new("testnet_config.json");
+        let overrides = Path::new("overrides.json");
+
+        assert_eq!(merged_environment(&[]), Ok(None));
+        assert_eq!(
+            merged_environment(&[declared(overrides, None), declared(overrides, None)]),
+            Ok(None)
+        );
+        assert_eq!(
+            merged_environment(&[
+                declared(mainnet, Some("mainnet3")),
+                declared(overrides, Some("mainnet3"))
+            ]),
+            Ok(Some("mainnet3".to_owned()))
+        );
+
+        let mixed = [
+            declared(mainnet, Some("mainnet3")),
+            declared(testnet, Some("testnet4")),
+        ];
+        assert_eq!(
+            merged_environment(&mixed),
+            Err(EnvironmentError::Mixed {
+                files: mixed.to_vec()
+            })
+        );
+        let undeclared = [
+            declared(mainnet, Some("mainnet3")),
+            declared(overrides, None),
+        ];
+        assert_eq!(
+            merged_environment(&undeclared).unwrap_err().to_string(),
+            "config files of different environments can't be merged: \
+             mainnet_config.json (mainnet3), overrides.json (no environment)"
+        );
+    }
+
+    #[test]
+    fn test_environment_files_are_selected_from_a_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let mainnet = write(
+            dir.path(),
+            "mainnet_config.json",
+            json!({ "environment": "mainnet3" }),
+        );
+        write(
+            dir.path(),
+            "testnet_config.json",
+            json!({ "environment": "testnet4" }),
+        );
+        write(dir.path(), "undeclared.json", json!({ "chains": {} }));
+        let overrides = write(
+            dir.path(),
+            "mainnet_overrides.json",
+            json!({ "environment": "mainnet3" }),
+        );
+        fs::write(dir.path().join("notes.txt"), "not a config").unwrap();
+
+        assert_eq!(
+            file_environment(&mainnet).unwrap().as_deref(),
+            Some("mainnet3")
+        );
+        assert_eq!(
+            select_environment_files(dir.path(), "mainnet3").unwrap(),
+            vec![mainnet, overrides]
+        );
+        assert!(select_environment_files(dir.path(), "devnet")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_db_is_tied_to_its_environment() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = DB::from_path(dir.path()).unwrap();
+
+        check_db_environment(&db, None).unwrap();
+        check_db_environment(&db, Some("mainnet3")).unwrap();
+        check_db_environment(&db, Some("mainnet3")).unwrap();
+        check_db_environment(&db, None).unwrap();
+        let err = check_db_environment(&db, Some("testnet4")).unwrap_err();
+        assert_eq!(
+            err.downcast::<EnvironmentError>().unwrap(),
+            EnvironmentError::DbMismatch {
+                db: "mainnet3".to_owned(),
+                config: "testnet4".to_owned(),
+            }
+        );
+    }
+}
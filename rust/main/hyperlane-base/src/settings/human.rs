@@ -0,0 +1,346 @@
+//! Human-friendly durations and sizes in settings values, e.g. `"30s"`,
+//! `"2h"` or `"10mb"`.
+//!
+//! Bare numbers are still accepted for backwards compatibility and read in
+//! the unit the field documents, but they are ambiguous, so each field set
+//! to one is recorded on the parsed settings and warned about once tracing is
+//! up.
+
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter},
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use hyperlane_core::config::ConfigPath;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Units of a duration, from the smallest
+const DURATION_UNITS: &[(&str, u64)] = &[
+    ("ms", 1),
+    ("s", 1_000),
+    ("m", 60 * 1_000),
+    ("h", 60 * 60 * 1_000),
+    ("d", 24 * 60 * 60 * 1_000),
+];
+
+/// Units of a size, from the smallest. Sizes are binary, so `1kb` is 1024
+/// bytes.
+const SIZE_UNITS: &[(&str, u64)] = &[("b", 1), ("kb", 1 << 10), ("mb", 1 << 20), ("gb", 1 << 30)];
+
+/// Errors parsing a human-friendly duration or size
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum HumanValueError {
+    /// Nothing to parse
+    #[error("Expected a value like `30s` or `10mb`, got an empty string")]
+    Empty,
+    /// A part of the value doesn't start with a number
+    #[error("Expected a number before `{0}`")]
+    MissingNumber(String),
+    /// A part of the value has no unit, or one which isn't known
+    #[error("Unknown unit `{unit}`, expected one of {expected}")]
+    UnknownUnit {
+        /// The unit found
+        unit: String,
+        /// The units which are known
+        expected: String,
+    },
+    /// The value doesn't fit
+    #[error("`{0}` is too large")]
+    Overflow(String),
+}
+
+/// The unit bare numbers of a duration field are read in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DurationUnit {
+    /// Milliseconds
+    Millis,
+    /// Seconds
+    Seconds,
+}
+
+impl DurationUnit {
+    /// A duration of `amount` of this unit
+    pub fn duration(self, amount: u64) -> Duration {
+        match self {
+            DurationUnit::Millis => Duration::from_millis(amount),
+            DurationUnit::Seconds => Duration::from_secs(amount),
+        }
+    }
+
+    /// Name of the unit in warnings
+    pub fn name(self) -> &'static str {
+        match self {
+            DurationUnit::Millis => "milliseconds",
+            DurationUnit::Seconds => "seconds",
+        }
+    }
+}
+
+/// A duration written like `"500ms"`, `"30s"`, `"5m"`, `"2h"` or `"1d"`.
+/// Parts may be combined, e.g. `"1h30m"`. Serializes back to the same form,
+/// in the largest unit which is exact.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct HumanDuration(pub Duration);
+
+impl From<HumanDuration> for Duration {
+    fn from(duration: HumanDuration) -> Self {
+        duration.0
+    }
+}
+
+impl FromStr for HumanDuration {
+    type Err = HumanValueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_human(s, DURATION_UNITS).map(|millis| Self(Duration::from_millis(millis)))
+    }
+}
+
+impl Display for HumanDuration {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        // sub-millisecond precision can't be configured, so isn't shown
+        fmt_human(f, self.0.as_millis() as u64, DURATION_UNITS, "s")
+    }
+}
+
+/// A size written like `"512b"`, `"512kb"`, `"10mb"` or `"1gb"`. Serializes
+/// back to the same form, in the largest unit which is exact.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct HumanSize(pub u64);
+
+impl From<HumanSize> for u64 {
+    fn from(size: HumanSize) -> Self {
+        size.0
+    }
+}
+
+impl FromStr for HumanSize {
+    type Err = HumanValueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_human(s, SIZE_UNITS).map(Self)
+    }
+}
+
+impl Display for HumanSize {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        fmt_human(f, self.0, SIZE_UNITS, "b")
+    }
+}
+
+macro_rules! impl_human_serde {
+    ($ty:ident, $bare:expr) => {
+        impl Serialize for $ty {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $ty {
+            /// Bare numbers are read in the smallest unit a setting is
+            /// documented in: seconds for durations, bytes for sizes
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                #[derive(Deserialize)]
+                #[serde(untagged)]
+                enum Raw {
+                    Number(u64),
+                    Text(String),
+                }
+                match Raw::deserialize(deserializer)? {
+                    Raw::Number(n) => Ok($bare(n)),
+                    Raw::Text(s) => s.parse().map_err(serde::de::Error::custom),
+                }
+            }
+        }
+    };
+}
+
+impl_human_serde!(HumanDuration, |n| HumanDuration(Duration::from_secs(n)));
+impl_human_serde!(HumanSize, HumanSize);
+
+fn parse_human(s: &str, units: &[(&str, u64)]) -> Result<u64, HumanValueError> {
+    let s = s.trim().to_ascii_lowercase();
+    if s.is_empty() {
+        return Err(HumanValueError::Empty);
+    }
+    let mut total = 0u64;
+    let mut rest = s.as_str();
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let (number, tail) = rest.split_at(digits);
+        let unit_len = tail
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(tail.len());
+        let (unit, tail) = tail.split_at(unit_len);
+        let unit = unit.trim();
+        if number.is_empty() {
+            return Err(HumanValueError::MissingNumber(unit.to_owned()));
+        }
+        let multiplier = units
+            .iter()
+            .find(|(name, _)| *name == unit)
+            .map(|(_, multiplier)| *multiplier)
+            .ok_or_else(|| HumanValueError::UnknownUnit {
+                unit: unit.to_owned(),
+                expected: units
+                    .iter()
+                    .map(|(name, _)| format!("`{name}`"))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            })?;
+        total = number
+            .parse::<u64>()
+            .ok()
+            .and_then(|n| n.checked_mul(multiplier))
+            .and_then(|n| total.checked_add(n))
+            .ok_or_else(|| HumanValueError::Overflow(s.clone()))?;
+        rest = tail;
+    }
+    Ok(total)
+}
+
+/// Write `amount` in the largest of `units` which is exact, or in `zero_unit`
+/// if it's zero, which is exact in all of them
+fn fmt_human(
+    f: &mut Formatter<'_>,
+    amount: u64,
+    units: &[(&str, u64)],
+    zero_unit: &str,
+) -> std::fmt::Result {
+    if amount == 0 {
+        return write!(f, "0{zero_unit}");
+    }
+    let (name, multiplier) = units
+        .iter()
+        .rev()
+        .find(|(_, multiplier)| amount % multiplier == 0)
+        .unwrap_or(&units[0]);
+    write!(f, "{}{name}", amount / multiplier)
+}
+
+/// The fields set to a bare number while parsing one config, shared by the
+/// parsers of its values
+#[derive(Debug, Clone, Default)]
+pub struct BareNumbers(Arc<Mutex<BTreeMap<String, &'static str>>>);
+
+impl BareNumbers {
+    /// Record that `field` was set to a bare number, read in `unit`
+    pub(crate) fn record(&self, field: &ConfigPath, unit: &'static str) {
+        self.0
+            .lock()
+            .unwrap()
+            .entry(field.json_name())
+            .or_insert(unit);
+    }
+
+    /// The units the recorded fields were read in, by their json name
+    pub fn fields(&self) -> BTreeMap<String, &'static str> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_durations_parse_every_unit() {
+        let cases = [
+            ("500ms", Duration::from_millis(500)),
+            ("30s", Duration::from_secs(30)),
+            ("5m", Duration::from_secs(5 * 60)),
+            ("2h", Duration::from_secs(2 * 60 * 60)),
+            ("1d", Duration::from_secs(24 * 60 * 60)),
+            ("1h30m", Duration::from_secs(90 * 60)),
+            (" 10S ", Duration::from_secs(10)),
+        ];
+        for (s, expected) in cases {
+            assert_eq!(s.parse::<HumanDuration>().unwrap().0, expected, "{s}");
+        }
+    }
+
+    #[test]
+    fn test_sizes_parse_every_unit() {
+        let cases = [
+            ("512b", 512),
+            ("512kb", 512 * 1024),
+            ("10mb", 10 * 1024 * 1024),
+            ("1gb", 1024 * 1024 * 1024),
+            ("1MB", 1024 * 1024),
+        ];
+        for (s, expected) in cases {
+            assert_eq!(s.parse::<HumanSize>().unwrap().0, expected, "{s}");
+        }
+    }
+
+    #[test]
+    fn test_malformed_values_are_rejected() {
+        assert_eq!("".parse::<HumanDuration>(), Err(HumanValueError::Empty));
+        assert_eq!(
+            "s".parse::<HumanDuration>(),
+            Err(HumanValueError::MissingNumber("s".to_owned()))
+        );
+        assert!(matches!(
+            "30 seconds".parse::<HumanDuration>(),
+            Err(HumanValueError::UnknownUnit { .. })
+        ));
+        // a bare number is only accepted by the settings parser, which
+        // knows the unit of the field
+        assert!(matches!(
+            "30".parse::<HumanDuration>(),
+            Err(HumanValueError::UnknownUnit { .. })
+        ));
+        assert!(matches!(
+            "99999999999999999999gb".parse::<HumanSize>(),
+            Err(HumanValueError::Overflow(_))
+        ));
+    }
+
+    #[test]
+    fn test_values_round_trip_through_their_human_form() {
+        let durations = [
+            (Duration::ZERO, "0s"),
+            (Duration::from_millis(1500), "1500ms"),
+            (Duration::from_secs(90), "90s"),
+            (Duration::from_secs(60 * 60), "1h"),
+            (Duration::from_secs(3 * 24 * 60 * 60), "3d"),
+        ];
+        for (duration, human) in durations {
+            let serialized = serde_json::to_value(HumanDuration(duration)).unwrap();
+            assert_eq!(serialized, human);
+            assert_eq!(
+                serde_json::from_value::<HumanDuration>(serialized)
+                    .unwrap()
+                    .0,
+                duration
+            );
+        }
+
+        let sizes = [
+            (0, "0b"),
+            (1000, "1000b"),
+            (256 * 1024, "256kb"),
+            (1 << 30, "1gb"),
+        ];
+        for (size, human) in sizes {
+            let serialized = serde_json::to_value(HumanSize(size)).unwrap();
+            assert_eq!(serialized, human);
+            assert_eq!(
+                serde_json::from_value::<HumanSize>(serialized).unwrap().0,
+                size
+            );
+        }
+
+        // bare numbers are still read, in seconds and bytes
+        assert_eq!(
+            serde_json::from_str::<HumanDuration>("30").unwrap().0,
+            Duration::from_secs(30)
+        );
+        assert_eq!(serde_json::from_str::<HumanSize>("30").unwrap().0, 30);
+    }
+}
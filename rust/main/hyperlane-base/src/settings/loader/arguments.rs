@@ -23,6 +23,9 @@ pub struct CommandLineArguments {
     /// Ignore empty env values (treat as unset).
     ignore_empty: bool,
 
+    /// Arguments to leave out, by their key.
+    exclude: Vec<String>,
+
     /// Alternate source for the environment. This can be used when you want to
     /// test your own code using this source, without the need to change the
     /// actual system environment variables.
@@ -41,6 +44,11 @@ impl CommandLineArguments {
         self
     }
 
+    pub fn exclude(mut self, key: &str) -> Self {
+        self.exclude.push(key.into());
+        self
+    }
+
     pub fn source<I, S>(mut self, source: I) -> Self
     where
         I: IntoIterator<Item = S>,
@@ -73,7 +81,7 @@ impl Source for CommandLineArguments {
             .transpose()
             .map_err(|e| ConfigError::Foreign(Box::new(e)))?
         {
-            if self.ignore_empty && value.is_empty() {
+            if (self.ignore_empty && value.is_empty()) || self.exclude.contains(&key) {
                 continue;
             }
 
@@ -331,4 +339,17 @@ mod test {
 
         assert!(config.is_empty());
     }
+
+    #[test]
+    fn exclude() {
+        let mut config = CommandLineArguments::default()
+            .source(["--key-a", "value-a", "--key-b=value-b"])
+            .exclude("key-a")
+            .collect()
+            .unwrap();
+
+        assert_arg!(config, "key.b", "value-b");
+
+        assert!(config.is_empty());
+    }
 }
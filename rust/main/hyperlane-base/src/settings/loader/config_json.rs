@@ -0,0 +1,270 @@
+//! A full agent config passed as a single JSON document, e.g. injected by a
+//! deployment system instead of mounting config files.
+//!
+//! The document is read from the `HYP_CONFIG_JSON` env var and the
+//! `--config-json` command line argument. Both are layered over the config
+//! files and under the individual env var and argument overrides, the
+//! argument's over the env var's, so that a later layer still overrides any
+//! value of an earlier one. The values keep the layer they're read from as
+//! their origin, which parsing errors are attributed to.
+
+use std::env;
+
+use config::{Config, ConfigError, Map, Source, Value, ValueKind};
+use convert_case::{Case, Casing};
+use eyre::{bail, ensure, Context, Result};
+use hyperlane_core::config::{ConfigParsingError, ConfigPath};
+
+use crate::settings::loader::arguments::CommandLineArguments;
+
+/// Env var the full config JSON is read from
+pub const CONFIG_JSON_ENV: &str = "HYP_CONFIG_JSON";
+/// Command line argument the full config JSON is read from
+pub const CONFIG_JSON_ARG: &str = "config-json";
+/// Largest config JSON accepted, well above the size of a config of every
+/// known chain
+pub const MAX_CONFIG_JSON_BYTES: usize = 4 * 1024 * 1024;
+
+/// A full config JSON and the layer it's read from
+#[derive(Clone, Debug)]
+pub struct ConfigJson {
+    origin: String,
+    value: serde_json::Value,
+}
+
+impl ConfigJson {
+    /// Parse the config `json` read from `origin`
+    pub fn parse(origin: &str, json: &str) -> Result<Self> {
+        if json.len() > MAX_CONFIG_JSON_BYTES {
+            bail!(
+                "{origin} is {} bytes, more than the {MAX_CONFIG_JSON_BYTES} bytes allowed",
+                json.len()
+            );
+        }
+        let value: serde_json::Value =
+            serde_json::from_str(json).with_context(|| format!("{origin} is not valid JSON"))?;
+        ensure!(value.is_object(), "{origin} must be a JSON object");
+        Ok(Self {
+            origin: origin.to_owned(),
+            value,
+        })
+    }
+
+    /// The layer the config is read from
+    pub fn origin(&self) -> &str {
+        &self.origin
+    }
+
+    /// The JSON pointer to the value at `path`, if the config sets it. Keys
+    /// are matched ignoring case, like the config layers are merged.
+    pub fn pointer(&self, path: &ConfigPath) -> Option<String> {
+        let mut value = &self.value;
+        let mut pointer = String::new();
+        for part in path.parts() {
+            let (key, next) = match value {
+                serde_json::Value::Object(object) => object
+                    .iter()
+                    .find(|(key, _)| key.to_case(Case::Flat) == part.to_case(Case::Flat))
+                    .map(|(key, next)| (key.clone(), next))?,
+                serde_json::Value::Array(array) => {
+                    let index: usize = part.parse().ok()?;
+                    (index.to_string(), array.get(index)?)
+                }
+                _ => return None,
+            };
+            pointer.push('/');
+            pointer.push_str(&key.replace('~', "~0").replace('/', "~1"));
+            value = next;
+        }
+        Some(pointer)
+    }
+}
+
+impl Source for ConfigJson {
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn collect(&self) -> Result<Map<String, Value>, ConfigError> {
+        match to_config_value(&self.origin, &self.value).kind {
+            ValueKind::Table(table) => Ok(table),
+            _ => Err(ConfigError::Message(format!(
+                "{} must be a JSON object",
+                self.origin
+            ))),
+        }
+    }
+}
+
+/// Converts JSON like the `config` crate's JSON files, so that a config reads
+/// the same from a file and from a config JSON
+fn to_config_value(origin: &str, value: &serde_json::Value) -> Value {
+    let kind = match value {
+        serde_json::Value::Null => ValueKind::Nil,
+        serde_json::Value::Bool(value) => ValueKind::Boolean(*value),
+        serde_json::Value::Number(value) => match value.as_i64() {
+            Some(value) => ValueKind::I64(value),
+            None => ValueKind::Float(value.as_f64().unwrap_or_default()),
+        },
+        serde_json::Value::String(value) => ValueKind::String(value.clone()),
+        serde_json::Value::Array(array) => ValueKind::Array(
+            array
+                .iter()
+                .map(|value| to_config_value(origin, value))
+                .collect(),
+        ),
+        serde_json::Value::Object(object) => ValueKind::Table(
+            object
+                .iter()
+                .map(|(key, value)| (key.clone(), to_config_value(origin, value)))
+                .collect(),
+        ),
+    };
+    Value::new(Some(&origin.to_owned()), kind)
+}
+
+/// The config JSONs to layer over the config files, in order: the env var's,
+/// then the command line argument's
+pub fn config_json_sources(arguments: &CommandLineArguments) -> Result<Vec<ConfigJson>> {
+    let mut sources = vec![];
+    if let Ok(json) = env::var(CONFIG_JSON_ENV) {
+        sources.push(ConfigJson::parse(CONFIG_JSON_ENV, &json)?);
+    }
+    let argument = arguments
+        .collect()
+        .context("Failed to read the command line arguments")?
+        .remove(CONFIG_JSON_ARG);
+    if let Some(argument) = argument {
+        let json = argument
+            .into_string()
+            .with_context(|| format!("--{CONFIG_JSON_ARG} must be a string"))?;
+        sources.push(ConfigJson::parse(&format!("--{CONFIG_JSON_ARG}"), &json)?);
+    }
+    Ok(sources)
+}
+
+/// Attribute each error to the layer setting the value at its path, with the
+/// JSON pointer to the value if the layer is a config JSON
+pub fn attribute_errors(
+    err: ConfigParsingError,
+    config: &Config,
+    config_jsons: &[ConfigJson],
+) -> ConfigParsingError {
+    err.with_path_context(|path| {
+        let origin = origin_at(&config.cache, path)?;
+        let pointer = config_jsons
+            .iter()
+            .find(|json| json.origin() == origin)
+            .and_then(|json| json.pointer(path));
+        Some(match pointer {
+            Some(pointer) => format!("Value set by {origin} at {pointer}"),
+            None => format!("Value set by {origin}"),
+        })
+    })
+}
+
+/// The layer setting the value at `path` of the merged config
+fn origin_at(root: &Value, path: &ConfigPath) -> Option<String> {
+    let mut value = root;
+    for part in path.parts() {
+        value = match &value.kind {
+            ValueKind::Table(table) => table.get(&part.to_case(Case::Flat))?,
+            ValueKind::Array(array) => array.get(part.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+    value.origin().map(str::to_owned)
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs::read_to_string, path::Path};
+
+    use config::File;
+    use hyperlane_core::config::FromRawConf;
+
+    use super::*;
+    use crate::settings::{loader::case_adapter::CaseAdapter, parser::RawAgentConf, Settings};
+
+    fn fixture() -> String {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/environments/mainnet_config.json");
+        read_to_string(path).unwrap()
+    }
+
+    fn load(builder: config::ConfigBuilder<config::builder::DefaultState>) -> (Config, Settings) {
+        let config = builder.build().unwrap();
+        let raw: RawAgentConf = config.clone().try_deserialize().unwrap();
+        (
+            config,
+            Settings::from_config(raw, &ConfigPath::default()).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_config_json_round_trips() {
+        env::set_var(CONFIG_JSON_ENV, fixture());
+        let sources =
+            config_json_sources(&CommandLineArguments::default().source(Vec::<String>::new()));
+        env::remove_var(CONFIG_JSON_ENV);
+        let sources = sources.unwrap();
+        assert_eq!(sources.len(), 1);
+        assert_eq!(sources[0].origin(), CONFIG_JSON_ENV);
+
+        let (config, from_json) =
+            load(Config::builder().add_source(CaseAdapter::new(sources[0].clone(), Case::Flat)));
+        let (_, from_file) = load(Config::builder().add_source(CaseAdapter::new(
+            File::from_str(&fixture(), config::FileFormat::Json),
+            Case::Flat,
+        )));
+        assert_eq!(from_json.config_fingerprint, from_file.config_fingerprint);
+        assert_eq!(from_json.environment.as_deref(), Some("mainnet3"));
+        assert_eq!(from_json.chains["ethereum"].finality_blocks, 64);
+        assert_eq!(
+            origin_at(&config.cache, &(ConfigPath::default() + "environment")).as_deref(),
+            Some(CONFIG_JSON_ENV)
+        );
+    }
+
+    #[test]
+    fn test_malformed_values_report_their_json_pointer() {
+        let mut json: serde_json::Value = serde_json::from_str(&fixture()).unwrap();
+        json["chains"]["ethereum"]["domainId"] = "one".into();
+        let arguments =
+            CommandLineArguments::default().source(["--config-json".to_owned(), json.to_string()]);
+        let sources = config_json_sources(&arguments).unwrap();
+        let argument = sources.last().unwrap();
+        assert_eq!(argument.origin(), "--config-json");
+
+        let config = Config::builder()
+            .add_source(CaseAdapter::new(argument.clone(), Case::Flat))
+            .build()
+            .unwrap();
+        let raw: RawAgentConf = config.clone().try_deserialize().unwrap();
+        let err = Settings::from_config(raw, &ConfigPath::default()).unwrap_err();
+        let err = attribute_errors(err, &config, &sources).to_string();
+        assert!(
+            err.contains("Value set by --config-json at /chains/ethereum/domainId"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_invalid_config_jsons_are_rejected() {
+        let err = |json: &str| {
+            ConfigJson::parse(CONFIG_JSON_ENV, json)
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(err("{\"chains\": "), "HYP_CONFIG_JSON is not valid JSON");
+        assert_eq!(err("[]"), "HYP_CONFIG_JSON must be a JSON object");
+        let oversized = format!("{{\"x\": \"{}\"}}", "a".repeat(MAX_CONFIG_JSON_BYTES));
+        assert_eq!(
+            err(&oversized),
+            format!(
+                "HYP_CONFIG_JSON is {} bytes, more than the {MAX_CONFIG_JSON_BYTES} bytes allowed",
+                oversized.len()
+            )
+        );
+    }
+}
@@ -22,6 +22,9 @@ pub struct Environment {
     /// Ignore empty env values (treat as unset).
     ignore_empty: bool,
 
+    /// Env vars to leave out, by their full name.
+    exclude: Vec<String>,
+
     /// Alternate source for the environment. This can be used when you want to test your own code
     /// using this source, without the need to change the actual system environment variables.
     source: Option<Map<String, String>>,
@@ -44,6 +47,11 @@ impl Environment {
         self
     }
 
+    pub fn exclude(mut self, key: &str) -> Self {
+        self.exclude.push(key.into());
+        self
+    }
+
     pub fn source<'a, I, S>(mut self, source: I) -> Self
     where
         I: IntoIterator<Item = (S, S)>,
@@ -73,6 +81,10 @@ impl Source for Environment {
         let prefix_pattern = self.prefix.as_deref().unwrap_or("");
 
         let mapper = |(key, value): (String, String)| -> Option<(String, Value)> {
+            if self.exclude.contains(&key) {
+                return None;
+            }
+
             let key = if prefix_pattern.is_empty() {
                 key
             } else if let Some(key) = key.strip_prefix(prefix_pattern) {
@@ -157,4 +169,21 @@ mod test {
 
         assert!(config.is_empty());
     }
+
+    #[test]
+    fn exclude() {
+        let mut config = Environment::default()
+            .source(ENVS.iter().cloned())
+            .exclude("PRE__KEY__A")
+            .prefix("PRE__")
+            .separator("__")
+            .collect()
+            .unwrap();
+
+        assert_env!(config, "key.b", "");
+        assert_env!(config, "KEY.C.PART_A", "value c a");
+        assert_env!(config, "KEY.C_PART_B", "value c b");
+
+        assert!(config.is_empty());
+    }
 }
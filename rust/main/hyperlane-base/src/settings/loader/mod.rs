@@ -2,29 +2,62 @@
 
 use std::{env, error::Error, fmt::Debug, path::PathBuf};
 
-use config::{Config, File};
+use config::{builder::DefaultState, Config, ConfigBuilder, File};
 use convert_case::Case;
 use eyre::{eyre, Context, Result};
 use hyperlane_core::config::*;
 use serde::de::DeserializeOwned;
 
-use crate::settings::loader::{
-    arguments::CommandLineArguments, case_adapter::CaseAdapter, environment::Environment,
+pub use self::config_json::{CONFIG_JSON_ARG, CONFIG_JSON_ENV, MAX_CONFIG_JSON_BYTES};
+use crate::settings::{
+    file_environment,
+    loader::{
+        arguments::CommandLineArguments,
+        case_adapter::CaseAdapter,
+        config_json::{attribute_errors, config_json_sources},
+        environment::Environment,
+    },
+    merged_environment, ENVIRONMENT_KEY,
 };
 
 mod arguments;
 mod case_adapter;
+mod config_json;
 mod environment;
 
 /// Deserialize a settings object from the configs.
 pub fn load_settings<T, R>() -> ConfigResult<R>
+where
+    T: DeserializeOwned + Debug,
+    R: FromRawConf<T>,
+{
+    load_settings_from_args::<T, R>(None)
+}
+
+/// Deserialize a settings object from the configs, reading command line
+/// overrides from `args` instead of the process arguments if provided.
+pub fn load_settings_from_args<T, R>(args: Option<Vec<String>>) -> ConfigResult<R>
 where
     T: DeserializeOwned + Debug,
     R: FromRawConf<T>,
 {
     let root_path = ConfigPath::default();
 
+    let mut arguments = CommandLineArguments::default().separator(".");
+    if let Some(args) = args {
+        arguments = arguments.source(args);
+    }
+    // The environment selected with `--environment` or `HYP_ENVIRONMENT`, if
+    // any, picks the default config files declaring it
+    let selected_environment = with_overrides(Config::builder(), &arguments)
+        .build()
+        .context("Failed to load config overrides")
+        .into_config_result(|| root_path.clone())?
+        .get_string(ENVIRONMENT_KEY)
+        .ok();
+
     let mut base_config_sources = vec![];
+    let mut merged_files = vec![];
     let mut builder = Config::builder();
 
     // Always load the default config files (`rust/main/config/*.json`)
@@ -32,15 +65,31 @@ where
         .read_dir()
         .context("Failed to open config directory")
         .into_config_result(|| root_path.clone())?
-        .map(Result::unwrap)
     {
-        if !entry.file_type().unwrap().is_file() {
+        let entry = entry
+            .context("Failed to read config directory")
+            .into_config_result(|| root_path.clone())?;
+        let is_file = entry
+            .file_type()
+            .with_context(|| format!("Failed to read config file {:?}", entry.path()))
+            .into_config_result(|| root_path.clone())?
+            .is_file();
+        if !is_file {
             continue;
         }
 
         let fname = entry.file_name();
-        let ext = fname.to_str().unwrap().split('.').last().unwrap_or("");
+        let ext = fname
+            .to_str()
+            .and_then(|fname| fname.split('.').last())
+            .unwrap_or("");
         if ext == "json" {
+            let environment =
+                file_environment(&entry.path()).into_config_result(|| root_path.clone())?;
+            if selected_environment.is_some() && environment != selected_environment {
+                continue;
+            }
+            merged_files.push((entry.path(), environment));
             base_config_sources.push(format!("{:?}", entry.path()));
             builder = builder.add_source(CaseAdapter::new(File::from(entry.path()), Case::Flat));
         }
@@ -55,6 +104,8 @@ where
         let p = PathBuf::from(path);
         if p.is_file() {
             if p.extension() == Some("json".as_ref()) {
+                let environment = file_environment(&p).into_config_result(|| root_path.clone())?;
+                merged_files.push((p.clone(), environment));
                 let config_file = File::from(p);
                 let re_cased_config_file = CaseAdapter::new(config_file, Case::Flat);
                 builder = builder.add_source(re_cased_config_file);
@@ -77,16 +128,30 @@ where
         }
     }
 
-    let config_deserializer = builder
-        // Use a base configuration env variable prefix
-        .add_source(CaseAdapter::new(
-            Environment::default().prefix("HYP_").separator("_"),
-            Case::Flat,
-        ))
-        .add_source(CaseAdapter::new(
-            CommandLineArguments::default().separator("."),
-            Case::Flat,
-        ))
+    // Files of different environments must not be mixed, nor be merged with
+    // the files of the selected environment
+    let environment = merged_environment(&merged_files)
+        .map_err(eyre::Report::from)
+        .into_config_result(|| root_path.clone())?;
+    if let (Some(selected), Some(declared)) = (&selected_environment, &environment) {
+        if selected != declared {
+            return Err(eyre!(
+                "Config files are for environment `{declared}`, but `{selected}` was selected"
+            ))
+            .into_config_result(|| root_path.clone());
+        }
+    }
+
+    // A full config JSON sits between the files and the individual overrides.
+    // It isn't filtered by environment: it's the config to run with.
+    let config_jsons = config_json_sources(&arguments)
+        .context("Failed to load the config JSON")
+        .into_config_result(|| root_path.clone())?;
+    for config_json in &config_jsons {
+        builder = builder.add_source(CaseAdapter::new(config_json.clone(), Case::Flat));
+    }
+
+    let config_deserializer = with_overrides(builder, &arguments)
         .build()
         .context("Failed to load config sources")
         .into_config_result(|| root_path.clone())?;
@@ -104,7 +169,7 @@ where
         }
     };
 
-    let raw_config = Config::try_deserialize::<T>(config_deserializer)
+    let raw_config = Config::try_deserialize::<T>(config_deserializer.clone())
         .or_else(|err| {
             let mut err = if let Some(source_err) = err.source() {
                 let source = format!("Config error source: {source_err}");
@@ -116,14 +181,40 @@ where
             for cfg_path in base_config_sources.iter().chain(config_file_paths.iter()) {
                 err = err.with_context(|| format!("Config loaded: {cfg_path}"));
             }
+            for config_json in &config_jsons {
+                err = err.with_context(|| format!("Config loaded: {}", config_json.origin()));
+            }
             eprintln!("Loaded config for debugging: {formatted_config}");
             err.context("Config deserialization error, please check the config reference (https://docs.hyperlane.xyz/docs/operators/agent-configuration/configuration-reference)")
         })
         .into_config_result(|| root_path.clone())?;
 
-    let res = raw_config.parse_config(&root_path);
+    let res = raw_config
+        .parse_config(&root_path)
+        .map_err(|err| attribute_errors(err, &config_deserializer, &config_jsons));
     if res.is_err() {
         eprintln!("Loaded config for debugging: {formatted_config}");
     }
     res
 }
+
+/// Add the sources overriding the config files and config JSONs: env vars
+/// with a base configuration prefix, then the command line arguments. The
+/// config JSONs themselves aren't overrides.
+fn with_overrides(
+    builder: ConfigBuilder<DefaultState>,
+    arguments: &CommandLineArguments,
+) -> ConfigBuilder<DefaultState> {
+    builder
+        .add_source(CaseAdapter::new(
+            Environment::default()
+                .prefix("HYP_")
+                .separator("_")
+                .exclude(CONFIG_JSON_ENV),
+            Case::Flat,
+        ))
+        .add_source(CaseAdapter::new(
+            arguments.clone().exclude(CONFIG_JSON_ARG),
+            Case::Flat,
+        ))
+}
@@ -61,10 +61,25 @@
 //!    E.g. `export HYP_CHAINS_ARBITRUM_DOMAINID=3000`
 //! 5. Arguments passed to the agent on the command line.
 //!    E.g. `--originChainName ethereum`
+//!
+//! ### Environments
+//!
+//! Config files may declare the environment they're for, e.g.
+//! `"environment": "mainnet3"`. All the files merged into one agent's config
+//! must declare the same environment, or none at all. Passing
+//! `--environment mainnet3` (or `HYP_ENVIRONMENT=mainnet3`) only loads the
+//! files of the `config` directory declaring that environment, and the
+//! agent's database is tied to the environment it was first run with.
 
+pub use alerts::*;
 pub use base::*;
 pub use chains::*;
 pub use checkpoint_syncer::*;
+pub use client_cache::*;
+pub use consistency::*;
+pub use environment::*;
+pub use fingerprint::*;
+pub use human::*;
 pub use signers::*;
 pub use trace::*;
 
@@ -75,11 +90,23 @@ mod envs {
     pub use hyperlane_sealevel as h_sealevel;
 }
 
+/// Alert sink configuration
+mod alerts;
 /// AWS Credentials provider.
 pub(crate) mod aws_credentials;
 mod base;
 /// Chain configuration
 mod chains;
+/// Shared contract clients
+mod client_cache;
+/// Cross-file config consistency checks
+mod consistency;
+/// Environments config files and databases belong to
+mod environment;
+/// Config hashing for change auditing
+mod fingerprint;
+/// Human-friendly durations and sizes
+mod human;
 pub mod loader;
 /// Signer configuration
 mod signers;
@@ -102,6 +129,12 @@ macro_rules! impl_loadable_from_settings {
             fn load() -> hyperlane_core::config::ConfigResult<Self> {
                 hyperlane_base::settings::loader::load_settings::<$settingsparser, Self>()
             }
+
+            fn load_from_args(args: Vec<String>) -> hyperlane_core::config::ConfigResult<Self> {
+                hyperlane_base::settings::loader::load_settings_from_args::<$settingsparser, Self>(
+                    Some(args),
+                )
+            }
         }
     };
 }
@@ -68,10 +68,55 @@ pub fn build_ethereum_connection_conf(
         })
         .unwrap_or_default();
 
+    let private_submission = chain
+        .get_opt_key("privateSubmission")
+        .take_err(err, || &chain.cwp + "private_submission")
+        .flatten()
+        .and_then(|value_parser| {
+            let url = value_parser
+                .chain(err)
+                .get_key("url")
+                .parse_from_str("Invalid url")
+                .end();
+            let mode = match value_parser
+                .chain(err)
+                .get_opt_key("mode")
+                .parse_string()
+                .unwrap_or("rpc")
+            {
+                "rpc" => Some(h_eth::PrivateSubmissionMode::Rpc),
+                "bundle" => Some(h_eth::PrivateSubmissionMode::Bundle),
+                mode => Err(eyre!("unknown private submission mode `{mode}`"))
+                    .take_err(err, || &value_parser.cwp + "mode"),
+            };
+            let fallback_after_blocks = value_parser
+                .chain(err)
+                .get_opt_key("fallbackAfterBlocks")
+                .parse_u32()
+                .unwrap_or(h_eth::DEFAULT_PRIVATE_SUBMISSION_FALLBACK_BLOCKS);
+            Some(h_eth::PrivateSubmissionConf {
+                url: url?,
+                mode: mode?,
+                fallback_after_blocks,
+            })
+        });
+
+    let get_logs_caps = chain
+        .chain(err)
+        .get_opt_key("getLogsCaps")
+        .into_obj_iter()
+        .map(|caps| {
+            caps.filter_map(|(host, cap)| Some((host, cap.chain(err).parse_u32().end()?)))
+                .collect()
+        })
+        .unwrap_or_default();
+
     Some(ChainConnectionConf::Ethereum(h_eth::ConnectionConf {
         rpc_connection: rpc_connection_conf?,
         transaction_overrides,
         operation_batch,
+        private_submission,
+        get_logs_caps,
     }))
 }
 
@@ -96,20 +141,13 @@ pub fn build_cosmos_connection_conf(
         });
 
     let prefix = chain
-        .chain(err)
+        .chain(&mut local_err)
         .get_key("bech32Prefix")
         .parse_string()
-        .end()
-        .or_else(|| {
-            local_err.push(
-                &chain.cwp + "bech32Prefix",
-                eyre!("Missing bech32 prefix for chain"),
-            );
-            None
-        });
+        .end();
 
     let canonical_asset = if let Some(asset) = chain
-        .chain(err)
+        .chain(&mut local_err)
         .get_opt_key("canonicalAsset")
         .parse_string()
         .end()
@@ -126,35 +164,55 @@ pub fn build_cosmos_connection_conf(
     };
 
     let gas_price = chain
-        .chain(err)
-        .get_opt_key("gasPrice")
+        .chain(&mut local_err)
+        .get_key("gasPrice")
         .and_then(parse_cosmos_gas_price)
         .end();
 
     let contract_address_bytes = chain
-        .chain(err)
-        .get_opt_key("contractAddressBytes")
+        .chain(&mut local_err)
+        .get_key("contractAddressBytes")
         .parse_u64()
-        .end();
+        .end()
+        .and_then(|bytes| {
+            usize::try_from(bytes)
+                .take_err(&mut local_err, || &chain.cwp + "contract_address_bytes")
+        });
 
     let native_token = parse_native_token(chain, err, 18);
 
     if !local_err.is_ok() {
         err.merge(local_err);
-        None
-    } else {
-        Some(ChainConnectionConf::Cosmos(h_cosmos::ConnectionConf::new(
-            grpcs,
-            rpcs.to_owned(),
-            chain_id.unwrap().to_string(),
-            prefix.unwrap().to_string(),
-            canonical_asset.unwrap(),
-            gas_price.unwrap(),
-            contract_address_bytes.unwrap().try_into().unwrap(),
-            operation_batch,
-            native_token,
-        )))
+        return None;
     }
+    // every missing value has been reported above
+    let (
+        Some(chain_id),
+        Some(prefix),
+        Some(canonical_asset),
+        Some(gas_price),
+        Some(contract_address_bytes),
+    ) = (
+        chain_id,
+        prefix,
+        canonical_asset,
+        gas_price,
+        contract_address_bytes,
+    )
+    else {
+        return None;
+    };
+    Some(ChainConnectionConf::Cosmos(h_cosmos::ConnectionConf::new(
+        grpcs,
+        rpcs.to_owned(),
+        chain_id.to_string(),
+        prefix.to_string(),
+        canonical_asset,
+        gas_price,
+        contract_address_bytes,
+        operation_batch,
+        native_token,
+    )))
 }
 
 fn build_sealevel_connection_conf(
@@ -1,4 +1,4 @@
-use std::{fmt::Debug, str::FromStr};
+use std::{fmt::Debug, str::FromStr, time::Duration};
 
 use convert_case::{Case, Casing};
 use derive_new::new;
@@ -8,6 +8,8 @@ use itertools::Itertools;
 use serde::de::{DeserializeOwned, StdError};
 use serde_json::Value;
 
+use crate::settings::{BareNumbers, DurationUnit, HumanDuration, HumanSize};
+
 #[allow(unused_imports)] // TODO: `rustc` 1.80.1 clippy issue
 pub use super::super::envs::*;
 
@@ -18,6 +20,10 @@ pub struct ValueParser<'v> {
     pub cwp: ConfigPath,
     /// Reference to the serde JSON value.
     pub val: &'v Value,
+    /// Fields set to a bare number, shared with the parsers of other values
+    /// of the same config.
+    #[new(default)]
+    pub bare_numbers: BareNumbers,
 }
 
 impl<'v> ValueParser<'v> {
@@ -26,6 +32,16 @@ impl<'v> ValueParser<'v> {
         ParseChain(Some(self.clone()), err)
     }
 
+    /// Create a parser for a value of the same config found outside of this
+    /// one, e.g. JSON embedded in a string.
+    pub fn with_value<'n>(&self, cwp: ConfigPath, val: &'n Value) -> ValueParser<'n> {
+        ValueParser {
+            cwp,
+            val,
+            bare_numbers: self.bare_numbers.clone(),
+        }
+    }
+
     /// Get a value at the given key and verify that it is present.
     pub fn get_key(&self, key: &str) -> ConfigResult<ValueParser<'v>> {
         self.get_opt_key(&key.to_case(Case::Flat))?
@@ -37,10 +53,9 @@ impl<'v> ValueParser<'v> {
     pub fn get_opt_key(&self, key: &str) -> ConfigResult<Option<ValueParser<'v>>> {
         let cwp = &self.cwp + key.to_case(Case::Snake);
         match self.val {
-            Value::Object(obj) => Ok(obj.get(&key.to_case(Case::Flat)).map(|val| Self {
-                val,
-                cwp: cwp.clone(),
-            })),
+            Value::Object(obj) => Ok(obj
+                .get(&key.to_case(Case::Flat))
+                .map(|val| self.with_value(cwp.clone(), val))),
             _ => Err(eyre!("Expected an object type")),
         }
         .into_config_result(|| cwp)
@@ -51,15 +66,12 @@ impl<'v> ValueParser<'v> {
     pub fn into_obj_iter(
         self,
     ) -> ConfigResult<impl Iterator<Item = (String, ValueParser<'v>)> + 'v> {
-        let cwp = self.cwp.clone();
+        let parent = self.clone();
         match self.val {
             Value::Object(obj) => Ok(obj.iter().map(move |(k, v)| {
                 (
                     k.clone(),
-                    Self {
-                        val: v,
-                        cwp: &cwp + k.to_case(Case::Snake),
-                    },
+                    parent.with_value(&parent.cwp + k.to_case(Case::Snake), v),
                 )
             })),
             _ => Err(eyre!("Expected an object type")),
@@ -69,13 +81,13 @@ impl<'v> ValueParser<'v> {
 
     /// Create an iterator over all array elements.
     pub fn into_array_iter(self) -> ConfigResult<impl Iterator<Item = ValueParser<'v>>> {
-        let cwp = self.cwp.clone();
+        let parent = self.clone();
 
         match self.val {
-            Value::Array(arr) => Ok(arr.iter().enumerate().map(move |(i, v)| Self {
-                val: v,
-                cwp: &cwp + i.to_string(),
-            }))
+            Value::Array(arr) => Ok(arr
+                .iter()
+                .enumerate()
+                .map(move |(i, v)| parent.with_value(&parent.cwp + i.to_string(), v)))
             .map(|itr| Box::new(itr) as Box<dyn Iterator<Item = ValueParser<'v>>>),
             Value::Object(obj) => obj
                 .iter()
@@ -98,10 +110,7 @@ impl<'v> ValueParser<'v> {
                 })
                 // convert to an iterator of value parsers over the values
                 .map(|itr| {
-                    itr.map(move |(i, v)| Self {
-                        val: v,
-                        cwp: &cwp + i.to_string(),
-                    })
+                    itr.map(move |(i, v)| parent.with_value(&parent.cwp + i.to_string(), v))
                 })
                 .map(|itr| Box::new(itr) as Box<dyn Iterator<Item = ValueParser<'v>>>),
             _ => Err(eyre!("Expected an array type")),
@@ -225,6 +234,55 @@ impl<'v> ValueParser<'v> {
         .into_config_result(|| self.cwp.clone())
     }
 
+    /// Parse a boolean flag, which is also set by passing it as a command
+    /// line argument without a value, e.g. `--takeover`.
+    pub fn parse_flag(&self) -> ConfigResult<bool> {
+        match self.val {
+            Value::String(s) if s.is_empty() => Ok(true),
+            _ => self.parse_bool(),
+        }
+    }
+
+    /// Parse a duration like `"30s"`, `"5m"` or `"2h"`. Bare numbers, which
+    /// are ambiguous, are read in `unit` and recorded in `bare_numbers`.
+    pub fn parse_duration(&self, unit: DurationUnit) -> ConfigResult<Duration> {
+        match self.bare_number()? {
+            Some(amount) => {
+                self.bare_numbers.record(&self.cwp, unit.name());
+                Ok(unit.duration(amount))
+            }
+            None => self
+                .parse_from_str::<HumanDuration>("Expected a duration like `30s`, `5m` or `2h`")
+                .map(Duration::from),
+        }
+    }
+
+    /// Parse a size like `"512kb"` or `"10mb"`. Bare numbers, which are
+    /// ambiguous, are read in bytes and recorded in `bare_numbers`.
+    pub fn parse_size(&self) -> ConfigResult<u64> {
+        match self.bare_number()? {
+            Some(amount) => {
+                self.bare_numbers.record(&self.cwp, "bytes");
+                Ok(amount)
+            }
+            None => self
+                .parse_from_str::<HumanSize>("Expected a size like `512kb` or `10mb`")
+                .map(u64::from),
+        }
+    }
+
+    /// The value if it's a number without a unit, which env vars and command
+    /// line arguments pass as a string
+    fn bare_number(&self) -> ConfigResult<Option<u64>> {
+        match self.val {
+            Value::Number(_) => self.parse_u64().map(Some),
+            Value::String(s) if !s.is_empty() && s.chars().all(|c| c.is_ascii_digit()) => {
+                self.parse_u64().map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
     /// Parse a string value.
     pub fn parse_string(&self) -> ConfigResult<&'v str> {
         match self.val {
@@ -307,12 +365,21 @@ define_basic_parse!(
     parse_i32: i32,
     parse_u256: U256,
     parse_bool: bool,
+    parse_flag: bool,
     parse_string: &'v str,
     parse_address_hash: H256,
     parse_private_key: H256
 );
 
 impl<'v, 'e> ParseChain<'e, ValueParser<'v>> {
+    pub fn parse_duration(self, unit: DurationUnit) -> ParseChain<'e, Duration> {
+        self.and_then(|v| v.parse_duration(unit))
+    }
+
+    pub fn parse_size(self) -> ParseChain<'e, u64> {
+        self.and_then(|v| v.parse_size())
+    }
+
     pub fn get_key(self, key: &str) -> Self {
         self.and_then(|v| v.get_key(key))
     }
@@ -394,6 +461,8 @@ impl<'e, T: Default> ParseChain<'e, T> {
 
 #[cfg(test)]
 mod test {
+    use std::collections::BTreeMap;
+
     use super::*;
 
     #[test]
@@ -413,4 +482,82 @@ mod test {
         let value_parser = ValueParser::new(Default::default(), &numeric_value);
         assert_eq!(num_u256, value_parser.parse_u256().unwrap());
     }
+
+    #[test]
+    fn test_duration_and_size_value_parsing() {
+        let config = serde_json::json!({
+            "ttl": "5m",
+            "pollinterval": "500ms",
+            "maxbody": "10mb",
+            "legacyttl": 30,
+            "legacypollinterval": "250",
+            "legacymaxbody": 1024,
+        });
+        // keys are flat-cased by the loader
+        let p = ValueParser::new(ConfigPath::default().join("durationtest"), &config);
+        let duration = |key, unit| p.get_key(key).unwrap().parse_duration(unit).unwrap();
+        let size = |key| p.get_key(key).unwrap().parse_size().unwrap();
+
+        assert_eq!(
+            duration("ttl", DurationUnit::Seconds),
+            Duration::from_secs(300)
+        );
+        assert_eq!(
+            duration("pollInterval", DurationUnit::Millis),
+            Duration::from_millis(500)
+        );
+        assert_eq!(size("maxBody"), 10 * 1024 * 1024);
+        // bare numbers, as numbers or strings, are read in the field's unit
+        assert_eq!(
+            duration("legacyTtl", DurationUnit::Seconds),
+            Duration::from_secs(30)
+        );
+        assert_eq!(
+            duration("legacyPollInterval", DurationUnit::Millis),
+            Duration::from_millis(250)
+        );
+        assert_eq!(size("legacyMaxBody"), 1024);
+
+        let invalid = Value::String("5 minutes".to_owned());
+        assert!(ValueParser::new(Default::default(), &invalid)
+            .parse_duration(DurationUnit::Seconds)
+            .is_err());
+    }
+
+    #[test]
+    fn test_bare_numbers_are_recorded_once_per_config() {
+        let config = serde_json::json!({ "windowseconds": 60, "window": "1m" });
+        let embedded = serde_json::json!({ "maxbody": 1024 });
+        let p = ValueParser::new(ConfigPath::default().join("baretest"), &config);
+        let parse = || {
+            for key in ["windowSeconds", "window"] {
+                p.get_key(key)
+                    .unwrap()
+                    .parse_duration(DurationUnit::Seconds)
+                    .unwrap();
+            }
+        };
+
+        // parsing the same field again records it once
+        parse();
+        parse();
+        // values found outside the config's value, e.g. embedded JSON, are
+        // recorded with it
+        p.with_value(ConfigPath::default().join("embedded"), &embedded)
+            .get_key("maxBody")
+            .unwrap()
+            .parse_size()
+            .unwrap();
+        assert_eq!(
+            p.bare_numbers.fields(),
+            BTreeMap::from([
+                ("baretest.windowSeconds".to_owned(), "seconds"),
+                ("embedded.maxBody".to_owned(), "bytes"),
+            ])
+        );
+
+        // another config records its own
+        let other = ValueParser::new(ConfigPath::default().join("baretest"), &config);
+        assert!(other.bare_numbers.fields().is_empty());
+    }
 }
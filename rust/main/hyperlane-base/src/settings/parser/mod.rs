@@ -5,8 +5,9 @@
 //! ANY CHANGES HERE NEED TO BE REFLECTED IN THE TYPESCRIPT SDK.
 
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     default::Default,
+    time::Duration,
 };
 
 use convert_case::{Case, Casing};
@@ -16,15 +17,21 @@ use serde::Deserialize;
 use serde_json::Value;
 use url::Url;
 
+use ethers_prometheus::exemplar::DEFAULT_EXEMPLAR_SAMPLE_RATE;
 use h_cosmos::RawCosmosAmount;
 use hyperlane_core::{
-    cfg_unwrap_all, config::*, HyperlaneDomain, HyperlaneDomainProtocol,
-    HyperlaneDomainTechnicalStack, IndexMode, ReorgPeriod,
+    accumulator::TREE_DEPTH, cfg_unwrap_all, config::*, DomainRegistry, HyperlaneDomain,
+    HyperlaneDomainProtocol, HyperlaneDomainTechnicalStack, IndexMode, KnownHyperlaneDomain,
+    ReorgPeriod, DEFAULT_FINALITY_BLOCKS,
 };
 
 use crate::settings::{
-    chains::IndexSettings, parser::connection_parser::build_connection_conf, trace::TracingConfig,
-    ChainConf, CoreContractAddresses, Settings, SignerConf,
+    chains::{AdditionalIgpConf, IndexSettings},
+    consistency::chain_domain_issues,
+    parser::connection_parser::build_connection_conf,
+    trace::TracingConfig,
+    AlertConf, AlertSinkConf, ChainConf, ConfigFingerprint, CoreContractAddresses, Settings,
+    SignerConf, ENVIRONMENT_KEY, PAGER_DUTY_EVENTS_URL,
 };
 
 pub use super::envs::*;
@@ -51,12 +58,33 @@ impl FromRawConf<RawAgentConf, Option<&HashSet<&str>>> for Settings {
 
         let p = ValueParser::new(cwp.clone(), &raw.0);
 
+        let environment = p
+            .chain(&mut err)
+            .get_opt_key(ENVIRONMENT_KEY)
+            .parse_string()
+            .end()
+            .map(str::to_owned);
+
         let metrics_port = p
             .chain(&mut err)
             .get_opt_key("metricsPort")
             .parse_u16()
             .unwrap_or(9090);
 
+        let exemplar_sample_rate = p
+            .chain(&mut err)
+            .get_opt_key("exemplarSampleRate")
+            .parse_f64()
+            .and_then(|rate| {
+                if (0.0..=1.0).contains(&rate) {
+                    Ok(rate)
+                } else {
+                    Err(eyre!("Expected a share of observations between 0 and 1"))
+                }
+                .into_config_result(|| cwp + "exemplar_sample_rate")
+            })
+            .unwrap_or(DEFAULT_EXEMPLAR_SAMPLE_RATE);
+
         let fmt = p
             .chain(&mut err)
             .get_opt_key("log")
@@ -71,6 +99,14 @@ impl FromRawConf<RawAgentConf, Option<&HashSet<&str>>> for Settings {
             .parse_value("Invalid log level")
             .unwrap_or_default();
 
+        let field_budget = p
+            .chain(&mut err)
+            .get_opt_key("log")
+            .get_opt_key("fieldBudgetBytes")
+            .parse_size()
+            .map(|bytes| bytes as usize)
+            .end();
+
         let raw_chains: Vec<(String, ValueParser)> = if let Some(filter) = filter {
             p.chain(&mut err)
                 .get_opt_key("chains")
@@ -96,7 +132,8 @@ impl FromRawConf<RawAgentConf, Option<&HashSet<&str>>> for Settings {
             .parse_string()
             .unwrap_or("fallback");
 
-        let chains: HashMap<String, ChainConf> = raw_chains
+        let mut deprecated_domain_names = BTreeMap::new();
+        let chains: HashMap<String, ChainConf> = consistent_chains(raw_chains, cwp, &mut err)
             .into_iter()
             .filter_map(|(name, chain)| {
                 parse_chain(chain, &name, default_rpc_consensus_type)
@@ -107,16 +144,179 @@ impl FromRawConf<RawAgentConf, Option<&HashSet<&str>>> for Settings {
                 if let Some(default_signer) = &default_signer {
                     chain.signer.get_or_insert_with(|| default_signer.clone());
                 }
-                (name, chain)
+                // chains configured under a deprecated domain name are keyed
+                // by the name of the domain they resolved to
+                match KnownHyperlaneDomain::from_deprecated_name(&name) {
+                    Some(domain) if chain.domain == domain.into() => {
+                        deprecated_domain_names.insert(name, domain);
+                        (domain.as_str().to_owned(), chain)
+                    }
+                    _ => (name, chain),
+                }
             })
             .collect();
 
+        // chains this release doesn't know about are resolved like the known
+        // ones, as long as no two of them claim the same name or domain id
+        let mut domains = DomainRegistry::new();
+        for (name, chain) in chains.iter().sorted_by_key(|(name, _)| *name) {
+            domains
+                .register_domain(chain.domain.clone())
+                .take_err(&mut err, || cwp + "chains" + name + "domainId");
+        }
+
+        let alerts = parse_alerts(&p, &mut err);
+
         err.into_result(Self {
+            environment,
             chains,
+            domains,
             metrics_port,
-            tracing: TracingConfig { fmt, level },
+            exemplar_sample_rate,
+            tracing: TracingConfig {
+                fmt,
+                level,
+                field_budget,
+            },
+            alerts,
+            config_fingerprint: ConfigFingerprint::new(&raw.0),
+            deprecated_domain_names,
+            bare_numbers: p.bare_numbers.fields(),
+        })
+    }
+}
+
+/// Check the name and domain id of each chain entry against each other and
+/// the known domains before parsing them, so that every inconsistent entry is
+/// reported in a single error. Returns the consistent entries; ones missing a
+/// name or domain id are left for parsing to report.
+fn consistent_chains<'a>(
+    raw_chains: Vec<(String, ValueParser<'a>)>,
+    cwp: &ConfigPath,
+    err: &mut ConfigParsingError,
+) -> Vec<(String, ValueParser<'a>)> {
+    let mut issues = vec![];
+    let consistent = raw_chains
+        .into_iter()
+        .filter(|(key, chain)| {
+            let mut missing = ConfigParsingError::default();
+            let name = chain
+                .chain(&mut missing)
+                .get_key("name")
+                .parse_string()
+                .end();
+            let domain = chain
+                .chain(&mut missing)
+                .get_opt_key("domainId")
+                .parse_u32()
+                .end()
+                .or_else(|| {
+                    chain
+                        .chain(&mut missing)
+                        .get_key("chainId")
+                        .parse_u32()
+                        .end()
+                });
+            let (Some(name), Some(domain)) = (name, domain) else {
+                return true;
+            };
+            let chain_issues = chain_domain_issues(key, name, domain);
+            let is_consistent = chain_issues.is_empty();
+            issues.extend(chain_issues);
+            is_consistent
         })
+        .collect();
+    if !issues.is_empty() {
+        err.push(
+            cwp + "chains",
+            eyre!(
+                "Chain names and domain ids are inconsistent:\n{}",
+                issues.iter().map(|issue| format!("  - {issue}")).join("\n")
+            ),
+        );
     }
+    consistent
+}
+
+fn parse_alerts(p: &ValueParser, err: &mut ConfigParsingError) -> AlertConf {
+    let default = AlertConf::default();
+    let sinks = p
+        .chain(err)
+        .get_opt_key("alerts")
+        .get_opt_key("sinks")
+        .into_array_iter()
+        .map(|sinks| {
+            sinks
+                .filter_map(|sink| parse_alert_sink(sink).take_config_err(err))
+                .collect()
+        })
+        .unwrap_or(default.sinks);
+    let rate_limit = p
+        .chain(err)
+        .get_opt_key("alerts")
+        .get_opt_key("rateLimitMinutes")
+        .parse_u64()
+        .map(|minutes| Duration::from_secs(minutes * 60))
+        .unwrap_or(default.rate_limit);
+
+    AlertConf { sinks, rate_limit }
+}
+
+fn parse_alert_sink(sink: ValueParser) -> ConfigResult<AlertSinkConf> {
+    let mut err = ConfigParsingError::default();
+
+    let sink_type = sink.chain(&mut err).get_key("type").parse_string().end();
+
+    let alert_sink = match sink_type {
+        Some("webhook") => {
+            let url = sink
+                .chain(&mut err)
+                .get_key("url")
+                .parse_from_str::<Url>("Invalid alert sink url")
+                .end();
+            let body_template = sink
+                .chain(&mut err)
+                .get_opt_key("bodyTemplate")
+                .parse_string()
+                .end()
+                .map(str::to_owned);
+            url.map(|url| AlertSinkConf::Webhook { url, body_template })
+        }
+        Some("slack") => sink
+            .chain(&mut err)
+            .get_key("url")
+            .parse_from_str::<Url>("Invalid alert sink url")
+            .end()
+            .map(|url| AlertSinkConf::Slack { url }),
+        Some("pagerDuty") => {
+            let url = match sink
+                .chain(&mut err)
+                .get_opt_key("url")
+                .parse_from_str::<Url>("Invalid alert sink url")
+                .end()
+            {
+                Some(url) => Some(url),
+                None => PAGER_DUTY_EVENTS_URL
+                    .parse::<Url>()
+                    .take_err(&mut err, || &sink.cwp + "url"),
+            };
+            let routing_key = sink
+                .chain(&mut err)
+                .get_key("routingKey")
+                .parse_string()
+                .end()
+                .map(str::to_owned);
+            url.zip(routing_key)
+                .map(|(url, routing_key)| AlertSinkConf::PagerDuty { url, routing_key })
+        }
+        Some(t) => {
+            Err(eyre!("Unknown alert sink type `{t}`")).take_err(&mut err, || &sink.cwp + "type")
+        }
+        None => None,
+    };
+
+    cfg_unwrap_all!(&sink.cwp, err: [alert_sink]);
+    err.into_result(alert_sink)
 }
 
 /// The chain name and ChainMetadata
@@ -137,9 +337,29 @@ fn parse_chain(
     let reorg_period = chain
         .chain(&mut err)
         .get_opt_key("blocks")
-        .get_key("reorgPeriod")
+        .get_opt_key("reorgPeriod")
         .parse_value("Invalid reorgPeriod")
+        .end()
+        .or_else(|| {
+            domain
+                .as_ref()
+                .and_then(HyperlaneDomain::recommended_reorg_period)
+        })
         .unwrap_or(ReorgPeriod::from_blocks(1));
+    let reorg_period_fallback = chain
+        .chain(&mut err)
+        .get_opt_key("blocks")
+        .get_opt_key("reorgPeriodFallback")
+        .parse_u32()
+        .end();
+    let finality_blocks = chain
+        .chain(&mut err)
+        .get_opt_key("blocks")
+        .get_opt_key("finalityBlocks")
+        .parse_u32()
+        .end()
+        .or_else(|| domain.as_ref().map(HyperlaneDomain::finality_blocks))
+        .unwrap_or(DEFAULT_FINALITY_BLOCKS);
 
     let rpcs = parse_base_and_override_urls(&chain, "rpcUrls", "customRpcUrls", "http", &mut err);
 
@@ -204,6 +424,44 @@ fn parse_chain(
         .parse_u32()
         .unwrap_or(1);
 
+    let additional_interchain_gas_paymasters = chain
+        .chain(&mut err)
+        .get_opt_key("additionalInterchainGasPaymasters")
+        .into_array_iter()
+        .map(|igps| {
+            igps.filter_map(|igp| {
+                let address = igp
+                    .chain(&mut err)
+                    .get_key("address")
+                    .parse_address_hash()
+                    .end();
+                let from = igp
+                    .chain(&mut err)
+                    .get_opt_key("from")
+                    .parse_u32()
+                    .unwrap_or(0);
+                address.map(|address| AdditionalIgpConf { address, from })
+            })
+            .collect()
+        })
+        .unwrap_or_default();
+
+    let merkle_tree_depth = chain
+        .chain(&mut err)
+        .get_opt_key("merkleTreeDepth")
+        .parse_u32()
+        .and_then(|depth| {
+            if (1..=TREE_DEPTH as u32).contains(&depth) {
+                Ok(depth as usize)
+            } else {
+                Err(eyre!(
+                    "Merkle tree depth must be between 1 and {TREE_DEPTH}"
+                ))
+                .into_config_result(|| &chain.cwp + "merkle_tree_depth")
+            }
+        })
+        .unwrap_or(TREE_DEPTH);
+
     cfg_unwrap_all!(&chain.cwp, err: [domain]);
     let connection = build_connection_conf(
         domain.domain_protocol(),
@@ -222,6 +480,8 @@ fn parse_chain(
         domain,
         signer,
         reorg_period,
+        reorg_period_fallback,
+        finality_blocks,
         addresses: CoreContractAddresses {
             mailbox,
             interchain_gas_paymaster,
@@ -234,7 +494,10 @@ fn parse_chain(
             from,
             chunk_size,
             mode,
+            pacer: None,
         },
+        additional_interchain_gas_paymasters,
+        merkle_tree_depth,
     })
 }
 
@@ -388,8 +651,9 @@ pub fn recase_json_value(mut val: Value, case: Case) -> Value {
         Value::Object(obj) => {
             let keys = obj.keys().cloned().collect_vec();
             for key in keys {
-                let val = obj.remove(&key).unwrap();
-                obj.insert(key.to_case(case), recase_json_value(val, case));
+                if let Some(val) = obj.remove(&key) {
+                    obj.insert(key.to_case(case), recase_json_value(val, case));
+                }
             }
         }
         _ => {}
@@ -77,7 +77,8 @@ impl BuildableWithSignerConf for hyperlane_ethereum::Signers {
                 let client = KmsClient::new_with_client(
                     rusoto_core::Client::new_with(
                         AwsChainCredentialsProvider::new(),
-                        utils::http_client_with_timeout().unwrap(),
+                        utils::http_client_with_timeout()
+                            .context("Failed to create the AWS KMS http client")?,
                     ),
                     region.clone(),
                 );
@@ -1,4 +1,5 @@
 use eyre::Result;
+use hyperlane_core::log_fields::set_log_field_budget;
 pub use span_metrics::TimeSpanLifetime;
 use tracing_subscriber::{
     filter::{LevelFilter, Targets},
@@ -55,12 +56,19 @@ pub struct TracingConfig {
     pub(crate) fmt: Style,
     #[serde(default)]
     pub(crate) level: Level,
+    /// Byte budget large fields such as message bodies and metadata are
+    /// truncated to in logs
+    #[serde(default)]
+    pub(crate) field_budget: Option<usize>,
 }
 
 impl TracingConfig {
     /// Attempt to instantiate and register a tracing subscriber setup from
     /// settings.
     pub fn start_tracing(&self, metrics: &CoreMetrics) -> Result<console_subscriber::Server> {
+        if let Some(budget) = self.field_budget {
+            set_log_field_budget(budget);
+        }
         let mut target_layer = Targets::new().with_default(self.level);
 
         if self.level < Level::DependencyTrace {
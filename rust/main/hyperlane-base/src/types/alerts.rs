@@ -0,0 +1,468 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use futures_util::future::join_all;
+use prometheus::{opts, IntCounterVec};
+use reqwest::{header::CONTENT_TYPE, Client};
+use serde::Serialize;
+use serde_json::{json, Value};
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+use crate::settings::{AlertConf, AlertSinkConf};
+
+/// How long a sink gets to accept an alert before the delivery counts as failed
+const SINK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Kind of critical event an alert is raised for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertKind {
+    /// A merkle tree no longer matches the checkpoints it was checked against
+    CheckpointDivergence,
+    /// A reorg deeper than the configured reorg period was detected
+    ReorgDetected,
+    /// Submissions stopped because a spend cap was reached
+    SpendCapTripped,
+    /// A signer's balance is too low to keep submitting transactions
+    SignerBalanceCritical,
+    /// Submissions to a destination were paused because too many reverted
+    CircuitBreakerOpened,
+    /// An origin's merkle tree holds as many leaves as its depth allows
+    MerkleTreeFull,
+    /// Another process appears to send transactions with the same signer
+    ConcurrentSigner,
+}
+
+impl AlertKind {
+    /// Name of the kind, as it's serialized
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::CheckpointDivergence => "checkpoint_divergence",
+            Self::ReorgDetected => "reorg_detected",
+            Self::SpendCapTripped => "spend_cap_tripped",
+            Self::SignerBalanceCritical => "signer_balance_critical",
+            Self::CircuitBreakerOpened => "circuit_breaker_opened",
+            Self::MerkleTreeFull => "merkle_tree_full",
+            Self::ConcurrentSigner => "concurrent_signer",
+        }
+    }
+}
+
+/// A critical event to push to the configured alert sinks
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Alert {
+    /// What happened
+    pub kind: AlertKind,
+    /// Alerts with the same key are rate limited together, and sinks which
+    /// support it group them into a single incident
+    pub dedup_key: String,
+    /// Where it happened, e.g. the agent and chain
+    pub source: String,
+    /// Human readable description
+    pub summary: String,
+    /// Any structured context worth attaching
+    pub details: Value,
+}
+
+impl Alert {
+    /// Create an alert without details
+    pub fn new(
+        kind: AlertKind,
+        dedup_key: impl Into<String>,
+        source: impl Into<String>,
+        summary: impl Into<String>,
+    ) -> Self {
+        Self {
+            kind,
+            dedup_key: dedup_key.into(),
+            source: source.into(),
+            summary: summary.into(),
+            details: Value::Null,
+        }
+    }
+
+    /// Attach structured context to the alert
+    pub fn with_details(mut self, details: Value) -> Self {
+        self.details = details;
+        self
+    }
+}
+
+/// Pushes alerts to the configured sinks. Delivery is fire-and-forget: it
+/// runs in the background, and a sink that is slow or down only bumps the
+/// `alert_sink_failures` counter.
+#[derive(Debug, Clone)]
+pub struct AlertDispatcher {
+    sinks: Arc<Vec<AlertSinkConf>>,
+    client: Client,
+    rate_limiter: Arc<Mutex<RateLimiter>>,
+    sink_failures: IntCounterVec,
+}
+
+impl Default for AlertDispatcher {
+    /// A dispatcher without sinks, which drops every alert
+    fn default() -> Self {
+        let sink_failures = IntCounterVec::new(
+            opts!("alert_sink_failures", "Alerts a sink failed to accept"),
+            &["sink"],
+        )
+        .expect("Valid metric definition");
+        Self::new(&AlertConf::default(), sink_failures)
+    }
+}
+
+impl AlertDispatcher {
+    /// Create a dispatcher for the configured sinks, counting failed
+    /// deliveries by sink
+    pub fn new(conf: &AlertConf, sink_failures: IntCounterVec) -> Self {
+        Self {
+            sinks: Arc::new(conf.sinks.clone()),
+            client: Client::new(),
+            rate_limiter: Arc::new(Mutex::new(RateLimiter::new(conf.rate_limit))),
+            sink_failures,
+        }
+    }
+
+    /// Send an alert to every sink in the background, unless one with the
+    /// same dedup key was sent within the rate limit window
+    pub fn dispatch(&self, alert: Alert) {
+        self.spawn_deliveries(alert);
+    }
+
+    /// Like `dispatch`, but waits until every sink accepted the alert or
+    /// timed out. Meant for right before the agent deliberately stops, when
+    /// a background delivery would be cut short.
+    pub async fn dispatch_and_wait(&self, alert: Alert) {
+        join_all(self.spawn_deliveries(alert)).await;
+    }
+
+    fn spawn_deliveries(&self, alert: Alert) -> Vec<JoinHandle<()>> {
+        if self.sinks.is_empty() {
+            return vec![];
+        }
+        let allowed = self
+            .rate_limiter
+            .lock()
+            .expect("Alert rate limiter lock poisoned")
+            .allow(&alert.dedup_key, Instant::now());
+        if !allowed {
+            debug!(?alert, "Alert rate limited");
+            return vec![];
+        }
+        self.sinks
+            .iter()
+            .map(|sink| {
+                let sink_name = sink.name();
+                let request = self
+                    .client
+                    .post(sink.url().clone())
+                    .header(CONTENT_TYPE, "application/json")
+                    .body(payload(sink, &alert))
+                    .timeout(SINK_TIMEOUT);
+                let failures = self.sink_failures.with_label_values(&[sink_name]);
+                let dedup_key = alert.dedup_key.clone();
+                tokio::spawn(async move {
+                    match request.send().await.and_then(|r| r.error_for_status()) {
+                        Ok(_) => debug!(sink = sink_name, %dedup_key, "Delivered alert"),
+                        Err(err) => {
+                            failures.inc();
+                            warn!(sink = sink_name, %dedup_key, ?err, "Failed to deliver alert");
+                        }
+                    }
+                })
+            })
+            .collect()
+    }
+}
+
+/// Body of the request delivering an alert to a sink
+fn payload(sink: &AlertSinkConf, alert: &Alert) -> String {
+    match sink {
+        AlertSinkConf::Webhook {
+            body_template: Some(template),
+            ..
+        } => render_template(template, alert),
+        AlertSinkConf::Webhook { .. } => json!(alert).to_string(),
+        AlertSinkConf::Slack { .. } => json!({
+            "text": format!(
+                "[critical] {} on {}: {} ({})",
+                alert.kind.as_str(),
+                alert.source,
+                alert.summary,
+                alert.dedup_key
+            ),
+        })
+        .to_string(),
+        AlertSinkConf::PagerDuty { routing_key, .. } => json!({
+            "routing_key": routing_key,
+            "event_action": "trigger",
+            "dedup_key": alert.dedup_key,
+            "payload": {
+                "summary": alert.summary,
+                "source": alert.source,
+                "severity": "critical",
+                "component": alert.kind.as_str(),
+                "custom_details": alert.details,
+            },
+        })
+        .to_string(),
+    }
+}
+
+/// Fill in a webhook body template. Strings are escaped so they can be
+/// placed inside JSON string literals, details are inserted as raw JSON.
+fn render_template(template: &str, alert: &Alert) -> String {
+    let escape = |s: &str| {
+        let quoted = Value::String(s.to_owned()).to_string();
+        quoted[1..quoted.len() - 1].to_owned()
+    };
+    template
+        .replace("{{kind}}", alert.kind.as_str())
+        .replace("{{dedup_key}}", &escape(&alert.dedup_key))
+        .replace("{{source}}", &escape(&alert.source))
+        .replace("{{summary}}", &escape(&alert.summary))
+        .replace("{{details}}", &alert.details.to_string())
+}
+
+/// Lets an alert through at most once per window for each dedup key
+#[derive(Debug)]
+struct RateLimiter {
+    window: Duration,
+    last_sent: HashMap<String, Instant>,
+}
+
+impl RateLimiter {
+    fn new(window: Duration) -> Self {
+        Self {
+            window,
+            last_sent: HashMap::new(),
+        }
+    }
+
+    fn allow(&mut self, dedup_key: &str, now: Instant) -> bool {
+        let window = self.window;
+        self.last_sent
+            .retain(|_, sent| now.saturating_duration_since(*sent) < window);
+        if self.last_sent.contains_key(dedup_key) {
+            return false;
+        }
+        self.last_sent.insert(dedup_key.to_owned(), now);
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::SocketAddr;
+
+    use axum::{http::StatusCode, routing::post, Json, Router};
+    use tokio::{
+        sync::mpsc::{self, UnboundedReceiver},
+        time::timeout,
+    };
+    use url::Url;
+
+    use super::*;
+
+    /// An HTTP endpoint recording the alerts posted to it and answering with
+    /// `status`
+    fn mock_sink(status: StatusCode) -> (Url, UnboundedReceiver<Value>) {
+        let (received, receiver) = mpsc::unbounded_channel();
+        let app = Router::new().route(
+            "/",
+            post(move |Json(body): Json<Value>| {
+                let received = received.clone();
+                async move {
+                    received.send(body).unwrap();
+                    status
+                }
+            }),
+        );
+        let server = axum::Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0)))
+            .serve(app.into_make_service());
+        let url = format!("http://{}/", server.local_addr()).parse().unwrap();
+        tokio::spawn(server);
+        (url, receiver)
+    }
+
+    /// An HTTP endpoint that never answers
+    fn hanging_sink() -> Url {
+        let app = Router::new().route("/", post(std::future::pending::<StatusCode>));
+        let server = axum::Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0)))
+            .serve(app.into_make_service());
+        let url = format!("http://{}/", server.local_addr()).parse().unwrap();
+        tokio::spawn(server);
+        url
+    }
+
+    fn dispatcher(sinks: Vec<AlertSinkConf>) -> AlertDispatcher {
+        AlertDispatcher {
+            sinks: Arc::new(sinks),
+            ..Default::default()
+        }
+    }
+
+    fn alert(dedup_key: &str) -> Alert {
+        Alert::new(
+            AlertKind::CheckpointDivergence,
+            dedup_key,
+            "relayer/ethereum",
+            "Merkle tree root \"0x01\" does not match",
+        )
+        .with_details(json!({ "leaf_index": 7 }))
+    }
+
+    async fn next(receiver: &mut UnboundedReceiver<Value>) -> Value {
+        timeout(Duration::from_secs(5), receiver.recv())
+            .await
+            .expect("Alert was not delivered")
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_payload_shape_by_sink() {
+        let (webhook_url, mut webhook) = mock_sink(StatusCode::OK);
+        let (templated_url, mut templated) = mock_sink(StatusCode::OK);
+        let (slack_url, mut slack) = mock_sink(StatusCode::OK);
+        let (pager_duty_url, mut pager_duty) = mock_sink(StatusCode::ACCEPTED);
+        let dispatcher = dispatcher(vec![
+            AlertSinkConf::Webhook {
+                url: webhook_url,
+                body_template: None,
+            },
+            AlertSinkConf::Webhook {
+                url: templated_url,
+                body_template: Some(
+                    r#"{"title": "{{kind}}: {{summary}}", "context": {{details}}}"#.to_owned(),
+                ),
+            },
+            AlertSinkConf::Slack { url: slack_url },
+            AlertSinkConf::PagerDuty {
+                url: pager_duty_url,
+                routing_key: "routing-key".to_owned(),
+            },
+        ]);
+
+        dispatcher.dispatch(alert("divergence:ethereum"));
+
+        assert_eq!(
+            next(&mut webhook).await,
+            json!({
+                "kind": "checkpoint_divergence",
+                "dedup_key": "divergence:ethereum",
+                "source": "relayer/ethereum",
+                "summary": "Merkle tree root \"0x01\" does not match",
+                "details": { "leaf_index": 7 },
+            })
+        );
+        assert_eq!(
+            next(&mut templated).await,
+            json!({
+                "title": "checkpoint_divergence: Merkle tree root \"0x01\" does not match",
+                "context": { "leaf_index": 7 },
+            })
+        );
+        assert_eq!(
+            next(&mut slack).await,
+            json!({
+                "text": "[critical] checkpoint_divergence on relayer/ethereum: Merkle tree root \"0x01\" does not match (divergence:ethereum)",
+            })
+        );
+        assert_eq!(
+            next(&mut pager_duty).await,
+            json!({
+                "routing_key": "routing-key",
+                "event_action": "trigger",
+                "dedup_key": "divergence:ethereum",
+                "payload": {
+                    "summary": "Merkle tree root \"0x01\" does not match",
+                    "source": "relayer/ethereum",
+                    "severity": "critical",
+                    "component": "checkpoint_divergence",
+                    "custom_details": { "leaf_index": 7 },
+                },
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_alerts_with_same_dedup_key_are_sent_once() {
+        let (url, mut sink) = mock_sink(StatusCode::OK);
+        let dispatcher = dispatcher(vec![AlertSinkConf::Webhook {
+            url,
+            body_template: None,
+        }]);
+
+        dispatcher.dispatch(alert("divergence:ethereum"));
+        dispatcher.dispatch(alert("divergence:ethereum"));
+        dispatcher.dispatch(alert("divergence:arbitrum"));
+
+        let mut dedup_keys = vec![
+            next(&mut sink).await["dedup_key"].clone(),
+            next(&mut sink).await["dedup_key"].clone(),
+        ];
+        dedup_keys.sort_by_key(|key| key.to_string());
+        assert_eq!(
+            dedup_keys,
+            vec![json!("divergence:arbitrum"), json!("divergence:ethereum")]
+        );
+        assert!(timeout(Duration::from_millis(200), sink.recv())
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_rate_limiter_lets_key_through_once_per_window() {
+        let mut rate_limiter = RateLimiter::new(Duration::from_secs(600));
+        let start = Instant::now();
+
+        assert!(rate_limiter.allow("a", start));
+        assert!(!rate_limiter.allow("a", start + Duration::from_secs(599)));
+        assert!(rate_limiter.allow("b", start + Duration::from_secs(599)));
+        assert!(rate_limiter.allow("a", start + Duration::from_secs(600)));
+        assert!(!rate_limiter.allow("b", start + Duration::from_secs(600)));
+    }
+
+    #[tokio::test]
+    async fn test_failing_sinks_do_not_block_delivery() {
+        let (failing_url, mut failing) = mock_sink(StatusCode::INTERNAL_SERVER_ERROR);
+        let (healthy_url, mut healthy) = mock_sink(StatusCode::OK);
+        let dispatcher = dispatcher(vec![
+            AlertSinkConf::Webhook {
+                url: hanging_sink(),
+                body_template: None,
+            },
+            AlertSinkConf::Slack { url: failing_url },
+            AlertSinkConf::Webhook {
+                url: healthy_url,
+                body_template: None,
+            },
+        ]);
+
+        let start = Instant::now();
+        dispatcher.dispatch(alert("divergence:ethereum"));
+        assert!(start.elapsed() < Duration::from_millis(100));
+
+        next(&mut healthy).await;
+        next(&mut failing).await;
+        let slack_failures = dispatcher.sink_failures.with_label_values(&["slack"]);
+        timeout(Duration::from_secs(5), async {
+            while slack_failures.get() == 0 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("Failed delivery was not counted");
+        assert_eq!(
+            dispatcher
+                .sink_failures
+                .with_label_values(&["webhook"])
+                .get(),
+            0
+        );
+    }
+}
@@ -1,12 +1,18 @@
+mod alerts;
 mod gcs_storage;
 mod local_storage;
 mod multisig;
+mod replicated_storage;
 mod s3_storage;
+mod validator_reputation;
 
 /// Reusable logic for working with storage backends.
 pub mod utils;
 
+pub use alerts::*;
 pub use gcs_storage::*;
 pub use local_storage::*;
 pub use multisig::*;
+pub use replicated_storage::*;
 pub use s3_storage::*;
+pub use validator_reputation::*;
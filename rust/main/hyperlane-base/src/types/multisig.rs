@@ -1,15 +1,32 @@
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Instant;
 
 use derive_new::new;
 use eyre::Result;
+use itertools::Either;
+use serde::Deserialize;
 use tracing::{debug, instrument};
 
 use hyperlane_core::{
     HyperlaneDomain, MultisigSignedCheckpoint, SignedCheckpointWithMessageId, H160, H256,
 };
 
-use crate::{CheckpointSyncer, CoreMetrics};
+use crate::{CheckpointFetchOutcome, CheckpointSyncer, CoreMetrics, ValidatorReputations};
+
+/// Which quorum checkpoint to deliver a message against, when several
+/// checkpoints cover its leaf
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CheckpointPreference {
+    /// The highest checkpoint with a quorum. Messages share fewer distinct
+    /// roots, which makes cached metadata and proofs more reusable.
+    #[default]
+    Latest,
+    /// The lowest checkpoint with a quorum that still covers the message, so
+    /// delivery doesn't wait on the slowest validators of the quorum.
+    EarliestSatisfiable,
+}
 
 /// For a particular validator set, fetches signed checkpoints from multiple
 /// validators to create MultisigSignedCheckpoints.
@@ -19,9 +36,25 @@ pub struct MultisigCheckpointSyncer {
     checkpoint_syncers: HashMap<H160, Arc<dyn CheckpointSyncer>>,
     metrics: Arc<CoreMetrics>,
     app_context: Option<String>,
+    /// Reputations used to try the best validators first
+    #[new(default)]
+    reputations: Option<ValidatorReputations>,
 }
 
 impl MultisigCheckpointSyncer {
+    /// Order validators by reputation when assembling a quorum, and record
+    /// each checkpoint fetch in their track record
+    pub fn with_reputations(mut self, reputations: ValidatorReputations) -> Self {
+        self.reputations = Some(reputations);
+        self
+    }
+
+    fn record_fetch(&self, validator: &H160, outcome: CheckpointFetchOutcome, started: Instant) {
+        if let Some(reputations) = &self.reputations {
+            reputations.record(validator, outcome, started.elapsed());
+        }
+    }
+
     /// Gets the latest checkpoint index from each validator's checkpoint syncer.
     /// Returns a vector of the latest indices, in an unspecified order, and does
     /// not contain indices for validators that did not provide a latest index.
@@ -75,18 +108,20 @@ impl MultisigCheckpointSyncer {
         latest_indices.values().copied().flatten().collect()
     }
 
-    /// Attempts to get the latest checkpoint with a quorum of signatures among
-    /// validators.
+    /// Attempts to get a checkpoint with a quorum of signatures among
+    /// validators, between `minimum_index` and `maximum_index`.
     ///
     /// First iterates through the `latest_index` of each validator's checkpoint
     /// syncer, looking for the highest index that >= `threshold` validators
     /// have returned.
     ///
-    /// Attempts to find a quorum of signed checkpoints from that index,
-    /// iterating backwards if unsuccessful, until the (optional) index is
-    /// reached.
+    /// With the `Latest` preference, attempts to find a quorum of signed
+    /// checkpoints from that index, iterating backwards if unsuccessful, until
+    /// `minimum_index` is reached. With `EarliestSatisfiable`, iterates
+    /// forwards from `minimum_index` up to that index instead.
     ///
     /// Note it's possible to not find a quorum.
+    #[allow(clippy::too_many_arguments)]
     #[instrument(err, skip(self))]
     pub async fn fetch_checkpoint_in_range(
         &self,
@@ -94,6 +129,7 @@ impl MultisigCheckpointSyncer {
         threshold: usize,
         minimum_index: u32,
         maximum_index: u32,
+        preference: CheckpointPreference,
         origin: &HyperlaneDomain,
         destination: &HyperlaneDomain,
     ) -> Result<Option<MultisigSignedCheckpoint>> {
@@ -123,7 +159,13 @@ impl MultisigCheckpointSyncer {
                 debug!(%start_index, %highest_quorum_index, "Highest quorum index is below the minimum index");
                 return Ok(None);
             }
-            for index in (minimum_index..=start_index).rev() {
+            let indices = match preference {
+                CheckpointPreference::Latest => Either::Left((minimum_index..=start_index).rev()),
+                CheckpointPreference::EarliestSatisfiable => {
+                    Either::Right(minimum_index..=start_index)
+                }
+            };
+            for index in indices {
                 if let Ok(Some(checkpoint)) =
                     self.fetch_checkpoint(validators, threshold, index).await
                 {
@@ -138,6 +180,10 @@ impl MultisigCheckpointSyncer {
     /// Fetches a MultisigSignedCheckpointWithMessageId if there is a quorum.
     /// Validators must reflect the onchain ordering of the set
     /// Returns Ok(None) if there is no quorum.
+    ///
+    /// With reputations, validators are tried best first. Every validator is
+    /// still tried until a quorum is found, and signatures are returned in
+    /// the onchain order regardless.
     #[instrument(err, skip(self))]
     pub async fn fetch_checkpoint(
         &self,
@@ -145,15 +191,27 @@ impl MultisigCheckpointSyncer {
         threshold: usize,
         index: u32,
     ) -> Result<Option<MultisigSignedCheckpoint>> {
-        // Keeps track of signed validator checkpoints for a particular root.
+        // Keeps track of signed validator checkpoints for a particular root, along with
+        // the onchain position of their signer.
         // In practice, it's likely that validators will all sign the same root for a
         // particular index, but we'd like to be robust to this not being the case
-        let mut signed_checkpoints_per_root: HashMap<H256, Vec<SignedCheckpointWithMessageId>> =
-            HashMap::new();
+        let mut signed_checkpoints_per_root: HashMap<
+            H256,
+            Vec<(usize, SignedCheckpointWithMessageId)>,
+        > = HashMap::new();
 
-        for validator in validators.iter() {
+        let ordered_validators = match &self.reputations {
+            Some(reputations) => reputations.order(validators),
+            None => validators.to_vec(),
+        };
+        for validator in ordered_validators.iter() {
             let addr = H160::from(*validator);
+            let position = validators
+                .iter()
+                .position(|v| v == validator)
+                .unwrap_or_default();
             if let Some(checkpoint_syncer) = self.checkpoint_syncers.get(&addr) {
+                let started = Instant::now();
                 // Gracefully ignore an error fetching the checkpoint from a validator's
                 // checkpoint syncer, which can happen if the validator has not
                 // signed the checkpoint at `index`.
@@ -167,6 +225,11 @@ impl MultisigCheckpointSyncer {
                             checkpoint_index = signed_checkpoint.value.index,
                             "Checkpoint index mismatch"
                         );
+                        self.record_fetch(
+                            &addr,
+                            CheckpointFetchOutcome::VerificationFailed,
+                            started,
+                        );
                         continue;
                     }
 
@@ -179,13 +242,19 @@ impl MultisigCheckpointSyncer {
                             index = index,
                             "Checkpoint signature mismatch"
                         );
+                        self.record_fetch(
+                            &addr,
+                            CheckpointFetchOutcome::VerificationFailed,
+                            started,
+                        );
                         continue;
                     }
+                    self.record_fetch(&addr, CheckpointFetchOutcome::Verified, started);
 
                     // Push the signed checkpoint into the hashmap
                     let root = signed_checkpoint.value.root;
                     let signed_checkpoints = signed_checkpoints_per_root.entry(root).or_default();
-                    signed_checkpoints.push(signed_checkpoint);
+                    signed_checkpoints.push((position, signed_checkpoint));
 
                     // Count the number of signatures for this signed checkpoint
                     let signature_count = signed_checkpoints.len();
@@ -199,7 +268,13 @@ impl MultisigCheckpointSyncer {
 
                     // If we've hit a quorum, create a MultisigSignedCheckpoint
                     if signature_count >= threshold {
-                        let checkpoint: MultisigSignedCheckpoint = signed_checkpoints.try_into()?;
+                        signed_checkpoints.sort_by_key(|(position, _)| *position);
+                        let mut signed_checkpoints: Vec<_> = signed_checkpoints
+                            .iter()
+                            .map(|(_, signed_checkpoint)| signed_checkpoint.clone())
+                            .collect();
+                        let checkpoint: MultisigSignedCheckpoint =
+                            (&mut signed_checkpoints).try_into()?;
                         debug!(checkpoint=?checkpoint, "Fetched multisig checkpoint");
                         return Ok(Some(checkpoint));
                     }
@@ -209,6 +284,7 @@ impl MultisigCheckpointSyncer {
                         index = index,
                         "Unable to find signed checkpoint"
                     );
+                    self.record_fetch(&addr, CheckpointFetchOutcome::Unavailable, started);
                 }
             } else {
                 debug!(%validator, "Unable to find checkpoint syncer");
@@ -219,3 +295,152 @@ impl MultisigCheckpointSyncer {
         Ok(None)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    use async_trait::async_trait;
+    use hyperlane_core::{
+        Checkpoint, CheckpointWithMessageId, HyperlaneSigner, HyperlaneSignerExt, ReorgEvent,
+        SignedAnnouncement,
+    };
+    use hyperlane_ethereum::test_keys::test_signers;
+    use prometheus::Registry;
+
+    use crate::{
+        db::{test_utils, HyperlaneRocksDB},
+        AgentMetadata,
+    };
+
+    use super::*;
+
+    /// Checkpoint storage serving a single checkpoint after a delay
+    #[derive(Debug)]
+    struct MockStorage {
+        checkpoint: SignedCheckpointWithMessageId,
+        delay: Duration,
+        fetches: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl CheckpointSyncer for MockStorage {
+        async fn latest_index(&self) -> Result<Option<u32>> {
+            Ok(Some(self.checkpoint.value.index))
+        }
+        async fn write_latest_index(&self, _index: u32) -> Result<()> {
+            unimplemented!()
+        }
+        async fn fetch_checkpoint(
+            &self,
+            _index: u32,
+        ) -> Result<Option<SignedCheckpointWithMessageId>> {
+            self.fetches.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(self.delay).await;
+            Ok(Some(self.checkpoint.clone()))
+        }
+        async fn write_checkpoint(
+            &self,
+            _signed_checkpoint: &SignedCheckpointWithMessageId,
+        ) -> Result<()> {
+            unimplemented!()
+        }
+        async fn write_metadata(&self, _metadata: &AgentMetadata) -> Result<()> {
+            unimplemented!()
+        }
+        async fn write_announcement(&self, _announcement: &SignedAnnouncement) -> Result<()> {
+            unimplemented!()
+        }
+        fn announcement_location(&self) -> String {
+            unimplemented!()
+        }
+        async fn write_reorg_status(&self, _reorg_event: &ReorgEvent) -> Result<()> {
+            unimplemented!()
+        }
+        async fn reorg_status(&self) -> Result<Option<ReorgEvent>> {
+            unimplemented!()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_quorum_prefers_reputable_validators() {
+        test_utils::run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(&HyperlaneDomain::new_test_domain("test"), db);
+            let metrics = Arc::new(CoreMetrics::new("test", 0, Registry::new()).unwrap());
+            let reputations = ValidatorReputations::new(db, metrics.clone());
+            let checkpoint = CheckpointWithMessageId {
+                checkpoint: Checkpoint {
+                    merkle_tree_hook_address: H256::repeat_byte(1),
+                    mailbox_domain: 1,
+                    root: H256::repeat_byte(2),
+                    index: 0,
+                },
+                message_id: H256::repeat_byte(3),
+            };
+
+            // In onchain order: a slow validator, a faulty one whose storage
+            // serves checkpoints signed by someone else, then three healthy ones
+            let impostor = test_signers(0);
+            let mut validators = vec![];
+            let mut storages = vec![];
+            let mut signatures = vec![];
+            for key in 1..=5u32 {
+                let validator = test_signers(key);
+                let signed = match key {
+                    2 => impostor.sign(checkpoint).await.unwrap(),
+                    _ => validator.sign(checkpoint).await.unwrap(),
+                };
+                let delay = match key {
+                    1 => Duration::from_millis(1_200),
+                    _ => Duration::ZERO,
+                };
+                validators.push(H256::from(validator.eth_address()));
+                signatures.push(signed.signature);
+                storages.push(Arc::new(MockStorage {
+                    checkpoint: signed,
+                    delay,
+                    fetches: AtomicUsize::new(0),
+                }));
+            }
+            let checkpoint_syncers = validators
+                .iter()
+                .zip(&storages)
+                .map(|(v, s)| (H160::from(*v), s.clone() as Arc<dyn CheckpointSyncer>))
+                .collect();
+            let syncer = MultisigCheckpointSyncer::new(checkpoint_syncers, metrics, None)
+                .with_reputations(reputations.clone());
+            let fetches = |i: usize| storages[i].fetches.load(Ordering::SeqCst);
+
+            // Without a track record, validators are tried in onchain order
+            let quorum = syncer.fetch_checkpoint(&validators, 3, 0).await.unwrap();
+            assert_eq!(
+                quorum.unwrap().signatures,
+                vec![signatures[0], signatures[2], signatures[3]]
+            );
+            assert_eq!((fetches(0), fetches(1), fetches(4)), (1, 1, 0));
+
+            // The slow and faulty validators are then tried last, while
+            // signatures stay in onchain order
+            let order = reputations.order(&validators);
+            assert_eq!(order[3..], [validators[0], validators[1]]);
+            let quorum = syncer.fetch_checkpoint(&validators, 3, 0).await.unwrap();
+            assert_eq!(quorum.unwrap().signatures, signatures[2..].to_vec());
+            assert_eq!((fetches(0), fetches(1), fetches(4)), (1, 1, 1));
+
+            // A quorum needing the slow validator is still found
+            let quorum = syncer.fetch_checkpoint(&validators, 4, 0).await.unwrap();
+            assert_eq!(
+                quorum.unwrap().signatures,
+                vec![signatures[0], signatures[2], signatures[3], signatures[4]]
+            );
+
+            // Once their track records decay, validators regain their standing
+            reputations.age(Duration::from_secs(30 * 24 * 60 * 60));
+            assert_eq!(reputations.order(&validators), validators);
+        })
+        .await;
+    }
+}
@@ -0,0 +1,559 @@
+//! Checkpoint storage replicated across several backends.
+//!
+//! A validator publishing to a single bucket stops being usable by relayers
+//! whenever that bucket is unavailable. [`ReplicatedCheckpointSyncer`] writes
+//! to all of its backends at once and succeeds once a quorum of them accepted
+//! the write. The writes a backend missed are kept and retried by
+//! [`ReplicatedCheckpointSyncer::catch_up`] until it has them all. Reads go
+//! to the backends in order, falling back to the next one when a backend
+//! fails or misses what's read.
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use eyre::{eyre, Result};
+use futures::future::{join_all, BoxFuture};
+use hyperlane_core::{ReorgEvent, SignedAnnouncement, SignedCheckpointWithMessageId};
+use prometheus::{IntGauge, IntGaugeVec};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use crate::settings::AnnouncedStorageLocations;
+use crate::traits::CheckpointSyncer;
+use crate::AgentMetadata;
+
+/// Writes a backend missed, to be retried
+#[derive(Debug, Default)]
+struct PendingWrites {
+    checkpoints: BTreeMap<u32, SignedCheckpointWithMessageId>,
+    latest_index: Option<u32>,
+}
+
+#[derive(Debug)]
+struct Backend {
+    syncer: Arc<dyn CheckpointSyncer>,
+    location: String,
+    pending: Mutex<PendingWrites>,
+    lag: Option<IntGauge>,
+}
+
+impl Backend {
+    fn queue_checkpoint(&self, signed_checkpoint: &SignedCheckpointWithMessageId) {
+        let mut pending = self.pending.lock().unwrap();
+        pending
+            .checkpoints
+            .insert(signed_checkpoint.value.index, signed_checkpoint.clone());
+        self.report_lag(&pending);
+    }
+
+    fn queue_latest_index(&self, index: u32) {
+        let mut pending = self.pending.lock().unwrap();
+        pending.latest_index = pending.latest_index.max(Some(index));
+    }
+
+    fn report_lag(&self, pending: &PendingWrites) {
+        if let Some(lag) = &self.lag {
+            lag.set(pending.checkpoints.len() as i64);
+        }
+    }
+
+    /// Retry the missed writes, oldest first, until one fails again
+    async fn catch_up(&self) {
+        let checkpoints: Vec<_> = self
+            .pending
+            .lock()
+            .unwrap()
+            .checkpoints
+            .values()
+            .cloned()
+            .collect();
+        for signed_checkpoint in checkpoints {
+            let index = signed_checkpoint.value.index;
+            if let Err(err) = self.syncer.write_checkpoint(&signed_checkpoint).await {
+                debug!(location = %self.location, index, ?err, "Checkpoint storage backend is still failing");
+                return;
+            }
+            let mut pending = self.pending.lock().unwrap();
+            pending.checkpoints.remove(&index);
+            self.report_lag(&pending);
+        }
+
+        // The latest index is only moved once the checkpoints up to it are in
+        let Some(index) = self.pending.lock().unwrap().latest_index else {
+            return;
+        };
+        if let Err(err) = self.syncer.update_latest_index(index).await {
+            debug!(location = %self.location, index, ?err, "Checkpoint storage backend is still failing");
+            return;
+        }
+        let mut pending = self.pending.lock().unwrap();
+        if pending.latest_index == Some(index) {
+            pending.latest_index = None;
+        }
+        info!(location = %self.location, index, "Checkpoint storage backend caught up");
+    }
+}
+
+/// Checkpoint storage replicated across several backends, the first of which
+/// is the primary
+#[derive(Debug)]
+pub struct ReplicatedCheckpointSyncer {
+    backends: Vec<Backend>,
+    write_quorum: usize,
+    announce_alternates: bool,
+}
+
+impl ReplicatedCheckpointSyncer {
+    /// Replicate checkpoints across `backends`, writes succeeding once
+    /// `write_quorum` of them accepted them
+    pub fn new(backends: Vec<Arc<dyn CheckpointSyncer>>, write_quorum: usize) -> Self {
+        let backends = backends
+            .into_iter()
+            .map(|syncer| Backend {
+                location: syncer.announcement_location(),
+                syncer,
+                pending: Default::default(),
+                lag: None,
+            })
+            .collect();
+        Self {
+            backends,
+            write_quorum,
+            announce_alternates: false,
+        }
+    }
+
+    /// Report the number of checkpoints each backend is missing, labeled by
+    /// its storage location
+    pub fn with_lag_gauge(mut self, lag: &IntGaugeVec) -> Self {
+        for backend in &mut self.backends {
+            let gauge = lag.with_label_values(&[&backend.location]);
+            gauge.set(0);
+            backend.lag = Some(gauge);
+        }
+        self
+    }
+
+    /// List the other backends as alternates in the announced storage
+    /// location, for relayers to fall back to
+    pub fn with_announced_alternates(mut self) -> Self {
+        self.announce_alternates = true;
+        self
+    }
+
+    /// The number of checkpoints each backend is missing, by storage location
+    pub fn lags(&self) -> Vec<(String, usize)> {
+        self.backends
+            .iter()
+            .map(|backend| {
+                let pending = backend.pending.lock().unwrap().checkpoints.len();
+                (backend.location.clone(), pending)
+            })
+            .collect()
+    }
+
+    /// Retry the writes the backends missed once
+    pub async fn catch_up(&self) {
+        join_all(self.backends.iter().map(Backend::catch_up)).await;
+    }
+
+    /// Retry the writes the backends missed every `interval`
+    pub fn spawn_catch_up(self: Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                self.catch_up().await;
+            }
+        })
+    }
+
+    /// The indices of the backends that failed the write, or an error if
+    /// fewer than the write quorum accepted it
+    fn check_quorum(&self, what: &str, results: Vec<Result<()>>) -> Result<Vec<usize>> {
+        let mut failed = vec![];
+        for (i, result) in results.into_iter().enumerate() {
+            if let Err(err) = result {
+                warn!(location = %self.backends[i].location, ?err, "Failed to write {what} to checkpoint storage backend");
+                failed.push(i);
+            }
+        }
+        let accepted = self.backends.len() - failed.len();
+        if accepted < self.write_quorum {
+            return Err(eyre!(
+                "Only {accepted} of {} checkpoint storage backends accepted the {what}, below the write quorum of {}",
+                self.backends.len(),
+                self.write_quorum
+            ));
+        }
+        Ok(failed)
+    }
+
+    /// The first value read from a backend, falling back to the next one
+    /// when a backend fails or has none. Fails if every backend failed.
+    async fn read_with_fallback<'a, T: Send>(
+        &'a self,
+        read: impl Fn(&'a dyn CheckpointSyncer) -> BoxFuture<'a, Result<Option<T>>> + Send,
+    ) -> Result<Option<T>> {
+        let mut last_err = None;
+        let mut answered = false;
+        for backend in &self.backends {
+            match read(backend.syncer.as_ref()).await {
+                Ok(Some(value)) => return Ok(Some(value)),
+                Ok(None) => answered = true,
+                Err(err) => {
+                    debug!(location = %backend.location, ?err, "Failed to read from checkpoint storage backend, falling back to the next one");
+                    last_err = Some(err);
+                }
+            }
+        }
+        match last_err {
+            Some(err) if !answered => Err(err),
+            _ => Ok(None),
+        }
+    }
+}
+
+#[async_trait]
+impl CheckpointSyncer for ReplicatedCheckpointSyncer {
+    async fn latest_index(&self) -> Result<Option<u32>> {
+        self.read_with_fallback(|syncer| syncer.latest_index())
+            .await
+    }
+
+    async fn write_latest_index(&self, index: u32) -> Result<()> {
+        let results = join_all(
+            self.backends
+                .iter()
+                .map(|backend| backend.syncer.write_latest_index(index)),
+        )
+        .await;
+        for i in self.check_quorum("latest index", results)? {
+            self.backends[i].queue_latest_index(index);
+        }
+        Ok(())
+    }
+
+    async fn update_latest_index(&self, index: u32) -> Result<()> {
+        let results = join_all(
+            self.backends
+                .iter()
+                .map(|backend| backend.syncer.update_latest_index(index)),
+        )
+        .await;
+        for i in self.check_quorum("latest index", results)? {
+            self.backends[i].queue_latest_index(index);
+        }
+        Ok(())
+    }
+
+    async fn fetch_checkpoint(&self, index: u32) -> Result<Option<SignedCheckpointWithMessageId>> {
+        self.read_with_fallback(|syncer| syncer.fetch_checkpoint(index))
+            .await
+    }
+
+    async fn write_checkpoint(
+        &self,
+        signed_checkpoint: &SignedCheckpointWithMessageId,
+    ) -> Result<()> {
+        let results = join_all(
+            self.backends
+                .iter()
+                .map(|backend| backend.syncer.write_checkpoint(signed_checkpoint)),
+        )
+        .await;
+        let failed: Vec<_> = results.iter().map(Result::is_err).collect();
+        // The backends that missed the checkpoint get it later even if the
+        // write as a whole fails, as it's retried
+        for (backend, _) in self.backends.iter().zip(failed).filter(|(_, f)| *f) {
+            backend.queue_checkpoint(signed_checkpoint);
+        }
+        self.check_quorum("checkpoint", results)?;
+        Ok(())
+    }
+
+    async fn write_metadata(&self, metadata: &AgentMetadata) -> Result<()> {
+        let results = join_all(
+            self.backends
+                .iter()
+                .map(|backend| backend.syncer.write_metadata(metadata)),
+        )
+        .await;
+        self.check_quorum("metadata", results)?;
+        Ok(())
+    }
+
+    async fn write_announcement(&self, signed_announcement: &SignedAnnouncement) -> Result<()> {
+        let results = join_all(
+            self.backends
+                .iter()
+                .map(|backend| backend.syncer.write_announcement(signed_announcement)),
+        )
+        .await;
+        self.check_quorum("announcement", results)?;
+        Ok(())
+    }
+
+    fn announcement_location(&self) -> String {
+        let mut locations = self.backends.iter().map(|backend| backend.location.clone());
+        let primary = locations.next().unwrap_or_default();
+        if !self.announce_alternates {
+            return primary;
+        }
+        AnnouncedStorageLocations {
+            primary,
+            alternates: locations.collect(),
+        }
+        .to_string()
+    }
+
+    async fn write_reorg_status(&self, reorg_event: &ReorgEvent) -> Result<()> {
+        let results = join_all(
+            self.backends
+                .iter()
+                .map(|backend| backend.syncer.write_reorg_status(reorg_event)),
+        )
+        .await;
+        self.check_quorum("reorg status", results)?;
+        Ok(())
+    }
+
+    async fn reorg_status(&self) -> Result<Option<ReorgEvent>> {
+        self.read_with_fallback(|syncer| syncer.reorg_status())
+            .await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        collections::HashMap,
+        sync::atomic::{AtomicBool, Ordering},
+    };
+
+    use hyperlane_core::{Checkpoint, CheckpointWithMessageId, Signature, H256, U256};
+
+    use super::*;
+
+    /// An in-memory backend whose writes and reads fail while it's down
+    #[derive(Debug, Default)]
+    struct FlakyStorage {
+        location: String,
+        down: AtomicBool,
+        checkpoints: Mutex<HashMap<u32, SignedCheckpointWithMessageId>>,
+        latest_index: Mutex<Option<u32>>,
+    }
+
+    impl FlakyStorage {
+        fn new(location: &str) -> Arc<Self> {
+            Arc::new(Self {
+                location: location.to_owned(),
+                ..Default::default()
+            })
+        }
+
+        fn set_down(&self, down: bool) {
+            self.down.store(down, Ordering::SeqCst);
+        }
+
+        fn check_up(&self) -> Result<()> {
+            if self.down.load(Ordering::SeqCst) {
+                return Err(eyre!("{} is down", self.location));
+            }
+            Ok(())
+        }
+
+        fn has_checkpoint(&self, index: u32) -> bool {
+            self.checkpoints.lock().unwrap().contains_key(&index)
+        }
+    }
+
+    #[async_trait]
+    impl CheckpointSyncer for FlakyStorage {
+        async fn latest_index(&self) -> Result<Option<u32>> {
+            self.check_up()?;
+            Ok(*self.latest_index.lock().unwrap())
+        }
+
+        async fn write_latest_index(&self, index: u32) -> Result<()> {
+            self.check_up()?;
+            *self.latest_index.lock().unwrap() = Some(index);
+            Ok(())
+        }
+
+        async fn fetch_checkpoint(
+            &self,
+            index: u32,
+        ) -> Result<Option<SignedCheckpointWithMessageId>> {
+            self.check_up()?;
+            Ok(self.checkpoints.lock().unwrap().get(&index).cloned())
+        }
+
+        async fn write_checkpoint(
+            &self,
+            signed_checkpoint: &SignedCheckpointWithMessageId,
+        ) -> Result<()> {
+            self.check_up()?;
+            self.checkpoints
+                .lock()
+                .unwrap()
+                .insert(signed_checkpoint.value.index, signed_checkpoint.clone());
+            Ok(())
+        }
+
+        async fn write_metadata(&self, _metadata: &AgentMetadata) -> Result<()> {
+            self.check_up()
+        }
+
+        async fn write_announcement(&self, _announcement: &SignedAnnouncement) -> Result<()> {
+            self.check_up()
+        }
+
+        fn announcement_location(&self) -> String {
+            self.location.clone()
+        }
+
+        async fn write_reorg_status(&self, _reorg_event: &ReorgEvent) -> Result<()> {
+            self.check_up()
+        }
+
+        async fn reorg_status(&self) -> Result<Option<ReorgEvent>> {
+            self.check_up()?;
+            Ok(None)
+        }
+    }
+
+    fn signed_checkpoint(index: u32) -> SignedCheckpointWithMessageId {
+        SignedCheckpointWithMessageId {
+            value: CheckpointWithMessageId {
+                checkpoint: Checkpoint {
+                    merkle_tree_hook_address: H256::zero(),
+                    mailbox_domain: 1,
+                    root: H256::from_low_u64_be(index as u64),
+                    index,
+                },
+                message_id: H256::from_low_u64_be(index as u64),
+            },
+            signature: Signature {
+                r: U256::one(),
+                s: U256::one(),
+                v: 27,
+            },
+        }
+    }
+
+    fn replicate(
+        backends: &[&Arc<FlakyStorage>],
+        write_quorum: usize,
+    ) -> ReplicatedCheckpointSyncer {
+        ReplicatedCheckpointSyncer::new(
+            backends
+                .iter()
+                .map(|&backend| backend.clone() as Arc<dyn CheckpointSyncer>)
+                .collect(),
+            write_quorum,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_writes_succeed_once_a_quorum_of_backends_accepts_them() {
+        let (primary, flaky) = (FlakyStorage::new("primary"), FlakyStorage::new("flaky"));
+        flaky.set_down(true);
+
+        let syncer = replicate(&[&primary, &flaky], 1);
+        syncer
+            .write_checkpoint(&signed_checkpoint(0))
+            .await
+            .unwrap();
+        syncer.update_latest_index(0).await.unwrap();
+        assert!(primary.has_checkpoint(0));
+        assert!(!flaky.has_checkpoint(0));
+        assert_eq!(
+            syncer.lags(),
+            vec![("primary".to_owned(), 0), ("flaky".to_owned(), 1)]
+        );
+
+        // Both backends have to accept the write for a quorum of two
+        let strict = replicate(&[&primary, &flaky], 2);
+        let err = strict
+            .write_checkpoint(&signed_checkpoint(1))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Only 1 of 2 checkpoint storage backends accepted the checkpoint, below the write quorum of 2"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_backends_catch_up_on_the_writes_they_missed() {
+        let (primary, flaky) = (FlakyStorage::new("primary"), FlakyStorage::new("flaky"));
+        let lag = IntGaugeVec::new(prometheus::Opts::new("lag", "lag"), &["location"]).unwrap();
+        let syncer = replicate(&[&primary, &flaky], 1).with_lag_gauge(&lag);
+
+        flaky.set_down(true);
+        for index in 0..3 {
+            syncer
+                .write_checkpoint(&signed_checkpoint(index))
+                .await
+                .unwrap();
+        }
+        syncer.update_latest_index(2).await.unwrap();
+        assert_eq!(lag.with_label_values(&["flaky"]).get(), 3);
+
+        // Still down, nothing is caught up
+        syncer.catch_up().await;
+        assert_eq!(lag.with_label_values(&["flaky"]).get(), 3);
+
+        flaky.set_down(false);
+        syncer.catch_up().await;
+        assert!((0..3).all(|index| flaky.has_checkpoint(index)));
+        assert_eq!(flaky.latest_index().await.unwrap(), Some(2));
+        assert_eq!(lag.with_label_values(&["flaky"]).get(), 0);
+        assert_eq!(lag.with_label_values(&["primary"]).get(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_reads_fall_back_to_the_next_backend() {
+        let (primary, alternate) = (FlakyStorage::new("primary"), FlakyStorage::new("alternate"));
+        alternate
+            .write_checkpoint(&signed_checkpoint(4))
+            .await
+            .unwrap();
+        let syncer = replicate(&[&primary, &alternate], 1);
+
+        // Missing from the primary
+        assert_eq!(
+            syncer.fetch_checkpoint(4).await.unwrap(),
+            Some(signed_checkpoint(4))
+        );
+        // The primary is down
+        primary.set_down(true);
+        alternate.write_latest_index(4).await.unwrap();
+        assert_eq!(syncer.latest_index().await.unwrap(), Some(4));
+        // Every backend is down
+        alternate.set_down(true);
+        assert!(syncer.fetch_checkpoint(4).await.is_err());
+    }
+
+    #[test]
+    fn test_alternates_are_announced_after_the_primary() {
+        let (primary, alternate) = (
+            FlakyStorage::new("s3://bucket/us-east-1"),
+            FlakyStorage::new("gs://backup"),
+        );
+        assert_eq!(
+            replicate(&[&primary, &alternate], 1).announcement_location(),
+            "s3://bucket/us-east-1"
+        );
+        assert_eq!(
+            replicate(&[&primary, &alternate], 1)
+                .with_announced_alternates()
+                .announcement_location(),
+            "s3://bucket/us-east-1|gs://backup"
+        );
+    }
+}
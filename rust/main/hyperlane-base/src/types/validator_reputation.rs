@@ -0,0 +1,287 @@
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Formatter},
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use hyperlane_core::{H160, H256};
+use tracing::warn;
+
+use crate::{
+    db::{HyperlaneRocksDB, ValidatorReputation},
+    CoreMetrics,
+};
+
+/// Default time it takes for a validator's track record to lose half its
+/// weight
+pub const DEFAULT_REPUTATION_HALF_LIFE: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// Weight of the newest sample in the fetch latency moving average
+const LATENCY_EWMA_ALPHA: f64 = 0.2;
+/// Fetch latency at which a validator's score is halved
+const LATENCY_SCALE_MS: f64 = 1_000.;
+
+/// Result of fetching a checkpoint from a validator's storage
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckpointFetchOutcome {
+    /// A checkpoint was fetched and verified
+    Verified,
+    /// The fetch errored or found no checkpoint
+    Unavailable,
+    /// A checkpoint was fetched but failed verification, e.g. it was signed
+    /// by someone else or is for a different index
+    VerificationFailed,
+}
+
+impl ValidatorReputation {
+    /// Score between 0 and 1, higher is better. Validators without a track
+    /// record score 0.5; failed fetches, failed verifications and slow fetches
+    /// lower the score.
+    pub fn score(&self) -> f64 {
+        let attempts = self.successes + self.failures + self.verification_failures;
+        // Laplace smoothing, so a single fetch doesn't decide the standing
+        let reliability = (self.successes + 1.) / (attempts + 2.);
+        let verification = 1. / (1. + self.verification_failures);
+        let speed = 1. / (1. + self.latency_ewma_ms / LATENCY_SCALE_MS);
+        reliability * verification * speed
+    }
+
+    /// The reputation as of `now`, with the track record exponentially
+    /// decayed since it was last updated
+    pub fn decayed(self, now: u64, half_life: Duration) -> Self {
+        let elapsed = now.saturating_sub(self.updated_at) as f64;
+        let factor = 0.5f64.powf(elapsed / half_life.as_secs_f64().max(1.));
+        Self {
+            successes: self.successes * factor,
+            failures: self.failures * factor,
+            verification_failures: self.verification_failures * factor,
+            latency_ewma_ms: self.latency_ewma_ms * factor,
+            updated_at: now.max(self.updated_at),
+        }
+    }
+
+    /// Add a fetch to the track record
+    pub fn record(&mut self, outcome: CheckpointFetchOutcome, latency: Duration) {
+        let latency_ms = latency.as_secs_f64() * 1_000.;
+        let attempts = self.successes + self.failures + self.verification_failures;
+        self.latency_ewma_ms = if attempts == 0. {
+            latency_ms
+        } else {
+            LATENCY_EWMA_ALPHA * latency_ms + (1. - LATENCY_EWMA_ALPHA) * self.latency_ewma_ms
+        };
+        match outcome {
+            CheckpointFetchOutcome::Verified => self.successes += 1.,
+            CheckpointFetchOutcome::Unavailable => self.failures += 1.,
+            CheckpointFetchOutcome::VerificationFailed => self.verification_failures += 1.,
+        }
+    }
+}
+
+/// Reputation of a validator as of now
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ValidatorStanding {
+    /// The validator
+    pub validator: H160,
+    /// Its decayed track record
+    pub reputation: ValidatorReputation,
+    /// Its score, see `ValidatorReputation::score`
+    pub score: f64,
+}
+
+/// Reputations of the validators signing checkpoints for an origin, persisted
+/// in the origin's db and shared by every checkpoint syncer of the origin.
+///
+/// Used to try fast, reliable validators first when assembling a quorum.
+/// Reputation only affects the order validators are tried in, never whether
+/// they are, so any satisfiable quorum is still found.
+#[derive(Clone)]
+pub struct ValidatorReputations {
+    db: HyperlaneRocksDB,
+    metrics: Arc<CoreMetrics>,
+    half_life: Duration,
+    reputations: Arc<Mutex<HashMap<H160, ValidatorReputation>>>,
+}
+
+impl Debug for ValidatorReputations {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "ValidatorReputations {{ origin: {}, half_life: {:?} }}",
+            self.db.domain(),
+            self.half_life
+        )
+    }
+}
+
+impl ValidatorReputations {
+    /// Create reputations for the validators of the db's origin
+    pub fn new(db: HyperlaneRocksDB, metrics: Arc<CoreMetrics>) -> Self {
+        Self {
+            db,
+            metrics,
+            half_life: DEFAULT_REPUTATION_HALF_LIFE,
+            reputations: Default::default(),
+        }
+    }
+
+    /// Set how long it takes for a track record to lose half its weight
+    pub fn with_half_life(mut self, half_life: Duration) -> Self {
+        self.half_life = half_life;
+        self
+    }
+
+    /// Current standing of a validator
+    pub fn standing(&self, validator: &H160) -> ValidatorStanding {
+        let reputation = {
+            let mut reputations = self.reputations.lock().unwrap();
+            self.load(&mut reputations, validator)
+                .decayed(now(), self.half_life)
+        };
+        ValidatorStanding {
+            validator: *validator,
+            reputation,
+            score: reputation.score(),
+        }
+    }
+
+    /// Current standing of every validator fetched from since startup, best
+    /// first
+    pub fn standings(&self) -> Vec<ValidatorStanding> {
+        let validators: Vec<_> = self.reputations.lock().unwrap().keys().copied().collect();
+        let mut standings: Vec<_> = validators.iter().map(|v| self.standing(v)).collect();
+        standings.sort_by(|a, b| b.score.total_cmp(&a.score));
+        standings
+    }
+
+    /// The validators in the order to try them in when assembling a quorum:
+    /// best score first, ties keeping their given order
+    pub fn order(&self, validators: &[H256]) -> Vec<H256> {
+        let mut scored: Vec<_> = validators
+            .iter()
+            .map(|v| (self.standing(&H160::from(*v)).score, *v))
+            .collect();
+        scored.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+        scored.into_iter().map(|(_, v)| v).collect()
+    }
+
+    /// Add a checkpoint fetch to a validator's track record
+    pub fn record(&self, validator: &H160, outcome: CheckpointFetchOutcome, latency: Duration) {
+        let reputation = {
+            let mut reputations = self.reputations.lock().unwrap();
+            let mut reputation = self
+                .load(&mut reputations, validator)
+                .decayed(now(), self.half_life);
+            reputation.record(outcome, latency);
+            reputations.insert(*validator, reputation);
+            reputation
+        };
+        if let Err(err) = self.db.store_validator_reputation(validator, &reputation) {
+            warn!(?validator, ?err, "Failed to persist validator reputation");
+        }
+        self.metrics
+            .validator_reputation_score()
+            .with_label_values(&[self.db.domain().name(), &format!("{validator:?}")])
+            .set(reputation.score());
+    }
+
+    fn load(
+        &self,
+        reputations: &mut HashMap<H160, ValidatorReputation>,
+        validator: &H160,
+    ) -> ValidatorReputation {
+        *reputations.entry(*validator).or_insert_with(|| {
+            self.db
+                .retrieve_validator_reputation(validator)
+                .unwrap_or_else(|err| {
+                    warn!(?validator, ?err, "Failed to load validator reputation");
+                    None
+                })
+                .unwrap_or_else(|| ValidatorReputation {
+                    updated_at: now(),
+                    ..Default::default()
+                })
+        })
+    }
+
+    /// Move every track record `elapsed` into the past
+    #[cfg(test)]
+    pub(crate) fn age(&self, elapsed: Duration) {
+        for reputation in self.reputations.lock().unwrap().values_mut() {
+            reputation.updated_at -= elapsed.as_secs();
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use hyperlane_core::HyperlaneDomain;
+    use prometheus::Registry;
+
+    use crate::db::test_utils;
+
+    use super::*;
+
+    #[test]
+    fn test_score_penalizes_failures_and_latency() {
+        let fast = Duration::from_millis(50);
+        let mut healthy = ValidatorReputation::default();
+        let mut slow = ValidatorReputation::default();
+        let mut faulty = ValidatorReputation::default();
+        for _ in 0..10 {
+            healthy.record(CheckpointFetchOutcome::Verified, fast);
+            slow.record(CheckpointFetchOutcome::Verified, Duration::from_secs(5));
+            faulty.record(CheckpointFetchOutcome::VerificationFailed, fast);
+        }
+        assert!(healthy.score() > ValidatorReputation::default().score());
+        assert!(slow.score() < ValidatorReputation::default().score());
+        assert!(faulty.score() < slow.score());
+    }
+
+    #[test]
+    fn test_decay_restores_neutral_standing() {
+        let mut faulty = ValidatorReputation::default();
+        for _ in 0..10 {
+            faulty.record(CheckpointFetchOutcome::Unavailable, Duration::from_secs(1));
+        }
+        let half_life = Duration::from_secs(60);
+        let halved = faulty.decayed(60, half_life);
+        assert_eq!(halved.failures, 5.);
+        let recovered = faulty.decayed(60 * 60, half_life);
+        assert!((recovered.score() - ValidatorReputation::default().score()).abs() < 1e-6);
+    }
+
+    #[tokio::test]
+    async fn test_reputations_are_persisted() {
+        test_utils::run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(&HyperlaneDomain::new_test_domain("test"), db);
+            let metrics = Arc::new(CoreMetrics::new("test", 0, Registry::new()).unwrap());
+            let validator = H160::from_low_u64_be(1);
+
+            let reputations = ValidatorReputations::new(db.clone(), metrics.clone());
+            reputations.record(
+                &validator,
+                CheckpointFetchOutcome::VerificationFailed,
+                Duration::from_millis(10),
+            );
+            let score = reputations.standing(&validator).score;
+            let reported = metrics
+                .validator_reputation_score()
+                .with_label_values(&["test", &format!("{validator:?}")])
+                .get();
+            assert!((reported - score).abs() < 1e-3);
+
+            // A restarted agent picks up the track record
+            let restarted = ValidatorReputations::new(db, metrics);
+            assert!(restarted.standing(&validator).score < 0.5);
+        })
+        .await;
+    }
+}
@@ -2,8 +2,11 @@ use std::{collections::BTreeSet, fs::read_to_string, path::Path};
 
 use config::{Config, FileFormat};
 use eyre::Context;
-use hyperlane_base::settings::{parser::RawAgentConf, Settings};
-use hyperlane_core::{config::*, KnownHyperlaneDomain};
+use hyperlane_base::settings::{
+    file_environment, merged_environment, parser::RawAgentConf, select_environment_files,
+    validate_config_consistency, validate_config_dir, ConsistencyIssue, EnvironmentError, Settings,
+};
+use hyperlane_core::{config::*, HyperlaneDomain, KnownHyperlaneDomain};
 use walkdir::WalkDir;
 
 /// Relative path to the `hyperlane-monorepo/rust/main/config/`
@@ -31,12 +34,6 @@ fn is_blacklisted(path: &Path) -> bool {
         .any(|x| path.to_str().unwrap().contains(x))
 }
 
-#[derive(Clone, Debug, Ord, PartialEq, PartialOrd, Eq, Hash)]
-struct ChainCoordinate {
-    name: String,
-    domain: u32,
-}
-
 fn config_paths(root: &Path) -> Vec<String> {
     WalkDir::new(root)
         .min_depth(2)
@@ -86,31 +83,248 @@ fn hyperlane_settings() -> Vec<Settings> {
         .collect()
 }
 
-fn chain_name_domain_records() -> BTreeSet<ChainCoordinate> {
-    hyperlane_settings()
+fn print_issues(issues: &[ConsistencyIssue]) {
+    for issue in issues {
+        println!("{issue}");
+    }
+}
+
+#[test]
+fn agent_json_config_consistency_checks() {
+    // Verify that the on-disk json-based configuration data agrees
+    // with itself and with the hard-coded, macro-maintained mapping
+    // in `hyperlane-core/src/chain.rs` named by the macro
+    // `domain_and_chain`.
+    let issues = validate_config_consistency(&hyperlane_settings());
+    print_issues(&issues);
+    assert!(issues.is_empty(), "{} config issues found", issues.len());
+}
+
+#[test]
+fn evm_chain_ids_agree_with_chain_metadata() {
+    // The canonical EVM chain ids hard-coded in `hyperlane-core/src/chain.rs`
+    // must match the `chainId`s in the chain metadata of every known domain
+    let config_path = format!("{}/{}", env!("CARGO_MANIFEST_DIR"), AGENT_CONFIG_PATH_ROOT);
+    let mut checked = 0;
+    for entry in WalkDir::new(config_path).into_iter().filter_map(|x| x.ok()) {
+        let path = entry.path().display().to_string();
+        if !path.ends_with(".json") || is_blacklisted(entry.path()) {
+            continue;
+        }
+        let config: serde_json::Value = serde_json::from_str(&read_to_string(&path).unwrap())
+            .unwrap_or_else(|e| panic!("{path}: {e}"));
+        let Some(chains) = config["chains"].as_object() else {
+            continue;
+        };
+        for (name, chain) in chains {
+            let (Ok(domain), Some("ethereum")) = (
+                name.parse::<KnownHyperlaneDomain>(),
+                chain["protocol"].as_str(),
+            ) else {
+                continue;
+            };
+            assert_eq!(
+                domain.evm_chain_id(),
+                chain["chainId"].as_u64(),
+                "{name} in {path}"
+            );
+            checked += 1;
+        }
+    }
+    assert!(checked > 0);
+}
+
+#[test]
+fn inconsistent_fixture_configs_report_every_issue_class() {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/inconsistent_config");
+    let issues = validate_config_dir(&fixtures).unwrap();
+    print_issues(&issues);
+
+    let has = |expected: &dyn Fn(&ConsistencyIssue) -> bool| issues.iter().any(expected);
+    assert!(has(&|i| matches!(
+        i,
+        ConsistencyIssue::DomainNameMismatch { chain, domain: 1, .. } if chain == "notethereum"
+    )));
+    assert!(has(&|i| matches!(
+        i,
+        ConsistencyIssue::Unparseable { file, .. } if file.ends_with("mismatched.json")
+    )));
+    assert!(has(&|i| i
+        == &ConsistencyIssue::DuplicateDomainId {
+            domain: 123456,
+            chains: vec!["chaina".into(), "chainb".into()],
+        }));
+    assert!(has(&|i| matches!(
+        i,
+        ConsistencyIssue::InvalidAddress { chain, contract: "mailbox", .. } if chain == "chaina"
+    )));
+    assert!(has(&|i| matches!(
+        i,
+        ConsistencyIssue::InvalidAddress { chain, contract: "interchain gas paymaster", .. }
+            if chain == "chaina"
+    )));
+    let out_of_bounds: BTreeSet<_> = issues
         .iter()
-        .flat_map(|x: &Settings| {
-            x.chains.values().map(|v| ChainCoordinate {
-                name: v.domain.name().into(),
-                domain: (&v.domain).into(),
-            })
+        .filter_map(|i| match i {
+            ConsistencyIssue::ReorgPeriodOutOfBounds { chain, .. } => Some(chain.as_str()),
+            _ => None,
         })
-        .collect()
+        .collect();
+    assert_eq!(out_of_bounds, BTreeSet::from(["celo", "chaina"]));
+    assert!(has(&|i| matches!(
+        i,
+        ConsistencyIssue::UnknownRelayChain { chain, file }
+            if chain == "missingchain" && file.ends_with("relayer.json")
+    )));
 }
 
 #[test]
-fn agent_json_config_consistency_checks() {
-    // Verify that the hard-coded, macro-maintained
-    // mapping in `hyperlane-core/src/chain.rs` named
-    // by the macro `domain_and_chain` is complete
-    // and in agreement with our on-disk json-based
-    // configuration data.
-    let chain_coords = chain_name_domain_records();
-    for ChainCoordinate { name, domain } in chain_coords.into_iter() {
-        assert_eq!(
-            KnownHyperlaneDomain::try_from(domain).unwrap().to_string(),
-            name
-        );
-        assert_eq!(name.parse::<KnownHyperlaneDomain>().unwrap() as u32, domain);
+fn environment_flag_selects_only_matching_files() {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/environments");
+    let selected = select_environment_files(&fixtures, "mainnet3").unwrap();
+    assert_eq!(
+        selected,
+        vec![
+            fixtures.join("mainnet_config.json"),
+            fixtures.join("mainnet_relayer.json")
+        ]
+    );
+    let declared: Vec<_> = selected
+        .into_iter()
+        .map(|path| {
+            let environment = file_environment(&path).unwrap();
+            (path, environment)
+        })
+        .collect();
+    assert_eq!(
+        merged_environment(&declared).unwrap().as_deref(),
+        Some("mainnet3")
+    );
+}
+
+#[test]
+fn mixed_environment_fixtures_are_not_merged() {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/environments");
+    let declared: Vec<_> = ["mainnet_config.json", "testnet_config.json"]
+        .into_iter()
+        .map(|file| {
+            let path = fixtures.join(file);
+            let environment = file_environment(&path).unwrap();
+            (path, environment)
+        })
+        .collect();
+    assert_eq!(
+        merged_environment(&declared),
+        Err(EnvironmentError::Mixed {
+            files: vec![
+                (
+                    fixtures.join("mainnet_config.json"),
+                    Some("mainnet3".to_owned())
+                ),
+                (
+                    fixtures.join("testnet_config.json"),
+                    Some("testnet4".to_owned())
+                ),
+            ],
+        })
+    );
+    // Each environment is consistent on its own
+    assert!(validate_config_dir(&fixtures).unwrap().is_empty());
+}
+
+#[test]
+fn deprecated_domain_names_load_as_their_replacement() {
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/environments/testnet_config.json");
+    let raw: RawAgentConf = Config::builder()
+        .add_source(config::File::from(fixture))
+        .build()
+        .unwrap()
+        .try_deserialize()
+        .unwrap();
+    let settings = Settings::from_config(raw, &ConfigPath::default()).unwrap();
+
+    let base_sepolia = HyperlaneDomain::Known(KnownHyperlaneDomain::BaseSepolia);
+    assert_eq!(settings.chains["basesepolia"].domain, base_sepolia);
+    assert!(!settings.chains.contains_key("basegoerli"));
+    assert_eq!(settings.lookup_domain("basegoerli").unwrap(), base_sepolia);
+    assert_eq!(
+        settings.lookup_domain("arbitrumsepolia").unwrap(),
+        HyperlaneDomain::Known(KnownHyperlaneDomain::ArbitrumSepolia)
+    );
+    assert_eq!(settings.warn_deprecated_domain_names(), ["basegoerli"]);
+}
+
+#[test]
+fn configured_chains_extend_the_domain_registry() {
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/inconsistent_config/relayer.json");
+    let mut raw: serde_json::Value =
+        serde_json::from_str(&read_to_string(fixture).unwrap()).unwrap();
+    let parse = |raw: &serde_json::Value| {
+        let raw: RawAgentConf = Config::builder()
+            .add_source(config::File::from_str(&raw.to_string(), FileFormat::Json))
+            .build()
+            .unwrap()
+            .try_deserialize()
+            .unwrap();
+        Settings::from_config(raw, &ConfigPath::default())
+    };
+
+    let settings = parse(&raw).unwrap();
+    assert_eq!(settings.domains.domain_id_from_name("chaina"), Some(123456));
+    assert_eq!(settings.domains.name_from_domain_id(123456), Some("chaina"));
+    assert_eq!(settings.domains.domain_id_from_name("ethereum"), Some(1));
+
+    // Another chain claiming the domain id of `chaina`
+    let mut chainb = raw["chains"]["chaina"].clone();
+    chainb["name"] = "chainb".into();
+    raw["chains"]["chainb"] = chainb;
+    let err = parse(&raw).unwrap_err().to_string();
+    assert!(
+        err.contains("Domain id 123456 is already registered as `chaina`"),
+        "{err}"
+    );
+}
+
+#[test]
+fn configured_finality_blocks_override_the_domain_defaults() {
+    // Where a config specifies `finalityBlocks` the parsed chain uses it,
+    // otherwise the domain's built-in default, never anything else
+    let crate_root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let paths = [AGENT_CONFIG_PATH_ROOT, "tests/fixtures"]
+        .into_iter()
+        .flat_map(|root| config_paths(&crate_root.join(root)));
+    let (mut checked, mut overridden) = (0, 0);
+    for path in paths {
+        if !path.ends_with(".json") {
+            continue;
+        }
+        let contents = read_to_string(&path).unwrap();
+        let config: serde_json::Value =
+            serde_json::from_str(&contents).unwrap_or_else(|e| panic!("{path}: {e}"));
+        let Some(raw) = Config::builder()
+            .add_source(config::File::from_str(&contents, FileFormat::Json))
+            .build()
+            .ok()
+            .and_then(|c| c.try_deserialize::<RawAgentConf>().ok())
+        else {
+            continue;
+        };
+        let Ok(settings) = Settings::from_config(raw, &ConfigPath::default()) else {
+            continue;
+        };
+        for (name, chain) in &settings.chains {
+            let configured = config["chains"][name]["blocks"]["finalityBlocks"].as_u64();
+            let expected = configured.map_or(chain.domain.finality_blocks(), |blocks| {
+                u32::try_from(blocks).unwrap()
+            });
+            assert_eq!(chain.finality_blocks, expected, "{name} in {path}");
+            checked += 1;
+            overridden += usize::from(configured.is_some());
+        }
     }
+    assert!(checked > 0);
+    // The fixtures override at least one default
+    assert!(overridden > 0);
 }
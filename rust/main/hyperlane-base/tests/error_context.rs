@@ -0,0 +1,65 @@
+//! The oneline error reports of failures deep in the relaying stack keep the
+//! chain and message context of the spans they occurred in, even when they're
+//! logged by another task.
+
+#![cfg(feature = "oneline-eyre")]
+
+use eyre::{Result, WrapErr};
+use hyperlane_base::{oneline_eyre, WithChainContext};
+use hyperlane_core::{
+    ChainCommunicationError, ChainResult, HyperlaneDomain, KnownHyperlaneDomain, H256,
+};
+use tracing::{info_span, Instrument};
+use tracing_error::ErrorLayer;
+use tracing_subscriber::{layer::SubscriberExt, Registry};
+
+/// A provider call timing out
+async fn mock_provider_call() -> ChainResult<()> {
+    Err(ChainCommunicationError::from_other_str(
+        "deadline has elapsed",
+    ))
+}
+
+/// A metadata builder fetching from the provider
+async fn mock_metadata_build() -> Result<()> {
+    mock_provider_call()
+        .await
+        .with_chain_context(&HyperlaneDomain::Known(KnownHyperlaneDomain::Arbitrum))?;
+    Ok(())
+}
+
+/// A pending message being prepared
+async fn mock_prepare() -> Result<()> {
+    mock_metadata_build()
+        .instrument(info_span!("build", destination_domain = "arbitrum"))
+        .await
+        .context("When building metadata")?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_errors_logged_by_another_task_keep_their_span_context() {
+    oneline_eyre::install().unwrap();
+    tracing::subscriber::set_global_default(Registry::default().with(ErrorLayer::default()))
+        .unwrap();
+
+    let message_id = H256::from_low_u64_be(42);
+    let task = tokio::spawn(mock_prepare().instrument(info_span!(
+        "MessageProcessor",
+        origin = "ethereum",
+        message_id = ?message_id,
+    )));
+    // Formatted outside of the spans the error occurred in
+    let err = task.await.unwrap().wrap_err("Error preparing message");
+    let report = format!("{:?}", err.unwrap_err());
+
+    assert!(report.starts_with("Error preparing message"), "{report}");
+    assert!(report.contains("deadline has elapsed"), "{report}");
+    assert!(report.contains("chain=arbitrum"), "{report}");
+    assert!(
+        report.contains(&format!(
+            "Span context: destination=arbitrum origin=ethereum message_id={message_id:?}"
+        )),
+        "{report}"
+    );
+}
@@ -0,0 +1,151 @@
+//! Malformed configs are reported as config errors at startup, with the path
+//! of each offending value, rather than panicking while the settings are
+//! parsed or the signers and contract clients are built from them.
+
+use std::{
+    fs::read_to_string,
+    panic::{catch_unwind, AssertUnwindSafe},
+    path::Path,
+};
+
+use config::{Config, FileFormat};
+use hyperlane_base::settings::{parser::RawAgentConf, Settings, SignerConf};
+use hyperlane_core::{config::*, H256};
+use walkdir::WalkDir;
+
+/// Sources building agents from their config, which must report malformed
+/// configs as errors instead of panicking
+const PANIC_FREE_SOURCES: &[&str] = &[
+    "src/settings/base.rs",
+    "src/settings/chains.rs",
+    "src/settings/signers.rs",
+    "src/settings/loader",
+    "src/settings/parser",
+];
+
+/// Calls which panic on an error or a missing value
+const PANICKING_CALLS: &[&str] = &[
+    ".unwrap()",
+    ".expect(",
+    "Result::unwrap",
+    "Option::unwrap",
+    "panic!(",
+    "unreachable!(",
+];
+
+fn mainnet_config() -> serde_json::Value {
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/environments/mainnet_config.json");
+    serde_json::from_str(&read_to_string(fixture).unwrap()).unwrap()
+}
+
+/// Parse `raw`, failing the test if parsing panics
+fn parse(raw: serde_json::Value) -> ConfigResult<Settings> {
+    catch_unwind(AssertUnwindSafe(|| {
+        let raw: RawAgentConf = Config::builder()
+            .add_source(config::File::from_str(&raw.to_string(), FileFormat::Json))
+            .build()
+            .unwrap()
+            .try_deserialize()
+            .unwrap();
+        Settings::from_config(raw, &ConfigPath::default())
+    }))
+    .expect("Parsing a malformed config panicked")
+}
+
+#[test]
+fn malformed_values_are_reported_with_their_path() {
+    let mut raw = mainnet_config();
+    raw["chains"]["ethereum"]["rpcUrls"][0]["http"] = "not a url".into();
+    raw["chains"]["ethereum"]["signer"] = serde_json::json!({
+        "type": "hexKey",
+        "key": "0xnothex",
+    });
+    raw["chains"]["ethereum"]["domainId"] = "one".into();
+
+    // Every malformed value is reported, not only the first one
+    let err = parse(raw).unwrap_err().to_string();
+    for path in [
+        "chains.ethereum.rpcUrls.0.http",
+        "chains.ethereum.signer.key",
+        "chains.ethereum.domainId",
+    ] {
+        assert!(err.contains(&format!("config_path: `{path}`")), "{err}");
+    }
+    assert!(err.contains("Invalid url"), "{err}");
+    assert!(
+        err.contains("Expected a valid private key in hex or base58"),
+        "{err}"
+    );
+}
+
+#[test]
+fn incomplete_cosmos_chains_are_reported_with_their_path() {
+    let mut raw = mainnet_config();
+    raw["chains"]["neutron"] = serde_json::json!({
+        "name": "neutron",
+        "domainId": 1853125230,
+        "protocol": "cosmos",
+        "chainId": "neutron-1",
+        "bech32Prefix": "neutron",
+        "rpcUrls": [{ "http": "http://127.0.0.1:26657" }],
+        "grpcUrls": [{ "http": "http://127.0.0.1:9090" }],
+        "mailbox": "0x848426d50eb2104d5c6381ec63757930b1c14659c40db8b8081e516e7c5238fc",
+        "interchainGasPaymaster": "0x504ee9ac43ec5814e00c7d21869a90ec52becb489636bdf893b7df9d606b5d67",
+        "validatorAnnounce": "0xf3aa0d652226e21ae35cd9035c492ae41725edc9036edf0d6a48701b153b90a0",
+        "merkleTreeHook": "0xcd30a0001cc1f436c41ef764a712ebabc5a144140e3fd03eafe64a9a24e4e27c",
+    });
+
+    let err = parse(raw).unwrap_err().to_string();
+    for path in [
+        "chains.neutron.gasPrice",
+        "chains.neutron.contractAddressBytes",
+    ] {
+        assert!(err.contains(&format!("config_path: `{path}`")), "{err}");
+    }
+}
+
+#[tokio::test]
+async fn invalid_signer_keys_are_reported() {
+    let conf = SignerConf::HexKey { key: H256::zero() };
+    let err = conf
+        .build::<hyperlane_ethereum::Signers>()
+        .await
+        .unwrap_err();
+    assert!(
+        format!("{err:?}").contains("Invalid ethereum signer key"),
+        "{err:?}"
+    );
+}
+
+#[test]
+fn config_sources_do_not_panic() {
+    let crate_root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let mut offenders = vec![];
+    for source in PANIC_FREE_SOURCES {
+        for entry in WalkDir::new(crate_root.join(source)) {
+            let path = entry.unwrap().into_path();
+            if path.extension().map_or(true, |ext| ext != "rs") {
+                continue;
+            }
+            let contents = read_to_string(&path).unwrap();
+            // Tests may panic
+            let lines = contents
+                .lines()
+                .take_while(|line| !line.trim_start().starts_with("#[cfg(test)]"));
+            for (i, line) in lines.enumerate() {
+                if line.trim_start().starts_with("//") {
+                    continue;
+                }
+                if PANICKING_CALLS.iter().any(|call| line.contains(call)) {
+                    offenders.push(format!("{}:{}: {}", path.display(), i + 1, line.trim()));
+                }
+            }
+        }
+    }
+    assert!(
+        offenders.is_empty(),
+        "Report malformed configs as errors instead of panicking:\n{}",
+        offenders.join("\n")
+    );
+}
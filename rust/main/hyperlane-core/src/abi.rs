@@ -0,0 +1,383 @@
+//! Conversions between core types and Solidity ABI tokens, for building
+//! and reading contract calls by hand.
+
+use ethers_core::abi::{self, ParamType, Token};
+use thiserror::Error;
+
+use crate::{Address, Decode, Encode, HyperlaneMessage, H160, H256, U256};
+
+/// Signature of the Mailbox `process` function
+const PROCESS_SIGNATURE: &str = "process(bytes,bytes)";
+/// Selector of the `Error(string)` revert reason
+const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+/// Selector of the `Panic(uint256)` revert reason
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+/// Failure to convert an ABI token into a core type
+#[derive(Debug, Error)]
+pub enum AbiTokenError {
+    /// The token isn't of the type the value is encoded as
+    #[error("Expected {expected} token, got {found:?}")]
+    UnexpectedToken {
+        /// The expected token type
+        expected: &'static str,
+        /// The token that was found
+        found: Token,
+    },
+    /// A bytes32 address has non-zero bytes where a 20 byte address is padded
+    #[error("Expected a left-padded 20 byte address, got {0:?}")]
+    InvalidPadding(H256),
+    /// The bytes aren't an encoded Hyperlane message
+    #[error("Invalid encoded message: {0}")]
+    InvalidMessage(String),
+    /// The bytes aren't calldata or revert output of the expected shape
+    #[error("Invalid call data: {0}")]
+    InvalidCallData(String),
+    /// ABI decoding failed
+    #[error(transparent)]
+    Abi(#[from] abi::Error),
+}
+
+/// Convert a value into the ABI token it's encoded as in contract calls
+pub trait ToAbiToken {
+    /// The ABI token for this value
+    fn to_abi_token(&self) -> Token;
+}
+
+/// Convert an ABI token back into a value
+pub trait FromAbiToken: Sized {
+    /// Read a value from its ABI token
+    fn from_abi_token(token: Token) -> Result<Self, AbiTokenError>;
+}
+
+/// `bytes32`
+impl ToAbiToken for H256 {
+    fn to_abi_token(&self) -> Token {
+        Token::FixedBytes(self.as_bytes().to_vec())
+    }
+}
+
+/// `bytes32`, or an `address` which is left-padded
+impl FromAbiToken for H256 {
+    fn from_abi_token(token: Token) -> Result<Self, AbiTokenError> {
+        match token {
+            Token::FixedBytes(bytes) if bytes.len() == 32 => Ok(H256::from_slice(&bytes)),
+            Token::Address(address) => Ok(H160::from(address).into()),
+            found => Err(AbiTokenError::UnexpectedToken {
+                expected: "bytes32",
+                found,
+            }),
+        }
+    }
+}
+
+/// `address`
+impl ToAbiToken for H160 {
+    fn to_abi_token(&self) -> Token {
+        Token::Address((*self).into())
+    }
+}
+
+/// `address`, or a `bytes32` address as used by Hyperlane messages, as long
+/// as it's a left-padded 20 byte address
+impl FromAbiToken for H160 {
+    fn from_abi_token(token: Token) -> Result<Self, AbiTokenError> {
+        match token {
+            Token::Address(address) => Ok(address.into()),
+            token @ Token::FixedBytes(_) => {
+                let bytes32 = H256::from_abi_token(token)?;
+                if !crate::utils::is_padded_h160(&bytes32) {
+                    return Err(AbiTokenError::InvalidPadding(bytes32));
+                }
+                Ok(bytes32.into())
+            }
+            found => Err(AbiTokenError::UnexpectedToken {
+                expected: "address",
+                found,
+            }),
+        }
+    }
+}
+
+/// `bytes32`, left-padded like the sender and recipient of Hyperlane
+/// messages. Addresses longer than 32 bytes don't fit and are encoded as
+/// `bytes` instead.
+impl ToAbiToken for Address {
+    fn to_abi_token(&self) -> Token {
+        if self.0.len() > 32 {
+            return Token::Bytes(self.0.to_vec());
+        }
+        let mut bytes32 = vec![0u8; 32 - self.0.len()];
+        bytes32.extend_from_slice(&self.0);
+        Token::FixedBytes(bytes32)
+    }
+}
+
+/// `bytes32`, `address` or `bytes`, keeping every byte of the token
+impl FromAbiToken for Address {
+    fn from_abi_token(token: Token) -> Result<Self, AbiTokenError> {
+        match token {
+            Token::FixedBytes(bytes) | Token::Bytes(bytes) => Ok(Address(bytes.into())),
+            Token::Address(address) => Ok(Address(address.as_bytes().to_vec().into())),
+            found => Err(AbiTokenError::UnexpectedToken {
+                expected: "bytes32",
+                found,
+            }),
+        }
+    }
+}
+
+/// `uint256`
+impl ToAbiToken for U256 {
+    fn to_abi_token(&self) -> Token {
+        Token::Uint((*self).into())
+    }
+}
+
+/// `uint256`
+impl FromAbiToken for U256 {
+    fn from_abi_token(token: Token) -> Result<Self, AbiTokenError> {
+        match token {
+            Token::Uint(value) => Ok(value.into()),
+            found => Err(AbiTokenError::UnexpectedToken {
+                expected: "uint256",
+                found,
+            }),
+        }
+    }
+}
+
+/// `bytes` holding the packed message, as taken by the Mailbox
+impl ToAbiToken for HyperlaneMessage {
+    fn to_abi_token(&self) -> Token {
+        Token::Bytes(self.to_vec())
+    }
+}
+
+/// `bytes` holding the packed message
+impl FromAbiToken for HyperlaneMessage {
+    fn from_abi_token(token: Token) -> Result<Self, AbiTokenError> {
+        match token {
+            Token::Bytes(bytes) => HyperlaneMessage::read_from(&mut bytes.as_slice())
+                .map_err(|err| AbiTokenError::InvalidMessage(err.to_string())),
+            found => Err(AbiTokenError::UnexpectedToken {
+                expected: "bytes",
+                found,
+            }),
+        }
+    }
+}
+
+/// Calldata of a Mailbox `process(metadata, message)` call
+pub fn encode_process_call(metadata: &[u8], message: &HyperlaneMessage) -> Vec<u8> {
+    let mut calldata = ethers_core::utils::id(PROCESS_SIGNATURE).to_vec();
+    calldata.extend(abi::encode(&[
+        Token::Bytes(metadata.to_vec()),
+        message.to_abi_token(),
+    ]));
+    calldata
+}
+
+/// Decode the arguments of a Mailbox `process` call back into its metadata
+/// and message
+pub fn decode_process_call(calldata: &[u8]) -> Result<(Vec<u8>, HyperlaneMessage), AbiTokenError> {
+    let selector = ethers_core::utils::id(PROCESS_SIGNATURE);
+    let Some(args) = calldata.strip_prefix(selector.as_slice()) else {
+        return Err(AbiTokenError::InvalidCallData(
+            "calldata isn't a `process` call".to_owned(),
+        ));
+    };
+    let mut tokens = abi::decode(&[ParamType::Bytes, ParamType::Bytes], args)?.into_iter();
+    let (Some(Token::Bytes(metadata)), Some(message)) = (tokens.next(), tokens.next()) else {
+        unreachable!("decoded tokens match the param types")
+    };
+    Ok((metadata, HyperlaneMessage::from_abi_token(message)?))
+}
+
+/// Why a contract call reverted, read from its revert output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RevertReason {
+    /// `revert("reason")` or a failed `require`
+    Error(String),
+    /// A failed `assert`, arithmetic overflow or similar, by panic code
+    Panic(U256),
+    /// A custom error, which needs the contract's ABI to be decoded
+    Custom {
+        /// Selector of the custom error
+        selector: [u8; 4],
+        /// ABI encoded arguments of the error
+        data: Vec<u8>,
+    },
+    /// A revert without any output
+    Empty,
+}
+
+/// Decode the output of a reverted call
+pub fn decode_revert(output: &[u8]) -> Result<RevertReason, AbiTokenError> {
+    if output.is_empty() {
+        return Ok(RevertReason::Empty);
+    }
+    let Some((selector, data)) = output.split_first_chunk::<4>() else {
+        return Err(AbiTokenError::InvalidCallData(format!(
+            "revert output of {} bytes is too short for a selector",
+            output.len()
+        )));
+    };
+    match *selector {
+        ERROR_SELECTOR => match abi::decode(&[ParamType::String], data)?.pop() {
+            Some(Token::String(reason)) => Ok(RevertReason::Error(reason)),
+            _ => unreachable!("decoded tokens match the param types"),
+        },
+        PANIC_SELECTOR => match abi::decode(&[ParamType::Uint(256)], data)?.pop() {
+            Some(token) => Ok(RevertReason::Panic(U256::from_abi_token(token)?)),
+            None => unreachable!("decoded tokens match the param types"),
+        },
+        selector => Ok(RevertReason::Custom {
+            selector,
+            data: data.to_vec(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs::File, str::FromStr};
+
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::test_utils::find_vector;
+
+    /// Fixtures checked against Solidity's own encoding by the Solidity test
+    /// suite, see `solidity/test/AbiVectors.t.sol`
+    #[derive(Deserialize)]
+    struct Fixtures {
+        process: Vec<ProcessFixture>,
+        errors: Vec<ErrorFixture>,
+        panics: Vec<PanicFixture>,
+    }
+
+    #[derive(Deserialize)]
+    struct ProcessFixture {
+        version: u8,
+        nonce: u32,
+        origin: u32,
+        sender: H256,
+        destination: u32,
+        recipient: H256,
+        body: String,
+        metadata: String,
+        calldata: String,
+        id: H256,
+    }
+
+    #[derive(Deserialize)]
+    struct ErrorFixture {
+        reason: String,
+        data: String,
+    }
+
+    #[derive(Deserialize)]
+    struct PanicFixture {
+        code: u64,
+        data: String,
+    }
+
+    fn fixtures() -> Fixtures {
+        serde_json::from_reader(File::open(find_vector("abi.json")).unwrap()).unwrap()
+    }
+
+    fn unhex(s: &str) -> Vec<u8> {
+        hex::decode(s.trim_start_matches("0x")).unwrap()
+    }
+
+    #[test]
+    fn test_process_call_matches_solidity_encoding() {
+        for fixture in fixtures().process {
+            let message = HyperlaneMessage {
+                version: fixture.version,
+                nonce: fixture.nonce,
+                origin: fixture.origin,
+                sender: fixture.sender,
+                destination: fixture.destination,
+                recipient: fixture.recipient,
+                body: unhex(&fixture.body),
+            };
+            let metadata = unhex(&fixture.metadata);
+            assert_eq!(message.id(), fixture.id);
+
+            let calldata = encode_process_call(&metadata, &message);
+            assert_eq!(
+                hex::encode(&calldata),
+                hex::encode(unhex(&fixture.calldata))
+            );
+            assert_eq!(decode_process_call(&calldata).unwrap(), (metadata, message));
+        }
+    }
+
+    #[test]
+    fn test_revert_reasons_match_solidity_encoding() {
+        let fixtures = fixtures();
+        for fixture in fixtures.errors {
+            assert_eq!(
+                decode_revert(&unhex(&fixture.data)).unwrap(),
+                RevertReason::Error(fixture.reason)
+            );
+        }
+        for fixture in fixtures.panics {
+            assert_eq!(
+                decode_revert(&unhex(&fixture.data)).unwrap(),
+                RevertReason::Panic(fixture.code.into())
+            );
+        }
+
+        assert_eq!(decode_revert(&[]).unwrap(), RevertReason::Empty);
+        assert_eq!(
+            decode_revert(&[1, 2, 3, 4, 5]).unwrap(),
+            RevertReason::Custom {
+                selector: [1, 2, 3, 4],
+                data: vec![5]
+            }
+        );
+        assert!(decode_revert(&[1, 2]).is_err());
+    }
+
+    #[test]
+    fn test_addresses_round_trip_through_bytes32() {
+        let evm_address = H160::from_str("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed").unwrap();
+        let bytes32 = H256::from(evm_address);
+
+        assert_eq!(
+            H160::from_abi_token(bytes32.to_abi_token()).unwrap(),
+            evm_address
+        );
+        assert_eq!(
+            H256::from_abi_token(evm_address.to_abi_token()).unwrap(),
+            bytes32
+        );
+        assert!(matches!(
+            H160::from_abi_token(H256::repeat_byte(1).to_abi_token()),
+            Err(AbiTokenError::InvalidPadding(_))
+        ));
+
+        let address = Address(evm_address.as_bytes().to_vec().into());
+        assert_eq!(address.to_abi_token(), bytes32.to_abi_token());
+        assert_eq!(
+            Address::from_abi_token(address.to_abi_token()).unwrap().0,
+            bytes32.as_bytes()
+        );
+    }
+
+    #[test]
+    fn test_message_token_rejects_truncated_message() {
+        let message = HyperlaneMessage::default();
+        assert_eq!(
+            HyperlaneMessage::from_abi_token(message.to_abi_token()).unwrap(),
+            message
+        );
+        assert!(matches!(
+            HyperlaneMessage::from_abi_token(Token::Bytes(vec![3; 10])),
+            Err(AbiTokenError::InvalidMessage(_))
+        ));
+    }
+}
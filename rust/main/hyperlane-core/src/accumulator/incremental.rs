@@ -1,10 +1,13 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 use derive_new::new;
 
-use crate::accumulator::{
-    hash_concat,
-    merkle::{merkle_root_from_branch, Proof},
-    H256, TREE_DEPTH, ZERO_HASHES,
+use crate::{
+    accumulator::{
+        hash_concat,
+        merkle::{merkle_root_from_branch, Proof},
+        H256, TREE_DEPTH, ZERO_HASHES,
+    },
+    Decode, Encode, HyperlaneProtocolError,
 };
 
 #[derive(BorshDeserialize, BorshSerialize, Debug, Clone, new, PartialEq, Eq)]
@@ -61,6 +64,40 @@ impl IncrementalMerkle {
         node
     }
 
+    /// Calculate the root of the tree as if it were only `depth` levels
+    /// deep, which is the leftmost subtree of that depth while the tree holds
+    /// no more than 2^`depth` leaves.
+    pub fn root_at_depth(&self, depth: usize) -> H256 {
+        assert!(depth <= TREE_DEPTH, "depth {depth} exceeds {TREE_DEPTH}");
+        if depth < TREE_DEPTH && self.count == 1 << depth {
+            // The subtree is full, so ingesting its last leaf stored its root
+            return self.branch[depth];
+        }
+        let mut node: H256 = Default::default();
+        let mut size = self.count;
+        for (i, elem) in self.branch.iter().enumerate().take(depth) {
+            node = if (size & 1) == 1 {
+                hash_concat(elem, node)
+            } else {
+                hash_concat(node, ZERO_HASHES[i])
+            };
+            size /= 2;
+        }
+        node
+    }
+
+    /// Ingest `leaves`, yielding the root of the tree after each one, i.e.
+    /// the root of every checkpoint the leaves make up
+    pub fn roots_iter<'a>(
+        &'a mut self,
+        leaves: impl IntoIterator<Item = H256> + 'a,
+    ) -> impl Iterator<Item = H256> + 'a {
+        leaves.into_iter().map(move |leaf| {
+            self.ingest(leaf);
+            self.root()
+        })
+    }
+
     /// Get the number of items in the tree
     pub fn count(&self) -> usize {
         self.count
@@ -89,6 +126,39 @@ impl IncrementalMerkle {
     }
 }
 
+impl Encode for IncrementalMerkle {
+    fn write_to<W>(&self, writer: &mut W) -> std::io::Result<usize>
+    where
+        W: std::io::Write,
+    {
+        writer.write_all(&(self.count as u64).to_be_bytes())?;
+        for hash in self.branch.iter() {
+            writer.write_all(hash.as_bytes())?;
+        }
+        Ok(8 + TREE_DEPTH * 32)
+    }
+}
+
+impl Decode for IncrementalMerkle {
+    fn read_from<R>(reader: &mut R) -> Result<Self, HyperlaneProtocolError>
+    where
+        R: std::io::Read,
+        Self: Sized,
+    {
+        let mut count_bytes = [0u8; 8];
+        let mut branch = [H256::default(); TREE_DEPTH];
+
+        reader.read_exact(&mut count_bytes)?;
+        for item in &mut branch {
+            reader.read_exact(item.as_bytes_mut())?;
+        }
+
+        let count = u64::from_be_bytes(count_bytes) as usize;
+
+        Ok(Self { branch, count })
+    }
+}
+
 #[cfg(all(test, feature = "ethers"))]
 mod test {
     use ethers_core::utils::hash_message;
@@ -123,4 +193,33 @@ mod test {
             }
         }
     }
+
+    #[test]
+    fn it_round_trips_encoding() {
+        let mut tree = IncrementalMerkle::default();
+        for i in 0..5u64 {
+            tree.ingest(H256::from_low_u64_be(i));
+        }
+        let decoded = IncrementalMerkle::read_from(&mut tree.to_vec().as_slice()).unwrap();
+        assert_eq!(decoded, tree);
+        assert_eq!(decoded.root(), tree.root());
+    }
+
+    #[test]
+    fn it_yields_the_root_after_each_leaf() {
+        let leaves: Vec<_> = (0..5u64).map(H256::from_low_u64_be).collect();
+        let mut expected = IncrementalMerkle::default();
+        let expected_roots: Vec<_> = leaves
+            .iter()
+            .map(|leaf| {
+                expected.ingest(*leaf);
+                expected.root()
+            })
+            .collect();
+
+        let mut tree = IncrementalMerkle::default();
+        let roots: Vec<_> = tree.roots_iter(leaves).collect();
+        assert_eq!(roots, expected_roots);
+        assert_eq!(tree, expected);
+    }
 }
@@ -0,0 +1,212 @@
+//! Optional counting of the hashes merkle tree operations compute, to tell
+//! what proof generation costs per message without profiling.
+//!
+//! Every hash of the accumulators goes through [`hash_concat`], which counts
+//! it on the current thread with the `hash-metrics` feature enabled. Without
+//! it, counting compiles to nothing and [`HashCounters`] holds no data.
+//!
+//! [`hash_concat`]: super::hash_concat
+
+use std::ops::{Add, Sub};
+#[cfg(feature = "hash-metrics")]
+use std::{
+    cell::Cell,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use serde::Serialize;
+
+/// Whether hashes are counted at all, i.e. the `hash-metrics` feature is
+/// enabled
+pub const ENABLED: bool = cfg!(feature = "hash-metrics");
+
+/// Kind of merkle tree operation hashes are counted for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HashOperation {
+    /// Ingesting leaves
+    Ingest,
+    /// Proving leaves
+    Prove,
+    /// Computing roots
+    Root,
+}
+
+impl HashOperation {
+    /// Every operation
+    pub const ALL: [HashOperation; 3] = [Self::Ingest, Self::Prove, Self::Root];
+
+    /// Name of the operation, e.g. as a metric label
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Ingest => "ingest",
+            Self::Prove => "prove",
+            Self::Root => "root",
+        }
+    }
+}
+
+/// Hashes computed, and bytes hashed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct HashCount {
+    /// Number of hash invocations
+    pub hashes: u64,
+    /// Number of bytes hashed
+    pub bytes: u64,
+}
+
+impl Add for HashCount {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            hashes: self.hashes + other.hashes,
+            bytes: self.bytes + other.bytes,
+        }
+    }
+}
+
+impl Sub for HashCount {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self {
+            hashes: self.hashes.saturating_sub(other.hashes),
+            bytes: self.bytes.saturating_sub(other.bytes),
+        }
+    }
+}
+
+#[cfg(feature = "hash-metrics")]
+thread_local! {
+    /// Hashes computed on this thread so far
+    static HASHED: Cell<HashCount> = Cell::new(HashCount::default());
+}
+
+/// Count a hash of `bytes` bytes on the current thread
+#[cfg(feature = "hash-metrics")]
+pub(super) fn record_hash(bytes: usize) {
+    HASHED.with(|hashed| {
+        hashed.set(
+            hashed.get()
+                + HashCount {
+                    hashes: 1,
+                    bytes: bytes as u64,
+                },
+        )
+    });
+}
+
+/// Run `f`, counting the hashes it computes. Counts are always zero with the
+/// `hash-metrics` feature disabled.
+#[inline(always)]
+pub fn count_hashes<T>(f: impl FnOnce() -> T) -> (T, HashCount) {
+    #[cfg(feature = "hash-metrics")]
+    {
+        let before = HASHED.with(Cell::get);
+        let result = f();
+        (result, HASHED.with(Cell::get) - before)
+    }
+    #[cfg(not(feature = "hash-metrics"))]
+    {
+        (f(), HashCount::default())
+    }
+}
+
+/// Running totals of the hashes computed by each operation
+#[derive(Debug, Default)]
+pub struct HashCounters {
+    /// Hashes and bytes hashed, by operation in the order of
+    /// [`HashOperation::ALL`]
+    #[cfg(feature = "hash-metrics")]
+    counts: [[AtomicU64; 2]; 3],
+}
+
+impl HashCounters {
+    /// Add `count` to the totals of `operation`
+    #[inline(always)]
+    #[cfg_attr(not(feature = "hash-metrics"), allow(unused_variables))]
+    pub fn record(&self, operation: HashOperation, count: HashCount) {
+        #[cfg(feature = "hash-metrics")]
+        {
+            let [hashes, bytes] = &self.counts[operation as usize];
+            hashes.fetch_add(count.hashes, Ordering::Relaxed);
+            bytes.fetch_add(count.bytes, Ordering::Relaxed);
+        }
+    }
+
+    /// Totals of `operation` so far
+    #[cfg_attr(not(feature = "hash-metrics"), allow(unused_variables))]
+    pub fn get(&self, operation: HashOperation) -> HashCount {
+        #[cfg(feature = "hash-metrics")]
+        {
+            let [hashes, bytes] = &self.counts[operation as usize];
+            HashCount {
+                hashes: hashes.load(Ordering::Relaxed),
+                bytes: bytes.load(Ordering::Relaxed),
+            }
+        }
+        #[cfg(not(feature = "hash-metrics"))]
+        {
+            HashCount::default()
+        }
+    }
+}
+
+#[cfg(all(test, feature = "hash-metrics"))]
+mod test {
+    use super::*;
+    use crate::{
+        accumulator::{incremental::IncrementalMerkle, TREE_DEPTH},
+        H256,
+    };
+
+    fn hashes(hashes: u64) -> HashCount {
+        HashCount {
+            hashes,
+            bytes: hashes * 64,
+        }
+    }
+
+    #[test]
+    fn it_counts_the_hashes_of_incremental_tree_operations() {
+        let mut tree = IncrementalMerkle::default();
+
+        // Ingesting hashes once per subtree the leaf fills, i.e. the second
+        // leaf fills one and the fourth two
+        let ((), count) = count_hashes(|| {
+            for i in 0..4 {
+                tree.ingest(H256::from_low_u64_be(i));
+            }
+        });
+        assert_eq!(count, hashes(3));
+
+        // The root hashes once per level
+        assert_eq!(count_hashes(|| tree.root()).1, hashes(TREE_DEPTH as u64));
+        assert_eq!(count_hashes(|| tree.root_at_depth(3)).1, hashes(3));
+        // The root of a full subtree was stored by the last ingest
+        assert_eq!(count_hashes(|| tree.root_at_depth(2)).1, hashes(0));
+    }
+
+    #[test]
+    fn it_accumulates_counts_by_operation() {
+        let counters = HashCounters::default();
+        counters.record(HashOperation::Ingest, hashes(3));
+        counters.record(HashOperation::Ingest, hashes(2));
+        counters.record(HashOperation::Root, hashes(32));
+
+        assert_eq!(counters.get(HashOperation::Ingest), hashes(5));
+        assert_eq!(counters.get(HashOperation::Prove), hashes(0));
+        assert_eq!(counters.get(HashOperation::Root), hashes(32));
+    }
+
+    #[test]
+    fn it_counts_nested_operations_in_both() {
+        let mut tree = IncrementalMerkle::default();
+        let ((_, inner), outer) = count_hashes(|| {
+            tree.ingest(H256::zero());
+            count_hashes(|| tree.root())
+        });
+        assert_eq!(inner, hashes(TREE_DEPTH as u64));
+        assert_eq!(outer, inner);
+    }
+}
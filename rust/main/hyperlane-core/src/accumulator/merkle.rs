@@ -76,6 +76,7 @@ pub enum MerkleTree {
 #[derive(Debug, Clone, Copy, serde::Deserialize, serde::Serialize, PartialEq, Eq)]
 pub struct Proof {
     /// The leaf
+    #[serde(with = "crate::hex::Hex32")]
     pub leaf: H256,
     /// The index
     pub index: usize,
@@ -88,6 +89,11 @@ impl Proof {
     pub fn root(&self) -> H256 {
         merkle_root_from_branch(self.leaf, self.path.as_ref(), TREE_DEPTH, self.index)
     }
+
+    /// The merkle branch, without copying it
+    pub fn branch(&self) -> &[H256; TREE_DEPTH] {
+        &self.path
+    }
 }
 
 impl Encode for Proof {
@@ -5,6 +5,8 @@ use crate::H256;
 /// A lightweight incremental merkle, suitable for running on-chain. Stores O
 /// (1) data
 pub mod incremental;
+/// Optional counting of the hashes computed by merkle tree operations.
+pub mod instrumentation;
 /// A full incremental merkle. Suitable for running off-chain.
 pub mod merkle;
 /// Utilities for manipulating proofs to reflect sparse merkle trees.
@@ -16,6 +18,8 @@ pub use zero_hashes::{TREE_DEPTH, ZERO_HASHES};
 const EMPTY_SLICE: &[H256] = &[];
 
 pub(super) fn hash_concat(left: impl AsRef<[u8]>, right: impl AsRef<[u8]>) -> H256 {
+    #[cfg(feature = "hash-metrics")]
+    instrumentation::record_hash(left.as_ref().len() + right.as_ref().len());
     H256::from_slice(
         Keccak256::new()
             .chain(left)
@@ -66,4 +70,18 @@ mod test {
             INITIAL_ROOT
         );
     }
+
+    #[test]
+    fn it_computes_roots_of_shallower_trees() {
+        let leaves: Vec<_> = (0..16).map(H256::from_low_u64_be).collect();
+        let mut tree = incremental::IncrementalMerkle::default();
+        for count in 0..=leaves.len() {
+            if count > 0 {
+                tree.ingest(leaves[count - 1]);
+            }
+            let expected = merkle::MerkleTree::create(&leaves[..count], 4).hash();
+            assert_eq!(tree.root_at_depth(4), expected);
+            assert_eq!(tree.root_at_depth(TREE_DEPTH), tree.root());
+        }
+    }
 }
@@ -0,0 +1,6 @@
+//! Print the known domains as JSON, for tooling which can't link against
+//! this crate, e.g. `cargo run -p hyperlane-core --bin dump-domains`.
+
+fn main() {
+    println!("{}", hyperlane_core::dump_domains_json());
+}
@@ -1,29 +1,396 @@
 #![allow(missing_docs)]
 
 use std::{
+    cmp::Ordering,
     fmt::{Debug, Formatter},
     hash::{Hash, Hasher},
     num::NonZeroU32,
+    ops::{Add, Sub},
+    str::FromStr,
 };
 
 use derive_new::new;
+use num::{bigint::Sign, BigInt, Signed, Zero};
 use num_derive::FromPrimitive;
-use num_traits::FromPrimitive;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 #[cfg(feature = "strum")]
-use strum::{EnumIter, EnumString, IntoStaticStr};
+use strum::{EnumIter, EnumString, IntoEnumIterator, IntoStaticStr};
 
 use crate::{
-    utils::many_to_one, ChainCommunicationError, HyperlaneProtocolError, IndexMode, H160, H256,
+    utils::{
+        bytes_to_hex, closest_names, fmt_did_you_mean, is_padded_h160, many_to_one,
+        to_checksum_address,
+    },
+    ChainCommunicationError, ConversionError, IndexMode, H160, H256, U256,
 };
 
+/// An address on any domain, as its raw bytes. Addresses are compared,
+/// ordered and hashed by their [canonical](Address::canonicalize) form, so an
+/// EVM address equals the same address padded to 32 bytes, i.e.
+/// `Address::from(h160) == Address::try_from_slice(H256::from(h160).as_bytes())?`.
 #[derive(Debug, Clone)]
 pub struct Address(pub bytes::Bytes);
 
-#[derive(Debug, Clone)]
+/// Bytes addresses shorter than it are padded to when canonicalized
+const CANONICAL_ADDRESS_LEN: usize = 32;
+
+/// A string which isn't a hex encoded address
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressParseError {
+    /// An odd number of hex digits, which can't be whole bytes
+    #[error("Odd number of hex digits ({0}) in address")]
+    OddLength(usize),
+    /// A character which isn't a hex digit
+    #[error("Invalid character {character:?} at position {index} in address")]
+    InvalidCharacter { character: char, index: usize },
+}
+
+/// Parses hex, with or without a `0x` prefix and in any case
+impl FromStr for Address {
+    type Err = AddressParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let digits = s.strip_prefix("0x").unwrap_or(s);
+        hex::decode(digits)
+            .map(|bytes| Address(bytes.into()))
+            .map_err(|err| match err {
+                hex::FromHexError::InvalidHexCharacter { c, index } => {
+                    AddressParseError::InvalidCharacter {
+                        character: c,
+                        index: index + s.len() - digits.len(),
+                    }
+                }
+                hex::FromHexError::OddLength | hex::FromHexError::InvalidStringLength => {
+                    AddressParseError::OddLength(digits.len())
+                }
+            })
+    }
+}
+
+/// Prefixed hex, checksummed as in EIP-55 for 20 byte addresses and lowercase
+/// otherwise
+impl std::fmt::Display for Address {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.0.len() == 20 {
+            f.write_str(&to_checksum_address(&H160::from_slice(&self.0)))
+        } else {
+            f.write_str(&bytes_to_hex(&self.0))
+        }
+    }
+}
+
+impl Address {
+    /// An address of 20 or 32 bytes, copying them. Other lengths are
+    /// rejected up front, rather than where the address is converted.
+    pub fn try_from_slice(bytes: &[u8]) -> Result<Self, ConversionError> {
+        match bytes.len() {
+            20 | 32 => Ok(Address(bytes.to_vec().into())),
+            len => Err(ConversionError::InvalidAddressLength(len)),
+        }
+    }
+
+    /// The bytes of the address, without copying them
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// The address padded with zeros on the left to 32 bytes, like message
+    /// senders and recipients. Addresses of 32 bytes or more are unchanged.
+    pub fn canonicalize(&self) -> Address {
+        if self.0.len() >= CANONICAL_ADDRESS_LEN {
+            return self.clone();
+        }
+        Address(self.canonical_bytes().collect::<Vec<_>>().into())
+    }
+
+    /// The bytes of the canonical form, without allocating it
+    fn canonical_bytes(&self) -> impl Iterator<Item = u8> + '_ {
+        let padding = CANONICAL_ADDRESS_LEN.saturating_sub(self.0.len());
+        std::iter::repeat(0)
+            .take(padding)
+            .chain(self.0.iter().copied())
+    }
+
+    /// The EVM address of an address on `domain`. Addresses of non-EVM
+    /// domains and ones with bytes beyond the 20 of an EVM address are
+    /// rejected rather than truncated.
+    pub fn to_h160(&self, domain: &HyperlaneDomain) -> Result<H160, ConversionError> {
+        if !domain.is_evm() {
+            return Err(ConversionError::NonEvmAddress(domain.domain_protocol()));
+        }
+        H160::try_from(self)
+    }
+
+    /// The EVM address this is, if it's one of 20 bytes or 32 bytes padded
+    /// from one. Unlike converting from an [`H256`], bytes beyond the 20 of
+    /// an EVM address are an error rather than dropped.
+    pub fn into_evm_h160(self) -> Result<H160, ConversionError> {
+        H160::try_from(&self)
+    }
+}
+
+impl PartialEq for Address {
+    fn eq(&self, other: &Self) -> bool {
+        self.canonical_bytes().eq(other.canonical_bytes())
+    }
+}
+
+impl Eq for Address {}
+
+impl PartialOrd for Address {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Address {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.canonical_bytes().cmp(other.canonical_bytes())
+    }
+}
+
+impl Hash for Address {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.canonical_bytes().for_each(|byte| state.write_u8(byte));
+    }
+}
+
+/// The 20 bytes of an EVM address
+impl From<H160> for Address {
+    fn from(h160: H160) -> Self {
+        Address(h160.as_bytes().to_vec().into())
+    }
+}
+
+/// The 32 bytes of e.g. a message sender or recipient, as on the wire
+impl From<H256> for Address {
+    fn from(h256: H256) -> Self {
+        Address(h256.as_bytes().to_vec().into())
+    }
+}
+
+/// 32 byte addresses, and 20 byte ones padded with zeros on the left like
+/// message senders and recipients
+impl TryFrom<&Address> for H256 {
+    type Error = ConversionError;
+
+    fn try_from(address: &Address) -> Result<Self, Self::Error> {
+        match address.0.len() {
+            20 => Ok(H256::from(H160::from_slice(&address.0))),
+            32 => Ok(H256::from_slice(&address.0)),
+            len => Err(ConversionError::InvalidAddressLength(len)),
+        }
+    }
+}
+
+/// 20 byte addresses, and 32 byte ones padded from 20 bytes. Addresses with
+/// bytes beyond the 20 of an EVM address are rejected rather than truncated.
+impl TryFrom<&Address> for H160 {
+    type Error = ConversionError;
+
+    fn try_from(address: &Address) -> Result<Self, Self::Error> {
+        match address.0.len() {
+            20 => Ok(H160::from_slice(&address.0)),
+            32 => {
+                let h256 = H256::from_slice(&address.0);
+                if !is_padded_h160(&h256) {
+                    return Err(ConversionError::InvalidEvmAddressPadding(h256));
+                }
+                Ok(H160::from(h256))
+            }
+            len => Err(ConversionError::InvalidAddressLength(len)),
+        }
+    }
+}
+
+/// An amount of a domain's native token, in its smallest unit. Unlike the
+/// [`U256`] providers report balances as, it may go negative, e.g. when
+/// subtracting more than the balance, see [`Balance::checked_sub`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Balance(pub num::BigInt);
 
+/// Digits after the decimal point a balance is displayed with
+const BALANCE_DISPLAY_DECIMALS: usize = 4;
+
+impl Balance {
+    /// Whether the balance is zero
+    pub fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+
+    /// The balance less `other`, or `None` if it would be negative
+    pub fn checked_sub(&self, other: &Balance) -> Option<Balance> {
+        let difference = &self.0 - &other.0;
+        (!difference.is_negative()).then_some(Balance(difference))
+    }
+
+    /// The balance in whole units of a token with `decimals`, e.g. `1.394023`,
+    /// keeping every fractional digit but trailing zeros
+    pub fn format_units(&self, decimals: u8) -> String {
+        let (sign, whole, fraction) = self.split_units(decimals);
+        fmt_units(sign, &whole, fraction.trim_end_matches('0'))
+    }
+
+    /// Parse an amount in whole units of a token with `decimals`, e.g. `0.5`.
+    /// Amounts with more fractional digits than the token has are rejected
+    /// rather than rounded.
+    pub fn from_units(amount: &str, decimals: u8) -> Result<Balance, BalanceParseError> {
+        let invalid = || BalanceParseError::Invalid(amount.to_owned());
+        let (sign, unsigned) = match amount.strip_prefix('-') {
+            Some(unsigned) => ("-", unsigned),
+            None => ("", amount),
+        };
+        let (whole, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+        let is_digits = |digits: &str| digits.bytes().all(|b| b.is_ascii_digit());
+        if (whole.is_empty() && fraction.is_empty()) || !is_digits(whole) || !is_digits(fraction) {
+            return Err(invalid());
+        }
+        if fraction.len() > decimals as usize {
+            return Err(BalanceParseError::TooManyDecimals {
+                amount: amount.to_owned(),
+                decimals,
+            });
+        }
+        let digits = format!(
+            "{sign}{whole}{fraction:0<width$}",
+            width = decimals as usize
+        );
+        digits.parse().map(Balance).map_err(|_| invalid())
+    }
+
+    /// The balance in units of the domain's native token, e.g. `1.2534 ETH`,
+    /// truncated to a few decimals
+    pub fn display_for_domain(&self, domain: &HyperlaneDomain) -> String {
+        let NativeToken { symbol, decimals } = domain.native_token();
+        let (sign, whole, fraction) = self.split_units(decimals);
+        let fraction = &fraction[..fraction.len().min(BALANCE_DISPLAY_DECIMALS)];
+        let amount = fmt_units(sign, &whole, fraction.trim_end_matches('0'));
+        format!("{amount} {symbol}")
+    }
+
+    /// The sign, whole units and every fractional digit of the balance in a
+    /// token with `decimals`
+    fn split_units(&self, decimals: u8) -> (&'static str, String, String) {
+        let decimals = decimals as usize;
+        let amount = self.0.to_string();
+        let (sign, digits) = match amount.strip_prefix('-') {
+            Some(digits) => ("-", digits),
+            None => ("", amount.as_str()),
+        };
+        let digits = format!("{digits:0>width$}", width = decimals + 1);
+        let (whole, fraction) = digits.split_at(digits.len() - decimals);
+        (sign, whole.to_owned(), fraction.to_owned())
+    }
+}
+
+/// An amount of whole units, without a sign if it's truncated to zero
+fn fmt_units(sign: &str, whole: &str, fraction: &str) -> String {
+    match (whole, fraction) {
+        ("0", "") => "0".to_owned(),
+        (whole, "") => format!("{sign}{whole}"),
+        (whole, fraction) => format!("{sign}{whole}.{fraction}"),
+    }
+}
+
+/// A string which isn't an amount of whole units of a token
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum BalanceParseError {
+    /// Not a decimal number
+    #[error("Invalid amount {0:?}, expected a decimal number like 0.5")]
+    Invalid(String),
+    /// More fractional digits than the token has decimals
+    #[error("Amount {amount:?} has more than the {decimals} decimals of the token")]
+    TooManyDecimals { amount: String, decimals: u8 },
+}
+
+impl Add for Balance {
+    type Output = Balance;
+
+    fn add(self, other: Balance) -> Balance {
+        Balance(self.0 + other.0)
+    }
+}
+
+/// Goes negative rather than saturating or panicking when `other` is larger
+impl Sub for Balance {
+    type Output = Balance;
+
+    fn sub(self, other: Balance) -> Balance {
+        Balance(self.0 - other.0)
+    }
+}
+
+impl From<u64> for Balance {
+    fn from(amount: u64) -> Self {
+        Balance(amount.into())
+    }
+}
+
+impl From<U256> for Balance {
+    fn from(amount: U256) -> Self {
+        let mut bytes = [0u8; 32];
+        amount.to_little_endian(&mut bytes);
+        Balance(BigInt::from_bytes_le(Sign::Plus, &bytes))
+    }
+}
+
+/// Balances which are negative or don't fit in 256 bits are rejected
+impl TryFrom<&Balance> for U256 {
+    type Error = ConversionError;
+
+    fn try_from(balance: &Balance) -> Result<Self, Self::Error> {
+        let (sign, bytes) = balance.0.to_bytes_le();
+        if sign == Sign::Minus || bytes.len() > 32 {
+            return Err(ConversionError::BalanceOutOfRange(balance.0.clone()));
+        }
+        Ok(U256::from_little_endian(&bytes))
+    }
+}
+
+#[cfg(feature = "ethers")]
+impl From<ethers_core::types::U256> for Balance {
+    fn from(amount: ethers_core::types::U256) -> Self {
+        Balance::from(U256::from(amount))
+    }
+}
+
+#[cfg(feature = "ethers")]
+impl TryFrom<&Balance> for ethers_core::types::U256 {
+    type Error = ConversionError;
+
+    fn try_from(balance: &Balance) -> Result<Self, Self::Error> {
+        U256::try_from(balance).map(Into::into)
+    }
+}
+
+/// The native token of a domain, which gas is paid in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NativeToken {
+    /// Ticker symbol
+    pub symbol: &'static str,
+    /// Decimals of the token's whole unit
+    pub decimals: u8,
+}
+
+impl NativeToken {
+    /// The native token assumed for domains which aren't known
+    pub const UNKNOWN: Self = Self {
+        symbol: "NATIVE",
+        decimals: 18,
+    };
+}
+
+/// The token a domain pays gas in, for weighing gas costs across domains
+#[derive(Debug, Clone)]
+pub struct GasToken {
+    /// Ticker symbol of the native token
+    pub symbol: &'static str,
+    /// The canonical contract wrapping the native token, if the domain has
+    /// one which is tracked
+    pub wrapped_address: Option<Address>,
+}
+
 #[derive(Debug, Clone, new)]
 pub struct ContractLocator<'a> {
     pub domain: &'a HyperlaneDomain,
@@ -35,14 +402,84 @@ impl<'a> std::fmt::Display for ContractLocator<'a> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "{}[@{}]+contract:0x{:x}",
+            "{}[@{}]+contract:{}",
             self.domain.name(),
             self.domain.id(),
-            self.address
+            Address::from(self.address)
         )
     }
 }
 
+/// A string which isn't a [`ContractLocator`] of a known domain
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ContractLocatorParseError {
+    /// Not of the form `name[@id]+contract:0x…`
+    #[error("Expected a contract locator like `ethereum[@1]+contract:0x…`, got {0:?}")]
+    Malformed(String),
+    /// The domain id isn't a known domain
+    #[error(transparent)]
+    UnknownDomain(#[from] UnknownDomainError),
+    /// The chain name isn't the name of the domain with the id
+    #[error("Chain name {name:?} isn't the name of domain {domain_id}")]
+    DomainMismatch { name: String, domain_id: u32 },
+    /// The contract address isn't hex
+    #[error(transparent)]
+    InvalidAddress(#[from] AddressParseError),
+    /// The contract address is neither 20 nor 32 bytes
+    #[error("Invalid contract address length {0}, expected 20 or 32 bytes")]
+    InvalidAddressLength(usize),
+}
+
+#[cfg(feature = "strum")]
+impl ContractLocator<'static> {
+    /// The locator of a contract on the known domain with `domain_id`, as
+    /// long as `name` is that domain's name or an alias of it, e.g. from
+    /// config which has both. An empty name is taken to be the domain's.
+    pub fn try_new(
+        name: &str,
+        domain_id: u32,
+        address: H256,
+    ) -> Result<Self, ContractLocatorParseError> {
+        let domain = HyperlaneDomain::all()
+            .iter()
+            .find(|(_, _, id)| *id == domain_id)
+            .map(|(domain, ..)| domain)
+            .ok_or(UnknownDomainError { domain_id })?;
+        if !name.is_empty() && HyperlaneDomain::from_str(name).ok().as_ref() != Some(domain) {
+            return Err(ContractLocatorParseError::DomainMismatch {
+                name: name.to_owned(),
+                domain_id,
+            });
+        }
+        Ok(ContractLocator { domain, address })
+    }
+}
+
+/// Parses the [`Display`](std::fmt::Display) form of a locator, e.g.
+/// `ethereum[@1]+contract:0x…`. The chain name may be left out, as in
+/// `[@1]+contract:0x…`, and is otherwise checked against the domain id, see
+/// [`ContractLocator::try_new`]. Only known domains can be parsed, since the
+/// locator borrows its domain.
+#[cfg(feature = "strum")]
+impl FromStr for ContractLocator<'static> {
+    type Err = ContractLocatorParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let malformed = || ContractLocatorParseError::Malformed(s.to_owned());
+        let (domain, address) = s.split_once("+contract:").ok_or_else(malformed)?;
+        let (name, domain_id) = domain
+            .strip_suffix(']')
+            .and_then(|domain| domain.split_once("[@"))
+            .ok_or_else(malformed)?;
+        let domain_id: u32 = domain_id.parse().map_err(|_| malformed())?;
+
+        let address: Address = address.parse()?;
+        let address = H256::try_from(&address)
+            .map_err(|_| ContractLocatorParseError::InvalidAddressLength(address.0.len()))?;
+        ContractLocator::try_new(name, domain_id, address)
+    }
+}
+
 #[derive(Default, Debug, Clone, PartialEq)]
 pub enum ReorgPeriod {
     #[default]
@@ -117,95 +554,270 @@ impl<'de> Deserialize<'de> for ReorgPeriod {
     }
 }
 
-/// All domains supported by Hyperlane.
-#[derive(FromPrimitive, PartialEq, Eq, Debug, Clone, Copy, Hash, Serialize)]
-#[cfg_attr(
-    feature = "strum",
-    derive(strum::Display, EnumString, IntoStaticStr, EnumIter)
-)]
-#[cfg_attr(
-    feature = "strum",
-    strum(serialize_all = "lowercase", ascii_case_insensitive)
-)]
-pub enum KnownHyperlaneDomain {
-    Ancient8 = 888888888,
-    Arbitrum = 42161,
-    Avalanche = 43114,
-    #[cfg_attr(feature = "strum", strum(serialize = "bsc"))]
-    BinanceSmartChain = 56,
-    Blast = 81457,
-    Bob = 60808,
-    Celo = 42220,
-    Cheesechain = 383353,
-    Cyber = 7560,
-    DegenChain = 666666666,
-    EclipseMainnet = 1408864445,
-    Endurance = 648,
-    Ethereum = 1,
-    Fraxtal = 252,
-    Fuji = 43113,
-    FuseMainnet = 122,
-    Gnosis = 100,
-    InEvm = 2525,
-    Injective = 6909546,
-    Kroma = 255,
-    Linea = 59144,
-    Lisk = 1135,
-    Lukso = 42,
-    MantaPacific = 169,
-    Mantle = 5000,
-    Merlin = 4200,
-    Metis = 1088,
-    Mint = 185,
-    Mode = 34443,
-    Moonbeam = 1284,
-    Neutron = 1853125230,
-    Optimism = 10,
-    Osmosis = 875,
-    Polygon = 137,
-    ProofOfPlay = 70700,
-    ReAl = 111188,
-    Redstone = 690,
-    Sanko = 1996,
-    Sei = 1329,
-    SolanaMainnet = 1399811149,
-    Taiko = 167000,
-    Tangle = 5845,
-    Viction = 88,
-    Worldchain = 480,
-    Xai = 660279,
-    Xlayer = 196,
-    Zetachain = 7000,
-    Zircuit = 48900,
-    ZoraMainnet = 7777777,
+/// Blocks to wait before treating an event as final on domains without a
+/// more specific default, e.g. rollups whose sequencer orders blocks
+pub const DEFAULT_FINALITY_BLOCKS: u32 = 1;
+
+/// Defines the known domains from a single table of `variant: "name" = id,
+/// type;` rows, expanding into the [`KnownHyperlaneDomain`] enum, its name
+/// and id conversions and its [`HyperlaneDomainType`]. Adding a chain is a
+/// one row change. Alternative names a domain is also parsed from follow its
+/// name, as in `"name" | "alias"`. Duplicate ids fail to compile as duplicate
+/// enum discriminants, and duplicate names or aliases fail a const assertion.
+macro_rules! domain_and_chain {
+    ($(
+        $(#[$meta:meta])*
+        $variant:ident: $name:literal $(| $alias:literal)* = $id:literal, $domain_type:ident;
+    )*) => {
+        /// All domains supported by Hyperlane.
+        #[derive(PartialEq, Eq, Debug, Clone, Copy, Hash, Serialize)]
+        #[cfg_attr(feature = "strum", derive(EnumIter))]
+        pub enum KnownHyperlaneDomain {
+            $($(#[$meta])* $variant = $id,)*
+        }
+
+        // No two domains may share a name or alias, which are matched
+        // case-insensitively
+        const _: () = {
+            let names = KnownHyperlaneDomain::NAMES;
+            let mut i = 0;
+            while i < names.len() {
+                let mut j = i + 1;
+                while j < names.len() {
+                    assert!(
+                        !eq_ignore_ascii_case(names[i], names[j]),
+                        "Two known domains have the same name"
+                    );
+                    j += 1;
+                }
+                i += 1;
+            }
+        };
+
+        impl KnownHyperlaneDomain {
+            /// The names and aliases of all the known domains
+            pub const NAMES: &'static [&'static str] = &[$($name, $($alias,)*)*];
+
+            /// The name of the domain, as used in config
+            pub const fn as_str(self) -> &'static str {
+                match self {
+                    $(Self::$variant => $name,)*
+                }
+            }
+
+            pub const fn domain_type(self) -> HyperlaneDomainType {
+                match self {
+                    $(Self::$variant => HyperlaneDomainType::$domain_type,)*
+                }
+            }
+
+            /// The known domain with the given id
+            pub const fn from_domain_id(domain_id: u32) -> Option<Self> {
+                match domain_id {
+                    $($id => Some(Self::$variant),)*
+                    _ => None,
+                }
+            }
+
+            /// The name of the known domain with the given id, as used in
+            /// config
+            pub const fn name_from_domain_id(domain_id: u32) -> Option<&'static str> {
+                match domain_id {
+                    $($id => Some($name),)*
+                    _ => None,
+                }
+            }
+        }
+
+        impl HyperlaneDomain {
+            /// Every known domain, with its name and id
+            pub fn all() -> &'static [(HyperlaneDomain, &'static str, u32)] {
+                static ALL: &[(HyperlaneDomain, &str, u32)] = &[
+                    $((HyperlaneDomain::Known(KnownHyperlaneDomain::$variant), $name, $id),)*
+                ];
+                ALL
+            }
+        }
+
+        impl TryFrom<u32> for KnownHyperlaneDomain {
+            type Error = UnknownDomainError;
+
+            fn try_from(domain_id: u32) -> Result<Self, Self::Error> {
+                Self::from_domain_id(domain_id).ok_or(UnknownDomainError { domain_id })
+            }
+        }
+
+        impl FromStr for KnownHyperlaneDomain {
+            type Err = UnknownDomainNameError;
+
+            /// Parse a domain name or one of its aliases, ignoring ASCII case
+            fn from_str(name: &str) -> Result<Self, Self::Err> {
+                $(if name.eq_ignore_ascii_case($name)
+                    $(|| name.eq_ignore_ascii_case($alias))*
+                {
+                    return Ok(Self::$variant);
+                })*
+                Err(UnknownDomainNameError {
+                    name: name.to_owned(),
+                })
+            }
+        }
+    };
+}
+
+/// A known domain in [`dump_domains_json`]
+#[cfg(feature = "strum")]
+#[derive(Serialize)]
+struct DomainEntry {
+    name: &'static str,
+    id: u32,
+    #[serde(rename = "type")]
+    domain_type: &'static str,
+    protocol: &'static str,
+}
+
+/// The known domains as a JSON array of their name, id, type and protocol,
+/// for tooling in other languages to consume
+#[cfg(feature = "strum")]
+pub fn dump_domains_json() -> String {
+    let entries: Vec<DomainEntry> = HyperlaneDomain::all()
+        .iter()
+        .map(|(domain, name, id)| DomainEntry {
+            name,
+            id: *id,
+            domain_type: domain.domain_type().into(),
+            protocol: domain.domain_protocol().into(),
+        })
+        .collect();
+    serde_json::to_string_pretty(&entries).expect("domain entries always serialize")
+}
+
+/// Names of retired testnets which resolve to the domain replacing them while
+/// deployments migrate, so that configs still using them keep loading. They
+/// aren't accepted as names of the domain otherwise, e.g. by `FromStr`, since
+/// the retired testnet had its own domain id.
+const DEPRECATED_DOMAIN_NAMES: &[(&str, KnownHyperlaneDomain)] =
+    &[("basegoerli", KnownHyperlaneDomain::BaseSepolia)];
+
+impl KnownHyperlaneDomain {
+    /// The domain replacing the retired testnet with the deprecated `name`,
+    /// ignoring ASCII case
+    pub fn from_deprecated_name(name: &str) -> Option<Self> {
+        DEPRECATED_DOMAIN_NAMES
+            .iter()
+            .find(|(deprecated, _)| deprecated.eq_ignore_ascii_case(name))
+            .map(|(_, domain)| *domain)
+    }
+}
+
+/// `str::eq_ignore_ascii_case`, usable in const contexts
+const fn eq_ignore_ascii_case(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i].to_ascii_lowercase() != b[i].to_ascii_lowercase() {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+domain_and_chain! {
+    Ancient8: "ancient8" = 888888888, Mainnet;
+    Arbitrum: "arbitrum" = 42161, Mainnet;
+    Avalanche: "avalanche" = 43114, Mainnet;
+    BinanceSmartChain: "bsc" | "binancesmartchain" = 56, Mainnet;
+    Blast: "blast" = 81457, Mainnet;
+    Bob: "bob" = 60808, Mainnet;
+    Celo: "celo" = 42220, Mainnet;
+    Cheesechain: "cheesechain" = 383353, Mainnet;
+    Cyber: "cyber" = 7560, Mainnet;
+    DegenChain: "degenchain" = 666666666, Mainnet;
+    EclipseMainnet: "eclipsemainnet" = 1408864445, Mainnet;
+    Endurance: "endurance" = 648, Mainnet;
+    Ethereum: "ethereum" = 1, Mainnet;
+    Fraxtal: "fraxtal" = 252, Mainnet;
+    Fuji: "fuji" = 43113, Testnet;
+    FuseMainnet: "fusemainnet" = 122, Mainnet;
+    Gnosis: "gnosis" = 100, Mainnet;
+    InEvm: "inevm" = 2525, Mainnet;
+    Injective: "injective" = 6909546, Mainnet;
+    Kroma: "kroma" = 255, Mainnet;
+    Linea: "linea" = 59144, Mainnet;
+    Lisk: "lisk" = 1135, Mainnet;
+    Lukso: "lukso" = 42, Mainnet;
+    MantaPacific: "mantapacific" = 169, Mainnet;
+    Mantle: "mantle" = 5000, Mainnet;
+    Merlin: "merlin" = 4200, Mainnet;
+    Metis: "metis" = 1088, Mainnet;
+    Mint: "mint" = 185, Mainnet;
+    Mode: "mode" = 34443, Mainnet;
+    Moonbeam: "moonbeam" = 1284, Mainnet;
+    Neutron: "neutron" = 1853125230, Mainnet;
+    Optimism: "optimism" = 10, Mainnet;
+    Osmosis: "osmosis" = 875, Mainnet;
+    Polygon: "polygon" | "matic" = 137, Mainnet;
+    ProofOfPlay: "proofofplay" = 70700, Mainnet;
+    ReAl: "real" = 111188, Mainnet;
+    Redstone: "redstone" = 690, Mainnet;
+    Sanko: "sanko" = 1996, Mainnet;
+    Sei: "sei" = 1329, Mainnet;
+    SolanaMainnet: "solanamainnet" = 1399811149, Mainnet;
+    Taiko: "taiko" = 167000, Mainnet;
+    Tangle: "tangle" = 5845, Mainnet;
+    Viction: "viction" = 88, Mainnet;
+    Worldchain: "worldchain" = 480, Mainnet;
+    Xai: "xai" = 660279, Mainnet;
+    Xlayer: "xlayer" = 196, Mainnet;
+    Zetachain: "zetachain" = 7000, Mainnet;
+    Zircuit: "zircuit" = 48900, Mainnet;
+    ZoraMainnet: "zoramainnet" = 7777777, Mainnet;
 
     // -- Local chains --
     //
-    Test1 = 9913371,
-    Test2 = 9913372,
-    Test3 = 9913373,
-    FuelTest1 = 13374,
-    SealevelTest1 = 13375,
-    SealevelTest2 = 13376,
-    CosmosTest99990 = 99990,
-    CosmosTest99991 = 99991,
+    Test1: "test1" = 9913371, LocalTestChain;
+    Test2: "test2" = 9913372, LocalTestChain;
+    Test3: "test3" = 9913373, LocalTestChain;
+    FuelTest1: "fueltest1" = 13374, LocalTestChain;
+    SealevelTest1: "sealeveltest1" = 13375, LocalTestChain;
+    SealevelTest2: "sealeveltest2" = 13376, LocalTestChain;
+    CosmosTest99990: "cosmostest99990" = 99990, LocalTestChain;
+    CosmosTest99991: "cosmostest99991" = 99991, LocalTestChain;
 
     // -- Test chains --
     //
-    Alfajores = 44787,
-    #[cfg_attr(feature = "strum", strum(serialize = "bsctestnet"))]
-    BinanceSmartChainTestnet = 97,
-    Chiado = 10200,
-    ConnextSepolia = 6398,
-    Holesky = 17000,
-    MoonbaseAlpha = 1287,
-    PlumeTestnet = 161221135,
-    ScrollSepolia = 534351,
-    Sepolia = 11155111,
-    SuperpositionTestnet = 98985,
-}
-
-#[derive(Clone, Serialize)]
+    Alfajores: "alfajores" = 44787, Testnet;
+    ArbitrumSepolia: "arbitrumsepolia" = 421614, Testnet;
+    BaseSepolia: "basesepolia" = 84532, Testnet;
+    BinanceSmartChainTestnet: "bsctestnet" = 97, Testnet;
+    Chiado: "chiado" = 10200, Testnet;
+    ConnextSepolia: "connextsepolia" = 6398, Testnet;
+    Holesky: "holesky" = 17000, Testnet;
+    MoonbaseAlpha: "moonbasealpha" = 1287, Testnet;
+    OptimismSepolia: "optimismsepolia" = 11155420, Testnet;
+    PlumeTestnet: "plumetestnet" = 161221135, Testnet;
+    ScrollSepolia: "scrollsepolia" = 534351, Testnet;
+    Sepolia: "sepolia" = 11155111, Testnet;
+    SuperpositionTestnet: "superpositiontestnet" = 98985, Testnet;
+}
+
+impl std::fmt::Display for KnownHyperlaneDomain {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<KnownHyperlaneDomain> for &'static str {
+    fn from(domain: KnownHyperlaneDomain) -> Self {
+        domain.as_str()
+    }
+}
+
+#[derive(Clone)]
 pub enum HyperlaneDomain {
     Known(KnownHyperlaneDomain),
     Unknown {
@@ -230,8 +842,10 @@ impl HyperlaneDomain {
     }
 }
 
-/// Types of Hyperlane domains.
-#[derive(FromPrimitive, Copy, Clone, Eq, PartialEq, Debug, Serialize)]
+/// Types of Hyperlane domains. They display and serialize in lowercase, e.g.
+/// as `localtestchain`, which is also how they're labelled in metrics.
+#[derive(FromPrimitive, Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 #[cfg_attr(
     feature = "strum",
     derive(strum::Display, EnumString, IntoStaticStr, EnumIter)
@@ -251,6 +865,39 @@ pub enum HyperlaneDomainType {
     Unknown,
 }
 
+impl HyperlaneDomainType {
+    /// Whether this is a mainnet
+    pub const fn is_mainnet(self) -> bool {
+        // matched exhaustively so that a new type has to be considered here
+        match self {
+            HyperlaneDomainType::Mainnet => true,
+            HyperlaneDomainType::Testnet
+            | HyperlaneDomainType::LocalTestChain
+            | HyperlaneDomainType::Unknown => false,
+        }
+    }
+
+    /// Whether this is a testnet
+    pub const fn is_testnet(self) -> bool {
+        match self {
+            HyperlaneDomainType::Testnet => true,
+            HyperlaneDomainType::Mainnet
+            | HyperlaneDomainType::LocalTestChain
+            | HyperlaneDomainType::Unknown => false,
+        }
+    }
+
+    /// Whether this is a local chain for testing
+    pub const fn is_local_test_chain(self) -> bool {
+        match self {
+            HyperlaneDomainType::LocalTestChain => true,
+            HyperlaneDomainType::Mainnet
+            | HyperlaneDomainType::Testnet
+            | HyperlaneDomainType::Unknown => false,
+        }
+    }
+}
+
 /// Hyperlane domain protocol types.
 #[derive(FromPrimitive, Copy, Clone, Eq, PartialEq, Debug, Serialize)]
 #[cfg_attr(
@@ -273,10 +920,18 @@ pub enum HyperlaneDomainProtocol {
 }
 
 impl HyperlaneDomainProtocol {
+    /// Whether domains of this protocol are EVM chains
+    pub const fn is_evm(self) -> bool {
+        matches!(self, HyperlaneDomainProtocol::Ethereum)
+    }
+
+    /// Pretty print an address on a domain of this protocol, never dropping
+    /// non-zero bytes
     pub fn fmt_address(&self, addr: H256) -> String {
         use HyperlaneDomainProtocol::*;
         match self {
-            Ethereum => format!("{:?}", H160::from(addr)),
+            Ethereum if is_padded_h160(&addr) => to_checksum_address(&H160::from(addr)),
+            Ethereum => format!("{:?}", addr),
             Fuel => format!("{:?}", addr),
             Sealevel => format!("{:?}", addr),
             Cosmos => format!("{:?}", addr),
@@ -298,6 +953,7 @@ pub enum HyperlaneDomainTechnicalStack {
     ArbitrumNitro,
     OpStack,
     PolygonCDK,
+    PolygonPoS,
     PolkadotSubstrate,
     ZkSync,
     #[default]
@@ -305,34 +961,6 @@ pub enum HyperlaneDomainTechnicalStack {
 }
 
 impl KnownHyperlaneDomain {
-    #[cfg(feature = "strum")]
-    pub fn as_str(self) -> &'static str {
-        self.into()
-    }
-
-    pub const fn domain_type(self) -> HyperlaneDomainType {
-        use self::{HyperlaneDomainType::*, KnownHyperlaneDomain::*};
-
-        many_to_one!(match self {
-            Mainnet: [
-                Ancient8, Arbitrum, Avalanche, BinanceSmartChain, Blast, Bob, Celo, Cheesechain, Cyber,
-                DegenChain, EclipseMainnet, Endurance, Ethereum, Fraxtal, FuseMainnet, Gnosis,
-                InEvm, Injective, Kroma, Linea, Lisk, Lukso, MantaPacific, Mantle, Merlin,
-                Metis, Mint, Mode, Moonbeam, Neutron, Optimism, Osmosis, Polygon, ProofOfPlay,
-                ReAl, Redstone, Sanko, Sei, SolanaMainnet, Taiko, Tangle, Viction, Worldchain, Xai,
-                Xlayer, Zetachain, Zircuit, ZoraMainnet,
-            ],
-            Testnet: [
-                Alfajores, BinanceSmartChainTestnet, Chiado, ConnextSepolia, Fuji, Holesky, MoonbaseAlpha,
-                PlumeTestnet, ScrollSepolia, Sepolia, SuperpositionTestnet
-            ],
-            LocalTestChain: [
-                Test1, Test2, Test3, FuelTest1, SealevelTest1, SealevelTest2, CosmosTest99990,
-                CosmosTest99991
-            ],
-        })
-    }
-
     pub const fn domain_protocol(self) -> HyperlaneDomainProtocol {
         use KnownHyperlaneDomain::*;
 
@@ -348,8 +976,9 @@ impl KnownHyperlaneDomain {
                 Test1, Test2, Test3,
 
                 // Test chains
-                Alfajores, BinanceSmartChainTestnet, Chiado, ConnextSepolia, Holesky, MoonbaseAlpha, PlumeTestnet,
-                ScrollSepolia, Sepolia, SuperpositionTestnet
+                Alfajores, ArbitrumSepolia, BaseSepolia, BinanceSmartChainTestnet, Chiado, ConnextSepolia,
+                Holesky, MoonbaseAlpha, OptimismSepolia, PlumeTestnet, ScrollSepolia, Sepolia,
+                SuperpositionTestnet
 
             ],
             HyperlaneDomainProtocol::Fuel: [FuelTest1],
@@ -363,39 +992,380 @@ impl KnownHyperlaneDomain {
         })
     }
 
-    pub const fn domain_technical_stack(self) -> HyperlaneDomainTechnicalStack {
+    /// The canonical EVM chain id of the domain, or `None` if it isn't an EVM
+    /// chain. Chain ids are not always the same as domain ids, so this is kept
+    /// as an explicit table rather than derived.
+    pub const fn evm_chain_id(self) -> Option<u64> {
         use KnownHyperlaneDomain::*;
 
-        many_to_one!(match self {
-            HyperlaneDomainTechnicalStack::ArbitrumNitro: [
-                Arbitrum, Cheesechain, DegenChain, InEvm, ProofOfPlay, ReAl, Sanko, Xai,
+        match self {
+            Ancient8 => Some(888888888),
+            Arbitrum => Some(42161),
+            Avalanche => Some(43114),
+            BinanceSmartChain => Some(56),
+            Blast => Some(81457),
+            Bob => Some(60808),
+            Celo => Some(42220),
+            Cheesechain => Some(383353),
+            Cyber => Some(7560),
+            DegenChain => Some(666666666),
+            Endurance => Some(648),
+            Ethereum => Some(1),
+            Fraxtal => Some(252),
+            Fuji => Some(43113),
+            FuseMainnet => Some(122),
+            Gnosis => Some(100),
+            InEvm => Some(2525),
+            Kroma => Some(255),
+            Linea => Some(59144),
+            Lisk => Some(1135),
+            Lukso => Some(42),
+            MantaPacific => Some(169),
+            Mantle => Some(5000),
+            Merlin => Some(4200),
+            Metis => Some(1088),
+            Mint => Some(185),
+            Mode => Some(34443),
+            Moonbeam => Some(1284),
+            Optimism => Some(10),
+            Polygon => Some(137),
+            ProofOfPlay => Some(70700),
+            ReAl => Some(111188),
+            Redstone => Some(690),
+            Sanko => Some(1996),
+            Sei => Some(1329),
+            Taiko => Some(167000),
+            Tangle => Some(5845),
+            Viction => Some(88),
+            Worldchain => Some(480),
+            Xai => Some(660279),
+            Xlayer => Some(196),
+            Zetachain => Some(7000),
+            Zircuit => Some(48900),
+            ZoraMainnet => Some(7777777),
+
+            // Local chains
+            Test1 => Some(9913371),
+            Test2 => Some(9913372),
+            Test3 => Some(9913373),
+
+            // Test chains
+            Alfajores => Some(44787),
+            ArbitrumSepolia => Some(421614),
+            BaseSepolia => Some(84532),
+            BinanceSmartChainTestnet => Some(97),
+            Chiado => Some(10200),
+            ConnextSepolia => Some(6398),
+            Holesky => Some(17000),
+            MoonbaseAlpha => Some(1287),
+            OptimismSepolia => Some(11155420),
+            PlumeTestnet => Some(161221135),
+            ScrollSepolia => Some(534351),
+            Sepolia => Some(11155111),
+            SuperpositionTestnet => Some(98985),
+
+            // Non-EVM chains
+            EclipseMainnet | Injective | Neutron | Osmosis | SolanaMainnet | FuelTest1
+            | SealevelTest1 | SealevelTest2 | CosmosTest99990 | CosmosTest99991 => None,
+        }
+    }
 
-                // Test chains
-                ConnextSepolia, PlumeTestnet, SuperpositionTestnet
-            ],
-            HyperlaneDomainTechnicalStack::OpStack: [
-                Ancient8, Blast, Bob, Cyber, Fraxtal, Kroma, Lisk, MantaPacific, Mantle, Metis,
-                Mint, Mode, Optimism, Redstone, Worldchain, Zircuit, ZoraMainnet
-            ],
-            HyperlaneDomainTechnicalStack::PolygonCDK: [
-                Merlin, Xlayer
-            ],
-            HyperlaneDomainTechnicalStack::PolkadotSubstrate: [
-                Moonbeam, Tangle
-            ],
-            HyperlaneDomainTechnicalStack::ZkSync: [],
-            HyperlaneDomainTechnicalStack::Other: [
-                Avalanche, BinanceSmartChain, Celo, EclipseMainnet, Endurance, Ethereum,
-                FuseMainnet, Gnosis, Injective, Linea, Lukso, Neutron, Osmosis, Polygon,
-                Sei, SolanaMainnet, Taiko, Viction, Zetachain,
+    /// The known domain with the given canonical EVM chain id
+    #[cfg(feature = "strum")]
+    pub fn from_evm_chain_id(chain_id: u64) -> Option<Self> {
+        Self::iter().find(|domain| domain.evm_chain_id() == Some(chain_id))
+    }
 
-                // Local chains
-                CosmosTest99990, CosmosTest99991, FuelTest1, SealevelTest1, SealevelTest2, Test1,
-                Test2, Test3,
+    /// The reorg period to index the domain with unless one is configured.
+    /// Chains with a finality gadget are indexed up to the block tag they
+    /// expose; `None` leaves the choice to the agent.
+    pub fn recommended_reorg_period(self) -> Option<ReorgPeriod> {
+        use KnownHyperlaneDomain::*;
 
-                // Test chains
-                Alfajores, BinanceSmartChainTestnet, Chiado, Fuji, Holesky, MoonbaseAlpha, ScrollSepolia,
-                Sepolia
+        match self {
+            Ethereum | Gnosis | Chiado | Holesky | Sepolia => {
+                Some(ReorgPeriod::Tag("finalized".into()))
+            }
+            _ if matches!(
+                self.domain_technical_stack(),
+                HyperlaneDomainTechnicalStack::OpStack
+            ) =>
+            {
+                Some(ReorgPeriod::Tag("safe".into()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Blocks to wait before treating an event as final unless configured
+    /// otherwise
+    pub const fn finality_blocks(self) -> u32 {
+        use KnownHyperlaneDomain::*;
+
+        match self {
+            Ethereum | Holesky | Sepolia => 32,
+            Polygon => 128,
+            BinanceSmartChain | BinanceSmartChainTestnet => 15,
+            _ => DEFAULT_FINALITY_BLOCKS,
+        }
+    }
+
+    /// The mainnet the domain belongs to: itself for a mainnet, the mainnet
+    /// it's the testnet of for a testnet. `None` for testnets whose mainnet
+    /// isn't known, and for local test chains.
+    pub const fn canonical_mainnet(self) -> Option<Self> {
+        use KnownHyperlaneDomain::*;
+
+        match self {
+            Ancient8 | Arbitrum | Avalanche | BinanceSmartChain | Blast | Bob | Celo
+            | Cheesechain | Cyber | DegenChain | EclipseMainnet | Endurance | Ethereum
+            | Fraxtal | FuseMainnet | Gnosis | InEvm | Injective | Kroma | Linea | Lisk | Lukso
+            | MantaPacific | Mantle | Merlin | Metis | Mint | Mode | Moonbeam | Neutron
+            | Optimism | Osmosis | Polygon | ProofOfPlay | ReAl | Redstone | Sanko | Sei
+            | SolanaMainnet | Taiko | Tangle | Viction | Worldchain | Xai | Xlayer | Zetachain
+            | Zircuit | ZoraMainnet => Some(self),
+
+            // Test chains
+            Alfajores => Some(Celo),
+            ArbitrumSepolia => Some(Arbitrum),
+            BinanceSmartChainTestnet => Some(BinanceSmartChain),
+            Chiado => Some(Gnosis),
+            Fuji => Some(Avalanche),
+            Holesky | Sepolia => Some(Ethereum),
+            MoonbaseAlpha => Some(Moonbeam),
+            OptimismSepolia => Some(Optimism),
+            BaseSepolia | ConnextSepolia | PlumeTestnet | ScrollSepolia | SuperpositionTestnet => {
+                None
+            }
+
+            // Local chains
+            Test1 | Test2 | Test3 | FuelTest1 | SealevelTest1 | SealevelTest2 | CosmosTest99990
+            | CosmosTest99991 => None,
+        }
+    }
+
+    /// The native token of the domain
+    pub const fn native_token(self) -> NativeToken {
+        use KnownHyperlaneDomain::*;
+
+        let (symbol, decimals) = match self {
+            Ancient8 | Arbitrum | Blast | Bob | Cyber | Ethereum | Kroma | Linea | Lisk
+            | MantaPacific | Mint | Mode | Optimism | ProofOfPlay | Redstone | Taiko
+            | Worldchain | Zircuit | ZoraMainnet => ("ETH", 18),
+            Avalanche => ("AVAX", 18),
+            BinanceSmartChain => ("BNB", 18),
+            Celo => ("CELO", 18),
+            Cheesechain => ("CHEESE", 18),
+            DegenChain => ("DEGEN", 18),
+            EclipseMainnet => ("ETH", 9),
+            Endurance => ("ACE", 18),
+            Fraxtal => ("frxETH", 18),
+            FuseMainnet => ("FUSE", 18),
+            Gnosis => ("xDai", 18),
+            InEvm | Injective => ("INJ", 18),
+            Lukso => ("LYX", 18),
+            Mantle => ("MNT", 18),
+            Merlin => ("BTC", 18),
+            Metis => ("METIS", 18),
+            Moonbeam => ("GLMR", 18),
+            Neutron => ("NTRN", 6),
+            Osmosis => ("OSMO", 6),
+            Polygon => ("POL", 18),
+            ReAl => ("reETH", 18),
+            Sanko => ("DMT", 18),
+            Sei => ("SEI", 18),
+            SolanaMainnet => ("SOL", 9),
+            Tangle => ("TNT", 18),
+            Viction => ("VIC", 18),
+            Xai => ("XAI", 18),
+            Xlayer => ("OKB", 18),
+            Zetachain => ("ZETA", 18),
+
+            // Local chains
+            Test1 | Test2 | Test3 => ("TEST", 18),
+            FuelTest1 => ("ETH", 9),
+            SealevelTest1 | SealevelTest2 => ("SOL", 9),
+            CosmosTest99990 | CosmosTest99991 => ("OSMO", 6),
+
+            // Test chains
+            ArbitrumSepolia | BaseSepolia | ConnextSepolia | Holesky | OptimismSepolia
+            | PlumeTestnet | ScrollSepolia | Sepolia => ("ETH", 18),
+            Alfajores => ("CELO", 18),
+            BinanceSmartChainTestnet => ("BNB", 18),
+            Chiado => ("xDai", 18),
+            Fuji => ("AVAX", 18),
+            MoonbaseAlpha => ("DEV", 18),
+            SuperpositionTestnet => ("SPN", 18),
+        };
+        NativeToken { symbol, decimals }
+    }
+
+    /// The checksummed address of the canonical contract wrapping the native
+    /// token, e.g. WETH. Only mainnets are tracked, and of those not the
+    /// non-EVM ones.
+    pub const fn wrapped_native_address(self) -> Option<&'static str> {
+        use KnownHyperlaneDomain::*;
+
+        match self {
+            Ancient8 | Bob | Cyber | Lisk | Mint | Mode | Optimism | Redstone | Worldchain
+            | Zircuit | ZoraMainnet => Some("0x4200000000000000000000000000000000000006"),
+            Arbitrum => Some("0x82aF49447D8a07e3bd95BD0d56f35241523fBab1"),
+            Avalanche => Some("0xB31f66AA3C1e785363F0875A1B74E27b85FD66c7"),
+            BinanceSmartChain => Some("0xbb4CdB9CBd36B01bD1cBaEBF2De08d9173bc095c"),
+            Blast => Some("0x4300000000000000000000000000000000000004"),
+            Celo => Some("0x471EcE3750Da237f93B8E339c536989b8978a438"),
+            DegenChain => Some("0xEb54dACB4C2ccb64F8074eceEa33b5eBb38E5387"),
+            Ethereum => Some("0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"),
+            Fraxtal => Some("0xFC00000000000000000000000000000000000006"),
+            FuseMainnet => Some("0x0BE9e53fd7EDaC9F859882AfdDa116645287C629"),
+            Gnosis => Some("0xe91D153E0b41518A2Ce8Dd3D7944Fa863463a97d"),
+            InEvm => Some("0x69011706b3f6C6eaeD7D2Bc13801558B4fd94CBF"),
+            Linea => Some("0xe5D7C2a44FfDDf6b295A15c148167daaAf5Cf34f"),
+            Lukso => Some("0x2dB41674F2b882889e5E1Bd09a3f3613952bC472"),
+            MantaPacific => Some("0x0Dc808adcE2099A9F62AA87D9670745AbA741746"),
+            Mantle => Some("0x78c1b0C915c4FAA5FffA6CAbf0219DA63d7f4cb8"),
+            Merlin => Some("0xF6D226f9Dc15d9bB51182815b320D3fBE324e1bA"),
+            Metis => Some("0x75cb093E4D61d2A2e65D8e0BBb01DE8d89b53481"),
+            Moonbeam => Some("0xAcc15dC74880C9944775448304B263D191c6077F"),
+            Polygon => Some("0x0d500B1d8E8eF31E21C99d1Db9A6444d3ADf1270"),
+            Sei => Some("0xE30feDd158A2e3b13e9badaeABaFc5516e95e8C7"),
+            Taiko => Some("0xA51894664A773981C6C112C43ce576f315d5b1B6"),
+            Viction => Some("0xC054751BdBD24Ae713BA3Dc9Bd9434aBe2abc1ce"),
+            Xlayer => Some("0xe538905cf8410324e03A5A23C1c177a474D59b2b"),
+            Zetachain => Some("0x5F0b1a82749cb4E2278EC87F8BF6B618dC71a8bf"),
+            Cheesechain | Endurance | Kroma | ProofOfPlay | ReAl | Sanko | Tangle | Xai => None,
+
+            // Non-EVM mainnets
+            EclipseMainnet | Injective | Neutron | Osmosis | SolanaMainnet => None,
+
+            // Local chains
+            Test1 | Test2 | Test3 | FuelTest1 | SealevelTest1 | SealevelTest2 | CosmosTest99990
+            | CosmosTest99991 => None,
+
+            // Test chains
+            Alfajores | ArbitrumSepolia | BaseSepolia | BinanceSmartChainTestnet | Chiado => None,
+            ConnextSepolia | Fuji | Holesky | MoonbaseAlpha | OptimismSepolia => None,
+            PlumeTestnet | ScrollSepolia | Sepolia | SuperpositionTestnet => None,
+        }
+    }
+
+    /// The block explorer of the domain, without a trailing slash. Local
+    /// chains have none.
+    pub const fn explorer_url(self) -> Option<&'static str> {
+        use KnownHyperlaneDomain::*;
+
+        let url = match self {
+            Ancient8 => "https://scan.ancient8.gg",
+            Arbitrum => "https://arbiscan.io",
+            Avalanche => "https://snowtrace.io",
+            BinanceSmartChain => "https://bscscan.com",
+            Blast => "https://blastscan.io",
+            Bob => "https://explorer.gobob.xyz",
+            Celo => "https://celoscan.io",
+            Cheesechain => "https://fetascan.io",
+            Cyber => "https://cyberscan.co",
+            DegenChain => "https://explorer.degen.tips",
+            EclipseMainnet => "https://explorer.eclipse.xyz",
+            Endurance => "https://explorer-endurance.fusionist.io",
+            Ethereum => "https://etherscan.io",
+            Fraxtal => "https://fraxscan.com",
+            FuseMainnet => "https://explorer.fuse.io",
+            Gnosis => "https://gnosisscan.io",
+            InEvm => "https://explorer.inevm.com",
+            Injective => "https://www.mintscan.io/injective",
+            Kroma => "https://kromascan.com",
+            Linea => "https://lineascan.build",
+            Lisk => "https://blockscout.lisk.com",
+            Lukso => "https://explorer.execution.mainnet.lukso.network",
+            MantaPacific => "https://pacific-explorer.manta.network",
+            Mantle => "https://explorer.mantle.xyz",
+            Merlin => "https://scan.merlinchain.io",
+            Metis => "https://andromeda-explorer.metis.io",
+            Mint => "https://explorer.mintchain.io",
+            Mode => "https://explorer.mode.network",
+            Moonbeam => "https://moonscan.io",
+            Neutron => "https://www.mintscan.io/neutron",
+            Optimism => "https://optimistic.etherscan.io",
+            Osmosis => "https://www.mintscan.io/osmosis",
+            Polygon => "https://polygonscan.com",
+            ProofOfPlay => "https://explorer.apex.proofofplay.com",
+            ReAl => "https://explorer.re.al",
+            Redstone => "https://explorer.redstone.xyz",
+            Sanko => "https://explorer.sanko.xyz",
+            Sei => "https://seitrace.com",
+            SolanaMainnet => "https://explorer.solana.com",
+            Taiko => "https://taikoscan.io",
+            Tangle => "https://explorer.tangle.tools",
+            Viction => "https://www.vicscan.xyz",
+            Worldchain => "https://worldscan.org",
+            Xai => "https://explorer.xai-chain.net",
+            Xlayer => "https://www.oklink.com/xlayer",
+            Zetachain => "https://zetachain.blockscout.com",
+            Zircuit => "https://explorer.zircuit.com",
+            ZoraMainnet => "https://explorer.zora.energy",
+
+            // Local chains
+            Test1 | Test2 | Test3 | FuelTest1 | SealevelTest1 | SealevelTest2 | CosmosTest99990
+            | CosmosTest99991 => return None,
+
+            // Test chains
+            Alfajores => "https://alfajores.celoscan.io",
+            ArbitrumSepolia => "https://sepolia.arbiscan.io",
+            BaseSepolia => "https://sepolia.basescan.org",
+            BinanceSmartChainTestnet => "https://testnet.bscscan.com",
+            Chiado => "https://gnosis-chiado.blockscout.com",
+            ConnextSepolia => "https://scan.testnets.everclear.org",
+            Fuji => "https://testnet.snowtrace.io",
+            Holesky => "https://holesky.etherscan.io",
+            MoonbaseAlpha => "https://moonbase.moonscan.io",
+            OptimismSepolia => "https://sepolia-optimistic.etherscan.io",
+            PlumeTestnet => "https://testnet-explorer.plumenetwork.xyz",
+            ScrollSepolia => "https://sepolia.scrollscan.com",
+            Sepolia => "https://sepolia.etherscan.io",
+            SuperpositionTestnet => "https://testnet-explorer.superposition.so",
+        };
+        Some(url)
+    }
+
+    pub const fn domain_technical_stack(self) -> HyperlaneDomainTechnicalStack {
+        use KnownHyperlaneDomain::*;
+
+        many_to_one!(match self {
+            HyperlaneDomainTechnicalStack::ArbitrumNitro: [
+                Arbitrum, Cheesechain, DegenChain, InEvm, ProofOfPlay, ReAl, Sanko, Xai,
+
+                // Test chains
+                ArbitrumSepolia, ConnextSepolia, PlumeTestnet, SuperpositionTestnet
+            ],
+            HyperlaneDomainTechnicalStack::OpStack: [
+                Ancient8, Blast, Bob, Cyber, Fraxtal, Kroma, Lisk, MantaPacific, Mantle, Metis,
+                Mint, Mode, Optimism, Redstone, Worldchain, Zircuit, ZoraMainnet,
+
+                // Test chains
+                BaseSepolia, OptimismSepolia
+            ],
+            HyperlaneDomainTechnicalStack::PolygonCDK: [
+                Merlin, Xlayer
+            ],
+            HyperlaneDomainTechnicalStack::PolygonPoS: [
+                Polygon
+            ],
+            HyperlaneDomainTechnicalStack::PolkadotSubstrate: [
+                Moonbeam, Tangle
+            ],
+            HyperlaneDomainTechnicalStack::ZkSync: [],
+            HyperlaneDomainTechnicalStack::Other: [
+                Avalanche, BinanceSmartChain, Celo, EclipseMainnet, Endurance, Ethereum,
+                FuseMainnet, Gnosis, Injective, Linea, Lukso, Neutron, Osmosis, Sei,
+                SolanaMainnet, Taiko, Viction, Zetachain,
+
+                // Local chains
+                CosmosTest99990, CosmosTest99991, FuelTest1, SealevelTest1, SealevelTest2, Test1,
+                Test2, Test3,
+
+                // Test chains
+                Alfajores, BinanceSmartChainTestnet, Chiado, Fuji, Holesky, MoonbaseAlpha, ScrollSepolia,
+                Sepolia
            ],
         })
     }
@@ -415,6 +1385,20 @@ impl Hash for HyperlaneDomain {
     }
 }
 
+/// Domains are ordered by id, like they're compared, so they can key ordered
+/// maps. They can't be `Copy` as unknown domains carry their name.
+impl PartialOrd for HyperlaneDomain {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for HyperlaneDomain {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.id().cmp(&other.id())
+    }
+}
+
 #[cfg(feature = "strum")]
 impl AsRef<str> for HyperlaneDomain {
     fn as_ref(&self) -> &str {
@@ -428,11 +1412,37 @@ impl From<&HyperlaneDomain> for u32 {
     }
 }
 
-impl TryFrom<u32> for KnownHyperlaneDomain {
-    type Error = HyperlaneProtocolError;
+/// A domain id which isn't one of the known domains
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("Unknown or invalid domain ID ({domain_id})")]
+pub struct UnknownDomainError {
+    pub domain_id: u32,
+}
+
+/// A domain name which isn't one of the known domains. It's displayed with
+/// the known names closest to it, in case it's misspelled.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub struct UnknownDomainNameError {
+    pub name: String,
+}
+
+impl std::fmt::Display for UnknownDomainNameError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let closest = closest_names(&self.name, KnownHyperlaneDomain::NAMES.iter().copied());
+        write!(
+            f,
+            "Unknown domain name ({}){}",
+            self.name,
+            fmt_did_you_mean(&closest)
+        )
+    }
+}
 
-    fn try_from(domain_id: u32) -> Result<Self, Self::Error> {
-        FromPrimitive::from_u32(domain_id).ok_or(HyperlaneProtocolError::UnknownDomainId(domain_id))
+/// Any domain id can be carried through, see
+/// [`HyperlaneDomain::from_domain_id`].
+impl From<u32> for HyperlaneDomain {
+    fn from(domain_id: u32) -> Self {
+        HyperlaneDomain::from_domain_id(domain_id)
     }
 }
 
@@ -474,10 +1484,80 @@ impl From<KnownHyperlaneDomain> for HyperlaneDomain {
     }
 }
 
+/// Only known domains can be parsed from their name, ignoring ASCII case
+/// and accepting their aliases. Others need their config, see
+/// [`HyperlaneDomain::from_config`].
+impl FromStr for HyperlaneDomain {
+    type Err = UnknownDomainNameError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        name.parse().map(HyperlaneDomain::Known)
+    }
+}
+
+/// Serialized as the canonical name
+#[cfg(feature = "strum")]
+impl Serialize for HyperlaneDomain {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.name())
+    }
+}
+
+/// Deserialized from a domain id, as a number or a numeric string, or from
+/// the name of a known domain, see [`FromStr`]. Ids which aren't known are
+/// carried through, including as their `unknown:<id>` name, so that every
+/// domain round-trips.
+#[cfg(feature = "strum")]
+impl<'de> Deserialize<'de> for HyperlaneDomain {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        use serde::de;
+
+        struct HyperlaneDomainVisitor;
+
+        impl<'de> de::Visitor<'de> for HyperlaneDomainVisitor {
+            type Value = HyperlaneDomain;
+
+            fn expecting(&self, f: &mut Formatter) -> std::fmt::Result {
+                f.write_str("a domain id or a domain name")
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+                let domain_id = v
+                    .try_into()
+                    .map_err(|_| E::custom(format!("Domain id {v} is out of range")))?;
+                Ok(HyperlaneDomain::from_domain_id(domain_id))
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+                let domain_id = v
+                    .try_into()
+                    .map_err(|_| E::custom(format!("Domain id {v} is out of range")))?;
+                Ok(HyperlaneDomain::from_domain_id(domain_id))
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                let id = v.strip_prefix("unknown:").unwrap_or(v);
+                match id.parse::<u32>() {
+                    Ok(domain_id) => Ok(HyperlaneDomain::from_domain_id(domain_id)),
+                    Err(_) => v.parse().map_err(E::custom),
+                }
+            }
+        }
+
+        deserializer.deserialize_any(HyperlaneDomainVisitor)
+    }
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum HyperlaneDomainConfigError {
-    #[error("Domain name (`{0}`) does not match the name of a known domain id; the name is probably misspelled.")]
-    UnknownDomainName(String),
+    #[error("Domain name (`{0}`) does not match the name of the known domain id; the name is probably misspelled, did you mean `{1}`?")]
+    UnknownDomainName(String, &'static str),
     #[error("The domain name (`{0}`) implies a different domain than the domain id provided; the domain id ({1}) is probably wrong.")]
     DomainNameMismatch(String, u32),
 }
@@ -492,10 +1572,15 @@ impl HyperlaneDomain {
     ) -> Result<Self, HyperlaneDomainConfigError> {
         let name = name.to_ascii_lowercase();
         if let Ok(domain) = KnownHyperlaneDomain::try_from(domain_id) {
-            if name == domain.as_str().to_ascii_lowercase() {
+            if name == domain.as_str().to_ascii_lowercase()
+                || KnownHyperlaneDomain::from_deprecated_name(&name) == Some(domain)
+            {
                 Ok(HyperlaneDomain::Known(domain))
             } else {
-                Err(HyperlaneDomainConfigError::UnknownDomainName(name))
+                Err(HyperlaneDomainConfigError::UnknownDomainName(
+                    name,
+                    domain.as_str(),
+                ))
             }
         } else if name.as_str().parse::<KnownHyperlaneDomain>().is_ok() {
             Err(HyperlaneDomainConfigError::DomainNameMismatch(
@@ -513,6 +1598,64 @@ impl HyperlaneDomain {
         }
     }
 
+    /// The domain with the given id. Ids which aren't known are carried
+    /// through as an unknown domain named `unknown:<id>`, e.g. to observe
+    /// messages sent to chains added since this release. Lacking config, such
+    /// a domain is assumed to be EVM-based; use [`Self::from_config`] to build
+    /// one agents can connect to.
+    pub fn from_domain_id(domain_id: u32) -> Self {
+        match KnownHyperlaneDomain::try_from(domain_id) {
+            Ok(domain) => HyperlaneDomain::Known(domain),
+            Err(_) => HyperlaneDomain::Unknown {
+                domain_id,
+                domain_name: format!("unknown:{domain_id}"),
+                domain_type: HyperlaneDomainType::Unknown,
+                domain_protocol: HyperlaneDomainProtocol::Ethereum,
+                domain_technical_stack: HyperlaneDomainTechnicalStack::Other,
+            },
+        }
+    }
+
+    /// Whether the domain is one of the known domains
+    pub const fn is_known(&self) -> bool {
+        matches!(self, HyperlaneDomain::Known(_))
+    }
+
+    /// The known mainnet domains
+    #[cfg(feature = "strum")]
+    pub fn mainnets() -> impl Iterator<Item = HyperlaneDomain> {
+        Self::known_of_type(HyperlaneDomainType::Mainnet)
+    }
+
+    /// The known testnet domains
+    #[cfg(feature = "strum")]
+    pub fn testnets() -> impl Iterator<Item = HyperlaneDomain> {
+        Self::known_of_type(HyperlaneDomainType::Testnet)
+    }
+
+    /// The known local test chain domains
+    #[cfg(feature = "strum")]
+    pub fn local_test_chains() -> impl Iterator<Item = HyperlaneDomain> {
+        Self::known_of_type(HyperlaneDomainType::LocalTestChain)
+    }
+
+    #[cfg(feature = "strum")]
+    fn known_of_type(domain_type: HyperlaneDomainType) -> impl Iterator<Item = HyperlaneDomain> {
+        KnownHyperlaneDomain::iter()
+            .filter(move |domain| domain.domain_type() == domain_type)
+            .map(HyperlaneDomain::Known)
+    }
+
+    /// Whether the domain is a mainnet
+    pub const fn is_mainnet(&self) -> bool {
+        self.domain_type().is_mainnet()
+    }
+
+    /// Whether the domain is a testnet
+    pub const fn is_testnet(&self) -> bool {
+        self.domain_type().is_testnet()
+    }
+
     /// The chain name
     #[cfg(feature = "strum")]
     pub fn name(&self) -> &str {
@@ -551,6 +1694,11 @@ impl HyperlaneDomain {
         }
     }
 
+    /// Whether this is an EVM chain
+    pub const fn is_evm(&self) -> bool {
+        self.domain_protocol().is_evm()
+    }
+
     pub const fn domain_technical_stack(&self) -> HyperlaneDomainTechnicalStack {
         match self {
             HyperlaneDomain::Known(domain) => domain.domain_technical_stack(),
@@ -561,6 +1709,126 @@ impl HyperlaneDomain {
         }
     }
 
+    /// The canonical EVM chain id of the domain. Only known for known EVM
+    /// domains.
+    pub const fn evm_chain_id(&self) -> Option<u64> {
+        match self {
+            HyperlaneDomain::Known(domain) => domain.evm_chain_id(),
+            HyperlaneDomain::Unknown { .. } => None,
+        }
+    }
+
+    /// The known domain with the given canonical EVM chain id
+    #[cfg(feature = "strum")]
+    pub fn from_evm_chain_id(chain_id: u64) -> Option<Self> {
+        KnownHyperlaneDomain::from_evm_chain_id(chain_id).map(HyperlaneDomain::Known)
+    }
+
+    /// The reorg period to index the domain with unless one is configured.
+    /// Only known for known domains.
+    pub fn recommended_reorg_period(&self) -> Option<ReorgPeriod> {
+        match self {
+            HyperlaneDomain::Known(domain) => domain.recommended_reorg_period(),
+            HyperlaneDomain::Unknown { .. } => None,
+        }
+    }
+
+    /// The native token of the domain. Unknown domains are assumed to have one
+    /// with 18 decimals, see [`NativeToken::UNKNOWN`].
+    pub const fn native_token(&self) -> NativeToken {
+        match self {
+            HyperlaneDomain::Known(domain) => domain.native_token(),
+            HyperlaneDomain::Unknown { .. } => NativeToken::UNKNOWN,
+        }
+    }
+
+    /// The token the domain pays gas in, and its wrapped contract if one is
+    /// tracked, see [`KnownHyperlaneDomain::wrapped_native_address`].
+    pub fn gas_token(&self) -> GasToken {
+        let wrapped_address = match self {
+            HyperlaneDomain::Known(domain) => domain.wrapped_native_address(),
+            HyperlaneDomain::Unknown { .. } => None,
+        }
+        .map(|address| {
+            address
+                .parse()
+                .expect("wrapped native addresses are valid hex")
+        });
+        GasToken {
+            symbol: self.native_token().symbol,
+            wrapped_address,
+        }
+    }
+
+    /// The block explorer of the domain, if it is a known domain which isn't
+    /// a local test chain
+    pub const fn explorer_url(&self) -> Option<&'static str> {
+        match self {
+            HyperlaneDomain::Known(domain) => domain.explorer_url(),
+            HyperlaneDomain::Unknown { .. } => None,
+        }
+    }
+
+    /// The block explorer page of the transaction with the given hash.
+    /// Sealevel and Fuel transactions aren't identified by a 32 byte hash,
+    /// so have no such page.
+    pub fn explorer_tx_url(&self, tx_hash: H256) -> Option<String> {
+        let url = self.explorer_url()?;
+        match self.domain_protocol() {
+            HyperlaneDomainProtocol::Ethereum => {
+                Some(format!("{url}/tx/{}", bytes_to_hex(tx_hash.as_bytes())))
+            }
+            HyperlaneDomainProtocol::Cosmos => {
+                Some(format!("{url}/tx/{}", hex::encode_upper(tx_hash)))
+            }
+            HyperlaneDomainProtocol::Sealevel | HyperlaneDomainProtocol::Fuel => None,
+        }
+    }
+
+    /// The block explorer page of the given account. EVM addresses padded to
+    /// 32 bytes are linked by their 20 byte form. Cosmos addresses are
+    /// bech32 encoded with a prefix the domain doesn't know, so have no such
+    /// page.
+    pub fn explorer_address_url(&self, address: &Address) -> Option<String> {
+        let url = self.explorer_url()?;
+        let address = match self.domain_protocol() {
+            HyperlaneDomainProtocol::Ethereum => {
+                bytes_to_hex(address.to_h160(self).ok()?.as_bytes())
+            }
+            HyperlaneDomainProtocol::Sealevel => bs58::encode(&address.0).into_string(),
+            HyperlaneDomainProtocol::Cosmos | HyperlaneDomainProtocol::Fuel => return None,
+        };
+        Some(format!("{url}/address/{address}"))
+    }
+
+    /// Blocks to wait before treating an event as final unless configured
+    /// otherwise. Unknown domains default to [`DEFAULT_FINALITY_BLOCKS`].
+    pub const fn finality_blocks(&self) -> u32 {
+        match self {
+            HyperlaneDomain::Known(domain) => domain.finality_blocks(),
+            HyperlaneDomain::Unknown { .. } => DEFAULT_FINALITY_BLOCKS,
+        }
+    }
+
+    /// The mainnet the domain belongs to, see
+    /// [`KnownHyperlaneDomain::canonical_mainnet`]. Always `None` for unknown
+    /// domains.
+    pub fn canonical_mainnet(&self) -> Option<HyperlaneDomain> {
+        match self {
+            HyperlaneDomain::Known(domain) => {
+                domain.canonical_mainnet().map(HyperlaneDomain::Known)
+            }
+            HyperlaneDomain::Unknown { .. } => None,
+        }
+    }
+
+    /// The known testnets of `mainnet`
+    #[cfg(feature = "strum")]
+    pub fn testnets_of(mainnet: &HyperlaneDomain) -> impl Iterator<Item = HyperlaneDomain> + '_ {
+        Self::testnets()
+            .filter(move |testnet| testnet.canonical_mainnet().as_ref() == Some(mainnet))
+    }
+
     pub const fn is_arbitrum_nitro(&self) -> bool {
         matches!(
             self.domain_technical_stack(),
@@ -568,6 +1836,13 @@ impl HyperlaneDomain {
         )
     }
 
+    pub const fn is_op_stack(&self) -> bool {
+        matches!(
+            self.domain_technical_stack(),
+            HyperlaneDomainTechnicalStack::OpStack
+        )
+    }
+
     pub const fn is_injective(&self) -> bool {
         matches!(self, Self::Known(KnownHyperlaneDomain::Injective))
     }
@@ -585,9 +1860,23 @@ impl HyperlaneDomain {
 #[cfg(test)]
 #[cfg(feature = "strum")]
 mod tests {
-    use std::{num::NonZeroU32, str::FromStr};
-
-    use crate::{KnownHyperlaneDomain, ReorgPeriod};
+    use std::{
+        cmp::Ordering,
+        collections::{BTreeMap, HashSet},
+        num::NonZeroU32,
+        str::FromStr,
+    };
+
+    use num::BigInt;
+    use strum::IntoEnumIterator;
+
+    use crate::{
+        Address, AddressParseError, Balance, BalanceParseError, ContractLocator,
+        ContractLocatorParseError, ConversionError, HyperlaneCoreError, HyperlaneDomain,
+        HyperlaneDomainProtocol, HyperlaneDomainTechnicalStack, HyperlaneDomainType,
+        KnownHyperlaneDomain, NativeToken, ReorgPeriod, UnknownDomainError, UnknownDomainNameError,
+        DEFAULT_FINALITY_BLOCKS, H160, H256, U256,
+    };
 
     #[test]
     fn domain_strings() {
@@ -611,6 +1900,49 @@ mod tests {
         assert_eq!(KnownHyperlaneDomain::Ethereum as u32, 1);
     }
 
+    #[test]
+    fn names_agree_with_display() {
+        for known in KnownHyperlaneDomain::iter() {
+            let domain = HyperlaneDomain::Known(known);
+            assert_eq!(domain.name(), domain.to_string());
+            assert_eq!(known.as_str(), known.to_string());
+            assert_eq!(
+                KnownHyperlaneDomain::name_from_domain_id(known as u32),
+                Some(domain.name())
+            );
+            assert_eq!(
+                KnownHyperlaneDomain::from_domain_id(known as u32),
+                Some(known)
+            );
+        }
+        let unknown = HyperlaneDomain::from_domain_id(80002);
+        assert_eq!(unknown.name(), unknown.to_string());
+        assert_eq!(KnownHyperlaneDomain::name_from_domain_id(80002), None);
+        assert_eq!(KnownHyperlaneDomain::from_domain_id(80002), None);
+    }
+
+    #[test]
+    fn domain_table_lists_every_known_domain() {
+        let all = HyperlaneDomain::all();
+        assert_eq!(all.len(), KnownHyperlaneDomain::iter().count());
+        for (domain, name, id) in all {
+            assert_eq!(domain.name(), *name);
+            assert_eq!(domain.id(), *id);
+        }
+
+        let dumped: Vec<serde_json::Value> = serde_json::from_str(&dump_domains_json()).unwrap();
+        assert_eq!(dumped.len(), all.len());
+        assert_eq!(
+            dumped.iter().find(|entry| entry["id"] == 1).unwrap(),
+            &serde_json::json!({
+                "name": "ethereum",
+                "id": 1,
+                "type": "mainnet",
+                "protocol": "ethereum",
+            })
+        );
+    }
+
     #[test]
     fn test_name_from_domain_id() {
         assert_eq!(
@@ -624,21 +1956,851 @@ mod tests {
         assert!(KnownHyperlaneDomain::try_from(0xf00u32).is_err());
     }
 
+    #[test]
+    fn unknown_domain_ids_are_carried_through() {
+        for known in KnownHyperlaneDomain::iter() {
+            let domain = HyperlaneDomain::from(known as u32);
+            assert_eq!(domain, HyperlaneDomain::Known(known));
+            assert!(domain.is_known());
+            assert_eq!(domain.name(), known.as_str());
+            assert_eq!(domain.domain_type(), known.domain_type());
+        }
+
+        let unknown = HyperlaneDomain::from_domain_id(80002);
+        assert!(!unknown.is_known());
+        assert_eq!(unknown.id(), 80002);
+        assert_eq!(unknown.name(), "unknown:80002");
+        assert_eq!(unknown.domain_type(), HyperlaneDomainType::Unknown);
+        assert_eq!(unknown.evm_chain_id(), None);
+        // Equal to the same domain built from config
+        assert_eq!(
+            unknown,
+            HyperlaneDomain::from_config(
+                80002,
+                "newchain",
+                HyperlaneDomainProtocol::Ethereum,
+                Default::default(),
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn unknown_domain_errors_are_typed() {
+        assert_eq!(
+            KnownHyperlaneDomain::try_from(0xf00),
+            Err(UnknownDomainError { domain_id: 0xf00 })
+        );
+
+        let err: HyperlaneCoreError = KnownHyperlaneDomain::try_from(0xf00).unwrap_err().into();
+        assert!(matches!(
+            err,
+            HyperlaneCoreError::UnknownDomain(UnknownDomainError { domain_id: 0xf00 })
+        ));
+
+        // Agents keep using eyre, and can still match on the original error
+        let report: eyre::Report = err.into();
+        assert!(matches!(
+            report.downcast_ref::<HyperlaneCoreError>(),
+            Some(HyperlaneCoreError::UnknownDomain(UnknownDomainError {
+                domain_id: 0xf00
+            }))
+        ));
+    }
+
+    #[test]
+    fn known_domains_round_trip() {
+        for domain in KnownHyperlaneDomain::iter() {
+            assert_eq!(KnownHyperlaneDomain::try_from(domain as u32), Ok(domain));
+            assert_eq!(domain.as_str().parse(), Ok(domain));
+            assert_eq!(domain.to_string().to_uppercase().parse(), Ok(domain));
+        }
+        assert_eq!(
+            "foo".parse::<KnownHyperlaneDomain>(),
+            Err(UnknownDomainNameError {
+                name: "foo".to_owned()
+            })
+        );
+    }
+
+    #[test]
+    fn domain_type_iterators_partition_the_known_domains() {
+        let partition: Vec<HyperlaneDomain> = HyperlaneDomain::mainnets()
+            .chain(HyperlaneDomain::testnets())
+            .chain(HyperlaneDomain::local_test_chains())
+            .collect();
+        let unique: HashSet<u32> = partition.iter().map(HyperlaneDomain::id).collect();
+        assert_eq!(partition.len(), unique.len());
+        assert_eq!(partition.len(), KnownHyperlaneDomain::iter().count());
+
+        assert!(HyperlaneDomain::mainnets().all(|domain| domain.is_mainnet()));
+        assert!(HyperlaneDomain::testnets().all(|domain| domain.is_testnet()));
+        assert!(HyperlaneDomain::local_test_chains()
+            .all(|domain| !domain.is_mainnet() && !domain.is_testnet()));
+        assert!(HyperlaneDomain::mainnets()
+            .any(|domain| domain == KnownHyperlaneDomain::Ethereum.into()));
+        assert!(HyperlaneDomain::testnets()
+            .any(|domain| domain == KnownHyperlaneDomain::Sepolia.into()));
+    }
+
+    #[test]
+    fn domain_types_serialize_as_their_lowercase_display() {
+        for domain_type in HyperlaneDomainType::iter() {
+            let serialized = serde_json::to_string(&domain_type).unwrap();
+            assert_eq!(serialized, format!("\"{domain_type}\""));
+            assert_eq!(
+                serde_json::from_str::<HyperlaneDomainType>(&serialized).unwrap(),
+                domain_type
+            );
+        }
+        assert_eq!(
+            HyperlaneDomainType::LocalTestChain.to_string(),
+            "localtestchain"
+        );
+
+        let domain_types: HashSet<HyperlaneDomainType> = KnownHyperlaneDomain::iter()
+            .map(KnownHyperlaneDomain::domain_type)
+            .collect();
+        assert!(domain_types.contains(&HyperlaneDomainType::Mainnet));
+        assert!(!domain_types.contains(&HyperlaneDomainType::Unknown));
+        assert!(HyperlaneDomainType::LocalTestChain.is_local_test_chain());
+    }
+
+    #[test]
+    fn domains_key_ordered_maps_by_id() {
+        let unknown = HyperlaneDomain::from_domain_id(80002);
+        let domains: BTreeMap<HyperlaneDomain, &str> = [
+            (unknown.clone(), "unknown"),
+            (
+                HyperlaneDomain::Known(KnownHyperlaneDomain::Arbitrum),
+                "arbitrum",
+            ),
+            (
+                HyperlaneDomain::Known(KnownHyperlaneDomain::Ethereum),
+                "ethereum",
+            ),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(
+            domains.values().copied().collect::<Vec<_>>(),
+            vec!["ethereum", "arbitrum", "unknown"]
+        );
+        // Found by id, like they're compared
+        assert_eq!(domains[&HyperlaneDomain::from_domain_id(80002)], "unknown");
+        assert!(HyperlaneDomain::Known(KnownHyperlaneDomain::Ethereum) < unknown);
+    }
+
     #[test]
     fn test_domain_id_from_name() {
+        let domain_id = |name: &str| name.parse::<KnownHyperlaneDomain>().map(|v| v as u32);
+        assert_eq!(domain_id("ethereum"), Ok(1));
+        assert_eq!(domain_id("EthEreum"), Ok(1));
+        assert_eq!(domain_id("Bsc"), Ok(56));
+        // Aliases follow the same rules
+        assert_eq!(domain_id("binancesmartchain"), Ok(56));
+        assert_eq!(domain_id("BinanceSmartChain"), Ok(56));
+        assert_eq!(domain_id("MATIC"), Ok(137));
+        assert!(domain_id("foo").is_err());
+    }
+
+    #[test]
+    fn unknown_domain_names_suggest_the_closest_known_ones() {
+        let err = |name: &str| {
+            name.parse::<KnownHyperlaneDomain>()
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            err("arbitrun"),
+            "Unknown domain name (arbitrun); did you mean `arbitrum`?"
+        );
+        assert_eq!(
+            err("Etherium"),
+            "Unknown domain name (Etherium); did you mean `ethereum`?"
+        );
+        assert_eq!(err("newchain"), "Unknown domain name (newchain)");
+
+        // A misspelled name of a known domain id in chain config
+        let config_err = HyperlaneDomain::from_config(
+            42161,
+            "arbitrun",
+            HyperlaneDomainProtocol::Ethereum,
+            Default::default(),
+        )
+        .unwrap_err();
+        assert!(config_err.to_string().ends_with("did you mean `arbitrum`?"));
+    }
+
+    #[test]
+    fn hyperlane_domains_parse_case_insensitively_and_from_aliases() {
+        let ethereum = HyperlaneDomain::Known(KnownHyperlaneDomain::Ethereum);
+        let polygon = HyperlaneDomain::Known(KnownHyperlaneDomain::Polygon);
+        assert_eq!(HyperlaneDomain::from_str("Ethereum"), Ok(ethereum.clone()));
+        assert_eq!(HyperlaneDomain::from_str("ETHEREUM"), Ok(ethereum.clone()));
+        assert_eq!(HyperlaneDomain::from_str("matic"), Ok(polygon.clone()));
+        assert_eq!(HyperlaneDomain::from_str("Polygon"), Ok(polygon.clone()));
+        assert_eq!(
+            HyperlaneDomain::from_str("newchain"),
+            Err(UnknownDomainNameError {
+                name: "newchain".to_owned()
+            })
+        );
+
+        // The canonical name is displayed whatever the domain was parsed from
+        assert_eq!(ethereum.to_string(), "ethereum");
+        assert_eq!(
+            HyperlaneDomain::from_str("Matic").unwrap().to_string(),
+            "polygon"
+        );
+        assert_eq!(
+            HyperlaneDomain::from_str("binancesmartchain")
+                .unwrap()
+                .name(),
+            "bsc"
+        );
+    }
+
+    #[test]
+    fn sepolia_testnets_replace_the_goerli_ones() {
+        use HyperlaneDomainTechnicalStack::*;
+
+        let testnets = [
+            (KnownHyperlaneDomain::Sepolia, 11155111, Other),
+            (KnownHyperlaneDomain::ArbitrumSepolia, 421614, ArbitrumNitro),
+            (KnownHyperlaneDomain::OptimismSepolia, 11155420, OpStack),
+            (KnownHyperlaneDomain::BaseSepolia, 84532, OpStack),
+            (KnownHyperlaneDomain::ScrollSepolia, 534351, Other),
+        ];
+        for (domain, id, stack) in testnets {
+            assert_eq!(domain as u32, id);
+            assert_eq!(domain.domain_type(), HyperlaneDomainType::Testnet);
+            assert_eq!(domain.domain_technical_stack(), stack);
+            assert_eq!(domain.evm_chain_id(), Some(id as u64));
+            assert_eq!(domain.as_str().parse(), Ok(domain));
+        }
+
+        // Configs still naming the retired Base Goerli resolve to its
+        // replacement, but only with the replacement's domain id
+        let config = |domain_id, name| {
+            HyperlaneDomain::from_config(
+                domain_id,
+                name,
+                HyperlaneDomainProtocol::Ethereum,
+                Default::default(),
+            )
+        };
+        assert_eq!(
+            config(84532, "basegoerli").unwrap(),
+            HyperlaneDomain::Known(KnownHyperlaneDomain::BaseSepolia)
+        );
+        assert!(!config(84531, "basegoerli").unwrap().is_known());
+        assert_eq!(
+            KnownHyperlaneDomain::from_deprecated_name("BaseGoerli"),
+            Some(KnownHyperlaneDomain::BaseSepolia)
+        );
+        assert!("basegoerli".parse::<KnownHyperlaneDomain>().is_err());
+    }
+
+    #[test]
+    fn hyperlane_domains_deserialize_from_ids_and_names() {
+        let ethereum = HyperlaneDomain::Known(KnownHyperlaneDomain::Ethereum);
+        let domain = |json: &str| serde_json::from_str::<HyperlaneDomain>(json);
+        assert_eq!(domain("1").unwrap(), ethereum);
+        assert_eq!(domain("\"1\"").unwrap(), ethereum);
+        assert_eq!(domain("\"ethereum\"").unwrap(), ethereum);
+        assert_eq!(domain("\"Ethereum\"").unwrap(), ethereum);
+        assert_eq!(
+            domain("\"matic\"").unwrap(),
+            HyperlaneDomain::Known(KnownHyperlaneDomain::Polygon)
+        );
+        // Ids which aren't known are carried through
+        assert_eq!(domain("6648936").unwrap().name(), "unknown:6648936");
+        assert_eq!(domain("\"6648936\"").unwrap().id(), 6648936);
+
+        // Serialized as the canonical name, from which every domain round-trips
+        assert_eq!(serde_json::to_string(&ethereum).unwrap(), "\"ethereum\"");
+        for json in ["137", "\"137\"", "\"matic\"", "\"polygon\"", "6648936"] {
+            let domain = domain(json).unwrap();
+            let serialized = serde_json::to_string(&domain).unwrap();
+            assert_eq!(
+                serde_json::from_str::<HyperlaneDomain>(&serialized).unwrap(),
+                domain
+            );
+        }
+
+        let err = domain("\"arbitrun\"").unwrap_err().to_string();
+        assert!(err.contains("Unknown domain name (arbitrun)"), "{err}");
+        assert!(err.contains("did you mean `arbitrum`?"), "{err}");
+        let err = domain("-1").unwrap_err().to_string();
+        assert!(err.contains("Domain id -1 is out of range"), "{err}");
+        let err = domain("true").unwrap_err().to_string();
+        assert!(
+            err.contains("expected a domain id or a domain name"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn evm_chain_ids() {
+        let mut seen = HashSet::new();
+        for domain in KnownHyperlaneDomain::iter() {
+            let is_evm = domain.domain_protocol().is_evm();
+            match domain.evm_chain_id() {
+                Some(chain_id) => {
+                    assert!(is_evm, "{domain} is not an EVM chain");
+                    assert!(seen.insert(chain_id), "chain id {chain_id} is used twice");
+                    assert_eq!(
+                        KnownHyperlaneDomain::from_evm_chain_id(chain_id),
+                        Some(domain)
+                    );
+                }
+                None => assert!(
+                    !is_evm || domain.domain_type() == HyperlaneDomainType::LocalTestChain,
+                    "{domain} is missing an EVM chain id"
+                ),
+            }
+        }
+
+        assert_eq!(
+            HyperlaneDomain::from_evm_chain_id(42161),
+            Some(HyperlaneDomain::Known(KnownHyperlaneDomain::Arbitrum))
+        );
+        assert_eq!(HyperlaneDomain::from_evm_chain_id(0xf00), None);
+        assert_eq!(
+            HyperlaneDomain::new_test_domain("test").evm_chain_id(),
+            None
+        );
+    }
+
+    #[test]
+    fn recommended_reorg_periods() {
+        let tag = |tag: &str| Some(ReorgPeriod::Tag(tag.to_owned()));
+        assert_eq!(
+            KnownHyperlaneDomain::Ethereum.recommended_reorg_period(),
+            tag("finalized")
+        );
+        assert_eq!(
+            KnownHyperlaneDomain::Optimism.recommended_reorg_period(),
+            tag("safe")
+        );
+        assert_eq!(
+            KnownHyperlaneDomain::Arbitrum.recommended_reorg_period(),
+            None
+        );
+        assert_eq!(
+            HyperlaneDomain::new_test_domain("test").recommended_reorg_period(),
+            None
+        );
+    }
+
+    #[test]
+    fn native_tokens_cover_every_domain() {
+        for domain in KnownHyperlaneDomain::iter() {
+            let token = domain.native_token();
+            assert!(!token.symbol.is_empty(), "{domain}");
+            assert!(token.decimals <= 18, "{domain}");
+        }
+        assert_eq!(
+            KnownHyperlaneDomain::Ethereum.native_token(),
+            NativeToken {
+                symbol: "ETH",
+                decimals: 18
+            }
+        );
+        for domain in [
+            KnownHyperlaneDomain::Test1,
+            KnownHyperlaneDomain::Test2,
+            KnownHyperlaneDomain::Test3,
+        ] {
+            assert_eq!(domain.native_token().symbol, "TEST");
+        }
+        assert_eq!(
+            HyperlaneDomain::new_test_domain("test").native_token(),
+            NativeToken::UNKNOWN
+        );
+    }
+
+    #[test]
+    fn mainnet_gas_tokens_have_checksummed_wrapped_addresses() {
+        for domain in HyperlaneDomain::mainnets() {
+            let HyperlaneDomain::Known(known) = domain else {
+                unreachable!()
+            };
+            let gas_token = domain.gas_token();
+            assert_eq!(gas_token.symbol, known.native_token().symbol);
+            let Some(address) = gas_token.wrapped_address else {
+                continue;
+            };
+            assert!(domain.is_evm(), "{domain}");
+            assert_eq!(address.as_bytes().len(), 20, "{domain}");
+            assert_eq!(
+                Some(address.to_string()).as_deref(),
+                known.wrapped_native_address(),
+                "{domain}"
+            );
+        }
+
+        let polygon = HyperlaneDomain::Known(KnownHyperlaneDomain::Polygon).gas_token();
+        assert_eq!(polygon.symbol, "POL");
+        assert!(polygon.wrapped_address.is_some());
+        for domain in HyperlaneDomain::testnets().chain(HyperlaneDomain::local_test_chains()) {
+            assert!(domain.gas_token().wrapped_address.is_none(), "{domain}");
+        }
+        let unknown = HyperlaneDomain::new_test_domain("test").gas_token();
+        assert_eq!(unknown.symbol, NativeToken::UNKNOWN.symbol);
+        assert!(unknown.wrapped_address.is_none());
+    }
+
+    #[test]
+    fn addresses_round_trip_through_hex() {
+        let evm: Address = "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2"
+            .parse()
+            .unwrap();
+        assert_eq!(evm.as_bytes().len(), 20);
+        assert_eq!(
+            evm.to_string(),
+            "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
+        );
+        assert_eq!(evm.to_string().parse::<Address>().unwrap(), evm);
+        assert_eq!(
+            "C02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
+                .parse::<Address>()
+                .unwrap(),
+            evm
+        );
+
+        let h256 = H256::repeat_byte(0xab);
+        let padded = Address(h256.as_bytes().to_vec().into());
+        assert_eq!(padded.to_string(), format!("0x{h256:x}"));
+        assert_eq!(padded.to_string().parse::<Address>().unwrap(), padded);
+
+        assert_eq!(
+            "0xabc".parse::<Address>(),
+            Err(AddressParseError::OddLength(3))
+        );
+        assert_eq!(
+            "0xabzd".parse::<Address>(),
+            Err(AddressParseError::InvalidCharacter {
+                character: 'z',
+                index: 4
+            })
+        );
+    }
+
+    #[test]
+    fn balances_add_subtract_and_compare() {
+        let balance = Balance::from(1_000u64);
+        let spend = Balance::from(U256::from(400));
+        assert_eq!(balance.clone() + spend.clone(), Balance::from(1_400u64));
+        assert_eq!(balance.clone() - spend.clone(), Balance::from(600u64));
+        assert_eq!(balance.checked_sub(&spend), Some(Balance::from(600u64)));
+        assert!(spend < balance);
+        assert!(balance.checked_sub(&balance).unwrap().is_zero());
+        assert!(Balance::default().is_zero());
+
+        // negative results
+        let overdrawn = spend.clone() - balance.clone();
+        assert_eq!(overdrawn, Balance(BigInt::from(-600)));
+        assert!(overdrawn < Balance::default());
+        assert_eq!(spend.checked_sub(&balance), None);
+
+        // beyond u64 and up to U256::MAX
+        let max = Balance::from(U256::MAX);
+        assert_eq!(
+            max.0.to_string(),
+            "115792089237316195423570985008687907853269984665640564039457584007913129639935"
+        );
+        let beyond_max = max.clone() + Balance::from(1u64);
+        assert!(beyond_max > max);
+        assert_eq!(beyond_max - max.clone(), Balance::from(1u64));
+        assert!(Balance::from(U256::from(u64::MAX) + 1) > Balance::from(u64::MAX));
+        assert_eq!(Balance::from(u64::MAX).checked_sub(&max), None);
+    }
+
+    #[test]
+    fn balances_display_in_native_token_units() {
+        use KnownHyperlaneDomain::*;
+
+        let display = |amount: &str, domain: KnownHyperlaneDomain| {
+            Balance(amount.parse().unwrap()).display_for_domain(&HyperlaneDomain::Known(domain))
+        };
+        assert_eq!(display("1253400000000000000", Ethereum), "1.2534 ETH");
+        // Truncated to 4 decimals, without trailing zeros
+        assert_eq!(display("1253499999999999999", Ethereum), "1.2534 ETH");
+        assert_eq!(display("2500000000000000000", Ethereum), "2.5 ETH");
+        assert_eq!(display("3000000000000000000", Polygon), "3 POL");
+        assert_eq!(display("1000000000000", Ethereum), "0 ETH");
+        assert_eq!(display("0", Ethereum), "0 ETH");
+        assert_eq!(display("-1500000000000000000", Ethereum), "-1.5 ETH");
+        assert_eq!(display("-1", Ethereum), "0 ETH");
+        assert_eq!(display("1234567", Osmosis), "1.2345 OSMO");
+        assert_eq!(display("5", Osmosis), "0 OSMO");
+        assert_eq!(display("12000000000", SolanaMainnet), "12 SOL");
+        assert_eq!(display("1000000000000000000", Test1), "1 TEST");
+    }
+
+    #[test]
+    fn balances_round_trip_through_u256() {
+        // 0, MAX, every power of two and its neighbours, and pseudo-random
+        // values spread across the whole range
+        let mut samples = vec![U256::zero(), U256::one(), U256::MAX, U256::MAX - 1];
+        for bit in 0..256 {
+            let power = U256::one() << bit;
+            samples.extend([power, power - 1, power.saturating_add(U256::one())]);
+        }
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        for _ in 0..1_000 {
+            let mut words = [0u64; 4];
+            for word in &mut words {
+                // xorshift64
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                *word = state;
+            }
+            samples.push(U256(words) >> (state % 256) as usize);
+        }
+
+        for amount in samples {
+            let balance = Balance::from(amount);
+            assert_eq!(balance.0.to_string(), amount.to_string());
+            assert_eq!(U256::try_from(&balance).unwrap(), amount);
+        }
+
+        let beyond_max = Balance::from(U256::MAX) + Balance::from(1u64);
+        assert!(matches!(
+            U256::try_from(&beyond_max),
+            Err(ConversionError::BalanceOutOfRange(_))
+        ));
+        assert!(matches!(
+            U256::try_from(&Balance(BigInt::from(-1))),
+            Err(ConversionError::BalanceOutOfRange(_))
+        ));
+    }
+
+    #[test]
+    fn balances_format_and_parse_units() {
+        let balance = Balance(1_394_023_000_000_000_000u64.into());
+        assert_eq!(balance.format_units(18), "1.394023");
+        assert_eq!(Balance::from_units("1.394023", 18), Ok(balance));
+        // every digit is kept, so nothing is ever rounded up
+        assert_eq!(
+            Balance(1_999_999_999_999_999_999u64.into()).format_units(18),
+            "1.999999999999999999"
+        );
+        assert_eq!(Balance(5u64.into()).format_units(0), "5");
+        assert_eq!(Balance(BigInt::from(-150)).format_units(2), "-1.5");
+        assert_eq!(Balance::default().format_units(18), "0");
+
+        assert_eq!(
+            Balance::from_units("0.5", 18),
+            Ok(Balance(500_000_000_000_000_000u64.into()))
+        );
+        assert_eq!(
+            Balance::from_units("12", 6),
+            Ok(Balance(12_000_000u64.into()))
+        );
+        assert_eq!(Balance::from_units(".5", 1), Ok(Balance(5u64.into())));
+        assert_eq!(
+            Balance::from_units("-1.5", 2),
+            Ok(Balance(BigInt::from(-150)))
+        );
+        assert_eq!(
+            Balance::from_units("0.1234567", 6),
+            Err(BalanceParseError::TooManyDecimals {
+                amount: "0.1234567".to_owned(),
+                decimals: 6
+            })
+        );
+        for invalid in ["", ".", "abc", "1.2.3", "1,5", "+1", "1e18"] {
+            assert_eq!(
+                Balance::from_units(invalid, 18),
+                Err(BalanceParseError::Invalid(invalid.to_owned())),
+                "{invalid}"
+            );
+        }
+    }
+
+    #[test]
+    fn explorer_urls_link_transactions_and_addresses() {
+        use KnownHyperlaneDomain::*;
+
+        let known = HyperlaneDomain::Known;
+        let evm_address = H160::repeat_byte(0xab);
+        let padded = Address(H256::from(evm_address).as_bytes().to_vec().into());
+        let tx_hash = H256::repeat_byte(0x0c);
+
+        for domain in KnownHyperlaneDomain::iter() {
+            let url = domain.explorer_url();
+            if domain.domain_type() == HyperlaneDomainType::LocalTestChain {
+                assert_eq!(url, None, "{domain}");
+            } else {
+                let url = url.unwrap_or_else(|| panic!("{domain} has no explorer"));
+                assert!(url.starts_with("https://"), "{domain}");
+                assert!(!url.ends_with('/'), "{domain}");
+            }
+        }
+
         assert_eq!(
-            "ethereum".parse::<KnownHyperlaneDomain>().map(|v| v as u32),
-            Ok(1)
+            known(Ethereum).explorer_tx_url(tx_hash).as_deref(),
+            Some(
+                "https://etherscan.io/tx/0x0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c"
+            )
         );
+        // Padded EVM addresses link to their 20 byte form
         assert_eq!(
-            "EthEreum".parse::<KnownHyperlaneDomain>().map(|v| v as u32),
-            Ok(1)
+            known(Arbitrum).explorer_address_url(&padded).as_deref(),
+            Some("https://arbiscan.io/address/0xabababababababababababababababababababab")
         );
         assert_eq!(
-            "Bsc".parse::<KnownHyperlaneDomain>().map(|v| v as u32),
-            Ok(56)
+            known(Osmosis).explorer_tx_url(tx_hash).as_deref(),
+            Some(
+                "https://www.mintscan.io/osmosis/tx/0C0C0C0C0C0C0C0C0C0C0C0C0C0C0C0C0C0C0C0C0C0C0C0C0C0C0C0C0C0C0C0C"
+            )
+        );
+        assert_eq!(known(Osmosis).explorer_address_url(&padded), None);
+        assert_eq!(
+            known(SolanaMainnet)
+                .explorer_address_url(&Address(vec![0; 32].into()))
+                .as_deref(),
+            Some("https://explorer.solana.com/address/11111111111111111111111111111111")
+        );
+        assert_eq!(known(SolanaMainnet).explorer_tx_url(tx_hash), None);
+
+        // Local and unknown chains have no explorer
+        assert_eq!(known(Test1).explorer_tx_url(tx_hash), None);
+        assert_eq!(
+            HyperlaneDomain::new_test_domain("test").explorer_address_url(&padded),
+            None
+        );
+    }
+
+    #[test]
+    fn finality_blocks_default_per_domain() {
+        assert_eq!(KnownHyperlaneDomain::Ethereum.finality_blocks(), 32);
+        assert_eq!(KnownHyperlaneDomain::Polygon.finality_blocks(), 128);
+        assert_eq!(KnownHyperlaneDomain::Arbitrum.finality_blocks(), 1);
+        assert_eq!(
+            HyperlaneDomain::Known(KnownHyperlaneDomain::Polygon).finality_blocks(),
+            128
+        );
+        assert_eq!(
+            HyperlaneDomain::new_test_domain("test").finality_blocks(),
+            DEFAULT_FINALITY_BLOCKS
+        );
+    }
+
+    #[test]
+    fn only_evm_addresses_convert_to_h160() {
+        let ethereum = HyperlaneDomain::Known(KnownHyperlaneDomain::Ethereum);
+        let solana = HyperlaneDomain::Known(KnownHyperlaneDomain::SolanaMainnet);
+        assert!(ethereum.is_evm());
+        assert!(!solana.is_evm());
+
+        let evm_address = H160::repeat_byte(0xab);
+        let address = |bytes: &[u8]| Address(bytes.to_vec().into());
+        assert_eq!(
+            address(evm_address.as_bytes()).to_h160(&ethereum).unwrap(),
+            evm_address
+        );
+        assert_eq!(
+            address(H256::from(evm_address).as_bytes())
+                .to_h160(&ethereum)
+                .unwrap(),
+            evm_address
+        );
+        assert!(matches!(
+            address(H256::repeat_byte(0xab).as_bytes()).to_h160(&ethereum),
+            Err(ConversionError::InvalidEvmAddressPadding(_))
+        ));
+        assert!(matches!(
+            address(H256::from(evm_address).as_bytes()).to_h160(&solana),
+            Err(ConversionError::NonEvmAddress(
+                HyperlaneDomainProtocol::Sealevel
+            ))
+        ));
+    }
+
+    #[test]
+    fn addresses_of_unexpected_lengths_are_rejected() {
+        let evm_address = H160::repeat_byte(0xab);
+        let padded = H256::from(evm_address);
+        for bytes in [evm_address.as_bytes(), padded.as_bytes()] {
+            let address = Address::try_from_slice(bytes).unwrap();
+            assert_eq!(H160::try_from(&address).unwrap(), evm_address);
+        }
+        assert!(matches!(
+            Address::try_from_slice(&[0xab; 33]),
+            Err(ConversionError::InvalidAddressLength(33))
+        ));
+
+        // e.g. from the ABI, which keeps every byte of a token
+        let err = H160::try_from(&Address(vec![0xab; 19].into())).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid address length 19, expected 20 or 32 bytes"
+        );
+    }
+
+    #[test]
+    fn addresses_convert_losslessly_between_20_and_32_bytes() {
+        let evm_address = H160::repeat_byte(0xab);
+        let padded = H256::from(evm_address);
+
+        // padded
+        let address = Address::from(padded);
+        assert_eq!(address.as_bytes().len(), 32);
+        assert_eq!(H256::try_from(&address).unwrap(), padded);
+        assert_eq!(address.into_evm_h160().unwrap(), evm_address);
+
+        // unpadded
+        let address = Address::try_from_slice(evm_address.as_bytes()).unwrap();
+        assert_eq!(H256::try_from(&address).unwrap(), padded);
+        assert_eq!(address.into_evm_h160().unwrap(), evm_address);
+
+        // garbage in the high bytes
+        let mut garbage = padded;
+        garbage.0[0] = 0x01;
+        let address = Address::from(garbage);
+        assert_eq!(H256::try_from(&address).unwrap(), garbage);
+        assert!(matches!(
+            address.into_evm_h160(),
+            Err(ConversionError::InvalidEvmAddressPadding(h256)) if h256 == garbage
+        ));
+
+        assert!(matches!(
+            H256::try_from(&Address(vec![0xab; 31].into())),
+            Err(ConversionError::InvalidAddressLength(31))
+        ));
+    }
+
+    #[test]
+    fn addresses_compare_by_their_canonical_form() {
+        let evm_address = H160::repeat_byte(0xab);
+        let padded = H256::from(evm_address);
+        let address = Address::from(evm_address);
+        let padded_address = Address::try_from_slice(padded.as_bytes()).unwrap();
+
+        assert_eq!(address, padded_address);
+        assert_eq!(address.canonicalize().as_bytes(), padded.as_bytes());
+        assert_eq!(address.cmp(&padded_address), Ordering::Equal);
+        assert_eq!(
+            HashSet::from([address.clone(), padded_address]).len(),
+            1,
+            "padding doesn't deduplicate"
+        );
+
+        let other = Address::from(H160::repeat_byte(0xac));
+        assert_ne!(address, other);
+        assert!(address < other);
+        assert!(Address::from(H256::repeat_byte(0xff)) > other);
+        // longer addresses are left as they are
+        let long = Address(vec![0xab; 33].into());
+        assert_eq!(long.canonicalize().as_bytes().len(), 33);
+        assert_ne!(long, Address::from(H256::repeat_byte(0xab)));
+    }
+
+    #[test]
+    fn contract_locators_round_trip_through_display() {
+        let addresses = [
+            H256::zero(),
+            H256::from(H160::repeat_byte(0xab)),
+            H256::repeat_byte(0xff),
+            H256::from_low_u64_be(0x1234_5678),
+        ];
+        for (domain, ..) in HyperlaneDomain::all() {
+            for address in addresses {
+                let displayed = ContractLocator::new(domain, address).to_string();
+                let parsed: ContractLocator = displayed.parse().unwrap();
+                assert_eq!(parsed.domain, domain);
+                assert_eq!(parsed.address, address);
+                assert_eq!(parsed.to_string(), displayed);
+            }
+        }
+
+        let address = H256::from(H160::repeat_byte(0xab));
+        let ethereum = HyperlaneDomain::Known(KnownHyperlaneDomain::Ethereum);
+        for unnamed in [
+            format!("[@1]+contract:{address:?}"),
+            format!("ETHEREUM[@1]+contract:{address:?}"),
+            format!("[@1]+contract:{:?}", H160::repeat_byte(0xab)),
+        ] {
+            let parsed: ContractLocator = unnamed.parse().unwrap();
+            assert_eq!(parsed.domain, &ethereum, "{unnamed}");
+            assert_eq!(parsed.address, address, "{unnamed}");
+        }
+    }
+
+    #[test]
+    fn contract_locators_are_only_built_for_matching_names_and_ids() {
+        let address = H256::repeat_byte(0xab);
+        let avalanche = HyperlaneDomain::Known(KnownHyperlaneDomain::Avalanche);
+        for name in ["avalanche", "AVALANCHE", ""] {
+            let locator = ContractLocator::try_new(name, 43114, address).unwrap();
+            assert_eq!(locator.domain, &avalanche, "{name}");
+            assert_eq!(locator.address, address);
+        }
+        let bsc = ContractLocator::try_new("binancesmartchain", 56, address).unwrap();
+        assert_eq!(bsc.domain.name(), "bsc");
+
+        assert_eq!(
+            ContractLocator::try_new("fuji", 43114, address).unwrap_err(),
+            ContractLocatorParseError::DomainMismatch {
+                name: "fuji".to_owned(),
+                domain_id: 43114
+            }
+        );
+        assert_eq!(
+            ContractLocator::try_new("avalanche", 80002, address).unwrap_err(),
+            ContractLocatorParseError::UnknownDomain(UnknownDomainError { domain_id: 80002 })
+        );
+    }
+
+    #[test]
+    fn malformed_contract_locators_are_rejected() {
+        let parse = |s: &str| {
+            s.parse::<ContractLocator>()
+                .map(|locator| locator.to_string())
+        };
+        let address = format!("{:?}", H256::repeat_byte(0xab));
+        for malformed in [
+            String::new(),
+            format!("ethereum[@1]{address}"),
+            format!("ethereum@1+contract:{address}"),
+            format!("ethereum[@one]+contract:{address}"),
+        ] {
+            assert_eq!(
+                parse(&malformed),
+                Err(ContractLocatorParseError::Malformed(malformed.clone()))
+            );
+        }
+        assert_eq!(
+            parse(&format!("[@80002]+contract:{address}")),
+            Err(ContractLocatorParseError::UnknownDomain(
+                UnknownDomainError { domain_id: 80002 }
+            ))
+        );
+        assert_eq!(
+            parse(&format!("polygon[@1]+contract:{address}")),
+            Err(ContractLocatorParseError::DomainMismatch {
+                name: "polygon".to_owned(),
+                domain_id: 1
+            })
+        );
+        assert_eq!(
+            parse("ethereum[@1]+contract:0xzz"),
+            Err(ContractLocatorParseError::InvalidAddress(
+                AddressParseError::InvalidCharacter {
+                    character: 'z',
+                    index: 2
+                }
+            ))
+        );
+        assert_eq!(
+            parse("ethereum[@1]+contract:0xabcd"),
+            Err(ContractLocatorParseError::InvalidAddressLength(2))
         );
-        assert!("foo".parse::<KnownHyperlaneDomain>().is_err());
     }
 
     #[test]
@@ -668,4 +2830,94 @@ mod tests {
             ReorgPeriod::Tag("finalized".into())
         );
     }
+
+    #[test]
+    fn rollup_domains_have_their_technical_stack() {
+        use HyperlaneDomainTechnicalStack::*;
+
+        let stack = |domain| HyperlaneDomain::Known(domain).domain_technical_stack();
+        assert_eq!(stack(KnownHyperlaneDomain::Arbitrum), ArbitrumNitro);
+        assert_eq!(stack(KnownHyperlaneDomain::Optimism), OpStack);
+        assert_eq!(stack(KnownHyperlaneDomain::Polygon), PolygonPoS);
+        assert_eq!(stack(KnownHyperlaneDomain::Xlayer), PolygonCDK);
+        assert_eq!(stack(KnownHyperlaneDomain::Ethereum), Other);
+        assert!(HyperlaneDomain::Known(KnownHyperlaneDomain::Arbitrum).is_arbitrum_nitro());
+        assert!(HyperlaneDomain::Known(KnownHyperlaneDomain::Optimism).is_op_stack());
+        assert!(!HyperlaneDomain::Known(KnownHyperlaneDomain::Ethereum).is_op_stack());
+
+        // Unknown domains take theirs from config
+        assert_eq!("PolygonPoS".parse(), Ok(PolygonPoS));
+        let zksync = HyperlaneDomain::from_config(
+            888_888,
+            "newchain",
+            HyperlaneDomainProtocol::Ethereum,
+            "zksync".parse().unwrap(),
+        )
+        .unwrap();
+        assert_eq!(zksync.domain_technical_stack(), ZkSync);
+        assert!(!zksync.is_arbitrum_nitro());
+    }
+
+    #[test]
+    fn testnets_map_to_their_canonical_mainnet() {
+        for domain in KnownHyperlaneDomain::iter() {
+            let mainnet = domain.canonical_mainnet();
+            match domain.domain_type() {
+                HyperlaneDomainType::Mainnet => assert_eq!(mainnet, Some(domain)),
+                HyperlaneDomainType::Testnet => assert!(
+                    mainnet.map_or(true, |mainnet| mainnet.domain_type()
+                        == HyperlaneDomainType::Mainnet
+                        && mainnet.domain_protocol() == domain.domain_protocol()),
+                    "{domain} maps to {mainnet:?}"
+                ),
+                HyperlaneDomainType::LocalTestChain | HyperlaneDomainType::Unknown => {
+                    assert_eq!(mainnet, None)
+                }
+            }
+        }
+
+        let known = HyperlaneDomain::Known;
+        assert_eq!(
+            known(KnownHyperlaneDomain::Fuji).canonical_mainnet(),
+            Some(known(KnownHyperlaneDomain::Avalanche))
+        );
+        assert_eq!(
+            known(KnownHyperlaneDomain::Alfajores).canonical_mainnet(),
+            Some(known(KnownHyperlaneDomain::Celo))
+        );
+        assert_eq!(
+            known(KnownHyperlaneDomain::ScrollSepolia).canonical_mainnet(),
+            None
+        );
+        assert_eq!(
+            HyperlaneDomain::from_domain_id(80002).canonical_mainnet(),
+            None
+        );
+
+        // The reverse mapping covers every testnet with a known mainnet
+        let testnets_of = |mainnet| {
+            HyperlaneDomain::testnets_of(&known(mainnet))
+                .map(|testnet| testnet.name().to_owned())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            testnets_of(KnownHyperlaneDomain::Ethereum),
+            vec!["holesky", "sepolia"]
+        );
+        assert_eq!(testnets_of(KnownHyperlaneDomain::Gnosis), vec!["chiado"]);
+        assert_eq!(
+            testnets_of(KnownHyperlaneDomain::Optimism),
+            vec!["optimismsepolia"]
+        );
+        assert!(testnets_of(KnownHyperlaneDomain::Polygon).is_empty());
+        let mapped = HyperlaneDomain::mainnets()
+            .flat_map(|mainnet| HyperlaneDomain::testnets_of(&mainnet).collect::<Vec<_>>())
+            .count();
+        assert_eq!(
+            mapped,
+            HyperlaneDomain::testnets()
+                .filter(|testnet| testnet.canonical_mainnet().is_some())
+                .count()
+        );
+    }
 }
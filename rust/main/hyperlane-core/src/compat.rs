@@ -0,0 +1,30 @@
+//! Functions which used to return `eyre::Result`, kept so downstream crates
+//! can migrate to [`HyperlaneCoreError`](crate::HyperlaneCoreError)
+//! incrementally. They will be removed in a future release.
+
+use crate::{Indexed, SequenceIndexed, H256};
+
+/// Creates a big-endian hex representation of the address
+#[deprecated(note = "use `hyperlane_core::bytes_to_address`, which returns a `ConversionError`")]
+pub fn bytes_to_address(data: Vec<u8>) -> eyre::Result<H256> {
+    Ok(crate::bytes_to_address(data)?)
+}
+
+/// Converts a hex or base58 string to an H256.
+#[deprecated(
+    note = "use `hyperlane_core::utils::hex_or_base58_to_h256`, which returns a `ConversionError`"
+)]
+pub fn hex_or_base58_to_h256(string: &str) -> eyre::Result<H256> {
+    Ok(crate::utils::hex_or_base58_to_h256(string)?)
+}
+
+/// Convert a vector of `Indexed` values to a vector of `SequenceIndexed` values
+/// so that if any `Option` is `None`, the conversion will fail
+#[deprecated(
+    note = "use `hyperlane_core::indexed_to_sequence_indexed_array`, which returns a `ConversionError`"
+)]
+pub fn indexed_to_sequence_indexed_array<T, U>(
+    indexed_array: Vec<(Indexed<T>, U)>,
+) -> eyre::Result<Vec<(SequenceIndexed<T>, U)>> {
+    Ok(crate::indexed_to_sequence_indexed_array(indexed_array)?)
+}
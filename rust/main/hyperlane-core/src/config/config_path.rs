@@ -66,6 +66,11 @@ impl ConfigPath {
         )
     }
 
+    /// The parts of the path, in snake case.
+    pub fn parts(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(|s| s.as_str())
+    }
+
     /// Get the JSON formatted path.
     pub fn json_name(&self) -> String {
         self.0
@@ -109,6 +109,23 @@ impl ConfigParsingError {
         }
     }
 
+    /// Wrap each error for which `context` returns some context, given the
+    /// path of the error.
+    pub fn with_path_context<C>(self, context: impl Fn(&ConfigPath) -> Option<C>) -> Self
+    where
+        C: Display + Send + Sync + 'static,
+    {
+        Self(
+            self.0
+                .into_iter()
+                .map(|(path, report)| match context(&path) {
+                    Some(context) => (path, report.wrap_err(context)),
+                    None => (path, report),
+                })
+                .collect(),
+        )
+    }
+
     /// Checks if there are no errors.
     pub fn is_ok(&self) -> bool {
         self.0.is_empty()
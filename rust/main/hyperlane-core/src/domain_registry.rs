@@ -0,0 +1,236 @@
+use std::collections::HashMap;
+
+use crate::{
+    HyperlaneDomain, HyperlaneDomainProtocol, HyperlaneDomainTechnicalStack, HyperlaneDomainType,
+    KnownHyperlaneDomain, UnknownDomainNameError,
+};
+
+/// A domain which can't be registered because it conflicts with one already
+/// in the [`DomainRegistry`]
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum DomainRegistryError {
+    /// The name is already registered with another domain id
+    #[error("Domain `{name}` is already registered with domain id {registered_id}; it can't be registered with domain id {domain_id}")]
+    ConflictingId {
+        /// Name of the domain
+        name: String,
+        /// Id the name is registered with
+        registered_id: u32,
+        /// Id it was registered with again
+        domain_id: u32,
+    },
+    /// The domain id is already registered under another name
+    #[error("Domain id {domain_id} is already registered as `{registered_name}`; it can't be registered as `{name}`")]
+    ConflictingName {
+        /// The domain id
+        domain_id: u32,
+        /// Name the id is registered under
+        registered_name: String,
+        /// Name it was registered under again
+        name: String,
+    },
+}
+
+/// The domains agents can resolve by id and by name: the known domains,
+/// extended at runtime with domains this release doesn't know about, e.g.
+/// permissionless deployments declared in the agent config. Names are
+/// matched ignoring ASCII case, and known domains also by their aliases.
+#[derive(Clone, Debug, Default)]
+pub struct DomainRegistry {
+    /// Domains registered at runtime by id
+    domains: HashMap<u32, HyperlaneDomain>,
+    /// Ids of the domains registered at runtime by lowercase name
+    ids_by_name: HashMap<String, u32>,
+}
+
+impl DomainRegistry {
+    /// A registry of the known domains only
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a domain which isn't known, returning it. Registering a
+    /// domain again with the same id is a no-op, including a known one.
+    pub fn register(
+        &mut self,
+        name: &str,
+        domain_id: u32,
+        domain_type: HyperlaneDomainType,
+        domain_protocol: HyperlaneDomainProtocol,
+    ) -> Result<HyperlaneDomain, DomainRegistryError> {
+        self.register_domain(HyperlaneDomain::Unknown {
+            domain_id,
+            domain_name: name.to_ascii_lowercase(),
+            domain_type,
+            domain_protocol,
+            domain_technical_stack: HyperlaneDomainTechnicalStack::default(),
+        })
+    }
+
+    /// Register `domain`, e.g. one built from config, returning the domain
+    /// registered with its id. See [`Self::register`].
+    pub fn register_domain(
+        &mut self,
+        domain: HyperlaneDomain,
+    ) -> Result<HyperlaneDomain, DomainRegistryError> {
+        let (name, domain_id) = (domain.name().to_ascii_lowercase(), domain.id());
+        if let Some(registered_id) = self.domain_id_from_name(&name) {
+            return if registered_id == domain_id {
+                Ok(self.domain(domain_id))
+            } else {
+                Err(DomainRegistryError::ConflictingId {
+                    name,
+                    registered_id,
+                    domain_id,
+                })
+            };
+        }
+        if let Some(registered_name) = self.name_from_domain_id(domain_id) {
+            return Err(DomainRegistryError::ConflictingName {
+                domain_id,
+                registered_name: registered_name.to_owned(),
+                name,
+            });
+        }
+        self.ids_by_name.insert(name, domain_id);
+        self.domains.insert(domain_id, domain.clone());
+        Ok(domain)
+    }
+
+    /// The name of the domain with the given id, if it is known or registered
+    pub fn name_from_domain_id(&self, domain_id: u32) -> Option<&str> {
+        KnownHyperlaneDomain::name_from_domain_id(domain_id)
+            .or_else(|| self.domains.get(&domain_id).map(HyperlaneDomain::name))
+    }
+
+    /// The id of the domain with the given name, if it is known or registered
+    pub fn domain_id_from_name(&self, name: &str) -> Option<u32> {
+        match name.parse::<KnownHyperlaneDomain>() {
+            Ok(domain) => Some(domain as u32),
+            Err(_) => self.ids_by_name.get(&name.to_ascii_lowercase()).copied(),
+        }
+    }
+
+    /// The domain with the given id. Ids which are neither known nor
+    /// registered are carried through, see
+    /// [`HyperlaneDomain::from_domain_id`].
+    pub fn domain(&self, domain_id: u32) -> HyperlaneDomain {
+        self.domains
+            .get(&domain_id)
+            .cloned()
+            .unwrap_or_else(|| HyperlaneDomain::from_domain_id(domain_id))
+    }
+
+    /// The domain with the given name, if it is known or registered
+    pub fn domain_from_name(&self, name: &str) -> Result<HyperlaneDomain, UnknownDomainNameError> {
+        self.domain_id_from_name(name)
+            .map(|domain_id| self.domain(domain_id))
+            .ok_or_else(|| UnknownDomainNameError {
+                name: name.to_owned(),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registered_domains_resolve_like_known_ones() {
+        let mut registry = DomainRegistry::new();
+        assert_eq!(registry.name_from_domain_id(1), Some("ethereum"));
+        assert_eq!(registry.domain_id_from_name("MATIC"), Some(137));
+        assert_eq!(registry.name_from_domain_id(888_888), None);
+        assert_eq!(registry.domain_id_from_name("newchain"), None);
+
+        let domain = registry
+            .register(
+                "NewChain",
+                888_888,
+                HyperlaneDomainType::Mainnet,
+                HyperlaneDomainProtocol::Ethereum,
+            )
+            .unwrap();
+        assert_eq!(domain.name(), "newchain");
+        assert!(domain.is_mainnet());
+        assert_eq!(registry.name_from_domain_id(888_888), Some("newchain"));
+        assert_eq!(registry.domain_id_from_name("NEWCHAIN"), Some(888_888));
+        assert_eq!(registry.domain(888_888).name(), "newchain");
+        assert_eq!(registry.domain_from_name("newchain"), Ok(domain.clone()));
+        // Ids which aren't registered are still carried through
+        assert_eq!(registry.domain(999_999).name(), "unknown:999999");
+
+        // Registering the same domain again is a no-op
+        assert_eq!(
+            registry.register(
+                "newchain",
+                888_888,
+                HyperlaneDomainType::Mainnet,
+                HyperlaneDomainProtocol::Ethereum,
+            ),
+            Ok(domain)
+        );
+        assert_eq!(
+            registry.register_domain(HyperlaneDomain::Known(KnownHyperlaneDomain::Ethereum)),
+            Ok(HyperlaneDomain::Known(KnownHyperlaneDomain::Ethereum))
+        );
+    }
+
+    #[test]
+    fn conflicting_registrations_are_rejected() {
+        let mut registry = DomainRegistry::new();
+        let mut register = |name: &str, domain_id| {
+            registry.register(
+                name,
+                domain_id,
+                HyperlaneDomainType::Unknown,
+                HyperlaneDomainProtocol::Ethereum,
+            )
+        };
+        register("newchain", 888_888).unwrap();
+
+        let err = register("newchain", 888_889).unwrap_err();
+        assert_eq!(
+            err,
+            DomainRegistryError::ConflictingId {
+                name: "newchain".to_owned(),
+                registered_id: 888_888,
+                domain_id: 888_889,
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "Domain `newchain` is already registered with domain id 888888; it can't be registered with domain id 888889"
+        );
+        // Known domains, also by their aliases
+        assert!(matches!(
+            register("Ethereum", 888_889),
+            Err(DomainRegistryError::ConflictingId {
+                registered_id: 1,
+                ..
+            })
+        ));
+        assert!(matches!(
+            register("matic", 888_889),
+            Err(DomainRegistryError::ConflictingId {
+                registered_id: 137,
+                ..
+            })
+        ));
+
+        assert_eq!(
+            register("otherchain", 888_888),
+            Err(DomainRegistryError::ConflictingName {
+                domain_id: 888_888,
+                registered_name: "newchain".to_owned(),
+                name: "otherchain".to_owned(),
+            })
+        );
+        assert!(matches!(
+            register("otherchain", 1),
+            Err(DomainRegistryError::ConflictingName { registered_name, .. })
+                if registered_name == "ethereum"
+        ));
+        assert_eq!(registry.domain_id_from_name("otherchain"), None);
+    }
+}
@@ -11,8 +11,8 @@ use crate::rpc_clients::RpcClientError;
 use std::string::FromUtf8Error;
 
 use crate::{
-    Error as PrimitiveTypeError, HyperlaneProviderError, HyperlaneSignerError, ReorgPeriod, H256,
-    U256,
+    Error as PrimitiveTypeError, HyperlaneDomainConfigError, HyperlaneDomainProtocol,
+    HyperlaneProviderError, HyperlaneSignerError, ReorgPeriod, UnknownDomainError, H256, U256,
 };
 
 /// The result of interacting with a chain.
@@ -134,6 +134,14 @@ pub enum ChainCommunicationError {
         /// The available amount of funds.
         available: U256,
     },
+    /// The gas price of the destination is above the ceiling set for the transaction
+    #[error("Gas price {gas_price:?} exceeds the ceiling of {ceiling:?}")]
+    GasPriceCeilingExceeded {
+        /// The gas price the transaction would have to pay.
+        gas_price: U256,
+        /// The highest gas price allowed for the transaction.
+        ceiling: U256,
+    },
     /// Primitive type error
     #[error(transparent)]
     PrimitiveTypeError(#[from] PrimitiveTypeError),
@@ -159,6 +167,32 @@ pub enum ChainCommunicationError {
     /// Invalid reorg period
     #[error("Invalid reorg period: {0:?}")]
     InvalidReorgPeriod(ReorgPeriod),
+    /// The provider may have silently left logs out of its response to a
+    /// log query, e.g. by capping the number of logs returned. The queried
+    /// range is split and queried again.
+    #[error("Possibly truncated response of {logs} logs for blocks {from}..={to}: {reason}")]
+    PossiblyTruncatedResponse {
+        /// First block of the queried range
+        from: u32,
+        /// Last block of the queried range
+        to: u32,
+        /// Number of logs the provider returned
+        logs: usize,
+        /// Why the response is suspected to be truncated
+        reason: String,
+    },
+    /// The RPC serves another chain than the domain it's configured for
+    #[error(
+        "The RPC of {domain} serves chain id {actual}, but the chain id of {domain} is {expected}"
+    )]
+    ChainIdMismatch {
+        /// Name of the domain the RPC is configured for
+        domain: String,
+        /// Chain id of the domain
+        expected: u64,
+        /// Chain id the RPC serves
+        actual: u64,
+    },
 }
 
 impl ChainCommunicationError {
@@ -260,3 +294,88 @@ pub enum HyperlaneProtocolError {
     #[error("A gas limit was expected for `process` contract call")]
     ProcessGasLimitRequired,
 }
+
+impl From<UnknownDomainError> for HyperlaneProtocolError {
+    fn from(err: UnknownDomainError) -> Self {
+        Self::UnknownDomainId(err.domain_id)
+    }
+}
+
+/// The result of a fallible hyperlane-core trait method or function.
+pub type HyperlaneCoreResult<T> = Result<T, HyperlaneCoreError>;
+
+/// Errors returned by hyperlane-core traits and functions.
+///
+/// Unlike `eyre::Report`, this can be matched on by library consumers. It
+/// converts into an `eyre::Report` with `?` through eyre's blanket impl, so
+/// agent binaries can keep using eyre.
+#[derive(Debug, thiserror::Error)]
+pub enum HyperlaneCoreError {
+    /// Error interacting with a chain
+    #[error(transparent)]
+    ChainCommunication(#[from] ChainCommunicationError),
+    /// Hyperlane protocol error
+    #[error(transparent)]
+    Protocol(#[from] HyperlaneProtocolError),
+    /// A domain id which isn't a known domain
+    #[error(transparent)]
+    UnknownDomain(#[from] UnknownDomainError),
+    /// A domain config which is inconsistent with the known domains
+    #[error(transparent)]
+    DomainConfig(#[from] HyperlaneDomainConfigError),
+    /// Failed to convert between types
+    #[error(transparent)]
+    Conversion(#[from] ConversionError),
+    /// Any other error, e.g. from the storage backing a trait implementation.
+    /// The original error can be recovered with `downcast_ref`.
+    #[error(transparent)]
+    Other(Box<dyn StdError + Send + Sync>),
+}
+
+impl HyperlaneCoreError {
+    /// Create a core error from any other existing error
+    pub fn from_other(err: impl Into<Box<dyn StdError + Send + Sync>>) -> Self {
+        Self::Other(err.into())
+    }
+}
+
+impl From<eyre::Report> for HyperlaneCoreError {
+    fn from(report: eyre::Report) -> Self {
+        Self::from_other(report)
+    }
+}
+
+/// Errors converting between hyperlane-core types
+#[derive(Debug, thiserror::Error)]
+pub enum ConversionError {
+    /// The bytes are neither a 20 nor a 32 byte address
+    #[error("Invalid address length {0}, expected 20 or 32 bytes")]
+    InvalidAddressLength(usize),
+    /// A hex string of the wrong length
+    #[error("Invalid hex string length {0}")]
+    InvalidHexLength(usize),
+    /// A base58 string which doesn't decode to 32 bytes
+    #[error("Invalid length of base58 string {0}")]
+    InvalidBase58Length(usize),
+    /// Hash hex parsing error
+    #[error(transparent)]
+    Hex(#[from] fixed_hash::rustc_hex::FromHexError),
+    /// A malformed hex string
+    #[error(transparent)]
+    InvalidHex(#[from] crate::hex::HexError),
+    /// Base58 parsing error
+    #[error(transparent)]
+    Base58(#[from] bs58::decode::Error),
+    /// An indexed value without the sequence it was expected to have
+    #[error("Missing indexing sequence")]
+    MissingSequence,
+    /// An address of a non-EVM domain converted to an EVM address
+    #[error("Addresses of {0:?} domains are not EVM addresses")]
+    NonEvmAddress(HyperlaneDomainProtocol),
+    /// A 32 byte address with non-zero bytes beyond the 20 of an EVM address
+    #[error("Address {0:?} is not a left-padded EVM address")]
+    InvalidEvmAddressPadding(H256),
+    /// A balance which is negative or doesn't fit in 256 bits
+    #[error("Balance {0} is out of the range of a U256")]
+    BalanceOutOfRange(num::BigInt),
+}
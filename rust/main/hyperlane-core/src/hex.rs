@@ -0,0 +1,391 @@
+//! Hex parsing and serde adapters shared by every public-facing type that
+//! carries bytes: settings addresses, admin endpoint bodies, proofs and
+//! message bodies.
+//!
+//! Input may or may not be `0x`-prefixed; output is always `0x`-prefixed
+//! lowercase. The adapters are unit structs exposing `serialize` and
+//! `deserialize`, so they plug into `#[serde(with = "...")]` the same way a
+//! `serde_with::serde_as` adapter would, e.g.
+//!
+//! ```
+//! use hyperlane_core::{hex::Hex20Or32, H256};
+//!
+//! #[derive(serde::Serialize, serde::Deserialize)]
+//! struct Route {
+//!     #[serde(with = "Hex20Or32")]
+//!     recipient: H256,
+//! }
+//! ```
+//!
+//! Errors name what was expected and where the input went wrong; callers
+//! that deserialize nested documents (axum's `Json`, the settings parser)
+//! prefix them with the field path.
+
+use std::{fmt, marker::PhantomData};
+
+use serde::{de, Deserialize, Deserializer, Serializer};
+
+use crate::H256;
+
+/// Errors parsing a hex string
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum HexError {
+    /// A character that isn't a hex digit
+    #[error("invalid hex character {character:?} at position {index}")]
+    InvalidCharacter {
+        /// The offending character
+        character: char,
+        /// Its position in the input, counting any `0x` prefix
+        index: usize,
+    },
+    /// An odd number of hex digits
+    #[error("odd number of hex digits ({0})")]
+    OddLength(usize),
+    /// A well-formed string of the wrong length
+    #[error("expected {expected} bytes, got {actual}")]
+    InvalidLength {
+        /// Description of the accepted lengths
+        expected: &'static str,
+        /// Number of bytes decoded
+        actual: usize,
+    },
+}
+
+/// Encodes bytes as a `0x`-prefixed lowercase hex string
+pub fn encode(bytes: impl AsRef<[u8]>) -> String {
+    format!("0x{}", ::hex::encode(bytes))
+}
+
+/// Decodes a hex string, with or without a `0x` prefix
+pub fn decode(s: &str) -> Result<Vec<u8>, HexError> {
+    let (digits, offset) = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(digits) => (digits, 2),
+        None => (s, 0),
+    };
+    if let Some((index, character)) = digits.char_indices().find(|(_, c)| !c.is_ascii_hexdigit()) {
+        return Err(HexError::InvalidCharacter {
+            character,
+            index: index + offset,
+        });
+    }
+    if digits.len() % 2 != 0 {
+        return Err(HexError::OddLength(digits.len()));
+    }
+    Ok(::hex::decode(digits).expect("validated hex digits"))
+}
+
+/// Decodes a hex string of exactly 32 bytes
+pub fn decode_32(s: &str) -> Result<H256, HexError> {
+    let bytes = decode(s)?;
+    if bytes.len() != 32 {
+        return Err(HexError::InvalidLength {
+            expected: "32",
+            actual: bytes.len(),
+        });
+    }
+    Ok(H256::from_slice(&bytes))
+}
+
+/// Decodes a 20 byte address, left-padding it to 32 bytes, or a 32 byte one
+pub fn decode_20_or_32(s: &str) -> Result<H256, HexError> {
+    let bytes = decode(s)?;
+    match bytes.len() {
+        20 => {
+            let mut padded = [0u8; 32];
+            padded[12..].copy_from_slice(&bytes);
+            Ok(H256(padded))
+        }
+        32 => Ok(H256::from_slice(&bytes)),
+        actual => Err(HexError::InvalidLength {
+            expected: "20 or 32",
+            actual,
+        }),
+    }
+}
+
+struct HexVisitor<A>(PhantomData<A>);
+
+impl<'de, A: HexAdapter> de::Visitor<'de> for HexVisitor<A> {
+    type Value = A::Decoded;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(A::EXPECTING)
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        A::decode(v).map_err(E::custom)
+    }
+}
+
+fn deserialize_with<'de, A, D>(deserializer: D) -> Result<A::Decoded, D::Error>
+where
+    A: HexAdapter,
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_str(HexVisitor::<A>(PhantomData))
+}
+
+/// The decoding half of a hex adapter, shared so adapters compose, e.g. in
+/// [`HexSeq`]
+pub trait HexAdapter {
+    /// The decoded value
+    type Decoded;
+    /// What the adapter expects, used in error messages
+    const EXPECTING: &'static str;
+    /// Decodes a hex string, with or without a `0x` prefix
+    fn decode(s: &str) -> Result<Self::Decoded, HexError>;
+}
+
+macro_rules! impl_hex_adapter {
+    ($adapter:ident, $decoded:ty, $expecting:literal, $decode:path) => {
+        impl HexAdapter for $adapter {
+            type Decoded = $decoded;
+            const EXPECTING: &'static str = $expecting;
+
+            fn decode(s: &str) -> Result<Self::Decoded, HexError> {
+                $decode(s)
+            }
+        }
+
+        impl $adapter {
+            /// Serializes as `0x`-prefixed lowercase hex
+            pub fn serialize<S>(v: impl AsRef<[u8]>, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+            {
+                serializer.serialize_str(&encode(v))
+            }
+
+            /// Deserializes from hex, with or without a `0x` prefix
+            pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+            where
+                D: Deserializer<'de>,
+                T: From<$decoded>,
+            {
+                deserialize_with::<Self, _>(deserializer).map(T::from)
+            }
+        }
+    };
+}
+
+/// Serde adapter for byte strings of any length, e.g. message bodies and
+/// metadata
+pub struct HexBytes;
+
+/// Serde adapter for 32 byte values such as message ids, leaves and roots
+pub struct Hex32;
+
+/// Serde adapter for addresses given either in 20 byte (EVM) or 32 byte
+/// form. Always serializes the 32 byte form so values compare equal as
+/// strings.
+pub struct Hex20Or32;
+
+impl_hex_adapter!(HexBytes, Vec<u8>, "a hex string", decode);
+impl_hex_adapter!(Hex32, H256, "a 32 byte hex string", decode_32);
+impl_hex_adapter!(
+    Hex20Or32,
+    H256,
+    "a 20 or 32 byte hex string",
+    decode_20_or_32
+);
+
+/// Applies a hex adapter to each element of a list, for use as
+/// `#[serde(with = "HexSeq::<Hex32>")]`
+pub struct HexSeq<A>(PhantomData<A>);
+
+impl<A: HexAdapter> HexSeq<A> {
+    /// Serializes each element as `0x`-prefixed lowercase hex
+    pub fn serialize<S, T>(v: &[T], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: AsRef<[u8]>,
+    {
+        serializer.collect_seq(v.iter().map(encode))
+    }
+
+    /// Deserializes each element with the element adapter
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: From<A::Decoded>,
+    {
+        struct Element<A: HexAdapter>(A::Decoded);
+
+        impl<'de, A: HexAdapter> Deserialize<'de> for Element<A> {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                deserialize_with::<A, _>(deserializer).map(Element)
+            }
+        }
+
+        let elements = Vec::<Element<A>>::deserialize(deserializer)?;
+        Ok(elements.into_iter().map(|e| T::from(e.0)).collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde::Serialize;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        accumulator::{merkle::Proof, TREE_DEPTH},
+        HyperlaneMessage,
+    };
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Adapted {
+        #[serde(with = "HexBytes")]
+        bytes: Vec<u8>,
+        #[serde(with = "Hex32")]
+        id: H256,
+        #[serde(with = "Hex20Or32")]
+        address: H256,
+        #[serde(with = "HexSeq::<Hex20Or32>")]
+        addresses: Vec<H256>,
+    }
+
+    fn evm_address() -> H256 {
+        decode_20_or_32("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed").unwrap()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let adapted = Adapted {
+            bytes: vec![0xde, 0xad, 0xbe, 0xef],
+            id: H256::repeat_byte(0xab),
+            address: evm_address(),
+            addresses: vec![evm_address(), H256::repeat_byte(1)],
+        };
+        let serialized = serde_json::to_string(&adapted).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Adapted>(&serialized).unwrap(),
+            adapted
+        );
+    }
+
+    #[test]
+    fn test_accepts_optional_prefix_and_any_case() {
+        let adapted: Adapted = serde_json::from_value(json!({
+            "bytes": "DEADBEEF",
+            "id": "AB".repeat(32),
+            "address": "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            "addresses": ["5aaeb6053f3e94c9b9a09f33669435e7ef1beaed"],
+        }))
+        .unwrap();
+        assert_eq!(adapted.bytes, vec![0xde, 0xad, 0xbe, 0xef]);
+        assert_eq!(adapted.id, H256::repeat_byte(0xab));
+        assert_eq!(adapted.address, evm_address());
+        assert_eq!(adapted.addresses, vec![evm_address()]);
+
+        assert_eq!(decode("0x").unwrap(), Vec::<u8>::new());
+        assert_eq!(decode("").unwrap(), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn test_rejects_malformed_input() {
+        assert_eq!(
+            decode("0xabzz"),
+            Err(HexError::InvalidCharacter {
+                character: 'z',
+                index: 4
+            })
+        );
+        assert_eq!(decode("abc"), Err(HexError::OddLength(3)));
+        assert_eq!(
+            decode_32(&encode([1u8; 31])),
+            Err(HexError::InvalidLength {
+                expected: "32",
+                actual: 31
+            })
+        );
+        assert_eq!(
+            decode_20_or_32(&encode([1u8; 21])),
+            Err(HexError::InvalidLength {
+                expected: "20 or 32",
+                actual: 21
+            })
+        );
+
+        let valid = json!({
+            "bytes": "0x",
+            "id": encode([0u8; 32]),
+            "address": encode([0u8; 20]),
+            "addresses": [],
+        });
+        for (field, value, message) in [
+            (
+                "bytes",
+                json!("0xg0"),
+                "invalid hex character 'g' at position 2",
+            ),
+            ("bytes", json!([1, 2]), "expected a hex string"),
+            ("id", json!(encode([0u8; 20])), "expected 32 bytes, got 20"),
+            ("address", json!("0x1234"), "expected 20 or 32 bytes, got 2"),
+            ("addresses", json!(["0x1"]), "odd number of hex digits (1)"),
+        ] {
+            let mut input = valid.clone();
+            input[field] = value;
+            let err = serde_json::from_value::<Adapted>(input).unwrap_err();
+            assert!(
+                err.to_string().contains(message),
+                "{field}: {err} doesn't mention {message}"
+            );
+        }
+    }
+
+    /// The JSON forms below are served by the relayer's admin endpoints and
+    /// read back by tooling, so changing them is a breaking API change
+    #[test]
+    fn test_serialized_forms_are_stable() {
+        let adapted = Adapted {
+            bytes: vec![0xde, 0xad],
+            id: H256::repeat_byte(0xab),
+            address: evm_address(),
+            addresses: vec![evm_address()],
+        };
+        let address = "0x0000000000000000000000005aaeb6053f3e94c9b9a09f33669435e7ef1beaed";
+        assert_eq!(
+            serde_json::to_value(&adapted).unwrap(),
+            json!({
+                "bytes": "0xdead",
+                "id": format!("0x{}", "ab".repeat(32)),
+                "address": address,
+                "addresses": [address],
+            })
+        );
+
+        let message = HyperlaneMessage {
+            version: 3,
+            nonce: 7,
+            origin: 1,
+            sender: evm_address(),
+            destination: 2,
+            recipient: H256::repeat_byte(0xcd),
+            body: vec![0x01, 0xff],
+        };
+        assert_eq!(
+            serde_json::to_value(&message).unwrap(),
+            json!({
+                "version": 3,
+                "nonce": 7,
+                "origin": 1,
+                "sender": address,
+                "destination": 2,
+                "recipient": format!("0x{}", "cd".repeat(32)),
+                "body": "0x01ff",
+            })
+        );
+
+        let proof = Proof {
+            leaf: H256::repeat_byte(0x11),
+            index: 5,
+            path: [H256::zero(); TREE_DEPTH],
+        };
+        let value = serde_json::to_value(proof).unwrap();
+        assert_eq!(value["leaf"], json!(format!("0x{}", "11".repeat(32))));
+        assert_eq!(value["index"], json!(5));
+        assert_eq!(value["path"][0], json!(encode([0u8; 32])));
+        assert_eq!(serde_json::from_value::<Proof>(value).unwrap(), proof);
+    }
+}
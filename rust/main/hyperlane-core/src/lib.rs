@@ -8,6 +8,8 @@
 extern crate core;
 
 pub use chain::*;
+#[cfg(feature = "strum")]
+pub use domain_registry::*;
 pub use error::*;
 pub use error::{ChainCommunicationError, ChainResult, HyperlaneProtocolError};
 pub use identifiers::HyperlaneIdentifier;
@@ -17,11 +19,21 @@ pub use types::*;
 /// Accumulator management
 pub mod accumulator;
 
+/// Solidity ABI token conversions
+#[cfg(feature = "ethers")]
+pub mod abi;
+
 /// Async Traits for contract instances for use in applications
 mod traits;
 /// Utilities to match contract values
 pub mod utils;
 
+pub mod hex;
+
+pub mod log_fields;
+
+pub mod compat;
+
 /// Testing utilities
 #[cfg(any(test, feature = "test-utils"))]
 pub mod test_utils;
@@ -34,6 +46,8 @@ pub mod metrics;
 mod types;
 
 mod chain;
+#[cfg(feature = "strum")]
+mod domain_registry;
 mod error;
 
 /// Implementations of custom rpc client logic (e.g. fallback)
@@ -0,0 +1,207 @@
+//! Formatting of potentially large log fields, such as message bodies,
+//! metadata and calldata.
+//!
+//! Such fields are truncated to a byte budget, shared by the whole process,
+//! and suffixed with their original length and keccak256 hash so that log
+//! lines can still be correlated with the full values. Full values are never
+//! logged: they're available from the relayer's admin server instead.
+
+use std::{
+    fmt::{Debug, Display, Formatter},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use sha3::{digest::Update, Digest, Keccak256};
+
+/// Byte budget of large log fields unless configured otherwise
+pub const DEFAULT_LOG_FIELD_BUDGET: usize = 1024;
+
+static LOG_FIELD_BUDGET: AtomicUsize = AtomicUsize::new(DEFAULT_LOG_FIELD_BUDGET);
+
+/// Set the byte budget of large log fields for the whole process
+pub fn set_log_field_budget(bytes: usize) {
+    LOG_FIELD_BUDGET.store(bytes, Ordering::Relaxed);
+}
+
+/// The byte budget of large log fields
+pub fn log_field_budget() -> usize {
+    LOG_FIELD_BUDGET.load(Ordering::Relaxed)
+}
+
+/// Formats bytes as `0x` prefixed hex, truncated to the log field budget
+#[derive(Clone, Copy)]
+pub struct LogBytes<'a>(pub &'a [u8]);
+
+impl Display for LogBytes<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        fmt_bytes(f, self.0, log_field_budget())
+    }
+}
+
+impl Debug for LogBytes<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(self, f)
+    }
+}
+
+/// Formats text truncated to the log field budget, without splitting a
+/// UTF-8 character
+#[derive(Clone, Copy)]
+pub struct LogText<'a>(pub &'a str);
+
+impl Display for LogText<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        fmt_text(f, self.0, log_field_budget())
+    }
+}
+
+impl Debug for LogText<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(self, f)
+    }
+}
+
+/// Formats optional bytes, e.g. metadata that couldn't be built, as
+/// [`LogBytes`] or `None`
+#[derive(Clone, Copy)]
+pub struct LogOptBytes<'a>(pub Option<&'a [u8]>);
+
+impl Display for LogOptBytes<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            Some(bytes) => Display::fmt(&LogBytes(bytes), f),
+            None => write!(f, "None"),
+        }
+    }
+}
+
+impl Debug for LogOptBytes<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(self, f)
+    }
+}
+
+/// The longest prefix of `value` that fits in `budget` bytes, cut at a
+/// character boundary
+pub fn truncate_at_char_boundary(value: &str, budget: usize) -> &str {
+    if value.len() <= budget {
+        return value;
+    }
+    let mut end = budget;
+    while !value.is_char_boundary(end) {
+        end -= 1;
+    }
+    &value[..end]
+}
+
+fn fmt_bytes(f: &mut Formatter<'_>, bytes: &[u8], budget: usize) -> std::fmt::Result {
+    // Two hex characters per byte, after the `0x` prefix
+    let kept = budget.saturating_sub(2) / 2;
+    if bytes.len() <= kept {
+        return write!(f, "0x{}", hex::encode(bytes));
+    }
+    write!(f, "0x{}", hex::encode(&bytes[..kept]))?;
+    fmt_suffix(f, bytes)
+}
+
+fn fmt_text(f: &mut Formatter<'_>, text: &str, budget: usize) -> std::fmt::Result {
+    let kept = truncate_at_char_boundary(text, budget);
+    f.write_str(kept)?;
+    if kept.len() < text.len() {
+        fmt_suffix(f, text.as_bytes())?;
+    }
+    Ok(())
+}
+
+fn fmt_suffix(f: &mut Formatter<'_>, full: &[u8]) -> std::fmt::Result {
+    let hash = Keccak256::new().chain(full).finalize();
+    write!(
+        f,
+        "…[truncated, {} bytes, keccak256 0x{}]",
+        full.len(),
+        hex::encode(hash)
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct Bytes<'a>(&'a [u8], usize);
+
+    impl Display for Bytes<'_> {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            fmt_bytes(f, self.0, self.1)
+        }
+    }
+
+    struct Text<'a>(&'a str, usize);
+
+    impl Display for Text<'_> {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            fmt_text(f, self.0, self.1)
+        }
+    }
+
+    fn bytes(value: &[u8], budget: usize) -> String {
+        Bytes(value, budget).to_string()
+    }
+
+    fn text(value: &str, budget: usize) -> String {
+        Text(value, budget).to_string()
+    }
+
+    fn keccak(value: &[u8]) -> String {
+        hex::encode(Keccak256::new().chain(value).finalize())
+    }
+
+    #[test]
+    fn test_bytes_are_truncated_at_the_budget() {
+        let value = [0xab; 8];
+        // 0x + 8 bytes as hex fits in 18 bytes
+        assert_eq!(bytes(&value, 18), format!("0x{}", "ab".repeat(8)));
+        assert_eq!(
+            bytes(&value, 17),
+            format!(
+                "0x{}…[truncated, 8 bytes, keccak256 0x{}]",
+                "ab".repeat(7),
+                keccak(&value)
+            )
+        );
+        assert!(bytes(&value, 0).starts_with("0x…[truncated, 8 bytes"));
+        assert_eq!(bytes(&[], 0), "0x");
+    }
+
+    #[test]
+    fn test_text_is_truncated_at_a_char_boundary() {
+        assert_eq!(text("short", 5), "short");
+        let suffix = |value: &str| {
+            format!(
+                "…[truncated, {} bytes, keccak256 0x{}]",
+                value.len(),
+                keccak(value.as_bytes())
+            )
+        };
+        assert_eq!(text("longer", 4), format!("long{}", suffix("longer")));
+
+        // "é" takes two bytes, so cutting after its first one keeps it out
+        let value = "abé€";
+        assert_eq!(truncate_at_char_boundary(value, 3), "ab");
+        assert_eq!(truncate_at_char_boundary(value, 4), "abé");
+        // "€" takes three bytes
+        for budget in 4..7 {
+            assert_eq!(text(value, budget), format!("abé{}", suffix(value)));
+        }
+        assert_eq!(text(value, 7), value);
+    }
+
+    #[test]
+    fn test_helpers_use_the_process_budget() {
+        let body = vec![1u8; DEFAULT_LOG_FIELD_BUDGET];
+        let formatted = LogBytes(&body).to_string();
+        assert!(formatted.len() < DEFAULT_LOG_FIELD_BUDGET + 100);
+        assert!(formatted.contains(&format!("{} bytes", body.len())));
+        assert_eq!(LogOptBytes(None).to_string(), "None");
+        assert_eq!(LogText("small").to_string(), "small");
+    }
+}
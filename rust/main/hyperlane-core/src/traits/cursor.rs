@@ -6,9 +6,8 @@ use std::{
 
 use async_trait::async_trait;
 use auto_impl::auto_impl;
-use eyre::Result;
 
-use crate::{Indexed, LogMeta};
+use crate::{HyperlaneCoreResult, Indexed, LogMeta};
 
 /// A cursor governs event indexing for a contract.
 #[async_trait]
@@ -17,7 +16,7 @@ pub trait ContractSyncCursor<T>: Debug + Send + Sync + 'static {
     /// The next block range that should be queried.
     /// This method should be tolerant to being called multiple times in a row
     /// without any updates in between.
-    async fn next_action(&mut self) -> Result<(CursorAction, Duration)>;
+    async fn next_action(&mut self) -> HyperlaneCoreResult<(CursorAction, Duration)>;
 
     /// The latest block that has been queried, used as a proxy for health.
     /// TODO: consider a better way to assess health
@@ -32,7 +31,7 @@ pub trait ContractSyncCursor<T>: Debug + Send + Sync + 'static {
         &mut self,
         logs: Vec<(Indexed<T>, LogMeta)>,
         range: RangeInclusive<u32>,
-    ) -> Result<()>;
+    ) -> HyperlaneCoreResult<()>;
 }
 
 /// The action that should be taken by the contract sync loop
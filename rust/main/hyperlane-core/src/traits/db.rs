@@ -2,9 +2,8 @@ use std::fmt::Debug;
 
 use async_trait::async_trait;
 use auto_impl::auto_impl;
-use eyre::Result;
 
-use crate::{Indexed, LogMeta};
+use crate::{HyperlaneCoreResult, Indexed, LogMeta};
 
 /// Interface for a HyperlaneLogStore that ingests logs.
 #[async_trait]
@@ -12,7 +11,7 @@ use crate::{Indexed, LogMeta};
 pub trait HyperlaneLogStore<T>: Send + Sync + Debug {
     /// Store a list of logs and their associated metadata
     /// Returns the number of elements that were stored.
-    async fn store_logs(&self, logs: &[(Indexed<T>, LogMeta)]) -> Result<u32>;
+    async fn store_logs(&self, logs: &[(Indexed<T>, LogMeta)]) -> HyperlaneCoreResult<u32>;
 }
 
 /// A sequence is a monotonically increasing number that is incremented every time a message ID is indexed.
@@ -28,10 +27,19 @@ pub trait Sequenced: 'static + Send + Sync {
 #[auto_impl(&, Box, Arc)]
 pub trait HyperlaneSequenceAwareIndexerStoreReader<T>: Send + Sync + Debug {
     /// Gets data by its sequence.
-    async fn retrieve_by_sequence(&self, sequence: u32) -> Result<Option<T>>;
+    async fn retrieve_by_sequence(&self, sequence: u32) -> HyperlaneCoreResult<Option<T>>;
+
+    /// Whether data of the sequence is stored. Stores indexing their
+    /// sequences can tell without retrieving the data.
+    async fn is_sequence_stored(&self, sequence: u32) -> HyperlaneCoreResult<bool> {
+        Ok(self.retrieve_by_sequence(sequence).await?.is_some())
+    }
 
     /// Gets the block number at which the log occurred.
-    async fn retrieve_log_block_number_by_sequence(&self, sequence: u32) -> Result<Option<u64>>;
+    async fn retrieve_log_block_number_by_sequence(
+        &self,
+        sequence: u32,
+    ) -> HyperlaneCoreResult<Option<u64>>;
 }
 
 /// Extension of HyperlaneLogStore trait for sequence-aware indexer stores.
@@ -52,8 +60,8 @@ impl<T, U> HyperlaneSequenceAwareIndexerStore<T> for U where
 #[auto_impl(&, Box, Arc)]
 pub trait HyperlaneWatermarkedLogStore<T>: HyperlaneLogStore<T> {
     /// Gets the block number high watermark
-    async fn retrieve_high_watermark(&self) -> Result<Option<u32>>;
+    async fn retrieve_high_watermark(&self) -> HyperlaneCoreResult<Option<u32>>;
 
     /// Stores the block number high watermark
-    async fn store_high_watermark(&self, block_number: u32) -> Result<()>;
+    async fn store_high_watermark(&self, block_number: u32) -> HyperlaneCoreResult<()>;
 }
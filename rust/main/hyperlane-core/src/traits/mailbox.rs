@@ -40,6 +40,20 @@ pub trait Mailbox: HyperlaneContract + Send + Sync + Debug {
         tx_gas_limit: Option<U256>,
     ) -> ChainResult<TxOutcome>;
 
+    /// Process a message like `process`, refusing with
+    /// `ChainCommunicationError::GasPriceCeilingExceeded` to pay more than
+    /// `max_gas_price` per unit of gas. Chains without a fee market ignore the
+    /// ceiling.
+    async fn process_with_gas_price_ceiling(
+        &self,
+        message: &HyperlaneMessage,
+        metadata: &[u8],
+        tx_gas_limit: Option<U256>,
+        _max_gas_price: Option<U256>,
+    ) -> ChainResult<TxOutcome> {
+        self.process(message, metadata, tx_gas_limit).await
+    }
+
     /// Process a message with a proof against the provided signed checkpoint
     async fn process_batch(
         &self,
@@ -47,9 +47,36 @@ pub struct TxOutcome {
     pub gas_used: U256,
     /// Price paid for the gas
     pub gas_price: FixedPointNumber,
+    /// How the transaction was broadcast
+    pub submission_path: TxSubmissionPath,
     // TODO: more? What can be abstracted across all chains?
 }
 
+/// How a transaction was broadcast to the chain
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TxSubmissionPath {
+    /// Through the public mempool
+    #[default]
+    Public,
+    /// Through a private submission endpoint, e.g. to avoid being front-run
+    Private,
+    /// Through the public mempool, after it wasn't included in time when
+    /// submitted privately
+    PublicFallback,
+}
+
+impl TxSubmissionPath {
+    /// Label of the path in metrics
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Public => "public",
+            Self::Private => "private",
+            Self::PublicFallback => "public_fallback",
+        }
+    }
+}
+
 #[cfg(feature = "ethers")]
 impl From<ethers_core::types::TransactionReceipt> for TxOutcome {
     fn from(t: ethers_core::types::TransactionReceipt) -> Self {
@@ -61,6 +88,7 @@ impl From<ethers_core::types::TransactionReceipt> for TxOutcome {
                 .effective_gas_price
                 .and_then(|price| U256::from(price).try_into().ok())
                 .unwrap_or(FixedPointNumber::zero()),
+            submission_path: TxSubmissionPath::Public,
         }
     }
 }
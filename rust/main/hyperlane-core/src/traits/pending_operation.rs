@@ -8,8 +8,9 @@ use std::{
 };
 
 use crate::{
-    ChainResult, Decode, Encode, FixedPointNumber, HyperlaneDomain, HyperlaneMessage,
-    HyperlaneProtocolError, Mailbox, TryBatchAs, TxOutcome, H256, U256,
+    ChainCommunicationError, ChainResult, Decode, Encode, FixedPointNumber, HyperlaneDomain,
+    HyperlaneMessage, HyperlaneProtocolError, Mailbox, MessageKey, TryBatchAs, TxOutcome, H256,
+    U256,
 };
 use async_trait::async_trait;
 use num::CheckedDiv;
@@ -55,6 +56,13 @@ pub trait PendingOperation: Send + Sync + Debug + TryBatchAs<HyperlaneMessage> {
     /// The domain this originates from.
     fn origin_domain_id(&self) -> u32;
 
+    /// The id of this operation qualified by the origin it was indexed from,
+    /// which identifies it even among operations of several origins sharing
+    /// an id.
+    fn key(&self) -> MessageKey {
+        MessageKey::new(self.origin_domain_id(), self.id())
+    }
+
     /// Get the operation status from the local db, if there is one
     fn retrieve_status_from_db(&self) -> Option<PendingOperationStatus>;
 
@@ -152,14 +160,42 @@ pub trait PendingOperation: Send + Sync + Debug + TryBatchAs<HyperlaneMessage> {
     /// retried immediately.
     fn reset_attempts(&mut self);
 
+    /// Record the lane of the queue this operation was last popped from, so
+    /// the attempt it's popped for can tell whether it jumped the queue.
+    fn set_lane(&mut self, _lane: QueueLane) {}
+
     /// Set the number of times this operation has been retried.
     #[cfg(any(test, feature = "test-utils"))]
     fn set_retries(&mut self, retries: u32);
 
+    /// Time elapsed since this operation was created, if tracked.
+    fn age(&self) -> Option<Duration> {
+        None
+    }
+
     /// If this operation points to a mailbox contract, return it
     fn try_get_mailbox(&self) -> Option<Arc<dyn Mailbox>> {
         None
     }
+
+    /// Stages of the pipeline this operation took longer than its latency
+    /// budget in, if it's timed against any
+    fn latency_budget_breaches(&self) -> Vec<String> {
+        vec![]
+    }
+}
+
+/// Lane of a queue an operation is popped from
+#[derive(Debug, Display, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum QueueLane {
+    /// Ordered with the rest of the queue
+    #[default]
+    Normal,
+    /// Drained before the rest of the queue, e.g. for operations an operator
+    /// asked to retry
+    Priority,
 }
 
 #[derive(Debug, Display, Clone, Serialize, Deserialize, PartialEq)]
@@ -250,6 +286,15 @@ pub enum ReprepareReason {
     #[strum(to_string = "Delivery transaction reverted or reorged")]
     /// Delivery transaction reverted or reorged
     RevertedOrReorged,
+    #[strum(to_string = "Origin merkle tree unavailable")]
+    /// The origin's merkle tree is rebuilding or diverged, and the ISM needs proofs
+    TreeUnavailable,
+    #[strum(to_string = "Circuit breaker of the destination is open")]
+    /// Submissions to the destination are paused after too many of them reverted
+    CircuitOpen,
+    #[strum(to_string = "Gas price exceeds the ceiling of the message's route")]
+    /// The destination's gas price is above what the message is worth paying for
+    GasPriceCeiling,
 }
 
 #[derive(Display, Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -295,7 +340,7 @@ pub fn gas_used_by_operation(
     let tx_gas_estimate = FixedPointNumber::try_from(tx_estimated_cost)?;
     let gas_used_by_operation = (gas_used_by_tx * operation_gas_estimate)
         .checked_div(&tx_gas_estimate)
-        .ok_or(eyre::eyre!("Division by zero"))?;
+        .ok_or_else(|| ChainCommunicationError::from_other_str("Division by zero"))?;
     gas_used_by_operation.try_into()
 }
 
@@ -320,7 +365,7 @@ impl PartialOrd for QueueOperation {
 
 impl PartialEq for QueueOperation {
     fn eq(&self, other: &Self) -> bool {
-        self.id().eq(&other.id())
+        self.key().eq(&other.key())
     }
 }
 
@@ -339,8 +384,10 @@ impl Ord for QueueOperation {
                     // Should execute in order of nonce for the same origin
                     self.priority().cmp(&other.priority())
                 } else {
-                    // There is no priority between these messages, so arbitrarily use the id
-                    self.id().cmp(&other.id())
+                    // There is no priority between these messages, so arbitrarily use the id,
+                    // then the origin to stay consistent with `eq`
+                    (self.id(), self.origin_domain_id())
+                        .cmp(&(other.id(), other.origin_domain_id()))
                 }
             }
         }
@@ -30,6 +30,22 @@ pub trait HyperlaneProvider: HyperlaneChain + Send + Sync + Debug {
 
     /// Fetch metrics related to this chain
     async fn get_chain_metrics(&self) -> ChainResult<Option<ChainInfo>>;
+
+    /// Fetch the chain id reported by the RPC, if the protocol has one
+    async fn get_chain_id(&self) -> ChainResult<Option<u64>> {
+        Ok(None)
+    }
+
+    /// Fetch the number of transactions sent from `address`, including the
+    /// ones still pending if `pending` is set, if the protocol has account
+    /// nonces
+    async fn get_transaction_count(
+        &self,
+        _address: String,
+        _pending: bool,
+    ) -> ChainResult<Option<u64>> {
+        Ok(None)
+    }
 }
 
 /// Errors when querying for provider information.
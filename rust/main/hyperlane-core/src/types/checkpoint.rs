@@ -1,10 +1,16 @@
-use std::fmt::Debug;
+use std::{
+    fmt::Debug,
+    io::{Read, Write},
+};
 
 use derive_more::Deref;
 use serde::{Deserialize, Serialize};
 use sha3::{digest::Update, Digest, Keccak256};
 
-use crate::{utils::domain_hash, Signable, Signature, SignedType, H256};
+use crate::{
+    utils::domain_hash, Decode, Encode, HyperlaneProtocolError, Signable, Signature, SignedType,
+    H256, U256,
+};
 
 /// An Hyperlane checkpoint
 #[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
@@ -54,7 +60,7 @@ impl Signable for CheckpointWithMessageId {
 pub type SignedCheckpointWithMessageId = SignedType<CheckpointWithMessageId>;
 
 /// A checkpoint and multiple signatures
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct MultisigSignedCheckpoint {
     /// The checkpoint
     pub checkpoint: CheckpointWithMessageId,
@@ -62,6 +68,72 @@ pub struct MultisigSignedCheckpoint {
     pub signatures: Vec<Signature>,
 }
 
+impl MultisigSignedCheckpoint {
+    /// Split into one signed checkpoint per signature
+    pub fn signed_checkpoints(&self) -> Vec<SignedCheckpointWithMessageId> {
+        self.signatures
+            .iter()
+            .map(|signature| SignedCheckpointWithMessageId {
+                value: self.checkpoint,
+                signature: *signature,
+            })
+            .collect()
+    }
+}
+
+impl Encode for MultisigSignedCheckpoint {
+    fn write_to<W>(&self, writer: &mut W) -> std::io::Result<usize>
+    where
+        W: Write,
+    {
+        let mut written = 0;
+        written += self.checkpoint.merkle_tree_hook_address.write_to(writer)?;
+        written += self.checkpoint.mailbox_domain.write_to(writer)?;
+        written += self.checkpoint.root.write_to(writer)?;
+        written += self.checkpoint.index.write_to(writer)?;
+        written += self.checkpoint.message_id.write_to(writer)?;
+        written += (self.signatures.len() as u32).write_to(writer)?;
+        for signature in &self.signatures {
+            written += signature.r.write_to(writer)?;
+            written += signature.s.write_to(writer)?;
+            written += signature.v.write_to(writer)?;
+        }
+        Ok(written)
+    }
+}
+
+impl Decode for MultisigSignedCheckpoint {
+    fn read_from<R>(reader: &mut R) -> Result<Self, HyperlaneProtocolError>
+    where
+        R: Read,
+        Self: Sized,
+    {
+        let checkpoint = CheckpointWithMessageId {
+            checkpoint: Checkpoint {
+                merkle_tree_hook_address: H256::read_from(reader)?,
+                mailbox_domain: u32::read_from(reader)?,
+                root: H256::read_from(reader)?,
+                index: u32::read_from(reader)?,
+            },
+            message_id: H256::read_from(reader)?,
+        };
+        let signature_count = u32::read_from(reader)?;
+        let signatures = (0..signature_count)
+            .map(|_| {
+                Ok(Signature {
+                    r: U256::read_from(reader)?,
+                    s: U256::read_from(reader)?,
+                    v: u64::read_from(reader)?,
+                })
+            })
+            .collect::<Result<_, HyperlaneProtocolError>>()?;
+        Ok(Self {
+            checkpoint,
+            signatures,
+        })
+    }
+}
+
 /// Error types for MultisigSignedCheckpoint
 #[derive(Debug, thiserror::Error)]
 pub enum MultisigSignedCheckpointError {
@@ -98,3 +170,38 @@ impl TryFrom<&mut Vec<SignedCheckpointWithMessageId>> for MultisigSignedCheckpoi
         })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_encoding_multisig_signed_checkpoint() {
+        let checkpoint = MultisigSignedCheckpoint {
+            checkpoint: CheckpointWithMessageId {
+                checkpoint: Checkpoint {
+                    merkle_tree_hook_address: H256::repeat_byte(1),
+                    mailbox_domain: 2,
+                    root: H256::repeat_byte(3),
+                    index: 4,
+                },
+                message_id: H256::repeat_byte(5),
+            },
+            signatures: vec![
+                Signature {
+                    r: U256::from(6),
+                    s: U256::from(7),
+                    v: 27,
+                },
+                Signature {
+                    r: U256::from(8),
+                    s: U256::from(9),
+                    v: 28,
+                },
+            ],
+        };
+        let encoded = checkpoint.to_vec();
+        let decoded = MultisigSignedCheckpoint::read_from(&mut &encoded[..]).unwrap();
+        assert_eq!(checkpoint, decoded);
+    }
+}
@@ -1,6 +1,6 @@
 use uint::unroll;
 
-use crate::{H256, H512};
+use crate::{ConversionError, H256, H512};
 
 /// Creates a big-endian hex representation of the address
 pub fn address_to_bytes(data: &H256) -> Vec<u8> {
@@ -13,9 +13,9 @@ pub fn address_to_bytes(data: &H256) -> Vec<u8> {
 }
 
 /// Creates a big-endian hex representation of the address
-pub fn bytes_to_address(data: Vec<u8>) -> eyre::Result<H256> {
+pub fn bytes_to_address(data: Vec<u8>) -> Result<H256, ConversionError> {
     if (data.len() != 20) && (data.len() != 32) {
-        return Err(eyre::eyre!("Invalid address length"));
+        return Err(ConversionError::InvalidAddressLength(data.len()));
     }
     if data.len() == 20 {
         let mut prefix = vec![0; 12];
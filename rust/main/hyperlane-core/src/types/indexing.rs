@@ -1,6 +1,8 @@
 use derive_new::new;
 
-use crate::{HyperlaneMessage, InterchainGasPayment, MerkleTreeInsertion, Sequenced, H256};
+use crate::{
+    ConversionError, HyperlaneMessage, InterchainGasPayment, MerkleTreeInsertion, Sequenced, H256,
+};
 
 /// Wrapper struct that adds indexing information to a type
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, new)]
@@ -20,12 +22,12 @@ pub struct SequenceIndexed<T> {
 }
 
 impl<T> TryFrom<Indexed<T>> for SequenceIndexed<T> {
-    type Error = eyre::Report;
+    type Error = ConversionError;
 
     fn try_from(value: Indexed<T>) -> Result<Self, Self::Error> {
         match value.sequence {
             Some(sequence) => Ok(SequenceIndexed::new(value.inner, sequence)),
-            None => eyre::bail!("Missing indexing sequence"),
+            None => Err(ConversionError::MissingSequence),
         }
     }
 }
@@ -34,7 +36,7 @@ impl<T> TryFrom<Indexed<T>> for SequenceIndexed<T> {
 /// so that if any `Option` is `None`, the conversion will fail
 pub fn indexed_to_sequence_indexed_array<T, U>(
     indexed_array: Vec<(Indexed<T>, U)>,
-) -> Result<Vec<(SequenceIndexed<T>, U)>, eyre::Report> {
+) -> Result<Vec<(SequenceIndexed<T>, U)>, ConversionError> {
     indexed_array
         .into_iter()
         .map(|(item, meta)| SequenceIndexed::<T>::try_from(item).map(|si| (si, meta)))
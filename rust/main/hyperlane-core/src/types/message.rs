@@ -1,8 +1,11 @@
+use derive_new::new;
 use serde::Serialize;
 use sha3::{digest::Update, Digest, Keccak256};
 use std::fmt::{Debug, Display, Formatter};
 
-use crate::utils::{fmt_address_for_domain, fmt_domain};
+use crate::hex::HexBytes;
+use crate::log_fields::LogBytes;
+use crate::utils::{fmt_address_for_domain_id, fmt_domain};
 use crate::{Decode, Encode, HyperlaneProtocolError, H256};
 
 const HYPERLANE_MESSAGE_PREFIX_LEN: usize = 77;
@@ -15,7 +18,7 @@ pub type RawHyperlaneMessage = Vec<u8>;
 
 impl From<&HyperlaneMessage> for RawHyperlaneMessage {
     fn from(m: &HyperlaneMessage) -> Self {
-        let mut message_vec = vec![];
+        let mut message_vec = Vec::with_capacity(m.encoded_len());
         m.write_to(&mut message_vec).expect("!write_to");
         message_vec
     }
@@ -37,6 +40,7 @@ pub struct HyperlaneMessage {
     /// 32  Address in destination convention
     pub recipient: H256,
     /// 0+  Message contents
+    #[serde(serialize_with = "HexBytes::serialize")]
     pub body: Vec<u8>,
 }
 
@@ -59,15 +63,15 @@ impl Debug for HyperlaneMessage {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "HyperlaneMessage {{ id: {:?}, version: {}, nonce: {}, origin: {}, sender: {}, destination: {}, recipient: {}, body: 0x{} }}",
+            "HyperlaneMessage {{ id: {:?}, version: {}, nonce: {}, origin: {}, sender: {}, destination: {}, recipient: {}, body: {} }}",
             self.id(),
             self.version,
             self.nonce,
             fmt_domain(self.origin),
-            fmt_address_for_domain(self.origin, self.sender),
+            fmt_address_for_domain_id(&self.sender, self.origin),
             fmt_domain(self.destination),
-            fmt_address_for_domain(self.destination, self.recipient),
-            hex::encode(&self.body)
+            fmt_address_for_domain_id(&self.recipient, self.destination),
+            LogBytes(&self.body)
         )
     }
 }
@@ -76,9 +80,11 @@ impl Display for HyperlaneMessage {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "HyperlaneMessage {{ id: {:?}, nonce: {}, .. }}",
+            "HyperlaneMessage {{ id: {:?}, nonce: {}, sender: {}, recipient: {}, .. }}",
             self.id(),
-            self.nonce
+            self.nonce,
+            fmt_address_for_domain_id(&self.sender, self.origin),
+            fmt_address_for_domain_id(&self.recipient, self.destination),
         )
     }
 }
@@ -122,7 +128,11 @@ impl Encode for HyperlaneMessage {
         writer.write_all(&self.destination.to_be_bytes())?;
         writer.write_all(self.recipient.as_ref())?;
         writer.write_all(&self.body)?;
-        Ok(HYPERLANE_MESSAGE_PREFIX_LEN + self.body.len())
+        Ok(self.encoded_len())
+    }
+
+    fn to_vec(&self) -> Vec<u8> {
+        RawHyperlaneMessage::from(self)
     }
 }
 
@@ -167,6 +177,107 @@ impl Decode for HyperlaneMessage {
 impl HyperlaneMessage {
     /// Convert the message to a message id
     pub fn id(&self) -> H256 {
-        H256::from_slice(Keccak256::new().chain(self.to_vec()).finalize().as_slice())
+        // hashes the encoding field by field rather than encoding it first,
+        // since ids are taken at every stage a message goes through
+        let hash = Keccak256::new()
+            .chain(self.version.to_be_bytes())
+            .chain(self.nonce.to_be_bytes())
+            .chain(self.origin.to_be_bytes())
+            .chain(self.sender.as_bytes())
+            .chain(self.destination.to_be_bytes())
+            .chain(self.recipient.as_bytes())
+            .chain(&self.body)
+            .finalize();
+        H256::from_slice(hash.as_slice())
+    }
+
+    /// The message contents, without copying them
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    /// Length of the canonical encoding of the message
+    pub fn encoded_len(&self) -> usize {
+        HYPERLANE_MESSAGE_PREFIX_LEN + self.body.len()
+    }
+}
+
+/// Identifies a message across origins. Message ids are unique in theory,
+/// but a misconfigured chain, e.g. a fork of another one, can dispatch the
+/// very messages of another origin. State shared by several origins is keyed
+/// by the id and the origin the message was indexed from, rather than the
+/// origin within the message, so that such messages are tracked
+/// independently.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, new)]
+pub struct MessageKey {
+    /// Origin domain id
+    pub origin: u32,
+    /// Message id
+    pub id: H256,
+}
+
+impl Display for MessageKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{:?}", fmt_domain(self.origin), self.id)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use crate::{log_fields::DEFAULT_LOG_FIELD_BUDGET, KnownHyperlaneDomain, H160};
+
+    use super::*;
+
+    #[test]
+    fn test_display_collapses_padded_evm_addresses() {
+        let sender = H160::from_str("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed").unwrap();
+        let recipient = H256::from_low_u64_be(1);
+        let message = HyperlaneMessage {
+            origin: KnownHyperlaneDomain::Ethereum as u32,
+            sender: sender.into(),
+            destination: KnownHyperlaneDomain::SolanaMainnet as u32,
+            recipient,
+            ..Default::default()
+        };
+        assert_eq!(
+            message.to_string(),
+            format!(
+                "HyperlaneMessage {{ id: {:?}, nonce: 0, sender: 0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed, recipient: {recipient:?}, .. }}",
+                message.id()
+            )
+        );
+    }
+
+    #[test]
+    fn test_debug_truncates_large_bodies() {
+        let message = HyperlaneMessage {
+            body: vec![0xcd; 4 * DEFAULT_LOG_FIELD_BUDGET],
+            ..Default::default()
+        };
+        let logged = format!("{message:?}");
+        assert!(logged.len() < 2 * DEFAULT_LOG_FIELD_BUDGET);
+        assert!(logged.contains(&LogBytes(&message.body).to_string()));
+    }
+
+    #[test]
+    fn test_id_is_the_hash_of_the_encoding() {
+        let message = HyperlaneMessage {
+            nonce: 7,
+            origin: KnownHyperlaneDomain::Ethereum as u32,
+            sender: H256::from_low_u64_be(1),
+            destination: KnownHyperlaneDomain::Arbitrum as u32,
+            recipient: H256::from_low_u64_be(2),
+            body: b"hello".to_vec(),
+            ..Default::default()
+        };
+        let encoded = message.to_vec();
+        assert_eq!(encoded.len(), message.encoded_len());
+        assert_eq!(HyperlaneMessage::from(&encoded), message);
+        assert_eq!(
+            message.id(),
+            H256::from_slice(Keccak256::digest(&encoded).as_slice())
+        );
     }
 }
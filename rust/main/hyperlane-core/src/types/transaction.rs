@@ -1,10 +1,13 @@
-use std::sync::Arc;
+use std::{
+    fmt::{Debug, Formatter},
+    sync::Arc,
+};
 
-use crate::{ChainResult, Mailbox, U256};
+use crate::{log_fields::LogBytes, ChainResult, Mailbox, U256};
 use derive_new::new;
 
 /// State for the next submission attempt generated by a prepare call.
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct MessageSubmissionData {
     /// Transaction metadata - currently only applies to Messages, so this field can be made optional or generic if other
     /// operations are submitted in the future.
@@ -13,6 +16,15 @@ pub struct MessageSubmissionData {
     pub gas_limit: U256,
 }
 
+impl Debug for MessageSubmissionData {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MessageSubmissionData")
+            .field("metadata", &LogBytes(&self.metadata))
+            .field("gas_limit", &self.gas_limit)
+            .finish()
+    }
+}
+
 /// A an item to be batched for submission to the chain.
 #[derive(new, Clone, Debug)]
 pub struct BatchItem<T> {
@@ -33,3 +45,22 @@ pub trait TryBatchAs<T> {
         Err(crate::ChainCommunicationError::BatchingFailed)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::log_fields::DEFAULT_LOG_FIELD_BUDGET;
+
+    use super::*;
+
+    #[test]
+    fn test_debug_truncates_metadata() {
+        let data = MessageSubmissionData {
+            metadata: vec![0xef; 4 * DEFAULT_LOG_FIELD_BUDGET],
+            gas_limit: U256::from(100_000),
+        };
+        let logged = format!("{data:?}");
+        assert!(logged.len() < 2 * DEFAULT_LOG_FIELD_BUDGET);
+        assert!(logged.contains(&LogBytes(&data.metadata).to_string()));
+        assert!(logged.contains("gas_limit: 100000"));
+    }
+}
@@ -1,24 +1,24 @@
-use eyre::Result;
 use sha3::{digest::Update, Digest, Keccak256};
 use std::str::FromStr;
 
 #[cfg(feature = "float")]
 use std::time::Duration;
 
-use crate::{KnownHyperlaneDomain, H160, H256, U256};
+use crate::{ConversionError, HyperlaneDomain, KnownHyperlaneDomain, H160, H256, U256};
 
-/// Converts a hex or base58 string to an H256.
-pub fn hex_or_base58_to_h256(string: &str) -> Result<H256> {
-    let h256 = if string.starts_with("0x") {
-        match string.len() {
-            66 => H256::from_str(string)?,
-            42 => H160::from_str(string)?.into(),
-            _ => eyre::bail!("Invalid hex string"),
-        }
+/// Converts a hex or base58 string to an H256. Hex may omit the `0x` prefix
+/// and be either a 20 byte address, which is left-padded, or 32 bytes.
+pub fn hex_or_base58_to_h256(string: &str) -> Result<H256, ConversionError> {
+    // Base58 strings of these lengths never decode to 32 bytes, so unprefixed
+    // hex of a valid length can't be mistaken for one
+    let is_hex = string.starts_with("0x")
+        || (matches!(string.len(), 40 | 64) && string.chars().all(|c| c.is_ascii_hexdigit()));
+    let h256 = if is_hex {
+        crate::hex::decode_20_or_32(string)?
     } else {
         let bytes = bs58::decode(string).into_vec()?;
         if bytes.len() != 32 {
-            eyre::bail!("Invalid length of base58 string")
+            return Err(ConversionError::InvalidBase58Length(bytes.len()));
         }
         H256::from_slice(bytes.as_slice())
     };
@@ -50,11 +50,46 @@ pub fn announcement_domain_hash(address: H256, domain: impl Into<u32>) -> H256 {
     )
 }
 
-/// Pretty print an address based on the domain it is for.
-pub fn fmt_address_for_domain(domain: u32, addr: H256) -> String {
-    KnownHyperlaneDomain::try_from(domain)
-        .map(|d| d.domain_protocol().fmt_address(addr))
-        .unwrap_or_else(|_| format!("{addr:?}"))
+/// Pretty print an address based on the domain it is for. Zero-padded EVM
+/// addresses are collapsed to their checksummed 20-byte form; anything else,
+/// including addresses on unknown domains, is printed as the full 32 bytes.
+pub fn fmt_address_for_domain(h: &H256, domain: Option<&HyperlaneDomain>) -> String {
+    domain
+        .map(|d| d.domain_protocol().fmt_address(*h))
+        .unwrap_or_else(|| format!("{h:?}"))
+}
+
+/// Pretty print an address based on the id of the domain it is for.
+pub fn fmt_address_for_domain_id(h: &H256, domain: u32) -> String {
+    let domain = KnownHyperlaneDomain::try_from(domain)
+        .ok()
+        .map(HyperlaneDomain::Known);
+    fmt_address_for_domain(h, domain.as_ref())
+}
+
+/// Format a 20-byte address with an EIP-55 mixed-case checksum
+pub fn to_checksum_address(address: &H160) -> String {
+    let hex_address = hex::encode(address.as_bytes());
+    let hash = Keccak256::new().chain(hex_address.as_bytes()).finalize();
+    let checksummed: String = hex_address
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let nibble = (hash[i / 2] >> (if i % 2 == 0 { 4 } else { 0 })) & 0xf;
+            if nibble >= 8 {
+                c.to_ascii_uppercase()
+            } else {
+                c
+            }
+        })
+        .collect();
+    format!("0x{checksummed}")
+}
+
+/// Whether the top 12 bytes of `h` are zero, i.e. it is a padded 20-byte
+/// address
+pub fn is_padded_h160(h: &H256) -> bool {
+    h.as_bytes()[..12].iter().all(|b| *b == 0)
 }
 
 /// Pretty print a byte slice, including a hex prefix
@@ -87,6 +122,66 @@ pub fn fmt_domain(domain: u32) -> String {
     }
 }
 
+/// The `candidates` closest to a misspelled `name` by edit distance, ignoring
+/// ASCII case, closest first. Only the ones a typo away are returned, at
+/// most three.
+pub fn closest_names<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Vec<&'a str> {
+    let name = name.to_ascii_lowercase();
+    let max_distance = (name.chars().count() / 3).max(1);
+    let mut closest: Vec<(usize, &str)> = candidates
+        .into_iter()
+        .map(|candidate| {
+            (
+                edit_distance(&name, &candidate.to_ascii_lowercase()),
+                candidate,
+            )
+        })
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect();
+    closest.sort();
+    closest.dedup();
+    closest
+        .into_iter()
+        .take(3)
+        .map(|(_, candidate)| candidate)
+        .collect()
+}
+
+/// A hint listing the names a misspelled one may have been meant as, e.g.
+/// `; did you mean `arbitrum`?`, or nothing if there are none
+pub fn fmt_did_you_mean(names: &[&str]) -> String {
+    match names {
+        [] => String::new(),
+        [name] => format!("; did you mean `{name}`?"),
+        names => format!(
+            "; did you mean one of {}?",
+            names
+                .iter()
+                .map(|name| format!("`{name}`"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+/// The Levenshtein distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
 /// Formats the duration in the most appropriate time units.
 #[cfg(feature = "float")]
 pub fn fmt_duration(dur: Duration) -> String {
@@ -266,3 +361,105 @@ macro_rules! unwrap_or_none_result {
 }
 
 pub(crate) use many_to_one;
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::hex::HexError;
+
+    const ADDRESS: &str = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+
+    fn domain(domain: KnownHyperlaneDomain) -> Option<HyperlaneDomain> {
+        Some(HyperlaneDomain::Known(domain))
+    }
+
+    #[test]
+    fn test_hex_or_base58_to_h256() {
+        let padded: H256 = H160::from_str(ADDRESS).unwrap().into();
+        let full = format!("{padded:?}");
+        for input in [ADDRESS, &ADDRESS[2..], &full, &full[2..]] {
+            assert_eq!(hex_or_base58_to_h256(input).unwrap(), padded, "{input}");
+        }
+        let base58 = bs58::encode(padded.as_bytes()).into_string();
+        assert_eq!(hex_or_base58_to_h256(&base58).unwrap(), padded);
+
+        assert!(matches!(
+            hex_or_base58_to_h256("0x1234"),
+            Err(ConversionError::InvalidHex(HexError::InvalidLength {
+                actual: 2,
+                ..
+            }))
+        ));
+        assert!(matches!(
+            hex_or_base58_to_h256(&format!("{}zz", &ADDRESS[..40])),
+            Err(ConversionError::InvalidHex(HexError::InvalidCharacter {
+                character: 'z',
+                ..
+            }))
+        ));
+    }
+
+    #[test]
+    fn test_fmt_padded_evm_address() {
+        let h: H256 = H160::from_str(ADDRESS).unwrap().into();
+        let ethereum = domain(KnownHyperlaneDomain::Ethereum);
+        assert_eq!(fmt_address_for_domain(&h, ethereum.as_ref()), ADDRESS);
+        assert_eq!(
+            fmt_address_for_domain_id(&h, KnownHyperlaneDomain::Ethereum as u32),
+            ADDRESS
+        );
+    }
+
+    #[test]
+    fn test_fmt_unpadded_evm_address_is_lossless() {
+        let h =
+            H256::from_str("0x586d41b02fb35df0f84ecb2b73e076b40c929ee3e1ceeada9a078aa7b46d3b08")
+                .unwrap();
+        let ethereum = domain(KnownHyperlaneDomain::Ethereum);
+        assert_eq!(
+            fmt_address_for_domain(&h, ethereum.as_ref()),
+            format!("{h:?}")
+        );
+
+        // A single non-zero byte in the padding keeps the full width
+        let mut bytes = H256::from(H160::from_str(ADDRESS).unwrap()).to_fixed_bytes();
+        bytes[0] = 1;
+        let h = H256::from(bytes);
+        assert_eq!(
+            fmt_address_for_domain(&h, ethereum.as_ref()),
+            format!("{h:?}")
+        );
+    }
+
+    #[test]
+    fn test_fmt_address_on_non_evm_or_unknown_domain() {
+        let h: H256 = H160::from_str(ADDRESS).unwrap().into();
+        let full = format!("{h:?}");
+        let solana = domain(KnownHyperlaneDomain::SolanaMainnet);
+        assert_eq!(fmt_address_for_domain(&h, solana.as_ref()), full);
+        assert_eq!(fmt_address_for_domain(&h, None), full);
+        assert_eq!(fmt_address_for_domain_id(&h, 0xdead_beef), full);
+    }
+
+    #[test]
+    fn test_closest_names_are_a_typo_away() {
+        let names = ["arbitrum", "arbitrumnova", "avalanche", "ethereum", "base"];
+        assert_eq!(closest_names("arbitrun", names), vec!["arbitrum"]);
+        assert_eq!(closest_names("ETHEREM", names), vec!["ethereum"]);
+        assert_eq!(closest_names("bas", names), vec!["base"]);
+        assert!(closest_names("solana", names).is_empty());
+
+        assert_eq!(fmt_did_you_mean(&[]), "");
+        assert_eq!(
+            fmt_did_you_mean(&["arbitrum"]),
+            "; did you mean `arbitrum`?"
+        );
+        assert_eq!(
+            fmt_did_you_mean(&["base", "blast"]),
+            "; did you mean one of `base`, `blast`?"
+        );
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+}
@@ -0,0 +1,107 @@
+//! Allocations made by messages as they go through the relaying stages,
+//! counted through the accessors the relayer uses and checked against a
+//! recorded baseline, so that an accessor starting to copy shows up here.
+//!
+//! Allocations are counted by a global allocator, per thread so that tests
+//! running alongside don't skew the counts.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+    hint::black_box,
+};
+
+use hyperlane_core::{HyperlaneMessage, MessageKey, RawHyperlaneMessage, H256};
+
+/// Number of synthetic messages processed
+const MESSAGES: usize = 1_000;
+
+/// Allocations per message of the relaying stages: the raw encoding the
+/// message is submitted with. Ids, keys and bodies are read in place.
+const BASELINE_ALLOCATIONS_PER_MESSAGE: usize = 1;
+
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+fn count_allocation() {
+    // the counter may already be gone while the thread is torn down
+    let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count_allocation();
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count_allocation();
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Allocations made on this thread while running `f`
+fn allocations<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let before = ALLOCATIONS.with(Cell::get);
+    let result = black_box(f());
+    (result, ALLOCATIONS.with(Cell::get) - before)
+}
+
+fn synthetic_messages() -> Vec<HyperlaneMessage> {
+    (0..MESSAGES as u32)
+        .map(|nonce| HyperlaneMessage {
+            nonce,
+            origin: 1,
+            sender: H256::from_low_u64_be(nonce as u64),
+            destination: 2,
+            recipient: H256::from_low_u64_be(u64::MAX - nonce as u64),
+            body: vec![nonce as u8; 64 + (nonce as usize % 256)],
+            ..Default::default()
+        })
+        .collect()
+}
+
+/// What the relayer reads of a message through its stages: its id as it's
+/// indexed and its status persisted, its key as it's queued, its id and body
+/// as its metadata is built, and its raw encoding as it's submitted
+fn process(message: &HyperlaneMessage) -> (H256, MessageKey, usize) {
+    let indexed = black_box(message.id());
+    let key = black_box(MessageKey::new(message.origin, message.id()));
+    let prepared = black_box(message.id());
+    let body = black_box(message.body());
+    let raw = RawHyperlaneMessage::from(message);
+    assert_eq!(indexed, prepared);
+    (indexed, key, raw.len() + body.len())
+}
+
+#[test]
+fn message_ids_dont_allocate() {
+    let message = &synthetic_messages()[0];
+    let (_, allocated) = allocations(|| message.id());
+    assert_eq!(allocated, 0);
+}
+
+#[test]
+fn processing_messages_stays_within_the_baseline() {
+    let messages = synthetic_messages();
+
+    let (processed, allocated) = allocations(|| messages.iter().map(process).collect::<Vec<_>>());
+
+    assert_eq!(processed.len(), MESSAGES);
+    // plus one for the results
+    assert_eq!(
+        allocated,
+        MESSAGES * BASELINE_ALLOCATIONS_PER_MESSAGE + 1,
+        "{allocated} allocations processing {MESSAGES} messages"
+    );
+}
@@ -44,6 +44,14 @@ mock! {
             tx_gas_limit: Option<U256>,
         ) -> ChainResult<TxOutcome> {}
 
+        pub fn process_with_gas_price_ceiling(
+            &self,
+            message: &HyperlaneMessage,
+            metadata: &[u8],
+            tx_gas_limit: Option<U256>,
+            max_gas_price: Option<U256>,
+        ) -> ChainResult<TxOutcome> {}
+
         pub fn process_estimate_costs(
             &self,
             message: &HyperlaneMessage,
@@ -91,6 +99,16 @@ impl Mailbox for MockMailboxContract {
         self.process(message, metadata, tx_gas_limit)
     }
 
+    async fn process_with_gas_price_ceiling(
+        &self,
+        message: &HyperlaneMessage,
+        metadata: &[u8],
+        tx_gas_limit: Option<U256>,
+        max_gas_price: Option<U256>,
+    ) -> ChainResult<TxOutcome> {
+        self.process_with_gas_price_ceiling(message, metadata, tx_gas_limit, max_gas_price)
+    }
+
     async fn process_batch(
         &self,
         messages: &[BatchItem<HyperlaneMessage>],
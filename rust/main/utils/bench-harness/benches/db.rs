@@ -0,0 +1,20 @@
+use bench_harness::{db_entries, write_batch, TempDb, DB_BATCH_SIZE};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+fn batch_writes(c: &mut Criterion) {
+    let db = TempDb::new();
+    let typed = db.typed();
+    let entries = db_entries(DB_BATCH_SIZE);
+
+    let mut group = c.benchmark_group("typed_db_batch_write");
+    group.throughput(Throughput::Elements(DB_BATCH_SIZE as u64));
+    group.bench_with_input(
+        BenchmarkId::from_parameter(DB_BATCH_SIZE),
+        &entries,
+        |b, entries| b.iter(|| write_batch(&typed, entries)),
+    );
+    group.finish();
+}
+
+criterion_group!(benches, batch_writes);
+criterion_main!(benches);
@@ -0,0 +1,37 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use hyperlane_core::{HyperlaneDomain, KnownHyperlaneDomain};
+use strum::IntoEnumIterator;
+
+fn domain_names(c: &mut Criterion) {
+    let ids: Vec<u32> = KnownHyperlaneDomain::iter().map(|d| d as u32).collect();
+    let domains: Vec<HyperlaneDomain> = KnownHyperlaneDomain::iter()
+        .map(HyperlaneDomain::Known)
+        .collect();
+
+    let mut group = c.benchmark_group("domain_names");
+    group.bench_function("name", |b| {
+        b.iter(|| {
+            for domain in &domains {
+                black_box(domain.name());
+            }
+        })
+    });
+    group.bench_function("to_string", |b| {
+        b.iter(|| {
+            for domain in &domains {
+                black_box(domain.to_string());
+            }
+        })
+    });
+    group.bench_function("name_from_domain_id", |b| {
+        b.iter(|| {
+            for id in &ids {
+                black_box(KnownHyperlaneDomain::name_from_domain_id(black_box(*id)));
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, domain_names);
+criterion_main!(benches);
@@ -0,0 +1,77 @@
+use bench_harness::{
+    leaves, merkle_tree_builder, proof_indices, INGESTED_LEAVES, PROOF_BATCH_SIZE,
+    PROVEN_TREE_SIZES, ROOTS_ITER_LEAVES,
+};
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use futures::executor::block_on;
+use hyperlane_core::accumulator::incremental::IncrementalMerkle;
+use relayer::MerkleTreeBuilder;
+
+fn ingestion(c: &mut Criterion) {
+    let leaves = leaves(INGESTED_LEAVES);
+    let mut group = c.benchmark_group("ingest_leaves");
+    group
+        .sample_size(10)
+        .throughput(Throughput::Elements(INGESTED_LEAVES as u64));
+    group.bench_function("unbatched", |b| {
+        b.iter_batched(
+            MerkleTreeBuilder::new,
+            |mut builder| {
+                for leaf in &leaves {
+                    block_on(builder.ingest_message_id(*leaf)).unwrap();
+                }
+                builder
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("batched", |b| {
+        b.iter_batched(
+            MerkleTreeBuilder::new,
+            |mut builder| {
+                block_on(builder.ingest_message_ids(&leaves)).unwrap();
+                builder
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+fn proofs(c: &mut Criterion) {
+    let mut group = c.benchmark_group("prove");
+    for size in PROVEN_TREE_SIZES {
+        let builder = merkle_tree_builder(size);
+        let root_index = size as u32 - 1;
+        let indices = proof_indices(size, PROOF_BATCH_SIZE);
+
+        group.throughput(Throughput::Elements(1));
+        group.bench_with_input(BenchmarkId::new("single", size), &indices[0], |b, index| {
+            b.iter(|| builder.get_proof(*index, root_index).unwrap())
+        });
+        group.throughput(Throughput::Elements(PROOF_BATCH_SIZE as u64));
+        group.bench_with_input(BenchmarkId::new("batched", size), &indices, |b, indices| {
+            b.iter(|| builder.get_proofs(indices, root_index).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn roots(c: &mut Criterion) {
+    let leaves = leaves(ROOTS_ITER_LEAVES);
+    let mut group = c.benchmark_group("roots_iter");
+    group
+        .sample_size(10)
+        .throughput(Throughput::Elements(ROOTS_ITER_LEAVES as u64));
+    group.bench_function(BenchmarkId::from_parameter(ROOTS_ITER_LEAVES), |b| {
+        b.iter_batched(
+            IncrementalMerkle::default,
+            |mut tree| tree.roots_iter(leaves.iter().copied()).last(),
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, ingestion, proofs, roots);
+criterion_main!(benches);
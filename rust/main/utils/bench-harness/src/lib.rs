@@ -0,0 +1,160 @@
+//! Fixtures for benchmarking the agents' performance-sensitive paths without
+//! running an agent: merkle tree builders with leaves ingested and throwaway
+//! databases. Inputs are generated from a fixed seed, so that every run
+//! measures the same work.
+//!
+//! The benches using them are run with `cargo bench -p bench-harness`.
+
+#![forbid(unsafe_code)]
+#![warn(missing_docs)]
+
+use futures::executor::block_on;
+use hyperlane_base::db::{test_utils::setup_db, TypedDB, DB};
+use hyperlane_core::{HyperlaneDomain, H256};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use relayer::MerkleTreeBuilder;
+use tempfile::TempDir;
+
+/// Seed all inputs are generated from
+pub const SEED: u64 = 0x6879_7065_726c_616e;
+
+/// Number of leaves ingested by the ingestion benches
+pub const INGESTED_LEAVES: usize = 100_000;
+
+/// Sizes of the trees proofs are generated in
+pub const PROVEN_TREE_SIZES: [usize; 3] = [1_000, 100_000, 1_000_000];
+
+/// Number of leaves proven at once by the batched proof benches
+pub const PROOF_BATCH_SIZE: usize = 100;
+
+/// Number of leaves whose roots the root iteration bench computes
+pub const ROOTS_ITER_LEAVES: usize = 100_000;
+
+/// Number of entries written in a single batch by the database benches
+pub const DB_BATCH_SIZE: usize = 10_000;
+
+/// Prefix the database benches write their entries under
+pub const DB_BENCH_PREFIX: &str = "bench_entry_";
+
+fn rng() -> StdRng {
+    StdRng::seed_from_u64(SEED)
+}
+
+/// `count` pseudo-random leaves, the same on every call
+pub fn leaves(count: usize) -> Vec<H256> {
+    let mut rng = rng();
+    (0..count).map(|_| H256(rng.gen())).collect()
+}
+
+/// A merkle tree builder with [`leaves`]`(count)` ingested
+pub fn merkle_tree_builder(count: usize) -> MerkleTreeBuilder {
+    let mut builder = MerkleTreeBuilder::new();
+    block_on(builder.ingest_message_ids(&leaves(count))).expect("Failed to ingest leaves");
+    builder
+}
+
+/// `batch_size` pseudo-random indices of leaves in a tree of `count` leaves,
+/// the same on every call
+pub fn proof_indices(count: usize, batch_size: usize) -> Vec<u32> {
+    let mut rng = rng();
+    (0..batch_size)
+        .map(|_| rng.gen_range(0..count) as u32)
+        .collect()
+}
+
+/// `count` pseudo-random database entries, the same on every call
+pub fn db_entries(count: usize) -> Vec<(u32, H256)> {
+    leaves(count)
+        .into_iter()
+        .enumerate()
+        .map(|(i, value)| (i as u32, value))
+        .collect()
+}
+
+/// A database in a temporary directory, removed on drop
+pub struct TempDb {
+    db: DB,
+    // Dropped after the database
+    _dir: TempDir,
+}
+
+impl TempDb {
+    /// An empty database
+    pub fn new() -> Self {
+        let dir = TempDir::new().expect("Failed to create temporary directory");
+        let db = setup_db(dir.path().to_str().unwrap().into());
+        Self { db, _dir: dir }
+    }
+
+    /// The database scoped to a test domain
+    pub fn typed(&self) -> TypedDB {
+        TypedDB::new(&HyperlaneDomain::new_test_domain("bench"), self.db.clone())
+    }
+}
+
+impl Default for TempDb {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Write `entries` to `db` in a single batch
+pub fn write_batch(db: &TypedDB, entries: &[(u32, H256)]) {
+    let mut batch = db.batch();
+    for (key, value) in entries {
+        batch.store_keyed_encodable(DB_BENCH_PREFIX, key, value);
+    }
+    db.write(batch).expect("Failed to write batch");
+}
+
+#[cfg(test)]
+mod test {
+    use hyperlane_core::accumulator::incremental::IncrementalMerkle;
+
+    use super::*;
+
+    // The setup of every bench, at sizes small enough for the test suite
+
+    #[test]
+    fn test_inputs_are_deterministic() {
+        assert_eq!(leaves(10), leaves(10));
+        assert_eq!(proof_indices(1_000, 10), proof_indices(1_000, 10));
+        assert!(proof_indices(5, 100).iter().all(|index| *index < 5));
+    }
+
+    #[test]
+    fn test_merkle_bench_setup() {
+        let leaves = leaves(64);
+        let mut builder = MerkleTreeBuilder::new();
+        for leaf in &leaves[..32] {
+            block_on(builder.ingest_message_id(*leaf)).unwrap();
+        }
+        block_on(builder.ingest_message_ids(&leaves[32..])).unwrap();
+        assert_eq!(builder.count(), 64);
+
+        let built = merkle_tree_builder(64);
+        let indices = proof_indices(64, 8);
+        let proofs = built.get_proofs(&indices, 63).unwrap();
+        assert_eq!(proofs.len(), 8);
+        assert_eq!(built.get_proof(indices[0], 63).unwrap(), proofs[0]);
+
+        let mut tree = IncrementalMerkle::default();
+        let last = tree.roots_iter(leaves).last().unwrap();
+        assert_eq!(last, built.snapshot().root());
+    }
+
+    #[test]
+    fn test_db_bench_setup() {
+        let db = TempDb::new();
+        let typed = db.typed();
+        let entries = db_entries(16);
+        write_batch(&typed, &entries);
+        let (key, value) = entries[3];
+        assert_eq!(
+            typed
+                .retrieve_keyed_decodable::<_, H256>(DB_BENCH_PREFIX, &key)
+                .unwrap(),
+            Some(value)
+        );
+    }
+}
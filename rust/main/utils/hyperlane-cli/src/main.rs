@@ -0,0 +1,40 @@
+//! Operator tooling for Hyperlane agent deployments.
+//!
+//! Commands:
+//! - `validate-config <dir>`: check that the agent config files in `dir`,
+//!   taken as one environment, are consistent. Exits non-zero if any issue is
+//!   found, so it can gate deploys.
+
+use std::{path::Path, process::ExitCode};
+
+use eyre::{bail, Result};
+use hyperlane_base::settings::validate_config_dir;
+
+const USAGE: &str = "Usage: hyperlane-cli validate-config <dir>";
+
+fn main() -> Result<ExitCode> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        ["validate-config", dir] => validate_config(Path::new(dir)),
+        _ => bail!(USAGE),
+    }
+}
+
+fn validate_config(dir: &Path) -> Result<ExitCode> {
+    let issues = validate_config_dir(dir)?;
+    for issue in &issues {
+        println!("{issue}");
+    }
+    if issues.is_empty() {
+        println!("No issues found in {}", dir.display());
+        Ok(ExitCode::SUCCESS)
+    } else {
+        println!("{} issues found in {}", issues.len(), dir.display());
+        Ok(ExitCode::FAILURE)
+    }
+}
@@ -0,0 +1,99 @@
+// SPDX-License-Identifier: Apache-2.0
+pragma solidity ^0.8.13;
+
+import "forge-std/Test.sol";
+
+import {IMailbox} from "../contracts/interfaces/IMailbox.sol";
+
+// must have keys ordered alphabetically
+struct ProcessFixture {
+    bytes body;
+    bytes callData; // `calldata` in the json, which is a reserved word
+    uint256 destination;
+    bytes32 id;
+    bytes metadata;
+    uint256 nonce;
+    uint256 origin;
+    bytes32 recipient;
+    bytes32 sender;
+    uint256 version;
+}
+
+// must have keys ordered alphabetically
+struct ErrorFixture {
+    bytes data;
+    string reason;
+}
+
+// must have keys ordered alphabetically
+struct PanicFixture {
+    uint256 code;
+    bytes data;
+}
+
+uint8 constant PROCESS_FIXTURE_COUNT = 2;
+uint8 constant ERROR_FIXTURE_COUNT = 3;
+uint8 constant PANIC_FIXTURE_COUNT = 3;
+
+/// @notice Checks the ABI encodings the agents are tested against
+contract AbiVectorsTest is Test {
+    using stdJson for string;
+
+    string json = vm.readFile("../vectors/abi.json");
+
+    function fixtureKey(
+        string memory kind,
+        uint256 index
+    ) internal pure returns (string memory) {
+        return string.concat(".", kind, "[", vm.toString(index), "]");
+    }
+
+    function testProcessCalldata() public {
+        for (uint256 i = 0; i < PROCESS_FIXTURE_COUNT; i++) {
+            ProcessFixture memory fixture = abi.decode(
+                json.parseRaw(fixtureKey("process", i)),
+                (ProcessFixture)
+            );
+            bytes memory message = abi.encodePacked(
+                uint8(fixture.version),
+                uint32(fixture.nonce),
+                uint32(fixture.origin),
+                fixture.sender,
+                uint32(fixture.destination),
+                fixture.recipient,
+                fixture.body
+            );
+            assertEq(keccak256(message), fixture.id);
+            assertEq(
+                abi.encodeCall(IMailbox.process, (fixture.metadata, message)),
+                fixture.callData
+            );
+        }
+    }
+
+    function testErrorRevertData() public {
+        for (uint256 i = 0; i < ERROR_FIXTURE_COUNT; i++) {
+            ErrorFixture memory fixture = abi.decode(
+                json.parseRaw(fixtureKey("errors", i)),
+                (ErrorFixture)
+            );
+            assertEq(
+                abi.encodeWithSignature("Error(string)", fixture.reason),
+                fixture.data
+            );
+        }
+    }
+
+    function testPanicRevertData() public {
+        for (uint256 i = 0; i < PANIC_FIXTURE_COUNT; i++) {
+            PanicFixture memory fixture = abi.decode(
+                json.parseRaw(fixtureKey("panics", i)),
+                (PanicFixture)
+            );
+            assertEq(
+                abi.encodeWithSignature("Panic(uint256)", fixture.code),
+                fixture.data
+            );
+        }
+    }
+}
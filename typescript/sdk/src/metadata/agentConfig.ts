@@ -10,7 +10,14 @@ import { MultiProvider } from '../providers/MultiProvider.js';
 import { ChainMap, ChainName } from '../types.js';
 
 import { ChainMetadataSchemaObject } from './chainMetadataTypes.js';
-import { ZHash, ZNzUint, ZUWei, ZUint } from './customZodTypes.js';
+import {
+  ZHash,
+  ZHumanDuration,
+  ZHumanSize,
+  ZNzUint,
+  ZUWei,
+  ZUint,
+} from './customZodTypes.js';
 import {
   HyperlaneDeploymentArtifacts,
   HyperlaneDeploymentArtifactsSchema,
@@ -153,6 +160,54 @@ export const AgentChainMetadataSchema = ChainMetadataSchemaObject.merge(
           ),
       })
       .optional(),
+    additionalInterchainGasPaymasters: z
+      .array(
+        z.object({
+          address: ZHash.describe('The address of the IGP contract.'),
+          from: ZUint.describe(
+            'The starting block from which to index its gas payments.',
+          ),
+        }),
+      )
+      .optional()
+      .describe(
+        'IGP contracts whose gas payments are indexed in addition to interchainGasPaymaster, e.g. legacy deployments.',
+      ),
+    privateSubmission: z
+      .object({
+        url: z
+          .string()
+          .url()
+          .describe('The private RPC or bundle relay endpoint.'),
+        mode: z
+          .enum(['rpc', 'bundle'])
+          .optional()
+          .describe(
+            'Whether the endpoint takes raw transactions (rpc) or eth_sendBundle bundles (bundle). Defaults to rpc.',
+          ),
+        fallbackAfterBlocks: ZNzUint.optional().describe(
+          'The number of blocks to wait for private inclusion before broadcasting the transaction publicly.',
+        ),
+      })
+      .optional()
+      .describe(
+        'Submit process transactions through a private endpoint, falling back to the public mempool. EVM only.',
+      ),
+    getLogsCaps: z
+      .record(ZUint)
+      .optional()
+      .describe(
+        'Number of logs the RPC providers cap eth_getLogs responses at, by host, on top of the built-in defaults. 0 means uncapped. EVM only.',
+      ),
+    merkleTreeDepth: z
+      .number()
+      .int()
+      .min(1)
+      .max(32)
+      .optional()
+      .describe(
+        'Depth of the merkle tree of the chain, if its contracts use a shallower tree than the standard 32.',
+      ),
   })
   .merge(AgentCosmosChainMetadataSchema.partial())
   .refine((metadata) => {
@@ -206,12 +261,61 @@ export const AgentChainMetadataSchema = ChainMetadataSchemaObject.merge(
 
 export type AgentChainMetadata = z.infer<typeof AgentChainMetadataSchema>;
 
+export enum AlertSinkType {
+  Webhook = 'webhook',
+  Slack = 'slack',
+  PagerDuty = 'pagerDuty',
+}
+
+const AlertSinkSchema = z.discriminatedUnion('type', [
+  z.object({
+    type: z.literal(AlertSinkType.Webhook),
+    url: z.string().url(),
+    bodyTemplate: z
+      .string()
+      .optional()
+      .describe(
+        'JSON body with {{kind}}, {{dedup_key}}, {{source}}, {{summary}} and {{details}} placeholders. The alert itself is posted if unset.',
+      ),
+  }),
+  z.object({
+    type: z.literal(AlertSinkType.Slack),
+    url: z.string().url().describe('Slack-compatible incoming webhook url.'),
+  }),
+  z.object({
+    type: z.literal(AlertSinkType.PagerDuty),
+    routingKey: z
+      .string()
+      .min(1)
+      .describe('Integration key of the PagerDuty service to alert.'),
+    url: z
+      .string()
+      .url()
+      .optional()
+      .describe('PagerDuty Events API v2 endpoint, the public one if unset.'),
+  }),
+]);
+
 export const AgentConfigSchema = z.object({
+  environment: z
+    .string()
+    .optional()
+    .describe(
+      'The environment the config is for, e.g. mainnet3. All config files merged into one agent config must declare the same one.',
+    ),
   metricsPort: ZNzUint.lte(65535)
     .optional()
     .describe(
       'The port to expose prometheus metrics on. Accessible via `GET /metrics`.',
     ),
+  exemplarSampleRate: z
+    .number()
+    .min(0)
+    .max(1)
+    .optional()
+    .describe(
+      'Share of the observations of the delivery pipeline histograms the OpenTelemetry trace is attached to as an exemplar, served when `/metrics` is scraped with the OpenMetrics format. Defaults to 0.1.',
+    ),
   chains: z
     .record(AgentChainMetadataSchema)
     .describe('Chain metadata for all chains that the agent will index.')
@@ -244,6 +348,20 @@ export const AgentConfigSchema = z.object({
         .nativeEnum(AgentLogLevel)
         .optional()
         .describe("The log level to use for the agent's logs."),
+      fieldBudgetBytes: ZHumanSize.optional().describe(
+        'Byte budget large fields such as message bodies and metadata are truncated to in logs. Defaults to 1024.',
+      ),
+    })
+    .optional(),
+  alerts: z
+    .object({
+      sinks: z
+        .array(AlertSinkSchema)
+        .optional()
+        .describe('Sinks critical events are pushed to as they happen.'),
+      rateLimitMinutes: ZNzUint.optional().describe(
+        'How long to wait before sending an alert with the same dedup key again. Defaults to 10.',
+      ),
     })
     .optional(),
 });
@@ -287,6 +405,91 @@ const MetricAppContextSchema = z.object({
   ),
 });
 
+export enum CheckpointPreference {
+  Latest = 'latest',
+  EarliestSatisfiable = 'earliestSatisfiable',
+}
+
+const CheckpointPreferenceRouteSchema = z.object({
+  preference: z.nativeEnum(CheckpointPreference),
+  matchingList: MatchingListSchema.describe(
+    'A matching list, any message that matches will use this preference.',
+  ),
+});
+
+const ShadowRouteSchema = z.object({
+  name: z
+    .string()
+    .min(1)
+    .describe('Name the divergences of the route are reported under.'),
+  matchingList: MatchingListSchema.describe(
+    'A matching list, any message that matches is also decided with this candidate config. A message is shadowed by the first route it matches.',
+  ),
+  gasPaymentEnforcement: z
+    .array(GasPaymentEnforcementSchema)
+    .optional()
+    .describe(
+      'Candidate gas payment enforcement policies. The active ones also apply to the candidate if unset.',
+    ),
+  gasPriceCeiling: ZUWei.optional().describe(
+    'Candidate gas price ceiling. The active ceiling also applies to the candidate if unset.',
+  ),
+  checkpointPreference: z
+    .nativeEnum(CheckpointPreference)
+    .optional()
+    .describe(
+      'Candidate quorum checkpoint preference. The active preference also applies to the candidate if unset.',
+    ),
+});
+
+const StageBudgetsSchema = z.object({
+  indexingSeconds: ZHumanDuration.optional().describe(
+    'Longest a message may take from the inclusion of its dispatch block to being handed to the submitter.',
+  ),
+  metadataSeconds: ZHumanDuration.optional().describe(
+    'Longest building the metadata of a message may take, in the attempt that finds the quorum covering it.',
+  ),
+  broadcastSeconds: ZHumanDuration.optional().describe(
+    'Longest a message may take from being ready to be submitted to its process transaction being broadcast.',
+  ),
+});
+
+const LatencyBudgetRouteSchema = StageBudgetsSchema.extend({
+  name: z
+    .string()
+    .min(1)
+    .describe('Name the latencies and breaches of the route are reported under.'),
+  matchingList: MatchingListSchema.describe(
+    'A matching list, any message that matches is held to the budgets of this route. A message is held to the budgets of the first route it matches.',
+  ),
+});
+
+const SupersessionRouteSchema = z.object({
+  matchingList: MatchingListSchema.describe(
+    'A matching list, any message that matches is grouped by this route. A message is grouped by the first route it matches.',
+  ),
+  supersession: z
+    .literal('latest_per_sender_recipient')
+    .describe(
+      'Which older messages a newer one supersedes: the undelivered ones of the same sender to the same recipient.',
+    ),
+  bodyPrefixBytes: ZUint.optional().describe(
+    'Leading bytes of the body that also group the messages, for apps multiplexing several channels between the same sender and recipient. Defaults to 0.',
+  ),
+});
+
+const RoundTripRouteSchema = z.object({
+  domains: z
+    .tuple([ZUint, ZUint])
+    .describe(
+      'Ids of the two domains of the route pair. Messages count in either direction.',
+    ),
+  maxRoundTrips: ZUint.describe(
+    'Times the same sender, recipient and body may cross the route pair, in either direction, within the window. The next message is parked, as are all the later ones until the tuple is cleared through the admin server.',
+  ),
+  windowSeconds: ZHumanDuration.describe('Window crossings are counted over.'),
+});
+
 export const RelayerAgentConfigSchema = AgentConfigSchema.extend({
   db: z
     .string()
@@ -336,6 +539,321 @@ export const RelayerAgentConfigSchema = AgentConfigSchema.extend({
     .describe(
       'A list of app contexts and their matching lists to use for metrics. A message will be classified as the first matching app context.',
     ),
+  checkpointPreferences: z
+    .union([z.array(CheckpointPreferenceRouteSchema), z.string().min(1)])
+    .optional()
+    .describe(
+      'Which quorum checkpoint messages are delivered against. A message uses the preference of the first matching list it matches, otherwise the latest checkpoint.',
+    ),
+  messageCompression: z
+    .object({
+      enabled: z
+        .boolean()
+        .optional()
+        .describe('Whether oversized message bodies are compressed.'),
+      threshold: ZUint.optional().describe(
+        'Body size in bytes above which messages are compressed.',
+      ),
+      level: z.number().int().optional().describe('The zstd compression level.'),
+      dictionary: z
+        .string()
+        .optional()
+        .describe('Path to a zstd dictionary used for compression.'),
+    })
+    .optional()
+    .describe('How message bodies are compressed in the database.'),
+  messageThrottle: z
+    .object({
+      windowSeconds: ZHumanDuration.optional().describe(
+        'Window message arrivals are counted over.',
+      ),
+      senderBurst: ZUint.optional().describe(
+        'Messages a sender may dispatch within the window before its messages are deprioritized. Senders are not throttled if unset.',
+      ),
+      routeBurst: ZUint.optional().describe(
+        'Messages a recipient on a destination may receive within the window before its messages are deprioritized. Routes are not throttled if unset.',
+      ),
+      throttledBudget: ZUint.optional().describe(
+        'Messages of throttled senders and routes handed to the submitter per window.',
+      ),
+    })
+    .optional()
+    .describe('How messages of bursting senders and routes are deprioritized.'),
+  circuitBreaker: z
+    .object({
+      windowSeconds: ZHumanDuration.optional().describe(
+        'Window the revert rate of process transactions is measured over.',
+      ),
+      revertThreshold: z
+        .number()
+        .gt(0)
+        .lte(1)
+        .optional()
+        .describe(
+          'Share of the process transactions within the window which, once reverted, pauses submissions to the destination. Submissions are never paused automatically if unset.',
+        ),
+      minSamples: ZUint.optional().describe(
+        'Process transactions needed within the window before the revert rate is acted on.',
+      ),
+      canaryIntervalSeconds: ZHumanDuration.optional().describe(
+        'How long paused submissions wait before a single canary transaction is let through.',
+      ),
+      canarySuccesses: ZUint.optional().describe(
+        'Consecutive canary transactions which need to succeed to resume submissions.',
+      ),
+    })
+    .optional()
+    .describe(
+      'When submissions to a destination are paused for reverting process transactions.',
+    ),
+  gasPriceCeiling: z
+    .object({
+      default: ZUWei.optional().describe(
+        'Highest gas price, in the smallest unit of the destination, messages are submitted at. Messages are submitted at any gas price if unset.',
+      ),
+      overrides: z
+        .array(
+          z.object({
+            matchingList: MatchingListSchema.describe(
+              'A matching list, any message that matches is submitted at gas prices up to this ceiling.',
+            ),
+            maxGasPrice: ZUWei.describe(
+              'Highest gas price, in the smallest unit of the destination, matching messages are submitted at.',
+            ),
+          }),
+        )
+        .optional()
+        .describe(
+          'Ceilings overriding the default, e.g. for the senders of high value messages. The first route a message matches decides.',
+        ),
+      recheckIntervalSeconds: ZHumanDuration.optional().describe(
+        'How often messages deferred for the gas price are checked against it again. Defaults to 30 seconds.',
+      ),
+    })
+    .optional()
+    .describe(
+      'Deferral of messages while the gas price of their destination is above the ceiling of their route. Process transactions never pay more than the ceiling.',
+    ),
+  shadowConfig: z
+    .union([z.array(ShadowRouteSchema), z.string().min(1)])
+    .optional()
+    .describe(
+      'Candidate configs evaluated alongside the active one, by route, without being acted on. Where they would have decided differently is counted, so they can be vetted against live traffic before promotion.',
+    ),
+  ccipRead: z
+    .object({
+      allowlist: z
+        .array(
+          z.object({
+            matchingList: MatchingListSchema.optional().describe(
+              'A matching list, any message that matches may send offchain lookups to these URLs. Matches any message if unset.',
+            ),
+            urls: z
+              .string()
+              .describe(
+                'Comma separated list of gateway URL prefixes. A URL is allowed if it has the same origin as one of them and extends its path.',
+              ),
+          }),
+        )
+        .optional()
+        .describe(
+          'Gateway URLs offchain lookups may be sent to, by route. A message uses the URLs of the first route it matches and may not send lookups if it matches none. Any URL is allowed if unset.',
+        ),
+      timeoutSeconds: ZHumanDuration.optional().describe(
+        'Timeout of each request to a gateway. Defaults to 10 seconds.',
+      ),
+      maxResponseBytes: ZHumanSize.optional().describe(
+        'Largest gateway response to accept. Defaults to 256 KiB.',
+      ),
+      maxLookups: ZUint.optional().describe(
+        'Most offchain lookups to perform for a message, as gateways may answer with another lookup. Defaults to 4.',
+      ),
+    })
+    .optional()
+    .describe(
+      'How the offchain lookups (ERC-3668) of CCIP read ISMs are performed.',
+    ),
+  merkleTreeCapacityWarning: z
+    .number()
+    .gt(0)
+    .lte(1)
+    .optional()
+    .describe(
+      'Fraction of an origin merkle tree capacity past which to warn that it is filling up. Defaults to 0.9.',
+    ),
+  merkleTreeShutdownTimeoutSeconds: ZHumanDuration.optional().describe(
+    'How long to wait for each origin to freeze its merkle tree for the coordinated snapshot taken on shutdown. Defaults to 10 seconds.',
+  ),
+  rebuildPacing: z
+    .object({
+      callsPerMinute: ZUint.optional().describe(
+        'Maximum number of chunks queried per minute when backfilling merkle tree insertions, e.g. for a full-history rebuild. Zero pauses backfilling. Unlimited by default.',
+      ),
+      chunkSleepMillis: ZHumanDuration.optional().describe(
+        'Minimum time between two backfilled chunks, in milliseconds. Defaults to 0.',
+      ),
+    })
+    .optional()
+    .describe(
+      'Pacing of merkle tree rebuilds, so that they stay within the RPC budget of the origin. The tip is never paced.',
+    ),
+  diskSpace: z
+    .object({
+      warningFreeBytes: ZHumanSize.optional().describe(
+        'Free disk space of the database, in bytes, below which to warn. Unset by default.',
+      ),
+      criticalFreeBytes: ZHumanSize.optional().describe(
+        'Free disk space of the database, in bytes, below which indexing is paused and the relayer reports as not ready, until space is freed up. Must be at most the warning threshold. Unset by default.',
+      ),
+      checkIntervalSeconds: ZHumanDuration.optional().describe(
+        'How often the free disk space is checked, in seconds. Defaults to 30.',
+      ),
+    })
+    .optional()
+    .describe(
+      'Thresholds of free disk space the database is watched against. Not watched unless a threshold is set.',
+    ),
+  instanceLock: z
+    .object({
+      enabled: z
+        .boolean()
+        .optional()
+        .describe(
+          'Whether to refuse to start while another relayer uses the database. Defaults to true.',
+        ),
+      heartbeatIntervalSeconds: ZHumanDuration.optional().describe(
+        'How often the relayer records its heartbeat in the database, in seconds. Defaults to 10.',
+      ),
+      heartbeatTtlSeconds: ZHumanDuration.optional().describe(
+        'How long after its last heartbeat another relayer is considered gone, in seconds. Must exceed the heartbeat interval. Defaults to 60.',
+      ),
+      signerObservationSeconds: ZHumanDuration.optional().describe(
+        'How long the nonces of the destination signers are watched at startup for transactions sent by another process, in seconds. Zero disables the observation. Defaults to 15.',
+      ),
+    })
+    .optional()
+    .describe(
+      'Protection against two relayers running on the same database or signers.',
+    ),
+  takeover: z
+    .boolean()
+    .optional()
+    .describe(
+      'Wait for the heartbeat of another relayer using the database to expire rather than refusing to start.',
+    ),
+  quorumPrefetch: z
+    .object({
+      maxPreparations: ZUint.optional().describe(
+        'Most messages to keep proofs prepared ahead of their quorum for. Zero disables preparing ahead of the quorum. Defaults to 1000.',
+      ),
+      ttlSeconds: ZHumanDuration.optional().describe(
+        'How long a proof prepared ahead of a quorum is kept if the quorum never completes, in seconds. Defaults to 600.',
+      ),
+      pollIntervalMillis: ZHumanDuration.optional().describe(
+        'How often messages whose quorum is anticipated are retried, in milliseconds. Defaults to 500.',
+      ),
+    })
+    .optional()
+    .describe(
+      'Preparation of merkle proofs once a majority but not all of the threshold signed a checkpoint covering a message, so it is delivered as soon as the quorum completes.',
+    ),
+  checkpointCache: z
+    .object({
+      maxRoutes: ZUint.optional().describe(
+        'Most origin, destination and ISM routes to cache quorum checkpoints for, the least recently used being evicted first. Zero disables the cache. Defaults to 256.',
+      ),
+      maxCheckpointsPerRoute: ZUint.optional().describe(
+        'Most quorum checkpoints cached for a single route, the lowest indices being evicted first. Defaults to 8.',
+      ),
+      ttlSeconds: ZHumanDuration.optional().describe(
+        'How long a quorum checkpoint is reused before fetching a newer one, in seconds. Defaults to 60.',
+      ),
+    })
+    .optional()
+    .describe(
+      'Reuse of the quorum checkpoints of merkle root multisig metadata, and their signatures, across the messages they cover, so that only the proof is built per message.',
+    ),
+  metadataPool: z
+    .object({
+      maxConcurrency: ZUint.optional().describe(
+        'Most metadata builds in flight across all routes. Zero lets metadata be built without limit. Defaults to 64.',
+      ),
+      maxInFlightPerRoute: ZUint.optional().describe(
+        'Most metadata builds in flight for a single origin and destination. Defaults to 16.',
+      ),
+    })
+    .optional()
+    .describe(
+      'Bounds on the metadata builds in flight, so that routes with slow validators or ISMs cannot starve the others. Routes waiting for a slot are served in turn.',
+    ),
+  latencyBudgets: StageBudgetsSchema.extend({
+    routes: z
+      .union([z.array(LatencyBudgetRouteSchema), z.string().min(1)])
+      .optional()
+      .describe(
+        'Budgets by route, falling back to the default ones for the stages a route leaves unset.',
+      ),
+    windowSeconds: ZHumanDuration.optional().describe(
+      'Window the latency percentiles of each route are reported over. Defaults to 1 hour.',
+    ),
+  })
+    .optional()
+    .describe(
+      'How long messages may spend in each stage of the pipeline. Stages taking longer are counted as breaches and flagged on the message status. Messages are only timed if a budget is set.',
+    ),
+  supersessionRoutes: z
+    .union([z.array(SupersessionRouteSchema), z.string().min(1)])
+    .optional()
+    .describe(
+      'Routes of apps dispatching "latest state" messages, of which only the newest matters. Older undelivered messages are cancelled as superseded once a newer one is indexed, unless their delivery was already broadcast.',
+    ),
+  roundTrips: z
+    .object({
+      routes: z
+        .union([z.array(RoundTripRouteSchema), z.string().min(1)])
+        .optional()
+        .describe('Route pairs whose round trips are limited.'),
+      maxTuples: ZUint.optional().describe(
+        'Sender, recipient and body tuples tracked per route pair, the ones which crossed least recently being forgotten first. Defaults to 10000.',
+      ),
+    })
+    .optional()
+    .describe(
+      'Replay protection for apps that dispatch a message while handling one, which can bounce the same message between two chains until funds run out.',
+    ),
+  corruptionTolerance: ZUint.optional().describe(
+    'How many corrupted db records the message and merkle tree scans of an origin skip before aborting. Defaults to 10.',
+  ),
+  exposeMessageEvents: z
+    .boolean()
+    .optional()
+    .describe(
+      'If true, the admin server streams the lifecycle events of messages over server-sent events.',
+    ),
+  lazyOriginStartup: z
+    .boolean()
+    .optional()
+    .describe(
+      'If true, the relayer reports ready once its priority origins are up and starts the others in the background.',
+    ),
+  priorityOrigins: z
+    .string()
+    .optional()
+    .describe(
+      'Comma separated list of origin chains to start before the others, after those with undelivered messages.',
+    ),
+  adminToken: z
+    .string()
+    .optional()
+    .describe(
+      'Bearer token operators authenticate admin requests with, e.g. to cancel messages. Such requests are refused if unset.',
+    ),
+  cancellationSenders: z
+    .string()
+    .optional()
+    .describe(
+      'Comma separated list of EVM sender addresses allowed to cancel their pending messages with an EIP-191 signature of the message id.',
+    ),
 });
 
 export type RelayerConfig = z.infer<typeof RelayerAgentConfigSchema>;
@@ -349,6 +867,50 @@ export const ScraperAgentConfigSchema = AgentConfigSchema.extend({
 
 export type ScraperConfig = z.infer<typeof ScraperAgentConfigSchema>;
 
+const CheckpointSyncerSchema = z.discriminatedUnion('type', [
+  z
+    .object({
+      type: z.literal('localStorage'),
+      path: z.string().min(1).describe('Path to the local storage location'),
+    })
+    .describe('A local checkpoint syncer'),
+  z
+    .object({
+      type: z.literal('s3'),
+      bucket: z.string().min(1),
+      region: z.string().min(1),
+      folder: z
+        .string()
+        .min(1)
+        .optional()
+        .describe(
+          'The folder/key-prefix to use, defaults to the root of the bucket',
+        ),
+    })
+    .describe('A checkpoint syncer that uses S3'),
+  z
+    .object({
+      type: z.literal('gcs'),
+      bucket: z.string().min(1),
+      folder: z
+        .string()
+        .min(1)
+        .optional()
+        .describe('The folder to use, defaults to the root of the bucket'),
+      service_account_key: z
+        .string()
+        .min(1)
+        .optional()
+        .describe('The path to GCS service account key file'),
+      user_secrets: z
+        .string()
+        .min(1)
+        .optional()
+        .describe('The path to GCS user secret file'),
+    })
+    .describe('A checkpoint syncer that uses Google Cloud Storage'),
+]);
+
 export const ValidatorAgentConfigSchema = AgentConfigSchema.extend({
   db: z
     .string()
@@ -359,51 +921,35 @@ export const ValidatorAgentConfigSchema = AgentConfigSchema.extend({
     .string()
     .min(1)
     .describe('Name of the chain to validate messages on'),
-  validator: AgentSignerSchema.describe('The validator attestation signer'),
-  checkpointSyncer: z.discriminatedUnion('type', [
-    z
-      .object({
-        type: z.literal('localStorage'),
-        path: z.string().min(1).describe('Path to the local storage location'),
-      })
-      .describe('A local checkpoint syncer'),
-    z
-      .object({
-        type: z.literal('s3'),
-        bucket: z.string().min(1),
-        region: z.string().min(1),
-        folder: z
-          .string()
-          .min(1)
-          .optional()
-          .describe(
-            'The folder/key-prefix to use, defaults to the root of the bucket',
-          ),
-      })
-      .describe('A checkpoint syncer that uses S3'),
-    z
-      .object({
-        type: z.literal('gcs'),
-        bucket: z.string().min(1),
-        folder: z
-          .string()
-          .min(1)
-          .optional()
-          .describe('The folder to use, defaults to the root of the bucket'),
-        service_account_key: z
-          .string()
-          .min(1)
-          .optional()
-          .describe('The path to GCS service account key file'),
-        user_secrets: z
-          .string()
-          .min(1)
-          .optional()
-          .describe('The path to GCS user secret file'),
-      })
-      .describe('A checkpoint syncer that uses Google Cloud Storage'),
-  ]),
-  interval: ZUint.optional().describe(
+  mode: z
+    .enum(['sign', 'verify'])
+    .optional()
+    .describe(
+      'Whether to sign and publish checkpoints, or only verify that the onchain roots can be reproduced. Defaults to sign.',
+    ),
+  validator: AgentSignerSchema.optional().describe(
+    'The validator attestation signer, required unless the mode is verify',
+  ),
+  checkpointSyncer: CheckpointSyncerSchema.optional().describe(
+    'Where to publish checkpoints, required unless the mode is verify or checkpointSyncers is set',
+  ),
+  checkpointSyncers: z
+    .array(CheckpointSyncerSchema)
+    .min(1)
+    .optional()
+    .describe(
+      'Several checkpoint storage backends to publish checkpoints to at once, the first being the primary announced one. Takes precedence over checkpointSyncer.',
+    ),
+  checkpointSyncerQuorum: ZUint.optional().describe(
+    'How many of the checkpointSyncers have to accept a checkpoint for it to be published, defaults to 1. Backends that missed it catch up in the background.',
+  ),
+  announceAlternateStorageLocations: z
+    .boolean()
+    .optional()
+    .describe(
+      'Whether to list the other checkpointSyncers as alternates after the primary in the announced storage location, as `<primary>|<alternate>|...`, for relayers to fall back to. Relayers older than this format ignore such announcements.',
+    ),
+  interval: ZHumanDuration.optional().describe(
     'How long to wait between checking for new checkpoints in seconds.',
   ),
 });
@@ -29,6 +29,7 @@ export enum ChainTechnicalStack {
   ArbitrumNitro = 'arbitrumnitro',
   OpStack = 'opstack',
   PolygonCDK = 'polygoncdk',
+  PolygonPoS = 'polygonpos',
   PolkadotSubstrate = 'polkadotsubstrate',
   ZkSync = 'zksync',
   Other = 'other',
@@ -139,6 +140,12 @@ export const ChainMetadataSchemaObject = z.object({
         .describe(
           'Number of blocks before a transaction has a near-zero chance of reverting or block tag.',
         ),
+      reorgPeriodFallback: ZUint.optional().describe(
+        'Number of blocks to use as the reorg period if the RPC provider does not support the reorgPeriod block tag.',
+      ),
+      finalityBlocks: ZUint.optional().describe(
+        "Number of blocks to wait before treating an event as final. Overrides the agents' built-in default for the chain.",
+      ),
       estimateBlockTime: z
         .number()
         .positive()
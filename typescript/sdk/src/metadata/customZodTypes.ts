@@ -8,6 +8,16 @@ import { z } from 'zod';
 export const ZUint = z.number().int().nonnegative();
 /** Zod NonZeroUint schema */
 export const ZNzUint = z.number().int().positive();
+/** Zod duration schema, e.g. '30s', '5m', '2h' or '1h30m'. Bare numbers are deprecated, and read in the unit the field documents */
+export const ZHumanDuration = z.union([
+  ZUint,
+  z.string().regex(/^\s*(\d+\s*(ms|s|m|h|d)\s*)+$/i),
+]);
+/** Zod size schema, e.g. '512kb' or '10mb', where 1kb is 1024 bytes. Bare numbers are deprecated, and read in bytes */
+export const ZHumanSize = z.union([
+  ZUint,
+  z.string().regex(/^\s*(\d+\s*(b|kb|mb|gb)\s*)+$/i),
+]);
 /** Zod unsigned Wei schema which accepts either a string number or a literal number */
 export const ZUWei = z.union([ZUint.safe(), z.string().regex(/^\d+$/)]);
 /** Zod 128, 160, 256, or 512 bit hex-defined hash with a 0x prefix for hex and no prefix for base58 */